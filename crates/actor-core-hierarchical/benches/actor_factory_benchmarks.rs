@@ -0,0 +1,35 @@
+//! Actor Factory Benchmarks
+//! 
+//! Compares one-at-a-time actor creation against batch creation.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
+use actor_core_hierarchical::{ActorFactory, HierarchicalActor};
+
+/// Benchmark create_actor loop vs create_batch
+fn bench_actor_creation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("actor_factory_creation");
+    group.sample_size(10);
+    let factory = ActorFactory::new_empty();
+    
+    for count in [100, 1_000, 10_000].iter() {
+        group.throughput(Throughput::Elements(*count as u64));
+        
+        group.bench_with_input(BenchmarkId::new("create_actor_loop", count), count, |b, &count| {
+            b.iter(|| {
+                let actors: Vec<HierarchicalActor> = (0..count)
+                    .map(|_| factory.create_actor("warrior").unwrap())
+                    .collect();
+                black_box(actors)
+            })
+        });
+        
+        group.bench_with_input(BenchmarkId::new("create_batch", count), count, |b, &count| {
+            b.iter(|| black_box(factory.create_batch("warrior", count, None).unwrap()))
+        });
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_actor_creation);
+criterion_main!(benches);
@@ -0,0 +1,100 @@
+//! Aggregation Benchmarks
+//! 
+//! Compares the monolithic aggregation path against plugin-based aggregation.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
+use actor_core_hierarchical::{AggregatorPlugin, GlobalAggregator, HierarchicalActor, SystemContribution};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+const SYSTEMS: [&str; 4] = ["elemental", "cultivation", "race", "item"];
+const STATS: [&str; 6] = ["health", "mana", "attack", "defense", "speed", "critical_rate"];
+
+/// Plugin that sums contributions per stat
+struct SumPlugin {
+    name: String,
+    system_name: String,
+}
+
+impl AggregatorPlugin for SumPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    fn system_name(&self) -> &str {
+        &self.system_name
+    }
+    
+    fn aggregate_partial(&self, contributions: &[SystemContribution]) -> HashMap<String, f64> {
+        let mut partial = HashMap::new();
+        for contribution in contributions {
+            *partial.entry(contribution.stat_name.clone()).or_insert(0.0) += contribution.value;
+        }
+        partial
+    }
+}
+
+/// Create an actor with the given number of contributions per system and stat
+fn create_actor(contributions_per_stat: usize) -> HierarchicalActor {
+    let mut actor = HierarchicalActor::new();
+    for system_name in SYSTEMS.iter() {
+        for stat_name in STATS.iter() {
+            for i in 0..contributions_per_stat {
+                actor.add_system_contribution(SystemContribution {
+                    system_name: system_name.to_string(),
+                    stat_name: stat_name.to_string(),
+                    value: i as f64 + 1.0,
+                    priority: 1,
+                    timestamp: Utc::now(),
+                });
+            }
+        }
+    }
+    actor
+}
+
+/// Create an aggregator with one plugin per system
+fn create_plugin_aggregator() -> GlobalAggregator {
+    let mut aggregator = GlobalAggregator::new();
+    for system_name in SYSTEMS.iter() {
+        aggregator
+            .register_plugin(Arc::new(SumPlugin {
+                name: format!("{}_sum", system_name),
+                system_name: system_name.to_string(),
+            }))
+            .unwrap();
+    }
+    aggregator
+}
+
+/// Benchmark monolithic vs plugin aggregation
+fn bench_aggregation_paths(c: &mut Criterion) {
+    let mut group = c.benchmark_group("aggregation_paths");
+    
+    for count in [1, 10, 100].iter() {
+        let actor = create_actor(*count);
+        group.throughput(Throughput::Elements((SYSTEMS.len() * STATS.len() * count) as u64));
+        
+        group.bench_with_input(BenchmarkId::new("monolithic", count), &actor, |b, actor| {
+            let mut aggregator = GlobalAggregator::new();
+            b.iter(|| {
+                aggregator.clear_cache();
+                black_box(aggregator.aggregate_actor_stats(actor))
+            })
+        });
+        
+        group.bench_with_input(BenchmarkId::new("plugins", count), &actor, |b, actor| {
+            let mut aggregator = create_plugin_aggregator();
+            b.iter(|| {
+                aggregator.clear_cache();
+                black_box(aggregator.aggregate_actor_stats(actor))
+            })
+        });
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_aggregation_paths);
+criterion_main!(benches);
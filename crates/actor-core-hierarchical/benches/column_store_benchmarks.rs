@@ -0,0 +1,73 @@
+//! Column Store Benchmarks
+//! 
+//! Compares zone-wide stat iteration over per-actor storage and the column store.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId, Throughput};
+use actor_core_hierarchical::{ActorColumnStore, HierarchicalActor};
+use std::collections::HashMap;
+
+const STATS: [&str; 8] = [
+    "health", "mana", "stamina", "attack", "defense", "speed", "critical_rate", "dodge_rate",
+];
+
+/// Create actors with all benchmark stats populated
+fn create_actors(count: usize) -> Vec<HierarchicalActor> {
+    (0..count)
+        .map(|i| {
+            let mut actor = HierarchicalActor::new();
+            let stats: HashMap<String, f64> = STATS
+                .iter()
+                .enumerate()
+                .map(|(j, stat)| (stat.to_string(), (i * STATS.len() + j) as f64))
+                .collect();
+            actor.update_global_stats_cache(stats);
+            actor
+        })
+        .collect()
+}
+
+/// Benchmark summing one stat across all actors
+fn bench_stat_iteration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("zone_stat_iteration");
+    
+    for count in [1_000, 10_000].iter() {
+        let actors = create_actors(*count);
+        let mut store = ActorColumnStore::with_stats(&STATS);
+        for actor in &actors {
+            store.insert_actor(actor);
+        }
+        group.throughput(Throughput::Elements(*count as u64));
+        
+        group.bench_with_input(BenchmarkId::new("per_actor", count), &actors, |b, actors| {
+            b.iter(|| {
+                let total: f64 = actors
+                    .iter()
+                    .filter_map(|actor| actor.get_global_stats_cache().get("health"))
+                    .sum();
+                black_box(total)
+            })
+        });
+        
+        group.bench_with_input(BenchmarkId::new("column_store", count), &store, |b, store| {
+            b.iter(|| {
+                let total: f64 = store.column("health").unwrap().iter().sum();
+                black_box(total)
+            })
+        });
+        
+        group.bench_with_input(BenchmarkId::new("column_store_regen", count), count, |b, _| {
+            let mut store = store.clone();
+            b.iter(|| {
+                for health in store.column_mut("health").unwrap() {
+                    *health += 1.0;
+                }
+                black_box(&store);
+            })
+        });
+    }
+    
+    group.finish();
+}
+
+criterion_group!(benches, bench_stat_iteration);
+criterion_main!(benches);
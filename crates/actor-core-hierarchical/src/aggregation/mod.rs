@@ -1,13 +1,17 @@
 //! # Aggregation Module
-//! 
+//!
 //! Custom aggregation strategies for hierarchical systems.
 
-// This module is reserved for future custom aggregators
-// Currently, aggregation is handled by GlobalAggregator in core module
+// Aggregation is driven by GlobalAggregator in the core module; systems can
+// plug in their own partial aggregation through AggregatorPlugin.
 
 // Future implementations:
 // - ElementalAggregator: Element-specific aggregation
-// - CultivationAggregator: Cultivation system aggregation  
+// - CultivationAggregator: Cultivation system aggregation
 // - MagicAggregator: Magic system aggregation
 // - RaceAggregator: Race-specific aggregation
 // - ItemAggregator: Item system aggregation
+
+pub mod plugin;
+
+pub use plugin::*;
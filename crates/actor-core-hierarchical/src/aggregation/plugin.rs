@@ -0,0 +1,115 @@
+//! # Aggregator Plugins
+//!
+//! Pluggable per-system aggregation for the global aggregator.
+//!
+//! A plugin owns the contributions of a single system: it reduces them to one
+//! partial value per stat, and may then adjust the combined result in a final
+//! pass. Plugins are always run in a deterministic order (priority, then name)
+//! so that floating point results do not depend on hash map iteration order.
+
+use crate::core::SystemContribution;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Per-system aggregation plugin
+pub trait AggregatorPlugin: Send + Sync {
+    /// Unique plugin name
+    fn name(&self) -> &str;
+
+    /// System whose contributions this plugin aggregates (e.g., "elemental")
+    fn system_name(&self) -> &str;
+
+    /// Combine order (lower runs first, ties are broken by plugin name)
+    fn priority(&self) -> u32 {
+        100
+    }
+
+    /// Reduce this system's contributions to one partial value per stat
+    fn aggregate_partial(&self, contributions: &[SystemContribution]) -> HashMap<String, f64>;
+
+    /// Final combine pass over the aggregated stats of all systems
+    fn combine(&self, _aggregated: &mut HashMap<String, f64>) {}
+}
+
+/// Registry of aggregator plugins, kept in combine order
+#[derive(Clone, Default)]
+pub struct AggregatorPluginRegistry {
+    plugins: Vec<Arc<dyn AggregatorPlugin>>,
+}
+
+impl std::fmt::Debug for AggregatorPluginRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AggregatorPluginRegistry")
+            .field("plugins", &self.get_plugin_names())
+            .finish()
+    }
+}
+
+impl AggregatorPluginRegistry {
+    /// Create an empty plugin registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a plugin
+    ///
+    /// Fails if a plugin with the same name, or a plugin for the same system, is already registered.
+    pub fn register(&mut self, plugin: Arc<dyn AggregatorPlugin>) -> Result<(), String> {
+        if plugin.name().is_empty() {
+            return Err("Aggregator plugin name cannot be empty".to_string());
+        }
+
+        for existing in &self.plugins {
+            if existing.name() == plugin.name() {
+                return Err(format!("Aggregator plugin '{}' is already registered", plugin.name()));
+            }
+            if existing.system_name() == plugin.system_name() {
+                return Err(format!(
+                    "System '{}' is already handled by aggregator plugin '{}'",
+                    plugin.system_name(),
+                    existing.name()
+                ));
+            }
+        }
+
+        self.plugins.push(plugin);
+        self.plugins.sort_by(|a, b| {
+            a.priority()
+                .cmp(&b.priority())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+        Ok(())
+    }
+
+    /// Unregister a plugin by name, returning whether it was registered
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let before = self.plugins.len();
+        self.plugins.retain(|plugin| plugin.name() != name);
+        self.plugins.len() != before
+    }
+
+    /// Get the plugin handling a system, if any
+    pub fn get_plugin_for_system(&self, system_name: &str) -> Option<&Arc<dyn AggregatorPlugin>> {
+        self.plugins.iter().find(|plugin| plugin.system_name() == system_name)
+    }
+
+    /// Get plugins in combine order
+    pub fn get_plugins(&self) -> &[Arc<dyn AggregatorPlugin>] {
+        &self.plugins
+    }
+
+    /// Get plugin names in combine order
+    pub fn get_plugin_names(&self) -> Vec<String> {
+        self.plugins.iter().map(|plugin| plugin.name().to_string()).collect()
+    }
+
+    /// Get number of registered plugins
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Check whether no plugins are registered
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
@@ -0,0 +1,115 @@
+//! # Actor Delta
+//!
+//! Change tracking for hierarchical actors so that only modified data blocks are
+//! re-aggregated and shipped to replica processes.
+
+use crate::core::SystemContribution;
+use element_core::ElementalSystemData;
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Utc};
+
+/// Dirty flags for each actor data block
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DirtyFlags {
+    /// Actor name changed
+    pub name: bool,
+
+    /// Metadata changed
+    pub metadata: bool,
+
+    /// Elemental system data changed
+    pub elemental: bool,
+
+    /// Global stats cache changed
+    pub global_stats: bool,
+
+    /// Systems whose contributions changed
+    pub systems: HashSet<String>,
+}
+
+impl DirtyFlags {
+    /// Check if no block is dirty
+    pub fn is_clean(&self) -> bool {
+        !self.name && !self.metadata && !self.elemental && !self.global_stats && self.systems.is_empty()
+    }
+
+    /// Mark a system's contributions as dirty
+    pub fn mark_system(&mut self, system_name: &str) {
+        if !self.systems.contains(system_name) {
+            self.systems.insert(system_name.to_string());
+        }
+    }
+
+    /// Clear all flags
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Set of modified blocks collected from an actor
+///
+/// Only blocks that changed since the previous `collect_changes` call are present.
+#[derive(Debug, Clone)]
+pub struct ActorDelta {
+    /// Actor the delta belongs to
+    pub actor_id: String,
+
+    /// Update timestamp of the source actor when the delta was collected
+    pub updated_at: DateTime<Utc>,
+
+    /// New actor name
+    pub name: Option<String>,
+
+    /// Full replacement metadata
+    pub metadata: Option<HashMap<String, String>>,
+
+    /// Full replacement elemental data
+    pub elemental_data: Option<Box<ElementalSystemData>>,
+
+    /// Full replacement global stats cache
+    pub global_stats: Option<HashMap<String, f64>>,
+
+    /// Full replacement contributions for each changed system
+    pub system_contributions: HashMap<String, Vec<SystemContribution>>,
+
+    /// Systems whose contributions were removed
+    pub removed_systems: Vec<String>,
+}
+
+impl ActorDelta {
+    /// Create an empty delta for an actor
+    pub fn new(actor_id: String, updated_at: DateTime<Utc>) -> Self {
+        Self {
+            actor_id,
+            updated_at,
+            name: None,
+            metadata: None,
+            elemental_data: None,
+            global_stats: None,
+            system_contributions: HashMap::new(),
+            removed_systems: Vec::new(),
+        }
+    }
+
+    /// Check if the delta carries no changes
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.metadata.is_none()
+            && self.elemental_data.is_none()
+            && self.global_stats.is_none()
+            && self.system_contributions.is_empty()
+            && self.removed_systems.is_empty()
+    }
+
+    /// Get all changed or removed systems, sorted by name
+    pub fn changed_systems(&self) -> Vec<&str> {
+        let mut systems: Vec<&str> = self.system_contributions
+            .keys()
+            .map(|system_name| system_name.as_str())
+            .chain(self.removed_systems.iter().map(|system_name| system_name.as_str()))
+            .collect();
+        systems.sort_unstable();
+        systems.dedup();
+        systems
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::core::HierarchicalActor;
 use element_core::{ElementalSystem, UnifiedElementRegistry as ElementalRegistry, ElementalSystemData, ElementalParams};
+use rayon::prelude::*;
 use std::collections::HashMap;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -57,6 +58,19 @@ pub struct ElementalSystemConfig {
     pub elemental_preferences: Vec<String>,
 }
 
+/// Overrides applied to every actor of a batch
+#[derive(Debug, Clone, Default)]
+pub struct ActorParamOverrides {
+    /// Name prefix; actors are named "{prefix} {index}" instead of "{type} Actor"
+    pub name_prefix: Option<String>,
+    
+    /// Metadata set on top of the actor type defaults
+    pub metadata: HashMap<String, String>,
+    
+    /// Custom elemental parameters
+    pub elemental_params: Option<ElementalParams>,
+}
+
 /// Default contribution for actor creation
 #[derive(Debug, Clone)]
 pub struct DefaultContribution {
@@ -192,6 +206,46 @@ impl ActorFactory {
         self.create_actor_with_options(actor_type, Some(elemental_params))
     }
     
+    /// Create many actors of the same type at once
+    ///
+    /// The actor type and elemental parameters are resolved against the registry once to
+    /// build a template; actors are then stamped out from it in parallel into a preallocated
+    /// vector. Much faster than calling `create_actor` in a loop when spawning NPCs.
+    pub fn create_batch(&self, actor_type: &str, count: usize, param_overrides: Option<ActorParamOverrides>) -> Result<Vec<HierarchicalActor>, String> {
+        let overrides = param_overrides.unwrap_or_default();
+        
+        // 1. Resolve the template once (shared registry lookups)
+        let mut template = self.create_actor_with_options(actor_type, overrides.elemental_params)?;
+        for (key, value) in overrides.metadata {
+            template.set_metadata(key, value);
+        }
+        
+        let template_name = template.name.clone();
+        let template_metadata = template.metadata.clone();
+        let template_dirty_flags = template.dirty_flags.clone();
+        let template_elemental_data = template.elemental_system.get_data().clone();
+        let name_prefix = overrides.name_prefix;
+        
+        // 2. Construct actors in parallel into a preallocated vector
+        let mut actors = Vec::with_capacity(count);
+        (0..count)
+            .into_par_iter()
+            .map(|index| {
+                let name = match &name_prefix {
+                    Some(prefix) => format!("{} {}", prefix, index),
+                    None => template_name.clone(),
+                };
+                let mut actor = HierarchicalActor::with_id_and_name(Uuid::new_v4().to_string(), name);
+                actor.metadata = template_metadata.clone();
+                actor.elemental_system = ElementalSystem::from_data(template_elemental_data.clone());
+                actor.dirty_flags = template_dirty_flags.clone();
+                actor
+            })
+            .collect_into_vec(&mut actors);
+        
+        Ok(actors)
+    }
+    
     /// Create actor with optional elemental parameters
     fn create_actor_with_options(&self, actor_type: &str, elemental_params: Option<ElementalParams>) -> Result<HierarchicalActor, String> {
         // 1. Create basic actor structure
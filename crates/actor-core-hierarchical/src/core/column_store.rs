@@ -0,0 +1,256 @@
+//! # Actor Column Store
+//!
+//! Optional structure-of-arrays storage for zone-wide ticks.
+//!
+//! Per-actor storage keeps each actor's stats together, which is ideal for reading one
+//! actor but scatters a single stat across memory when iterating thousands of actors.
+//! `ActorColumnStore` keeps one contiguous `f64` column per stat instead, with rows kept
+//! dense on removal. Actors are addressed through generational `ActorIndex` handles that
+//! stay valid while rows move and are rejected once the actor is removed.
+
+use crate::core::HierarchicalActor;
+use std::collections::HashMap;
+
+/// Handle to an actor row in an `ActorColumnStore`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ActorIndex {
+    /// Slot in the handle table
+    slot: u32,
+
+    /// Slot generation the handle was issued for
+    generation: u32,
+}
+
+/// Handle table entry
+#[derive(Debug, Clone)]
+struct Slot {
+    generation: u32,
+    row: Option<usize>,
+}
+
+/// Structure-of-arrays storage of actor stats
+#[derive(Debug, Clone, Default)]
+pub struct ActorColumnStore {
+    /// Column index by stat name
+    stat_columns: HashMap<String, usize>,
+
+    /// Stat names by column index
+    stat_names: Vec<String>,
+
+    /// One contiguous value array per stat, indexed by row
+    columns: Vec<Vec<f64>>,
+
+    /// Actor ID per row
+    actor_ids: Vec<String>,
+
+    /// Handle slot per row
+    row_slots: Vec<u32>,
+
+    /// Handle table
+    slots: Vec<Slot>,
+
+    /// Free handle slots
+    free_slots: Vec<u32>,
+
+    /// Handle lookup by actor ID
+    handles_by_id: HashMap<String, ActorIndex>,
+}
+
+impl ActorColumnStore {
+    /// Create an empty column store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a column store with predefined stat columns
+    pub fn with_stats(stat_names: &[&str]) -> Self {
+        let mut store = Self::new();
+        for stat_name in stat_names {
+            store.add_stat_column(stat_name);
+        }
+        store
+    }
+
+    /// Add a stat column (filled with 0.0 for existing rows), returning its index
+    pub fn add_stat_column(&mut self, stat_name: &str) -> usize {
+        if let Some(&column) = self.stat_columns.get(stat_name) {
+            return column;
+        }
+        let column = self.columns.len();
+        self.columns.push(vec![0.0; self.actor_ids.len()]);
+        self.stat_names.push(stat_name.to_string());
+        self.stat_columns.insert(stat_name.to_string(), column);
+        column
+    }
+
+    /// Insert an actor's stats, or refresh them if the actor is already stored
+    pub fn insert_actor(&mut self, actor: &HierarchicalActor) -> ActorIndex {
+        if let Some(&handle) = self.handles_by_id.get(actor.get_id()) {
+            self.sync_from_actor(handle, actor)
+                .expect("handle from the ID index is always valid");
+            return handle;
+        }
+
+        let row = self.actor_ids.len();
+        let handle = match self.free_slots.pop() {
+            Some(slot) => {
+                let entry = &mut self.slots[slot as usize];
+                entry.row = Some(row);
+                ActorIndex { slot, generation: entry.generation }
+            }
+            None => {
+                let slot = self.slots.len() as u32;
+                self.slots.push(Slot { generation: 0, row: Some(row) });
+                ActorIndex { slot, generation: 0 }
+            }
+        };
+
+        self.actor_ids.push(actor.get_id().to_string());
+        self.row_slots.push(handle.slot);
+        for column in &mut self.columns {
+            column.push(0.0);
+        }
+        self.handles_by_id.insert(actor.get_id().to_string(), handle);
+
+        self.sync_from_actor(handle, actor)
+            .expect("handle was just issued");
+        handle
+    }
+
+    /// Remove an actor, returning whether the handle was valid
+    ///
+    /// The last row is moved into the freed row to keep columns dense.
+    pub fn remove_actor(&mut self, handle: ActorIndex) -> bool {
+        let Some(row) = self.get_row(handle) else {
+            return false;
+        };
+
+        let last_row = self.actor_ids.len() - 1;
+        for column in &mut self.columns {
+            column.swap_remove(row);
+        }
+        let actor_id = self.actor_ids.swap_remove(row);
+        self.row_slots.swap_remove(row);
+        if row != last_row {
+            let moved_slot = self.row_slots[row];
+            self.slots[moved_slot as usize].row = Some(row);
+        }
+
+        let slot = &mut self.slots[handle.slot as usize];
+        slot.row = None;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(handle.slot);
+        self.handles_by_id.remove(&actor_id);
+        true
+    }
+
+    /// Get the handle of a stored actor
+    pub fn get_index(&self, actor_id: &str) -> Option<ActorIndex> {
+        self.handles_by_id.get(actor_id).copied()
+    }
+
+    /// Check whether a handle refers to a stored actor
+    pub fn contains(&self, handle: ActorIndex) -> bool {
+        self.get_row(handle).is_some()
+    }
+
+    /// Get a stat value for an actor
+    pub fn get(&self, handle: ActorIndex, stat_name: &str) -> Option<f64> {
+        let row = self.get_row(handle)?;
+        let column = *self.stat_columns.get(stat_name)?;
+        Some(self.columns[column][row])
+    }
+
+    /// Set a stat value for an actor, returning whether the handle and stat exist
+    pub fn set(&mut self, handle: ActorIndex, stat_name: &str, value: f64) -> bool {
+        let (Some(row), Some(&column)) = (self.get_row(handle), self.stat_columns.get(stat_name)) else {
+            return false;
+        };
+        self.columns[column][row] = value;
+        true
+    }
+
+    /// Get a contiguous stat column (row order matches `actor_ids`)
+    pub fn column(&self, stat_name: &str) -> Option<&[f64]> {
+        let column = *self.stat_columns.get(stat_name)?;
+        Some(&self.columns[column])
+    }
+
+    /// Get a mutable contiguous stat column (row order matches `actor_ids`)
+    pub fn column_mut(&mut self, stat_name: &str) -> Option<&mut [f64]> {
+        let column = *self.stat_columns.get(stat_name)?;
+        Some(&mut self.columns[column])
+    }
+
+    /// Get actor IDs in row order
+    pub fn actor_ids(&self) -> &[String] {
+        &self.actor_ids
+    }
+
+    /// Get stat names in column order
+    pub fn stat_names(&self) -> &[String] {
+        &self.stat_names
+    }
+
+    /// Get number of stored actors
+    pub fn len(&self) -> usize {
+        self.actor_ids.len()
+    }
+
+    /// Check whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.actor_ids.is_empty()
+    }
+
+    /// Copy an actor's global stats into its row
+    ///
+    /// Stats the store has not seen yet get a new column; stats missing on the actor keep their value.
+    pub fn sync_from_actor(&mut self, handle: ActorIndex, actor: &HierarchicalActor) -> Result<(), String> {
+        let row = self.get_row(handle)
+            .ok_or_else(|| "Invalid or stale actor index".to_string())?;
+        if self.actor_ids[row] != actor.get_id() {
+            return Err(format!(
+                "Actor index belongs to '{}', not '{}'",
+                self.actor_ids[row],
+                actor.get_id()
+            ));
+        }
+
+        for (stat_name, value) in actor.get_global_stats_cache() {
+            let column = self.add_stat_column(stat_name);
+            self.columns[column][row] = *value;
+        }
+        Ok(())
+    }
+
+    /// Write a row back into an actor's global stats
+    ///
+    /// Every column is written, so stats the actor never had are set to their stored value (0.0 by default).
+    pub fn sync_to_actor(&self, handle: ActorIndex, actor: &mut HierarchicalActor) -> Result<(), String> {
+        let row = self.get_row(handle)
+            .ok_or_else(|| "Invalid or stale actor index".to_string())?;
+        if self.actor_ids[row] != actor.get_id() {
+            return Err(format!(
+                "Actor index belongs to '{}', not '{}'",
+                self.actor_ids[row],
+                actor.get_id()
+            ));
+        }
+
+        let mut stats = actor.get_global_stats_cache().clone();
+        for (stat_name, column) in self.stat_names.iter().zip(&self.columns) {
+            stats.insert(stat_name.clone(), column[row]);
+        }
+        actor.update_global_stats_cache(stats);
+        Ok(())
+    }
+
+    /// Resolve a handle to its current row
+    fn get_row(&self, handle: ActorIndex) -> Option<usize> {
+        let slot = self.slots.get(handle.slot as usize)?;
+        if slot.generation != handle.generation {
+            return None;
+        }
+        slot.row
+    }
+}
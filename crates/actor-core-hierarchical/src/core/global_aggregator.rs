@@ -2,8 +2,10 @@
 //! 
 //! Global stats aggregation system for combining contributions from all game systems.
 
-use crate::core::{HierarchicalActor, SystemContribution};
-use std::collections::HashMap;
+use crate::core::{ActorDelta, HierarchicalActor, SystemContribution};
+use crate::aggregation::{AggregatorPlugin, AggregatorPluginRegistry};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use chrono::Utc;
 
 /// Global aggregator for combining stats from all systems
@@ -15,10 +17,26 @@ pub struct GlobalAggregator {
     /// Cache for aggregated results
     pub aggregation_cache: HashMap<String, HashMap<String, f64>>,
     
+    /// Registered per-system aggregator plugins
+    pub plugin_registry: AggregatorPluginRegistry,
+    
+    /// Per-actor system blocks for delta re-aggregation
+    incremental_state: HashMap<String, IncrementalState>,
+    
     /// Last cache update timestamp
     last_cache_update: chrono::DateTime<Utc>,
 }
 
+/// Per-actor state kept for delta re-aggregation
+#[derive(Debug, Clone, Default)]
+struct IncrementalState {
+    /// Stat values contributed by each system
+    system_blocks: HashMap<String, HashMap<String, Vec<f64>>>,
+    
+    /// Aggregated stats before the plugin combine pass
+    base_stats: HashMap<String, f64>,
+}
+
 /// Aggregation strategy for different stat types
 #[derive(Debug, Clone)]
 pub enum AggregationStrategy {
@@ -48,6 +66,8 @@ impl GlobalAggregator {
         let mut aggregator = Self {
             aggregation_strategies: HashMap::new(),
             aggregation_cache: HashMap::new(),
+            plugin_registry: AggregatorPluginRegistry::new(),
+            incremental_state: HashMap::new(),
             last_cache_update: Utc::now(),
         };
         
@@ -92,6 +112,7 @@ impl GlobalAggregator {
     /// Set aggregation strategy for a stat
     pub fn set_aggregation_strategy(&mut self, stat_name: String, strategy: AggregationStrategy) {
         self.aggregation_strategies.insert(stat_name, strategy);
+        self.incremental_state.clear();
     }
     
     /// Get aggregation strategy for a stat
@@ -114,6 +135,21 @@ impl GlobalAggregator {
         }
         
         // Aggregate stats from all systems
+        let aggregated_stats = if self.plugin_registry.is_empty() {
+            self.aggregate_monolithic(actor)
+        } else {
+            self.aggregate_with_plugins(actor)
+        };
+        
+        // Update cache
+        self.aggregation_cache.insert(actor_id.to_string(), aggregated_stats.clone());
+        self.last_cache_update = Utc::now();
+        
+        aggregated_stats
+    }
+    
+    /// Aggregate raw contributions from all systems in a single pass
+    fn aggregate_monolithic(&self, actor: &HierarchicalActor) -> HashMap<String, f64> {
         let mut aggregated_stats = HashMap::new();
         
         // Collect all contributions by stat name
@@ -123,7 +159,7 @@ impl GlobalAggregator {
             for contribution in contributions {
                 stat_contributions
                     .entry(contribution.stat_name.clone())
-                    .or_insert_with(Vec::new)
+                    .or_default()
                     .push(contribution.value);
             }
         }
@@ -135,13 +171,172 @@ impl GlobalAggregator {
             aggregated_stats.insert(stat_name, aggregated_value);
         }
         
-        // Update cache
-        self.aggregation_cache.insert(actor_id.to_string(), aggregated_stats.clone());
+        aggregated_stats
+    }
+    
+    /// Aggregate using registered plugins
+    ///
+    /// Systems with a plugin contribute one partial value per stat; systems without one
+    /// contribute their raw values. Values are gathered in plugin order followed by the
+    /// remaining systems sorted by name, so the result is deterministic.
+    fn aggregate_with_plugins(&self, actor: &HierarchicalActor) -> HashMap<String, f64> {
+        let state = self.build_incremental_state(actor);
+        self.finalize_stats(&state.base_stats)
+    }
+    
+    /// Re-aggregate only the systems changed by a delta
+    ///
+    /// The actor must already contain the changes (either the source actor the delta was
+    /// collected from, or a replica the delta was applied to). Falls back to a full
+    /// aggregation the first time an actor is seen.
+    pub fn aggregate_actor_delta(&mut self, actor: &HierarchicalActor, delta: &ActorDelta) -> HashMap<String, f64> {
+        let actor_id = actor.get_id().to_string();
+        
+        let state = match self.incremental_state.remove(&actor_id) {
+            Some(mut state) => {
+                let mut affected_stats: HashSet<String> = HashSet::new();
+                
+                // Replace the blocks of changed systems
+                for system_name in delta.changed_systems() {
+                    if let Some(old_block) = state.system_blocks.remove(system_name) {
+                        affected_stats.extend(old_block.into_keys());
+                    }
+                    if let Some(contributions) = actor.system_contributions.get(system_name) {
+                        let block = self.compute_system_block(system_name, contributions);
+                        affected_stats.extend(block.keys().cloned());
+                        state.system_blocks.insert(system_name.to_string(), block);
+                    }
+                }
+                
+                // Recompute only the stats touched by those systems
+                let system_order = self.ordered_systems(&state.system_blocks);
+                for stat_name in affected_stats {
+                    let values: Vec<f64> = system_order
+                        .iter()
+                        .filter_map(|system_name| state.system_blocks[*system_name].get(&stat_name))
+                        .flatten()
+                        .copied()
+                        .collect();
+                    
+                    if values.is_empty() {
+                        state.base_stats.remove(&stat_name);
+                    } else {
+                        let strategy = self.get_aggregation_strategy(&stat_name);
+                        let aggregated_value = self.apply_aggregation_strategy(&strategy, &values);
+                        state.base_stats.insert(stat_name, aggregated_value);
+                    }
+                }
+                
+                state
+            }
+            None => self.build_incremental_state(actor),
+        };
+        
+        let aggregated_stats = self.finalize_stats(&state.base_stats);
+        self.incremental_state.insert(actor_id.clone(), state);
+        self.aggregation_cache.insert(actor_id, aggregated_stats.clone());
         self.last_cache_update = Utc::now();
         
         aggregated_stats
     }
     
+    /// Build system blocks and base stats for all systems of an actor
+    fn build_incremental_state(&self, actor: &HierarchicalActor) -> IncrementalState {
+        let system_blocks: HashMap<String, HashMap<String, Vec<f64>>> = actor.system_contributions
+            .iter()
+            .map(|(system_name, contributions)| {
+                (system_name.clone(), self.compute_system_block(system_name, contributions))
+            })
+            .collect();
+        
+        let mut stat_contributions: HashMap<String, Vec<f64>> = HashMap::new();
+        for system_name in self.ordered_systems(&system_blocks) {
+            for (stat_name, values) in &system_blocks[system_name] {
+                stat_contributions
+                    .entry(stat_name.clone())
+                    .or_default()
+                    .extend_from_slice(values);
+            }
+        }
+        
+        let mut base_stats = HashMap::new();
+        for (stat_name, contributions) in stat_contributions {
+            let strategy = self.get_aggregation_strategy(&stat_name);
+            let aggregated_value = self.apply_aggregation_strategy(&strategy, &contributions);
+            base_stats.insert(stat_name, aggregated_value);
+        }
+        
+        IncrementalState { system_blocks, base_stats }
+    }
+    
+    /// Compute the stat values one system contributes
+    fn compute_system_block(&self, system_name: &str, contributions: &[SystemContribution]) -> HashMap<String, Vec<f64>> {
+        let mut block: HashMap<String, Vec<f64>> = HashMap::new();
+        
+        match self.plugin_registry.get_plugin_for_system(system_name) {
+            Some(plugin) => {
+                for (stat_name, value) in plugin.aggregate_partial(contributions) {
+                    block.insert(stat_name, vec![value]);
+                }
+            }
+            None => {
+                for contribution in contributions {
+                    block
+                        .entry(contribution.stat_name.clone())
+                        .or_default()
+                        .push(contribution.value);
+                }
+            }
+        }
+        
+        block
+    }
+    
+    /// Order systems for combining: plugin-owned systems first, then the rest by name
+    fn ordered_systems<'a, T>(&self, system_blocks: &'a HashMap<String, T>) -> Vec<&'a str> {
+        let mut ordered: Vec<&'a str> = self.plugin_registry
+            .get_plugins()
+            .iter()
+            .filter_map(|plugin| system_blocks.get_key_value(plugin.system_name()))
+            .map(|(system_name, _)| system_name.as_str())
+            .collect();
+        
+        let mut remaining: Vec<&'a str> = system_blocks
+            .keys()
+            .filter(|system_name| self.plugin_registry.get_plugin_for_system(system_name).is_none())
+            .map(|system_name| system_name.as_str())
+            .collect();
+        remaining.sort_unstable();
+        
+        ordered.extend(remaining);
+        ordered
+    }
+    
+    /// Run the plugin combine pass over base stats
+    fn finalize_stats(&self, base_stats: &HashMap<String, f64>) -> HashMap<String, f64> {
+        let mut aggregated_stats = base_stats.clone();
+        for plugin in self.plugin_registry.get_plugins() {
+            plugin.combine(&mut aggregated_stats);
+        }
+        aggregated_stats
+    }
+    
+    /// Register an aggregator plugin
+    pub fn register_plugin(&mut self, plugin: Arc<dyn AggregatorPlugin>) -> Result<(), String> {
+        self.plugin_registry.register(plugin)?;
+        self.clear_cache();
+        Ok(())
+    }
+    
+    /// Unregister an aggregator plugin by name
+    pub fn unregister_plugin(&mut self, name: &str) -> bool {
+        let removed = self.plugin_registry.unregister(name);
+        if removed {
+            self.clear_cache();
+        }
+        removed
+    }
+    
     /// Apply aggregation strategy to contributions
     pub fn apply_aggregation_strategy(&self, strategy: &AggregationStrategy, contributions: &[f64]) -> f64 {
         if contributions.is_empty() {
@@ -167,11 +362,13 @@ impl GlobalAggregator {
     /// Invalidate cache for an actor
     pub fn invalidate_actor_cache(&mut self, actor_id: &str) {
         self.aggregation_cache.remove(actor_id);
+        self.incremental_state.remove(actor_id);
     }
     
     /// Clear all cache
     pub fn clear_cache(&mut self) {
         self.aggregation_cache.clear();
+        self.incremental_state.clear();
     }
     
     /// Get cache statistics
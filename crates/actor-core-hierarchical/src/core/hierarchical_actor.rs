@@ -2,8 +2,10 @@
 //! 
 //! Core hierarchical actor data structure for managing actor properties across multiple game systems.
 
+use crate::core::{ActorDelta, DirtyFlags, HotActorData, HotDataSnapshot};
 use element_core::{ElementalSystem, ElementalSystemData};
 use std::collections::HashMap;
+use std::sync::Arc;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
@@ -32,6 +34,12 @@ pub struct HierarchicalActor {
     
     /// Actor metadata
     pub metadata: HashMap<String, String>,
+    
+    /// Blocks modified since the last collected delta
+    pub dirty_flags: DirtyFlags,
+    
+    /// Lock-free published copy of hot data for readers on other threads
+    hot_data: Arc<HotActorData>,
 }
 
 /// System contribution for hierarchical aggregation
@@ -70,6 +78,8 @@ impl Clone for HierarchicalActor {
             global_stats_cache: self.global_stats_cache.clone(),
             system_contributions: self.system_contributions.clone(),
             metadata: self.metadata.clone(),
+            dirty_flags: self.dirty_flags.clone(),
+            hot_data: Arc::new(HotActorData::new(HotDataSnapshot::clone(&self.hot_data.load()))),
         }
     }
 }
@@ -85,6 +95,8 @@ impl std::fmt::Debug for HierarchicalActor {
             .field("global_stats_cache", &self.global_stats_cache)
             .field("system_contributions", &self.system_contributions)
             .field("metadata", &self.metadata)
+            .field("dirty_flags", &self.dirty_flags)
+            .field("hot_data_version", &self.hot_data.version())
             .finish()
     }
 }
@@ -102,6 +114,8 @@ impl HierarchicalActor {
             global_stats_cache: HashMap::new(),
             system_contributions: HashMap::new(),
             metadata: HashMap::new(),
+            dirty_flags: DirtyFlags::default(),
+            hot_data: Arc::new(HotActorData::default()),
         }
     }
     
@@ -117,6 +131,8 @@ impl HierarchicalActor {
             global_stats_cache: HashMap::new(),
             system_contributions: HashMap::new(),
             metadata: HashMap::new(),
+            dirty_flags: DirtyFlags::default(),
+            hot_data: Arc::new(HotActorData::default()),
         }
     }
     
@@ -133,6 +149,7 @@ impl HierarchicalActor {
     /// Set actor name
     pub fn set_name(&mut self, name: String) {
         self.name = name;
+        self.dirty_flags.name = true;
         self.updated_at = Utc::now();
     }
     
@@ -143,6 +160,7 @@ impl HierarchicalActor {
     
     /// Get mutable elemental system data
     pub fn get_elemental_system_mut(&mut self) -> &mut ElementalSystem {
+        self.dirty_flags.elemental = true;
         self.updated_at = Utc::now();
         &mut self.elemental_system
     }
@@ -154,6 +172,7 @@ impl HierarchicalActor {
     
     /// Get mutable elemental system data
     pub fn get_elemental_data_mut(&mut self) -> &mut ElementalSystemData {
+        self.dirty_flags.elemental = true;
         self.updated_at = Utc::now();
         self.elemental_system.get_data_mut()
     }
@@ -161,6 +180,7 @@ impl HierarchicalActor {
     /// Add system contribution
     pub fn add_system_contribution(&mut self, contribution: SystemContribution) {
         let system_name = contribution.system_name.clone();
+        self.dirty_flags.mark_system(&system_name);
         self.system_contributions
             .entry(system_name)
             .or_insert_with(Vec::new)
@@ -168,6 +188,16 @@ impl HierarchicalActor {
         self.updated_at = Utc::now();
     }
     
+    /// Remove all contributions of a system
+    pub fn remove_system_contributions(&mut self, system_name: &str) -> Option<Vec<SystemContribution>> {
+        let removed = self.system_contributions.remove(system_name);
+        if removed.is_some() {
+            self.dirty_flags.mark_system(system_name);
+            self.updated_at = Utc::now();
+        }
+        removed
+    }
+    
     /// Get system contributions
     pub fn get_system_contributions(&self, system_name: &str) -> Option<&Vec<SystemContribution>> {
         self.system_contributions.get(system_name)
@@ -176,6 +206,7 @@ impl HierarchicalActor {
     /// Update global stats cache
     pub fn update_global_stats_cache(&mut self, stats: HashMap<String, f64>) {
         self.global_stats_cache = stats;
+        self.dirty_flags.global_stats = true;
         self.updated_at = Utc::now();
     }
     
@@ -187,6 +218,7 @@ impl HierarchicalActor {
     /// Set metadata
     pub fn set_metadata(&mut self, key: String, value: String) {
         self.metadata.insert(key, value);
+        self.dirty_flags.metadata = true;
         self.updated_at = Utc::now();
     }
     
@@ -209,5 +241,99 @@ impl HierarchicalActor {
     pub fn get_updated_at(&self) -> DateTime<Utc> {
         self.updated_at
     }
+    
+    /// Get a shared handle to the published hot data
+    ///
+    /// The handle can be moved to game loop threads; reads through it never lock.
+    pub fn get_hot_data_handle(&self) -> Arc<HotActorData> {
+        Arc::clone(&self.hot_data)
+    }
+    
+    /// Publish the current global stats and elemental arrays to hot data readers
+    pub fn publish_hot_data(&self) -> u64 {
+        self.hot_data.publish(HotDataSnapshot::new(
+            self.global_stats_cache.clone(),
+            self.elemental_system.get_data().clone(),
+        ))
+    }
+    
+    /// Get dirty flags
+    pub fn get_dirty_flags(&self) -> &DirtyFlags {
+        &self.dirty_flags
+    }
+    
+    /// Check if any block changed since the last collected delta
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty_flags.is_clean()
+    }
+    
+    /// Mark a system's contributions as dirty
+    pub fn mark_system_dirty(&mut self, system_name: &str) {
+        self.dirty_flags.mark_system(system_name);
+        self.updated_at = Utc::now();
+    }
+    
+    /// Collect modified blocks into a delta and clear the dirty flags
+    pub fn collect_changes(&mut self) -> ActorDelta {
+        let flags = std::mem::take(&mut self.dirty_flags);
+        let mut delta = ActorDelta::new(self.id.clone(), self.updated_at);
+        
+        if flags.name {
+            delta.name = Some(self.name.clone());
+        }
+        if flags.metadata {
+            delta.metadata = Some(self.metadata.clone());
+        }
+        if flags.elemental {
+            delta.elemental_data = Some(Box::new(self.elemental_system.get_data().clone()));
+        }
+        if flags.global_stats {
+            delta.global_stats = Some(self.global_stats_cache.clone());
+        }
+        for system_name in flags.systems {
+            match self.system_contributions.get(&system_name) {
+                Some(contributions) => {
+                    delta.system_contributions.insert(system_name, contributions.clone());
+                }
+                None => delta.removed_systems.push(system_name),
+            }
+        }
+        delta.removed_systems.sort();
+        
+        delta
+    }
+    
+    /// Apply a delta collected from another copy of this actor
+    ///
+    /// Replicas are not a source of changes, so applying a delta does not mark any block dirty.
+    pub fn apply_delta(&mut self, delta: &ActorDelta) -> Result<(), String> {
+        if delta.actor_id != self.id {
+            return Err(format!(
+                "Delta for actor '{}' cannot be applied to actor '{}'",
+                delta.actor_id, self.id
+            ));
+        }
+        
+        if let Some(name) = &delta.name {
+            self.name = name.clone();
+        }
+        if let Some(metadata) = &delta.metadata {
+            self.metadata = metadata.clone();
+        }
+        if let Some(elemental_data) = &delta.elemental_data {
+            self.elemental_system.set_data(elemental_data.as_ref().clone());
+        }
+        if let Some(global_stats) = &delta.global_stats {
+            self.global_stats_cache = global_stats.clone();
+        }
+        for (system_name, contributions) in &delta.system_contributions {
+            self.system_contributions.insert(system_name.clone(), contributions.clone());
+        }
+        for system_name in &delta.removed_systems {
+            self.system_contributions.remove(system_name);
+        }
+        
+        self.updated_at = delta.updated_at;
+        Ok(())
+    }
 }
-
@@ -0,0 +1,138 @@
+//! # Hot Actor Data
+//!
+//! Lock-free read path for the data the game loop reads every tick.
+//!
+//! Hot blocks are published as immutable, versioned snapshots behind an `ArcSwap`.
+//! Readers never take a lock: they load the current snapshot and read its arrays
+//! directly. Writers build a new copy and publish it, so a snapshot can never be
+//! observed half-written. Readers that combine several loads (or hold a snapshot
+//! across a tick) can compare versions to detect that a newer copy was published.
+
+use arc_swap::ArcSwap;
+use element_core::ElementalSystemData;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Immutable snapshot of an actor's hot data
+#[derive(Debug, Clone)]
+pub struct HotDataSnapshot {
+    /// Monotonic snapshot version (0 for the initial snapshot)
+    pub version: u64,
+
+    /// Aggregated global stats
+    pub global_stats: HashMap<String, f64>,
+
+    /// Elemental stat arrays
+    pub elemental_data: ElementalSystemData,
+}
+
+impl Default for HotDataSnapshot {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            global_stats: HashMap::new(),
+            elemental_data: ElementalSystemData::new(),
+        }
+    }
+}
+
+impl HotDataSnapshot {
+    /// Create a new snapshot from hot blocks
+    pub fn new(global_stats: HashMap<String, f64>, elemental_data: ElementalSystemData) -> Self {
+        Self {
+            version: 0,
+            global_stats,
+            elemental_data,
+        }
+    }
+
+    /// Get a global stat value
+    pub fn get_global_stat(&self, stat_name: &str) -> Option<f64> {
+        self.global_stats.get(stat_name).copied()
+    }
+}
+
+/// Shared handle to an actor's hot data
+///
+/// Cheap to share across threads through `Arc`; all reads are lock-free.
+#[derive(Debug)]
+pub struct HotActorData {
+    /// Currently published snapshot
+    current: ArcSwap<HotDataSnapshot>,
+}
+
+impl Default for HotActorData {
+    fn default() -> Self {
+        Self::new(HotDataSnapshot::default())
+    }
+}
+
+impl HotActorData {
+    /// Create a new handle with an initial snapshot
+    pub fn new(snapshot: HotDataSnapshot) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(snapshot),
+        }
+    }
+
+    /// Load the current snapshot (lock-free)
+    pub fn load(&self) -> Arc<HotDataSnapshot> {
+        self.current.load_full()
+    }
+
+    /// Read the current snapshot without cloning the `Arc` (lock-free)
+    pub fn read<R>(&self, f: impl FnOnce(&HotDataSnapshot) -> R) -> R {
+        f(&self.current.load())
+    }
+
+    /// Get the last published version
+    pub fn version(&self) -> u64 {
+        self.current.load().version
+    }
+
+    /// Check whether a snapshot is still the latest published one
+    pub fn is_current(&self, snapshot: &HotDataSnapshot) -> bool {
+        snapshot.version == self.version()
+    }
+
+    /// Run a read that spans several loads and retry until no write happened in between
+    ///
+    /// Seqlock-style: the version is sampled before and after the read and the read is
+    /// repeated if they differ. Returns the result together with the version it saw.
+    pub fn read_consistent<R>(&self, mut f: impl FnMut(&Self) -> R) -> (R, u64) {
+        loop {
+            let before = self.version();
+            let result = f(self);
+            if self.version() == before {
+                return (result, before);
+            }
+        }
+    }
+
+    /// Publish a new snapshot, returning its version
+    pub fn publish(&self, snapshot: HotDataSnapshot) -> u64 {
+        let mut published = 0;
+        self.current.rcu(|current| {
+            let mut next = snapshot.clone();
+            published = current.version + 1;
+            next.version = published;
+            next
+        });
+        published
+    }
+
+    /// Copy the current snapshot, modify it, and publish the copy
+    ///
+    /// Concurrent writers are serialized by retrying on conflict, so no update is lost.
+    pub fn update(&self, f: impl Fn(&mut HotDataSnapshot)) -> u64 {
+        let mut published = 0;
+        self.current.rcu(|current| {
+            let mut next = HotDataSnapshot::clone(current);
+            f(&mut next);
+            published = current.version + 1;
+            next.version = published;
+            next
+        });
+        published
+    }
+}
@@ -5,7 +5,13 @@
 pub mod hierarchical_actor;
 pub mod global_aggregator;
 pub mod actor_factory;
+pub mod actor_delta;
+pub mod hot_data;
+pub mod column_store;
 
 pub use hierarchical_actor::*;
 pub use global_aggregator::*;
 pub use actor_factory::*;
+pub use actor_delta::*;
+pub use hot_data::*;
+pub use column_store::*;
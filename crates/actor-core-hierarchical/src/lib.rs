@@ -22,6 +22,8 @@
 //! Actor Core Hierarchical
 //! +-- Core
 //! |   +-- HierarchicalActor      # Main actor data structure
+//! |   +-- HotActorData           # Lock-free published hot data
+//! |   +-- ActorColumnStore       # Structure-of-arrays stat storage
 //! |   +-- GlobalAggregator       # Global stats aggregation
 //! |   +-- ActorFactory           # Actor creation factory
 //! +-- Systems
@@ -30,7 +32,11 @@
 //! |   +-- BaseAdapter            # Base adapter trait
 //! |   +-- ActorAdapter           # Actor data conversion
 //! +-- Aggregation
-//!     +-- (Future: Custom aggregators)
+//! |   +-- AggregatorPlugin       # Per-system aggregation plugins
+//! +-- Persistence
+//!     +-- ActorBinaryCodec       # Versioned compact binary codec
+//!     +-- Bulk save/load         # Shutdown/startup dumps
+//!     +-- BSON documents         # MongoDB storage (mongodb-storage feature)
 //! ```
 //!
 //! ## Usage
@@ -60,9 +66,11 @@ pub mod core;
 pub mod systems;
 pub mod adapters;
 pub mod aggregation;
+pub mod persistence;
 
 // Re-export commonly used types
 pub use core::*;
 pub use systems::*;
 pub use adapters::*;
-pub use aggregation::*;
\ No newline at end of file
+pub use aggregation::*;
+pub use persistence::*;
\ No newline at end of file
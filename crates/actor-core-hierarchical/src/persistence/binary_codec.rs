@@ -0,0 +1,322 @@
+//! # Binary Codec
+//!
+//! Compact little-endian binary encoding for hierarchical actors.
+//!
+//! ```text
+//! header : magic "HACT" | schema_version u16 | block_count u16
+//! core   : id str | name str | created_at i64 (us) | updated_at i64 (us)
+//! blocks : (tag u16 | length u32 | payload)*
+//! ```
+//!
+//! Strings are a `u32` byte length followed by UTF-8 bytes. Empty blocks are omitted,
+//! and unknown block tags are skipped on decode.
+
+use crate::core::{HierarchicalActor, SystemContribution};
+use crate::persistence::{
+    elemental_arrays, elemental_arrays_mut, restore_mastery_level_enums, PersistenceResult,
+    ACTOR_SCHEMA_VERSION,
+};
+use element_core::MAX_ELEMENTS;
+use chrono::{DateTime, Utc};
+
+/// Magic bytes at the start of every encoded actor
+pub const ACTOR_BINARY_MAGIC: [u8; 4] = *b"HACT";
+
+/// Block tags
+pub const BLOCK_METADATA: u16 = 1;
+pub const BLOCK_SYSTEM_CONTRIBUTIONS: u16 = 2;
+pub const BLOCK_GLOBAL_STATS: u16 = 3;
+pub const BLOCK_ELEMENTAL: u16 = 4;
+
+impl HierarchicalActor {
+    /// Encode the actor with the binary codec
+    pub fn to_binary(&self) -> Vec<u8> {
+        ActorBinaryCodec::encode(self)
+    }
+
+    /// Decode an actor encoded with the binary codec
+    pub fn from_binary(bytes: &[u8]) -> PersistenceResult<Self> {
+        ActorBinaryCodec::decode(bytes)
+    }
+}
+
+/// Binary codec for hierarchical actors
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActorBinaryCodec;
+
+impl ActorBinaryCodec {
+    /// Encode an actor
+    pub fn encode(actor: &HierarchicalActor) -> Vec<u8> {
+        let mut blocks: Vec<(u16, Vec<u8>)> = Vec::new();
+
+        if !actor.metadata.is_empty() {
+            let mut writer = BinaryWriter::new();
+            let mut entries: Vec<_> = actor.metadata.iter().collect();
+            entries.sort();
+            writer.write_u32(entries.len() as u32);
+            for (key, value) in entries {
+                writer.write_str(key);
+                writer.write_str(value);
+            }
+            blocks.push((BLOCK_METADATA, writer.into_bytes()));
+        }
+
+        if !actor.system_contributions.is_empty() {
+            let mut writer = BinaryWriter::new();
+            let mut systems: Vec<_> = actor.system_contributions.iter().collect();
+            systems.sort_by(|a, b| a.0.cmp(b.0));
+            writer.write_u32(systems.len() as u32);
+            for (system_name, contributions) in systems {
+                writer.write_str(system_name);
+                writer.write_u32(contributions.len() as u32);
+                for contribution in contributions {
+                    writer.write_str(&contribution.stat_name);
+                    writer.write_f64(contribution.value);
+                    writer.write_u32(contribution.priority);
+                    writer.write_i64(contribution.timestamp.timestamp_micros());
+                }
+            }
+            blocks.push((BLOCK_SYSTEM_CONTRIBUTIONS, writer.into_bytes()));
+        }
+
+        if !actor.global_stats_cache.is_empty() {
+            let mut writer = BinaryWriter::new();
+            let mut stats: Vec<_> = actor.global_stats_cache.iter().collect();
+            stats.sort_by(|a, b| a.0.cmp(b.0));
+            writer.write_u32(stats.len() as u32);
+            for (stat_name, value) in stats {
+                writer.write_str(stat_name);
+                writer.write_f64(*value);
+            }
+            blocks.push((BLOCK_GLOBAL_STATS, writer.into_bytes()));
+        }
+
+        let elemental_data = actor.elemental_system.get_data();
+        let mut writer = BinaryWriter::new();
+        let arrays = elemental_arrays(elemental_data);
+        writer.write_u16(MAX_ELEMENTS as u16);
+        writer.write_u16(arrays.len() as u16);
+        for array in arrays {
+            for value in array.iter() {
+                writer.write_f64(*value);
+            }
+        }
+        for row in elemental_data.element_interaction_bonuses.iter() {
+            for value in row.iter() {
+                writer.write_f64(*value);
+            }
+        }
+        blocks.push((BLOCK_ELEMENTAL, writer.into_bytes()));
+
+        let mut writer = BinaryWriter::new();
+        writer.write_bytes(&ACTOR_BINARY_MAGIC);
+        writer.write_u16(ACTOR_SCHEMA_VERSION);
+        writer.write_u16(blocks.len() as u16);
+        writer.write_str(&actor.id);
+        writer.write_str(&actor.name);
+        writer.write_i64(actor.created_at.timestamp_micros());
+        writer.write_i64(actor.updated_at.timestamp_micros());
+        for (tag, payload) in blocks {
+            writer.write_u16(tag);
+            writer.write_u32(payload.len() as u32);
+            writer.write_bytes(&payload);
+        }
+        writer.into_bytes()
+    }
+
+    /// Decode an actor
+    pub fn decode(bytes: &[u8]) -> PersistenceResult<HierarchicalActor> {
+        let mut reader = BinaryReader::new(bytes);
+
+        if reader.read_bytes(4)? != ACTOR_BINARY_MAGIC {
+            return Err("Invalid actor binary: bad magic".to_string());
+        }
+        let schema_version = reader.read_u16()?;
+        if schema_version > ACTOR_SCHEMA_VERSION {
+            return Err(format!(
+                "Actor schema version {} is newer than supported version {}",
+                schema_version, ACTOR_SCHEMA_VERSION
+            ));
+        }
+        let block_count = reader.read_u16()?;
+
+        let id = reader.read_string()?;
+        let name = reader.read_string()?;
+        let mut actor = HierarchicalActor::with_id_and_name(id, name);
+        actor.created_at = reader.read_timestamp()?;
+        actor.updated_at = reader.read_timestamp()?;
+
+        for _ in 0..block_count {
+            let tag = reader.read_u16()?;
+            let length = reader.read_u32()? as usize;
+            let mut block = BinaryReader::new(reader.read_bytes(length)?);
+
+            match tag {
+                BLOCK_METADATA => {
+                    for _ in 0..block.read_u32()? {
+                        let key = block.read_string()?;
+                        let value = block.read_string()?;
+                        actor.metadata.insert(key, value);
+                    }
+                }
+                BLOCK_SYSTEM_CONTRIBUTIONS => {
+                    for _ in 0..block.read_u32()? {
+                        let system_name = block.read_string()?;
+                        let count = block.read_u32()? as usize;
+                        let mut contributions = Vec::with_capacity(count.min(block.remaining()));
+                        for _ in 0..count {
+                            contributions.push(SystemContribution {
+                                system_name: system_name.clone(),
+                                stat_name: block.read_string()?,
+                                value: block.read_f64()?,
+                                priority: block.read_u32()?,
+                                timestamp: block.read_timestamp()?,
+                            });
+                        }
+                        actor.system_contributions.insert(system_name, contributions);
+                    }
+                }
+                BLOCK_GLOBAL_STATS => {
+                    for _ in 0..block.read_u32()? {
+                        let stat_name = block.read_string()?;
+                        let value = block.read_f64()?;
+                        actor.global_stats_cache.insert(stat_name, value);
+                    }
+                }
+                BLOCK_ELEMENTAL => {
+                    let element_count = block.read_u16()? as usize;
+                    let array_count = block.read_u16()? as usize;
+                    let data = actor.elemental_system.get_data_mut();
+
+                    {
+                        let mut arrays = elemental_arrays_mut(data);
+                        for array_index in 0..array_count {
+                            for element_index in 0..element_count {
+                                let value = block.read_f64()?;
+                                if let Some(array) = arrays.get_mut(array_index) {
+                                    if element_index < MAX_ELEMENTS {
+                                        array[element_index] = value;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    for attacker in 0..element_count {
+                        for defender in 0..element_count {
+                            let value = block.read_f64()?;
+                            if attacker < MAX_ELEMENTS && defender < MAX_ELEMENTS {
+                                data.element_interaction_bonuses[attacker][defender] = value;
+                            }
+                        }
+                    }
+                    restore_mastery_level_enums(data);
+                }
+                // Blocks written by newer schema versions
+                _ => {}
+            }
+        }
+
+        Ok(actor)
+    }
+}
+
+/// Little-endian byte writer
+struct BinaryWriter {
+    buffer: Vec<u8>,
+}
+
+impl BinaryWriter {
+    fn new() -> Self {
+        Self { buffer: Vec::new() }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_f64(&mut self, value: f64) {
+        self.buffer.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_str(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.write_bytes(value.as_bytes());
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+/// Little-endian byte reader with bounds checking
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    fn read_bytes(&mut self, length: usize) -> PersistenceResult<&'a [u8]> {
+        if length > self.remaining() {
+            return Err(format!(
+                "Unexpected end of actor binary at offset {} (needed {} bytes)",
+                self.position, length
+            ));
+        }
+        let slice = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(slice)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> PersistenceResult<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    fn read_u16(&mut self) -> PersistenceResult<u16> {
+        Ok(u16::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_u32(&mut self) -> PersistenceResult<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_i64(&mut self) -> PersistenceResult<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_f64(&mut self) -> PersistenceResult<f64> {
+        Ok(f64::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_string(&mut self) -> PersistenceResult<String> {
+        let length = self.read_u32()? as usize;
+        let bytes = self.read_bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|e| format!("Invalid UTF-8 in actor binary: {}", e))
+    }
+
+    fn read_timestamp(&mut self) -> PersistenceResult<DateTime<Utc>> {
+        let micros = self.read_i64()?;
+        DateTime::from_timestamp_micros(micros).ok_or_else(|| format!("Invalid timestamp in actor binary: {}", micros))
+    }
+}
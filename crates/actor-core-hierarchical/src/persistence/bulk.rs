@@ -0,0 +1,97 @@
+//! # Bulk Persistence
+//!
+//! Bulk save/load of many actors at once, used on server shutdown and startup.
+//!
+//! ```text
+//! magic "HABK" | schema_version u16 | actor_count u32 | (length u32 | actor binary)*
+//! ```
+
+use crate::core::HierarchicalActor;
+use crate::persistence::{ActorBinaryCodec, PersistenceResult, ACTOR_SCHEMA_VERSION};
+use std::fs;
+use std::path::Path;
+
+/// Magic bytes at the start of a bulk actor dump
+pub const ACTOR_BULK_MAGIC: [u8; 4] = *b"HABK";
+
+/// Encode many actors into a single bulk dump
+pub fn save_actors(actors: &[HierarchicalActor]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&ACTOR_BULK_MAGIC);
+    bytes.extend_from_slice(&ACTOR_SCHEMA_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(actors.len() as u32).to_le_bytes());
+
+    for actor in actors {
+        let encoded = ActorBinaryCodec::encode(actor);
+        bytes.extend_from_slice(&(encoded.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&encoded);
+    }
+
+    bytes
+}
+
+/// Decode a bulk dump produced by `save_actors`
+pub fn load_actors(bytes: &[u8]) -> PersistenceResult<Vec<HierarchicalActor>> {
+    if bytes.len() < 10 || bytes[0..4] != ACTOR_BULK_MAGIC {
+        return Err("Invalid actor bulk dump: bad header".to_string());
+    }
+
+    let schema_version = u16::from_le_bytes([bytes[4], bytes[5]]);
+    if schema_version > ACTOR_SCHEMA_VERSION {
+        return Err(format!(
+            "Actor bulk dump schema version {} is newer than supported version {}",
+            schema_version, ACTOR_SCHEMA_VERSION
+        ));
+    }
+
+    let actor_count = u32::from_le_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+    let mut actors = Vec::with_capacity(actor_count.min(bytes.len()));
+    let mut position = 10;
+
+    for index in 0..actor_count {
+        if bytes.len() < position + 4 {
+            return Err(format!("Actor bulk dump truncated before actor {}", index));
+        }
+        let length = u32::from_le_bytes([
+            bytes[position],
+            bytes[position + 1],
+            bytes[position + 2],
+            bytes[position + 3],
+        ]) as usize;
+        position += 4;
+
+        if bytes.len() < position + length {
+            return Err(format!("Actor bulk dump truncated inside actor {}", index));
+        }
+        let actor = ActorBinaryCodec::decode(&bytes[position..position + length])
+            .map_err(|e| format!("Failed to decode actor {}: {}", index, e))?;
+        actors.push(actor);
+        position += length;
+    }
+
+    Ok(actors)
+}
+
+/// Save many actors to a file
+///
+/// The dump is written to a temporary file first and renamed into place, so a crash
+/// during shutdown never leaves a partially written dump behind.
+pub fn save_actors_to_file(path: impl AsRef<Path>, actors: &[HierarchicalActor]) -> PersistenceResult<()> {
+    let path = path.as_ref();
+    let temp_path = path.with_extension("tmp");
+
+    fs::write(&temp_path, save_actors(actors))
+        .map_err(|e| format!("Failed to write actor dump '{}': {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path)
+        .map_err(|e| format!("Failed to move actor dump into '{}': {}", path.display(), e))?;
+
+    Ok(())
+}
+
+/// Load many actors from a file written by `save_actors_to_file`
+pub fn load_actors_from_file(path: impl AsRef<Path>) -> PersistenceResult<Vec<HierarchicalActor>> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read actor dump '{}': {}", path.display(), e))?;
+    load_actors(&bytes)
+}
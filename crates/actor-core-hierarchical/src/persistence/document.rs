@@ -0,0 +1,219 @@
+//! # Document Persistence
+//!
+//! MongoDB BSON documents for hierarchical actors.
+//!
+//! ```text
+//! { _id, schema_version, name, created_at, updated_at,
+//!   metadata?: { key: value },
+//!   systems?: { system_name: [ { stat_name, value, priority, timestamp } ] },
+//!   global_stats?: { stat_name: value },
+//!   elemental?: { <array name>: [f64], element_interaction_bonuses: [[f64]] } }
+//! ```
+//!
+//! Timestamps are stored as BSON dates (millisecond precision) so they can be queried.
+
+use crate::core::{HierarchicalActor, SystemContribution};
+use crate::persistence::{
+    elemental_arrays, elemental_arrays_mut, restore_mastery_level_enums, PersistenceResult,
+    ACTOR_SCHEMA_VERSION, ELEMENTAL_ARRAY_NAMES,
+};
+use bson::{Bson, DateTime as BsonDateTime, Document};
+use chrono::{DateTime, Utc};
+use element_core::MAX_ELEMENTS;
+
+impl HierarchicalActor {
+    /// Convert the actor to a BSON document
+    pub fn to_document(&self) -> Document {
+        let mut document = Document::new();
+        document.insert("_id", self.id.clone());
+        document.insert("schema_version", ACTOR_SCHEMA_VERSION as i32);
+        document.insert("name", self.name.clone());
+        document.insert("created_at", to_bson_date(self.created_at));
+        document.insert("updated_at", to_bson_date(self.updated_at));
+
+        if !self.metadata.is_empty() {
+            let mut metadata = Document::new();
+            let mut entries: Vec<_> = self.metadata.iter().collect();
+            entries.sort();
+            for (key, value) in entries {
+                metadata.insert(key.clone(), value.clone());
+            }
+            document.insert("metadata", metadata);
+        }
+
+        if !self.system_contributions.is_empty() {
+            let mut systems = Document::new();
+            let mut entries: Vec<_> = self.system_contributions.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (system_name, contributions) in entries {
+                let contributions: Vec<Bson> = contributions
+                    .iter()
+                    .map(|contribution| {
+                        let mut entry = Document::new();
+                        entry.insert("stat_name", contribution.stat_name.clone());
+                        entry.insert("value", contribution.value);
+                        entry.insert("priority", contribution.priority as i64);
+                        entry.insert("timestamp", to_bson_date(contribution.timestamp));
+                        Bson::Document(entry)
+                    })
+                    .collect();
+                systems.insert(system_name.clone(), contributions);
+            }
+            document.insert("systems", systems);
+        }
+
+        if !self.global_stats_cache.is_empty() {
+            let mut global_stats = Document::new();
+            let mut entries: Vec<_> = self.global_stats_cache.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            for (stat_name, value) in entries {
+                global_stats.insert(stat_name.clone(), *value);
+            }
+            document.insert("global_stats", global_stats);
+        }
+
+        let elemental_data = self.elemental_system.get_data();
+        let mut elemental = Document::new();
+        for (name, array) in ELEMENTAL_ARRAY_NAMES.iter().zip(elemental_arrays(elemental_data)) {
+            elemental.insert(*name, array.to_vec());
+        }
+        let interactions: Vec<Bson> = elemental_data
+            .element_interaction_bonuses
+            .iter()
+            .map(|row| Bson::from(row.to_vec()))
+            .collect();
+        elemental.insert("element_interaction_bonuses", interactions);
+        document.insert("elemental", elemental);
+
+        document
+    }
+
+    /// Load an actor from a BSON document
+    ///
+    /// Optional blocks that are missing (e.g. systems added after the document was saved)
+    /// keep their defaults.
+    pub fn from_document(document: &Document) -> PersistenceResult<Self> {
+        let schema_version = document
+            .get_i32("schema_version")
+            .map_err(|e| format!("Invalid actor document schema_version: {}", e))?;
+        if schema_version > ACTOR_SCHEMA_VERSION as i32 {
+            return Err(format!(
+                "Actor document schema version {} is newer than supported version {}",
+                schema_version, ACTOR_SCHEMA_VERSION
+            ));
+        }
+
+        let id = document
+            .get_str("_id")
+            .map_err(|e| format!("Invalid actor document _id: {}", e))?;
+        let name = document
+            .get_str("name")
+            .map_err(|e| format!("Invalid actor document name: {}", e))?;
+
+        let mut actor = HierarchicalActor::with_id_and_name(id.to_string(), name.to_string());
+        actor.created_at = get_date(document, "created_at")?;
+        actor.updated_at = get_date(document, "updated_at")?;
+
+        if let Ok(metadata) = document.get_document("metadata") {
+            for (key, value) in metadata {
+                let value = value
+                    .as_str()
+                    .ok_or_else(|| format!("Invalid metadata value for '{}'", key))?;
+                actor.metadata.insert(key.clone(), value.to_string());
+            }
+        }
+
+        if let Ok(systems) = document.get_document("systems") {
+            for (system_name, entries) in systems {
+                let entries = entries
+                    .as_array()
+                    .ok_or_else(|| format!("Invalid contributions for system '{}'", system_name))?;
+                let mut contributions = Vec::with_capacity(entries.len());
+                for entry in entries {
+                    let entry = entry
+                        .as_document()
+                        .ok_or_else(|| format!("Invalid contribution in system '{}'", system_name))?;
+                    contributions.push(SystemContribution {
+                        system_name: system_name.clone(),
+                        stat_name: entry
+                            .get_str("stat_name")
+                            .map_err(|e| format!("Invalid contribution stat_name: {}", e))?
+                            .to_string(),
+                        value: entry
+                            .get_f64("value")
+                            .map_err(|e| format!("Invalid contribution value: {}", e))?,
+                        priority: entry
+                            .get_i64("priority")
+                            .map_err(|e| format!("Invalid contribution priority: {}", e))? as u32,
+                        timestamp: get_date(entry, "timestamp")?,
+                    });
+                }
+                actor.system_contributions.insert(system_name.clone(), contributions);
+            }
+        }
+
+        if let Ok(global_stats) = document.get_document("global_stats") {
+            for (stat_name, value) in global_stats {
+                let value = value
+                    .as_f64()
+                    .ok_or_else(|| format!("Invalid global stat value for '{}'", stat_name))?;
+                actor.global_stats_cache.insert(stat_name.clone(), value);
+            }
+        }
+
+        if let Ok(elemental) = document.get_document("elemental") {
+            let data = actor.elemental_system.get_data_mut();
+
+            for (name, array) in ELEMENTAL_ARRAY_NAMES.iter().zip(elemental_arrays_mut(data)) {
+                if let Ok(values) = elemental.get_array(*name) {
+                    copy_f64_values(values, &mut array[..])?;
+                }
+            }
+            if let Ok(rows) = elemental.get_array("element_interaction_bonuses") {
+                for (row, values) in data.element_interaction_bonuses.iter_mut().zip(rows) {
+                    let values = values
+                        .as_array()
+                        .ok_or_else(|| "Invalid element_interaction_bonuses row".to_string())?;
+                    copy_f64_values(values, &mut row[..])?;
+                }
+            }
+            restore_mastery_level_enums(data);
+        }
+
+        Ok(actor)
+    }
+}
+
+/// Convert many actors to BSON documents
+pub fn actors_to_documents(actors: &[HierarchicalActor]) -> Vec<Document> {
+    actors.iter().map(HierarchicalActor::to_document).collect()
+}
+
+/// Load many actors from BSON documents
+pub fn actors_from_documents(documents: &[Document]) -> PersistenceResult<Vec<HierarchicalActor>> {
+    documents.iter().map(HierarchicalActor::from_document).collect()
+}
+
+/// Convert a chrono timestamp to a BSON date
+fn to_bson_date(timestamp: DateTime<Utc>) -> BsonDateTime {
+    BsonDateTime::from_millis(timestamp.timestamp_millis())
+}
+
+/// Read a BSON date field as a chrono timestamp
+fn get_date(document: &Document, key: &str) -> PersistenceResult<DateTime<Utc>> {
+    let millis = document
+        .get_datetime(key)
+        .map_err(|e| format!("Invalid actor document {}: {}", key, e))?
+        .timestamp_millis();
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| format!("Invalid actor document {}: {}", key, millis))
+}
+
+/// Copy BSON doubles into a fixed-size array, ignoring extra values
+fn copy_f64_values(values: &[Bson], target: &mut [f64]) -> PersistenceResult<()> {
+    for (slot, value) in target.iter_mut().zip(values.iter().take(MAX_ELEMENTS)) {
+        *slot = value
+            .as_f64()
+            .ok_or_else(|| "Invalid elemental value, expected double".to_string())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! # Persistence Module
+//!
+//! Versioned persistence for hierarchical actors.
+//!
+//! Two formats are supported:
+//! - A compact binary codec for snapshots, bulk shutdown/startup dumps, and network transfer
+//! - MongoDB BSON documents (behind the `mongodb-storage` feature)
+//!
+//! Both formats carry a schema version and store each system as an optional block,
+//! so actors saved before a system existed still load (the block is simply absent),
+//! and blocks written by newer builds are skipped by older ones.
+//!
+//! Only primary elemental stats are persisted; derived stats are recalculated by element-core.
+
+pub mod binary_codec;
+pub mod bulk;
+#[cfg(feature = "mongodb-storage")]
+pub mod document;
+
+pub use binary_codec::*;
+pub use bulk::*;
+#[cfg(feature = "mongodb-storage")]
+pub use document::*;
+
+use element_core::{ElementMasteryLevel, ElementalSystemData, MAX_ELEMENTS};
+
+/// Result type for persistence operations
+pub type PersistenceResult<T> = Result<T, String>;
+
+/// Current actor schema version
+pub const ACTOR_SCHEMA_VERSION: u16 = 1;
+
+/// Names of the persisted elemental arrays, in storage order
+pub const ELEMENTAL_ARRAY_NAMES: [&str; 5] = [
+    "element_mastery_levels",
+    "element_mastery_experience",
+    "element_qi_amounts",
+    "element_qi_capacities",
+    "element_qi_regeneration_rates",
+];
+
+/// Get the persisted elemental arrays, in storage order
+pub(crate) fn elemental_arrays(data: &ElementalSystemData) -> [&[f64; MAX_ELEMENTS]; 5] {
+    [
+        &data.element_mastery_levels,
+        &data.element_mastery_experience,
+        &data.element_qi_amounts,
+        &data.element_qi_capacities,
+        &data.element_qi_regeneration_rates,
+    ]
+}
+
+/// Get the persisted elemental arrays mutably, in storage order
+pub(crate) fn elemental_arrays_mut(data: &mut ElementalSystemData) -> [&mut [f64; MAX_ELEMENTS]; 5] {
+    [
+        &mut data.element_mastery_levels,
+        &mut data.element_mastery_experience,
+        &mut data.element_qi_amounts,
+        &mut data.element_qi_capacities,
+        &mut data.element_qi_regeneration_rates,
+    ]
+}
+
+/// Restore mastery level enums from persisted experience
+pub(crate) fn restore_mastery_level_enums(data: &mut ElementalSystemData) {
+    for index in 0..MAX_ELEMENTS {
+        data.element_mastery_level_enums[index] =
+            ElementMasteryLevel::from_experience(data.element_mastery_experience[index] as i64);
+    }
+}
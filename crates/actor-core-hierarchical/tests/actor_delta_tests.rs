@@ -0,0 +1,111 @@
+//! # Actor Delta Tests
+//! 
+//! Integration tests for dirty tracking, delta collection, and delta re-aggregation.
+
+use actor_core_hierarchical::{GlobalAggregator, HierarchicalActor, SystemContribution};
+use chrono::Utc;
+
+fn contribution(system_name: &str, stat_name: &str, value: f64) -> SystemContribution {
+    SystemContribution {
+        system_name: system_name.to_string(),
+        stat_name: stat_name.to_string(),
+        value,
+        priority: 1,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_new_actor_is_clean() {
+    let actor = HierarchicalActor::new();
+    assert!(!actor.is_dirty());
+}
+
+#[test]
+fn test_collect_changes_only_includes_dirty_blocks() {
+    let mut actor = HierarchicalActor::new();
+    actor.add_system_contribution(contribution("elemental", "health", 100.0));
+    actor.add_system_contribution(contribution("race", "mana", 20.0));
+    
+    let delta = actor.collect_changes();
+    assert_eq!(delta.actor_id, actor.get_id());
+    assert_eq!(delta.changed_systems(), vec!["elemental", "race"]);
+    assert!(delta.name.is_none());
+    assert!(delta.elemental_data.is_none());
+    assert!(!actor.is_dirty());
+    
+    // Nothing changed since the last collection
+    assert!(actor.collect_changes().is_empty());
+    
+    actor.set_name("Renamed".to_string());
+    actor.get_elemental_data_mut().element_mastery_levels[0] = 5.0;
+    
+    let delta = actor.collect_changes();
+    assert_eq!(delta.name.as_deref(), Some("Renamed"));
+    assert_eq!(delta.elemental_data.as_ref().unwrap().element_mastery_levels[0], 5.0);
+    assert!(delta.system_contributions.is_empty());
+}
+
+#[test]
+fn test_apply_delta_to_replica() {
+    let mut source = HierarchicalActor::with_id_and_name("actor-1".to_string(), "Source".to_string());
+    let mut replica = HierarchicalActor::with_id_and_name("actor-1".to_string(), "Source".to_string());
+    
+    source.add_system_contribution(contribution("elemental", "health", 100.0));
+    source.set_metadata("class".to_string(), "mage".to_string());
+    source.get_elemental_data_mut().element_qi_amounts[2] = 42.0;
+    
+    let delta = source.collect_changes();
+    replica.apply_delta(&delta).unwrap();
+    
+    assert_eq!(replica.get_system_contributions("elemental").unwrap().len(), 1);
+    assert_eq!(replica.get_metadata("class").unwrap(), "mage");
+    assert_eq!(replica.get_elemental_data().element_qi_amounts[2], 42.0);
+    assert_eq!(replica.get_updated_at(), source.get_updated_at());
+    assert!(!replica.is_dirty());
+    
+    // Removing a system propagates as a removal
+    source.remove_system_contributions("elemental");
+    let delta = source.collect_changes();
+    assert_eq!(delta.removed_systems, vec!["elemental".to_string()]);
+    
+    replica.apply_delta(&delta).unwrap();
+    assert!(replica.get_system_contributions("elemental").is_none());
+}
+
+#[test]
+fn test_apply_delta_rejects_other_actor() {
+    let mut source = HierarchicalActor::new();
+    let mut other = HierarchicalActor::new();
+    
+    source.set_name("Source".to_string());
+    let delta = source.collect_changes();
+    
+    assert!(other.apply_delta(&delta).is_err());
+}
+
+#[test]
+fn test_delta_aggregation_matches_full_aggregation() {
+    let mut actor = HierarchicalActor::new();
+    actor.add_system_contribution(contribution("elemental", "health", 100.0));
+    actor.add_system_contribution(contribution("race", "health", 50.0));
+    actor.add_system_contribution(contribution("race", "speed", 3.0));
+    
+    let mut incremental = GlobalAggregator::new();
+    let delta = actor.collect_changes();
+    let stats = incremental.aggregate_actor_delta(&actor, &delta);
+    assert_eq!(stats.get("health").unwrap(), &150.0);
+    
+    // Change one system and drop a stat
+    actor.remove_system_contributions("race");
+    actor.add_system_contribution(contribution("race", "health", 10.0));
+    actor.add_system_contribution(contribution("cultivation", "mana", 30.0));
+    
+    let delta = actor.collect_changes();
+    let stats = incremental.aggregate_actor_delta(&actor, &delta);
+    
+    let mut full = GlobalAggregator::new();
+    assert_eq!(stats, full.aggregate_actor_stats(&actor));
+    assert_eq!(stats.get("health").unwrap(), &110.0);
+    assert!(!stats.contains_key("speed"));
+}
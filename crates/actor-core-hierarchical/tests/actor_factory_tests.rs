@@ -175,3 +175,57 @@ fn test_create_actor_with_elemental_params() {
     assert!(result.is_err());
     assert!(result.unwrap_err().contains("not found in registry"));
 }
+
+#[test]
+fn test_create_batch() {
+    let factory = ActorFactory::new_empty();
+    let actors = factory.create_batch("mage", 100, None).unwrap();
+    
+    assert_eq!(actors.len(), 100);
+    for actor in &actors {
+        assert_eq!(actor.get_name(), "mage Actor");
+        assert_eq!(actor.get_metadata("class").unwrap(), "mage");
+        assert_eq!(actor.get_metadata("elemental_system_initialized").unwrap(), "true");
+    }
+    
+    // Every actor gets its own ID
+    let ids: std::collections::HashSet<&str> = actors.iter().map(|actor| actor.get_id()).collect();
+    assert_eq!(ids.len(), 100);
+}
+
+#[test]
+fn test_create_batch_with_overrides() {
+    let factory = ActorFactory::new_empty();
+    let mut metadata = HashMap::new();
+    metadata.insert("zone".to_string(), "forest".to_string());
+    
+    let overrides = actor_core_hierarchical::ActorParamOverrides {
+        name_prefix: Some("Goblin".to_string()),
+        metadata,
+        elemental_params: None,
+    };
+    let actors = factory.create_batch("goblin", 3, Some(overrides)).unwrap();
+    
+    assert_eq!(actors[0].get_name(), "Goblin 0");
+    assert_eq!(actors[2].get_name(), "Goblin 2");
+    assert_eq!(actors[1].get_metadata("zone").unwrap(), "forest");
+    assert_eq!(actors[1].get_metadata("class").unwrap(), "goblin");
+}
+
+#[test]
+fn test_create_batch_propagates_template_errors() {
+    let factory = ActorFactory::new_empty();
+    let overrides = actor_core_hierarchical::ActorParamOverrides {
+        elemental_params: Some(ElementalParams {
+            primary_element: "missing".to_string(),
+            initial_mastery_levels: HashMap::new(),
+            initial_experience: HashMap::new(),
+            initial_qi_amounts: HashMap::new(),
+            elemental_preferences: vec![],
+        }),
+        ..Default::default()
+    };
+    
+    assert!(factory.create_batch("mage", 10, Some(overrides)).is_err());
+    assert!(factory.create_batch("mage", 0, None).unwrap().is_empty());
+}
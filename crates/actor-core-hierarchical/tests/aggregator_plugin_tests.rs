@@ -0,0 +1,180 @@
+//! # Aggregator Plugin Tests
+//! 
+//! Integration tests for custom aggregator plugins.
+
+use actor_core_hierarchical::{
+    AggregatorPlugin, AggregatorPluginRegistry, GlobalAggregator, HierarchicalActor, SystemContribution,
+};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Plugin that takes the highest contribution per stat
+struct HighestPlugin {
+    name: &'static str,
+    system_name: &'static str,
+    priority: u32,
+}
+
+impl AggregatorPlugin for HighestPlugin {
+    fn name(&self) -> &str {
+        self.name
+    }
+    
+    fn system_name(&self) -> &str {
+        self.system_name
+    }
+    
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+    
+    fn aggregate_partial(&self, contributions: &[SystemContribution]) -> HashMap<String, f64> {
+        let mut partial: HashMap<String, f64> = HashMap::new();
+        for contribution in contributions {
+            let entry = partial.entry(contribution.stat_name.clone()).or_insert(f64::NEG_INFINITY);
+            *entry = entry.max(contribution.value);
+        }
+        partial
+    }
+}
+
+/// Plugin that doubles health in the final combine pass
+struct DoubleHealthPlugin;
+
+impl AggregatorPlugin for DoubleHealthPlugin {
+    fn name(&self) -> &str {
+        "double_health"
+    }
+    
+    fn system_name(&self) -> &str {
+        "blessing"
+    }
+    
+    fn aggregate_partial(&self, _contributions: &[SystemContribution]) -> HashMap<String, f64> {
+        HashMap::new()
+    }
+    
+    fn combine(&self, aggregated: &mut HashMap<String, f64>) {
+        if let Some(health) = aggregated.get_mut("health") {
+            *health *= 2.0;
+        }
+    }
+}
+
+fn contribution(system_name: &str, stat_name: &str, value: f64) -> SystemContribution {
+    SystemContribution {
+        system_name: system_name.to_string(),
+        stat_name: stat_name.to_string(),
+        value,
+        priority: 1,
+        timestamp: Utc::now(),
+    }
+}
+
+#[test]
+fn test_plugin_partial_aggregation() {
+    let mut aggregator = GlobalAggregator::new();
+    let mut actor = HierarchicalActor::new();
+    
+    actor.add_system_contribution(contribution("elemental", "health", 100.0));
+    actor.add_system_contribution(contribution("elemental", "health", 40.0));
+    actor.add_system_contribution(contribution("cultivation", "health", 50.0));
+    
+    aggregator.register_plugin(Arc::new(HighestPlugin {
+        name: "elemental_highest",
+        system_name: "elemental",
+        priority: 10,
+    })).unwrap();
+    
+    let stats = aggregator.aggregate_actor_stats(&actor);
+    
+    // Elemental partial is max(100, 40) = 100, then summed with cultivation: 100 + 50 = 150
+    assert_eq!(stats.get("health").unwrap(), &150.0);
+}
+
+#[test]
+fn test_plugin_final_combine() {
+    let mut aggregator = GlobalAggregator::new();
+    let mut actor = HierarchicalActor::new();
+    
+    actor.add_system_contribution(contribution("elemental", "health", 100.0));
+    actor.add_system_contribution(contribution("blessing", "mana", 10.0));
+    
+    aggregator.register_plugin(Arc::new(DoubleHealthPlugin)).unwrap();
+    
+    let stats = aggregator.aggregate_actor_stats(&actor);
+    assert_eq!(stats.get("health").unwrap(), &200.0);
+    assert!(!stats.contains_key("mana"));
+}
+
+#[test]
+fn test_plugin_matches_monolithic_for_sum() {
+    let mut actor = HierarchicalActor::new();
+    actor.add_system_contribution(contribution("elemental", "attack", 10.0));
+    actor.add_system_contribution(contribution("elemental", "attack", 15.0));
+    actor.add_system_contribution(contribution("race", "attack", 5.0));
+    
+    struct SumPlugin;
+    impl AggregatorPlugin for SumPlugin {
+        fn name(&self) -> &str {
+            "elemental_sum"
+        }
+        fn system_name(&self) -> &str {
+            "elemental"
+        }
+        fn aggregate_partial(&self, contributions: &[SystemContribution]) -> HashMap<String, f64> {
+            let mut partial = HashMap::new();
+            for contribution in contributions {
+                *partial.entry(contribution.stat_name.clone()).or_insert(0.0) += contribution.value;
+            }
+            partial
+        }
+    }
+    
+    let mut monolithic = GlobalAggregator::new();
+    let mut plugged = GlobalAggregator::new();
+    plugged.register_plugin(Arc::new(SumPlugin)).unwrap();
+    
+    assert_eq!(
+        monolithic.aggregate_actor_stats(&actor),
+        plugged.aggregate_actor_stats(&actor)
+    );
+}
+
+#[test]
+fn test_registry_ordering_and_duplicates() {
+    let mut registry = AggregatorPluginRegistry::new();
+    
+    registry.register(Arc::new(HighestPlugin { name: "b", system_name: "race", priority: 5 })).unwrap();
+    registry.register(Arc::new(HighestPlugin { name: "a", system_name: "item", priority: 5 })).unwrap();
+    registry.register(Arc::new(HighestPlugin { name: "c", system_name: "magic", priority: 1 })).unwrap();
+    
+    // Priority first, then name
+    assert_eq!(registry.get_plugin_names(), vec!["c", "a", "b"]);
+    
+    // Duplicate name
+    assert!(registry.register(Arc::new(HighestPlugin { name: "a", system_name: "luck", priority: 1 })).is_err());
+    
+    // Duplicate system
+    assert!(registry.register(Arc::new(HighestPlugin { name: "d", system_name: "race", priority: 1 })).is_err());
+    
+    assert!(registry.unregister("a"));
+    assert!(!registry.unregister("a"));
+    assert_eq!(registry.len(), 2);
+}
+
+#[test]
+fn test_register_plugin_clears_cache() {
+    let mut aggregator = GlobalAggregator::new();
+    let actor = HierarchicalActor::new();
+    
+    let _stats = aggregator.aggregate_actor_stats(&actor);
+    assert_eq!(aggregator.aggregation_cache.len(), 1);
+    
+    aggregator.register_plugin(Arc::new(DoubleHealthPlugin)).unwrap();
+    assert_eq!(aggregator.aggregation_cache.len(), 0);
+    
+    assert!(aggregator.unregister_plugin("double_health"));
+    assert!(aggregator.plugin_registry.is_empty());
+}
@@ -0,0 +1,89 @@
+//! # Column Store Tests
+//! 
+//! Integration tests for structure-of-arrays actor storage.
+
+use actor_core_hierarchical::{ActorColumnStore, HierarchicalActor};
+use std::collections::HashMap;
+
+fn actor_with_stats(stats: &[(&str, f64)]) -> HierarchicalActor {
+    let mut actor = HierarchicalActor::new();
+    actor.update_global_stats_cache(
+        stats.iter().map(|(name, value)| (name.to_string(), *value)).collect::<HashMap<_, _>>(),
+    );
+    actor
+}
+
+#[test]
+fn test_insert_and_read_columns() {
+    let mut store = ActorColumnStore::with_stats(&["health"]);
+    let a = actor_with_stats(&[("health", 100.0), ("mana", 50.0)]);
+    let b = actor_with_stats(&[("health", 80.0)]);
+    
+    let a_index = store.insert_actor(&a);
+    let b_index = store.insert_actor(&b);
+    
+    assert_eq!(store.len(), 2);
+    assert_eq!(store.column("health").unwrap(), &[100.0, 80.0]);
+    assert_eq!(store.column("mana").unwrap(), &[50.0, 0.0]);
+    assert_eq!(store.get(a_index, "mana"), Some(50.0));
+    assert_eq!(store.get(b_index, "health"), Some(80.0));
+    assert_eq!(store.get_index(b.get_id()), Some(b_index));
+    
+    // Re-inserting refreshes the existing row
+    let mut a_updated = a.clone();
+    a_updated.global_stats_cache.insert("health".to_string(), 120.0);
+    assert_eq!(store.insert_actor(&a_updated), a_index);
+    assert_eq!(store.get(a_index, "health"), Some(120.0));
+    assert_eq!(store.len(), 2);
+}
+
+#[test]
+fn test_remove_keeps_handles_valid() {
+    let mut store = ActorColumnStore::new();
+    let actors: Vec<HierarchicalActor> = (0..4)
+        .map(|i| actor_with_stats(&[("health", i as f64)]))
+        .collect();
+    let handles: Vec<_> = actors.iter().map(|actor| store.insert_actor(actor)).collect();
+    
+    assert!(store.remove_actor(handles[1]));
+    assert!(!store.remove_actor(handles[1]));
+    assert!(!store.contains(handles[1]));
+    assert_eq!(store.get(handles[1], "health"), None);
+    
+    // Rows stay dense and the moved actor keeps its handle
+    assert_eq!(store.len(), 3);
+    assert_eq!(store.column("health").unwrap().len(), 3);
+    assert_eq!(store.get(handles[3], "health"), Some(3.0));
+    assert_eq!(store.get(handles[0], "health"), Some(0.0));
+    
+    // Freed slots are reused with a new generation
+    let replacement = store.insert_actor(&actor_with_stats(&[("health", 9.0)]));
+    assert_ne!(replacement, handles[1]);
+    assert_eq!(store.get(replacement, "health"), Some(9.0));
+    assert_eq!(store.get(handles[1], "health"), None);
+}
+
+#[test]
+fn test_sync_with_actor() {
+    let mut store = ActorColumnStore::new();
+    let mut actor = actor_with_stats(&[("health", 100.0)]);
+    let other = actor_with_stats(&[("health", 1.0)]);
+    let handle = store.insert_actor(&actor);
+    
+    // Zone tick regenerates health in the column
+    for health in store.column_mut("health").unwrap() {
+        *health += 5.0;
+    }
+    
+    store.sync_to_actor(handle, &mut actor).unwrap();
+    assert_eq!(actor.get_global_stats_cache().get("health"), Some(&105.0));
+    
+    actor.global_stats_cache.insert("health".to_string(), 90.0);
+    store.sync_from_actor(handle, &actor).unwrap();
+    assert_eq!(store.get(handle, "health"), Some(90.0));
+    
+    // Handles are bound to their actor
+    assert!(store.sync_from_actor(handle, &other).is_err());
+    assert!(store.set(handle, "health", 1.0));
+    assert!(!store.set(handle, "unknown", 1.0));
+}
@@ -0,0 +1,146 @@
+//! # Hot Data Tests
+//! 
+//! Integration and concurrency tests for the lock-free hot data read path.
+
+use actor_core_hierarchical::{HierarchicalActor, HotActorData, HotDataSnapshot};
+use element_core::ElementalSystemData;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+/// Build a snapshot where every hot value equals `value`
+fn uniform_snapshot(value: f64) -> HotDataSnapshot {
+    let mut elemental_data = ElementalSystemData::new();
+    elemental_data.element_mastery_levels = [value; 50];
+    elemental_data.element_qi_amounts = [value; 50];
+    
+    let mut global_stats = HashMap::new();
+    global_stats.insert("health".to_string(), value);
+    
+    HotDataSnapshot::new(global_stats, elemental_data)
+}
+
+#[test]
+fn test_actor_publish_hot_data() {
+    let mut actor = HierarchicalActor::new();
+    let handle = actor.get_hot_data_handle();
+    assert_eq!(handle.version(), 0);
+    
+    actor.get_elemental_data_mut().element_mastery_levels[3] = 12.0;
+    let mut stats = HashMap::new();
+    stats.insert("health".to_string(), 250.0);
+    actor.update_global_stats_cache(stats);
+    
+    // Readers do not see unpublished writes
+    assert_eq!(handle.load().elemental_data.element_mastery_levels[3], 0.0);
+    
+    let version = actor.publish_hot_data();
+    assert_eq!(version, 1);
+    
+    let snapshot = handle.load();
+    assert_eq!(snapshot.version, 1);
+    assert_eq!(snapshot.elemental_data.element_mastery_levels[3], 12.0);
+    assert_eq!(snapshot.get_global_stat("health"), Some(250.0));
+    assert!(handle.is_current(&snapshot));
+    
+    actor.publish_hot_data();
+    assert!(!handle.is_current(&snapshot));
+}
+
+#[test]
+fn test_cloned_actor_has_independent_hot_data() {
+    let actor = HierarchicalActor::new();
+    actor.publish_hot_data();
+    
+    let clone = actor.clone();
+    clone.publish_hot_data();
+    
+    assert_eq!(actor.get_hot_data_handle().version(), 1);
+    assert_eq!(clone.get_hot_data_handle().version(), 2);
+}
+
+#[test]
+fn test_concurrent_readers_never_see_torn_snapshots() {
+    const WRITES: u64 = 2_000;
+    
+    let hot_data = Arc::new(HotActorData::new(uniform_snapshot(0.0)));
+    let done = Arc::new(AtomicBool::new(false));
+    
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let hot_data = Arc::clone(&hot_data);
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                let mut last_version = 0;
+                while !done.load(Ordering::Acquire) {
+                    hot_data.read(|snapshot| {
+                        let expected = snapshot.version as f64;
+                        assert!(snapshot.elemental_data.element_mastery_levels.iter().all(|&v| v == expected));
+                        assert!(snapshot.elemental_data.element_qi_amounts.iter().all(|&v| v == expected));
+                        assert_eq!(snapshot.get_global_stat("health"), Some(expected));
+                        assert!(snapshot.version >= last_version);
+                        last_version = snapshot.version;
+                    });
+                }
+            })
+        })
+        .collect();
+    
+    for i in 1..=WRITES {
+        let version = hot_data.publish(uniform_snapshot(i as f64));
+        assert_eq!(version, i);
+    }
+    done.store(true, Ordering::Release);
+    
+    for reader in readers {
+        reader.join().unwrap();
+    }
+    assert_eq!(hot_data.version(), WRITES);
+}
+
+#[test]
+fn test_concurrent_updates_are_not_lost() {
+    let hot_data = Arc::new(HotActorData::default());
+    
+    let writers: Vec<_> = (0..4)
+        .map(|_| {
+            let hot_data = Arc::clone(&hot_data);
+            thread::spawn(move || {
+                for _ in 0..250 {
+                    hot_data.update(|snapshot| {
+                        *snapshot.global_stats.entry("counter".to_string()).or_insert(0.0) += 1.0;
+                    });
+                }
+            })
+        })
+        .collect();
+    
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    
+    let snapshot = hot_data.load();
+    assert_eq!(snapshot.get_global_stat("counter"), Some(1000.0));
+    assert_eq!(snapshot.version, 1000);
+}
+
+#[test]
+fn test_read_consistent_detects_concurrent_writes() {
+    let hot_data = HotActorData::new(uniform_snapshot(1.0));
+    let mut attempts = 0;
+    
+    let (sum, version) = hot_data.read_consistent(|hot_data| {
+        attempts += 1;
+        let first = hot_data.load().get_global_stat("health").unwrap();
+        if attempts == 1 {
+            // Simulate a writer publishing between two loads
+            hot_data.publish(uniform_snapshot(2.0));
+        }
+        first + hot_data.load().get_global_stat("health").unwrap()
+    });
+    
+    assert_eq!(attempts, 2);
+    assert_eq!(sum, 4.0);
+    assert_eq!(version, 1);
+}
@@ -0,0 +1,184 @@
+//! # Persistence Tests
+//! 
+//! Integration tests for the binary codec, bulk save/load, and BSON documents.
+
+use actor_core_hierarchical::{
+    load_actors, load_actors_from_file, save_actors, save_actors_to_file, HierarchicalActor, SystemContribution,
+    ACTOR_SCHEMA_VERSION, BLOCK_ELEMENTAL,
+};
+use chrono::Utc;
+use std::collections::HashMap;
+
+fn create_test_actor(name: &str) -> HierarchicalActor {
+    let mut actor = HierarchicalActor::with_id_and_name(uuid::Uuid::new_v4().to_string(), name.to_string());
+    actor.set_metadata("class".to_string(), "mage".to_string());
+    actor.add_system_contribution(SystemContribution {
+        system_name: "elemental".to_string(),
+        stat_name: "mana".to_string(),
+        value: 150.0,
+        priority: 2,
+        timestamp: Utc::now(),
+    });
+    
+    let mut stats = HashMap::new();
+    stats.insert("mana".to_string(), 150.0);
+    actor.update_global_stats_cache(stats);
+    
+    let data = actor.get_elemental_data_mut();
+    data.element_mastery_levels[1] = 7.5;
+    data.element_mastery_experience[1] = 2_000.0;
+    data.element_qi_amounts[4] = 33.0;
+    data.element_interaction_bonuses[1][2] = 0.25;
+    actor
+}
+
+fn assert_same_actor(loaded: &HierarchicalActor, original: &HierarchicalActor) {
+    assert_eq!(loaded.id, original.id);
+    assert_eq!(loaded.name, original.name);
+    assert_eq!(loaded.metadata, original.metadata);
+    assert_eq!(loaded.global_stats_cache, original.global_stats_cache);
+    
+    let contributions = loaded.get_system_contributions("elemental").unwrap();
+    assert_eq!(contributions.len(), 1);
+    assert_eq!(contributions[0].stat_name, "mana");
+    assert_eq!(contributions[0].value, 150.0);
+    assert_eq!(contributions[0].priority, 2);
+    
+    let data = loaded.get_elemental_data();
+    assert_eq!(data.element_mastery_levels[1], 7.5);
+    assert_eq!(data.element_qi_amounts[4], 33.0);
+    assert_eq!(data.element_interaction_bonuses[1][2], 0.25);
+    assert_eq!(
+        data.element_mastery_level_enums[1],
+        element_core::ElementMasteryLevel::from_experience(2_000)
+    );
+}
+
+#[test]
+fn test_binary_roundtrip() {
+    let actor = create_test_actor("Binary Mage");
+    let bytes = actor.to_binary();
+    
+    assert_eq!(&bytes[0..4], b"HACT");
+    assert_eq!(u16::from_le_bytes([bytes[4], bytes[5]]), ACTOR_SCHEMA_VERSION);
+    
+    let loaded = HierarchicalActor::from_binary(&bytes).unwrap();
+    assert_same_actor(&loaded, &actor);
+    assert_eq!(loaded.created_at.timestamp_micros(), actor.created_at.timestamp_micros());
+    assert_eq!(loaded.updated_at.timestamp_micros(), actor.updated_at.timestamp_micros());
+    assert!(!loaded.is_dirty());
+}
+
+#[test]
+fn test_binary_encoding_is_deterministic() {
+    let actor = create_test_actor("Deterministic");
+    assert_eq!(actor.to_binary(), actor.to_binary());
+}
+
+#[test]
+fn test_binary_missing_blocks_use_defaults() {
+    let actor = HierarchicalActor::with_id_and_name("bare".to_string(), "Bare".to_string());
+    let loaded = HierarchicalActor::from_binary(&actor.to_binary()).unwrap();
+    
+    assert!(loaded.metadata.is_empty());
+    assert!(loaded.system_contributions.is_empty());
+    assert!(loaded.global_stats_cache.is_empty());
+    assert_eq!(loaded.get_elemental_data().element_qi_capacities[0], 100.0);
+}
+
+#[test]
+fn test_binary_skips_unknown_blocks() {
+    let actor = create_test_actor("Forward Compatible");
+    let mut bytes = actor.to_binary();
+    
+    // Append a block from a hypothetical newer system and bump the block count
+    let block_count = u16::from_le_bytes([bytes[6], bytes[7]]) + 1;
+    bytes[6..8].copy_from_slice(&block_count.to_le_bytes());
+    bytes.extend_from_slice(&999u16.to_le_bytes());
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&[1, 2, 3]);
+    
+    let loaded = HierarchicalActor::from_binary(&bytes).unwrap();
+    assert_same_actor(&loaded, &actor);
+    assert_ne!(BLOCK_ELEMENTAL, 999);
+}
+
+#[test]
+fn test_binary_rejects_newer_schema_and_truncation() {
+    let actor = create_test_actor("Rejected");
+    let bytes = actor.to_binary();
+    
+    let mut newer = bytes.clone();
+    newer[4..6].copy_from_slice(&(ACTOR_SCHEMA_VERSION + 1).to_le_bytes());
+    assert!(HierarchicalActor::from_binary(&newer).unwrap_err().contains("newer"));
+    
+    assert!(HierarchicalActor::from_binary(&bytes[..bytes.len() - 1]).is_err());
+    assert!(HierarchicalActor::from_binary(b"NOPE").is_err());
+}
+
+#[test]
+fn test_bulk_save_and_load() {
+    let actors: Vec<HierarchicalActor> = (0..10)
+        .map(|i| create_test_actor(&format!("Actor {}", i)))
+        .collect();
+    
+    let loaded = load_actors(&save_actors(&actors)).unwrap();
+    assert_eq!(loaded.len(), actors.len());
+    for (loaded, original) in loaded.iter().zip(actors.iter()) {
+        assert_same_actor(loaded, original);
+    }
+    
+    assert!(load_actors(&[]).is_err());
+}
+
+#[test]
+fn test_bulk_file_roundtrip() {
+    let actors = vec![create_test_actor("File A"), create_test_actor("File B")];
+    let path = std::env::temp_dir().join(format!("actors-{}.bin", uuid::Uuid::new_v4()));
+    
+    save_actors_to_file(&path, &actors).unwrap();
+    let loaded = load_actors_from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    
+    assert_eq!(loaded.len(), 2);
+    assert_same_actor(&loaded[1], &actors[1]);
+}
+
+#[cfg(feature = "mongodb-storage")]
+mod document_tests {
+    use super::*;
+    use actor_core_hierarchical::{actors_from_documents, actors_to_documents};
+    
+    #[test]
+    fn test_document_roundtrip() {
+        let actor = create_test_actor("Document Mage");
+        let document = actor.to_document();
+        
+        assert_eq!(document.get_str("_id").unwrap(), actor.id);
+        assert_eq!(document.get_i32("schema_version").unwrap(), ACTOR_SCHEMA_VERSION as i32);
+        
+        let loaded = HierarchicalActor::from_document(&document).unwrap();
+        assert_same_actor(&loaded, &actor);
+        assert_eq!(loaded.updated_at.timestamp_millis(), actor.updated_at.timestamp_millis());
+    }
+    
+    #[test]
+    fn test_document_missing_blocks_use_defaults() {
+        let actor = create_test_actor("Old Document");
+        let mut document = actor.to_document();
+        document.remove("systems");
+        document.remove("elemental");
+        
+        let loaded = HierarchicalActor::from_document(&document).unwrap();
+        assert!(loaded.system_contributions.is_empty());
+        assert_eq!(loaded.get_elemental_data().element_mastery_levels[1], 0.0);
+    }
+    
+    #[test]
+    fn test_bulk_documents() {
+        let actors = vec![create_test_actor("Bulk A"), create_test_actor("Bulk B")];
+        let loaded = actors_from_documents(&actors_to_documents(&actors)).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_same_actor(&loaded[0], &actors[0]);
+    }
+}
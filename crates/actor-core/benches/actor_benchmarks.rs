@@ -35,7 +35,7 @@ impl actor_core::interfaces::Subsystem for BenchmarkSubsystem {
         100
     }
     
-    async fn contribute(&self, _actor: &Actor) -> ActorCoreResult<SubsystemOutput> {
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
         // Simulate processing time
         tokio::time::sleep(self.processing_time).await;
         
@@ -59,7 +59,7 @@ impl Subsystem for MockSubsystem {
         100
     }
     
-    async fn contribute(&self, _actor: &Actor) -> ActorCoreResult<SubsystemOutput> {
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
         Ok(SubsystemOutput {
             primary: vec![],
             derived: vec![],
@@ -0,0 +1,110 @@
+//! Typed context of a stat resolution.
+//!
+//! Callers describe the situation an actor is resolved in — the game time, the zone, whether
+//! it is fighting and which world events are running — and every subsystem receives the same
+//! [`AggregationContext`] in [`Subsystem::contribute`](crate::interfaces::Subsystem::contribute).
+//! Stats resolved under a context are cached under [`AggregationContext::cache_key`], apart
+//! from the context-free snapshot of the actor.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Situation an actor's stats are resolved in
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AggregationContext {
+    /// Game time of the resolution
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_time: Option<DateTime<Utc>>,
+    /// Zone the actor is in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone_id: Option<String>,
+    /// Whether the actor is in combat
+    #[serde(default)]
+    pub in_combat: bool,
+    /// Modifiers of running world events, e.g. `double_exp: 2.0`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub event_modifiers: BTreeMap<String, f64>,
+}
+
+impl AggregationContext {
+    /// Create an empty context
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build a context from the JSON map accepted by the APIs
+    pub fn from_map(map: HashMap<String, serde_json::Value>) -> ActorCoreResult<Self> {
+        let context: Self = serde_json::from_value(serde_json::Value::Object(map.into_iter().collect()))
+            .map_err(|e| ActorCoreError::InvalidInput(format!("Invalid aggregation context: {}", e)))?;
+        if let Some((name, value)) = context.event_modifiers.iter().find(|(_, value)| !value.is_finite()) {
+            return Err(ActorCoreError::InvalidInput(format!(
+                "Invalid aggregation context: event modifier {} is {}",
+                name, value
+            )));
+        }
+        Ok(context)
+    }
+
+    /// Set the game time
+    pub fn with_game_time(mut self, game_time: DateTime<Utc>) -> Self {
+        self.game_time = Some(game_time);
+        self
+    }
+
+    /// Set the zone
+    pub fn with_zone(mut self, zone_id: impl Into<String>) -> Self {
+        self.zone_id = Some(zone_id.into());
+        self
+    }
+
+    /// Set whether the actor is in combat
+    pub fn with_combat(mut self, in_combat: bool) -> Self {
+        self.in_combat = in_combat;
+        self
+    }
+
+    /// Add a world event modifier
+    pub fn with_event_modifier(mut self, name: impl Into<String>, value: f64) -> Self {
+        self.event_modifiers.insert(name.into(), value);
+        self
+    }
+
+    /// Get a world event modifier
+    pub fn event_modifier(&self, name: &str) -> Option<f64> {
+        self.event_modifiers.get(name).copied()
+    }
+
+    /// Check whether the context describes nothing
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Cache key of the actor's snapshot under this context; the actor ID itself for an
+    /// empty context
+    pub fn cache_key(&self, actor_id: &str) -> String {
+        if self.is_empty() {
+            actor_id.to_string()
+        } else {
+            format!("{}@ctx:{:016x}", actor_id, self.fingerprint())
+        }
+    }
+
+    /// Hash of the context, stable across processes sharing a cache
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = fxhash::FxHasher64::default();
+        self.game_time.map(|time| time.timestamp_millis()).hash(&mut hasher);
+        self.zone_id.hash(&mut hasher);
+        self.in_combat.hash(&mut hasher);
+        for (name, value) in &self.event_modifiers {
+            name.hash(&mut hasher);
+            value.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
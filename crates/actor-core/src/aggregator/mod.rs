@@ -6,19 +6,25 @@
 pub mod optimized;
 
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 use uuid::Uuid;
 
+use crate::aggregation_context::AggregationContext;
+use crate::derived_stats::DerivedStats;
 use crate::interfaces::{
     Aggregator, PluginRegistry, Cache, CombinerRegistry
 };
+use crate::registry::dimensions::DimensionRegistry;
+use crate::snapshot_history::SnapshotHistory;
 use crate::metrics::AggregatorMetrics;
 // use crate::types::*; // Unused import
 use crate::types::Actor;
 use crate::types::Snapshot;
+use crate::types::StatPrediction;
+use crate::snapshot_schema::{decode_snapshot, SNAPSHOT_SCHEMA_VERSION};
 use crate::types::Contribution;
 use crate::types::CapContribution;
 use crate::types::Caps;
@@ -37,6 +43,26 @@ pub struct AggregatorImpl {
     cache: Arc<dyn Cache>,
     /// Metrics for performance monitoring
     metrics: Arc<RwLock<AggregatorMetrics>>,
+    /// Cache keys of snapshots resolved under a context, per actor, so invalidating an
+    /// actor drops them too
+    context_keys: Mutex<HashMap<String, HashSet<String>>>,
+    /// Formulas computing derived stats from the capped primary stats
+    derived_stats: Option<Arc<DerivedStats>>,
+    /// Declared dimensions, checked against contributions and clamping their stats
+    dimensions: Option<Arc<DimensionRegistry>>,
+    /// Dimensions changed since each actor's context-free snapshot was cached
+    dirty: Mutex<HashMap<String, HashSet<String>>>,
+    /// Dimensions each subsystem contributed to in each actor's context-free snapshot
+    contributed: Mutex<HashMap<String, HashMap<String, HashSet<String>>>>,
+    /// Recent context-free snapshots and their contributions
+    history: Option<Arc<SnapshotHistory>>,
+}
+
+/// What an actor's subsystems contribute
+struct Collected {
+    contributions: Vec<Contribution>,
+    caps_used: HashMap<String, Caps>,
+    subsystems_processed: Vec<String>,
 }
 
 impl AggregatorImpl {
@@ -53,16 +79,92 @@ impl AggregatorImpl {
             caps_provider,
             cache,
             metrics: Arc::new(RwLock::new(AggregatorMetrics::default())),
+            context_keys: Mutex::new(HashMap::new()),
+            derived_stats: None,
+            dimensions: None,
+            dirty: Mutex::new(HashMap::new()),
+            contributed: Mutex::new(HashMap::new()),
+            history: None,
+        }
+    }
+
+    /// Compute derived stats with `derived_stats` after the primary stats are capped.
+    pub fn with_derived_stats(mut self, derived_stats: Arc<DerivedStats>) -> Self {
+        self.derived_stats = Some(derived_stats);
+        self
+    }
+
+    /// Check contributions against the dimensions declared in `dimensions`, and clamp
+    /// stats to their declared ranges.
+    pub fn with_dimension_registry(mut self, dimensions: Arc<DimensionRegistry>) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Record context-free snapshots in `history`, so changes between versions can be
+    /// traced to the subsystems behind them.
+    pub fn with_snapshot_history(mut self, history: Arc<SnapshotHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Get a cached snapshot by cache key, dropping entries that can no longer be read.
+    fn cached_snapshot(&self, actor_id: &str, cache_key: &str) -> Option<Snapshot> {
+        let value = self.cache.get(cache_key)?;
+        match decode_snapshot(value) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                // Stale or unreadable entries are dropped and resolved again
+                warn!("Discarding cached snapshot for {}: {}", actor_id, e);
+                if let Err(e) = self.cache.delete(cache_key) {
+                    warn!("Failed to invalidate cache for {}: {}", actor_id, e);
+                }
+                None
+            }
+        }
+    }
+
+    fn context_keys(&self) -> std::sync::MutexGuard<'_, HashMap<String, HashSet<String>>> {
+        self.context_keys.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn dirty(&self) -> std::sync::MutexGuard<'_, HashMap<String, HashSet<String>>> {
+        self.dirty.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn contributed(&self) -> std::sync::MutexGuard<'_, HashMap<String, HashMap<String, HashSet<String>>>> {
+        self.contributed.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Whether a subsystem may contribute to one of the `dirty` dimensions of an actor: it
+    /// did in the cached snapshot, it owns one of them, or it contributed to nothing then
+    /// and may start anywhere.
+    fn may_contribute(
+        &self,
+        contributed: Option<&HashMap<String, HashSet<String>>>,
+        subsystem_id: &str,
+        dirty: &HashSet<String>,
+    ) -> bool {
+        let owns_dirty = self.dimensions.as_ref().is_some_and(|dimensions| {
+            dirty.iter().any(|dimension| dimensions.owner(dimension).as_deref() == Some(subsystem_id))
+        });
+        match contributed.and_then(|contributed| contributed.get(subsystem_id)) {
+            Some(dimensions) if !dimensions.is_empty() => owns_dirty || !dimensions.is_disjoint(dirty),
+            _ => true,
         }
     }
 
     /// Get subsystems for an actor (helper method).
-    fn get_subsystems_for_actor(&self, _actor: &Actor) -> Vec<Arc<dyn crate::interfaces::Subsystem>> {
-        // Get all subsystems from the registry
-        self.subsystem_registry.get_by_priority()
+    fn get_subsystems_for_actor(&self, actor: &Actor) -> Vec<Arc<dyn crate::interfaces::Subsystem>> {
+        // The registry filters by the actor's enabled subsystem set
+        self.subsystem_registry.get_for_actor(actor)
     }
 
     /// Process contributions using bucket processor.
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "actor_core.process_contributions", skip_all, fields(contributions = contributions.len()))
+    )]
     async fn process_contributions(
         &self,
         contributions: Vec<Contribution>,
@@ -238,77 +340,57 @@ impl AggregatorImpl {
         }
     }
 
-    /// Create a snapshot from processed stats.
-    fn create_snapshot(
+    /// Run the actor's subsystems, collecting their contributions and caps. With `dirty`,
+    /// only the subsystems that may contribute to those dimensions run.
+    ///
+    /// The dimensions each subsystem contributes to a context-free snapshot are recorded.
+    async fn collect(
         &self,
         actor: &Actor,
-        primary_stats: HashMap<String, f64>,
-        caps_used: HashMap<String, Caps>,
-        subsystems_processed: &[String],
-        processing_time: u64,
-    ) -> Snapshot {
-        Snapshot {
-            actor_id: actor.id.clone(),
-            primary: primary_stats,
-            derived: HashMap::new(), // Simplified - no derived stats for now
-            caps_used,
-            version: actor.version,
-            created_at: chrono::Utc::now(),
-            subsystems_processed: subsystems_processed.to_vec(),
-            processing_time: Some(processing_time),
-            cache_hit: false,
-            metadata: HashMap::new(),
-        }
-    }
-}
-
-#[async_trait]
-impl Aggregator for AggregatorImpl {
-    async fn resolve(&self, actor: &Actor) -> ActorCoreResult<Snapshot> {
-        self.resolve_with_context(actor, None).await
-    }
-
-    async fn resolve_with_context(
-        &self,
-        actor: &Actor,
-        _context: Option<HashMap<String, serde_json::Value>>,
-    ) -> ActorCoreResult<Snapshot> {
-        // Check cache first
-        if let Some(cached_snapshot) = self.get_cached_snapshot(&actor.id) {
-            // Update cache hit metrics
-            {
-                let mut metrics = self.metrics.write().await;
-                metrics.cache_hits += 1;
-            }
-            return Ok(cached_snapshot);
-        }
-        
-        let start_time = std::time::Instant::now();
-        
+        context: &AggregationContext,
+        dirty: Option<&HashSet<String>>,
+    ) -> ActorCoreResult<Collected> {
         // Get subsystems for this actor
-        let subsystems = self.get_subsystems_for_actor(actor);
-        let mut subsystems_processed = Vec::new();
-        let mut all_contributions = Vec::new();
-        let mut caps_used = HashMap::new();
+        let mut subsystems = self.get_subsystems_for_actor(actor);
+        if let Some(dirty) = dirty {
+            let contributed = self.contributed();
+            let contributed = contributed.get(&actor.id);
+            subsystems.retain(|subsystem| self.may_contribute(contributed, subsystem.system_id(), dirty));
+        }
+        let mut contributed = HashMap::new();
+        let mut collected = Collected {
+            contributions: Vec::new(),
+            caps_used: HashMap::new(),
+            subsystems_processed: Vec::new(),
+        };
 
         // Process each subsystem
         for subsystem in subsystems {
             let subsystem_id = subsystem.system_id();
             
             // Get contributions from subsystem
-            match subsystem.contribute(actor).await {
+            match subsystem.contribute(actor, context).await {
                 Ok(output) => {
+                    // Contributions to undeclared dimensions are reported by policy
+                    if let Some(dimensions) = &self.dimensions {
+                        dimensions.check_output(&output)?;
+                    }
+                    let contributions = output.primary.iter().chain(&output.derived);
+                    let stats = contributions.map(|contribution| &contribution.stat_name);
+                    let stats = stats.chain(output.caps.iter().map(|cap| &cap.stat_name)).cloned().collect();
+                    contributed.insert(subsystem_id.to_string(), stats);
+                    
                     // Extract contributions from SubsystemOutput
-                    all_contributions.extend(output.primary);
-                    all_contributions.extend(output.derived);
+                    collected.contributions.extend(output.primary);
+                    collected.contributions.extend(output.derived);
                     
                     // Extract caps from SubsystemOutput and apply them to the snapshot
                     for cap_contrib in output.caps {
                         // Apply cap contribution to the snapshot
-                        self.apply_cap_contribution(&mut caps_used, cap_contrib);
+                        self.apply_cap_contribution(&mut collected.caps_used, cap_contrib);
                     }
                     
-                    subsystems_processed.push(subsystem_id.to_string());
+                    collected.subsystems_processed.push(subsystem_id.to_string());
                 }
                 Err(e) => {
                     warn!("Subsystem {} failed to contribute: {}", subsystem_id, e);
@@ -317,49 +399,271 @@ impl Aggregator for AggregatorImpl {
             }
         }
 
+        if context.is_empty() {
+            let mut recorded = self.contributed();
+            match dirty {
+                // Subsystems that did not run keep what they contributed
+                Some(_) => recorded.entry(actor.id.clone()).or_default().extend(contributed),
+                None => {
+                    recorded.insert(actor.id.clone(), contributed);
+                }
+            }
+        }
+        Ok(collected)
+    }
+
+    /// Cap an aggregated stat by the subsystems' caps, or else the caps provider's.
+    async fn cap_stat(
+        &self,
+        dimension: &str,
+        value: f64,
+        caps_used: &HashMap<String, Caps>,
+        actor: &Actor,
+    ) -> ActorCoreResult<f64> {
+        let capped_value = if let Some(caps_struct) = caps_used.get(dimension) {
+            caps_struct.clamp(value)
+        } else {
+            // Fallback to caps provider if no caps from subsystems
+            let caps_provider_value = self.apply_caps(dimension, value, actor).await?;
+            
+            // If caps provider doesn't provide caps, we cannot clamp without config
+            if caps_provider_value == value {
+                // Cannot clamp without config_manager - return original value
+                // This should be handled by the calling code to ensure config is available
+                caps_provider_value
+            } else {
+                caps_provider_value
+            }
+        };
+        Ok(match &self.dimensions {
+            Some(dimensions) => dimensions.clamp(dimension, capped_value),
+            None => capped_value,
+        })
+    }
+
+    /// Evaluate the derived stats, which read the capped primary stats and are capped like them.
+    fn derive(
+        &self,
+        actor: &Actor,
+        primary_stats: &HashMap<String, f64>,
+        caps_used: &HashMap<String, Caps>,
+    ) -> HashMap<String, f64> {
+        match &self.derived_stats {
+            Some(derived_stats) => derived_stats
+                .evaluate(actor, primary_stats)
+                .into_iter()
+                .map(|(stat, value)| match caps_used.get(&stat) {
+                    Some(caps) => (stat, caps.clamp(value)),
+                    None => (stat, value),
+                })
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Run the subsystems and the pipeline for an actor, adding `hypothetical`
+    /// contributions to theirs. With `record`, the snapshot goes into the history.
+    async fn aggregate(
+        &self,
+        actor: &Actor,
+        context: &AggregationContext,
+        hypothetical: &[Contribution],
+        record: bool,
+    ) -> ActorCoreResult<Snapshot> {
+        let start_time = std::time::Instant::now();
+        let Collected { mut contributions, caps_used, subsystems_processed } =
+            self.collect(actor, context, None).await?;
+
+        // What-if contributions go through the pipeline like any subsystem's
+        contributions.extend(hypothetical.iter().cloned());
+        let recorded = match &self.history {
+            Some(_) if record => Some(contributions.clone()),
+            _ => None,
+        };
+
         // Process all contributions
-        let primary_stats = self.process_contributions(all_contributions).await?;
+        let primary_stats = self.process_contributions(contributions).await?;
 
         // Apply caps to each stat
         let mut capped_stats = HashMap::new();
         for (dimension, value) in primary_stats {
-            let capped_value = if let Some(caps_struct) = caps_used.get(&dimension) {
-                caps_struct.clamp(value)
-            } else {
-                // Fallback to caps provider if no caps from subsystems
-                let caps_provider_value = self.apply_caps(&dimension, value, actor).await?;
-                
-                // If caps provider doesn't provide caps, we cannot clamp without config
-                if caps_provider_value == value {
-                    // Cannot clamp without config_manager - return original value
-                    // This should be handled by the calling code to ensure config is available
-                    caps_provider_value
-                } else {
-                    caps_provider_value
-                }
-            };
-            capped_stats.insert(dimension.clone(), capped_value);
+            let capped_value = self.cap_stat(&dimension, value, &caps_used, actor).await?;
+            capped_stats.insert(dimension, capped_value);
         }
 
+        let derived_stats = self.derive(actor, &capped_stats, &caps_used);
+
         let processing_time = start_time.elapsed().as_micros() as u64;
 
         // Create snapshot
-        let snapshot = self.create_snapshot(
+        let mut snapshot = self.create_snapshot(
             actor,
             capped_stats,
             caps_used,
             &subsystems_processed,
             processing_time,
         );
+        snapshot.derived = derived_stats;
+        if let (Some(history), Some(contributions)) = (&self.history, recorded) {
+            history.record(snapshot.clone(), contributions);
+        }
+        Ok(snapshot)
+    }
+
+    /// Re-aggregate the `dirty` dimensions of a cached snapshot, keeping the others.
+    ///
+    /// Only the subsystems that may contribute to a dirty dimension run, and only the dirty
+    /// dimensions are merged and capped. Derived stats are evaluated again, as they may
+    /// read any of them.
+    async fn reaggregate(
+        &self,
+        actor: &Actor,
+        mut snapshot: Snapshot,
+        dirty: &HashSet<String>,
+    ) -> ActorCoreResult<Snapshot> {
+        let start_time = std::time::Instant::now();
+        let Collected { contributions, mut caps_used, subsystems_processed } =
+            self.collect(actor, &AggregationContext::default(), Some(dirty)).await?;
+
+        // The contributions of the subsystems that did not run are kept from the history
+        let recorded = self.history.as_ref().map(|history| {
+            let mut recorded = history.latest_contributions(&actor.id);
+            recorded.retain(|contribution| !dirty.contains(&contribution.stat_name));
+            let patched = contributions.iter().filter(|contribution| dirty.contains(&contribution.stat_name));
+            recorded.extend(patched.cloned());
+            recorded
+        });
+        let contributions = contributions
+            .into_iter()
+            .filter(|contribution| dirty.contains(&contribution.stat_name))
+            .collect();
+        let stats = self.process_contributions(contributions).await?;
+
+        for dimension in dirty {
+            match caps_used.remove(dimension) {
+                Some(caps) => snapshot.caps_used.insert(dimension.clone(), caps),
+                None => snapshot.caps_used.remove(dimension),
+            };
+            // A dimension nothing contributes to any more leaves the snapshot
+            match stats.get(dimension) {
+                Some(&value) => {
+                    let capped_value = self.cap_stat(dimension, value, &snapshot.caps_used, actor).await?;
+                    snapshot.primary.insert(dimension.clone(), capped_value);
+                }
+                None => {
+                    snapshot.primary.remove(dimension);
+                }
+            }
+        }
+        snapshot.derived = self.derive(actor, &snapshot.primary, &snapshot.caps_used);
+
+        snapshot.version = actor.version;
+        snapshot.created_at = chrono::Utc::now();
+        for subsystem_id in subsystems_processed {
+            if !snapshot.subsystems_processed.contains(&subsystem_id) {
+                snapshot.subsystems_processed.push(subsystem_id);
+            }
+        }
+        snapshot.processing_time = Some(start_time.elapsed().as_micros() as u64);
+        snapshot.cache_hit = false;
+        if let (Some(history), Some(contributions)) = (&self.history, recorded) {
+            history.record(snapshot.clone(), contributions);
+        }
+        Ok(snapshot)
+    }
+
+    /// Create a snapshot from processed stats.
+    fn create_snapshot(
+        &self,
+        actor: &Actor,
+        primary_stats: HashMap<String, f64>,
+        caps_used: HashMap<String, Caps>,
+        subsystems_processed: &[String],
+        processing_time: u64,
+    ) -> Snapshot {
+        Snapshot {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            actor_id: actor.id.clone(),
+            primary: primary_stats,
+            derived: HashMap::new(),
+            caps_used,
+            version: actor.version,
+            created_at: chrono::Utc::now(),
+            subsystems_processed: subsystems_processed.to_vec(),
+            processing_time: Some(processing_time),
+            cache_hit: false,
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Aggregator for AggregatorImpl {
+    async fn resolve(&self, actor: &Actor) -> ActorCoreResult<Snapshot> {
+        self.resolve_with_context(actor, &AggregationContext::default()).await
+    }
+
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "actor_core.resolve", skip_all, fields(actor_id = %actor.id, version = actor.version))
+    )]
+    async fn resolve_with_context(
+        &self,
+        actor: &Actor,
+        context: &AggregationContext,
+    ) -> ActorCoreResult<Snapshot> {
+        // Check cache first; context-dependent stats are cached per context
+        let cache_key = context.cache_key(&actor.id);
+        let mut patch = None;
+        if let Some(cached_snapshot) = self.cached_snapshot(&actor.id, &cache_key) {
+            // Dirty dimensions are patched into the context-free snapshot
+            let dirty = if context.is_empty() { self.dirty().remove(&actor.id) } else { None };
+            match dirty {
+                Some(dirty) => patch = Some((cached_snapshot, dirty)),
+                None => {
+                    // Update cache hit metrics
+                    {
+                        let mut metrics = self.metrics.write().await;
+                        metrics.cache_hits += 1;
+                    }
+                    return Ok(cached_snapshot);
+                }
+            }
+        }
+        
+        // Held until the snapshot is cached, so swapped subsystems can drain
+        let _resolution = self.subsystem_registry.begin_resolution();
+        let snapshot = match patch {
+            Some((cached_snapshot, dirty)) => match self.reaggregate(actor, cached_snapshot, &dirty).await {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    // Resolved in full next time
+                    self.invalidate_cache(&actor.id);
+                    return Err(e);
+                }
+            },
+            None => {
+                if context.is_empty() {
+                    self.dirty().remove(&actor.id);
+                }
+                // Only context-free snapshots are recorded
+                self.aggregate(actor, context, &[], context.is_empty()).await?
+            }
+        };
+        let subsystems_processed = &snapshot.subsystems_processed;
+        let processing_time = snapshot.processing_time.unwrap_or_default();
 
         // Cache the snapshot (TTL should be loaded from configuration)
         // For now, we'll use a reasonable default but this should be configurable
         let cache_ttl = 3600; // TODO: Load from configuration
         self.cache.set(
-            actor.id.to_string(),
+            cache_key.clone(),
             serde_json::to_value(&snapshot)?,
             Some(cache_ttl),
         )?;
+        if !context.is_empty() {
+            self.context_keys().entry(actor.id.clone()).or_default().insert(cache_key);
+        }
 
         // Update metrics
         {
@@ -380,6 +684,20 @@ impl Aggregator for AggregatorImpl {
         Ok(snapshot)
     }
 
+    async fn resolve_hypothetical(
+        &self,
+        actor: &Actor,
+        hypothetical: &[Contribution],
+    ) -> ActorCoreResult<StatPrediction> {
+        // Both sides are resolved fresh, so a stale cached snapshot cannot skew the delta
+        let _resolution = self.subsystem_registry.begin_resolution();
+        let context = AggregationContext::default();
+        // Predictions are not resolutions, so neither side goes into the history
+        let current = self.aggregate(actor, &context, &[], false).await?;
+        let predicted = self.aggregate(actor, &context, hypothetical, false).await?;
+        Ok(StatPrediction::between(current, predicted))
+    }
+
     async fn resolve_batch(&self, actors: &[Actor]) -> ActorCoreResult<Vec<Snapshot>> {
         let mut results = Vec::new();
         
@@ -397,27 +715,35 @@ impl Aggregator for AggregatorImpl {
     }
 
     fn get_cached_snapshot(&self, actor_id: &String) -> Option<Snapshot> {
-        match self.cache.get(&actor_id.to_string()) {
-            Some(value) => {
-                match serde_json::from_value(value) {
-                    Ok(snapshot) => Some(snapshot),
-                    Err(e) => {
-                        warn!("Failed to deserialize cached snapshot for {}: {}", actor_id, e);
-                        None
-                    }
-                }
+        self.cached_snapshot(actor_id, actor_id)
+    }
+
+    fn invalidate_cache(&self, actor_id: &String) {
+        self.dirty().remove(actor_id);
+        self.contributed().remove(actor_id);
+        let context_keys = self.context_keys().remove(actor_id).unwrap_or_default();
+        for cache_key in std::iter::once(actor_id.clone()).chain(context_keys) {
+            if let Err(e) = self.cache.delete(&cache_key) {
+                warn!("Failed to invalidate cache for {}: {}", actor_id, e);
             }
-            None => None,
         }
     }
 
-    fn invalidate_cache(&self, actor_id: &String) {
-        if let Err(e) = self.cache.delete(&actor_id.to_string()) {
-            warn!("Failed to invalidate cache for {}: {}", actor_id, e);
+    fn mark_dirty(&self, actor_id: &str, dimensions: &[String]) {
+        // Snapshots resolved under a context are dropped rather than patched
+        let context_keys = self.context_keys().remove(actor_id).unwrap_or_default();
+        for cache_key in context_keys {
+            if let Err(e) = self.cache.delete(&cache_key) {
+                warn!("Failed to invalidate cache for {}: {}", actor_id, e);
+            }
         }
+        self.dirty().entry(actor_id.to_string()).or_default().extend(dimensions.iter().cloned());
     }
 
     fn clear_cache(&self) {
+        self.context_keys().clear();
+        self.dirty().clear();
+        self.contributed().clear();
         if let Err(e) = self.cache.clear() {
             warn!("Failed to clear cache: {}", e);
         }
@@ -18,10 +18,13 @@ use crate::metrics::AggregatorMetrics;
 // use crate::types::*; // Unused import
 use crate::types::Actor;
 use crate::types::Snapshot;
+use crate::types::StatPrediction;
+use crate::snapshot_schema::{decode_snapshot, SNAPSHOT_SCHEMA_VERSION};
 use crate::types::Contribution;
 use crate::types::CapContribution;
 use crate::types::Caps;
 use crate::types::SubsystemOutput;
+use crate::aggregation_context::AggregationContext;
 use crate::ActorCoreResult;
 use uuid::Uuid;
 
@@ -63,11 +66,15 @@ impl OptimizedAggregator {
     }
     
     /// Resolve actor stats with optimized processing.
-    async fn resolve_optimized(&self, actor: &Actor) -> ActorCoreResult<Snapshot> {
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "actor_core.resolve", skip_all, fields(actor_id = %actor.id, version = actor.version))
+    )]
+    async fn resolve_optimized(&self, actor: &Actor, context: &AggregationContext) -> ActorCoreResult<Snapshot> {
         let start_time = std::time::Instant::now();
         
         // Check cache first using atomic operations
-        let cache_key = self.generate_cache_key(actor);
+        let cache_key = self.generate_cache_key(actor, context);
         if let Some(cached_value) = self.cache.get(&cache_key) {
             if let Ok(cached_snapshot) = serde_json::from_value(cached_value) {
                 self.atomic_metrics.record_cache_hit();
@@ -77,22 +84,10 @@ impl OptimizedAggregator {
         
         self.atomic_metrics.record_cache_miss();
         
-        // Get subsystems with optimized collection
-        let subsystems = self.subsystem_registry.get_by_priority();
+        // Held until the snapshot is cached, so swapped subsystems can drain
+        let _resolution = self.subsystem_registry.begin_resolution();
         
-        // Use Vec for subsystem collections
-        let mut subsystem_outputs: Vec<SubsystemOutput> = Vec::new();
-        
-        // Process subsystems with optimized async batching
-        for subsystem in subsystems {
-            match subsystem.contribute(actor).await {
-                Ok(output) => subsystem_outputs.push(output),
-                Err(e) => {
-                    error!("Subsystem {} failed to contribute: {}", subsystem.system_id(), e);
-                    continue;
-                }
-            }
-        }
+        let subsystem_outputs = self.collect_outputs(actor, context).await;
         
         // Aggregate contributions with optimized processing
         let snapshot = self.aggregate_contributions_optimized(actor, &subsystem_outputs).await?;
@@ -109,6 +104,27 @@ impl OptimizedAggregator {
         Ok(snapshot)
     }
     
+    /// Collect the contributions of the actor's subsystems.
+    async fn collect_outputs(&self, actor: &Actor, context: &AggregationContext) -> Vec<SubsystemOutput> {
+        // Get subsystems with optimized collection
+        let subsystems = self.subsystem_registry.get_for_actor(actor);
+        
+        // Use Vec for subsystem collections
+        let mut subsystem_outputs: Vec<SubsystemOutput> = Vec::new();
+        
+        // Process subsystems with optimized async batching
+        for subsystem in subsystems {
+            match subsystem.contribute(actor, context).await {
+                Ok(output) => subsystem_outputs.push(output),
+                Err(e) => {
+                    error!("Subsystem {} failed to contribute: {}", subsystem.system_id(), e);
+                    continue;
+                }
+            }
+        }
+        subsystem_outputs
+    }
+    
     /// Aggregate contributions with micro-optimizations.
     async fn aggregate_contributions_optimized(
         &self,
@@ -140,6 +156,7 @@ impl OptimizedAggregator {
         
         // Create snapshot with optimized data structures
         Ok(Snapshot {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
             actor_id: actor.id.clone(),
             primary: aggregated_stats.into_iter().collect(),
             derived: HashMap::new(), // Simplified - no derived stats for now
@@ -237,7 +254,7 @@ impl OptimizedAggregator {
     }
     
     /// Generate cache key with optimized hashing.
-    fn generate_cache_key(&self, actor: &Actor) -> String {
+    fn generate_cache_key(&self, actor: &Actor, context: &AggregationContext) -> String {
         use std::hash::{Hash, Hasher};
         use fxhash::FxHasher;
         
@@ -249,6 +266,13 @@ impl OptimizedAggregator {
         for subsystem in &actor.subsystems {
             subsystem.hash(&mut hasher);
         }
+        actor.disabled_subsystems.hash(&mut hasher);
+        actor.template.hash(&mut hasher);
+        
+        // Context-dependent stats are cached per context
+        if !context.is_empty() {
+            context.fingerprint().hash(&mut hasher);
+        }
         
         format!("actor_{}", hasher.finish())
     }
@@ -268,19 +292,34 @@ impl OptimizedAggregator {
 impl Aggregator for OptimizedAggregator {
     /// Resolve actor stats by aggregating contributions from all subsystems.
     async fn resolve(&self, actor: &Actor) -> ActorCoreResult<Snapshot> {
-        self.resolve_optimized(actor).await
+        self.resolve_optimized(actor, &AggregationContext::default()).await
     }
     
     /// Resolve actor stats with additional context.
     async fn resolve_with_context(
         &self,
         actor: &Actor,
-        context: Option<HashMap<String, serde_json::Value>>,
+        context: &AggregationContext,
     ) -> ActorCoreResult<Snapshot> {
-        // For now, ignore context in optimized version
-        // TODO: Implement context-aware optimization
-        let _context = context;
-        self.resolve_optimized(actor).await
+        self.resolve_optimized(actor, context).await
+    }
+    
+    /// Predict the actor's stats with hypothetical contributions, bypassing the cache.
+    async fn resolve_hypothetical(
+        &self,
+        actor: &Actor,
+        hypothetical: &[Contribution],
+    ) -> ActorCoreResult<StatPrediction> {
+        let _resolution = self.subsystem_registry.begin_resolution();
+        let mut subsystem_outputs = self.collect_outputs(actor, &AggregationContext::default()).await;
+        let current = self.aggregate_contributions_optimized(actor, &subsystem_outputs).await?;
+        
+        // What-if contributions are aggregated like one more subsystem's
+        let mut what_if = SubsystemOutput::new("hypothetical".to_string());
+        what_if.primary = hypothetical.to_vec();
+        subsystem_outputs.push(what_if);
+        let predicted = self.aggregate_contributions_optimized(actor, &subsystem_outputs).await?;
+        Ok(StatPrediction::between(current, predicted))
     }
     
     async fn resolve_batch(&self, actors: &[Actor]) -> ActorCoreResult<Vec<Snapshot>> {
@@ -295,10 +334,12 @@ impl Aggregator for OptimizedAggregator {
         let cache_key = format!("actor_{}", actor_id);
         match self.cache.get(&cache_key) {
             Some(value) => {
-                match serde_json::from_value(value) {
+                match decode_snapshot(value) {
                     Ok(snapshot) => Some(snapshot),
                     Err(e) => {
-                        warn!("Failed to deserialize cached snapshot for {}: {}", actor_id, e);
+                        // Stale or unreadable entries are dropped and resolved again
+                        warn!("Discarding cached snapshot for {}: {}", actor_id, e);
+                        self.invalidate_cache(actor_id);
                         None
                     }
                 }
@@ -0,0 +1,231 @@
+//! Companion stats, resolved through an aggregation pipeline of their own.
+//!
+//! A companion's stats start from its owner's. [`CompanionInheritance`] holds a formula per
+//! inherited stat, e.g. `attack_power: owner.attack_power * 0.4 + level * 3`, where
+//! `owner.<stat>` reads the owner's resolved stats, `owner.level` the owner's level and
+//! `level` the companion's own. Species may replace or add formulas.
+//!
+//! Companions are actors of their own, with the companion ID as actor ID, its species as
+//! race and its level as level. Their aggregator is built with a [`CompanionSubsystem`],
+//! contributing the inherited stats as flat bonuses, alongside any other subsystems.
+//! [`CompanionPipeline`] resolves the owner through the owners' aggregator, then the
+//! companion through its own. Rosters, leveling and commands live in
+//! [`shared::companion`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use shared::companion::{Companion, CompanionManager};
+
+use crate::aggregation_context::AggregationContext;
+use crate::derived_stats::{DerivedStatDefinition, Formula};
+use crate::enums::Bucket;
+use crate::interfaces::{Aggregator, Subsystem};
+use crate::types::{Actor, Contribution, Snapshot, SubsystemOutput};
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// System ID of the companion inheritance subsystem
+pub const COMPANION_SYSTEM_ID: &str = "companion";
+
+/// Prefix of the owner's stats in inheritance formulas
+const OWNER_PREFIX: &str = "owner.";
+
+/// Inheritance formulas configuration file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompanionInheritanceConfig {
+    /// Stats every companion inherits
+    #[serde(default)]
+    pub inherit: Vec<DerivedStatDefinition>,
+    /// Formulas per species, replacing those of the same stats
+    #[serde(default)]
+    pub species: HashMap<String, Vec<DerivedStatDefinition>>,
+}
+
+/// Compiled inheritance formulas
+#[derive(Debug, Clone, Default)]
+pub struct CompanionInheritance {
+    inherit: Vec<(String, Formula)>,
+    species: HashMap<String, Vec<(String, Formula)>>,
+}
+
+impl CompanionInheritance {
+    /// Compile the formulas of a configuration.
+    pub fn from_config(config: &CompanionInheritanceConfig) -> ActorCoreResult<Self> {
+        let species = config
+            .species
+            .iter()
+            .map(|(species, definitions)| Ok((species.clone(), compile(definitions)?)))
+            .collect::<ActorCoreResult<_>>()?;
+        Ok(Self { inherit: compile(&config.inherit)?, species })
+    }
+
+    /// Compile the formulas of a YAML configuration.
+    pub fn from_yaml(content: &str) -> ActorCoreResult<Self> {
+        let config: CompanionInheritanceConfig = serde_yaml::from_str(content)?;
+        Self::from_config(&config)
+    }
+
+    /// Stats a companion of `species` at `level` inherits from its owner.
+    pub fn inherit(&self, species: &str, level: i64, owner_level: i64, owner: &Snapshot) -> HashMap<String, f64> {
+        let lookup = |name: &str| match name {
+            "level" => level as f64,
+            "owner.level" => owner_level as f64,
+            _ => name.strip_prefix(OWNER_PREFIX).and_then(|stat| owner.get_stat(stat)).unwrap_or(0.0),
+        };
+        let overrides = self.species.get(species).map(Vec::as_slice).unwrap_or_default();
+        let mut stats = HashMap::new();
+        for (stat, formula) in self.inherit.iter().chain(overrides) {
+            stats.insert(stat.clone(), formula.evaluate(&lookup));
+        }
+        stats
+    }
+}
+
+/// Compile inheritance formulas, which may only read `level` and the owner's stats
+fn compile(definitions: &[DerivedStatDefinition]) -> ActorCoreResult<Vec<(String, Formula)>> {
+    definitions
+        .iter()
+        .map(|definition| {
+            let formula = Formula::parse(&definition.formula)?;
+            if let Some(stat) = formula.stats().find(|stat| *stat != "level" && !stat.starts_with(OWNER_PREFIX)) {
+                return Err(ActorCoreError::ConfigurationError(format!(
+                    "Inherited stat {} reads '{}'; companions inherit from 'level' and 'owner.<stat>' only",
+                    definition.id, stat
+                )));
+            }
+            Ok((definition.id.clone(), formula))
+        })
+        .collect()
+}
+
+/// Build the actor a companion is resolved as
+pub fn companion_actor(companion: &Companion) -> Actor {
+    Actor::simple(&companion.id, &companion.species, companion.level as i64)
+}
+
+/// Owner stats last resolved for its companions
+#[derive(Debug, Clone)]
+struct OwnerStats {
+    level: i64,
+    snapshot: Snapshot,
+}
+
+/// Subsystem contributing the stats companions inherit from their owners
+pub struct CompanionSubsystem {
+    companions: Arc<CompanionManager>,
+    inheritance: CompanionInheritance,
+    owners: RwLock<HashMap<String, OwnerStats>>,
+    priority: i64,
+}
+
+impl CompanionSubsystem {
+    /// Create a new Companion Subsystem
+    pub fn new(companions: Arc<CompanionManager>, inheritance: CompanionInheritance) -> Self {
+        Self { companions, inheritance, owners: RwLock::new(HashMap::new()), priority: 100 }
+    }
+
+    /// Set the subsystem priority
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn companions(&self) -> &Arc<CompanionManager> {
+        &self.companions
+    }
+
+    /// Record the stats an owner's companions inherit from
+    pub fn set_owner(&self, owner: &Actor, snapshot: Snapshot) {
+        self.owners.write().insert(owner.id.clone(), OwnerStats { level: owner.level, snapshot });
+    }
+
+    /// Forget an owner's stats, e.g. when it logs out
+    pub fn forget_owner(&self, owner_id: &str) {
+        self.owners.write().remove(owner_id);
+    }
+}
+
+#[async_trait]
+impl Subsystem for CompanionSubsystem {
+    fn system_id(&self) -> &str {
+        COMPANION_SYSTEM_ID
+    }
+
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    async fn contribute(&self, actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(COMPANION_SYSTEM_ID.to_string());
+        let Some(companion) = self.companions.companion(&actor.id) else {
+            return Ok(output);
+        };
+        let owners = self.owners.read();
+        let owner = owners.get(&companion.owner_id).ok_or_else(|| {
+            ActorCoreError::SubsystemError(format!(
+                "Stats of {}, owner of companion {}, have not been resolved",
+                companion.owner_id, companion.id
+            ))
+        })?;
+        let inherited = self.inheritance.inherit(&companion.species, actor.level, owner.level, &owner.snapshot);
+        for (stat, value) in inherited {
+            output.add_contribution(Contribution::new(stat, Bucket::Flat, value, COMPANION_SYSTEM_ID.to_string()));
+        }
+        Ok(output)
+    }
+}
+
+/// Resolves companions after their owners, each through its own aggregator
+pub struct CompanionPipeline {
+    owners: Arc<dyn Aggregator>,
+    companions: Arc<dyn Aggregator>,
+    subsystem: Arc<CompanionSubsystem>,
+}
+
+impl CompanionPipeline {
+    /// Create a pipeline; `companions` must be built with `subsystem` registered.
+    pub fn new(
+        owners: Arc<dyn Aggregator>,
+        companions: Arc<dyn Aggregator>,
+        subsystem: Arc<CompanionSubsystem>,
+    ) -> Self {
+        Self { owners, companions, subsystem }
+    }
+
+    /// Resolve one of the owner's companions, active or stabled, from the owner's current stats.
+    pub async fn resolve(&self, owner: &Actor, companion_id: &str) -> ActorCoreResult<Snapshot> {
+        let companion = self
+            .subsystem
+            .companions()
+            .companion(companion_id)
+            .filter(|companion| companion.owner_id == owner.id)
+            .ok_or_else(|| {
+                ActorCoreError::InvalidInput(format!("Actor {} has no companion {}", owner.id, companion_id))
+            })?;
+        self.subsystem.set_owner(owner, self.owners.resolve(owner).await?);
+        self.resolve_companion(&companion).await
+    }
+
+    /// Resolve the companions the owner has summoned.
+    pub async fn resolve_active(&self, owner: &Actor) -> ActorCoreResult<Vec<Snapshot>> {
+        let active = self.subsystem.companions().active(&owner.id);
+        if active.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.subsystem.set_owner(owner, self.owners.resolve(owner).await?);
+        let mut snapshots = Vec::with_capacity(active.len());
+        for companion in &active {
+            snapshots.push(self.resolve_companion(companion).await?);
+        }
+        Ok(snapshots)
+    }
+
+    async fn resolve_companion(&self, companion: &Companion) -> ActorCoreResult<Snapshot> {
+        // The owner's stats or the companion's level may have changed since it was cached
+        self.companions.invalidate_cache(&companion.id);
+        self.companions.resolve(&companion_actor(companion)).await
+    }
+}
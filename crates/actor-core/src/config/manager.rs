@@ -93,7 +93,20 @@ impl ConfigurationManager {
         self.aggregator.refresh_config().await
     }
 
-    /// Save configuration (for persistence)
+    /// Reload every provider from its backing store, then drop cached values
+    ///
+    /// Returns the number of providers reloaded.
+    pub async fn reload(&self) -> ActorCoreResult<usize> {
+        let providers = self.aggregator.get_providers();
+        for provider in &providers {
+            provider.reload().await?;
+        }
+        self.refresh_config().await?;
+        info!("Reloaded {} configuration providers", providers.len());
+        Ok(providers.len())
+    }
+
+        /// Save configuration (for persistence)
     pub async fn save_configs(&self) -> ActorCoreResult<()> {
         info!("💾 Saving configuration to MongoDB...");
         
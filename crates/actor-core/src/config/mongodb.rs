@@ -15,6 +15,8 @@ use mongodb::{
 #[cfg(feature = "mongodb-storage")]
 use async_trait::async_trait;
 #[cfg(feature = "mongodb-storage")]
+use parking_lot::RwLock;
+#[cfg(feature = "mongodb-storage")]
 use std::collections::HashMap;
 #[cfg(feature = "mongodb-storage")]
 use tracing::{info, warn};
@@ -37,7 +39,7 @@ pub struct MongoDBConfigurationProvider {
     #[allow(dead_code)]
     database: Database,
     collection: Collection<ConfigurationDocument>,
-    config_data: HashMap<String, HashMap<String, ConfigurationValue>>,
+    config_data: RwLock<HashMap<String, HashMap<String, ConfigurationValue>>>,
     mongodb_config: MongoDBConfig,
 }
 
@@ -83,16 +85,12 @@ impl MongoDBConfigurationProvider {
         // Test connection
         database.run_command(mongodb::bson::doc! {"ping": 1}, None).await?;
 
-        let mut provider = Self {
-            base: BaseConfigurationProvider::new(
-                provider_id,
-                priority,
-                Vec::new(), // Will be populated after loading
-            ),
+        let provider = Self {
+            base: BaseConfigurationProvider::new(provider_id, priority, Vec::new()),
             client,
             database,
             collection,
-            config_data: HashMap::new(),
+            config_data: RwLock::new(HashMap::new()),
             mongodb_config,
         };
 
@@ -102,12 +100,11 @@ impl MongoDBConfigurationProvider {
         Ok(provider)
     }
 
-    /// Load configuration from MongoDB
-    pub async fn load_from_database(&mut self) -> ActorCoreResult<()> {
+    /// Load configuration from MongoDB, replacing what was loaded before
+    pub async fn load_from_database(&self) -> ActorCoreResult<()> {
         info!("Loading configuration from MongoDB...");
 
         let mut config_data = HashMap::new();
-        let mut supported_categories = Vec::new();
 
         // Find all configuration documents
         let find_options = FindOptions::builder()
@@ -142,25 +139,15 @@ impl MongoDBConfigurationProvider {
             };
 
             config_data
-                .entry(doc.category.clone())
+                .entry(doc.category)
                 .or_insert_with(HashMap::new)
-                .insert(doc.key.clone(), config_value);
-
-            if !supported_categories.contains(&doc.category) {
-                supported_categories.push(doc.category);
-            }
+                .insert(doc.key, config_value);
         }
 
-        self.config_data = config_data;
-
-        // Update the base provider with supported categories
-        self.base = BaseConfigurationProvider::new(
-            self.base.provider_id().to_string(),
-            self.base.priority(),
-            supported_categories,
-        );
+        let categories = config_data.len();
+        *self.config_data.write() = config_data;
 
-        info!("Loaded {} configuration categories from MongoDB", self.config_data.len());
+        info!("Loaded {} configuration categories from MongoDB", categories);
         Ok(())
     }
 
@@ -205,7 +192,7 @@ impl MongoDBConfigurationProvider {
     }
 
     /// Sync configuration from files to MongoDB
-    pub async fn sync_from_files(&mut self) -> ActorCoreResult<()> {
+    pub async fn sync_from_files(&self) -> ActorCoreResult<()> {
         if !self.mongodb_config.enable_auto_sync {
             info!("Auto-sync disabled, skipping file sync");
             return Ok(());
@@ -293,19 +280,15 @@ impl ConfigurationProvider for MongoDBConfigurationProvider {
     }
 
     fn get_supported_categories(&self) -> Vec<String> {
-        self.base.get_supported_categories()
+        self.config_data.read().keys().cloned().collect()
     }
 
     async fn get_config_value(&self, category: &str, key: &str) -> ActorCoreResult<Option<ConfigurationValue>> {
-        if let Some(category_data) = self.config_data.get(category) {
-            Ok(category_data.get(key).cloned())
-        } else {
-            Ok(None)
-        }
+        Ok(self.config_data.read().get(category).and_then(|category_data| category_data.get(key).cloned()))
     }
 
     async fn get_category_config(&self, category: &str) -> ActorCoreResult<HashMap<String, ConfigurationValue>> {
-        Ok(self.config_data.get(category).cloned().unwrap_or_default())
+        Ok(self.config_data.read().get(category).cloned().unwrap_or_default())
     }
 
     fn get_merge_rule(&self, _category: &str, _key: &str) -> Option<ConfigurationMergeRule> {
@@ -318,8 +301,12 @@ impl ConfigurationProvider for MongoDBConfigurationProvider {
         })
     }
 
+    async fn reload(&self) -> ActorCoreResult<()> {
+        self.load_from_database().await
+    }
+
     async fn validate_config(&self) -> ActorCoreResult<()> {
-        for (category, properties) in &self.config_data {
+        for (category, properties) in self.config_data.read().iter() {
             if category.is_empty() {
                 return Err(ActorCoreError::ConfigurationError(
                     "Category name cannot be empty".to_string()
@@ -134,13 +134,7 @@ impl MongoDBConfigManager {
 
     /// Load configuration from MongoDB
     pub async fn load_from_db(&self) -> ActorCoreResult<()> {
-        info!("Loading configuration from MongoDB...");
-        
-        // This would need to be implemented in MongoDBConfigurationProvider
-        // For now, we'll just log that this functionality is not yet implemented
-        warn!("Load from DB functionality not yet implemented in MongoDBConfigurationProvider");
-        
-        Ok(())
+        self.mongodb_provider.load_from_database().await
     }
 
     /// Save configuration to MongoDB
@@ -28,6 +28,13 @@ pub trait ConfigurationProvider: Send + Sync {
     
     /// Validate configuration data
     async fn validate_config(&self) -> ActorCoreResult<()>;
+
+    /// Re-read configuration from the provider's backing store
+    ///
+    /// Providers whose data is fixed at construction keep the default no-op.
+    async fn reload(&self) -> ActorCoreResult<()> {
+        Ok(())
+    }
     
     /// Check if this provider supports a specific category
     fn supports_category(&self, category: &str) -> bool {
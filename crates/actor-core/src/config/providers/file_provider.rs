@@ -317,6 +317,10 @@ impl ConfigurationProvider for FileConfigurationProvider {
         }
     }
     
+    async fn reload(&self) -> ActorCoreResult<()> {
+        self.load_from_file().await
+    }
+
     async fn validate_config(&self) -> ActorCoreResult<()> {
         let config_data = self.config_data.read();
         
@@ -0,0 +1,481 @@
+//! Derived stats computed from the primary stats by configurable formulas.
+//!
+//! A derived stat such as `max_hp = vitality * 10 + level * 5` is defined by a formula over
+//! the primary stats, the actor's `level` and other derived stats. [`DerivedStats`] compiles
+//! the formulas once, orders them so every stat is evaluated after the stats it reads, and
+//! rejects definitions that depend on themselves. The aggregator evaluates them after the
+//! primary stats are capped, filling [`Snapshot::derived`](crate::types::Snapshot::derived).
+//!
+//! Formulas support numbers, stat names, `+ - * / ^`, parentheses and the functions
+//! `min`, `max`, `clamp(value, min, max)`, `floor`, `ceil`, `round`, `abs` and `sqrt`.
+//! A stat the actor does not have reads as 0.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::types::Actor;
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Definition of one derived stat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DerivedStatDefinition {
+    /// Stat name in the snapshot
+    pub id: String,
+    /// Formula computing it
+    pub formula: String,
+}
+
+/// Derived stats configuration file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DerivedStatsConfig {
+    #[serde(default)]
+    pub derived_stats: Vec<DerivedStatDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Min,
+    Max,
+    Clamp,
+    Floor,
+    Ceil,
+    Round,
+    Abs,
+    Sqrt,
+}
+
+impl Function {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "min" => Self::Min,
+            "max" => Self::Max,
+            "clamp" => Self::Clamp,
+            "floor" => Self::Floor,
+            "ceil" => Self::Ceil,
+            "round" => Self::Round,
+            "abs" => Self::Abs,
+            "sqrt" => Self::Sqrt,
+            _ => return None,
+        })
+    }
+
+    /// Whether the function takes `count` arguments
+    fn accepts(self, count: usize) -> bool {
+        match self {
+            Self::Min | Self::Max => count >= 2,
+            Self::Clamp => count == 3,
+            Self::Floor | Self::Ceil | Self::Round | Self::Abs | Self::Sqrt => count == 1,
+        }
+    }
+
+    fn apply(self, args: &[f64]) -> f64 {
+        match self {
+            Self::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Self::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Self::Clamp => args[0].max(args[1]).min(args[2]),
+            Self::Floor => args[0].floor(),
+            Self::Ceil => args[0].ceil(),
+            Self::Round => args[0].round(),
+            Self::Abs => args[0].abs(),
+            Self::Sqrt => args[0].sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Stat(String),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    fn evaluate(&self, lookup: &dyn Fn(&str) -> f64) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Stat(name) => lookup(name),
+            Self::Neg(expr) => -expr.evaluate(lookup),
+            Self::Binary(op, left, right) => {
+                let (left, right) = (left.evaluate(lookup), right.evaluate(lookup));
+                match op {
+                    BinaryOp::Add => left + right,
+                    BinaryOp::Sub => left - right,
+                    BinaryOp::Mul => left * right,
+                    BinaryOp::Div => left / right,
+                    BinaryOp::Pow => left.powf(right),
+                }
+            }
+            Self::Call(function, args) => {
+                let args: Vec<f64> = args.iter().map(|arg| arg.evaluate(lookup)).collect();
+                function.apply(&args)
+            }
+        }
+    }
+
+    fn collect_stats(&self, stats: &mut BTreeSet<String>) {
+        match self {
+            Self::Number(_) => {}
+            Self::Stat(name) => {
+                stats.insert(name.clone());
+            }
+            Self::Neg(expr) => expr.collect_stats(stats),
+            Self::Binary(_, left, right) => {
+                left.collect_stats(stats);
+                right.collect_stats(stats);
+            }
+            Self::Call(_, args) => args.iter().for_each(|arg| arg.collect_stats(stats)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let value = text.parse().map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            tokens.push(match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Op(c),
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                _ => return Err(format!("unexpected '{}'", c)),
+            });
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the tokens of a formula
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut expr = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('+')) => BinaryOp::Add,
+                Some(Token::Op('-')) => BinaryOp::Sub,
+                _ => return Ok(expr),
+            };
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('*')) => BinaryOp::Mul,
+                Some(Token::Op('/')) => BinaryOp::Div,
+                _ => return Ok(expr),
+            };
+            self.position += 1;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&Token::Op('-')) {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.primary()?;
+        if self.eat(&Token::Op('^')) {
+            // Right associative, and binds tighter than a leading minus: -2^2 is -4
+            return Ok(Expr::Binary(BinaryOp::Pow, Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.advance() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Ident(name)) => {
+                if !self.eat(&Token::Open) {
+                    return Ok(Expr::Stat(name));
+                }
+                let function = Function::parse(&name).ok_or_else(|| format!("unknown function '{}'", name))?;
+                let mut args = Vec::new();
+                if !self.eat(&Token::Close) {
+                    loop {
+                        args.push(self.expression()?);
+                        if self.eat(&Token::Close) {
+                            break;
+                        }
+                        if !self.eat(&Token::Comma) {
+                            return Err(format!("expected ',' or ')' in call to '{}'", name));
+                        }
+                    }
+                }
+                if !function.accepts(args.len()) {
+                    return Err(format!("wrong number of arguments to '{}': {}", name, args.len()));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Some(Token::Open) => {
+                let expr = self.expression()?;
+                if !self.eat(&Token::Close) {
+                    return Err("missing ')'".to_string());
+                }
+                Ok(expr)
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of formula".to_string()),
+        }
+    }
+}
+
+/// A compiled formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    source: String,
+    expr: Expr,
+    stats: BTreeSet<String>,
+}
+
+impl Formula {
+    /// Compile a formula.
+    pub fn parse(source: &str) -> ActorCoreResult<Self> {
+        let invalid =
+            |reason: String| ActorCoreError::ConfigurationError(format!("Invalid formula '{}': {}", source, reason));
+        let mut parser = Parser { tokens: tokenize(source).map_err(invalid)?, position: 0 };
+        let expr = parser.expression().map_err(invalid)?;
+        if let Some(token) = parser.peek() {
+            return Err(invalid(format!("unexpected {:?}", token)));
+        }
+        let mut stats = BTreeSet::new();
+        expr.collect_stats(&mut stats);
+        Ok(Self { source: source.to_string(), expr, stats })
+    }
+
+    /// Get the formula as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the stats the formula reads.
+    pub fn stats(&self) -> impl Iterator<Item = &str> {
+        self.stats.iter().map(String::as_str)
+    }
+
+    /// Evaluate the formula, reading stats through `lookup`.
+    pub fn evaluate(&self, lookup: &dyn Fn(&str) -> f64) -> f64 {
+        self.expr.evaluate(lookup)
+    }
+}
+
+/// Compiled formulas with the derived stat each one produces
+type Plan = Vec<(String, Arc<Formula>)>;
+
+/// Derived stats pipeline stage.
+///
+/// Definitions can be reloaded at runtime; a reload that fails leaves the previous ones in
+/// place. Snapshots cached before a reload keep the old values until invalidated.
+#[derive(Default)]
+pub struct DerivedStats {
+    /// Compiled formulas in evaluation order
+    plan: RwLock<Arc<Plan>>,
+    /// Compiled formulas by source, reused by reloads that keep them
+    compiled: Mutex<HashMap<String, Arc<Formula>>>,
+}
+
+impl DerivedStats {
+    /// Create a stage without derived stats.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a stage computing the defined stats.
+    pub fn from_definitions(definitions: &[DerivedStatDefinition]) -> ActorCoreResult<Self> {
+        let stats = Self::new();
+        stats.load(definitions)?;
+        Ok(stats)
+    }
+
+    /// Create a stage from a YAML `derived_stats` configuration.
+    pub fn from_yaml(content: &str) -> ActorCoreResult<Self> {
+        let config: DerivedStatsConfig = serde_yaml::from_str(content)?;
+        Self::from_definitions(&config.derived_stats)
+    }
+
+    /// Create a stage from a YAML `derived_stats` configuration file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> ActorCoreResult<Self> {
+        Self::from_yaml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Replace the definitions, compiling and ordering them.
+    pub fn load(&self, definitions: &[DerivedStatDefinition]) -> ActorCoreResult<()> {
+        let mut formulas: HashMap<&str, Arc<Formula>> = HashMap::new();
+        let mut compiled = self.compiled.lock();
+        let mut still_used = HashMap::new();
+        for definition in definitions {
+            if definition.id.is_empty() {
+                return Err(ActorCoreError::ConfigurationError("Derived stat ID cannot be empty".to_string()));
+            }
+            let formula = match compiled.get(&definition.formula) {
+                Some(formula) => formula.clone(),
+                None => Arc::new(Formula::parse(&definition.formula)?),
+            };
+            still_used.insert(definition.formula.clone(), formula.clone());
+            if formulas.insert(&definition.id, formula).is_some() {
+                return Err(ActorCoreError::ConfigurationError(format!(
+                    "Duplicate derived stat: {}",
+                    definition.id
+                )));
+            }
+        }
+
+        let plan = order(&formulas)?;
+        *compiled = still_used;
+        *self.plan.write() = Arc::new(plan);
+        Ok(())
+    }
+
+    /// Get the derived stats in evaluation order.
+    pub fn order(&self) -> Vec<String> {
+        self.plan.read().iter().map(|(id, _)| id.clone()).collect()
+    }
+
+    /// Get the number of compiled formulas kept.
+    pub fn compiled_formulas(&self) -> usize {
+        self.compiled.lock().len()
+    }
+
+    /// Whether no derived stats are defined.
+    pub fn is_empty(&self) -> bool {
+        self.plan.read().is_empty()
+    }
+
+    /// Compute the derived stats of an actor from its primary stats.
+    ///
+    /// Formulas read derived stats first, then primary stats, then the actor's `level`.
+    /// A formula yielding NaN or infinity, e.g. by dividing by zero, gives 0.
+    pub fn evaluate(&self, actor: &Actor, primary: &HashMap<String, f64>) -> HashMap<String, f64> {
+        let plan = self.plan.read().clone();
+        let mut derived = HashMap::with_capacity(plan.len());
+        for (id, formula) in plan.iter() {
+            let lookup = |name: &str| match derived.get(name).or_else(|| primary.get(name)) {
+                Some(value) => *value,
+                None if name == "level" => actor.level as f64,
+                None => 0.0,
+            };
+            let mut value = formula.evaluate(&lookup);
+            if !value.is_finite() {
+                warn!("Derived stat {} of {} is {}, using 0", id, actor.id, value);
+                value = 0.0;
+            }
+            derived.insert(id.clone(), value);
+        }
+        derived
+    }
+}
+
+/// Order derived stats so each comes after the derived stats it reads, rejecting cycles
+fn order(formulas: &HashMap<&str, Arc<Formula>>) -> ActorCoreResult<Plan> {
+    fn visit<'a>(
+        id: &'a str,
+        formulas: &'a HashMap<&str, Arc<Formula>>,
+        done: &mut HashSet<&'a str>,
+        path: &mut Vec<&'a str>,
+        plan: &mut Plan,
+    ) -> ActorCoreResult<()> {
+        if done.contains(id) {
+            return Ok(());
+        }
+        if let Some(start) = path.iter().position(|on_path| *on_path == id) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(id);
+            return Err(ActorCoreError::ConfigurationError(format!(
+                "Derived stats depend on themselves: {}",
+                cycle.join(" -> ")
+            )));
+        }
+        let formula = &formulas[id];
+        path.push(id);
+        for stat in formula.stats() {
+            if let Some((dependency, _)) = formulas.get_key_value(stat) {
+                visit(dependency, formulas, done, path, plan)?;
+            }
+        }
+        path.pop();
+        done.insert(id);
+        plan.push((id.to_string(), formula.clone()));
+        Ok(())
+    }
+
+    // Sorted, so the order and any reported cycle do not depend on hashing
+    let mut ids: Vec<&str> = formulas.keys().copied().collect();
+    ids.sort_unstable();
+    let (mut done, mut path, mut plan) = (HashSet::new(), Vec::new(), Vec::new());
+    for id in ids {
+        visit(id, formulas, &mut done, &mut path, &mut plan)?;
+    }
+    Ok(plan)
+}
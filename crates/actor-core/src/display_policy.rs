@@ -0,0 +1,125 @@
+//! How stat values are rounded and formatted for clients.
+//!
+//! Each dimension may have a [`DisplayPolicy`] in the combiner configuration: whole numbers
+//! for hit points, two decimals of a percentage for critical chance, and so on. Snapshots
+//! keep full precision; policies only apply when a snapshot is handed to a client through
+//! [`display_snapshot`], so rounding never feeds back into aggregation or caching.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::interfaces::CombinerRegistry;
+use crate::types::Snapshot;
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Most decimal places a display policy may show
+pub const MAX_DISPLAY_PRECISION: u32 = 6;
+
+/// How a value is brought to the displayed precision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// To the nearest value, halves away from zero
+    #[default]
+    Round,
+    /// Down, so a stat is never shown higher than it is
+    Floor,
+    /// Up
+    Ceil,
+}
+
+/// Display policy of one dimension
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayPolicy {
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// Decimal places shown; of the percent figure for percentages
+    #[serde(default)]
+    pub precision: u32,
+    /// Show the value, a fraction, as a percentage
+    #[serde(default)]
+    pub percentage: bool,
+}
+
+impl DisplayPolicy {
+    /// Whole numbers, rounded to the nearest
+    pub fn integer() -> Self {
+        Self::default()
+    }
+
+    /// A percentage with `precision` decimals, e.g. 0.15347 shown as "15.35%" with 2
+    pub fn percentage(precision: u32) -> Self {
+        Self { rounding: Rounding::Round, precision, percentage: true }
+    }
+
+    /// Use another rounding
+    pub fn with_rounding(mut self, rounding: Rounding) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// Check the precision is within [`MAX_DISPLAY_PRECISION`]
+    pub fn validate(&self) -> ActorCoreResult<()> {
+        if self.precision > MAX_DISPLAY_PRECISION {
+            return Err(ActorCoreError::ConfigurationError(format!(
+                "Display precision {} is above the maximum of {}",
+                self.precision, MAX_DISPLAY_PRECISION
+            )));
+        }
+        Ok(())
+    }
+
+    /// Round a value to the displayed precision, keeping its unit: 0.15347 as a
+    /// percentage with 2 decimals becomes 0.1535
+    pub fn apply(&self, value: f64) -> f64 {
+        self.rounded(value) / self.scale()
+    }
+
+    /// Format a value for display, e.g. "1250" or "15.35%"
+    pub fn format(&self, value: f64) -> String {
+        let shown = self.rounded(value) / 10f64.powi(self.precision as i32);
+        format!("{:.*}{}", self.precision as usize, shown, if self.percentage { "%" } else { "" })
+    }
+
+    /// The value in units of the last shown decimal, rounded
+    fn rounded(&self, value: f64) -> f64 {
+        if !value.is_finite() {
+            return value;
+        }
+        let scaled = value * self.scale();
+        // Undo float error first, so 0.29 floors to 29% rather than 28%
+        let nearest = scaled.round();
+        let scaled = if (scaled - nearest).abs() < 1e-9 { nearest } else { scaled };
+        match self.rounding {
+            Rounding::Round => scaled.round(),
+            Rounding::Floor => scaled.floor(),
+            Rounding::Ceil => scaled.ceil(),
+        }
+    }
+
+    fn scale(&self) -> f64 {
+        let scale = 10f64.powi(self.precision as i32);
+        if self.percentage {
+            scale * 100.0
+        } else {
+            scale
+        }
+    }
+}
+
+/// Round a snapshot's stats by their dimensions' display policies for a client.
+///
+/// Stats without a policy keep full precision. Returns the formatted value of every stat
+/// that has one.
+pub fn display_snapshot(snapshot: &mut Snapshot, combiner: &dyn CombinerRegistry) -> HashMap<String, String> {
+    let mut formatted = HashMap::new();
+    for (dimension, value) in snapshot.primary.iter_mut().chain(snapshot.derived.iter_mut()) {
+        if let Some(policy) = combiner.get_display_policy(dimension) {
+            formatted.insert(dimension.clone(), policy.format(*value));
+            *value = policy.apply(*value);
+        }
+    }
+    formatted
+}
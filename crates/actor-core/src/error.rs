@@ -1,7 +1,7 @@
 //! Error types specific to the actor-core module.
 
 use thiserror::Error;
-use shared::ChaosError;
+use shared::{ChaosError, ErrorCode, ToApiError};
 
 /// Actor core specific errors.
 #[derive(Error, Debug)]
@@ -75,4 +75,36 @@ impl From<mongodb::error::Error> for ActorCoreError {
     fn from(err: mongodb::error::Error) -> Self {
         ActorCoreError::MongoDBError(err.to_string())
     }
-}
\ No newline at end of file
+}
+impl ToApiError for ActorCoreError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ActorCoreError::InvalidActor(_) => ErrorCode::InvalidActor,
+            ActorCoreError::InvalidContribution(_)
+            | ActorCoreError::InvalidCap(_)
+            | ActorCoreError::InvalidInput(_)
+            | ActorCoreError::ValidationError(_) => ErrorCode::ValidationFailed,
+            ActorCoreError::SubsystemError(_)
+            | ActorCoreError::CacheError(_)
+            | ActorCoreError::RegistryError(_)
+            | ActorCoreError::AggregationError(_)
+            | ActorCoreError::Io(_) => ErrorCode::Internal,
+            ActorCoreError::ConfigurationError(_) | ActorCoreError::YamlParsing(_) => ErrorCode::Configuration,
+            ActorCoreError::Serialization(_) => ErrorCode::InvalidFormat,
+            ActorCoreError::MongoDBError(_) => ErrorCode::Database,
+            ActorCoreError::Shared(err) => err.error_code(),
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            ActorCoreError::InvalidActor(message)
+            | ActorCoreError::InvalidContribution(message)
+            | ActorCoreError::InvalidCap(message)
+            | ActorCoreError::InvalidInput(message)
+            | ActorCoreError::ValidationError(message) => message.clone(),
+            ActorCoreError::Shared(err) => err.client_message(),
+            _ => self.error_code().default_message().to_string(),
+        }
+    }
+}
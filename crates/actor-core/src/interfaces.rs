@@ -6,7 +6,10 @@
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
 use tracing;
-use crate::types::{Actor, SubsystemOutput, Snapshot, Caps};
+use crate::aggregation_context::AggregationContext;
+use crate::display_policy::DisplayPolicy;
+use crate::registry::dimensions::DimensionDeclaration;
+use crate::types::{Actor, Contribution, SubsystemOutput, Snapshot, StatPrediction, Caps};
 use crate::ActorCoreResult;
 use crate::enums::{AcrossLayerPolicy, Operator};
 
@@ -23,8 +26,14 @@ pub trait Subsystem: Send + Sync {
     fn priority(&self) -> i64;
     
     /// Contribute to actor stats.
-    /// This method is called during stat aggregation to generate contributions.
-    async fn contribute(&self, actor: &Actor) -> ActorCoreResult<SubsystemOutput>;
+    /// This method is called during stat aggregation to generate contributions,
+    /// with the context the caller resolves the actor in.
+    async fn contribute(&self, actor: &Actor, context: &AggregationContext) -> ActorCoreResult<SubsystemOutput>;
+    
+    /// Get the dimensions this subsystem owns, checked for conflicts on registration.
+    fn dimensions(&self) -> Vec<DimensionDeclaration> {
+        Vec::new()
+    }
 }
 
 /// Optional trait for subsystems that can be configured.
@@ -100,13 +109,21 @@ pub trait Aggregator: Send + Sync {
     /// Resolve actor stats by aggregating contributions from all subsystems.
     async fn resolve(&self, actor: &Actor) -> ActorCoreResult<Snapshot>;
     
-    /// Resolve actor stats with additional context.
+    /// Resolve actor stats in a context, caching them apart from context-free snapshots.
     async fn resolve_with_context(
         &self, 
         actor: &Actor, 
-        context: Option<std::collections::HashMap<String, serde_json::Value>>
+        context: &AggregationContext
     ) -> ActorCoreResult<Snapshot>;
     
+    /// Predict the actor's stats with hypothetical contributions, e.g. from an item they
+    /// might equip, without reading or writing any cache.
+    async fn resolve_hypothetical(
+        &self,
+        actor: &Actor,
+        hypothetical: &[Contribution],
+    ) -> ActorCoreResult<StatPrediction>;
+    
     /// Resolve stats for multiple actors in batch.
     async fn resolve_batch(&self, actors: &[Actor]) -> ActorCoreResult<Vec<Snapshot>>;
     
@@ -116,6 +133,17 @@ pub trait Aggregator: Send + Sync {
     /// Invalidate cache for a specific actor.
     fn invalidate_cache(&self, actor_id: &String);
     
+    /// Mark dimensions of an actor as changed, e.g. after a buff on one stat expires.
+    ///
+    /// The next resolution only re-aggregates those dimensions and patches the cached
+    /// snapshot, asking only the subsystems that contributed to them, own them or
+    /// contributed nothing; a subsystem starting to contribute to a dimension it did not
+    /// before needs the actor invalidated instead. Aggregators that cannot patch
+    /// snapshots invalidate the actor instead.
+    fn mark_dirty(&self, actor_id: &str, _dimensions: &[String]) {
+        self.invalidate_cache(&actor_id.to_string());
+    }
+    
     /// Clear all caches.
     fn clear_cache(&self);
     
@@ -178,6 +206,19 @@ pub trait CombinerRegistry: Send + Sync {
     /// Set the merge rule for a dimension.
     fn set_rule(&self, dimension: &str, rule: MergeRule) -> ActorCoreResult<()>;
     
+    /// Get how a dimension is rounded and formatted for clients.
+    fn get_display_policy(&self, _dimension: &str) -> Option<DisplayPolicy> {
+        None
+    }
+    
+    /// Set how a dimension is rounded and formatted for clients.
+    fn set_display_policy(&self, dimension: &str, _policy: DisplayPolicy) -> ActorCoreResult<()> {
+        Err(crate::ActorCoreError::ConfigurationError(format!(
+            "Display policies are not supported by this registry: {}",
+            dimension
+        )))
+    }
+    
     /// Validate all rules.
     fn validate(&self) -> ActorCoreResult<()>;
 }
@@ -289,6 +330,13 @@ pub trait PluginRegistry: Send + Sync {
     /// Get all subsystems ordered by priority.
     fn get_by_priority(&self) -> Vec<std::sync::Arc<dyn Subsystem>>;
     
+    /// Get the subsystems enabled for an actor, ordered by priority.
+    ///
+    /// Registries without per-actor selection return every subsystem.
+    fn get_for_actor(&self, _actor: &Actor) -> Vec<std::sync::Arc<dyn Subsystem>> {
+        self.get_by_priority()
+    }
+    
     /// Get subsystems by priority range.
     fn get_by_priority_range(&self, min_priority: i64, max_priority: i64) -> Vec<std::sync::Arc<dyn Subsystem>>;
     
@@ -300,6 +348,33 @@ pub trait PluginRegistry: Send + Sync {
     
     /// Validate all registered subsystems.
     fn validate_all(&self) -> ActorCoreResult<()>;
+    
+    /// Mark a resolution as in flight until the returned guard is dropped.
+    ///
+    /// Aggregators take it before picking an actor's subsystems and hold it until the
+    /// snapshot is cached, so registries swapping subsystems at runtime can wait for the
+    /// resolutions still using the old ones. Other registries track nothing.
+    fn begin_resolution(&self) -> ResolutionGuard {
+        ResolutionGuard::default()
+    }
+}
+
+/// Keeps a resolution marked as in flight until dropped; see
+/// [`PluginRegistry::begin_resolution`].
+#[derive(Default)]
+pub struct ResolutionGuard(Option<Box<dyn std::any::Any + Send + Sync>>);
+
+impl ResolutionGuard {
+    /// Create a guard releasing the resolution when `release` is dropped.
+    pub fn new<T: Send + Sync + 'static>(release: T) -> Self {
+        Self(Some(Box::new(release)))
+    }
+}
+
+impl std::fmt::Debug for ResolutionGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ResolutionGuard").field(&self.0.is_some()).finish()
+    }
 }
 
 /// Cache provides caching functionality for the system.
@@ -179,6 +179,13 @@ pub mod interfaces;
 pub mod error;
 pub mod service_factory;
 pub mod validation;
+pub mod verification;
+pub mod snapshot_schema;
+pub mod snapshot_history;
+pub mod aggregation_context;
+pub mod derived_stats;
+pub mod display_policy;
+pub mod companion;
 
 // Inheritance support for extending actor-core
 pub mod inheritable;
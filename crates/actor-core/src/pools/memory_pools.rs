@@ -184,6 +184,8 @@ impl MemoryPool<crate::types::Actor> for ActorPool {
                 clean_actor.name.clear();
                 clean_actor.race.clear();
                 clean_actor.subsystems.clear();
+                clean_actor.disabled_subsystems.clear();
+                clean_actor.template = None;
                 clean_actor.data.clear();
                 clean_actor.version = 1;
                 
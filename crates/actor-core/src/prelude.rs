@@ -29,12 +29,18 @@ pub use crate::types::{
     CapContribution,
     SubsystemOutput,
     Snapshot,
+    StatPrediction,
     Caps,
     ModifierPack,
     EffectiveCaps,
     SubsystemMeta,
     Subsystem as SubsystemStruct,
 };
+pub use crate::aggregation_context::AggregationContext;
+pub use crate::derived_stats::{DerivedStatDefinition, DerivedStats, Formula};
+pub use crate::display_policy::{display_snapshot, DisplayPolicy, Rounding};
+pub use crate::snapshot_history::{SnapshotDelta, SnapshotHistory, SourceChange, StatChange};
+pub use crate::companion::{CompanionInheritance, CompanionInheritanceConfig, CompanionPipeline, CompanionSubsystem};
 
 // Enums - the behavioral definitions
 pub use crate::enums::{
@@ -56,6 +62,7 @@ pub use crate::interfaces::{
     CombinerRegistryAsync,
     CapLayerRegistryAsync,
     MergeRule,
+    ResolutionGuard,
 };
 
 // Registry implementations
@@ -71,6 +78,22 @@ pub use crate::registry::loader::{
     load_cap_layers,
     load_combiner,
     load_all,
+    parse_cap_layers,
+    parse_combiner,
+};
+pub use crate::registry::dimensions::{
+    DimensionDeclaration,
+    DimensionKind,
+    DimensionRegistry,
+    UnknownDimensionPolicy,
+};
+#[cfg(feature = "hot-reload")]
+pub use crate::registry::hot_reload::{HotReloadConfig, HotReloadRegistry, PluginChange};
+pub use crate::registry::subsystem_sets::{
+    load_subsystem_sets,
+    parse_subsystem_sets,
+    SubsystemSet,
+    SubsystemSets,
 };
 
 // Service implementations
@@ -108,6 +131,22 @@ pub use crate::validation::middleware::{
     ValidationError,
 };
 
+// Snapshot schema versions
+pub use crate::snapshot_schema::{
+    decode_snapshot,
+    SnapshotSchemaError,
+    SNAPSHOT_SCHEMA_VERSION,
+};
+
+// Snapshot verification
+pub use crate::verification::{
+    SnapshotVerifier,
+    VerificationConfig,
+    VerificationReport,
+    SnapshotMismatch,
+    MismatchSource,
+};
+
 // Deprecation management
 pub use crate::deprecation::{
     DeprecationManager,
@@ -238,6 +277,8 @@ pub const AVAILABLE_FEATURES: &[&str] = &[
     "cli-tools",
     #[cfg(feature = "heavy-deps")]
     "heavy-deps",
+    #[cfg(feature = "hot-reload")]
+    "hot-reload",
 ];
 
 /// Quick setup function for common use cases.
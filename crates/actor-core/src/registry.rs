@@ -3,9 +3,13 @@
 //! This module contains the concrete implementations of the registry traits
 //! including plugin registry, combiner registry, and cap layer registry.
 
+pub mod dimensions;
+#[cfg(feature = "hot-reload")]
+pub mod hot_reload;
 pub mod loader;
 pub mod optimized;
 pub mod runtime_registries;
+pub mod subsystem_sets;
 // Legacy subsystem_registration moved to examples/legacy_subsystems/
 
 use async_trait::async_trait;
@@ -15,7 +19,11 @@ use parking_lot::RwLock;
 use tracing::{info, warn};
 
 use crate::interfaces::{PluginRegistry, CombinerRegistry, CapLayerRegistry, CombinerRegistryAsync, CapLayerRegistryAsync, Subsystem as SubsystemTrait, MergeRule};
+use crate::display_policy::DisplayPolicy;
 use crate::enums::AcrossLayerPolicy;
+use crate::types::Actor;
+use dimensions::DimensionRegistry;
+use subsystem_sets::SubsystemSets;
 // use crate::types::*; // Unused import
 use crate::ActorCoreResult;
 
@@ -25,6 +33,10 @@ use crate::ActorCoreResult;
 pub struct PluginRegistryImpl {
     /// Map of system ID to subsystem
     subsystems: Arc<RwLock<HashMap<String, Arc<dyn SubsystemTrait>>>>,
+    /// Default subsystem sets per actor template and race
+    subsystem_sets: Arc<RwLock<SubsystemSets>>,
+    /// Dimensions owned by the registered subsystems
+    dimensions: Option<Arc<DimensionRegistry>>,
     /// Metrics for performance monitoring
     #[allow(dead_code)]
     metrics: Arc<RwLock<RegistryMetrics>>,
@@ -33,12 +45,31 @@ pub struct PluginRegistryImpl {
 impl PluginRegistryImpl {
     /// Create a new plugin registry instance.
     pub fn new() -> Self {
+        Self::with_subsystem_sets(SubsystemSets::default())
+    }
+
+    /// Create a plugin registry selecting subsystems per actor from `subsystem_sets`.
+    pub fn with_subsystem_sets(subsystem_sets: SubsystemSets) -> Self {
         Self {
             subsystems: Arc::new(RwLock::new(HashMap::new())),
+            subsystem_sets: Arc::new(RwLock::new(subsystem_sets)),
+            dimensions: None,
             metrics: Arc::new(RwLock::new(RegistryMetrics::default())),
         }
     }
 
+    /// Record the dimensions each subsystem declares in `dimensions`, rejecting
+    /// subsystems declaring a dimension another one owns.
+    pub fn with_dimension_registry(mut self, dimensions: Arc<DimensionRegistry>) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    /// Replace the subsystem sets, e.g. after the configuration is reloaded.
+    pub fn set_subsystem_sets(&self, subsystem_sets: SubsystemSets) {
+        *self.subsystem_sets.write() = subsystem_sets;
+    }
+
     /// Get all subsystems sorted by priority.
     fn get_subsystems_by_priority(&self) -> Vec<Arc<dyn SubsystemTrait>> {
         let subsystems = self.subsystems.read();
@@ -69,6 +100,10 @@ impl PluginRegistry for PluginRegistryImpl {
 
         let mut subsystems = self.subsystems.write();
         
+        if let Some(dimensions) = &self.dimensions {
+            dimensions.declare(&system_id, subsystem.dimensions())?;
+        }
+        
         if subsystems.contains_key(&system_id) {
             warn!("Overwriting existing subsystem: {}", system_id);
         }
@@ -83,6 +118,9 @@ impl PluginRegistry for PluginRegistryImpl {
         let mut subsystems = self.subsystems.write();
         
         if subsystems.remove(system_id).is_some() {
+            if let Some(dimensions) = &self.dimensions {
+                dimensions.release(system_id);
+            }
             info!("Unregistered subsystem: {}", system_id);
             Ok(())
         } else {
@@ -101,6 +139,11 @@ impl PluginRegistry for PluginRegistryImpl {
         self.get_subsystems_by_priority()
     }
 
+    fn get_for_actor(&self, actor: &Actor) -> Vec<Arc<dyn SubsystemTrait>> {
+        let subsystems = self.get_subsystems_by_priority();
+        self.subsystem_sets.read().filter(actor, subsystems)
+    }
+
     fn get_by_priority_range(&self, min_priority: i64, max_priority: i64) -> Vec<Arc<dyn SubsystemTrait>> {
         let subsystems = self.subsystems.read();
         let mut subsystem_list: Vec<Arc<dyn SubsystemTrait>> = Vec::new();
@@ -180,6 +223,8 @@ impl Default for RegistryMetrics {
 pub struct CombinerRegistryImpl {
     /// Map of dimension to merge rule
     rules: Arc<RwLock<HashMap<String, MergeRule>>>,
+    /// Map of dimension to display policy
+    display_policies: Arc<RwLock<HashMap<String, DisplayPolicy>>>,
     /// Metrics for performance monitoring
     #[allow(dead_code)]
     metrics: Arc<RwLock<CombinerMetrics>>,
@@ -190,6 +235,7 @@ impl CombinerRegistryImpl {
     pub fn new() -> Self {
         Self {
             rules: Arc::new(RwLock::new(HashMap::new())),
+            display_policies: Arc::new(RwLock::new(HashMap::new())),
             metrics: Arc::new(RwLock::new(CombinerMetrics::default())),
         }
     }
@@ -237,6 +283,21 @@ impl CombinerRegistry for CombinerRegistryImpl {
         Ok(())
     }
 
+    fn get_display_policy(&self, dimension: &str) -> Option<DisplayPolicy> {
+        self.display_policies.read().get(dimension).copied()
+    }
+
+    fn set_display_policy(&self, dimension: &str, policy: DisplayPolicy) -> ActorCoreResult<()> {
+        if dimension.is_empty() {
+            return Err(crate::ActorCoreError::ConfigurationError(
+                "Dimension cannot be empty".to_string()
+            ));
+        }
+        policy.validate()?;
+        self.display_policies.write().insert(dimension.to_string(), policy);
+        Ok(())
+    }
+
     fn validate(&self) -> ActorCoreResult<()> {
         let rules = self.rules.read();
         
@@ -0,0 +1,256 @@
+//! Dimension ownership, so subsystems cannot silently share a dimension name.
+//!
+//! Subsystems declare the dimensions they own through
+//! [`Subsystem::dimensions`](crate::interfaces::Subsystem::dimensions), with their kind, unit
+//! and valid range. A [`DimensionRegistry`] attached to the plugin registry rejects a
+//! subsystem declaring a dimension another one owns. Attached to the aggregator, it reports
+//! contributions to dimensions nobody declared as the [`UnknownDimensionPolicy`] says, and
+//! clamps each declared dimension to its range.
+//!
+//! Contributing to a dimension another subsystem owns is allowed: items and buffs add to
+//! the `strength` the stats subsystem owns.
+
+use std::collections::{HashMap, HashSet};
+
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::types::SubsystemOutput;
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// What a dimension measures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DimensionKind {
+    /// A plain stat, e.g. strength
+    #[default]
+    Stat,
+    /// A pool that is spent and refilled, e.g. health
+    Resource,
+    /// An amount per second, e.g. health regeneration
+    Rate,
+    /// A fraction, e.g. critical chance
+    Ratio,
+}
+
+/// A dimension a subsystem owns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DimensionDeclaration {
+    pub id: String,
+    #[serde(default)]
+    pub kind: DimensionKind,
+    /// Unit shown with the value, e.g. "hp"
+    #[serde(default)]
+    pub unit: Option<String>,
+    /// Lowest valid value
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Highest valid value
+    #[serde(default)]
+    pub max: Option<f64>,
+}
+
+impl DimensionDeclaration {
+    /// Declare a dimension without a unit or range
+    pub fn new(id: &str, kind: DimensionKind) -> Self {
+        Self { id: id.to_string(), kind, unit: None, min: None, max: None }
+    }
+
+    /// Set the unit
+    pub fn with_unit(mut self, unit: &str) -> Self {
+        self.unit = Some(unit.to_string());
+        self
+    }
+
+    /// Set the valid range
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Clamp a value to the declared range
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+
+    fn validate(&self, owner: &str) -> ActorCoreResult<()> {
+        if self.id.is_empty() {
+            return Err(ActorCoreError::RegistryError(format!("{} declares a dimension without an ID", owner)));
+        }
+        let invalid = |bound: Option<f64>| bound.is_some_and(f64::is_nan);
+        let inverted = matches!((self.min, self.max), (Some(min), Some(max)) if min > max);
+        if invalid(self.min) || invalid(self.max) || inverted {
+            return Err(ActorCoreError::RegistryError(format!(
+                "{} declares {} with an invalid range {:?}..{:?}",
+                owner, self.id, self.min, self.max
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What happens to contributions to dimensions no subsystem declared
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownDimensionPolicy {
+    /// Accept them silently
+    Allow,
+    /// Accept them, warning once per subsystem and dimension
+    #[default]
+    Warn,
+    /// Fail the resolution
+    Reject,
+}
+
+/// A declared dimension and the subsystem owning it
+#[derive(Debug, Clone)]
+struct Owned {
+    owner: String,
+    declaration: DimensionDeclaration,
+}
+
+/// Registry of the dimensions each subsystem owns.
+#[derive(Debug, Default)]
+pub struct DimensionRegistry {
+    dimensions: RwLock<HashMap<String, Owned>>,
+    unknown_policy: RwLock<UnknownDimensionPolicy>,
+    /// Unknown dimensions already warned about, per subsystem
+    warned: Mutex<HashSet<(String, String)>>,
+}
+
+impl DimensionRegistry {
+    /// Create a registry handling unknown dimensions by `unknown_policy`.
+    pub fn new(unknown_policy: UnknownDimensionPolicy) -> Self {
+        Self { unknown_policy: RwLock::new(unknown_policy), ..Default::default() }
+    }
+
+    /// Get how unknown dimensions are handled.
+    pub fn unknown_policy(&self) -> UnknownDimensionPolicy {
+        *self.unknown_policy.read()
+    }
+
+    /// Change how unknown dimensions are handled, e.g. after the configuration is reloaded.
+    pub fn set_unknown_policy(&self, unknown_policy: UnknownDimensionPolicy) {
+        *self.unknown_policy.write() = unknown_policy;
+    }
+
+    /// Declare the dimensions a subsystem owns, replacing the ones it declared before.
+    ///
+    /// Fails without declaring any if one is owned by another subsystem or declared twice.
+    pub fn declare(&self, owner: &str, declarations: Vec<DimensionDeclaration>) -> ActorCoreResult<()> {
+        let mut dimensions = self.dimensions.write();
+        let mut declared = HashSet::new();
+        for declaration in &declarations {
+            declaration.validate(owner)?;
+            if !declared.insert(declaration.id.as_str()) {
+                return Err(ActorCoreError::RegistryError(format!(
+                    "{} declares dimension {} twice",
+                    owner, declaration.id
+                )));
+            }
+            if let Some(owned) = dimensions.get(&declaration.id).filter(|owned| owned.owner != owner) {
+                return Err(ActorCoreError::RegistryError(format!(
+                    "Dimension {} of {} is already owned by {}",
+                    declaration.id, owner, owned.owner
+                )));
+            }
+        }
+
+        let count = declarations.len();
+        dimensions.retain(|_, owned| owned.owner != owner);
+        for declaration in declarations {
+            let owned = Owned { owner: owner.to_string(), declaration };
+            dimensions.insert(owned.declaration.id.clone(), owned);
+        }
+        info!("{} owns {} dimensions", owner, count);
+        Ok(())
+    }
+
+    /// Release the dimensions a subsystem owns, returning how many it had.
+    pub fn release(&self, owner: &str) -> usize {
+        let mut dimensions = self.dimensions.write();
+        let before = dimensions.len();
+        dimensions.retain(|_, owned| owned.owner != owner);
+        before - dimensions.len()
+    }
+
+    /// Get a declared dimension.
+    pub fn get(&self, dimension: &str) -> Option<DimensionDeclaration> {
+        self.dimensions.read().get(dimension).map(|owned| owned.declaration.clone())
+    }
+
+    /// Get the subsystem owning a dimension.
+    pub fn owner(&self, dimension: &str) -> Option<String> {
+        self.dimensions.read().get(dimension).map(|owned| owned.owner.clone())
+    }
+
+    /// Get the dimensions a subsystem owns, sorted by ID.
+    pub fn owned_by(&self, owner: &str) -> Vec<DimensionDeclaration> {
+        let mut owned: Vec<DimensionDeclaration> = self
+            .dimensions
+            .read()
+            .values()
+            .filter(|owned| owned.owner == owner)
+            .map(|owned| owned.declaration.clone())
+            .collect();
+        owned.sort_by(|a, b| a.id.cmp(&b.id));
+        owned
+    }
+
+    /// Get the number of declared dimensions.
+    pub fn len(&self) -> usize {
+        self.dimensions.read().len()
+    }
+
+    /// Whether no dimension is declared.
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.read().is_empty()
+    }
+
+    /// Clamp a value to its dimension's declared range, if any.
+    pub fn clamp(&self, dimension: &str, value: f64) -> f64 {
+        match self.dimensions.read().get(dimension) {
+            Some(owned) => owned.declaration.clamp(value),
+            None => value,
+        }
+    }
+
+    /// Check a subsystem's contributions go to declared dimensions.
+    pub fn check_output(&self, output: &SubsystemOutput) -> ActorCoreResult<()> {
+        let policy = self.unknown_policy();
+        if policy == UnknownDimensionPolicy::Allow {
+            return Ok(());
+        }
+        let unknown: Vec<&str> = {
+            let dimensions = self.dimensions.read();
+            output
+                .primary
+                .iter()
+                .chain(&output.derived)
+                .map(|contribution| contribution.stat_name.as_str())
+                .filter(|dimension| !dimensions.contains_key(*dimension))
+                .collect()
+        };
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        if policy == UnknownDimensionPolicy::Reject {
+            return Err(ActorCoreError::InvalidContribution(format!(
+                "{} contributes to undeclared dimensions: {}",
+                output.system_id,
+                unknown.join(", ")
+            )));
+        }
+
+        let mut warned = self.warned.lock();
+        for dimension in unknown {
+            if warned.insert((output.system_id.clone(), dimension.to_string())) {
+                warn!("{} contributes to undeclared dimension {}", output.system_id, dimension);
+            }
+        }
+        Ok(())
+    }
+}
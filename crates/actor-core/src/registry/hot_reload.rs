@@ -0,0 +1,300 @@
+//! Loading and unloading subsystems while the server runs.
+//!
+//! [`HotReloadRegistry`] is a [`PluginRegistry`] whose subsystems can be swapped at
+//! runtime, so new game systems deploy without a restart. Each load carries a version
+//! that must be newer than the one it replaces. A swap applies to resolutions starting
+//! after it; the registry then waits, up to the drain timeout, for the resolutions that
+//! started before it, and invalidates the cached snapshots of the actors that resolved
+//! with the changed subsystem. Only built with the `hot-reload` feature.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use super::subsystem_sets::SubsystemSets;
+use crate::interfaces::{Aggregator, PluginRegistry, ResolutionGuard, Subsystem};
+use crate::types::Actor;
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Hot reload configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct HotReloadConfig {
+    /// Longest wait for in-flight resolutions after a swap
+    pub drain_timeout: Duration,
+}
+
+impl Default for HotReloadConfig {
+    fn default() -> Self {
+        Self { drain_timeout: Duration::from_secs(5) }
+    }
+}
+
+/// Outcome of loading or unloading a subsystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginChange {
+    pub system_id: String,
+    /// Version now loaded, `None` once unloaded
+    pub version: Option<u64>,
+    /// Version it replaced
+    pub previous: Option<u64>,
+    /// Whether the resolutions in flight at the swap finished within the drain timeout
+    pub drained: bool,
+    /// Actors whose cached snapshots were invalidated
+    pub invalidated: usize,
+}
+
+/// A loaded subsystem and its version
+struct Loaded {
+    subsystem: Arc<dyn Subsystem>,
+    version: u64,
+}
+
+/// Resolutions started between two swaps
+#[derive(Default)]
+struct InFlight {
+    count: AtomicUsize,
+    finished: Notify,
+}
+
+impl InFlight {
+    /// Wait until no resolution is in flight, returning false on timeout
+    async fn drain(&self, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            // Created before checking, so a release in between still wakes it
+            let finished = self.finished.notified();
+            if self.count.load(Ordering::Acquire) == 0 {
+                return true;
+            }
+            if tokio::time::timeout_at(deadline, finished).await.is_err() {
+                return self.count.load(Ordering::Acquire) == 0;
+            }
+        }
+    }
+}
+
+/// Releases a resolution when dropped
+struct Release(Arc<InFlight>);
+
+impl Drop for Release {
+    fn drop(&mut self) {
+        if self.0.count.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.finished.notify_waiters();
+        }
+    }
+}
+
+/// Plugin registry loading and unloading versioned subsystems at runtime.
+pub struct HotReloadRegistry {
+    subsystems: RwLock<HashMap<String, Loaded>>,
+    subsystem_sets: RwLock<SubsystemSets>,
+    /// Resolutions since the last swap
+    in_flight: RwLock<Arc<InFlight>>,
+    /// Actors resolved with each subsystem, to invalidate when it changes
+    resolved_with: Mutex<HashMap<String, HashSet<String>>>,
+    config: HotReloadConfig,
+}
+
+impl HotReloadRegistry {
+    /// Create a registry enabling every subsystem for every actor.
+    pub fn new(config: HotReloadConfig) -> Self {
+        Self::with_subsystem_sets(SubsystemSets::default(), config)
+    }
+
+    /// Create a registry selecting subsystems per actor from `subsystem_sets`.
+    pub fn with_subsystem_sets(subsystem_sets: SubsystemSets, config: HotReloadConfig) -> Self {
+        Self {
+            subsystems: RwLock::new(HashMap::new()),
+            subsystem_sets: RwLock::new(subsystem_sets),
+            in_flight: RwLock::new(Arc::new(InFlight::default())),
+            resolved_with: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    /// Replace the subsystem sets, e.g. after the configuration is reloaded.
+    pub fn set_subsystem_sets(&self, subsystem_sets: SubsystemSets) {
+        *self.subsystem_sets.write() = subsystem_sets;
+    }
+
+    /// Get the loaded version of a subsystem.
+    pub fn version(&self, system_id: &str) -> Option<u64> {
+        self.subsystems.read().get(system_id).map(|loaded| loaded.version)
+    }
+
+    /// Get the loaded version of every subsystem.
+    pub fn versions(&self) -> HashMap<String, u64> {
+        self.subsystems.read().iter().map(|(id, loaded)| (id.clone(), loaded.version)).collect()
+    }
+
+    /// Get how many resolutions started since the last swap are in flight.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.read().count.load(Ordering::Acquire)
+    }
+
+    /// Load a subsystem, or replace the loaded one with a newer version.
+    ///
+    /// Returns once the resolutions using the replaced version have drained and the
+    /// affected snapshots are invalidated in `aggregator`. A subsystem new to the registry
+    /// invalidates every actor resolved so far, since any of them may enable it.
+    pub async fn load(
+        &self,
+        subsystem: Arc<dyn Subsystem>,
+        version: u64,
+        aggregator: &dyn Aggregator,
+    ) -> ActorCoreResult<PluginChange> {
+        let system_id = subsystem.system_id().to_string();
+        if system_id.is_empty() {
+            return Err(ActorCoreError::ConfigurationError("System ID cannot be empty".to_string()));
+        }
+        let previous = {
+            let mut subsystems = self.subsystems.write();
+            let previous = subsystems.get(&system_id).map(|loaded| loaded.version);
+            if let Some(previous) = previous.filter(|&previous| previous >= version) {
+                return Err(ActorCoreError::RegistryError(format!(
+                    "Subsystem {} version {} is not newer than the loaded version {}",
+                    system_id, version, previous
+                )));
+            }
+            subsystems.insert(system_id.clone(), Loaded { subsystem, version });
+            previous
+        };
+
+        let changed = [system_id.clone()];
+        let (drained, invalidated) = self.settle(previous.map(|_| &changed[..]), aggregator).await;
+        info!("Loaded subsystem {} version {} (was {:?})", system_id, version, previous);
+        Ok(PluginChange { system_id, version: Some(version), previous, drained, invalidated })
+    }
+
+    /// Unload a subsystem, waiting for the resolutions using it to drain.
+    pub async fn unload(&self, system_id: &str, aggregator: &dyn Aggregator) -> ActorCoreResult<PluginChange> {
+        let previous = self
+            .subsystems
+            .write()
+            .remove(system_id)
+            .map(|loaded| loaded.version)
+            .ok_or_else(|| ActorCoreError::RegistryError(format!("Subsystem not found: {}", system_id)))?;
+
+        let (drained, invalidated) = self.settle(Some(&[system_id.to_string()]), aggregator).await;
+        info!("Unloaded subsystem {} version {}", system_id, previous);
+        Ok(PluginChange {
+            system_id: system_id.to_string(),
+            version: None,
+            previous: Some(previous),
+            drained,
+            invalidated,
+        })
+    }
+
+    /// Drain the resolutions started before a swap, then invalidate the actors that
+    /// resolved with the changed subsystems, or every actor when `None`
+    async fn settle(&self, system_ids: Option<&[String]>, aggregator: &dyn Aggregator) -> (bool, usize) {
+        let before = std::mem::take(&mut *self.in_flight.write());
+        let drained = before.drain(self.config.drain_timeout).await;
+        if !drained {
+            warn!(
+                "{} resolutions still in flight after {:?}, invalidating {:?} anyway",
+                before.count.load(Ordering::Acquire),
+                self.config.drain_timeout,
+                system_ids
+            );
+        }
+
+        let actors: HashSet<String> = {
+            let mut resolved_with = self.resolved_with.lock();
+            match system_ids {
+                Some(system_ids) => system_ids.iter().filter_map(|id| resolved_with.remove(id)).flatten().collect(),
+                None => resolved_with.values().flatten().cloned().collect(),
+            }
+        };
+        for actor_id in &actors {
+            aggregator.invalidate_cache(actor_id);
+        }
+        (drained, actors.len())
+    }
+
+    fn by_priority(&self) -> Vec<Arc<dyn Subsystem>> {
+        let mut subsystems: Vec<Arc<dyn Subsystem>> =
+            self.subsystems.read().values().map(|loaded| loaded.subsystem.clone()).collect();
+        subsystems.sort_by(|a, b| b.priority().cmp(&a.priority()));
+        subsystems
+    }
+}
+
+impl PluginRegistry for HotReloadRegistry {
+    /// Register a subsystem at version 0, replacing any loaded one without draining.
+    fn register(&self, subsystem: Arc<dyn Subsystem>) -> ActorCoreResult<()> {
+        let system_id = subsystem.system_id().to_string();
+        if system_id.is_empty() {
+            return Err(ActorCoreError::ConfigurationError("System ID cannot be empty".to_string()));
+        }
+        if self.subsystems.write().insert(system_id.clone(), Loaded { subsystem, version: 0 }).is_some() {
+            warn!("Overwriting existing subsystem: {}", system_id);
+        }
+        Ok(())
+    }
+
+    fn unregister(&self, system_id: &str) -> ActorCoreResult<()> {
+        match self.subsystems.write().remove(system_id) {
+            Some(_) => Ok(()),
+            None => Err(ActorCoreError::RegistryError(format!("Subsystem not found: {}", system_id))),
+        }
+    }
+
+    fn get_by_id(&self, system_id: &str) -> Option<Arc<dyn Subsystem>> {
+        self.subsystems.read().get(system_id).map(|loaded| loaded.subsystem.clone())
+    }
+
+    fn get_by_priority(&self) -> Vec<Arc<dyn Subsystem>> {
+        self.by_priority()
+    }
+
+    fn get_for_actor(&self, actor: &Actor) -> Vec<Arc<dyn Subsystem>> {
+        let subsystems = self.subsystem_sets.read().filter(actor, self.by_priority());
+        let mut resolved_with = self.resolved_with.lock();
+        for subsystem in &subsystems {
+            resolved_with.entry(subsystem.system_id().to_string()).or_default().insert(actor.id.clone());
+        }
+        subsystems
+    }
+
+    fn get_by_priority_range(&self, min_priority: i64, max_priority: i64) -> Vec<Arc<dyn Subsystem>> {
+        self.by_priority()
+            .into_iter()
+            .filter(|subsystem| (min_priority..=max_priority).contains(&subsystem.priority()))
+            .collect()
+    }
+
+    fn is_registered(&self, system_id: &str) -> bool {
+        self.subsystems.read().contains_key(system_id)
+    }
+
+    fn count(&self) -> usize {
+        self.subsystems.read().len()
+    }
+
+    fn validate_all(&self) -> ActorCoreResult<()> {
+        for (system_id, loaded) in self.subsystems.read().iter() {
+            if loaded.subsystem.priority() < 0 {
+                return Err(ActorCoreError::ConfigurationError(format!(
+                    "Invalid priority for subsystem {}: {}",
+                    system_id,
+                    loaded.subsystem.priority()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn begin_resolution(&self) -> ResolutionGuard {
+        // Counted under the read lock, so a swap sees every resolution it must wait for
+        let in_flight = self.in_flight.read();
+        in_flight.count.fetch_add(1, Ordering::AcqRel);
+        ResolutionGuard::new(Release(in_flight.clone()))
+    }
+}
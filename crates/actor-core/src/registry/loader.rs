@@ -10,6 +10,7 @@ use thiserror::Error;
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
+use crate::display_policy::DisplayPolicy;
 use crate::interfaces::{CapLayerRegistry, CombinerRegistry, MergeRule};
 use crate::enums::{AcrossLayerPolicy, CapMode};
 use crate::types::Caps;
@@ -63,6 +64,9 @@ pub struct CombinerRuleConfig {
     pub id: String,
     pub bucket_order: Vec<String>,
     pub clamp: ClampConfig,
+    /// How the dimension is rounded and formatted for clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<DisplayPolicy>,
 }
 
 /// Clamp configuration.
@@ -95,12 +99,17 @@ pub fn load_cap_layers<P: AsRef<Path>>(path: P) -> Result<CapLayerRegistryImpl,
             error: e.to_string(),
         })?;
     
+    parse_cap_layers(&content)
+}
+
+/// Parse and validate cap layers configuration from YAML or JSON text.
+pub fn parse_cap_layers(content: &str) -> Result<CapLayerRegistryImpl, LoaderError> {
     // Try YAML first
-    let config = match serde_yaml::from_str::<CapLayersConfig>(&content) {
+    let config = match serde_yaml::from_str::<CapLayersConfig>(content) {
         Ok(config) => config,
         Err(yaml_err) => {
             // Fall back to JSON
-            serde_json::from_str::<CapLayersConfig>(&content)
+            serde_json::from_str::<CapLayersConfig>(content)
                 .map_err(|e| LoaderError::InvalidJson {
                     error: format!("YAML failed: {}, JSON failed: {}", yaml_err, e),
                 })?
@@ -128,12 +137,17 @@ pub fn load_combiner<P: AsRef<Path>>(path: P) -> Result<CombinerRegistryImpl, Lo
             error: e.to_string(),
         })?;
     
+    parse_combiner(&content)
+}
+
+/// Parse and validate combiner configuration from YAML or JSON text.
+pub fn parse_combiner(content: &str) -> Result<CombinerRegistryImpl, LoaderError> {
     // Try YAML first
-    let config = match serde_yaml::from_str::<CombinerConfig>(&content) {
+    let config = match serde_yaml::from_str::<CombinerConfig>(content) {
         Ok(config) => config,
         Err(yaml_err) => {
             // Fall back to JSON
-            serde_json::from_str::<CombinerConfig>(&content)
+            serde_json::from_str::<CombinerConfig>(content)
                 .map_err(|e| LoaderError::InvalidJson {
                     error: format!("YAML failed: {}, JSON failed: {}", yaml_err, e),
                 })?
@@ -221,13 +235,18 @@ fn validate_cap_layers_config(config: &CapLayersConfig) -> Result<(), LoaderErro
                 });
             }
             
-            // Validate min/max values
-            if let (Some(min), Some(max)) = (cap.min, cap.max) {
-                if min > max {
-                    return Err(LoaderError::ValidationError {
-                        message: format!("Invalid cap range for '{}': min ({}) > max ({})", cap.id, min, max),
-                    });
-                }
+            // Validate min/max values; NaN bounds would never clamp anything
+            if cap.min.is_some_and(f64::is_nan) || cap.max.is_some_and(f64::is_nan) {
+                return Err(LoaderError::ValidationError {
+                    message: format!("Cap '{}' has a NaN bound", cap.id),
+                });
+            }
+            // A missing bound takes its default, which can cross the one given
+            let (min, max) = (cap.min.unwrap_or(DEFAULT_CAP_MIN), cap.max.unwrap_or(DEFAULT_CAP_MAX));
+            if min > max {
+                return Err(LoaderError::ValidationError {
+                    message: format!("Invalid cap range for '{}': min ({}) > max ({})", cap.id, min, max),
+                });
             }
         }
     }
@@ -269,12 +288,23 @@ fn validate_combiner_config(config: &CombinerConfig) -> Result<(), LoaderError>
         }
         
         // Validate clamp values
+        if rule.clamp.min.is_nan() || rule.clamp.max.is_nan() {
+            return Err(LoaderError::ValidationError {
+                message: format!("Clamp of rule '{}' has a NaN bound", rule.id),
+            });
+        }
         if rule.clamp.min > rule.clamp.max {
             return Err(LoaderError::ValidationError {
                 message: format!("Invalid clamp range for rule '{}': min ({}) > max ({})", 
                     rule.id, rule.clamp.min, rule.clamp.max),
             });
         }
+
+        if let Some(display) = &rule.display {
+            display.validate().map_err(|e| LoaderError::ValidationError {
+                message: format!("Invalid display policy for rule '{}': {}", rule.id, e),
+            })?;
+        }
     }
     
     Ok(())
@@ -296,6 +326,12 @@ fn is_valid_bucket_type(bucket: &str) -> bool {
     core_buckets || extra_buckets
 }
 
+/// Minimum of a cap that does not set one
+const DEFAULT_CAP_MIN: f64 = 0.0;
+
+/// Maximum of a cap that does not set one
+const DEFAULT_CAP_MAX: f64 = 1000.0;
+
 /// Convert cap layers configuration to registry implementation.
 fn convert_cap_layers_config(config: CapLayersConfig) -> Result<CapLayerRegistryImpl, LoaderError> {
     let mut layers = HashMap::new();
@@ -315,15 +351,10 @@ fn convert_cap_layers_config(config: CapLayersConfig) -> Result<CapLayerRegistry
                 }),
             };
             
-            let _config = RegistryLoaderConfig::load_config().unwrap_or_else(|_| {
-                warn!("Failed to load registry loader config, using hardcoded defaults");
-                RegistryLoaderConfig::get_default_config()
-            });
-            
             let caps_obj = Caps::with_values(
                 cap_config.id.clone(),
-                cap_config.min.unwrap_or(0.0), // TODO: Load from config  
-                cap_config.max.unwrap_or(1000.0), // TODO: Load from config
+                cap_config.min.unwrap_or(DEFAULT_CAP_MIN), // TODO: Load from config
+                cap_config.max.unwrap_or(DEFAULT_CAP_MAX), // TODO: Load from config
                 crate::enums::AcrossLayerPolicy::Intersect
             );
             
@@ -339,8 +370,13 @@ fn convert_cap_layers_config(config: CapLayersConfig) -> Result<CapLayerRegistry
 /// Convert combiner configuration to registry implementation.
 fn convert_combiner_config(config: CombinerConfig) -> Result<CombinerRegistryImpl, LoaderError> {
     let mut rules = HashMap::new();
+    let mut display_policies = HashMap::new();
     
     for rule_config in config.rules {
+        if let Some(display) = rule_config.display {
+            display_policies.insert(rule_config.id.clone(), display);
+        }
+
         let bucket_order: Result<Vec<crate::enums::Bucket>, LoaderError> = rule_config.bucket_order
             .into_iter()
             .map(|bucket| match bucket.as_str() {
@@ -362,17 +398,12 @@ fn convert_combiner_config(config: CombinerConfig) -> Result<CombinerRegistryImp
         
         let bucket_order = bucket_order?;
         
-        let _config = RegistryLoaderConfig::load_config().unwrap_or_else(|_| {
-            warn!("Failed to load registry loader config, using hardcoded defaults");
-            RegistryLoaderConfig::get_default_config()
-        });
-        
         let clamp = Caps::with_values("clamp".to_string(), rule_config.clamp.min, rule_config.clamp.max, crate::enums::AcrossLayerPolicy::Intersect);
         
         rules.insert(rule_config.id, (bucket_order, clamp));
     }
     
-    Ok(CombinerRegistryImpl::new(rules))
+    Ok(CombinerRegistryImpl::new(rules).with_display_policies(display_policies))
 }
 
 /// Simple implementation of CapLayerRegistry for loaded configurations.
@@ -385,6 +416,13 @@ impl CapLayerRegistryImpl {
     pub fn new(layers: HashMap<String, (i64, HashMap<String, (CapMode, Caps)>)>) -> Self {
         Self { layers }
     }
+
+    /// Every cap as (layer, cap id, caps)
+    pub fn caps(&self) -> impl Iterator<Item = (&str, &str, &Caps)> {
+        self.layers.iter().flat_map(|(layer, (_, caps))| {
+            caps.iter().map(move |(id, (_, caps))| (layer.as_str(), id.as_str(), caps))
+        })
+    }
 }
 
 impl CapLayerRegistry for CapLayerRegistryImpl {
@@ -428,11 +466,23 @@ impl CapLayerRegistry for CapLayerRegistryImpl {
 #[derive(Debug, Clone)]
 pub struct CombinerRegistryImpl {
     rules: HashMap<String, (Vec<crate::enums::Bucket>, Caps)>,
+    display_policies: HashMap<String, DisplayPolicy>,
 }
 
 impl CombinerRegistryImpl {
     pub fn new(rules: HashMap<String, (Vec<crate::enums::Bucket>, Caps)>) -> Self {
-        Self { rules }
+        Self { rules, display_policies: HashMap::new() }
+    }
+
+    /// Set the display policies per dimension
+    pub fn with_display_policies(mut self, display_policies: HashMap<String, DisplayPolicy>) -> Self {
+        self.display_policies = display_policies;
+        self
+    }
+
+    /// Every rule's clamp as (rule id, clamp)
+    pub fn clamps(&self) -> impl Iterator<Item = (&str, &Caps)> {
+        self.rules.iter().map(|(id, (_, clamp))| (id.as_str(), clamp))
     }
 }
 
@@ -465,6 +515,17 @@ impl CombinerRegistry for CombinerRegistryImpl {
         ))
     }
     
+    fn get_display_policy(&self, dimension: &str) -> Option<DisplayPolicy> {
+        self.display_policies.get(dimension).copied()
+    }
+    
+    fn set_display_policy(&self, _dimension: &str, _policy: DisplayPolicy) -> ActorCoreResult<()> {
+        // This is a read-only implementation for loaded configs
+        Err(crate::ActorCoreError::ConfigurationError(
+            "Cannot modify display policies in loaded configuration".to_string()
+        ))
+    }
+    
     fn validate(&self) -> ActorCoreResult<()> {
         // Validation is already done during loading
         Ok(())
@@ -17,14 +17,21 @@ use crate::enums::Operator;
 // use crate::types::*; // Unused import
 use crate::types::Actor;
 use crate::types::SubsystemMeta;
+use super::subsystem_sets::SubsystemSets;
 use crate::ActorCoreResult;
 
+/// Subsystem IDs per actor with a fingerprint of the selection they were computed from
+type ActorSubsystems = FxHashMap<String, (u64, Vec<String>)>;
+
 /// Optimized plugin registry with FxHash and SmallVec optimizations.
 pub struct OptimizedPluginRegistry {
     /// Subsystems indexed by system ID using FxHash for faster lookups
     subsystems: Arc<RwLock<FxHashMap<String, Arc<dyn Subsystem>>>>,
-    /// Actor-to-subsystem mappings using Vec for collections
-    actor_subsystems: Arc<RwLock<FxHashMap<String, Vec<String>>>>,
+    /// Actor-to-subsystem mappings, keyed by actor ID with a fingerprint of the actor's
+    /// subsystem selection so changed selections are recomputed
+    actor_subsystems: Arc<RwLock<ActorSubsystems>>,
+    /// Default subsystem sets per actor template and race
+    subsystem_sets: Arc<RwLock<SubsystemSets>>,
     /// Subsystem metadata cache
     metadata_cache: Arc<RwLock<FxHashMap<String, SubsystemMeta>>>,
     /// Statistics with atomic operations
@@ -88,6 +95,7 @@ impl OptimizedPluginRegistry {
         Self {
             subsystems: Arc::new(RwLock::new(FxHashMap::default())),
             actor_subsystems: Arc::new(RwLock::new(FxHashMap::default())),
+            subsystem_sets: Arc::new(RwLock::new(SubsystemSets::default())),
             metadata_cache: Arc::new(RwLock::new(FxHashMap::default())),
             stats: Arc::new(RegistryStats::new()),
         }
@@ -121,14 +129,21 @@ impl OptimizedPluginRegistry {
         Ok(())
     }
     
+    /// Replace the subsystem sets, dropping the cached actor mappings.
+    pub fn set_subsystem_sets(&self, subsystem_sets: SubsystemSets) {
+        *self.subsystem_sets.write() = subsystem_sets;
+        self.actor_subsystems.write().clear();
+    }
+    
     /// Get subsystems for an actor with optimized lookup.
     pub async fn get_subsystems_for_actor(&self, actor: &Actor) -> ActorCoreResult<Vec<Arc<dyn Subsystem>>> {
         let actor_id = &actor.id;
+        let fingerprint = selection_fingerprint(actor);
         
         // Check cache first
         {
             let actor_subsystems = self.actor_subsystems.read();
-            if let Some(subsystem_ids) = actor_subsystems.get(&actor_id.to_string()) {
+            if let Some((_, subsystem_ids)) = actor_subsystems.get(actor_id).filter(|(cached, _)| *cached == fingerprint) {
                 self.stats.record_lookup(true);
                 
                 // Build result vector with pre-allocated capacity
@@ -153,7 +168,7 @@ impl OptimizedPluginRegistry {
         // Cache the result
         {
             let mut actor_subsystems = self.actor_subsystems.write();
-            actor_subsystems.insert(actor_id.to_string(), subsystem_ids.clone());
+            actor_subsystems.insert(actor_id.to_string(), (fingerprint, subsystem_ids.clone()));
         }
         
         // Build and return result
@@ -169,15 +184,16 @@ impl OptimizedPluginRegistry {
         Ok(result)
     }
     
-    /// Determine which subsystems apply to an actor (simplified implementation).
-    async fn determine_subsystems_for_actor(&self, _actor: &Actor) -> ActorCoreResult<Vec<String>> {
-        // Simplified logic: return all registered subsystems
-        // In a real implementation, this would use actor properties to determine relevant subsystems
+    /// Determine which subsystems are enabled for an actor.
+    async fn determine_subsystems_for_actor(&self, actor: &Actor) -> ActorCoreResult<Vec<String>> {
         let subsystems = self.subsystems.read();
+        let subsystem_sets = self.subsystem_sets.read();
         let mut result = Vec::new();
         
         for (system_id, _) in subsystems.iter() {
-            result.push(system_id.clone());
+            if subsystem_sets.is_enabled(actor, system_id) {
+                result.push(system_id.clone());
+            }
         }
         
         Ok(result)
@@ -217,6 +233,18 @@ impl OptimizedPluginRegistry {
     }
 }
 
+/// Hash of everything selecting an actor's subsystems.
+fn selection_fingerprint(actor: &Actor) -> u64 {
+    use std::hash::{Hash, Hasher};
+    
+    let mut hasher = fxhash::FxHasher::default();
+    actor.race.hash(&mut hasher);
+    actor.template.hash(&mut hasher);
+    actor.subsystems.hash(&mut hasher);
+    actor.disabled_subsystems.hash(&mut hasher);
+    hasher.finish()
+}
+
 /// Optimized combiner registry with fast rule lookups.
 pub struct OptimizedCombinerRegistry {
     /// Merge rules indexed by combination key using FxHash
@@ -0,0 +1,160 @@
+//! Per-actor subsystem selection.
+//!
+//! Every actor starts from a default set of subsystems picked by its template, then its
+//! race, then the global default. The actor's own `subsystems` are enabled on top of that
+//! set and its `disabled_subsystems` removed from it, so a cultivator template can enable
+//! `luyen_the` while a single NPC of that template opts out.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::loader::LoaderError;
+use crate::interfaces::Subsystem;
+use crate::types::Actor;
+
+/// Entry of a set enabling every registered subsystem
+pub const ALL_SUBSYSTEMS: &str = "*";
+
+/// Subsystem sets configuration root.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SubsystemSetsConfig {
+    /// Set of actors whose template and race have none
+    #[serde(default)]
+    pub default: Option<Vec<String>>,
+    /// Sets per race
+    #[serde(default)]
+    pub races: HashMap<String, Vec<String>>,
+    /// Sets per actor template
+    #[serde(default)]
+    pub templates: HashMap<String, Vec<String>>,
+}
+
+/// A default subsystem set
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubsystemSet {
+    /// Every registered subsystem
+    All,
+    /// Only these subsystems
+    Only(HashSet<String>),
+}
+
+impl SubsystemSet {
+    fn from_ids(ids: Vec<String>) -> Self {
+        if ids.iter().any(|id| id == ALL_SUBSYSTEMS) {
+            Self::All
+        } else {
+            Self::Only(ids.into_iter().collect())
+        }
+    }
+
+    /// Check whether the set contains a subsystem
+    pub fn contains(&self, system_id: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Only(ids) => ids.contains(system_id),
+        }
+    }
+}
+
+/// Default subsystem sets per actor template and race
+#[derive(Debug, Clone)]
+pub struct SubsystemSets {
+    default: SubsystemSet,
+    races: HashMap<String, SubsystemSet>,
+    templates: HashMap<String, SubsystemSet>,
+}
+
+impl Default for SubsystemSets {
+    /// Every subsystem for every actor, unless the actor opts out
+    fn default() -> Self {
+        Self {
+            default: SubsystemSet::All,
+            races: HashMap::new(),
+            templates: HashMap::new(),
+        }
+    }
+}
+
+impl SubsystemSets {
+    /// Build the sets from a validated configuration
+    pub fn from_config(config: SubsystemSetsConfig) -> Result<Self, LoaderError> {
+        validate_subsystem_sets_config(&config)?;
+        let convert = |sets: HashMap<String, Vec<String>>| {
+            sets.into_iter().map(|(name, ids)| (name, SubsystemSet::from_ids(ids))).collect()
+        };
+        Ok(Self {
+            default: config.default.map_or(SubsystemSet::All, SubsystemSet::from_ids),
+            races: convert(config.races),
+            templates: convert(config.templates),
+        })
+    }
+
+    /// Default set of an actor: its template's, else its race's, else the global one
+    pub fn default_set(&self, actor: &Actor) -> &SubsystemSet {
+        actor
+            .template
+            .as_ref()
+            .and_then(|template| self.templates.get(template))
+            .or_else(|| self.races.get(&actor.race))
+            .unwrap_or(&self.default)
+    }
+
+    /// Check whether a subsystem is enabled for an actor
+    pub fn is_enabled(&self, actor: &Actor, system_id: &str) -> bool {
+        if actor.disabled_subsystems.iter().any(|disabled| disabled == system_id) {
+            return false;
+        }
+        actor.subsystems.iter().any(|enabled| enabled == system_id) || self.default_set(actor).contains(system_id)
+    }
+
+    /// Keep the subsystems enabled for an actor, preserving their order
+    pub fn filter(&self, actor: &Actor, subsystems: Vec<Arc<dyn Subsystem>>) -> Vec<Arc<dyn Subsystem>> {
+        subsystems
+            .into_iter()
+            .filter(|subsystem| self.is_enabled(actor, subsystem.system_id()))
+            .collect()
+    }
+}
+
+/// Load subsystem sets from a YAML file.
+pub fn load_subsystem_sets<P: AsRef<Path>>(path: P) -> Result<SubsystemSets, LoaderError> {
+    let path = path.as_ref();
+    if !path.exists() {
+        return Err(LoaderError::FileNotFound {
+            path: path.display().to_string(),
+        });
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| LoaderError::IoError { error: e.to_string() })?;
+    let sets = parse_subsystem_sets(&content)?;
+    info!("Loaded subsystem sets from: {:?}", path);
+    Ok(sets)
+}
+
+/// Parse subsystem sets from YAML content.
+pub fn parse_subsystem_sets(content: &str) -> Result<SubsystemSets, LoaderError> {
+    let config: SubsystemSetsConfig =
+        serde_yaml::from_str(content).map_err(|e| LoaderError::InvalidYaml { error: e.to_string() })?;
+    SubsystemSets::from_config(config)
+}
+
+/// Validate subsystem sets configuration.
+fn validate_subsystem_sets_config(config: &SubsystemSetsConfig) -> Result<(), LoaderError> {
+    let named = config
+        .races
+        .iter()
+        .map(|(race, ids)| (format!("race {}", race), ids))
+        .chain(config.templates.iter().map(|(template, ids)| (format!("template {}", template), ids)))
+        .chain(config.default.iter().map(|ids| ("default set".to_string(), ids)));
+
+    for (name, ids) in named {
+        if ids.iter().any(|id| id.trim().is_empty()) {
+            return Err(LoaderError::ValidationError {
+                message: format!("Empty subsystem ID in {}", name),
+            });
+        }
+    }
+    Ok(())
+}
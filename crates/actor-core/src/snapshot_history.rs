@@ -0,0 +1,215 @@
+//! Recent snapshots per actor, to explain how their stats changed between versions.
+//!
+//! [`SnapshotHistory`] keeps the last snapshots of each actor along with the contributions
+//! they were aggregated from. [`SnapshotHistory::diff`] compares two versions stat by stat
+//! and breaks each change down by the subsystem and bucket whose contributions changed,
+//! for combat logs and anti-cheat. Attached to an aggregator, it records every
+//! context-free resolution; resolving a version again replaces its entry.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use crate::enums::Bucket;
+use crate::types::{Contribution, Snapshot};
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Snapshots kept per actor by default
+pub const DEFAULT_HISTORY_DEPTH: usize = 16;
+
+/// Change of one subsystem's contributions to a stat in one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceChange {
+    pub system: String,
+    pub bucket: Bucket,
+    /// Sum of the contributions before, 0 when there were none
+    pub before: f64,
+    pub after: f64,
+}
+
+/// Change of one stat between two snapshots
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatChange {
+    pub stat: String,
+    /// Value before, `None` when the stat was missing
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+    /// Contributions that changed, by subsystem and bucket; empty for derived stats
+    pub sources: Vec<SourceChange>,
+}
+
+impl StatChange {
+    /// Difference of the values, missing stats counting as 0
+    pub fn delta(&self) -> f64 {
+        self.after.unwrap_or(0.0) - self.before.unwrap_or(0.0)
+    }
+}
+
+/// What changed in an actor's stats between two versions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDelta {
+    pub actor_id: String,
+    pub from_version: i64,
+    pub to_version: i64,
+    /// Stats whose value or contributions changed, ordered by stat
+    pub changes: Vec<StatChange>,
+}
+
+impl SnapshotDelta {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Change of a stat, if it changed
+    pub fn change(&self, stat: &str) -> Option<&StatChange> {
+        self.changes.iter().find(|change| change.stat == stat)
+    }
+}
+
+/// A recorded snapshot and the contributions it was aggregated from
+#[derive(Debug, Clone)]
+struct Recorded {
+    snapshot: Snapshot,
+    contributions: Vec<Contribution>,
+}
+
+impl Recorded {
+    /// Contributions summed per stat, subsystem and bucket
+    fn sources(&self) -> HashMap<(&str, &str, Bucket), f64> {
+        let mut sources = HashMap::new();
+        for contribution in &self.contributions {
+            let key = (contribution.stat_name.as_str(), contribution.source.as_str(), contribution.bucket);
+            *sources.entry(key).or_insert(0.0) += contribution.value;
+        }
+        sources
+    }
+
+    fn stat(&self, stat: &str) -> Option<f64> {
+        self.snapshot.primary.get(stat).or_else(|| self.snapshot.derived.get(stat)).copied()
+    }
+}
+
+/// The last snapshots of each actor, with the contributions behind them.
+#[derive(Debug)]
+pub struct SnapshotHistory {
+    depth: usize,
+    actors: RwLock<HashMap<String, VecDeque<Recorded>>>,
+}
+
+impl Default for SnapshotHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+impl SnapshotHistory {
+    /// Create a history keeping the last `depth` snapshots per actor.
+    pub fn new(depth: usize) -> Self {
+        Self { depth: depth.max(1), actors: RwLock::new(HashMap::new()) }
+    }
+
+    /// Get how many snapshots are kept per actor.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Record a snapshot and the contributions it was aggregated from, replacing the
+    /// recorded snapshot of the same version and dropping the oldest beyond the depth.
+    pub fn record(&self, snapshot: Snapshot, contributions: Vec<Contribution>) {
+        let mut actors = self.actors.write();
+        let recorded = actors.entry(snapshot.actor_id.clone()).or_default();
+        let position = recorded.iter().position(|entry| entry.snapshot.version == snapshot.version);
+        let entry = Recorded { snapshot, contributions };
+        match position {
+            Some(position) => recorded[position] = entry,
+            None => recorded.push_back(entry),
+        }
+        while recorded.len() > self.depth {
+            recorded.pop_front();
+        }
+    }
+
+    /// Get the recorded snapshot of an actor at a version.
+    pub fn get(&self, actor_id: &str, version: i64) -> Option<Snapshot> {
+        self.actors
+            .read()
+            .get(actor_id)?
+            .iter()
+            .find(|entry| entry.snapshot.version == version)
+            .map(|entry| entry.snapshot.clone())
+    }
+
+    /// Get the contributions behind an actor's latest recorded snapshot.
+    pub(crate) fn latest_contributions(&self, actor_id: &str) -> Vec<Contribution> {
+        self.actors
+            .read()
+            .get(actor_id)
+            .and_then(|recorded| recorded.back())
+            .map(|entry| entry.contributions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Get the recorded versions of an actor, oldest first.
+    pub fn versions(&self, actor_id: &str) -> Vec<i64> {
+        self.actors
+            .read()
+            .get(actor_id)
+            .map(|recorded| recorded.iter().map(|entry| entry.snapshot.version).collect())
+            .unwrap_or_default()
+    }
+
+    /// Forget an actor's snapshots, e.g. when it logs out.
+    pub fn forget(&self, actor_id: &str) {
+        self.actors.write().remove(actor_id);
+    }
+
+    /// Compare an actor's snapshots at two recorded versions.
+    pub fn diff(&self, actor_id: &str, from_version: i64, to_version: i64) -> ActorCoreResult<SnapshotDelta> {
+        let actors = self.actors.read();
+        let find = |version: i64| {
+            actors
+                .get(actor_id)
+                .and_then(|recorded| recorded.iter().find(|entry| entry.snapshot.version == version))
+                .ok_or_else(|| {
+                    ActorCoreError::InvalidInput(format!(
+                        "No snapshot of {} at version {} in the history",
+                        actor_id, version
+                    ))
+                })
+        };
+        let (from, to) = (find(from_version)?, find(to_version)?);
+
+        let (before, after) = (from.sources(), to.sources());
+        let mut sources: BTreeMap<&str, Vec<SourceChange>> = BTreeMap::new();
+        for key in before.keys().chain(after.keys().filter(|key| !before.contains_key(*key))) {
+            let (stat, system, bucket) = *key;
+            let (was, is) = (before.get(key).copied().unwrap_or(0.0), after.get(key).copied().unwrap_or(0.0));
+            if was != is {
+                let change = SourceChange { system: system.to_string(), bucket, before: was, after: is };
+                sources.entry(stat).or_default().push(change);
+            }
+        }
+
+        let stats: BTreeSet<&str> = [&from.snapshot, &to.snapshot]
+            .into_iter()
+            .flat_map(|snapshot| snapshot.primary.keys().chain(snapshot.derived.keys()))
+            .map(String::as_str)
+            .chain(sources.keys().copied())
+            .collect();
+        let changes = stats
+            .into_iter()
+            .filter_map(|stat| {
+                let mut sources = sources.remove(stat).unwrap_or_default();
+                let (before, after) = (from.stat(stat), to.stat(stat));
+                if before == after && sources.is_empty() {
+                    return None;
+                }
+                sources.sort_by_key(|change| (change.system.clone(), format!("{:?}", change.bucket)));
+                Some(StatChange { stat: stat.to_string(), before, after, sources })
+            })
+            .collect();
+
+        Ok(SnapshotDelta { actor_id: actor_id.to_string(), from_version, to_version, changes })
+    }
+}
@@ -0,0 +1,95 @@
+//! Schema versions of cached and persisted snapshots.
+//!
+//! Snapshots outlive the process that resolved them: they sit in Redis and on-disk cache
+//! layers across deploys. Every serialized [`Snapshot`] carries a `schema_version`, and
+//! [`decode_snapshot`] upgrades entries written by older releases one version at a time
+//! before deserializing them. Entries written by a newer release cannot be read safely
+//! and are reported as [`SnapshotSchemaError::Newer`], so callers discard them and
+//! resolve again.
+//!
+//! Changing the shape of [`Snapshot`]:
+//! - a new field gets a `#[serde(default)]`, so older entries still load unchanged;
+//! - a renamed, removed or reinterpreted field bumps [`SNAPSHOT_SCHEMA_VERSION`] and adds
+//!   an upgrade to [`UPGRADES`] rewriting entries of the previous version.
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+use crate::types::Snapshot;
+
+/// Schema version written by this release
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = 2;
+
+/// Schema of entries written before snapshots carried a version
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// Upgrade of an entry from version `n + 1` to `n + 2` at index `n`
+type Upgrade = fn(&mut Map<String, Value>);
+
+/// Upgrades, in order, from [`LEGACY_SCHEMA_VERSION`] to [`SNAPSHOT_SCHEMA_VERSION`]
+const UPGRADES: [Upgrade; (SNAPSHOT_SCHEMA_VERSION - LEGACY_SCHEMA_VERSION) as usize] = [upgrade_v1];
+
+/// Why a serialized snapshot could not be read
+#[derive(Error, Debug)]
+pub enum SnapshotSchemaError {
+    #[error("snapshot is not a JSON object")]
+    NotAnObject,
+
+    #[error("snapshot has an invalid schema version: {0}")]
+    InvalidVersion(Value),
+
+    #[error("snapshot schema {found} is newer than the supported {supported}")]
+    Newer { found: u32, supported: u32 },
+
+    #[error("snapshot of schema {version} does not deserialize: {error}")]
+    Invalid { version: u32, error: String },
+}
+
+/// Schema version of a serialized snapshot, [`LEGACY_SCHEMA_VERSION`] when it has none
+pub fn schema_version(value: &Value) -> Result<u32, SnapshotSchemaError> {
+    let object = value.as_object().ok_or(SnapshotSchemaError::NotAnObject)?;
+    match object.get("schema_version") {
+        None => Ok(LEGACY_SCHEMA_VERSION),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= LEGACY_SCHEMA_VERSION)
+            .ok_or_else(|| SnapshotSchemaError::InvalidVersion(version.clone())),
+    }
+}
+
+/// Rewrite a serialized snapshot to [`SNAPSHOT_SCHEMA_VERSION`], returning the version
+/// it had
+pub fn upgrade_snapshot(value: &mut Value) -> Result<u32, SnapshotSchemaError> {
+    let found = schema_version(value)?;
+    if found > SNAPSHOT_SCHEMA_VERSION {
+        return Err(SnapshotSchemaError::Newer { found, supported: SNAPSHOT_SCHEMA_VERSION });
+    }
+    let object = value.as_object_mut().ok_or(SnapshotSchemaError::NotAnObject)?;
+    for (from, upgrade) in (LEGACY_SCHEMA_VERSION..).zip(UPGRADES.iter()) {
+        if from >= found {
+            upgrade(object);
+            object.insert("schema_version".to_string(), Value::from(from + 1));
+        }
+    }
+    Ok(found)
+}
+
+/// Read a serialized snapshot of any supported schema version
+pub fn decode_snapshot(mut value: Value) -> Result<Snapshot, SnapshotSchemaError> {
+    let version = upgrade_snapshot(&mut value)?;
+    serde_json::from_value(value).map_err(|e| SnapshotSchemaError::Invalid { version, error: e.to_string() })
+}
+
+/// Version 1 entries predate `schema_version` and the field defaults: their collections
+/// may be `null` rather than empty.
+fn upgrade_v1(object: &mut Map<String, Value>) {
+    for field in ["derived", "caps_used", "metadata"] {
+        if object.get(field).is_none_or(Value::is_null) {
+            object.insert(field.to_string(), Value::Object(Map::new()));
+        }
+    }
+    if object.get("subsystems_processed").is_none_or(Value::is_null) {
+        object.insert("subsystems_processed".to_string(), Value::Array(Vec::new()));
+    }
+}
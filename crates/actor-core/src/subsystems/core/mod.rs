@@ -5,7 +5,9 @@
 
 pub mod stat_change_notifier;
 pub mod resource_events;
+pub mod party_buffs;
 
 // Re-export commonly used core subsystems
 pub use stat_change_notifier::{StatChangeNotifier, StatChangeEvent, StatChangeListener};
-pub use resource_events::{ResourceEventManager, ResourceEvent, ResourceEventType, EventConfig, EventStats};
\ No newline at end of file
+pub use resource_events::{ResourceEventManager, ResourceEvent, ResourceEventType, EventConfig, EventStats};
+pub use party_buffs::{PartyBuffSubsystem, PARTY_BUFFS_SYSTEM_ID};
@@ -0,0 +1,59 @@
+//! Party Buff Subsystem
+//!
+//! Contributes the buffs party and raid members share with each other, as tracked by
+//! the shared [`PartyManager`].
+
+use async_trait::async_trait;
+use std::sync::Arc;
+use shared::party::{BuffMode, PartyManager};
+
+use crate::aggregation_context::AggregationContext;
+use crate::interfaces::Subsystem;
+use crate::types::{Actor, Contribution, SubsystemOutput};
+use crate::enums::Bucket;
+use crate::ActorCoreResult;
+
+/// System ID of the party buff subsystem
+pub const PARTY_BUFFS_SYSTEM_ID: &str = "party_buffs";
+
+/// Subsystem adding the buffs an actor's party members share with it
+pub struct PartyBuffSubsystem {
+    parties: Arc<PartyManager>,
+    priority: i64,
+}
+
+impl PartyBuffSubsystem {
+    /// Create a new Party Buff Subsystem
+    pub fn new(parties: Arc<PartyManager>) -> Self {
+        Self { parties, priority: 150 }
+    }
+
+    /// Set the subsystem priority
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[async_trait]
+impl Subsystem for PartyBuffSubsystem {
+    fn system_id(&self) -> &str {
+        PARTY_BUFFS_SYSTEM_ID
+    }
+
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    async fn contribute(&self, actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(PARTY_BUFFS_SYSTEM_ID.to_string());
+        for buff in self.parties.party_buffs(&actor.id) {
+            let bucket = match buff.mode {
+                BuffMode::Add => Bucket::Flat,
+                BuffMode::Multiply => Bucket::Mult,
+            };
+            output.add_contribution(Contribution::new(buff.stat, bucket, buff.value, PARTY_BUFFS_SYSTEM_ID.to_string()));
+        }
+        Ok(output)
+    }
+}
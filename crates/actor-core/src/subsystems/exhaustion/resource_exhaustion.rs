@@ -12,9 +12,11 @@ use tokio::sync::RwLock;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::aggregation_context::AggregationContext;
 use crate::interfaces::Subsystem;
 use crate::types::{Actor, SubsystemOutput, Snapshot};
 use crate::ActorCoreResult;
+use shared::clock::{wall_clock, SharedClock};
 
 /// Resource Exhaustion Subsystem
 pub struct ResourceExhaustionSubsystem {
@@ -28,6 +30,8 @@ pub struct ResourceExhaustionSubsystem {
     event_publisher: Arc<dyn ExhaustionEventPublisher + Send + Sync>,
     /// State tracker for active effects
     active_effects: Arc<RwLock<HashMap<String, HashMap<String, ExhaustionState>>>>,
+    /// Game clock used for effect and event timestamps
+    clock: SharedClock,
 }
 
 /// Exhaustion configuration loaded from YAML
@@ -243,9 +247,16 @@ impl ResourceExhaustionSubsystem {
             engine,
             event_publisher,
             active_effects: Arc::new(RwLock::new(HashMap::new())),
+            clock: wall_clock(),
         }
     }
 
+    /// Use a custom game clock for effect and event timestamps
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Load configuration from file
     pub async fn load_from_file(path: &str) -> ActorCoreResult<ExhaustionConfig> {
         let content = tokio::fs::read_to_string(path).await
@@ -419,6 +430,7 @@ impl ResourceExhaustionSubsystem {
 
     /// Apply exhaustion effects
     pub async fn apply_effects(&self, actor_id: &str, transitions: &[ExhaustionTransition]) -> ActorCoreResult<()> {
+        let now = self.clock.now();
         let mut active_effects = self.active_effects.write().await;
         let actor_effects = active_effects.entry(actor_id.to_string()).or_insert_with(HashMap::new);
 
@@ -440,16 +452,16 @@ impl ResourceExhaustionSubsystem {
                                 ThresholdState {
                                     threshold_id: transition.threshold_id.clone(),
                                     is_active: true,
-                                    activated_at: Utc::now(),
+                                    activated_at: now,
                                     applied_effects: transition.effects.clone(),
                                 }
                             );
                             thresholds
                         },
-                        last_evaluation: Utc::now(),
+                        last_evaluation: now,
                         coalescing_state: CoalescingState {
                             pending_events: Vec::new(),
-                            last_event_time: Utc::now(),
+                            last_event_time: now,
                         },
                     }
                 );
@@ -485,7 +497,7 @@ impl ResourceExhaustionSubsystem {
             resource_type: resource.to_string(),
             threshold_id: threshold_id.to_string(),
             effects: vec![effect.clone()],
-            timestamp: Utc::now(),
+            timestamp: self.clock.now(),
             idempotency_key,
             coalesced: false,
         };
@@ -517,7 +529,7 @@ impl ResourceExhaustionSubsystem {
             resource_type: resource.to_string(),
             threshold_id: threshold_id.to_string(),
             effects: vec![effect.clone()],
-            timestamp: Utc::now(),
+            timestamp: self.clock.now(),
             idempotency_key,
             coalesced: false,
         };
@@ -757,7 +769,7 @@ impl Subsystem for ResourceExhaustionSubsystem {
         self.priority
     }
 
-    async fn contribute(&self, _actor: &Actor) -> ActorCoreResult<SubsystemOutput> {
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
         // Resource Exhaustion Subsystem doesn't contribute to stats directly
         // It only processes exhaustion effects based on current resource values
         // This would typically be called by the combat system or resource manager
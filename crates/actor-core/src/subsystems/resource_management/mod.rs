@@ -42,9 +42,15 @@ pub trait SystemResourceCalculator {
 }
 
 // Re-export commonly used resource management subsystems
-pub use resource_database::InMemoryResourceDatabase;
+pub use resource_database::{
+    InMemoryResourceDatabase, ResourceRecord, ResourceStore, StoreWrite, WriteBehindConfig, WriteBehindResourceDatabase,
+    WriteBehindStats,
+};
 #[cfg(feature = "mongodb-storage")]
 pub use resource_database::MongoResourceDatabase;
 // Legacy system resource managers moved to examples/legacy_subsystems/
-pub use resource_cache::{ResourceCache, CacheConfig, CacheStats};
-pub use resource_regeneration::{ResourceRegenerationManager, RegenerationConfig, RegenerationStats};
\ No newline at end of file
+pub use resource_cache::{ResourceCache, CacheConfig, CacheStats, ResourceMetadata};
+pub use resource_regeneration::{
+    ResourceRegenerationManager, RegenerationConfig, RegenerationStats, RegenerationCurve, RegenerationTick,
+    RegenerationDelta,
+};
\ No newline at end of file
@@ -0,0 +1,202 @@
+//! Resource Database implementations
+//!
+//! This module provides database implementations for storing and retrieving
+//! actor resource data, including both MongoDB and in-memory implementations.
+//!
+//! Each actor's resources are one [`ResourceRecord`]. Stores only replace a record when
+//! its version is the one the writer read ([`ResourceStore::write`]), so concurrent
+//! writers notice each other; [`WriteBehindResourceDatabase`] queues updates in front of
+//! a store, merges conflicting writes resource by resource and keeps a local write-ahead
+//! log until they are flushed.
+//!
+//! NOTE: Legacy ResourceDatabase trait implementations have been removed
+//! to maintain the pure hub architecture. Use Runtime Registry System instead.
+
+pub mod wal;
+pub mod write_behind;
+
+pub use wal::{ResourceWal, WalEntry};
+pub use write_behind::{WriteBehindConfig, WriteBehindResourceDatabase, WriteBehindStats};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use crate::ActorCoreResult;
+
+/// Stored resources of one actor
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceRecord {
+    /// Actor ID
+    #[serde(rename = "_id")]
+    pub actor_id: String,
+    /// Resource values
+    pub resources: HashMap<String, f64>,
+    /// When each resource was last written, in milliseconds
+    #[serde(default)]
+    pub updated_at: HashMap<String, i64>,
+    /// Incremented on every write; 0 for an actor that was never stored
+    pub version: i64,
+}
+
+impl ResourceRecord {
+    /// Empty record of an actor that was never stored
+    pub fn new(actor_id: impl Into<String>) -> Self {
+        Self {
+            actor_id: actor_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Apply a write unless the record holds a newer value; returns whether it applied
+    pub fn merge(&mut self, resource_id: &str, value: f64, at_ms: i64) -> bool {
+        let newer_stored = self.updated_at.get(resource_id).is_some_and(|stored_at| *stored_at > at_ms);
+        if newer_stored {
+            return false;
+        }
+        self.resources.insert(resource_id.to_string(), value);
+        self.updated_at.insert(resource_id.to_string(), at_ms);
+        true
+    }
+}
+
+/// Outcome of a versioned write
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoreWrite {
+    /// The record was stored with the next version
+    Written,
+    /// Another writer got there first; this is what it stored
+    Conflict(ResourceRecord),
+}
+
+/// Persistent storage of actor resource records
+#[async_trait]
+pub trait ResourceStore: Send + Sync {
+    /// Load an actor's record
+    async fn load(&self, actor_id: &str) -> ActorCoreResult<Option<ResourceRecord>>;
+
+    /// Store `record` as version `record.version + 1`, if the stored version is still
+    /// `record.version`
+    async fn write(&self, record: &ResourceRecord) -> ActorCoreResult<StoreWrite>;
+}
+
+/// In-memory Resource Database for testing
+#[derive(Debug, Clone)]
+pub struct InMemoryResourceDatabase {
+    /// In-memory storage
+    storage: Arc<RwLock<HashMap<String, ResourceRecord>>>,
+}
+
+impl InMemoryResourceDatabase {
+    /// Create a new in-memory resource database
+    pub fn new() -> Self {
+        Self {
+            storage: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for InMemoryResourceDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl ResourceStore for InMemoryResourceDatabase {
+    async fn load(&self, actor_id: &str) -> ActorCoreResult<Option<ResourceRecord>> {
+        Ok(self.storage.read().await.get(actor_id).cloned())
+    }
+
+    async fn write(&self, record: &ResourceRecord) -> ActorCoreResult<StoreWrite> {
+        let mut storage = self.storage.write().await;
+        let stored_version = storage.get(&record.actor_id).map_or(0, |stored| stored.version);
+        if stored_version != record.version {
+            let current = storage.get(&record.actor_id).cloned();
+            return Ok(StoreWrite::Conflict(current.unwrap_or_else(|| ResourceRecord::new(&record.actor_id))));
+        }
+        let mut stored = record.clone();
+        stored.version += 1;
+        storage.insert(record.actor_id.clone(), stored);
+        Ok(StoreWrite::Written)
+    }
+}
+
+/// MongoDB Resource Database implementation
+#[cfg(feature = "mongodb-storage")]
+pub struct MongoResourceDatabase {
+    /// MongoDB client
+    client: mongodb::Client,
+    /// Database name
+    database_name: String,
+    /// Collection name for actor resources
+    collection_name: String,
+}
+
+#[cfg(feature = "mongodb-storage")]
+impl MongoResourceDatabase {
+    /// Create a new MongoDB resource database
+    pub fn new(client: mongodb::Client, database_name: String, collection_name: String) -> Self {
+        Self {
+            client,
+            database_name,
+            collection_name,
+        }
+    }
+
+    fn collection(&self) -> mongodb::Collection<ResourceRecord> {
+        self.client.database(&self.database_name).collection(&self.collection_name)
+    }
+
+    /// The stored record, for reporting a conflict
+    async fn current(&self, actor_id: &str) -> ActorCoreResult<ResourceRecord> {
+        Ok(self.load(actor_id).await?.unwrap_or_else(|| ResourceRecord::new(actor_id)))
+    }
+}
+
+#[cfg(feature = "mongodb-storage")]
+#[async_trait]
+impl ResourceStore for MongoResourceDatabase {
+    async fn load(&self, actor_id: &str) -> ActorCoreResult<Option<ResourceRecord>> {
+        self.collection()
+            .find_one(bson::doc! { "_id": actor_id }, None)
+            .await
+            .map_err(|e| crate::ActorCoreError::MongoDBError(format!("Failed to load resources of {}: {}", actor_id, e)))
+    }
+
+    async fn write(&self, record: &ResourceRecord) -> ActorCoreResult<StoreWrite> {
+        use mongodb::error::{ErrorKind, WriteFailure};
+
+        let mut stored = record.clone();
+        stored.version += 1;
+
+        if record.version == 0 {
+            // First write: the unique _id makes a concurrent first write fail
+            return match self.collection().insert_one(&stored, None).await {
+                Ok(_) => Ok(StoreWrite::Written),
+                Err(e) => match e.kind.as_ref() {
+                    ErrorKind::Write(WriteFailure::WriteError(write_error)) if write_error.code == 11000 => {
+                        Ok(StoreWrite::Conflict(self.current(&record.actor_id).await?))
+                    },
+                    _ => Err(crate::ActorCoreError::MongoDBError(format!(
+                        "Failed to store resources of {}: {}",
+                        record.actor_id, e
+                    ))),
+                },
+            };
+        }
+
+        let result = self
+            .collection()
+            .replace_one(bson::doc! { "_id": &record.actor_id, "version": record.version }, &stored, None)
+            .await
+            .map_err(|e| {
+                crate::ActorCoreError::MongoDBError(format!("Failed to store resources of {}: {}", record.actor_id, e))
+            })?;
+        if result.matched_count == 0 {
+            return Ok(StoreWrite::Conflict(self.current(&record.actor_id).await?));
+        }
+        Ok(StoreWrite::Written)
+    }
+}
@@ -0,0 +1,94 @@
+//! Local write-ahead log of resource writes that are not flushed yet.
+//!
+//! Every queued write is appended as one JSON line before it is acknowledged. After a
+//! flush the log is rewritten with the writes still queued, so after a crash replaying
+//! it restores exactly what had not reached the store. A line cut short by the crash is
+//! skipped.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+use crate::ActorCoreResult;
+
+/// One resource write
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalEntry {
+    pub actor_id: String,
+    pub resource_id: String,
+    pub value: f64,
+    /// When the write was made, in milliseconds
+    pub at_ms: i64,
+}
+
+/// Append-only log file of queued resource writes
+#[derive(Debug)]
+pub struct ResourceWal {
+    path: PathBuf,
+    file: File,
+    /// Sync the file after every append rather than leaving it to the OS
+    sync_writes: bool,
+}
+
+impl ResourceWal {
+    /// Open a log, creating it when missing, and return the entries it holds
+    pub async fn open(path: impl AsRef<Path>, sync_writes: bool) -> ActorCoreResult<(Self, Vec<WalEntry>)> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let entries = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => parse_entries(&path, &content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path).await?;
+        Ok((Self { path, file, sync_writes }, entries))
+    }
+
+    /// Append a write
+    pub async fn append(&mut self, entry: &WalEntry) -> ActorCoreResult<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line).await?;
+        if self.sync_writes {
+            self.file.sync_data().await?;
+        } else {
+            self.file.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Replace the log with `entries`, e.g. the writes still queued after a flush
+    pub async fn rewrite(&mut self, entries: impl IntoIterator<Item = &WalEntry>) -> ActorCoreResult<()> {
+        let mut content = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut content, entry)?;
+            content.push(b'\n');
+        }
+        // Write then rename so a crash leaves either the old or the new log
+        let temporary = self.path.with_extension("tmp");
+        let mut file = File::create(&temporary).await?;
+        file.write_all(&content).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temporary, &self.path).await?;
+        self.file = OpenOptions::new().append(true).open(&self.path).await?;
+        Ok(())
+    }
+}
+
+fn parse_entries(path: &Path, content: &str) -> Vec<WalEntry> {
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+    let mut entries = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) if index + 1 == lines.len() => {
+                warn!(path = %path.display(), "Skipping torn last entry of the resource log: {}", e);
+            },
+            Err(e) => warn!(path = %path.display(), line = index + 1, "Skipping unreadable resource log entry: {}", e),
+        }
+    }
+    entries
+}
@@ -0,0 +1,354 @@
+//! Write-behind queue in front of a [`ResourceStore`].
+//!
+//! Resource updates are acknowledged once they are in the local log and the cache, and
+//! reach the store in batches: every `flush_interval` the writes queued for each actor
+//! are coalesced into one versioned write of its record. When another writer stored the
+//! record in the meantime, the writes are merged into what it stored, the newer write of
+//! each resource winning, and written again.
+//!
+//! Reads see queued writes first, then the [`ResourceCache`], then the store, filling the
+//! cache with what they load.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, MutexGuard};
+use std::time::Duration;
+use futures::future::join_all;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+use shared::clock::{GameClock, SharedClock};
+use super::{ResourceRecord, ResourceStore, ResourceWal, StoreWrite, WalEntry};
+use crate::subsystems::resource_management::resource_cache::{ResourceCache, ResourceMetadata};
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// Write-behind configuration
+#[derive(Debug, Clone)]
+pub struct WriteBehindConfig {
+    /// Time between flushes of the background flusher
+    pub flush_interval: Duration,
+    /// Actors whose records are written concurrently
+    pub max_batch_actors: usize,
+    /// Conflicting writes retried per actor and flush before the writes are requeued
+    pub max_conflict_retries: u32,
+    /// Local write-ahead log; queued writes are lost on a crash without one
+    pub wal_path: Option<PathBuf>,
+    /// Sync the log to disk on every write rather than leaving it to the OS
+    pub sync_wal_writes: bool,
+}
+
+impl Default for WriteBehindConfig {
+    fn default() -> Self {
+        Self {
+            // TODO: Load these values from configuration
+            flush_interval: Duration::from_secs(1), // should be loaded from config
+            max_batch_actors: 100, // should be loaded from config
+            max_conflict_retries: 5, // should be loaded from config
+            wal_path: None,
+            sync_wal_writes: true,
+        }
+    }
+}
+
+/// Write-behind counters
+#[derive(Debug, Clone, Default)]
+pub struct WriteBehindStats {
+    /// Writes waiting for a flush
+    pub pending_writes: usize,
+    /// Writes accepted
+    pub queued_writes: u64,
+    /// Writes that replaced a queued write of the same resource
+    pub coalesced_writes: u64,
+    /// Writes restored from the log at startup
+    pub replayed_writes: u64,
+    /// Flushes run
+    pub flushes: u64,
+    /// Actor records written
+    pub records_written: u64,
+    /// Writes that found the record changed by another writer
+    pub conflicts: u64,
+    /// Writes dropped because another writer stored a newer value
+    pub superseded_writes: u64,
+    /// Actor writes that failed and were requeued
+    pub failed_writes: u64,
+}
+
+/// Queued writes and their log
+struct QueueState {
+    /// Latest queued write per actor and resource
+    pending: HashMap<String, HashMap<String, WalEntry>>,
+    wal: Option<ResourceWal>,
+}
+
+/// Resource database batching writes in front of a store
+pub struct WriteBehindResourceDatabase {
+    store: Arc<dyn ResourceStore>,
+    cache: Arc<ResourceCache>,
+    clock: SharedClock,
+    config: WriteBehindConfig,
+    state: Mutex<QueueState>,
+    /// Held for a whole flush so a write is never in two flushes at once
+    flushing: Mutex<()>,
+    stats: StdMutex<WriteBehindStats>,
+}
+
+impl WriteBehindResourceDatabase {
+    /// Open the database, replaying and flushing the writes a crash left in the log
+    pub async fn open(
+        store: Arc<dyn ResourceStore>,
+        cache: Arc<ResourceCache>,
+        clock: SharedClock,
+        config: WriteBehindConfig,
+    ) -> ActorCoreResult<Self> {
+        let (wal, replayed) = match &config.wal_path {
+            Some(path) => {
+                let (wal, entries) = ResourceWal::open(path, config.sync_wal_writes).await?;
+                (Some(wal), entries)
+            },
+            None => (None, Vec::new()),
+        };
+
+        let mut pending = HashMap::new();
+        let replayed_writes = replayed.len() as u64;
+        for entry in replayed {
+            queue(&mut pending, entry);
+        }
+        let database = Self {
+            store,
+            cache,
+            clock,
+            config,
+            state: Mutex::new(QueueState { pending, wal }),
+            flushing: Mutex::new(()),
+            stats: StdMutex::new(WriteBehindStats {
+                replayed_writes,
+                ..WriteBehindStats::default()
+            }),
+        };
+
+        if replayed_writes > 0 {
+            info!(writes = replayed_writes, "Replaying unflushed resource writes");
+            database.flush().await?;
+        }
+        Ok(database)
+    }
+
+    /// Queue a resource update; it is durable once this returns when a log is configured
+    pub async fn set(&self, actor_id: &str, resource_id: &str, value: f64) -> ActorCoreResult<()> {
+        let entry = WalEntry {
+            actor_id: actor_id.to_string(),
+            resource_id: resource_id.to_string(),
+            value,
+            at_ms: self.clock.now_millis(),
+        };
+
+        let coalesced = {
+            let mut state = self.state.lock().await;
+            if let Some(wal) = &mut state.wal {
+                wal.append(&entry).await?;
+            }
+            queue(&mut state.pending, entry)
+        };
+        {
+            let mut stats = self.counters();
+            stats.queued_writes += 1;
+            if coalesced {
+                stats.coalesced_writes += 1;
+            }
+        }
+
+        self.cache.set(actor_id, resource_id, value, cache_metadata()).await
+    }
+
+    /// Read a resource: queued writes, then the cache, then the store
+    pub async fn get(&self, actor_id: &str, resource_id: &str) -> ActorCoreResult<Option<f64>> {
+        if let Some(entry) = self.pending_write(actor_id, resource_id).await {
+            return Ok(Some(entry.value));
+        }
+        if let Some(value) = self.cache.get(actor_id, resource_id).await? {
+            return Ok(Some(value));
+        }
+        let Some(record) = self.store.load(actor_id).await? else {
+            return Ok(None);
+        };
+        self.fill_cache(&record).await?;
+        Ok(record.resources.get(resource_id).copied())
+    }
+
+    /// Read all resources of an actor, e.g. when it is loaded
+    pub async fn get_all(&self, actor_id: &str) -> ActorCoreResult<HashMap<String, f64>> {
+        let record = self.store.load(actor_id).await?.unwrap_or_else(|| ResourceRecord::new(actor_id));
+        self.fill_cache(&record).await?;
+
+        let mut resources = record.resources;
+        if let Some(writes) = self.state.lock().await.pending.get(actor_id) {
+            for entry in writes.values() {
+                resources.insert(entry.resource_id.clone(), entry.value);
+            }
+        }
+        Ok(resources)
+    }
+
+    /// Write every queued update to the store, returning how many actor records were
+    /// written. Writes that fail stay queued for the next flush.
+    pub async fn flush(&self) -> ActorCoreResult<usize> {
+        let _flushing = self.flushing.lock().await;
+        let batch: Vec<(String, HashMap<String, WalEntry>)> = self.state.lock().await.pending.drain().collect();
+        if batch.is_empty() {
+            return Ok(0);
+        }
+
+        let mut written = 0;
+        let mut failed = Vec::new();
+        for chunk in batch.chunks(self.config.max_batch_actors.max(1)) {
+            let results = join_all(chunk.iter().map(|(actor_id, writes)| self.write_actor(actor_id, writes))).await;
+            for ((actor_id, writes), result) in chunk.iter().zip(results) {
+                match result {
+                    Ok(()) => written += 1,
+                    Err(e) => {
+                        warn!(actor_id = %actor_id, "Failed to flush resource writes, keeping them queued: {}", e);
+                        failed.push(writes.clone());
+                    },
+                }
+            }
+        }
+
+        // Requeue failures behind the writes made during the flush, then drop what was
+        // flushed from the log
+        let failed_writes = failed.len() as u64;
+        {
+            let mut state = self.state.lock().await;
+            for writes in failed {
+                for entry in writes.into_values() {
+                    queue(&mut state.pending, entry);
+                }
+            }
+            let QueueState { pending, wal } = &mut *state;
+            if let Some(wal) = wal {
+                wal.rewrite(pending.values().flat_map(|writes| writes.values())).await?;
+            }
+        }
+
+        let mut stats = self.counters();
+        stats.flushes += 1;
+        stats.records_written += written as u64;
+        stats.failed_writes += failed_writes;
+        Ok(written)
+    }
+
+    /// Flush every `flush_interval` until the returned task is aborted
+    pub fn spawn_flusher(self: &Arc<Self>) -> JoinHandle<()> {
+        let database = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(database.config.flush_interval.max(Duration::from_millis(1)));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = database.flush().await {
+                    warn!("Failed to flush resource writes: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Get write-behind statistics
+    pub async fn stats(&self) -> WriteBehindStats {
+        let pending_writes = self.state.lock().await.pending.values().map(HashMap::len).sum();
+        WriteBehindStats {
+            pending_writes,
+            ..self.counters().clone()
+        }
+    }
+
+    /// Write an actor's queued updates, merging them into other writers' changes
+    async fn write_actor(&self, actor_id: &str, writes: &HashMap<String, WalEntry>) -> ActorCoreResult<()> {
+        let mut record = self.store.load(actor_id).await?.unwrap_or_else(|| ResourceRecord::new(actor_id));
+        let mut conflicts = 0;
+        loop {
+            let mut applied = 0;
+            for entry in writes.values() {
+                if record.merge(&entry.resource_id, entry.value, entry.at_ms) {
+                    applied += 1;
+                }
+            }
+            // Every write was superseded by a newer one
+            if applied == 0 {
+                break;
+            }
+
+            match self.store.write(&record).await? {
+                StoreWrite::Written => {
+                    record.version += 1;
+                    break;
+                },
+                StoreWrite::Conflict(current) => {
+                    conflicts += 1;
+                    self.counters().conflicts += 1;
+                    if conflicts > self.config.max_conflict_retries {
+                        return Err(ActorCoreError::SubsystemError(format!(
+                            "Resources of {} kept changing during {} writes",
+                            actor_id, conflicts
+                        )));
+                    }
+                    record = current;
+                },
+            }
+        }
+
+        let superseded = writes
+            .values()
+            .filter(|entry| record.updated_at.get(&entry.resource_id) != Some(&entry.at_ms))
+            .count();
+        self.counters().superseded_writes += superseded as u64;
+
+        // The record may hold newer values from other writers
+        self.fill_cache(&record).await
+    }
+
+    async fn pending_write(&self, actor_id: &str, resource_id: &str) -> Option<WalEntry> {
+        let state = self.state.lock().await;
+        state.pending.get(actor_id).and_then(|writes| writes.get(resource_id)).cloned()
+    }
+
+    async fn fill_cache(&self, record: &ResourceRecord) -> ActorCoreResult<()> {
+        let pending = self.state.lock().await.pending.get(&record.actor_id).cloned().unwrap_or_default();
+        for (resource_id, value) in &record.resources {
+            // Queued writes are newer than anything stored
+            if !pending.contains_key(resource_id) {
+                self.cache.set(&record.actor_id, resource_id, *value, cache_metadata()).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn counters(&self) -> MutexGuard<'_, WriteBehindStats> {
+        self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Queue a write unless a newer one of the same resource is queued; returns whether a
+/// queued write was replaced
+fn queue(pending: &mut HashMap<String, HashMap<String, WalEntry>>, entry: WalEntry) -> bool {
+    let writes = pending.entry(entry.actor_id.clone()).or_default();
+    match writes.get(&entry.resource_id) {
+        Some(queued) if queued.at_ms > entry.at_ms => false,
+        Some(_) => {
+            writes.insert(entry.resource_id.clone(), entry);
+            true
+        },
+        None => {
+            writes.insert(entry.resource_id.clone(), entry);
+            false
+        },
+    }
+}
+
+fn cache_metadata() -> ResourceMetadata {
+    ResourceMetadata {
+        category: "resource".to_string(),
+        dependencies: Vec::new(),
+        priority: 0,
+        is_shared: false,
+    }
+}
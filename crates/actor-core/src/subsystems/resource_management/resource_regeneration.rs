@@ -3,14 +3,22 @@
 //! This module provides a comprehensive resource regeneration system
 //! for the Enhanced Hybrid Resource Manager, handling automatic
 //! regeneration of various resources over time.
+//!
+//! Regeneration follows the game clock: each [`tick`](ResourceRegenerationManager::tick)
+//! regenerates every active task by the game time elapsed since its last update, in
+//! batches of `batch_size` tasks. Actors that were unloaded get the regeneration they
+//! missed from [`catch_up`](ResourceRegenerationManager::catch_up) when they load again.
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 use crate::types::Actor;
 use crate::ActorCoreResult;
 use super::SystemResourceCalculator;
+use shared::clock::{wall_clock, SharedClock};
 // Legacy system_resource_manager moved to examples/legacy_subsystems/
 // Use Runtime Registry System for resource calculations instead
 use tracing::{info};
@@ -29,8 +37,21 @@ pub struct ResourceRegenerationManager {
     /// Active regeneration tasks
     active_tasks: Arc<RwLock<HashMap<String, RegenerationTask>>>,
     /// Configuration
-    #[allow(dead_code)]
     config: RegenerationConfig,
+    /// Game clock used to measure elapsed regeneration time
+    clock: SharedClock,
+    /// Throughput counters
+    metrics: Mutex<RegenerationMetrics>,
+}
+
+/// Counters behind the throughput figures of [`RegenerationStats`]
+#[derive(Debug, Default)]
+struct RegenerationMetrics {
+    ticks: u64,
+    tasks_processed: u64,
+    tick_time: Duration,
+    catch_ups: u64,
+    caught_up_total: f64,
 }
 
 /// Regeneration Rule
@@ -42,12 +63,42 @@ pub struct RegenerationRule {
     pub base_rate: f64,
     /// Regeneration formula
     pub formula: String,
+    /// How the base rate turns into points per second
+    pub curve: RegenerationCurve,
     /// Regeneration conditions
     pub conditions: Vec<RegenerationCondition>,
     /// Regeneration modifiers
     pub modifiers: Vec<RegenerationModifier>,
 }
 
+/// How a resource refills over time
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegenerationCurve {
+    /// `base_rate` points per second
+    Flat,
+    /// `base_rate` of the resource's maximum per second, e.g. 0.01 for 1%
+    Percentage,
+    /// Another curve, scaled by `in_combat_multiplier` while the actor is in combat
+    CombatSuppressed {
+        curve: Box<RegenerationCurve>,
+        in_combat_multiplier: f64,
+    },
+}
+
+impl RegenerationCurve {
+    /// Points per second regenerated for a resource with the given maximum
+    pub fn rate(&self, base_rate: f64, max_value: f64, in_combat: bool) -> f64 {
+        match self {
+            RegenerationCurve::Flat => base_rate,
+            RegenerationCurve::Percentage => base_rate * max_value,
+            RegenerationCurve::CombatSuppressed { curve, in_combat_multiplier } => {
+                let rate = curve.rate(base_rate, max_value, in_combat);
+                if in_combat { rate * in_combat_multiplier } else { rate }
+            },
+        }
+    }
+}
+
 /// Regeneration Condition
 #[derive(Debug, Clone)]
 pub enum RegenerationCondition {
@@ -76,6 +127,8 @@ pub enum RegenerationModifier {
     Add(f64),
     /// Apply based on stat value
     StatBased(String, f64),
+    /// Multiply by a stat value, e.g. a rate another system derives per actor
+    StatScaled(String),
     /// Apply based on equipment
     EquipmentBased(String, f64),
     /// Apply based on environment
@@ -89,9 +142,9 @@ pub struct RegenerationTask {
     pub actor_id: String,
     /// Resource name
     pub resource_name: String,
-    /// Last update time
-    pub last_update: u64,
-    /// Current regeneration rate
+    /// Game time of the last update, in milliseconds
+    pub last_update_ms: i64,
+    /// Current regeneration rate, in points per second
     pub current_rate: f64,
     /// Total regenerated amount
     pub total_regenerated: f64,
@@ -110,6 +163,8 @@ pub struct RegenerationConfig {
     pub batch_size: usize,
     /// Enable performance monitoring
     pub enable_monitoring: bool,
+    /// Longest offline time, in seconds, that catch-up regenerates for
+    pub max_catch_up_secs: f64,
 }
 
 impl Default for RegenerationConfig {
@@ -121,6 +176,7 @@ impl Default for RegenerationConfig {
             enable_batch_processing: true,
             batch_size: 100, // should be loaded from config
             enable_monitoring: true,
+            max_catch_up_secs: 8.0 * 3600.0, // 8 hours - should be loaded from config
         }
     }
 }
@@ -128,11 +184,18 @@ impl Default for RegenerationConfig {
 impl ResourceRegenerationManager {
     /// Create a new Resource Regeneration Manager
     pub fn new(config: RegenerationConfig) -> Self {
+        Self::with_clock(config, wall_clock())
+    }
+    
+    /// Create a new Resource Regeneration Manager driven by a custom game clock
+    pub fn with_clock(config: RegenerationConfig, clock: SharedClock) -> Self {
         let mut manager = Self {
             system_id: "regeneration_system".to_string(),
             regeneration_rules: HashMap::new(),
             active_tasks: Arc::new(RwLock::new(HashMap::new())),
             config,
+            clock,
+            metrics: Mutex::new(RegenerationMetrics::default()),
         };
         
         // Initialize default regeneration rules
@@ -148,6 +211,7 @@ impl ResourceRegenerationManager {
             resource_name: "hp_current".to_string(),
             base_rate: 0.1, // 0.1 HP per second - should be loaded from config
             formula: "base_rate * vitality_modifier * rest_modifier".to_string(),
+            curve: RegenerationCurve::Flat,
             conditions: vec![
                 RegenerationCondition::NotInCombat,
                 RegenerationCondition::HealthAbove(0.1), // Only regenerate when above 10% health - should be loaded from config
@@ -163,6 +227,7 @@ impl ResourceRegenerationManager {
             resource_name: "mp_current".to_string(),
             base_rate: 0.2, // 0.2 MP per second - should be loaded from config
             formula: "base_rate * intelligence_modifier * meditation_modifier".to_string(),
+            curve: RegenerationCurve::Flat,
             conditions: vec![
                 RegenerationCondition::NotInCombat,
                 RegenerationCondition::Resting,
@@ -178,6 +243,7 @@ impl ResourceRegenerationManager {
             resource_name: "stamina_current".to_string(),
             base_rate: 0.5, // 0.5 stamina per second - should be loaded from config
             formula: "base_rate * constitution_modifier * rest_modifier".to_string(),
+            curve: RegenerationCurve::Flat,
             conditions: vec![
                 RegenerationCondition::NotMoving,
             ],
@@ -192,6 +258,7 @@ impl ResourceRegenerationManager {
             resource_name: "mana_current".to_string(),
             base_rate: 0.3, // 0.3 mana per second - should be loaded from config
             formula: "base_rate * wisdom_modifier * meditation_modifier".to_string(),
+            curve: RegenerationCurve::Flat,
             conditions: vec![
                 RegenerationCondition::NotInCombat,
                 RegenerationCondition::Resting,
@@ -229,7 +296,7 @@ impl ResourceRegenerationManager {
         let task = RegenerationTask {
             actor_id: actor.id.to_string(),
             resource_name: resource_name.to_string(),
-            last_update: chrono::Utc::now().timestamp() as u64,
+            last_update_ms: self.clock.now_millis(),
             current_rate: 0.0,
             total_regenerated: 0.0,
         };
@@ -253,73 +320,122 @@ impl ResourceRegenerationManager {
         Ok(())
     }
     
-    /// Update regeneration for all active tasks
+    /// Run a tick and apply what it regenerated to the actors' resources
     pub async fn update_regeneration(&self, actors: &HashMap<String, Actor>) -> ActorCoreResult<()> {
-        let current_time = chrono::Utc::now().timestamp() as u64;
-        let mut tasks_to_remove = Vec::new();
-        let mut tasks_to_update = Vec::new();
-        
-        // Get current tasks
-        {
-            let tasks = self.active_tasks.read().await;
-            for (task_key, task) in tasks.iter() {
-                let time_delta = current_time - task.last_update;
-                if time_delta > 0 {
-                    tasks_to_update.push((task_key.clone(), task.clone()));
-                }
+        let tick = self.tick(actors).await?;
+        for delta in &tick.deltas {
+            if let Some(actor) = actors.get(&delta.actor_id) {
+                self.update_actor_resource(actor, &delta.resource_name, delta.amount).await?;
             }
         }
+        Ok(())
+    }
+    
+    /// Regenerate every active task by the game time elapsed since its last update.
+    ///
+    /// Tasks are processed `batch_size` at a time. A task stops when its actor is not in
+    /// `actors` or one of its rule's conditions no longer holds. The caller applies the
+    /// returned deltas.
+    pub async fn tick(&self, actors: &HashMap<String, Actor>) -> ActorCoreResult<RegenerationTick> {
+        let started = Instant::now();
+        let now_ms = self.clock.now_millis();
+        let mut tick = RegenerationTick::default();
         
-        // Update tasks
-        for (task_key, mut task) in tasks_to_update {
-            if let Some(actor) = actors.get(&task.actor_id) {
-                // Check if regeneration should continue
-                if self.should_continue_regeneration(actor, &task.resource_name).await? {
-                    // Calculate regeneration amount
-                    let time_delta = current_time - task.last_update;
-                    let regen_amount = self.calculate_regeneration_amount(actor, &task.resource_name, time_delta as f64).await?;
-                    
-                    // Update task
-                    task.last_update = current_time;
-                    task.total_regenerated += regen_amount;
-                    
-                    // Update actor's resource
-                    self.update_actor_resource(actor, &task.resource_name, regen_amount).await?;
-                    
-                    // Update task in active tasks
-                    {
-                        let mut tasks = self.active_tasks.write().await;
-                        tasks.insert(task_key, task);
-                    }
-                } else {
-                    // Stop regeneration
-                    tasks_to_remove.push(task_key);
+        let task_keys: Vec<String> = self.active_tasks.read().await.keys().cloned().collect();
+        let batch_size = if self.config.enable_batch_processing { self.config.batch_size.max(1) } else { 1 };
+        
+        for batch in task_keys.chunks(batch_size) {
+            let mut tasks = self.active_tasks.write().await;
+            for task_key in batch {
+                // Stopped since the keys were read
+                let Some(task) = tasks.get_mut(task_key) else {
+                    continue;
+                };
+                let elapsed_ms = now_ms - task.last_update_ms;
+                if elapsed_ms <= 0 {
+                    continue;
+                }
+                
+                let rule = self.regeneration_rules.get(&task.resource_name);
+                let actor = actors.get(&task.actor_id);
+                let (Some(rule), Some(actor)) = (rule, actor) else {
+                    tasks.remove(task_key);
+                    tick.tasks_stopped += 1;
+                    continue;
+                };
+                if !self.conditions_hold(actor, rule)? {
+                    tasks.remove(task_key);
+                    tick.tasks_stopped += 1;
+                    continue;
+                }
+                
+                let elapsed_secs = elapsed_ms as f64 / 1000.0;
+                let amount = self.calculate_regeneration_amount(actor, rule, elapsed_secs)?;
+                task.last_update_ms = now_ms;
+                task.current_rate = amount / elapsed_secs;
+                task.total_regenerated += amount;
+                tick.tasks_processed += 1;
+                if amount > 0.0 {
+                    tick.deltas.push(RegenerationDelta {
+                        actor_id: task.actor_id.clone(),
+                        resource_name: task.resource_name.clone(),
+                        amount,
+                    });
                 }
-            } else {
-                // Actor not found, remove task
-                tasks_to_remove.push(task_key);
             }
         }
         
-        // Remove stopped tasks
-        {
-            let mut tasks = self.active_tasks.write().await;
-            for task_key in tasks_to_remove {
-                tasks.remove(&task_key);
+        if self.config.enable_monitoring {
+            let mut metrics = self.metrics();
+            metrics.ticks += 1;
+            metrics.tasks_processed += tick.tasks_processed as u64;
+            metrics.tick_time += started.elapsed();
+        }
+        Ok(tick)
+    }
+    
+    /// Regeneration an actor missed while unloaded, from `unloaded_at` until now.
+    ///
+    /// Offline actors count as resting, still and out of combat; the other conditions are
+    /// checked against the actor as it was saved. At most `max_catch_up_secs` of offline
+    /// time is regenerated. Returns the amount per resource, to apply before regeneration
+    /// is started again for the loaded actor.
+    pub async fn catch_up(&self, actor: &Actor, unloaded_at: DateTime<Utc>) -> ActorCoreResult<HashMap<String, f64>> {
+        let offline_ms = (self.clock.now() - unloaded_at).num_milliseconds().max(0);
+        let offline_secs = (offline_ms as f64 / 1000.0).min(self.config.max_catch_up_secs.max(0.0));
+        let mut amounts = HashMap::new();
+        if offline_secs <= 0.0 {
+            return Ok(amounts);
+        }
+        
+        let mut offline = actor.clone();
+        offline.data.insert("in_combat".to_string(), serde_json::Value::Bool(false));
+        offline.data.insert("resting".to_string(), serde_json::Value::Bool(true));
+        offline.data.insert("moving".to_string(), serde_json::Value::Bool(false));
+        
+        for (resource_name, rule) in &self.regeneration_rules {
+            if !self.conditions_hold(&offline, rule)? {
+                continue;
+            }
+            let amount = self.calculate_regeneration_amount(&offline, rule, offline_secs)?;
+            if amount > 0.0 {
+                amounts.insert(resource_name.clone(), amount);
             }
         }
         
-        Ok(())
+        if self.config.enable_monitoring {
+            let mut metrics = self.metrics();
+            metrics.catch_ups += 1;
+            metrics.caught_up_total += amounts.values().sum::<f64>();
+        }
+        info!(actor_id = %actor.id, offline_secs = offline_secs, resources = amounts.len(), "Caught up offline regeneration");
+        Ok(amounts)
     }
     
-    /// Check if regeneration should continue
-    async fn should_continue_regeneration(&self, actor: &Actor, resource_name: &str) -> ActorCoreResult<bool> {
-        let rule = self.regeneration_rules.get(resource_name)
-            .ok_or_else(|| to_actor_core_error(format!("No regeneration rule found for resource: {}", resource_name)))?;
-        
-        // Check all conditions
+    /// Check whether all of a rule's conditions hold
+    fn conditions_hold(&self, actor: &Actor, rule: &RegenerationRule) -> ActorCoreResult<bool> {
         for condition in &rule.conditions {
-            if !self.check_condition(actor, condition).await? {
+            if !self.check_condition(actor, condition)? {
                 return Ok(false);
             }
         }
@@ -328,29 +444,25 @@ impl ResourceRegenerationManager {
     }
     
     /// Check a regeneration condition
-    async fn check_condition(&self, actor: &Actor, condition: &RegenerationCondition) -> ActorCoreResult<bool> {
+    fn check_condition(&self, actor: &Actor, condition: &RegenerationCondition) -> ActorCoreResult<bool> {
+        let data = actor.get_data();
         match condition {
             RegenerationCondition::NotInCombat => {
                 // Check if actor is in combat
-                let data = actor.get_data();
-                let in_combat = data.get("in_combat").and_then(|v| v.as_bool()).unwrap_or(false);
-                Ok(!in_combat)
+                Ok(!is_in_combat(actor))
             },
             RegenerationCondition::Resting => {
                 // Check if actor is resting
-                let data = actor.get_data();
                 let resting = data.get("resting").and_then(|v| v.as_bool()).unwrap_or(false);
                 Ok(resting)
             },
             RegenerationCondition::NotMoving => {
                 // Check if actor is moving
-                let data = actor.get_data();
                 let moving = data.get("moving").and_then(|v| v.as_bool()).unwrap_or(false);
                 Ok(!moving)
             },
             RegenerationCondition::HealthAbove(threshold) => {
                 // Check if health is above threshold
-                let data = actor.get_data();
                 let current_hp = data.get("hp_current").and_then(|v| v.as_f64()).unwrap_or(0.0);
                 // TODO: Load default max HP from configuration instead of hardcoded 100.0
                 let max_hp = data.get("hp_max").and_then(|v| v.as_f64()).unwrap_or(100.0);
@@ -359,7 +471,6 @@ impl ResourceRegenerationManager {
             },
             RegenerationCondition::HealthBelow(threshold) => {
                 // Check if health is below threshold
-                let data = actor.get_data();
                 let current_hp = data.get("hp_current").and_then(|v| v.as_f64()).unwrap_or(0.0);
                 // TODO: Load default max HP from configuration instead of hardcoded 100.0
                 let max_hp = data.get("hp_max").and_then(|v| v.as_f64()).unwrap_or(100.0);
@@ -368,66 +479,68 @@ impl ResourceRegenerationManager {
             },
             RegenerationCondition::StatAbove(stat_name, threshold) => {
                 // Check if stat is above threshold
-                let data = actor.get_data();
                 let stat_value = data.get(stat_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 Ok(stat_value > *threshold)
             },
             RegenerationCondition::StatBelow(stat_name, threshold) => {
                 // Check if stat is below threshold
-                let data = actor.get_data();
                 let stat_value = data.get(stat_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 Ok(stat_value < *threshold)
             },
         }
     }
     
-    /// Calculate regeneration amount
-    async fn calculate_regeneration_amount(&self, actor: &Actor, resource_name: &str, time_delta: f64) -> ActorCoreResult<f64> {
-        let rule = self.regeneration_rules.get(resource_name)
-            .ok_or_else(|| to_actor_core_error(format!("No regeneration rule found for resource: {}", resource_name)))?;
+    /// Calculate the amount regenerated over `elapsed_secs`, without exceeding the maximum
+    fn calculate_regeneration_amount(&self, actor: &Actor, rule: &RegenerationRule, elapsed_secs: f64) -> ActorCoreResult<f64> {
+        let data = actor.get_data();
+        let current_value = data.get(&rule.resource_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        // TODO: Load default max value from configuration instead of hardcoded 100.0
+        let max_value = data.get(&format!("{}_max", rule.resource_name)).and_then(|v| v.as_f64()).unwrap_or(100.0);
         
-        let mut regen_amount = rule.base_rate * time_delta;
+        let rate = rule.curve.rate(rule.base_rate, max_value, is_in_combat(actor));
+        let mut regen_amount = rate * elapsed_secs;
         
         // Apply modifiers
         for modifier in &rule.modifiers {
-            regen_amount = self.apply_modifier(actor, modifier, regen_amount).await?;
+            regen_amount = self.apply_modifier(actor, modifier, regen_amount)?;
         }
         
-        // Ensure we don't exceed maximum
-        let data = actor.get_data();
-        let current_value = data.get(resource_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
-        // TODO: Load default max value from configuration instead of hardcoded 100.0
-        let max_value = data.get(&format!("{}_max", resource_name)).and_then(|v| v.as_f64()).unwrap_or(100.0);
-        
+        // Ensure we don't exceed maximum, nor drain a resource already above it
         let new_value = (current_value + regen_amount).min(max_value);
-        regen_amount = new_value - current_value;
+        regen_amount = (new_value - current_value).max(0.0);
         
         Ok(regen_amount)
     }
     
     /// Apply a regeneration modifier
-    async fn apply_modifier(&self, actor: &Actor, modifier: &RegenerationModifier, current_amount: f64) -> ActorCoreResult<f64> {
+    fn apply_modifier(&self, actor: &Actor, modifier: &RegenerationModifier, current_amount: f64) -> ActorCoreResult<f64> {
+        let data = actor.get_data();
         match modifier {
             RegenerationModifier::Multiply(factor) => Ok(current_amount * factor),
             RegenerationModifier::Add(amount) => Ok(current_amount + amount),
             RegenerationModifier::StatBased(stat_name, multiplier) => {
-                let data = actor.get_data();
                 let stat_value = data.get(stat_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 Ok(current_amount * (1.0 + stat_value * multiplier))
             },
+            RegenerationModifier::StatScaled(stat_name) => {
+                let stat_value = data.get(stat_name).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                Ok(current_amount * stat_value)
+            },
             RegenerationModifier::EquipmentBased(equipment_stat, multiplier) => {
-                let data = actor.get_data();
                 let equipment_value = data.get(equipment_stat).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 Ok(current_amount * (1.0 + equipment_value * multiplier))
             },
             RegenerationModifier::EnvironmentBased(environment_stat, multiplier) => {
-                let data = actor.get_data();
                 let environment_value = data.get(environment_stat).and_then(|v| v.as_f64()).unwrap_or(0.0);
                 Ok(current_amount * (1.0 + environment_value * multiplier))
             },
         }
     }
     
+    fn metrics(&self) -> MutexGuard<'_, RegenerationMetrics> {
+        self.metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+    
     /// Update actor's resource value
     async fn update_actor_resource(&self, actor: &Actor, resource_name: &str, amount: f64) -> ActorCoreResult<()> {
         // This would update the actor's resource value
@@ -452,14 +565,45 @@ impl ResourceRegenerationManager {
             *entry += task.total_regenerated;
         }
         
+        let metrics = self.metrics();
+        let tick_secs = metrics.tick_time.as_secs_f64();
         Ok(RegenerationStats {
             total_tasks,
             total_regenerated,
             resource_stats,
+            ticks: metrics.ticks,
+            tasks_processed: metrics.tasks_processed,
+            tasks_per_second: if tick_secs > 0.0 { metrics.tasks_processed as f64 / tick_secs } else { 0.0 },
+            catch_ups: metrics.catch_ups,
+            caught_up_total: metrics.caught_up_total,
         })
     }
 }
 
+/// Whether the actor's data marks it as in combat
+fn is_in_combat(actor: &Actor) -> bool {
+    actor.get_data().get("in_combat").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// What one tick regenerated
+#[derive(Debug, Clone, Default)]
+pub struct RegenerationTick {
+    /// Amounts regenerated, one per task that gained anything
+    pub deltas: Vec<RegenerationDelta>,
+    /// Tasks that were regenerated, including those already full
+    pub tasks_processed: usize,
+    /// Tasks stopped because their actor is gone or a condition no longer holds
+    pub tasks_stopped: usize,
+}
+
+/// Amount of a resource regenerated for an actor
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegenerationDelta {
+    pub actor_id: String,
+    pub resource_name: String,
+    pub amount: f64,
+}
+
 /// Regeneration Statistics
 #[derive(Debug, Clone)]
 pub struct RegenerationStats {
@@ -469,6 +613,16 @@ pub struct RegenerationStats {
     pub total_regenerated: f64,
     /// Statistics per resource
     pub resource_stats: HashMap<String, f64>,
+    /// Ticks run
+    pub ticks: u64,
+    /// Tasks regenerated across all ticks
+    pub tasks_processed: u64,
+    /// Tasks regenerated per second of tick processing time
+    pub tasks_per_second: f64,
+    /// Actors caught up after being unloaded
+    pub catch_ups: u64,
+    /// Total amount regenerated by catch-ups
+    pub caught_up_total: f64,
 }
 
 #[async_trait]
@@ -9,6 +9,7 @@ use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
 use crate::enums::{Bucket, CapMode, AcrossLayerPolicy, Operator};
+use crate::snapshot_schema::{LEGACY_SCHEMA_VERSION, SNAPSHOT_SCHEMA_VERSION};
 use crate::ActorCoreResult;
 
 /// Actor represents a character with stats, buffs, and subsystems.
@@ -26,8 +27,14 @@ pub struct Actor {
     pub core_resources: [f64; 9],
     /// Custom resources (flexible HashMap for game-specific resources)
     pub custom_resources: HashMap<String, f64>,
-    /// Subsystems attached to this actor
+    /// Subsystems enabled for this actor on top of its template's defaults
     pub subsystems: Vec<String>,
+    /// Subsystems disabled for this actor, overriding its template's defaults
+    #[serde(default)]
+    pub disabled_subsystems: Vec<String>,
+    /// Actor template selecting the default subsystems, e.g. `cultivator`
+    #[serde(default)]
+    pub template: Option<String>,
     /// Data (for compatibility)
     pub data: HashMap<String, serde_json::Value>,
     /// Version (for compatibility)
@@ -49,6 +56,8 @@ impl Actor {
             core_resources: [100.0, 100.0, 100.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0], // Default values
             custom_resources: HashMap::new(),
             subsystems: Vec::new(),
+            disabled_subsystems: Vec::new(),
+            template: None,
             data: HashMap::new(),
             version: 1,
             created_at: now,
@@ -66,6 +75,22 @@ impl Actor {
         &self.data
     }
 
+    /// Enable a subsystem for this actor, undoing an earlier opt-out
+    pub fn enable_subsystem(&mut self, system_id: &str) {
+        self.disabled_subsystems.retain(|disabled| disabled != system_id);
+        if !self.subsystems.iter().any(|enabled| enabled == system_id) {
+            self.subsystems.push(system_id.to_string());
+        }
+    }
+
+    /// Disable a subsystem for this actor, even when its template enables it
+    pub fn disable_subsystem(&mut self, system_id: &str) {
+        self.subsystems.retain(|enabled| enabled != system_id);
+        if !self.disabled_subsystems.iter().any(|disabled| disabled == system_id) {
+            self.disabled_subsystems.push(system_id.to_string());
+        }
+    }
+
     /// Create a simple actor for testing
     pub fn simple(id: &str, race: &str, level: i64) -> Self {
         let mut actor = Self::new(id.to_string(), race.to_string());
@@ -252,34 +277,52 @@ impl SubsystemOutput {
 }
 
 /// Snapshot represents the final aggregated stat state.
+///
+/// Serialized snapshots are read back with
+/// [`decode_snapshot`](crate::snapshot_schema::decode_snapshot), which upgrades entries
+/// of older schema versions; fields added later need a serde default.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
+    /// Schema version of the serialized form
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     /// Actor ID this snapshot belongs to
     pub actor_id: String,
     /// Primary stat values
     pub primary: HashMap<String, f64>,
     /// Derived stat values
+    #[serde(default)]
     pub derived: HashMap<String, f64>,
     /// Effective caps for each stat
+    #[serde(default)]
     pub caps_used: HashMap<String, Caps>,
     /// Version
     pub version: i64,
     /// Processing metadata
+    #[serde(default)]
     pub processing_time: Option<u64>,
     /// Number of subsystems processed
+    #[serde(default)]
     pub subsystems_processed: Vec<String>,
     /// Cache hit/miss information
+    #[serde(default)]
     pub cache_hit: bool,
     /// Metadata
+    #[serde(default)]
     pub metadata: HashMap<String, serde_json::Value>,
     /// Created timestamp
     pub created_at: DateTime<Utc>,
 }
 
+fn legacy_schema_version() -> u32 {
+    LEGACY_SCHEMA_VERSION
+}
+
 impl Snapshot {
     /// Create a new snapshot
     pub fn new(actor_id: String) -> Self {
         Self {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
             actor_id,
             primary: HashMap::new(),
             derived: HashMap::new(),
@@ -302,6 +345,89 @@ impl Snapshot {
     pub fn set_stat(&mut self, stat_name: String, value: f64) {
         self.primary.insert(stat_name, value);
     }
+
+    /// Hash of the resolved state: actor, version, stats and caps.
+    ///
+    /// Processing metadata and timestamps are left out, so two resolutions of the same
+    /// inputs hash the same regardless of when or how fast they ran.
+    pub fn content_hash(&self) -> String {
+        fn put_stats(bytes: &mut Vec<u8>, stats: &HashMap<String, f64>) {
+            let mut names: Vec<&String> = stats.keys().collect();
+            names.sort();
+            for name in names {
+                put_str(bytes, name);
+                put_f64(bytes, stats[name]);
+            }
+            bytes.push(0xff);
+        }
+        fn put_str(bytes: &mut Vec<u8>, value: &str) {
+            bytes.extend_from_slice(&(value.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(value.as_bytes());
+        }
+        fn put_f64(bytes: &mut Vec<u8>, value: f64) {
+            // -0.0 and 0.0 compare equal, so they must hash equal too
+            let value = if value == 0.0 { 0.0 } else { value };
+            bytes.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+
+        let mut bytes = Vec::new();
+        put_str(&mut bytes, &self.actor_id);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        put_stats(&mut bytes, &self.primary);
+        put_stats(&mut bytes, &self.derived);
+        let mut caps: Vec<(&String, &Caps)> = self.caps_used.iter().collect();
+        caps.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, cap) in caps {
+            put_str(&mut bytes, name);
+            put_f64(&mut bytes, cap.min);
+            put_f64(&mut bytes, cap.max);
+        }
+        format!("{:016x}", seahash::hash(&bytes))
+    }
+}
+
+/// Stats an actor would have with hypothetical contributions, e.g. for an item tooltip.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StatPrediction {
+    /// Actor ID
+    pub actor_id: String,
+    /// Stat values as the actor is now
+    pub current: HashMap<String, f64>,
+    /// Stat values with the hypothetical contributions
+    pub predicted: HashMap<String, f64>,
+    /// Predicted minus current value of every stat that changes; a stat missing on one
+    /// side counts as 0
+    pub delta: HashMap<String, f64>,
+}
+
+impl StatPrediction {
+    /// Compare the snapshots resolved without and with the hypothetical contributions.
+    pub fn between(current: Snapshot, predicted: Snapshot) -> Self {
+        let stats = |snapshot: Snapshot| -> HashMap<String, f64> {
+            snapshot.derived.into_iter().chain(snapshot.primary).collect()
+        };
+        let actor_id = predicted.actor_id.clone();
+        let (current, predicted) = (stats(current), stats(predicted));
+        let delta = current
+            .keys()
+            .chain(predicted.keys())
+            .filter_map(|stat| {
+                let change = predicted.get(stat).unwrap_or(&0.0) - current.get(stat).unwrap_or(&0.0);
+                (change != 0.0).then(|| (stat.clone(), change))
+            })
+            .collect();
+        Self {
+            actor_id,
+            current,
+            predicted,
+            delta,
+        }
+    }
+
+    /// Get the predicted change of a stat, 0 if it does not change.
+    pub fn delta(&self, stat_name: &str) -> f64 {
+        self.delta.get(stat_name).copied().unwrap_or(0.0)
+    }
 }
 
 /// Caps represents the effective min/max constraints for a stat.
@@ -9,8 +9,11 @@ use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 
 // use crate::types::*; // Unused import
+use crate::aggregation_context::AggregationContext;
 use crate::types::Actor;
 use crate::types::Snapshot;
+use crate::types::{Contribution, StatPrediction};
+use crate::snapshot_schema::decode_snapshot;
 // use crate::validation::*; // Unused import
 use crate::ActorCoreResult;
 
@@ -265,7 +268,7 @@ impl crate::interfaces::Aggregator for AggregatorValidationMiddleware {
     async fn resolve_with_context(
         &self,
         actor: &Actor,
-        context: Option<HashMap<String, serde_json::Value>>,
+        context: &AggregationContext,
     ) -> ActorCoreResult<Snapshot> {
         // Validate actor before processing
         let validation_result = self.validate_with_stats(|validator| {
@@ -280,16 +283,14 @@ impl crate::interfaces::Aggregator for AggregatorValidationMiddleware {
         }
 
         // Validate context if provided
-        if let Some(context_map) = &context {
-            if !context_map.is_empty() {
-                let context_validation = self.validate_with_stats(|validator| {
-                    validator.validate(context_map)
-                }).await;
-
-                if !context_validation.is_valid {
-                    warn!("Context validation failed: {:?}", context_validation.errors);
-                    // Context validation failures are warnings, not errors
-                }
+        if !context.is_empty() {
+            let context_validation = self.validate_with_stats(|validator| {
+                validator.validate(context)
+            }).await;
+
+            if !context_validation.is_valid {
+                warn!("Context validation failed: {:?}", context_validation.errors);
+                // Context validation failures are warnings, not errors
             }
         }
 
@@ -312,6 +313,26 @@ impl crate::interfaces::Aggregator for AggregatorValidationMiddleware {
         Ok(snapshot)
     }
 
+    /// Predict actor stats after validating the actor.
+    async fn resolve_hypothetical(
+        &self,
+        actor: &Actor,
+        hypothetical: &[Contribution],
+    ) -> ActorCoreResult<StatPrediction> {
+        let validation_result = self.validate_with_stats(|validator| {
+            validator.validate(actor)
+        }).await;
+
+        if !validation_result.is_valid {
+            error!("Actor validation failed: {:?}", validation_result.errors);
+            return Err(ActorCoreError::InvalidActor(
+                validation_result.first_error().unwrap_or_else(|| "Actor validation failed").to_string()
+            ));
+        }
+
+        self.inner.resolve_hypothetical(actor, hypothetical).await
+    }
+
     async fn resolve_batch(&self, actors: &[Actor]) -> ActorCoreResult<Vec<Snapshot>> {
         let mut results = Vec::with_capacity(actors.len());
         for actor in actors {
@@ -346,6 +367,10 @@ impl crate::interfaces::Aggregator for AggregatorValidationMiddleware {
         self.inner.invalidate_cache(actor_id);
     }
 
+    fn mark_dirty(&self, actor_id: &str, dimensions: &[String]) {
+        self.inner.mark_dirty(actor_id, dimensions);
+    }
+
     fn clear_cache(&self) {
         self.inner.clear_cache();
     }
@@ -431,7 +456,7 @@ impl crate::interfaces::Cache for CacheValidationMiddleware {
 
         // If we got a value, try to validate it as a snapshot
         if let Some(value) = &result {
-            if let Ok(snapshot) = serde_json::from_value::<Snapshot>(value.clone()) {
+            if let Ok(snapshot) = decode_snapshot(value.clone()) {
                 let validation_result = futures::executor::block_on(self.validate_with_stats(|validator| {
                     validator.validate(&snapshot)
                 }));
@@ -464,7 +489,7 @@ impl crate::interfaces::Cache for CacheValidationMiddleware {
         }
 
         // If the value is a snapshot, validate it
-        if let Ok(snapshot) = serde_json::from_value::<Snapshot>(value.clone()) {
+        if let Ok(snapshot) = decode_snapshot(value.clone()) {
             let validation_result = futures::executor::block_on(self.validate_with_stats(|validator| {
                 validator.validate(&snapshot)
             }));
@@ -622,6 +647,16 @@ impl crate::interfaces::PluginRegistry for RegistryValidationMiddleware {
         self.inner.get_by_priority()
     }
 
+    /// Get subsystems enabled for an actor.
+    fn get_for_actor(&self, actor: &crate::types::Actor) -> Vec<Arc<dyn crate::interfaces::Subsystem>> {
+        self.inner.get_for_actor(actor)
+    }
+
+    /// Track resolutions in the wrapped registry.
+    fn begin_resolution(&self) -> crate::interfaces::ResolutionGuard {
+        self.inner.begin_resolution()
+    }
+
     /// Get subsystems by priority range.
     fn get_by_priority_range(&self, min_priority: i64, max_priority: i64) -> Vec<Arc<dyn crate::interfaces::Subsystem>> {
         self.inner.get_by_priority_range(min_priority, max_priority)
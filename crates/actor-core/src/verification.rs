@@ -0,0 +1,193 @@
+//! Server-side verification of resolved snapshots.
+//!
+//! Cached snapshots and the snapshot hashes clients report back are only trusted as far
+//! as they can be reproduced. [`SnapshotVerifier`] re-resolves a sample of actors from
+//! their contributions and compares the result against both: a cached snapshot that
+//! differs points at cache corruption, a client hash that differs points at tampering.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::interfaces::Aggregator;
+use crate::types::{Actor, Snapshot};
+use crate::{ActorCoreError, ActorCoreResult};
+
+/// How many actors are verified and how often
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VerificationConfig {
+    /// Share of actors verified per round, from 0 (none) to 1 (all)
+    pub sample_rate: f64,
+    /// Most actors verified per round, whatever the sample rate
+    pub max_per_round: usize,
+    /// Time between rounds
+    pub interval_secs: u64,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 0.05,
+            max_per_round: 100,
+            interval_secs: 60,
+        }
+    }
+}
+
+impl VerificationConfig {
+    /// Validate the configuration.
+    pub fn validate(&self) -> ActorCoreResult<()> {
+        if !(0.0..=1.0).contains(&self.sample_rate) {
+            return Err(ActorCoreError::ConfigurationError(format!(
+                "sample_rate must be between 0 and 1, got {}",
+                self.sample_rate
+            )));
+        }
+        if self.interval_secs == 0 {
+            return Err(ActorCoreError::ConfigurationError(
+                "interval_secs must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Where a snapshot that could not be reproduced came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MismatchSource {
+    /// The snapshot cache
+    Cache,
+    /// A hash reported by the client
+    Client,
+}
+
+/// A snapshot that does not match a fresh resolution of the same actor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotMismatch {
+    pub actor_id: String,
+    pub source: MismatchSource,
+    /// Hash of the fresh resolution
+    pub expected_hash: String,
+    /// Hash of the cached snapshot or the one reported by the client
+    pub observed_hash: String,
+    /// Stats that differ, when the observed values are known
+    pub stats: Vec<String>,
+}
+
+/// Outcome of a verification round
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerificationReport {
+    /// Actors verified
+    pub sampled: usize,
+    /// Actors that could not be re-resolved
+    pub failed: usize,
+    pub mismatches: Vec<SnapshotMismatch>,
+}
+
+/// Re-resolves sampled actors and compares them against cached and client-reported snapshots
+pub struct SnapshotVerifier {
+    aggregator: Arc<dyn Aggregator>,
+    config: VerificationConfig,
+}
+
+impl SnapshotVerifier {
+    /// Create a verifier resolving through `aggregator`, whose cache is the one checked.
+    pub fn new(aggregator: Arc<dyn Aggregator>, config: VerificationConfig) -> Self {
+        Self { aggregator, config }
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &VerificationConfig {
+        &self.config
+    }
+
+    /// Pick the actors to verify this round at random, per the sampling configuration.
+    pub fn sample<'a>(&self, actors: &'a [Actor]) -> Vec<&'a Actor> {
+        let wanted = (actors.len() as f64 * self.config.sample_rate.clamp(0.0, 1.0)).ceil() as usize;
+        let amount = wanted.min(self.config.max_per_round).min(actors.len());
+        rand::seq::index::sample(&mut rand::thread_rng(), actors.len(), amount)
+            .into_iter()
+            .map(|index| &actors[index])
+            .collect()
+    }
+
+    /// Re-resolve an actor and compare it against its cached snapshot and, if given, the
+    /// hash its client reported.
+    ///
+    /// The fresh resolution replaces the cached snapshot, so a corrupted entry is repaired
+    /// as it is found. Cached snapshots of an older actor version are stale rather than
+    /// corrupted and are not reported.
+    pub async fn verify(&self, actor: &Actor, client_hash: Option<&str>) -> ActorCoreResult<Vec<SnapshotMismatch>> {
+        let cached = self.aggregator.get_cached_snapshot(&actor.id);
+        self.aggregator.invalidate_cache(&actor.id);
+        let fresh = self.aggregator.resolve(actor).await?;
+        let expected_hash = fresh.content_hash();
+
+        let mut mismatches = Vec::new();
+        if let Some(cached) = cached {
+            let observed_hash = cached.content_hash();
+            if cached.version != fresh.version {
+                debug!("Cached snapshot of {} is stale (v{} < v{})", actor.id, cached.version, fresh.version);
+            } else if observed_hash != expected_hash {
+                mismatches.push(SnapshotMismatch {
+                    actor_id: actor.id.clone(),
+                    source: MismatchSource::Cache,
+                    expected_hash: expected_hash.clone(),
+                    observed_hash,
+                    stats: differing_stats(&cached, &fresh),
+                });
+            }
+        }
+        if let Some(client_hash) = client_hash.filter(|hash| *hash != expected_hash) {
+            mismatches.push(SnapshotMismatch {
+                actor_id: actor.id.clone(),
+                source: MismatchSource::Client,
+                expected_hash,
+                observed_hash: client_hash.to_string(),
+                stats: Vec::new(),
+            });
+        }
+
+        for mismatch in &mismatches {
+            warn!(
+                "Snapshot mismatch for {} ({:?}): expected {}, found {}",
+                mismatch.actor_id, mismatch.source, mismatch.expected_hash, mismatch.observed_hash
+            );
+        }
+        Ok(mismatches)
+    }
+
+    /// Verify a sample of `actors`, comparing against client hashes by actor ID where known.
+    pub async fn run(&self, actors: &[Actor], client_hashes: &HashMap<String, String>) -> VerificationReport {
+        let mut report = VerificationReport::default();
+        for actor in self.sample(actors) {
+            report.sampled += 1;
+            let client_hash = client_hashes.get(&actor.id).map(String::as_str);
+            match self.verify(actor, client_hash).await {
+                Ok(mismatches) => report.mismatches.extend(mismatches),
+                Err(e) => {
+                    warn!("Failed to re-resolve {} for verification: {}", actor.id, e);
+                    report.failed += 1;
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Primary and derived stats whose values differ between two snapshots, sorted by name
+fn differing_stats(observed: &Snapshot, expected: &Snapshot) -> Vec<String> {
+    let mut stats = BTreeSet::new();
+    for (observed, expected) in [(&observed.primary, &expected.primary), (&observed.derived, &expected.derived)] {
+        for name in observed.keys().chain(expected.keys()) {
+            if observed.get(name) != expected.get(name) {
+                stats.insert(name.clone());
+            }
+        }
+    }
+    stats.into_iter().collect()
+}
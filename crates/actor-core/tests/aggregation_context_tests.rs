@@ -0,0 +1,104 @@
+//! Tests for typed aggregation contexts and their cache separation.
+
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Strength rises by 5 in combat, scaled by the `blood_moon` event
+struct BattleSubsystem;
+
+#[async_trait]
+impl actor_core::interfaces::Subsystem for BattleSubsystem {
+    fn system_id(&self) -> &str {
+        "battle"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let combat_bonus = if context.in_combat { 5.0 } else { 0.0 };
+        let strength = 10.0 + combat_bonus * context.event_modifier("blood_moon").unwrap_or(1.0);
+        let mut output = SubsystemOutput::new("battle".to_string());
+        output.add_contribution(Contribution::new("strength".to_string(), Bucket::Flat, strength, "battle".to_string()));
+        Ok(output)
+    }
+}
+
+fn aggregator() -> Arc<dyn Aggregator> {
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(Arc::new(BattleSubsystem)).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    combiner
+        .set_rule(
+            "strength",
+            MergeRule {
+                use_pipeline: true,
+                operator: Operator::Sum,
+                clamp_default: None,
+            },
+        )
+        .unwrap();
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    ServiceFactory::create_aggregator(plugins, combiner, caps_provider, CacheFactory::create_in_memory_cache(1024, 60))
+}
+
+#[tokio::test]
+async fn subsystems_receive_the_context() {
+    let aggregator = aggregator();
+    let actor = Actor::new("hero".to_string(), "Human".to_string());
+
+    let context = AggregationContext::new().with_combat(true).with_event_modifier("blood_moon", 2.0);
+    let snapshot = aggregator.resolve_with_context(&actor, &context).await.unwrap();
+    assert_eq!(snapshot.get_stat("strength"), Some(20.0));
+}
+
+#[tokio::test]
+async fn context_snapshots_do_not_poison_the_cache() {
+    let aggregator = aggregator();
+    let actor = Actor::new("hero".to_string(), "Human".to_string());
+    let combat = AggregationContext::new().with_zone("arena").with_combat(true);
+
+    assert_eq!(aggregator.resolve_with_context(&actor, &combat).await.unwrap().get_stat("strength"), Some(15.0));
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("strength"), Some(10.0));
+    assert_eq!(aggregator.resolve_with_context(&actor, &combat).await.unwrap().get_stat("strength"), Some(15.0));
+    assert_eq!(aggregator.get_cached_snapshot(&actor.id).unwrap().get_stat("strength"), Some(10.0));
+
+    aggregator.invalidate_cache(&actor.id);
+    assert!(aggregator.get_cached_snapshot(&actor.id).is_none());
+    let metrics_before = aggregator.get_metrics().await;
+    aggregator.resolve_with_context(&actor, &combat).await.unwrap();
+    // The context snapshot was invalidated with the actor and resolved again
+    assert_eq!(aggregator.get_metrics().await.cache_hits, metrics_before.cache_hits);
+}
+
+#[test]
+fn cache_keys_separate_contexts() {
+    let empty = AggregationContext::new();
+    let arena = AggregationContext::new().with_zone("arena");
+    assert_eq!(empty.cache_key("hero"), "hero");
+    assert_ne!(arena.cache_key("hero"), "hero");
+    assert_ne!(arena.cache_key("hero"), arena.clone().with_combat(true).cache_key("hero"));
+    assert_eq!(arena.cache_key("hero"), AggregationContext::new().with_zone("arena").cache_key("hero"));
+}
+
+#[test]
+fn contexts_are_built_from_api_maps() {
+    let map: HashMap<String, serde_json::Value> = serde_json::from_value(json!({
+        "game_time": "2026-01-01T12:00:00Z",
+        "zone_id": "forest",
+        "in_combat": true,
+        "event_modifiers": { "double_exp": 2.0 }
+    }))
+    .unwrap();
+    let context = AggregationContext::from_map(map).unwrap();
+    assert_eq!(context.zone_id.as_deref(), Some("forest"));
+    assert!(context.in_combat);
+    assert_eq!(context.event_modifier("double_exp"), Some(2.0));
+
+    let unknown: HashMap<String, serde_json::Value> = serde_json::from_value(json!({ "zone": "forest" })).unwrap();
+    assert!(matches!(AggregationContext::from_map(unknown), Err(ActorCoreError::InvalidInput(_))));
+}
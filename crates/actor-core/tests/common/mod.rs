@@ -0,0 +1,13 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use chrono::{TimeZone, Utc};
+use shared::clock::SimulatedClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Clock starting at a fixed time and ticking one second at a time
+pub fn create_test_clock() -> Arc<SimulatedClock> {
+    Arc::new(SimulatedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap(), Duration::from_secs(1)))
+}
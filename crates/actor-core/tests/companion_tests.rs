@@ -0,0 +1,121 @@
+//! Tests for resolving companions from their owners' stats.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::companion::companion_actor;
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use shared::companion::{CompanionConfig, CompanionManager};
+use shared::wall_clock;
+use std::sync::Mutex;
+
+const INHERITANCE: &str = r#"
+inherit:
+  - id: attack_power
+    formula: owner.attack_power * 0.5 + level * 2
+  - id: max_hp
+    formula: owner.max_hp * 0.3
+species:
+  turtle:
+    - id: max_hp
+      formula: owner.max_hp * 0.6 + owner.level
+"#;
+
+/// Owner stats held by the test
+#[derive(Default)]
+struct Training {
+    stats: Mutex<HashMap<&'static str, f64>>,
+}
+
+#[async_trait]
+impl Subsystem for Training {
+    fn system_id(&self) -> &str {
+        "training"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("training".to_string());
+        for (&stat, &value) in self.stats.lock().unwrap().iter() {
+            output.add_contribution(Contribution::new(stat.to_string(), Bucket::Flat, value, "training".to_string()));
+        }
+        Ok(output)
+    }
+}
+
+fn aggregator(subsystem: Arc<dyn Subsystem>) -> Arc<dyn Aggregator> {
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(subsystem).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    for dimension in ["attack_power", "max_hp"] {
+        let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+        combiner.set_rule(dimension, rule).unwrap();
+    }
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    Arc::new(AggregatorImpl::new(plugins, combiner, caps_provider, cache))
+}
+
+fn create_test_training() -> Arc<Training> {
+    let training = Arc::new(Training::default());
+    training.stats.lock().unwrap().extend([("attack_power", 100.0), ("max_hp", 1000.0)]);
+    training
+}
+
+fn create_test_pipeline(companions: &Arc<CompanionManager>, training: &Arc<Training>) -> CompanionPipeline {
+    let inheritance = CompanionInheritance::from_yaml(INHERITANCE).unwrap();
+    let subsystem = Arc::new(CompanionSubsystem::new(companions.clone(), inheritance));
+    CompanionPipeline::new(aggregator(training.clone()), aggregator(subsystem.clone()), subsystem)
+}
+
+#[tokio::test]
+async fn companions_inherit_from_their_owner_and_level() {
+    let companions = Arc::new(CompanionManager::new(wall_clock(), CompanionConfig::default()));
+    let training = create_test_training();
+    let pipeline = create_test_pipeline(&companions, &training);
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    let turtle = companions.adopt("hero", "turtle", "Shell", "beast").await.unwrap();
+    let hero = Actor::simple("hero", "Human", 20);
+
+    let snapshot = pipeline.resolve(&hero, &wolf.id).await.unwrap();
+    assert_eq!(snapshot.get_stat("attack_power"), Some(52.0));
+    assert_eq!(snapshot.get_stat("max_hp"), Some(300.0));
+    // Species formulas replace the common ones
+    assert_eq!(pipeline.resolve(&hero, &turtle.id).await.unwrap().get_stat("max_hp"), Some(620.0));
+
+    // Levels apply at once, while the owner's cached snapshot is kept until invalidated
+    training.stats.lock().unwrap().insert("attack_power", 200.0);
+    let wolf = companions.grant_experience(&wolf.id, 100.0).await.unwrap();
+    assert_eq!(companion_actor(&wolf).level, 2);
+    assert_eq!(pipeline.resolve(&hero, &wolf.id).await.unwrap().get_stat("attack_power"), Some(54.0));
+}
+
+#[tokio::test]
+async fn only_active_companions_are_resolved_with_their_owner() {
+    let companions = Arc::new(CompanionManager::new(wall_clock(), CompanionConfig::default()));
+    let training = create_test_training();
+    let pipeline = create_test_pipeline(&companions, &training);
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    companions.adopt("hero", "turtle", "Shell", "beast").await.unwrap();
+    let hero = Actor::simple("hero", "Human", 20);
+    assert!(pipeline.resolve_active(&hero).await.unwrap().is_empty());
+
+    companions.summon("hero", &wolf.id).await.unwrap();
+    let snapshots = pipeline.resolve_active(&hero).await.unwrap();
+    assert_eq!(snapshots.iter().map(|snapshot| snapshot.actor_id.as_str()).collect::<Vec<_>>(), [wolf.id.as_str()]);
+
+    // Other owners cannot resolve someone else's companion
+    let rival = Actor::simple("rival", "Human", 20);
+    assert!(matches!(pipeline.resolve(&rival, &wolf.id).await, Err(ActorCoreError::InvalidInput(_))));
+}
+
+#[test]
+fn inheritance_formulas_only_read_level_and_owner_stats() {
+    let config = CompanionInheritanceConfig {
+        inherit: vec![DerivedStatDefinition { id: "speed".to_string(), formula: "agility * 2".to_string() }],
+        species: HashMap::new(),
+    };
+    assert!(matches!(CompanionInheritance::from_config(&config), Err(ActorCoreError::ConfigurationError(_))));
+}
@@ -0,0 +1,139 @@
+//! Tests for computing derived stats from formulas over the primary stats.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn definition(id: &str, formula: &str) -> DerivedStatDefinition {
+    DerivedStatDefinition { id: id.to_string(), formula: formula.to_string() }
+}
+
+fn evaluate(formula: &str, stats: &[(&str, f64)]) -> f64 {
+    let stats: HashMap<&str, f64> = stats.iter().copied().collect();
+    Formula::parse(formula).unwrap().evaluate(&|name| stats.get(name).copied().unwrap_or(0.0))
+}
+
+#[test]
+fn formulas_follow_operator_precedence() {
+    assert_eq!(evaluate("vitality * 10 + level * 5", &[("vitality", 12.0), ("level", 3.0)]), 135.0);
+    assert_eq!(evaluate("(1 + 2) * 3 - 8 / 4", &[]), 7.0);
+    assert_eq!(evaluate("-2 ^ 2 + 2 ^ 3 ^ 2", &[]), 508.0);
+    assert_eq!(evaluate("clamp(agility * 0.01, 0, 0.5) + max(1, 2, 3)", &[("agility", 80.0)]), 3.5);
+    assert_eq!(evaluate("floor(sqrt(wisdom)) + abs(-1)", &[("wisdom", 17.0)]), 5.0);
+
+    let formula = Formula::parse("max(strength, agility) * 2 + level").unwrap();
+    assert_eq!(formula.stats().collect::<Vec<_>>(), ["agility", "level", "strength"]);
+
+    for invalid in ["1 +", "(1 + 2", "unknown(1)", "min(1)", "2 $ 3", "1 2"] {
+        assert!(matches!(Formula::parse(invalid), Err(ActorCoreError::ConfigurationError(_))), "{}", invalid);
+    }
+}
+
+#[test]
+fn derived_stats_are_ordered_by_dependency() {
+    let derived = DerivedStats::from_definitions(&[
+        definition("effective_hp", "max_hp * (1 + defense / 100)"),
+        definition("max_hp", "vitality * 10 + level * 5"),
+        definition("regen", "max_hp / 0"),
+    ])
+    .unwrap();
+    let order = derived.order();
+    let position = |id: &str| order.iter().position(|stat| stat == id).unwrap();
+    assert!(position("max_hp") < position("effective_hp"));
+
+    let actor = Actor::simple("hero", "Human", 4);
+    let primary = HashMap::from([("vitality".to_string(), 12.0), ("defense".to_string(), 50.0)]);
+    let stats = derived.evaluate(&actor, &primary);
+    assert_eq!(stats["max_hp"], 140.0);
+    assert_eq!(stats["effective_hp"], 210.0);
+    // Division by zero does not leak infinities into snapshots
+    assert_eq!(stats["regen"], 0.0);
+}
+
+#[test]
+fn cycles_and_duplicates_are_rejected() {
+    let cycle = DerivedStats::from_definitions(&[
+        definition("a", "b + 1"),
+        definition("b", "c * 2"),
+        definition("c", "a"),
+    ]);
+    match cycle {
+        Err(ActorCoreError::ConfigurationError(message)) => {
+            assert!(message.contains("a -> b -> c -> a"), "{}", message)
+        }
+        other => panic!("expected a cycle error, got {:?}", other.map(|_| ())),
+    }
+    assert!(DerivedStats::from_definitions(&[definition("a", "a + 1")]).is_err());
+    assert!(DerivedStats::from_definitions(&[definition("a", "1"), definition("a", "2")]).is_err());
+
+    // A failed reload keeps the previous definitions
+    let derived = DerivedStats::from_definitions(&[definition("max_hp", "vitality * 10")]).unwrap();
+    assert!(derived.load(&[definition("max_hp", "max_hp * 2")]).is_err());
+    assert_eq!(derived.order(), ["max_hp"]);
+}
+
+#[test]
+fn compiled_formulas_are_reused_across_reloads() {
+    let derived = DerivedStats::from_yaml(
+        r#"
+derived_stats:
+  - id: "max_hp"
+    formula: "vitality * 10"
+  - id: "max_mana"
+    formula: "intelligence * 8"
+"#,
+    )
+    .unwrap();
+    assert_eq!(derived.compiled_formulas(), 2);
+
+    derived
+        .load(&[definition("max_hp", "vitality * 10"), definition("max_stamina", "vitality * 10")])
+        .unwrap();
+    // Both stats share one compiled formula, and the unused one is dropped
+    assert_eq!(derived.compiled_formulas(), 1);
+}
+
+/// Primary stats of a sturdy fighter
+struct Fighter;
+
+#[async_trait]
+impl Subsystem for Fighter {
+    fn system_id(&self) -> &str {
+        "fighter"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("fighter".to_string());
+        output.add_contribution(Contribution::new("vitality".to_string(), Bucket::Flat, 12.0, "fighter".to_string()));
+        Ok(output)
+    }
+}
+
+#[tokio::test]
+async fn snapshots_include_derived_stats() {
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(Arc::new(Fighter)).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    let rule = MergeRule {
+        use_pipeline: true,
+        operator: Operator::Sum,
+        clamp_default: None,
+    };
+    combiner.set_rule("vitality", rule).unwrap();
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let derived = DerivedStats::from_definitions(&[definition("max_hp", "vitality * 10 + level * 5")]).unwrap();
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    let aggregator =
+        AggregatorImpl::new(plugins, combiner, caps_provider, cache).with_derived_stats(Arc::new(derived));
+
+    let snapshot = aggregator.resolve(&Actor::simple("hero", "Human", 4)).await.unwrap();
+    assert_eq!(snapshot.get_stat("vitality"), Some(12.0));
+    assert_eq!(snapshot.derived.get("max_hp"), Some(&140.0));
+}
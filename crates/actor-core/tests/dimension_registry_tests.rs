@@ -0,0 +1,152 @@
+//! Tests for dimension ownership and contributions to undeclared dimensions.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Owns some dimensions and contributes to others
+struct Owner {
+    id: &'static str,
+    owns: Vec<DimensionDeclaration>,
+    contributes: Vec<(&'static str, f64)>,
+}
+
+#[async_trait]
+impl Subsystem for Owner {
+    fn system_id(&self) -> &str {
+        self.id
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(self.id.to_string());
+        for &(dimension, value) in &self.contributes {
+            let contribution = Contribution::new(dimension.to_string(), Bucket::Flat, value, self.id.to_string());
+            output.add_contribution(contribution);
+        }
+        Ok(output)
+    }
+
+    fn dimensions(&self) -> Vec<DimensionDeclaration> {
+        self.owns.clone()
+    }
+}
+
+fn stats() -> Arc<dyn Subsystem> {
+    Arc::new(Owner {
+        id: "stats",
+        owns: vec![
+            DimensionDeclaration::new("strength", DimensionKind::Stat),
+            DimensionDeclaration::new("crit_chance", DimensionKind::Ratio).with_range(0.0, 1.0),
+        ],
+        contributes: vec![("strength", 10.0), ("crit_chance", 0.8)],
+    })
+}
+
+fn gear(contributes: Vec<(&'static str, f64)>) -> Arc<dyn Subsystem> {
+    Arc::new(Owner { id: "gear", owns: Vec::new(), contributes })
+}
+
+fn setup(policy: UnknownDimensionPolicy, subsystems: Vec<Arc<dyn Subsystem>>) -> AggregatorImpl {
+    let dimensions = Arc::new(DimensionRegistry::new(policy));
+    let plugins = PluginRegistryImpl::new().with_dimension_registry(dimensions.clone());
+    for subsystem in subsystems {
+        plugins.register(subsystem).unwrap();
+    }
+    let combiner = RegistryFactory::create_combiner_registry();
+    for dimension in ["strength", "crit_chance", "luck"] {
+        let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+        combiner.set_rule(dimension, rule).unwrap();
+    }
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    AggregatorImpl::new(Arc::new(plugins), combiner, caps_provider, cache).with_dimension_registry(dimensions)
+}
+
+#[test]
+fn conflicting_ownership_is_rejected_at_registration() {
+    let dimensions = Arc::new(DimensionRegistry::default());
+    let plugins = PluginRegistryImpl::new().with_dimension_registry(dimensions.clone());
+    plugins.register(stats()).unwrap();
+    assert_eq!(dimensions.owner("strength").as_deref(), Some("stats"));
+
+    let rival = Arc::new(Owner {
+        id: "talents",
+        owns: vec![
+            DimensionDeclaration::new("willpower", DimensionKind::Stat),
+            DimensionDeclaration::new("strength", DimensionKind::Stat),
+        ],
+        contributes: Vec::new(),
+    });
+    match plugins.register(rival) {
+        Err(ActorCoreError::RegistryError(message)) => assert!(message.contains("owned by stats"), "{}", message),
+        other => panic!("expected an ownership conflict, got {:?}", other),
+    }
+    // Nothing of the rejected subsystem is registered
+    assert!(!plugins.is_registered("talents"));
+    assert!(dimensions.get("willpower").is_none());
+
+    // Declaring a dimension twice or with an inverted range is rejected too
+    let strength = DimensionDeclaration::new("might", DimensionKind::Stat);
+    assert!(dimensions.declare("talents", vec![strength.clone(), strength]).is_err());
+    let inverted = DimensionDeclaration::new("might", DimensionKind::Stat).with_range(10.0, 0.0);
+    assert!(dimensions.declare("talents", vec![inverted]).is_err());
+}
+
+#[test]
+fn redeclaring_replaces_and_unregistering_releases() {
+    let dimensions = Arc::new(DimensionRegistry::default());
+    let plugins = PluginRegistryImpl::new().with_dimension_registry(dimensions.clone());
+    plugins.register(stats()).unwrap();
+    assert_eq!(dimensions.len(), 2);
+
+    // Declaring again replaces the previous declarations, as does registering again
+    let health = DimensionDeclaration::new("health", DimensionKind::Resource).with_unit("hp");
+    dimensions.declare("stats", vec![health.clone()]).unwrap();
+    assert_eq!(dimensions.owned_by("stats"), [health]);
+
+    plugins.register(stats()).unwrap();
+    let owned: Vec<String> = dimensions.owned_by("stats").into_iter().map(|declaration| declaration.id).collect();
+    assert_eq!(owned, ["crit_chance", "strength"]);
+
+    // Released dimensions can be taken by another subsystem
+    plugins.unregister("stats").unwrap();
+    assert!(dimensions.is_empty());
+    dimensions.declare("talents", vec![DimensionDeclaration::new("strength", DimensionKind::Stat)]).unwrap();
+    assert_eq!(dimensions.owner("strength").as_deref(), Some("talents"));
+}
+
+#[tokio::test]
+async fn undeclared_dimensions_follow_the_policy() {
+    let actor = Actor::simple("hero", "Human", 1);
+
+    // Contributing to another subsystem's dimension is fine under any policy
+    let aggregator = setup(UnknownDimensionPolicy::Reject, vec![stats(), gear(vec![("strength", 5.0)])]);
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("strength"), Some(15.0));
+
+    let aggregator = setup(UnknownDimensionPolicy::Reject, vec![stats(), gear(vec![("luck", 3.0)])]);
+    match aggregator.resolve(&actor).await {
+        Err(ActorCoreError::InvalidContribution(message)) => {
+            assert!(message.contains("gear contributes to undeclared dimensions: luck"), "{}", message)
+        }
+        other => panic!("expected an undeclared dimension error, got {:?}", other.map(|_| ())),
+    }
+
+    for policy in [UnknownDimensionPolicy::Warn, UnknownDimensionPolicy::Allow] {
+        let aggregator = setup(policy, vec![stats(), gear(vec![("luck", 3.0)])]);
+        assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("luck"), Some(3.0));
+    }
+}
+
+#[tokio::test]
+async fn stats_are_clamped_to_their_declared_range() {
+    let aggregator = setup(UnknownDimensionPolicy::Warn, vec![stats(), gear(vec![("crit_chance", 0.5)])]);
+    let snapshot = aggregator.resolve(&Actor::simple("hero", "Human", 1)).await.unwrap();
+    assert_eq!(snapshot.get_stat("crit_chance"), Some(1.0));
+    assert_eq!(snapshot.get_stat("strength"), Some(10.0));
+}
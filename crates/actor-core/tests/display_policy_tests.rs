@@ -0,0 +1,71 @@
+//! Tests for rounding and formatting stats for display.
+
+use actor_core::display_policy::MAX_DISPLAY_PRECISION;
+use actor_core::prelude::*;
+use actor_core::registry::loader::parse_combiner;
+
+#[test]
+fn policies_round_and_format_values() {
+    let whole = DisplayPolicy::integer();
+    assert_eq!((whole.apply(1249.5), whole.format(1249.5)), (1250.0, "1250".to_string()));
+    let floored = whole.with_rounding(Rounding::Floor);
+    assert_eq!(floored.format(1249.9), "1249");
+    assert_eq!(DisplayPolicy { precision: 2, ..floored }.format(3.14159), "3.14");
+
+    let crit = DisplayPolicy::percentage(2);
+    assert_eq!(crit.apply(0.15347), 0.1535);
+    assert_eq!(crit.format(0.15347), "15.35%");
+    // Float error does not push exact values over a rounding edge
+    let floored_crit = DisplayPolicy::percentage(0).with_rounding(Rounding::Floor);
+    assert_eq!(floored_crit.format(0.29), "29%");
+    assert_eq!(DisplayPolicy::percentage(0).with_rounding(Rounding::Ceil).format(0.57), "57%");
+
+    let too_precise = DisplayPolicy { precision: MAX_DISPLAY_PRECISION + 1, ..DisplayPolicy::integer() };
+    assert!(too_precise.validate().is_err());
+}
+
+#[test]
+fn snapshots_keep_full_precision_until_displayed() {
+    let combiner = RegistryFactory::create_combiner_registry();
+    combiner.set_display_policy("hp_max", DisplayPolicy::integer().with_rounding(Rounding::Floor)).unwrap();
+    combiner.set_display_policy("crit_chance", DisplayPolicy::percentage(1)).unwrap();
+
+    let mut snapshot = Snapshot::new("hero".to_string());
+    snapshot.set_stat("hp_max".to_string(), 1520.8);
+    snapshot.set_stat("crit_chance".to_string(), 0.12345);
+    snapshot.set_stat("strength".to_string(), 10.25);
+    let full = snapshot.clone();
+
+    let formatted = display_snapshot(&mut snapshot, combiner.as_ref());
+    assert_eq!(snapshot.get_stat("hp_max"), Some(1520.0));
+    assert_eq!(snapshot.get_stat("crit_chance"), Some(0.123));
+    assert_eq!(snapshot.get_stat("strength"), Some(10.25));
+    assert_eq!(formatted["crit_chance"], "12.3%");
+    assert_eq!(formatted.len(), 2);
+    assert_eq!(full.get_stat("hp_max"), Some(1520.8));
+}
+
+#[test]
+fn combiner_configs_carry_display_policies() {
+    let config = r#"
+rules:
+  - id: "hp_max"
+    bucket_order: ["FLAT", "MULT"]
+    clamp: { min: 1, max: 999999 }
+    display: { rounding: floor }
+  - id: "crit_chance"
+    bucket_order: ["FLAT"]
+    clamp: { min: 0, max: 1 }
+    display: { precision: 2, percentage: true }
+  - id: "attack"
+    bucket_order: ["FLAT"]
+    clamp: { min: 0, max: 99999 }
+"#;
+    let combiner = parse_combiner(config).unwrap();
+    assert_eq!(combiner.get_display_policy("crit_chance"), Some(DisplayPolicy::percentage(2)));
+    assert_eq!(combiner.get_display_policy("hp_max").map(|policy| policy.rounding), Some(Rounding::Floor));
+    assert_eq!(combiner.get_display_policy("attack"), None);
+
+    let invalid = config.replace("precision: 2", "precision: 9");
+    assert!(parse_combiner(&invalid).is_err());
+}
@@ -0,0 +1,134 @@
+//! Tests for loading and unloading subsystems at runtime.
+
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Contributes a fixed strength, standing in for one version of a game system
+struct Training {
+    id: &'static str,
+    strength: f64,
+}
+
+#[async_trait]
+impl Subsystem for Training {
+    fn system_id(&self) -> &str {
+        self.id
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(self.id.to_string());
+        let strength = Contribution::new("strength".to_string(), Bucket::Flat, self.strength, self.id.to_string());
+        output.add_contribution(strength);
+        Ok(output)
+    }
+}
+
+fn training(id: &'static str, strength: f64) -> Arc<dyn Subsystem> {
+    Arc::new(Training { id, strength })
+}
+
+fn setup(drain_timeout: Duration) -> (Arc<HotReloadRegistry>, Arc<dyn Aggregator>) {
+    let plugins = Arc::new(HotReloadRegistry::new(HotReloadConfig { drain_timeout }));
+    let combiner = RegistryFactory::create_combiner_registry();
+    combiner
+        .set_rule(
+            "strength",
+            MergeRule {
+                use_pipeline: true,
+                operator: Operator::Sum,
+                clamp_default: None,
+            },
+        )
+        .unwrap();
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    let aggregator = ServiceFactory::create_aggregator(plugins.clone(), combiner, caps_provider, cache);
+    (plugins, aggregator)
+}
+
+#[tokio::test]
+async fn newer_versions_replace_subsystems_and_invalidate_their_actors() {
+    let (plugins, aggregator) = setup(Duration::from_secs(1));
+    let hero = Actor::new("hero".to_string(), "Human".to_string());
+
+    let loaded = plugins.load(training("training", 10.0), 1, aggregator.as_ref()).await.unwrap();
+    assert_eq!((loaded.version, loaded.previous, loaded.drained), (Some(1), None, true));
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("strength"), Some(10.0));
+    assert!(aggregator.get_cached_snapshot(&hero.id).is_some());
+
+    let upgraded = plugins.load(training("training", 15.0), 2, aggregator.as_ref()).await.unwrap();
+    assert_eq!((upgraded.previous, upgraded.invalidated), (Some(1), 1));
+    assert!(aggregator.get_cached_snapshot(&hero.id).is_none());
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("strength"), Some(15.0));
+
+    // Versions only move forward
+    for version in [1, 2] {
+        let stale = plugins.load(training("training", 5.0), version, aggregator.as_ref()).await;
+        assert!(matches!(stale, Err(ActorCoreError::RegistryError(_))));
+    }
+    assert_eq!(plugins.version("training"), Some(2));
+}
+
+#[tokio::test]
+async fn new_and_unloaded_subsystems_invalidate_resolved_actors() {
+    let (plugins, aggregator) = setup(Duration::from_secs(1));
+    let hero = Actor::new("hero".to_string(), "Human".to_string());
+    plugins.load(training("training", 10.0), 1, aggregator.as_ref()).await.unwrap();
+    aggregator.resolve(&hero).await.unwrap();
+
+    // Any resolved actor may enable a subsystem it has not seen yet
+    let added = plugins.load(training("mentoring", 3.0), 1, aggregator.as_ref()).await.unwrap();
+    assert_eq!(added.invalidated, 1);
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("strength"), Some(13.0));
+
+    let unloaded = plugins.unload("mentoring", aggregator.as_ref()).await.unwrap();
+    assert_eq!((unloaded.version, unloaded.previous, unloaded.invalidated), (None, Some(1), 1));
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("strength"), Some(10.0));
+    assert_eq!(plugins.versions().len(), 1);
+    assert!(matches!(
+        plugins.unload("mentoring", aggregator.as_ref()).await,
+        Err(ActorCoreError::RegistryError(_))
+    ));
+}
+
+#[tokio::test]
+async fn swaps_wait_for_resolutions_in_flight() {
+    let (plugins, aggregator) = setup(Duration::from_secs(5));
+    plugins.load(training("training", 10.0), 1, aggregator.as_ref()).await.unwrap();
+
+    let resolution = plugins.begin_resolution();
+    assert_eq!(plugins.in_flight(), 1);
+    let swap = {
+        let (plugins, aggregator) = (plugins.clone(), aggregator.clone());
+        tokio::spawn(async move { plugins.load(training("training", 15.0), 2, aggregator.as_ref()).await })
+    };
+    // The new version is in place while the swap drains
+    while plugins.version("training") != Some(2) {
+        tokio::task::yield_now().await;
+    }
+    assert!(!swap.is_finished());
+
+    // Resolutions started after the swap are not waited for
+    let _later = plugins.begin_resolution();
+    drop(resolution);
+    assert!(swap.await.unwrap().unwrap().drained);
+    assert_eq!(plugins.in_flight(), 1);
+}
+
+#[tokio::test]
+async fn swaps_give_up_draining_after_the_timeout() {
+    let (plugins, aggregator) = setup(Duration::from_millis(20));
+    plugins.load(training("training", 10.0), 1, aggregator.as_ref()).await.unwrap();
+
+    let _stuck = plugins.begin_resolution();
+    let swapped = plugins.load(training("training", 15.0), 2, aggregator.as_ref()).await.unwrap();
+    assert!(!swapped.drained);
+    assert_eq!(plugins.version("training"), Some(2));
+}
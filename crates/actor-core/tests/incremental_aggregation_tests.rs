@@ -0,0 +1,180 @@
+//! Tests for re-aggregating only the dimensions marked dirty.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Contributes whatever stats it currently holds
+#[derive(Default)]
+struct Equipment {
+    stats: Mutex<HashMap<&'static str, f64>>,
+}
+
+impl Equipment {
+    fn set(&self, stat: &'static str, value: f64) {
+        self.stats.lock().unwrap().insert(stat, value);
+    }
+
+    fn remove(&self, stat: &'static str) {
+        self.stats.lock().unwrap().remove(stat);
+    }
+}
+
+#[async_trait]
+impl Subsystem for Equipment {
+    fn system_id(&self) -> &str {
+        "equipment"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("equipment".to_string());
+        for (&stat, &value) in self.stats.lock().unwrap().iter() {
+            output.add_contribution(Contribution::new(stat.to_string(), Bucket::Flat, value, "equipment".to_string()));
+        }
+        Ok(output)
+    }
+}
+
+/// Contributes agility, counting how often it is asked to
+#[derive(Default)]
+struct Talents {
+    calls: AtomicUsize,
+}
+
+#[async_trait]
+impl Subsystem for Talents {
+    fn system_id(&self) -> &str {
+        "talents"
+    }
+
+    fn priority(&self) -> i64 {
+        50
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let mut output = SubsystemOutput::new("talents".to_string());
+        output.add_contribution(Contribution::new("agility".to_string(), Bucket::Flat, 3.0, "talents".to_string()));
+        Ok(output)
+    }
+}
+
+fn setup(subsystems: Vec<Arc<dyn Subsystem>>) -> AggregatorImpl {
+    let plugins = RegistryFactory::create_plugin_registry();
+    for subsystem in subsystems {
+        plugins.register(subsystem).unwrap();
+    }
+    let combiner = RegistryFactory::create_combiner_registry();
+    for dimension in ["strength", "agility", "vitality"] {
+        let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+        combiner.set_rule(dimension, rule).unwrap();
+    }
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let derived = DerivedStats::from_definitions(&[DerivedStatDefinition {
+        id: "max_hp".to_string(),
+        formula: "vitality * 10".to_string(),
+    }])
+    .unwrap();
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    AggregatorImpl::new(plugins, combiner, caps_provider, cache).with_derived_stats(Arc::new(derived))
+}
+
+fn dirty(dimensions: &[&str]) -> Vec<String> {
+    dimensions.iter().map(|dimension| dimension.to_string()).collect()
+}
+
+#[tokio::test]
+async fn only_dirty_dimensions_are_reaggregated() {
+    let equipment = Arc::new(Equipment::default());
+    equipment.set("strength", 10.0);
+    equipment.set("agility", 5.0);
+    let aggregator = setup(vec![equipment.clone()]);
+    let actor = Actor::simple("hero", "Human", 1);
+    aggregator.resolve(&actor).await.unwrap();
+
+    equipment.set("strength", 12.0);
+    equipment.set("agility", 7.0);
+    aggregator.mark_dirty("hero", &dirty(&["strength"]));
+    let patched = aggregator.resolve(&actor).await.unwrap();
+    assert_eq!(patched.get_stat("strength"), Some(12.0));
+    // Not marked, so still the cached value
+    assert_eq!(patched.get_stat("agility"), Some(5.0));
+
+    // The patched snapshot is cached
+    let cached = aggregator.get_cached_snapshot(&"hero".to_string()).unwrap();
+    assert_eq!(cached.get_stat("strength"), Some(12.0));
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("strength"), Some(12.0));
+
+    // Invalidating resolves every dimension again
+    aggregator.invalidate_cache(&"hero".to_string());
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("agility"), Some(7.0));
+}
+
+#[tokio::test]
+async fn patched_snapshots_update_derived_and_removed_stats() {
+    let equipment = Arc::new(Equipment::default());
+    equipment.set("strength", 10.0);
+    equipment.set("vitality", 8.0);
+    let aggregator = setup(vec![equipment.clone()]);
+    let actor = Actor::simple("hero", "Human", 1);
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().derived.get("max_hp"), Some(&80.0));
+
+    equipment.set("vitality", 10.0);
+    equipment.remove("strength");
+    aggregator.mark_dirty("hero", &dirty(&["vitality"]));
+    aggregator.mark_dirty("hero", &dirty(&["strength"]));
+    let patched = aggregator.resolve(&actor).await.unwrap();
+    assert_eq!(patched.derived.get("max_hp"), Some(&100.0));
+    // Nothing contributes to strength any more
+    assert_eq!(patched.get_stat("strength"), None);
+}
+
+#[tokio::test]
+async fn dirty_actors_without_a_cached_snapshot_are_resolved_in_full() {
+    let equipment = Arc::new(Equipment::default());
+    equipment.set("strength", 10.0);
+    equipment.set("agility", 5.0);
+    let aggregator = setup(vec![equipment.clone()]);
+    let actor = Actor::simple("hero", "Human", 1);
+
+    aggregator.mark_dirty("hero", &dirty(&["strength"]));
+    let snapshot = aggregator.resolve(&actor).await.unwrap();
+    assert_eq!((snapshot.get_stat("strength"), snapshot.get_stat("agility")), (Some(10.0), Some(5.0)));
+
+    // The full resolution cleared the dirty dimensions
+    equipment.set("strength", 20.0);
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("strength"), Some(10.0));
+}
+
+#[tokio::test]
+async fn only_subsystems_contributing_to_dirty_dimensions_run_again() {
+    let equipment = Arc::new(Equipment::default());
+    equipment.set("strength", 10.0);
+    let talents = Arc::new(Talents::default());
+    let aggregator = setup(vec![equipment.clone(), talents.clone()]);
+    let actor = Actor::simple("hero", "Human", 1);
+    aggregator.resolve(&actor).await.unwrap();
+    assert_eq!(talents.calls.load(Ordering::SeqCst), 1);
+
+    equipment.set("strength", 12.0);
+    aggregator.mark_dirty("hero", &dirty(&["strength"]));
+    let patched = aggregator.resolve(&actor).await.unwrap();
+    assert_eq!((patched.get_stat("strength"), patched.get_stat("agility")), (Some(12.0), Some(3.0)));
+    assert_eq!(talents.calls.load(Ordering::SeqCst), 1);
+
+    // Equipment did not contribute to agility, so it takes invalidating to pick it up
+    equipment.set("agility", 2.0);
+    aggregator.mark_dirty("hero", &dirty(&["agility"]));
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("agility"), Some(3.0));
+    assert_eq!(talents.calls.load(Ordering::SeqCst), 2);
+    aggregator.invalidate_cache(&"hero".to_string());
+    assert_eq!(aggregator.resolve(&actor).await.unwrap().get_stat("agility"), Some(5.0));
+}
@@ -0,0 +1,31 @@
+//! Loader bound validation tests.
+//!
+//! Cap and clamp bounds that would silently disable or invert clamping must be
+//! rejected when configuration is parsed.
+
+use actor_core::interfaces::CombinerRegistry;
+use actor_core::registry::loader::*;
+
+/// Test that NaN cap and clamp bounds are rejected instead of silently disabling clamping.
+#[test]
+fn test_nan_bounds_are_rejected() {
+    let cap_layers = "layers:\n  - name: base\n    priority: 1\n    caps:\n      - id: attack\n        cap_mode: HARD_MAX\n        min: 0\n        max: .nan\n";
+    assert!(matches!(parse_cap_layers(cap_layers), Err(LoaderError::ValidationError { .. })));
+
+    let combiner = "rules:\n  - id: attack\n    bucket_order: [FLAT]\n    clamp:\n      min: .nan\n      max: 10\n";
+    assert!(matches!(parse_combiner(combiner), Err(LoaderError::ValidationError { .. })));
+
+    // The sample files parse the same from text as from disk
+    let content = std::fs::read_to_string("configs/combiner.yaml").unwrap();
+    assert!(parse_combiner(&content).unwrap().get_rule("attack").is_some());
+}
+
+/// Test that a single bound crossing the default of the missing one is rejected.
+#[test]
+fn test_single_bound_crossing_default_is_rejected() {
+    let cap_layers = "layers:\n  - name: debuffs\n    priority: 1\n    caps:\n      - id: attack\n        cap_mode: HARD_MIN\n        min: 5000\n";
+    assert!(matches!(parse_cap_layers(cap_layers), Err(LoaderError::ValidationError { .. })));
+
+    let registry = load_cap_layers("configs/cap_layers.yaml").unwrap();
+    assert!(registry.caps().all(|(_, _, caps)| caps.is_valid()));
+}
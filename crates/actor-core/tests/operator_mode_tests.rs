@@ -34,7 +34,7 @@ struct FixedSubsystem {
 impl actor_core::interfaces::Subsystem for FixedSubsystem {
     fn system_id(&self) -> &str { &self.id }
     fn priority(&self) -> i64 { self.prio }
-    async fn contribute(&self, _actor: &Actor) -> actor_core::ActorCoreResult<SubsystemOutput> {
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> actor_core::ActorCoreResult<SubsystemOutput> {
         let mut out = SubsystemOutput::new(self.id.clone());
         for (i, v) in self.values.iter().enumerate() {
             out.add_primary(Contribution {
@@ -0,0 +1,162 @@
+//! Tests for the write-behind resource database: coalescing, conflict merging, log replay
+//! and read-through caching.
+
+mod common;
+
+use actor_core::subsystems::{
+    CacheConfig, InMemoryResourceDatabase, ResourceCache, ResourceRecord, ResourceStore, StoreWrite, WriteBehindConfig,
+    WriteBehindResourceDatabase,
+};
+use actor_core::ActorCoreResult;
+use async_trait::async_trait;
+use common::create_test_clock;
+use shared::clock::{SharedClock, SimulatedClock};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+fn cache() -> Arc<ResourceCache> {
+    Arc::new(ResourceCache::new(CacheConfig::default()))
+}
+
+async fn open(
+    store: Arc<dyn ResourceStore>,
+    clock: Arc<SimulatedClock>,
+    wal_path: Option<PathBuf>,
+) -> WriteBehindResourceDatabase {
+    let config = WriteBehindConfig {
+        wal_path,
+        ..WriteBehindConfig::default()
+    };
+    let clock: SharedClock = clock;
+    WriteBehindResourceDatabase::open(store, cache(), clock, config).await.unwrap()
+}
+
+#[tokio::test]
+async fn writes_are_coalesced_and_readable_before_a_flush() {
+    let store = Arc::new(InMemoryResourceDatabase::new());
+    let clock = create_test_clock();
+    let database = open(store.clone(), clock.clone(), None).await;
+
+    database.set("player_1", "hp", 90.0).await.unwrap();
+    clock.tick();
+    database.set("player_1", "hp", 75.0).await.unwrap();
+    database.set("player_1", "mp", 40.0).await.unwrap();
+
+    assert_eq!(database.get("player_1", "hp").await.unwrap(), Some(75.0));
+    assert!(store.load("player_1").await.unwrap().is_none());
+    let stats = database.stats().await;
+    assert_eq!(stats.pending_writes, 2);
+    assert_eq!(stats.coalesced_writes, 1);
+
+    assert_eq!(database.flush().await.unwrap(), 1);
+    let record = store.load("player_1").await.unwrap().unwrap();
+    assert_eq!(record.version, 1);
+    assert_eq!(record.resources["hp"], 75.0);
+    assert_eq!(record.resources["mp"], 40.0);
+    assert_eq!(database.stats().await.pending_writes, 0);
+}
+
+#[tokio::test]
+async fn stale_writes_lose_to_newer_writes_of_another_instance() {
+    let store = Arc::new(InMemoryResourceDatabase::new());
+    let clock = create_test_clock();
+    let zone_a = open(store.clone(), clock.clone(), None).await;
+    let zone_b = open(store.clone(), clock.clone(), None).await;
+
+    zone_a.set("player_1", "hp", 50.0).await.unwrap();
+    clock.tick();
+    zone_b.set("player_1", "hp", 80.0).await.unwrap();
+    zone_b.set("player_1", "mp", 10.0).await.unwrap();
+
+    zone_b.flush().await.unwrap();
+    zone_a.flush().await.unwrap();
+
+    let record = store.load("player_1").await.unwrap().unwrap();
+    assert_eq!(record.resources["hp"], 80.0);
+    assert_eq!(record.resources["mp"], 10.0);
+    assert_eq!(record.version, 1);
+    assert_eq!(zone_a.stats().await.superseded_writes, 1);
+    // The losing instance now reads what the other one stored
+    assert_eq!(zone_a.get("player_1", "hp").await.unwrap(), Some(80.0));
+}
+
+/// Store where another writer stores a resource just before the first write
+struct RacingStore {
+    inner: InMemoryResourceDatabase,
+    raced: AtomicBool,
+}
+
+#[async_trait]
+impl ResourceStore for RacingStore {
+    async fn load(&self, actor_id: &str) -> ActorCoreResult<Option<ResourceRecord>> {
+        self.inner.load(actor_id).await
+    }
+
+    async fn write(&self, record: &ResourceRecord) -> ActorCoreResult<StoreWrite> {
+        if !self.raced.swap(true, Ordering::SeqCst) {
+            let mut other = ResourceRecord::new(&record.actor_id);
+            other.merge("stamina", 30.0, i64::MAX);
+            assert_eq!(self.inner.write(&other).await?, StoreWrite::Written);
+        }
+        self.inner.write(record).await
+    }
+}
+
+#[tokio::test]
+async fn conflicting_writes_are_merged_and_retried() {
+    let store = Arc::new(RacingStore {
+        inner: InMemoryResourceDatabase::new(),
+        raced: AtomicBool::new(false),
+    });
+    let database = open(store.clone(), create_test_clock(), None).await;
+
+    database.set("player_1", "hp", 60.0).await.unwrap();
+    assert_eq!(database.flush().await.unwrap(), 1);
+
+    let record = store.load("player_1").await.unwrap().unwrap();
+    assert_eq!(record.version, 2);
+    assert_eq!(record.resources["hp"], 60.0);
+    assert_eq!(record.resources["stamina"], 30.0);
+    assert_eq!(database.stats().await.conflicts, 1);
+}
+
+#[tokio::test]
+async fn unflushed_writes_are_replayed_from_the_log() {
+    let directory = tempfile::tempdir().unwrap();
+    let wal_path = directory.path().join("resources.wal");
+    let store = Arc::new(InMemoryResourceDatabase::new());
+    let clock = create_test_clock();
+
+    let crashed = open(store.clone(), clock.clone(), Some(wal_path.clone())).await;
+    crashed.set("player_1", "hp", 20.0).await.unwrap();
+    crashed.set("player_2", "mp", 35.0).await.unwrap();
+    drop(crashed);
+    assert!(store.load("player_1").await.unwrap().is_none());
+
+    let restarted = open(store.clone(), clock, Some(wal_path.clone())).await;
+    assert_eq!(restarted.stats().await.replayed_writes, 2);
+    assert_eq!(store.load("player_1").await.unwrap().unwrap().resources["hp"], 20.0);
+    assert_eq!(store.load("player_2").await.unwrap().unwrap().resources["mp"], 35.0);
+    // Flushed writes are dropped from the log
+    assert!(std::fs::read_to_string(&wal_path).unwrap().trim().is_empty());
+}
+
+#[tokio::test]
+async fn reads_fall_through_to_the_store_and_fill_the_cache() {
+    let store = Arc::new(InMemoryResourceDatabase::new());
+    let mut record = ResourceRecord::new("player_1");
+    record.merge("hp", 100.0, 0);
+    record.merge("mp", 50.0, 0);
+    store.write(&record).await.unwrap();
+
+    let cache = cache();
+    let clock: SharedClock = create_test_clock();
+    let database =
+        WriteBehindResourceDatabase::open(store, cache.clone(), clock, WriteBehindConfig::default()).await.unwrap();
+
+    assert_eq!(cache.get("player_1", "mp").await.unwrap(), None);
+    assert_eq!(database.get("player_1", "hp").await.unwrap(), Some(100.0));
+    assert_eq!(cache.get("player_1", "mp").await.unwrap(), Some(50.0));
+    assert_eq!(database.get("player_2", "hp").await.unwrap(), None);
+}
@@ -0,0 +1,122 @@
+//! Tests for clock-driven resource regeneration and offline catch-up.
+
+mod common;
+
+use actor_core::subsystems::resource_management::resource_regeneration::RegenerationRule;
+use actor_core::subsystems::{RegenerationConfig, RegenerationCurve, ResourceRegenerationManager};
+use actor_core::types::Actor;
+use chrono::Duration as ChronoDuration;
+use common::create_test_clock;
+use serde_json::json;
+use shared::clock::GameClock;
+use std::collections::HashMap;
+use std::time::Duration;
+
+fn actor(id: &str, data: serde_json::Value) -> Actor {
+    let mut actor = Actor::new(id.to_string(), "Human".to_string());
+    actor.set_data(serde_json::from_value(data).unwrap());
+    actor
+}
+
+fn actors(actors: &[&Actor]) -> HashMap<String, Actor> {
+    actors.iter().map(|actor| (actor.id.clone(), (*actor).clone())).collect()
+}
+
+/// Focus refills `base_rate` of its maximum per second, a quarter of that in combat
+fn focus_rule(base_rate: f64) -> RegenerationRule {
+    RegenerationRule {
+        resource_name: "focus".to_string(),
+        base_rate,
+        formula: "base_rate * focus_max".to_string(),
+        curve: RegenerationCurve::CombatSuppressed {
+            curve: Box::new(RegenerationCurve::Percentage),
+            in_combat_multiplier: 0.25,
+        },
+        conditions: Vec::new(),
+        modifiers: Vec::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_ticks_regenerate_by_elapsed_game_time() {
+    let clock = create_test_clock();
+    let manager = ResourceRegenerationManager::with_clock(RegenerationConfig::default(), clock.clone());
+    let hero = actor("hero", json!({"hp_current": 50.0, "hp_max": 100.0, "hp_current_max": 100.0}));
+    manager.start_regeneration(&hero, "hp_current").await.unwrap();
+
+    clock.advance(Duration::from_secs(10));
+    let tick = manager.tick(&actors(&[&hero])).await.unwrap();
+    assert_eq!(tick.deltas.len(), 1);
+    assert!((tick.deltas[0].amount - 1.0).abs() < 1e-9);
+
+    // No game time passed, nothing regenerates; a paused clock behaves the same
+    clock.pause();
+    clock.tick();
+    let tick = manager.tick(&actors(&[&hero])).await.unwrap();
+    assert!(tick.deltas.is_empty());
+}
+
+#[tokio::test]
+async fn test_percentage_curves_are_suppressed_in_combat() {
+    let clock = create_test_clock();
+    let mut manager = ResourceRegenerationManager::with_clock(RegenerationConfig::default(), clock.clone());
+    manager.add_regeneration_rule(focus_rule(0.01));
+    let calm = actor("calm", json!({"focus": 0.0, "focus_max": 200.0}));
+    let fighting = actor("fighting", json!({"focus": 0.0, "focus_max": 200.0, "in_combat": true}));
+    manager.start_regeneration(&calm, "focus").await.unwrap();
+    manager.start_regeneration(&fighting, "focus").await.unwrap();
+
+    clock.advance(Duration::from_secs(4));
+    let tick = manager.tick(&actors(&[&calm, &fighting])).await.unwrap();
+    let amount = |actor_id: &str| tick.deltas.iter().find(|delta| delta.actor_id == actor_id).unwrap().amount;
+    assert!((amount("calm") - 8.0).abs() < 1e-9);
+    assert!((amount("fighting") - 2.0).abs() < 1e-9);
+    // Suppressed, not stopped: combat keeps the task running
+    assert_eq!(tick.tasks_stopped, 0);
+}
+
+#[tokio::test]
+async fn test_batches_cover_every_active_actor() {
+    let clock = create_test_clock();
+    let config = RegenerationConfig { batch_size: 2, ..RegenerationConfig::default() };
+    let mut manager = ResourceRegenerationManager::with_clock(config, clock.clone());
+    manager.add_regeneration_rule(focus_rule(0.01));
+    let loaded: Vec<Actor> =
+        (0..5).map(|i| actor(&format!("actor-{}", i), json!({"focus": 0.0, "focus_max": 100.0}))).collect();
+    for actor in &loaded {
+        manager.start_regeneration(actor, "focus").await.unwrap();
+    }
+    let unloaded = actor("unloaded", json!({"focus": 0.0}));
+    manager.start_regeneration(&unloaded, "focus").await.unwrap();
+
+    clock.advance(Duration::from_secs(1));
+    let tick = manager.tick(&actors(&loaded.iter().collect::<Vec<_>>())).await.unwrap();
+    assert_eq!((tick.tasks_processed, tick.tasks_stopped, tick.deltas.len()), (5, 1, 5));
+
+    let stats = manager.get_regeneration_stats().await.unwrap();
+    assert_eq!((stats.total_tasks, stats.ticks, stats.tasks_processed), (5, 1, 5));
+    assert!((stats.total_regenerated - 5.0).abs() < 1e-9);
+}
+
+#[tokio::test]
+async fn test_catch_up_regenerates_offline_time() {
+    let clock = create_test_clock();
+    let config = RegenerationConfig { max_catch_up_secs: 3600.0, ..RegenerationConfig::default() };
+    let mut manager = ResourceRegenerationManager::with_clock(config, clock.clone());
+    manager.add_regeneration_rule(focus_rule(0.0001));
+    // Saved mid-fight; offline actors are out of combat and resting
+    let hero = actor("hero", json!({"focus": 0.0, "focus_max": 10_000.0, "mp_current": 0.0, "in_combat": true}));
+
+    let amounts = manager.catch_up(&hero, clock.now() - ChronoDuration::minutes(10)).await.unwrap();
+    assert!((amounts["focus"] - 600.0).abs() < 1e-6);
+    // 0.2 MP per second, capped at the default maximum of 100
+    assert!((amounts["mp_current"] - 100.0).abs() < 1e-9);
+
+    // A day offline regenerates no more than the configured hour
+    let amounts = manager.catch_up(&hero, clock.now() - ChronoDuration::days(1)).await.unwrap();
+    assert!((amounts["focus"] - 3_600.0).abs() < 1e-6);
+
+    let stats = manager.get_regeneration_stats().await.unwrap();
+    assert_eq!(stats.catch_ups, 2);
+    assert!(stats.caught_up_total > 4_200.0);
+}
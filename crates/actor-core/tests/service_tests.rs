@@ -4,7 +4,6 @@
 //! including AggregatorImpl, CapsProviderImpl, and related functionality.
 
 use actor_core::prelude::*;
-use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Mock subsystem for testing
@@ -38,7 +37,7 @@ impl actor_core::interfaces::Subsystem for MockSubsystem {
         self.priority
     }
     
-    async fn contribute(&self, _actor: &Actor) -> actor_core::ActorCoreResult<SubsystemOutput> {
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> actor_core::ActorCoreResult<SubsystemOutput> {
         let mut output = SubsystemOutput::new(self.system_id.clone());
         
         for contribution in &self.contributions {
@@ -129,11 +128,9 @@ async fn test_aggregator_impl_with_context() {
     let actor = Actor::new("TestActor".to_string(), "Human".to_string());
     
     // Test resolve_with_context
-    let context = Some(HashMap::from([
-        ("context_type".to_string(), serde_json::Value::String("combat".to_string())),
-    ]));
+    let context = AggregationContext::new().with_zone("battlefield").with_combat(true);
     
-    let snapshot = aggregator.resolve_with_context(&actor, context).await.unwrap();
+    let snapshot = aggregator.resolve_with_context(&actor, &context).await.unwrap();
     assert_eq!(snapshot.actor_id, actor.id);
 }
 
@@ -0,0 +1,122 @@
+//! Tests for keeping recent snapshots and diffing them by subsystem and bucket.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Contributes whatever it currently holds, per stat and bucket
+struct Source {
+    id: &'static str,
+    stats: Mutex<HashMap<&'static str, (Bucket, f64)>>,
+}
+
+impl Source {
+    fn new(id: &'static str, stats: &[(&'static str, Bucket, f64)]) -> Arc<Self> {
+        let stats = stats.iter().map(|&(stat, bucket, value)| (stat, (bucket, value))).collect();
+        Arc::new(Self { id, stats: Mutex::new(stats) })
+    }
+
+    fn set(&self, stat: &'static str, bucket: Bucket, value: f64) {
+        self.stats.lock().unwrap().insert(stat, (bucket, value));
+    }
+}
+
+#[async_trait]
+impl Subsystem for Source {
+    fn system_id(&self) -> &str {
+        self.id
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(self.id.to_string());
+        for (&stat, &(bucket, value)) in self.stats.lock().unwrap().iter() {
+            output.add_contribution(Contribution::new(stat.to_string(), bucket, value, self.id.to_string()));
+        }
+        Ok(output)
+    }
+}
+
+fn snapshot(version: i64, strength: f64) -> Snapshot {
+    let mut snapshot = Snapshot::new("hero".to_string());
+    snapshot.version = version;
+    snapshot.primary.insert("strength".to_string(), strength);
+    snapshot
+}
+
+#[tokio::test]
+async fn diffs_name_the_subsystem_and_bucket_behind_a_change() {
+    let gear = Source::new("gear", &[("strength", Bucket::Flat, 10.0), ("agility", Bucket::Flat, 4.0)]);
+    let buffs = Source::new("buffs", &[("strength", Bucket::Flat, 5.0)]);
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(gear.clone()).unwrap();
+    plugins.register(buffs.clone()).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    for dimension in ["strength", "agility"] {
+        let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+        combiner.set_rule(dimension, rule).unwrap();
+    }
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    let history = Arc::new(SnapshotHistory::new(4));
+    let aggregator =
+        AggregatorImpl::new(plugins, combiner, caps_provider, cache).with_snapshot_history(history.clone());
+
+    let mut actor = Actor::simple("hero", "Human", 1);
+    actor.version = 1;
+    aggregator.resolve(&actor).await.unwrap();
+
+    // A stronger buff lands on the next tick
+    buffs.set("strength", Bucket::Flat, 8.0);
+    actor.version = 2;
+    aggregator.invalidate_cache(&actor.id);
+    aggregator.resolve(&actor).await.unwrap();
+    assert_eq!(history.versions("hero"), [1, 2]);
+
+    let delta = history.diff("hero", 1, 2).unwrap();
+    assert_eq!(delta.changes.len(), 1);
+    let strength = delta.change("strength").unwrap();
+    assert_eq!((strength.before, strength.after, strength.delta()), (Some(15.0), Some(18.0), 3.0));
+    let source = SourceChange { system: "buffs".to_string(), bucket: Bucket::Flat, before: 5.0, after: 8.0 };
+    assert_eq!(strength.sources, [source]);
+
+    // Patched snapshots are recorded too
+    gear.set("agility", Bucket::Flat, 6.0);
+    actor.version = 3;
+    aggregator.mark_dirty("hero", &["agility".to_string()]);
+    aggregator.resolve(&actor).await.unwrap();
+    let agility = history.diff("hero", 2, 3).unwrap().change("agility").cloned().unwrap();
+    assert_eq!((agility.before, agility.after), (Some(4.0), Some(6.0)));
+    assert!(history.diff("hero", 3, 3).unwrap().is_empty());
+
+    // Predictions are not recorded
+    actor.version = 4;
+    let boost = Contribution::new("strength".to_string(), Bucket::Flat, 2.0, "preview".to_string());
+    aggregator.resolve_hypothetical(&actor, &[boost]).await.unwrap();
+    assert_eq!(history.versions("hero"), [1, 2, 3]);
+}
+
+#[test]
+fn history_keeps_the_last_versions_per_actor() {
+    let history = SnapshotHistory::new(2);
+    history.record(snapshot(1, 10.0), Vec::new());
+    history.record(snapshot(2, 12.0), Vec::new());
+    // Resolving a version again replaces it
+    history.record(snapshot(2, 13.0), Vec::new());
+    assert_eq!(history.versions("hero"), [1, 2]);
+    assert_eq!(history.get("hero", 2).unwrap().get_stat("strength"), Some(13.0));
+
+    history.record(snapshot(3, 14.0), Vec::new());
+    assert_eq!(history.versions("hero"), [2, 3]);
+    assert!(matches!(history.diff("hero", 1, 3), Err(ActorCoreError::InvalidInput(_))));
+    assert_eq!(history.diff("hero", 2, 3).unwrap().change("strength").unwrap().delta(), 1.0);
+
+    history.forget("hero");
+    assert!(history.versions("hero").is_empty());
+}
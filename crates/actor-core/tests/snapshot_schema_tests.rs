@@ -0,0 +1,81 @@
+//! Tests for reading snapshots serialized by older and newer releases.
+
+use actor_core::prelude::*;
+use actor_core::snapshot_schema::{schema_version, LEGACY_SCHEMA_VERSION};
+use std::sync::Arc;
+
+fn fixture(name: &str) -> serde_json::Value {
+    let path = format!("{}/tests/fixtures/snapshots/{}", env!("CARGO_MANIFEST_DIR"), name);
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn test_unversioned_snapshots_are_upgraded() {
+    let value = fixture("v1.json");
+    assert_eq!(schema_version(&value).unwrap(), LEGACY_SCHEMA_VERSION);
+
+    let snapshot = decode_snapshot(value).unwrap();
+    assert_eq!(snapshot.schema_version, SNAPSHOT_SCHEMA_VERSION);
+    assert_eq!(snapshot.actor_id, "hero");
+    assert_eq!(snapshot.version, 3);
+    assert_eq!(snapshot.get_stat("agility"), Some(4.5));
+    assert_eq!(snapshot.derived.get("attack_power"), Some(&25.0));
+    assert_eq!(snapshot.caps_used["strength"].max, 99.0);
+    assert_eq!(snapshot.processing_time, Some(120));
+}
+
+#[test]
+fn test_null_collections_of_version_1_become_empty() {
+    let snapshot = decode_snapshot(fixture("v1_null_collections.json")).unwrap();
+    assert_eq!(snapshot.get_stat("strength"), Some(10.0));
+    assert!(snapshot.derived.is_empty() && snapshot.caps_used.is_empty() && snapshot.metadata.is_empty());
+    assert!(snapshot.subsystems_processed.is_empty());
+    assert_eq!(snapshot.processing_time, None);
+}
+
+#[test]
+fn test_current_snapshots_round_trip() {
+    let mut snapshot = Snapshot::new("hero".to_string());
+    snapshot.set_stat("strength".to_string(), 12.0);
+    let value = serde_json::to_value(&snapshot).unwrap();
+    assert_eq!(value["schema_version"], SNAPSHOT_SCHEMA_VERSION);
+
+    let decoded = decode_snapshot(value).unwrap();
+    assert_eq!(decoded.content_hash(), snapshot.content_hash());
+    assert_eq!(decoded.schema_version, SNAPSHOT_SCHEMA_VERSION);
+}
+
+#[test]
+fn test_newer_and_invalid_versions_are_rejected() {
+    assert!(matches!(
+        decode_snapshot(fixture("v3.json")),
+        Err(SnapshotSchemaError::Newer { found: 3, .. })
+    ));
+
+    let mut value = fixture("v1.json");
+    value["schema_version"] = serde_json::json!("two");
+    assert!(matches!(decode_snapshot(value), Err(SnapshotSchemaError::InvalidVersion(_))));
+    assert!(matches!(decode_snapshot(serde_json::json!([1, 2])), Err(SnapshotSchemaError::NotAnObject)));
+}
+
+#[tokio::test]
+async fn test_cached_snapshots_of_other_versions() {
+    let cache = Arc::new(InMemoryCache::new(1000, 3600));
+    let caps_provider = Arc::new(CapsProviderImpl::new(Arc::new(CapLayerRegistryImpl::new())));
+    let aggregator = AggregatorImpl::new(
+        Arc::new(PluginRegistryImpl::new()),
+        Arc::new(CombinerRegistryImpl::new()),
+        caps_provider,
+        cache.clone(),
+    );
+
+    // An entry left by an older release is migrated on read
+    cache.set("hero".to_string(), fixture("v1.json"), None).unwrap();
+    let cached = aggregator.get_cached_snapshot(&"hero".to_string()).unwrap();
+    assert_eq!((cached.schema_version, cached.version), (SNAPSHOT_SCHEMA_VERSION, 3));
+
+    // One left by a newer release is discarded so the actor is resolved again
+    cache.set("hero".to_string(), fixture("v3.json"), None).unwrap();
+    assert!(aggregator.get_cached_snapshot(&"hero".to_string()).is_none());
+    assert!(cache.get("hero").is_none());
+}
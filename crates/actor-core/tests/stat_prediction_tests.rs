@@ -0,0 +1,85 @@
+//! Tests for predicting stats with hypothetical contributions.
+
+use actor_core::interfaces::Subsystem;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Base stats every actor has
+struct BaseStats;
+
+#[async_trait]
+impl Subsystem for BaseStats {
+    fn system_id(&self) -> &str {
+        "base"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("base".to_string());
+        for (stat, value) in [("strength", 10.0), ("agility", 8.0)] {
+            output.add_contribution(Contribution::new(stat.to_string(), Bucket::Flat, value, "base".to_string()));
+        }
+        Ok(output)
+    }
+}
+
+fn aggregator() -> Arc<dyn Aggregator> {
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(Arc::new(BaseStats)).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    for stat in ["strength", "agility", "attack_power"] {
+        let rule = MergeRule {
+            use_pipeline: true,
+            operator: Operator::Sum,
+            clamp_default: None,
+        };
+        combiner.set_rule(stat, rule).unwrap();
+    }
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    ServiceFactory::create_aggregator(plugins, combiner, caps_provider, CacheFactory::create_in_memory_cache(1024, 60))
+}
+
+/// Contributions of a sword the actor might equip
+fn sword() -> Vec<Contribution> {
+    vec![
+        Contribution::new("strength".to_string(), Bucket::Flat, 5.0, "item:sword".to_string()),
+        Contribution::new("attack_power".to_string(), Bucket::Flat, 12.0, "item:sword".to_string()),
+    ]
+}
+
+#[tokio::test]
+async fn predictions_report_the_change_per_stat() {
+    let aggregator = aggregator();
+    let hero = Actor::new("hero".to_string(), "Human".to_string());
+
+    let prediction = aggregator.resolve_hypothetical(&hero, &sword()).await.unwrap();
+    assert_eq!(prediction.actor_id, "hero");
+    assert_eq!(prediction.current.get("strength"), Some(&10.0));
+    assert_eq!(prediction.predicted.get("strength"), Some(&15.0));
+    assert_eq!(prediction.delta("strength"), 5.0);
+    // New stats change from nothing, untouched ones are left out of the delta
+    assert_eq!(prediction.delta("attack_power"), 12.0);
+    assert!(!prediction.delta.contains_key("agility"));
+    assert_eq!(prediction.delta.len(), 2);
+}
+
+#[tokio::test]
+async fn predictions_leave_the_cache_alone() {
+    let aggregator = aggregator();
+    let hero = Actor::new("hero".to_string(), "Human".to_string());
+
+    aggregator.resolve_hypothetical(&hero, &sword()).await.unwrap();
+    assert!(aggregator.get_cached_snapshot(&hero.id).is_none());
+
+    aggregator.resolve(&hero).await.unwrap();
+    aggregator.resolve_hypothetical(&hero, &sword()).await.unwrap();
+    let cached = aggregator.get_cached_snapshot(&hero.id).unwrap();
+    assert_eq!((cached.get_stat("strength"), cached.get_stat("attack_power")), (Some(10.0), None));
+
+    // Nothing hypothetical, nothing changes
+    assert!(aggregator.resolve_hypothetical(&hero, &[]).await.unwrap().delta.is_empty());
+}
@@ -0,0 +1,106 @@
+//! Tests for per-actor subsystem selection.
+
+use actor_core::prelude::*;
+use actor_core::registry::loader::LoaderError;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+struct NamedSubsystem {
+    id: &'static str,
+    priority: i64,
+}
+
+#[async_trait]
+impl actor_core::interfaces::Subsystem for NamedSubsystem {
+    fn system_id(&self) -> &str {
+        self.id
+    }
+
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    async fn contribute(
+        &self,
+        _actor: &Actor,
+        _context: &AggregationContext,
+    ) -> actor_core::ActorCoreResult<SubsystemOutput> {
+        Ok(SubsystemOutput::new(self.id.to_string()))
+    }
+}
+
+const SETS: &str = r#"
+default: ["combat", "equipment"]
+races:
+  Spirit: ["*"]
+templates:
+  cultivator: ["luyen_the", "combat"]
+"#;
+
+fn registry() -> PluginRegistryImpl {
+    let registry = PluginRegistryImpl::with_subsystem_sets(parse_subsystem_sets(SETS).unwrap());
+    for (id, priority) in [("luyen_the", 300), ("combat", 200), ("equipment", 100)] {
+        registry.register(Arc::new(NamedSubsystem { id, priority })).unwrap();
+    }
+    registry
+}
+
+fn ids(subsystems: Vec<Arc<dyn actor_core::interfaces::Subsystem>>) -> Vec<String> {
+    subsystems.iter().map(|subsystem| subsystem.system_id().to_string()).collect()
+}
+
+fn actor(race: &str, template: Option<&str>) -> Actor {
+    let mut actor = Actor::new("actor_1".to_string(), race.to_string());
+    actor.template = template.map(str::to_string);
+    actor
+}
+
+#[test]
+fn template_set_selects_subsystems_in_priority_order() {
+    let registry = registry();
+    assert_eq!(ids(registry.get_for_actor(&actor("Human", Some("cultivator")))), ["luyen_the", "combat"]);
+    assert_eq!(ids(registry.get_for_actor(&actor("Human", None))), ["combat", "equipment"]);
+}
+
+#[test]
+fn race_set_applies_without_a_configured_template() {
+    let registry = registry();
+    assert_eq!(
+        ids(registry.get_for_actor(&actor("Spirit", Some("wanderer")))),
+        ["luyen_the", "combat", "equipment"]
+    );
+}
+
+#[test]
+fn actors_opt_in_and_out_of_their_defaults() {
+    let registry = registry();
+    let mut npc = actor("Human", None);
+    npc.enable_subsystem("luyen_the");
+    npc.disable_subsystem("equipment");
+    assert_eq!(ids(registry.get_for_actor(&npc)), ["luyen_the", "combat"]);
+
+    npc.disable_subsystem("luyen_the");
+    assert!(npc.subsystems.is_empty());
+    assert_eq!(ids(registry.get_for_actor(&npc)), ["combat"]);
+}
+
+#[test]
+fn registries_without_sets_enable_everything() {
+    let registry = PluginRegistryImpl::new();
+    registry.register(Arc::new(NamedSubsystem { id: "luyen_the", priority: 1 })).unwrap();
+    assert_eq!(ids(registry.get_for_actor(&actor("Human", Some("mundane_npc")))), ["luyen_the"]);
+}
+
+#[test]
+fn empty_subsystem_ids_are_rejected() {
+    let result = parse_subsystem_sets("templates:\n  cultivator: [\"luyen_the\", \"\"]\n");
+    assert!(matches!(result, Err(LoaderError::ValidationError { .. })));
+}
+
+#[test]
+fn shipped_configuration_loads() {
+    let sets = load_subsystem_sets(concat!(env!("CARGO_MANIFEST_DIR"), "/configs/subsystem_sets.yaml")).unwrap();
+    let npc = actor("Human", Some("mundane_npc"));
+    assert!(!sets.is_enabled(&npc, "luyen_the"));
+    assert!(sets.is_enabled(&actor("Human", Some("cultivator")), "luyen_the"));
+}
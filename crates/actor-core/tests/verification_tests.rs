@@ -0,0 +1,86 @@
+//! Verification Tests
+//!
+//! This module contains tests for snapshot hashing and the verifier that
+//! cross-checks cached and client-reported snapshots against fresh resolutions.
+
+use actor_core::prelude::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn verifier(aggregator: Arc<dyn Aggregator>) -> SnapshotVerifier {
+        SnapshotVerifier::new(aggregator, VerificationConfig { sample_rate: 1.0, max_per_round: 10, interval_secs: 60 })
+    }
+
+    #[test]
+    fn test_content_hash_ignores_processing_metadata() {
+        let mut snapshot = Snapshot::new("player1".to_string());
+        snapshot.set_stat("strength".to_string(), 50.0);
+        snapshot.set_stat("agility".to_string(), 0.0);
+
+        let mut rerun = snapshot.clone();
+        rerun.processing_time = Some(1234);
+        rerun.cache_hit = true;
+        rerun.created_at = chrono::Utc::now() + chrono::Duration::seconds(5);
+        rerun.set_stat("agility".to_string(), -0.0);
+        assert_eq!(snapshot.content_hash(), rerun.content_hash());
+
+        rerun.set_stat("strength".to_string(), 51.0);
+        assert_ne!(snapshot.content_hash(), rerun.content_hash());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_cache_entry_is_flagged_and_repaired() {
+        let (aggregator, cache) = quick_setup().await.unwrap();
+        let actor = create_simple_actor("player1", "human", 10);
+        let resolved = aggregator.resolve(&actor).await.unwrap();
+
+        let mut tampered = resolved.clone();
+        tampered.set_stat("strength".to_string(), 9999.0);
+        cache.set(actor.id.clone(), serde_json::to_value(&tampered).unwrap(), Some(3600)).unwrap();
+
+        let verifier = verifier(aggregator.clone());
+        let mismatches = verifier.verify(&actor, None).await.unwrap();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(mismatches[0].source, MismatchSource::Cache);
+        assert_eq!(mismatches[0].expected_hash, resolved.content_hash());
+        assert_eq!(mismatches[0].stats, vec!["strength".to_string()]);
+
+        // The fresh resolution replaced the tampered entry
+        assert!(verifier.verify(&actor, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_client_hashes_are_checked() {
+        let (aggregator, _cache) = quick_setup().await.unwrap();
+        let actor = create_simple_actor("player1", "human", 10);
+        let expected = aggregator.resolve(&actor).await.unwrap().content_hash();
+
+        let verifier = verifier(aggregator);
+        assert!(verifier.verify(&actor, Some(&expected)).await.unwrap().is_empty());
+
+        let mut client_hashes = HashMap::new();
+        client_hashes.insert(actor.id.clone(), "0000000000000000".to_string());
+        let report = verifier.run(&[actor], &client_hashes).await;
+        assert_eq!(report.sampled, 1);
+        assert_eq!(report.mismatches.len(), 1);
+        assert_eq!(report.mismatches[0].source, MismatchSource::Client);
+    }
+
+    #[tokio::test]
+    async fn test_sampling_follows_the_configuration() {
+        let (aggregator, _cache) = quick_setup().await.unwrap();
+        let actors: Vec<Actor> = (0..50).map(|i| create_simple_actor(&format!("player{}", i), "human", 1)).collect();
+
+        let config = VerificationConfig { sample_rate: 0.1, max_per_round: 100, interval_secs: 60 };
+        assert_eq!(SnapshotVerifier::new(aggregator.clone(), config).sample(&actors).len(), 5);
+
+        let config = VerificationConfig { sample_rate: 1.0, max_per_round: 3, interval_secs: 60 };
+        assert_eq!(SnapshotVerifier::new(aggregator.clone(), config).sample(&actors).len(), 3);
+
+        let config = VerificationConfig { sample_rate: 0.0, ..Default::default() };
+        assert!(SnapshotVerifier::new(aggregator, config.clone()).sample(&actors).is_empty());
+        assert!(VerificationConfig { sample_rate: 1.5, ..config }.validate().is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Snapshot Codec Benchmarks
+//!
+//! Compares the compact protobuf snapshot encoding with serde_json, for both speed and
+//! encoded size.
+
+use actor_core::enums::AcrossLayerPolicy;
+use actor_core::types::{Caps, Snapshot};
+use api::codec::snapshot_delta;
+use api::grpc::proto::CompactSnapshot;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use prost::Message;
+
+/// Create a snapshot with `stats` primary, derived, and capped stats
+fn create_snapshot(stats: usize) -> Snapshot {
+    let mut snapshot = Snapshot::new("actor_benchmark".to_string());
+    for i in 0..stats {
+        let name = format!("stat_{:03}", i);
+        snapshot.primary.insert(name.clone(), i as f64 * 1.5);
+        snapshot.derived.insert(name.clone(), i as f64 * 2.25);
+        let mut caps = Caps::new(name.clone(), AcrossLayerPolicy::Intersect);
+        caps.min = 0.0;
+        caps.max = 1000.0;
+        snapshot.caps_used.insert(name, caps);
+    }
+    snapshot
+}
+
+/// Benchmark encoding a full snapshot
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_encode");
+
+    for stats in [16, 128].iter() {
+        let snapshot = create_snapshot(*stats);
+        let json_size = serde_json::to_vec(&snapshot).unwrap().len();
+        let protobuf_size = CompactSnapshot::from(&snapshot).encode_to_vec().len();
+        println!("{} stats: json {} bytes, protobuf {} bytes", stats, json_size, protobuf_size);
+
+        group.throughput(Throughput::Elements(*stats as u64));
+        group.bench_with_input(BenchmarkId::new("serde_json", stats), &snapshot, |b, snapshot| {
+            b.iter(|| black_box(serde_json::to_vec(snapshot).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("protobuf", stats), &snapshot, |b, snapshot| {
+            b.iter(|| black_box(CompactSnapshot::from(snapshot).encode_to_vec()))
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark decoding a full snapshot
+fn bench_decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("snapshot_decode");
+
+    for stats in [16, 128].iter() {
+        let snapshot = create_snapshot(*stats);
+        let json = serde_json::to_vec(&snapshot).unwrap();
+        let protobuf = CompactSnapshot::from(&snapshot).encode_to_vec();
+
+        group.throughput(Throughput::Elements(*stats as u64));
+        group.bench_with_input(BenchmarkId::new("serde_json", stats), &json, |b, json| {
+            b.iter(|| black_box(serde_json::from_slice::<Snapshot>(json).unwrap()))
+        });
+        group.bench_with_input(BenchmarkId::new("protobuf", stats), &protobuf, |b, protobuf| {
+            b.iter(|| {
+                let compact = CompactSnapshot::decode(protobuf.as_slice()).unwrap();
+                black_box(Snapshot::try_from(compact).unwrap())
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// Benchmark computing and encoding a delta where a few stats changed
+fn bench_delta(c: &mut Criterion) {
+    let previous = create_snapshot(128);
+    let mut current = previous.clone();
+    current.version += 1;
+    for i in 0..4 {
+        current.primary.insert(format!("stat_{:03}", i), -1.0);
+    }
+
+    c.bench_function("snapshot_delta_encode", |b| {
+        b.iter(|| black_box(snapshot_delta(&previous, &current).encode_to_vec()))
+    });
+}
+
+criterion_group!(benches, bench_encode, bench_decode, bench_delta);
+criterion_main!(benches);
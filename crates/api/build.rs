@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+
+    // Sync messages also travel as JSON to WebSocket clients that opt out of protobuf.
+    let serde_messages = [
+        ".chaos.v1.StatValues",
+        ".chaos.v1.StatCapValues",
+        ".chaos.v1.CompactSnapshot",
+        ".chaos.v1.SnapshotDelta",
+        ".chaos.v1.ElementalDelta",
+    ];
+    let mut config = tonic_build::configure();
+    for message in serde_messages {
+        config = config.type_attribute(message, "#[derive(serde::Serialize, serde::Deserialize)]");
+    }
+
+    config
+        .file_descriptor_set_path(out_dir.join("chaos_descriptor.bin"))
+        .compile(
+            &[
+                "proto/chaos/v1/actor.proto",
+                "proto/chaos/v1/combat.proto",
+                "proto/chaos/v1/snapshot.proto",
+                "proto/chaos/v1/world.proto",
+            ],
+            &["proto"],
+        )?;
+
+    println!("cargo:rerun-if-changed=proto");
+    Ok(())
+}
@@ -0,0 +1,10 @@
+//! Print the OpenAPI document, or write it to the path given as the first argument.
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let document = api::openapi::openapi().to_pretty_json()?;
+    match std::env::args().nth(1) {
+        Some(path) => std::fs::write(path, document)?,
+        None => println!("{}", document),
+    }
+    Ok(())
+}
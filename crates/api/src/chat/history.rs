@@ -0,0 +1,90 @@
+//! Chat history storage.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use shared::{ChaosResult, SharedClock};
+
+use super::ChatMessage;
+
+/// How much history is kept per conversation.
+#[derive(Debug, Clone, Copy)]
+pub struct RetentionPolicy {
+    /// Messages kept per conversation; older ones are dropped first
+    pub max_messages: usize,
+    /// Age after which messages are dropped
+    pub max_age: Duration,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: 200,
+            max_age: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// Storage for delivered chat messages, keyed by conversation
+/// ([`ChatTarget::conversation`](super::ChatTarget::conversation)).
+#[async_trait]
+pub trait ChatHistory: Send + Sync {
+    /// Store a delivered message.
+    async fn append(&self, conversation: &str, message: &ChatMessage) -> ChaosResult<()>;
+
+    /// Get the latest `limit` messages of a conversation, oldest first.
+    async fn recent(&self, conversation: &str, limit: usize) -> ChaosResult<Vec<ChatMessage>>;
+}
+
+/// History kept in memory, for tests and single-instance deployments.
+#[derive(Debug)]
+pub struct MemoryChatHistory {
+    clock: SharedClock,
+    retention: RetentionPolicy,
+    conversations: Mutex<HashMap<String, VecDeque<ChatMessage>>>,
+}
+
+impl MemoryChatHistory {
+    /// Create an empty history.
+    pub fn new(clock: SharedClock, retention: RetentionPolicy) -> Self {
+        Self {
+            clock,
+            retention,
+            conversations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn prune(&self, messages: &mut VecDeque<ChatMessage>) {
+        let cutoff = chrono::Duration::from_std(self.retention.max_age)
+            .ok()
+            .and_then(|max_age| self.clock.now().checked_sub_signed(max_age));
+        while messages.len() > self.retention.max_messages
+            || messages.front().zip(cutoff).is_some_and(|(oldest, cutoff)| oldest.sent_at < cutoff)
+        {
+            messages.pop_front();
+        }
+    }
+}
+
+#[async_trait]
+impl ChatHistory for MemoryChatHistory {
+    async fn append(&self, conversation: &str, message: &ChatMessage) -> ChaosResult<()> {
+        let mut conversations = self.conversations.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let messages = conversations.entry(conversation.to_string()).or_default();
+        messages.push_back(message.clone());
+        self.prune(messages);
+        Ok(())
+    }
+
+    async fn recent(&self, conversation: &str, limit: usize) -> ChaosResult<Vec<ChatMessage>> {
+        let mut conversations = self.conversations.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let Some(messages) = conversations.get_mut(conversation) else {
+            return Ok(Vec::new());
+        };
+        self.prune(messages);
+        let skip = messages.len().saturating_sub(limit);
+        Ok(messages.iter().skip(skip).cloned().collect())
+    }
+}
@@ -0,0 +1,111 @@
+//! In-game chat.
+//!
+//! Clients post with [`ClientMessage::Chat`](crate::websocket::ClientMessage::Chat) over
+//! their WebSocket connection. Zone, party and guild messages go to the sessions
+//! subscribed to the matching [`Channel`], so the [`ChannelAuthorizer`] deciding who may
+//! subscribe also decides who may read and post; whispers go to every session of the
+//! recipient. The [`ChatService`] rate limits each sender, runs the message through its
+//! [`ModerationFilter`]s, keeps it in the [`ChatHistory`] and reports refused messages to
+//! anti-cheat on [`CHAT_ABUSE`](shared::events::topics::CHAT_ABUSE).
+//!
+//! [`ChannelAuthorizer`]: crate::websocket::ChannelAuthorizer
+
+pub mod history;
+pub mod moderation;
+pub mod service;
+
+pub use history::{ChatHistory, MemoryChatHistory, RetentionPolicy};
+pub use moderation::{ModerationFilter, Verdict, WordFilter};
+pub use service::{ChatConfig, ChatRateLimit, ChatService};
+
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+use shared::Timestamp;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::ApiErrorResponse;
+use crate::websocket::Channel;
+
+/// Prefix of whisper targets
+const WHISPER_PREFIX: &str = "whisper:";
+
+/// Where a chat message goes.
+///
+/// Targets travel as channel strings (`zone:<id>`, `party:<id>`, `guild:<id>`) or as
+/// `whisper:<user_id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ChatTarget {
+    /// Everyone subscribed to a zone, party or guild channel
+    Channel(Channel),
+    /// One user
+    Whisper(String),
+}
+
+impl ChatTarget {
+    /// Get the history key of the conversation `sender_id` posts to.
+    ///
+    /// Both sides of a whisper share one conversation.
+    pub fn conversation(&self, sender_id: &str) -> String {
+        match self {
+            Self::Channel(channel) => channel.to_string(),
+            Self::Whisper(recipient) => {
+                let (first, second) = if sender_id <= recipient.as_str() {
+                    (sender_id, recipient.as_str())
+                } else {
+                    (recipient.as_str(), sender_id)
+                };
+                format!("{}{}:{}", WHISPER_PREFIX, first, second)
+            }
+        }
+    }
+}
+
+impl FromStr for ChatTarget {
+    type Err = ApiErrorResponse;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.strip_prefix(WHISPER_PREFIX) {
+            Some("") => Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "Whisper needs a recipient")),
+            Some(recipient) => Ok(Self::Whisper(recipient.to_string())),
+            None => value.parse().map(Self::Channel),
+        }
+    }
+}
+
+impl TryFrom<String> for ChatTarget {
+    type Error = ApiErrorResponse;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ChatTarget> for String {
+    fn from(target: ChatTarget) -> Self {
+        target.to_string()
+    }
+}
+
+impl fmt::Display for ChatTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Channel(channel) => channel.fmt(f),
+            Self::Whisper(recipient) => write!(f, "{}{}", WHISPER_PREFIX, recipient),
+        }
+    }
+}
+
+/// A delivered chat message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub id: String,
+    /// Sending user
+    pub sender_id: String,
+    /// Channel, or recipient of a whisper
+    pub target: ChatTarget,
+    /// Text after moderation
+    pub text: String,
+    pub sent_at: Timestamp,
+}
@@ -0,0 +1,95 @@
+//! Moderation hooks chat messages pass through before delivery.
+
+use std::collections::HashSet;
+
+use super::ChatTarget;
+
+/// What a filter decided about a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// Deliver the message as is
+    Allow,
+    /// Deliver this text instead, e.g. with words masked
+    Replace(String),
+    /// Refuse the message and report the sender
+    Reject(String),
+}
+
+/// Checks chat messages before they are delivered.
+///
+/// Filters run in the order they were added; each one sees the text left by the
+/// previous one, and the first rejection stops the message.
+pub trait ModerationFilter: Send + Sync {
+    /// Check a message `sender_id` posts to `target`.
+    fn check(&self, sender_id: &str, target: &ChatTarget, text: &str) -> Verdict;
+}
+
+/// Filter for a list of banned words, matched whole and case-insensitively.
+#[derive(Debug, Clone)]
+pub struct WordFilter {
+    words: HashSet<String>,
+    reject: bool,
+}
+
+impl WordFilter {
+    /// Create a filter that replaces banned words with `*`.
+    pub fn mask<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            words: words.into_iter().map(|word| word.as_ref().to_lowercase()).collect(),
+            reject: false,
+        }
+    }
+
+    /// Create a filter that refuses messages containing banned words.
+    pub fn reject<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self {
+            reject: true,
+            ..Self::mask(words)
+        }
+    }
+
+    fn is_banned(&self, word: &str) -> bool {
+        !word.is_empty() && self.words.contains(&word.to_lowercase())
+    }
+}
+
+impl ModerationFilter for WordFilter {
+    fn check(&self, _sender_id: &str, _target: &ChatTarget, text: &str) -> Verdict {
+        let mut output = String::with_capacity(text.len());
+        let mut word = String::new();
+        let mut masked = false;
+        for c in text.chars().chain(std::iter::once(' ')) {
+            if c.is_alphanumeric() {
+                word.push(c);
+                continue;
+            }
+            if self.is_banned(&word) {
+                if self.reject {
+                    return Verdict::Reject(format!("Banned word '{}'", word));
+                }
+                output.extend(word.chars().map(|_| '*'));
+                masked = true;
+            } else {
+                output.push_str(&word);
+            }
+            word.clear();
+            output.push(c);
+        }
+        // Drop the separator chained on above
+        output.pop();
+
+        if masked {
+            Verdict::Replace(output)
+        } else {
+            Verdict::Allow
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Posting, delivering and reading chat messages.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use shared::error::ErrorCode;
+use shared::events::topics::{ChatAbuseKind, ChatAbuseReported, CHAT_ABUSE};
+use shared::events::{EventBus, EventBusExt};
+use shared::{SharedClock, Timestamp};
+use uuid::Uuid;
+
+use super::history::ChatHistory;
+use super::moderation::{ModerationFilter, Verdict};
+use super::{ChatMessage, ChatTarget};
+use crate::error::{ApiErrorResponse, ApiResult};
+use crate::websocket::{SessionHandle, SessionManager, ServerMessage};
+
+/// Characters of a refused message included in abuse reports
+const EXCERPT_CHARS: usize = 64;
+
+/// Senders tracked by the rate limiter before idle ones are swept
+const MIN_SWEEP_SENDERS: usize = 1024;
+
+/// Messages a sender may post within a window.
+#[derive(Debug, Clone, Copy)]
+pub struct ChatRateLimit {
+    pub max_messages: usize,
+    pub window: Duration,
+}
+
+impl Default for ChatRateLimit {
+    fn default() -> Self {
+        Self {
+            max_messages: 5,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Chat configuration.
+#[derive(Debug, Clone)]
+pub struct ChatConfig {
+    /// Longest message, in characters
+    pub max_message_len: usize,
+    /// Per-sender rate limit, across all channels and whispers
+    pub rate_limit: ChatRateLimit,
+    /// Most messages a history request returns
+    pub max_history: usize,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            max_message_len: 500,
+            rate_limit: ChatRateLimit::default(),
+            max_history: 50,
+        }
+    }
+}
+
+/// When each sender's recent messages were posted
+#[derive(Debug)]
+struct SendTimes {
+    by_sender: HashMap<String, VecDeque<Timestamp>>,
+    /// Sender count that triggers the next sweep
+    sweep_at: usize,
+}
+
+impl Default for SendTimes {
+    fn default() -> Self {
+        Self {
+            by_sender: HashMap::new(),
+            sweep_at: MIN_SWEEP_SENDERS,
+        }
+    }
+}
+
+/// Checks, delivers and records chat messages.
+pub struct ChatService {
+    config: ChatConfig,
+    clock: SharedClock,
+    history: Arc<dyn ChatHistory>,
+    filters: Vec<Arc<dyn ModerationFilter>>,
+    sent: Mutex<SendTimes>,
+    bus: Option<(Arc<dyn EventBus>, String)>,
+}
+
+impl ChatService {
+    /// Create a chat service without moderation filters.
+    pub fn new(config: ChatConfig, clock: SharedClock, history: Arc<dyn ChatHistory>) -> Self {
+        Self {
+            config,
+            clock,
+            history,
+            filters: Vec::new(),
+            sent: Mutex::new(SendTimes::default()),
+            bus: None,
+        }
+    }
+
+    /// Run messages through a moderation filter, after the ones added before it.
+    pub fn with_filter(mut self, filter: Arc<dyn ModerationFilter>) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Report refused messages on [`CHAT_ABUSE`] as `source`.
+    pub fn with_bus(mut self, bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        self.bus = Some((bus, source.into()));
+        self
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &ChatConfig {
+        &self.config
+    }
+
+    /// Post a message from a session's user.
+    ///
+    /// Channel messages need the session to be subscribed to the channel; whispers need
+    /// the recipient to be connected. Returns the delivered message.
+    pub async fn send(
+        &self,
+        manager: &SessionManager,
+        session: &SessionHandle,
+        target: ChatTarget,
+        text: &str,
+    ) -> ApiResult<ChatMessage> {
+        let sender_id = session.claims().sub.clone();
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "Message is empty"));
+        }
+        if text.chars().count() > self.config.max_message_len {
+            return Err(ApiErrorResponse::new(
+                ErrorCode::ValidationFailed,
+                format!("Messages are at most {} characters", self.config.max_message_len),
+            ));
+        }
+        self.authorize(session, &target)?;
+        if let ChatTarget::Whisper(recipient) = &target {
+            if !manager.is_online(recipient) {
+                return Err(ApiErrorResponse::new(ErrorCode::NotFound, format!("{} is not online", recipient)));
+            }
+        }
+
+        if !self.within_rate_limit(&sender_id) {
+            let limit = self.config.rate_limit;
+            let reason = format!("More than {} messages in {:?}", limit.max_messages, limit.window);
+            self.report(&sender_id, &target, ChatAbuseKind::Flooding, &reason, text).await;
+            return Err(ApiErrorResponse::new(ErrorCode::RateLimited, "Sending messages too fast"));
+        }
+
+        let mut moderated = text.to_string();
+        for filter in &self.filters {
+            match filter.check(&sender_id, &target, &moderated) {
+                Verdict::Allow => {}
+                Verdict::Replace(replacement) => moderated = replacement,
+                Verdict::Reject(reason) => {
+                    self.report(&sender_id, &target, ChatAbuseKind::Filtered, &reason, text).await;
+                    return Err(ApiErrorResponse::new(ErrorCode::PermissionDenied, "Message was blocked"));
+                }
+            }
+        }
+
+        let message = ChatMessage {
+            id: Uuid::new_v4().to_string(),
+            sender_id: sender_id.clone(),
+            target: target.clone(),
+            text: moderated,
+            sent_at: self.clock.now(),
+        };
+        // A message that reached its readers is not taken back because it could not be kept
+        if let Err(e) = self.history.append(&target.conversation(&sender_id), &message).await {
+            tracing::warn!(message_id = %message.id, "Failed to store chat message: {}", e);
+        }
+
+        let outbound = ServerMessage::Chat {
+            message: message.clone(),
+        };
+        match &target {
+            ChatTarget::Channel(channel) => {
+                manager.fan_out(channel, outbound);
+            }
+            ChatTarget::Whisper(recipient) => {
+                manager.send_to_user(recipient, outbound.clone());
+                manager.send_to_user(&sender_id, outbound);
+            }
+        }
+        Ok(message)
+    }
+
+    /// Get the latest messages of a channel or of the session user's whispers with
+    /// another user, oldest first.
+    pub async fn history(
+        &self,
+        session: &SessionHandle,
+        target: &ChatTarget,
+        limit: Option<usize>,
+    ) -> ApiResult<Vec<ChatMessage>> {
+        self.authorize(session, target)?;
+        let limit = limit.unwrap_or(self.config.max_history).min(self.config.max_history);
+        let conversation = target.conversation(&session.claims().sub);
+        Ok(self.history.recent(&conversation, limit).await?)
+    }
+
+    fn authorize(&self, session: &SessionHandle, target: &ChatTarget) -> ApiResult<()> {
+        match target {
+            ChatTarget::Channel(channel) if !session.is_subscribed(channel) => Err(ApiErrorResponse::new(
+                ErrorCode::PermissionDenied,
+                format!("Subscribe to '{}' to chat in it", channel),
+            )),
+            ChatTarget::Whisper(recipient) if *recipient == session.claims().sub => Err(ApiErrorResponse::new(
+                ErrorCode::ValidationFailed,
+                "Cannot whisper to yourself",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a message from `sender_id` if it is within the rate limit.
+    fn within_rate_limit(&self, sender_id: &str) -> bool {
+        let now = self.clock.now();
+        let window = chrono::Duration::from_std(self.config.rate_limit.window).unwrap_or_else(|_| chrono::Duration::zero());
+        let mut sent = self.sent.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if sent.by_sender.len() >= sent.sweep_at {
+            // Forget senders whose window has passed, so idle users do not pile up
+            sent.by_sender.retain(|_, times| times.back().is_some_and(|last| now - *last < window));
+            sent.sweep_at = (sent.by_sender.len() * 2).max(MIN_SWEEP_SENDERS);
+        }
+
+        let times = sent.by_sender.entry(sender_id.to_string()).or_default();
+        while times.front().is_some_and(|first| now - *first >= window) {
+            times.pop_front();
+        }
+        if times.len() >= self.config.rate_limit.max_messages {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+
+    async fn report(&self, sender_id: &str, target: &ChatTarget, kind: ChatAbuseKind, reason: &str, text: &str) {
+        tracing::info!(sender_id, target = %target, ?kind, "Chat message refused: {}", reason);
+        let Some((bus, source)) = &self.bus else {
+            return;
+        };
+        let report = ChatAbuseReported {
+            sender_id: sender_id.to_string(),
+            channel: target.to_string(),
+            kind,
+            reason: reason.to_string(),
+            excerpt: text.chars().take(EXCERPT_CHARS).collect(),
+        };
+        if let Err(e) = bus.publish_message(source, &CHAT_ABUSE, &report).await {
+            tracing::warn!("Failed to report chat abuse by {}: {}", sender_id, e);
+        }
+    }
+}
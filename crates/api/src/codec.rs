@@ -0,0 +1,341 @@
+//! Compact binary encoding of stat snapshots for real-time sync.
+//!
+//! Snapshots, snapshot deltas, and elemental deltas are encoded with the messages in
+//! `proto/chaos/v1/snapshot.proto`, which also documents the schema evolution rules.
+//! Stat names are sent once per message in a dictionary, so a typical snapshot is a
+//! fraction of its JSON size (see `benches/snapshot_codec.rs`).
+//!
+//! Protobuf is the default [`SnapshotEncoding`] on WebSocket and gRPC paths; JSON
+//! stays available for debugging and for clients without a protobuf runtime.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use actor_core::enums::AcrossLayerPolicy;
+use actor_core::types::{Caps, Snapshot};
+use chrono::{TimeZone, Utc};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+
+use crate::error::{ApiErrorResponse, ApiResult};
+use crate::grpc::proto::{
+    sync_frame, CompactSnapshot, ElementalDelta, SnapshotDelta, StatCapValues, StatValues, SyncFrame,
+};
+
+/// Wire encoding of sync updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnapshotEncoding {
+    /// Protobuf messages from `snapshot.proto`
+    #[default]
+    Protobuf,
+    /// The same messages as JSON
+    Json,
+}
+
+impl SnapshotEncoding {
+    /// Get the MIME type of the encoding.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Protobuf => "application/x-protobuf",
+            Self::Json => "application/json",
+        }
+    }
+}
+
+impl FromStr for SnapshotEncoding {
+    type Err = ApiErrorResponse;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "protobuf" | "proto" => Ok(Self::Protobuf),
+            "json" => Ok(Self::Json),
+            _ => Err(ApiErrorResponse::new(
+                ErrorCode::ValidationFailed,
+                format!("Unknown snapshot encoding: '{}'", value),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SnapshotEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Protobuf => write!(f, "protobuf"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Per-element stat values of an actor, keyed by element ID then stat name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ElementalStats {
+    /// Actor ID
+    pub actor_id: String,
+    /// Version of the values
+    pub version: i64,
+    /// Stat values by element
+    pub elements: HashMap<String, HashMap<String, f64>>,
+}
+
+/// Interns names into a message's dictionary.
+#[derive(Default)]
+struct Dictionary {
+    names: Vec<String>,
+    indexes: HashMap<String, u32>,
+}
+
+impl Dictionary {
+    fn index(&mut self, name: &str) -> u32 {
+        if let Some(index) = self.indexes.get(name) {
+            return *index;
+        }
+        let index = self.names.len() as u32;
+        self.names.push(name.to_string());
+        self.indexes.insert(name.to_string(), index);
+        index
+    }
+
+    fn values<'a>(&mut self, values: impl IntoIterator<Item = (&'a String, f64)>) -> Option<StatValues> {
+        let mut encoded = StatValues::default();
+        for (name, value) in values {
+            encoded.index.push(self.index(name));
+            encoded.value.push(value);
+        }
+        Some(encoded).filter(|encoded| !encoded.index.is_empty())
+    }
+
+    fn caps<'a>(&mut self, caps: impl IntoIterator<Item = (&'a String, &'a Caps)>) -> Option<StatCapValues> {
+        let mut encoded = StatCapValues::default();
+        for (name, caps) in caps {
+            encoded.index.push(self.index(name));
+            encoded.min.push(caps.min);
+            encoded.max.push(caps.max);
+        }
+        Some(encoded).filter(|encoded| !encoded.index.is_empty())
+    }
+}
+
+impl From<&Snapshot> for CompactSnapshot {
+    fn from(snapshot: &Snapshot) -> Self {
+        let mut dictionary = Dictionary::default();
+        let primary = dictionary.values(sorted(&snapshot.primary).map(|(name, value)| (name, *value)));
+        let derived = dictionary.values(sorted(&snapshot.derived).map(|(name, value)| (name, *value)));
+        let caps = dictionary.caps(sorted(&snapshot.caps_used));
+        Self {
+            actor_id: snapshot.actor_id.clone(),
+            version: snapshot.version,
+            dimensions: dictionary.names,
+            primary,
+            derived,
+            caps,
+            cache_hit: snapshot.cache_hit,
+            created_at_ms: snapshot.created_at.timestamp_millis(),
+        }
+    }
+}
+
+impl TryFrom<CompactSnapshot> for Snapshot {
+    type Error = ApiErrorResponse;
+
+    fn try_from(compact: CompactSnapshot) -> ApiResult<Self> {
+        let mut snapshot = Snapshot::new(compact.actor_id);
+        snapshot.version = compact.version;
+        snapshot.cache_hit = compact.cache_hit;
+        snapshot.created_at = Utc
+            .timestamp_millis_opt(compact.created_at_ms)
+            .single()
+            .ok_or_else(|| invalid("created_at_ms is out of range"))?;
+        snapshot.primary = decode_values(&compact.dimensions, compact.primary)?.collect();
+        snapshot.derived = decode_values(&compact.dimensions, compact.derived)?.collect();
+        snapshot.caps_used = decode_caps(&compact.dimensions, compact.caps)?.collect();
+        Ok(snapshot)
+    }
+}
+
+/// Compute the changes turning `previous` into `current`.
+pub fn snapshot_delta(previous: &Snapshot, current: &Snapshot) -> SnapshotDelta {
+    let mut dictionary = Dictionary::default();
+    let primary = dictionary.values(changed(&previous.primary, &current.primary));
+    let derived = dictionary.values(changed(&previous.derived, &current.derived));
+    let caps = dictionary.caps(
+        sorted(&current.caps_used)
+            .filter(|(name, caps)| previous.caps_used.get(*name).is_none_or(|old| old.min != caps.min || old.max != caps.max)),
+    );
+    let removed_primary = removed(&mut dictionary, &previous.primary, &current.primary);
+    let removed_derived = removed(&mut dictionary, &previous.derived, &current.derived);
+    let removed_caps = removed(&mut dictionary, &previous.caps_used, &current.caps_used);
+    SnapshotDelta {
+        actor_id: current.actor_id.clone(),
+        base_version: previous.version,
+        version: current.version,
+        dimensions: dictionary.names,
+        primary,
+        derived,
+        caps,
+        removed_primary,
+        removed_derived,
+        removed_caps,
+    }
+}
+
+/// Apply a delta produced by [`snapshot_delta`].
+///
+/// Fails with `CONFLICT` if the delta was computed against another version; the client
+/// should then request a full snapshot.
+pub fn apply_snapshot_delta(snapshot: &mut Snapshot, delta: SnapshotDelta) -> ApiResult<()> {
+    check_base(&snapshot.actor_id, snapshot.version, &delta.actor_id, delta.base_version)?;
+    let dimensions = &delta.dimensions;
+    let primary: Vec<_> = decode_values(dimensions, delta.primary)?.collect();
+    let derived: Vec<_> = decode_values(dimensions, delta.derived)?.collect();
+    let caps: Vec<_> = decode_caps(dimensions, delta.caps)?.collect();
+    let removed_primary = decode_names(dimensions, &delta.removed_primary)?;
+    let removed_derived = decode_names(dimensions, &delta.removed_derived)?;
+    let removed_caps = decode_names(dimensions, &delta.removed_caps)?;
+
+    for name in removed_primary {
+        snapshot.primary.remove(name);
+    }
+    for name in removed_derived {
+        snapshot.derived.remove(name);
+    }
+    for name in removed_caps {
+        snapshot.caps_used.remove(name);
+    }
+    snapshot.primary.extend(primary);
+    snapshot.derived.extend(derived);
+    snapshot.caps_used.extend(caps);
+    snapshot.version = delta.version;
+    Ok(())
+}
+
+/// Compute the per-element changes turning `previous` into `current`.
+///
+/// Elemental stats are fixed per element, so only added and changed values are sent.
+pub fn elemental_delta(previous: &ElementalStats, current: &ElementalStats) -> ElementalDelta {
+    let mut elements = Dictionary::default();
+    let mut dimensions = Dictionary::default();
+    let mut delta = ElementalDelta {
+        actor_id: current.actor_id.clone(),
+        base_version: previous.version,
+        version: current.version,
+        ..ElementalDelta::default()
+    };
+    let empty = HashMap::new();
+    for (element, stats) in sorted(&current.elements) {
+        let old = previous.elements.get(element).unwrap_or(&empty);
+        for (stat, value) in changed(old, stats) {
+            delta.element.push(elements.index(element));
+            delta.stat.push(dimensions.index(stat));
+            delta.value.push(value);
+        }
+    }
+    delta.elements = elements.names;
+    delta.dimensions = dimensions.names;
+    delta
+}
+
+/// Apply a delta produced by [`elemental_delta`].
+pub fn apply_elemental_delta(stats: &mut ElementalStats, delta: ElementalDelta) -> ApiResult<()> {
+    check_base(&stats.actor_id, stats.version, &delta.actor_id, delta.base_version)?;
+    if delta.element.len() != delta.stat.len() || delta.stat.len() != delta.value.len() {
+        return Err(invalid("Elemental delta lists differ in length"));
+    }
+    let elements = decode_names(&delta.elements, &delta.element)?;
+    let dimensions = decode_names(&delta.dimensions, &delta.stat)?;
+    for ((element, stat), value) in elements.into_iter().zip(dimensions).zip(delta.value) {
+        stats
+            .elements
+            .entry(element.to_string())
+            .or_default()
+            .insert(stat.to_string(), value);
+    }
+    stats.version = delta.version;
+    Ok(())
+}
+
+/// Encode a sync update as a binary WebSocket frame.
+pub fn encode_frame(channel: &str, update: sync_frame::Update) -> Vec<u8> {
+    SyncFrame {
+        channel: channel.to_string(),
+        update: Some(update),
+    }
+    .encode_to_vec()
+}
+
+/// Decode a binary WebSocket frame.
+pub fn decode_frame(bytes: &[u8]) -> ApiResult<SyncFrame> {
+    SyncFrame::decode(bytes).map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.to_string()))
+}
+
+fn sorted<V>(map: &HashMap<String, V>) -> impl Iterator<Item = (&String, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries.into_iter()
+}
+
+fn changed<'a>(previous: &'a HashMap<String, f64>, current: &'a HashMap<String, f64>) -> impl Iterator<Item = (&'a String, f64)> {
+    sorted(current)
+        .filter(move |(name, value)| previous.get(*name) != Some(*value))
+        .map(|(name, value)| (name, *value))
+}
+
+fn removed<V, W>(dictionary: &mut Dictionary, previous: &HashMap<String, V>, current: &HashMap<String, W>) -> Vec<u32> {
+    let names: BTreeSet<_> = previous.keys().filter(|name| !current.contains_key(*name)).collect();
+    names.into_iter().map(|name| dictionary.index(name)).collect()
+}
+
+fn check_base(actor_id: &str, version: i64, delta_actor_id: &str, base_version: i64) -> ApiResult<()> {
+    if actor_id != delta_actor_id {
+        return Err(invalid("Delta belongs to another actor"));
+    }
+    if version != base_version {
+        return Err(ApiErrorResponse::new(
+            ErrorCode::Conflict,
+            format!("Delta applies to version {}, have {}", base_version, version),
+        ));
+    }
+    Ok(())
+}
+
+fn decode_names<'a>(dictionary: &'a [String], indexes: &[u32]) -> ApiResult<Vec<&'a str>> {
+    indexes
+        .iter()
+        .map(|index| {
+            dictionary
+                .get(*index as usize)
+                .map(String::as_str)
+                .ok_or_else(|| invalid("Dictionary index out of range"))
+        })
+        .collect()
+}
+
+fn decode_values(dictionary: &[String], values: Option<StatValues>) -> ApiResult<impl Iterator<Item = (String, f64)>> {
+    let values = values.unwrap_or_default();
+    if values.index.len() != values.value.len() {
+        return Err(invalid("Stat value lists differ in length"));
+    }
+    let names: Vec<String> = decode_names(dictionary, &values.index)?.into_iter().map(str::to_string).collect();
+    Ok(names.into_iter().zip(values.value))
+}
+
+fn decode_caps(dictionary: &[String], caps: Option<StatCapValues>) -> ApiResult<impl Iterator<Item = (String, Caps)>> {
+    let caps = caps.unwrap_or_default();
+    if caps.index.len() != caps.min.len() || caps.min.len() != caps.max.len() {
+        return Err(invalid("Cap lists differ in length"));
+    }
+    let names: Vec<String> = decode_names(dictionary, &caps.index)?.into_iter().map(str::to_string).collect();
+    Ok(names.into_iter().zip(caps.min.into_iter().zip(caps.max)).map(|(name, (min, max))| {
+        // The combining policy is server-side detail and is not sent over the wire.
+        let mut decoded = Caps::new(name.clone(), AcrossLayerPolicy::Intersect);
+        decoded.min = min;
+        decoded.max = max;
+        (name, decoded)
+    }))
+}
+
+fn invalid(message: &str) -> ApiErrorResponse {
+    ApiErrorResponse::new(ErrorCode::InvalidFormat, message)
+}
@@ -0,0 +1,77 @@
+//! API error handling.
+//!
+//! Handlers return [`ApiResult`]; any error implementing [`ToApiError`] (core crate
+//! errors, `ChaosError`, ...) converts into [`ApiErrorResponse`] with `?`, which renders
+//! as an HTTP response or a gRPC status using the shared error code catalog.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use shared::error::{ApiError, ErrorCode, ToApiError};
+
+/// Result type for API handlers.
+pub type ApiResult<T> = Result<T, ApiErrorResponse>;
+
+/// Metadata key carrying the stable error code on gRPC responses.
+pub const GRPC_ERROR_CODE_METADATA: &str = "x-chaos-error-code";
+
+/// Error returned by API handlers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiErrorResponse {
+    /// Client-facing error
+    pub error: ApiError,
+}
+
+impl ApiErrorResponse {
+    /// Create an error response with a client-safe message.
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            error: ApiError::new(code, message),
+        }
+    }
+
+    /// Get the HTTP status code.
+    pub fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(self.error.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Convert into a gRPC status.
+    pub fn to_grpc_status(&self) -> tonic::Status {
+        let code = tonic::Code::from_i32(self.error.grpc_code() as i32);
+        let mut status = tonic::Status::new(code, self.error.message.clone());
+        if let Ok(value) = self.error.code.as_str().parse() {
+            status.metadata_mut().insert(GRPC_ERROR_CODE_METADATA, value);
+        }
+        status
+    }
+}
+
+impl<E: ToApiError> From<E> for ApiErrorResponse {
+    fn from(error: E) -> Self {
+        let error = error.to_api_error();
+        if !error.code.exposes_details() {
+            tracing::error!(code = %error.code, "API request failed");
+        }
+        Self { error }
+    }
+}
+
+impl std::fmt::Display for ApiErrorResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for ApiErrorResponse {}
+
+impl IntoResponse for ApiErrorResponse {
+    fn into_response(self) -> Response {
+        (self.status_code(), Json(self.error)).into_response()
+    }
+}
+
+impl From<ApiErrorResponse> for tonic::Status {
+    fn from(error: ApiErrorResponse) -> Self {
+        error.to_grpc_status()
+    }
+}
@@ -0,0 +1,238 @@
+//! Global cooldown-aware action queue in front of a [`CombatBackend`].
+//!
+//! Every resolved action starts its attacker's global cooldown. An action submitted within
+//! [`queue_window`](ActionQueueConfig::queue_window) of the cooldown ending is held and
+//! handed to the backend once it ends, so players can press their next skill slightly early
+//! without the server trusting client timing; earlier ones are rejected. Held actions are
+//! resolved against the actor's state when they run, so resources and conditions are
+//! checked then rather than when they were queued. Each actor holds at most
+//! [`max_depth`](ActionQueueConfig::max_depth) actions, which can be cancelled until they
+//! run.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::Duration as ChronoDuration;
+use shared::{SharedClock, Timestamp};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use super::combat::CombatBackend;
+use super::proto::{CombatAction, CombatActionResult};
+use crate::error::ApiResult;
+
+/// Action queue configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct ActionQueueConfig {
+    /// Cooldown started by every resolved action
+    pub global_cooldown: Duration,
+    /// How long before the cooldown ends an action may be queued
+    pub queue_window: Duration,
+    /// Most actions an actor may have queued
+    pub max_depth: usize,
+    /// How often queued actions are checked for running
+    pub poll_interval: Duration,
+}
+
+impl Default for ActionQueueConfig {
+    fn default() -> Self {
+        Self {
+            global_cooldown: Duration::from_millis(1500),
+            queue_window: Duration::from_millis(400),
+            max_depth: 1,
+            poll_interval: Duration::from_millis(20),
+        }
+    }
+}
+
+/// An action waiting for its attacker's global cooldown
+struct Queued {
+    action: CombatAction,
+    reply: oneshot::Sender<ApiResult<CombatActionResult>>,
+}
+
+/// Global cooldown and queued actions of one actor
+#[derive(Default)]
+struct ActorQueue {
+    cooldown_ends: Option<Timestamp>,
+    queued: VecDeque<Queued>,
+}
+
+/// Queues combat actions behind each attacker's global cooldown.
+///
+/// Implements [`CombatBackend`] itself, so it is mounted in place of the backend it wraps:
+///
+/// ```ignore
+/// let queue = Arc::new(ActionQueue::new(backend, ActionQueueConfig::default(), shared::wall_clock()));
+/// queue.spawn();
+/// let router = GrpcServerBuilder::new().with_combat(queue).build().await?;
+/// ```
+pub struct ActionQueue {
+    backend: Arc<dyn CombatBackend>,
+    config: ActionQueueConfig,
+    clock: SharedClock,
+    actors: Mutex<HashMap<String, ActorQueue>>,
+}
+
+impl ActionQueue {
+    /// Create a queue in front of a combat backend.
+    pub fn new(backend: Arc<dyn CombatBackend>, config: ActionQueueConfig, clock: SharedClock) -> Self {
+        Self {
+            backend,
+            config,
+            clock,
+            actors: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &ActionQueueConfig {
+        &self.config
+    }
+
+    /// Get how many actions an actor has queued.
+    pub fn queued(&self, actor_id: &str) -> usize {
+        self.lock().get(actor_id).map_or(0, |queue| queue.queued.len())
+    }
+
+    /// Run queued actions every poll interval until the task is aborted.
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(queue.config.poll_interval);
+            loop {
+                interval.tick().await;
+                queue.run_due().await;
+            }
+        })
+    }
+
+    /// Run the queued actions whose attacker's global cooldown has ended.
+    ///
+    /// Each actor runs at most one, since it starts the cooldown again.
+    pub async fn run_due(&self) {
+        let now = self.clock.now();
+        let due: Vec<Queued> = {
+            let mut actors = self.lock();
+            let mut due = Vec::new();
+            for queue in actors.values_mut() {
+                if queue.cooldown_ends.is_some_and(|ends| ends > now) {
+                    continue;
+                }
+                // Skip actions whose caller went away
+                while let Some(queued) = queue.queued.pop_front() {
+                    if !queued.reply.is_closed() {
+                        queue.cooldown_ends = Some(now + self.cooldown());
+                        due.push(queued);
+                        break;
+                    }
+                }
+            }
+            actors.retain(|_, queue| !queue.queued.is_empty() || queue.cooldown_ends.is_some_and(|ends| ends > now));
+            due
+        };
+
+        for queued in due {
+            let result = self.resolve(queued.action, now).await;
+            let _ = queued.reply.send(result);
+        }
+    }
+
+    /// Cancel a queued action, returning whether it was still waiting.
+    pub fn cancel(&self, attacker_id: &str, action_id: &str) -> bool {
+        let cancelled = {
+            let mut actors = self.lock();
+            let Some(queue) = actors.get_mut(attacker_id) else {
+                return false;
+            };
+            match queue.queued.iter().position(|queued| queued.action.action_id == action_id) {
+                Some(index) => queue.queued.remove(index),
+                None => None,
+            }
+        };
+        match cancelled {
+            Some(queued) => {
+                let _ = queued.reply.send(Ok(rejected(&queued.action, "Cancelled")));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resolve an action right away, queue it or reject it
+    async fn submit(&self, action: CombatAction) -> ApiResult<CombatActionResult> {
+        let now = self.clock.now();
+        let receiver = {
+            let mut actors = self.lock();
+            let queue = actors.entry(action.attacker_id.clone()).or_default();
+            let ready_at = queue.cooldown_ends.map_or(now, |ends| ends.max(now))
+                + self.cooldown() * queue.queued.len() as i32;
+            if queue.queued.is_empty() && ready_at <= now {
+                queue.cooldown_ends = Some(now + self.cooldown());
+                None
+            } else if queue.queued.len() >= self.config.max_depth {
+                return Ok(rejected(&action, "Action queue is full"));
+            } else if ready_at - now > window(self.config.queue_window) {
+                return Ok(rejected(&action, "On global cooldown"));
+            } else {
+                let (reply, receiver) = oneshot::channel();
+                queue.queued.push_back(Queued { action: action.clone(), reply });
+                Some(receiver)
+            }
+        };
+
+        match receiver {
+            None => self.resolve(action, now).await,
+            // The sender only goes away with the queue itself
+            Some(receiver) => receiver.await.unwrap_or_else(|_| Ok(rejected(&action, "Action queue stopped"))),
+        }
+    }
+
+    /// Hand an action to the backend, giving the cooldown back when it is rejected
+    async fn resolve(&self, action: CombatAction, started: Timestamp) -> ApiResult<CombatActionResult> {
+        let attacker_id = action.attacker_id.clone();
+        let result = self.backend.submit_action(action).await;
+        if !matches!(&result, Ok(result) if result.accepted) {
+            if let Some(queue) = self.lock().get_mut(&attacker_id) {
+                if queue.cooldown_ends == Some(started + self.cooldown()) {
+                    queue.cooldown_ends = None;
+                }
+            }
+        }
+        result
+    }
+
+    fn cooldown(&self) -> ChronoDuration {
+        window(self.config.global_cooldown)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, ActorQueue>> {
+        self.actors.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl CombatBackend for ActionQueue {
+    async fn submit_action(&self, action: CombatAction) -> ApiResult<CombatActionResult> {
+        self.submit(action).await
+    }
+
+    async fn cancel_action(&self, attacker_id: &str, action_id: &str) -> ApiResult<bool> {
+        Ok(self.cancel(attacker_id, action_id))
+    }
+}
+
+fn window(duration: Duration) -> ChronoDuration {
+    ChronoDuration::from_std(duration).unwrap_or(ChronoDuration::MAX)
+}
+
+fn rejected(action: &CombatAction, reason: &str) -> CombatActionResult {
+    CombatActionResult {
+        action_id: action.action_id.clone(),
+        accepted: false,
+        rejection_reason: reason.to_string(),
+        hits: Vec::new(),
+    }
+}
@@ -0,0 +1,104 @@
+//! Actor stats gRPC service backed by an actor-core aggregator.
+
+use std::sync::Arc;
+
+use actor_core::aggregation_context::AggregationContext;
+use actor_core::interfaces::Aggregator;
+use actor_core::types::{Actor, Snapshot};
+use shared::error::ErrorCode;
+use tonic::{Request, Response, Status};
+
+use super::convert::struct_to_map;
+use super::proto::actor_stats_server::{ActorStats, ActorStatsServer};
+use super::proto::{
+    ActorId, CompactSnapshot, InvalidateStatsResponse, ResolveStatsBatchRequest, ResolveStatsBatchResponse,
+    ResolveStatsRequest, StatSnapshot,
+};
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Largest number of actors accepted in one batch request.
+pub const MAX_BATCH_SIZE: usize = 256;
+
+/// gRPC service resolving actor stats.
+#[derive(Clone)]
+pub struct ActorStatsService {
+    aggregator: Arc<dyn Aggregator>,
+}
+
+impl ActorStatsService {
+    /// Create a service backed by an aggregator.
+    pub fn new(aggregator: Arc<dyn Aggregator>) -> Self {
+        Self { aggregator }
+    }
+
+    /// Wrap the service in its generated tonic server.
+    pub fn into_server(self) -> ActorStatsServer<Self> {
+        ActorStatsServer::new(self)
+    }
+
+    async fn resolve(&self, request: ResolveStatsRequest) -> ApiResult<Snapshot> {
+        let actor = required_actor(request.actor)?;
+        let snapshot = match request.context {
+            Some(context) => {
+                let context = AggregationContext::from_map(struct_to_map(context))?;
+                self.aggregator.resolve_with_context(&actor, &context).await
+            },
+            None => self.aggregator.resolve(&actor).await,
+        }?;
+        Ok(snapshot)
+    }
+}
+
+#[tonic::async_trait]
+impl ActorStats for ActorStatsService {
+    async fn resolve_stats(&self, request: Request<ResolveStatsRequest>) -> Result<Response<StatSnapshot>, Status> {
+        let snapshot = self.resolve(request.into_inner()).await?;
+        Ok(Response::new(snapshot.into()))
+    }
+
+    async fn resolve_stats_compact(
+        &self,
+        request: Request<ResolveStatsRequest>,
+    ) -> Result<Response<CompactSnapshot>, Status> {
+        let snapshot = self.resolve(request.into_inner()).await?;
+        Ok(Response::new(CompactSnapshot::from(&snapshot)))
+    }
+
+    async fn resolve_stats_batch(
+        &self,
+        request: Request<ResolveStatsBatchRequest>,
+    ) -> Result<Response<ResolveStatsBatchResponse>, Status> {
+        let actors = request.into_inner().actors;
+        if actors.len() > MAX_BATCH_SIZE {
+            return Err(ApiErrorResponse::new(
+                ErrorCode::ValidationFailed,
+                format!("At most {} actors per batch", MAX_BATCH_SIZE),
+            )
+            .into());
+        }
+        let actors = actors.into_iter().map(Actor::try_from).collect::<ApiResult<Vec<_>>>()?;
+        let snapshots = self.aggregator.resolve_batch(&actors).await.map_err(ApiErrorResponse::from)?;
+        Ok(Response::new(ResolveStatsBatchResponse {
+            snapshots: snapshots.into_iter().map(StatSnapshot::from).collect(),
+        }))
+    }
+
+    async fn get_cached_stats(&self, request: Request<ActorId>) -> Result<Response<StatSnapshot>, Status> {
+        let actor_id = request.into_inner().id;
+        let snapshot = self.aggregator.get_cached_snapshot(&actor_id).ok_or_else(|| {
+            ApiErrorResponse::new(ErrorCode::NotFound, format!("No cached stats for actor '{}'", actor_id))
+        })?;
+        Ok(Response::new(snapshot.into()))
+    }
+
+    async fn invalidate_stats(&self, request: Request<ActorId>) -> Result<Response<InvalidateStatsResponse>, Status> {
+        self.aggregator.invalidate_cache(&request.into_inner().id);
+        Ok(Response::new(InvalidateStatsResponse {}))
+    }
+}
+
+fn required_actor(actor: Option<super::proto::Actor>) -> ApiResult<Actor> {
+    actor
+        .ok_or_else(|| ApiErrorResponse::new(ErrorCode::ValidationFailed, "Actor is required"))?
+        .try_into()
+}
@@ -0,0 +1,145 @@
+//! Combat gRPC service.
+//!
+//! Combat resolution lives outside this crate; the service validates requests and
+//! delegates to a [`CombatBackend`], usually behind an [`ActionQueue`] enforcing the
+//! global cooldown. The combat log is streamed from an [`EventStreamHub`].
+//!
+//! [`ActionQueue`]: super::ActionQueue
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use shared::error::ErrorCode;
+use tonic::{Request, Response, Status};
+
+use super::convert::payload_struct;
+use super::proto::combat_server::{Combat, CombatServer};
+use super::proto::{
+    CancelActionRequest, CancelActionResult, CombatAction, CombatActionResult, CombatLogEntry, CombatLogRequest,
+};
+use super::streaming::{event_type, involves_actor, EventStream, EventStreamHub, StreamedEvent, COMBAT_LOG_TOPIC};
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Resolves combat actions for [`CombatService`].
+#[async_trait]
+pub trait CombatBackend: Send + Sync {
+    /// Resolve a validated combat action.
+    async fn submit_action(&self, action: CombatAction) -> ApiResult<CombatActionResult>;
+
+    /// Cancel an action that has not run yet, returning whether it was cancelled.
+    ///
+    /// Backends resolving actions right away have nothing to cancel.
+    async fn cancel_action(&self, _attacker_id: &str, _action_id: &str) -> ApiResult<bool> {
+        Ok(false)
+    }
+}
+
+/// gRPC service accepting combat actions.
+#[derive(Clone)]
+pub struct CombatService {
+    backend: Arc<dyn CombatBackend>,
+    events: Option<Arc<EventStreamHub>>,
+}
+
+impl CombatService {
+    /// Create a service backed by a combat backend.
+    pub fn new(backend: Arc<dyn CombatBackend>) -> Self {
+        Self { backend, events: None }
+    }
+
+    /// Serve the combat log stream from a hub.
+    pub fn with_event_streams(mut self, events: Arc<EventStreamHub>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Wrap the service in its generated tonic server.
+    pub fn into_server(self) -> CombatServer<Self> {
+        CombatServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl Combat for CombatService {
+    type SubscribeCombatLogStream = EventStream<CombatLogEntry>;
+
+    async fn submit_action(&self, request: Request<CombatAction>) -> Result<Response<CombatActionResult>, Status> {
+        let action = request.into_inner();
+        validate_action(&action)?;
+        let result = self.backend.submit_action(action).await?;
+        Ok(Response::new(result))
+    }
+
+    async fn cancel_action(
+        &self,
+        request: Request<CancelActionRequest>,
+    ) -> Result<Response<CancelActionResult>, Status> {
+        let request = request.into_inner();
+        validate_cancel(&request)?;
+        let cancelled = self.backend.cancel_action(&request.attacker_id, &request.action_id).await?;
+        Ok(Response::new(CancelActionResult { cancelled }))
+    }
+
+    async fn subscribe_combat_log(
+        &self,
+        request: Request<CombatLogRequest>,
+    ) -> Result<Response<Self::SubscribeCombatLogStream>, Status> {
+        let events = self
+            .events
+            .as_ref()
+            .ok_or_else(|| ApiErrorResponse::new(ErrorCode::Unimplemented, "Combat log streams are not enabled"))?;
+        let request = request.into_inner();
+
+        let actor_ids = request.actor_ids;
+        let resume_token = Some(request.resume_token.as_str()).filter(|token| !token.is_empty());
+        let stream = events
+            .subscribe(COMBAT_LOG_TOPIC, resume_token, move |envelope| {
+                actor_ids.is_empty() || involves_actor(envelope, &actor_ids)
+            })
+            .await?;
+
+        Ok(Response::new(stream.map(|event| event.map(to_log_entry)).boxed()))
+    }
+}
+
+fn to_log_entry(event: StreamedEvent) -> CombatLogEntry {
+    CombatLogEntry {
+        event_id: event.envelope.id.to_string(),
+        event_type: event_type(&event.envelope).to_string(),
+        payload: payload_struct(&event.envelope.payload),
+        timestamp: event.envelope.timestamp.to_rfc3339(),
+        resume_token: event.resume_token,
+        gap: event.gap,
+    }
+}
+
+fn validate_action(action: &CombatAction) -> ApiResult<()> {
+    let missing = if action.action_id.is_empty() {
+        Some("action_id")
+    } else if action.attacker_id.is_empty() {
+        Some("attacker_id")
+    } else if action.skill_id.is_empty() {
+        Some("skill_id")
+    } else {
+        None
+    };
+    match missing {
+        Some(field) => Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, format!("{} is required", field))),
+        None => Ok(()),
+    }
+}
+
+fn validate_cancel(request: &CancelActionRequest) -> ApiResult<()> {
+    let missing = if request.attacker_id.is_empty() {
+        Some("attacker_id")
+    } else if request.action_id.is_empty() {
+        Some("action_id")
+    } else {
+        None
+    };
+    match missing {
+        Some(field) => Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, format!("{} is required", field))),
+        None => Ok(()),
+    }
+}
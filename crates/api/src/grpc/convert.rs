@@ -0,0 +1,129 @@
+//! Conversions between protobuf messages and core crate types.
+
+use std::collections::HashMap;
+
+use actor_core::types::{Actor, Snapshot};
+use prost_types::value::Kind;
+use shared::error::ErrorCode;
+
+use super::proto;
+use crate::error::{ApiErrorResponse, ApiResult};
+
+impl TryFrom<proto::Actor> for Actor {
+    type Error = ApiErrorResponse;
+
+    fn try_from(actor: proto::Actor) -> ApiResult<Self> {
+        if actor.id.is_empty() {
+            return Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "Actor id is required"));
+        }
+
+        let mut converted = Actor::new(actor.id, actor.race);
+        converted.name = actor.name;
+        converted.level = actor.level;
+        if !actor.core_resources.is_empty() {
+            if actor.core_resources.len() != converted.core_resources.len() {
+                return Err(ApiErrorResponse::new(
+                    ErrorCode::ValidationFailed,
+                    format!("Expected {} core resources", converted.core_resources.len()),
+                ));
+            }
+            converted.core_resources.copy_from_slice(&actor.core_resources);
+        }
+        converted.custom_resources = actor.custom_resources;
+        converted.subsystems = actor.subsystems;
+        converted.version = actor.version;
+        converted.data = actor.data.map(struct_to_map).unwrap_or_default();
+        Ok(converted)
+    }
+}
+
+impl From<&Actor> for proto::Actor {
+    fn from(actor: &Actor) -> Self {
+        Self {
+            id: actor.id.clone(),
+            name: actor.name.clone(),
+            race: actor.race.clone(),
+            level: actor.level,
+            core_resources: actor.core_resources.to_vec(),
+            custom_resources: actor.custom_resources.clone(),
+            subsystems: actor.subsystems.clone(),
+            version: actor.version,
+            data: Some(map_to_struct(&actor.data)),
+        }
+    }
+}
+
+impl From<Snapshot> for proto::StatSnapshot {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            actor_id: snapshot.actor_id,
+            primary: snapshot.primary,
+            derived: snapshot.derived,
+            caps: snapshot
+                .caps_used
+                .into_iter()
+                .map(|(stat, caps)| (stat, proto::StatCaps { min: caps.min, max: caps.max }))
+                .collect(),
+            version: snapshot.version,
+            cache_hit: snapshot.cache_hit,
+            created_at: snapshot.created_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Convert a protobuf `Struct` into a JSON object map.
+pub fn struct_to_map(value: prost_types::Struct) -> HashMap<String, serde_json::Value> {
+    value.fields.into_iter().map(|(key, value)| (key, value_to_json(value))).collect()
+}
+
+/// Convert a JSON object map into a protobuf `Struct`.
+pub fn map_to_struct(map: &HashMap<String, serde_json::Value>) -> prost_types::Struct {
+    prost_types::Struct {
+        fields: map.iter().map(|(key, value)| (key.clone(), json_to_value(value))).collect(),
+    }
+}
+
+/// Convert a protobuf `Value` into JSON.
+pub fn value_to_json(value: prost_types::Value) -> serde_json::Value {
+    match value.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(value)) => serde_json::Value::Bool(value),
+        Some(Kind::NumberValue(value)) => serde_json::Number::from_f64(value)
+            .map(serde_json::Value::Number)
+            .unwrap_or(serde_json::Value::Null),
+        Some(Kind::StringValue(value)) => serde_json::Value::String(value),
+        Some(Kind::ListValue(list)) => serde_json::Value::Array(list.values.into_iter().map(value_to_json).collect()),
+        Some(Kind::StructValue(value)) => serde_json::Value::Object(
+            value.fields.into_iter().map(|(key, value)| (key, value_to_json(value))).collect(),
+        ),
+    }
+}
+
+/// Convert JSON into a protobuf `Value`.
+pub fn json_to_value(value: &serde_json::Value) -> prost_types::Value {
+    let kind = match value {
+        serde_json::Value::Null => Kind::NullValue(prost_types::NullValue::NullValue as i32),
+        serde_json::Value::Bool(value) => Kind::BoolValue(*value),
+        serde_json::Value::Number(value) => Kind::NumberValue(value.as_f64().unwrap_or_default()),
+        serde_json::Value::String(value) => Kind::StringValue(value.clone()),
+        serde_json::Value::Array(values) => Kind::ListValue(prost_types::ListValue {
+            values: values.iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(fields) => Kind::StructValue(prost_types::Struct {
+            fields: fields.iter().map(|(key, value)| (key.clone(), json_to_value(value))).collect(),
+        }),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+/// Convert an event payload into a protobuf `Struct`, wrapping non-object payloads
+/// under a `value` field.
+pub fn payload_struct(payload: &serde_json::Value) -> Option<prost_types::Struct> {
+    match json_to_value(payload).kind {
+        Some(Kind::StructValue(fields)) => Some(fields),
+        Some(kind) => Some(prost_types::Struct {
+            fields: [("value".to_string(), prost_types::Value { kind: Some(kind) })].into_iter().collect(),
+        }),
+        None => None,
+    }
+}
@@ -0,0 +1,34 @@
+//! gRPC services.
+//!
+//! Proto definitions live in `proto/chaos/v1` and are compiled by `build.rs` into
+//! [`proto`]. Each service wraps a backend (an actor-core [`Aggregator`] for stats,
+//! [`CombatBackend`] / [`WorldBackend`] for combat and world queries) and is mounted by
+//! [`GrpcServerBuilder`] together with the standard health and reflection services.
+//! Combat actions are held behind each attacker's global cooldown by an [`ActionQueue`].
+//! Zone event and combat log streams bridge the shared event bus through an
+//! [`EventStreamHub`].
+//!
+//! [`Aggregator`]: actor_core::interfaces::Aggregator
+
+pub mod action_queue;
+pub mod actor;
+pub mod combat;
+pub mod convert;
+pub mod server;
+pub mod streaming;
+pub mod world;
+
+pub use action_queue::{ActionQueue, ActionQueueConfig};
+pub use actor::ActorStatsService;
+pub use combat::{CombatBackend, CombatService};
+pub use server::GrpcServerBuilder;
+pub use streaming::{EventStreamConfig, EventStreamHub};
+pub use world::{WorldBackend, WorldService};
+
+/// Generated protobuf types, servers, and clients.
+pub mod proto {
+    tonic::include_proto!("chaos.v1");
+
+    /// Encoded file descriptor set for the reflection service.
+    pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("chaos_descriptor");
+}
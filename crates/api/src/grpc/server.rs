@@ -0,0 +1,149 @@
+//! Server builder mounting the gRPC services.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actor_core::interfaces::Aggregator;
+use tonic::transport::server::Router;
+use tonic::transport::Server;
+use tonic_health::server::HealthReporter;
+
+use super::proto::actor_stats_server::ActorStatsServer;
+use super::proto::combat_server::CombatServer;
+use super::proto::world_server::WorldServer;
+use super::proto::FILE_DESCRIPTOR_SET;
+use super::{ActorStatsService, CombatBackend, CombatService, EventStreamHub, WorldBackend, WorldService};
+
+/// Builder for a tonic server exposing the Chaos gRPC services.
+///
+/// Only the services given a backend are mounted. The `grpc.health.v1` service is always
+/// mounted and reports each mounted service as serving; reflection is on by default.
+///
+/// ```ignore
+/// let router = GrpcServerBuilder::new()
+///     .with_actor_stats(aggregator)
+///     .build()
+///     .await?;
+/// router.serve(addr).await?;
+/// ```
+pub struct GrpcServerBuilder {
+    actor_stats: Option<ActorStatsService>,
+    combat: Option<CombatService>,
+    world: Option<WorldService>,
+    event_streams: Option<Arc<EventStreamHub>>,
+    enable_reflection: bool,
+    timeout: Option<Duration>,
+}
+
+impl Default for GrpcServerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrpcServerBuilder {
+    /// Create a builder with no services mounted.
+    pub fn new() -> Self {
+        Self {
+            actor_stats: None,
+            combat: None,
+            world: None,
+            event_streams: None,
+            enable_reflection: true,
+            timeout: None,
+        }
+    }
+
+    /// Mount the actor stats service.
+    pub fn with_actor_stats(mut self, aggregator: Arc<dyn Aggregator>) -> Self {
+        self.actor_stats = Some(ActorStatsService::new(aggregator));
+        self
+    }
+
+    /// Mount the combat service.
+    pub fn with_combat(mut self, backend: Arc<dyn CombatBackend>) -> Self {
+        self.combat = Some(CombatService::new(backend));
+        self
+    }
+
+    /// Mount the world query service.
+    pub fn with_world(mut self, backend: Arc<dyn WorldBackend>) -> Self {
+        self.world = Some(WorldService::new(backend));
+        self
+    }
+
+    /// Serve zone event and combat log streams from a hub.
+    pub fn with_event_streams(mut self, hub: Arc<EventStreamHub>) -> Self {
+        self.event_streams = Some(hub);
+        self
+    }
+
+    /// Enable or disable the reflection service.
+    pub fn with_reflection(mut self, enable: bool) -> Self {
+        self.enable_reflection = enable;
+        self
+    }
+
+    /// Set a per-request timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Build the router, returning the health reporter so callers can flip service
+    /// status (e.g. to not-serving during shutdown).
+    pub async fn build_with_health(mut self) -> Result<(Router, HealthReporter), tonic_reflection::server::Error> {
+        if let Some(hub) = &self.event_streams {
+            self.combat = self.combat.map(|service| service.with_event_streams(hub.clone()));
+            self.world = self.world.map(|service| service.with_event_streams(hub.clone()));
+        }
+
+        let (mut reporter, health_service) = tonic_health::server::health_reporter();
+        if self.actor_stats.is_some() {
+            reporter.set_serving::<ActorStatsServer<ActorStatsService>>().await;
+        }
+        if self.combat.is_some() {
+            reporter.set_serving::<CombatServer<CombatService>>().await;
+        }
+        if self.world.is_some() {
+            reporter.set_serving::<WorldServer<WorldService>>().await;
+        }
+
+        let reflection_service = if self.enable_reflection {
+            Some(
+                tonic_reflection::server::Builder::configure()
+                    .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+                    .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+                    .build()?,
+            )
+        } else {
+            None
+        };
+
+        let mut server = Server::builder();
+        if let Some(timeout) = self.timeout {
+            server = server.timeout(timeout);
+        }
+        let router = server
+            .add_service(health_service)
+            .add_optional_service(reflection_service)
+            .add_optional_service(self.actor_stats.map(ActorStatsService::into_server))
+            .add_optional_service(self.combat.map(CombatService::into_server))
+            .add_optional_service(self.world.map(WorldService::into_server));
+
+        Ok((router, reporter))
+    }
+
+    /// Build the router.
+    pub async fn build(self) -> Result<Router, tonic_reflection::server::Error> {
+        Ok(self.build_with_health().await?.0)
+    }
+
+    /// Build the router and serve it until the process exits.
+    pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
+        tracing::info!(%addr, "Starting gRPC server");
+        self.build().await?.serve(addr).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+//! Bridges the shared event bus to gRPC server streams.
+//!
+//! [`EventStreamHub`] keeps one bus subscription per topic and a bounded replay buffer
+//! of recent events, each numbered with a sequence. Every streamed event carries a
+//! resume token (`<epoch>-<sequence>`); a reconnecting client passes its last token
+//! and receives the buffered events after it. If the token is too old or comes from a
+//! previous server instance, the stream continues live and flags the first event with
+//! `gap`.
+//!
+//! Flow control: each subscriber has a bounded queue drained by tonic as the HTTP/2
+//! window allows. A subscriber that falls further behind than the live buffer is ended
+//! with `RESOURCE_EXHAUSTED` and is expected to reconnect with its last resume token.
+
+use std::collections::{HashMap, VecDeque};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use futures::stream::{self, Stream, StreamExt};
+use shared::error::ErrorCode;
+use shared::events::{topics, EventBus, EventEnvelope};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tonic::Status;
+
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Bus topic carrying combat log entries.
+pub const COMBAT_LOG_TOPIC: &str = topics::COMBAT_LOG;
+
+/// Get the bus topic carrying a zone's events.
+pub fn zone_topic(zone_id: &str) -> String {
+    topics::zone(zone_id)
+}
+
+/// Boxed server stream returned by streaming RPCs.
+pub type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+/// Streaming configuration.
+#[derive(Debug, Clone)]
+pub struct EventStreamConfig {
+    /// Events kept per topic for resuming clients
+    pub replay_capacity: usize,
+    /// Events a subscriber may fall behind before its stream is ended
+    pub live_capacity: usize,
+    /// Events queued per subscriber waiting for the transport
+    pub subscriber_buffer: usize,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            replay_capacity: 1024,
+            live_capacity: 1024,
+            subscriber_buffer: 64,
+        }
+    }
+}
+
+/// Event delivered to a stream subscriber.
+#[derive(Debug, Clone)]
+pub struct StreamedEvent {
+    /// Bus envelope
+    pub envelope: EventEnvelope,
+    /// Token resuming after this event
+    pub resume_token: String,
+    /// Events were missed before this one
+    pub gap: bool,
+}
+
+/// Position in a topic's event sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResumeToken {
+    /// Hub instance the sequence belongs to
+    pub epoch: u64,
+    /// Sequence of the last received event
+    pub sequence: u64,
+}
+
+impl ResumeToken {
+    /// Encode the token.
+    pub fn encode(&self) -> String {
+        format!("{:x}-{:x}", self.epoch, self.sequence)
+    }
+
+    /// Decode a token produced by [`encode`](Self::encode).
+    pub fn decode(token: &str) -> ApiResult<Self> {
+        let invalid = || ApiErrorResponse::new(ErrorCode::ValidationFailed, "Invalid resume token");
+        let (epoch, sequence) = token.split_once('-').ok_or_else(invalid)?;
+        Ok(Self {
+            epoch: u64::from_str_radix(epoch, 16).map_err(|_| invalid())?,
+            sequence: u64::from_str_radix(sequence, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+struct TopicState {
+    next_sequence: u64,
+    recent: VecDeque<(u64, EventEnvelope)>,
+}
+
+struct TopicStream {
+    epoch: u64,
+    state: Mutex<TopicState>,
+    live: broadcast::Sender<(u64, EventEnvelope)>,
+}
+
+type TopicMap = Arc<tokio::sync::Mutex<HashMap<String, Arc<TopicStream>>>>;
+
+/// Shares bus subscriptions and replay buffers between gRPC stream subscribers.
+pub struct EventStreamHub {
+    bus: Arc<dyn EventBus>,
+    config: EventStreamConfig,
+    topics: TopicMap,
+}
+
+impl EventStreamHub {
+    /// Create a hub with the default configuration.
+    pub fn new(bus: Arc<dyn EventBus>) -> Self {
+        Self::with_config(bus, EventStreamConfig::default())
+    }
+
+    /// Create a hub with a custom configuration.
+    pub fn with_config(bus: Arc<dyn EventBus>, config: EventStreamConfig) -> Self {
+        Self {
+            bus,
+            config,
+            topics: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Subscribe to a topic, resuming after `resume_token` when given.
+    ///
+    /// Only events accepted by `filter` are delivered.
+    pub async fn subscribe<F>(
+        &self,
+        topic: &str,
+        resume_token: Option<&str>,
+        filter: F,
+    ) -> ApiResult<EventStream<StreamedEvent>>
+    where
+        F: Fn(&EventEnvelope) -> bool + Send + 'static,
+    {
+        let resume = resume_token.map(ResumeToken::decode).transpose()?;
+        let topic_stream = self.topic(topic).await?;
+
+        let (mut live, replay, mut gap) = {
+            let state = topic_stream.state.lock().unwrap();
+            let live = topic_stream.live.subscribe();
+            match resume {
+                None => (live, Vec::new(), false),
+                Some(token) => {
+                    let oldest = state.recent.front().map_or(state.next_sequence, |(sequence, _)| *sequence);
+                    let known = token.epoch == topic_stream.epoch && token.sequence < state.next_sequence;
+                    if !known {
+                        (live, Vec::new(), true)
+                    } else {
+                        let replay: Vec<_> = state
+                            .recent
+                            .iter()
+                            .filter(|(sequence, _)| *sequence > token.sequence)
+                            .cloned()
+                            .collect();
+                        (live, replay, token.sequence + 1 < oldest)
+                    }
+                }
+            }
+        };
+
+        let epoch = topic_stream.epoch;
+        let (sender, receiver) = mpsc::channel(self.config.subscriber_buffer.max(1));
+        tokio::spawn(async move {
+            let mut deliver = move |sequence: u64, envelope: EventEnvelope| {
+                if !filter(&envelope) {
+                    return None;
+                }
+                let event = StreamedEvent {
+                    envelope,
+                    resume_token: ResumeToken { epoch, sequence }.encode(),
+                    gap,
+                };
+                gap = false;
+                Some(event)
+            };
+
+            for (sequence, envelope) in replay {
+                if let Some(event) = deliver(sequence, envelope) {
+                    if sender.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+            loop {
+                match live.recv().await {
+                    Ok((sequence, envelope)) => {
+                        if let Some(event) = deliver(sequence, envelope) {
+                            if sender.send(Ok(event)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped, "Event stream subscriber fell behind, ending stream");
+                        let status = ApiErrorResponse::new(
+                            ErrorCode::RateLimited,
+                            "Subscriber fell behind; reconnect with the last resume token",
+                        );
+                        let _ = sender.send(Err(status.into())).await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+
+        Ok(stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|item| (item, receiver))
+        })
+        .boxed())
+    }
+
+    async fn topic(&self, topic: &str) -> ApiResult<Arc<TopicStream>> {
+        let mut topics = self.topics.lock().await;
+        if let Some(existing) = topics.get(topic) {
+            return Ok(existing.clone());
+        }
+
+        let mut subscription = self.bus.subscribe_topic(topic).await?;
+        let topic_stream = Arc::new(TopicStream {
+            epoch: rand_epoch(),
+            state: Mutex::new(TopicState {
+                next_sequence: 0,
+                recent: VecDeque::new(),
+            }),
+            live: broadcast::channel(self.config.live_capacity.max(1)).0,
+        });
+        topics.insert(topic.to_string(), topic_stream.clone());
+
+        let pump = topic_stream.clone();
+        let replay_capacity = self.config.replay_capacity;
+        let topic_map = self.topics.clone();
+        let topic_name = topic.to_string();
+        tokio::spawn(async move {
+            while let Some(envelope) = subscription.next().await {
+                let mut state = pump.state.lock().unwrap();
+                let sequence = state.next_sequence;
+                state.next_sequence += 1;
+                state.recent.push_back((sequence, envelope.clone()));
+                while state.recent.len() > replay_capacity {
+                    state.recent.pop_front();
+                }
+                // Sent under the lock so new subscribers see each event exactly once
+                let _ = pump.live.send((sequence, envelope));
+            }
+            tracing::debug!(topic = %topic_name, "Event bus subscription ended");
+            topic_map.lock().await.remove(&topic_name);
+        });
+
+        Ok(topic_stream)
+    }
+}
+
+impl std::fmt::Debug for EventStreamHub {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStreamHub")
+            .field("backend", &self.bus.backend_name())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Get an event's type from its payload (`event_type` or `type` field).
+pub fn event_type(envelope: &EventEnvelope) -> &str {
+    envelope
+        .payload
+        .get("event_type")
+        .or_else(|| envelope.payload.get("type"))
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+}
+
+/// Check whether a combat event involves one of the actors.
+pub fn involves_actor(envelope: &EventEnvelope, actor_ids: &[String]) -> bool {
+    let payload = &envelope.payload;
+    let single = ["actor_id", "attacker_id", "source_id", "target_id"]
+        .iter()
+        .filter_map(|field| payload.get(*field).and_then(|value| value.as_str()));
+    let targets = payload
+        .get("target_ids")
+        .and_then(|value| value.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value.as_str());
+    single.chain(targets).any(|id| actor_ids.iter().any(|wanted| wanted == id))
+}
+
+fn rand_epoch() -> u64 {
+    uuid::Uuid::new_v4().as_u64_pair().0
+}
@@ -0,0 +1,128 @@
+//! World query gRPC service.
+//!
+//! World state lives outside this crate; the service validates queries and delegates
+//! to a [`WorldBackend`]. Zone event streams are served from an [`EventStreamHub`].
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use shared::error::ErrorCode;
+use tonic::{Request, Response, Status};
+
+use super::convert::payload_struct;
+use super::proto::world_server::{World, WorldServer};
+use super::proto::{ActorPosition, NearbyQuery, NearbyResponse, WorldActorId, ZoneEvent, ZoneEventsRequest};
+use super::streaming::{event_type, zone_topic, EventStream, EventStreamHub, StreamedEvent};
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Default number of results for nearby queries.
+pub const DEFAULT_NEARBY_LIMIT: u32 = 50;
+
+/// Largest number of results for nearby queries.
+pub const MAX_NEARBY_LIMIT: u32 = 500;
+
+/// Answers world queries for [`WorldService`].
+#[async_trait]
+pub trait WorldBackend: Send + Sync {
+    /// Get the position of an actor, or `None` if it is not in the world.
+    async fn actor_position(&self, actor_id: &str) -> ApiResult<Option<ActorPosition>>;
+
+    /// List actors near a point; `query.limit` is already clamped.
+    async fn query_nearby(&self, query: NearbyQuery) -> ApiResult<Vec<ActorPosition>>;
+}
+
+/// gRPC service answering world queries.
+#[derive(Clone)]
+pub struct WorldService {
+    backend: Arc<dyn WorldBackend>,
+    events: Option<Arc<EventStreamHub>>,
+}
+
+impl WorldService {
+    /// Create a service backed by a world backend.
+    pub fn new(backend: Arc<dyn WorldBackend>) -> Self {
+        Self { backend, events: None }
+    }
+
+    /// Serve zone event streams from a hub.
+    pub fn with_event_streams(mut self, events: Arc<EventStreamHub>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Wrap the service in its generated tonic server.
+    pub fn into_server(self) -> WorldServer<Self> {
+        WorldServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl World for WorldService {
+    type SubscribeZoneEventsStream = EventStream<ZoneEvent>;
+
+    async fn get_actor_position(&self, request: Request<WorldActorId>) -> Result<Response<ActorPosition>, Status> {
+        let actor_id = request.into_inner().id;
+        let position = self.backend.actor_position(&actor_id).await?.ok_or_else(|| {
+            ApiErrorResponse::new(ErrorCode::NotFound, format!("Actor '{}' is not in the world", actor_id))
+        })?;
+        Ok(Response::new(position))
+    }
+
+    async fn query_nearby(&self, request: Request<NearbyQuery>) -> Result<Response<NearbyResponse>, Status> {
+        let mut query = request.into_inner();
+        if query.zone_id.is_empty() || query.center.is_none() {
+            return Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "zone_id and center are required").into());
+        }
+        if !query.radius.is_finite() || query.radius <= 0.0 {
+            return Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "radius must be positive").into());
+        }
+        query.limit = match query.limit {
+            0 => DEFAULT_NEARBY_LIMIT,
+            limit => limit.min(MAX_NEARBY_LIMIT),
+        };
+        let actors = self.backend.query_nearby(query).await?;
+        Ok(Response::new(NearbyResponse { actors }))
+    }
+
+    async fn subscribe_zone_events(
+        &self,
+        request: Request<ZoneEventsRequest>,
+    ) -> Result<Response<Self::SubscribeZoneEventsStream>, Status> {
+        let events = self
+            .events
+            .as_ref()
+            .ok_or_else(|| ApiErrorResponse::new(ErrorCode::Unimplemented, "Zone event streams are not enabled"))?;
+        let request = request.into_inner();
+        if request.zone_id.is_empty() {
+            return Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, "zone_id is required").into());
+        }
+
+        let event_types = request.event_types;
+        let resume_token = Some(request.resume_token.as_str()).filter(|token| !token.is_empty());
+        let stream = events
+            .subscribe(&zone_topic(&request.zone_id), resume_token, move |envelope| {
+                event_types.is_empty() || event_types.iter().any(|wanted| wanted == event_type(envelope))
+            })
+            .await?;
+
+        let zone_id = request.zone_id;
+        Ok(Response::new(
+            stream
+                .map(move |event| event.map(|event| to_zone_event(&zone_id, event)))
+                .boxed(),
+        ))
+    }
+}
+
+fn to_zone_event(zone_id: &str, event: StreamedEvent) -> ZoneEvent {
+    ZoneEvent {
+        event_id: event.envelope.id.to_string(),
+        zone_id: zone_id.to_string(),
+        event_type: event_type(&event.envelope).to_string(),
+        payload: payload_struct(&event.envelope.payload),
+        timestamp: event.envelope.timestamp.to_rfc3339(),
+        resume_token: event.resume_token,
+        gap: event.gap,
+    }
+}
@@ -4,13 +4,14 @@
 //! including REST endpoints, gRPC services, and WebSocket connections.
 
 pub mod rest;
+pub mod codec;
 pub mod grpc;
 pub mod websocket;
-pub mod auth;
+pub mod chat;
 pub mod middleware;
 pub mod error;
-pub mod types;
+#[cfg(feature = "openapi")]
+pub mod openapi;
 
 // Re-export commonly used types
 pub use error::*;
-pub use types::*;
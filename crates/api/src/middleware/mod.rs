@@ -0,0 +1,9 @@
+//! Axum middleware shared by the REST routers.
+
+pub mod validation;
+pub mod versioning;
+
+pub use validation::{RequestRejection, ValidatedJson, ValidatedQuery, ValidationRejection};
+pub use versioning::{
+    versioned_router, ApiVersion, Deprecation, VersionPolicy, Versioned, VersionedJson, VersionedRequest, VersionedResponse,
+};
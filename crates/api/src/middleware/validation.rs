@@ -0,0 +1,192 @@
+//! Request validation.
+//!
+//! Request DTOs derive [`validator::Validate`]; the [`ValidatedJson`] and
+//! [`ValidatedQuery`] extractors decode and validate them before the handler runs.
+//! Bodies that fail to parse are rejected with `400 INVALID_FORMAT`; bodies that parse
+//! but break a rule are rejected with `422 VALIDATION_FAILED` and one entry per
+//! offending field.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use axum::async_trait;
+use axum::extract::{FromRequest, FromRequestParts, Query, Request};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use shared::error::{ApiError, ErrorCode};
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::error::ApiErrorResponse;
+
+/// One failed validation rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// Dotted path of the field (`items[2].quantity`)
+    pub field: String,
+    /// Rule that failed (`length`, `range`, `one_of`, ...)
+    pub code: String,
+    /// Human-readable message
+    pub message: Option<String>,
+    /// Rule parameters (bounds, allowed values, ...)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub params: HashMap<String, serde_json::Value>,
+}
+
+/// `422 Unprocessable Entity` response listing every failed rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationRejection {
+    /// Client-facing error (always `VALIDATION_FAILED`)
+    #[serde(flatten)]
+    pub error: ApiError,
+    /// Failed rules, sorted by field
+    pub fields: Vec<FieldError>,
+}
+
+impl From<ValidationErrors> for ValidationRejection {
+    fn from(errors: ValidationErrors) -> Self {
+        let mut fields = Vec::new();
+        collect_errors(None, &errors, &mut fields);
+        fields.sort_by(|a, b| a.field.cmp(&b.field));
+        Self {
+            error: ApiError::new(ErrorCode::ValidationFailed, "Request validation failed"),
+            fields,
+        }
+    }
+}
+
+impl IntoResponse for ValidationRejection {
+    fn into_response(self) -> Response {
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(self)).into_response()
+    }
+}
+
+/// Rejection of the validating extractors.
+#[derive(Debug)]
+pub enum RequestRejection {
+    /// Body or query could not be decoded
+    Format(ApiErrorResponse),
+    /// Decoded value broke a validation rule
+    Invalid(ValidationRejection),
+}
+
+impl From<ApiErrorResponse> for RequestRejection {
+    fn from(error: ApiErrorResponse) -> Self {
+        Self::Format(error)
+    }
+}
+
+impl From<ValidationErrors> for RequestRejection {
+    fn from(errors: ValidationErrors) -> Self {
+        Self::Invalid(errors.into())
+    }
+}
+
+impl IntoResponse for RequestRejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Format(error) => error.into_response(),
+            Self::Invalid(rejection) => rejection.into_response(),
+        }
+    }
+}
+
+/// JSON body extractor that validates the decoded value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = RequestRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(request, state)
+            .await
+            .map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Query-string extractor that validates the decoded value.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for ValidatedQuery<T>
+where
+    S: Send + Sync,
+    T: DeserializeOwned + Validate,
+{
+    type Rejection = RequestRejection;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.body_text()))?;
+        value.validate()?;
+        Ok(Self(value))
+    }
+}
+
+/// Check that a value is one of the allowed values.
+///
+/// Use from a `#[validate(custom(function = ...))]` function, since validator has no
+/// built-in enum membership rule.
+pub fn one_of(value: &str, allowed: &[&str]) -> Result<(), ValidationError> {
+    if allowed.contains(&value) {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("one_of");
+    error.message = Some(Cow::Owned(format!("Must be one of: {}", allowed.join(", "))));
+    error.add_param(Cow::Borrowed("allowed"), &allowed);
+    Err(error)
+}
+
+/// Check that a map has at most `max` entries.
+pub fn max_entries<K, V>(map: &HashMap<K, V>, max: usize) -> Result<(), ValidationError> {
+    if map.len() <= max {
+        return Ok(());
+    }
+    let mut error = ValidationError::new("max_entries");
+    error.message = Some(Cow::Owned(format!("At most {} entries allowed", max)));
+    error.add_param(Cow::Borrowed("max"), &max);
+    Err(error)
+}
+
+fn collect_errors(prefix: Option<&str>, errors: &ValidationErrors, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{}.{}", prefix, field),
+            None => field.to_string(),
+        };
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                out.extend(field_errors.iter().map(|error| FieldError {
+                    field: path.clone(),
+                    code: error.code.to_string(),
+                    message: error.message.as_ref().map(|message| message.to_string()),
+                    params: error
+                        .params
+                        .iter()
+                        .filter(|(name, _)| name.as_ref() != "value")
+                        .map(|(name, value)| (name.to_string(), value.clone()))
+                        .collect(),
+                }));
+            }
+            ValidationErrorsKind::Struct(nested) => collect_errors(Some(&path), nested, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_errors(Some(&format!("{}[{}]", path, index)), nested, out);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! API versioning.
+//!
+//! [`versioned_router`] mounts a router under `/v1`, `/v2`, ... and at its bare paths.
+//! The version comes from the path prefix, else the `X-Api-Version` header, else
+//! [`ApiVersion::LATEST`]. Handlers read it with the [`ApiVersion`] extractor and use
+//! [`Versioned`] / [`VersionedJson`] to adapt request and response bodies; responses
+//! for deprecated versions carry `Deprecation`, `Sunset`, and `Link` headers.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::async_trait;
+use axum::body::Bytes;
+use axum::extract::{FromRequest, FromRequestParts, Request, State};
+use axum::http::header::HeaderValue;
+use axum::http::request::Parts;
+use axum::http::HeaderMap;
+use axum::middleware::{from_fn_with_state, Next};
+use axum::response::{IntoResponse, Response};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+use validator::Validate;
+
+use super::validation::RequestRejection;
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Request header selecting the API version.
+pub const API_VERSION_HEADER: &str = "x-api-version";
+
+/// Published API versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApiVersion {
+    /// Flat snapshot bodies, `refresh` flag on resolve
+    V1,
+    /// Current version
+    V2,
+}
+
+impl ApiVersion {
+    /// Newest version.
+    pub const LATEST: ApiVersion = ApiVersion::V2;
+
+    /// All versions, oldest first.
+    pub const ALL: [ApiVersion; 2] = [ApiVersion::V1, ApiVersion::V2];
+
+    /// Get the path segment (`v1`, `v2`, ...).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::V1 => "v1",
+            Self::V2 => "v2",
+        }
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiVersion {
+    type Err = ApiErrorResponse;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let number = value.strip_prefix(['v', 'V']).unwrap_or(value);
+        match number {
+            "1" => Ok(Self::V1),
+            "2" => Ok(Self::V2),
+            _ => Err(ApiErrorResponse::new(
+                ErrorCode::ValidationFailed,
+                format!("Unknown API version: '{}'", value),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ApiVersion {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ApiVersion>().copied().unwrap_or(ApiVersion::LATEST))
+    }
+}
+
+/// Deprecation notice for a version.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Deprecation {
+    /// When the version stops being served
+    pub sunset: Option<DateTime<Utc>>,
+    /// Migration guide or successor documentation
+    pub link: Option<String>,
+}
+
+/// Which versions are served and which are deprecated.
+#[derive(Debug, Clone)]
+pub struct VersionPolicy {
+    /// Versions accepted by the router
+    pub supported: Vec<ApiVersion>,
+    /// Deprecated versions
+    pub deprecated: HashMap<ApiVersion, Deprecation>,
+}
+
+impl Default for VersionPolicy {
+    fn default() -> Self {
+        Self {
+            supported: ApiVersion::ALL.to_vec(),
+            deprecated: HashMap::from([(ApiVersion::V1, Deprecation::default())]),
+        }
+    }
+}
+
+impl VersionPolicy {
+    /// Mark a version as deprecated.
+    pub fn deprecate(mut self, version: ApiVersion, deprecation: Deprecation) -> Self {
+        self.deprecated.insert(version, deprecation);
+        self
+    }
+
+    /// Stop serving a version.
+    pub fn retire(mut self, version: ApiVersion) -> Self {
+        self.supported.retain(|supported| *supported != version);
+        self.deprecated.remove(&version);
+        self
+    }
+
+    /// Check whether a version is served.
+    pub fn supports(&self, version: ApiVersion) -> bool {
+        self.supported.contains(&version)
+    }
+}
+
+#[derive(Clone)]
+struct VersionState {
+    policy: Arc<VersionPolicy>,
+    path_version: Option<ApiVersion>,
+}
+
+/// Mount `router` under every supported version prefix and at its bare paths.
+pub fn versioned_router(router: Router, policy: VersionPolicy) -> Router {
+    let policy = Arc::new(policy);
+    let mut versioned = Router::new();
+    for &version in &policy.supported {
+        let state = VersionState {
+            policy: policy.clone(),
+            path_version: Some(version),
+        };
+        versioned = versioned.nest(
+            &format!("/{}", version),
+            router.clone().layer(from_fn_with_state(state, version_middleware)),
+        );
+    }
+    let state = VersionState {
+        policy,
+        path_version: None,
+    };
+    versioned.merge(router.layer(from_fn_with_state(state, version_middleware)))
+}
+
+async fn version_middleware(State(state): State<VersionState>, mut request: Request, next: Next) -> Response {
+    let version = match resolve_version(&state, request.headers()) {
+        Ok(version) => version,
+        Err(error) => return error.into_response(),
+    };
+    request.extensions_mut().insert(version);
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(API_VERSION_HEADER, HeaderValue::from_static(version.as_str()));
+    if let Some(deprecation) = state.policy.deprecated.get(&version) {
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        if let Some(sunset) = deprecation.sunset {
+            let date = sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+            if let Ok(value) = HeaderValue::from_str(&date) {
+                headers.insert("sunset", value);
+            }
+        }
+        if let Some(link) = &deprecation.link {
+            if let Ok(value) = HeaderValue::from_str(&format!("<{}>; rel=\"deprecation\"", link)) {
+                headers.insert("link", value);
+            }
+        }
+    }
+    response
+}
+
+fn resolve_version(state: &VersionState, headers: &HeaderMap) -> ApiResult<ApiVersion> {
+    let version = match state.path_version {
+        Some(version) => version,
+        None => match headers.get(API_VERSION_HEADER) {
+            Some(value) => value
+                .to_str()
+                .map_err(|_| ApiErrorResponse::new(ErrorCode::ValidationFailed, "Invalid API version header"))?
+                .parse()?,
+            None => ApiVersion::LATEST,
+        },
+    };
+    if !state.policy.supports(version) {
+        return Err(ApiErrorResponse::new(
+            ErrorCode::ValidationFailed,
+            format!("API version {} is no longer supported", version),
+        ));
+    }
+    Ok(version)
+}
+
+/// Request body that older API versions send in a different shape.
+pub trait VersionedRequest: Sized {
+    /// Decode a body sent by a client speaking `version`.
+    fn from_versioned(version: ApiVersion, body: serde_json::Value) -> ApiResult<Self>;
+}
+
+/// Response body that older API versions expect in a different shape.
+pub trait VersionedResponse {
+    /// Encode the body for a client speaking `version`.
+    fn to_versioned(self, version: ApiVersion) -> serde_json::Value;
+}
+
+/// Decode a JSON body with [`VersionedRequest`]; `T` is the current type.
+pub fn decode_as<T: DeserializeOwned>(body: serde_json::Value) -> ApiResult<T> {
+    serde_json::from_value(body).map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.to_string()))
+}
+
+/// Extractor decoding a JSON body for the request's API version, then validating the
+/// current-version value.
+///
+/// An empty body decodes as `{}`.
+#[derive(Debug, Clone)]
+pub struct Versioned<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for Versioned<T>
+where
+    S: Send + Sync,
+    T: VersionedRequest + Validate,
+{
+    type Rejection = RequestRejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let version = request.extensions().get::<ApiVersion>().copied().unwrap_or(ApiVersion::LATEST);
+        let bytes = Bytes::from_request(request, state)
+            .await
+            .map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.to_string()))?;
+        let body = if bytes.is_empty() {
+            serde_json::Value::Object(serde_json::Map::new())
+        } else {
+            serde_json::from_slice(&bytes).map_err(|e| ApiErrorResponse::new(ErrorCode::InvalidFormat, e.to_string()))?
+        };
+        let value = T::from_versioned(version, body)?;
+        value.validate()?;
+        Ok(Versioned(value))
+    }
+}
+
+/// JSON response encoded for the request's API version.
+#[derive(Debug, Clone)]
+pub struct VersionedJson<T>(pub ApiVersion, pub T);
+
+impl<T: VersionedResponse> IntoResponse for VersionedJson<T> {
+    fn into_response(self) -> Response {
+        Json(self.1.to_versioned(self.0)).into_response()
+    }
+}
@@ -0,0 +1,56 @@
+//! OpenAPI document for the REST endpoints (behind the `openapi` feature).
+//!
+//! Serve [`swagger_router`] to expose `/api-docs/openapi.json` and Swagger UI, or run
+//! `cargo run -p api --example openapi --features openapi` to write the document for
+//! SDK generation.
+
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::rest::actors;
+
+/// Path the OpenAPI document is served at.
+pub const OPENAPI_JSON_PATH: &str = "/api-docs/openapi.json";
+
+/// Path Swagger UI is served at.
+pub const SWAGGER_UI_PATH: &str = "/swagger-ui";
+
+/// Error body returned by every endpoint (mirrors [`shared::error::ApiError`]).
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ErrorBody {
+    /// Stable error code, e.g. `NOT_FOUND`
+    pub code: String,
+    /// Stable numeric error code
+    pub numeric_code: u32,
+    /// Client-safe message
+    pub message: String,
+}
+
+/// OpenAPI description of the REST API.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Chaos World API", description = "REST endpoints of the Chaos World backend"),
+    paths(actors::get_snapshot, actors::resolve, actors::get_contributions),
+    components(schemas(
+        ErrorBody,
+        actors::SnapshotResponse,
+        actors::ProcessingInfo,
+        actors::ResolveRequest,
+        actors::SubsystemContributions,
+        actors::ContributionsResponse,
+    )),
+    tags((name = "actors", description = "Actor stat resolution"))
+)]
+pub struct ApiDoc;
+
+/// Build the OpenAPI document.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+/// Router serving the OpenAPI document and Swagger UI.
+pub fn swagger_router() -> Router {
+    SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_JSON_PATH, openapi()).into()
+}
@@ -0,0 +1,304 @@
+//! Actor stat endpoints backed by the actor-core aggregator.
+//!
+//! - `GET /actors/:id/snapshot`: cached snapshot, resolving on a cache miss
+//! - `POST /actors/:id/resolve`: resolve now, optionally with extra context
+//! - `GET /actors/:id/contributions?dimension=`: raw subsystem contributions
+//!
+//! Snapshot and resolve bodies are versioned; older shapes live in [`super::v1`]. Stats in
+//! snapshots are rounded by the combiner's display policies, see
+//! [`actor_core::display_policy`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actor_core::aggregation_context::AggregationContext;
+use actor_core::display_policy::display_snapshot;
+use actor_core::interfaces::{Aggregator, CombinerRegistry, PluginRegistry};
+use actor_core::types::{Actor, CapContribution, Caps, Contribution, Snapshot};
+use async_trait::async_trait;
+use axum::extract::{Path, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+use validator::{Validate, ValidationError};
+
+use crate::error::{ApiErrorResponse, ApiResult};
+use crate::middleware::validation::{max_entries, ValidatedQuery};
+use crate::middleware::versioning::{decode_as, ApiVersion, Versioned, VersionedJson, VersionedRequest, VersionedResponse};
+
+/// Loads actors for the stat endpoints.
+#[async_trait]
+pub trait ActorSource: Send + Sync {
+    /// Load an actor by ID, or `None` if it does not exist.
+    async fn get_actor(&self, actor_id: &str) -> ApiResult<Option<Actor>>;
+}
+
+/// Shared state for the actor routes.
+#[derive(Clone)]
+pub struct ActorRestState {
+    /// Stat aggregator
+    pub aggregator: Arc<dyn Aggregator>,
+    /// Registered subsystems, queried for raw contributions
+    pub plugins: Arc<dyn PluginRegistry>,
+    /// Actor lookup
+    pub actors: Arc<dyn ActorSource>,
+    /// Merge rules, whose display policies round the snapshots sent to clients
+    pub combiner: Arc<dyn CombinerRegistry>,
+}
+
+impl ActorRestState {
+    /// Build the response for a snapshot, rounded for display.
+    pub fn snapshot_response(&self, mut snapshot: Snapshot) -> SnapshotResponse {
+        let formatted = display_snapshot(&mut snapshot, self.combiner.as_ref());
+        SnapshotResponse { formatted, ..snapshot.into() }
+    }
+}
+
+/// Build the actor routes.
+pub fn router(state: ActorRestState) -> Router {
+    Router::new()
+        .route("/actors/:id/snapshot", get(get_snapshot))
+        .route("/actors/:id/resolve", post(resolve))
+        .route("/actors/:id/contributions", get(get_contributions))
+        .with_state(state)
+}
+
+/// Serialized stat snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SnapshotResponse {
+    /// Actor ID
+    pub actor_id: String,
+    /// Primary stat values
+    pub primary: HashMap<String, f64>,
+    /// Derived stat values
+    pub derived: HashMap<String, f64>,
+    /// Effective caps per stat
+    #[cfg_attr(feature = "openapi", schema(value_type = HashMap<String, Object>))]
+    pub caps_used: HashMap<String, Caps>,
+    /// Snapshot version
+    pub version: i64,
+    /// How the snapshot was produced
+    pub processing: ProcessingInfo,
+    /// Free-form metadata
+    #[cfg_attr(feature = "openapi", schema(value_type = HashMap<String, Object>))]
+    pub metadata: HashMap<String, serde_json::Value>,
+    /// Display strings of the stats with a display policy, e.g. `"15.35%"`
+    #[serde(default)]
+    pub formatted: HashMap<String, String>,
+    /// When the snapshot was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// Processing metadata of a snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ProcessingInfo {
+    /// Resolution time in microseconds
+    pub processing_time_us: Option<u64>,
+    /// Subsystems that contributed
+    pub subsystems_processed: Vec<String>,
+    /// Whether the snapshot came from cache
+    pub cache_hit: bool,
+}
+
+impl From<Snapshot> for SnapshotResponse {
+    fn from(snapshot: Snapshot) -> Self {
+        Self {
+            actor_id: snapshot.actor_id,
+            primary: snapshot.primary,
+            derived: snapshot.derived,
+            caps_used: snapshot.caps_used,
+            version: snapshot.version,
+            processing: ProcessingInfo {
+                processing_time_us: snapshot.processing_time,
+                subsystems_processed: snapshot.subsystems_processed,
+                cache_hit: snapshot.cache_hit,
+            },
+            metadata: snapshot.metadata,
+            formatted: HashMap::new(),
+            created_at: snapshot.created_at,
+        }
+    }
+}
+
+impl VersionedResponse for SnapshotResponse {
+    fn to_versioned(self, version: ApiVersion) -> serde_json::Value {
+        let body = match version {
+            ApiVersion::V1 => serde_json::to_value(super::v1::SnapshotV1::from(self)),
+            ApiVersion::V2 => serde_json::to_value(self),
+        };
+        body.unwrap_or(serde_json::Value::Null)
+    }
+}
+
+/// Most context entries accepted by `POST /actors/:id/resolve`.
+pub const MAX_CONTEXT_ENTRIES: usize = 64;
+
+/// Body of `POST /actors/:id/resolve`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ResolveRequest {
+    /// Resolution context, see [`AggregationContext`]
+    #[serde(default)]
+    #[validate(custom(function = "validate_context"))]
+    #[cfg_attr(feature = "openapi", schema(value_type = Option<HashMap<String, Object>>))]
+    pub context: Option<HashMap<String, serde_json::Value>>,
+    /// Drop the cached snapshot before resolving
+    #[serde(default)]
+    pub force: bool,
+}
+
+impl VersionedRequest for ResolveRequest {
+    fn from_versioned(version: ApiVersion, body: serde_json::Value) -> ApiResult<Self> {
+        match version {
+            ApiVersion::V1 => decode_as::<super::v1::ResolveRequestV1>(body).map(Self::from),
+            ApiVersion::V2 => decode_as(body),
+        }
+    }
+}
+
+fn validate_context(context: &HashMap<String, serde_json::Value>) -> Result<(), ValidationError> {
+    max_entries(context, MAX_CONTEXT_ENTRIES)
+}
+
+/// Query of `GET /actors/:id/contributions`.
+#[derive(Debug, Clone, Default, Deserialize, Validate)]
+#[cfg_attr(feature = "openapi", derive(utoipa::IntoParams))]
+#[cfg_attr(feature = "openapi", into_params(parameter_in = Query))]
+pub struct ContributionsQuery {
+    /// Only return contributions to this dimension
+    #[validate(length(min = 1, max = 64))]
+    pub dimension: Option<String>,
+}
+
+/// Contributions of one subsystem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct SubsystemContributions {
+    /// Subsystem ID
+    pub system_id: String,
+    /// Subsystem priority
+    pub priority: i64,
+    /// Primary stat contributions
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Object>))]
+    pub primary: Vec<Contribution>,
+    /// Derived stat contributions
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Object>))]
+    pub derived: Vec<Contribution>,
+    /// Cap contributions
+    #[cfg_attr(feature = "openapi", schema(value_type = Vec<Object>))]
+    pub caps: Vec<CapContribution>,
+}
+
+/// Response of `GET /actors/:id/contributions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ContributionsResponse {
+    /// Actor ID
+    pub actor_id: String,
+    /// Dimension filter that was applied
+    pub dimension: Option<String>,
+    /// Contributions by subsystem, highest priority first
+    pub subsystems: Vec<SubsystemContributions>,
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/actors/{id}/snapshot",
+    tag = "actors",
+    params(("id" = String, Path, description = "Actor ID")),
+    responses(
+        (status = 200, description = "Stat snapshot", body = SnapshotResponse),
+        (status = 404, description = "Actor not found", body = crate::openapi::ErrorBody),
+    )
+))]
+pub(crate) async fn get_snapshot(
+    version: ApiVersion,
+    State(state): State<ActorRestState>,
+    Path(actor_id): Path<String>,
+) -> ApiResult<VersionedJson<SnapshotResponse>> {
+    if let Some(snapshot) = state.aggregator.get_cached_snapshot(&actor_id) {
+        return Ok(VersionedJson(version, state.snapshot_response(snapshot)));
+    }
+    let actor = load_actor(&state, &actor_id).await?;
+    let snapshot = state.aggregator.resolve(&actor).await?;
+    Ok(VersionedJson(version, state.snapshot_response(snapshot)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    post,
+    path = "/actors/{id}/resolve",
+    tag = "actors",
+    params(("id" = String, Path, description = "Actor ID")),
+    request_body(content = Option<ResolveRequest>, description = "Resolution options"),
+    responses(
+        (status = 200, description = "Freshly resolved snapshot", body = SnapshotResponse),
+        (status = 404, description = "Actor not found", body = crate::openapi::ErrorBody),
+    )
+))]
+pub(crate) async fn resolve(
+    version: ApiVersion,
+    State(state): State<ActorRestState>,
+    Path(actor_id): Path<String>,
+    Versioned(request): Versioned<ResolveRequest>,
+) -> ApiResult<VersionedJson<SnapshotResponse>> {
+    let actor = load_actor(&state, &actor_id).await?;
+    if request.force {
+        state.aggregator.invalidate_cache(&actor_id);
+    }
+    let context = request.context.map(AggregationContext::from_map).transpose()?.unwrap_or_default();
+    let snapshot = state.aggregator.resolve_with_context(&actor, &context).await?;
+    Ok(VersionedJson(version, state.snapshot_response(snapshot)))
+}
+
+#[cfg_attr(feature = "openapi", utoipa::path(
+    get,
+    path = "/actors/{id}/contributions",
+    tag = "actors",
+    params(("id" = String, Path, description = "Actor ID"), ContributionsQuery),
+    responses(
+        (status = 200, description = "Subsystem contributions", body = ContributionsResponse),
+        (status = 404, description = "Actor not found", body = crate::openapi::ErrorBody),
+    )
+))]
+pub(crate) async fn get_contributions(
+    State(state): State<ActorRestState>,
+    Path(actor_id): Path<String>,
+    ValidatedQuery(query): ValidatedQuery<ContributionsQuery>,
+) -> ApiResult<Json<ContributionsResponse>> {
+    let actor = load_actor(&state, &actor_id).await?;
+    let matches = |dimension: &str| query.dimension.as_deref().is_none_or(|wanted| wanted == dimension);
+
+    let mut subsystems = Vec::new();
+    for subsystem in state.plugins.get_by_priority() {
+        let output = subsystem.contribute(&actor, &AggregationContext::default()).await?;
+        let contributions = SubsystemContributions {
+            system_id: output.system_id,
+            priority: subsystem.priority(),
+            primary: output.primary.into_iter().filter(|c| matches(&c.dimension)).collect(),
+            derived: output.derived.into_iter().filter(|c| matches(&c.dimension)).collect(),
+            caps: output.caps.into_iter().filter(|c| matches(&c.dimension)).collect(),
+        };
+        let empty = contributions.primary.is_empty() && contributions.derived.is_empty() && contributions.caps.is_empty();
+        if !empty {
+            subsystems.push(contributions);
+        }
+    }
+
+    Ok(Json(ContributionsResponse {
+        actor_id,
+        dimension: query.dimension,
+        subsystems,
+    }))
+}
+
+async fn load_actor(state: &ActorRestState, actor_id: &str) -> ApiResult<Actor> {
+    state
+        .actors
+        .get_actor(actor_id)
+        .await?
+        .ok_or_else(|| ApiErrorResponse::new(ErrorCode::NotFound, format!("Actor '{}' not found", actor_id)))
+}
@@ -0,0 +1,330 @@
+//! Bulk endpoints for admin tooling.
+//!
+//! - `POST /actors:batchResolve`: resolve many actors through [`Aggregator::resolve_batch`]
+//! - `POST /items:batchGrant`: grant items through an [`ItemGranter`]
+//!
+//! Batches succeed partially: every entry gets its own result, in request order, and
+//! the response is `207 Multi-Status` when any entry failed. Malformed or oversized
+//! batches are rejected as a whole.
+//!
+//! [`Aggregator::resolve_batch`]: actor_core::interfaces::Aggregator::resolve_batch
+
+use std::sync::Arc;
+
+use actor_core::types::Actor;
+use async_trait::async_trait;
+use axum::extract::{FromRequest, Path, Request, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use shared::error::{ApiError, ErrorCode};
+use validator::Validate;
+
+use super::actors::{ActorRestState, SnapshotResponse};
+use crate::error::{ApiErrorResponse, ApiResult};
+use crate::middleware::validation::ValidatedJson;
+
+/// Grants items for `POST /items:batchGrant`.
+///
+/// Implemented on top of the item services; this crate only handles the batching.
+#[async_trait]
+pub trait ItemGranter: Send + Sync {
+    /// Grant one validated entry.
+    async fn grant(&self, grant: &ItemGrant) -> ApiResult<GrantedItem>;
+}
+
+/// Batch execution limits.
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    /// Entries processed at the same time
+    pub max_concurrency: usize,
+    /// Actors passed to one `resolve_batch` call
+    pub resolve_chunk_size: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            resolve_chunk_size: 16,
+        }
+    }
+}
+
+/// Shared state for the batch routes.
+///
+/// Actions whose backend is not configured respond with `404 NOT_FOUND`.
+#[derive(Clone, Default)]
+pub struct BatchState {
+    actors: Option<ActorRestState>,
+    items: Option<Arc<dyn ItemGranter>>,
+    config: BatchConfig,
+}
+
+impl BatchState {
+    /// Create a state with no backends.
+    pub fn new(config: BatchConfig) -> Self {
+        Self {
+            config,
+            ..Self::default()
+        }
+    }
+
+    /// Enable `POST /actors:batchResolve`.
+    pub fn with_actors(mut self, actors: ActorRestState) -> Self {
+        self.actors = Some(actors);
+        self
+    }
+
+    /// Enable `POST /items:batchGrant`.
+    pub fn with_items(mut self, items: Arc<dyn ItemGranter>) -> Self {
+        self.items = Some(items);
+        self
+    }
+}
+
+/// Build the batch routes.
+pub fn router(state: BatchState) -> Router {
+    // `resource:method` is a single path segment, so both actions share one route.
+    Router::new().route("/:action", post(dispatch)).with_state(state)
+}
+
+/// Entry of `POST /actors:batchResolve`.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct BatchResolveEntry {
+    /// Actor ID
+    #[validate(length(min = 1, max = 128))]
+    pub actor_id: String,
+    /// Drop the cached snapshot before resolving
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Body of `POST /actors:batchResolve`.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct BatchResolveRequest {
+    /// Actors to resolve, at most 100
+    #[validate(length(min = 1, max = 100), nested)]
+    pub entries: Vec<BatchResolveEntry>,
+}
+
+/// Entry of `POST /items:batchGrant`.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ItemGrant {
+    /// Receiving actor
+    #[validate(length(min = 1, max = 128))]
+    pub actor_id: String,
+    /// Item definition ID
+    #[validate(length(min = 1, max = 128))]
+    pub item_id: String,
+    /// Number of items
+    #[validate(range(min = 1, max = 9999))]
+    pub quantity: u32,
+}
+
+/// Body of `POST /items:batchGrant`.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct BatchGrantRequest {
+    /// Grants to apply, at most 500
+    #[validate(length(min = 1, max = 500), nested)]
+    pub grants: Vec<ItemGrant>,
+}
+
+/// Result of one item grant.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrantedItem {
+    /// Receiving actor
+    pub actor_id: String,
+    /// Item definition ID
+    pub item_id: String,
+    /// Number of items granted
+    pub quantity: u32,
+    /// Created item instances
+    #[serde(default)]
+    pub instance_ids: Vec<String>,
+}
+
+/// Outcome of one batch entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BatchOutcome<T> {
+    /// Entry succeeded
+    Ok {
+        /// Entry result
+        result: T,
+    },
+    /// Entry failed
+    Error {
+        /// Why the entry failed
+        error: ApiError,
+    },
+}
+
+/// Result of one batch entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchEntryResult<T> {
+    /// Position of the entry in the request
+    pub index: usize,
+    /// What happened
+    #[serde(flatten)]
+    pub outcome: BatchOutcome<T>,
+}
+
+/// Response of a batch endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResponse<T> {
+    /// Entries that succeeded
+    pub succeeded: usize,
+    /// Entries that failed
+    pub failed: usize,
+    /// Per-entry results, in request order
+    pub results: Vec<BatchEntryResult<T>>,
+}
+
+impl<T> BatchResponse<T> {
+    fn from_results(results: Vec<ApiResult<T>>) -> Self {
+        let results: Vec<_> = results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| BatchEntryResult {
+                index,
+                outcome: match result {
+                    Ok(result) => BatchOutcome::Ok { result },
+                    Err(error) => BatchOutcome::Error { error: error.error },
+                },
+            })
+            .collect();
+        let failed = results
+            .iter()
+            .filter(|entry| matches!(entry.outcome, BatchOutcome::Error { .. }))
+            .count();
+        Self {
+            succeeded: results.len() - failed,
+            failed,
+            results,
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for BatchResponse<T> {
+    fn into_response(self) -> Response {
+        let status = if self.failed == 0 {
+            StatusCode::OK
+        } else {
+            StatusCode::MULTI_STATUS
+        };
+        (status, Json(self)).into_response()
+    }
+}
+
+async fn dispatch(
+    State(state): State<BatchState>,
+    Path(action): Path<String>,
+    request: Request,
+) -> Result<Response, Response> {
+    // Bodies are decoded here because their type depends on the action.
+    match action.as_str() {
+        "actors:batchResolve" => {
+            let actors = state.actors.as_ref().ok_or_else(|| not_found(&action).into_response())?;
+            let ValidatedJson(body) = ValidatedJson::from_request(request, &())
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(batch_resolve(actors, &state.config, body).await.into_response())
+        }
+        "items:batchGrant" => {
+            let items = state.items.as_ref().ok_or_else(|| not_found(&action).into_response())?;
+            let ValidatedJson(body) = ValidatedJson::from_request(request, &())
+                .await
+                .map_err(IntoResponse::into_response)?;
+            Ok(batch_grant(items.as_ref(), &state.config, body).await.into_response())
+        }
+        _ => Err(not_found(&action).into_response()),
+    }
+}
+
+async fn batch_resolve(
+    state: &ActorRestState,
+    config: &BatchConfig,
+    request: BatchResolveRequest,
+) -> BatchResponse<SnapshotResponse> {
+    let concurrency = config.max_concurrency.max(1);
+    // Entries are moved into their futures, which then borrow nothing from the request
+    let loaded: Vec<ApiResult<Actor>> = stream::iter(request.entries)
+        .map(|entry| async move {
+            let actor = state
+                .actors
+                .get_actor(&entry.actor_id)
+                .await?
+                .ok_or_else(|| ApiErrorResponse::new(ErrorCode::NotFound, format!("Actor '{}' not found", entry.actor_id)))?;
+            if entry.force {
+                state.aggregator.invalidate_cache(&entry.actor_id);
+            }
+            Ok(actor)
+        })
+        .buffered(concurrency)
+        .collect()
+        .await;
+
+    let mut results: Vec<Option<ApiResult<SnapshotResponse>>> = Vec::with_capacity(loaded.len());
+    let mut pending = Vec::new();
+    for (index, actor) in loaded.into_iter().enumerate() {
+        match actor {
+            Ok(actor) => {
+                pending.push((index, actor));
+                results.push(None);
+            }
+            Err(error) => results.push(Some(Err(error))),
+        }
+    }
+
+    let chunks: Vec<_> = pending.chunks(config.resolve_chunk_size.max(1)).map(<[_]>::to_vec).collect();
+    let resolved: Vec<Vec<(usize, ApiResult<SnapshotResponse>)>> = stream::iter(chunks)
+        .map(|chunk| resolve_chunk(state, chunk))
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+    for (index, result) in resolved.into_iter().flatten() {
+        results[index] = Some(result);
+    }
+
+    BatchResponse::from_results(results.into_iter().map(|result| result.expect("every entry resolved")).collect())
+}
+
+/// Resolve a chunk with one `resolve_batch` call, retrying actors one by one if the
+/// call fails so the error is reported against the right entry.
+async fn resolve_chunk(state: &ActorRestState, chunk: Vec<(usize, Actor)>) -> Vec<(usize, ApiResult<SnapshotResponse>)> {
+    let actors: Vec<Actor> = chunk.iter().map(|(_, actor)| actor.clone()).collect();
+    if let Ok(snapshots) = state.aggregator.resolve_batch(&actors).await {
+        if snapshots.len() == chunk.len() {
+            return chunk
+                .iter()
+                .zip(snapshots)
+                .map(|((index, _), snapshot)| (*index, Ok(state.snapshot_response(snapshot))))
+                .collect();
+        }
+    }
+
+    let mut results = Vec::with_capacity(chunk.len());
+    for (index, actor) in chunk {
+        let result = state.aggregator.resolve(&actor).await;
+        let result = result.map(|snapshot| state.snapshot_response(snapshot));
+        results.push((index, result.map_err(ApiErrorResponse::from)));
+    }
+    results
+}
+
+async fn batch_grant(items: &dyn ItemGranter, config: &BatchConfig, request: BatchGrantRequest) -> BatchResponse<GrantedItem> {
+    let results = stream::iter(request.grants)
+        .map(|grant| async move { items.grant(&grant).await })
+        .buffered(config.max_concurrency.max(1))
+        .collect()
+        .await;
+    BatchResponse::from_results(results)
+}
+
+fn not_found(action: &str) -> ApiErrorResponse {
+    ApiErrorResponse::new(ErrorCode::NotFound, format!("Unknown batch action '{}'", action))
+}
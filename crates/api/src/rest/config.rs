@@ -0,0 +1,40 @@
+//! Runtime configuration endpoints for admin tooling.
+//!
+//! - `POST /config/reload`: re-read every configuration provider (MongoDB, files, ...)
+//!   through [`ConfigurationManager::reload`] and drop cached values
+//!
+//! Changes written to the configuration store are picked up by a service only after
+//! a reload; `admin-cli config reload` calls this route on each target service.
+
+use std::sync::Arc;
+
+use actor_core::config::ConfigurationManager;
+use axum::extract::State;
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::ApiResult;
+
+/// Response of `POST /config/reload`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadResponse {
+    /// Providers re-read
+    pub providers: usize,
+    /// When the reload finished
+    pub reloaded_at: DateTime<Utc>,
+}
+
+/// Configuration routes.
+pub fn router(config: Arc<ConfigurationManager>) -> Router {
+    Router::new().route("/config/reload", post(reload)).with_state(config)
+}
+
+async fn reload(State(config): State<Arc<ConfigurationManager>>) -> ApiResult<Json<ReloadResponse>> {
+    let providers = config.reload().await?;
+    Ok(Json(ReloadResponse {
+        providers,
+        reloaded_at: Utc::now(),
+    }))
+}
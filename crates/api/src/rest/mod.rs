@@ -0,0 +1,13 @@
+//! REST endpoints.
+//!
+//! Each resource module exposes a `router` taking its state; services merge the
+//! routers they need under their own prefix, usually wrapped in
+//! [`versioned_router`](crate::middleware::versioned_router).
+
+pub mod actors;
+pub mod batch;
+pub mod config;
+pub mod v1;
+
+pub use actors::{ActorRestState, ActorSource};
+pub use batch::{BatchConfig, BatchState, ItemGranter};
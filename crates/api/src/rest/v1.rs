@@ -0,0 +1,75 @@
+//! Version 1 request and response bodies.
+//!
+//! v1 clients receive flat snapshots (processing fields at the top level) and send
+//! `refresh` instead of `force` when resolving. Each type converts to or from its
+//! current counterpart in [`super::actors`].
+
+use std::collections::HashMap;
+
+use actor_core::types::Caps;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::actors::{ResolveRequest, SnapshotResponse};
+
+/// v1 stat snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotV1 {
+    /// Actor ID
+    pub actor_id: String,
+    /// Primary stat values
+    pub primary: HashMap<String, f64>,
+    /// Derived stat values
+    pub derived: HashMap<String, f64>,
+    /// Effective caps per stat
+    #[serde(default)]
+    pub caps_used: HashMap<String, Caps>,
+    /// Snapshot version
+    pub version: i64,
+    /// Resolution time in microseconds
+    pub processing_time: Option<u64>,
+    /// Subsystems that contributed
+    #[serde(default)]
+    pub subsystems_processed: Vec<String>,
+    /// Whether the snapshot came from cache
+    #[serde(default)]
+    pub cache_hit: bool,
+    /// When the snapshot was created
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<SnapshotResponse> for SnapshotV1 {
+    fn from(snapshot: SnapshotResponse) -> Self {
+        Self {
+            actor_id: snapshot.actor_id,
+            primary: snapshot.primary,
+            derived: snapshot.derived,
+            caps_used: snapshot.caps_used,
+            version: snapshot.version,
+            processing_time: snapshot.processing.processing_time_us,
+            subsystems_processed: snapshot.processing.subsystems_processed,
+            cache_hit: snapshot.processing.cache_hit,
+            created_at: snapshot.created_at,
+        }
+    }
+}
+
+/// v1 body of `POST /actors/:id/resolve`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResolveRequestV1 {
+    /// Extra resolution context
+    #[serde(default)]
+    pub context: Option<HashMap<String, serde_json::Value>>,
+    /// Drop the cached snapshot before resolving
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+impl From<ResolveRequestV1> for ResolveRequest {
+    fn from(request: ResolveRequestV1) -> Self {
+        Self {
+            context: request.context,
+            force: request.refresh,
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! JWT validation for the WebSocket handshake.
+
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Claims carried by WebSocket access tokens.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WsClaims {
+    /// User ID
+    pub sub: String,
+    /// Expiry (seconds since the Unix epoch)
+    pub exp: usize,
+    /// Roles granted to the user
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+impl WsClaims {
+    /// Check whether the user has a role.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|granted| granted == role)
+    }
+}
+
+/// Validates handshake tokens.
+#[derive(Clone)]
+pub struct TokenValidator {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl TokenValidator {
+    /// Create a validator for HS256 tokens signed with `secret`.
+    pub fn hs256(secret: &[u8]) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret),
+            validation: Validation::new(Algorithm::HS256),
+        }
+    }
+
+    /// Validate a token and return its claims.
+    pub fn validate(&self, token: &str) -> ApiResult<WsClaims> {
+        decode::<WsClaims>(token, &self.key, &self.validation)
+            .map(|data| data.claims)
+            .map_err(|error| match error.kind() {
+                ErrorKind::ExpiredSignature => ApiErrorResponse::new(ErrorCode::TokenExpired, "Token has expired"),
+                _ => ApiErrorResponse::new(ErrorCode::Unauthenticated, "Invalid token"),
+            })
+    }
+}
+
+impl std::fmt::Debug for TokenValidator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenValidator").finish_non_exhaustive()
+    }
+}
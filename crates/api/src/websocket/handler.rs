@@ -0,0 +1,181 @@
+//! Axum upgrade handler and per-connection task.
+
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::Response;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use shared::error::ErrorCode;
+use tokio::sync::mpsc;
+
+use super::manager::{SessionHandle, SessionManager};
+use super::protocol::{ClientMessage, ServerMessage};
+use crate::codec::{encode_frame, SnapshotEncoding};
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Query-string parameters accepted on the upgrade request.
+#[derive(Debug, Default, Deserialize)]
+pub struct ConnectParams {
+    /// Access token, for clients that cannot set headers
+    pub token: Option<String>,
+    /// Encoding of stat updates (`protobuf` by default, or `json`)
+    #[serde(default)]
+    pub encoding: SnapshotEncoding,
+}
+
+/// Authenticate the handshake and upgrade the connection.
+///
+/// Requests without a valid token are rejected before the upgrade.
+pub async fn ws_handler(
+    State(manager): State<Arc<SessionManager>>,
+    Query(params): Query<ConnectParams>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> ApiResult<Response> {
+    let token = bearer_token(&headers).or(params.token.as_deref());
+    let claims = manager.authenticate(token)?;
+    Ok(upgrade.on_upgrade(move |socket| async move {
+        let (session, outbound) = manager.register(claims);
+        run_connection(manager.clone(), session.clone(), socket, outbound, params.encoding).await;
+        manager.unregister(session.id());
+    }))
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+async fn run_connection(
+    manager: Arc<SessionManager>,
+    session: SessionHandle,
+    socket: WebSocket,
+    mut outbound: mpsc::Receiver<ServerMessage>,
+    encoding: SnapshotEncoding,
+) {
+    let (mut sink, mut stream) = socket.split();
+    let config = manager.config().clone();
+
+    let welcome = ServerMessage::Welcome {
+        session_id: session.id().to_string(),
+        heartbeat_interval_secs: config.heartbeat_interval.as_secs(),
+        encoding,
+    };
+    if send_json(&mut sink, &welcome).await.is_err() {
+        return;
+    }
+
+    let mut heartbeat = tokio::time::interval(config.heartbeat_interval);
+    heartbeat.tick().await;
+
+    loop {
+        tokio::select! {
+            incoming = stream.next() => {
+                let Some(Ok(message)) = incoming else {
+                    break;
+                };
+                session.touch();
+                match message {
+                    Message::Text(text) => {
+                        let reply = handle_client_message(&manager, &session, &text).await;
+                        if let Some(reply) = reply {
+                            if send_json(&mut sink, &reply).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Message::Close(_) => break,
+                    Message::Binary(_) | Message::Ping(_) | Message::Pong(_) => {}
+                }
+            }
+            message = outbound.recv() => {
+                let Some(message) = message else {
+                    break;
+                };
+                if send_message(&mut sink, message, encoding).await.is_err() {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if session.idle_for() > config.idle_timeout {
+                    manager.expire(session.id());
+                    break;
+                }
+                if sink.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+            _ = session.closed() => break,
+        }
+    }
+
+    let _ = sink.send(Message::Close(None)).await;
+}
+
+async fn handle_client_message(manager: &SessionManager, session: &SessionHandle, text: &str) -> Option<ServerMessage> {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(message) => message,
+        Err(error) => {
+            return Some(ApiErrorResponse::new(ErrorCode::InvalidFormat, error.to_string()).into());
+        }
+    };
+
+    match message {
+        ClientMessage::Subscribe { channel } => Some(match manager.subscribe(session, channel.clone()).await {
+            Ok(()) => ServerMessage::Subscribed { channel },
+            Err(error) => error.into(),
+        }),
+        ClientMessage::Unsubscribe { channel } => {
+            manager.unsubscribe(session, &channel);
+            Some(ServerMessage::Unsubscribed { channel })
+        }
+        ClientMessage::Chat { target, text } => {
+            let Some(chat) = manager.chat() else {
+                return Some(chat_unavailable());
+            };
+            // Delivered messages come back through the channel or whisper like everyone else's
+            chat.send(manager, session, target, &text).await.err().map(Into::into)
+        }
+        ClientMessage::ChatHistory { target, limit } => {
+            let Some(chat) = manager.chat() else {
+                return Some(chat_unavailable());
+            };
+            Some(match chat.history(session, &target, limit).await {
+                Ok(messages) => ServerMessage::ChatHistory { target, messages },
+                Err(error) => error.into(),
+            })
+        }
+        ClientMessage::Ping => Some(ServerMessage::Pong),
+    }
+}
+
+fn chat_unavailable() -> ServerMessage {
+    ApiErrorResponse::new(ErrorCode::ServiceUnavailable, "Chat is not available").into()
+}
+
+async fn send_message<S>(sink: &mut S, message: ServerMessage, encoding: SnapshotEncoding) -> Result<(), ()>
+where
+    S: SinkExt<Message> + Unpin,
+{
+    match (message, encoding) {
+        (ServerMessage::Sync { channel, update }, SnapshotEncoding::Protobuf) => {
+            let frame = encode_frame(&channel.to_string(), update.into());
+            sink.send(Message::Binary(frame)).await.map_err(|_| ())
+        }
+        (message, _) => send_json(sink, &message).await,
+    }
+}
+
+async fn send_json<S>(sink: &mut S, message: &ServerMessage) -> Result<(), ()>
+where
+    S: SinkExt<Message> + Unpin,
+{
+    let text = serde_json::to_string(message).map_err(|_| ())?;
+    sink.send(Message::Text(text)).await.map_err(|_| ())
+}
@@ -0,0 +1,448 @@
+//! Session and channel bookkeeping for WebSocket connections.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use shared::error::ErrorCode;
+use shared::events::topics::{self, PartyChanged, PartyLootUpdated, TutorialHintChanged};
+use shared::events::EventBus;
+use tokio::sync::{mpsc, Notify};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use super::auth::{TokenValidator, WsClaims};
+use super::metrics::WebSocketMetrics;
+use super::protocol::{Channel, ServerMessage, SyncUpdate};
+use crate::chat::ChatService;
+use crate::error::{ApiErrorResponse, ApiResult};
+
+/// Unique ID of a WebSocket session.
+pub type SessionId = Uuid;
+
+/// WebSocket layer configuration.
+#[derive(Debug, Clone)]
+pub struct WebSocketConfig {
+    /// How often the server pings clients
+    pub heartbeat_interval: Duration,
+    /// How long a client may stay silent before it is disconnected
+    pub idle_timeout: Duration,
+    /// Outbound messages buffered per session
+    pub send_buffer: usize,
+    /// Consecutive dropped messages before a slow client is disconnected
+    pub max_dropped_messages: u32,
+    /// Channels a single session may subscribe to
+    pub max_subscriptions: usize,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(15),
+            idle_timeout: Duration::from_secs(45),
+            send_buffer: 256,
+            max_dropped_messages: 64,
+            max_subscriptions: 32,
+        }
+    }
+}
+
+/// Decides whether a session may subscribe to a channel.
+#[async_trait]
+pub trait ChannelAuthorizer: Send + Sync {
+    /// Check whether the session's user may subscribe to the channel.
+    async fn can_subscribe(&self, claims: &WsClaims, channel: &Channel) -> bool;
+}
+
+/// Authorizer that allows every subscription.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAll;
+
+#[async_trait]
+impl ChannelAuthorizer for AllowAll {
+    async fn can_subscribe(&self, _claims: &WsClaims, _channel: &Channel) -> bool {
+        true
+    }
+}
+
+/// Per-connection state shared between the manager and the connection task.
+#[derive(Debug)]
+struct SessionState {
+    id: SessionId,
+    claims: WsClaims,
+    outbound: mpsc::Sender<ServerMessage>,
+    subscriptions: Mutex<HashSet<Channel>>,
+    last_seen: Mutex<Instant>,
+    dropped_in_a_row: AtomicU32,
+    closed: AtomicBool,
+    close_signal: Notify,
+}
+
+/// Connection-side handle to a registered session.
+#[derive(Debug, Clone)]
+pub struct SessionHandle {
+    state: Arc<SessionState>,
+}
+
+impl SessionHandle {
+    /// Get the session ID.
+    pub fn id(&self) -> SessionId {
+        self.state.id
+    }
+
+    /// Get the authenticated user's claims.
+    pub fn claims(&self) -> &WsClaims {
+        &self.state.claims
+    }
+
+    /// Record activity from the client.
+    pub fn touch(&self) {
+        *self.state.last_seen.lock().unwrap() = Instant::now();
+    }
+
+    /// Get the time since the client was last heard from.
+    pub fn idle_for(&self) -> Duration {
+        self.state.last_seen.lock().unwrap().elapsed()
+    }
+
+    /// Get the subscribed channels.
+    pub fn subscriptions(&self) -> Vec<Channel> {
+        self.state.subscriptions.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Check whether the session is subscribed to a channel.
+    pub fn is_subscribed(&self, channel: &Channel) -> bool {
+        self.state.subscriptions.lock().unwrap().contains(channel)
+    }
+
+    /// Check whether the manager has closed the session.
+    pub fn is_closed(&self) -> bool {
+        self.state.closed.load(Ordering::Acquire)
+    }
+
+    /// Wait until the manager closes the session.
+    pub async fn closed(&self) {
+        while !self.is_closed() {
+            self.state.close_signal.notified().await;
+        }
+    }
+}
+
+/// Tracks connected sessions and routes channel broadcasts to them.
+pub struct SessionManager {
+    config: WebSocketConfig,
+    validator: TokenValidator,
+    authorizer: Arc<dyn ChannelAuthorizer>,
+    sessions: RwLock<HashMap<SessionId, Arc<SessionState>>>,
+    channels: RwLock<HashMap<Channel, HashSet<SessionId>>>,
+    metrics: WebSocketMetrics,
+    chat: Option<Arc<ChatService>>,
+}
+
+impl SessionManager {
+    /// Create a manager that allows every subscription.
+    pub fn new(config: WebSocketConfig, validator: TokenValidator) -> Self {
+        Self::with_authorizer(config, validator, Arc::new(AllowAll))
+    }
+
+    /// Create a manager with a subscription authorizer.
+    pub fn with_authorizer(
+        config: WebSocketConfig,
+        validator: TokenValidator,
+        authorizer: Arc<dyn ChannelAuthorizer>,
+    ) -> Self {
+        Self {
+            config,
+            validator,
+            authorizer,
+            sessions: RwLock::new(HashMap::new()),
+            channels: RwLock::new(HashMap::new()),
+            metrics: WebSocketMetrics::new(),
+            chat: None,
+        }
+    }
+
+    /// Let sessions chat through a chat service.
+    pub fn with_chat(mut self, chat: Arc<ChatService>) -> Self {
+        self.chat = Some(chat);
+        self
+    }
+
+    /// Get the chat service, if sessions may chat.
+    pub fn chat(&self) -> Option<&Arc<ChatService>> {
+        self.chat.as_ref()
+    }
+
+    /// Get the configuration.
+    pub fn config(&self) -> &WebSocketConfig {
+        &self.config
+    }
+
+    /// Get the connection metrics.
+    pub fn metrics(&self) -> &WebSocketMetrics {
+        &self.metrics
+    }
+
+    /// Get the number of connected sessions.
+    pub fn session_count(&self) -> usize {
+        self.sessions.read().unwrap().len()
+    }
+
+    /// Get the number of sessions subscribed to a channel.
+    pub fn subscriber_count(&self, channel: &Channel) -> usize {
+        self.channels.read().unwrap().get(channel).map_or(0, HashSet::len)
+    }
+
+    /// Validate a handshake token.
+    pub fn authenticate(&self, token: Option<&str>) -> ApiResult<WsClaims> {
+        let result = match token {
+            Some(token) => self.validator.validate(token),
+            None => Err(ApiErrorResponse::new(ErrorCode::Unauthenticated, "Missing token")),
+        };
+        if result.is_err() {
+            self.metrics.auth_failed();
+        }
+        result
+    }
+
+    /// Register a new session, returning its handle and outbound message queue.
+    pub fn register(&self, claims: WsClaims) -> (SessionHandle, mpsc::Receiver<ServerMessage>) {
+        let (outbound, receiver) = mpsc::channel(self.config.send_buffer.max(1));
+        let state = Arc::new(SessionState {
+            id: Uuid::new_v4(),
+            claims,
+            outbound,
+            subscriptions: Mutex::new(HashSet::new()),
+            last_seen: Mutex::new(Instant::now()),
+            dropped_in_a_row: AtomicU32::new(0),
+            closed: AtomicBool::new(false),
+            close_signal: Notify::new(),
+        });
+        self.sessions.write().unwrap().insert(state.id, state.clone());
+        self.metrics.connection_opened();
+        tracing::debug!(session_id = %state.id, user_id = %state.claims.sub, "WebSocket session opened");
+        (SessionHandle { state }, receiver)
+    }
+
+    /// Remove a session and all its subscriptions.
+    pub fn unregister(&self, session_id: SessionId) {
+        let Some(state) = self.sessions.write().unwrap().remove(&session_id) else {
+            return;
+        };
+        let subscriptions = std::mem::take(&mut *state.subscriptions.lock().unwrap());
+        let mut channels = self.channels.write().unwrap();
+        for channel in subscriptions {
+            remove_subscriber(&mut channels, &channel, session_id);
+        }
+        drop(channels);
+
+        state.closed.store(true, Ordering::Release);
+        state.close_signal.notify_one();
+        self.metrics.connection_closed();
+        tracing::debug!(%session_id, "WebSocket session closed");
+    }
+
+    /// Disconnect a session that stopped sending heartbeats.
+    pub fn expire(&self, session_id: SessionId) {
+        self.metrics.idle_timed_out();
+        self.unregister(session_id);
+    }
+
+    /// Subscribe a session to a channel.
+    pub async fn subscribe(&self, session: &SessionHandle, channel: Channel) -> ApiResult<()> {
+        if !self.authorizer.can_subscribe(session.claims(), &channel).await {
+            return Err(ApiErrorResponse::new(
+                ErrorCode::PermissionDenied,
+                format!("Not allowed to subscribe to '{}'", channel),
+            ));
+        }
+
+        {
+            let mut subscriptions = session.state.subscriptions.lock().unwrap();
+            if subscriptions.contains(&channel) {
+                return Ok(());
+            }
+            if subscriptions.len() >= self.config.max_subscriptions {
+                return Err(ApiErrorResponse::new(
+                    ErrorCode::RateLimited,
+                    format!("At most {} subscriptions per connection", self.config.max_subscriptions),
+                ));
+            }
+            subscriptions.insert(channel.clone());
+        }
+        self.channels
+            .write()
+            .unwrap()
+            .entry(channel)
+            .or_default()
+            .insert(session.id());
+        Ok(())
+    }
+
+    /// Unsubscribe a session from a channel.
+    pub fn unsubscribe(&self, session: &SessionHandle, channel: &Channel) {
+        if session.state.subscriptions.lock().unwrap().remove(channel) {
+            remove_subscriber(&mut self.channels.write().unwrap(), channel, session.id());
+        }
+    }
+
+    /// Queue a message for one session.
+    ///
+    /// Returns `false` if the message was dropped.
+    pub fn send(&self, session_id: SessionId, message: ServerMessage) -> bool {
+        let state = self.sessions.read().unwrap().get(&session_id).cloned();
+        match state {
+            Some(state) => self.deliver(&state, message),
+            None => false,
+        }
+    }
+
+    /// Queue a message for every session of a user.
+    ///
+    /// Returns the number of sessions the message was queued for.
+    pub fn send_to_user(&self, user_id: &str, message: ServerMessage) -> usize {
+        let targets: Vec<Arc<SessionState>> = self
+            .sessions
+            .read()
+            .unwrap()
+            .values()
+            .filter(|state| state.claims.sub == user_id)
+            .cloned()
+            .collect();
+        targets
+            .iter()
+            .filter(|state| self.deliver(state, message.clone()))
+            .count()
+    }
+
+    /// Check whether a user has a connected session.
+    pub fn is_online(&self, user_id: &str) -> bool {
+        self.sessions.read().unwrap().values().any(|state| state.claims.sub == user_id)
+    }
+
+    /// Publish an event to every subscriber of a channel.
+    ///
+    /// Never waits on a client: messages for clients whose buffer is full are dropped,
+    /// and clients that keep falling behind are disconnected. Returns the number of
+    /// sessions the event was queued for.
+    pub fn broadcast(&self, channel: &Channel, payload: serde_json::Value) -> usize {
+        let message = ServerMessage::Event {
+            channel: channel.clone(),
+            payload,
+        };
+        self.fan_out(channel, message)
+    }
+
+    /// Publish a stat update to every subscriber of a channel.
+    ///
+    /// Delivery follows the same rules as [`broadcast`](Self::broadcast).
+    pub fn publish_sync(&self, channel: &Channel, update: SyncUpdate) -> usize {
+        let message = ServerMessage::Sync {
+            channel: channel.clone(),
+            update,
+        };
+        self.fan_out(channel, message)
+    }
+
+    /// Forward party changes from the event bus to the `party:<party_id>` channels until
+    /// the bus closes.
+    pub async fn forward_party_changes(self: Arc<Self>, bus: Arc<dyn EventBus>) -> ApiResult<JoinHandle<()>> {
+        let mut subscription = bus.subscribe_topic(topics::PARTY_CHANGED.name()).await?;
+        Ok(tokio::spawn(async move {
+            while let Some(envelope) = subscription.next().await {
+                match envelope.decode_message::<PartyChanged>() {
+                    Ok(changed) => {
+                        let channel = Channel::Party(changed.party_id.clone());
+                        self.broadcast(&channel, serde_json::to_value(&changed).unwrap_or_default());
+                    }
+                    Err(e) => tracing::warn!("Dropping undecodable party change: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Forward party loot rolls and awards from the event bus to the `party:<party_id>`
+    /// channels until the bus closes.
+    pub async fn forward_party_loot(self: Arc<Self>, bus: Arc<dyn EventBus>) -> ApiResult<JoinHandle<()>> {
+        let mut subscription = bus.subscribe_topic(topics::PARTY_LOOT.name()).await?;
+        Ok(tokio::spawn(async move {
+            while let Some(envelope) = subscription.next().await {
+                match envelope.decode_message::<PartyLootUpdated>() {
+                    Ok(updated) => {
+                        let channel = Channel::Party(updated.party_id.clone());
+                        self.broadcast(&channel, serde_json::to_value(&updated).unwrap_or_default());
+                    }
+                    Err(e) => tracing::warn!("Dropping undecodable party loot update: {}", e),
+                }
+            }
+        }))
+    }
+
+    /// Push tutorial hints from the event bus to the sessions of the players' users until
+    /// the bus closes.
+    pub async fn forward_tutorial_hints(self: Arc<Self>, bus: Arc<dyn EventBus>) -> ApiResult<JoinHandle<()>> {
+        let mut subscription = bus.subscribe_topic(topics::TUTORIAL_HINT.name()).await?;
+        Ok(tokio::spawn(async move {
+            while let Some(envelope) = subscription.next().await {
+                match envelope.decode_message::<TutorialHintChanged>() {
+                    Ok(changed) => {
+                        let user_id = changed.user_id.clone();
+                        self.send_to_user(&user_id, ServerMessage::Tutorial { update: changed });
+                    }
+                    Err(e) => tracing::warn!("Dropping undecodable tutorial hint: {}", e),
+                }
+            }
+        }))
+    }
+
+    pub(crate) fn fan_out(&self, channel: &Channel, message: ServerMessage) -> usize {
+        let targets: Vec<Arc<SessionState>> = {
+            let channels = self.channels.read().unwrap();
+            let Some(subscribers) = channels.get(channel) else {
+                return 0;
+            };
+            let sessions = self.sessions.read().unwrap();
+            subscribers.iter().filter_map(|id| sessions.get(id).cloned()).collect()
+        };
+
+        targets
+            .iter()
+            .filter(|state| self.deliver(state, message.clone()))
+            .count()
+    }
+
+    fn deliver(&self, state: &Arc<SessionState>, message: ServerMessage) -> bool {
+        match state.outbound.try_send(message) {
+            Ok(()) => {
+                state.dropped_in_a_row.store(0, Ordering::Relaxed);
+                self.metrics.message_sent();
+                true
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.metrics.message_dropped();
+                let dropped = state.dropped_in_a_row.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped >= self.config.max_dropped_messages {
+                    tracing::warn!(session_id = %state.id, dropped, "Disconnecting slow WebSocket client");
+                    self.metrics.slow_client_disconnected();
+                    self.unregister(state.id);
+                }
+                false
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.unregister(state.id);
+                false
+            }
+        }
+    }
+}
+
+fn remove_subscriber(channels: &mut HashMap<Channel, HashSet<SessionId>>, channel: &Channel, session_id: SessionId) {
+    if let Some(subscribers) = channels.get_mut(channel) {
+        subscribers.remove(&session_id);
+        if subscribers.is_empty() {
+            channels.remove(channel);
+        }
+    }
+}
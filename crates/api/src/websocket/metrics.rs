@@ -0,0 +1,84 @@
+//! Connection metrics for the WebSocket layer.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Live counters updated by the session manager.
+#[derive(Debug, Default)]
+pub struct WebSocketMetrics {
+    connected_clients: AtomicU64,
+    total_connections: AtomicU64,
+    auth_failures: AtomicU64,
+    messages_sent: AtomicU64,
+    messages_dropped: AtomicU64,
+    slow_client_disconnects: AtomicU64,
+    idle_timeouts: AtomicU64,
+}
+
+/// Point-in-time copy of [`WebSocketMetrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebSocketMetricsSnapshot {
+    /// Currently connected clients
+    pub connected_clients: u64,
+    /// Connections accepted since startup
+    pub total_connections: u64,
+    /// Handshakes rejected for a missing or invalid token
+    pub auth_failures: u64,
+    /// Messages queued for delivery
+    pub messages_sent: u64,
+    /// Messages dropped because a client's buffer was full
+    pub messages_dropped: u64,
+    /// Clients disconnected for falling too far behind
+    pub slow_client_disconnects: u64,
+    /// Clients disconnected for missing heartbeats
+    pub idle_timeouts: u64,
+}
+
+impl WebSocketMetrics {
+    /// Create zeroed metrics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take a snapshot of the counters.
+    pub fn snapshot(&self) -> WebSocketMetricsSnapshot {
+        WebSocketMetricsSnapshot {
+            connected_clients: self.connected_clients.load(Ordering::Relaxed),
+            total_connections: self.total_connections.load(Ordering::Relaxed),
+            auth_failures: self.auth_failures.load(Ordering::Relaxed),
+            messages_sent: self.messages_sent.load(Ordering::Relaxed),
+            messages_dropped: self.messages_dropped.load(Ordering::Relaxed),
+            slow_client_disconnects: self.slow_client_disconnects.load(Ordering::Relaxed),
+            idle_timeouts: self.idle_timeouts.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn connection_opened(&self) {
+        self.connected_clients.fetch_add(1, Ordering::Relaxed);
+        self.total_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_closed(&self) {
+        self.connected_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn auth_failed(&self) {
+        self.auth_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn message_sent(&self) {
+        self.messages_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn message_dropped(&self) {
+        self.messages_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn slow_client_disconnected(&self) {
+        self.slow_client_disconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn idle_timed_out(&self) {
+        self.idle_timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+}
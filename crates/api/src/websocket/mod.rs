@@ -0,0 +1,24 @@
+//! WebSocket connections.
+//!
+//! Clients connect to [`ws_handler`] with a JWT (`Authorization: Bearer ...` or
+//! `?token=...`). Each accepted connection becomes a session in the [`SessionManager`],
+//! which tracks channel subscriptions, drops idle connections, and fans out
+//! [`ServerMessage`]s to subscribers without letting one slow client stall a broadcast.
+//!
+//! Sessions chat over the same connection when the manager has a
+//! [`ChatService`](crate::chat::ChatService).
+//!
+//! Stat updates ([`SyncUpdate`]) are sent as protobuf binary frames unless the client
+//! connects with `?encoding=json`.
+
+pub mod auth;
+pub mod handler;
+pub mod manager;
+pub mod metrics;
+pub mod protocol;
+
+pub use auth::{TokenValidator, WsClaims};
+pub use handler::ws_handler;
+pub use manager::{AllowAll, ChannelAuthorizer, SessionHandle, SessionId, SessionManager, WebSocketConfig};
+pub use metrics::{WebSocketMetrics, WebSocketMetricsSnapshot};
+pub use protocol::{Channel, ClientMessage, ServerMessage, SyncUpdate};
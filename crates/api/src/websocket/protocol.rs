@@ -0,0 +1,158 @@
+//! Messages exchanged over WebSocket connections.
+
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+use shared::events::topics::TutorialHintChanged;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chat::{ChatMessage, ChatTarget};
+use crate::codec::SnapshotEncoding;
+use crate::error::ApiErrorResponse;
+use crate::grpc::proto::{sync_frame, CompactSnapshot, ElementalDelta, SnapshotDelta};
+
+/// Broadcast channel a session can subscribe to.
+///
+/// Channels travel as `zone:<id>` / `party:<id>` / `guild:<id>` strings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Channel {
+    /// Everything happening in a zone
+    Zone(String),
+    /// Party members' updates and chat
+    Party(String),
+    /// Guild members' chat
+    Guild(String),
+}
+
+impl Channel {
+    /// Get the channel ID (zone, party or guild ID).
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Zone(id) | Self::Party(id) | Self::Guild(id) => id,
+        }
+    }
+}
+
+impl FromStr for Channel {
+    type Err = ApiErrorResponse;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (kind, id) = value
+            .split_once(':')
+            .filter(|(_, id)| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .ok_or_else(|| ApiErrorResponse::new(ErrorCode::ValidationFailed, format!("Invalid channel: '{}'", value)))?;
+        match kind {
+            "zone" => Ok(Self::Zone(id.to_string())),
+            "party" => Ok(Self::Party(id.to_string())),
+            "guild" => Ok(Self::Guild(id.to_string())),
+            _ => Err(ApiErrorResponse::new(ErrorCode::ValidationFailed, format!("Unknown channel kind: '{}'", kind))),
+        }
+    }
+}
+
+impl TryFrom<String> for Channel {
+    type Error = ApiErrorResponse;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Channel> for String {
+    fn from(channel: Channel) -> Self {
+        channel.to_string()
+    }
+}
+
+impl fmt::Display for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zone(id) => write!(f, "zone:{}", id),
+            Self::Party(id) => write!(f, "party:{}", id),
+            Self::Guild(id) => write!(f, "guild:{}", id),
+        }
+    }
+}
+
+/// Message sent by a client.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// Start receiving a channel's events
+    Subscribe { channel: Channel },
+    /// Stop receiving a channel's events
+    Unsubscribe { channel: Channel },
+    /// Post a chat message
+    Chat { target: ChatTarget, text: String },
+    /// Fetch the latest chat messages of a channel or whisper conversation
+    ChatHistory {
+        target: ChatTarget,
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    /// Application-level heartbeat
+    Ping,
+}
+
+/// Message sent to a client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// Sent once after the handshake
+    Welcome {
+        session_id: String,
+        heartbeat_interval_secs: u64,
+        encoding: SnapshotEncoding,
+    },
+    /// Subscription confirmed
+    Subscribed { channel: Channel },
+    /// Unsubscription confirmed
+    Unsubscribed { channel: Channel },
+    /// Event published on a channel
+    Event { channel: Channel, payload: serde_json::Value },
+    /// Stat update published on a channel; sent as a binary frame to protobuf sessions
+    Sync { channel: Channel, update: SyncUpdate },
+    /// Chat message posted to a channel the session is subscribed to, or whispered to
+    /// or by its user
+    Chat { message: ChatMessage },
+    /// Reply to [`ClientMessage::ChatHistory`], oldest message first
+    ChatHistory { target: ChatTarget, messages: Vec<ChatMessage> },
+    /// Tutorial hint to show or hide, sent to every session of the player's user
+    Tutorial { update: TutorialHintChanged },
+    /// Reply to [`ClientMessage::Ping`]
+    Pong,
+    /// Request failed; the connection stays open
+    Error { code: ErrorCode, message: String },
+}
+
+impl From<ApiErrorResponse> for ServerMessage {
+    fn from(error: ApiErrorResponse) -> Self {
+        Self::Error {
+            code: error.error.code,
+            message: error.error.message,
+        }
+    }
+}
+
+/// Stat update carried by [`ServerMessage::Sync`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum SyncUpdate {
+    /// Full snapshot
+    Snapshot(CompactSnapshot),
+    /// Changes since the previous snapshot
+    Delta(SnapshotDelta),
+    /// Changes to per-element stats
+    Elemental(ElementalDelta),
+}
+
+impl From<SyncUpdate> for sync_frame::Update {
+    fn from(update: SyncUpdate) -> Self {
+        match update {
+            SyncUpdate::Snapshot(snapshot) => Self::Snapshot(snapshot),
+            SyncUpdate::Delta(delta) => Self::Delta(delta),
+            SyncUpdate::Elemental(delta) => Self::Elemental(delta),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Integration tests for chat over WebSocket sessions.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use api::chat::{ChatConfig, ChatRateLimit, ChatService, ChatTarget, MemoryChatHistory, RetentionPolicy, WordFilter};
+use api::websocket::{Channel, ServerMessage, SessionManager, TokenValidator, WebSocketConfig, WsClaims};
+use chrono::Utc;
+use common::create_test_clock;
+use shared::error::ErrorCode;
+use shared::events::topics::{ChatAbuseKind, ChatAbuseReported, CHAT_ABUSE};
+use shared::events::{EventBus, InProcessEventBus};
+use shared::SimulatedClock;
+
+fn claims(user: &str) -> WsClaims {
+    WsClaims {
+        sub: user.to_string(),
+        exp: (Utc::now().timestamp() + 3600) as usize,
+        roles: Vec::new(),
+    }
+}
+
+fn chat(clock: Arc<SimulatedClock>, config: ChatConfig) -> ChatService {
+    let history = Arc::new(MemoryChatHistory::new(clock.clone(), RetentionPolicy::default()));
+    ChatService::new(config, clock, history)
+}
+
+fn manager(chat: ChatService) -> SessionManager {
+    SessionManager::new(WebSocketConfig::default(), TokenValidator::hs256(b"test-secret")).with_chat(Arc::new(chat))
+}
+
+fn received_text(message: ServerMessage) -> String {
+    match message {
+        ServerMessage::Chat { message } => message.text,
+        other => panic!("expected a chat message, got {:?}", other),
+    }
+}
+
+#[test]
+fn targets_parse_and_serialize() {
+    let target: ChatTarget = "guild:g1".parse().unwrap();
+    assert_eq!(target, ChatTarget::Channel(Channel::Guild("g1".to_string())));
+    assert_eq!("whisper:bob".parse::<ChatTarget>().unwrap(), ChatTarget::Whisper("bob".to_string()));
+    assert!("whisper:".parse::<ChatTarget>().is_err());
+
+    // Both sides of a whisper read the same conversation
+    let to_bob = ChatTarget::Whisper("bob".to_string());
+    let to_alice = ChatTarget::Whisper("alice".to_string());
+    assert_eq!(to_bob.conversation("alice"), to_alice.conversation("bob"));
+}
+
+#[tokio::test]
+async fn channel_messages_reach_subscribers_only() {
+    let manager = manager(chat(create_test_clock(Duration::from_secs(1)), ChatConfig::default()));
+    let party = Channel::Party("p1".to_string());
+    let (alice, mut alice_rx) = manager.register(claims("alice"));
+    let (bob, mut bob_rx) = manager.register(claims("bob"));
+    let (eve, mut eve_rx) = manager.register(claims("eve"));
+    manager.subscribe(&alice, party.clone()).await.unwrap();
+    manager.subscribe(&bob, party.clone()).await.unwrap();
+
+    let chat = manager.chat().unwrap();
+    chat.send(&manager, &alice, ChatTarget::Channel(party.clone()), "  pull in 5  ").await.unwrap();
+    assert_eq!(received_text(alice_rx.try_recv().unwrap()), "pull in 5");
+    assert_eq!(received_text(bob_rx.try_recv().unwrap()), "pull in 5");
+    assert!(eve_rx.try_recv().is_err());
+
+    // Posting needs a subscription, and so does reading the history
+    let error = chat.send(&manager, &eve, ChatTarget::Channel(party.clone()), "hi").await.unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::PermissionDenied);
+    assert!(chat.history(&eve, &ChatTarget::Channel(party.clone()), None).await.is_err());
+
+    let history = chat.history(&bob, &ChatTarget::Channel(party), None).await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].sender_id, "alice");
+}
+
+#[tokio::test]
+async fn whispers_reach_every_session_of_both_users() {
+    let manager = manager(chat(create_test_clock(Duration::from_secs(1)), ChatConfig::default()));
+    let (alice, mut alice_rx) = manager.register(claims("alice"));
+    let (_bob_pc, mut bob_pc_rx) = manager.register(claims("bob"));
+    let (bob_phone, mut bob_phone_rx) = manager.register(claims("bob"));
+    let chat = manager.chat().unwrap();
+
+    chat.send(&manager, &alice, ChatTarget::Whisper("bob".to_string()), "hey").await.unwrap();
+    assert_eq!(received_text(bob_pc_rx.try_recv().unwrap()), "hey");
+    assert_eq!(received_text(bob_phone_rx.try_recv().unwrap()), "hey");
+    assert_eq!(received_text(alice_rx.try_recv().unwrap()), "hey");
+
+    let history = chat.history(&bob_phone, &ChatTarget::Whisper("alice".to_string()), None).await.unwrap();
+    assert_eq!(history.len(), 1);
+
+    let offline = chat.send(&manager, &alice, ChatTarget::Whisper("carol".to_string()), "hi").await.unwrap_err();
+    assert_eq!(offline.error.code, ErrorCode::NotFound);
+    assert!(chat.send(&manager, &alice, ChatTarget::Whisper("alice".to_string()), "me").await.is_err());
+}
+
+#[tokio::test]
+async fn flooding_and_filtered_messages_are_refused_and_reported() {
+    let clock = create_test_clock(Duration::from_secs(1));
+    let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+    let mut reports = bus.subscribe_topic(CHAT_ABUSE.name()).await.unwrap();
+    let config = ChatConfig {
+        rate_limit: ChatRateLimit {
+            max_messages: 2,
+            window: Duration::from_secs(10),
+        },
+        ..ChatConfig::default()
+    };
+    let chat = chat(clock.clone(), config)
+        .with_filter(Arc::new(WordFilter::mask(["darn"])))
+        .with_filter(Arc::new(WordFilter::reject(["goldseller"])))
+        .with_bus(bus.clone(), "chat-test");
+    let manager = manager(chat);
+    let zone = Channel::Zone("z1".to_string());
+    let (alice, mut alice_rx) = manager.register(claims("alice"));
+    manager.subscribe(&alice, zone.clone()).await.unwrap();
+    let chat = manager.chat().unwrap();
+    let target = ChatTarget::Channel(zone);
+
+    // Masked words are delivered as stars, rejected ones not at all
+    chat.send(&manager, &alice, target.clone(), "Darn, missed").await.unwrap();
+    assert_eq!(received_text(alice_rx.try_recv().unwrap()), "****, missed");
+    let blocked = chat.send(&manager, &alice, target.clone(), "visit goldseller now").await.unwrap_err();
+    assert_eq!(blocked.error.code, ErrorCode::PermissionDenied);
+
+    let flooded = chat.send(&manager, &alice, target.clone(), "again").await.unwrap_err();
+    assert_eq!(flooded.error.code, ErrorCode::RateLimited);
+    clock.advance(Duration::from_secs(10));
+    chat.send(&manager, &alice, target, "later").await.unwrap();
+
+    let mut kinds = Vec::new();
+    for _ in 0..2 {
+        let report: ChatAbuseReported = reports.next().await.unwrap().decode_message().unwrap();
+        assert_eq!(report.sender_id, "alice");
+        assert_eq!(report.channel, "zone:z1");
+        kinds.push(report.kind);
+    }
+    assert_eq!(kinds, [ChatAbuseKind::Filtered, ChatAbuseKind::Flooding]);
+}
+
+#[tokio::test]
+async fn history_keeps_the_latest_messages_within_retention() {
+    let clock = create_test_clock(Duration::from_secs(1));
+    let retention = RetentionPolicy {
+        max_messages: 3,
+        max_age: Duration::from_secs(60),
+    };
+    let history = Arc::new(MemoryChatHistory::new(clock.clone(), retention));
+    let manager = manager(ChatService::new(ChatConfig::default(), clock.clone(), history));
+    let guild = Channel::Guild("g1".to_string());
+    let (alice, _rx) = manager.register(claims("alice"));
+    manager.subscribe(&alice, guild.clone()).await.unwrap();
+    let chat = manager.chat().unwrap();
+    let target = ChatTarget::Channel(guild);
+
+    for text in ["one", "two", "three", "four"] {
+        chat.send(&manager, &alice, target.clone(), text).await.unwrap();
+    }
+    let texts = |messages: Vec<api::chat::ChatMessage>| messages.into_iter().map(|m| m.text).collect::<Vec<_>>();
+    assert_eq!(texts(chat.history(&alice, &target, None).await.unwrap()), ["two", "three", "four"]);
+    assert_eq!(texts(chat.history(&alice, &target, Some(1)).await.unwrap()), ["four"]);
+
+    clock.advance(Duration::from_secs(61));
+    assert!(chat.history(&alice, &target, None).await.unwrap().is_empty());
+}
@@ -0,0 +1,120 @@
+//! Integration tests for the compact snapshot encoding.
+
+use std::collections::HashMap;
+
+use actor_core::enums::AcrossLayerPolicy;
+use actor_core::types::{Caps, Snapshot};
+use api::codec::{
+    apply_elemental_delta, apply_snapshot_delta, decode_frame, elemental_delta, encode_frame, snapshot_delta,
+    ElementalStats, SnapshotEncoding,
+};
+use api::grpc::proto::{sync_frame, CompactSnapshot};
+use api::websocket::{Channel, ServerMessage, SyncUpdate};
+use prost::Message;
+use shared::error::ErrorCode;
+
+fn snapshot() -> Snapshot {
+    let mut snapshot = Snapshot::new("hero".to_string());
+    snapshot.version = 3;
+    snapshot.cache_hit = true;
+    snapshot.primary.insert("strength".to_string(), 10.0);
+    snapshot.primary.insert("agility".to_string(), 4.5);
+    snapshot.derived.insert("attack_power".to_string(), 25.0);
+    let mut caps = Caps::new("strength".to_string(), AcrossLayerPolicy::Intersect);
+    caps.min = 0.0;
+    caps.max = 99.0;
+    snapshot.caps_used.insert("strength".to_string(), caps);
+    snapshot
+}
+
+#[test]
+fn snapshot_round_trips_and_is_smaller_than_json() {
+    let original = snapshot();
+    let bytes = CompactSnapshot::from(&original).encode_to_vec();
+    assert!(bytes.len() < serde_json::to_vec(&original).unwrap().len());
+
+    let compact = CompactSnapshot::decode(bytes.as_slice()).unwrap();
+    // "strength" is shared by primary and caps but sent once.
+    assert_eq!(compact.dimensions.len(), 3);
+    let decoded = Snapshot::try_from(compact).unwrap();
+    assert_eq!(decoded.primary, original.primary);
+    assert_eq!(decoded.derived, original.derived);
+    assert_eq!(decoded.caps_used["strength"].max, 99.0);
+    assert_eq!(decoded.version, 3);
+    assert_eq!(decoded.created_at.timestamp_millis(), original.created_at.timestamp_millis());
+}
+
+#[test]
+fn corrupt_dictionary_indexes_are_rejected() {
+    let mut compact = CompactSnapshot::from(&snapshot());
+    compact.dimensions.truncate(1);
+    let error = Snapshot::try_from(compact).unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::InvalidFormat);
+}
+
+#[test]
+fn delta_carries_only_changes() {
+    let previous = snapshot();
+    let mut current = previous.clone();
+    current.version = 4;
+    current.primary.insert("strength".to_string(), 12.0);
+    current.primary.remove("agility");
+    current.derived.insert("defense".to_string(), 7.0);
+
+    let delta = snapshot_delta(&previous, &current);
+    assert_eq!(delta.primary.as_ref().unwrap().value, vec![12.0]);
+    assert_eq!(delta.removed_primary.len(), 1);
+    assert!(delta.caps.is_none());
+
+    let mut client = previous.clone();
+    apply_snapshot_delta(&mut client, delta.clone()).unwrap();
+    assert_eq!(client.primary, current.primary);
+    assert_eq!(client.derived, current.derived);
+    assert_eq!(client.version, 4);
+
+    let error = apply_snapshot_delta(&mut client, delta).unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::Conflict);
+}
+
+#[test]
+fn elemental_delta_applies_per_element() {
+    let previous = ElementalStats {
+        actor_id: "hero".to_string(),
+        version: 1,
+        elements: HashMap::from([("fire".to_string(), HashMap::from([("mastery".to_string(), 10.0)]))]),
+    };
+    let mut current = previous.clone();
+    current.version = 2;
+    current.elements.get_mut("fire").unwrap().insert("power_point".to_string(), 3.0);
+    current
+        .elements
+        .insert("water".to_string(), HashMap::from([("mastery".to_string(), 1.0)]));
+
+    let delta = elemental_delta(&previous, &current);
+    assert_eq!(delta.value.len(), 2);
+    assert_eq!(delta.elements, vec!["fire".to_string(), "water".to_string()]);
+
+    let mut client = previous;
+    apply_elemental_delta(&mut client, delta).unwrap();
+    assert_eq!(client, current);
+}
+
+#[test]
+fn sync_updates_encode_as_frames_or_json() {
+    let update = SyncUpdate::Snapshot(CompactSnapshot::from(&snapshot()));
+    let frame = decode_frame(&encode_frame("zone:forest", update.clone().into())).unwrap();
+    assert_eq!(frame.channel, "zone:forest");
+    assert!(matches!(frame.update, Some(sync_frame::Update::Snapshot(ref s)) if s.actor_id == "hero"));
+
+    let message = ServerMessage::Sync {
+        channel: Channel::Zone("forest".to_string()),
+        update,
+    };
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(json["type"], "sync");
+    assert_eq!(json["update"]["kind"], "snapshot");
+    assert_eq!(json["update"]["data"]["actor_id"], "hero");
+
+    assert_eq!(SnapshotEncoding::default(), SnapshotEncoding::Protobuf);
+    assert_eq!("json".parse::<SnapshotEncoding>().unwrap(), SnapshotEncoding::Json);
+}
@@ -0,0 +1,13 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use chrono::{TimeZone, Utc};
+use shared::SimulatedClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Clock starting at a fixed time and advancing by `tick`
+pub fn create_test_clock(tick: Duration) -> Arc<SimulatedClock> {
+    Arc::new(SimulatedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap(), tick))
+}
@@ -0,0 +1,141 @@
+//! Integration tests for queueing combat actions behind the global cooldown.
+
+mod common;
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use api::error::ApiResult;
+use api::grpc::proto::{CombatAction, CombatActionResult};
+use api::grpc::{ActionQueue, ActionQueueConfig, CombatBackend};
+use async_trait::async_trait;
+use common::create_test_clock;
+use shared::SimulatedClock;
+use tokio::task::JoinHandle;
+
+/// Accepts actions while the attacker has mana left, spending one per action
+#[derive(Default)]
+struct ManaBackend {
+    mana: AtomicU32,
+    resolved: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl CombatBackend for ManaBackend {
+    async fn submit_action(&self, action: CombatAction) -> ApiResult<CombatActionResult> {
+        let spent = self.mana.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |mana| mana.checked_sub(1));
+        self.resolved.lock().unwrap().push(action.action_id.clone());
+        Ok(CombatActionResult {
+            action_id: action.action_id,
+            accepted: spent.is_ok(),
+            rejection_reason: if spent.is_ok() { String::new() } else { "Not enough mana".to_string() },
+            hits: Vec::new(),
+        })
+    }
+}
+
+fn create_test_backend() -> Arc<ManaBackend> {
+    Arc::new(ManaBackend { mana: AtomicU32::new(10), ..Default::default() })
+}
+
+fn create_test_queue(backend: &Arc<ManaBackend>, clock: &Arc<SimulatedClock>, max_depth: usize) -> Arc<ActionQueue> {
+    let config = ActionQueueConfig {
+        global_cooldown: Duration::from_millis(1500),
+        queue_window: Duration::from_millis(400),
+        max_depth,
+        ..Default::default()
+    };
+    Arc::new(ActionQueue::new(backend.clone(), config, clock.clone()))
+}
+
+fn action(id: &str) -> CombatAction {
+    CombatAction {
+        action_id: id.to_string(),
+        attacker_id: "hero".to_string(),
+        target_ids: vec!["wolf".to_string()],
+        skill_id: "slash".to_string(),
+        tick: 0,
+    }
+}
+
+/// Submit an action that may be queued, waiting until it is
+async fn submit_queued(queue: &Arc<ActionQueue>, id: &str) -> JoinHandle<ApiResult<CombatActionResult>> {
+    let queued = queue.queued("hero");
+    let submitter = queue.clone();
+    let action = action(id);
+    let handle = tokio::spawn(async move { submitter.submit_action(action).await });
+    while queue.queued("hero") == queued && !handle.is_finished() {
+        tokio::task::yield_now().await;
+    }
+    handle
+}
+
+#[tokio::test]
+async fn queues_actions_submitted_shortly_before_the_cooldown_ends() {
+    let (clock, backend) = (create_test_clock(Duration::from_millis(100)), create_test_backend());
+    let queue = create_test_queue(&backend, &clock, 1);
+    assert!(queue.submit_action(action("first")).await.unwrap().accepted);
+
+    // Far from the end of the cooldown
+    clock.advance(Duration::from_millis(500));
+    let early = queue.submit_action(action("early")).await.unwrap();
+    assert_eq!((early.accepted, early.rejection_reason.as_str()), (false, "On global cooldown"));
+
+    clock.advance(Duration::from_millis(700));
+    let queued = submit_queued(&queue, "second").await;
+    assert_eq!(queue.queued("hero"), 1);
+    let full = queue.submit_action(action("third")).await.unwrap();
+    assert_eq!(full.rejection_reason, "Action queue is full");
+
+    // Nothing runs until the cooldown is over
+    queue.run_due().await;
+    assert!(!queued.is_finished());
+    clock.advance(Duration::from_millis(300));
+    queue.run_due().await;
+    assert!(queued.await.unwrap().unwrap().accepted);
+    assert_eq!(*backend.resolved.lock().unwrap(), ["first", "second"]);
+
+    // The queued action started a new cooldown when it ran
+    clock.advance(Duration::from_millis(1000));
+    assert!(!queue.submit_action(action("fourth")).await.unwrap().accepted);
+}
+
+#[tokio::test]
+async fn queued_actions_are_checked_when_they_run() {
+    let (clock, backend) = (create_test_clock(Duration::from_millis(100)), create_test_backend());
+    let queue = create_test_queue(&backend, &clock, 2);
+    assert!(queue.submit_action(action("first")).await.unwrap().accepted);
+    clock.advance(Duration::from_millis(1200));
+    let starved = submit_queued(&queue, "starved").await;
+
+    // Mana ran out while the action waited
+    backend.mana.store(0, Ordering::SeqCst);
+    clock.advance(Duration::from_millis(300));
+    queue.run_due().await;
+    let result = starved.await.unwrap().unwrap();
+    assert_eq!((result.accepted, result.rejection_reason.as_str()), (false, "Not enough mana"));
+
+    // A rejected action gives the cooldown back
+    backend.mana.store(1, Ordering::SeqCst);
+    assert!(queue.submit_action(action("retry")).await.unwrap().accepted);
+}
+
+#[tokio::test]
+async fn queued_actions_can_be_cancelled() {
+    let (clock, backend) = (create_test_clock(Duration::from_millis(100)), create_test_backend());
+    let queue = create_test_queue(&backend, &clock, 2);
+    assert!(queue.submit_action(action("first")).await.unwrap().accepted);
+    clock.advance(Duration::from_millis(1200));
+    let cancelled = submit_queued(&queue, "cancelled").await;
+
+    assert!(queue.cancel_action("hero", "cancelled").await.unwrap());
+    assert!(!queue.cancel_action("hero", "cancelled").await.unwrap());
+    let result = cancelled.await.unwrap().unwrap();
+    assert_eq!((result.accepted, result.rejection_reason.as_str()), (false, "Cancelled"));
+
+    clock.advance(Duration::from_millis(300));
+    queue.run_due().await;
+    assert_eq!(*backend.resolved.lock().unwrap(), ["first"]);
+    assert_eq!(queue.queued("hero"), 0);
+}
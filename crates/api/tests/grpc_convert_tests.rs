@@ -0,0 +1,57 @@
+//! Integration tests for gRPC message conversions.
+
+use actor_core::types::{Actor, Snapshot};
+use api::grpc::convert::{json_to_value, value_to_json};
+use api::grpc::proto;
+use std::collections::HashMap;
+
+#[test]
+fn actor_round_trips_through_proto() {
+    let mut actor = Actor::new("actor-1".to_string(), "human".to_string());
+    actor.name = "Aria".to_string();
+    actor.level = 12;
+    actor.custom_resources.insert("qi".to_string(), 40.0);
+    actor.data.insert("guild".to_string(), serde_json::json!({ "id": "g-1", "rank": 2.0 }));
+
+    let message = proto::Actor::from(&actor);
+    let converted = Actor::try_from(message).unwrap();
+    assert_eq!(converted.id, actor.id);
+    assert_eq!(converted.name, actor.name);
+    assert_eq!(converted.level, actor.level);
+    assert_eq!(converted.core_resources, actor.core_resources);
+    assert_eq!(converted.custom_resources, actor.custom_resources);
+    assert_eq!(converted.data, actor.data);
+}
+
+#[test]
+fn invalid_actors_are_rejected() {
+    let missing_id = proto::Actor::default();
+    assert!(Actor::try_from(missing_id).is_err());
+
+    let short_resources = proto::Actor {
+        id: "actor-1".to_string(),
+        core_resources: vec![1.0, 2.0],
+        ..proto::Actor::default()
+    };
+    let error = Actor::try_from(short_resources).unwrap_err();
+    assert_eq!(error.to_grpc_status().code(), tonic::Code::InvalidArgument);
+}
+
+#[test]
+fn snapshot_converts_to_proto() {
+    let mut snapshot = Snapshot::new("actor-1".to_string());
+    snapshot.primary.insert("strength".to_string(), 10.0);
+    snapshot.derived = HashMap::from([("attack".to_string(), 25.0)]);
+
+    let message = proto::StatSnapshot::from(snapshot);
+    assert_eq!(message.actor_id, "actor-1");
+    assert_eq!(message.primary["strength"], 10.0);
+    assert_eq!(message.derived["attack"], 25.0);
+    assert!(!message.created_at.is_empty());
+}
+
+#[test]
+fn json_values_round_trip() {
+    let value = serde_json::json!({ "a": [true, null, "x", 1.5], "b": { "c": 2.0 } });
+    assert_eq!(value_to_json(json_to_value(&value)), value);
+}
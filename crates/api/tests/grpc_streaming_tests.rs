@@ -0,0 +1,120 @@
+//! Integration tests for bridging the event bus to gRPC streams.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use api::grpc::streaming::{zone_topic, EventStreamConfig, EventStreamHub, ResumeToken, StreamedEvent};
+use futures::StreamExt;
+use shared::events::{EventBusExt, InProcessEventBus, Topic};
+
+async fn next(stream: &mut api::grpc::streaming::EventStream<StreamedEvent>) -> StreamedEvent {
+    tokio::time::timeout(Duration::from_secs(1), stream.next())
+        .await
+        .expect("timed out waiting for event")
+        .expect("stream ended")
+        .expect("stream error")
+}
+
+async fn publish(bus: &InProcessEventBus, topic: &str, payload: serde_json::Value) {
+    let topic: Topic<serde_json::Value> = Topic::dynamic(topic);
+    bus.publish(&topic, &payload).await.unwrap();
+}
+
+#[tokio::test]
+async fn filters_events_per_subscriber() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let hub = EventStreamHub::new(bus.clone());
+    let topic = zone_topic("forest");
+
+    let mut spawns = hub
+        .subscribe(&topic, None, |envelope| envelope.payload["type"] == "spawn")
+        .await
+        .unwrap();
+    publish(&bus, &topic, serde_json::json!({ "type": "chat" })).await;
+    publish(&bus, &topic, serde_json::json!({ "type": "spawn", "id": 1 })).await;
+
+    let event = next(&mut spawns).await;
+    assert_eq!(event.envelope.payload["id"], 1);
+    assert!(!event.gap);
+}
+
+#[tokio::test]
+async fn resume_token_replays_missed_events() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let hub = EventStreamHub::new(bus.clone());
+    let topic = zone_topic("forest");
+
+    let mut first = hub.subscribe(&topic, None, |_| true).await.unwrap();
+    for id in 0..3 {
+        publish(&bus, &topic, serde_json::json!({ "id": id })).await;
+    }
+    let token = next(&mut first).await.resume_token;
+    drop(first);
+
+    let mut resumed = hub.subscribe(&topic, Some(&token), |_| true).await.unwrap();
+    assert_eq!(next(&mut resumed).await.envelope.payload["id"], 1);
+    assert_eq!(next(&mut resumed).await.envelope.payload["id"], 2);
+
+    publish(&bus, &topic, serde_json::json!({ "id": 3 })).await;
+    assert_eq!(next(&mut resumed).await.envelope.payload["id"], 3);
+}
+
+#[tokio::test]
+async fn stale_resume_token_reports_gap() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let config = EventStreamConfig {
+        replay_capacity: 2,
+        ..EventStreamConfig::default()
+    };
+    let hub = EventStreamHub::with_config(bus.clone(), config);
+    let topic = zone_topic("forest");
+
+    let mut first = hub.subscribe(&topic, None, |_| true).await.unwrap();
+    for id in 0..5 {
+        publish(&bus, &topic, serde_json::json!({ "id": id })).await;
+    }
+    let token = next(&mut first).await.resume_token;
+    for _ in 1..5 {
+        next(&mut first).await;
+    }
+
+    let mut resumed = hub.subscribe(&topic, Some(&token), |_| true).await.unwrap();
+    let event = next(&mut resumed).await;
+    assert!(event.gap);
+    assert_eq!(event.envelope.payload["id"], 3);
+
+    let foreign = ResumeToken { epoch: 0, sequence: 0 }.encode();
+    let mut restarted = hub.subscribe(&topic, Some(&foreign), |_| true).await.unwrap();
+    publish(&bus, &topic, serde_json::json!({ "id": 5 })).await;
+    assert!(next(&mut restarted).await.gap);
+
+    assert!(hub.subscribe(&topic, Some("not-a-token"), |_| true).await.is_err());
+}
+
+#[tokio::test]
+async fn slow_subscribers_are_ended() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let config = EventStreamConfig {
+        live_capacity: 2,
+        subscriber_buffer: 1,
+        ..EventStreamConfig::default()
+    };
+    let hub = EventStreamHub::with_config(bus.clone(), config);
+    let topic = zone_topic("forest");
+
+    let mut slow = hub.subscribe(&topic, None, |_| true).await.unwrap();
+    for id in 0..16 {
+        publish(&bus, &topic, serde_json::json!({ "id": id })).await;
+        tokio::task::yield_now().await;
+    }
+
+    let mut saw_error = false;
+    while let Ok(Some(item)) = tokio::time::timeout(Duration::from_secs(1), slow.next()).await {
+        if let Err(status) = item {
+            assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+            saw_error = true;
+            break;
+        }
+    }
+    assert!(saw_error);
+}
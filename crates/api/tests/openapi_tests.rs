@@ -0,0 +1,18 @@
+//! Integration tests for the generated OpenAPI document.
+
+#![cfg(feature = "openapi")]
+
+#[test]
+fn document_lists_actor_routes_and_schemas() {
+    let document = serde_json::to_value(api::openapi::openapi()).unwrap();
+
+    for path in ["/actors/{id}/snapshot", "/actors/{id}/resolve", "/actors/{id}/contributions"] {
+        assert!(document["paths"].get(path).is_some(), "missing path {}", path);
+    }
+    let schemas = &document["components"]["schemas"];
+    for schema in ["SnapshotResponse", "ContributionsResponse", "ErrorBody"] {
+        assert!(schemas.get(schema).is_some(), "missing schema {}", schema);
+    }
+    let parameters = document["paths"]["/actors/{id}/contributions"]["get"]["parameters"].as_array().unwrap();
+    assert!(parameters.iter().any(|parameter| parameter["name"] == "dimension"));
+}
@@ -0,0 +1,259 @@
+//! Integration tests for the actor REST routes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actor_core::aggregation_context::AggregationContext;
+use actor_core::display_policy::DisplayPolicy;
+use actor_core::enums::{Bucket, Operator};
+use actor_core::interfaces::{MergeRule, PluginRegistry, Subsystem};
+use actor_core::service_factory::ServiceFactory;
+use actor_core::types::{Actor, Contribution, SubsystemOutput};
+use actor_core::ActorCoreResult;
+use api::error::ApiResult;
+use api::middleware::versioning::{versioned_router, ApiVersion, VersionPolicy, VersionedRequest, VersionedResponse};
+use api::rest::actors::{router, ContributionsResponse, ResolveRequest, SnapshotResponse};
+use api::rest::v1::SnapshotV1;
+use api::rest::{ActorRestState, ActorSource};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use tower::ServiceExt;
+
+struct FixedSubsystem;
+
+#[async_trait]
+impl Subsystem for FixedSubsystem {
+    fn system_id(&self) -> &str {
+        "fixed"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("fixed".to_string());
+        for (stat, value) in [("strength", 10.0), ("agility", 4.0), ("crit_chance", 0.15347)] {
+            output.add_contribution(Contribution::new(stat.to_string(), Bucket::Flat, value, "fixed".to_string()));
+        }
+        Ok(output)
+    }
+}
+
+struct Actors(HashMap<String, Actor>);
+
+#[async_trait]
+impl ActorSource for Actors {
+    async fn get_actor(&self, actor_id: &str) -> ApiResult<Option<Actor>> {
+        Ok(self.0.get(actor_id).cloned())
+    }
+}
+
+fn app() -> Router {
+    let plugins: Arc<dyn PluginRegistry> = ServiceFactory::create_plugin_registry();
+    plugins.register(Arc::new(FixedSubsystem)).unwrap();
+    let caps_provider = ServiceFactory::create_caps_provider(ServiceFactory::create_cap_layer_registry());
+    let combiner = ServiceFactory::create_combiner_registry();
+    for stat in ["strength", "agility", "crit_chance"] {
+        let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+        combiner.set_rule(stat, rule).unwrap();
+    }
+    combiner.set_display_policy("crit_chance", DisplayPolicy::percentage(2)).unwrap();
+    let aggregator = ServiceFactory::create_aggregator(
+        plugins.clone(),
+        combiner.clone(),
+        caps_provider,
+        ServiceFactory::create_cache().unwrap(),
+    );
+    let actor = Actor::new("hero".to_string(), "human".to_string());
+    router(ActorRestState {
+        aggregator,
+        plugins,
+        actors: Arc::new(Actors(HashMap::from([(actor.id.clone(), actor)]))),
+        combiner,
+    })
+}
+
+fn versioned_app() -> Router {
+    versioned_router(app(), VersionPolicy::default())
+}
+
+async fn call(app: Router, method: Method, uri: &str, body: Option<serde_json::Value>) -> (StatusCode, serde_json::Value) {
+    let (status, _, body) = call_with_headers(app, Request::builder().method(method).uri(uri), body).await;
+    (status, body)
+}
+
+async fn call_with_headers(
+    app: Router,
+    mut request: axum::http::request::Builder,
+    body: Option<serde_json::Value>,
+) -> (StatusCode, axum::http::HeaderMap, serde_json::Value) {
+    let body = match body {
+        Some(body) => {
+            request = request.header("content-type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+    let response = app.oneshot(request.body(body).unwrap()).await.unwrap();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, headers, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+}
+
+#[tokio::test]
+async fn snapshot_includes_caps_and_processing() {
+    let (status, body) = call(app(), Method::GET, "/actors/hero/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let snapshot: SnapshotResponse = serde_json::from_value(body.clone()).unwrap();
+    assert_eq!(snapshot.actor_id, "hero");
+    assert_eq!(snapshot.primary.get("strength"), Some(&10.0));
+    assert!(body.get("caps_used").is_some());
+    assert!(body["processing"].get("cache_hit").is_some());
+}
+
+#[tokio::test]
+async fn snapshots_are_rounded_for_display() {
+    let (status, body) = call(app(), Method::GET, "/actors/hero/snapshot", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let snapshot: SnapshotResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(snapshot.primary.get("crit_chance"), Some(&0.1535));
+    assert_eq!(snapshot.formatted.get("crit_chance").map(String::as_str), Some("15.35%"));
+    // Stats without a policy keep full precision and have no display string
+    assert_eq!(snapshot.primary.get("strength"), Some(&10.0));
+    assert!(!snapshot.formatted.contains_key("strength"));
+}
+
+#[tokio::test]
+async fn resolve_accepts_context() {
+    let body = serde_json::json!({ "context": { "zone_id": "forest", "in_combat": true }, "force": true });
+    let (status, body) = call(app(), Method::POST, "/actors/hero/resolve", Some(body)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["actor_id"], "hero");
+
+    // Context entries are typed; unknown ones are rejected rather than ignored
+    let body = serde_json::json!({ "context": { "zone": "forest" } });
+    let (status, body) = call(app(), Method::POST, "/actors/hero/resolve", Some(body)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "VALIDATION_FAILED");
+
+    let (status, _) = call(app(), Method::POST, "/actors/hero/resolve", None).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn contributions_filter_by_dimension() {
+    let (status, body) = call(app(), Method::GET, "/actors/hero/contributions?dimension=agility", None).await;
+    assert_eq!(status, StatusCode::OK);
+    let response: ContributionsResponse = serde_json::from_value(body).unwrap();
+    assert_eq!(response.subsystems.len(), 1);
+    assert_eq!(response.subsystems[0].primary.len(), 1);
+    assert_eq!(response.subsystems[0].primary[0].value, 4.0);
+
+    let (_, body) = call(app(), Method::GET, "/actors/hero/contributions?dimension=intellect", None).await;
+    assert_eq!(body["subsystems"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn invalid_requests_are_rejected() {
+    let uri = format!("/actors/hero/contributions?dimension={}", "x".repeat(65));
+    let (status, body) = call(app(), Method::GET, &uri, None).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "dimension");
+
+    let context: serde_json::Map<_, _> = (0..65).map(|i| (format!("k{}", i), serde_json::json!(i))).collect();
+    let body = serde_json::json!({ "context": context });
+    let (status, body) = call(app(), Method::POST, "/actors/hero/resolve", Some(body)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["code"], "max_entries");
+}
+
+#[tokio::test]
+async fn unknown_actor_is_not_found() {
+    let (status, body) = call(app(), Method::GET, "/actors/ghost/snapshot", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "NOT_FOUND");
+}
+
+#[tokio::test]
+async fn v1_path_returns_flat_snapshot_with_deprecation() {
+    let request = Request::builder().uri("/v1/actors/hero/snapshot");
+    let (status, headers, body) = call_with_headers(versioned_app(), request, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(headers["x-api-version"], "v1");
+    assert_eq!(headers["deprecation"], "true");
+    assert!(body.get("processing").is_none());
+    assert!(body.get("cache_hit").is_some());
+    let snapshot: SnapshotV1 = serde_json::from_value(body).unwrap();
+    assert_eq!(snapshot.primary.get("strength"), Some(&10.0));
+}
+
+#[tokio::test]
+async fn version_header_selects_version() {
+    let request = Request::builder().uri("/actors/hero/snapshot").header("x-api-version", "1");
+    let (_, headers, body) = call_with_headers(versioned_app(), request, None).await;
+    assert_eq!(headers["x-api-version"], "v1");
+    assert!(body.get("cache_hit").is_some());
+
+    let request = Request::builder().uri("/actors/hero/snapshot");
+    let (_, headers, body) = call_with_headers(versioned_app(), request, None).await;
+    assert_eq!(headers["x-api-version"], "v2");
+    assert!(headers.get("deprecation").is_none());
+    assert!(body.get("processing").is_some());
+
+    let request = Request::builder().uri("/actors/hero/snapshot").header("x-api-version", "v9");
+    let (status, _, _) = call_with_headers(versioned_app(), request, None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn v1_resolve_body_is_adapted() {
+    let body = serde_json::json!({ "refresh": true });
+    let (status, body) = call(versioned_app(), Method::POST, "/v1/actors/hero/resolve", Some(body)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["actor_id"], "hero");
+
+    let policy = VersionPolicy::default().retire(ApiVersion::V1);
+    let app = versioned_router(app(), policy);
+    let (status, _) = call(app, Method::GET, "/v1/actors/hero/snapshot", None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn old_bodies_still_deserialize() {
+    // Bodies recorded from v1 clients before the schema changed.
+    let resolve_v1 = serde_json::json!({ "context": { "zone": "forest" }, "refresh": true });
+    let request = ResolveRequest::from_versioned(ApiVersion::V1, resolve_v1).unwrap();
+    assert!(request.force);
+    assert_eq!(request.context.unwrap()["zone"], "forest");
+
+    let snapshot_v1 = serde_json::json!({
+        "actor_id": "hero",
+        "primary": { "strength": 10.0 },
+        "derived": {},
+        "version": 3,
+        "processing_time": 120,
+        "created_at": "2024-01-01T00:00:00Z"
+    });
+    let snapshot: SnapshotV1 = serde_json::from_value(snapshot_v1).unwrap();
+    assert_eq!(snapshot.processing_time, Some(120));
+    assert!(!snapshot.cache_hit);
+
+    let current: SnapshotResponse = serde_json::from_value(serde_json::json!({
+        "actor_id": "hero",
+        "primary": {},
+        "derived": {},
+        "caps_used": {},
+        "version": 1,
+        "processing": { "processing_time_us": 5, "subsystems_processed": [], "cache_hit": true },
+        "metadata": {},
+        "created_at": "2024-01-01T00:00:00Z"
+    }))
+    .unwrap();
+    let encoded = current.to_versioned(ApiVersion::V1);
+    assert_eq!(encoded["processing_time"], 5);
+    assert_eq!(encoded["cache_hit"], true);
+}
@@ -0,0 +1,152 @@
+//! Integration tests for the batch REST routes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actor_core::interfaces::PluginRegistry;
+use actor_core::service_factory::ServiceFactory;
+use actor_core::types::Actor;
+use api::error::{ApiErrorResponse, ApiResult};
+use api::rest::batch::{router, BatchResponse, GrantedItem, ItemGrant};
+use api::rest::{actors, ActorRestState, ActorSource, BatchConfig, BatchState, ItemGranter};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::Router;
+use shared::error::ErrorCode;
+use tower::ServiceExt;
+
+struct Actors(HashMap<String, Actor>);
+
+#[async_trait]
+impl ActorSource for Actors {
+    async fn get_actor(&self, actor_id: &str) -> ApiResult<Option<Actor>> {
+        Ok(self.0.get(actor_id).cloned())
+    }
+}
+
+struct Inventory;
+
+#[async_trait]
+impl ItemGranter for Inventory {
+    async fn grant(&self, grant: &ItemGrant) -> ApiResult<GrantedItem> {
+        if grant.item_id == "cursed" {
+            return Err(ApiErrorResponse::new(ErrorCode::GameRuleViolation, "Item cannot be granted"));
+        }
+        Ok(GrantedItem {
+            actor_id: grant.actor_id.clone(),
+            item_id: grant.item_id.clone(),
+            quantity: grant.quantity,
+            instance_ids: vec![format!("{}-1", grant.item_id)],
+        })
+    }
+}
+
+fn actor_state() -> ActorRestState {
+    let plugins: Arc<dyn PluginRegistry> = ServiceFactory::create_plugin_registry();
+    let caps_provider = ServiceFactory::create_caps_provider(ServiceFactory::create_cap_layer_registry());
+    let combiner = ServiceFactory::create_combiner_registry();
+    let aggregator = ServiceFactory::create_aggregator(
+        plugins.clone(),
+        combiner.clone(),
+        caps_provider,
+        ServiceFactory::create_cache().unwrap(),
+    );
+    let actors = ["hero", "sidekick", "villain"]
+        .into_iter()
+        .map(|id| (id.to_string(), Actor::new(id.to_string(), "human".to_string())))
+        .collect();
+    ActorRestState {
+        aggregator,
+        plugins,
+        actors: Arc::new(Actors(actors)),
+        combiner,
+    }
+}
+
+fn app() -> Router {
+    let config = BatchConfig {
+        max_concurrency: 2,
+        resolve_chunk_size: 2,
+    };
+    let state = BatchState::new(config)
+        .with_actors(actor_state())
+        .with_items(Arc::new(Inventory));
+    router(state).merge(actors::router(actor_state()))
+}
+
+async fn post(app: Router, uri: &str, body: serde_json::Value) -> (StatusCode, serde_json::Value) {
+    let request = Request::post(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null))
+}
+
+#[tokio::test]
+async fn batch_resolve_reports_each_entry() {
+    let body = serde_json::json!({ "entries": [
+        { "actor_id": "hero" },
+        { "actor_id": "ghost" },
+        { "actor_id": "sidekick", "force": true },
+        { "actor_id": "villain" },
+    ]});
+    let (status, body) = post(app(), "/actors:batchResolve", body).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+
+    let response: BatchResponse<serde_json::Value> = serde_json::from_value(body.clone()).unwrap();
+    assert_eq!((response.succeeded, response.failed), (3, 1));
+    let results = body["results"].as_array().unwrap();
+    let indexes: Vec<_> = results.iter().map(|entry| entry["index"].as_u64().unwrap()).collect();
+    assert_eq!(indexes, vec![0, 1, 2, 3]);
+    assert_eq!(results[0]["status"], "ok");
+    assert_eq!(results[0]["result"]["actor_id"], "hero");
+    assert_eq!(results[1]["status"], "error");
+    assert_eq!(results[1]["error"]["code"], "NOT_FOUND");
+    assert_eq!(results[3]["result"]["actor_id"], "villain");
+}
+
+#[tokio::test]
+async fn batch_grant_succeeds_partially() {
+    let body = serde_json::json!({ "grants": [
+        { "actor_id": "hero", "item_id": "sword", "quantity": 1 },
+        { "actor_id": "hero", "item_id": "cursed", "quantity": 1 },
+    ]});
+    let (status, body) = post(app(), "/items:batchGrant", body).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["results"][0]["result"]["instance_ids"][0], "sword-1");
+    assert_eq!(body["results"][1]["error"]["code"], "GAME_RULE_VIOLATION");
+
+    let body = serde_json::json!({ "grants": [{ "actor_id": "hero", "item_id": "shield", "quantity": 2 }] });
+    let (status, body) = post(app(), "/items:batchGrant", body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["succeeded"], 1);
+}
+
+#[tokio::test]
+async fn oversized_or_invalid_batches_are_rejected() {
+    let entries: Vec<_> = (0..101).map(|i| serde_json::json!({ "actor_id": format!("a{}", i) })).collect();
+    let (status, body) = post(app(), "/actors:batchResolve", serde_json::json!({ "entries": entries })).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "entries");
+
+    let body = serde_json::json!({ "grants": [{ "actor_id": "hero", "item_id": "sword", "quantity": 0 }] });
+    let (status, body) = post(app(), "/items:batchGrant", body).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "grants[0].quantity");
+}
+
+#[tokio::test]
+async fn unknown_or_disabled_actions_are_not_found() {
+    let (status, _) = post(app(), "/actors:batchDelete", serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let app = router(BatchState::default());
+    let body = serde_json::json!({ "grants": [{ "actor_id": "hero", "item_id": "sword", "quantity": 1 }] });
+    let (status, body) = post(app, "/items:batchGrant", body).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["code"], "NOT_FOUND");
+}
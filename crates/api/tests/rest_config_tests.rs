@@ -0,0 +1,83 @@
+//! Integration tests for the configuration REST routes.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use actor_core::config::aggregator::ConfigurationAggregatorImpl;
+use actor_core::config::combiner::ConfigurationCombinerImpl;
+use actor_core::config::registry::ConfigurationRegistryImpl;
+use actor_core::config::*;
+use actor_core::ActorCoreResult;
+use api::rest::config::{router, ReloadResponse};
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use tower::ServiceExt;
+
+/// Provider counting how often it was reloaded
+#[derive(Default)]
+struct CountingProvider {
+    reloads: AtomicUsize,
+}
+
+#[async_trait]
+impl ConfigurationProvider for CountingProvider {
+    fn provider_id(&self) -> &str {
+        "counting_provider"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    fn get_supported_categories(&self) -> Vec<String> {
+        vec!["flags".to_string()]
+    }
+
+    async fn get_config_value(&self, _category: &str, _key: &str) -> ActorCoreResult<Option<ConfigurationValue>> {
+        Ok(None)
+    }
+
+    async fn get_category_config(&self, _category: &str) -> ActorCoreResult<HashMap<String, ConfigurationValue>> {
+        Ok(HashMap::new())
+    }
+
+    fn get_merge_rule(&self, _category: &str, _key: &str) -> Option<ConfigurationMergeRule> {
+        None
+    }
+
+    async fn validate_config(&self) -> ActorCoreResult<()> {
+        Ok(())
+    }
+
+    async fn reload(&self) -> ActorCoreResult<()> {
+        self.reloads.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[tokio::test]
+async fn reload_rereads_every_provider() {
+    let registry = Arc::new(ConfigurationRegistryImpl::new());
+    let combiner = Arc::new(ConfigurationCombinerImpl::new());
+    let aggregator = Arc::new(ConfigurationAggregatorImpl::new(registry.clone(), combiner.clone()));
+    let provider = Arc::new(CountingProvider::default());
+    let mut loader = ConfigurationLoader::new(registry.clone(), combiner.clone(), aggregator.clone());
+    loader.add_provider(provider.clone());
+    let manager = Arc::new(ConfigurationManager::new(registry, combiner, aggregator, Arc::new(loader)));
+    manager.initialize().await.unwrap();
+
+    let request = Request::post("/config/reload").body(Body::empty()).unwrap();
+    let response = router(manager).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: ReloadResponse = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body.providers, 1);
+    assert_eq!(provider.reloads.load(Ordering::SeqCst), 1);
+}
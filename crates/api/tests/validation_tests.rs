@@ -0,0 +1,109 @@
+//! Integration tests for the validating extractors.
+
+use api::middleware::validation::{one_of, ValidatedJson, ValidatedQuery, ValidationRejection};
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::routing::{get, post};
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use shared::error::ErrorCode;
+use tower::ServiceExt;
+use validator::{Validate, ValidationError};
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+struct GrantItem {
+    #[validate(length(min = 1, max = 32))]
+    item_id: String,
+    #[validate(range(min = 1, max = 999))]
+    quantity: u32,
+    #[validate(custom(function = "validate_rarity"))]
+    rarity: String,
+}
+
+fn validate_rarity(rarity: &str) -> Result<(), ValidationError> {
+    one_of(rarity, &["common", "rare", "epic"])
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct GrantRequest {
+    #[validate(length(min = 1, max = 2), nested)]
+    items: Vec<GrantItem>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+struct SearchQuery {
+    #[validate(range(min = 1, max = 100))]
+    limit: u32,
+}
+
+fn app() -> Router {
+    Router::new()
+        .route(
+            "/grant",
+            post(|ValidatedJson(request): ValidatedJson<GrantRequest>| async move { request.items.len().to_string() }),
+        )
+        .route(
+            "/search",
+            get(|ValidatedQuery(query): ValidatedQuery<SearchQuery>| async move { query.limit.to_string() }),
+        )
+}
+
+async fn send(request: Request<Body>) -> (StatusCode, Vec<u8>) {
+    let response = app().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, bytes.to_vec())
+}
+
+fn post_json(body: serde_json::Value) -> Request<Body> {
+    Request::post("/grant")
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn valid_requests_reach_the_handler() {
+    let body = serde_json::json!({ "items": [{ "item_id": "sword", "quantity": 1, "rarity": "rare" }] });
+    let (status, bytes) = send(post_json(body)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bytes, b"1");
+
+    let (status, _) = send(Request::get("/search?limit=10").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn rule_violations_are_reported_per_field() {
+    let body = serde_json::json!({ "items": [{ "item_id": "", "quantity": 1000, "rarity": "mythic" }] });
+    let (status, bytes) = send(post_json(body)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let rejection: ValidationRejection = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(rejection.error.code, ErrorCode::ValidationFailed);
+    let fields: Vec<_> = rejection.fields.iter().map(|f| (f.field.as_str(), f.code.as_str())).collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("items[0].item_id", "length"),
+            ("items[0].quantity", "range"),
+            ("items[0].rarity", "one_of"),
+        ]
+    );
+    assert!(rejection.fields[2].params.contains_key("allowed"));
+
+    let (status, _) = send(Request::get("/search?limit=0").body(Body::empty()).unwrap()).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn malformed_bodies_are_bad_requests() {
+    let request = Request::post("/grant")
+        .header("content-type", "application/json")
+        .body(Body::from("{not json"))
+        .unwrap();
+    let (status, bytes) = send(request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["code"], "INVALID_FORMAT");
+}
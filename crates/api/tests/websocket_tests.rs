@@ -0,0 +1,137 @@
+//! Integration tests for the WebSocket session manager.
+
+use api::websocket::{Channel, ClientMessage, ServerMessage, SessionManager, TokenValidator, WebSocketConfig, WsClaims};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use shared::error::ErrorCode;
+use shared::events::topics::{TutorialHint, TutorialHintChanged, TUTORIAL_HINT};
+use shared::events::{EventBus, EventBusExt, InProcessEventBus};
+use std::sync::Arc;
+
+const SECRET: &[u8] = b"test-secret";
+
+fn claims(user: &str) -> WsClaims {
+    WsClaims {
+        sub: user.to_string(),
+        exp: (chrono::Utc::now().timestamp() + 3600) as usize,
+        roles: Vec::new(),
+    }
+}
+
+fn manager(config: WebSocketConfig) -> SessionManager {
+    SessionManager::new(config, TokenValidator::hs256(SECRET))
+}
+
+#[test]
+fn channels_parse_and_serialize() {
+    let channel: Channel = "zone:forest-1".parse().unwrap();
+    assert_eq!(channel, Channel::Zone("forest-1".to_string()));
+    assert_eq!("guild:g1".parse::<Channel>().unwrap(), Channel::Guild("g1".to_string()));
+    assert!("raid:1".parse::<Channel>().is_err());
+    assert!("zone:".parse::<Channel>().is_err());
+
+    let message: ClientMessage = serde_json::from_str(r#"{"type":"subscribe","channel":"party:p1"}"#).unwrap();
+    assert_eq!(
+        message,
+        ClientMessage::Subscribe {
+            channel: Channel::Party("p1".to_string())
+        }
+    );
+}
+
+#[test]
+fn handshake_requires_valid_token() {
+    let manager = manager(WebSocketConfig::default());
+    let token = encode(&Header::default(), &claims("user-1"), &EncodingKey::from_secret(SECRET)).unwrap();
+
+    assert_eq!(manager.authenticate(Some(&token)).unwrap().sub, "user-1");
+    assert_eq!(manager.authenticate(None).unwrap_err().error.code, ErrorCode::Unauthenticated);
+    assert!(manager.authenticate(Some("garbage")).is_err());
+    assert_eq!(manager.metrics().snapshot().auth_failures, 2);
+}
+
+#[tokio::test]
+async fn broadcast_reaches_subscribers_only() {
+    let manager = manager(WebSocketConfig::default());
+    let zone = Channel::Zone("z1".to_string());
+    let (alice, mut alice_rx) = manager.register(claims("alice"));
+    let (_bob, mut bob_rx) = manager.register(claims("bob"));
+
+    manager.subscribe(&alice, zone.clone()).await.unwrap();
+    assert_eq!(manager.broadcast(&zone, serde_json::json!({ "hp": 10 })), 1);
+    assert!(matches!(alice_rx.try_recv().unwrap(), ServerMessage::Event { .. }));
+    assert!(bob_rx.try_recv().is_err());
+
+    manager.unsubscribe(&alice, &zone);
+    assert_eq!(manager.subscriber_count(&zone), 0);
+    assert_eq!(manager.broadcast(&zone, serde_json::json!(null)), 0);
+}
+
+#[tokio::test]
+async fn slow_clients_are_disconnected() {
+    let config = WebSocketConfig {
+        send_buffer: 1,
+        max_dropped_messages: 2,
+        ..WebSocketConfig::default()
+    };
+    let manager = manager(config);
+    let zone = Channel::Zone("z1".to_string());
+    let (session, _rx) = manager.register(claims("slow"));
+    manager.subscribe(&session, zone.clone()).await.unwrap();
+
+    assert_eq!(manager.broadcast(&zone, serde_json::json!(1)), 1);
+    assert_eq!(manager.broadcast(&zone, serde_json::json!(2)), 0);
+    assert!(!session.is_closed());
+    assert_eq!(manager.broadcast(&zone, serde_json::json!(3)), 0);
+    assert!(session.is_closed());
+
+    let metrics = manager.metrics().snapshot();
+    assert_eq!(metrics.connected_clients, 0);
+    assert_eq!(metrics.messages_dropped, 2);
+    assert_eq!(metrics.slow_client_disconnects, 1);
+    assert_eq!(manager.subscriber_count(&zone), 0);
+}
+
+#[tokio::test]
+async fn subscriptions_are_limited() {
+    let config = WebSocketConfig {
+        max_subscriptions: 1,
+        ..WebSocketConfig::default()
+    };
+    let manager = manager(config);
+    let (session, _rx) = manager.register(claims("user"));
+
+    manager.subscribe(&session, Channel::Zone("a".to_string())).await.unwrap();
+    let error = manager
+        .subscribe(&session, Channel::Zone("b".to_string()))
+        .await
+        .unwrap_err();
+    assert_eq!(error.error.code, ErrorCode::RateLimited);
+
+    manager.unregister(session.id());
+    session.closed().await;
+    assert_eq!(manager.session_count(), 0);
+}
+
+#[tokio::test]
+async fn tutorial_hints_reach_every_session_of_their_user() {
+    let manager = Arc::new(manager(WebSocketConfig::default()));
+    let (_phone, mut phone_rx) = manager.register(claims("alice"));
+    let (_desktop, mut desktop_rx) = manager.register(claims("alice"));
+    let (_bob, mut bob_rx) = manager.register(claims("bob"));
+    let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+    manager.clone().forward_tutorial_hints(bus.clone()).await.unwrap();
+
+    let changed = TutorialHintChanged {
+        user_id: "alice".to_string(),
+        actor_id: "hero".to_string(),
+        tutorial_id: "first_steps".to_string(),
+        hint: Some(TutorialHint { step_id: "hunt".to_string(), text: "Hunt a wolf".to_string(), anchor: None }),
+        at: chrono::Utc::now(),
+    };
+    bus.publish_message("event-service", &TUTORIAL_HINT, &changed).await.unwrap();
+
+    let expected = ServerMessage::Tutorial { update: changed };
+    assert_eq!(phone_rx.recv().await.unwrap(), expected);
+    assert_eq!(desktop_rx.recv().await.unwrap(), expected);
+    assert!(bob_rx.try_recv().is_err());
+}
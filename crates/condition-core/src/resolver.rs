@@ -142,6 +142,14 @@ impl ConditionResolver {
 
 #[async_trait::async_trait]
 impl ConditionResolverTrait for ConditionResolver {
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "condition_core.resolve_condition",
+            skip_all,
+            fields(condition_id = %condition_config.condition_id, function = %condition_config.function_name)
+        )
+    )]
     async fn resolve_condition(
         &self,
         condition_config: &ConditionConfig,
@@ -150,6 +158,10 @@ impl ConditionResolverTrait for ConditionResolver {
         self.evaluate_single_condition(condition_config, context).await
     }
 
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "condition_core.resolve_conditions", skip_all, fields(conditions = condition_configs.len()))
+    )]
     async fn resolve_conditions(
         &self,
         condition_configs: &[ConditionConfig],
@@ -165,6 +177,14 @@ impl ConditionResolverTrait for ConditionResolver {
         Ok(results)
     }
 
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "condition_core.resolve_condition_chain",
+            skip_all,
+            fields(chain_id = %chain_config.chain_id, conditions = chain_config.conditions.len())
+        )
+    )]
     async fn resolve_condition_chain(
         &self,
         chain_config: &ConditionChainConfig,
@@ -5,7 +5,10 @@
 //! - Combat-Core adapter: exposes read-only element combat stats.
 //! - Condition-Core adapter: exposes standardized condition queries.
 //! - Actor-Core adapter: minimal hooks to map element IDs to indices.
+//! - Qi resources: per-element qi registered as actor-core resources (`qi_resources`).
 //!
+pub mod qi_resources;
+
 use std::sync::Arc;
 use crate::unified_registry::UnifiedElementRegistry;
 use crate::core::elemental_system::ElementalSystem;
@@ -0,0 +1,207 @@
+//! # Qi Resources
+//!
+//! Bridge exposing per-element qi as actor-core resources.
+//!
+//! The `ElementalSystem` stays the single store of qi amounts. The bridge:
+//! - derives each element's qi capacity from its mastery realm and its regeneration rate
+//!   from the derived `resource_regeneration` stat (`refresh`);
+//! - registers one `<element>_qi` resource per element with actor-core's resource registry;
+//! - mirrors amounts, caps and rates onto the actor, where regeneration and UI resource bars
+//!   read them (`sync_actor`);
+//! - applies regeneration ticks and skill costs back to the elemental system.
+
+use std::sync::Arc;
+
+use actor_core::runtime_registry::{RegenType, ResourceDefinition, ResourceRegistry, ResourceType};
+use actor_core::subsystems::resource_management::resource_regeneration::{
+    RegenerationCurve, RegenerationModifier, RegenerationRule,
+};
+use actor_core::subsystems::RegenerationDelta;
+use actor_core::types::Actor;
+use actor_core::ActorCoreResult;
+
+use crate::core::elemental_data::{ElementMasteryRealm, ElementalSystemData, MAX_ELEMENTS};
+use crate::core::elemental_system::ElementalSystem;
+use crate::unified_registry::UnifiedElementRegistry;
+use crate::{ElementCoreError, ElementCoreResult};
+
+/// Resource category of elemental qi
+pub const QI_RESOURCE_CATEGORY: &str = "elemental_qi";
+
+/// Subsystem owning the qi resources
+pub const QI_SUBSYSTEM_ID: &str = "element_core";
+
+/// Resource ID of an element's qi, e.g. `fire_qi`
+pub fn qi_resource_id(element_id: &str) -> String {
+    format!("{}_qi", element_id)
+}
+
+/// Qi resource tuning
+#[derive(Debug, Clone)]
+pub struct QiResourceConfig {
+    /// Qi capacity at the first mastery realm, scaled by the realm multiplier
+    pub base_capacity: f64,
+    /// Qi per second at a `resource_regeneration` of 1.0
+    pub base_regeneration_rate: f64,
+}
+
+impl Default for QiResourceConfig {
+    fn default() -> Self {
+        Self { base_capacity: 100.0, base_regeneration_rate: 1.0 }
+    }
+}
+
+/// Bridge between element qi and actor-core resource management
+pub struct QiResourceBridge {
+    pub registry: Arc<UnifiedElementRegistry>,
+    pub config: QiResourceConfig,
+}
+
+impl QiResourceBridge {
+    pub fn new(registry: Arc<UnifiedElementRegistry>) -> Self { Self::with_config(registry, QiResourceConfig::default()) }
+
+    pub fn with_config(registry: Arc<UnifiedElementRegistry>, config: QiResourceConfig) -> Self { Self { registry, config } }
+
+    /// Qi capacity of an element, from its mastery realm
+    pub fn qi_capacity(&self, data: &ElementalSystemData, index: usize) -> f64 {
+        let realm = ElementMasteryRealm::from_mastery(data.element_mastery_levels[index]);
+        self.config.base_capacity * realm.get_realm_multiplier()
+    }
+
+    /// Qi regenerated per second for an element, from the derived regeneration stat
+    pub fn qi_regeneration_rate(&self, data: &ElementalSystemData, index: usize) -> f64 {
+        self.config.base_regeneration_rate * data.resource_regeneration[index]
+    }
+
+    /// Recompute capacities and regeneration rates, e.g. after mastery changes
+    pub fn refresh(&self, system: &mut ElementalSystem) {
+        for (_, index) in self.elements() {
+            let capacity = self.qi_capacity(system.get_data(), index);
+            let rate = self.qi_regeneration_rate(system.get_data(), index);
+            let data = system.get_data_mut();
+            data.element_qi_capacities[index] = capacity;
+            data.element_qi_regeneration_rates[index] = rate;
+            data.element_qi_amounts[index] = data.element_qi_amounts[index].min(capacity);
+        }
+    }
+
+    /// Register one qi resource per element; returns how many were registered
+    pub async fn register_resources(&self, resources: &dyn ResourceRegistry) -> ActorCoreResult<usize> {
+        let elements = self.elements();
+        for (element_id, _) in &elements {
+            resources.register_resource(self.resource_definition(element_id)).await?;
+        }
+        Ok(elements.len())
+    }
+
+    /// Resource definition of an element's qi; per-actor caps and rates come from `sync_actor`
+    pub fn resource_definition(&self, element_id: &str) -> ResourceDefinition {
+        let name = self.registry.get_element(element_id).map(|element| element.name).unwrap_or_else(|| element_id.to_string());
+        let now = chrono::Utc::now();
+        ResourceDefinition {
+            id: qi_resource_id(element_id),
+            name: format!("{} Qi", name),
+            description: Some(format!("{} qi, capped by mastery realm", name)),
+            category: QI_RESOURCE_CATEGORY.to_string(),
+            resource_type: ResourceType::Custom("qi".to_string()),
+            base_value: 0.0,
+            min_value: 0.0,
+            max_value: self.config.base_capacity * ElementMasteryRealm::ElementalAscension.get_realm_multiplier(),
+            regen_rate: self.config.base_regeneration_rate,
+            regen_type: RegenType::Passive,
+            dependencies: Vec::new(),
+            tags: vec!["qi".to_string(), element_id.to_string()],
+            subsystem_id: QI_SUBSYSTEM_ID.to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Regeneration rules driving qi at the rate `sync_actor` stores on each actor
+    pub fn regeneration_rules(&self) -> Vec<RegenerationRule> {
+        self.elements()
+            .into_iter()
+            .map(|(element_id, _)| {
+                let resource_id = qi_resource_id(&element_id);
+                RegenerationRule {
+                    formula: format!("base_rate * {}_regen", resource_id),
+                    modifiers: vec![RegenerationModifier::StatScaled(format!("{}_regen", resource_id))],
+                    resource_name: resource_id,
+                    base_rate: 1.0,
+                    curve: RegenerationCurve::Flat,
+                    conditions: Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Mirror qi amounts, caps and rates onto the actor
+    pub fn sync_actor(&self, system: &ElementalSystem, actor: &mut Actor) {
+        let data = system.get_data();
+        for (element_id, index) in self.elements() {
+            let resource_id = qi_resource_id(&element_id);
+            let amount = data.element_qi_amounts[index];
+            actor.custom_resources.insert(resource_id.clone(), amount);
+            actor.data.insert(resource_id.clone(), amount.into());
+            actor.data.insert(format!("{}_max", resource_id), data.element_qi_capacities[index].into());
+            actor.data.insert(format!("{}_regen", resource_id), data.element_qi_regeneration_rates[index].into());
+        }
+    }
+
+    /// Apply an actor's regeneration deltas to its qi; returns how many applied
+    pub fn apply_regeneration(&self, system: &mut ElementalSystem, actor_id: &str, deltas: &[RegenerationDelta]) -> usize {
+        let mut applied = 0;
+        for delta in deltas.iter().filter(|delta| delta.actor_id == actor_id) {
+            let Some(index) = self.resource_index(&delta.resource_name) else { continue };
+            let current = system.get_data().element_qi_amounts[index];
+            if system.set_element_qi_amount(index, (current + delta.amount).max(0.0)) {
+                applied += 1;
+            }
+        }
+        applied
+    }
+
+    /// Spend qi on a skill; returns what is left
+    pub fn spend_qi(&self, system: &mut ElementalSystem, element_id: &str, cost: f64) -> ElementCoreResult<f64> {
+        let index = self.element_index(element_id)?;
+        if !cost.is_finite() || cost < 0.0 {
+            return Err(ElementCoreError::Validation { message: format!("Qi cost must be a non-negative number, got {}", cost) });
+        }
+        let available = system.get_data().element_qi_amounts[index];
+        if available < cost {
+            return Err(ElementCoreError::Validation {
+                message: format!("Not enough {} qi: {} needed, {} available", element_id, cost, available),
+            });
+        }
+        system.get_data_mut().element_qi_amounts[index] = available - cost;
+        Ok(available - cost)
+    }
+
+    /// Registered elements with their indices, ordered by ID
+    fn elements(&self) -> Vec<(String, usize)> {
+        let mut elements: Vec<(String, usize)> = self
+            .registry
+            .get_element_ids()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|element_id| {
+                let index = self.registry.get_element_index(&element_id).ok().flatten()?;
+                (index < MAX_ELEMENTS).then_some((element_id, index))
+            })
+            .collect();
+        elements.sort();
+        elements
+    }
+
+    fn element_index(&self, element_id: &str) -> ElementCoreResult<usize> {
+        self.registry
+            .get_element_index(element_id)?
+            .filter(|index| *index < MAX_ELEMENTS)
+            .ok_or_else(|| ElementCoreError::ElementNotFound { element_id: element_id.to_string() })
+    }
+
+    fn resource_index(&self, resource_id: &str) -> Option<usize> {
+        let element_id = resource_id.strip_suffix("_qi")?;
+        self.element_index(element_id).ok()
+    }
+}
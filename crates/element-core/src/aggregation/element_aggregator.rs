@@ -226,6 +226,14 @@ impl ElementAggregator {
     }
     
     /// Aggregate contributions from all registered systems
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(
+            name = "element_core.aggregate",
+            skip_all,
+            fields(actor_id = %actor.id, element_type = %element_type)
+        )
+    )]
     pub async fn aggregate_contributions(
         &self,
         actor: &Actor,
@@ -259,6 +267,10 @@ impl ElementAggregator {
     }
     
     /// Collect contributions from all registered systems
+    #[cfg_attr(
+        feature = "otel",
+        tracing::instrument(name = "element_core.collect_contributions", skip_all, fields(element_type = %element_type))
+    )]
     async fn collect_contributions(
         &self,
         actor: &Actor,
@@ -16,6 +16,7 @@ use crate::unified_registry::unified_element_registry::UnifiedElementRegistry;
 use crate::unified_registry::element_definition as unified_def;
 use crate::unified_registry::element_category::ElementCategory;
 use crate::unified_registry::element_interaction::{ElementInteraction, InteractionType};
+use crate::unified_registry::status_interaction::StatusInteractionMatrix;
 
 /// Element configuration loader
 pub struct ElementConfigLoader {
@@ -63,10 +64,16 @@ impl ElementConfigLoader {
         let content = fs::read_to_string(file_path)
             .map_err(|e| format!("Failed to read file {}: {}", file_path.display(), e))?;
 
-        let config: ElementConfig = serde_yaml::from_str(&content)
-            .map_err(|e| format!("Failed to parse YAML from {}: {}", file_path.display(), e))?;
+        Self::parse_element_config(&content)
+            .map_err(|e| format!("Failed to parse YAML from {}: {}", file_path.display(), e))
+    }
 
-        Ok(config)
+    /// Parse an element configuration from YAML text
+    ///
+    /// Lets callers such as the CMS check submitted configs before they are written
+    /// to the config directory.
+    pub fn parse_element_config(yaml: &str) -> Result<ElementConfig, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
     }
 
     /// Populate a unified registry from all YAML element configs and central interactions config
@@ -152,6 +159,14 @@ impl ElementConfigLoader {
                     }
                 }
             }
+
+            // Status interactions sit next to the element interactions; unlike those, an
+            // invalid matrix fails the load rather than leaving statuses undefined
+            let status_path = grand.join("configs").join("status_interactions.yaml");
+            if status_path.exists() {
+                let content = fs::read_to_string(&status_path)?;
+                unified.set_status_interactions(StatusInteractionMatrix::from_yaml(&content)?);
+            }
         }
 
         Ok(())
@@ -269,4 +284,10 @@ mod tests {
         let loader = ElementConfigLoader::default();
         assert_eq!(loader.config_dir, "docs/element-core/elements/configs");
     }
+
+    #[test]
+    fn test_parse_element_config_errors() {
+        let error = ElementConfigLoader::parse_element_config("version: 1\nelement:\n  id: fire\n").unwrap_err();
+        assert!(error.to_string().contains("missing field"));
+    }
 }
@@ -7,6 +7,7 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use crate::{ElementCoreResult, ElementCoreError};
 use crate::unified_registry::{ElementDefinition, ElementCategory, ElementProperties, DerivedStatConfig, StatusEffectConfig, ElementReferences, ElementAliases};
+use crate::unified_registry::StatusInteractionMatrix;
 
 /// YAML configuration loader for element configurations
 /// 
@@ -115,7 +116,17 @@ impl YamlConfigLoader {
         let content = std::fs::read_to_string(&file_path)
             .map_err(|e| ElementCoreError::Io(e))?;
         
-        let config: ElementConfig = serde_yaml::from_str(&content)
+        let config = self.parse_element_config(&content)?;
+        
+        // Cache the configuration
+        self.config_cache.insert(element_id.to_string(), config.clone());
+        
+        Ok(config)
+    }
+    
+    /// Parse and validate an element configuration from YAML text
+    pub fn parse_element_config(&self, content: &str) -> ElementCoreResult<ElementConfig> {
+        let config: ElementConfig = serde_yaml::from_str(content)
             .map_err(|e| ElementCoreError::Config { 
                 message: format!("Failed to parse YAML: {}", e)
             })?;
@@ -123,9 +134,6 @@ impl YamlConfigLoader {
         // Validate configuration
         self.validate_config(&config)?;
         
-        // Cache the configuration
-        self.config_cache.insert(element_id.to_string(), config.clone());
-        
         Ok(config)
     }
     
@@ -182,6 +190,22 @@ impl YamlConfigLoader {
         Ok(config)
     }
     
+    /// Load the status interaction matrix, validated for conflicting rules and conversion cycles
+    pub fn load_status_interaction_config(&self) -> ElementCoreResult<StatusInteractionMatrix> {
+        let file_path = self.config_dir.join("status_interactions.yaml");
+        
+        if !file_path.exists() {
+            return Err(ElementCoreError::Config { 
+                message: format!("Status interaction configuration file not found: {}", file_path.display())
+            });
+        }
+        
+        let content = std::fs::read_to_string(&file_path)
+            .map_err(|e| ElementCoreError::Io(e))?;
+        
+        StatusInteractionMatrix::from_yaml(&content)
+    }
+    
     /// Load probability configuration
     pub fn load_probability_config(&self) -> ElementCoreResult<ProbabilityConfig> {
         let file_path = self.config_dir.join("probability_config.yaml");
@@ -413,6 +437,22 @@ pub struct ProbabilityConfig {
     pub custom_functions: HashMap<String, CustomFunctionConfig>,
 }
 
+impl ProbabilityConfig {
+    /// Probability of an element's sigmoid at `x`, e.g. a mastery surplus
+    ///
+    /// `x` is divided by the element's scaling factor; elements without a configuration
+    /// use the default steepness and midpoint, unscaled.
+    pub fn probability(&self, element_id: &str, x: f64) -> f64 {
+        let (steepness, midpoint, scaling_factor) = match self.sigmoid.element_configs.get(element_id) {
+            Some(config) => (config.steepness, config.midpoint, config.scaling_factor),
+            None => (self.sigmoid.default_steepness, self.sigmoid.default_midpoint, 1.0),
+        };
+        let scaling_factor = if scaling_factor > 0.0 { scaling_factor } else { 1.0 };
+        let probability = 1.0 / (1.0 + (-steepness * (x / scaling_factor - midpoint)).exp());
+        if probability.is_finite() { probability.clamp(0.0, 1.0) } else { 0.0 }
+    }
+}
+
 /// Sigmoid configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SigmoidConfig {
@@ -8,6 +8,7 @@ use crate::config::elemental_config_loader::ElementConfigLoader;
 use crate::{ElementalParams, common_traits::Validatable};
 use crate::common_traits::ElementSetter;
 use crate::unified_registry::element_interaction::{ElementInteraction, InteractionType};
+use crate::unified_registry::status_interaction::StatusInteractionMatrix;
 use std::sync::Arc;
 
 /// Elemental factory for creating elemental system instances
@@ -116,6 +117,13 @@ impl ElementalFactory {
                     }
                 }
             }
+
+            // Status reactions, exclusions and amplification combos loaded alongside
+            let status_path = interactions_dir.join("status_interactions.yaml");
+            if status_path.exists() {
+                let content = std::fs::read_to_string(&status_path)?;
+                unified.set_status_interactions(StatusInteractionMatrix::from_yaml(&content)?);
+            }
         }
 
         Ok(Self::new(unified))
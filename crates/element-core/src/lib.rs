@@ -36,6 +36,7 @@
 //! - **Generating, Overcoming, Neutral, Same, Opposite** interaction types
 //! - **Matrix-based Lookups**: O(1) interaction factor retrieval
 //! - **Configurable Bonuses**: Custom interaction multipliers
+//! - **Status Interactions**: Reactions, exclusions and amplification combos between status effects
 //! 
 //! ### 🛠️ **Developer Experience**
 //! - **Comprehensive Validation**: All data structures validate their integrity
@@ -120,7 +121,8 @@ pub use unified_registry::{
     UnifiedElementRegistry, ElementCategory, SystemRegistration,
     SystemCapability, SystemHealth, ElementPlugin, ElementInteraction,
     RegistryConfig, RegistryMetrics, ElementProperties, DerivedStatConfig,
-    StatusEffectConfig, SpreadRules, EnvironmentMod
+    StatusEffectConfig, SpreadRules, EnvironmentMod, StatusInteractionConfig,
+    StatusInteractionMatrix, StatusApplication, BonusDamage
 };
 
 // Re-export from aggregation module
@@ -136,7 +138,7 @@ pub use common_traits::{
 };
 
 /// Parameters for configuring elemental system when creating an actor
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ElementalParams {
     /// Primary element specialization
     pub primary_element: String,
@@ -39,6 +39,12 @@
 //! - **Configurable Bonuses**: Custom interaction multipliers
 //! - **Performance**: Optimized for game loop access patterns
 //! 
+//! ### `StatusInteractionMatrix`
+//! - **On-apply Reactions**: Freeze removes burn, burn on freeze converts into wet
+//! - **Mutual Exclusions**: Statuses a target holds at most one of
+//! - **Amplification Combos**: Bonus damage events, e.g. shock on a wet target
+//! - **Validation**: Duplicate pairs and conversion cycles are rejected
+//! 
 //! ### `RegistryConfig`
 //! - **Configuration Management**: Registry-wide settings
 //! - **Performance Tuning**: Cache and performance configurations
@@ -73,6 +79,7 @@ pub mod system_registration;
 pub mod element_category;
 pub mod element_plugin;
 pub mod element_interaction;
+pub mod status_interaction;
 pub mod registry_config;
 pub mod registry_metrics;
 
@@ -82,5 +89,6 @@ pub use system_registration::*;
 pub use element_category::*;
 pub use element_plugin::*;
 pub use element_interaction::*;
+pub use status_interaction::*;
 pub use registry_config::*;
 pub use registry_metrics::*;
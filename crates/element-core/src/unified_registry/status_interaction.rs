@@ -0,0 +1,420 @@
+//! # Status Interaction
+//!
+//! This module defines how status effects interact when one is applied to a target that
+//! already holds others: on-apply reactions (freeze removes burn), mutual exclusions and
+//! amplification combos (wet amplifies shock) that raise bonus damage events.
+//!
+//! The matrix is loaded from `status_interactions.yaml` next to `interaction_config.yaml`.
+
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::{ElementCoreResult, ElementCoreError};
+
+/// Status interaction configuration loaded from YAML
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StatusInteractionConfig {
+    /// Configuration version
+    pub version: u32,
+
+    /// Reactions when a status is applied on top of another
+    #[serde(default)]
+    pub reactions: Vec<StatusReaction>,
+
+    /// Groups of statuses a target holds at most one of
+    #[serde(default)]
+    pub exclusions: Vec<StatusExclusion>,
+
+    /// Combos dealing bonus damage
+    #[serde(default)]
+    pub amplifications: Vec<StatusAmplification>,
+}
+
+/// Reaction to a status being applied while another is present
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusReaction {
+    /// Reaction identifier
+    pub id: String,
+
+    /// Status being applied
+    pub applied: String,
+
+    /// Status already on the target
+    pub present: String,
+
+    /// What happens
+    pub effect: ReactionEffect,
+}
+
+/// Outcome of a status reaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReactionEffect {
+    /// The present status is removed, e.g. freeze removes burn
+    Remove,
+
+    /// The applied status does not take, e.g. burning targets cannot be frozen
+    Immune,
+
+    /// Both statuses are removed and `into` is applied instead, e.g. burn on freeze leaves wet
+    Convert { into: String },
+}
+
+/// Statuses that exclude each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusExclusion {
+    /// Exclusion identifier
+    pub id: String,
+
+    /// Statuses a target holds at most one of
+    pub statuses: Vec<String>,
+
+    /// Whether a newly applied status replaces the held one or is rejected
+    #[serde(default)]
+    pub policy: ExclusionPolicy,
+}
+
+/// How a mutual exclusion is resolved
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExclusionPolicy {
+    /// The applied status replaces the held one
+    #[default]
+    Replace,
+
+    /// The applied status does not take while another one is held
+    Reject,
+}
+
+/// Amplification combo, e.g. shock on a wet target
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatusAmplification {
+    /// Amplification identifier
+    pub id: String,
+
+    /// Status being applied
+    pub applied: String,
+
+    /// Status already on the target
+    pub present: String,
+
+    /// Bonus damage as a multiplier of the applied status' damage
+    pub bonus_multiplier: f64,
+
+    /// Element of the bonus damage, the applied status' when unset
+    #[serde(default)]
+    pub element: Option<String>,
+
+    /// Whether the present status is used up by the combo
+    #[serde(default)]
+    pub consumes: bool,
+}
+
+/// Bonus damage event raised by an amplification combo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BonusDamage {
+    /// Amplification that triggered
+    pub amplification_id: String,
+
+    /// Status being applied
+    pub applied: String,
+
+    /// Status that amplified it
+    pub present: String,
+
+    /// Bonus damage multiplier
+    pub multiplier: f64,
+
+    /// Element of the bonus damage
+    pub element: Option<String>,
+}
+
+/// Result of applying a status to a target
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatusApplication {
+    /// Statuses added to the target, in order
+    pub added: Vec<String>,
+
+    /// Statuses removed from the target
+    pub removed: Vec<String>,
+
+    /// Statuses that did not take
+    pub resisted: Vec<String>,
+
+    /// Bonus damage events to deal
+    pub bonus_damage: Vec<BonusDamage>,
+
+    /// Statuses held afterwards
+    pub held: Vec<String>,
+}
+
+/// Status interaction matrix
+///
+/// Built from a validated [`StatusInteractionConfig`]: rule identifiers are unique, each
+/// status pair has at most one reaction and one amplification, and conversions never lead
+/// back to a status they started from.
+#[derive(Debug, Clone, Default)]
+pub struct StatusInteractionMatrix {
+    /// Reactions by (applied, present)
+    reactions: HashMap<(String, String), StatusReaction>,
+
+    /// Amplifications by (applied, present)
+    amplifications: HashMap<(String, String), StatusAmplification>,
+
+    /// Exclusion groups
+    exclusions: Vec<StatusExclusion>,
+
+    /// Exclusion group of each status
+    exclusion_index: HashMap<String, usize>,
+}
+
+impl StatusInteractionMatrix {
+    /// Build and validate a status interaction matrix
+    pub fn new(config: StatusInteractionConfig) -> ElementCoreResult<Self> {
+        let mut matrix = Self::default();
+        let mut ids = HashSet::new();
+        let mut check_id = |id: &str| {
+            if id.is_empty() {
+                return Err(invalid("Status interaction ID cannot be empty".to_string()));
+            }
+            if !ids.insert(id.to_string()) {
+                return Err(invalid(format!("Status interaction '{}' is defined twice", id)));
+            }
+            Ok(())
+        };
+
+        for reaction in config.reactions {
+            check_id(&reaction.id)?;
+            check_pair(&reaction.id, &reaction.applied, &reaction.present)?;
+            if let ReactionEffect::Convert { into } = &reaction.effect {
+                if into.is_empty() {
+                    return Err(invalid(format!("Reaction '{}' converts into an empty status", reaction.id)));
+                }
+            }
+            let key = (reaction.applied.clone(), reaction.present.clone());
+            if let Some(existing) = matrix.reactions.get(&key) {
+                return Err(invalid(format!(
+                    "Reactions '{}' and '{}' both apply {} on {}",
+                    existing.id, reaction.id, key.0, key.1
+                )));
+            }
+            matrix.reactions.insert(key, reaction);
+        }
+
+        for amplification in config.amplifications {
+            check_id(&amplification.id)?;
+            check_pair(&amplification.id, &amplification.applied, &amplification.present)?;
+            if !amplification.bonus_multiplier.is_finite() || amplification.bonus_multiplier <= 0.0 {
+                return Err(invalid(format!(
+                    "Amplification '{}' bonus multiplier must be positive",
+                    amplification.id
+                )));
+            }
+            let key = (amplification.applied.clone(), amplification.present.clone());
+            if let Some(existing) = matrix.amplifications.get(&key) {
+                return Err(invalid(format!(
+                    "Amplifications '{}' and '{}' both apply {} on {}",
+                    existing.id, amplification.id, key.0, key.1
+                )));
+            }
+            if matches!(matrix.reactions.get(&key), Some(StatusReaction { effect: ReactionEffect::Immune, .. })) {
+                return Err(invalid(format!(
+                    "Amplification '{}' can never trigger: {} is resisted on {}",
+                    amplification.id, key.0, key.1
+                )));
+            }
+            matrix.amplifications.insert(key, amplification);
+        }
+
+        for exclusion in config.exclusions {
+            check_id(&exclusion.id)?;
+            let distinct: HashSet<&String> = exclusion.statuses.iter().collect();
+            if distinct.len() < 2 || distinct.len() != exclusion.statuses.len() {
+                return Err(invalid(format!(
+                    "Exclusion '{}' needs at least two distinct statuses",
+                    exclusion.id
+                )));
+            }
+            for status in &exclusion.statuses {
+                if let Some(&group) = matrix.exclusion_index.get(status) {
+                    return Err(invalid(format!(
+                        "Status '{}' is in both exclusions '{}' and '{}'",
+                        status, matrix.exclusions[group].id, exclusion.id
+                    )));
+                }
+                matrix.exclusion_index.insert(status.clone(), matrix.exclusions.len());
+            }
+            matrix.exclusions.push(exclusion);
+        }
+
+        matrix.check_conversion_cycles()?;
+        Ok(matrix)
+    }
+
+    /// Parse and validate a status interaction matrix from YAML text
+    pub fn from_yaml(content: &str) -> ElementCoreResult<Self> {
+        let config: StatusInteractionConfig = serde_yaml::from_str(content)
+            .map_err(|e| ElementCoreError::Config {
+                message: format!("Failed to parse YAML: {}", e)
+            })?;
+        Self::new(config)
+    }
+
+    /// Get the reaction to `applied` on a target holding `present`
+    pub fn get_reaction(&self, applied: &str, present: &str) -> Option<&StatusReaction> {
+        self.reactions.get(&(applied.to_string(), present.to_string()))
+    }
+
+    /// Get the amplification of `applied` on a target holding `present`
+    pub fn get_amplification(&self, applied: &str, present: &str) -> Option<&StatusAmplification> {
+        self.amplifications.get(&(applied.to_string(), present.to_string()))
+    }
+
+    /// Get the exclusion group a status belongs to
+    pub fn get_exclusion(&self, status: &str) -> Option<&StatusExclusion> {
+        self.exclusion_index.get(status).map(|&group| &self.exclusions[group])
+    }
+
+    /// Check if the matrix defines no interactions
+    pub fn is_empty(&self) -> bool {
+        self.reactions.is_empty() && self.amplifications.is_empty() && self.exclusions.is_empty()
+    }
+
+    /// Resolve applying a status to a target holding `present`
+    ///
+    /// Resistances are checked first, then amplifications raise their bonus damage,
+    /// reactions remove or convert the held statuses and finally the applied status
+    /// replaces the members of its exclusion group.
+    pub fn apply(&self, applied: &str, present: &[String]) -> StatusApplication {
+        let mut result = StatusApplication { held: present.to_vec(), ..Default::default() };
+        self.apply_status(applied, &mut result);
+        result
+    }
+
+    fn apply_status(&self, applied: &str, result: &mut StatusApplication) {
+        let resisted = result.held.iter().any(|held| {
+            matches!(self.get_reaction(applied, held), Some(StatusReaction { effect: ReactionEffect::Immune, .. }))
+        });
+        let rejected = self.get_exclusion(applied).is_some_and(|exclusion| {
+            exclusion.policy == ExclusionPolicy::Reject
+                && result.held.iter().any(|held| held != applied && exclusion.statuses.contains(held))
+        });
+        if resisted || rejected {
+            result.resisted.push(applied.to_string());
+            return;
+        }
+
+        for present in result.held.clone() {
+            if let Some(amplification) = self.get_amplification(applied, &present) {
+                result.bonus_damage.push(BonusDamage {
+                    amplification_id: amplification.id.clone(),
+                    applied: applied.to_string(),
+                    present: present.clone(),
+                    multiplier: amplification.bonus_multiplier,
+                    element: amplification.element.clone(),
+                });
+                if amplification.consumes {
+                    remove(&present, result);
+                }
+            }
+        }
+
+        for present in result.held.clone() {
+            match self.get_reaction(applied, &present).map(|reaction| &reaction.effect) {
+                Some(ReactionEffect::Remove) => remove(&present, result),
+                Some(ReactionEffect::Convert { into }) => {
+                    remove(&present, result);
+                    self.apply_status(into, result);
+                    return;
+                }
+                Some(ReactionEffect::Immune) | None => {}
+            }
+        }
+
+        if let Some(exclusion) = self.get_exclusion(applied) {
+            for status in &exclusion.statuses {
+                if status != applied && result.held.contains(status) {
+                    remove(status, result);
+                }
+            }
+        }
+
+        if !result.held.iter().any(|held| held == applied) {
+            result.held.push(applied.to_string());
+            result.added.push(applied.to_string());
+        }
+    }
+
+    /// Reject conversions that can lead back to a status they started from
+    fn check_conversion_cycles(&self) -> ElementCoreResult<()> {
+        let mut edges: HashMap<&str, Vec<&str>> = HashMap::new();
+        for reaction in self.reactions.values() {
+            if let ReactionEffect::Convert { into } = &reaction.effect {
+                edges.entry(reaction.applied.as_str()).or_default().push(into.as_str());
+            }
+        }
+        for targets in edges.values_mut() {
+            targets.sort_unstable();
+        }
+
+        let mut starts: Vec<&str> = edges.keys().copied().collect();
+        starts.sort_unstable();
+        let mut done = HashSet::new();
+        for start in starts {
+            let mut path = Vec::new();
+            if let Some(cycle) = find_cycle(start, &edges, &mut path, &mut done) {
+                return Err(invalid(format!("Status conversions form a cycle: {}", cycle.join(" -> "))));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Depth-first search returning the first cycle reachable from `status`
+fn find_cycle<'a>(
+    status: &'a str,
+    edges: &HashMap<&'a str, Vec<&'a str>>,
+    path: &mut Vec<&'a str>,
+    done: &mut HashSet<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(position) = path.iter().position(|&visited| visited == status) {
+        let mut cycle: Vec<String> = path[position..].iter().map(|s| s.to_string()).collect();
+        cycle.push(status.to_string());
+        return Some(cycle);
+    }
+    if done.contains(status) {
+        return None;
+    }
+    path.push(status);
+    for &next in edges.get(status).into_iter().flatten() {
+        if let Some(cycle) = find_cycle(next, edges, path, done) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    done.insert(status);
+    None
+}
+
+fn remove(status: &str, result: &mut StatusApplication) {
+    result.held.retain(|held| held != status);
+    match result.added.iter().position(|added| added == status) {
+        Some(index) => {
+            result.added.remove(index);
+        }
+        None => result.removed.push(status.to_string()),
+    }
+}
+
+fn check_pair(id: &str, applied: &str, present: &str) -> ElementCoreResult<()> {
+    if applied.is_empty() || present.is_empty() {
+        return Err(invalid(format!("Status interaction '{}' needs both statuses", id)));
+    }
+    if applied == present {
+        return Err(invalid(format!("Status interaction '{}' pairs {} with itself", id, applied)));
+    }
+    Ok(())
+}
+
+fn invalid(message: String) -> ElementCoreError {
+    ElementCoreError::Validation { message }
+}
@@ -15,6 +15,7 @@ use crate::unified_registry::{
 };
 use crate::unified_registry::element_category::ElementalElement;
 use crate::unified_registry::element_interaction::ElementInteraction;
+use crate::unified_registry::status_interaction::StatusInteractionMatrix;
 use crate::common_traits::{ElementGetter, ElementSetter, Validatable, Cacheable, MetricsProvider, Configurable, Serializable, ElementHelper};
 use actor_core::Actor;
 
@@ -47,6 +48,9 @@ pub struct UnifiedElementRegistry {
     /// Interaction matrix
     interaction_matrix: DashMap<String, ElementInteraction>,
     
+    /// Status interaction matrix
+    status_interactions: RwLock<Arc<StatusInteractionMatrix>>,
+    
     /// Configuration
     config: RegistryConfig,
     
@@ -66,6 +70,7 @@ impl UnifiedElementRegistry {
             categories: DashMap::new(),
             plugins: DashMap::new(),
             interaction_matrix: DashMap::new(),
+            status_interactions: RwLock::new(Arc::new(StatusInteractionMatrix::default())),
             config: RegistryConfig::default(),
             metrics: Arc::new(RwLock::new(RegistryMetrics::default())),
         }
@@ -82,6 +87,7 @@ impl UnifiedElementRegistry {
             categories: DashMap::new(),
             plugins: DashMap::new(),
             interaction_matrix: DashMap::new(),
+            status_interactions: RwLock::new(Arc::new(StatusInteractionMatrix::default())),
             config,
             metrics: Arc::new(RwLock::new(RegistryMetrics::default())),
         }
@@ -449,6 +455,17 @@ impl UnifiedElementRegistry {
         self.interaction_matrix.len()
     }
     
+    /// Replace the status interaction matrix
+    pub fn set_status_interactions(&self, matrix: StatusInteractionMatrix) {
+        let mut current = self.status_interactions.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = Arc::new(matrix);
+    }
+    
+    /// Get the status interaction matrix
+    pub fn get_status_interactions(&self) -> Arc<StatusInteractionMatrix> {
+        self.status_interactions.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+    
     /// Get registry configuration
     pub fn get_config(&self) -> &RegistryConfig {
         &self.config
@@ -535,6 +552,7 @@ impl UnifiedElementRegistry {
         self.categories.clear();
         self.plugins.clear();
         self.interaction_matrix.clear();
+        self.set_status_interactions(StatusInteractionMatrix::default());
         
         Ok(())
     }
@@ -0,0 +1,28 @@
+//! # Probability Config Tests
+//!
+//! Tests for the per-element sigmoid probability curve
+
+use element_core::config::yaml_loader::{ElementSigmoidConfig, ProbabilityConfig, SigmoidConfig};
+use std::collections::HashMap;
+
+#[test]
+fn test_probability_follows_the_element_sigmoid() {
+    let mut element_configs = HashMap::new();
+    element_configs.insert("fire".to_string(), ElementSigmoidConfig {
+        steepness: 1.0,
+        midpoint: 0.0,
+        scaling_factor: 100.0,
+    });
+    let config = ProbabilityConfig {
+        version: 1,
+        sigmoid: SigmoidConfig { default_steepness: 2.0, default_midpoint: 0.5, element_configs },
+        custom_functions: HashMap::new(),
+    };
+
+    // Even odds at the midpoint, rising with x
+    assert!((config.probability("water", 0.5) - 0.5).abs() < 1e-9);
+    assert!(config.probability("water", 1.5) > config.probability("water", 0.5));
+    // Fire's x is scaled down by 100
+    assert!((config.probability("fire", 100.0) - 1.0 / (1.0 + (-1.0f64).exp())).abs() < 1e-9);
+    assert_eq!(config.probability("water", f64::NAN), 0.0);
+}
@@ -0,0 +1,101 @@
+//! # Qi Resource Bridge Tests
+//!
+//! Tests for exposing element qi through actor-core resource management
+
+use element_core::adapters::qi_resources::{qi_resource_id, QiResourceBridge, QI_RESOURCE_CATEGORY};
+use element_core::unified_registry::{ElementCategory, ElementDefinition, PhysicalElement, UnifiedElementRegistry};
+use element_core::{ElementCoreError, ElementalSystem};
+use actor_core::runtime_registry::{ResourceRegistry, ResourceRegistryImpl};
+use actor_core::subsystems::RegenerationDelta;
+use actor_core::Actor;
+use std::sync::Arc;
+
+async fn bridge() -> QiResourceBridge {
+    let registry = UnifiedElementRegistry::new();
+    for (id, name, kind) in [("fire", "Fire", PhysicalElement::Fire), ("water", "Water", PhysicalElement::Water)] {
+        let category = ElementCategory::Physical(kind);
+        let element = ElementDefinition::new(id.to_string(), name.to_string(), format!("{} element", name), category);
+        registry.register_element(element).await.unwrap();
+    }
+    QiResourceBridge::new(Arc::new(registry))
+}
+
+fn index(bridge: &QiResourceBridge, element_id: &str) -> usize {
+    bridge.registry.get_element_index(element_id).unwrap().unwrap()
+}
+
+#[tokio::test]
+async fn test_capacity_follows_mastery_realm_and_regen_follows_derived_stat() {
+    let bridge = bridge().await;
+    let fire = index(&bridge, "fire");
+    let mut system = ElementalSystem::new();
+    {
+        let data = system.get_data_mut();
+        data.element_mastery_levels[fire] = 3500.0; // Elemental Harmony
+        data.resource_regeneration[fire] = 2.5;
+        data.element_qi_amounts[fire] = 10_000.0;
+    }
+
+    bridge.refresh(&mut system);
+
+    let data = system.get_data();
+    assert_eq!(data.element_qi_capacities[fire], 500.0);
+    assert_eq!(data.element_qi_regeneration_rates[fire], 2.5);
+    assert_eq!(data.element_qi_amounts[fire], 500.0);
+}
+
+#[tokio::test]
+async fn test_registers_one_resource_per_element() {
+    let bridge = bridge().await;
+    let resources = ResourceRegistryImpl::new();
+
+    assert_eq!(bridge.register_resources(&resources).await.unwrap(), 2);
+
+    let qi = resources.get_resources_by_category(QI_RESOURCE_CATEGORY).await.unwrap();
+    assert_eq!(qi.len(), 2);
+    let fire = resources.get_resource("fire_qi").await.unwrap().unwrap();
+    assert_eq!(fire.name, "Fire Qi");
+    assert_eq!(fire.subsystem_id, "element_core");
+}
+
+#[tokio::test]
+async fn test_actor_and_regeneration_read_the_elemental_system() {
+    let bridge = bridge().await;
+    let water = index(&bridge, "water");
+    let mut system = ElementalSystem::new();
+    system.get_data_mut().resource_regeneration[water] = 3.0;
+    bridge.refresh(&mut system);
+    system.set_element_qi_amount(water, 40.0);
+
+    let mut actor = Actor::new("cultivator".to_string(), "Human".to_string());
+    bridge.sync_actor(&system, &mut actor);
+    assert_eq!(actor.custom_resources.get("water_qi"), Some(&40.0));
+    assert_eq!(actor.data.get("water_qi_max").and_then(|v| v.as_f64()), Some(100.0));
+    assert_eq!(actor.data.get("water_qi_regen").and_then(|v| v.as_f64()), Some(3.0));
+
+    let rules = bridge.regeneration_rules();
+    assert_eq!(rules.len(), 2);
+    assert!(rules.iter().any(|rule| rule.resource_name == qi_resource_id("water")));
+
+    let deltas = vec![
+        RegenerationDelta { actor_id: actor.id.to_string(), resource_name: "water_qi".to_string(), amount: 80.0 },
+        RegenerationDelta { actor_id: "someone_else".to_string(), resource_name: "water_qi".to_string(), amount: 5.0 },
+        RegenerationDelta { actor_id: actor.id.to_string(), resource_name: "hp_current".to_string(), amount: 5.0 },
+    ];
+    assert_eq!(bridge.apply_regeneration(&mut system, &actor.id.to_string(), &deltas), 1);
+    assert_eq!(system.get_data().element_qi_amounts[water], 100.0);
+}
+
+#[tokio::test]
+async fn test_skill_costs_spend_qi() {
+    let bridge = bridge().await;
+    let fire = index(&bridge, "fire");
+    let mut system = ElementalSystem::new();
+    bridge.refresh(&mut system);
+    system.set_element_qi_amount(fire, 30.0);
+
+    assert_eq!(bridge.spend_qi(&mut system, "fire", 20.0).unwrap(), 10.0);
+    assert!(matches!(bridge.spend_qi(&mut system, "fire", 20.0), Err(ElementCoreError::Validation { .. })));
+    assert_eq!(system.get_data().element_qi_amounts[fire], 10.0);
+    assert!(matches!(bridge.spend_qi(&mut system, "void", 1.0), Err(ElementCoreError::ElementNotFound { .. })));
+}
@@ -0,0 +1,189 @@
+//! # Status Interaction Tests
+//!
+//! Tests for status reactions, mutual exclusions and amplification combos
+
+use element_core::unified_registry::{
+    ExclusionPolicy, ReactionEffect, StatusAmplification, StatusExclusion, StatusInteractionConfig,
+    StatusInteractionMatrix, StatusReaction, UnifiedElementRegistry,
+};
+use element_core::ElementCoreError;
+
+fn statuses(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+fn reaction(id: &str, applied: &str, present: &str, effect: ReactionEffect) -> StatusReaction {
+    StatusReaction { id: id.to_string(), applied: applied.to_string(), present: present.to_string(), effect }
+}
+
+fn convert(into: &str) -> ReactionEffect {
+    ReactionEffect::Convert { into: into.to_string() }
+}
+
+fn matrix() -> StatusInteractionMatrix {
+    StatusInteractionMatrix::new(StatusInteractionConfig {
+        version: 1,
+        reactions: vec![
+            reaction("freeze_extinguishes_burn", "frozen", "burning", ReactionEffect::Remove),
+            reaction("burn_thaws_freeze", "burning", "frozen", convert("wet")),
+            reaction("burning_resists_chill", "chilled", "burning", ReactionEffect::Immune),
+        ],
+        exclusions: vec![
+            StatusExclusion {
+                id: "temperature".to_string(),
+                statuses: statuses(&["chilled", "frozen"]),
+                policy: ExclusionPolicy::Replace,
+            },
+            StatusExclusion {
+                id: "crowd_control".to_string(),
+                statuses: statuses(&["stunned", "rooted"]),
+                policy: ExclusionPolicy::Reject,
+            },
+        ],
+        amplifications: vec![StatusAmplification {
+            id: "wet_conducts_shock".to_string(),
+            applied: "shocked".to_string(),
+            present: "wet".to_string(),
+            bonus_multiplier: 0.5,
+            element: Some("lightning".to_string()),
+            consumes: true,
+        }],
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_reactions_remove_convert_and_resist() {
+    let matrix = matrix();
+
+    let frozen = matrix.apply("frozen", &statuses(&["burning", "poisoned"]));
+    assert_eq!(frozen.added, ["frozen"]);
+    assert_eq!(frozen.removed, ["burning"]);
+    assert_eq!(frozen.held, ["poisoned", "frozen"]);
+
+    // Burning a frozen target leaves it wet instead
+    let thawed = matrix.apply("burning", &statuses(&["frozen"]));
+    assert_eq!(thawed.added, ["wet"]);
+    assert_eq!(thawed.removed, ["frozen"]);
+    assert_eq!(thawed.held, ["wet"]);
+
+    let resisted = matrix.apply("chilled", &statuses(&["burning"]));
+    assert_eq!(resisted.resisted, ["chilled"]);
+    assert!(resisted.added.is_empty());
+    assert_eq!(resisted.held, ["burning"]);
+}
+
+#[test]
+fn test_exclusions_replace_or_reject() {
+    let matrix = matrix();
+
+    let frozen = matrix.apply("frozen", &statuses(&["chilled"]));
+    assert_eq!(frozen.removed, ["chilled"]);
+    assert_eq!(frozen.held, ["frozen"]);
+
+    let rooted = matrix.apply("rooted", &statuses(&["stunned"]));
+    assert_eq!(rooted.resisted, ["rooted"]);
+    assert_eq!(rooted.held, ["stunned"]);
+
+    // Re-applying a held status does not stack it
+    let stunned = matrix.apply("stunned", &statuses(&["stunned"]));
+    assert!(stunned.added.is_empty() && stunned.resisted.is_empty());
+    assert_eq!(stunned.held, ["stunned"]);
+}
+
+#[test]
+fn test_amplifications_raise_bonus_damage() {
+    let matrix = matrix();
+
+    let shocked = matrix.apply("shocked", &statuses(&["wet"]));
+    assert_eq!(shocked.bonus_damage.len(), 1);
+    assert_eq!(shocked.bonus_damage[0].amplification_id, "wet_conducts_shock");
+    assert_eq!(shocked.bonus_damage[0].multiplier, 0.5);
+    assert_eq!(shocked.bonus_damage[0].element.as_deref(), Some("lightning"));
+    assert_eq!(shocked.removed, ["wet"]);
+    assert_eq!(shocked.held, ["shocked"]);
+
+    assert!(matrix.apply("shocked", &statuses(&["burning"])).bonus_damage.is_empty());
+}
+
+#[test]
+fn test_conversion_cycles_are_rejected() {
+    let config = StatusInteractionConfig {
+        version: 1,
+        reactions: vec![
+            reaction("burn_thaws_freeze", "burning", "frozen", convert("wet")),
+            reaction("wet_freezes_in_cold", "wet", "chilled", convert("frozen")),
+            reaction("frost_takes_fire", "frozen", "oiled", convert("burning")),
+        ],
+        ..Default::default()
+    };
+    match StatusInteractionMatrix::new(config) {
+        Err(ElementCoreError::Validation { message }) => {
+            assert!(message.contains("burning -> wet -> frozen -> burning"), "{}", message)
+        }
+        other => panic!("expected a cycle, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_conflicting_rules_are_rejected() {
+    let duplicate = StatusInteractionConfig {
+        version: 1,
+        reactions: vec![
+            reaction("a", "frozen", "burning", ReactionEffect::Remove),
+            reaction("b", "frozen", "burning", ReactionEffect::Immune),
+        ],
+        ..Default::default()
+    };
+    assert!(StatusInteractionMatrix::new(duplicate).is_err());
+
+    let with_itself = StatusInteractionConfig {
+        version: 1,
+        reactions: vec![reaction("a", "burning", "burning", ReactionEffect::Remove)],
+        ..Default::default()
+    };
+    assert!(StatusInteractionMatrix::new(with_itself).is_err());
+
+    let overlapping = StatusInteractionConfig {
+        version: 1,
+        exclusions: ["a", "b"]
+            .iter()
+            .zip([["stunned", "rooted"], ["rooted", "slowed"]])
+            .map(|(id, members)| StatusExclusion {
+                id: id.to_string(),
+                statuses: statuses(&members),
+                policy: ExclusionPolicy::Replace,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    assert!(StatusInteractionMatrix::new(overlapping).is_err());
+}
+
+#[test]
+fn test_matrix_loads_from_yaml_into_registry() {
+    let yaml = r#"
+version: 1
+reactions:
+  - id: freeze_extinguishes_burn
+    applied: frozen
+    present: burning
+    effect: { kind: remove }
+amplifications:
+  - id: wet_conducts_shock
+    applied: shocked
+    present: wet
+    bonus_multiplier: 0.5
+"#;
+    let registry = UnifiedElementRegistry::new();
+    assert!(registry.get_status_interactions().is_empty());
+
+    registry.set_status_interactions(StatusInteractionMatrix::from_yaml(yaml).unwrap());
+    let matrix = registry.get_status_interactions();
+    assert_eq!(matrix.get_reaction("frozen", "burning").unwrap().effect, ReactionEffect::Remove);
+    assert!(!matrix.get_amplification("shocked", "wet").unwrap().consumes);
+    assert!(matrix.get_exclusion("frozen").is_none());
+
+    let shipped = std::fs::read_to_string("../../docs/element-core/configs/status_interactions.yaml").unwrap();
+    assert!(StatusInteractionMatrix::from_yaml(&shipped).is_ok());
+}
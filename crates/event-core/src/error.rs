@@ -0,0 +1,50 @@
+//! Error types for event-core.
+
+use thiserror::Error;
+
+/// Errors raised by event schedules, their storage, matchmaking and tutorials
+#[derive(Debug, Error)]
+pub enum EventCoreError {
+    /// A schedule that cannot be run
+    #[error("Invalid schedule {schedule}: {reason}")]
+    InvalidSchedule { schedule: String, reason: String },
+
+    /// A schedule that does not exist
+    #[error("Schedule not found: {0}")]
+    ScheduleNotFound(String),
+
+    /// Reading or writing schedules, checkpoints or locks failed
+    #[error("Schedule storage error: {0}")]
+    Storage(String),
+
+    /// A run could not be announced
+    #[error("Failed to announce run: {0}")]
+    Announce(String),
+
+    /// A matchmaking queue that cannot form groups
+    #[error("Invalid queue {queue}: {reason}")]
+    InvalidQueue { queue: String, reason: String },
+
+    /// A matchmaking queue that does not exist
+    #[error("Queue not found: {0}")]
+    QueueNotFound(String),
+
+    /// Players that cannot be queued together
+    #[error("Invalid ticket for queue {queue}: {reason}")]
+    InvalidTicket { queue: String, reason: String },
+
+    /// No instance could be opened or joined for a group
+    #[error("Failed to allocate instance: {0}")]
+    Allocation(String),
+
+    /// A tutorial that cannot be completed, or an action it does not allow
+    #[error("Invalid tutorial {tutorial}: {reason}")]
+    InvalidTutorial { tutorial: String, reason: String },
+
+    /// A tutorial that does not exist or was not started
+    #[error("Tutorial not found: {0}")]
+    TutorialNotFound(String),
+}
+
+/// Result type for event-core operations
+pub type EventCoreResult<T> = Result<T, EventCoreError>;
@@ -0,0 +1,59 @@
+//! Interfaces event schedulers, the matchmaker and tutorial hosts run on.
+
+use async_trait::async_trait;
+use shared::Timestamp;
+use std::time::Duration;
+
+use crate::error::EventCoreResult;
+use crate::schedule::EventSchedule;
+use crate::scheduler::ScheduleCheckpoint;
+use crate::tutorial::TutorialProgress;
+
+/// Where schedules and their checkpoints are stored
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    async fn schedules(&self) -> EventCoreResult<Vec<EventSchedule>>;
+
+    async fn checkpoints(&self) -> EventCoreResult<Vec<ScheduleCheckpoint>>;
+
+    async fn checkpoint(&self, schedule_id: &str) -> EventCoreResult<Option<ScheduleCheckpoint>>;
+
+    async fn save_checkpoint(&self, checkpoint: &ScheduleCheckpoint) -> EventCoreResult<()>;
+
+    /// Pause or resume a schedule; `false` when it does not exist
+    async fn set_paused(&self, schedule_id: &str, paused: bool) -> EventCoreResult<bool>;
+
+    /// Ask for a run to start right away; `false` when the schedule does not exist
+    async fn request_trigger(&self, schedule_id: &str, at: Timestamp) -> EventCoreResult<bool>;
+}
+
+/// Leases making sure a single scheduler instance runs each schedule
+#[async_trait]
+pub trait LockManager: Send + Sync {
+    /// Take `key` for `owner`, or extend it when `owner` already holds it, until `ttl`
+    /// after `now`; `false` while another owner holds it
+    async fn acquire(&self, key: &str, owner: &str, now: Timestamp, ttl: Duration) -> EventCoreResult<bool>;
+
+    /// Give `key` up if `owner` holds it
+    async fn release(&self, key: &str, owner: &str) -> EventCoreResult<()>;
+}
+
+/// Where matched groups are given instances. world-core has no instancing yet, so the
+/// service hosting the matchmaker provides it
+#[async_trait]
+pub trait InstanceAllocator: Send + Sync {
+    /// Open an instance of the `instance` template for the players of `match_id`,
+    /// returning its id
+    async fn allocate(&self, instance: &str, match_id: &str, actor_ids: &[String]) -> EventCoreResult<String>;
+
+    /// Let players backfilled by `match_id` into a running instance
+    async fn admit(&self, instance_id: &str, match_id: &str, actor_ids: &[String]) -> EventCoreResult<()>;
+}
+
+/// Where tutorial progress is stored
+#[async_trait]
+pub trait TutorialStore: Send + Sync {
+    async fn progress(&self) -> EventCoreResult<Vec<TutorialProgress>>;
+
+    async fn save_progress(&self, progress: &TutorialProgress) -> EventCoreResult<()>;
+}
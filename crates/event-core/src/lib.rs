@@ -0,0 +1,20 @@
+//! Event Core - Scheduled world events and their lifecycles.
+//!
+//! This crate provides event schedules, the lifecycle of the runs they start, the
+//! checkpoints a scheduler resumes from after a crash, the instance group finder and the
+//! tutorial scripting hooks in the Chaos World MMORPG.
+
+pub mod schedule;
+pub mod scheduler;
+pub mod matchmaking;
+pub mod tutorial;
+pub mod interfaces;
+pub mod error;
+
+// Re-export commonly used types
+pub use schedule::*;
+pub use scheduler::*;
+pub use matchmaking::*;
+pub use tutorial::*;
+pub use interfaces::*;
+pub use error::*;
@@ -0,0 +1,585 @@
+//! Instance matchmaking: the group finder.
+//!
+//! Players queue for an instance alone or as a party, listing the roles each member can
+//! fill. Every [`Matchmaker::tick`]:
+//! - tickets waiting longer than the queue's `timeout_secs` leave it
+//! - instances that lost players are backfilled first, from tickets filling the missing
+//!   roles
+//! - then groups are formed around the longest waiting ticket: other tickets join while
+//!   their members' levels are within `level_band` of it and their rating within a band
+//!   that widens the longer it waits, as long as every member still gets one of their
+//!   roles
+//!
+//! Full groups are given an instance through an [`InstanceAllocator`]; when that fails
+//! their tickets go back in line without losing their place. Wait times are recorded per
+//! role in [`QueueMetrics`].
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::error::{EventCoreError, EventCoreResult};
+use crate::interfaces::InstanceAllocator;
+
+/// Part a player takes in a group; job-core has no class roles yet to map these from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Tank,
+    Healer,
+    Dps,
+}
+
+/// How many players of each role a group takes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RoleCounts {
+    pub tanks: u32,
+    pub healers: u32,
+    pub dps: u32,
+}
+
+impl RoleCounts {
+    pub fn total(&self) -> u32 {
+        self.tanks + self.healers + self.dps
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// One entry per open slot
+    fn slots(&self) -> Vec<Role> {
+        let mut slots = vec![Role::Tank; self.tanks as usize];
+        slots.extend(std::iter::repeat_n(Role::Healer, self.healers as usize));
+        slots.extend(std::iter::repeat_n(Role::Dps, self.dps as usize));
+        slots
+    }
+
+    fn remove(&mut self, role: Role) {
+        let count = match role {
+            Role::Tank => &mut self.tanks,
+            Role::Healer => &mut self.healers,
+            Role::Dps => &mut self.dps,
+        };
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// An instance players queue for, e.g. a five player dungeon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueDefinition {
+    pub id: String,
+    /// Instance template allocated for each group
+    pub instance: String,
+    pub roles: RoleCounts,
+    /// Largest level difference between a member and the group's first ticket
+    pub level_band: u32,
+    /// Largest rating difference between tickets at first
+    pub rating_band: f64,
+    /// Rating band added for each second the first ticket has waited
+    #[serde(default)]
+    pub rating_band_growth: f64,
+    /// Tickets leave the queue after waiting this long
+    pub timeout_secs: u64,
+    /// Whether instances that lost players are refilled from the queue
+    #[serde(default)]
+    pub backfill: bool,
+}
+
+impl QueueDefinition {
+    /// Reject queues that could never form a group
+    pub fn validate(&self) -> EventCoreResult<()> {
+        let invalid =
+            |reason: &str| Err(EventCoreError::InvalidQueue { queue: self.id.clone(), reason: reason.to_string() });
+        if self.id.is_empty() || self.instance.is_empty() {
+            return invalid("id and instance are required");
+        }
+        if self.roles.is_empty() {
+            return invalid("groups need at least one role");
+        }
+        let non_negative = |value: f64| value.is_finite() && value >= 0.0;
+        if !(non_negative(self.rating_band) && non_negative(self.rating_band_growth)) {
+            return invalid("rating_band and rating_band_growth must be non-negative numbers");
+        }
+        if self.timeout_secs == 0 {
+            return invalid("timeout_secs must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+/// A queued player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueMember {
+    pub actor_id: String,
+    pub level: u32,
+    pub rating: f64,
+    /// Roles the player's job can fill
+    pub roles: Vec<Role>,
+}
+
+/// Players queued together; they are matched into the same group or not at all
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ticket {
+    pub ticket_id: String,
+    pub queue_id: String,
+    pub members: Vec<QueueMember>,
+    pub queued_at: Timestamp,
+}
+
+impl Ticket {
+    pub fn rating(&self) -> f64 {
+        self.members.iter().map(|member| member.rating).sum::<f64>() / self.members.len() as f64
+    }
+
+    pub fn level(&self) -> u32 {
+        let total: u64 = self.members.iter().map(|member| member.level as u64).sum();
+        (total / self.members.len() as u64) as u32
+    }
+
+    fn waited_secs(&self, now: Timestamp) -> f64 {
+        (now - self.queued_at).num_milliseconds().max(0) as f64 / 1000.0
+    }
+
+    /// Whether every member is within `level_band` of `level` and the ticket within
+    /// `rating_band` of `rating`
+    fn within(&self, level: u32, level_band: u32, rating: f64, rating_band: f64) -> bool {
+        self.members.iter().all(|member| member.level.abs_diff(level) <= level_band)
+            && (self.rating() - rating).abs() <= rating_band
+    }
+}
+
+/// A player placed in an instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchedPlayer {
+    pub actor_id: String,
+    pub role: Role,
+}
+
+/// Players sent to an instance together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Match {
+    pub match_id: String,
+    pub queue_id: String,
+    pub instance_id: String,
+    pub players: Vec<MatchedPlayer>,
+    pub tickets: Vec<String>,
+    /// Players joining an instance already running rather than a new one
+    pub backfill: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchmakingEvent {
+    Matched(Match),
+    /// A ticket waited too long and left the queue
+    TimedOut(Ticket),
+}
+
+/// Wait times of matched players
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WaitStats {
+    pub count: u64,
+    pub total_secs: f64,
+    pub longest_secs: f64,
+}
+
+impl WaitStats {
+    pub fn average_secs(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total_secs / self.count as f64
+        }
+    }
+
+    fn record(&mut self, secs: f64) {
+        self.count += 1;
+        self.total_secs += secs;
+        self.longest_secs = self.longest_secs.max(secs);
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct QueueMetrics {
+    /// Tickets in the queue
+    pub waiting: usize,
+    /// Groups sent to new instances
+    pub matched: u64,
+    /// Tickets sent to running instances
+    pub backfilled: u64,
+    pub timed_out: u64,
+    /// Wait times by the role players were matched for
+    pub waits: BTreeMap<Role, WaitStats>,
+}
+
+/// Open slots of a running instance
+#[derive(Debug, Clone)]
+struct BackfillRequest {
+    instance_id: String,
+    missing: RoleCounts,
+    level: u32,
+    rating: f64,
+    requested_at: Timestamp,
+}
+
+#[derive(Debug)]
+struct Queue {
+    definition: QueueDefinition,
+    /// Oldest first
+    tickets: Vec<Ticket>,
+    backfills: Vec<BackfillRequest>,
+    metrics: QueueMetrics,
+}
+
+/// Queues of the group finder
+#[derive(Debug, Default)]
+pub struct Matchmaker {
+    queues: HashMap<String, Queue>,
+    queued_actors: HashSet<String>,
+}
+
+impl Matchmaker {
+    pub fn new(definitions: Vec<QueueDefinition>) -> EventCoreResult<Self> {
+        let mut queues = HashMap::new();
+        for definition in definitions {
+            definition.validate()?;
+            let id = definition.id.clone();
+            let queue =
+                Queue { definition, tickets: Vec::new(), backfills: Vec::new(), metrics: QueueMetrics::default() };
+            if queues.insert(id.clone(), queue).is_some() {
+                return Err(EventCoreError::InvalidQueue { queue: id, reason: "defined twice".to_string() });
+            }
+        }
+        Ok(Self { queues, queued_actors: HashSet::new() })
+    }
+
+    /// Queue `members` together, returning their ticket id
+    pub fn enqueue(&mut self, queue_id: &str, members: Vec<QueueMember>, now: Timestamp) -> EventCoreResult<String> {
+        let queue = self.queues.get_mut(queue_id).ok_or_else(|| EventCoreError::QueueNotFound(queue_id.to_string()))?;
+        let invalid = |reason: &str| {
+            Err(EventCoreError::InvalidTicket { queue: queue_id.to_string(), reason: reason.to_string() })
+        };
+        if members.is_empty() {
+            return invalid("a ticket needs at least one member");
+        }
+        if members.iter().any(|member| member.roles.is_empty() || !member.rating.is_finite()) {
+            return invalid("members need a role and a rating");
+        }
+        if members.iter().any(|member| self.queued_actors.contains(&member.actor_id)) {
+            return invalid("a member is already queued");
+        }
+        if assign_roles(&members.iter().collect::<Vec<_>>(), queue.definition.roles).is_none() {
+            return invalid("members do not fit the group's roles");
+        }
+        let ticket_id = uuid::Uuid::new_v4().to_string();
+        let ticket = Ticket { ticket_id: ticket_id.clone(), queue_id: queue_id.to_string(), members, queued_at: now };
+        if !ticket.within(ticket.level(), queue.definition.level_band, ticket.rating(), 0.0) {
+            return invalid("members are too far apart in level");
+        }
+
+        self.queued_actors.extend(ticket.members.iter().map(|member| member.actor_id.clone()));
+        queue.tickets.push(ticket);
+        Ok(ticket_id)
+    }
+
+    /// Take a ticket out of its queue
+    pub fn leave(&mut self, ticket_id: &str) -> Option<Ticket> {
+        for queue in self.queues.values_mut() {
+            if let Some(index) = queue.tickets.iter().position(|ticket| ticket.ticket_id == ticket_id) {
+                let ticket = queue.tickets.remove(index);
+                for member in &ticket.members {
+                    self.queued_actors.remove(&member.actor_id);
+                }
+                return Some(ticket);
+            }
+        }
+        None
+    }
+
+    /// Ask for players to fill the `missing` roles of a running instance whose group has
+    /// the given average level and rating; a later request replaces an earlier one
+    pub fn request_backfill(
+        &mut self,
+        queue_id: &str,
+        instance_id: &str,
+        missing: RoleCounts,
+        level: u32,
+        rating: f64,
+        now: Timestamp,
+    ) -> EventCoreResult<()> {
+        let queue = self.queues.get_mut(queue_id).ok_or_else(|| EventCoreError::QueueNotFound(queue_id.to_string()))?;
+        if !queue.definition.backfill {
+            return Err(EventCoreError::InvalidQueue {
+                queue: queue_id.to_string(),
+                reason: "the queue does not backfill".to_string(),
+            });
+        }
+        queue.backfills.retain(|request| request.instance_id != instance_id);
+        if !missing.is_empty() {
+            let instance_id = instance_id.to_string();
+            queue.backfills.push(BackfillRequest { instance_id, missing, level, rating, requested_at: now });
+        }
+        Ok(())
+    }
+
+    /// Stop backfilling an instance, e.g. once it closed; `false` when it was not
+    pub fn cancel_backfill(&mut self, instance_id: &str) -> bool {
+        let mut cancelled = false;
+        for queue in self.queues.values_mut() {
+            let before = queue.backfills.len();
+            queue.backfills.retain(|request| request.instance_id != instance_id);
+            cancelled |= queue.backfills.len() < before;
+        }
+        cancelled
+    }
+
+    pub fn tickets(&self, queue_id: &str) -> &[Ticket] {
+        self.queues.get(queue_id).map_or(&[], |queue| queue.tickets.as_slice())
+    }
+
+    pub fn metrics(&self, queue_id: &str) -> Option<QueueMetrics> {
+        let queue = self.queues.get(queue_id)?;
+        Some(QueueMetrics { waiting: queue.tickets.len(), ..queue.metrics.clone() })
+    }
+
+    /// Time tickets out, backfill running instances and form new groups
+    pub async fn tick(&mut self, allocator: &dyn InstanceAllocator, now: Timestamp) -> Vec<MatchmakingEvent> {
+        let mut events = Vec::new();
+        let mut queue_ids: Vec<String> = self.queues.keys().cloned().collect();
+        queue_ids.sort();
+        for queue_id in queue_ids {
+            self.time_out(&queue_id, now, &mut events);
+            self.backfill(&queue_id, allocator, now, &mut events).await;
+            self.form_groups(&queue_id, allocator, now, &mut events).await;
+        }
+        events
+    }
+
+    fn time_out(&mut self, queue_id: &str, now: Timestamp, events: &mut Vec<MatchmakingEvent>) {
+        let Some(queue) = self.queues.get_mut(queue_id) else {
+            return;
+        };
+        let timeout = queue.definition.timeout_secs as f64;
+        let (expired, waiting) = std::mem::take(&mut queue.tickets)
+            .into_iter()
+            .partition::<Vec<_>, _>(|ticket| ticket.waited_secs(now) >= timeout);
+        queue.tickets = waiting;
+        for ticket in expired {
+            queue.metrics.timed_out += 1;
+            for member in &ticket.members {
+                self.queued_actors.remove(&member.actor_id);
+            }
+            events.push(MatchmakingEvent::TimedOut(ticket));
+        }
+    }
+
+    async fn backfill(
+        &mut self,
+        queue_id: &str,
+        allocator: &dyn InstanceAllocator,
+        now: Timestamp,
+        events: &mut Vec<MatchmakingEvent>,
+    ) {
+        let Some(queue) = self.queues.get_mut(queue_id) else {
+            return;
+        };
+        for request in std::mem::take(&mut queue.backfills) {
+            let definition = &queue.definition;
+            let waited_secs = (now - request.requested_at).num_milliseconds().max(0) as f64 / 1000.0;
+            let rating_band = definition.rating_band + definition.rating_band_growth * waited_secs;
+            let (level, level_band) = (request.level, definition.level_band);
+            let Some((picked, roles)) = pick(&queue.tickets, request.missing, false, |ticket| {
+                ticket.within(level, level_band, request.rating, rating_band)
+            }) else {
+                queue.backfills.push(request);
+                continue;
+            };
+
+            let tickets = take(&mut queue.tickets, &picked);
+            let match_id = uuid::Uuid::new_v4().to_string();
+            let matched = build_match(match_id, queue_id, &request.instance_id, &tickets, &roles, true);
+            let actor_ids: Vec<String> = matched.players.iter().map(|player| player.actor_id.clone()).collect();
+            match allocator.admit(&request.instance_id, &matched.match_id, &actor_ids).await {
+                Ok(()) => {
+                    queue.metrics.backfilled += tickets.len() as u64;
+                    record_waits(&mut queue.metrics, &tickets, &roles, now);
+                    self.queued_actors.retain(|actor_id| !actor_ids.contains(actor_id));
+                    let mut request = request;
+                    roles.iter().for_each(|role| request.missing.remove(*role));
+                    if !request.missing.is_empty() {
+                        queue.backfills.push(request);
+                    }
+                    events.push(MatchmakingEvent::Matched(matched));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to backfill instance {}: {}", request.instance_id, e);
+                    restore(&mut queue.tickets, tickets);
+                    queue.backfills.push(request);
+                }
+            }
+        }
+    }
+
+    async fn form_groups(
+        &mut self,
+        queue_id: &str,
+        allocator: &dyn InstanceAllocator,
+        now: Timestamp,
+        events: &mut Vec<MatchmakingEvent>,
+    ) {
+        let Some(queue) = self.queues.get_mut(queue_id) else {
+            return;
+        };
+        let mut anchor = 0;
+        while anchor < queue.tickets.len() {
+            let definition = &queue.definition;
+            let first = &queue.tickets[anchor];
+            let rating_band = definition.rating_band + definition.rating_band_growth * first.waited_secs(now);
+            let (level, rating, level_band) = (first.level(), first.rating(), definition.level_band);
+            let candidates = &queue.tickets[anchor..];
+            let Some((picked, roles)) = pick(candidates, definition.roles, true, |ticket| {
+                ticket.within(level, level_band, rating, rating_band)
+            }) else {
+                anchor += 1;
+                continue;
+            };
+
+            let picked: Vec<usize> = picked.into_iter().map(|index| index + anchor).collect();
+            let tickets = take(&mut queue.tickets, &picked);
+            let actor_ids: Vec<String> =
+                tickets.iter().flat_map(|ticket| ticket.members.iter().map(|member| member.actor_id.clone())).collect();
+            let match_id = uuid::Uuid::new_v4().to_string();
+            match allocator.allocate(&queue.definition.instance, &match_id, &actor_ids).await {
+                Ok(instance_id) => {
+                    let matched = build_match(match_id, queue_id, &instance_id, &tickets, &roles, false);
+                    queue.metrics.matched += 1;
+                    record_waits(&mut queue.metrics, &tickets, &roles, now);
+                    self.queued_actors.retain(|actor_id| !actor_ids.contains(actor_id));
+                    events.push(MatchmakingEvent::Matched(matched));
+                }
+                Err(e) => {
+                    // Allocation is down; the tickets keep their place until the next tick
+                    tracing::warn!("Failed to allocate {} for queue {}: {}", queue.definition.instance, queue_id, e);
+                    restore(&mut queue.tickets, tickets);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Pick tickets, oldest first, whose members fill `slots`, all of them when `fill` is
+/// set; returns their indices and the role of each of their members in order
+fn pick(
+    tickets: &[Ticket],
+    slots: RoleCounts,
+    fill: bool,
+    eligible: impl Fn(&Ticket) -> bool,
+) -> Option<(Vec<usize>, Vec<Role>)> {
+    let mut picked = Vec::new();
+    let mut members: Vec<&QueueMember> = Vec::new();
+    let mut roles = Vec::new();
+    for (index, ticket) in tickets.iter().enumerate() {
+        if members.len() as u32 == slots.total() {
+            break;
+        }
+        if !eligible(ticket) {
+            continue;
+        }
+        let mut with_ticket = members.clone();
+        with_ticket.extend(ticket.members.iter());
+        if let Some(assigned) = assign_roles(&with_ticket, slots) {
+            picked.push(index);
+            members = with_ticket;
+            roles = assigned;
+        }
+    }
+    let done = if fill { members.len() as u32 == slots.total() } else { !members.is_empty() };
+    done.then_some((picked, roles))
+}
+
+/// Give each member one of their roles without exceeding `slots`, by augmenting paths
+fn assign_roles(members: &[&QueueMember], slots: RoleCounts) -> Option<Vec<Role>> {
+    let slots = slots.slots();
+    if members.len() > slots.len() {
+        return None;
+    }
+    let mut holders: Vec<Option<usize>> = vec![None; slots.len()];
+    for member in 0..members.len() {
+        let mut seen = vec![false; slots.len()];
+        if !augment(member, members, &slots, &mut holders, &mut seen) {
+            return None;
+        }
+    }
+    let mut roles = vec![Role::Dps; members.len()];
+    for (slot, holder) in holders.iter().enumerate() {
+        if let Some(member) = holder {
+            roles[*member] = slots[slot];
+        }
+    }
+    Some(roles)
+}
+
+fn augment(
+    member: usize,
+    members: &[&QueueMember],
+    slots: &[Role],
+    holders: &mut [Option<usize>],
+    seen: &mut [bool],
+) -> bool {
+    for (slot, role) in slots.iter().enumerate() {
+        if seen[slot] || !members[member].roles.contains(role) {
+            continue;
+        }
+        seen[slot] = true;
+        if holders[slot].is_none_or(|holder| augment(holder, members, slots, holders, seen)) {
+            holders[slot] = Some(member);
+            return true;
+        }
+    }
+    false
+}
+
+/// Remove the tickets at the ascending `indices`
+fn take(tickets: &mut Vec<Ticket>, indices: &[usize]) -> Vec<Ticket> {
+    let mut taken: Vec<Ticket> = indices.iter().rev().map(|index| tickets.remove(*index)).collect();
+    taken.reverse();
+    taken
+}
+
+/// Put tickets back in line by the time they queued
+fn restore(tickets: &mut Vec<Ticket>, taken: Vec<Ticket>) {
+    tickets.extend(taken);
+    tickets.sort_by_key(|ticket| ticket.queued_at);
+}
+
+fn build_match(
+    match_id: String,
+    queue_id: &str,
+    instance_id: &str,
+    tickets: &[Ticket],
+    roles: &[Role],
+    backfill: bool,
+) -> Match {
+    let members = tickets.iter().flat_map(|ticket| ticket.members.iter());
+    Match {
+        match_id,
+        queue_id: queue_id.to_string(),
+        instance_id: instance_id.to_string(),
+        players: members
+            .zip(roles)
+            .map(|(member, role)| MatchedPlayer { actor_id: member.actor_id.clone(), role: *role })
+            .collect(),
+        tickets: tickets.iter().map(|ticket| ticket.ticket_id.clone()).collect(),
+        backfill,
+    }
+}
+
+fn record_waits(metrics: &mut QueueMetrics, tickets: &[Ticket], roles: &[Role], now: Timestamp) {
+    let waits = tickets.iter().flat_map(|ticket| ticket.members.iter().map(move |_| ticket.waited_secs(now)));
+    for (waited, role) in waits.zip(roles) {
+        metrics.waits.entry(*role).or_default().record(waited);
+    }
+}
@@ -0,0 +1,84 @@
+//! Event schedules: what runs, when, and for how long.
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::error::{EventCoreError, EventCoreResult};
+
+/// When the runs of a schedule start
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    /// A single run
+    Once { at: Timestamp },
+    /// A run every `interval_secs` from `start`
+    Every { start: Timestamp, interval_secs: u64 },
+}
+
+impl Recurrence {
+    /// First start at or after `time`
+    pub fn next_at_or_after(&self, time: Timestamp) -> Option<Timestamp> {
+        match self {
+            Recurrence::Once { at } => (*at >= time).then_some(*at),
+            Recurrence::Every { start, interval_secs } => {
+                if time <= *start {
+                    return Some(*start);
+                }
+                let interval = (*interval_secs).max(1) as i64 * 1000;
+                let periods = (time - *start).num_milliseconds() / interval;
+                let next = *start + Duration::milliseconds(periods * interval);
+                Some(if next < time { next + Duration::milliseconds(interval) } else { next })
+            }
+        }
+    }
+
+    /// First start after `time`
+    pub fn next_after(&self, time: Timestamp) -> Option<Timestamp> {
+        self.next_at_or_after(time + Duration::milliseconds(1))
+    }
+}
+
+/// A world event run on a schedule, e.g. a weekly goblin invasion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventSchedule {
+    pub id: String,
+    /// Name of the world event announced for each run
+    pub name: String,
+    /// Zone the runs are limited to, the whole world when absent
+    #[serde(default)]
+    pub zone: Option<String>,
+    pub recurrence: Recurrence,
+    pub duration_secs: u64,
+    /// Paused schedules skip their runs; runs in progress still end
+    #[serde(default)]
+    pub paused: bool,
+    /// Set by an admin to start a run right away, once per request
+    #[serde(default)]
+    pub trigger_requested_at: Option<Timestamp>,
+}
+
+impl EventSchedule {
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(self.duration_secs as i64)
+    }
+
+    /// Reject schedules the scheduler could not run
+    pub fn validate(&self) -> EventCoreResult<()> {
+        let invalid = |reason: &str| {
+            Err(EventCoreError::InvalidSchedule { schedule: self.id.clone(), reason: reason.to_string() })
+        };
+        if self.id.is_empty() || self.name.is_empty() {
+            return invalid("id and name are required");
+        }
+        if self.duration_secs == 0 {
+            return invalid("duration_secs must be at least 1");
+        }
+        if let Recurrence::Every { interval_secs, .. } = self.recurrence {
+            if interval_secs == 0 {
+                return invalid("interval_secs must be at least 1");
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+//! The lifecycle of schedule runs.
+//!
+//! A schedule's progress lives in a [`ScheduleCheckpoint`] that [`advance`] moves forward:
+//! runs start when they are due and end once their duration is over. Schedulers save the
+//! checkpoint after every change, so whichever instance runs the schedule next resumes
+//! where the last one stopped, starting late any run that should be in progress and
+//! skipping runs whose time has passed.
+
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::schedule::EventSchedule;
+
+/// A run of a schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRun {
+    /// The same for every scheduler starting this run, so it can be announced more than once
+    pub run_id: String,
+    pub schedule_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub zone: Option<String>,
+    pub started_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Started by an admin rather than the recurrence
+    #[serde(default)]
+    pub triggered: bool,
+}
+
+/// A run starting or ending
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    Started(EventRun),
+    Ended(EventRun),
+}
+
+/// Where a schedule is at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleCheckpoint {
+    pub schedule_id: String,
+    /// Start of the next scheduled run, none once the recurrence is over
+    pub next_start: Option<Timestamp>,
+    /// Run in progress
+    pub active: Option<EventRun>,
+    /// Latest trigger request already handled
+    #[serde(default)]
+    pub handled_trigger: Option<Timestamp>,
+    pub updated_at: Timestamp,
+}
+
+impl ScheduleCheckpoint {
+    /// Checkpoint of a schedule run for the first time; a run that should already be in
+    /// progress is still started
+    pub fn new(schedule: &EventSchedule, now: Timestamp) -> Self {
+        Self {
+            schedule_id: schedule.id.clone(),
+            next_start: schedule.recurrence.next_at_or_after(now - schedule.duration()),
+            active: None,
+            handled_trigger: None,
+            updated_at: now,
+        }
+    }
+}
+
+/// Move a schedule to `now`, returning the runs that started and ended
+pub fn advance(schedule: &EventSchedule, checkpoint: &mut ScheduleCheckpoint, now: Timestamp) -> Vec<Transition> {
+    let mut transitions = Vec::new();
+    end_if_over(checkpoint, now, &mut transitions);
+
+    if let Some(requested) = schedule.trigger_requested_at {
+        if checkpoint.handled_trigger.is_none_or(|handled| requested > handled) {
+            checkpoint.handled_trigger = Some(requested);
+            if checkpoint.active.is_none() {
+                start(schedule, checkpoint, now, now + schedule.duration(), true, &mut transitions);
+            }
+        }
+    }
+
+    while let Some(next_start) = checkpoint.next_start.filter(|next_start| *next_start <= now) {
+        checkpoint.next_start = schedule.recurrence.next_after(next_start);
+        let ends_at = next_start + schedule.duration();
+        // Runs are skipped while paused, behind another run, or once their time has passed
+        if !schedule.paused && checkpoint.active.is_none() && now < ends_at {
+            start(schedule, checkpoint, next_start, ends_at, false, &mut transitions);
+        }
+    }
+
+    end_if_over(checkpoint, now, &mut transitions);
+    checkpoint.updated_at = now;
+    transitions
+}
+
+fn start(
+    schedule: &EventSchedule,
+    checkpoint: &mut ScheduleCheckpoint,
+    started_at: Timestamp,
+    ends_at: Timestamp,
+    triggered: bool,
+    transitions: &mut Vec<Transition>,
+) {
+    let run = EventRun {
+        run_id: format!("{}:{}", schedule.id, started_at.timestamp_millis()),
+        schedule_id: schedule.id.clone(),
+        name: schedule.name.clone(),
+        zone: schedule.zone.clone(),
+        started_at,
+        ends_at,
+        triggered,
+    };
+    checkpoint.active = Some(run.clone());
+    transitions.push(Transition::Started(run));
+}
+
+fn end_if_over(checkpoint: &mut ScheduleCheckpoint, now: Timestamp, transitions: &mut Vec<Transition>) {
+    if checkpoint.active.as_ref().is_some_and(|run| run.ends_at <= now) {
+        transitions.extend(checkpoint.active.take().map(Transition::Ended));
+    }
+}
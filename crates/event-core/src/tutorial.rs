@@ -0,0 +1,378 @@
+//! Tutorial flows: scripted steps players go through in the starter zone.
+//!
+//! A [`TutorialDefinition`] lists steps in order. Each step shows a UI hint and waits for
+//! player actions matching its [`StepTrigger`], e.g. a first kill or a first loot; once
+//! enough of them happened the next step's hint is shown. Players can skip a tutorial
+//! that allows it and resume it later from the step they left.
+//!
+//! Progress is tracked as a hidden quest chain: every step is a hidden quest named by
+//! [`quest_id`], completed in order, and the chain named by [`chain_id`] completes with
+//! the last step. [`Tutorials`] only keeps the progress; hosts store the
+//! [`TutorialChanges`] it returns and deliver their updates to the players.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::error::{EventCoreError, EventCoreResult};
+
+/// Kind of player action a step waits for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Kill,
+    Loot,
+    EnterZone,
+    TalkTo,
+    /// Anything else a client or game server reports, e.g. opening the inventory
+    Custom,
+}
+
+/// Something a player did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerAction {
+    pub kind: ActionKind,
+    /// What it was done to: the NPC template killed, the item looted, the zone entered,
+    /// the NPC talked to or the custom action's name
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+impl PlayerAction {
+    pub fn new(kind: ActionKind, subject: Option<&str>) -> Self {
+        Self { kind, subject: subject.map(str::to_string) }
+    }
+}
+
+/// Actions completing a step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepTrigger {
+    pub action: ActionKind,
+    /// Only actions done to this count, any when absent
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Matching actions needed, 1 for a first kill or loot
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+impl StepTrigger {
+    fn matches(&self, action: &PlayerAction) -> bool {
+        self.action == action.kind
+            && self.subject.as_ref().is_none_or(|subject| action.subject.as_ref() == Some(subject))
+    }
+}
+
+/// Hint shown to the player while a step is current
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UiHint {
+    pub text: String,
+    /// UI element the client highlights, e.g. `inventory_button`
+    #[serde(default)]
+    pub anchor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TutorialStep {
+    pub id: String,
+    pub trigger: StepTrigger,
+    pub hint: UiHint,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TutorialDefinition {
+    pub id: String,
+    pub steps: Vec<TutorialStep>,
+    /// Whether players can skip the tutorial
+    #[serde(default = "default_skippable")]
+    pub skippable: bool,
+}
+
+fn default_skippable() -> bool {
+    true
+}
+
+impl TutorialDefinition {
+    /// Reject tutorials that could never be completed
+    pub fn validate(&self) -> EventCoreResult<()> {
+        let invalid =
+            |reason: String| Err(EventCoreError::InvalidTutorial { tutorial: self.id.clone(), reason });
+        if self.id.is_empty() || self.steps.is_empty() {
+            return invalid("id and at least one step are required".to_string());
+        }
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if !ids.insert(step.id.as_str()) {
+                return invalid(format!("step {} is listed twice", step.id));
+            }
+            if step.trigger.count == 0 {
+                return invalid(format!("step {} needs at least one action", step.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hidden quest of a tutorial step
+pub fn quest_id(tutorial_id: &str, step_id: &str) -> String {
+    format!("tutorial.{}.{}", tutorial_id, step_id)
+}
+
+/// Hidden quest chain of a tutorial
+pub fn chain_id(tutorial_id: &str) -> String {
+    format!("tutorial.{}", tutorial_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TutorialStatus {
+    Active,
+    Skipped,
+    Completed,
+}
+
+/// How far an actor got in a tutorial
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TutorialProgress {
+    pub actor_id: String,
+    /// User hints are pushed to
+    pub user_id: String,
+    pub tutorial_id: String,
+    pub status: TutorialStatus,
+    /// Index of the current step
+    pub step: usize,
+    /// Matching actions seen for the current step
+    pub count: u32,
+    /// Hidden quests completed so far, in order
+    pub completed_quests: Vec<String>,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// What players are told about their tutorials
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TutorialUpdate {
+    /// Show the hint of the current step
+    ShowHint { user_id: String, actor_id: String, tutorial_id: String, step_id: String, hint: UiHint },
+    /// Hide the tutorial's hint, skipped or completed
+    HideHint { user_id: String, actor_id: String, tutorial_id: String },
+    /// Hidden quest of a step completed
+    QuestCompleted { actor_id: String, tutorial_id: String, quest_id: String },
+    /// Hidden quest chain of the tutorial completed
+    ChainCompleted { actor_id: String, tutorial_id: String, chain_id: String },
+}
+
+/// Progress changed by a call and the updates to deliver
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TutorialChanges {
+    /// Progress to store
+    pub progress: Vec<TutorialProgress>,
+    pub updates: Vec<TutorialUpdate>,
+}
+
+/// Tutorial definitions and the progress of every actor through them
+#[derive(Debug, Default)]
+pub struct Tutorials {
+    definitions: HashMap<String, TutorialDefinition>,
+    /// Progress by actor, then tutorial
+    progress: HashMap<String, HashMap<String, TutorialProgress>>,
+}
+
+impl Tutorials {
+    pub fn new(definitions: Vec<TutorialDefinition>) -> EventCoreResult<Self> {
+        let mut tutorials = Self::default();
+        for definition in definitions {
+            definition.validate()?;
+            tutorials.definitions.insert(definition.id.clone(), definition);
+        }
+        Ok(tutorials)
+    }
+
+    /// Put back progress loaded from storage. Progress through unknown tutorials is
+    /// dropped; progress past the last step of a tutorial that got shorter completes it.
+    pub fn restore(&mut self, mut progress: TutorialProgress) {
+        let Some(definition) = self.definitions.get(&progress.tutorial_id) else {
+            return;
+        };
+        if progress.step >= definition.steps.len() {
+            progress.status = TutorialStatus::Completed;
+        }
+        self.progress.entry(progress.actor_id.clone()).or_default().insert(progress.tutorial_id.clone(), progress);
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = &TutorialDefinition> {
+        self.definitions.values()
+    }
+
+    pub fn progress(&self, actor_id: &str, tutorial_id: &str) -> Option<&TutorialProgress> {
+        self.progress.get(actor_id)?.get(tutorial_id)
+    }
+
+    /// Start a tutorial for an actor, showing its first hint. Starting it again shows the
+    /// current hint once more while it is active and does nothing otherwise.
+    pub fn start(
+        &mut self,
+        actor_id: &str,
+        user_id: &str,
+        tutorial_id: &str,
+        now: Timestamp,
+    ) -> EventCoreResult<TutorialChanges> {
+        let definition = self.definitions.get(tutorial_id).ok_or_else(|| not_found(tutorial_id))?;
+        let progress = self.progress.entry(actor_id.to_string()).or_default();
+        let mut changes = TutorialChanges::default();
+        match progress.get_mut(tutorial_id) {
+            Some(progress) if progress.status == TutorialStatus::Active => {
+                // The player may be on another account's session now
+                if progress.user_id != user_id {
+                    progress.user_id = user_id.to_string();
+                    progress.updated_at = now;
+                    changes.progress.push(progress.clone());
+                }
+                changes.updates.push(show_hint(definition, progress));
+            }
+            Some(_) => {}
+            None => {
+                let started = TutorialProgress {
+                    actor_id: actor_id.to_string(),
+                    user_id: user_id.to_string(),
+                    tutorial_id: tutorial_id.to_string(),
+                    status: TutorialStatus::Active,
+                    step: 0,
+                    count: 0,
+                    completed_quests: Vec::new(),
+                    started_at: now,
+                    updated_at: now,
+                };
+                changes.updates.push(show_hint(definition, &started));
+                changes.progress.push(started.clone());
+                progress.insert(tutorial_id.to_string(), started);
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Count an action towards the current step of the actor's active tutorials,
+    /// completing the steps it finishes
+    pub fn record(&mut self, actor_id: &str, action: &PlayerAction, now: Timestamp) -> TutorialChanges {
+        let mut changes = TutorialChanges::default();
+        let Some(progress) = self.progress.get_mut(actor_id) else {
+            return changes;
+        };
+        for progress in progress.values_mut().filter(|progress| progress.status == TutorialStatus::Active) {
+            let Some(definition) = self.definitions.get(&progress.tutorial_id) else {
+                continue;
+            };
+            let step = &definition.steps[progress.step];
+            if !step.trigger.matches(action) {
+                continue;
+            }
+            progress.count += 1;
+            progress.updated_at = now;
+            if progress.count >= step.trigger.count {
+                let quest_id = quest_id(&definition.id, &step.id);
+                progress.completed_quests.push(quest_id.clone());
+                changes.updates.push(TutorialUpdate::QuestCompleted {
+                    actor_id: actor_id.to_string(),
+                    tutorial_id: definition.id.clone(),
+                    quest_id,
+                });
+                progress.step += 1;
+                progress.count = 0;
+                if progress.step < definition.steps.len() {
+                    changes.updates.push(show_hint(definition, progress));
+                } else {
+                    progress.status = TutorialStatus::Completed;
+                    changes.updates.push(TutorialUpdate::ChainCompleted {
+                        actor_id: actor_id.to_string(),
+                        tutorial_id: definition.id.clone(),
+                        chain_id: chain_id(&definition.id),
+                    });
+                    changes.updates.push(hide_hint(progress));
+                }
+            }
+            changes.progress.push(progress.clone());
+        }
+        changes
+    }
+
+    /// Skip an active tutorial, keeping the step reached for when it is resumed
+    pub fn skip(&mut self, actor_id: &str, tutorial_id: &str, now: Timestamp) -> EventCoreResult<TutorialChanges> {
+        if !self.definition(tutorial_id)?.skippable {
+            return Err(EventCoreError::InvalidTutorial {
+                tutorial: tutorial_id.to_string(),
+                reason: "it cannot be skipped".to_string(),
+            });
+        }
+        let progress = self.started(actor_id, tutorial_id)?;
+        let mut changes = TutorialChanges::default();
+        if progress.status == TutorialStatus::Active {
+            progress.status = TutorialStatus::Skipped;
+            progress.updated_at = now;
+            changes.updates.push(hide_hint(progress));
+            changes.progress.push(progress.clone());
+        }
+        Ok(changes)
+    }
+
+    /// Resume a skipped tutorial from the step it was skipped at
+    pub fn resume(&mut self, actor_id: &str, tutorial_id: &str, now: Timestamp) -> EventCoreResult<TutorialChanges> {
+        let definition = self.definitions.get(tutorial_id).ok_or_else(|| not_found(tutorial_id))?;
+        let progress = self
+            .progress
+            .get_mut(actor_id)
+            .and_then(|progress| progress.get_mut(tutorial_id))
+            .ok_or_else(|| not_started(actor_id, tutorial_id))?;
+        let mut changes = TutorialChanges::default();
+        if progress.status == TutorialStatus::Skipped {
+            progress.status = TutorialStatus::Active;
+            progress.updated_at = now;
+            changes.updates.push(show_hint(definition, progress));
+            changes.progress.push(progress.clone());
+        }
+        Ok(changes)
+    }
+
+    fn definition(&self, tutorial_id: &str) -> EventCoreResult<&TutorialDefinition> {
+        self.definitions.get(tutorial_id).ok_or_else(|| not_found(tutorial_id))
+    }
+
+    fn started(&mut self, actor_id: &str, tutorial_id: &str) -> EventCoreResult<&mut TutorialProgress> {
+        self.progress
+            .get_mut(actor_id)
+            .and_then(|progress| progress.get_mut(tutorial_id))
+            .ok_or_else(|| not_started(actor_id, tutorial_id))
+    }
+}
+
+fn not_found(tutorial_id: &str) -> EventCoreError {
+    EventCoreError::TutorialNotFound(tutorial_id.to_string())
+}
+
+fn not_started(actor_id: &str, tutorial_id: &str) -> EventCoreError {
+    EventCoreError::TutorialNotFound(format!("{} for {}", tutorial_id, actor_id))
+}
+
+fn show_hint(definition: &TutorialDefinition, progress: &TutorialProgress) -> TutorialUpdate {
+    let step = &definition.steps[progress.step];
+    TutorialUpdate::ShowHint {
+        user_id: progress.user_id.clone(),
+        actor_id: progress.actor_id.clone(),
+        tutorial_id: definition.id.clone(),
+        step_id: step.id.clone(),
+        hint: step.hint.clone(),
+    }
+}
+
+fn hide_hint(progress: &TutorialProgress) -> TutorialUpdate {
+    TutorialUpdate::HideHint {
+        user_id: progress.user_id.clone(),
+        actor_id: progress.actor_id.clone(),
+        tutorial_id: progress.tutorial_id.clone(),
+    }
+}
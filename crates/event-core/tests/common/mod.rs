@@ -0,0 +1,11 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use chrono::{Duration, TimeZone, Utc};
+use shared::Timestamp;
+
+/// `secs` seconds after the fixed start of every test timeline
+pub fn at(secs: i64) -> Timestamp {
+    Utc.timestamp_opt(1_700_000_000, 0).unwrap() + Duration::seconds(secs)
+}
@@ -0,0 +1,200 @@
+//! Matchmaking tests: role composition, level and rating bands, timeouts, backfill and
+//! instance allocation.
+
+mod common;
+
+use async_trait::async_trait;
+use common::at;
+use event_core::{
+    EventCoreError, EventCoreResult, InstanceAllocator, Match, Matchmaker, MatchmakingEvent, QueueDefinition,
+    QueueMember, Role, RoleCounts,
+};
+use std::sync::Mutex;
+
+/// Hands out `dungeon-1`, `dungeon-2`, ... unless told to fail
+#[derive(Default)]
+struct Instances {
+    allocated: Mutex<Vec<Vec<String>>>,
+    admitted: Mutex<Vec<(String, Vec<String>)>>,
+    down: Mutex<bool>,
+}
+
+#[async_trait]
+impl InstanceAllocator for Instances {
+    async fn allocate(&self, instance: &str, _match_id: &str, actor_ids: &[String]) -> EventCoreResult<String> {
+        if *self.down.lock().unwrap() {
+            return Err(EventCoreError::Allocation("no world host available".to_string()));
+        }
+        let mut allocated = self.allocated.lock().unwrap();
+        allocated.push(actor_ids.to_vec());
+        Ok(format!("{}-{}", instance, allocated.len()))
+    }
+
+    async fn admit(&self, instance_id: &str, _match_id: &str, actor_ids: &[String]) -> EventCoreResult<()> {
+        self.admitted.lock().unwrap().push((instance_id.to_string(), actor_ids.to_vec()));
+        Ok(())
+    }
+}
+
+/// One tank, one healer and two dps within 5 levels
+fn dungeon() -> QueueDefinition {
+    QueueDefinition {
+        id: "crypt".to_string(),
+        instance: "dungeon".to_string(),
+        roles: RoleCounts { tanks: 1, healers: 1, dps: 2 },
+        level_band: 5,
+        rating_band: 100.0,
+        rating_band_growth: 10.0,
+        timeout_secs: 600,
+        backfill: true,
+    }
+}
+
+fn player(actor_id: &str, level: u32, rating: f64, roles: &[Role]) -> QueueMember {
+    QueueMember { actor_id: actor_id.to_string(), level, rating, roles: roles.to_vec() }
+}
+
+fn matched_groups(events: &[MatchmakingEvent]) -> Vec<&Match> {
+    events
+        .iter()
+        .filter_map(|event| match event {
+            MatchmakingEvent::Matched(matched) => Some(matched),
+            MatchmakingEvent::TimedOut(_) => None,
+        })
+        .collect()
+}
+
+fn role_of(matched: &Match, actor_id: &str) -> Role {
+    matched.players.iter().find(|player| player.actor_id == actor_id).unwrap().role
+}
+
+#[tokio::test]
+async fn groups_form_once_every_role_is_filled() {
+    let mut matchmaker = Matchmaker::new(vec![dungeon()]).unwrap();
+    let instances = Instances::default();
+
+    // A party of a tank and a flexible healer queues with one dps
+    let druid = player("druid", 21, 1000.0, &[Role::Healer, Role::Dps]);
+    let party = vec![player("tank", 20, 1000.0, &[Role::Tank]), druid];
+    let party_ticket = matchmaker.enqueue("crypt", party, at(0)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("rogue", 20, 1000.0, &[Role::Dps])], at(1)).unwrap();
+    assert!(matchmaker.tick(&instances, at(2)).await.is_empty());
+
+    // A second tank cannot join; a healer can, and the druid moves over to dps
+    matchmaker.enqueue("crypt", vec![player("warrior", 20, 1000.0, &[Role::Tank])], at(3)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("priest", 22, 1000.0, &[Role::Healer])], at(4)).unwrap();
+    let events = matchmaker.tick(&instances, at(10)).await;
+    let matched = matched_groups(&events);
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].instance_id, "dungeon-1");
+    assert_eq!(matched[0].players.len(), 4);
+    assert!(matched[0].tickets.contains(&party_ticket));
+    assert_eq!((role_of(matched[0], "druid"), role_of(matched[0], "priest")), (Role::Dps, Role::Healer));
+    assert_eq!(matchmaker.tickets("crypt").len(), 1);
+
+    // Queue times are recorded by role
+    let metrics = matchmaker.metrics("crypt").unwrap();
+    assert_eq!((metrics.waiting, metrics.matched), (1, 1));
+    assert_eq!(metrics.waits[&Role::Dps].count, 2);
+    assert_eq!(metrics.waits[&Role::Tank].longest_secs, 10.0);
+    assert_eq!(metrics.waits[&Role::Healer].average_secs(), 6.0);
+}
+
+#[tokio::test]
+async fn bands_keep_levels_apart_and_ratings_widen_with_time() {
+    let mut queue = dungeon();
+    queue.roles = RoleCounts { tanks: 1, healers: 0, dps: 1 };
+    let mut matchmaker = Matchmaker::new(vec![queue]).unwrap();
+    let instances = Instances::default();
+
+    matchmaker.enqueue("crypt", vec![player("tank", 20, 1000.0, &[Role::Tank])], at(0)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("veteran", 40, 1000.0, &[Role::Dps])], at(0)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("expert", 24, 1250.0, &[Role::Dps])], at(0)).unwrap();
+    // Nobody is within 5 levels and 100 rating of the tank yet
+    assert!(matchmaker.tick(&instances, at(1)).await.is_empty());
+    // After 15s the band is 250 wide: the expert gets in, the veteran never does
+    let events = matchmaker.tick(&instances, at(15)).await;
+    let players = &matched_groups(&events)[0].players;
+    assert_eq!(players.iter().map(|player| player.actor_id.as_str()).collect::<Vec<_>>(), ["tank", "expert"]);
+
+    // Parties must fit the group and its level band themselves
+    let party = vec![player("a", 20, 1000.0, &[Role::Tank]), player("b", 20, 1000.0, &[Role::Tank])];
+    assert!(matches!(matchmaker.enqueue("crypt", party, at(20)), Err(EventCoreError::InvalidTicket { .. })));
+    let party = vec![player("a", 10, 1000.0, &[Role::Tank]), player("b", 30, 1000.0, &[Role::Dps])];
+    assert!(matchmaker.enqueue("crypt", party, at(20)).is_err());
+    assert!(matchmaker.enqueue("crypt", vec![player("veteran", 40, 1000.0, &[Role::Dps])], at(20)).is_err());
+    assert!(matches!(matchmaker.enqueue("raid", Vec::new(), at(20)), Err(EventCoreError::QueueNotFound(_))));
+}
+
+#[tokio::test]
+async fn tickets_time_out_and_keep_their_place_when_allocation_fails() {
+    let mut queue = dungeon();
+    queue.roles = RoleCounts { tanks: 1, healers: 0, dps: 0 };
+    queue.timeout_secs = 60;
+    let mut matchmaker = Matchmaker::new(vec![queue]).unwrap();
+    let instances = Instances::default();
+
+    *instances.down.lock().unwrap() = true;
+    let first = matchmaker.enqueue("crypt", vec![player("first", 20, 1000.0, &[Role::Tank])], at(0)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("second", 20, 1000.0, &[Role::Tank])], at(30)).unwrap();
+    assert!(matchmaker.tick(&instances, at(31)).await.is_empty());
+    assert_eq!(matchmaker.tickets("crypt")[0].ticket_id, first);
+
+    // The first ticket waited too long; its player may queue again
+    let events = matchmaker.tick(&instances, at(61)).await;
+    assert!(matches!(&events[..], [MatchmakingEvent::TimedOut(ticket)] if ticket.ticket_id == first));
+    matchmaker.enqueue("crypt", vec![player("first", 20, 1000.0, &[Role::Tank])], at(62)).unwrap();
+
+    *instances.down.lock().unwrap() = false;
+    let events = matchmaker.tick(&instances, at(63)).await;
+    assert_eq!(matched_groups(&events)[0].players[0].actor_id, "second");
+    assert_eq!(matched_groups(&events).len(), 2);
+    let metrics = matchmaker.metrics("crypt").unwrap();
+    assert_eq!((metrics.waiting, metrics.matched, metrics.timed_out), (0, 2, 1));
+
+    // Leaving frees the player as well
+    let ticket = matchmaker.enqueue("crypt", vec![player("third", 20, 1000.0, &[Role::Tank])], at(70)).unwrap();
+    assert!(matchmaker.leave(&ticket).is_some());
+    assert!(matchmaker.leave(&ticket).is_none());
+}
+
+#[tokio::test]
+async fn running_instances_are_backfilled_first() {
+    let mut matchmaker = Matchmaker::new(vec![dungeon()]).unwrap();
+    let instances = Instances::default();
+
+    // dungeon-7 lost its healer and a dps
+    let missing = RoleCounts { tanks: 0, healers: 1, dps: 1 };
+    matchmaker.request_backfill("crypt", "dungeon-7", missing, 20, 1000.0, at(0)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("priest", 21, 1000.0, &[Role::Healer])], at(1)).unwrap();
+    matchmaker.enqueue("crypt", vec![player("tank", 20, 1000.0, &[Role::Tank])], at(2)).unwrap();
+
+    let events = matchmaker.tick(&instances, at(5)).await;
+    let matched = matched_groups(&events);
+    assert_eq!(matched.len(), 1);
+    assert!(matched[0].backfill);
+    assert_eq!(matched[0].instance_id, "dungeon-7");
+    assert_eq!(instances.admitted.lock().unwrap()[0], ("dungeon-7".to_string(), vec!["priest".to_string()]));
+
+    // The dps slot stays open until filled or cancelled
+    matchmaker.enqueue("crypt", vec![player("rogue", 19, 1000.0, &[Role::Dps])], at(6)).unwrap();
+    let events = matchmaker.tick(&instances, at(7)).await;
+    assert_eq!(role_of(matched_groups(&events)[0], "rogue"), Role::Dps);
+    assert!(!matchmaker.cancel_backfill("dungeon-7"));
+    assert_eq!(matchmaker.metrics("crypt").unwrap().backfilled, 2);
+
+    // Queues without backfill refuse requests
+    let mut queue = dungeon();
+    queue.backfill = false;
+    let mut matchmaker = Matchmaker::new(vec![queue]).unwrap();
+    assert!(matchmaker.request_backfill("crypt", "dungeon-7", missing, 20, 1000.0, at(0)).is_err());
+}
+
+#[test]
+fn invalid_queues_are_rejected() {
+    let mut queue = dungeon();
+    queue.roles = RoleCounts::default();
+    assert!(matches!(Matchmaker::new(vec![queue]), Err(EventCoreError::InvalidQueue { .. })));
+    assert!(Matchmaker::new(vec![dungeon(), dungeon()]).is_err());
+    assert!(dungeon().validate().is_ok());
+}
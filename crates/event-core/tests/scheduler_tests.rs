@@ -0,0 +1,131 @@
+//! Scheduler tests: recurrences, run lifecycles, pausing, triggers and resuming.
+
+mod common;
+
+use common::at;
+use event_core::{advance, EventCoreError, EventSchedule, Recurrence, ScheduleCheckpoint, Transition};
+use shared::Timestamp;
+
+/// A 10 minute invasion every hour from `at(0)`
+fn invasion() -> EventSchedule {
+    EventSchedule {
+        id: "invasion".to_string(),
+        name: "Goblin Invasion".to_string(),
+        zone: Some("forest".to_string()),
+        recurrence: Recurrence::Every { start: at(0), interval_secs: 3600 },
+        duration_secs: 600,
+        paused: false,
+        trigger_requested_at: None,
+    }
+}
+
+fn started(transitions: &[Transition]) -> Vec<Timestamp> {
+    transitions
+        .iter()
+        .filter_map(|transition| match transition {
+            Transition::Started(run) => Some(run.started_at),
+            Transition::Ended(_) => None,
+        })
+        .collect()
+}
+
+fn ended(transitions: &[Transition]) -> usize {
+    transitions.iter().filter(|transition| matches!(transition, Transition::Ended(_))).count()
+}
+
+#[test]
+fn recurrences_find_their_next_start() {
+    let every = Recurrence::Every { start: at(0), interval_secs: 60 };
+    assert_eq!(every.next_at_or_after(at(-500)), Some(at(0)));
+    assert_eq!(every.next_at_or_after(at(60)), Some(at(60)));
+    assert_eq!(every.next_at_or_after(at(61)), Some(at(120)));
+    assert_eq!(every.next_after(at(60)), Some(at(120)));
+
+    let once = Recurrence::Once { at: at(100) };
+    assert_eq!(once.next_at_or_after(at(100)), Some(at(100)));
+    assert_eq!(once.next_after(at(100)), None);
+}
+
+#[test]
+fn runs_start_and_end_on_schedule() {
+    let schedule = invasion();
+    let mut checkpoint = ScheduleCheckpoint::new(&schedule, at(-10));
+
+    assert!(advance(&schedule, &mut checkpoint, at(-1)).is_empty());
+    let transitions = advance(&schedule, &mut checkpoint, at(0));
+    assert_eq!(started(&transitions), vec![at(0)]);
+    let run = checkpoint.active.clone().unwrap();
+    assert_eq!(run.run_id, format!("invasion:{}", at(0).timestamp_millis()));
+    assert_eq!(run.ends_at, at(600));
+    assert_eq!(checkpoint.next_start, Some(at(3600)));
+
+    assert!(advance(&schedule, &mut checkpoint, at(300)).is_empty());
+    assert_eq!(ended(&advance(&schedule, &mut checkpoint, at(600))), 1);
+    assert!(checkpoint.active.is_none());
+}
+
+#[test]
+fn resumed_checkpoints_start_late_runs_and_skip_missed_ones() {
+    let schedule = invasion();
+
+    // First seen in the middle of a run: it starts late and keeps its end
+    let mut checkpoint = ScheduleCheckpoint::new(&schedule, at(300));
+    let transitions = advance(&schedule, &mut checkpoint, at(300));
+    assert_eq!(started(&transitions), vec![at(0)]);
+    assert_eq!(checkpoint.active.as_ref().unwrap().ends_at, at(600));
+
+    // Down for hours: the run in progress ends and the missed runs are skipped
+    let transitions = advance(&schedule, &mut checkpoint, at(3 * 3600 + 700));
+    assert_eq!(ended(&transitions), 1);
+    assert!(started(&transitions).is_empty());
+    assert_eq!(checkpoint.next_start, Some(at(4 * 3600)));
+
+    // Checkpoints survive a round trip through storage
+    let stored = serde_json::to_string(&checkpoint).unwrap();
+    assert_eq!(serde_json::from_str::<ScheduleCheckpoint>(&stored).unwrap(), checkpoint);
+}
+
+#[test]
+fn paused_schedules_skip_runs_but_honor_triggers_once() {
+    let mut schedule = invasion();
+    schedule.paused = true;
+    let mut checkpoint = ScheduleCheckpoint::new(&schedule, at(-10));
+
+    assert!(advance(&schedule, &mut checkpoint, at(0)).is_empty());
+    assert_eq!(checkpoint.next_start, Some(at(3600)));
+
+    schedule.trigger_requested_at = Some(at(100));
+    let transitions = advance(&schedule, &mut checkpoint, at(120));
+    assert_eq!(started(&transitions), vec![at(120)]);
+    let run = checkpoint.active.clone().unwrap();
+    assert!(run.triggered);
+    assert_eq!(run.ends_at, at(720));
+
+    // The same request is not handled twice
+    assert_eq!(ended(&advance(&schedule, &mut checkpoint, at(800))), 1);
+    assert!(advance(&schedule, &mut checkpoint, at(900)).is_empty());
+}
+
+#[test]
+fn one_off_schedules_run_once() {
+    let mut schedule = invasion();
+    schedule.recurrence = Recurrence::Once { at: at(50) };
+    let mut checkpoint = ScheduleCheckpoint::new(&schedule, at(0));
+
+    assert_eq!(started(&advance(&schedule, &mut checkpoint, at(60))), vec![at(50)]);
+    assert_eq!(checkpoint.next_start, None);
+    assert_eq!(ended(&advance(&schedule, &mut checkpoint, at(650))), 1);
+    assert!(advance(&schedule, &mut checkpoint, at(10_000)).is_empty());
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    let mut schedule = invasion();
+    schedule.duration_secs = 0;
+    assert!(matches!(schedule.validate(), Err(EventCoreError::InvalidSchedule { .. })));
+
+    let mut schedule = invasion();
+    schedule.recurrence = Recurrence::Every { start: at(0), interval_secs: 0 };
+    assert!(schedule.validate().is_err());
+    assert!(invasion().validate().is_ok());
+}
@@ -0,0 +1,122 @@
+//! Tutorial tests: steps triggered by player actions, hints, skipping and resuming, and
+//! the hidden quest chain tracking completion.
+
+mod common;
+
+use common::at;
+use event_core::{
+    chain_id, quest_id, ActionKind, EventCoreError, PlayerAction, StepTrigger, TutorialDefinition, TutorialStatus,
+    TutorialStep, TutorialUpdate, Tutorials, UiHint,
+};
+
+fn step(id: &str, action: ActionKind, subject: Option<&str>, count: u32) -> TutorialStep {
+    TutorialStep {
+        id: id.to_string(),
+        trigger: StepTrigger { action, subject: subject.map(str::to_string), count },
+        hint: UiHint { text: format!("Hint for {}", id), anchor: None },
+    }
+}
+
+/// Kill two wolves, loot anything, then talk to the elder
+fn first_steps() -> TutorialDefinition {
+    TutorialDefinition {
+        id: "first_steps".to_string(),
+        steps: vec![
+            step("hunt", ActionKind::Kill, Some("wolf"), 2),
+            step("loot", ActionKind::Loot, None, 1),
+            step("report", ActionKind::TalkTo, Some("elder"), 1),
+        ],
+        skippable: true,
+    }
+}
+
+fn shown_steps(updates: &[TutorialUpdate]) -> Vec<&str> {
+    updates
+        .iter()
+        .filter_map(|update| match update {
+            TutorialUpdate::ShowHint { step_id, .. } => Some(step_id.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn steps_complete_as_hidden_quests_in_order() {
+    let mut tutorials = Tutorials::new(vec![first_steps()]).unwrap();
+    let started = tutorials.start("hero", "user-1", "first_steps", at(0)).unwrap();
+    assert_eq!(shown_steps(&started.updates), ["hunt"]);
+    assert_eq!(started.progress.len(), 1);
+
+    // Only matching actions count towards the current step
+    let kill = |subject: &str| PlayerAction::new(ActionKind::Kill, Some(subject));
+    assert!(tutorials.record("hero", &kill("boar"), at(1)).progress.is_empty());
+    assert!(tutorials.record("hero", &PlayerAction::new(ActionKind::Loot, None), at(1)).updates.is_empty());
+    assert!(tutorials.record("hero", &kill("wolf"), at(2)).updates.is_empty());
+    let hunted = tutorials.record("hero", &kill("wolf"), at(3));
+    assert_eq!(
+        hunted.updates[0],
+        TutorialUpdate::QuestCompleted {
+            actor_id: "hero".to_string(),
+            tutorial_id: "first_steps".to_string(),
+            quest_id: quest_id("first_steps", "hunt"),
+        }
+    );
+    assert_eq!(shown_steps(&hunted.updates), ["loot"]);
+
+    tutorials.record("hero", &PlayerAction::new(ActionKind::Loot, Some("wolf_pelt")), at(4));
+    let done = tutorials.record("hero", &PlayerAction::new(ActionKind::TalkTo, Some("elder")), at(5));
+    assert!(done.updates.contains(&TutorialUpdate::ChainCompleted {
+        actor_id: "hero".to_string(),
+        tutorial_id: "first_steps".to_string(),
+        chain_id: chain_id("first_steps"),
+    }));
+    assert!(matches!(done.updates.last(), Some(TutorialUpdate::HideHint { .. })));
+
+    let progress = tutorials.progress("hero", "first_steps").unwrap();
+    assert_eq!(progress.status, TutorialStatus::Completed);
+    assert_eq!(progress.completed_quests.len(), 3);
+    assert!(tutorials.record("hero", &kill("wolf"), at(6)).updates.is_empty());
+    assert!(tutorials.start("hero", "user-1", "first_steps", at(7)).unwrap().updates.is_empty());
+}
+
+#[test]
+fn skipped_tutorials_resume_where_they_were_left() {
+    let mut tutorials = Tutorials::new(vec![first_steps()]).unwrap();
+    tutorials.start("hero", "user-1", "first_steps", at(0)).unwrap();
+    tutorials.record("hero", &PlayerAction::new(ActionKind::Kill, Some("wolf")), at(1));
+
+    let skipped = tutorials.skip("hero", "first_steps", at(2)).unwrap();
+    assert!(matches!(skipped.updates[..], [TutorialUpdate::HideHint { .. }]));
+    assert!(tutorials.record("hero", &PlayerAction::new(ActionKind::Kill, Some("wolf")), at(3)).updates.is_empty());
+
+    let resumed = tutorials.resume("hero", "first_steps", at(4)).unwrap();
+    assert_eq!(shown_steps(&resumed.updates), ["hunt"]);
+    let hunted = tutorials.record("hero", &PlayerAction::new(ActionKind::Kill, Some("wolf")), at(5));
+    assert_eq!(shown_steps(&hunted.updates), ["loot"]);
+
+    // Progress survives a restart of the host
+    let stored = tutorials.progress("hero", "first_steps").unwrap().clone();
+    let mut restarted = Tutorials::new(vec![first_steps()]).unwrap();
+    restarted.restore(stored);
+    let reconnected = restarted.start("hero", "user-2", "first_steps", at(6)).unwrap();
+    assert_eq!(shown_steps(&reconnected.updates), ["loot"]);
+    assert_eq!(reconnected.progress[0].user_id, "user-2");
+}
+
+#[test]
+fn tutorials_are_validated() {
+    let mut mandatory = first_steps();
+    mandatory.skippable = false;
+    let mut tutorials = Tutorials::new(vec![mandatory]).unwrap();
+    tutorials.start("hero", "user-1", "first_steps", at(0)).unwrap();
+    assert!(matches!(tutorials.skip("hero", "first_steps", at(1)), Err(EventCoreError::InvalidTutorial { .. })));
+    assert!(matches!(tutorials.resume("rookie", "first_steps", at(1)), Err(EventCoreError::TutorialNotFound(_))));
+    assert!(matches!(tutorials.start("hero", "user-1", "crafting", at(1)), Err(EventCoreError::TutorialNotFound(_))));
+
+    let mut repeated = first_steps();
+    repeated.steps.push(step("hunt", ActionKind::Kill, None, 1));
+    assert!(Tutorials::new(vec![repeated]).is_err());
+    let mut impossible = first_steps();
+    impossible.steps[1].trigger.count = 0;
+    assert!(Tutorials::new(vec![impossible]).is_err());
+}
@@ -0,0 +1,34 @@
+//! Error types for item-core.
+
+use thiserror::Error;
+
+/// Errors raised while distributing loot or infusing items
+#[derive(Debug, Error)]
+pub enum ItemCoreError {
+    /// A party that does not exist
+    #[error("Party not found: {0}")]
+    PartyNotFound(String),
+
+    /// A drop that cannot be distributed
+    #[error("Invalid drop for party {party}: {reason}")]
+    InvalidDrop { party: String, reason: String },
+
+    /// A looted item that does not exist or was forgotten
+    #[error("Loot not found: {0}")]
+    LootNotFound(String),
+
+    /// A member that was not eligible for the drop
+    #[error("{actor} is not eligible for loot {loot}")]
+    NotEligible { loot: String, actor: String },
+
+    /// An action the item's distribution does not allow right now
+    #[error("Cannot {action} loot {loot}: {reason}")]
+    InvalidLootAction { loot: String, action: &'static str, reason: String },
+
+    /// An infusion that cannot be attempted, e.g. for lack of qi
+    #[error("Cannot infuse {item}: {reason}")]
+    InvalidInfusion { item: String, reason: String },
+}
+
+/// Result type for item-core operations
+pub type ItemCoreResult<T> = Result<T, ItemCoreError>;
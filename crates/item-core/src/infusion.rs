@@ -0,0 +1,320 @@
+//! Elemental infusion: spending an element's qi and mastery to imbue crafted items.
+//!
+//! An [`Infuser`] adds an [`ElementalAffix`] of an element and tier to an [`InfusedItem`].
+//! Each attempt costs qi and mastery experience of the element, both growing with the tier.
+//! The odds come from element-core's probability engine: the element's sigmoid over the
+//! crafter's mastery surplus, in tiers, above what the tier requires. A failed attempt adds
+//! nothing and consumes part of the cost.
+//!
+//! Equipped infused items contribute their affixes' stats back to element-core through
+//! [`InfusedEquipment`], an [`ElementContributor`].
+
+use crate::error::{ItemCoreError, ItemCoreResult};
+use actor_core::Actor;
+use async_trait::async_trait;
+use element_core::unified_registry::UnifiedElementRegistry;
+use element_core::{
+    ElementContribution, ElementContributor, ElementCoreResult, ElementEvent, ElementalSystem, ProbabilityConfig,
+    MAX_ELEMENTS,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Contributor ID of equipped infused items
+pub const INFUSION_SYSTEM_ID: &str = "item_core";
+
+/// Contributor priority of equipped infused items, that of equipment bonuses
+pub const INFUSION_PRIORITY: i64 = 800;
+
+/// Infusion costs, limits and affix strength
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfusionConfig {
+    /// Highest affix tier
+    pub max_tier: u8,
+    /// Most affixes one item holds
+    pub max_affixes: usize,
+    /// Qi of the element spent per tier
+    pub qi_per_tier: f64,
+    /// Mastery experience of the element spent per tier
+    pub experience_per_tier: f64,
+    /// Mastery a tier requires; each tier of surplus improves the odds
+    pub mastery_per_tier: f64,
+    /// Share of the cost a failed attempt consumes
+    pub failure_cost: f64,
+    /// Element stats an affix adds per tier, e.g. `power_point`
+    pub affix_stats: HashMap<String, f64>,
+}
+
+impl Default for InfusionConfig {
+    fn default() -> Self {
+        Self {
+            max_tier: 5,
+            max_affixes: 2,
+            qi_per_tier: 50.0,
+            experience_per_tier: 100.0,
+            mastery_per_tier: 500.0,
+            failure_cost: 0.5,
+            affix_stats: HashMap::from([("power_point".to_string(), 10.0), ("crit_rate".to_string(), 0.01)]),
+        }
+    }
+}
+
+/// An element imbued into an item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementalAffix {
+    pub element_id: String,
+    pub tier: u8,
+    /// Element stats the affix adds, fixed when it was infused
+    pub stats: HashMap<String, f64>,
+}
+
+/// A crafted item and the affixes infused into it
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InfusedItem {
+    pub item_id: String,
+    pub affixes: Vec<ElementalAffix>,
+}
+
+impl InfusedItem {
+    pub fn new(item_id: impl Into<String>) -> Self {
+        Self { item_id: item_id.into(), affixes: Vec::new() }
+    }
+
+    /// Affix of an element, if infused
+    pub fn affix(&self, element_id: &str) -> Option<&ElementalAffix> {
+        self.affixes.iter().find(|affix| affix.element_id == element_id)
+    }
+
+    /// Element stats the item adds for an element
+    pub fn element_stats(&self, element_id: &str) -> HashMap<String, f64> {
+        let mut stats = HashMap::new();
+        for affix in self.affixes.iter().filter(|affix| affix.element_id == element_id) {
+            for (stat, value) in &affix.stats {
+                *stats.entry(stat.clone()).or_insert(0.0) += value;
+            }
+        }
+        stats
+    }
+}
+
+/// Result of an infusion attempt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InfusionOutcome {
+    pub item_id: String,
+    pub element_id: String,
+    pub tier: u8,
+    /// Odds the attempt had
+    pub chance: f64,
+    /// Affix added, `None` when the attempt failed
+    pub affix: Option<ElementalAffix>,
+    pub qi_spent: f64,
+    pub experience_spent: f64,
+}
+
+impl InfusionOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.affix.is_some()
+    }
+}
+
+/// Infuses items with elements, spending the crafter's qi and mastery
+pub struct Infuser {
+    registry: Arc<UnifiedElementRegistry>,
+    probability: ProbabilityConfig,
+    config: InfusionConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl Infuser {
+    pub fn new(registry: Arc<UnifiedElementRegistry>, probability: ProbabilityConfig, config: InfusionConfig) -> Self {
+        Self { registry, probability, config, rng: Mutex::new(StdRng::from_entropy()) }
+    }
+
+    /// Roll with a seeded random source, for reproducible attempts
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        *self.rng.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()) = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    pub fn config(&self) -> &InfusionConfig {
+        &self.config
+    }
+
+    /// Odds of infusing an element at `tier` with the crafter's current mastery, `None` for
+    /// unknown elements
+    pub fn chance(&self, crafter: &ElementalSystem, element_id: &str, tier: u8) -> Option<f64> {
+        let index = self.element_index(element_id)?;
+        Some(self.chance_at(crafter, element_id, index, tier))
+    }
+
+    /// Try to infuse `item` with an element at `tier`, spending the crafter's qi and
+    /// mastery experience of the element
+    ///
+    /// Fails without spending anything when the item cannot take the affix or the crafter
+    /// cannot pay the full cost. A failed roll is not an error: the outcome carries no affix
+    /// and the share of the cost that was consumed.
+    pub fn infuse(
+        &self,
+        crafter: &mut ElementalSystem,
+        item: &mut InfusedItem,
+        element_id: &str,
+        tier: u8,
+    ) -> ItemCoreResult<InfusionOutcome> {
+        let invalid = |reason: String| ItemCoreError::InvalidInfusion { item: item.item_id.clone(), reason };
+        let index = self.element_index(element_id).ok_or_else(|| invalid(format!("Unknown element {}", element_id)))?;
+        if tier == 0 || tier > self.config.max_tier {
+            return Err(invalid(format!("Tier {} is not between 1 and {}", tier, self.config.max_tier)));
+        }
+        if item.affix(element_id).is_some() {
+            return Err(invalid(format!("The item is already infused with {}", element_id)));
+        }
+        if item.affixes.len() >= self.config.max_affixes {
+            return Err(invalid(format!("The item already holds {} affixes", item.affixes.len())));
+        }
+
+        let qi_cost = self.config.qi_per_tier * tier as f64;
+        let experience_cost = self.config.experience_per_tier * tier as f64;
+        let data = crafter.get_data();
+        let (qi, experience) = (data.element_qi_amounts[index], data.element_mastery_experience[index]);
+        if qi < qi_cost {
+            return Err(invalid(format!("Not enough {} qi: {} needed, {} available", element_id, qi_cost, qi)));
+        }
+        if experience < experience_cost {
+            return Err(invalid(format!(
+                "Not enough {} mastery experience: {} needed, {} available",
+                element_id, experience_cost, experience
+            )));
+        }
+
+        let chance = self.chance_at(crafter, element_id, index, tier);
+        let succeeded = self.rng().gen::<f64>() < chance;
+        let share = if succeeded { 1.0 } else { self.config.failure_cost.clamp(0.0, 1.0) };
+        let (qi_spent, experience_spent) = (qi_cost * share, experience_cost * share);
+        {
+            let data = crafter.get_data_mut();
+            data.element_qi_amounts[index] = qi - qi_spent;
+            data.element_mastery_experience[index] = experience - experience_spent;
+        }
+        crafter.update_element_mastery_level(index);
+
+        let affix = succeeded.then(|| ElementalAffix {
+            element_id: element_id.to_string(),
+            tier,
+            stats: self.config.affix_stats.iter().map(|(stat, value)| (stat.clone(), value * tier as f64)).collect(),
+        });
+        if let Some(affix) = &affix {
+            item.affixes.push(affix.clone());
+        }
+        Ok(InfusionOutcome {
+            item_id: item.item_id.clone(),
+            element_id: element_id.to_string(),
+            tier,
+            chance,
+            affix,
+            qi_spent,
+            experience_spent,
+        })
+    }
+
+    /// Sigmoid of the crafter's mastery surplus, in tiers, above what `tier` requires
+    fn chance_at(&self, crafter: &ElementalSystem, element_id: &str, index: usize, tier: u8) -> f64 {
+        let mastery = crafter.get_data().element_mastery_levels[index];
+        let required = self.config.mastery_per_tier * tier as f64;
+        let surplus = if self.config.mastery_per_tier > 0.0 {
+            (mastery - required) / self.config.mastery_per_tier
+        } else {
+            mastery
+        };
+        self.probability.probability(element_id, surplus)
+    }
+
+    fn element_index(&self, element_id: &str) -> Option<usize> {
+        self.registry.get_element_index(element_id).ok().flatten().filter(|index| *index < MAX_ELEMENTS)
+    }
+
+    fn rng(&self) -> MutexGuard<'_, StdRng> {
+        self.rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Infused items each actor has equipped, contributing their affixes to element stats
+#[derive(Default)]
+pub struct InfusedEquipment {
+    equipped: Mutex<HashMap<String, Vec<InfusedItem>>>,
+}
+
+impl InfusedEquipment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Equip an item, replacing the equipped item of the same ID
+    pub fn equip(&self, actor_id: &str, item: InfusedItem) {
+        let mut equipped = self.equipped();
+        let items = equipped.entry(actor_id.to_string()).or_default();
+        items.retain(|equipped| equipped.item_id != item.item_id);
+        items.push(item);
+    }
+
+    /// Unequip an item, returning it
+    pub fn unequip(&self, actor_id: &str, item_id: &str) -> Option<InfusedItem> {
+        let mut equipped = self.equipped();
+        let items = equipped.get_mut(actor_id)?;
+        let position = items.iter().position(|item| item.item_id == item_id)?;
+        let item = items.remove(position);
+        if items.is_empty() {
+            equipped.remove(actor_id);
+        }
+        Some(item)
+    }
+
+    pub fn equipped_items(&self, actor_id: &str) -> Vec<InfusedItem> {
+        self.equipped().get(actor_id).cloned().unwrap_or_default()
+    }
+
+    /// Element stats an actor's equipped items add for an element
+    pub fn element_stats(&self, actor_id: &str, element_id: &str) -> HashMap<String, f64> {
+        let mut stats = HashMap::new();
+        for item in self.equipped().get(actor_id).into_iter().flatten() {
+            for (stat, value) in item.element_stats(element_id) {
+                *stats.entry(stat).or_insert(0.0) += value;
+            }
+        }
+        stats
+    }
+
+    fn equipped(&self) -> MutexGuard<'_, HashMap<String, Vec<InfusedItem>>> {
+        self.equipped.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl ElementContributor for InfusedEquipment {
+    fn system_id(&self) -> &str {
+        INFUSION_SYSTEM_ID
+    }
+
+    fn priority(&self) -> i64 {
+        INFUSION_PRIORITY
+    }
+
+    async fn contribute_element_stats(
+        &self,
+        actor: &Actor,
+        element_type: &str,
+    ) -> ElementCoreResult<ElementContribution> {
+        Ok(ElementContribution::new(
+            INFUSION_SYSTEM_ID.to_string(),
+            element_type.to_string(),
+            self.element_stats(&actor.id, element_type),
+            INFUSION_PRIORITY,
+        ))
+    }
+
+    async fn handle_element_event(&self, _event: &ElementEvent) -> ElementCoreResult<()> {
+        Ok(())
+    }
+}
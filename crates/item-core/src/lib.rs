@@ -0,0 +1,14 @@
+//! Item Core - Item generation, properties, and inventory management.
+//!
+//! This crate provides the distribution of group loot and the elemental infusion of
+//! crafted items in the Chaos World MMORPG; item generation and inventories are still to
+//! come.
+
+pub mod loot;
+pub mod infusion;
+pub mod error;
+
+// Re-export commonly used types
+pub use loot::*;
+pub use infusion::*;
+pub use error::*;
@@ -0,0 +1,455 @@
+//! Group loot: sharing out what a party looted, decided on the server.
+//!
+//! [`GroupLoot`] applies the party's [`LootRules`] to every item of a [`PartyDrop`]. Items
+//! below the rarity threshold, and every item under free-for-all, go to the first eligible
+//! member claiming them. At or above it:
+//! - round-robin awards the item to the next eligible member in turn;
+//! - master loot waits for the master looter to hand the item out;
+//! - need before greed opens a roll. Members choose need, greed or pass and the server
+//!   rolls 1-100 for them. The roll resolves once every eligible member chose, or when its
+//!   timer runs out on [`GroupLoot::tick`]; the best need roll beats the best greed roll,
+//!   and items everyone passed on become free for all.
+//!
+//! Only members eligible for a drop, e.g. near the kill, take part in it. A winner may hand
+//! the item to another eligible member within the trade window, even when it would bind
+//! otherwise. Every step goes out on [`PARTY_LOOT`] so all members see the results.
+
+use crate::error::{ItemCoreError, ItemCoreResult};
+use chrono::Duration;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use shared::clock::SharedClock;
+use shared::events::topics::{PartyLootUpdate, PartyLootUpdated, PARTY_LOOT};
+use shared::events::{EventBus, EventBusExt};
+use shared::party::{LootMethod, LootRules, PartyManager, RollChoice};
+use shared::types::Timestamp;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Highest number a roll can come up with
+const MAX_ROLL: u32 = 100;
+
+/// Timers of group loot
+#[derive(Debug, Clone)]
+pub struct LootConfig {
+    /// How long members have to choose need, greed or pass
+    pub roll_secs: u64,
+    /// How long a winner may hand the item to another eligible member
+    pub trade_window_secs: u64,
+}
+
+impl Default for LootConfig {
+    fn default() -> Self {
+        Self { roll_secs: 60, trade_window_secs: 7200 }
+    }
+}
+
+/// An item dropped for a party
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootItem {
+    pub item_id: String,
+    pub quantity: u32,
+    /// Rarity tier, compared with the party's [`LootRules::rarity_threshold`]
+    pub rarity: u8,
+}
+
+/// Items dropped for a party, e.g. by a boss it killed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyDrop {
+    pub party_id: String,
+    /// What dropped the items, e.g. the NPC template
+    pub source: String,
+    pub items: Vec<LootItem>,
+    /// Members that may loot the drop
+    pub eligible: Vec<String>,
+}
+
+/// A member's choice in a roll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Roll {
+    pub actor_id: String,
+    pub choice: RollChoice,
+    /// Rolled by the server; passes have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roll: Option<u32>,
+}
+
+/// Where a looted item stands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LootStatus {
+    Rolling { ends_at: Timestamp },
+    AwaitingMasterLooter { master_looter: String },
+    FreeForAll,
+    /// `actor_id` holds the item; it was awarded at `at`
+    Awarded { actor_id: String, at: Timestamp },
+}
+
+/// An item a party looted and how it is being distributed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyLoot {
+    pub loot_id: String,
+    pub party_id: String,
+    pub source: String,
+    pub item: LootItem,
+    pub eligible: Vec<String>,
+    /// Method applied to the item; free for all below the rarity threshold
+    pub method: LootMethod,
+    pub status: LootStatus,
+    pub rolls: Vec<Roll>,
+    pub dropped_at: Timestamp,
+}
+
+impl PartyLoot {
+    /// Member holding the item once awarded
+    pub fn holder(&self) -> Option<&str> {
+        match &self.status {
+            LootStatus::Awarded { actor_id, .. } => Some(actor_id),
+            _ => None,
+        }
+    }
+
+    pub fn is_eligible(&self, actor_id: &str) -> bool {
+        self.eligible.iter().any(|eligible| eligible == actor_id)
+    }
+
+    fn require_eligible(&self, actor_id: &str) -> ItemCoreResult<()> {
+        if self.is_eligible(actor_id) {
+            Ok(())
+        } else {
+            Err(ItemCoreError::NotEligible { loot: self.loot_id.clone(), actor: actor_id.to_string() })
+        }
+    }
+
+    fn refuse(&self, action: &'static str, reason: impl Into<String>) -> ItemCoreError {
+        ItemCoreError::InvalidLootAction { loot: self.loot_id.clone(), action, reason: reason.into() }
+    }
+
+    /// Award the item to the best need roll, else the best greed roll, else to whoever
+    /// claims it first
+    fn resolve(&mut self, now: Timestamp) -> PartyLootUpdate {
+        let best = |wanted: RollChoice| {
+            self.rolls.iter().filter(|roll| roll.choice == wanted).max_by_key(|roll| roll.roll).cloned()
+        };
+        match best(RollChoice::Need).or_else(|| best(RollChoice::Greed)) {
+            Some(winner) => {
+                self.status = LootStatus::Awarded { actor_id: winner.actor_id.clone(), at: now };
+                PartyLootUpdate::Awarded {
+                    actor_id: winner.actor_id,
+                    method: self.method,
+                    choice: Some(winner.choice),
+                    roll: winner.roll,
+                }
+            }
+            None => {
+                self.status = LootStatus::FreeForAll;
+                PartyLootUpdate::FreeForAll
+            }
+        }
+    }
+
+    fn award(&mut self, actor_id: &str, now: Timestamp) -> PartyLootUpdate {
+        self.status = LootStatus::Awarded { actor_id: actor_id.to_string(), at: now };
+        PartyLootUpdate::Awarded { actor_id: actor_id.to_string(), method: self.method, choice: None, roll: None }
+    }
+}
+
+struct State {
+    loot: HashMap<String, PartyLoot>,
+    rng: StdRng,
+}
+
+/// Group loot of one process, announcing every step on the event bus
+pub struct GroupLoot {
+    parties: Arc<PartyManager>,
+    clock: SharedClock,
+    config: LootConfig,
+    bus: Option<(Arc<dyn EventBus>, String)>,
+    state: Mutex<State>,
+}
+
+impl GroupLoot {
+    pub fn new(parties: Arc<PartyManager>, clock: SharedClock, config: LootConfig) -> Self {
+        let state = State { loot: HashMap::new(), rng: StdRng::from_entropy() };
+        Self { parties, clock, config, bus: None, state: Mutex::new(state) }
+    }
+
+    /// Roll with a seeded random source, for reproducible rolls
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.state.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner()).rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Publish loot updates on `bus`, tagged with `source`
+    pub fn with_bus(mut self, bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        self.bus = Some((bus, source.into()));
+        self
+    }
+
+    /// Start distributing a drop by the party's current loot rules
+    pub async fn distribute(&self, mut drop: PartyDrop) -> ItemCoreResult<Vec<PartyLoot>> {
+        let party =
+            self.parties.get(&drop.party_id).ok_or_else(|| ItemCoreError::PartyNotFound(drop.party_id.clone()))?;
+        let invalid = |reason: String| ItemCoreError::InvalidDrop { party: drop.party_id.clone(), reason };
+        if drop.eligible.is_empty() {
+            return Err(invalid("Nobody is eligible for the drop".to_string()));
+        }
+        if let Some(stranger) = drop.eligible.iter().find(|actor_id| !party.is_member(actor_id)) {
+            return Err(invalid(format!("{} is not a member", stranger)));
+        }
+
+        let mut seen = HashSet::new();
+        drop.eligible.retain(|actor_id| seen.insert(actor_id.clone()));
+
+        let now = self.clock.now();
+        let mut looted = Vec::with_capacity(drop.items.len());
+        let mut updates = Vec::with_capacity(drop.items.len());
+        for item in drop.items {
+            let method = effective_method(&party.loot_rules, item.rarity);
+            let mut loot = PartyLoot {
+                loot_id: Uuid::new_v4().to_string(),
+                party_id: drop.party_id.clone(),
+                source: drop.source.clone(),
+                item,
+                eligible: drop.eligible.clone(),
+                method,
+                status: LootStatus::FreeForAll,
+                rolls: Vec::new(),
+                dropped_at: now,
+            };
+            let update = match (method, &party.loot_rules.master_looter) {
+                (LootMethod::RoundRobin, _) => {
+                    let looter = self.next_looter(&loot)?;
+                    loot.award(&looter, now)
+                }
+                (LootMethod::MasterLooter, Some(master_looter)) => {
+                    loot.status = LootStatus::AwaitingMasterLooter { master_looter: master_looter.clone() };
+                    PartyLootUpdate::AwaitingMasterLooter { master_looter: master_looter.clone() }
+                }
+                (LootMethod::NeedBeforeGreed, _) => {
+                    let ends_at = now + Duration::seconds(self.config.roll_secs as i64);
+                    loot.status = LootStatus::Rolling { ends_at };
+                    PartyLootUpdate::RollStarted { eligible: loot.eligible.clone(), ends_at }
+                }
+                (LootMethod::FreeForAll, _) | (LootMethod::MasterLooter, None) => PartyLootUpdate::FreeForAll,
+            };
+            updates.push((loot.clone(), update));
+            looted.push(loot);
+        }
+        {
+            let mut state = self.state();
+            for loot in &looted {
+                state.loot.insert(loot.loot_id.clone(), loot.clone());
+            }
+        }
+        for (loot, update) in updates {
+            self.publish(&loot, update).await;
+        }
+        Ok(looted)
+    }
+
+    /// Choose need, greed or pass on an item being rolled for; the server rolls for the
+    /// member and resolves the roll once every eligible member chose
+    pub async fn roll(&self, loot_id: &str, actor_id: &str, choice: RollChoice) -> ItemCoreResult<PartyLoot> {
+        let now = self.clock.now();
+        let (loot, updates) = {
+            let mut state = self.state();
+            let State { loot, rng } = &mut *state;
+            let loot = loot.get_mut(loot_id).ok_or_else(|| ItemCoreError::LootNotFound(loot_id.to_string()))?;
+            loot.require_eligible(actor_id)?;
+            match loot.status {
+                LootStatus::Rolling { ends_at } if now < ends_at => {}
+                _ => return Err(loot.refuse("roll on", "The item is not being rolled for")),
+            }
+            if loot.rolls.iter().any(|roll| roll.actor_id == actor_id) {
+                return Err(loot.refuse("roll on", format!("{} already rolled", actor_id)));
+            }
+            let roll = (choice != RollChoice::Pass).then(|| unique_roll(rng, &loot.rolls));
+            loot.rolls.push(Roll { actor_id: actor_id.to_string(), choice, roll });
+            let mut updates = vec![PartyLootUpdate::Rolled { actor_id: actor_id.to_string(), choice, roll }];
+            if loot.rolls.len() == loot.eligible.len() {
+                updates.push(loot.resolve(now));
+            }
+            (loot.clone(), updates)
+        };
+        for update in updates {
+            self.publish(&loot, update).await;
+        }
+        Ok(loot)
+    }
+
+    /// Hand an item out as the master looter
+    pub async fn assign(&self, loot_id: &str, by: &str, to: &str) -> ItemCoreResult<PartyLoot> {
+        let now = self.clock.now();
+        let (loot, update) = {
+            let mut state = self.state();
+            let loot = state.loot.get_mut(loot_id).ok_or_else(|| ItemCoreError::LootNotFound(loot_id.to_string()))?;
+            match &loot.status {
+                LootStatus::AwaitingMasterLooter { master_looter } if master_looter == by => {}
+                LootStatus::AwaitingMasterLooter { .. } => {
+                    return Err(loot.refuse("assign", format!("{} is not the master looter", by)))
+                }
+                _ => return Err(loot.refuse("assign", "The item is not awaiting the master looter")),
+            }
+            loot.require_eligible(to)?;
+            let update = loot.award(to, now);
+            (loot.clone(), update)
+        };
+        self.publish(&loot, update).await;
+        Ok(loot)
+    }
+
+    /// Pick up an item free for all
+    pub async fn claim(&self, loot_id: &str, actor_id: &str) -> ItemCoreResult<PartyLoot> {
+        let now = self.clock.now();
+        let (loot, update) = {
+            let mut state = self.state();
+            let loot = state.loot.get_mut(loot_id).ok_or_else(|| ItemCoreError::LootNotFound(loot_id.to_string()))?;
+            loot.require_eligible(actor_id)?;
+            if loot.status != LootStatus::FreeForAll {
+                return Err(loot.refuse("claim", "The item is not free for all"));
+            }
+            let update = loot.award(actor_id, now);
+            (loot.clone(), update)
+        };
+        self.publish(&loot, update).await;
+        Ok(loot)
+    }
+
+    /// Check whether `from` may still hand the item to `to`
+    pub fn can_trade(&self, loot_id: &str, from: &str, to: &str) -> bool {
+        let now = self.clock.now();
+        self.state().loot.get(loot_id).is_some_and(|loot| self.check_trade(loot, from, to, now).is_ok())
+    }
+
+    /// Hand an awarded item to another member eligible for its drop, within the trade
+    /// window opened when it was awarded
+    pub async fn trade(&self, loot_id: &str, from: &str, to: &str) -> ItemCoreResult<PartyLoot> {
+        let now = self.clock.now();
+        let loot = {
+            let mut state = self.state();
+            let loot = state.loot.get_mut(loot_id).ok_or_else(|| ItemCoreError::LootNotFound(loot_id.to_string()))?;
+            self.check_trade(loot, from, to, now)?;
+            if let LootStatus::Awarded { actor_id, .. } = &mut loot.status {
+                *actor_id = to.to_string();
+            }
+            loot.clone()
+        };
+        self.publish(&loot, PartyLootUpdate::Traded { from: from.to_string(), to: to.to_string() }).await;
+        Ok(loot)
+    }
+
+    /// Resolve the rolls whose timer ran out, members that did not choose passing, and
+    /// forget the items settled longer than the trade window ago. Returns the resolved items.
+    pub async fn tick(&self) -> Vec<PartyLoot> {
+        let now = self.clock.now();
+        let window = Duration::seconds(self.config.trade_window_secs as i64);
+        let resolved: Vec<(PartyLoot, PartyLootUpdate)> = {
+            let mut state = self.state();
+            state.loot.retain(|_, loot| match &loot.status {
+                LootStatus::Awarded { at, .. } => now < *at + window,
+                LootStatus::FreeForAll => now < loot.dropped_at + window,
+                _ => true,
+            });
+            state
+                .loot
+                .values_mut()
+                .filter(|loot| matches!(loot.status, LootStatus::Rolling { ends_at } if now >= ends_at))
+                .map(|loot| {
+                    let update = loot.resolve(now);
+                    (loot.clone(), update)
+                })
+                .collect()
+        };
+        for (loot, update) in &resolved {
+            self.publish(loot, update.clone()).await;
+        }
+        resolved.into_iter().map(|(loot, _)| loot).collect()
+    }
+
+    pub fn get(&self, loot_id: &str) -> Option<PartyLoot> {
+        self.state().loot.get(loot_id).cloned()
+    }
+
+    /// Items of a party still being distributed or within their trade window, oldest first
+    pub fn party_loot(&self, party_id: &str) -> Vec<PartyLoot> {
+        let mut loot: Vec<PartyLoot> =
+            self.state().loot.values().filter(|loot| loot.party_id == party_id).cloned().collect();
+        loot.sort_by_key(|loot| loot.dropped_at);
+        loot
+    }
+
+    /// Next member in round-robin order that is eligible for the item
+    fn next_looter(&self, loot: &PartyLoot) -> ItemCoreResult<String> {
+        let party_id = &loot.party_id;
+        let members = self.parties.get(party_id).map(|party| party.members.len()).unwrap_or_default();
+        for _ in 0..members {
+            let looter =
+                self.parties.next_looter(party_id).map_err(|_| ItemCoreError::PartyNotFound(party_id.clone()))?;
+            if loot.is_eligible(&looter) {
+                return Ok(looter);
+            }
+        }
+        Err(ItemCoreError::PartyNotFound(party_id.clone()))
+    }
+
+    fn check_trade(&self, loot: &PartyLoot, from: &str, to: &str, now: Timestamp) -> ItemCoreResult<()> {
+        let LootStatus::Awarded { actor_id, at } = &loot.status else {
+            return Err(loot.refuse("trade", "The item was not awarded yet"));
+        };
+        if actor_id != from {
+            return Err(loot.refuse("trade", format!("{} does not hold the item", from)));
+        }
+        if from == to {
+            return Err(loot.refuse("trade", "The item already belongs to the receiver"));
+        }
+        if now >= *at + Duration::seconds(self.config.trade_window_secs as i64) {
+            return Err(loot.refuse("trade", "The trade window has closed"));
+        }
+        loot.require_eligible(to)
+    }
+
+    async fn publish(&self, loot: &PartyLoot, update: PartyLootUpdate) {
+        let Some((bus, source)) = &self.bus else {
+            return;
+        };
+        let message = PartyLootUpdated {
+            party_id: loot.party_id.clone(),
+            loot_id: loot.loot_id.clone(),
+            item_id: loot.item.item_id.clone(),
+            quantity: loot.item.quantity,
+            update,
+            at: self.clock.now(),
+        };
+        if let Err(e) = bus.publish_message(source, &PARTY_LOOT, &message).await {
+            warn!("Failed to publish loot update of party {}: {}", loot.party_id, e);
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Method applied to an item of the given rarity
+fn effective_method(rules: &LootRules, rarity: u8) -> LootMethod {
+    if rarity < rules.rarity_threshold {
+        LootMethod::FreeForAll
+    } else {
+        rules.method
+    }
+}
+
+/// Roll 1-100, rerolling numbers already taken so rolls never tie
+fn unique_roll(rng: &mut StdRng, rolls: &[Roll]) -> u32 {
+    let taken = rolls.iter().filter(|roll| roll.roll.is_some()).count() as u32;
+    loop {
+        let roll = rng.gen_range(1..=MAX_ROLL);
+        if taken >= MAX_ROLL || !rolls.iter().any(|other| other.roll == Some(roll)) {
+            return roll;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Elemental infusion tests: odds from mastery, costs, failures and equipped affixes
+//! contributing to element stats.
+
+use actor_core::Actor;
+use element_core::unified_registry::{ElementCategory, ElementDefinition, PhysicalElement, UnifiedElementRegistry};
+use element_core::config::yaml_loader::SigmoidConfig;
+use element_core::{ElementContributor, ElementalSystem, ProbabilityConfig};
+use item_core::{InfusedEquipment, InfusedItem, InfusionConfig, Infuser, ItemCoreError};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+async fn create_test_registry() -> Arc<UnifiedElementRegistry> {
+    let registry = UnifiedElementRegistry::new();
+    for (id, name, kind) in [("fire", "Fire", PhysicalElement::Fire), ("water", "Water", PhysicalElement::Water)] {
+        let description = format!("{} element", name);
+        let category = ElementCategory::Physical(kind);
+        let element = ElementDefinition::new(id.to_string(), name.to_string(), description, category);
+        registry.register_element(element).await.unwrap();
+    }
+    Arc::new(registry)
+}
+
+fn create_test_infuser(registry: Arc<UnifiedElementRegistry>) -> Infuser {
+    // Steep enough that a few tiers of surplus or shortfall settle the roll
+    let probability = ProbabilityConfig {
+        version: 1,
+        sigmoid: SigmoidConfig { default_steepness: 4.0, default_midpoint: 0.0, element_configs: HashMap::new() },
+        custom_functions: HashMap::new(),
+    };
+    Infuser::new(registry, probability, InfusionConfig::default()).with_seed(7)
+}
+
+/// A crafter with fire mastery `mastery` and plenty of qi and experience
+fn crafter(fire: usize, mastery: f64) -> ElementalSystem {
+    let mut system = ElementalSystem::new();
+    let data = system.get_data_mut();
+    data.element_mastery_levels[fire] = mastery;
+    data.element_qi_amounts[fire] = 1000.0;
+    data.element_mastery_experience[fire] = 1000.0;
+    system
+}
+
+#[tokio::test]
+async fn odds_follow_mastery_above_the_tier_requirement() {
+    let registry = create_test_registry().await;
+    let fire = registry.get_element_index("fire").unwrap().unwrap();
+    let infuser = create_test_infuser(registry);
+    let novice = crafter(fire, 500.0);
+    let master = crafter(fire, 3000.0);
+
+    // Exactly the mastery a tier requires gives even odds
+    assert!((infuser.chance(&novice, "fire", 1).unwrap() - 0.5).abs() < 1e-9);
+    assert!(infuser.chance(&novice, "fire", 3).unwrap() < infuser.chance(&novice, "fire", 1).unwrap());
+    assert!(infuser.chance(&master, "fire", 3).unwrap() > 0.99);
+    assert_eq!(infuser.chance(&master, "void", 1), None);
+}
+
+#[tokio::test]
+async fn successful_infusions_spend_the_full_cost() {
+    let registry = create_test_registry().await;
+    let fire = registry.get_element_index("fire").unwrap().unwrap();
+    let infuser = create_test_infuser(registry);
+    let mut master = crafter(fire, 4000.0);
+    let mut sword = InfusedItem::new("flame_sword");
+
+    let outcome = infuser.infuse(&mut master, &mut sword, "fire", 2).unwrap();
+    assert!(outcome.succeeded());
+    assert_eq!((outcome.qi_spent, outcome.experience_spent), (100.0, 200.0));
+    assert_eq!(master.get_data().element_qi_amounts[fire], 900.0);
+    assert_eq!(master.get_data().element_mastery_experience[fire], 800.0);
+
+    let affix = sword.affix("fire").unwrap();
+    assert_eq!(affix.tier, 2);
+    assert_eq!(affix.stats["power_point"], 20.0);
+
+    // One affix per element
+    assert!(matches!(
+        infuser.infuse(&mut master, &mut sword, "fire", 1),
+        Err(ItemCoreError::InvalidInfusion { item, .. }) if item == "flame_sword"
+    ));
+}
+
+#[tokio::test]
+async fn failed_infusions_consume_part_of_the_cost() {
+    let registry = create_test_registry().await;
+    let fire = registry.get_element_index("fire").unwrap().unwrap();
+    let infuser = create_test_infuser(registry);
+    let mut novice = crafter(fire, 0.0);
+    let mut sword = InfusedItem::new("flame_sword");
+
+    let outcome = infuser.infuse(&mut novice, &mut sword, "fire", 5).unwrap();
+    assert!(!outcome.succeeded());
+    assert!(outcome.chance < 0.01);
+    assert_eq!((outcome.qi_spent, outcome.experience_spent), (125.0, 250.0));
+    assert_eq!(novice.get_data().element_qi_amounts[fire], 875.0);
+    assert!(sword.affixes.is_empty());
+
+    // Attempts the crafter cannot pay for in full spend nothing
+    novice.get_data_mut().element_qi_amounts[fire] = 40.0;
+    assert!(infuser.infuse(&mut novice, &mut sword, "fire", 1).is_err());
+    assert_eq!(novice.get_data().element_qi_amounts[fire], 40.0);
+    assert!(infuser.infuse(&mut novice, &mut sword, "fire", 9).is_err());
+}
+
+#[tokio::test]
+async fn equipped_items_contribute_their_affixes() {
+    let registry = create_test_registry().await;
+    let fire = registry.get_element_index("fire").unwrap().unwrap();
+    let infuser = create_test_infuser(registry);
+    let mut master = crafter(fire, 4000.0);
+    let mut sword = InfusedItem::new("flame_sword");
+    infuser.infuse(&mut master, &mut sword, "fire", 1).unwrap();
+    let mut ring = InfusedItem::new("ember_ring");
+    infuser.infuse(&mut master, &mut ring, "fire", 3).unwrap();
+
+    let equipment = InfusedEquipment::new();
+    equipment.equip("hero", sword);
+    equipment.equip("hero", ring);
+    let hero = Actor::simple("hero", "Human", 10);
+
+    let contribution = equipment.contribute_element_stats(&hero, "fire").await.unwrap();
+    assert_eq!(contribution.system_id, "item_core");
+    assert_eq!(contribution.get_stat("power_point"), Some(40.0));
+    assert!(equipment.contribute_element_stats(&hero, "water").await.unwrap().is_empty());
+
+    equipment.unequip("hero", "ember_ring").unwrap();
+    assert_eq!(equipment.element_stats("hero", "fire")["power_point"], 10.0);
+}
@@ -0,0 +1,164 @@
+//! Group loot tests: need and greed rolls, round-robin, master loot, rarity thresholds,
+//! roll timers and the trade window.
+
+use chrono::{TimeZone, Utc};
+use item_core::{GroupLoot, ItemCoreError, LootConfig, LootItem, LootStatus, PartyDrop};
+use shared::events::topics::{PartyLootUpdate, PartyLootUpdated, PARTY_LOOT};
+use shared::events::{EventBus, InProcessEventBus};
+use shared::party::{LootMethod, LootRules, PartyConfig, PartyManager, RollChoice};
+use shared::SimulatedClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+fn create_test_clock() -> Arc<SimulatedClock> {
+    Arc::new(SimulatedClock::new(Utc.timestamp_opt(1_700_000_000, 0).unwrap(), Duration::from_secs(1)))
+}
+
+/// A party of a leader, a tank and a healer using `method` for items of rarity 3 and up
+async fn create_test_party(parties: &PartyManager, method: LootMethod) -> String {
+    let party = parties.create("leader").await.unwrap();
+    parties.add_member(&party.id, "leader", "tank").await.unwrap();
+    parties.add_member(&party.id, "leader", "healer").await.unwrap();
+    let master_looter = (method == LootMethod::MasterLooter).then(|| "leader".to_string());
+    let rules = LootRules { method, rarity_threshold: 3, master_looter };
+    parties.set_loot_rules(&party.id, "leader", rules).await.unwrap();
+    party.id
+}
+
+fn create_test_loot(parties: &Arc<PartyManager>, clock: &Arc<SimulatedClock>) -> GroupLoot {
+    let config = LootConfig { roll_secs: 30, trade_window_secs: 600 };
+    GroupLoot::new(parties.clone(), clock.clone(), config).with_seed(7)
+}
+
+fn party_drop(party_id: &str, items: &[(&str, u8)], eligible: &[&str]) -> PartyDrop {
+    PartyDrop {
+        party_id: party_id.to_string(),
+        source: "lich_king".to_string(),
+        items: items
+            .iter()
+            .map(|(item_id, rarity)| LootItem { item_id: item_id.to_string(), quantity: 1, rarity: *rarity })
+            .collect(),
+        eligible: eligible.iter().map(|actor_id| actor_id.to_string()).collect(),
+    }
+}
+
+#[tokio::test]
+async fn need_beats_greed_and_every_member_hears_the_result() {
+    let clock = create_test_clock();
+    let parties = Arc::new(PartyManager::new(clock.clone(), PartyConfig::default()));
+    let party_id = create_test_party(&parties, LootMethod::NeedBeforeGreed).await;
+    let bus = Arc::new(InProcessEventBus::new());
+    let mut updates = bus.subscribe_topic(PARTY_LOOT.name()).await.unwrap();
+    let loot = create_test_loot(&parties, &clock).with_bus(bus.clone(), "game-server");
+
+    let drop = party_drop(&party_id, &[("frostmourne", 4)], &["leader", "tank", "healer"]);
+    let looted = loot.distribute(drop).await.unwrap();
+    let loot_id = looted[0].loot_id.clone();
+    assert!(matches!(looted[0].status, LootStatus::Rolling { .. }));
+
+    let greed = loot.roll(&loot_id, "leader", RollChoice::Greed).await.unwrap();
+    assert!(greed.rolls[0].roll.is_some_and(|roll| (1..=100).contains(&roll)));
+    assert!(loot.roll(&loot_id, "leader", RollChoice::Need).await.is_err());
+    loot.roll(&loot_id, "healer", RollChoice::Pass).await.unwrap();
+    let resolved = loot.roll(&loot_id, "tank", RollChoice::Need).await.unwrap();
+    assert_eq!(resolved.holder(), Some("tank"));
+
+    let mut seen = Vec::new();
+    for _ in 0..5 {
+        let updated: PartyLootUpdated = updates.next().await.unwrap().decode_message().unwrap();
+        assert_eq!((updated.party_id.as_str(), updated.item_id.as_str()), (party_id.as_str(), "frostmourne"));
+        seen.push(updated.update);
+    }
+    assert!(matches!(&seen[0], PartyLootUpdate::RollStarted { eligible, .. } if eligible.len() == 3));
+    assert!(matches!(&seen[2], PartyLootUpdate::Rolled { choice: RollChoice::Pass, roll: None, .. }));
+    assert!(matches!(
+        &seen[4],
+        PartyLootUpdate::Awarded { actor_id, method: LootMethod::NeedBeforeGreed, choice: Some(RollChoice::Need), .. }
+            if actor_id == "tank"
+    ));
+}
+
+#[tokio::test]
+async fn roll_timers_resolve_on_the_server() {
+    let clock = create_test_clock();
+    let parties = Arc::new(PartyManager::new(clock.clone(), PartyConfig::default()));
+    let party_id = create_test_party(&parties, LootMethod::NeedBeforeGreed).await;
+    let loot = create_test_loot(&parties, &clock);
+    let drop = party_drop(&party_id, &[("helm", 3), ("boots", 3)], &["leader", "tank", "healer"]);
+    let looted = loot.distribute(drop).await.unwrap();
+    let (helm, boots) = (looted[0].loot_id.clone(), looted[1].loot_id.clone());
+
+    loot.roll(&helm, "healer", RollChoice::Greed).await.unwrap();
+    assert!(loot.tick().await.is_empty());
+
+    // Members that did not choose in time pass; nobody rolling leaves the item free for all
+    clock.advance(Duration::from_secs(30));
+    assert!(loot.roll(&helm, "tank", RollChoice::Need).await.is_err());
+    assert_eq!(loot.tick().await.len(), 2);
+    assert_eq!(loot.get(&helm).unwrap().holder(), Some("healer"));
+    assert_eq!(loot.get(&boots).unwrap().status, LootStatus::FreeForAll);
+    assert!(matches!(loot.claim(&boots, "stranger").await, Err(ItemCoreError::NotEligible { .. })));
+    assert_eq!(loot.claim(&boots, "tank").await.unwrap().holder(), Some("tank"));
+    assert!(loot.claim(&boots, "leader").await.is_err());
+}
+
+#[tokio::test]
+async fn round_robin_skips_members_out_of_range_and_cheap_items_are_free_for_all() {
+    let clock = create_test_clock();
+    let parties = Arc::new(PartyManager::new(clock.clone(), PartyConfig::default()));
+    let party_id = create_test_party(&parties, LootMethod::RoundRobin).await;
+    let loot = create_test_loot(&parties, &clock);
+
+    let drop = party_drop(&party_id, &[("ring", 3), ("cloak", 3), ("gloves", 3), ("linen", 1)], &["leader", "healer"]);
+    let looted = loot.distribute(drop).await.unwrap();
+    let holders: Vec<Option<&str>> = looted[..3].iter().map(|loot| loot.holder()).collect();
+    assert_eq!(holders, [Some("leader"), Some("healer"), Some("leader")]);
+    assert_eq!((looted[3].method, &looted[3].status), (LootMethod::FreeForAll, &LootStatus::FreeForAll));
+    assert_eq!(loot.party_loot(&party_id).len(), 4);
+
+    // Drops are for members only
+    let drop = party_drop(&party_id, &[("ring", 3)], &["leader", "stranger"]);
+    assert!(matches!(loot.distribute(drop).await, Err(ItemCoreError::InvalidDrop { .. })));
+}
+
+#[tokio::test]
+async fn master_looter_hands_items_out() {
+    let clock = create_test_clock();
+    let parties = Arc::new(PartyManager::new(clock.clone(), PartyConfig::default()));
+    let party_id = create_test_party(&parties, LootMethod::MasterLooter).await;
+    let loot = create_test_loot(&parties, &clock);
+    let looted = loot.distribute(party_drop(&party_id, &[("crown", 5)], &["leader", "tank"])).await.unwrap();
+    let loot_id = looted[0].loot_id.clone();
+    let waiting = LootStatus::AwaitingMasterLooter { master_looter: "leader".to_string() };
+    assert_eq!(looted[0].status, waiting);
+
+    assert!(matches!(loot.assign(&loot_id, "tank", "tank").await, Err(ItemCoreError::InvalidLootAction { .. })));
+    assert!(loot.assign(&loot_id, "leader", "healer").await.is_err());
+    assert_eq!(loot.assign(&loot_id, "leader", "tank").await.unwrap().holder(), Some("tank"));
+}
+
+#[tokio::test]
+async fn winners_may_trade_within_the_window() {
+    let clock = create_test_clock();
+    let parties = Arc::new(PartyManager::new(clock.clone(), PartyConfig::default()));
+    let party_id = create_test_party(&parties, LootMethod::RoundRobin).await;
+    let loot = create_test_loot(&parties, &clock);
+    let drop = party_drop(&party_id, &[("blade", 4), ("shield", 4)], &["leader", "tank"]);
+    let looted = loot.distribute(drop).await.unwrap();
+    let (blade, shield) = (looted[0].loot_id.clone(), looted[1].loot_id.clone());
+
+    // Only the holder trades, and only with members eligible for the drop
+    assert!(!loot.can_trade(&blade, "tank", "leader"));
+    assert!(!loot.can_trade(&blade, "leader", "healer"));
+    assert!(loot.can_trade(&blade, "leader", "tank"));
+    assert_eq!(loot.trade(&blade, "leader", "tank").await.unwrap().holder(), Some("tank"));
+    assert!(loot.can_trade(&blade, "tank", "leader"));
+
+    // The window does not restart on trades and closes after ten minutes
+    clock.advance(Duration::from_secs(600));
+    assert!(loot.trade(&blade, "tank", "leader").await.is_err());
+    assert!(!loot.can_trade(&shield, "tank", "leader"));
+    loot.tick().await;
+    assert!(loot.get(&blade).is_none());
+    assert!(matches!(loot.trade(&blade, "tank", "leader").await, Err(ItemCoreError::LootNotFound(_))));
+}
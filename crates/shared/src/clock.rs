@@ -0,0 +1,231 @@
+//! Game clock abstraction for time-dependent systems.
+//!
+//! Systems that depend on elapsed time (regeneration, decay, cooldowns, schedulers)
+//! take a [`SharedClock`] instead of calling `Utc::now()` directly. Production code
+//! uses [`WallClock`]; tests and offline progression use [`SimulatedClock`] to step,
+//! pause, and fast-forward time deterministically; [`ScaledClock`] runs time faster or
+//! slower than its source.
+
+use crate::types::Timestamp;
+use chrono::{Duration as ChronoDuration, Utc};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Source of the current game time.
+pub trait GameClock: Send + Sync + Debug {
+    /// Get the current game time.
+    fn now(&self) -> Timestamp;
+
+    /// Check whether the clock is paused.
+    fn is_paused(&self) -> bool {
+        false
+    }
+
+    /// Get the current game time as Unix seconds.
+    fn now_secs(&self) -> i64 {
+        self.now().timestamp()
+    }
+
+    /// Get the current game time as Unix milliseconds.
+    fn now_millis(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+}
+
+/// Clock handle shared between systems.
+pub type SharedClock = Arc<dyn GameClock>;
+
+/// Get a shared wall clock.
+pub fn wall_clock() -> SharedClock {
+    Arc::new(WallClock)
+}
+
+/// Real time clock backed by the system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallClock;
+
+impl GameClock for WallClock {
+    fn now(&self) -> Timestamp {
+        Utc::now()
+    }
+}
+
+#[derive(Debug)]
+struct SimulatedState {
+    now: Timestamp,
+    paused: bool,
+}
+
+/// Simulated clock that only moves when told to.
+///
+/// [`tick`](Self::tick) advances by a fixed step and is ignored while paused;
+/// [`advance`](Self::advance) fast-forwards by an arbitrary duration even while paused.
+#[derive(Debug)]
+pub struct SimulatedClock {
+    state: Mutex<SimulatedState>,
+    step: ChronoDuration,
+}
+
+impl SimulatedClock {
+    /// Create a simulated clock starting at `start` with a fixed tick step.
+    pub fn new(start: Timestamp, step: Duration) -> Self {
+        Self {
+            state: Mutex::new(SimulatedState { now: start, paused: false }),
+            step: to_chrono(step),
+        }
+    }
+
+    /// Create a simulated clock starting at the current wall time.
+    pub fn starting_now(step: Duration) -> Self {
+        Self::new(Utc::now(), step)
+    }
+
+    /// Get the fixed tick step.
+    pub fn step(&self) -> Duration {
+        self.step.to_std().unwrap_or_default()
+    }
+
+    /// Advance by one step, returning the new time.
+    pub fn tick(&self) -> Timestamp {
+        self.tick_n(1)
+    }
+
+    /// Advance by `count` steps, returning the new time.
+    pub fn tick_n(&self, count: u32) -> Timestamp {
+        let mut state = self.state();
+        if !state.paused {
+            state.now += self.step * count as i32;
+        }
+        state.now
+    }
+
+    /// Fast-forward by a duration, returning the new time.
+    pub fn advance(&self, duration: Duration) -> Timestamp {
+        let mut state = self.state();
+        state.now += to_chrono(duration);
+        state.now
+    }
+
+    /// Jump to a specific time.
+    pub fn set(&self, now: Timestamp) {
+        self.state().now = now;
+    }
+
+    /// Stop ticks from advancing the clock.
+    pub fn pause(&self) {
+        self.state().paused = true;
+    }
+
+    /// Let ticks advance the clock again.
+    pub fn resume(&self) {
+        self.state().paused = false;
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimulatedState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl GameClock for SimulatedClock {
+    fn now(&self) -> Timestamp {
+        self.state().now
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state().paused
+    }
+}
+
+#[derive(Debug)]
+struct ScaledState {
+    source_anchor: Timestamp,
+    scaled_anchor: Timestamp,
+    scale: f64,
+    paused: bool,
+}
+
+/// Clock that runs at a multiple of another clock's speed.
+///
+/// Changing the scale or pausing re-anchors the clock, so game time never jumps.
+#[derive(Debug)]
+pub struct ScaledClock {
+    source: SharedClock,
+    state: Mutex<ScaledState>,
+}
+
+impl ScaledClock {
+    /// Create a scaled clock starting at the source's current time.
+    pub fn new(source: SharedClock, scale: f64) -> Self {
+        let now = source.now();
+        Self {
+            source,
+            state: Mutex::new(ScaledState {
+                source_anchor: now,
+                scaled_anchor: now,
+                scale: scale.max(0.0),
+                paused: false,
+            }),
+        }
+    }
+
+    /// Get the current time scale.
+    pub fn scale(&self) -> f64 {
+        self.state().scale
+    }
+
+    /// Change the time scale (negative values are clamped to 0).
+    pub fn set_scale(&self, scale: f64) {
+        let mut state = self.state();
+        self.reanchor(&mut state);
+        state.scale = scale.max(0.0);
+    }
+
+    /// Freeze game time.
+    pub fn pause(&self) {
+        let mut state = self.state();
+        self.reanchor(&mut state);
+        state.paused = true;
+    }
+
+    /// Unfreeze game time.
+    pub fn resume(&self) {
+        let mut state = self.state();
+        self.reanchor(&mut state);
+        state.paused = false;
+    }
+
+    fn reanchor(&self, state: &mut ScaledState) {
+        let source_now = self.source.now();
+        state.scaled_anchor = Self::scaled_now(state, source_now);
+        state.source_anchor = source_now;
+    }
+
+    fn scaled_now(state: &ScaledState, source_now: Timestamp) -> Timestamp {
+        if state.paused {
+            return state.scaled_anchor;
+        }
+        let elapsed = (source_now - state.source_anchor).num_microseconds().unwrap_or(i64::MAX);
+        let scaled = (elapsed as f64 * state.scale) as i64;
+        state.scaled_anchor + ChronoDuration::microseconds(scaled)
+    }
+
+    fn state(&self) -> MutexGuard<'_, ScaledState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl GameClock for ScaledClock {
+    fn now(&self) -> Timestamp {
+        let state = self.state();
+        Self::scaled_now(&state, self.source.now())
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state().paused || self.source.is_paused()
+    }
+}
+
+fn to_chrono(duration: Duration) -> ChronoDuration {
+    ChronoDuration::from_std(duration).unwrap_or(ChronoDuration::MAX)
+}
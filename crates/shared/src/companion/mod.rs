@@ -0,0 +1,438 @@
+//! Companions: pets that follow their owner, level up and fight at its side.
+//!
+//! The [`CompanionManager`] keeps each owner's [`CompanionRoster`]: companions are adopted
+//! into the stable, summoned to fight (at most [`CompanionConfig::max_active`] at once) and
+//! stabled again. Every companion levels along a [`LevelingTrack`] of the configuration and
+//! obeys a [`CompanionCommand`] telling it when to engage.
+//!
+//! Other systems plug in through:
+//! - actor-core's companion pipeline, resolving a companion's stats from its owner's through
+//!   inheritance formulas.
+//! - [`CompanionManager::attribute`], crediting whatever a companion does in combat, damage,
+//!   healing or threat, to its owner.
+//! - A [`CompanionStore`], saving each roster whenever it changes so active and stabled
+//!   companions survive logouts and restarts.
+
+mod store;
+
+pub use store::{CompanionStore, FileCompanionStore, MemoryCompanionStore};
+
+use crate::clock::SharedClock;
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use uuid::Uuid;
+
+/// Whether a companion is out in the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanionStatus {
+    /// Summoned, following its owner and resolved with its own stats
+    Active,
+    /// Kept in the stable
+    Stabled,
+}
+
+/// When a companion engages enemies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompanionCommand {
+    /// Never attacks, even when hit
+    Passive,
+    /// Fights back when it or its owner is attacked
+    Defensive,
+    /// Attacks its owner's target and anything hostile nearby
+    Aggressive,
+}
+
+impl CompanionCommand {
+    /// Whether a companion under this command engages; `provoked` when it or its owner
+    /// is being attacked
+    pub fn engages(self, provoked: bool) -> bool {
+        match self {
+            CompanionCommand::Passive => false,
+            CompanionCommand::Defensive => provoked,
+            CompanionCommand::Aggressive => true,
+        }
+    }
+}
+
+/// How fast companions on a track level up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelingTrack {
+    pub max_level: u32,
+    /// Experience from level 1 to 2
+    pub base_experience: f64,
+    /// Factor the experience needed grows by each level
+    pub growth: f64,
+}
+
+impl LevelingTrack {
+    /// Experience needed to go from `level` to the next, `None` at the maximum level
+    pub fn experience_to_next(&self, level: u32) -> Option<f64> {
+        (level < self.max_level).then(|| self.base_experience * self.growth.powi(level.saturating_sub(1) as i32))
+    }
+}
+
+/// Roster limits and leveling tracks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompanionConfig {
+    /// Companions an owner may have summoned at once
+    pub max_active: usize,
+    /// Companions an owner may keep, active and stabled
+    pub max_companions: usize,
+    /// Leveling tracks by ID
+    pub tracks: HashMap<String, LevelingTrack>,
+}
+
+impl Default for CompanionConfig {
+    fn default() -> Self {
+        let track = |max_level, base_experience, growth| LevelingTrack { max_level, base_experience, growth };
+        Self {
+            max_active: 1,
+            max_companions: 10,
+            tracks: HashMap::from([
+                ("beast".to_string(), track(60, 100.0, 1.15)),
+                ("spirit".to_string(), track(100, 150.0, 1.1)),
+            ]),
+        }
+    }
+}
+
+/// A companion and its progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Companion {
+    /// Actor ID of the companion when resolved and in combat
+    pub id: String,
+    pub owner_id: String,
+    /// Species, selecting the stats it inherits from its owner
+    pub species: String,
+    pub name: String,
+    /// Leveling track it follows
+    pub track: String,
+    pub level: u32,
+    /// Experience towards the next level
+    pub experience: f64,
+    pub status: CompanionStatus,
+    pub command: CompanionCommand,
+    pub adopted_at: Timestamp,
+}
+
+impl Companion {
+    pub fn is_active(&self) -> bool {
+        self.status == CompanionStatus::Active
+    }
+}
+
+/// The companions of one owner, as persisted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompanionRoster {
+    pub owner_id: String,
+    /// Companions in the order they were adopted
+    pub companions: Vec<Companion>,
+}
+
+impl CompanionRoster {
+    pub fn new(owner_id: impl Into<String>) -> Self {
+        Self { owner_id: owner_id.into(), companions: Vec::new() }
+    }
+
+    pub fn companion(&self, companion_id: &str) -> Option<&Companion> {
+        self.companions.iter().find(|companion| companion.id == companion_id)
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Companion> {
+        self.companions.iter().filter(|companion| companion.is_active())
+    }
+
+    pub fn stabled(&self) -> impl Iterator<Item = &Companion> {
+        self.companions.iter().filter(|companion| !companion.is_active())
+    }
+}
+
+/// Who combat credit goes to for what an actor did
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CombatAttribution {
+    /// Actor that dealt the damage, healing or threat
+    pub source_id: String,
+    /// Actor credited with it: the owner for companions, else the source itself
+    pub credited_id: String,
+    /// Companion that acted on its owner's behalf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub companion_id: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Rosters of the owners loaded in this process
+    rosters: HashMap<String, CompanionRoster>,
+    /// Owner of each loaded companion
+    owners: HashMap<String, String>,
+}
+
+/// Companions of the owners loaded in one process, saved to a store as they change
+pub struct CompanionManager {
+    clock: SharedClock,
+    config: CompanionConfig,
+    store: Option<Arc<dyn CompanionStore>>,
+    state: Mutex<State>,
+}
+
+impl CompanionManager {
+    pub fn new(clock: SharedClock, config: CompanionConfig) -> Self {
+        Self { clock, config, store: None, state: Mutex::new(State::default()) }
+    }
+
+    /// Load rosters from and save them to `store`
+    pub fn with_store(mut self, store: Arc<dyn CompanionStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn config(&self) -> &CompanionConfig {
+        &self.config
+    }
+
+    /// Load an owner's roster from the store, e.g. on login; rosters already loaded are kept
+    pub async fn load(&self, owner_id: &str) -> ChaosResult<CompanionRoster> {
+        if let Some(roster) = self.roster(owner_id) {
+            return Ok(roster);
+        }
+        let stored = match &self.store {
+            Some(store) => store.load(owner_id).await?,
+            None => None,
+        };
+        let mut state = self.state();
+        let roster = state
+            .rosters
+            .entry(owner_id.to_string())
+            .or_insert_with(|| stored.unwrap_or_else(|| CompanionRoster::new(owner_id)))
+            .clone();
+        for companion in &roster.companions {
+            state.owners.insert(companion.id.clone(), owner_id.to_string());
+        }
+        Ok(roster)
+    }
+
+    /// Drop an owner's roster from memory, e.g. on logout; it was saved as it changed
+    pub fn unload(&self, owner_id: &str) {
+        let mut state = self.state();
+        if let Some(roster) = state.rosters.remove(owner_id) {
+            for companion in &roster.companions {
+                state.owners.remove(&companion.id);
+            }
+        }
+    }
+
+    /// Adopt a companion into the owner's stable, at level 1 and defensive
+    pub async fn adopt(&self, owner_id: &str, species: &str, name: &str, track: &str) -> ChaosResult<Companion> {
+        if !self.config.tracks.contains_key(track) {
+            return Err(ChaosError::Validation(format!("Unknown leveling track {}", track)));
+        }
+        self.load(owner_id).await?;
+        let companion = {
+            let mut state = self.state();
+            let roster = roster_mut(&mut state, owner_id)?;
+            if roster.companions.len() >= self.config.max_companions {
+                return Err(ChaosError::Validation(format!(
+                    "Actor {} already keeps {} companions",
+                    owner_id,
+                    roster.companions.len()
+                )));
+            }
+            let companion = Companion {
+                id: Uuid::new_v4().to_string(),
+                owner_id: owner_id.to_string(),
+                species: species.to_string(),
+                name: name.to_string(),
+                track: track.to_string(),
+                level: 1,
+                experience: 0.0,
+                status: CompanionStatus::Stabled,
+                command: CompanionCommand::Defensive,
+                adopted_at: self.clock.now(),
+            };
+            roster.companions.push(companion.clone());
+            state.owners.insert(companion.id.clone(), owner_id.to_string());
+            companion
+        };
+        self.save(owner_id).await?;
+        Ok(companion)
+    }
+
+    /// Summon a stabled companion to its owner's side
+    pub async fn summon(&self, owner_id: &str, companion_id: &str) -> ChaosResult<Companion> {
+        let max_active = self.config.max_active;
+        self.update(owner_id, companion_id, |roster, index| {
+            if roster.companions[index].is_active() {
+                return Err(ChaosError::Validation(format!("Companion {} is already summoned", companion_id)));
+            }
+            if roster.active().count() >= max_active {
+                return Err(ChaosError::Validation(format!(
+                    "Actor {} already has {} companions summoned",
+                    roster.owner_id, max_active
+                )));
+            }
+            roster.companions[index].status = CompanionStatus::Active;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Send an active companion back to the stable
+    pub async fn stable(&self, owner_id: &str, companion_id: &str) -> ChaosResult<Companion> {
+        self.update(owner_id, companion_id, |roster, index| {
+            roster.companions[index].status = CompanionStatus::Stabled;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Set when a companion engages
+    pub async fn set_command(
+        &self,
+        owner_id: &str,
+        companion_id: &str,
+        command: CompanionCommand,
+    ) -> ChaosResult<Companion> {
+        self.update(owner_id, companion_id, |roster, index| {
+            roster.companions[index].command = command;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Release a companion for good, returning it
+    pub async fn release(&self, owner_id: &str, companion_id: &str) -> ChaosResult<Companion> {
+        let companion = {
+            let mut state = self.state();
+            let roster = roster_mut(&mut state, owner_id)?;
+            let index = position(roster, companion_id)?;
+            let companion = roster.companions.remove(index);
+            state.owners.remove(companion_id);
+            companion
+        };
+        self.save(owner_id).await?;
+        Ok(companion)
+    }
+
+    /// Grant experience to a companion, levelling it up along its track; experience
+    /// beyond the maximum level is lost
+    pub async fn grant_experience(&self, companion_id: &str, amount: f64) -> ChaosResult<Companion> {
+        if !amount.is_finite() || amount < 0.0 {
+            return Err(ChaosError::Validation(format!("Invalid experience amount {}", amount)));
+        }
+        let owner_id = self
+            .owner_of(companion_id)
+            .ok_or_else(|| ChaosError::Validation(format!("Companion {} not found", companion_id)))?;
+        let tracks = &self.config.tracks;
+        self.update(&owner_id, companion_id, |roster, index| {
+            let companion = &mut roster.companions[index];
+            let track = tracks.get(&companion.track).ok_or_else(|| {
+                ChaosError::Configuration(format!("Unknown leveling track {}", companion.track))
+            })?;
+            companion.experience += amount;
+            while let Some(needed) = track.experience_to_next(companion.level) {
+                if companion.experience < needed {
+                    break;
+                }
+                companion.experience -= needed;
+                companion.level += 1;
+            }
+            if companion.level >= track.max_level {
+                companion.experience = 0.0;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Get a loaded companion
+    pub fn companion(&self, companion_id: &str) -> Option<Companion> {
+        let state = self.state();
+        let owner_id = state.owners.get(companion_id)?;
+        state.rosters.get(owner_id)?.companion(companion_id).cloned()
+    }
+
+    /// Get a loaded owner's roster
+    pub fn roster(&self, owner_id: &str) -> Option<CompanionRoster> {
+        self.state().rosters.get(owner_id).cloned()
+    }
+
+    /// Get the companions an owner has summoned
+    pub fn active(&self, owner_id: &str) -> Vec<Companion> {
+        self.state()
+            .rosters
+            .get(owner_id)
+            .map(|roster| roster.active().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the owner of a loaded companion
+    pub fn owner_of(&self, companion_id: &str) -> Option<String> {
+        self.state().owners.get(companion_id).cloned()
+    }
+
+    /// Credit what an actor did in combat to its owner when it is a companion
+    pub fn attribute(&self, source_id: &str) -> CombatAttribution {
+        match self.owner_of(source_id) {
+            Some(owner_id) => CombatAttribution {
+                source_id: source_id.to_string(),
+                credited_id: owner_id,
+                companion_id: Some(source_id.to_string()),
+            },
+            None => CombatAttribution {
+                source_id: source_id.to_string(),
+                credited_id: source_id.to_string(),
+                companion_id: None,
+            },
+        }
+    }
+
+    /// Apply `change` to one of the owner's companions, then save the roster
+    async fn update(
+        &self,
+        owner_id: &str,
+        companion_id: &str,
+        change: impl FnOnce(&mut CompanionRoster, usize) -> ChaosResult<()>,
+    ) -> ChaosResult<Companion> {
+        let companion = {
+            let mut state = self.state();
+            let roster = roster_mut(&mut state, owner_id)?;
+            let index = position(roster, companion_id)?;
+            change(roster, index)?;
+            roster.companions[index].clone()
+        };
+        self.save(owner_id).await?;
+        Ok(companion)
+    }
+
+    async fn save(&self, owner_id: &str) -> ChaosResult<()> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        match self.roster(owner_id) {
+            Some(roster) => store.save(&roster).await,
+            None => Ok(()),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn roster_mut<'a>(state: &'a mut State, owner_id: &str) -> ChaosResult<&'a mut CompanionRoster> {
+    state
+        .rosters
+        .get_mut(owner_id)
+        .ok_or_else(|| ChaosError::Validation(format!("Companions of {} are not loaded", owner_id)))
+}
+
+fn position(roster: &CompanionRoster, companion_id: &str) -> ChaosResult<usize> {
+    roster.companions.iter().position(|companion| companion.id == companion_id).ok_or_else(|| {
+        ChaosError::Validation(format!("Actor {} has no companion {}", roster.owner_id, companion_id))
+    })
+}
@@ -0,0 +1,79 @@
+//! Where companion rosters are kept between sessions.
+
+use super::CompanionRoster;
+use crate::error::ChaosResult;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Saved companion rosters, keyed by owner
+#[async_trait]
+pub trait CompanionStore: Send + Sync {
+    async fn save(&self, roster: &CompanionRoster) -> ChaosResult<()>;
+
+    async fn load(&self, owner_id: &str) -> ChaosResult<Option<CompanionRoster>>;
+}
+
+/// Rosters that live as long as the process
+#[derive(Debug, Default)]
+pub struct MemoryCompanionStore {
+    rosters: Mutex<HashMap<String, CompanionRoster>>,
+}
+
+impl MemoryCompanionStore {
+    fn rosters(&self) -> MutexGuard<'_, HashMap<String, CompanionRoster>> {
+        self.rosters.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl CompanionStore for MemoryCompanionStore {
+    async fn save(&self, roster: &CompanionRoster) -> ChaosResult<()> {
+        self.rosters().insert(roster.owner_id.clone(), roster.clone());
+        Ok(())
+    }
+
+    async fn load(&self, owner_id: &str) -> ChaosResult<Option<CompanionRoster>> {
+        Ok(self.rosters().get(owner_id).cloned())
+    }
+}
+
+/// Rosters kept in a directory, one JSON file per owner
+#[derive(Debug)]
+pub struct FileCompanionStore {
+    dir: PathBuf,
+}
+
+impl FileCompanionStore {
+    /// Open a roster directory, creating it when it does not exist yet.
+    pub fn open(dir: impl AsRef<Path>) -> ChaosResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, owner_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", owner_id))
+    }
+}
+
+#[async_trait]
+impl CompanionStore for FileCompanionStore {
+    async fn save(&self, roster: &CompanionRoster) -> ChaosResult<()> {
+        // Write then rename so a crash never leaves a truncated file
+        let path = self.path(&roster.owner_id);
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, serde_json::to_vec_pretty(roster)?).await?;
+        tokio::fs::rename(&temporary, &path).await?;
+        Ok(())
+    }
+
+    async fn load(&self, owner_id: &str) -> ChaosResult<Option<CompanionRoster>> {
+        match tokio::fs::read(self.path(owner_id)).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Error types and result definitions for the Chaos World backend.
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Main error type for the Chaos World backend.
@@ -60,3 +61,337 @@ impl From<serde_json::Error> for ChaosError {
         ChaosError::Serialization(err.to_string())
     }
 }
+
+/// Canonical gRPC status codes.
+///
+/// Mirrors `google.rpc.Code` so crates can map errors without depending on tonic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(i32)]
+pub enum GrpcCode {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+/// Stable error codes shared by every service and client.
+///
+/// Numeric codes and string codes are part of the public API: never renumber or
+/// rename an existing code, only add new ones. Codes are grouped by range:
+/// 1xxx internal, 2xxx request, 3xxx auth, 4xxx throttling, 5xxx dependencies,
+/// 6xxx game rules.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Unexpected server-side failure
+    Internal,
+    /// Server misconfiguration
+    Configuration,
+    /// Operation not implemented
+    Unimplemented,
+    /// Request failed validation
+    ValidationFailed,
+    /// Request body could not be parsed
+    InvalidFormat,
+    /// Requested resource does not exist
+    NotFound,
+    /// Resource already exists
+    AlreadyExists,
+    /// Request conflicts with the current resource state
+    Conflict,
+    /// Caller is not authenticated
+    Unauthenticated,
+    /// Caller is not allowed to perform the operation
+    PermissionDenied,
+    /// Authentication token has expired
+    TokenExpired,
+    /// Caller exceeded a rate limit
+    RateLimited,
+    /// Downstream service is unavailable
+    ServiceUnavailable,
+    /// Operation timed out
+    Timeout,
+    /// Downstream service returned an error
+    UpstreamError,
+    /// Database failure
+    Database,
+    /// Action is not allowed by game rules
+    GameRuleViolation,
+    /// Actor is missing or in an invalid state
+    InvalidActor,
+}
+
+impl ErrorCode {
+    /// All error codes, in catalog order.
+    pub const ALL: [ErrorCode; 18] = [
+        ErrorCode::Internal,
+        ErrorCode::Configuration,
+        ErrorCode::Unimplemented,
+        ErrorCode::ValidationFailed,
+        ErrorCode::InvalidFormat,
+        ErrorCode::NotFound,
+        ErrorCode::AlreadyExists,
+        ErrorCode::Conflict,
+        ErrorCode::Unauthenticated,
+        ErrorCode::PermissionDenied,
+        ErrorCode::TokenExpired,
+        ErrorCode::RateLimited,
+        ErrorCode::ServiceUnavailable,
+        ErrorCode::Timeout,
+        ErrorCode::UpstreamError,
+        ErrorCode::Database,
+        ErrorCode::GameRuleViolation,
+        ErrorCode::InvalidActor,
+    ];
+
+    /// Get the stable numeric code.
+    pub fn code(self) -> u32 {
+        match self {
+            ErrorCode::Internal => 1000,
+            ErrorCode::Configuration => 1001,
+            ErrorCode::Unimplemented => 1002,
+            ErrorCode::ValidationFailed => 2000,
+            ErrorCode::InvalidFormat => 2001,
+            ErrorCode::NotFound => 2002,
+            ErrorCode::AlreadyExists => 2003,
+            ErrorCode::Conflict => 2004,
+            ErrorCode::Unauthenticated => 3000,
+            ErrorCode::PermissionDenied => 3001,
+            ErrorCode::TokenExpired => 3002,
+            ErrorCode::RateLimited => 4000,
+            ErrorCode::ServiceUnavailable => 5000,
+            ErrorCode::Timeout => 5001,
+            ErrorCode::UpstreamError => 5002,
+            ErrorCode::Database => 5003,
+            ErrorCode::GameRuleViolation => 6000,
+            ErrorCode::InvalidActor => 6001,
+        }
+    }
+
+    /// Get the stable string code.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::Internal => "INTERNAL",
+            ErrorCode::Configuration => "CONFIGURATION",
+            ErrorCode::Unimplemented => "UNIMPLEMENTED",
+            ErrorCode::ValidationFailed => "VALIDATION_FAILED",
+            ErrorCode::InvalidFormat => "INVALID_FORMAT",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::AlreadyExists => "ALREADY_EXISTS",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Unauthenticated => "UNAUTHENTICATED",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::TokenExpired => "TOKEN_EXPIRED",
+            ErrorCode::RateLimited => "RATE_LIMITED",
+            ErrorCode::ServiceUnavailable => "SERVICE_UNAVAILABLE",
+            ErrorCode::Timeout => "TIMEOUT",
+            ErrorCode::UpstreamError => "UPSTREAM_ERROR",
+            ErrorCode::Database => "DATABASE",
+            ErrorCode::GameRuleViolation => "GAME_RULE_VIOLATION",
+            ErrorCode::InvalidActor => "INVALID_ACTOR",
+        }
+    }
+
+    /// Look up a code by its numeric value.
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.into_iter().find(|error_code| error_code.code() == code)
+    }
+
+    /// Look up a code by its string value.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|error_code| error_code.as_str() == name)
+    }
+
+    /// Get the HTTP status code.
+    pub fn http_status(self) -> u16 {
+        match self {
+            ErrorCode::Internal | ErrorCode::Configuration | ErrorCode::Database => 500,
+            ErrorCode::Unimplemented => 501,
+            ErrorCode::ValidationFailed | ErrorCode::InvalidFormat | ErrorCode::InvalidActor => 400,
+            ErrorCode::NotFound => 404,
+            ErrorCode::AlreadyExists | ErrorCode::Conflict => 409,
+            ErrorCode::Unauthenticated | ErrorCode::TokenExpired => 401,
+            ErrorCode::PermissionDenied => 403,
+            ErrorCode::RateLimited => 429,
+            ErrorCode::ServiceUnavailable => 503,
+            ErrorCode::Timeout => 504,
+            ErrorCode::UpstreamError => 502,
+            ErrorCode::GameRuleViolation => 422,
+        }
+    }
+
+    /// Get the gRPC status code.
+    pub fn grpc_code(self) -> GrpcCode {
+        match self {
+            ErrorCode::Internal | ErrorCode::Configuration | ErrorCode::Database => GrpcCode::Internal,
+            ErrorCode::Unimplemented => GrpcCode::Unimplemented,
+            ErrorCode::ValidationFailed | ErrorCode::InvalidFormat | ErrorCode::InvalidActor => {
+                GrpcCode::InvalidArgument
+            }
+            ErrorCode::NotFound => GrpcCode::NotFound,
+            ErrorCode::AlreadyExists => GrpcCode::AlreadyExists,
+            ErrorCode::Conflict => GrpcCode::Aborted,
+            ErrorCode::Unauthenticated | ErrorCode::TokenExpired => GrpcCode::Unauthenticated,
+            ErrorCode::PermissionDenied => GrpcCode::PermissionDenied,
+            ErrorCode::RateLimited => GrpcCode::ResourceExhausted,
+            ErrorCode::ServiceUnavailable | ErrorCode::UpstreamError => GrpcCode::Unavailable,
+            ErrorCode::Timeout => GrpcCode::DeadlineExceeded,
+            ErrorCode::GameRuleViolation => GrpcCode::FailedPrecondition,
+        }
+    }
+
+    /// Get the default client-safe message.
+    pub fn default_message(self) -> &'static str {
+        match self {
+            ErrorCode::Internal | ErrorCode::Configuration | ErrorCode::Database => "Internal server error",
+            ErrorCode::Unimplemented => "Not implemented",
+            ErrorCode::ValidationFailed => "Invalid request",
+            ErrorCode::InvalidFormat => "Invalid request format",
+            ErrorCode::NotFound => "Resource not found",
+            ErrorCode::AlreadyExists => "Resource already exists",
+            ErrorCode::Conflict => "Request conflicts with current state",
+            ErrorCode::Unauthenticated => "Authentication required",
+            ErrorCode::PermissionDenied => "Access denied",
+            ErrorCode::TokenExpired => "Authentication token expired",
+            ErrorCode::RateLimited => "Rate limit exceeded",
+            ErrorCode::ServiceUnavailable | ErrorCode::UpstreamError => "Service unavailable",
+            ErrorCode::Timeout => "Request timeout",
+            ErrorCode::GameRuleViolation => "Action not allowed",
+            ErrorCode::InvalidActor => "Invalid actor",
+        }
+    }
+
+    /// Check whether the details of errors with this code may be shown to clients.
+    pub fn exposes_details(self) -> bool {
+        !matches!(
+            self,
+            ErrorCode::Internal
+                | ErrorCode::Configuration
+                | ErrorCode::Database
+                | ErrorCode::ServiceUnavailable
+                | ErrorCode::UpstreamError
+        )
+    }
+
+    /// Check whether a request failing with this code may be retried.
+    pub fn is_retryable(self) -> bool {
+        matches!(
+            self,
+            ErrorCode::ServiceUnavailable | ErrorCode::Timeout | ErrorCode::UpstreamError | ErrorCode::RateLimited
+        )
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Client-facing error payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiError {
+    /// Stable error code
+    pub code: ErrorCode,
+    /// Stable numeric error code
+    pub numeric_code: u32,
+    /// Client-safe message
+    pub message: String,
+}
+
+impl ApiError {
+    /// Create an API error with a client-safe message.
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            numeric_code: code.code(),
+            message: message.into(),
+        }
+    }
+
+    /// Create an API error with the code's default message.
+    pub fn from_code(code: ErrorCode) -> Self {
+        Self::new(code, code.default_message())
+    }
+
+    /// Get the HTTP status code.
+    pub fn http_status(&self) -> u16 {
+        self.code.http_status()
+    }
+
+    /// Get the gRPC status code.
+    pub fn grpc_code(&self) -> GrpcCode {
+        self.code.grpc_code()
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}): {}", self.code, self.numeric_code, self.message)
+    }
+}
+
+/// Maps an error onto the stable code catalog and a client-safe message.
+pub trait ToApiError {
+    /// Get the stable error code.
+    fn error_code(&self) -> ErrorCode;
+
+    /// Get the client-safe message.
+    ///
+    /// Defaults to the code's generic message so internal details never leak.
+    fn client_message(&self) -> String {
+        self.error_code().default_message().to_string()
+    }
+
+    /// Build the client-facing error.
+    fn to_api_error(&self) -> ApiError {
+        ApiError::new(self.error_code(), self.client_message())
+    }
+}
+
+impl ToApiError for ApiError {
+    fn error_code(&self) -> ErrorCode {
+        self.code
+    }
+
+    fn client_message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl ToApiError for ChaosError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ChaosError::Database(_) => ErrorCode::Database,
+            ChaosError::Network(_) => ErrorCode::ServiceUnavailable,
+            ChaosError::Authentication(_) => ErrorCode::Unauthenticated,
+            ChaosError::Validation(_) => ErrorCode::ValidationFailed,
+            ChaosError::Serialization(_) => ErrorCode::InvalidFormat,
+            ChaosError::Configuration(_) => ErrorCode::Configuration,
+            ChaosError::Internal(_) => ErrorCode::Internal,
+            ChaosError::ExternalService(_) => ErrorCode::UpstreamError,
+            ChaosError::Io(_) => ErrorCode::Internal,
+            ChaosError::Generic(_) => ErrorCode::Internal,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            ChaosError::Validation(message) => message.clone(),
+            _ => self.error_code().default_message().to_string(),
+        }
+    }
+}
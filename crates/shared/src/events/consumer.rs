@@ -0,0 +1,350 @@
+//! Message consumers with retries, dead letters and replay after restarts.
+//!
+//! A [`Consumer`] subscribes to a topic of versioned messages and hands each one to its
+//! handler. Failing handlers are retried with a growing delay; messages that still fail,
+//! or that cannot be decoded at all, are published on [`DEAD_LETTER`] and skipped so
+//! one bad message cannot stall the topic.
+//!
+//! Progress is kept as the timestamp of the last handled message in a [`Checkpoints`]
+//! store. On start the consumer asks publishers to replay everything after its
+//! checkpoint (see [`replay`](super::replay)); with [`FileCheckpoints`] this survives a
+//! process restart. Deliveries are at least once: handlers should be idempotent.
+
+use super::replay::request_replay;
+use super::topics::{self, DEAD_LETTER};
+use super::{validate_topic, EventBus, EventBusExt, EventEnvelope, Message, Topic, TypedSubscription};
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Minimum time between checkpoint file writes.
+const CHECKPOINT_WRITE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A message a consumer gave up on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub consumer: String,
+    pub topic: String,
+    pub envelope: EventEnvelope,
+    pub error: String,
+    /// Handler runs, 0 when the message could not be decoded
+    pub attempts: u32,
+    pub failed_at: Timestamp,
+}
+
+impl Message for DeadLetter {
+    const TYPE: &'static str = "system.dead_letter";
+    const VERSION: u32 = 1;
+}
+
+/// Where consumers keep their progress.
+pub trait Checkpoints: Send + Sync {
+    /// Get the timestamp of the last message a consumer handled on a topic.
+    fn load(&self, consumer: &str, topic: &str) -> Option<Timestamp>;
+
+    /// Record a handled message; checkpoints never move backwards.
+    fn save(&self, consumer: &str, topic: &str, timestamp: Timestamp);
+
+    /// Persist pending progress.
+    fn flush(&self) -> ChaosResult<()> {
+        Ok(())
+    }
+}
+
+/// Checkpoints that live as long as the process.
+#[derive(Debug, Default)]
+pub struct MemoryCheckpoints {
+    positions: Mutex<HashMap<String, Timestamp>>,
+}
+
+impl Checkpoints for MemoryCheckpoints {
+    fn load(&self, consumer: &str, topic: &str) -> Option<Timestamp> {
+        lock(&self.positions).get(&key(consumer, topic)).copied()
+    }
+
+    fn save(&self, consumer: &str, topic: &str, timestamp: Timestamp) {
+        advance(&mut lock(&self.positions), consumer, topic, timestamp);
+    }
+}
+
+#[derive(Debug)]
+struct FileState {
+    positions: HashMap<String, Timestamp>,
+    dirty: bool,
+    written: Instant,
+}
+
+/// Checkpoints kept in a JSON file, written at most once a second.
+#[derive(Debug)]
+pub struct FileCheckpoints {
+    path: PathBuf,
+    state: Mutex<FileState>,
+}
+
+impl FileCheckpoints {
+    /// Open a checkpoint file, starting empty when it does not exist yet.
+    pub fn open(path: impl AsRef<Path>) -> ChaosResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let positions = match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            state: Mutex::new(FileState {
+                positions,
+                dirty: false,
+                written: Instant::now(),
+            }),
+        })
+    }
+
+    fn write(&self, state: &mut FileState) -> ChaosResult<()> {
+        // Write then rename so a crash never leaves a truncated file
+        let temporary = self.path.with_extension("tmp");
+        std::fs::write(&temporary, serde_json::to_vec_pretty(&state.positions)?)?;
+        std::fs::rename(&temporary, &self.path)?;
+        state.dirty = false;
+        state.written = Instant::now();
+        Ok(())
+    }
+}
+
+impl Checkpoints for FileCheckpoints {
+    fn load(&self, consumer: &str, topic: &str) -> Option<Timestamp> {
+        lock(&self.state).positions.get(&key(consumer, topic)).copied()
+    }
+
+    fn save(&self, consumer: &str, topic: &str, timestamp: Timestamp) {
+        let mut state = lock(&self.state);
+        advance(&mut state.positions, consumer, topic, timestamp);
+        state.dirty = true;
+        if state.written.elapsed() >= CHECKPOINT_WRITE_INTERVAL {
+            if let Err(e) = self.write(&mut state) {
+                tracing::warn!(path = %self.path.display(), error = %e, "Failed to write consumer checkpoints");
+            }
+        }
+    }
+
+    fn flush(&self) -> ChaosResult<()> {
+        let mut state = lock(&self.state);
+        if state.dirty {
+            self.write(&mut state)?;
+        }
+        Ok(())
+    }
+}
+
+/// Consumer settings.
+#[derive(Debug, Clone)]
+pub struct ConsumerOptions {
+    /// Consumer name: a single topic token, unique per consuming service
+    pub name: String,
+    /// Handler runs before a message is dead-lettered
+    pub max_attempts: u32,
+    /// Delay before the first retry, growing linearly with each attempt
+    pub retry_backoff: Duration,
+    /// Ask publishers for missed messages on start
+    pub replay: bool,
+    /// Recent message ids remembered to drop duplicates
+    pub dedupe_window: usize,
+}
+
+impl ConsumerOptions {
+    /// Options with the defaults for a named consumer.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            max_attempts: 3,
+            retry_backoff: Duration::from_millis(200),
+            replay: true,
+            dedupe_window: 4096,
+        }
+    }
+}
+
+/// Handles the messages of one topic.
+pub struct Consumer<M> {
+    bus: Arc<dyn EventBus>,
+    topic: Topic<M>,
+    options: ConsumerOptions,
+    checkpoints: Arc<dyn Checkpoints>,
+}
+
+impl<M: Message + Clone> Consumer<M> {
+    /// Create a consumer with in-memory checkpoints.
+    pub fn new(bus: Arc<dyn EventBus>, topic: Topic<M>, options: ConsumerOptions) -> Self {
+        Self {
+            bus,
+            topic,
+            options,
+            checkpoints: Arc::new(MemoryCheckpoints::default()),
+        }
+    }
+
+    /// Keep progress in another store.
+    pub fn with_checkpoints(mut self, checkpoints: Arc<dyn Checkpoints>) -> Self {
+        self.checkpoints = checkpoints;
+        self
+    }
+
+    /// Subscribe and handle messages on a background task until the bus closes.
+    ///
+    /// Subscribing happens before this returns, so messages published afterwards are
+    /// not missed.
+    pub async fn spawn<F, Fut>(self, handler: F) -> ChaosResult<JoinHandle<()>>
+    where
+        F: Fn(M, EventEnvelope) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ChaosResult<()>> + Send,
+    {
+        validate_topic(&self.options.name)
+            .ok()
+            .filter(|_| !self.options.name.contains('.'))
+            .ok_or_else(|| ChaosError::Validation(format!("Invalid consumer name: '{}'", self.options.name)))?;
+
+        let mut live = self.bus.subscribe(&self.topic).await?.into_inner();
+        let mut replayed = if self.options.replay {
+            let reply: Topic<EventEnvelope> = Topic::dynamic(topics::replay_reply(&self.options.name));
+            let replayed = self.bus.subscribe(&reply).await?;
+            let after = self.checkpoints.load(&self.options.name, self.topic.name());
+            if let Err(e) = request_replay(self.bus.as_ref(), &self.options.name, self.topic.name(), after).await {
+                tracing::warn!(consumer = %self.options.name, error = %e, "Failed to request replay");
+            }
+            Some(replayed)
+        } else {
+            None
+        };
+
+        Ok(tokio::spawn(async move {
+            let mut seen = RecentIds::new(self.options.dedupe_window);
+            loop {
+                let envelope = tokio::select! {
+                    envelope = live.next() => match envelope {
+                        Some(envelope) => envelope,
+                        None => break,
+                    },
+                    envelope = next_replayed(&mut replayed) => match envelope {
+                        Some(Ok(envelope)) if envelope.topic == self.topic.name() => envelope,
+                        Some(_) => continue,
+                        None => {
+                            replayed = None;
+                            continue;
+                        }
+                    },
+                };
+                if seen.insert(envelope.id) {
+                    self.handle(envelope, &handler).await;
+                }
+            }
+            if let Err(e) = self.checkpoints.flush() {
+                tracing::warn!(consumer = %self.options.name, error = %e, "Failed to flush checkpoints");
+            }
+        }))
+    }
+
+    async fn handle<F, Fut>(&self, envelope: EventEnvelope, handler: &F)
+    where
+        F: Fn(M, EventEnvelope) -> Fut,
+        Fut: Future<Output = ChaosResult<()>>,
+    {
+        let timestamp = envelope.timestamp;
+        match envelope.decode_message::<M>() {
+            Ok(message) => {
+                let mut attempt = 0;
+                loop {
+                    attempt += 1;
+                    match handler(message.clone(), envelope.clone()).await {
+                        Ok(()) => break,
+                        Err(e) if attempt < self.options.max_attempts => {
+                            tracing::warn!(consumer = %self.options.name, attempt, error = %e, "Message handler failed, retrying");
+                            tokio::time::sleep(self.options.retry_backoff * attempt).await;
+                        }
+                        Err(e) => {
+                            self.dead_letter(envelope, e.to_string(), attempt).await;
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => self.dead_letter(envelope, e.to_string(), 0).await,
+        }
+        self.checkpoints.save(&self.options.name, self.topic.name(), timestamp);
+    }
+
+    async fn dead_letter(&self, envelope: EventEnvelope, error: String, attempts: u32) {
+        tracing::error!(consumer = %self.options.name, topic = %envelope.topic, id = %envelope.id, error = %error, "Dead-lettering message");
+        let letter = DeadLetter {
+            consumer: self.options.name.clone(),
+            topic: envelope.topic.clone(),
+            envelope,
+            error,
+            attempts,
+            failed_at: Utc::now(),
+        };
+        if let Err(e) = self.bus.publish_message(&self.options.name, &DEAD_LETTER, &letter).await {
+            tracing::error!(consumer = %self.options.name, error = %e, "Failed to publish dead letter");
+        }
+    }
+}
+
+async fn next_replayed(replayed: &mut Option<TypedSubscription<EventEnvelope>>) -> Option<ChaosResult<EventEnvelope>> {
+    match replayed {
+        Some(replayed) => replayed.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Bounded set of recently seen ids.
+struct RecentIds {
+    capacity: usize,
+    order: VecDeque<Uuid>,
+    ids: HashSet<Uuid>,
+}
+
+impl RecentIds {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::new(),
+            ids: HashSet::new(),
+        }
+    }
+
+    /// Remember an id, `false` when it was seen already.
+    fn insert(&mut self, id: Uuid) -> bool {
+        if !self.ids.insert(id) {
+            return false;
+        }
+        self.order.push_back(id);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+fn key(consumer: &str, topic: &str) -> String {
+    format!("{}/{}", consumer, topic)
+}
+
+fn advance(positions: &mut HashMap<String, Timestamp>, consumer: &str, topic: &str, timestamp: Timestamp) {
+    let position = positions.entry(key(consumer, topic)).or_insert(timestamp);
+    if timestamp > *position {
+        *position = timestamp;
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,116 @@
+//! In-process event bus backed by tokio broadcast channels.
+
+use super::{validate_topic, EventBus, EventEnvelope, Subscription};
+use crate::error::{ChaosError, ChaosResult};
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Default number of events buffered per topic.
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Event bus that delivers events within the current process.
+///
+/// Each topic gets its own broadcast channel. Events published while nobody is
+/// subscribed are dropped, matching NATS core semantics. Subscribers that fall more
+/// than the channel capacity behind skip the missed events.
+#[derive(Debug)]
+pub struct InProcessEventBus {
+    channels: RwLock<HashMap<String, broadcast::Sender<EventEnvelope>>>,
+    capacity: usize,
+}
+
+impl Default for InProcessEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InProcessEventBus {
+    /// Create a bus with the default per-topic capacity.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CHANNEL_CAPACITY)
+    }
+
+    /// Create a bus with a custom per-topic capacity.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            channels: RwLock::new(HashMap::new()),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Get the number of active subscribers on a topic.
+    pub fn subscriber_count(&self, topic: &str) -> usize {
+        self.channels
+            .read()
+            .map(|channels| channels.get(topic).map_or(0, |sender| sender.receiver_count()))
+            .unwrap_or(0)
+    }
+
+    fn sender(&self, topic: &str) -> ChaosResult<broadcast::Sender<EventEnvelope>> {
+        if let Some(sender) = self.read_channels()?.get(topic) {
+            return Ok(sender.clone());
+        }
+
+        let mut channels = self
+            .channels
+            .write()
+            .map_err(|_| ChaosError::Internal("Event bus lock poisoned".to_string()))?;
+        Ok(channels
+            .entry(topic.to_string())
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .clone())
+    }
+
+    fn read_channels(
+        &self,
+    ) -> ChaosResult<std::sync::RwLockReadGuard<'_, HashMap<String, broadcast::Sender<EventEnvelope>>>> {
+        self.channels
+            .read()
+            .map_err(|_| ChaosError::Internal("Event bus lock poisoned".to_string()))
+    }
+}
+
+#[async_trait]
+impl EventBus for InProcessEventBus {
+    async fn publish_envelope(&self, envelope: EventEnvelope) -> ChaosResult<()> {
+        validate_topic(&envelope.topic)?;
+
+        if let Some(sender) = self.read_channels()?.get(&envelope.topic) {
+            // No receivers is not an error: the event is simply dropped
+            let _ = sender.send(envelope);
+        }
+        Ok(())
+    }
+
+    async fn subscribe_topic(&self, topic: &str) -> ChaosResult<Subscription> {
+        validate_topic(topic)?;
+
+        let receiver = self.sender(topic)?.subscribe();
+        let topic_name = topic.to_string();
+        let stream = stream::unfold(receiver, move |mut receiver| {
+            let topic_name = topic_name.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(envelope) => return Some((envelope, receiver)),
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!(topic = %topic_name, skipped, "Event subscriber lagged, events dropped");
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        });
+
+        Ok(Subscription::new(topic, stream.boxed()))
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "in-process"
+    }
+}
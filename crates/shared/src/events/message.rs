@@ -0,0 +1,77 @@
+//! Versioned messages exchanged between services.
+//!
+//! A [`Message`] travels in an [`EventEnvelope`] stamped with its type name and schema
+//! version. Consumers decode with [`EventEnvelope::decode_message`], which upgrades
+//! payloads from older publishers through [`Message::upgrade`] and rejects payloads
+//! from newer ones instead of misreading them. Plain events without a type or version
+//! (publishers predating versioning) decode as the current version.
+
+use super::{Event, EventEnvelope, Topic};
+use crate::error::{ChaosError, ChaosResult};
+use chrono::Utc;
+use std::cmp::Ordering;
+use uuid::Uuid;
+
+/// Event with a stable type name and schema version.
+pub trait Message: Event {
+    /// Type name, unique across the bus (e.g. `world.actor_spawned`).
+    const TYPE: &'static str;
+
+    /// Current schema version, starting at 1. Bump it on incompatible payload changes.
+    const VERSION: u32;
+
+    /// Convert a payload of an older `version` to the current schema.
+    ///
+    /// The default accepts no older versions.
+    fn upgrade(version: u32, payload: serde_json::Value) -> ChaosResult<serde_json::Value> {
+        let _ = payload;
+        Err(ChaosError::Validation(format!(
+            "{} v{} cannot be upgraded to v{}",
+            Self::TYPE,
+            version,
+            Self::VERSION
+        )))
+    }
+}
+
+impl EventEnvelope {
+    /// Wrap a versioned message for a topic.
+    pub fn message<M: Message>(topic: &Topic<M>, message: &M) -> ChaosResult<Self> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            topic: topic.name().to_string(),
+            source: None,
+            timestamp: Utc::now(),
+            payload: serde_json::to_value(message)?,
+            message_type: Some(M::TYPE.to_string()),
+            version: M::VERSION,
+        })
+    }
+
+    /// Decode the payload as a versioned message, upgrading older versions.
+    pub fn decode_message<M: Message>(&self) -> ChaosResult<M> {
+        if let Some(message_type) = &self.message_type {
+            if message_type != M::TYPE {
+                return Err(ChaosError::Validation(format!(
+                    "Expected a {} message, got {}",
+                    M::TYPE,
+                    message_type
+                )));
+            }
+        }
+        if self.message_type.is_none() && self.version == 0 {
+            return self.decode();
+        }
+
+        match self.version.cmp(&M::VERSION) {
+            Ordering::Equal => self.decode(),
+            Ordering::Less => Ok(serde_json::from_value(M::upgrade(self.version, self.payload.clone())?)?),
+            Ordering::Greater => Err(ChaosError::Validation(format!(
+                "{} v{} is newer than the supported v{}",
+                M::TYPE,
+                self.version,
+                M::VERSION
+            ))),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+//! Shared event bus for publishing and subscribing to game events.
+//!
+//! Every service and core crate publishes through the same [`EventBus`] trait instead of
+//! inventing its own sink. Events travel as JSON [`EventEnvelope`]s on named topics;
+//! [`Topic`] adds a compile-time event type on top so publishers and subscribers agree
+//! on the payload.
+//!
+//! Backends:
+//! - [`InProcessEventBus`]: tokio broadcast channels, for tests and single-process setups.
+//! - [`NatsEventBus`]: NATS, for production (behind the `nats` feature).
+//!
+//! Messages exchanged between services implement [`Message`], which gives them a type
+//! name and schema version; [`topics`] lists the topics and their conventions.
+//! [`Consumer`] handles them with retries, dead letters and replay after a restart
+//! from the publishers' [`ReplayingBus`] buffers.
+
+pub mod consumer;
+pub mod in_process;
+pub mod message;
+#[cfg(feature = "nats")]
+pub mod nats;
+pub mod replay;
+pub mod topics;
+
+pub use consumer::{Checkpoints, Consumer, ConsumerOptions, DeadLetter, FileCheckpoints, MemoryCheckpoints};
+pub use in_process::InProcessEventBus;
+pub use message::Message;
+#[cfg(feature = "nats")]
+pub use nats::NatsEventBus;
+pub use replay::{ReplayBuffer, ReplayRequest, ReplayingBus};
+
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use uuid::Uuid;
+
+/// Marker trait for types that can be published as events.
+pub trait Event: Serialize + DeserializeOwned + Send + Sync + 'static {}
+
+impl<T> Event for T where T: Serialize + DeserializeOwned + Send + Sync + 'static {}
+
+/// A named topic carrying events of type `E`.
+pub struct Topic<E> {
+    name: Cow<'static, str>,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E> Topic<E> {
+    /// Create a topic with a static name.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name: Cow::Borrowed(name),
+            _event: PhantomData,
+        }
+    }
+
+    /// Create a topic with a runtime name (e.g. per-zone topics).
+    pub fn dynamic(name: impl Into<String>) -> Self {
+        Self {
+            name: Cow::Owned(name.into()),
+            _event: PhantomData,
+        }
+    }
+
+    /// Get the topic name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl<E> Clone for Topic<E> {
+    fn clone(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            _event: PhantomData,
+        }
+    }
+}
+
+impl<E> std::fmt::Debug for Topic<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Topic").field("name", &self.name).finish()
+    }
+}
+
+/// Wire format for events on the bus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    /// Unique event identifier
+    pub id: Uuid,
+    /// Topic the event was published on
+    pub topic: String,
+    /// Publishing service or crate, if known
+    pub source: Option<String>,
+    /// When the event was published
+    pub timestamp: Timestamp,
+    /// Event payload
+    pub payload: serde_json::Value,
+    /// Type name of a versioned [`Message`], absent for plain events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_type: Option<String>,
+    /// Schema version of a [`Message`] payload, 0 for plain events
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl EventEnvelope {
+    /// Wrap an event for a topic.
+    pub fn new<E: Event>(topic: &Topic<E>, event: &E) -> ChaosResult<Self> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            topic: topic.name().to_string(),
+            source: None,
+            timestamp: Utc::now(),
+            payload: serde_json::to_value(event)?,
+            message_type: None,
+            version: 0,
+        })
+    }
+
+    /// Set the publishing source.
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Decode the payload as a typed event.
+    pub fn decode<E: Event>(&self) -> ChaosResult<E> {
+        Ok(serde_json::from_value(self.payload.clone())?)
+    }
+}
+
+/// Stream of raw events received on a topic.
+pub struct Subscription {
+    topic: String,
+    stream: BoxStream<'static, EventEnvelope>,
+}
+
+impl Subscription {
+    /// Create a subscription from a stream of envelopes.
+    pub fn new(topic: impl Into<String>, stream: BoxStream<'static, EventEnvelope>) -> Self {
+        Self {
+            topic: topic.into(),
+            stream,
+        }
+    }
+
+    /// Get the subscribed topic.
+    pub fn topic(&self) -> &str {
+        &self.topic
+    }
+
+    /// Wait for the next event, or `None` once the bus is closed.
+    pub async fn next(&mut self) -> Option<EventEnvelope> {
+        self.stream.next().await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = EventEnvelope;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+impl std::fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Subscription").field("topic", &self.topic).finish()
+    }
+}
+
+/// Subscription that decodes events of type `E`.
+#[derive(Debug)]
+pub struct TypedSubscription<E> {
+    inner: Subscription,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<E: Event> TypedSubscription<E> {
+    /// Get the subscribed topic.
+    pub fn topic(&self) -> &str {
+        self.inner.topic()
+    }
+
+    /// Wait for the next event; payloads that fail to decode are returned as errors.
+    pub async fn next(&mut self) -> Option<ChaosResult<E>> {
+        let envelope = self.inner.next().await?;
+        Some(envelope.decode())
+    }
+
+    /// Wait for the next event together with its envelope.
+    pub async fn next_with_envelope(&mut self) -> Option<ChaosResult<(E, EventEnvelope)>> {
+        let envelope = self.inner.next().await?;
+        Some(envelope.decode().map(|event| (event, envelope)))
+    }
+
+    /// Get the raw subscription.
+    pub fn into_inner(self) -> Subscription {
+        self.inner
+    }
+}
+
+/// Publish/subscribe integration point shared by all services and core crates.
+#[async_trait]
+pub trait EventBus: Send + Sync {
+    /// Publish a raw envelope on its topic.
+    async fn publish_envelope(&self, envelope: EventEnvelope) -> ChaosResult<()>;
+
+    /// Subscribe to raw envelopes on a topic.
+    async fn subscribe_topic(&self, topic: &str) -> ChaosResult<Subscription>;
+
+    /// Get the backend name (for logging and health checks).
+    fn backend_name(&self) -> &'static str;
+
+    /// Whether the bus can currently deliver events; backends without a connection always can.
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    /// Wait until published events have been handed to the server, e.g. before exiting.
+    async fn flush(&self) -> ChaosResult<()> {
+        Ok(())
+    }
+}
+
+/// Typed publish/subscribe helpers available on every [`EventBus`].
+#[async_trait]
+pub trait EventBusExt: EventBus {
+    /// Publish a typed event.
+    async fn publish<E: Event>(&self, topic: &Topic<E>, event: &E) -> ChaosResult<()> {
+        self.publish_envelope(EventEnvelope::new(topic, event)?).await
+    }
+
+    /// Publish a typed event tagged with its source.
+    async fn publish_from<E: Event>(&self, source: &str, topic: &Topic<E>, event: &E) -> ChaosResult<()> {
+        self.publish_envelope(EventEnvelope::new(topic, event)?.with_source(source)).await
+    }
+
+    /// Publish a versioned message tagged with its source.
+    async fn publish_message<M: Message>(&self, source: &str, topic: &Topic<M>, message: &M) -> ChaosResult<()> {
+        self.publish_envelope(EventEnvelope::message(topic, message)?.with_source(source)).await
+    }
+
+    /// Subscribe to typed events.
+    async fn subscribe<E: Event>(&self, topic: &Topic<E>) -> ChaosResult<TypedSubscription<E>> {
+        Ok(TypedSubscription {
+            inner: self.subscribe_topic(topic.name()).await?,
+            _event: PhantomData,
+        })
+    }
+}
+
+impl<T: EventBus + ?Sized> EventBusExt for T {}
+
+/// Validate a topic name.
+///
+/// Topics are dot-separated tokens (e.g. `combat.damage`, `world.zone.42`) so they map
+/// directly onto NATS subjects.
+pub fn validate_topic(topic: &str) -> ChaosResult<()> {
+    let valid = !topic.is_empty()
+        && topic.split('.').all(|token| {
+            !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ChaosError::Validation(format!("Invalid event topic: '{}'", topic)))
+    }
+}
+
+/// Where services find the event bus
+#[derive(Debug, Clone, Default)]
+pub struct BusConfig {
+    /// NATS server; without it events stay within the process
+    pub nats_url: Option<String>,
+    /// Subject prefix shared by the services of one environment
+    pub subject_prefix: Option<String>,
+}
+
+impl BusConfig {
+    /// Read `NATS_URL` and `NATS_SUBJECT_PREFIX`.
+    pub fn from_env() -> Self {
+        Self {
+            nats_url: std::env::var("NATS_URL").ok().filter(|url| !url.is_empty()),
+            subject_prefix: std::env::var("NATS_SUBJECT_PREFIX").ok().filter(|prefix| !prefix.is_empty()),
+        }
+    }
+}
+
+/// Connect to the configured bus, falling back to an in-process bus without a NATS URL.
+pub async fn connect(config: &BusConfig) -> ChaosResult<Arc<dyn EventBus>> {
+    match &config.nats_url {
+        #[cfg(feature = "nats")]
+        Some(nats_url) => {
+            let mut bus = NatsEventBus::connect(nats_url).await?;
+            if let Some(prefix) = &config.subject_prefix {
+                bus = bus.with_subject_prefix(prefix.clone())?;
+            }
+            Ok(Arc::new(bus))
+        }
+        #[cfg(not(feature = "nats"))]
+        Some(nats_url) => {
+            tracing::warn!("Built without the nats feature, ignoring {} and using an in-process event bus", nats_url);
+            Ok(Arc::new(InProcessEventBus::new()))
+        }
+        None => {
+            tracing::warn!("No NATS_URL set, events stay within this process");
+            Ok(Arc::new(InProcessEventBus::new()))
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! NATS event bus for production deployments.
+
+use super::{validate_topic, EventBus, EventEnvelope, Subscription};
+use crate::error::{ChaosError, ChaosResult};
+use async_trait::async_trait;
+use futures::stream::StreamExt;
+
+/// Event bus that publishes envelopes as JSON on NATS subjects.
+///
+/// Topic names map one-to-one onto subjects, optionally under a shared prefix
+/// (e.g. `chaos.prod`) so several environments can share a NATS cluster.
+#[derive(Debug, Clone)]
+pub struct NatsEventBus {
+    client: async_nats::Client,
+    subject_prefix: Option<String>,
+}
+
+impl NatsEventBus {
+    /// Connect to a NATS server.
+    pub async fn connect(url: &str) -> ChaosResult<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| ChaosError::Network(format!("Failed to connect to NATS at {}: {}", url, e)))?;
+        Ok(Self::from_client(client))
+    }
+
+    /// Wrap an existing NATS client.
+    pub fn from_client(client: async_nats::Client) -> Self {
+        Self {
+            client,
+            subject_prefix: None,
+        }
+    }
+
+    /// Publish and subscribe under a subject prefix.
+    pub fn with_subject_prefix(mut self, prefix: impl Into<String>) -> ChaosResult<Self> {
+        let prefix = prefix.into();
+        validate_topic(&prefix)?;
+        self.subject_prefix = Some(prefix);
+        Ok(self)
+    }
+
+    /// Get the underlying NATS client.
+    pub fn client(&self) -> &async_nats::Client {
+        &self.client
+    }
+
+    /// Flush pending publishes to the server.
+    pub async fn flush(&self) -> ChaosResult<()> {
+        self.client
+            .flush()
+            .await
+            .map_err(|e| ChaosError::Network(format!("Failed to flush NATS client: {}", e)))
+    }
+
+    fn subject(&self, topic: &str) -> String {
+        match &self.subject_prefix {
+            Some(prefix) => format!("{}.{}", prefix, topic),
+            None => topic.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl EventBus for NatsEventBus {
+    async fn publish_envelope(&self, envelope: EventEnvelope) -> ChaosResult<()> {
+        validate_topic(&envelope.topic)?;
+
+        let subject = self.subject(&envelope.topic);
+        let payload = serde_json::to_vec(&envelope)?;
+        self.client
+            .publish(subject.clone(), payload.into())
+            .await
+            .map_err(|e| ChaosError::Network(format!("Failed to publish to NATS subject {}: {}", subject, e)))
+    }
+
+    async fn subscribe_topic(&self, topic: &str) -> ChaosResult<Subscription> {
+        validate_topic(topic)?;
+
+        let subject = self.subject(topic);
+        let subscriber = self
+            .client
+            .subscribe(subject.clone())
+            .await
+            .map_err(|e| ChaosError::Network(format!("Failed to subscribe to NATS subject {}: {}", subject, e)))?;
+
+        let stream = subscriber.filter_map(move |message| {
+            let subject = subject.clone();
+            async move {
+                match serde_json::from_slice::<EventEnvelope>(&message.payload) {
+                    Ok(envelope) => Some(envelope),
+                    Err(e) => {
+                        tracing::warn!(subject = %subject, error = %e, "Dropping malformed event envelope");
+                        None
+                    }
+                }
+            }
+        });
+
+        Ok(Subscription::new(topic, stream.boxed()))
+    }
+
+    fn backend_name(&self) -> &'static str {
+        "nats"
+    }
+
+    fn is_connected(&self) -> bool {
+        self.client.connection_state() == async_nats::connection::State::Connected
+    }
+
+    async fn flush(&self) -> ChaosResult<()> {
+        NatsEventBus::flush(self).await
+    }
+}
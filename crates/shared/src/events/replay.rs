@@ -0,0 +1,199 @@
+//! Replay of recently published events for consumers that restart.
+//!
+//! Neither backend keeps events for absent subscribers, so a consumer that restarts
+//! misses whatever was published while it was down. Publishers wrap their bus in a
+//! [`ReplayingBus`], which keeps the last events of every topic it published on in a
+//! bounded [`ReplayBuffer`] and answers [`ReplayRequest`]s: a restarted consumer asks
+//! for a topic's events after its last checkpoint and receives them on its own reply
+//! topic ([`topics::replay_reply`]), wrapped in envelopes. Replayed events may overlap
+//! with live ones; consumers drop duplicates by event id.
+
+use super::topics::{self, REPLAY_REQUEST};
+use super::{EventBus, EventBusExt, EventEnvelope, Message, Subscription, Topic};
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+/// Default number of events kept per topic.
+pub const DEFAULT_REPLAY_CAPACITY: usize = 1024;
+
+/// A consumer asking for events it missed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayRequest {
+    /// Consumer name, also the last token of its reply topic
+    pub consumer: String,
+    pub topic: String,
+    /// Only events published after this; everything buffered when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Timestamp>,
+}
+
+impl Message for ReplayRequest {
+    const TYPE: &'static str = "system.replay_request";
+    const VERSION: u32 = 1;
+}
+
+/// Bounded per-topic history of published events.
+#[derive(Debug)]
+pub struct ReplayBuffer {
+    capacity: usize,
+    topics: Mutex<HashMap<String, VecDeque<EventEnvelope>>>,
+}
+
+impl Default for ReplayBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_REPLAY_CAPACITY)
+    }
+}
+
+impl ReplayBuffer {
+    /// Create a buffer keeping up to `capacity` events per topic.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            topics: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remember a published event, dropping the topic's oldest when full.
+    pub fn record(&self, envelope: &EventEnvelope) {
+        let mut topics = self.topics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let events = topics.entry(envelope.topic.clone()).or_default();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(envelope.clone());
+    }
+
+    /// Get a topic's buffered events published after `after`, oldest first.
+    pub fn since(&self, topic: &str, after: Option<Timestamp>) -> Vec<EventEnvelope> {
+        let topics = self.topics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        topics
+            .get(topic)
+            .map(|events| {
+                events
+                    .iter()
+                    .filter(|envelope| after.is_none_or(|after| envelope.timestamp > after))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Event bus that records what it publishes and replays it on request.
+pub struct ReplayingBus {
+    inner: Arc<dyn EventBus>,
+    buffer: ReplayBuffer,
+}
+
+impl ReplayingBus {
+    /// Wrap a bus with a buffer of the default capacity.
+    pub fn new(inner: Arc<dyn EventBus>) -> Self {
+        Self::with_buffer(inner, ReplayBuffer::default())
+    }
+
+    /// Wrap a bus with a custom buffer.
+    pub fn with_buffer(inner: Arc<dyn EventBus>, buffer: ReplayBuffer) -> Self {
+        Self { inner, buffer }
+    }
+
+    /// Get the buffer of published events.
+    pub fn buffer(&self) -> &ReplayBuffer {
+        &self.buffer
+    }
+
+    /// Answer replay requests until the bus closes.
+    ///
+    /// Requests for topics this instance never published on are ignored, so every
+    /// publisher can serve requests without coordination.
+    pub async fn serve(self: Arc<Self>) -> ChaosResult<JoinHandle<()>> {
+        let mut requests = self.inner.subscribe(&REPLAY_REQUEST).await?;
+        Ok(tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let request = match request {
+                    Ok(request) => request,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Ignoring malformed replay request");
+                        continue;
+                    }
+                };
+                if let Err(e) = self.replay(&request).await {
+                    tracing::warn!(consumer = %request.consumer, topic = %request.topic, error = %e, "Replay failed");
+                }
+            }
+        }))
+    }
+
+    async fn replay(&self, request: &ReplayRequest) -> ChaosResult<()> {
+        let events = self.buffer.since(&request.topic, request.after);
+        if events.is_empty() {
+            return Ok(());
+        }
+        tracing::debug!(consumer = %request.consumer, topic = %request.topic, count = events.len(), "Replaying events");
+        let reply: Topic<EventEnvelope> = Topic::dynamic(topics::replay_reply(&request.consumer));
+        for envelope in &events {
+            // Straight to the inner bus: replies are not worth replaying themselves
+            self.inner.publish(&reply, envelope).await?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for ReplayingBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReplayingBus")
+            .field("backend", &self.inner.backend_name())
+            .field("buffer", &self.buffer)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl EventBus for ReplayingBus {
+    async fn publish_envelope(&self, envelope: EventEnvelope) -> ChaosResult<()> {
+        // System topics carry requests and replies, not state worth replaying
+        if !envelope.topic.starts_with("system.") {
+            self.buffer.record(&envelope);
+        }
+        self.inner.publish_envelope(envelope).await
+    }
+
+    async fn subscribe_topic(&self, topic: &str) -> ChaosResult<Subscription> {
+        self.inner.subscribe_topic(topic).await
+    }
+
+    fn backend_name(&self) -> &'static str {
+        self.inner.backend_name()
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    async fn flush(&self) -> ChaosResult<()> {
+        self.inner.flush().await
+    }
+}
+
+/// Ask the publishers of `topic` to replay what `consumer` missed since `after`.
+pub async fn request_replay(
+    bus: &dyn EventBus,
+    consumer: &str,
+    topic: &str,
+    after: Option<Timestamp>,
+) -> ChaosResult<()> {
+    if consumer.contains('.') {
+        return Err(ChaosError::Validation(format!("Consumer name '{}' must be a single topic token", consumer)));
+    }
+    let request = ReplayRequest {
+        consumer: consumer.to_string(),
+        topic: topic.to_string(),
+        after,
+    };
+    bus.publish_envelope(EventEnvelope::message(&REPLAY_REQUEST, &request)?.with_source(consumer)).await
+}
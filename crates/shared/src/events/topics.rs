@@ -0,0 +1,701 @@
+//! Topics shared between services and the messages they carry.
+//!
+//! Conventions:
+//! - Names are `<domain>.<entity>.<event>` in lowercase snake case, e.g.
+//!   `world.actor.spawned`. The domain is the owning service's area (`world`, `combat`,
+//!   `event`, `cms`, `game`), not the publishing binary.
+//! - Per-instance topics append the id as the last token: `world.zone.<zone_id>`.
+//! - Infrastructure lives under `system.*`: lifecycle, dead letters and replay.
+//! - Message type names mirror the topic with an underscore for the event:
+//!   `world.actor_spawned`. Payload changes that break old consumers bump
+//!   [`Message::VERSION`] and add an upgrade path.
+//!
+//! Flows between the game services:
+//! - chaos-backend publishes [`ACTOR_SPAWNED`] and [`ACTOR_DESPAWNED`] from the game
+//!   loop; world-service follows them to track zone populations.
+//! - event-service publishes [`WORLD_EVENT`] when scheduled world events start or end;
+//!   chaos-backend applies them to the simulation.
+//! - Every service announces itself on [`SERVICE_LIFECYCLE`].
+//! - Consumers that give up on a message publish it on [`DEAD_LETTER`]; event-service
+//!   keeps the recent ones for inspection.
+//! - Combat results go to [`COMBAT_LOG`], streamed to clients by the api crate.
+//! - Game servers report gameplay telemetry on [`ACTOR_STAT_CHANGED`],
+//!   [`ACTOR_EXPERIENCE_GAINED`], [`ACTOR_MOVED`] and [`DAMAGE_DEALT`];
+//!   anti-cheat-service checks it against its detection rules.
+//! - world-service hosts zones: it publishes [`ACTOR_SPAWNED`] / [`ACTOR_DESPAWNED`] for
+//!   the NPCs of its spawns and the entities it is told about, [`ACTOR_MOVED`] for position
+//!   updates, and [`ZoneWeatherChanged`] / [`ZoneHazardPulsed`] / [`ZoneAuraChanged`] on
+//!   the [`zone`] topics.
+//! - world-service instances taking over an actor from another instance announce it on
+//!   [`ACTOR_HANDED_OVER`]; the previous owner lets the actor go.
+//! - world-service instances running a zone publish its state on the
+//!   [`zone_replication`] topics; standby instances keep a copy to take the zone over
+//!   when the primary fails.
+//! - admin-cli asks world-service to move a zone to a standby instance on
+//!   [`ZONE_FAILOVER`]; the instance running the zone steps down and a standby takes it
+//!   over.
+//! - chaos-backend re-resolves sampled actors and reports snapshots it could not
+//!   reproduce on [`ACTOR_SNAPSHOT_MISMATCH`]; anti-cheat-service raises incidents for them.
+//! - The [`PartyManager`](crate::party::PartyManager) announces group changes as
+//!   [`PARTY_CHANGED`]; the api crate forwards them to the websocket `party:<party_id>`
+//!   channels.
+//! - The api crate's chat reports senders that flood channels or post filtered messages on
+//!   [`CHAT_ABUSE`]; anti-cheat-service checks them against its chat rules.
+//! - The [`Market`](crate::market::Market) reports every sale on [`MARKET_TRADE`] for
+//!   economy analytics; anti-cheat-service flags trades far from the item's usual price.
+//! - world-service runs NPC behavior trees; actions it cannot carry out itself, such as
+//!   attacks, go to the game servers on [`NPC_ACTION`].
+//! - world-service validates the positions game servers relay from clients and reports
+//!   the moves it refuses on [`MOVEMENT_REJECTED`]; anti-cheat-service raises incidents
+//!   for them.
+//! - [`Wallets`](crate::wallet::Wallets) report every balance change on
+//!   [`CURRENCY_CHANGED`] and game servers every loot roll on [`LOOT_DROPPED`];
+//!   analytics-service aggregates them with [`MARKET_TRADE`] into economy metrics.
+//! - item-core's group loot announces rolls, awards and trades of party drops on
+//!   [`PARTY_LOOT`]; the api crate forwards them to the websocket `party:<party_id>`
+//!   channels.
+//! - event-service runs event-core tutorials: kills reported on [`ACTOR_EXPERIENCE_GAINED`]
+//!   and loot on [`LOOT_DROPPED`] advance them, and their hints go out on
+//!   [`TUTORIAL_HINT`]; the api crate pushes them to the player's websocket sessions.
+
+use super::{Message, Topic};
+use crate::party::{LootMethod, LootRules, PartyRole, RollChoice};
+use crate::types::Timestamp;
+use crate::wallet::TransactionKind;
+use serde::{Deserialize, Serialize};
+
+pub use super::consumer::DeadLetter;
+pub use super::replay::ReplayRequest;
+
+/// Actors entering the world
+pub const ACTOR_SPAWNED: Topic<ActorSpawned> = Topic::new("world.actor.spawned");
+
+/// Actors leaving the world
+pub const ACTOR_DESPAWNED: Topic<ActorDespawned> = Topic::new("world.actor.despawned");
+
+/// Scheduled world events starting and ending
+pub const WORLD_EVENT: Topic<WorldEventChanged> = Topic::new("event.world.changed");
+
+/// Service instances starting and stopping
+pub const SERVICE_LIFECYCLE: Topic<ServiceLifecycle> = Topic::new("system.service.lifecycle");
+
+/// Messages consumers gave up on
+pub const DEAD_LETTER: Topic<DeadLetter> = Topic::new("system.dead_letter");
+
+/// Consumers asking publishers to replay what they missed
+pub const REPLAY_REQUEST: Topic<ReplayRequest> = Topic::new("system.replay.request");
+
+/// Combat log entries
+pub const COMBAT_LOG: &str = "combat.log";
+
+/// Actor stats changing outside of regeneration, e.g. level ups and equipment
+pub const ACTOR_STAT_CHANGED: Topic<ActorStatChanged> = Topic::new("world.actor.stat_changed");
+
+/// Experience awarded to actors
+pub const ACTOR_EXPERIENCE_GAINED: Topic<ExperienceGained> = Topic::new("world.actor.experience_gained");
+
+/// Actor positions, as accepted from clients
+pub const ACTOR_MOVED: Topic<ActorMoved> = Topic::new("world.actor.moved");
+
+/// Damage dealt by one actor to another
+pub const DAMAGE_DEALT: Topic<DamageDealt> = Topic::new("combat.damage.dealt");
+
+/// Cached or client-reported actor snapshots that differ from a fresh resolution
+pub const ACTOR_SNAPSHOT_MISMATCH: Topic<ActorSnapshotMismatch> = Topic::new("world.actor.snapshot_mismatch");
+
+/// Actors moving between world-service instances
+pub const ACTOR_HANDED_OVER: Topic<ActorHandedOver> = Topic::new("world.actor.handed_over");
+
+/// Operators moving zones between world-service instances
+pub const ZONE_FAILOVER: Topic<ZoneFailoverRequested> = Topic::new("world.zone_failover.requested");
+
+/// Parties and raids forming, changing and disbanding
+pub const PARTY_CHANGED: Topic<PartyChanged> = Topic::new("game.party.changed");
+
+/// Chat messages refused for flooding or moderation
+pub const CHAT_ABUSE: Topic<ChatAbuseReported> = Topic::new("game.chat.abuse_reported");
+
+/// Auction house sales
+pub const MARKET_TRADE: Topic<MarketTrade> = Topic::new("game.market.trade");
+
+/// Actions NPC behavior trees ask game servers to carry out
+pub const NPC_ACTION: Topic<NpcActionRequested> = Topic::new("world.npc.action_requested");
+
+/// Client positions world-service refused and put the actor back from
+pub const MOVEMENT_REJECTED: Topic<MovementRejected> = Topic::new("world.actor.movement_rejected");
+
+/// Currencies earned, spent and exchanged, one message per currency changed
+pub const CURRENCY_CHANGED: Topic<CurrencyChanged> = Topic::new("game.currency.changed");
+
+/// Loot rolled for actors, including rolls that dropped nothing
+pub const LOOT_DROPPED: Topic<LootDropped> = Topic::new("game.loot.dropped");
+
+/// Rolls, awards and trades of the items a party looted
+pub const PARTY_LOOT: Topic<PartyLootUpdated> = Topic::new("game.party.loot_updated");
+
+/// Tutorial hints shown and hidden on players' clients
+pub const TUTORIAL_HINT: Topic<TutorialHintChanged> = Topic::new("game.tutorial.hint_changed");
+
+/// Get the topic carrying a zone's events.
+pub fn zone(zone_id: &str) -> String {
+    format!("world.zone.{}", zone_id)
+}
+
+/// Get the topic carrying a zone's state to its standby instances.
+pub fn zone_replication(zone_id: &str) -> String {
+    format!("world.zone_replication.{}", zone_id)
+}
+
+/// Get the topic a consumer receives its replayed messages on.
+pub fn replay_reply(consumer: &str) -> String {
+    format!("system.replay.{}", consumer)
+}
+
+/// An actor entered the world
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorSpawned {
+    pub actor_id: String,
+    pub zone: Option<String>,
+    /// Spawn point of NPCs, absent for players
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn_point: Option<String>,
+}
+
+impl Message for ActorSpawned {
+    const TYPE: &'static str = "world.actor_spawned";
+    const VERSION: u32 = 1;
+}
+
+/// Why an actor left the world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DespawnReason {
+    Died,
+    LoggedOut,
+    Removed,
+}
+
+/// An actor left the world
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorDespawned {
+    pub actor_id: String,
+    pub zone: Option<String>,
+    pub reason: DespawnReason,
+}
+
+impl Message for ActorDespawned {
+    const TYPE: &'static str = "world.actor_despawned";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorldEventState {
+    Started,
+    Ended,
+}
+
+/// A scheduled world event (invasion, double experience, ...) started or ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorldEventChanged {
+    pub event_id: String,
+    pub name: String,
+    /// Zone the event is limited to, the whole world when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    pub state: WorldEventState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<Timestamp>,
+}
+
+impl Message for WorldEventChanged {
+    const TYPE: &'static str = "event.world_changed";
+    const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleState {
+    Started,
+    Stopping,
+    Stopped,
+}
+
+/// A service instance changed state
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceLifecycle {
+    pub service: String,
+    /// Distinguishes replicas of one service
+    pub instance: String,
+    pub state: LifecycleState,
+    /// Address the instance serves on, if it serves anything
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl Message for ServiceLifecycle {
+    const TYPE: &'static str = "system.service_lifecycle";
+    const VERSION: u32 = 1;
+}
+
+/// A stat of an actor changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorStatChanged {
+    pub actor_id: String,
+    pub stat: String,
+    pub previous: f64,
+    pub current: f64,
+    /// Highest value the actor may have, when the stat is capped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cap: Option<f64>,
+    /// What changed it, e.g. `level_up` or `equipment`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Message for ActorStatChanged {
+    const TYPE: &'static str = "world.actor_stat_changed";
+    const VERSION: u32 = 1;
+}
+
+/// An actor was awarded experience
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExperienceGained {
+    pub actor_id: String,
+    pub amount: f64,
+    /// What awarded it, e.g. `kill` or `quest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl Message for ExperienceGained {
+    const TYPE: &'static str = "world.actor_experience_gained";
+    const VERSION: u32 = 1;
+}
+
+/// An actor moved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorMoved {
+    pub actor_id: String,
+    pub zone: Option<String>,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// Movement speed stat of the actor, in units per second
+    pub max_speed: f64,
+    /// Set for server-initiated moves (teleports, zone changes) that skip the travel
+    #[serde(default)]
+    pub teleported: bool,
+}
+
+impl Message for ActorMoved {
+    const TYPE: &'static str = "world.actor_moved";
+    const VERSION: u32 = 1;
+}
+
+/// One actor damaged another
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamageDealt {
+    pub attacker_id: String,
+    pub target_id: String,
+    pub amount: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skill: Option<String>,
+}
+
+impl Message for DamageDealt {
+    const TYPE: &'static str = "combat.damage_dealt";
+    const VERSION: u32 = 1;
+}
+
+/// A snapshot of an actor could not be reproduced from its contributions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorSnapshotMismatch {
+    pub actor_id: String,
+    /// Where the snapshot came from: `cache` or `client`
+    pub source: String,
+    /// Hash of the fresh resolution
+    pub expected_hash: String,
+    pub observed_hash: String,
+    /// Stats that differ, when the observed values are known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stats: Vec<String>,
+}
+
+impl Message for ActorSnapshotMismatch {
+    const TYPE: &'static str = "world.actor_snapshot_mismatch";
+    const VERSION: u32 = 1;
+}
+
+/// The weather of a zone changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneWeatherChanged {
+    pub zone: String,
+    /// Weather names, e.g. `clear` or `storm`
+    pub previous: String,
+    pub current: String,
+    /// When the weather changes next
+    pub until: Timestamp,
+}
+
+impl Message for ZoneWeatherChanged {
+    const TYPE: &'static str = "world.zone_weather_changed";
+    const VERSION: u32 = 1;
+}
+
+/// A zone hazard pulsed on the actors inside it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneHazardPulsed {
+    pub zone: String,
+    pub hazard_id: String,
+    /// Resource affected, e.g. `health`
+    pub resource: String,
+    /// Added to the resource of every actor, negative for damage
+    pub amount: f64,
+    pub actor_ids: Vec<String>,
+}
+
+impl Message for ZoneHazardPulsed {
+    const TYPE: &'static str = "world.zone_hazard_pulsed";
+    const VERSION: u32 = 1;
+}
+
+/// Actors came under or left an aura in a zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneAuraChanged {
+    pub zone: String,
+    /// Area aura or actor projecting the aura
+    pub source_id: String,
+    pub aura_id: String,
+    pub applied: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Message for ZoneAuraChanged {
+    const TYPE: &'static str = "world.zone_aura_changed";
+    const VERSION: u32 = 1;
+}
+
+/// An instance took an actor over from another one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActorHandedOver {
+    pub actor_id: String,
+    pub from_instance: String,
+    pub to_instance: String,
+    /// Zone the actor entered
+    pub zone: String,
+    /// Presence epoch of the new owner
+    pub epoch: u64,
+}
+
+impl Message for ActorHandedOver {
+    const TYPE: &'static str = "world.actor_handed_over";
+    const VERSION: u32 = 1;
+}
+
+/// State of a zone, published by its primary instance after its ticks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneReplicated {
+    pub zone: String,
+    pub instance: String,
+    /// Fencing token of the primary's zone lease; standbys ignore lower ones
+    pub token: u64,
+    /// Numbers the replicas of a token, so standbys notice the ones they missed
+    pub seq: u64,
+    /// The state replaces the standby's copy instead of updating it
+    pub full: bool,
+    /// world-core's `ZoneReplica`
+    pub state: serde_json::Value,
+}
+
+impl Message for ZoneReplicated {
+    const TYPE: &'static str = "world.zone_replicated";
+    const VERSION: u32 = 1;
+}
+
+/// An operator asked the instance running a zone to hand it to a standby
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneFailoverRequested {
+    pub zone: String,
+    /// Standby instance to take the zone over; the first standby to notice when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_instance: Option<String>,
+    pub operator: String,
+    pub requested_at: Timestamp,
+}
+
+impl Message for ZoneFailoverRequested {
+    const TYPE: &'static str = "world.zone_failover_requested";
+    const VERSION: u32 = 1;
+}
+
+/// What changed in a party
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PartyChange {
+    Created { leader_id: String },
+    MemberJoined { actor_id: String },
+    MemberLeft { actor_id: String, kicked: bool },
+    LeaderChanged { leader_id: String },
+    RoleChanged { actor_id: String, role: PartyRole },
+    LootRulesChanged { rules: LootRules },
+    ConvertedToRaid,
+    Disbanded,
+}
+
+/// A party or raid changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyChanged {
+    pub party_id: String,
+    pub change: PartyChange,
+    pub at: Timestamp,
+}
+
+impl Message for PartyChanged {
+    const TYPE: &'static str = "game.party_changed";
+    const VERSION: u32 = 1;
+}
+
+/// Why a chat message was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatAbuseKind {
+    /// The sender went over the message rate limit
+    Flooding,
+    /// A moderation filter rejected the message
+    Filtered,
+}
+
+/// A chat message was refused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatAbuseReported {
+    /// Sending user
+    pub sender_id: String,
+    /// Chat target, e.g. `zone:forest-1` or `whisper:<user_id>`
+    pub channel: String,
+    pub kind: ChatAbuseKind,
+    /// Filter verdict or rate limit crossed
+    pub reason: String,
+    /// Start of the refused message
+    pub excerpt: String,
+}
+
+impl Message for ChatAbuseReported {
+    const TYPE: &'static str = "game.chat_abuse_reported";
+    const VERSION: u32 = 1;
+}
+
+/// An auction house listing was sold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketTrade {
+    pub listing_id: String,
+    pub item_id: String,
+    pub quantity: u32,
+    pub seller_id: String,
+    pub buyer_id: String,
+    /// Currency the buyer paid
+    pub price: u64,
+    /// Cut the market kept, removed from the economy
+    pub fee: u64,
+    /// Whether the buyer paid the buyout price
+    pub buyout: bool,
+    /// Median unit price of the item's recent sales, when it sold before
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_unit_price: Option<f64>,
+    pub at: Timestamp,
+}
+
+impl MarketTrade {
+    /// Price paid per item
+    pub fn unit_price(&self) -> f64 {
+        self.price as f64 / self.quantity.max(1) as f64
+    }
+}
+
+impl Message for MarketTrade {
+    const TYPE: &'static str = "game.market_trade";
+    const VERSION: u32 = 1;
+}
+
+/// An NPC's behavior tree chose an action, e.g. `attack` with `{ "skill": "bite" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NpcActionRequested {
+    pub npc_id: String,
+    pub zone: String,
+    pub action: String,
+    /// Entity the NPC is targeting, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_id: Option<String>,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+impl Message for NpcActionRequested {
+    const TYPE: &'static str = "world.npc_action_requested";
+    const VERSION: u32 = 1;
+}
+
+/// Why a reported position was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementViolationKind {
+    /// Faster than the actor's speed stat allows
+    Speed,
+    /// Too far in one update to have been travelled
+    Teleport,
+    /// Into or through an obstacle
+    Collision,
+    /// Outside the zone bounds
+    OutOfBounds,
+}
+
+impl MovementViolationKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MovementViolationKind::Speed => "speed",
+            MovementViolationKind::Teleport => "teleport",
+            MovementViolationKind::Collision => "collision",
+            MovementViolationKind::OutOfBounds => "out_of_bounds",
+        }
+    }
+}
+
+/// A reported position was refused and the actor put back where it was
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MovementRejected {
+    pub actor_id: String,
+    pub zone: String,
+    pub kind: MovementViolationKind,
+    /// Last accepted position, where the actor was put back
+    pub from: [f64; 3],
+    /// Position the client claimed
+    pub attempted: [f64; 3],
+    /// Measured value (speed, distance), equal to `limit` for collisions and bounds
+    pub observed: f64,
+    pub limit: f64,
+}
+
+impl Message for MovementRejected {
+    const TYPE: &'static str = "world.actor_movement_rejected";
+    const VERSION: u32 = 1;
+}
+
+/// A wallet balance changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CurrencyChanged {
+    /// Shared by the currencies changed in one operation, e.g. both sides of an exchange
+    pub transaction_id: String,
+    pub actor_id: String,
+    pub currency: String,
+    /// Amount credited (positive) or debited (negative)
+    pub delta: i64,
+    pub balance: u64,
+    pub kind: TransactionKind,
+    /// What the currency was earned or spent on, e.g. `quest:1042`
+    pub reason: String,
+    pub at: Timestamp,
+}
+
+impl CurrencyChanged {
+    /// Kind of faucet or sink, the reason up to its first `:`, e.g. `quest`
+    pub fn source(&self) -> &str {
+        self.reason.split(':').next().unwrap_or_default()
+    }
+}
+
+impl Message for CurrencyChanged {
+    const TYPE: &'static str = "game.currency_changed";
+    const VERSION: u32 = 1;
+}
+
+/// An item stack that dropped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedItem {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+/// Loot was rolled for an actor, e.g. on killing an NPC
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootDropped {
+    pub actor_id: String,
+    /// What was looted, e.g. the loot table or NPC template
+    pub source: String,
+    /// Empty when the roll dropped nothing
+    pub items: Vec<DroppedItem>,
+    pub at: Timestamp,
+}
+
+impl Message for LootDropped {
+    const TYPE: &'static str = "game.loot_dropped";
+    const VERSION: u32 = 1;
+}
+
+/// What happened to an item a party looted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PartyLootUpdate {
+    /// The eligible members may roll until `ends_at`
+    RollStarted { eligible: Vec<String>, ends_at: Timestamp },
+    /// A member chose; passes carry no roll
+    Rolled { actor_id: String, choice: RollChoice, roll: Option<u32> },
+    /// The master looter has to hand the item out
+    AwaitingMasterLooter { master_looter: String },
+    /// Any eligible member may pick the item up
+    FreeForAll,
+    /// The item went to a member; `choice` and `roll` are set when it was won by rolling
+    Awarded {
+        actor_id: String,
+        method: LootMethod,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        choice: Option<RollChoice>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        roll: Option<u32>,
+    },
+    /// The winner handed the item to another eligible member within the trade window
+    Traded { from: String, to: String },
+}
+
+/// An item a party looted was rolled for, awarded or traded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyLootUpdated {
+    pub party_id: String,
+    pub loot_id: String,
+    pub item_id: String,
+    pub quantity: u32,
+    pub update: PartyLootUpdate,
+    pub at: Timestamp,
+}
+
+impl Message for PartyLootUpdated {
+    const TYPE: &'static str = "game.party_loot_updated";
+    const VERSION: u32 = 1;
+}
+
+/// Hint of the tutorial step a player is on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TutorialHint {
+    pub step_id: String,
+    pub text: String,
+    /// UI element the client highlights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<String>,
+}
+
+/// A player's tutorial moved to another step, or its hint is to be hidden
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TutorialHintChanged {
+    /// User whose sessions show the hint
+    pub user_id: String,
+    pub actor_id: String,
+    pub tutorial_id: String,
+    /// Hint to show; none once the tutorial is skipped or completed
+    #[serde(default)]
+    pub hint: Option<TutorialHint>,
+    pub at: Timestamp,
+}
+
+impl Message for TutorialHintChanged {
+    const TYPE: &'static str = "game.tutorial_hint_changed";
+    const VERSION: u32 = 1;
+}
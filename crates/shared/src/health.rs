@@ -0,0 +1,410 @@
+//! Liveness, readiness and dependency health checks.
+//!
+//! Services expose two probes:
+//! - `/health/live` answers as long as the process serves requests; orchestrators
+//!   restart the service when it fails. It never touches dependencies, so a database
+//!   outage does not cause restart loops.
+//! - `/health/ready` runs the dependency checks registered on a [`HealthRegistry`] and
+//!   answers 503 when a critical one fails, so load balancers stop routing to the
+//!   instance. Failing optional checks only mark the service degraded.
+//!
+//! Once a [`Shutdown`] the registry follows is triggered, readiness fails without
+//! running the checks so the instance is taken out of rotation while it drains.
+//!
+//! Check results are cached for a short time so frequent probes from several load
+//! balancers do not hammer the dependencies, and every check runs with a timeout so a
+//! hanging dependency cannot hang the probe.
+
+use crate::error::{ChaosError, ChaosResult};
+use crate::events::EventBus;
+use crate::shutdown::Shutdown;
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Probe path for liveness
+pub const LIVE_PATH: &str = "/health/live";
+
+/// Probe path for readiness
+pub const READY_PATH: &str = "/health/ready";
+
+/// How long check results are reused by default
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// How long a check may take by default
+pub const DEFAULT_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Health of a service or one of its dependencies, from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Healthy,
+    /// Working with reduced functionality
+    Degraded,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    /// Whether traffic should be routed to the service
+    pub fn is_ready(self) -> bool {
+        self != HealthStatus::Unhealthy
+    }
+
+    /// HTTP status code of a probe response
+    pub fn http_status(self) -> u16 {
+        if self.is_ready() {
+            200
+        } else {
+            503
+        }
+    }
+}
+
+/// Outcome of one dependency check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub status: HealthStatus,
+    /// Whether failing makes the service unready
+    pub critical: bool,
+    pub latency_ms: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub checked_at: Timestamp,
+}
+
+/// Probe response body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub service: String,
+    pub status: HealthStatus,
+    pub uptime_secs: u64,
+    /// Dependency checks by name, empty for liveness
+    #[serde(default)]
+    pub checks: BTreeMap<String, CheckResult>,
+}
+
+/// A dependency check.
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    /// Check the dependency, failing with the reason it is unusable.
+    async fn check(&self) -> ChaosResult<()>;
+}
+
+/// Check backed by a closure, for dependencies only the service knows how to reach.
+pub struct FnCheck<F>(F);
+
+/// Create a check from a closure returning a future.
+pub fn check_fn<F, Fut>(check: F) -> FnCheck<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = ChaosResult<()>> + Send,
+{
+    FnCheck(check)
+}
+
+#[async_trait]
+impl<F, Fut> HealthCheck for FnCheck<F>
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = ChaosResult<()>> + Send,
+{
+    async fn check(&self) -> ChaosResult<()> {
+        (self.0)().await
+    }
+}
+
+/// Healthy when a TCP connection can be opened, e.g. to a downstream service.
+#[derive(Debug, Clone)]
+pub struct TcpCheck {
+    address: String,
+}
+
+impl TcpCheck {
+    /// Check a `host:port` address.
+    pub fn new(address: impl Into<String>) -> Self {
+        Self { address: address.into() }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for TcpCheck {
+    async fn check(&self) -> ChaosResult<()> {
+        TcpStream::connect(&self.address).await?;
+        Ok(())
+    }
+}
+
+/// Healthy when a Redis server answers `PING`.
+#[derive(Debug, Clone)]
+pub struct RedisCheck {
+    address: String,
+}
+
+impl RedisCheck {
+    /// Check a Redis `host:port` address or `redis://host:port` URL.
+    pub fn new(address: impl AsRef<str>) -> Self {
+        let address = address.as_ref();
+        let address = address.strip_prefix("redis://").unwrap_or(address);
+        // Credentials and database number are not needed to answer PING
+        let address = address.rsplit('@').next().unwrap_or(address);
+        let address = address.split('/').next().unwrap_or(address);
+        Self { address: address.to_string() }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for RedisCheck {
+    async fn check(&self) -> ChaosResult<()> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        stream.write_all(b"PING\r\n").await?;
+        let mut reply = [0u8; 64];
+        let read = stream.read(&mut reply).await?;
+        let reply = String::from_utf8_lossy(&reply[..read]);
+        // Servers requiring authentication answer -NOAUTH, which still proves they are up
+        if reply.starts_with("+PONG") || reply.starts_with("-NOAUTH") {
+            Ok(())
+        } else {
+            Err(ChaosError::ExternalService(format!("Unexpected PING reply: {}", reply.trim_end())))
+        }
+    }
+}
+
+/// Healthy while the event bus is connected.
+pub struct BusCheck {
+    bus: Arc<dyn EventBus>,
+}
+
+impl BusCheck {
+    pub fn new(bus: Arc<dyn EventBus>) -> Self {
+        Self { bus }
+    }
+}
+
+#[async_trait]
+impl HealthCheck for BusCheck {
+    async fn check(&self) -> ChaosResult<()> {
+        if self.bus.is_connected() {
+            Ok(())
+        } else {
+            Err(ChaosError::Network(format!("{} event bus is disconnected", self.bus.backend_name())))
+        }
+    }
+}
+
+/// Healthy when a `GET` on a URL succeeds, e.g. a downstream service's readiness probe.
+#[cfg(feature = "health-http")]
+pub struct HttpCheck {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "health-http")]
+impl HttpCheck {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+        }
+    }
+}
+
+#[cfg(feature = "health-http")]
+#[async_trait]
+impl HealthCheck for HttpCheck {
+    async fn check(&self) -> ChaosResult<()> {
+        let response = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .map_err(|e| ChaosError::Network(e.to_string()))?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(ChaosError::ExternalService(format!("{} answered {}", self.url, response.status())))
+        }
+    }
+}
+
+struct Registered {
+    name: String,
+    critical: bool,
+    check: Arc<dyn HealthCheck>,
+    cached: Mutex<Option<(Instant, CheckResult)>>,
+}
+
+/// Dependency checks of a service.
+pub struct HealthRegistry {
+    service: String,
+    started: Instant,
+    cache_ttl: Duration,
+    timeout: Duration,
+    checks: Vec<Registered>,
+    shutdown: Option<Shutdown>,
+}
+
+impl HealthRegistry {
+    /// Create a registry without checks.
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            started: Instant::now(),
+            cache_ttl: DEFAULT_CACHE_TTL,
+            timeout: DEFAULT_CHECK_TIMEOUT,
+            checks: Vec::new(),
+            shutdown: None,
+        }
+    }
+
+    /// Reuse check results for `ttl`.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Fail checks taking longer than `timeout`.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Report unready once `shutdown` is triggered.
+    pub fn with_shutdown(mut self, shutdown: &Shutdown) -> Self {
+        self.shutdown = Some(shutdown.clone());
+        self
+    }
+
+    /// Add a check the service cannot work without.
+    pub fn register(self, name: impl Into<String>, check: impl HealthCheck + 'static) -> Self {
+        self.add(name.into(), true, Arc::new(check))
+    }
+
+    /// Add a check whose failure only degrades the service.
+    pub fn register_optional(self, name: impl Into<String>, check: impl HealthCheck + 'static) -> Self {
+        self.add(name.into(), false, Arc::new(check))
+    }
+
+    fn add(mut self, name: String, critical: bool, check: Arc<dyn HealthCheck>) -> Self {
+        self.checks.push(Registered {
+            name,
+            critical,
+            check,
+            cached: Mutex::new(None),
+        });
+        self
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Report that the process is alive.
+    pub fn liveness(&self) -> HealthReport {
+        HealthReport {
+            service: self.service.clone(),
+            status: HealthStatus::Healthy,
+            uptime_secs: self.started.elapsed().as_secs(),
+            checks: BTreeMap::new(),
+        }
+    }
+
+    /// Run the dependency checks, reusing recent results.
+    pub async fn readiness(&self) -> HealthReport {
+        if self.shutdown.as_ref().is_some_and(Shutdown::is_triggered) {
+            return self.shutting_down();
+        }
+
+        let results = join_all(self.checks.iter().map(|registered| self.run(registered))).await;
+        let checks: BTreeMap<String, CheckResult> = self
+            .checks
+            .iter()
+            .map(|registered| registered.name.clone())
+            .zip(results)
+            .collect();
+
+        let status = checks
+            .values()
+            .map(|result| match result.status {
+                HealthStatus::Unhealthy if !result.critical => HealthStatus::Degraded,
+                status => status,
+            })
+            .max()
+            .unwrap_or(HealthStatus::Healthy);
+
+        HealthReport {
+            service: self.service.clone(),
+            status,
+            uptime_secs: self.started.elapsed().as_secs(),
+            checks,
+        }
+    }
+
+    fn shutting_down(&self) -> HealthReport {
+        let result = CheckResult {
+            status: HealthStatus::Unhealthy,
+            critical: true,
+            latency_ms: 0.0,
+            error: Some("Shutting down".to_string()),
+            checked_at: Utc::now(),
+        };
+        HealthReport {
+            service: self.service.clone(),
+            status: HealthStatus::Unhealthy,
+            uptime_secs: self.started.elapsed().as_secs(),
+            checks: BTreeMap::from([("shutdown".to_string(), result)]),
+        }
+    }
+
+    fn fresh(&self, registered: &Registered) -> Option<CheckResult> {
+        let cached = lock(&registered.cached);
+        cached.as_ref().filter(|(at, _)| at.elapsed() < self.cache_ttl).map(|(_, result)| result.clone())
+    }
+
+    async fn run(&self, registered: &Registered) -> CheckResult {
+        if let Some(result) = self.fresh(registered) {
+            return result;
+        }
+
+        let started = Instant::now();
+        let outcome = match tokio::time::timeout(self.timeout, registered.check.check()).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(ChaosError::Network(format!("Timed out after {:?}", self.timeout))),
+        };
+        let result = CheckResult {
+            status: if outcome.is_ok() { HealthStatus::Healthy } else { HealthStatus::Unhealthy },
+            critical: registered.critical,
+            latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+            error: outcome.err().map(|e| e.to_string()),
+            checked_at: Utc::now(),
+        };
+        if let Some(error) = &result.error {
+            tracing::warn!(service = %self.service, check = %registered.name, error = %error, "Health check failed");
+        }
+        *lock(&registered.cached) = Some((Instant::now(), result.clone()));
+        result
+    }
+}
+
+impl std::fmt::Debug for HealthRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthRegistry")
+            .field("service", &self.service)
+            .field("checks", &self.checks.iter().map(|registered| &registered.name).collect::<Vec<_>>())
+            .field("cache_ttl", &self.cache_ttl)
+            .field("timeout", &self.timeout)
+            .field("shutdown", &self.shutdown)
+            .finish()
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
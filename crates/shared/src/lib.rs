@@ -7,9 +7,26 @@ pub mod error;
 pub mod types;
 pub mod utils;
 pub mod constants;
+pub mod clock;
+pub mod events;
+pub mod query;
+pub mod trace;
+pub mod preview;
+pub mod maintenance;
+pub mod health;
+pub mod shutdown;
+pub mod presence;
+pub mod saga;
+pub mod party;
+pub mod companion;
+pub mod market;
+pub mod wallet;
+#[cfg(feature = "telemetry")]
+pub mod telemetry;
 
 // Re-export commonly used types
-pub use error::{ChaosError, ChaosResult};
+pub use error::{ApiError, ChaosError, ChaosResult, ErrorCode, GrpcCode, ToApiError};
 pub use types::*;
 pub use utils::*;
 pub use constants::*;
+pub use clock::{GameClock, SharedClock, SimulatedClock, ScaledClock, WallClock, wall_clock};
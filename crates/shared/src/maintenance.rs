@@ -0,0 +1,195 @@
+//! Maintenance mode and in-game broadcasts.
+//!
+//! `admin-cli world maintenance` publishes a [`MaintenanceState`] on [`MAINTENANCE_TOPIC`].
+//! Services keep the latest state in a [`MaintenanceFlag`] (see [`MaintenanceFlag::follow`])
+//! and refuse new logins and game connections from non-admins while it is enabled;
+//! players already in the game stay connected until the announced shutdown.
+//!
+//! `admin-cli broadcast` publishes [`Broadcast`]s on [`BROADCAST_TOPIC`] for game servers
+//! to relay to connected players; shutdown countdowns are broadcasts too.
+//!
+//! The state is carried by the bus only: a service started while maintenance is on
+//! learns about it the next time the state is published.
+
+use crate::error::ChaosResult;
+use crate::events::{EventBus, EventBusExt, Topic};
+use crate::types::Timestamp;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Topic carrying maintenance state changes
+pub const MAINTENANCE_TOPIC: Topic<MaintenanceState> = Topic::new("system.maintenance");
+
+/// Topic carrying messages shown to every player
+pub const BROADCAST_TOPIC: Topic<Broadcast> = Topic::new("game.broadcast");
+
+/// Notice shown when maintenance is enabled without a message
+pub const DEFAULT_MAINTENANCE_MESSAGE: &str = "The server is under maintenance, please try again later";
+
+/// Time before a shutdown at which players are warned
+pub const SHUTDOWN_WARNINGS: [Duration; 8] = [
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(15 * 60),
+    Duration::from_secs(10 * 60),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(2 * 60),
+    Duration::from_secs(60),
+    Duration::from_secs(30),
+    Duration::from_secs(10),
+];
+
+/// Whether the game is in maintenance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    pub enabled: bool,
+    /// Notice shown to players who are turned away
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// When the game servers shut down, if a shutdown is scheduled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shutdown_at: Option<Timestamp>,
+    pub updated_by: String,
+    pub updated_at: Timestamp,
+}
+
+impl Default for MaintenanceState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            message: None,
+            shutdown_at: None,
+            updated_by: String::new(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+impl MaintenanceState {
+    /// Maintenance turned on by an admin
+    pub fn on(updated_by: impl Into<String>, message: Option<String>, shutdown_at: Option<Timestamp>) -> Self {
+        Self {
+            enabled: true,
+            message,
+            shutdown_at,
+            updated_by: updated_by.into(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Maintenance turned off by an admin
+    pub fn off(updated_by: impl Into<String>) -> Self {
+        Self {
+            updated_by: updated_by.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether a new login or game connection is refused
+    pub fn refuses(&self, is_admin: bool) -> bool {
+        self.enabled && !is_admin
+    }
+
+    /// Notice for players who are turned away
+    pub fn notice(&self) -> &str {
+        self.message.as_deref().unwrap_or(DEFAULT_MAINTENANCE_MESSAGE)
+    }
+}
+
+/// How prominently clients show a broadcast
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BroadcastSeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
+
+/// Message shown to every connected player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Broadcast {
+    pub text: String,
+    #[serde(default)]
+    pub severity: BroadcastSeverity,
+    pub sent_by: String,
+    pub sent_at: Timestamp,
+}
+
+impl Broadcast {
+    pub fn new(text: impl Into<String>, severity: BroadcastSeverity, sent_by: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            severity,
+            sent_by: sent_by.into(),
+            sent_at: Utc::now(),
+        }
+    }
+}
+
+/// Latest maintenance state seen by a service
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceFlag {
+    state: Arc<RwLock<MaintenanceState>>,
+}
+
+impl MaintenanceFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current state
+    pub fn get(&self) -> MaintenanceState {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the state
+    pub fn set(&self, state: MaintenanceState) {
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = state;
+    }
+
+    /// Whether maintenance is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// Keep the flag in sync with [`MAINTENANCE_TOPIC`] until the bus closes
+    pub async fn follow(&self, bus: &dyn EventBus) -> ChaosResult<tokio::task::JoinHandle<()>> {
+        let mut subscription = bus.subscribe(&MAINTENANCE_TOPIC).await?;
+        let flag = self.clone();
+        Ok(tokio::spawn(async move {
+            while let Some(event) = subscription.next().await {
+                match event {
+                    Ok(state) => {
+                        tracing::info!(
+                            enabled = state.enabled,
+                            updated_by = %state.updated_by,
+                            "Maintenance state changed"
+                        );
+                        flag.set(state);
+                    }
+                    Err(e) => tracing::warn!("Ignoring malformed maintenance event: {}", e),
+                }
+            }
+        }))
+    }
+}
+
+/// Warnings still ahead of a shutdown `remaining` from now, as time before the shutdown
+pub fn shutdown_warnings(remaining: Duration) -> Vec<Duration> {
+    SHUTDOWN_WARNINGS.iter().copied().filter(|warning| *warning <= remaining).collect()
+}
+
+/// Countdown text for a warning
+pub fn shutdown_notice(remaining: Duration) -> String {
+    let seconds = remaining.as_secs();
+    let left = match seconds {
+        0..=59 => format!("{} second{}", seconds, if seconds == 1 { "" } else { "s" }),
+        _ => {
+            let minutes = seconds.div_ceil(60);
+            format!("{} minute{}", minutes, if minutes == 1 { "" } else { "s" })
+        }
+    };
+    format!("The server shuts down for maintenance in {}", left)
+}
@@ -0,0 +1,624 @@
+//! Auction house.
+//!
+//! Sellers list items for a fixed duration, paying a deposit the market keeps. Buyers
+//! bid or pay the buyout price; the currency is taken from their [`Wallet`] when they
+//! bid and held in escrow by the market until the listing closes. Outbid bidders are
+//! refunded by mail. A bid in the last moments of a listing extends it, so a sniper
+//! cannot win without giving the other bidders a chance to answer.
+//!
+//! Closed listings are settled by [`Market::settle_due`], usually from
+//! [`Market::spawn_settlement`]: the buyer is mailed the items, the seller the price
+//! less the market's cut plus the deposit, and unsold items go back to the seller.
+//! Every sale is announced on [`MARKET_TRADE`] for economy analytics and price
+//! monitoring.
+//!
+//! Items are taken from the seller's inventory by the caller before listing; the market
+//! only hands them out again by mail. Mail ids are derived from the listing, so a
+//! settlement interrupted halfway is retried without delivering anything twice, as long
+//! as the [`Mailer`] ignores ids it has already delivered. One [`Market`] serves a store
+//! at a time.
+//!
+//! Listings are searched with the shared [`ListQuery`](crate::query::ListQuery) over
+//! [`SEARCH_FIELDS`].
+
+pub mod store;
+
+pub use store::{MarketStore, MemoryMarketStore};
+
+use crate::clock::SharedClock;
+use crate::error::{ChaosError, ChaosResult};
+use crate::events::topics::{MarketTrade, MARKET_TRADE};
+use crate::events::{EventBus, EventBusExt};
+use crate::query::{FilterSpec, ListQuery, Page};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Fields listings can be filtered and sorted by
+pub const SEARCH_FIELDS: &[&str] =
+    &["item.item_id", "item.category", "seller_id", "status", "price", "buyout_price", "created_at", "ends_at"];
+
+/// Listings settled per settlement run
+const SETTLEMENT_BATCH: usize = 100;
+
+/// What is being sold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketItem {
+    pub item_id: String,
+    pub quantity: u32,
+    /// Category buyers browse by, e.g. `weapon` or `herb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    Active,
+    Sold,
+    /// Ended without bids
+    Expired,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bid {
+    pub bidder_id: String,
+    pub amount: u64,
+    pub at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Listing {
+    pub id: String,
+    pub seller_id: String,
+    pub item: MarketItem,
+    pub start_price: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buyout_price: Option<u64>,
+    /// Paid by the seller when listing; returned only with a sale
+    pub deposit: u64,
+    /// Highest bid, or the start price while there is none
+    pub price: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bid: Option<Bid>,
+    pub created_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Times a late bid pushed `ends_at` back
+    pub extensions: u32,
+    pub status: ListingStatus,
+    /// Whether the buyer paid the buyout price
+    #[serde(default)]
+    pub bought_out: bool,
+    /// Outbid bids whose refund has not been mailed yet
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refunds: Vec<Bid>,
+    /// Whether the items and currency of a closed listing were delivered
+    #[serde(default)]
+    pub settled: bool,
+}
+
+impl Listing {
+    /// Lowest amount the next bid may offer
+    pub fn min_bid(&self, config: &MarketConfig) -> u64 {
+        match &self.bid {
+            None => self.start_price,
+            Some(bid) => bid.amount + ((bid.amount as f64 * config.min_bid_increment).ceil() as u64).max(1),
+        }
+    }
+
+    /// Whether the listing still needs delivering or refunding
+    pub fn needs_settlement(&self, now: Timestamp) -> bool {
+        match self.status {
+            ListingStatus::Active => self.ends_at <= now || !self.refunds.is_empty(),
+            _ => !self.settled || !self.refunds.is_empty(),
+        }
+    }
+}
+
+/// Auction house rules
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarketConfig {
+    /// Durations a seller may choose from
+    pub durations: Vec<Duration>,
+    /// Deposit per day of listing, as a fraction of the start price
+    pub deposit_rate: f64,
+    pub min_deposit: u64,
+    /// Fraction of the price the market keeps from a sale
+    pub sale_cut: f64,
+    /// Fraction of the current bid the next bid must add
+    pub min_bid_increment: f64,
+    /// Bids this close to the end extend the listing...
+    pub snipe_window: Duration,
+    /// ...so it ends this long after the bid
+    pub snipe_extension: Duration,
+    /// Times a listing may be extended
+    pub max_extensions: u32,
+    /// Recent sales per item the reference price is the median of
+    pub reference_sales: usize,
+}
+
+impl Default for MarketConfig {
+    fn default() -> Self {
+        let hours = |hours: u64| Duration::from_secs(hours * 60 * 60);
+        Self {
+            durations: vec![hours(12), hours(24), hours(48)],
+            deposit_rate: 0.05,
+            min_deposit: 1,
+            sale_cut: 0.05,
+            min_bid_increment: 0.05,
+            snipe_window: Duration::from_secs(5 * 60),
+            snipe_extension: Duration::from_secs(5 * 60),
+            max_extensions: 12,
+            reference_sales: 20,
+        }
+    }
+}
+
+impl MarketConfig {
+    /// Deposit for listing at `start_price` for `duration`
+    pub fn deposit(&self, start_price: u64, duration: Duration) -> u64 {
+        let days = duration.as_secs_f64() / (24.0 * 60.0 * 60.0);
+        ((start_price as f64 * self.deposit_rate * days).ceil() as u64).max(self.min_deposit)
+    }
+
+    /// Cut the market keeps from a sale at `price`
+    pub fn cut(&self, price: u64) -> u64 {
+        ((price as f64 * self.sale_cut).floor() as u64).min(price)
+    }
+}
+
+/// A new listing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewListing {
+    pub item: MarketItem,
+    pub start_price: u64,
+    #[serde(default)]
+    pub buyout_price: Option<u64>,
+    pub duration: Duration,
+}
+
+/// Mail carrying currency or items
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mail {
+    /// Derived from the listing; the same mail always has the same id
+    pub id: String,
+    pub recipient_id: String,
+    pub subject: String,
+    pub currency: u64,
+    #[serde(default)]
+    pub items: Vec<MarketItem>,
+}
+
+/// Currency balances the market takes bids, buyouts and deposits from
+#[async_trait]
+pub trait Wallet: Send + Sync {
+    /// Take `amount` from an owner; fails when the balance is short
+    async fn withdraw(&self, owner_id: &str, amount: u64, reference: &str) -> ChaosResult<()>;
+}
+
+/// Delivers what the market owes players
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Deliver a mail; one whose id was delivered before must be ignored
+    async fn send(&self, mail: &Mail) -> ChaosResult<()>;
+}
+
+/// Auction house over a listing store
+pub struct Market {
+    config: MarketConfig,
+    clock: SharedClock,
+    store: Arc<dyn MarketStore>,
+    wallet: Arc<dyn Wallet>,
+    mailer: Arc<dyn Mailer>,
+    bus: Option<(Arc<dyn EventBus>, String)>,
+    /// Serializes changes to listings between reading and saving them
+    writes: tokio::sync::Mutex<()>,
+    /// Recent unit prices per item
+    prices: Mutex<HashMap<String, VecDeque<f64>>>,
+}
+
+impl Market {
+    pub fn new(
+        config: MarketConfig,
+        clock: SharedClock,
+        store: Arc<dyn MarketStore>,
+        wallet: Arc<dyn Wallet>,
+        mailer: Arc<dyn Mailer>,
+    ) -> Self {
+        Self {
+            config,
+            clock,
+            store,
+            wallet,
+            mailer,
+            bus: None,
+            writes: tokio::sync::Mutex::new(()),
+            prices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Publish sales on `bus`, tagged with `source`
+    pub fn with_bus(mut self, bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        self.bus = Some((bus, source.into()));
+        self
+    }
+
+    pub fn config(&self) -> &MarketConfig {
+        &self.config
+    }
+
+    pub async fn get(&self, listing_id: &str) -> ChaosResult<Option<Listing>> {
+        self.store.get(listing_id).await
+    }
+
+    /// Search active listings; `status` may be filtered on to search others
+    pub async fn search(&self, mut query: ListQuery) -> ChaosResult<Page<Listing>> {
+        query.filter.validate_fields(SEARCH_FIELDS)?;
+        query.sort.validate_fields(SEARCH_FIELDS)?;
+        if !query.filter.conditions.iter().any(|condition| condition.field == "status") {
+            let conditions = std::mem::take(&mut query.filter.conditions);
+            query.filter = FilterSpec::new().eq("status", "active");
+            query.filter.conditions.extend(conditions);
+        }
+        self.store.search(&query).await
+    }
+
+    /// List an item, taking the deposit from the seller
+    pub async fn create_listing(&self, seller_id: &str, listing: NewListing) -> ChaosResult<Listing> {
+        if !self.config.durations.contains(&listing.duration) {
+            return Err(ChaosError::Validation(format!("Listings cannot last {:?}", listing.duration)));
+        }
+        if listing.item.quantity == 0 || listing.start_price == 0 {
+            return Err(ChaosError::Validation("Listings need an item and a start price".to_string()));
+        }
+        if listing.buyout_price.is_some_and(|buyout| buyout < listing.start_price) {
+            return Err(ChaosError::Validation("The buyout price is below the start price".to_string()));
+        }
+
+        let now = self.clock.now();
+        let id = Uuid::new_v4().to_string();
+        let deposit = self.config.deposit(listing.start_price, listing.duration);
+        self.wallet.withdraw(seller_id, deposit, &format!("market:{}:deposit", id)).await?;
+        let listing = Listing {
+            id,
+            seller_id: seller_id.to_string(),
+            item: listing.item,
+            start_price: listing.start_price,
+            buyout_price: listing.buyout_price,
+            deposit,
+            price: listing.start_price,
+            bid: None,
+            created_at: now,
+            ends_at: now + to_chrono(listing.duration),
+            extensions: 0,
+            status: ListingStatus::Active,
+            bought_out: false,
+            refunds: Vec::new(),
+            settled: false,
+        };
+        if let Err(e) = self.store.insert(&listing).await {
+            // The listing never existed, so the deposit goes back with the items
+            self.mail(Mail {
+                id: format!("market:{}:unlisted", listing.id),
+                recipient_id: seller_id.to_string(),
+                subject: "Your listing could not be created".to_string(),
+                currency: deposit,
+                items: vec![listing.item.clone()],
+            })
+            .await;
+            return Err(e);
+        }
+        info!(listing_id = %listing.id, seller_id, item_id = %listing.item.item_id, "Item listed");
+        Ok(listing)
+    }
+
+    /// Bid on a listing; a bid reaching the buyout price buys the listing out
+    pub async fn bid(&self, listing_id: &str, bidder_id: &str, amount: u64) -> ChaosResult<Listing> {
+        let listing = {
+            let _writes = self.writes.lock().await;
+            let mut listing = self.open_listing(listing_id, bidder_id).await?;
+            if let Some(buyout) = listing.buyout_price.filter(|buyout| amount >= *buyout) {
+                self.take_payment(&listing, bidder_id, buyout, "buyout").await?;
+                self.close_sold(&mut listing, bidder_id, buyout, true);
+            } else {
+                let min_bid = listing.min_bid(&self.config);
+                if amount < min_bid {
+                    return Err(ChaosError::Validation(format!("Bids on this listing start at {}", min_bid)));
+                }
+                self.take_payment(&listing, bidder_id, amount, "bid").await?;
+                let now = self.clock.now();
+                if let Some(outbid) = listing.bid.replace(Bid { bidder_id: bidder_id.to_string(), amount, at: now }) {
+                    listing.refunds.push(outbid);
+                }
+                listing.price = amount;
+                if listing.ends_at - now < to_chrono(self.config.snipe_window)
+                    && listing.extensions < self.config.max_extensions
+                {
+                    listing.ends_at = now + to_chrono(self.config.snipe_extension);
+                    listing.extensions += 1;
+                }
+            }
+            self.save_paid(&listing, bidder_id, listing.price).await?;
+            listing
+        };
+        self.after_change(listing).await
+    }
+
+    /// Pay the buyout price
+    pub async fn buyout(&self, listing_id: &str, buyer_id: &str) -> ChaosResult<Listing> {
+        let listing = {
+            let _writes = self.writes.lock().await;
+            let mut listing = self.open_listing(listing_id, buyer_id).await?;
+            let buyout = listing
+                .buyout_price
+                .ok_or_else(|| ChaosError::Validation(format!("Listing {} has no buyout price", listing_id)))?;
+            self.take_payment(&listing, buyer_id, buyout, "buyout").await?;
+            self.close_sold(&mut listing, buyer_id, buyout, true);
+            self.save_paid(&listing, buyer_id, buyout).await?;
+            listing
+        };
+        self.after_change(listing).await
+    }
+
+    /// Take a listing without bids off the market; the deposit is not returned
+    pub async fn cancel(&self, listing_id: &str, seller_id: &str) -> ChaosResult<Listing> {
+        let listing = {
+            let _writes = self.writes.lock().await;
+            let mut listing = self.require(listing_id).await?;
+            if listing.seller_id != seller_id {
+                return Err(ChaosError::Authentication(format!("Listing {} is not yours", listing_id)));
+            }
+            if listing.status != ListingStatus::Active || listing.bid.is_some() {
+                return Err(ChaosError::Validation(format!("Listing {} can no longer be cancelled", listing_id)));
+            }
+            listing.status = ListingStatus::Cancelled;
+            self.store.save(&listing).await?;
+            listing
+        };
+        self.after_change(listing).await
+    }
+
+    /// Close ended listings and deliver what closed listings owe; returns the number of
+    /// listings fully settled
+    pub async fn settle_due(&self) -> ChaosResult<usize> {
+        let due = self.store.needing_settlement(self.clock.now(), SETTLEMENT_BATCH).await?;
+        let mut settled = 0;
+        for listing in due {
+            let listing = {
+                let _writes = self.writes.lock().await;
+                // Bids may have come in since the batch was read
+                let Some(mut listing) = self.store.get(&listing.id).await? else {
+                    continue;
+                };
+                if listing.status == ListingStatus::Active && listing.ends_at <= self.clock.now() {
+                    match listing.bid.clone() {
+                        Some(bid) => self.close_sold(&mut listing, &bid.bidder_id, bid.amount, false),
+                        None => listing.status = ListingStatus::Expired,
+                    }
+                    self.store.save(&listing).await?;
+                    if listing.status == ListingStatus::Sold {
+                        self.publish_trade(&listing).await;
+                    }
+                }
+                listing
+            };
+            if self.deliver(listing).await?.settled {
+                settled += 1;
+            }
+        }
+        Ok(settled)
+    }
+
+    /// Settle due listings every `interval`
+    pub fn spawn_settlement(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match self.settle_due().await {
+                    Ok(0) => {}
+                    Ok(settled) => info!("Settled {} market listing(s)", settled),
+                    Err(e) => warn!("Market settlement failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Median unit price of an item's recent sales
+    pub fn reference_price(&self, item_id: &str) -> Option<f64> {
+        let prices = self.prices();
+        let recent = prices.get(item_id).filter(|recent| !recent.is_empty())?;
+        let mut sorted: Vec<f64> = recent.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let middle = sorted.len() / 2;
+        Some(if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] })
+    }
+
+    async fn require(&self, listing_id: &str) -> ChaosResult<Listing> {
+        self.store
+            .get(listing_id)
+            .await?
+            .ok_or_else(|| ChaosError::Validation(format!("Listing {} not found", listing_id)))
+    }
+
+    /// A listing `buyer_id` may still bid on or buy
+    async fn open_listing(&self, listing_id: &str, buyer_id: &str) -> ChaosResult<Listing> {
+        let listing = self.require(listing_id).await?;
+        if listing.status != ListingStatus::Active || listing.ends_at <= self.clock.now() {
+            return Err(ChaosError::Validation(format!("Listing {} has ended", listing_id)));
+        }
+        if listing.seller_id == buyer_id {
+            return Err(ChaosError::Validation("Sellers cannot buy their own listings".to_string()));
+        }
+        Ok(listing)
+    }
+
+    async fn take_payment(&self, listing: &Listing, payer_id: &str, amount: u64, kind: &str) -> ChaosResult<()> {
+        let reference = format!("market:{}:{}:{}:{}", listing.id, kind, payer_id, self.clock.now().timestamp_millis());
+        self.wallet.withdraw(payer_id, amount, &reference).await
+    }
+
+    /// Save a listing `payer_id` paid `amount` for, refunding the payment if it cannot be saved
+    async fn save_paid(&self, listing: &Listing, payer_id: &str, amount: u64) -> ChaosResult<()> {
+        if let Err(e) = self.store.save(listing).await {
+            self.mail(Mail {
+                id: format!("market:{}:unsaved:{}:{}", listing.id, payer_id, self.clock.now().timestamp_millis()),
+                recipient_id: payer_id.to_string(),
+                subject: format!("Your offer for {} was not placed", listing.item.item_id),
+                currency: amount,
+                items: Vec::new(),
+            })
+            .await;
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn close_sold(&self, listing: &mut Listing, buyer_id: &str, price: u64, bought_out: bool) {
+        let now = self.clock.now();
+        // A buyout refunds the standing bid; closing an ended listing keeps it as the sale
+        if let Some(outbid) = listing.bid.take().filter(|_| bought_out) {
+            listing.refunds.push(outbid);
+        }
+        listing.bid = Some(Bid { bidder_id: buyer_id.to_string(), amount: price, at: now });
+        listing.price = price;
+        listing.bought_out = bought_out;
+        listing.status = ListingStatus::Sold;
+        listing.ends_at = listing.ends_at.min(now);
+    }
+
+    /// Announce and deliver a listing a bid, buyout or cancellation just changed
+    async fn after_change(&self, listing: Listing) -> ChaosResult<Listing> {
+        if listing.status == ListingStatus::Sold {
+            self.publish_trade(&listing).await;
+        }
+        // Whatever cannot be delivered now is retried by the settlement job
+        match self.deliver(listing.clone()).await {
+            Ok(listing) => Ok(listing),
+            Err(e) => {
+                warn!(listing_id = %listing.id, "Market delivery deferred: {}", e);
+                Ok(listing)
+            }
+        }
+    }
+
+    /// Mail pending refunds, and the items and currency of a closed listing
+    async fn deliver(&self, mut listing: Listing) -> ChaosResult<Listing> {
+        let item_id = listing.item.item_id.clone();
+        let mut refunded = Vec::new();
+        while let Some(refund) = listing.refunds.first().cloned() {
+            let mail = Mail {
+                id: format!("market:{}:refund:{}:{}", listing.id, refund.bidder_id, refund.at.timestamp_millis()),
+                recipient_id: refund.bidder_id.clone(),
+                subject: format!("Outbid on {}", item_id),
+                currency: refund.amount,
+                items: Vec::new(),
+            };
+            self.mailer.send(&mail).await?;
+            refunded.push(listing.refunds.remove(0));
+        }
+
+        if listing.status != ListingStatus::Active && !listing.settled {
+            for mail in self.closing_mails(&listing) {
+                self.mailer.send(&mail).await?;
+            }
+            listing.settled = true;
+        }
+
+        let _writes = self.writes.lock().await;
+        // Keep bids that came in while mailing; only what was delivered is recorded
+        let mut current = self.require(&listing.id).await?;
+        current.refunds.retain(|refund| !refunded.contains(refund));
+        current.settled |= listing.settled;
+        self.store.save(&current).await?;
+        Ok(current)
+    }
+
+    fn closing_mails(&self, listing: &Listing) -> Vec<Mail> {
+        let item_id = &listing.item.item_id;
+        match (&listing.status, &listing.bid) {
+            (ListingStatus::Sold, Some(bid)) => vec![
+                Mail {
+                    id: format!("market:{}:items", listing.id),
+                    recipient_id: bid.bidder_id.clone(),
+                    subject: format!("You won {}", item_id),
+                    currency: 0,
+                    items: vec![listing.item.clone()],
+                },
+                Mail {
+                    id: format!("market:{}:proceeds", listing.id),
+                    recipient_id: listing.seller_id.clone(),
+                    subject: format!("Sold {}", item_id),
+                    currency: bid.amount - self.config.cut(bid.amount) + listing.deposit,
+                    items: Vec::new(),
+                },
+            ],
+            _ => vec![Mail {
+                id: format!("market:{}:returned", listing.id),
+                recipient_id: listing.seller_id.clone(),
+                subject: format!("{} was not sold", item_id),
+                currency: 0,
+                items: vec![listing.item.clone()],
+            }],
+        }
+    }
+
+    async fn publish_trade(&self, listing: &Listing) {
+        let Some(bid) = &listing.bid else {
+            return;
+        };
+        let trade = MarketTrade {
+            listing_id: listing.id.clone(),
+            item_id: listing.item.item_id.clone(),
+            quantity: listing.item.quantity,
+            seller_id: listing.seller_id.clone(),
+            buyer_id: bid.bidder_id.clone(),
+            price: bid.amount,
+            fee: self.config.cut(bid.amount),
+            buyout: listing.bought_out,
+            reference_unit_price: self.reference_price(&listing.item.item_id),
+            at: self.clock.now(),
+        };
+        self.record_price(&trade.item_id, trade.unit_price());
+        info!(listing_id = %trade.listing_id, buyer_id = %trade.buyer_id, price = trade.price, "Listing sold");
+
+        let Some((bus, source)) = &self.bus else {
+            return;
+        };
+        if let Err(e) = bus.publish_message(source, &MARKET_TRADE, &trade).await {
+            warn!("Failed to publish sale of listing {}: {}", listing.id, e);
+        }
+    }
+
+    fn record_price(&self, item_id: &str, unit_price: f64) {
+        let mut prices = self.prices();
+        let recent = prices.entry(item_id.to_string()).or_default();
+        recent.push_back(unit_price);
+        while recent.len() > self.config.reference_sales {
+            recent.pop_front();
+        }
+    }
+
+    /// Best-effort mail for money taken without a listing to show for it
+    async fn mail(&self, mail: Mail) {
+        if let Err(e) = self.mailer.send(&mail).await {
+            warn!(mail_id = %mail.id, recipient_id = %mail.recipient_id, "Failed to mail {} back: {}", mail.currency, e);
+        }
+    }
+
+    fn prices(&self) -> MutexGuard<'_, HashMap<String, VecDeque<f64>>> {
+        self.prices.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::days(365))
+}
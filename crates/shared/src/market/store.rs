@@ -0,0 +1,77 @@
+//! Listing persistence.
+
+use super::Listing;
+use crate::error::{ChaosError, ChaosResult};
+use crate::query::memory::apply_query;
+use crate::query::{ListQuery, Page};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+#[async_trait]
+pub trait MarketStore: Send + Sync {
+    /// Store a new listing; fails when the ID is taken
+    async fn insert(&self, listing: &Listing) -> ChaosResult<()>;
+    async fn get(&self, listing_id: &str) -> ChaosResult<Option<Listing>>;
+    /// Replace an existing listing
+    async fn save(&self, listing: &Listing) -> ChaosResult<()>;
+    async fn search(&self, query: &ListQuery) -> ChaosResult<Page<Listing>>;
+    /// Up to `limit` listings that have ended or still owe a delivery at `now`
+    async fn needing_settlement(&self, now: Timestamp, limit: usize) -> ChaosResult<Vec<Listing>>;
+}
+
+/// Listings held in memory; for tests and single-instance deployments
+#[derive(Default)]
+pub struct MemoryMarketStore {
+    listings: Mutex<HashMap<String, Listing>>,
+}
+
+impl MemoryMarketStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn listings(&self) -> MutexGuard<'_, HashMap<String, Listing>> {
+        self.listings.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl MarketStore for MemoryMarketStore {
+    async fn insert(&self, listing: &Listing) -> ChaosResult<()> {
+        let mut listings = self.listings();
+        if listings.contains_key(&listing.id) {
+            return Err(ChaosError::Validation(format!("Listing {} already exists", listing.id)));
+        }
+        listings.insert(listing.id.clone(), listing.clone());
+        Ok(())
+    }
+
+    async fn get(&self, listing_id: &str) -> ChaosResult<Option<Listing>> {
+        Ok(self.listings().get(listing_id).cloned())
+    }
+
+    async fn save(&self, listing: &Listing) -> ChaosResult<()> {
+        match self.listings().get_mut(&listing.id) {
+            Some(stored) => {
+                *stored = listing.clone();
+                Ok(())
+            }
+            None => Err(ChaosError::Validation(format!("Listing {} not found", listing.id))),
+        }
+    }
+
+    async fn search(&self, query: &ListQuery) -> ChaosResult<Page<Listing>> {
+        let listings: Vec<Listing> = self.listings().values().cloned().collect();
+        apply_query(listings, query)
+    }
+
+    async fn needing_settlement(&self, now: Timestamp, limit: usize) -> ChaosResult<Vec<Listing>> {
+        let mut due: Vec<Listing> =
+            self.listings().values().filter(|listing| listing.needs_settlement(now)).cloned().collect();
+        due.sort_by_key(|listing| listing.ends_at);
+        due.truncate(limit);
+        Ok(due)
+    }
+}
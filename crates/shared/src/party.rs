@@ -0,0 +1,512 @@
+//! Parties and raids: groups of players adventuring together.
+//!
+//! The [`PartyManager`] creates groups, tracks their members and roles and enforces who
+//! may do what: the leader manages the group, assistants may also invite. A party
+//! converts into a raid to grow beyond [`PartyConfig::max_party_members`]. When the
+//! leader leaves, the longest-standing assistant (else member) takes over; the group
+//! disbands when its last member leaves.
+//!
+//! Other systems plug in through:
+//! - [`LootRules`], set by the leader and read by item-core when distributing drops;
+//!   [`PartyManager::next_looter`] walks round-robin loot.
+//! - [`SharedBuff`]s, auras a member shares with the rest of the group; stat aggregation
+//!   adds [`PartyManager::party_buffs`] to each member's contributions.
+//! - [`PartyChanged`] messages on [`PARTY_CHANGED`], which the websocket layer forwards
+//!   to the `party:<party_id>` channels.
+
+use crate::clock::SharedClock;
+use crate::error::{ChaosError, ChaosResult};
+use crate::events::topics::{PartyChange, PartyChanged, PARTY_CHANGED};
+use crate::events::{EventBus, EventBusExt};
+use crate::types::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Kind of group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartyKind {
+    Party,
+    Raid,
+}
+
+/// Role of a member within its group
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartyRole {
+    /// Manages the group; exactly one per group
+    Leader,
+    /// May invite members and mark targets
+    Assistant,
+    Member,
+}
+
+/// How drops are distributed among the members
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LootMethod {
+    /// Whoever picks the item up first
+    FreeForAll,
+    /// Members take turns, in the order they joined
+    RoundRobin,
+    /// The master looter hands items out
+    MasterLooter,
+    /// Members roll need or greed on items at or above the threshold
+    NeedBeforeGreed,
+}
+
+/// What a member rolls for under [`LootMethod::NeedBeforeGreed`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollChoice {
+    /// Wants the item for itself; need rolls beat every greed roll
+    Need,
+    Greed,
+    Pass,
+}
+
+/// Loot settings of a group, consumed by item-core
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LootRules {
+    pub method: LootMethod,
+    /// Items below this rarity tier are free for all whatever the method
+    pub rarity_threshold: u8,
+    /// Member distributing loot under [`LootMethod::MasterLooter`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub master_looter: Option<String>,
+}
+
+impl Default for LootRules {
+    fn default() -> Self {
+        Self { method: LootMethod::RoundRobin, rarity_threshold: 2, master_looter: None }
+    }
+}
+
+/// A member of a group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PartyMember {
+    pub actor_id: String,
+    pub role: PartyRole,
+    pub joined_at: Timestamp,
+}
+
+/// A party or raid
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Party {
+    pub id: String,
+    pub kind: PartyKind,
+    /// Members in the order they joined
+    pub members: Vec<PartyMember>,
+    pub loot_rules: LootRules,
+    pub created_at: Timestamp,
+}
+
+impl Party {
+    /// Get the leader's actor ID
+    pub fn leader_id(&self) -> &str {
+        self.members
+            .iter()
+            .find(|member| member.role == PartyRole::Leader)
+            .map(|member| member.actor_id.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn member(&self, actor_id: &str) -> Option<&PartyMember> {
+        self.members.iter().find(|member| member.actor_id == actor_id)
+    }
+
+    pub fn is_member(&self, actor_id: &str) -> bool {
+        self.member(actor_id).is_some()
+    }
+
+    pub fn member_ids(&self) -> Vec<String> {
+        self.members.iter().map(|member| member.actor_id.clone()).collect()
+    }
+
+    fn role_of(&self, actor_id: &str) -> Option<PartyRole> {
+        self.member(actor_id).map(|member| member.role)
+    }
+}
+
+/// How a shared buff modifies the stat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BuffMode {
+    /// Added to the stat
+    Add,
+    /// Multiplies the stat
+    Multiply,
+}
+
+/// Buff a member shares with the rest of its group, e.g. a leadership aura
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharedBuff {
+    pub buff_id: String,
+    /// Member sharing the buff
+    pub source_actor_id: String,
+    pub stat: String,
+    pub value: f64,
+    pub mode: BuffMode,
+}
+
+/// Group size limits
+#[derive(Debug, Clone)]
+pub struct PartyConfig {
+    pub max_party_members: usize,
+    pub max_raid_members: usize,
+}
+
+impl Default for PartyConfig {
+    fn default() -> Self {
+        Self { max_party_members: 5, max_raid_members: 40 }
+    }
+}
+
+impl PartyConfig {
+    pub fn max_members(&self, kind: PartyKind) -> usize {
+        match kind {
+            PartyKind::Party => self.max_party_members,
+            PartyKind::Raid => self.max_raid_members,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    parties: HashMap<String, Party>,
+    /// Group of each member
+    membership: HashMap<String, String>,
+    /// Buffs each member shares
+    buffs: HashMap<String, Vec<SharedBuff>>,
+    /// Position of the next round-robin looter in each group
+    loot_cursors: HashMap<String, usize>,
+}
+
+/// Groups of one process, announcing their changes on the event bus
+pub struct PartyManager {
+    clock: SharedClock,
+    config: PartyConfig,
+    bus: Option<(Arc<dyn EventBus>, String)>,
+    state: Mutex<State>,
+}
+
+impl PartyManager {
+    pub fn new(clock: SharedClock, config: PartyConfig) -> Self {
+        Self { clock, config, bus: None, state: Mutex::new(State::default()) }
+    }
+
+    /// Publish changes on `bus`, tagged with `source`
+    pub fn with_bus(mut self, bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        self.bus = Some((bus, source.into()));
+        self
+    }
+
+    /// Create a party led by `leader_id`
+    pub async fn create(&self, leader_id: &str) -> ChaosResult<Party> {
+        let party = {
+            let mut state = self.state();
+            ensure_ungrouped(&state, leader_id)?;
+            let now = self.clock.now();
+            let party = Party {
+                id: Uuid::new_v4().to_string(),
+                kind: PartyKind::Party,
+                members: vec![PartyMember { actor_id: leader_id.to_string(), role: PartyRole::Leader, joined_at: now }],
+                loot_rules: LootRules::default(),
+                created_at: now,
+            };
+            state.membership.insert(leader_id.to_string(), party.id.clone());
+            state.parties.insert(party.id.clone(), party.clone());
+            party
+        };
+        self.publish(&party.id, PartyChange::Created { leader_id: leader_id.to_string() }).await;
+        Ok(party)
+    }
+
+    /// Add `actor_id` to the group; `by` must be its leader or an assistant
+    pub async fn add_member(&self, party_id: &str, by: &str, actor_id: &str) -> ChaosResult<Party> {
+        let party = {
+            let mut state = self.state();
+            ensure_ungrouped(&state, actor_id)?;
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader, PartyRole::Assistant])?;
+            if party.members.len() >= self.config.max_members(party.kind) {
+                return Err(ChaosError::Validation(format!("Party {} is full", party_id)));
+            }
+            party.members.push(PartyMember { actor_id: actor_id.to_string(), role: PartyRole::Member, joined_at: self.clock.now() });
+            let party = party.clone();
+            state.membership.insert(actor_id.to_string(), party_id.to_string());
+            party
+        };
+        self.publish(party_id, PartyChange::MemberJoined { actor_id: actor_id.to_string() }).await;
+        Ok(party)
+    }
+
+    /// Leave the current group, returning it unless it disbanded
+    pub async fn leave(&self, actor_id: &str) -> ChaosResult<Option<Party>> {
+        let party_id = self
+            .party_id_of(actor_id)
+            .ok_or_else(|| ChaosError::Validation(format!("Actor {} is not in a party", actor_id)))?;
+        self.remove_member(&party_id, actor_id, false).await
+    }
+
+    /// Remove `actor_id` from the group; only the leader may kick
+    pub async fn kick(&self, party_id: &str, by: &str, actor_id: &str) -> ChaosResult<Option<Party>> {
+        {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            if by == actor_id {
+                return Err(ChaosError::Validation("Leaders leave their party instead of kicking themselves".to_string()));
+            }
+        }
+        self.remove_member(party_id, actor_id, true).await
+    }
+
+    /// Hand leadership to another member; the previous leader becomes an assistant
+    pub async fn transfer_leadership(&self, party_id: &str, by: &str, new_leader_id: &str) -> ChaosResult<Party> {
+        let party = {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            require_member(party, new_leader_id)?;
+            for member in &mut party.members {
+                if member.actor_id == by {
+                    member.role = PartyRole::Assistant;
+                } else if member.actor_id == new_leader_id {
+                    member.role = PartyRole::Leader;
+                }
+            }
+            party.clone()
+        };
+        self.publish(party_id, PartyChange::LeaderChanged { leader_id: new_leader_id.to_string() }).await;
+        Ok(party)
+    }
+
+    /// Make a member an assistant or a plain member; only the leader may
+    pub async fn set_role(&self, party_id: &str, by: &str, actor_id: &str, role: PartyRole) -> ChaosResult<Party> {
+        if role == PartyRole::Leader {
+            return Err(ChaosError::Validation("Leadership is handed over with transfer_leadership".to_string()));
+        }
+        let party = {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            if by == actor_id {
+                return Err(ChaosError::Validation("Leaders cannot change their own role".to_string()));
+            }
+            require_member(party, actor_id)?;
+            if let Some(member) = party.members.iter_mut().find(|member| member.actor_id == actor_id) {
+                member.role = role;
+            }
+            party.clone()
+        };
+        self.publish(party_id, PartyChange::RoleChanged { actor_id: actor_id.to_string(), role }).await;
+        Ok(party)
+    }
+
+    /// Turn a party into a raid; only the leader may
+    pub async fn convert_to_raid(&self, party_id: &str, by: &str) -> ChaosResult<Party> {
+        let party = {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            if party.kind == PartyKind::Raid {
+                return Err(ChaosError::Validation(format!("Party {} is already a raid", party_id)));
+            }
+            party.kind = PartyKind::Raid;
+            party.clone()
+        };
+        self.publish(party_id, PartyChange::ConvertedToRaid).await;
+        Ok(party)
+    }
+
+    /// Change the loot rules; only the leader may
+    pub async fn set_loot_rules(&self, party_id: &str, by: &str, rules: LootRules) -> ChaosResult<Party> {
+        let party = {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            match (&rules.method, &rules.master_looter) {
+                (LootMethod::MasterLooter, Some(looter)) => require_member(party, looter)?,
+                (LootMethod::MasterLooter, None) => {
+                    return Err(ChaosError::Validation("Master loot needs a master looter".to_string()))
+                }
+                _ => {}
+            }
+            party.loot_rules = rules.clone();
+            party.clone()
+        };
+        self.publish(party_id, PartyChange::LootRulesChanged { rules }).await;
+        Ok(party)
+    }
+
+    /// Disband the group; only the leader may
+    pub async fn disband(&self, party_id: &str, by: &str) -> ChaosResult<()> {
+        {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_role(party, by, &[PartyRole::Leader])?;
+            remove_party(&mut state, party_id);
+        }
+        self.publish(party_id, PartyChange::Disbanded).await;
+        Ok(())
+    }
+
+    pub fn get(&self, party_id: &str) -> Option<Party> {
+        self.state().parties.get(party_id).cloned()
+    }
+
+    /// Get the group an actor is in
+    pub fn party_of(&self, actor_id: &str) -> Option<Party> {
+        let state = self.state();
+        state.membership.get(actor_id).and_then(|party_id| state.parties.get(party_id)).cloned()
+    }
+
+    /// Member to receive the next item under round-robin loot, taking turns in join order
+    pub fn next_looter(&self, party_id: &str) -> ChaosResult<String> {
+        let mut state = self.state();
+        let party = state
+            .parties
+            .get(party_id)
+            .ok_or_else(|| ChaosError::Validation(format!("Party {} not found", party_id)))?;
+        let members = party.member_ids();
+        let cursor = state.loot_cursors.entry(party_id.to_string()).or_insert(0);
+        let looter = members[*cursor % members.len()].clone();
+        *cursor = (*cursor + 1) % members.len();
+        Ok(looter)
+    }
+
+    /// Share a buff with the actor's group, replacing one with the same ID
+    pub fn share_buff(&self, buff: SharedBuff) -> ChaosResult<()> {
+        let mut state = self.state();
+        if !state.membership.contains_key(&buff.source_actor_id) {
+            return Err(ChaosError::Validation(format!("Actor {} is not in a party", buff.source_actor_id)));
+        }
+        let buffs = state.buffs.entry(buff.source_actor_id.clone()).or_default();
+        buffs.retain(|shared| shared.buff_id != buff.buff_id);
+        buffs.push(buff);
+        Ok(())
+    }
+
+    /// Stop sharing a buff
+    pub fn unshare_buff(&self, actor_id: &str, buff_id: &str) {
+        if let Some(buffs) = self.state().buffs.get_mut(actor_id) {
+            buffs.retain(|shared| shared.buff_id != buff_id);
+        }
+    }
+
+    /// Buffs the other members of the actor's group share with it
+    pub fn party_buffs(&self, actor_id: &str) -> Vec<SharedBuff> {
+        let state = self.state();
+        let Some(party) = state.membership.get(actor_id).and_then(|party_id| state.parties.get(party_id)) else {
+            return Vec::new();
+        };
+        party
+            .members
+            .iter()
+            .filter(|member| member.actor_id != actor_id)
+            .filter_map(|member| state.buffs.get(&member.actor_id))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    async fn remove_member(&self, party_id: &str, actor_id: &str, kicked: bool) -> ChaosResult<Option<Party>> {
+        let (party, new_leader) = {
+            let mut state = self.state();
+            let party = party_mut(&mut state, party_id)?;
+            require_member(party, actor_id)?;
+            let was_leader = party.role_of(actor_id) == Some(PartyRole::Leader);
+            party.members.retain(|member| member.actor_id != actor_id);
+            let mut new_leader = None;
+            if was_leader && !party.members.is_empty() {
+                let successor = party
+                    .members
+                    .iter()
+                    .position(|member| member.role == PartyRole::Assistant)
+                    .unwrap_or(0);
+                party.members[successor].role = PartyRole::Leader;
+                new_leader = Some(party.members[successor].actor_id.clone());
+            }
+            if party.loot_rules.master_looter.as_deref() == Some(actor_id) {
+                party.loot_rules = LootRules::default();
+            }
+            let party = (!party.members.is_empty()).then(|| party.clone());
+            state.membership.remove(actor_id);
+            state.buffs.remove(actor_id);
+            if party.is_none() {
+                remove_party(&mut state, party_id);
+            }
+            (party, new_leader)
+        };
+
+        self.publish(party_id, PartyChange::MemberLeft { actor_id: actor_id.to_string(), kicked }).await;
+        match (&party, new_leader) {
+            (None, _) => self.publish(party_id, PartyChange::Disbanded).await,
+            (Some(_), Some(leader_id)) => self.publish(party_id, PartyChange::LeaderChanged { leader_id }).await,
+            _ => {}
+        }
+        Ok(party)
+    }
+
+    fn party_id_of(&self, actor_id: &str) -> Option<String> {
+        self.state().membership.get(actor_id).cloned()
+    }
+
+    /// Announce a change; the change stands even if the bus cannot take it
+    async fn publish(&self, party_id: &str, change: PartyChange) {
+        let Some((bus, source)) = &self.bus else {
+            return;
+        };
+        let message = PartyChanged { party_id: party_id.to_string(), change, at: self.clock.now() };
+        if let Err(e) = bus.publish_message(source, &PARTY_CHANGED, &message).await {
+            warn!("Failed to publish change of party {}: {}", party_id, e);
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn party_mut<'a>(state: &'a mut State, party_id: &str) -> ChaosResult<&'a mut Party> {
+    state
+        .parties
+        .get_mut(party_id)
+        .ok_or_else(|| ChaosError::Validation(format!("Party {} not found", party_id)))
+}
+
+fn ensure_ungrouped(state: &State, actor_id: &str) -> ChaosResult<()> {
+    match state.membership.get(actor_id) {
+        Some(party_id) => Err(ChaosError::Validation(format!("Actor {} is already in party {}", actor_id, party_id))),
+        None => Ok(()),
+    }
+}
+
+fn require_member(party: &Party, actor_id: &str) -> ChaosResult<()> {
+    if party.is_member(actor_id) {
+        Ok(())
+    } else {
+        Err(ChaosError::Validation(format!("Actor {} is not in party {}", actor_id, party.id)))
+    }
+}
+
+fn require_role(party: &Party, actor_id: &str, roles: &[PartyRole]) -> ChaosResult<()> {
+    match party.role_of(actor_id) {
+        Some(role) if roles.contains(&role) => Ok(()),
+        _ => Err(ChaosError::Authentication(format!("Actor {} may not manage party {}", actor_id, party.id))),
+    }
+}
+
+fn remove_party(state: &mut State, party_id: &str) {
+    if let Some(party) = state.parties.remove(party_id) {
+        for member in &party.members {
+            state.membership.remove(&member.actor_id);
+            state.buffs.remove(&member.actor_id);
+        }
+    }
+    state.loot_cursors.remove(party_id);
+}
@@ -0,0 +1,187 @@
+//! Presence and leases kept in process memory.
+
+use super::{ClaimOutcome, Lease, LeaseOutcome, LeaseRegistry, Presence, PresenceOwner, PresenceRegistry};
+use crate::clock::SharedClock;
+use crate::error::ChaosResult;
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+struct Entry {
+    presence: Presence,
+    expires_at: Timestamp,
+}
+
+/// Presence registry of a single process; claims expire on its clock
+pub struct InMemoryPresence {
+    clock: SharedClock,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl InMemoryPresence {
+    pub fn new(clock: SharedClock) -> Self {
+        Self { clock, entries: Mutex::new(HashMap::new()) }
+    }
+
+    fn set(&self, owner: &PresenceOwner, actor_id: &str, zone: &str, ttl: Duration, from: Option<&str>) -> ClaimOutcome {
+        let now = self.clock.now();
+        let mut entries = self.entries();
+        let current = entries.get(actor_id).filter(|entry| entry.expires_at > now).map(|entry| &entry.presence);
+        if let Some(current) = current {
+            if current.instance != owner.instance && Some(current.instance.as_str()) != from {
+                return ClaimOutcome::HeldBy(current.clone());
+            }
+        }
+        let epoch = match current {
+            Some(current) if current.instance == owner.instance => current.epoch,
+            Some(current) => current.epoch + 1,
+            None => 1,
+        };
+        let presence = Presence {
+            actor_id: actor_id.to_string(),
+            zone: zone.to_string(),
+            instance: owner.instance.clone(),
+            address: owner.address.clone(),
+            epoch,
+            updated_at: now,
+        };
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::zero());
+        entries.insert(actor_id.to_string(), Entry { presence: presence.clone(), expires_at });
+        ClaimOutcome::Claimed(presence)
+    }
+
+    fn entries(&self) -> MutexGuard<'_, HashMap<String, Entry>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl PresenceRegistry for InMemoryPresence {
+    async fn claim(&self, owner: &PresenceOwner, actor_id: &str, zone: &str, ttl: Duration) -> ChaosResult<ClaimOutcome> {
+        Ok(self.set(owner, actor_id, zone, ttl, None))
+    }
+
+    async fn handover(
+        &self,
+        owner: &PresenceOwner,
+        actor_id: &str,
+        zone: &str,
+        from_instance: &str,
+        ttl: Duration,
+    ) -> ChaosResult<ClaimOutcome> {
+        Ok(self.set(owner, actor_id, zone, ttl, Some(from_instance)))
+    }
+
+    async fn heartbeat(&self, owner: &PresenceOwner, actor_ids: &[String], ttl: Duration) -> ChaosResult<Vec<String>> {
+        let now = self.clock.now();
+        let expires_at = now + chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::zero());
+        let mut entries = self.entries();
+        let mut lost = Vec::new();
+        for actor_id in actor_ids {
+            match entries.get_mut(actor_id) {
+                Some(entry) if entry.expires_at > now && entry.presence.instance == owner.instance => {
+                    entry.expires_at = expires_at;
+                    entry.presence.updated_at = now;
+                }
+                _ => lost.push(actor_id.clone()),
+            }
+        }
+        Ok(lost)
+    }
+
+    async fn release(&self, instance: &str, actor_id: &str) -> ChaosResult<bool> {
+        let now = self.clock.now();
+        let mut entries = self.entries();
+        let held = entries
+            .get(actor_id)
+            .is_some_and(|entry| entry.expires_at > now && entry.presence.instance == instance);
+        if held {
+            entries.remove(actor_id);
+        }
+        Ok(held)
+    }
+
+    async fn lookup(&self, actor_id: &str) -> ChaosResult<Option<Presence>> {
+        let now = self.clock.now();
+        Ok(self
+            .entries()
+            .get(actor_id)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.presence.clone()))
+    }
+}
+
+/// Lease registry of a single process; leases expire on its clock
+pub struct InMemoryLeases {
+    clock: SharedClock,
+    leases: Mutex<HashMap<String, Lease>>,
+    /// Last fencing token of each lease, kept after the lease lapses
+    tokens: Mutex<HashMap<String, u64>>,
+}
+
+impl InMemoryLeases {
+    pub fn new(clock: SharedClock) -> Self {
+        Self { clock, leases: Mutex::new(HashMap::new()), tokens: Mutex::new(HashMap::new()) }
+    }
+}
+
+#[async_trait]
+impl LeaseRegistry for InMemoryLeases {
+    async fn acquire(
+        &self,
+        owner: &PresenceOwner,
+        name: &str,
+        ttl: Duration,
+        take_from: Option<&str>,
+    ) -> ChaosResult<LeaseOutcome> {
+        let now = self.clock.now();
+        let mut leases = lock(&self.leases);
+        let current = leases.get(name).filter(|lease| lease.expires_at > now);
+        if let Some(current) = current {
+            if current.instance != owner.instance && Some(current.instance.as_str()) != take_from {
+                return Ok(LeaseOutcome::HeldBy(current.clone()));
+            }
+        }
+        let token = match current {
+            Some(current) if current.instance == owner.instance => current.token,
+            _ => {
+                let mut tokens = lock(&self.tokens);
+                let token = tokens.entry(name.to_string()).or_insert(0);
+                *token += 1;
+                *token
+            }
+        };
+        let lease = Lease {
+            name: name.to_string(),
+            instance: owner.instance.clone(),
+            address: owner.address.clone(),
+            token,
+            expires_at: now + chrono::Duration::from_std(ttl).unwrap_or_else(|_| chrono::Duration::zero()),
+        };
+        leases.insert(name.to_string(), lease.clone());
+        Ok(LeaseOutcome::Acquired(lease))
+    }
+
+    async fn release(&self, instance: &str, name: &str, token: u64) -> ChaosResult<bool> {
+        let now = self.clock.now();
+        let mut leases = lock(&self.leases);
+        let held = leases
+            .get(name)
+            .is_some_and(|lease| lease.expires_at > now && lease.instance == instance && lease.token == token);
+        if held {
+            leases.remove(name);
+        }
+        Ok(held)
+    }
+
+    async fn current(&self, name: &str) -> ChaosResult<Option<Lease>> {
+        let now = self.clock.now();
+        Ok(lock(&self.leases).get(name).filter(|lease| lease.expires_at > now).cloned())
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
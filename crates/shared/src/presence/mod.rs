@@ -0,0 +1,173 @@
+//! Actor presence: which server instance owns each actor.
+//!
+//! world-service claims the actors of the zones it hosts and renews the claims with
+//! heartbeats; claims that are not renewed expire, so the actors of a crashed instance
+//! become free to claim. Services routing combat or chat actions find the owner with
+//! [`PresenceRegistry::lookup`]. When an actor moves to a zone hosted by another
+//! instance, that instance takes it over with [`PresenceRegistry::handover`] and
+//! announces it on [`ACTOR_HANDED_OVER`](crate::events::topics::ACTOR_HANDED_OVER) so the
+//! previous owner lets go.
+//!
+//! Zones follow the same idea at a coarser grain: the instance running a zone holds its
+//! [`Lease`], and standby instances take the lease over when it is not renewed. Each new
+//! holder gets a higher fencing token, so what an instance that lost its lease still
+//! sends can be told apart and refused.
+//!
+//! [`InMemoryPresence`] and [`InMemoryLeases`] serve tests and single-process setups;
+//! [`RedisPresence`] and [`RedisLeases`] (feature `presence-redis`) are shared by every
+//! instance.
+
+pub mod memory;
+#[cfg(feature = "presence-redis")]
+pub mod redis;
+
+pub use memory::{InMemoryLeases, InMemoryPresence};
+#[cfg(feature = "presence-redis")]
+pub use self::redis::{RedisLeases, RedisPresence};
+
+use crate::error::ChaosResult;
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Where an actor is and who owns it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Presence {
+    pub actor_id: String,
+    pub zone: String,
+    /// Instance owning the actor
+    pub instance: String,
+    /// Address actions for the actor are routed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Bumped whenever the owner changes while the actor stays present, so routes
+    /// resolved from an older presence can be told apart
+    pub epoch: u64,
+    pub updated_at: Timestamp,
+}
+
+/// An instance claiming actors
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceOwner {
+    pub instance: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl PresenceOwner {
+    pub fn new(instance: impl Into<String>, address: Option<String>) -> Self {
+        Self { instance: instance.into(), address }
+    }
+}
+
+/// Result of claiming an actor
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClaimOutcome {
+    /// The actor is ours
+    Claimed(Presence),
+    /// Another instance holds the actor
+    HeldBy(Presence),
+}
+
+impl ClaimOutcome {
+    pub fn is_claimed(&self) -> bool {
+        matches!(self, ClaimOutcome::Claimed(_))
+    }
+
+    pub fn presence(&self) -> &Presence {
+        match self {
+            ClaimOutcome::Claimed(presence) | ClaimOutcome::HeldBy(presence) => presence,
+        }
+    }
+}
+
+/// Registry of actor owners
+#[async_trait]
+pub trait PresenceRegistry: Send + Sync {
+    /// Claim an unowned actor for `owner` until `ttl` from now, or renew the claim when
+    /// `owner` already holds it
+    async fn claim(&self, owner: &PresenceOwner, actor_id: &str, zone: &str, ttl: Duration) -> ChaosResult<ClaimOutcome>;
+
+    /// Take an actor over from `from_instance`, or claim it when nobody holds it
+    async fn handover(
+        &self,
+        owner: &PresenceOwner,
+        actor_id: &str,
+        zone: &str,
+        from_instance: &str,
+        ttl: Duration,
+    ) -> ChaosResult<ClaimOutcome>;
+
+    /// Renew the claims `owner` holds, returning the actors it no longer holds
+    async fn heartbeat(&self, owner: &PresenceOwner, actor_ids: &[String], ttl: Duration) -> ChaosResult<Vec<String>>;
+
+    /// Give an actor up if `instance` holds it, returning whether it did
+    async fn release(&self, instance: &str, actor_id: &str) -> ChaosResult<bool>;
+
+    /// Owner of an actor, `None` when nobody holds it
+    async fn lookup(&self, actor_id: &str) -> ChaosResult<Option<Presence>>;
+
+    /// Owners of the actors that are held
+    async fn lookup_many(&self, actor_ids: &[String]) -> ChaosResult<Vec<Presence>> {
+        let mut found = Vec::new();
+        for actor_id in actor_ids {
+            found.extend(self.lookup(actor_id).await?);
+        }
+        Ok(found)
+    }
+}
+
+/// Lease on something a single instance runs at a time, e.g. a zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lease {
+    pub name: String,
+    /// Instance holding the lease
+    pub instance: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// Fencing token, higher for every new holder, lapsed leases included
+    pub token: u64,
+    pub expires_at: Timestamp,
+}
+
+/// Result of acquiring a lease
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaseOutcome {
+    /// The lease is ours
+    Acquired(Lease),
+    /// Another instance holds the lease
+    HeldBy(Lease),
+}
+
+impl LeaseOutcome {
+    pub fn is_acquired(&self) -> bool {
+        matches!(self, LeaseOutcome::Acquired(_))
+    }
+
+    pub fn lease(&self) -> &Lease {
+        match self {
+            LeaseOutcome::Acquired(lease) | LeaseOutcome::HeldBy(lease) => lease,
+        }
+    }
+}
+
+/// Registry of leases with fencing tokens
+#[async_trait]
+pub trait LeaseRegistry: Send + Sync {
+    /// Acquire a free lease for `owner` until `ttl` from now, or renew it when `owner`
+    /// already holds it; `take_from` lets `owner` take the lease from that instance
+    async fn acquire(
+        &self,
+        owner: &PresenceOwner,
+        name: &str,
+        ttl: Duration,
+        take_from: Option<&str>,
+    ) -> ChaosResult<LeaseOutcome>;
+
+    /// Give a lease up if `instance` holds it with `token`, returning whether it did
+    async fn release(&self, instance: &str, name: &str, token: u64) -> ChaosResult<bool>;
+
+    /// Holder of a lease, `None` when it is free
+    async fn current(&self, name: &str) -> ChaosResult<Option<Lease>>;
+}
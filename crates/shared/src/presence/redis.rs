@@ -0,0 +1,361 @@
+//! Presence in Redis, one hash per actor expiring with its claim, and leases, one hash
+//! per lease next to a counter of its fencing tokens that does not expire.
+//!
+//! Claims, handovers, heartbeats, acquisitions and releases run as Lua scripts so the
+//! owner check and the write happen atomically.
+
+use super::{ClaimOutcome, Lease, LeaseOutcome, LeaseRegistry, Presence, PresenceOwner, PresenceRegistry};
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use redis::aio::MultiplexedConnection;
+use redis::{RedisError, Script};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// Prefix of the presence keys
+pub const KEY_PREFIX: &str = "presence:actor:";
+
+/// KEYS[1] presence; ARGV instance, address, zone, now (ms), ttl (ms), previous owner
+/// allowed to be taken over (empty for none). Replies 1 or 0 followed by the hash.
+const CLAIM: &str = r#"
+local current = redis.call('HGET', KEYS[1], 'instance')
+if current and current ~= ARGV[1] and (ARGV[6] == '' or current ~= ARGV[6]) then
+    local reply = redis.call('HGETALL', KEYS[1])
+    table.insert(reply, 1, '0')
+    return reply
+end
+local epoch = tonumber(redis.call('HGET', KEYS[1], 'epoch') or '0')
+if current ~= ARGV[1] then
+    epoch = epoch + 1
+end
+redis.call('HSET', KEYS[1], 'instance', ARGV[1], 'address', ARGV[2], 'zone', ARGV[3], 'updated_at', ARGV[4], 'epoch', epoch)
+redis.call('PEXPIRE', KEYS[1], ARGV[5])
+local reply = redis.call('HGETALL', KEYS[1])
+table.insert(reply, 1, '1')
+return reply
+"#;
+
+/// KEYS presences; ARGV instance, now (ms), ttl (ms). Replies the keys no longer held.
+const HEARTBEAT: &str = r#"
+local lost = {}
+for _, key in ipairs(KEYS) do
+    if redis.call('HGET', key, 'instance') == ARGV[1] then
+        redis.call('HSET', key, 'updated_at', ARGV[2])
+        redis.call('PEXPIRE', key, ARGV[3])
+    else
+        table.insert(lost, key)
+    end
+end
+return lost
+"#;
+
+/// KEYS[1] presence; ARGV instance. Replies 1 when it was released.
+const RELEASE: &str = r#"
+if redis.call('HGET', KEYS[1], 'instance') == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Prefix of the lease keys
+pub const LEASE_KEY_PREFIX: &str = "lease:held:";
+
+/// Prefix of the fencing token counters of the leases
+pub const TOKEN_KEY_PREFIX: &str = "lease:token:";
+
+/// KEYS[1] lease, KEYS[2] token counter; ARGV instance, address, expires_at (ms), ttl
+/// (ms), holder allowed to be taken over (empty for none). Replies 1 or 0 followed by the
+/// hash.
+const ACQUIRE: &str = r#"
+local current = redis.call('HGET', KEYS[1], 'instance')
+if current and current ~= ARGV[1] and (ARGV[5] == '' or current ~= ARGV[5]) then
+    local reply = redis.call('HGETALL', KEYS[1])
+    table.insert(reply, 1, '0')
+    return reply
+end
+local token = redis.call('HGET', KEYS[1], 'token')
+if current ~= ARGV[1] then
+    token = redis.call('INCR', KEYS[2])
+end
+redis.call('HSET', KEYS[1], 'instance', ARGV[1], 'address', ARGV[2], 'token', token, 'expires_at', ARGV[3])
+redis.call('PEXPIRE', KEYS[1], ARGV[4])
+local reply = redis.call('HGETALL', KEYS[1])
+table.insert(reply, 1, '1')
+return reply
+"#;
+
+/// KEYS[1] lease; ARGV instance, token. Replies 1 when it was released.
+const RELEASE_LEASE: &str = r#"
+if redis.call('HGET', KEYS[1], 'instance') == ARGV[1] and redis.call('HGET', KEYS[1], 'token') == ARGV[2] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// Presence registry shared by every instance through Redis
+pub struct RedisPresence {
+    client: redis::Client,
+    connection: OnceCell<MultiplexedConnection>,
+    claim: Script,
+    heartbeat: Script,
+    release: Script,
+}
+
+impl RedisPresence {
+    /// Connect lazily to a `redis://` URL
+    pub fn new(url: &str) -> ChaosResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| ChaosError::Configuration(format!("Invalid presence Redis URL: {}", e)))?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            claim: Script::new(CLAIM),
+            heartbeat: Script::new(HEARTBEAT),
+            release: Script::new(RELEASE),
+        })
+    }
+
+    async fn connection(&self) -> ChaosResult<MultiplexedConnection> {
+        self.connection
+            .get_or_try_init(|| self.client.get_multiplexed_tokio_connection())
+            .await
+            .cloned()
+            .map_err(redis_error)
+    }
+
+    async fn set(
+        &self,
+        owner: &PresenceOwner,
+        actor_id: &str,
+        zone: &str,
+        ttl: Duration,
+        from_instance: &str,
+    ) -> ChaosResult<ClaimOutcome> {
+        let mut connection = self.connection().await?;
+        let reply: Vec<String> = self
+            .claim
+            .key(key(actor_id))
+            .arg(&owner.instance)
+            .arg(owner.address.as_deref().unwrap_or(""))
+            .arg(zone)
+            .arg(Utc::now().timestamp_millis())
+            .arg(ttl_millis(ttl))
+            .arg(from_instance)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        let claimed = reply.first().is_some_and(|status| status == "1");
+        let presence = parse(actor_id, reply.into_iter().skip(1))
+            .ok_or_else(|| ChaosError::Serialization(format!("Malformed presence of {}", actor_id)))?;
+        Ok(if claimed { ClaimOutcome::Claimed(presence) } else { ClaimOutcome::HeldBy(presence) })
+    }
+}
+
+#[async_trait]
+impl PresenceRegistry for RedisPresence {
+    async fn claim(&self, owner: &PresenceOwner, actor_id: &str, zone: &str, ttl: Duration) -> ChaosResult<ClaimOutcome> {
+        self.set(owner, actor_id, zone, ttl, "").await
+    }
+
+    async fn handover(
+        &self,
+        owner: &PresenceOwner,
+        actor_id: &str,
+        zone: &str,
+        from_instance: &str,
+        ttl: Duration,
+    ) -> ChaosResult<ClaimOutcome> {
+        self.set(owner, actor_id, zone, ttl, from_instance).await
+    }
+
+    async fn heartbeat(&self, owner: &PresenceOwner, actor_ids: &[String], ttl: Duration) -> ChaosResult<Vec<String>> {
+        if actor_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection = self.connection().await?;
+        let mut invocation = self.heartbeat.prepare_invoke();
+        for actor_id in actor_ids {
+            invocation.key(key(actor_id));
+        }
+        let lost: Vec<String> = invocation
+            .arg(&owner.instance)
+            .arg(Utc::now().timestamp_millis())
+            .arg(ttl_millis(ttl))
+            .invoke_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(lost
+            .into_iter()
+            .map(|key| key.strip_prefix(KEY_PREFIX).map(str::to_string).unwrap_or(key))
+            .collect())
+    }
+
+    async fn release(&self, instance: &str, actor_id: &str) -> ChaosResult<bool> {
+        let mut connection = self.connection().await?;
+        let released: i64 = self
+            .release
+            .key(key(actor_id))
+            .arg(instance)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(released > 0)
+    }
+
+    async fn lookup(&self, actor_id: &str) -> ChaosResult<Option<Presence>> {
+        let mut connection = self.connection().await?;
+        let fields: Vec<String> = redis::cmd("HGETALL")
+            .arg(key(actor_id))
+            .query_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(parse(actor_id, fields.into_iter()))
+    }
+
+    async fn lookup_many(&self, actor_ids: &[String]) -> ChaosResult<Vec<Presence>> {
+        if actor_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut connection = self.connection().await?;
+        let mut pipeline = redis::pipe();
+        for actor_id in actor_ids {
+            pipeline.cmd("HGETALL").arg(key(actor_id));
+        }
+        let replies: Vec<Vec<String>> = pipeline.query_async(&mut connection).await.map_err(redis_error)?;
+        Ok(actor_ids
+            .iter()
+            .zip(replies)
+            .filter_map(|(actor_id, fields)| parse(actor_id, fields.into_iter()))
+            .collect())
+    }
+}
+
+/// Lease registry shared by every instance through Redis
+pub struct RedisLeases {
+    client: redis::Client,
+    connection: OnceCell<MultiplexedConnection>,
+    acquire: Script,
+    release: Script,
+}
+
+impl RedisLeases {
+    /// Connect lazily to a `redis://` URL
+    pub fn new(url: &str) -> ChaosResult<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| ChaosError::Configuration(format!("Invalid lease Redis URL: {}", e)))?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            acquire: Script::new(ACQUIRE),
+            release: Script::new(RELEASE_LEASE),
+        })
+    }
+
+    async fn connection(&self) -> ChaosResult<MultiplexedConnection> {
+        self.connection
+            .get_or_try_init(|| self.client.get_multiplexed_tokio_connection())
+            .await
+            .cloned()
+            .map_err(redis_error)
+    }
+}
+
+#[async_trait]
+impl LeaseRegistry for RedisLeases {
+    async fn acquire(
+        &self,
+        owner: &PresenceOwner,
+        name: &str,
+        ttl: Duration,
+        take_from: Option<&str>,
+    ) -> ChaosResult<LeaseOutcome> {
+        let mut connection = self.connection().await?;
+        let ttl = ttl_millis(ttl);
+        let reply: Vec<String> = self
+            .acquire
+            .key(format!("{}{}", LEASE_KEY_PREFIX, name))
+            .key(format!("{}{}", TOKEN_KEY_PREFIX, name))
+            .arg(&owner.instance)
+            .arg(owner.address.as_deref().unwrap_or(""))
+            .arg(Utc::now().timestamp_millis() + ttl as i64)
+            .arg(ttl)
+            .arg(take_from.unwrap_or(""))
+            .invoke_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        let acquired = reply.first().is_some_and(|status| status == "1");
+        let lease = parse_lease(name, reply.into_iter().skip(1))
+            .ok_or_else(|| ChaosError::Serialization(format!("Malformed lease {}", name)))?;
+        Ok(if acquired { LeaseOutcome::Acquired(lease) } else { LeaseOutcome::HeldBy(lease) })
+    }
+
+    async fn release(&self, instance: &str, name: &str, token: u64) -> ChaosResult<bool> {
+        let mut connection = self.connection().await?;
+        let released: i64 = self
+            .release
+            .key(format!("{}{}", LEASE_KEY_PREFIX, name))
+            .arg(instance)
+            .arg(token)
+            .invoke_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(released > 0)
+    }
+
+    async fn current(&self, name: &str) -> ChaosResult<Option<Lease>> {
+        let mut connection = self.connection().await?;
+        let fields: Vec<String> = redis::cmd("HGETALL")
+            .arg(format!("{}{}", LEASE_KEY_PREFIX, name))
+            .query_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+        Ok(parse_lease(name, fields.into_iter()))
+    }
+}
+
+fn key(actor_id: &str) -> String {
+    format!("{}{}", KEY_PREFIX, actor_id)
+}
+
+fn ttl_millis(ttl: Duration) -> u64 {
+    (ttl.as_millis() as u64).max(1)
+}
+
+/// Presence from the flat field/value list of a hash; `None` when it is empty
+fn parse(actor_id: &str, mut fields: impl Iterator<Item = String>) -> Option<Presence> {
+    let mut hash = HashMap::new();
+    while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+        hash.insert(field, value);
+    }
+    let updated_at: Timestamp = Utc.timestamp_millis_opt(hash.get("updated_at")?.parse().ok()?).single()?;
+    Some(Presence {
+        actor_id: actor_id.to_string(),
+        zone: hash.remove("zone")?,
+        instance: hash.remove("instance")?,
+        address: hash.remove("address").filter(|address| !address.is_empty()),
+        epoch: hash.get("epoch")?.parse().ok()?,
+        updated_at,
+    })
+}
+
+/// Lease from the flat field/value list of its hash; `None` when it is empty
+fn parse_lease(name: &str, mut fields: impl Iterator<Item = String>) -> Option<Lease> {
+    let mut hash = HashMap::new();
+    while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+        hash.insert(field, value);
+    }
+    Some(Lease {
+        name: name.to_string(),
+        instance: hash.remove("instance")?,
+        address: hash.remove("address").filter(|address| !address.is_empty()),
+        token: hash.get("token")?.parse().ok()?,
+        expires_at: Utc.timestamp_millis_opt(hash.get("expires_at")?.parse().ok()?).single()?,
+    })
+}
+
+fn redis_error(error: RedisError) -> ChaosError {
+    ChaosError::ExternalService(format!("Presence Redis error: {}", error))
+}
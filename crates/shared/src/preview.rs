@@ -0,0 +1,146 @@
+//! Draft content previews.
+//!
+//! Content editors test unpublished content with a preview token issued by the CMS.
+//! Requests carrying the token in `X-Chaos-Preview` make the services selected for the
+//! preview load draft revisions instead of published content, for that request only.
+//! Services run request handling inside [`scope`] and their config loaders go through
+//! [`load_content`], which asks a [`DraftSource`] for the draft while a preview is active
+//! and falls back to published content otherwise.
+
+use async_trait::async_trait;
+use serde_json::Value;
+use std::future::Future;
+
+use crate::error::ChaosResult;
+
+/// Header carrying the preview token.
+pub const PREVIEW_HEADER: &str = "x-chaos-preview";
+
+/// Header naming the service asking the CMS for a draft.
+pub const PREVIEW_SERVICE_HEADER: &str = "x-chaos-service";
+
+/// Prefix of every preview token.
+pub const PREVIEW_TOKEN_PREFIX: &str = "pv_";
+
+/// Longest preview token that is accepted.
+pub const MAX_PREVIEW_TOKEN_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT_PREVIEW: PreviewContext;
+}
+
+/// Preview requested by the current request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreviewContext {
+    pub token: String,
+}
+
+impl PreviewContext {
+    /// Read the preview token from a header value.
+    ///
+    /// Malformed tokens are ignored, the request then sees published content.
+    pub fn from_header(value: Option<&str>) -> Option<Self> {
+        let token = value?.trim();
+        is_valid_preview_token(token).then(|| Self { token: token.to_string() })
+    }
+}
+
+/// Check the shape of a preview token, the CMS decides whether it is live.
+pub fn is_valid_preview_token(token: &str) -> bool {
+    token.starts_with(PREVIEW_TOKEN_PREFIX)
+        && token.len() > PREVIEW_TOKEN_PREFIX.len()
+        && token.len() <= MAX_PREVIEW_TOKEN_LEN
+        && token.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+/// Run `future` with `preview` as the active preview, or with none.
+pub async fn scope<F: Future>(preview: Option<PreviewContext>, future: F) -> F::Output {
+    match preview {
+        Some(preview) => CURRENT_PREVIEW.scope(preview, future).await,
+        None => future.await,
+    }
+}
+
+/// Preview active for the running task.
+pub fn current() -> Option<PreviewContext> {
+    CURRENT_PREVIEW.try_with(|preview| preview.clone()).ok()
+}
+
+/// Where draft revisions come from, usually the CMS preview API.
+#[async_trait]
+pub trait DraftSource: Send + Sync {
+    /// Draft of a content document, `None` when the preview does not cover it.
+    async fn draft(&self, preview: &PreviewContext, content_type: &str, content_id: &str) -> ChaosResult<Option<Value>>;
+}
+
+/// Load a content document, honouring the active preview.
+///
+/// `published` loads the published document and is only called when there is no
+/// preview or the preview does not cover the document.
+pub async fn load_content<S, F, Fut>(
+    drafts: &S,
+    content_type: &str,
+    content_id: &str,
+    published: F,
+) -> ChaosResult<Option<Value>>
+where
+    S: DraftSource + ?Sized,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ChaosResult<Option<Value>>>,
+{
+    if let Some(preview) = current() {
+        if let Some(draft) = drafts.draft(&preview, content_type, content_id).await? {
+            return Ok(Some(draft));
+        }
+    }
+    published().await
+}
+
+/// [`DraftSource`] asking the CMS preview API for drafts.
+#[cfg(feature = "preview-client")]
+pub struct CmsDraftSource {
+    client: reqwest::Client,
+    base_url: String,
+    service: String,
+}
+
+#[cfg(feature = "preview-client")]
+impl CmsDraftSource {
+    /// `base_url` is the CMS API root, e.g. `http://cms:8080/api/v1`.
+    pub fn new(base_url: impl Into<String>, service: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            service: service.into(),
+        }
+    }
+}
+
+#[cfg(feature = "preview-client")]
+#[async_trait]
+impl DraftSource for CmsDraftSource {
+    async fn draft(&self, preview: &PreviewContext, content_type: &str, content_id: &str) -> ChaosResult<Option<Value>> {
+        use crate::error::ChaosError;
+
+        let url = format!("{}/preview/content/{}/{}", self.base_url, content_type, content_id);
+        let response = self
+            .client
+            .get(&url)
+            .header(PREVIEW_HEADER, &preview.token)
+            .header(PREVIEW_SERVICE_HEADER, &self.service)
+            .send()
+            .await
+            .map_err(|e| ChaosError::Network(e.to_string()))?;
+
+        // Not covered by the preview, or the preview expired: use published content
+        if response.status() == reqwest::StatusCode::NOT_FOUND || response.status() == reqwest::StatusCode::FORBIDDEN {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            return Err(ChaosError::ExternalService(format!("CMS preview returned {}", response.status())));
+        }
+
+        let mut body: Value = response.json().await.map_err(|e| ChaosError::Serialization(e.to_string()))?;
+        Ok(body.pointer_mut("/data/data").map(Value::take))
+    }
+}
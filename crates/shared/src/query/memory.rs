@@ -0,0 +1,135 @@
+//! In-memory evaluation of list queries.
+//!
+//! Lets stores without a query engine (tests, in-process caches) serve the same
+//! [`ListQuery`] as their MongoDB counterparts. Items are compared through their JSON
+//! form, so field names match the serialized ones.
+
+use super::{FilterCondition, FilterOp, FilterSpec, ListQuery, Page, PageCursor, SortDirection, SortSpec};
+use crate::error::{ChaosError, ChaosResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Filter, sort and paginate items.
+pub fn apply_query<T: Serialize>(items: impl IntoIterator<Item = T>, query: &ListQuery) -> ChaosResult<Page<T>> {
+    let mut rows = Vec::new();
+    for item in items {
+        let json = serde_json::to_value(&item)?;
+        if matches_filter(&query.filter, &json) {
+            rows.push((sort_key(&query.sort, &json), item));
+        }
+    }
+    rows.sort_by(|(a, _), (b, _)| compare_keys(&query.sort, a, b));
+
+    if let Some(cursor) = query.page.after()? {
+        if query.sort.is_empty() || cursor.values.len() != query.sort.fields.len() {
+            return Err(ChaosError::Validation("Page cursor does not match the sort order".to_string()));
+        }
+        rows.retain(|(key, _)| compare_keys(&query.sort, key, &cursor.values) == Ordering::Greater);
+    }
+
+    let limit = query.page.limit();
+    match query.page.skip() {
+        Some(skip) => {
+            let total = rows.len() as u64;
+            let items = rows.into_iter().skip(skip as usize).take(limit as usize).map(|(_, item)| item).collect();
+            Ok(Page::from_offset(items, total, &query.page))
+        }
+        None => {
+            let has_more = rows.len() > limit as usize;
+            rows.truncate(limit as usize);
+            let next_cursor = if has_more {
+                rows.last().map(|(key, _)| PageCursor::new(key.clone()).encode())
+            } else {
+                None
+            };
+            Ok(Page {
+                items: rows.into_iter().map(|(_, item)| item).collect(),
+                total: None,
+                next_cursor,
+                has_more,
+            })
+        }
+    }
+}
+
+/// Check whether an item's JSON form matches every condition of a filter.
+pub fn matches_filter(filter: &FilterSpec, item: &Value) -> bool {
+    filter.conditions.iter().all(|condition| matches_condition(condition, item))
+}
+
+fn matches_condition(condition: &FilterCondition, item: &Value) -> bool {
+    let field = lookup(item, &condition.field);
+    let value = &condition.value;
+    match condition.op {
+        FilterOp::Exists => value.as_bool() == Some(!field.is_null()),
+        FilterOp::Eq => compare_values(field, value) == Ordering::Equal,
+        FilterOp::Ne => compare_values(field, value) != Ordering::Equal,
+        FilterOp::Gt => !field.is_null() && compare_values(field, value) == Ordering::Greater,
+        FilterOp::Gte => !field.is_null() && compare_values(field, value) != Ordering::Less,
+        FilterOp::Lt => !field.is_null() && compare_values(field, value) == Ordering::Less,
+        FilterOp::Lte => !field.is_null() && compare_values(field, value) != Ordering::Greater,
+        FilterOp::In => value
+            .as_array()
+            .is_some_and(|values| values.iter().any(|v| compare_values(field, v) == Ordering::Equal)),
+        FilterOp::NotIn => value
+            .as_array()
+            .is_some_and(|values| values.iter().all(|v| compare_values(field, v) != Ordering::Equal)),
+        FilterOp::Contains => match (field.as_str(), value.as_str()) {
+            (Some(text), Some(needle)) => text.to_lowercase().contains(&needle.to_lowercase()),
+            _ => false,
+        },
+    }
+}
+
+/// Get a dot-separated field of a JSON value; `null` when missing.
+fn lookup<'a>(item: &'a Value, field: &str) -> &'a Value {
+    field
+        .split('.')
+        .try_fold(item, |value, part| value.get(part))
+        .unwrap_or(&Value::Null)
+}
+
+fn sort_key(sort: &SortSpec, item: &Value) -> Vec<Value> {
+    sort.fields.iter().map(|sort_field| lookup(item, &sort_field.field).clone()).collect()
+}
+
+fn compare_keys(sort: &SortSpec, a: &[Value], b: &[Value]) -> Ordering {
+    for ((sort_field, a), b) in sort.fields.iter().zip(a).zip(b) {
+        let ordering = compare_values(a, b);
+        let ordering = match sort_field.direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Order JSON values the way MongoDB orders BSON: null first, then numbers, strings
+/// and booleans.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or_default(), b.as_f64().unwrap_or_default());
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ if type_rank(a) == type_rank(b) => a.to_string().cmp(&b.to_string()),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Number(_) => 1,
+        Value::String(_) => 2,
+        Value::Object(_) => 3,
+        Value::Array(_) => 4,
+        Value::Bool(_) => 5,
+    }
+}
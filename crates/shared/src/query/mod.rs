@@ -0,0 +1,489 @@
+//! Pagination, sorting, and filtering types for list endpoints.
+//!
+//! List endpoints accept a [`ListQuery`] (usually built from [`PageParams`] query-string
+//! parameters) and return a [`Page`]. Backends translate the query into their own
+//! syntax; MongoDB helpers live in [`mongo`] (behind the `mongodb` feature), and
+//! [`memory`] evaluates queries over in-memory items.
+
+pub mod memory;
+#[cfg(feature = "mongodb")]
+pub mod mongo;
+
+use crate::error::{ChaosError, ChaosResult};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Default page size.
+pub const DEFAULT_PAGE_LIMIT: u32 = 20;
+
+/// Largest page size a client may request.
+pub const MAX_PAGE_LIMIT: u32 = 100;
+
+/// Which page of results to return.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PageRequest {
+    /// Skip `offset` results, then return up to `limit`.
+    Offset { offset: u64, limit: u32 },
+    /// Return up to `limit` results after the position encoded in `after`.
+    Cursor { after: Option<String>, limit: u32 },
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        Self::first(DEFAULT_PAGE_LIMIT)
+    }
+}
+
+impl PageRequest {
+    /// First page of `limit` results.
+    pub fn first(limit: u32) -> Self {
+        Self::Offset { offset: 0, limit }
+    }
+
+    /// Offset-based page.
+    pub fn offset(offset: u64, limit: u32) -> Self {
+        Self::Offset { offset, limit }
+    }
+
+    /// 1-based page number of `per_page` results.
+    pub fn page(page: u32, per_page: u32) -> Self {
+        Self::Offset {
+            offset: page.saturating_sub(1) as u64 * per_page as u64,
+            limit: per_page,
+        }
+    }
+
+    /// Cursor-based page.
+    pub fn cursor(after: Option<String>, limit: u32) -> Self {
+        Self::Cursor { after, limit }
+    }
+
+    /// Get the page size.
+    pub fn limit(&self) -> u32 {
+        match self {
+            Self::Offset { limit, .. } | Self::Cursor { limit, .. } => *limit,
+        }
+    }
+
+    /// Get the number of results to skip (offset pages only).
+    pub fn skip(&self) -> Option<u64> {
+        match self {
+            Self::Offset { offset, .. } => Some(*offset),
+            Self::Cursor { .. } => None,
+        }
+    }
+
+    /// Decode the cursor position (cursor pages only).
+    pub fn after(&self) -> ChaosResult<Option<PageCursor>> {
+        match self {
+            Self::Cursor { after: Some(after), .. } => PageCursor::decode(after).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Clamp the page size to `1..=max_limit`.
+    pub fn clamped(mut self, max_limit: u32) -> Self {
+        match &mut self {
+            Self::Offset { limit, .. } | Self::Cursor { limit, .. } => *limit = (*limit).clamp(1, max_limit.max(1)),
+        }
+        self
+    }
+}
+
+/// Opaque position in a cursor-paginated result set.
+///
+/// Holds the sort-key values of the last returned item, in [`SortSpec`] field order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PageCursor {
+    /// Sort-key values of the last returned item
+    pub values: Vec<serde_json::Value>,
+}
+
+impl PageCursor {
+    /// Create a cursor from sort-key values.
+    pub fn new(values: Vec<serde_json::Value>) -> Self {
+        Self { values }
+    }
+
+    /// Encode the cursor as a URL-safe token.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&self.values).unwrap_or_default())
+    }
+
+    /// Decode a token produced by [`encode`](Self::encode).
+    pub fn decode(token: &str) -> ChaosResult<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| ChaosError::Validation("Invalid page cursor".to_string()))?;
+        let values = serde_json::from_slice(&bytes)
+            .map_err(|_| ChaosError::Validation("Invalid page cursor".to_string()))?;
+        Ok(Self { values })
+    }
+}
+
+/// Sort direction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    /// Get the reverse direction.
+    pub fn reverse(self) -> Self {
+        match self {
+            Self::Asc => Self::Desc,
+            Self::Desc => Self::Asc,
+        }
+    }
+}
+
+impl FromStr for SortDirection {
+    type Err = ChaosError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "asc" | "ascending" | "1" => Ok(Self::Asc),
+            "desc" | "descending" | "-1" => Ok(Self::Desc),
+            _ => Err(ChaosError::Validation(format!("Invalid sort direction: '{}'", value))),
+        }
+    }
+}
+
+/// Single sort key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortField {
+    /// Field name (dot-separated for nested fields)
+    pub field: String,
+    /// Sort direction
+    pub direction: SortDirection,
+}
+
+/// Ordered list of sort keys.
+///
+/// Parses from and formats to the `-created_at,name` query-string syntax, where a
+/// leading `-` means descending.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortSpec {
+    /// Sort keys, most significant first
+    pub fields: Vec<SortField>,
+}
+
+impl SortSpec {
+    /// Create an empty sort spec.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an ascending key.
+    pub fn asc(self, field: impl Into<String>) -> Self {
+        self.by(field, SortDirection::Asc)
+    }
+
+    /// Add a descending key.
+    pub fn desc(self, field: impl Into<String>) -> Self {
+        self.by(field, SortDirection::Desc)
+    }
+
+    /// Add a key.
+    pub fn by(mut self, field: impl Into<String>, direction: SortDirection) -> Self {
+        self.fields.push(SortField {
+            field: field.into(),
+            direction,
+        });
+        self
+    }
+
+    /// Check whether no keys are set.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Reject fields outside an allow-list.
+    pub fn validate_fields(&self, allowed: &[&str]) -> ChaosResult<()> {
+        for sort_field in &self.fields {
+            if !allowed.contains(&sort_field.field.as_str()) {
+                return Err(ChaosError::Validation(format!("Cannot sort by '{}'", sort_field.field)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = ChaosError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut spec = SortSpec::new();
+        for token in value.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+            let (field, direction) = match token.strip_prefix('-') {
+                Some(field) => (field, SortDirection::Desc),
+                None => (token.strip_prefix('+').unwrap_or(token), SortDirection::Asc),
+            };
+            validate_field_name(field)?;
+            spec = spec.by(field, direction);
+        }
+        Ok(spec)
+    }
+}
+
+impl fmt::Display for SortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, sort_field) in self.fields.iter().enumerate() {
+            if index > 0 {
+                f.write_str(",")?;
+            }
+            if sort_field.direction == SortDirection::Desc {
+                f.write_str("-")?;
+            }
+            f.write_str(&sort_field.field)?;
+        }
+        Ok(())
+    }
+}
+
+/// Filter comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// Value is one of an array of values
+    In,
+    /// Value is none of an array of values
+    NotIn,
+    /// Case-insensitive substring match on strings
+    Contains,
+    /// Field presence (value is a boolean)
+    Exists,
+}
+
+/// Single filter condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterCondition {
+    /// Field name (dot-separated for nested fields)
+    pub field: String,
+    /// Comparison operator
+    pub op: FilterOp,
+    /// Value to compare against
+    pub value: serde_json::Value,
+}
+
+/// Conjunction of filter conditions.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FilterSpec {
+    /// Conditions that must all match
+    pub conditions: Vec<FilterCondition>,
+}
+
+impl FilterSpec {
+    /// Create an empty filter.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a condition.
+    pub fn with(mut self, field: impl Into<String>, op: FilterOp, value: impl Into<serde_json::Value>) -> Self {
+        self.conditions.push(FilterCondition {
+            field: field.into(),
+            op,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Add an equality condition.
+    pub fn eq(self, field: impl Into<String>, value: impl Into<serde_json::Value>) -> Self {
+        self.with(field, FilterOp::Eq, value)
+    }
+
+    /// Add a condition only when a value is present.
+    pub fn with_opt<V: Into<serde_json::Value>>(self, field: impl Into<String>, op: FilterOp, value: Option<V>) -> Self {
+        match value {
+            Some(value) => self.with(field, op, value),
+            None => self,
+        }
+    }
+
+    /// Check whether no conditions are set.
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Validate field names and operator values, rejecting fields outside an allow-list.
+    pub fn validate_fields(&self, allowed: &[&str]) -> ChaosResult<()> {
+        for condition in &self.conditions {
+            validate_field_name(&condition.field)?;
+            if !allowed.contains(&condition.field.as_str()) {
+                return Err(ChaosError::Validation(format!("Cannot filter by '{}'", condition.field)));
+            }
+            let valid_value = match condition.op {
+                FilterOp::In | FilterOp::NotIn => condition.value.is_array(),
+                FilterOp::Contains => condition.value.is_string(),
+                FilterOp::Exists => condition.value.is_boolean(),
+                _ => true,
+            };
+            if !valid_value {
+                return Err(ChaosError::Validation(format!(
+                    "Invalid value for {:?} filter on '{}'",
+                    condition.op, condition.field
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Page, sort, and filter for a list request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// Which page to return
+    pub page: PageRequest,
+    /// Result order
+    pub sort: SortSpec,
+    /// Result filter
+    pub filter: FilterSpec,
+}
+
+impl ListQuery {
+    /// Create a query for a page.
+    pub fn new(page: PageRequest) -> Self {
+        Self {
+            page,
+            ..Self::default()
+        }
+    }
+
+    /// Set the sort order.
+    pub fn with_sort(mut self, sort: SortSpec) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Set the filter.
+    pub fn with_filter(mut self, filter: FilterSpec) -> Self {
+        self.filter = filter;
+        self
+    }
+}
+
+/// Flat query-string parameters for list endpoints.
+///
+/// `?page=2&limit=50&sort=-created_at` or `?cursor=...&limit=50`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageParams {
+    /// 1-based page number
+    pub page: Option<u32>,
+    /// Page size
+    pub limit: Option<u32>,
+    /// Number of results to skip (alternative to `page`)
+    pub offset: Option<u64>,
+    /// Cursor from a previous page (takes precedence over `page`/`offset`)
+    pub cursor: Option<String>,
+    /// Sort spec, e.g. `-created_at,name`
+    pub sort: Option<String>,
+}
+
+impl PageParams {
+    /// Build the page request, clamping the page size to [`MAX_PAGE_LIMIT`].
+    pub fn page_request(&self) -> PageRequest {
+        // Clamp first so page numbers count pages of the size actually returned
+        let limit = self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        if let Some(cursor) = &self.cursor {
+            PageRequest::cursor(Some(cursor.clone()), limit)
+        } else if let Some(offset) = self.offset {
+            PageRequest::offset(offset, limit)
+        } else {
+            PageRequest::page(self.page.unwrap_or(1), limit)
+        }
+    }
+
+    /// Parse the sort spec.
+    pub fn sort_spec(&self) -> ChaosResult<SortSpec> {
+        self.sort.as_deref().map_or_else(|| Ok(SortSpec::new()), SortSpec::from_str)
+    }
+
+    /// Build a list query without filters.
+    pub fn to_list_query(&self) -> ChaosResult<ListQuery> {
+        Ok(ListQuery::new(self.page_request()).with_sort(self.sort_spec()?))
+    }
+}
+
+/// One page of results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Page<T> {
+    /// Results on this page
+    pub items: Vec<T>,
+    /// Total number of matching results, when known
+    pub total: Option<u64>,
+    /// Cursor for the next page, when there is one
+    pub next_cursor: Option<String>,
+    /// Whether more results follow this page
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    /// Build an offset page from its items and the total result count.
+    pub fn from_offset(items: Vec<T>, total: u64, request: &PageRequest) -> Self {
+        let end = request.skip().unwrap_or(0) + items.len() as u64;
+        Self {
+            items,
+            total: Some(total),
+            next_cursor: None,
+            has_more: end < total,
+        }
+    }
+
+    /// Build a cursor page from up to `limit + 1` fetched items.
+    ///
+    /// Fetching one extra item tells whether another page exists without a count query;
+    /// `cursor_of` extracts the cursor of the last returned item.
+    pub fn from_cursor(mut items: Vec<T>, limit: u32, cursor_of: impl Fn(&T) -> PageCursor) -> Self {
+        let has_more = items.len() > limit as usize;
+        items.truncate(limit as usize);
+        let next_cursor = if has_more {
+            items.last().map(|item| cursor_of(item).encode())
+        } else {
+            None
+        };
+        Self {
+            items,
+            total: None,
+            next_cursor,
+            has_more,
+        }
+    }
+
+    /// Map the items of the page.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+            has_more: self.has_more,
+        }
+    }
+}
+
+/// Reject field names that are empty or could inject query operators.
+fn validate_field_name(field: &str) -> ChaosResult<()> {
+    let valid = !field.is_empty()
+        && field
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ChaosError::Validation(format!("Invalid field name: '{}'", field)))
+    }
+}
@@ -0,0 +1,139 @@
+//! MongoDB translation of list queries.
+
+use super::{FilterOp, FilterSpec, ListQuery, PageCursor, SortDirection, SortSpec};
+use crate::error::{ChaosError, ChaosResult};
+use bson::{doc, Bson, Document};
+
+/// List query translated into MongoDB `find` arguments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MongoQuery {
+    /// Query filter, including the cursor position for cursor pages
+    pub filter: Document,
+    /// Sort document (empty when unsorted)
+    pub sort: Document,
+    /// Number of documents to skip (offset pages only)
+    pub skip: Option<u64>,
+    /// Maximum number of documents to return
+    pub limit: i64,
+}
+
+impl MongoQuery {
+    /// Get the sort document, or `None` when unsorted.
+    pub fn sort_option(&self) -> Option<Document> {
+        (!self.sort.is_empty()).then(|| self.sort.clone())
+    }
+
+    /// Fetch one extra document so [`Page::from_cursor`](super::Page::from_cursor) can
+    /// tell whether another page exists.
+    pub fn with_lookahead(mut self) -> Self {
+        self.limit += 1;
+        self
+    }
+}
+
+/// Translate a list query into MongoDB `find` arguments.
+pub fn to_mongo_query(query: &ListQuery) -> ChaosResult<MongoQuery> {
+    let mut filter = filter_to_document(&query.filter)?;
+    if let Some(cursor) = query.page.after()? {
+        let position = cursor_to_document(&query.sort, &cursor)?;
+        filter = if filter.is_empty() {
+            position
+        } else {
+            doc! { "$and": [filter, position] }
+        };
+    }
+
+    Ok(MongoQuery {
+        filter,
+        sort: sort_to_document(&query.sort),
+        skip: query.page.skip(),
+        limit: query.page.limit() as i64,
+    })
+}
+
+/// Translate a sort spec into a MongoDB sort document.
+pub fn sort_to_document(sort: &SortSpec) -> Document {
+    let mut document = Document::new();
+    for sort_field in &sort.fields {
+        document.insert(sort_field.field.clone(), direction_value(sort_field.direction));
+    }
+    document
+}
+
+/// Translate a filter spec into a MongoDB filter document.
+pub fn filter_to_document(filter: &FilterSpec) -> ChaosResult<Document> {
+    let mut clauses = Vec::with_capacity(filter.conditions.len());
+    for condition in &filter.conditions {
+        let value = to_bson(&condition.value)?;
+        let predicate = match condition.op {
+            FilterOp::Eq => doc! { "$eq": value },
+            FilterOp::Ne => doc! { "$ne": value },
+            FilterOp::Gt => doc! { "$gt": value },
+            FilterOp::Gte => doc! { "$gte": value },
+            FilterOp::Lt => doc! { "$lt": value },
+            FilterOp::Lte => doc! { "$lte": value },
+            FilterOp::In => doc! { "$in": value },
+            FilterOp::NotIn => doc! { "$nin": value },
+            FilterOp::Contains => {
+                let text = condition.value.as_str().ok_or_else(|| {
+                    ChaosError::Validation(format!("Contains filter on '{}' requires a string", condition.field))
+                })?;
+                doc! { "$regex": regex::escape(text), "$options": "i" }
+            }
+            FilterOp::Exists => doc! { "$exists": value },
+        };
+        let mut clause = Document::new();
+        clause.insert(condition.field.clone(), predicate);
+        clauses.push(clause);
+    }
+
+    Ok(match clauses.len() {
+        0 => Document::new(),
+        1 => clauses.remove(0),
+        _ => doc! { "$and": clauses },
+    })
+}
+
+/// Build a keyset filter selecting documents after a cursor position.
+///
+/// For sort keys `a, b` this is `a > x OR (a = x AND b > y)`, with `<` for
+/// descending keys.
+pub fn cursor_to_document(sort: &SortSpec, cursor: &PageCursor) -> ChaosResult<Document> {
+    if sort.fields.is_empty() || cursor.values.len() != sort.fields.len() {
+        return Err(ChaosError::Validation("Page cursor does not match the sort order".to_string()));
+    }
+
+    let values = cursor.values.iter().map(to_bson).collect::<ChaosResult<Vec<_>>>()?;
+    let mut branches = Vec::with_capacity(sort.fields.len());
+    for (index, sort_field) in sort.fields.iter().enumerate() {
+        let mut branch = Document::new();
+        for (previous, value) in sort.fields[..index].iter().zip(&values) {
+            branch.insert(previous.field.clone(), value.clone());
+        }
+        let op = match sort_field.direction {
+            SortDirection::Asc => "$gt",
+            SortDirection::Desc => "$lt",
+        };
+        let mut predicate = Document::new();
+        predicate.insert(op, values[index].clone());
+        branch.insert(sort_field.field.clone(), predicate);
+        branches.push(branch);
+    }
+
+    Ok(if branches.len() == 1 {
+        branches.remove(0)
+    } else {
+        doc! { "$or": branches }
+    })
+}
+
+fn direction_value(direction: SortDirection) -> i32 {
+    match direction {
+        SortDirection::Asc => 1,
+        SortDirection::Desc => -1,
+    }
+}
+
+fn to_bson(value: &serde_json::Value) -> ChaosResult<Bson> {
+    bson::to_bson(value).map_err(|e| ChaosError::Serialization(e.to_string()))
+}
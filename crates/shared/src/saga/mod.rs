@@ -0,0 +1,324 @@
+//! Sagas: operations spanning several services that must not half-complete.
+//!
+//! A [`Saga`] is a list of [`SagaStep`]s, each with a compensation undoing it. The
+//! [`SagaCoordinator`] runs the steps in order, retrying failures according to the
+//! step's [`RetryPolicy`]; when a step gives up, the steps that completed are compensated
+//! in reverse order. Progress is saved to a [`SagaStore`] after every step, so
+//! [`SagaCoordinator::recover`] finishes (or unwinds) the sagas a crash interrupted.
+//!
+//! Completing a quest is the typical case: the quest is marked complete, its item reward
+//! granted and its experience awarded. If the experience cannot be awarded, the item is
+//! taken back and the quest reopened rather than leaving the player with half a reward.
+//!
+//! Steps run at least once: one interrupted by a crash runs again on recovery, so both
+//! `execute` and `compensate` must be idempotent, typically by keying their writes on the
+//! saga id. A saga runs on one coordinator at a time.
+
+pub mod store;
+
+pub use store::{FileSagaStore, MemorySagaStore, SagaStore};
+
+use crate::clock::SharedClock;
+use crate::error::{ChaosError, ChaosResult};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// How often a step or compensation is tried before the saga gives up on it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay before the first retry, growing linearly with each attempt
+    pub retry_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 3, retry_backoff: Duration::from_millis(200) }
+    }
+}
+
+/// Data shared by the steps of a saga, saved with its progress
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SagaContext {
+    pub saga_id: String,
+    #[serde(default)]
+    pub data: Map<String, Value>,
+}
+
+impl SagaContext {
+    pub fn new(saga_id: impl Into<String>) -> Self {
+        Self { saga_id: saga_id.into(), data: Map::new() }
+    }
+
+    /// Add a value, e.g. what a step created for its compensation to undo
+    pub fn with(mut self, key: &str, value: impl Serialize) -> ChaosResult<Self> {
+        self.set(key, value)?;
+        Ok(self)
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.data.get(key).and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Get a value steps cannot do without
+    pub fn require<T: DeserializeOwned>(&self, key: &str) -> ChaosResult<T> {
+        self.get(key)
+            .ok_or_else(|| ChaosError::Validation(format!("Saga {} is missing {}", self.saga_id, key)))
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Serialize) -> ChaosResult<()> {
+        self.data.insert(key.to_string(), serde_json::to_value(value)?);
+        Ok(())
+    }
+}
+
+/// One step of a saga and how to undo it
+#[async_trait]
+pub trait SagaStep: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Do the step's work; changes to `context` are kept only when it succeeds.
+    /// [`ChaosError::Validation`] errors are not retried.
+    async fn execute(&self, context: &mut SagaContext) -> ChaosResult<()>;
+
+    /// Undo the step after a later one failed
+    async fn compensate(&self, context: &SagaContext) -> ChaosResult<()>;
+
+    /// Retries of this step, the saga's when `None`
+    fn retry(&self) -> Option<RetryPolicy> {
+        None
+    }
+}
+
+/// Steps run in order, compensated in reverse when one fails
+pub struct Saga {
+    name: String,
+    steps: Vec<Arc<dyn SagaStep>>,
+    retry: RetryPolicy,
+}
+
+impl Saga {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), steps: Vec::new(), retry: RetryPolicy::default() }
+    }
+
+    pub fn step(mut self, step: impl SagaStep + 'static) -> Self {
+        self.steps.push(Arc::new(step));
+        self
+    }
+
+    /// Retries of the steps without their own policy
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn steps(&self) -> impl Iterator<Item = &str> {
+        self.steps.iter().map(|step| step.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SagaStatus {
+    /// Steps are being run
+    Running,
+    /// A step failed; completed steps are being undone
+    Compensating,
+    /// Every step completed
+    Completed,
+    /// A step failed and every completed step was undone
+    Compensated,
+    /// A compensation failed too; the saga needs manual attention
+    Failed,
+}
+
+impl SagaStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, SagaStatus::Completed | SagaStatus::Compensated | SagaStatus::Failed)
+    }
+}
+
+/// Progress of a saga
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SagaState {
+    pub saga_id: String,
+    /// Name of the saga definition
+    pub saga: String,
+    pub status: SagaStatus,
+    /// Steps completed, and not compensated yet while compensating
+    pub completed: usize,
+    pub context: SagaContext,
+    /// Why the saga is being, or was, compensated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// Runs sagas and recovers them after a crash
+pub struct SagaCoordinator {
+    store: Arc<dyn SagaStore>,
+    clock: SharedClock,
+    sagas: HashMap<String, Arc<Saga>>,
+}
+
+impl SagaCoordinator {
+    pub fn new(store: Arc<dyn SagaStore>, clock: SharedClock) -> Self {
+        Self { store, clock, sagas: HashMap::new() }
+    }
+
+    /// Make a saga runnable, and recoverable, by name
+    pub fn register(mut self, saga: Saga) -> Self {
+        self.sagas.insert(saga.name.clone(), Arc::new(saga));
+        self
+    }
+
+    /// Run a saga to its end. Starting an id that already exists resumes it instead, so a
+    /// caller retrying a request does not run the saga twice.
+    pub async fn start(&self, saga: &str, context: SagaContext) -> ChaosResult<SagaState> {
+        validate_saga_id(&context.saga_id)?;
+        if let Some(state) = self.store.load(&context.saga_id).await? {
+            return self.resume(state).await;
+        }
+        let definition = self.definition(saga)?;
+        let now = self.clock.now();
+        let state = SagaState {
+            saga_id: context.saga_id.clone(),
+            saga: definition.name.clone(),
+            status: SagaStatus::Running,
+            completed: 0,
+            context,
+            error: None,
+            started_at: now,
+            updated_at: now,
+        };
+        self.store.save(&state).await?;
+        self.drive(&definition, state).await
+    }
+
+    /// Continue a saga from its saved progress
+    pub async fn resume(&self, state: SagaState) -> ChaosResult<SagaState> {
+        if state.status.is_finished() {
+            return Ok(state);
+        }
+        let definition = self.definition(&state.saga)?;
+        self.drive(&definition, state).await
+    }
+
+    /// Finish every saga a crash interrupted, returning where each ended
+    pub async fn recover(&self) -> ChaosResult<Vec<SagaState>> {
+        let mut recovered = Vec::new();
+        for state in self.store.unfinished().await? {
+            info!(saga = %state.saga, saga_id = %state.saga_id, "Recovering saga");
+            match self.resume(state).await {
+                Ok(state) => recovered.push(state),
+                Err(e) => warn!("Failed to recover a saga: {}", e),
+            }
+        }
+        Ok(recovered)
+    }
+
+    async fn drive(&self, saga: &Saga, mut state: SagaState) -> ChaosResult<SagaState> {
+        loop {
+            match state.status {
+                SagaStatus::Running if state.completed >= saga.steps.len() => {
+                    state.status = SagaStatus::Completed;
+                }
+                SagaStatus::Running => {
+                    let step = &saga.steps[state.completed];
+                    let mut context = state.context.clone();
+                    let retry = step.retry().unwrap_or(saga.retry);
+                    match attempt(step.as_ref(), Action::Execute, retry, &mut context).await {
+                        Ok(()) => {
+                            state.context = context;
+                            state.completed += 1;
+                        }
+                        Err(e) => {
+                            warn!(saga_id = %state.saga_id, step = step.name(), "Saga step failed, compensating: {}", e);
+                            state.status = SagaStatus::Compensating;
+                            state.error = Some(format!("{}: {}", step.name(), e));
+                        }
+                    }
+                }
+                SagaStatus::Compensating if state.completed == 0 => {
+                    state.status = SagaStatus::Compensated;
+                }
+                SagaStatus::Compensating => {
+                    let step = &saga.steps[state.completed - 1];
+                    let retry = step.retry().unwrap_or(saga.retry);
+                    let mut context = state.context.clone();
+                    match attempt(step.as_ref(), Action::Compensate, retry, &mut context).await {
+                        Ok(()) => state.completed -= 1,
+                        Err(e) => {
+                            warn!(saga_id = %state.saga_id, step = step.name(), "Saga compensation failed: {}", e);
+                            state.status = SagaStatus::Failed;
+                            let cause = state.error.take().unwrap_or_default();
+                            state.error = Some(format!("{}; compensating {}: {}", cause, step.name(), e));
+                        }
+                    }
+                }
+                SagaStatus::Completed | SagaStatus::Compensated | SagaStatus::Failed => return Ok(state),
+            }
+            state.updated_at = self.clock.now();
+            self.store.save(&state).await?;
+        }
+    }
+
+    fn definition(&self, saga: &str) -> ChaosResult<Arc<Saga>> {
+        self.sagas
+            .get(saga)
+            .cloned()
+            .ok_or_else(|| ChaosError::Configuration(format!("Unknown saga {}", saga)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Action {
+    Execute,
+    Compensate,
+}
+
+/// Run a step until it succeeds, fails for good or runs out of attempts
+async fn attempt(step: &dyn SagaStep, action: Action, retry: RetryPolicy, context: &mut SagaContext) -> ChaosResult<()> {
+    let mut attempt = 1;
+    loop {
+        let result = match action {
+            Action::Execute => step.execute(context).await,
+            Action::Compensate => step.compensate(context).await,
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e @ ChaosError::Validation(_)) => return Err(e),
+            Err(e) if attempt >= retry.max_attempts.max(1) => return Err(e),
+            Err(e) => {
+                warn!("Attempt {} of {} failed, retrying: {}", attempt, retry.max_attempts, e);
+                tokio::time::sleep(retry.retry_backoff * attempt).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Saga ids name files and keys, so they are kept to `[A-Za-z0-9._-]`
+fn validate_saga_id(saga_id: &str) -> ChaosResult<()> {
+    let valid = !saga_id.is_empty()
+        && !saga_id.starts_with('.')
+        && saga_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ChaosError::Validation(format!("Invalid saga id: {:?}", saga_id)))
+    }
+}
@@ -0,0 +1,103 @@
+//! Where saga progress is kept between steps.
+
+use super::SagaState;
+use crate::error::ChaosResult;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+/// Saved progress of sagas, keyed by saga id
+#[async_trait]
+pub trait SagaStore: Send + Sync {
+    async fn save(&self, state: &SagaState) -> ChaosResult<()>;
+
+    async fn load(&self, saga_id: &str) -> ChaosResult<Option<SagaState>>;
+
+    /// Sagas still running or compensating
+    async fn unfinished(&self) -> ChaosResult<Vec<SagaState>>;
+}
+
+/// Saga progress that lives as long as the process
+#[derive(Debug, Default)]
+pub struct MemorySagaStore {
+    states: Mutex<HashMap<String, SagaState>>,
+}
+
+impl MemorySagaStore {
+    fn states(&self) -> MutexGuard<'_, HashMap<String, SagaState>> {
+        self.states.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl SagaStore for MemorySagaStore {
+    async fn save(&self, state: &SagaState) -> ChaosResult<()> {
+        self.states().insert(state.saga_id.clone(), state.clone());
+        Ok(())
+    }
+
+    async fn load(&self, saga_id: &str) -> ChaosResult<Option<SagaState>> {
+        Ok(self.states().get(saga_id).cloned())
+    }
+
+    async fn unfinished(&self) -> ChaosResult<Vec<SagaState>> {
+        Ok(self.states().values().filter(|state| !state.status.is_finished()).cloned().collect())
+    }
+}
+
+/// Saga progress kept in a directory, one JSON file per saga
+#[derive(Debug)]
+pub struct FileSagaStore {
+    dir: PathBuf,
+}
+
+impl FileSagaStore {
+    /// Open a saga directory, creating it when it does not exist yet.
+    pub fn open(dir: impl AsRef<Path>) -> ChaosResult<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, saga_id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", saga_id))
+    }
+}
+
+#[async_trait]
+impl SagaStore for FileSagaStore {
+    async fn save(&self, state: &SagaState) -> ChaosResult<()> {
+        // Write then rename so a crash never leaves a truncated file
+        let path = self.path(&state.saga_id);
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, serde_json::to_vec_pretty(state)?).await?;
+        tokio::fs::rename(&temporary, &path).await?;
+        Ok(())
+    }
+
+    async fn load(&self, saga_id: &str) -> ChaosResult<Option<SagaState>> {
+        match tokio::fs::read(self.path(saga_id)).await {
+            Ok(content) => Ok(Some(serde_json::from_slice(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn unfinished(&self) -> ChaosResult<Vec<SagaState>> {
+        let mut states = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("json") {
+                continue;
+            }
+            let state: SagaState = serde_json::from_slice(&tokio::fs::read(&path).await?)?;
+            if !state.status.is_finished() {
+                states.push(state);
+            }
+        }
+        states.sort_by_key(|state| state.started_at);
+        Ok(states)
+    }
+}
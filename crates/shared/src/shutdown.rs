@@ -0,0 +1,235 @@
+//! Coordinated graceful shutdown.
+//!
+//! A [`Shutdown`] is triggered once, by Ctrl-C, SIGTERM or [`Shutdown::trigger`]. From
+//! then on:
+//! - readiness probes of a [`HealthRegistry`](crate::health::HealthRegistry) following
+//!   it fail, so load balancers stop routing to the instance;
+//! - servers stop accepting connections, e.g. through
+//!   `axum::serve(..).with_graceful_shutdown(shutdown.signal())`;
+//! - in-flight work gets until a common deadline to finish. Servers are drained with
+//!   [`Shutdown::drain`]; long-lived work the server does not track, like upgraded
+//!   WebSocket connections or background writes, holds an [`InFlight`] guard and is
+//!   awaited with [`Shutdown::wait_idle`].
+//!
+//! What is left after the deadline is cut off, so a stuck client cannot keep the
+//! process alive. Services flush their own state (database writes, bus messages)
+//! after draining.
+
+use std::future::{Future, IntoFuture};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// How long in-flight work may take by default once shutdown starts
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Environment variable overriding the drain timeout, in seconds
+pub const DRAIN_TIMEOUT_ENV: &str = "SHUTDOWN_DRAIN_SECS";
+
+/// How long outstanding MongoDB operations may take once a service has drained, before
+/// its client is shut down anyway
+pub const MONGODB_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Shutdown coordinator shared by everything that has to stop cleanly.
+#[derive(Clone)]
+pub struct Shutdown {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Drain deadline, set when shutdown is triggered
+    deadline: watch::Sender<Option<Instant>>,
+    drain_timeout: Duration,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+impl Shutdown {
+    /// Create a coordinator giving in-flight work `drain_timeout` to finish.
+    pub fn new(drain_timeout: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                deadline: watch::channel(None).0,
+                drain_timeout,
+                in_flight: AtomicUsize::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    /// Create a coordinator with the drain timeout from `SHUTDOWN_DRAIN_SECS`.
+    pub fn from_env() -> Self {
+        let drain_timeout = std::env::var(DRAIN_TIMEOUT_ENV)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+        Self::new(drain_timeout)
+    }
+
+    pub fn drain_timeout(&self) -> Duration {
+        self.inner.drain_timeout
+    }
+
+    /// Trigger shutdown on Ctrl-C or SIGTERM.
+    pub fn listen_for_signals(&self) -> JoinHandle<()> {
+        let shutdown = self.clone();
+        tokio::spawn(async move {
+            let ctrl_c = async {
+                if let Err(e) = tokio::signal::ctrl_c().await {
+                    warn!("Cannot listen for Ctrl-C: {}", e);
+                    std::future::pending::<()>().await;
+                }
+            };
+            #[cfg(unix)]
+            let terminate = async {
+                match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+                    Ok(mut signal) => {
+                        signal.recv().await;
+                    }
+                    Err(e) => {
+                        warn!("Cannot listen for SIGTERM: {}", e);
+                        std::future::pending::<()>().await;
+                    }
+                }
+            };
+            #[cfg(not(unix))]
+            let terminate = std::future::pending::<()>();
+
+            tokio::select! {
+                _ = ctrl_c => {}
+                _ = terminate => {}
+            }
+            shutdown.trigger();
+        })
+    }
+
+    /// Start shutting down; later calls keep the first deadline.
+    pub fn trigger(&self) {
+        let deadline = Instant::now() + self.inner.drain_timeout;
+        let started = self.inner.deadline.send_if_modified(|current| {
+            if current.is_some() {
+                return false;
+            }
+            *current = Some(deadline);
+            true
+        });
+        if started {
+            info!(
+                in_flight = self.in_flight(),
+                "Shutting down, draining for up to {:?}", self.inner.drain_timeout
+            );
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.inner.deadline.borrow().is_some()
+    }
+
+    /// Resolves once shutdown is triggered, e.g. to stop accepting connections.
+    pub fn signal(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut deadline = self.inner.deadline.subscribe();
+        async move {
+            // The sender lives as long as any handle, so this only fails when nobody can trigger
+            let _ = deadline.wait_for(Option::is_some).await;
+        }
+    }
+
+    /// Count work as in flight until the guard is dropped.
+    pub fn track(&self) -> InFlight {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlight { inner: self.inner.clone() }
+    }
+
+    /// Tracked work still running
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Run `work`, cutting it off at the drain deadline once shutdown is triggered.
+    ///
+    /// `None` when the deadline passed first.
+    pub async fn drain<F: IntoFuture>(&self, work: F) -> Option<F::Output> {
+        let work = work.into_future();
+        tokio::pin!(work);
+        tokio::select! {
+            output = &mut work => return Some(output),
+            _ = self.signal() => {}
+        }
+        tokio::select! {
+            output = &mut work => Some(output),
+            _ = tokio::time::sleep_until(self.deadline()) => {
+                warn!("Drain deadline passed, cutting off in-flight work");
+                None
+            }
+        }
+    }
+
+    /// Wait for tracked work to finish, up to the drain deadline.
+    ///
+    /// `false` when work was still running at the deadline.
+    pub async fn wait_idle(&self) -> bool {
+        let deadline = self.deadline();
+        loop {
+            let idle = self.inner.idle.notified();
+            tokio::pin!(idle);
+            idle.as_mut().enable();
+            let in_flight = self.in_flight();
+            if in_flight == 0 {
+                return true;
+            }
+            tokio::select! {
+                _ = &mut idle => {}
+                _ = tokio::time::sleep_until(deadline) => {
+                    warn!(in_flight, "Drain deadline passed with work in flight");
+                    return false;
+                }
+            }
+        }
+    }
+
+    /// Drain deadline, or a full drain timeout from now when shutdown was not triggered
+    fn deadline(&self) -> Instant {
+        let deadline = *self.inner.deadline.borrow();
+        deadline.unwrap_or_else(|| Instant::now() + self.inner.drain_timeout)
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new(DEFAULT_DRAIN_TIMEOUT)
+    }
+}
+
+impl std::fmt::Debug for Shutdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Shutdown")
+            .field("triggered", &self.is_triggered())
+            .field("in_flight", &self.in_flight())
+            .field("drain_timeout", &self.inner.drain_timeout)
+            .finish()
+    }
+}
+
+/// Work shutdown waits for, released on drop.
+pub struct InFlight {
+    inner: Arc<Inner>,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl std::fmt::Debug for InFlight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InFlight").finish_non_exhaustive()
+    }
+}
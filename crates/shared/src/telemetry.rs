@@ -0,0 +1,265 @@
+//! Log output and, with the `otel` feature, OpenTelemetry export.
+//!
+//! [`init`] installs the service's subscriber: log lines filtered by `RUST_LOG` and, when
+//! the `otel` feature is enabled and `OTEL_EXPORTER_OTLP_ENDPOINT` is set, spans and
+//! metrics exported over OTLP with the service's resource attributes. Core crates mark
+//! their hot paths (actor resolution, element aggregation, condition evaluation) with
+//! spans behind their own `otel` features, so library users without a collector
+//! compile none of it.
+//!
+//! The exported spans continue the W3C trace context the backend already propagates
+//! (see [`trace`](crate::trace)): [`export_span`] parents a span on an incoming
+//! `traceparent` and returns the context to forward downstream.
+
+use crate::trace::TraceContext;
+use std::time::Duration;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Collector endpoint; nothing is exported when it is unset
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Deployment environment resource attribute, e.g. `production`
+pub const ENVIRONMENT_ENV: &str = "DEPLOYMENT_ENVIRONMENT";
+
+/// Instance resource attribute, the host name when unset
+pub const INSTANCE_ENV: &str = "SERVICE_INSTANCE_ID";
+
+/// Layer added to a service's subscriber
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync + 'static>;
+
+/// Who is reporting, and where to
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryOptions {
+    pub service_name: String,
+    pub service_version: String,
+    pub instance_id: Option<String>,
+    pub environment: Option<String>,
+    /// OTLP gRPC endpoint, e.g. `http://otel-collector:4317`
+    pub otlp_endpoint: Option<String>,
+    /// Filter used when `RUST_LOG` is unset
+    pub default_filter: String,
+}
+
+impl TelemetryOptions {
+    pub fn new(service_name: impl Into<String>, service_version: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            service_version: service_version.into(),
+            instance_id: None,
+            environment: None,
+            otlp_endpoint: None,
+            default_filter: "info".to_string(),
+        }
+    }
+
+    /// Options of a service, completed from the environment
+    pub fn from_env(service_name: impl Into<String>, service_version: impl Into<String>) -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            instance_id: env(INSTANCE_ENV).or_else(|| env("HOSTNAME")),
+            environment: env(ENVIRONMENT_ENV),
+            otlp_endpoint: env(OTLP_ENDPOINT_ENV),
+            ..Self::new(service_name, service_version)
+        }
+    }
+
+    pub fn with_default_filter(mut self, filter: impl Into<String>) -> Self {
+        self.default_filter = filter.into();
+        self
+    }
+
+    /// OpenTelemetry resource attributes describing the service
+    pub fn resource_attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attributes = vec![
+            ("service.name", self.service_name.clone()),
+            ("service.version", self.service_version.clone()),
+            ("service.namespace", "chaos-world".to_string()),
+        ];
+        if let Some(instance_id) = &self.instance_id {
+            attributes.push(("service.instance.id", instance_id.clone()));
+        }
+        if let Some(environment) = &self.environment {
+            attributes.push(("deployment.environment", environment.clone()));
+        }
+        attributes
+    }
+}
+
+/// Installed telemetry; [`Telemetry::shutdown`] flushes what is still buffered
+pub struct Telemetry {
+    #[cfg(feature = "otel")]
+    meter_provider: Option<opentelemetry_sdk::metrics::MeterProvider>,
+}
+
+impl Telemetry {
+    /// Whether spans and metrics are exported
+    pub fn is_exporting(&self) -> bool {
+        #[cfg(feature = "otel")]
+        {
+            self.meter_provider.is_some()
+        }
+        #[cfg(not(feature = "otel"))]
+        {
+            false
+        }
+    }
+
+    /// Export buffered spans and metrics; call once the service has drained
+    pub fn shutdown(self) {
+        #[cfg(feature = "otel")]
+        if let Some(meter_provider) = self.meter_provider {
+            if let Err(e) = meter_provider.shutdown() {
+                tracing::warn!("Failed to flush metrics: {}", e);
+            }
+            opentelemetry::global::shutdown_tracer_provider();
+        }
+    }
+}
+
+/// Install the global subscriber: logs on stdout plus OTLP export when configured
+pub fn init(options: &TelemetryOptions) -> Telemetry {
+    init_with(options, Vec::new())
+}
+
+/// [`init`] with extra layers, e.g. a log file
+pub fn init_with(options: &TelemetryOptions, mut layers: Vec<BoxedLayer>) -> Telemetry {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(&options.default_filter));
+    layers.push(Box::new(tracing_subscriber::fmt::layer()));
+
+    #[cfg(feature = "otel")]
+    let (telemetry, export_error) = match otel::install(options) {
+        Ok(Some((layer, meter_provider))) => {
+            layers.push(layer);
+            (Telemetry { meter_provider: Some(meter_provider) }, None)
+        }
+        Ok(None) => (Telemetry { meter_provider: None }, None),
+        Err(e) => (Telemetry { meter_provider: None }, Some(e)),
+    };
+    #[cfg(not(feature = "otel"))]
+    let telemetry = Telemetry {};
+
+    tracing_subscriber::registry().with(layers).with(filter).init();
+
+    #[cfg(feature = "otel")]
+    if let Some(e) = export_error {
+        tracing::warn!("OpenTelemetry export disabled: {}", e);
+    }
+    if telemetry.is_exporting() {
+        tracing::info!(
+            service = %options.service_name,
+            endpoint = options.otlp_endpoint.as_deref().unwrap_or_default(),
+            "Exporting spans and metrics over OTLP"
+        );
+    } else if options.otlp_endpoint.is_some() && !cfg!(feature = "otel") {
+        tracing::warn!("{} is set but this build has no OpenTelemetry support", OTLP_ENDPOINT_ENV);
+    }
+    telemetry
+}
+
+/// Parent `span` on an incoming trace and return the context to forward downstream,
+/// `None` when spans are not exported
+pub fn export_span(span: &tracing::Span, parent: Option<&TraceContext>) -> Option<TraceContext> {
+    #[cfg(feature = "otel")]
+    {
+        otel::export_span(span, parent)
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = (span, parent);
+        None
+    }
+}
+
+/// Record how long an operation took, in seconds, on a histogram named `name`
+pub fn record_duration(name: &'static str, elapsed: Duration, attributes: &[(&'static str, String)]) {
+    #[cfg(feature = "otel")]
+    otel::record_duration(name, elapsed, attributes);
+    #[cfg(not(feature = "otel"))]
+    let _ = (name, elapsed, attributes);
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::{BoxedLayer, TelemetryOptions};
+    use crate::error::{ChaosError, ChaosResult};
+    use crate::trace::TraceContext;
+    use opentelemetry::metrics::{Histogram, Unit};
+    use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId, TraceState};
+    use opentelemetry::{global, KeyValue};
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::metrics::MeterProvider;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+    use std::collections::HashMap;
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    /// Meter the shared histograms are created on
+    const METER: &str = "chaos-world";
+
+    pub(super) fn install(options: &TelemetryOptions) -> ChaosResult<Option<(BoxedLayer, MeterProvider)>> {
+        let Some(endpoint) = &options.otlp_endpoint else {
+            return Ok(None);
+        };
+        let resource = Resource::new(
+            options
+                .resource_attributes()
+                .into_iter()
+                .map(|(key, value)| KeyValue::new(key, value)),
+        );
+
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+            .with_trace_config(trace::config().with_resource(resource.clone()))
+            .install_batch(runtime::Tokio)
+            .map_err(|e| ChaosError::ExternalService(format!("OTLP trace exporter: {}", e)))?;
+        let meter_provider = opentelemetry_otlp::new_pipeline()
+            .metrics(runtime::Tokio)
+            .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+            .with_resource(resource)
+            .build()
+            .map_err(|e| ChaosError::ExternalService(format!("OTLP metrics exporter: {}", e)))?;
+
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        global::set_meter_provider(meter_provider.clone());
+        Ok(Some((Box::new(tracing_opentelemetry::layer().with_tracer(tracer)), meter_provider)))
+    }
+
+    pub(super) fn export_span(span: &tracing::Span, parent: Option<&TraceContext>) -> Option<TraceContext> {
+        if let Some(parent) = parent {
+            let remote = SpanContext::new(
+                TraceId::from_hex(&parent.trace_id).ok()?,
+                SpanId::from_hex(&parent.span_id).ok()?,
+                TraceFlags::new(parent.flags),
+                true,
+                TraceState::default(),
+            );
+            span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote));
+        }
+        let context = span.context();
+        let exported = context.span().span_context().clone();
+        exported.is_valid().then(|| TraceContext {
+            trace_id: exported.trace_id().to_string(),
+            span_id: exported.span_id().to_string(),
+            flags: exported.trace_flags().to_u8(),
+        })
+    }
+
+    pub(super) fn record_duration(name: &'static str, elapsed: Duration, attributes: &[(&'static str, String)]) {
+        static HISTOGRAMS: OnceLock<Mutex<HashMap<&'static str, Histogram<f64>>>> = OnceLock::new();
+        let histogram = HISTOGRAMS
+            .get_or_init(Default::default)
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(name)
+            .or_insert_with(|| global::meter(METER).f64_histogram(name).with_unit(Unit::new("s")).init())
+            .clone();
+        let attributes: Vec<KeyValue> =
+            attributes.iter().map(|(key, value)| KeyValue::new(*key, value.clone())).collect();
+        histogram.record(elapsed.as_secs_f64(), &attributes);
+    }
+}
@@ -0,0 +1,134 @@
+//! Request IDs and W3C trace context propagation.
+//!
+//! Every request entering the backend carries an `X-Request-Id` and a W3C
+//! `traceparent` header. The edge (the API gateway) generates them when a client did
+//! not send any, each hop creates a child [`TraceContext`] for its own span, and both
+//! values are passed on to downstream services and logged, so one player action can
+//! be followed across services.
+
+use uuid::Uuid;
+
+/// Header carrying the request ID.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// W3C trace context header.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// W3C vendor-specific trace state header, forwarded unchanged.
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Longest client-supplied request ID that is accepted.
+pub const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Only supported `traceparent` version.
+const TRACEPARENT_VERSION: &str = "00";
+
+/// `sampled` bit of the trace flags.
+const FLAG_SAMPLED: u8 = 0x01;
+
+/// Generate a new request ID.
+pub fn new_request_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// Check whether a client-supplied request ID can be reused.
+///
+/// IDs end up in logs and headers, so only short, printable ASCII values are kept.
+pub fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|byte| byte.is_ascii_graphic())
+}
+
+/// Reuse a valid incoming request ID or generate a new one.
+pub fn request_id_or_new(incoming: Option<&str>) -> String {
+    match incoming {
+        Some(id) if is_valid_request_id(id) => id.to_string(),
+        _ => new_request_id(),
+    }
+}
+
+/// W3C trace context of one span.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits shared by every span of the trace.
+    pub trace_id: String,
+    /// 16 lowercase hex digits identifying this span.
+    pub span_id: String,
+    /// Trace flags (bit 0: sampled).
+    pub flags: u8,
+}
+
+impl TraceContext {
+    /// Start a new sampled trace.
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_hex(32),
+            span_id: random_hex(16),
+            flags: FLAG_SAMPLED,
+        }
+    }
+
+    /// Parse a `traceparent` header value.
+    ///
+    /// Returns `None` for unsupported versions and malformed or all-zero IDs.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        if version != TRACEPARENT_VERSION || parts.next().is_some() {
+            return None;
+        }
+        if !is_hex_id(trace_id, 32) || !is_hex_id(span_id, 16) || flags.len() != 2 {
+            return None;
+        }
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            flags: u8::from_str_radix(flags, 16).ok()?,
+        })
+    }
+
+    /// Continue an incoming trace or start a new one.
+    ///
+    /// The returned context is a new span whose parent is the incoming span.
+    pub fn continue_or_new(traceparent: Option<&str>) -> Self {
+        traceparent
+            .and_then(Self::parse)
+            .map(|parent| parent.child())
+            .unwrap_or_else(Self::new_root)
+    }
+
+    /// Create a child span in the same trace.
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: random_hex(16),
+            flags: self.flags,
+        }
+    }
+
+    /// Check whether the trace is sampled.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & FLAG_SAMPLED != 0
+    }
+
+    /// Format as a `traceparent` header value.
+    pub fn to_traceparent(&self) -> String {
+        format!("{}-{}-{}-{:02x}", TRACEPARENT_VERSION, self.trace_id, self.span_id, self.flags)
+    }
+}
+
+/// Check a lowercase hex ID of the given length that is not all zeros.
+fn is_hex_id(id: &str, len: usize) -> bool {
+    id.len() == len
+        && id.bytes().all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+        && id.bytes().any(|byte| byte != b'0')
+}
+
+/// Random lowercase hex string of up to 32 digits.
+fn random_hex(len: usize) -> String {
+    let mut hex = Uuid::new_v4().simple().to_string();
+    hex.truncate(len);
+    hex
+}
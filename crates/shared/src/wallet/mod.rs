@@ -0,0 +1,369 @@
+//! Actor wallets holding several currencies.
+//!
+//! Currencies are defined in [`WalletConfig`] (gold, honor, seasonal tokens, ...), each
+//! with an optional cap. [`Wallets`] earns, spends and exchanges them; every operation
+//! changes one actor's balances all at once or not at all, and appends a [`LedgerEntry`]
+//! per currency it touched. The ledger is what support and anti-cheat read when a
+//! balance looks wrong, searched with the shared [`ListQuery`] over [`LEDGER_FIELDS`].
+//!
+//! Earnings beyond a currency's cap are dropped, as players expect from capped
+//! currencies; what was actually credited is recorded. Exchanges follow the configured
+//! [`ExchangeRule`]s and refuse to convert into a currency the actor cannot hold.
+//!
+//! Wallets are versioned: a commit only applies on top of the version it was computed
+//! from, and conflicting commits (another instance changing the same actor) are
+//! recomputed from the new balances. Committed changes are announced on
+//! [`CURRENCY_CHANGED`] for economy analytics.
+
+pub mod store;
+
+pub use store::{MemoryWalletStore, WalletStore};
+
+use crate::clock::SharedClock;
+use crate::error::{ChaosError, ChaosResult};
+use crate::events::topics::{CurrencyChanged, CURRENCY_CHANGED};
+use crate::events::{EventBus, EventBusExt};
+use crate::market;
+use crate::query::{ListQuery, Page};
+use crate::types::Timestamp;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Fields ledger entries can be filtered and sorted by
+pub const LEDGER_FIELDS: &[&str] = &["actor_id", "currency", "kind", "reason", "reference", "delta", "at"];
+
+/// Times an operation is recomputed after losing a commit race
+const MAX_COMMIT_ATTEMPTS: usize = 5;
+
+/// A currency actors can hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurrencyDef {
+    pub id: String,
+    pub name: String,
+    /// Most an actor may hold; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cap: Option<u64>,
+}
+
+/// Converts `from_amount` of one currency into `to_amount` of another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExchangeRule {
+    pub from: String,
+    pub to: String,
+    pub from_amount: u64,
+    pub to_amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WalletConfig {
+    pub currencies: Vec<CurrencyDef>,
+    pub exchanges: Vec<ExchangeRule>,
+}
+
+impl Default for WalletConfig {
+    fn default() -> Self {
+        let currency =
+            |id: &str, name: &str, cap: Option<u64>| CurrencyDef { id: id.to_string(), name: name.to_string(), cap };
+        Self {
+            currencies: vec![
+                currency("gold", "Gold", Some(9_999_999_999)),
+                currency("honor", "Honor", Some(75_000)),
+                currency("season_token", "Season Token", Some(5_000)),
+            ],
+            exchanges: vec![ExchangeRule {
+                from: "honor".to_string(),
+                to: "gold".to_string(),
+                from_amount: 10,
+                to_amount: 1,
+            }],
+        }
+    }
+}
+
+impl WalletConfig {
+    pub fn currency(&self, currency: &str) -> Option<&CurrencyDef> {
+        self.currencies.iter().find(|def| def.id == currency)
+    }
+
+    pub fn exchange(&self, from: &str, to: &str) -> Option<&ExchangeRule> {
+        self.exchanges.iter().find(|rule| rule.from == from && rule.to == to)
+    }
+
+    /// Reject duplicate currencies and exchanges that could never apply
+    pub fn validate(&self) -> ChaosResult<()> {
+        let mut ids = std::collections::HashSet::new();
+        for def in &self.currencies {
+            if !ids.insert(def.id.as_str()) {
+                return Err(ChaosError::Configuration(format!("Duplicate currency: {}", def.id)));
+            }
+        }
+        for rule in &self.exchanges {
+            if self.currency(&rule.from).is_none() || self.currency(&rule.to).is_none() || rule.from == rule.to {
+                return Err(ChaosError::Configuration(format!("Invalid exchange from {} to {}", rule.from, rule.to)));
+            }
+            if rule.from_amount == 0 || rule.to_amount == 0 {
+                let message = format!("Exchange from {} to {} needs non-zero amounts", rule.from, rule.to);
+                return Err(ChaosError::Configuration(message));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An actor's balances
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActorWallet {
+    pub actor_id: String,
+    /// Balance per currency; currencies never held are absent
+    pub balances: BTreeMap<String, u64>,
+    /// Commits applied so far
+    pub version: u64,
+}
+
+impl ActorWallet {
+    pub fn new(actor_id: impl Into<String>) -> Self {
+        Self { actor_id: actor_id.into(), ..Self::default() }
+    }
+
+    pub fn balance(&self, currency: &str) -> u64 {
+        self.balances.get(currency).copied().unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionKind {
+    Earn,
+    Spend,
+    Exchange,
+}
+
+/// One currency changing in one operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub id: String,
+    /// Shared by the entries of one operation
+    pub transaction_id: String,
+    pub actor_id: String,
+    pub currency: String,
+    /// Amount credited (positive) or debited (negative)
+    pub delta: i64,
+    /// Balance after the change
+    pub balance: u64,
+    pub kind: TransactionKind,
+    /// What the currency was earned or spent on, e.g. `quest:1042` or `market:bid`
+    pub reason: String,
+    /// Caller's identifier for the operation, e.g. a listing ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    pub at: Timestamp,
+}
+
+/// Earns, spends and exchanges currencies
+pub struct Wallets {
+    config: WalletConfig,
+    clock: SharedClock,
+    store: Arc<dyn WalletStore>,
+    bus: Option<(Arc<dyn EventBus>, String)>,
+}
+
+impl Wallets {
+    pub fn new(config: WalletConfig, clock: SharedClock, store: Arc<dyn WalletStore>) -> Self {
+        Self { config, clock, store, bus: None }
+    }
+
+    /// Publish balance changes on `bus`, tagged with `source`
+    pub fn with_bus(mut self, bus: Arc<dyn EventBus>, source: impl Into<String>) -> Self {
+        self.bus = Some((bus, source.into()));
+        self
+    }
+
+    pub fn config(&self) -> &WalletConfig {
+        &self.config
+    }
+
+    pub async fn wallet(&self, actor_id: &str) -> ChaosResult<ActorWallet> {
+        Ok(self.store.load(actor_id).await?.unwrap_or_else(|| ActorWallet::new(actor_id)))
+    }
+
+    pub async fn balance(&self, actor_id: &str, currency: &str) -> ChaosResult<u64> {
+        self.currency(currency)?;
+        Ok(self.wallet(actor_id).await?.balance(currency))
+    }
+
+    /// Credit up to `amount`, less whatever would exceed the cap
+    pub async fn earn(
+        &self,
+        actor_id: &str,
+        currency: &str,
+        amount: u64,
+        reason: &str,
+        reference: Option<&str>,
+    ) -> ChaosResult<LedgerEntry> {
+        let cap = self.currency(currency)?.cap;
+        let mut entries = self
+            .commit(actor_id, TransactionKind::Earn, reason, reference, |wallet| {
+                let balance = wallet.balance(currency);
+                let credited = amount.min(cap.unwrap_or(u64::MAX).saturating_sub(balance));
+                Ok(vec![(currency.to_string(), signed(currency, credited)?)])
+            })
+            .await?;
+        Ok(entries.remove(0))
+    }
+
+    /// Debit `amount`; fails when the balance is short
+    pub async fn spend(
+        &self,
+        actor_id: &str,
+        currency: &str,
+        amount: u64,
+        reason: &str,
+        reference: Option<&str>,
+    ) -> ChaosResult<LedgerEntry> {
+        self.currency(currency)?;
+        let mut entries = self
+            .commit(actor_id, TransactionKind::Spend, reason, reference, |wallet| {
+                if wallet.balance(currency) < amount {
+                    return Err(ChaosError::Validation(format!("Not enough {} to spend {}", currency, amount)));
+                }
+                Ok(vec![(currency.to_string(), -signed(currency, amount)?)])
+            })
+            .await?;
+        Ok(entries.remove(0))
+    }
+
+    /// Convert `amount` of `from` into `to` at the configured rate; `amount` must be a
+    /// multiple of the rule's `from_amount`
+    pub async fn exchange(&self, actor_id: &str, from: &str, to: &str, amount: u64) -> ChaosResult<Vec<LedgerEntry>> {
+        let rule = self
+            .config
+            .exchange(from, to)
+            .ok_or_else(|| ChaosError::Validation(format!("{} cannot be exchanged for {}", from, to)))?;
+        if amount == 0 || !amount.is_multiple_of(rule.from_amount) {
+            return Err(ChaosError::Validation(format!("{} is exchanged in lots of {}", from, rule.from_amount)));
+        }
+        let received = (amount / rule.from_amount)
+            .checked_mul(rule.to_amount)
+            .ok_or_else(|| ChaosError::Validation(format!("Exchanging {} {} overflows", amount, from)))?;
+        let cap = self.currency(to)?.cap;
+        let reason = format!("exchange:{}:{}", from, to);
+        self.commit(actor_id, TransactionKind::Exchange, &reason, None, |wallet| {
+            if wallet.balance(from) < amount {
+                return Err(ChaosError::Validation(format!("Not enough {} to exchange {}", from, amount)));
+            }
+            if cap.is_some_and(|cap| wallet.balance(to).saturating_add(received) > cap) {
+                return Err(ChaosError::Validation(format!("Exchanging would exceed the {} cap", to)));
+            }
+            Ok(vec![(from.to_string(), -signed(from, amount)?), (to.to_string(), signed(to, received)?)])
+        })
+        .await
+    }
+
+    /// Search the ledger, e.g. one actor's entries newest first
+    pub async fn ledger(&self, query: &ListQuery) -> ChaosResult<Page<LedgerEntry>> {
+        query.filter.validate_fields(LEDGER_FIELDS)?;
+        query.sort.validate_fields(LEDGER_FIELDS)?;
+        self.store.ledger(query).await
+    }
+
+    fn currency(&self, currency: &str) -> ChaosResult<&CurrencyDef> {
+        self.config.currency(currency).ok_or_else(|| ChaosError::Validation(format!("Unknown currency: {}", currency)))
+    }
+
+    /// Apply the balance changes `plan` computes from the current wallet, retrying on
+    /// conflicting commits
+    async fn commit(
+        &self,
+        actor_id: &str,
+        kind: TransactionKind,
+        reason: &str,
+        reference: Option<&str>,
+        plan: impl Fn(&ActorWallet) -> ChaosResult<Vec<(String, i64)>>,
+    ) -> ChaosResult<Vec<LedgerEntry>> {
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            let current = self.wallet(actor_id).await?;
+            let changes = plan(&current)?;
+
+            let mut wallet = current.clone();
+            wallet.version += 1;
+            let transaction_id = Uuid::new_v4().to_string();
+            let at = self.clock.now();
+            let mut entries = Vec::with_capacity(changes.len());
+            for (currency, delta) in changes {
+                let balance = wallet.balance(&currency).checked_add_signed(delta).ok_or_else(|| {
+                    ChaosError::Validation(format!("{} {} would overflow the balance", delta, currency))
+                })?;
+                wallet.balances.insert(currency.clone(), balance);
+                entries.push(LedgerEntry {
+                    id: Uuid::new_v4().to_string(),
+                    transaction_id: transaction_id.clone(),
+                    actor_id: actor_id.to_string(),
+                    currency,
+                    delta,
+                    balance,
+                    kind,
+                    reason: reason.to_string(),
+                    reference: reference.map(str::to_string),
+                    at,
+                });
+            }
+
+            if self.store.commit(current.version, &wallet, &entries).await? {
+                info!(actor_id, transaction_id = %transaction_id, ?kind, reason, "Wallet updated");
+                self.publish(&entries).await;
+                return Ok(entries);
+            }
+        }
+        Err(ChaosError::Internal(format!("Wallet of {} is changing too often to update", actor_id)))
+    }
+
+    async fn publish(&self, entries: &[LedgerEntry]) {
+        let Some((bus, source)) = &self.bus else {
+            return;
+        };
+        for entry in entries {
+            let changed = CurrencyChanged {
+                transaction_id: entry.transaction_id.clone(),
+                actor_id: entry.actor_id.clone(),
+                currency: entry.currency.clone(),
+                delta: entry.delta,
+                balance: entry.balance,
+                kind: entry.kind,
+                reason: entry.reason.clone(),
+                at: entry.at,
+            };
+            if let Err(e) = bus.publish_message(source, &CURRENCY_CHANGED, &changed).await {
+                warn!("Failed to publish {} change of {}: {}", entry.currency, entry.actor_id, e);
+            }
+        }
+    }
+}
+
+/// Convert an amount to a ledger delta, failing when it does not fit
+fn signed(currency: &str, amount: u64) -> ChaosResult<i64> {
+    i64::try_from(amount).map_err(|_| ChaosError::Validation(format!("{} {} is too large", amount, currency)))
+}
+
+/// One currency of [`Wallets`], as the market charges it
+pub struct CurrencyWallet {
+    wallets: Arc<Wallets>,
+    currency: String,
+}
+
+impl CurrencyWallet {
+    pub fn new(wallets: Arc<Wallets>, currency: impl Into<String>) -> Self {
+        Self { wallets, currency: currency.into() }
+    }
+}
+
+#[async_trait]
+impl market::Wallet for CurrencyWallet {
+    async fn withdraw(&self, owner_id: &str, amount: u64, reference: &str) -> ChaosResult<()> {
+        self.wallets.spend(owner_id, &self.currency, amount, "market", Some(reference)).await.map(|_| ())
+    }
+}
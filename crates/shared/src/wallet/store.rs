@@ -0,0 +1,63 @@
+//! Wallet and ledger persistence.
+
+use super::{ActorWallet, LedgerEntry};
+use crate::error::ChaosResult;
+use crate::query::memory::apply_query;
+use crate::query::{ListQuery, Page};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+#[async_trait]
+pub trait WalletStore: Send + Sync {
+    async fn load(&self, actor_id: &str) -> ChaosResult<Option<ActorWallet>>;
+    /// Store `wallet` and append `entries`, both or neither, if the stored wallet is
+    /// still at `expected_version` (0 for a wallet never stored); returns whether it was
+    async fn commit(&self, expected_version: u64, wallet: &ActorWallet, entries: &[LedgerEntry]) -> ChaosResult<bool>;
+    async fn ledger(&self, query: &ListQuery) -> ChaosResult<Page<LedgerEntry>>;
+}
+
+/// Wallets held in memory; for tests and single-instance deployments
+#[derive(Default)]
+pub struct MemoryWalletStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    wallets: HashMap<String, ActorWallet>,
+    ledger: Vec<LedgerEntry>,
+}
+
+impl MemoryWalletStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl WalletStore for MemoryWalletStore {
+    async fn load(&self, actor_id: &str) -> ChaosResult<Option<ActorWallet>> {
+        Ok(self.state().wallets.get(actor_id).cloned())
+    }
+
+    async fn commit(&self, expected_version: u64, wallet: &ActorWallet, entries: &[LedgerEntry]) -> ChaosResult<bool> {
+        let mut state = self.state();
+        let stored_version = state.wallets.get(&wallet.actor_id).map_or(0, |stored| stored.version);
+        if stored_version != expected_version {
+            return Ok(false);
+        }
+        state.wallets.insert(wallet.actor_id.clone(), wallet.clone());
+        state.ledger.extend_from_slice(entries);
+        Ok(true)
+    }
+
+    async fn ledger(&self, query: &ListQuery) -> ChaosResult<Page<LedgerEntry>> {
+        let ledger = self.state().ledger.clone();
+        apply_query(ledger, query)
+    }
+}
@@ -0,0 +1,76 @@
+//! Integration tests for the game clock abstraction.
+
+use chrono::{TimeZone, Utc};
+use shared::clock::{GameClock, ScaledClock, SharedClock, SimulatedClock, WallClock};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn start() -> chrono::DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+#[test]
+fn simulated_clock_ticks_by_fixed_step() {
+    let clock = SimulatedClock::new(start(), Duration::from_millis(50));
+    assert_eq!(clock.now(), start());
+
+    clock.tick();
+    clock.tick_n(19);
+    assert_eq!(clock.now(), start() + chrono::Duration::seconds(1));
+    assert_eq!(clock.step(), Duration::from_millis(50));
+}
+
+#[test]
+fn simulated_clock_pause_blocks_ticks_but_not_fast_forward() {
+    let clock = SimulatedClock::new(start(), Duration::from_secs(1));
+    clock.pause();
+    assert!(clock.is_paused());
+
+    clock.tick_n(10);
+    assert_eq!(clock.now(), start());
+
+    clock.advance(Duration::from_secs(3600));
+    assert_eq!(clock.now_secs(), start().timestamp() + 3600);
+
+    clock.resume();
+    clock.tick();
+    assert_eq!(clock.now_secs(), start().timestamp() + 3601);
+}
+
+#[test]
+fn scaled_clock_multiplies_elapsed_time() {
+    let source = Arc::new(SimulatedClock::new(start(), Duration::from_secs(1)));
+    let scaled = ScaledClock::new(source.clone() as SharedClock, 4.0);
+
+    source.tick_n(10);
+    assert_eq!(scaled.now(), start() + chrono::Duration::seconds(40));
+
+    // Changing the scale keeps the time already elapsed
+    scaled.set_scale(0.5);
+    source.tick_n(10);
+    assert_eq!(scaled.now(), start() + chrono::Duration::seconds(45));
+}
+
+#[test]
+fn scaled_clock_pause_freezes_time() {
+    let source = Arc::new(SimulatedClock::new(start(), Duration::from_secs(1)));
+    let scaled = ScaledClock::new(source.clone() as SharedClock, 2.0);
+
+    source.tick_n(5);
+    scaled.pause();
+    source.tick_n(5);
+    assert!(scaled.is_paused());
+    assert_eq!(scaled.now(), start() + chrono::Duration::seconds(10));
+
+    scaled.resume();
+    source.tick_n(5);
+    assert_eq!(scaled.now(), start() + chrono::Duration::seconds(20));
+}
+
+#[test]
+fn wall_clock_tracks_system_time() {
+    let before = Utc::now();
+    let now = WallClock.now();
+    assert!(now >= before);
+    assert!(!WallClock.is_paused());
+}
@@ -0,0 +1,18 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use chrono::{TimeZone, Utc};
+use shared::{SimulatedClock, Timestamp};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Fixed start of every test timeline
+pub fn start_time() -> Timestamp {
+    Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+}
+
+/// Clock starting at [`start_time`] and ticking one second at a time
+pub fn create_test_clock() -> Arc<SimulatedClock> {
+    Arc::new(SimulatedClock::new(start_time(), Duration::from_secs(1)))
+}
@@ -0,0 +1,118 @@
+//! Integration tests for companion rosters, leveling, commands and persistence.
+
+mod common;
+
+use common::create_test_clock;
+use shared::companion::{
+    CompanionCommand, CompanionConfig, CompanionManager, CompanionStatus, CompanionStore, FileCompanionStore,
+    LevelingTrack, MemoryCompanionStore,
+};
+use shared::error::ChaosError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn config() -> CompanionConfig {
+    let track = LevelingTrack { max_level: 4, base_experience: 100.0, growth: 2.0 };
+    CompanionConfig { max_active: 1, max_companions: 2, tracks: HashMap::from([("beast".to_string(), track)]) }
+}
+
+fn manager() -> CompanionManager {
+    let clock = create_test_clock();
+    CompanionManager::new(clock, config())
+}
+
+#[tokio::test]
+async fn owners_summon_and_stable_within_their_limits() {
+    let companions = manager();
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    let hawk = companions.adopt("hero", "hawk", "Talon", "beast").await.unwrap();
+    assert_eq!(wolf.status, CompanionStatus::Stabled);
+    assert!(companions.adopt("hero", "bear", "Bruno", "beast").await.is_err());
+    assert!(companions.adopt("rival", "bear", "Bruno", "mythic").await.is_err());
+
+    companions.summon("hero", &wolf.id).await.unwrap();
+    // One companion at a time
+    assert!(companions.summon("hero", &hawk.id).await.is_err());
+    companions.stable("hero", &wolf.id).await.unwrap();
+    companions.summon("hero", &hawk.id).await.unwrap();
+    let active = companions.active("hero");
+    assert_eq!(active.iter().map(|companion| companion.name.as_str()).collect::<Vec<_>>(), ["Talon"]);
+
+    // Only owners command their companions
+    assert!(matches!(
+        companions.set_command("rival", &hawk.id, CompanionCommand::Passive).await,
+        Err(ChaosError::Validation(_))
+    ));
+    companions.release("hero", &wolf.id).await.unwrap();
+    assert_eq!(companions.owner_of(&wolf.id), None);
+    assert_eq!(companions.roster("hero").unwrap().companions.len(), 1);
+}
+
+#[tokio::test]
+async fn companions_level_along_their_track() {
+    let companions = manager();
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+
+    // 100 to reach level 2, 200 more to reach level 3
+    let wolf = companions.grant_experience(&wolf.id, 350.0).await.unwrap();
+    assert_eq!((wolf.level, wolf.experience), (3, 50.0));
+    let wolf = companions.grant_experience(&wolf.id, 1000.0).await.unwrap();
+    assert_eq!((wolf.level, wolf.experience), (4, 0.0));
+    assert!(companions.grant_experience(&wolf.id, -1.0).await.is_err());
+}
+
+#[tokio::test]
+async fn commands_decide_when_companions_engage() {
+    assert!(!CompanionCommand::Passive.engages(true));
+    assert!(CompanionCommand::Defensive.engages(true));
+    assert!(!CompanionCommand::Defensive.engages(false));
+    assert!(CompanionCommand::Aggressive.engages(false));
+
+    let companions = manager();
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    let wolf = companions.set_command("hero", &wolf.id, CompanionCommand::Aggressive).await.unwrap();
+    assert_eq!(companions.companion(&wolf.id).unwrap().command, CompanionCommand::Aggressive);
+}
+
+#[tokio::test]
+async fn companion_combat_is_credited_to_the_owner() {
+    let companions = manager();
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+
+    let attribution = companions.attribute(&wolf.id);
+    assert_eq!(attribution.credited_id, "hero");
+    assert_eq!(attribution.companion_id.as_deref(), Some(wolf.id.as_str()));
+    let attribution = companions.attribute("hero");
+    assert_eq!((attribution.credited_id.as_str(), attribution.companion_id), ("hero", None));
+}
+
+#[tokio::test]
+async fn rosters_survive_unloading() {
+    let store = Arc::new(MemoryCompanionStore::default());
+    let companions = manager().with_store(store.clone());
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    companions.summon("hero", &wolf.id).await.unwrap();
+    companions.grant_experience(&wolf.id, 150.0).await.unwrap();
+
+    companions.unload("hero");
+    assert_eq!(companions.companion(&wolf.id), None);
+    let roster = companions.load("hero").await.unwrap();
+    let wolf = roster.companion(&wolf.id).unwrap();
+    assert_eq!((wolf.status, wolf.level, wolf.experience), (CompanionStatus::Active, 2, 50.0));
+    assert_eq!(store.load("hero").await.unwrap().unwrap(), roster);
+}
+
+#[tokio::test]
+async fn file_store_keeps_rosters_across_restarts() {
+    let dir = std::env::temp_dir().join(format!("companions-{}", uuid::Uuid::new_v4()));
+    let companions = manager().with_store(Arc::new(FileCompanionStore::open(&dir).unwrap()));
+    let wolf = companions.adopt("hero", "wolf", "Fang", "beast").await.unwrap();
+    companions.summon("hero", &wolf.id).await.unwrap();
+
+    let restarted = manager().with_store(Arc::new(FileCompanionStore::open(&dir).unwrap()));
+    assert!(restarted.load("nobody").await.unwrap().companions.is_empty());
+    let roster = restarted.load("hero").await.unwrap();
+    assert_eq!(roster.active().map(|companion| companion.id.as_str()).collect::<Vec<_>>(), [wolf.id.as_str()]);
+    assert_eq!(restarted.owner_of(&wolf.id).as_deref(), Some("hero"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
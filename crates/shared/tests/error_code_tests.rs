@@ -0,0 +1,53 @@
+//! Integration tests for the error code catalog.
+
+use shared::error::{ApiError, ChaosError, ErrorCode, GrpcCode, ToApiError};
+use std::collections::HashSet;
+
+#[test]
+fn codes_are_unique_and_round_trip() {
+    let mut numeric = HashSet::new();
+    let mut names = HashSet::new();
+
+    for code in ErrorCode::ALL {
+        assert!(numeric.insert(code.code()), "duplicate numeric code {}", code.code());
+        assert!(names.insert(code.as_str()), "duplicate string code {}", code.as_str());
+        assert_eq!(ErrorCode::from_code(code.code()), Some(code));
+        assert_eq!(ErrorCode::from_name(code.as_str()), Some(code));
+    }
+    assert_eq!(ErrorCode::from_code(9999), None);
+}
+
+#[test]
+fn serde_uses_string_codes() {
+    for code in ErrorCode::ALL {
+        let json = serde_json::to_string(&code).unwrap();
+        assert_eq!(json, format!("\"{}\"", code.as_str()));
+        assert_eq!(serde_json::from_str::<ErrorCode>(&json).unwrap(), code);
+    }
+
+    let json = serde_json::to_value(ApiError::from_code(ErrorCode::NotFound)).unwrap();
+    assert_eq!(json["code"], "NOT_FOUND");
+    assert_eq!(json["numeric_code"], 2002);
+}
+
+#[test]
+fn codes_map_to_http_and_grpc() {
+    assert_eq!(ErrorCode::ValidationFailed.http_status(), 400);
+    assert_eq!(ErrorCode::ValidationFailed.grpc_code(), GrpcCode::InvalidArgument);
+    assert_eq!(ErrorCode::Unauthenticated.http_status(), 401);
+    assert_eq!(ErrorCode::RateLimited.grpc_code(), GrpcCode::ResourceExhausted);
+    assert_eq!(ErrorCode::Timeout.http_status(), 504);
+    assert_eq!(GrpcCode::Unauthenticated as i32, 16);
+}
+
+#[test]
+fn chaos_errors_hide_internal_details() {
+    let error = ChaosError::Database("connection to 10.0.0.5 refused".to_string()).to_api_error();
+    assert_eq!(error.code, ErrorCode::Database);
+    assert_eq!(error.http_status(), 500);
+    assert!(!error.message.contains("10.0.0.5"));
+
+    let error = ChaosError::Validation("level must be positive".to_string()).to_api_error();
+    assert_eq!(error.code, ErrorCode::ValidationFailed);
+    assert_eq!(error.message, "level must be positive");
+}
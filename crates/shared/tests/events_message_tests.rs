@@ -0,0 +1,177 @@
+//! Integration tests for versioned messages, consumers and replay.
+
+use serde::{Deserialize, Serialize};
+use shared::events::topics::DEAD_LETTER;
+use shared::events::{
+    Checkpoints, Consumer, ConsumerOptions, EventBus, EventBusExt, EventEnvelope, InProcessEventBus, MemoryCheckpoints,
+    Message, ReplayingBus, Topic,
+};
+use shared::{ChaosError, ChaosResult};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// v1 payloads carried `hp` instead of `health`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct ActorHealed {
+    actor_id: String,
+    health: f64,
+}
+
+impl Message for ActorHealed {
+    const TYPE: &'static str = "combat.actor_healed";
+    const VERSION: u32 = 2;
+
+    fn upgrade(version: u32, mut payload: serde_json::Value) -> ChaosResult<serde_json::Value> {
+        match version {
+            1 => {
+                let hp = payload["hp"].take();
+                payload["health"] = hp;
+                Ok(payload)
+            }
+            _ => Err(ChaosError::Validation(format!("Unknown version {}", version))),
+        }
+    }
+}
+
+const HEALED: Topic<ActorHealed> = Topic::new("combat.actor.healed");
+
+fn healed(health: f64) -> ActorHealed {
+    ActorHealed {
+        actor_id: "player-1".to_string(),
+        health,
+    }
+}
+
+async fn receive<T>(receiver: &mut mpsc::UnboundedReceiver<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(1), receiver.recv())
+        .await
+        .expect("timed out")
+        .unwrap()
+}
+
+#[test]
+fn messages_are_stamped_and_old_versions_upgraded() {
+    let envelope = EventEnvelope::message(&HEALED, &healed(10.0)).unwrap();
+    assert_eq!(envelope.message_type.as_deref(), Some("combat.actor_healed"));
+    assert_eq!(envelope.version, 2);
+    assert_eq!(envelope.decode_message::<ActorHealed>().unwrap(), healed(10.0));
+
+    let mut old = envelope.clone();
+    old.version = 1;
+    old.payload = serde_json::json!({ "actor_id": "player-1", "hp": 25.0 });
+    assert_eq!(old.decode_message::<ActorHealed>().unwrap(), healed(25.0));
+
+    // Publishers predating versioning send plain events
+    let plain = EventEnvelope::new(&Topic::dynamic("combat.actor.healed"), &healed(5.0)).unwrap();
+    assert_eq!(plain.decode_message::<ActorHealed>().unwrap(), healed(5.0));
+}
+
+#[test]
+fn newer_versions_and_other_types_are_rejected() {
+    let mut newer = EventEnvelope::message(&HEALED, &healed(10.0)).unwrap();
+    newer.version = 3;
+    assert!(newer.decode_message::<ActorHealed>().is_err());
+
+    let mut other = EventEnvelope::message(&HEALED, &healed(10.0)).unwrap();
+    other.message_type = Some("combat.actor_damaged".to_string());
+    assert!(other.decode_message::<ActorHealed>().is_err());
+}
+
+#[tokio::test]
+async fn failing_messages_are_dead_lettered_after_retries() {
+    let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+    let mut dead_letters = bus.subscribe(&DEAD_LETTER).await.unwrap();
+    let (attempts, mut attempted) = mpsc::unbounded_channel();
+
+    let options = ConsumerOptions {
+        retry_backoff: Duration::from_millis(1),
+        replay: false,
+        ..ConsumerOptions::new("healer")
+    };
+    let consumer = Consumer::new(bus.clone(), HEALED, options)
+        .spawn(move |message: ActorHealed, _| {
+            let attempts = attempts.clone();
+            async move {
+                attempts.send(message.health).unwrap();
+                if message.health < 0.0 {
+                    Err(ChaosError::Validation("negative heal".to_string()))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+    bus.publish_message("combat", &HEALED, &healed(-1.0)).await.unwrap();
+    bus.publish_message("combat", &HEALED, &healed(3.0)).await.unwrap();
+
+    let letter = dead_letters.next().await.unwrap().unwrap();
+    assert_eq!(letter.consumer, "healer");
+    assert_eq!(letter.topic, "combat.actor.healed");
+    assert_eq!(letter.attempts, 3);
+    assert_eq!(letter.envelope.decode_message::<ActorHealed>().unwrap(), healed(-1.0));
+
+    // The bad message does not block the next one
+    for expected in [-1.0, -1.0, -1.0, 3.0] {
+        assert_eq!(receive(&mut attempted).await, expected);
+    }
+    consumer.abort();
+}
+
+#[tokio::test]
+async fn restarted_consumers_replay_what_they_missed() {
+    let bus = Arc::new(ReplayingBus::new(Arc::new(InProcessEventBus::new())));
+    bus.clone().serve().await.unwrap();
+    let checkpoints: Arc<dyn Checkpoints> = Arc::new(MemoryCheckpoints::default());
+
+    let start = |checkpoints: Arc<dyn Checkpoints>| {
+        let bus: Arc<dyn EventBus> = bus.clone();
+        async move {
+            let (handled, received) = mpsc::unbounded_channel();
+            let handle = Consumer::new(bus, HEALED, ConsumerOptions::new("healer"))
+                .with_checkpoints(checkpoints)
+                .spawn(move |message: ActorHealed, _| {
+                    let handled = handled.clone();
+                    async move {
+                        handled.send(message.health).unwrap();
+                        Ok(())
+                    }
+                })
+                .await
+                .unwrap();
+            (handle, received)
+        }
+    };
+
+    // Published before the consumer ever ran
+    bus.publish_message("combat", &HEALED, &healed(1.0)).await.unwrap();
+    bus.publish_message("combat", &HEALED, &healed(2.0)).await.unwrap();
+
+    let (first, mut received) = start(checkpoints.clone()).await;
+    assert_eq!(receive(&mut received).await, 1.0);
+    assert_eq!(receive(&mut received).await, 2.0);
+    first.abort();
+    let _ = first.await;
+
+    // Published while it was down
+    bus.publish_message("combat", &HEALED, &healed(3.0)).await.unwrap();
+
+    let (second, mut received) = start(checkpoints).await;
+    assert_eq!(receive(&mut received).await, 3.0);
+    bus.publish_message("combat", &HEALED, &healed(4.0)).await.unwrap();
+    assert_eq!(receive(&mut received).await, 4.0);
+    let nothing = tokio::time::timeout(Duration::from_millis(50), received.recv()).await;
+    assert!(nothing.is_err());
+    second.abort();
+}
+
+#[tokio::test]
+async fn consumer_names_must_be_single_tokens() {
+    let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+    let result = Consumer::new(bus, HEALED, ConsumerOptions::new("world.service"))
+        .spawn(|_: ActorHealed, _| async { Ok(()) })
+        .await;
+    assert!(result.is_err());
+}
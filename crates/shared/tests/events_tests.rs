@@ -0,0 +1,88 @@
+//! Integration tests for the shared event bus.
+
+use serde::{Deserialize, Serialize};
+use shared::events::{EventBus, EventBusExt, EventEnvelope, InProcessEventBus, Topic};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DamageDealt {
+    attacker: String,
+    target: String,
+    amount: f64,
+}
+
+const DAMAGE: Topic<DamageDealt> = Topic::new("combat.damage");
+
+fn damage(amount: f64) -> DamageDealt {
+    DamageDealt {
+        attacker: "player-1".to_string(),
+        target: "goblin-7".to_string(),
+        amount,
+    }
+}
+
+#[tokio::test]
+async fn typed_publish_reaches_every_subscriber() {
+    let bus = InProcessEventBus::new();
+    let mut first = bus.subscribe(&DAMAGE).await.unwrap();
+    let mut second = bus.subscribe(&DAMAGE).await.unwrap();
+
+    bus.publish_from("combat-core", &DAMAGE, &damage(42.0)).await.unwrap();
+
+    assert_eq!(first.next().await.unwrap().unwrap(), damage(42.0));
+    let (event, envelope) = second.next_with_envelope().await.unwrap().unwrap();
+    assert_eq!(event, damage(42.0));
+    assert_eq!(envelope.topic, "combat.damage");
+    assert_eq!(envelope.source.as_deref(), Some("combat-core"));
+}
+
+#[tokio::test]
+async fn topics_are_isolated_and_unsubscribed_publishes_are_dropped() {
+    let bus = InProcessEventBus::new();
+    let other: Topic<DamageDealt> = Topic::dynamic("world.zone.1");
+
+    // Nobody is listening yet: dropped without error
+    bus.publish(&DAMAGE, &damage(1.0)).await.unwrap();
+
+    let mut damage_events = bus.subscribe(&DAMAGE).await.unwrap();
+    bus.publish(&other, &damage(2.0)).await.unwrap();
+    bus.publish(&DAMAGE, &damage(3.0)).await.unwrap();
+
+    assert_eq!(damage_events.next().await.unwrap().unwrap(), damage(3.0));
+    let pending = tokio::time::timeout(Duration::from_millis(20), damage_events.next()).await;
+    assert!(pending.is_err());
+}
+
+#[tokio::test]
+async fn bus_works_behind_trait_object() {
+    let bus: std::sync::Arc<dyn EventBus> = std::sync::Arc::new(InProcessEventBus::new());
+    let mut raw = bus.subscribe_topic("combat.damage").await.unwrap();
+
+    bus.publish(&DAMAGE, &damage(5.0)).await.unwrap();
+
+    let envelope: EventEnvelope = raw.next().await.unwrap();
+    assert_eq!(envelope.decode::<DamageDealt>().unwrap(), damage(5.0));
+    assert_eq!(bus.backend_name(), "in-process");
+}
+
+#[tokio::test]
+async fn lagging_subscriber_skips_missed_events() {
+    let bus = InProcessEventBus::with_capacity(2);
+    let mut events = bus.subscribe(&DAMAGE).await.unwrap();
+
+    for amount in 0..5 {
+        bus.publish(&DAMAGE, &damage(amount as f64)).await.unwrap();
+    }
+
+    assert_eq!(events.next().await.unwrap().unwrap(), damage(3.0));
+    assert_eq!(events.next().await.unwrap().unwrap(), damage(4.0));
+}
+
+#[tokio::test]
+async fn invalid_topics_are_rejected() {
+    let bus = InProcessEventBus::new();
+    assert!(bus.subscribe_topic("").await.is_err());
+    assert!(bus.subscribe_topic("combat..damage").await.is_err());
+    assert!(bus.subscribe_topic("combat.*").await.is_err());
+    assert!(bus.publish(&Topic::<DamageDealt>::dynamic("bad topic"), &damage(1.0)).await.is_err());
+}
@@ -0,0 +1,102 @@
+//! Integration tests for service health checks.
+
+use shared::health::{check_fn, HealthRegistry, HealthStatus, RedisCheck, TcpCheck};
+use shared::ChaosError;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+fn counting(runs: &Arc<AtomicU32>, healthy: bool) -> impl Fn() -> std::future::Ready<shared::ChaosResult<()>> + Send + Sync {
+    let runs = runs.clone();
+    move || {
+        runs.fetch_add(1, Ordering::SeqCst);
+        std::future::ready(if healthy { Ok(()) } else { Err(ChaosError::Database("connection refused".to_string())) })
+    }
+}
+
+#[tokio::test]
+async fn readiness_fails_only_on_critical_checks() {
+    let runs = Arc::new(AtomicU32::new(0));
+
+    let degraded = HealthRegistry::new("user-management")
+        .register("mongodb", check_fn(counting(&runs, true)))
+        .register_optional("redis", check_fn(counting(&runs, false)));
+    let report = degraded.readiness().await;
+    assert_eq!(report.status, HealthStatus::Degraded);
+    assert!(report.status.is_ready());
+    assert_eq!(report.checks["mongodb"].status, HealthStatus::Healthy);
+    assert_eq!(report.checks["redis"].status, HealthStatus::Unhealthy);
+    assert!(!report.checks["redis"].critical);
+    assert!(report.checks["redis"].error.as_deref().unwrap().contains("connection refused"));
+
+    let unhealthy = HealthRegistry::new("user-management").register("mongodb", check_fn(counting(&runs, false)));
+    let report = unhealthy.readiness().await;
+    assert_eq!(report.status, HealthStatus::Unhealthy);
+    assert_eq!(report.status.http_status(), 503);
+
+    // Liveness never runs checks
+    let before = runs.load(Ordering::SeqCst);
+    assert_eq!(unhealthy.liveness().status, HealthStatus::Healthy);
+    assert!(unhealthy.liveness().checks.is_empty());
+    assert_eq!(runs.load(Ordering::SeqCst), before);
+}
+
+#[tokio::test]
+async fn results_are_cached_for_the_ttl() {
+    let runs = Arc::new(AtomicU32::new(0));
+    let registry = HealthRegistry::new("chaos-backend")
+        .with_cache_ttl(Duration::from_millis(50))
+        .register("mongodb", check_fn(counting(&runs, true)));
+
+    registry.readiness().await;
+    registry.readiness().await;
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    registry.readiness().await;
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn hanging_checks_time_out() {
+    let registry = HealthRegistry::new("chaos-backend")
+        .with_timeout(Duration::from_millis(20))
+        .register("mongodb", check_fn(|| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        }));
+
+    let report = registry.readiness().await;
+    assert_eq!(report.status, HealthStatus::Unhealthy);
+    assert!(report.checks["mongodb"].latency_ms < 1000.0);
+}
+
+#[tokio::test]
+async fn network_checks_reach_their_servers() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buffer = [0u8; 64];
+                if let Ok(read) = socket.read(&mut buffer).await {
+                    if buffer[..read].starts_with(b"PING") {
+                        let _ = socket.write_all(b"+PONG\r\n").await;
+                    }
+                }
+            });
+        }
+    });
+
+    let registry = HealthRegistry::new("user-management")
+        .register("redis", RedisCheck::new(format!("redis://{}/0", address)))
+        .register("chat-service", TcpCheck::new(address));
+    assert_eq!(registry.readiness().await.status, HealthStatus::Healthy);
+
+    // Nothing listens on port 1
+    let registry = HealthRegistry::new("user-management").register("redis", RedisCheck::new("127.0.0.1:1"));
+    assert_eq!(registry.readiness().await.status, HealthStatus::Unhealthy);
+}
@@ -0,0 +1,53 @@
+//! Integration tests for maintenance mode and broadcasts.
+
+use shared::events::{EventBusExt, InProcessEventBus};
+use shared::maintenance::{self, MaintenanceFlag, MaintenanceState, MAINTENANCE_TOPIC};
+use std::time::Duration;
+
+#[test]
+fn maintenance_refuses_non_admins() {
+    let state = MaintenanceState::on("ops", None, None);
+    assert!(state.refuses(false));
+    assert!(!state.refuses(true));
+    assert_eq!(state.notice(), maintenance::DEFAULT_MAINTENANCE_MESSAGE);
+
+    let state = MaintenanceState::on("ops", Some("Patch 1.2".to_string()), None);
+    assert_eq!(state.notice(), "Patch 1.2");
+    assert!(!MaintenanceState::off("ops").refuses(false));
+}
+
+#[test]
+fn shutdown_countdown() {
+    let warnings = maintenance::shutdown_warnings(Duration::from_secs(5 * 60));
+    assert_eq!(warnings.first(), Some(&Duration::from_secs(5 * 60)));
+    assert_eq!(warnings.last(), Some(&Duration::from_secs(10)));
+    assert!(maintenance::shutdown_warnings(Duration::from_secs(5)).is_empty());
+
+    assert_eq!(
+        maintenance::shutdown_notice(Duration::from_secs(90)),
+        "The server shuts down for maintenance in 2 minutes"
+    );
+    assert_eq!(
+        maintenance::shutdown_notice(Duration::from_secs(1)),
+        "The server shuts down for maintenance in 1 second"
+    );
+}
+
+#[tokio::test]
+async fn flag_follows_the_bus() {
+    let bus = InProcessEventBus::new();
+    let flag = MaintenanceFlag::new();
+    let follower = flag.follow(&bus).await.unwrap();
+    assert!(!flag.is_enabled());
+
+    let state = MaintenanceState::on("ops", Some("Patch 1.2".to_string()), None);
+    bus.publish(&MAINTENANCE_TOPIC, &state).await.unwrap();
+    for _ in 0..100 {
+        if flag.is_enabled() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(flag.get(), state);
+    follower.abort();
+}
@@ -0,0 +1,275 @@
+//! Integration tests for the auction house.
+
+mod common;
+
+use async_trait::async_trait;
+use common::create_test_clock;
+use shared::error::{ChaosError, ChaosResult};
+use shared::events::topics::{MarketTrade, MARKET_TRADE};
+use shared::events::{EventBus, InProcessEventBus};
+use shared::market::{
+    ListingStatus, Mail, Mailer, Market, MarketConfig, MarketItem, MemoryMarketStore, NewListing, Wallet,
+};
+use shared::query::{FilterSpec, ListQuery, PageRequest, SortSpec};
+use shared::SimulatedClock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Default)]
+struct FakeWallet {
+    balances: Mutex<HashMap<String, u64>>,
+}
+
+impl FakeWallet {
+    fn with(balances: &[(&str, u64)]) -> Arc<Self> {
+        let wallet = Self::default();
+        wallet.balances.lock().unwrap().extend(balances.iter().map(|(owner, amount)| (owner.to_string(), *amount)));
+        Arc::new(wallet)
+    }
+
+    fn balance(&self, owner: &str) -> u64 {
+        self.balances.lock().unwrap().get(owner).copied().unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl Wallet for FakeWallet {
+    async fn withdraw(&self, owner_id: &str, amount: u64, _reference: &str) -> ChaosResult<()> {
+        let mut balances = self.balances.lock().unwrap();
+        let balance = balances.entry(owner_id.to_string()).or_default();
+        if *balance < amount {
+            return Err(ChaosError::Validation(format!("{} cannot pay {}", owner_id, amount)));
+        }
+        *balance -= amount;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+struct FakeMailer {
+    delivered: Mutex<Vec<Mail>>,
+    ids: Mutex<HashSet<String>>,
+    down: AtomicBool,
+}
+
+impl FakeMailer {
+    fn currency_for(&self, recipient: &str) -> u64 {
+        self.delivered.lock().unwrap().iter().filter(|mail| mail.recipient_id == recipient).map(|mail| mail.currency).sum()
+    }
+
+    fn items_for(&self, recipient: &str) -> Vec<MarketItem> {
+        self.delivered
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|mail| mail.recipient_id == recipient)
+            .flat_map(|mail| mail.items.clone())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl Mailer for FakeMailer {
+    async fn send(&self, mail: &Mail) -> ChaosResult<()> {
+        if self.down.load(Ordering::SeqCst) {
+            return Err(ChaosError::ExternalService("mail is down".to_string()));
+        }
+        if self.ids.lock().unwrap().insert(mail.id.clone()) {
+            self.delivered.lock().unwrap().push(mail.clone());
+        }
+        Ok(())
+    }
+}
+
+fn create_test_wallet() -> Arc<FakeWallet> {
+    FakeWallet::with(&[("seller", 1_000), ("alice", 1_000), ("bob", 1_000)])
+}
+
+fn create_test_market(
+    config: MarketConfig,
+    clock: &Arc<SimulatedClock>,
+    wallet: &Arc<FakeWallet>,
+    mailer: &Arc<FakeMailer>,
+) -> Market {
+    Market::new(config, clock.clone(), Arc::new(MemoryMarketStore::new()), wallet.clone(), mailer.clone())
+}
+
+fn sword(start_price: u64, buyout_price: Option<u64>) -> NewListing {
+    NewListing {
+        item: MarketItem { item_id: "sword".to_string(), quantity: 1, category: Some("weapon".to_string()) },
+        start_price,
+        buyout_price,
+        duration: 12 * HOUR,
+    }
+}
+
+#[tokio::test]
+async fn bids_are_escrowed_and_outbid_bidders_refunded() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig::default(), &clock, &wallet, &mailer);
+    let listing = market.create_listing("seller", sword(100, None)).await.unwrap();
+    // 5% of the start price per day, for half a day
+    assert_eq!(listing.deposit, 3);
+    assert_eq!(wallet.balance("seller"), 997);
+
+    market.bid(&listing.id, "alice", 100).await.unwrap();
+    assert_eq!(wallet.balance("alice"), 900);
+    // The next bid must add at least 5%
+    assert!(market.bid(&listing.id, "bob", 104).await.is_err());
+    let listing = market.bid(&listing.id, "bob", 105).await.unwrap();
+    assert_eq!(listing.price, 105);
+    assert!(listing.refunds.is_empty());
+    assert_eq!(mailer.currency_for("alice"), 100);
+
+    // Sellers cannot bid their own prices up
+    assert!(market.bid(&listing.id, "seller", 200).await.is_err());
+}
+
+#[tokio::test]
+async fn settlement_delivers_items_and_proceeds() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let mut trades = bus.subscribe_topic(MARKET_TRADE.name()).await.unwrap();
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market =
+        create_test_market(MarketConfig::default(), &clock, &wallet, &mailer).with_bus(bus.clone(), "market");
+
+    let sold = market.create_listing("seller", sword(100, None)).await.unwrap();
+    market.bid(&sold.id, "alice", 200).await.unwrap();
+    let unsold = market.create_listing("seller", sword(100, None)).await.unwrap();
+
+    // Nothing is due before the listings end
+    assert_eq!(market.settle_due().await.unwrap(), 0);
+    clock.advance(12 * HOUR);
+    assert_eq!(market.settle_due().await.unwrap(), 2);
+
+    let sold = market.get(&sold.id).await.unwrap().unwrap();
+    assert_eq!((sold.status, sold.settled), (ListingStatus::Sold, true));
+    assert_eq!(market.get(&unsold.id).await.unwrap().unwrap().status, ListingStatus::Expired);
+    assert_eq!(mailer.items_for("alice").len(), 1);
+    // Price less the 5% cut, plus the deposit; the unsold listing's deposit is kept
+    assert_eq!(mailer.currency_for("seller"), 200 - 10 + 3);
+    assert_eq!(mailer.items_for("seller").len(), 1);
+    assert_eq!(market.settle_due().await.unwrap(), 0);
+
+    let trade: MarketTrade = trades.next().await.unwrap().decode_message().unwrap();
+    assert_eq!((trade.buyer_id.as_str(), trade.price, trade.fee, trade.buyout), ("alice", 200, 10, false));
+    assert_eq!(trade.reference_unit_price, None);
+    assert_eq!(market.reference_price("sword"), Some(200.0));
+}
+
+#[tokio::test]
+async fn late_bids_extend_the_listing() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig { max_extensions: 1, ..MarketConfig::default() }, &clock, &wallet, &mailer);
+    let listing = market.create_listing("seller", sword(100, None)).await.unwrap();
+    let ends_at = listing.ends_at;
+
+    clock.advance(12 * HOUR - Duration::from_secs(60));
+    let listing = market.bid(&listing.id, "alice", 100).await.unwrap();
+    assert_eq!(listing.extensions, 1);
+    assert_eq!(listing.ends_at, ends_at + chrono::Duration::minutes(4));
+
+    // The listing may not be extended again
+    clock.advance(Duration::from_secs(4 * 60 - 30));
+    let listing = market.bid(&listing.id, "bob", 110).await.unwrap();
+    assert_eq!(listing.extensions, 1);
+    clock.advance(Duration::from_secs(90));
+    assert!(market.bid(&listing.id, "alice", 200).await.is_err());
+}
+
+#[tokio::test]
+async fn buyouts_close_the_listing_at_once() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig::default(), &clock, &wallet, &mailer);
+    let listing = market.create_listing("seller", sword(100, Some(300))).await.unwrap();
+    market.bid(&listing.id, "alice", 150).await.unwrap();
+
+    // Bids at the buyout price are buyouts, charged the buyout price
+    let listing = market.bid(&listing.id, "bob", 500).await.unwrap();
+    assert_eq!((listing.status, listing.price, listing.bought_out), (ListingStatus::Sold, 300, true));
+    assert_eq!(wallet.balance("bob"), 700);
+    assert!(listing.settled);
+    assert_eq!(mailer.currency_for("alice"), 150);
+    assert_eq!(mailer.items_for("bob").len(), 1);
+    assert!(market.buyout(&listing.id, "alice").await.is_err());
+
+    // Buyouts fail cleanly when the buyer cannot pay
+    let listing = market.create_listing("seller", sword(100, Some(2_000))).await.unwrap();
+    assert!(market.buyout(&listing.id, "alice").await.is_err());
+    assert_eq!(wallet.balance("alice"), 850);
+}
+
+#[tokio::test]
+async fn only_unbid_listings_can_be_cancelled() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig::default(), &clock, &wallet, &mailer);
+    let listing = market.create_listing("seller", sword(100, None)).await.unwrap();
+    assert!(matches!(market.cancel(&listing.id, "alice").await, Err(ChaosError::Authentication(_))));
+    let cancelled = market.cancel(&listing.id, "seller").await.unwrap();
+    assert_eq!(cancelled.status, ListingStatus::Cancelled);
+    // The items come back, the deposit does not
+    assert_eq!(mailer.items_for("seller").len(), 1);
+    assert_eq!(mailer.currency_for("seller"), 0);
+
+    let listing = market.create_listing("seller", sword(100, None)).await.unwrap();
+    market.bid(&listing.id, "alice", 100).await.unwrap();
+    assert!(market.cancel(&listing.id, "seller").await.is_err());
+
+    // Only configured durations are accepted
+    assert!(market.create_listing("seller", NewListing { duration: HOUR, ..sword(100, None) }).await.is_err());
+    assert!(market.create_listing("seller", sword(100, Some(50))).await.is_err());
+}
+
+#[tokio::test]
+async fn failed_deliveries_are_retried_once() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig::default(), &clock, &wallet, &mailer);
+    let listing = market.create_listing("seller", sword(100, None)).await.unwrap();
+    market.bid(&listing.id, "alice", 100).await.unwrap();
+
+    mailer.down.store(true, Ordering::SeqCst);
+    // The refund waits for the mail to come back
+    let listing = market.bid(&listing.id, "bob", 120).await.unwrap();
+    assert_eq!(listing.refunds.len(), 1);
+    clock.advance(12 * HOUR);
+    assert!(market.settle_due().await.is_err());
+    assert_eq!(market.get(&listing.id).await.unwrap().unwrap().status, ListingStatus::Sold);
+
+    mailer.down.store(false, Ordering::SeqCst);
+    assert_eq!(market.settle_due().await.unwrap(), 1);
+    assert_eq!(market.settle_due().await.unwrap(), 0);
+    assert_eq!(mailer.currency_for("alice"), 100);
+    assert_eq!(mailer.items_for("bob").len(), 1);
+    assert_eq!(mailer.currency_for("seller"), 120 - 6 + 3);
+}
+
+#[tokio::test]
+async fn search_uses_the_shared_query_types() {
+    let (clock, wallet, mailer) = (create_test_clock(), create_test_wallet(), Arc::new(FakeMailer::default()));
+    let market = create_test_market(MarketConfig::default(), &clock, &wallet, &mailer);
+    let cheap = market.create_listing("seller", sword(50, None)).await.unwrap();
+    let dear = market.create_listing("seller", sword(500, None)).await.unwrap();
+    let herb = NewListing {
+        item: MarketItem { item_id: "ginseng".to_string(), quantity: 10, category: Some("herb".to_string()) },
+        ..sword(20, None)
+    };
+    market.create_listing("seller", herb).await.unwrap();
+    market.cancel(&cheap.id, "seller").await.unwrap();
+
+    let query = ListQuery::new(PageRequest::first(10))
+        .with_filter(FilterSpec::new().eq("item.category", "weapon"))
+        .with_sort(SortSpec::new().asc("price"));
+    let page = market.search(query).await.unwrap();
+    // Cancelled listings are left out unless asked for
+    assert_eq!(page.items.iter().map(|listing| listing.id.as_str()).collect::<Vec<_>>(), [dear.id.as_str()]);
+
+    let query = ListQuery::new(PageRequest::first(10)).with_filter(FilterSpec::new().eq("status", "cancelled"));
+    assert_eq!(market.search(query).await.unwrap().items.len(), 1);
+
+    let query = ListQuery::new(PageRequest::first(10)).with_filter(FilterSpec::new().eq("deposit", 3));
+    assert!(market.search(query).await.is_err());
+}
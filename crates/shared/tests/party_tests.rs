@@ -0,0 +1,134 @@
+//! Integration tests for party and raid management.
+
+mod common;
+
+use common::create_test_clock;
+use shared::error::ChaosError;
+use shared::events::topics::{PartyChange, PartyChanged, PARTY_CHANGED};
+use shared::events::{EventBus, InProcessEventBus};
+use shared::party::{BuffMode, LootMethod, LootRules, PartyConfig, PartyKind, PartyManager, PartyRole, SharedBuff};
+use std::sync::Arc;
+
+fn manager() -> PartyManager {
+    let clock = create_test_clock();
+    PartyManager::new(clock, PartyConfig { max_party_members: 3, max_raid_members: 6 })
+}
+
+#[tokio::test]
+async fn leaders_and_assistants_manage_membership() {
+    let parties = manager();
+    let party = parties.create("leader").await.unwrap();
+    parties.add_member(&party.id, "leader", "tank").await.unwrap();
+
+    // Plain members cannot invite until promoted
+    assert!(matches!(parties.add_member(&party.id, "tank", "healer").await, Err(ChaosError::Authentication(_))));
+    parties.set_role(&party.id, "leader", "tank", PartyRole::Assistant).await.unwrap();
+    let party = parties.add_member(&party.id, "tank", "healer").await.unwrap();
+    assert_eq!(party.member_ids(), ["leader", "tank", "healer"]);
+
+    // Actors belong to one group at a time
+    let other = parties.create("loner").await.unwrap();
+    assert!(parties.add_member(&other.id, "loner", "tank").await.is_err());
+
+    // Full parties convert into raids to grow
+    assert!(parties.add_member(&party.id, "leader", "mage").await.is_err());
+    assert!(parties.convert_to_raid(&party.id, "tank").await.is_err());
+    assert_eq!(parties.convert_to_raid(&party.id, "leader").await.unwrap().kind, PartyKind::Raid);
+    parties.add_member(&party.id, "leader", "mage").await.unwrap();
+    assert_eq!(parties.party_of("mage").unwrap().id, party.id);
+}
+
+#[tokio::test]
+async fn leadership_passes_on_and_empty_groups_disband() {
+    let parties = manager();
+    let party = parties.create("leader").await.unwrap();
+    parties.add_member(&party.id, "leader", "a").await.unwrap();
+    parties.add_member(&party.id, "leader", "b").await.unwrap();
+    parties.set_role(&party.id, "leader", "b", PartyRole::Assistant).await.unwrap();
+
+    // The assistant takes over from a departing leader
+    let party = parties.leave("leader").await.unwrap().unwrap();
+    assert_eq!(party.leader_id(), "b");
+    assert!(parties.party_of("leader").is_none());
+
+    let party = parties.kick(&party.id, "b", "a").await.unwrap().unwrap();
+    assert_eq!(party.member_ids(), ["b"]);
+    assert!(parties.leave("b").await.unwrap().is_none());
+    assert!(parties.get(&party.id).is_none());
+}
+
+#[tokio::test]
+async fn loot_rules_drive_round_robin_and_master_loot() {
+    let parties = manager();
+    let party = parties.create("leader").await.unwrap();
+    parties.add_member(&party.id, "leader", "a").await.unwrap();
+
+    assert_eq!(party.loot_rules.method, LootMethod::RoundRobin);
+    let looters: Vec<String> = (0..3).map(|_| parties.next_looter(&party.id).unwrap()).collect();
+    assert_eq!(looters, ["leader", "a", "leader"]);
+
+    let master_loot = |looter: Option<&str>| LootRules {
+        method: LootMethod::MasterLooter,
+        rarity_threshold: 3,
+        master_looter: looter.map(str::to_string),
+    };
+    assert!(parties.set_loot_rules(&party.id, "leader", master_loot(None)).await.is_err());
+    assert!(parties.set_loot_rules(&party.id, "leader", master_loot(Some("stranger"))).await.is_err());
+    assert!(parties.set_loot_rules(&party.id, "a", master_loot(Some("a"))).await.is_err());
+    let party = parties.set_loot_rules(&party.id, "leader", master_loot(Some("a"))).await.unwrap();
+    assert_eq!(party.loot_rules.master_looter.as_deref(), Some("a"));
+
+    // Master loot falls back to the defaults when the master looter leaves
+    let party = parties.leave("a").await.unwrap().unwrap();
+    assert_eq!(party.loot_rules, LootRules::default());
+}
+
+#[tokio::test]
+async fn buffs_are_shared_with_the_other_members() {
+    let parties = manager();
+    let party = parties.create("bard").await.unwrap();
+    parties.add_member(&party.id, "bard", "knight").await.unwrap();
+    let aura = SharedBuff {
+        buff_id: "war_song".to_string(),
+        source_actor_id: "bard".to_string(),
+        stat: "attack".to_string(),
+        value: 1.1,
+        mode: BuffMode::Multiply,
+    };
+    assert!(parties.share_buff(SharedBuff { source_actor_id: "stranger".to_string(), ..aura.clone() }).is_err());
+    parties.share_buff(aura.clone()).unwrap();
+
+    assert_eq!(parties.party_buffs("knight"), vec![aura]);
+    assert!(parties.party_buffs("bard").is_empty());
+
+    // Buffs stop applying once their source leaves
+    parties.leave("bard").await.unwrap();
+    assert!(parties.party_buffs("knight").is_empty());
+}
+
+#[tokio::test]
+async fn changes_are_published_on_the_bus() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let mut changes = bus.subscribe_topic(PARTY_CHANGED.name()).await.unwrap();
+    let parties = manager().with_bus(bus.clone(), "party-test");
+
+    let party = parties.create("leader").await.unwrap();
+    parties.add_member(&party.id, "leader", "a").await.unwrap();
+    parties.disband(&party.id, "leader").await.unwrap();
+
+    let mut received = Vec::new();
+    for _ in 0..3 {
+        let changed: PartyChanged = changes.next().await.unwrap().decode_message().unwrap();
+        assert_eq!(changed.party_id, party.id);
+        received.push(changed.change);
+    }
+    assert_eq!(
+        received,
+        [
+            PartyChange::Created { leader_id: "leader".to_string() },
+            PartyChange::MemberJoined { actor_id: "a".to_string() },
+            PartyChange::Disbanded,
+        ]
+    );
+    assert!(parties.party_of("a").is_none());
+}
@@ -0,0 +1,110 @@
+//! Integration tests for the actor presence registry and zone leases.
+
+mod common;
+
+use common::create_test_clock;
+use shared::presence::{
+    ClaimOutcome, InMemoryLeases, InMemoryPresence, LeaseOutcome, LeaseRegistry, PresenceOwner, PresenceRegistry,
+};
+use shared::SimulatedClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+const TTL: Duration = Duration::from_secs(30);
+
+fn registry() -> (Arc<SimulatedClock>, InMemoryPresence) {
+    let clock = create_test_clock();
+    (clock.clone(), InMemoryPresence::new(clock))
+}
+
+fn owner(instance: &str) -> PresenceOwner {
+    PresenceOwner::new(instance, Some(format!("{}:50051", instance)))
+}
+
+#[tokio::test]
+async fn actors_are_claimed_by_a_single_instance() {
+    let (_, registry) = registry();
+    let claimed = registry.claim(&owner("a"), "hero", "forest", TTL).await.unwrap();
+    assert!(claimed.is_claimed());
+    assert_eq!(claimed.presence().epoch, 1);
+
+    // Renewing keeps the epoch; others see who holds the actor
+    assert_eq!(registry.claim(&owner("a"), "hero", "forest", TTL).await.unwrap().presence().epoch, 1);
+    match registry.claim(&owner("b"), "hero", "city", TTL).await.unwrap() {
+        ClaimOutcome::HeldBy(presence) => assert_eq!(presence.instance, "a"),
+        ClaimOutcome::Claimed(_) => panic!("claimed an actor held by another instance"),
+    }
+
+    let presence = registry.lookup("hero").await.unwrap().unwrap();
+    assert_eq!((presence.zone.as_str(), presence.address.as_deref()), ("forest", Some("a:50051")));
+    assert!(registry.lookup("nobody").await.unwrap().is_none());
+    let ids = vec!["hero".to_string(), "nobody".to_string()];
+    assert_eq!(registry.lookup_many(&ids).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn handovers_move_actors_between_instances() {
+    let (_, registry) = registry();
+    registry.claim(&owner("a"), "hero", "forest", TTL).await.unwrap();
+
+    // Only the current owner can be taken over from
+    assert!(!registry.handover(&owner("c"), "hero", "desert", "b", TTL).await.unwrap().is_claimed());
+    let handed = registry.handover(&owner("b"), "hero", "city", "a", TTL).await.unwrap();
+    assert!(handed.is_claimed());
+    assert_eq!(handed.presence().epoch, 2);
+    assert_eq!(registry.lookup("hero").await.unwrap().unwrap().instance, "b");
+
+    // The previous owner can no longer renew or release the actor
+    let actors = vec!["hero".to_string()];
+    assert_eq!(registry.heartbeat(&owner("a"), &actors, TTL).await.unwrap(), actors);
+    assert!(!registry.release("a", "hero").await.unwrap());
+    assert!(registry.release("b", "hero").await.unwrap());
+    assert!(registry.lookup("hero").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn claims_expire_without_heartbeats() {
+    let (clock, registry) = registry();
+    let actors = vec!["hero".to_string(), "wolf".to_string()];
+    registry.claim(&owner("a"), "hero", "forest", TTL).await.unwrap();
+    registry.claim(&owner("a"), "wolf", "forest", TTL).await.unwrap();
+
+    clock.advance(Duration::from_secs(20));
+    assert!(registry.heartbeat(&owner("a"), &actors[..1], TTL).await.unwrap().is_empty());
+    clock.advance(Duration::from_secs(20));
+    assert!(registry.lookup("hero").await.unwrap().is_some());
+    assert!(registry.lookup("wolf").await.unwrap().is_none());
+
+    // Expired actors are free to claim again
+    assert!(registry.claim(&owner("b"), "wolf", "forest", TTL).await.unwrap().is_claimed());
+    assert_eq!(registry.heartbeat(&owner("a"), &actors, TTL).await.unwrap(), vec!["wolf".to_string()]);
+}
+
+#[tokio::test]
+async fn lease_tokens_fence_previous_holders() {
+    let clock = create_test_clock();
+    let leases = InMemoryLeases::new(clock.clone());
+    let ttl = Duration::from_secs(5);
+
+    let acquired = leases.acquire(&owner("a"), "zone:forest", ttl, None).await.unwrap();
+    assert_eq!(acquired.lease().token, 1);
+    assert_eq!(leases.acquire(&owner("a"), "zone:forest", ttl, None).await.unwrap().lease().token, 1);
+    match leases.acquire(&owner("b"), "zone:forest", ttl, None).await.unwrap() {
+        LeaseOutcome::HeldBy(lease) => assert_eq!(lease.instance, "a"),
+        LeaseOutcome::Acquired(_) => panic!("acquired a lease held by another instance"),
+    }
+
+    // A lapsed lease goes to the next instance with a higher token, never a reused one
+    clock.advance(Duration::from_secs(5));
+    assert!(leases.current("zone:forest").await.unwrap().is_none());
+    assert_eq!(leases.acquire(&owner("b"), "zone:forest", ttl, None).await.unwrap().lease().token, 2);
+    assert!(!leases.release("a", "zone:forest", 1).await.unwrap());
+
+    // Taking over from the holder, then releasing with the current token only
+    let taken = leases.acquire(&owner("a"), "zone:forest", ttl, Some("b")).await.unwrap();
+    assert!(taken.is_acquired());
+    assert_eq!(taken.lease().token, 3);
+    assert!(!leases.release("a", "zone:forest", 2).await.unwrap());
+    assert!(leases.release("a", "zone:forest", 3).await.unwrap());
+    assert_eq!(leases.acquire(&owner("b"), "zone:forest", ttl, None).await.unwrap().lease().token, 4);
+}
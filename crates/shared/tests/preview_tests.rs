@@ -0,0 +1,53 @@
+//! Integration tests for draft content previews.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use shared::error::ChaosResult;
+use shared::preview::{self, DraftSource, PreviewContext};
+
+/// Covers `quest/quest_001` for the token `pv_editor`
+struct FakeDrafts;
+
+#[async_trait]
+impl DraftSource for FakeDrafts {
+    async fn draft(&self, preview: &PreviewContext, content_type: &str, content_id: &str) -> ChaosResult<Option<Value>> {
+        let covered = preview.token == "pv_editor" && content_type == "quest" && content_id == "quest_001";
+        Ok(covered.then(|| json!({ "title": "Draft" })))
+    }
+}
+
+async fn load(content_id: &str) -> Option<Value> {
+    preview::load_content(&FakeDrafts, "quest", content_id, || async { Ok(Some(json!({ "title": "Published" }))) })
+        .await
+        .unwrap()
+}
+
+#[test]
+fn preview_tokens_are_checked() {
+    assert!(preview::is_valid_preview_token("pv_3f2a9c"));
+    for token in ["", "pv_", "3f2a9c", "pv_3f2a-9c", "pv_ 3f2a"] {
+        assert!(!preview::is_valid_preview_token(token), "accepted {:?}", token);
+    }
+    assert!(!preview::is_valid_preview_token(&format!("pv_{}", "a".repeat(preview::MAX_PREVIEW_TOKEN_LEN))));
+
+    assert_eq!(PreviewContext::from_header(Some(" pv_editor ")).unwrap().token, "pv_editor");
+    assert!(PreviewContext::from_header(Some("editor")).is_none());
+    assert!(PreviewContext::from_header(None).is_none());
+}
+
+#[tokio::test]
+async fn drafts_are_only_loaded_inside_a_preview_scope() {
+    assert_eq!(load("quest_001").await, Some(json!({ "title": "Published" })));
+
+    let preview = PreviewContext::from_header(Some("pv_editor"));
+    preview::scope(preview, async {
+        assert!(preview::current().is_some());
+        assert_eq!(load("quest_001").await, Some(json!({ "title": "Draft" })));
+        // Content outside the preview stays published
+        assert_eq!(load("quest_002").await, Some(json!({ "title": "Published" })));
+    })
+    .await;
+
+    assert!(preview::current().is_none());
+    preview::scope(None, async { assert!(preview::current().is_none()) }).await;
+}
@@ -0,0 +1,164 @@
+//! Integration tests for list query types.
+
+use shared::query::memory::{apply_query, matches_filter};
+use shared::query::{
+    FilterOp, FilterSpec, ListQuery, Page, PageCursor, PageParams, PageRequest, SortDirection, SortSpec,
+    MAX_PAGE_LIMIT,
+};
+
+#[test]
+fn page_numbers_become_offsets() {
+    assert_eq!(PageRequest::page(1, 20), PageRequest::offset(0, 20));
+    assert_eq!(PageRequest::page(3, 25), PageRequest::offset(50, 25));
+    assert_eq!(PageRequest::page(0, 10), PageRequest::offset(0, 10));
+    assert_eq!(PageRequest::offset(0, 500).clamped(MAX_PAGE_LIMIT).limit(), MAX_PAGE_LIMIT);
+    assert_eq!(PageRequest::offset(0, 0).clamped(MAX_PAGE_LIMIT).limit(), 1);
+}
+
+#[test]
+fn page_request_serde_is_tagged() {
+    let request = PageRequest::cursor(Some("abc".to_string()), 10);
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["type"], "cursor");
+    assert_eq!(serde_json::from_value::<PageRequest>(json).unwrap(), request);
+}
+
+#[test]
+fn cursor_round_trips() {
+    let cursor = PageCursor::new(vec![serde_json::json!("2024-01-01"), serde_json::json!(42)]);
+    let request = PageRequest::cursor(Some(cursor.encode()), 10);
+    assert_eq!(request.after().unwrap(), Some(cursor));
+    assert!(PageCursor::decode("not a cursor!").is_err());
+}
+
+#[test]
+fn sort_spec_parses_and_formats() {
+    let spec: SortSpec = "-created_at, name".parse().unwrap();
+    assert_eq!(spec, SortSpec::new().desc("created_at").asc("name"));
+    assert_eq!(spec.to_string(), "-created_at,name");
+    assert!("$where".parse::<SortSpec>().is_err());
+    assert!(spec.validate_fields(&["created_at"]).is_err());
+    assert!(spec.validate_fields(&["created_at", "name"]).is_ok());
+    assert_eq!("DESC".parse::<SortDirection>().unwrap(), SortDirection::Desc);
+}
+
+#[test]
+fn filter_validation_checks_fields_and_values() {
+    let filter = FilterSpec::new()
+        .eq("status", "active")
+        .with("level", FilterOp::In, serde_json::json!([1, 2]))
+        .with_opt("name", FilterOp::Contains, None::<String>);
+    assert_eq!(filter.conditions.len(), 2);
+    assert!(filter.validate_fields(&["status", "level"]).is_ok());
+    assert!(filter.validate_fields(&["status"]).is_err());
+
+    let bad_value = FilterSpec::new().with("level", FilterOp::In, 1);
+    assert!(bad_value.validate_fields(&["level"]).is_err());
+}
+
+#[test]
+fn page_params_build_queries() {
+    let params = PageParams {
+        page: Some(2),
+        limit: Some(1000),
+        sort: Some("-level".to_string()),
+        ..PageParams::default()
+    };
+    let query = params.to_list_query().unwrap();
+    assert_eq!(query.page, PageRequest::offset(MAX_PAGE_LIMIT as u64, MAX_PAGE_LIMIT));
+    assert_eq!(query.sort, SortSpec::new().desc("level"));
+
+    let params = PageParams {
+        cursor: Some("token".to_string()),
+        page: Some(5),
+        ..PageParams::default()
+    };
+    assert!(matches!(params.page_request(), PageRequest::Cursor { .. }));
+}
+
+#[test]
+fn pages_report_more_results() {
+    let page = Page::from_offset(vec![1, 2], 5, &PageRequest::offset(2, 2));
+    assert!(page.has_more);
+    let page = Page::from_offset(vec![5], 5, &PageRequest::offset(4, 2));
+    assert!(!page.has_more);
+
+    let page = Page::from_cursor(vec![1, 2, 3], 2, |item| PageCursor::new(vec![serde_json::json!(item)]));
+    assert_eq!(page.items, vec![1, 2]);
+    let next = PageCursor::decode(page.next_cursor.as_deref().unwrap()).unwrap();
+    assert_eq!(next.values, vec![serde_json::json!(2)]);
+    assert_eq!(page.map(|item| item * 10).items, vec![10, 20]);
+}
+
+#[cfg(feature = "mongodb")]
+#[test]
+fn mongo_translation() {
+    use bson::doc;
+    use shared::query::mongo::to_mongo_query;
+
+    let query = ListQuery::new(PageRequest::offset(40, 20))
+        .with_sort(SortSpec::new().desc("created_at"))
+        .with_filter(FilterSpec::new().eq("status", "active").with("name", FilterOp::Contains, "a.b"));
+    let mongo = to_mongo_query(&query).unwrap();
+    assert_eq!(mongo.sort, doc! { "created_at": -1 });
+    assert_eq!(mongo.skip, Some(40));
+    assert_eq!(mongo.limit, 20);
+    assert_eq!(
+        mongo.filter,
+        doc! { "$and": [
+            { "status": { "$eq": "active" } },
+            { "name": { "$regex": "a\\.b", "$options": "i" } },
+        ] }
+    );
+
+    let cursor = PageCursor::new(vec![serde_json::json!(10), serde_json::json!("x")]);
+    let query = ListQuery::new(PageRequest::cursor(Some(cursor.encode()), 5))
+        .with_sort(SortSpec::new().desc("level").asc("name"));
+    let mongo = to_mongo_query(&query).unwrap().with_lookahead();
+    assert_eq!(mongo.limit, 6);
+    assert_eq!(
+        mongo.filter,
+        doc! { "$or": [
+            { "level": { "$lt": 10_i64 } },
+            { "level": 10_i64, "name": { "$gt": "x" } },
+        ] }
+    );
+}
+
+#[test]
+fn list_query_defaults_to_first_page() {
+    let query = ListQuery::default();
+    assert_eq!(query.page, PageRequest::first(20));
+    assert!(query.sort.is_empty() && query.filter.is_empty());
+}
+
+#[test]
+fn memory_queries_filter_sort_and_page() {
+    let items = vec![
+        serde_json::json!({ "name": "axe", "level": 3, "meta": { "tier": "rare" } }),
+        serde_json::json!({ "name": "bow", "level": 7, "meta": { "tier": "common" } }),
+        serde_json::json!({ "name": "club", "level": 5, "meta": { "tier": "rare" } }),
+        serde_json::json!({ "name": "dagger", "level": 9 }),
+    ];
+
+    let query = ListQuery::new(PageRequest::offset(0, 10))
+        .with_filter(FilterSpec::new().eq("meta.tier", "rare"))
+        .with_sort(SortSpec::new().desc("level"));
+    let page = apply_query(items.clone(), &query).unwrap();
+    assert_eq!(page.total, Some(2));
+    assert_eq!(page.items.iter().map(|item| item["name"].as_str().unwrap()).collect::<Vec<_>>(), ["club", "axe"]);
+
+    let filter = FilterSpec::new().with("level", FilterOp::Gte, 5).with("meta.tier", FilterOp::Exists, false);
+    assert!(matches_filter(&filter, &items[3]));
+    assert!(!matches_filter(&filter, &items[1]));
+
+    // Cursor pages resume after the last item's sort key
+    let sort = SortSpec::new().asc("level");
+    let first = apply_query(items.clone(), &ListQuery::new(PageRequest::cursor(None, 3)).with_sort(sort.clone())).unwrap();
+    assert!(first.has_more);
+    let query = ListQuery::new(PageRequest::cursor(first.next_cursor, 3)).with_sort(sort);
+    let rest = apply_query(items, &query).unwrap();
+    assert_eq!(rest.items.len(), 1);
+    assert_eq!(rest.items[0]["name"], "dagger");
+    assert!(!rest.has_more && rest.next_cursor.is_none());
+}
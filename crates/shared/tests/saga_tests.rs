@@ -0,0 +1,221 @@
+//! Integration tests for sagas, using a quest reward flow.
+
+mod common;
+
+use async_trait::async_trait;
+use common::{create_test_clock, start_time};
+use shared::saga::{
+    FileSagaStore, MemorySagaStore, RetryPolicy, Saga, SagaContext, SagaCoordinator, SagaState, SagaStatus,
+    SagaStep, SagaStore,
+};
+use shared::{ChaosError, ChaosResult};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// What the quest, inventory and leveling services would hold
+#[derive(Debug, Default)]
+struct World {
+    completed_quests: Vec<String>,
+    items: Vec<String>,
+    experience: u64,
+    /// Failures the leveling service returns before succeeding
+    experience_failures: u32,
+    level_capped: bool,
+    inventory_down: bool,
+}
+
+type Shared = Arc<Mutex<World>>;
+
+struct CompleteQuest(Shared);
+
+#[async_trait]
+impl SagaStep for CompleteQuest {
+    fn name(&self) -> &str {
+        "complete_quest"
+    }
+
+    async fn execute(&self, context: &mut SagaContext) -> ChaosResult<()> {
+        let quest: String = context.require("quest")?;
+        let mut world = self.0.lock().unwrap();
+        if !world.completed_quests.contains(&quest) {
+            world.completed_quests.push(quest);
+        }
+        Ok(())
+    }
+
+    async fn compensate(&self, context: &SagaContext) -> ChaosResult<()> {
+        let quest: String = context.require("quest")?;
+        self.0.lock().unwrap().completed_quests.retain(|completed| *completed != quest);
+        Ok(())
+    }
+}
+
+struct GrantItem(Shared);
+
+#[async_trait]
+impl SagaStep for GrantItem {
+    fn name(&self) -> &str {
+        "grant_item"
+    }
+
+    async fn execute(&self, context: &mut SagaContext) -> ChaosResult<()> {
+        let item_id = format!("{}-sword", context.saga_id);
+        let mut world = self.0.lock().unwrap();
+        if !world.items.contains(&item_id) {
+            world.items.push(item_id.clone());
+        }
+        context.set("item_id", item_id)
+    }
+
+    async fn compensate(&self, context: &SagaContext) -> ChaosResult<()> {
+        let item_id: String = context.require("item_id")?;
+        let mut world = self.0.lock().unwrap();
+        if world.inventory_down {
+            return Err(ChaosError::ExternalService("inventory-service is unavailable".to_string()));
+        }
+        world.items.retain(|item| *item != item_id);
+        Ok(())
+    }
+}
+
+struct AwardExperience(Shared);
+
+#[async_trait]
+impl SagaStep for AwardExperience {
+    fn name(&self) -> &str {
+        "award_experience"
+    }
+
+    async fn execute(&self, context: &mut SagaContext) -> ChaosResult<()> {
+        let amount: u64 = context.require("experience")?;
+        let mut world = self.0.lock().unwrap();
+        if world.level_capped {
+            return Err(ChaosError::Validation("Character is at the level cap".to_string()));
+        }
+        if world.experience_failures > 0 {
+            world.experience_failures -= 1;
+            return Err(ChaosError::Network("leveling-service timed out".to_string()));
+        }
+        world.experience += amount;
+        Ok(())
+    }
+
+    async fn compensate(&self, _context: &SagaContext) -> ChaosResult<()> {
+        Ok(())
+    }
+}
+
+fn quest_reward(world: &Shared) -> Saga {
+    Saga::new("quest_reward")
+        .step(CompleteQuest(world.clone()))
+        .step(GrantItem(world.clone()))
+        .step(AwardExperience(world.clone()))
+        .with_retry(RetryPolicy { max_attempts: 3, retry_backoff: Duration::from_millis(1) })
+}
+
+fn coordinator(store: Arc<dyn SagaStore>, world: &Shared) -> SagaCoordinator {
+    let clock = create_test_clock();
+    SagaCoordinator::new(store, clock).register(quest_reward(world))
+}
+
+fn context(saga_id: &str) -> SagaContext {
+    SagaContext::new(saga_id).with("quest", "wolf-hunt").unwrap().with("experience", 250u64).unwrap()
+}
+
+#[tokio::test]
+async fn completed_sagas_apply_every_step() {
+    let world = Shared::default();
+    let store = Arc::new(MemorySagaStore::default());
+    let coordinator = coordinator(store.clone(), &world);
+
+    let state = coordinator.start("quest_reward", context("reward-1")).await.unwrap();
+    assert_eq!((state.status, state.completed), (SagaStatus::Completed, 3));
+    assert_eq!(state.context.get::<String>("item_id").as_deref(), Some("reward-1-sword"));
+    assert_eq!(store.load("reward-1").await.unwrap(), Some(state));
+
+    // Starting the same saga again does not reward twice
+    coordinator.start("quest_reward", context("reward-1")).await.unwrap();
+    let world = world.lock().unwrap();
+    assert_eq!((world.completed_quests.len(), world.items.len(), world.experience), (1, 1, 250));
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried() {
+    let world = Shared::default();
+    world.lock().unwrap().experience_failures = 2;
+    let coordinator = coordinator(Arc::new(MemorySagaStore::default()), &world);
+
+    let state = coordinator.start("quest_reward", context("reward-2")).await.unwrap();
+    assert_eq!(state.status, SagaStatus::Completed);
+    assert_eq!(world.lock().unwrap().experience, 250);
+}
+
+#[tokio::test]
+async fn failed_steps_compensate_the_completed_ones() {
+    let world = Shared::default();
+    world.lock().unwrap().level_capped = true;
+    let coordinator = coordinator(Arc::new(MemorySagaStore::default()), &world);
+
+    let state = coordinator.start("quest_reward", context("reward-3")).await.unwrap();
+    assert_eq!((state.status, state.completed), (SagaStatus::Compensated, 0));
+    assert!(state.error.unwrap().starts_with("award_experience"));
+    let world = world.lock().unwrap();
+    assert!(world.completed_quests.is_empty() && world.items.is_empty());
+}
+
+#[tokio::test]
+async fn failed_compensations_leave_the_saga_failed() {
+    let world = Shared::default();
+    {
+        let mut world = world.lock().unwrap();
+        world.level_capped = true;
+        world.inventory_down = true;
+    }
+    let coordinator = coordinator(Arc::new(MemorySagaStore::default()), &world);
+
+    let state = coordinator.start("quest_reward", context("reward-4")).await.unwrap();
+    // The item could not be taken back, so the quest is left completed for a person to fix
+    assert_eq!((state.status, state.completed), (SagaStatus::Failed, 2));
+    assert!(state.error.unwrap().contains("compensating grant_item"));
+    assert_eq!(world.lock().unwrap().completed_quests, vec!["wolf-hunt".to_string()]);
+}
+
+#[tokio::test]
+async fn interrupted_sagas_are_recovered() {
+    let dir = std::env::temp_dir().join(format!("sagas-{}", uuid::Uuid::new_v4()));
+    let world = Shared::default();
+    world.lock().unwrap().completed_quests.push("wolf-hunt".to_string());
+
+    // A coordinator crashed after completing the quest
+    let now = start_time();
+    let interrupted = SagaState {
+        saga_id: "reward-5".to_string(),
+        saga: "quest_reward".to_string(),
+        status: SagaStatus::Running,
+        completed: 1,
+        context: context("reward-5"),
+        error: None,
+        started_at: now,
+        updated_at: now,
+    };
+    FileSagaStore::open(&dir).unwrap().save(&interrupted).await.unwrap();
+
+    let store = Arc::new(FileSagaStore::open(&dir).unwrap());
+    let recovered = coordinator(store.clone(), &world).recover().await.unwrap();
+    assert_eq!(recovered.len(), 1);
+    assert_eq!(recovered[0].status, SagaStatus::Completed);
+    assert!(store.unfinished().await.unwrap().is_empty());
+    {
+        let world = world.lock().unwrap();
+        assert_eq!((world.completed_quests.len(), world.items.len(), world.experience), (1, 1, 250));
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn invalid_saga_ids_are_rejected() {
+    let coordinator = coordinator(Arc::new(MemorySagaStore::default()), &Shared::default());
+    let result = coordinator.start("quest_reward", context("../reward")).await;
+    assert!(matches!(result, Err(ChaosError::Validation(_))));
+}
@@ -0,0 +1,96 @@
+//! Integration tests for graceful shutdown.
+
+use shared::health::{check_fn, HealthRegistry, HealthStatus};
+use shared::shutdown::Shutdown;
+use std::time::Duration;
+use tokio::time::Instant;
+
+#[tokio::test]
+async fn signal_resolves_once_triggered() {
+    let shutdown = Shutdown::new(Duration::from_secs(1));
+    let signal = tokio::spawn(shutdown.signal());
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!signal.is_finished());
+    assert!(!shutdown.is_triggered());
+
+    shutdown.trigger();
+    shutdown.trigger();
+    tokio::time::timeout(Duration::from_secs(1), signal).await.unwrap().unwrap();
+    assert!(shutdown.is_triggered());
+
+    // Late subscribers see the shutdown too
+    tokio::time::timeout(Duration::from_secs(1), shutdown.signal()).await.unwrap();
+}
+
+#[tokio::test]
+async fn drain_lets_work_finish_before_the_deadline() {
+    let shutdown = Shutdown::new(Duration::from_millis(200));
+    let work = async {
+        shutdown.signal().await;
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        "drained"
+    };
+    shutdown.trigger();
+    assert_eq!(shutdown.drain(work).await, Some("drained"));
+}
+
+#[tokio::test]
+async fn drain_cuts_off_work_at_the_deadline() {
+    let shutdown = Shutdown::new(Duration::from_millis(50));
+    let started = Instant::now();
+    let trigger = shutdown.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        trigger.trigger();
+    });
+
+    let output = shutdown.drain(std::future::pending::<()>()).await;
+    assert_eq!(output, None);
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn wait_idle_waits_for_tracked_work() {
+    let shutdown = Shutdown::new(Duration::from_secs(1));
+    assert!(shutdown.wait_idle().await);
+
+    let session = shutdown.track();
+    let background = shutdown.track();
+    assert_eq!(shutdown.in_flight(), 2);
+    drop(background);
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(session);
+    });
+
+    shutdown.trigger();
+    assert!(shutdown.wait_idle().await);
+    assert_eq!(shutdown.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn wait_idle_gives_up_at_the_deadline() {
+    let shutdown = Shutdown::new(Duration::from_millis(30));
+    let _stuck = shutdown.track();
+    shutdown.trigger();
+    assert!(!shutdown.wait_idle().await);
+    assert_eq!(shutdown.in_flight(), 1);
+}
+
+#[tokio::test]
+async fn readiness_fails_once_shutdown_starts() {
+    let shutdown = Shutdown::new(Duration::from_secs(1));
+    let registry = HealthRegistry::new("world-service")
+        .with_shutdown(&shutdown)
+        .register("event_bus", check_fn(|| async { Ok(()) }));
+    assert_eq!(registry.readiness().await.status, HealthStatus::Healthy);
+
+    shutdown.trigger();
+    let report = registry.readiness().await;
+    assert_eq!(report.status, HealthStatus::Unhealthy);
+    assert_eq!(report.checks["shutdown"].error.as_deref(), Some("Shutting down"));
+    assert!(!report.checks.contains_key("event_bus"));
+
+    // The process is still alive while it drains
+    assert_eq!(registry.liveness().status, HealthStatus::Healthy);
+}
@@ -0,0 +1,27 @@
+//! Integration tests for service telemetry options.
+
+#![cfg(feature = "telemetry")]
+
+use shared::telemetry::{export_span, TelemetryOptions};
+use shared::trace::TraceContext;
+
+#[test]
+fn resource_attributes_describe_the_service() {
+    let mut options = TelemetryOptions::new("world-service", "0.1.0");
+    options.instance_id = Some("world-1".to_string());
+    options.environment = Some("staging".to_string());
+
+    let attributes = options.resource_attributes();
+    assert!(attributes.contains(&("service.name", "world-service".to_string())));
+    assert!(attributes.contains(&("service.version", "0.1.0".to_string())));
+    assert!(attributes.contains(&("service.instance.id", "world-1".to_string())));
+    assert!(attributes.contains(&("deployment.environment", "staging".to_string())));
+}
+
+#[test]
+fn spans_are_not_exported_without_a_collector() {
+    // No OpenTelemetry layer is installed, so the gateway keeps its own trace context
+    let span = tracing::info_span!("request");
+    let parent = TraceContext::new_root();
+    assert_eq!(export_span(&span, Some(&parent)), None);
+}
@@ -0,0 +1,51 @@
+//! Integration tests for request IDs and trace context propagation.
+
+use shared::trace::{self, TraceContext};
+
+#[test]
+fn traceparent_round_trips() {
+    let header = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    let context = TraceContext::parse(header).unwrap();
+    assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(context.span_id, "00f067aa0ba902b7");
+    assert!(context.is_sampled());
+    assert_eq!(context.to_traceparent(), header);
+}
+
+#[test]
+fn malformed_traceparents_are_rejected() {
+    for header in [
+        "",
+        "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+        "00-4bf92f3577b34da6-00f067aa0ba902b7-01",
+    ] {
+        assert!(TraceContext::parse(header).is_none(), "accepted {:?}", header);
+    }
+}
+
+#[test]
+fn continued_trace_keeps_trace_id_with_new_span() {
+    let parent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00";
+    let context = TraceContext::continue_or_new(Some(parent));
+    assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_ne!(context.span_id, "00f067aa0ba902b7");
+    assert!(!context.is_sampled());
+    assert!(TraceContext::parse(&context.to_traceparent()).is_some());
+
+    let root = TraceContext::continue_or_new(Some("garbage"));
+    assert!(root.is_sampled());
+    assert_eq!(TraceContext::parse(&root.to_traceparent()), Some(root));
+}
+
+#[test]
+fn request_ids_are_reused_only_when_valid() {
+    assert_eq!(trace::request_id_or_new(Some("client-req-42")), "client-req-42");
+    assert_ne!(trace::request_id_or_new(Some("has space")), "has space");
+    assert_ne!(trace::request_id_or_new(Some(&"x".repeat(200))), "x".repeat(200));
+    assert!(trace::is_valid_request_id(&trace::request_id_or_new(None)));
+}
@@ -0,0 +1,140 @@
+//! Integration tests for actor wallets.
+
+mod common;
+
+use common::create_test_clock;
+use shared::error::ChaosError;
+use shared::events::topics::{CurrencyChanged, CURRENCY_CHANGED};
+use shared::events::{EventBus, InProcessEventBus};
+use shared::market::Wallet;
+use shared::query::{FilterSpec, ListQuery, PageRequest, SortSpec};
+use shared::wallet::{CurrencyWallet, ExchangeRule, MemoryWalletStore, TransactionKind, WalletConfig, Wallets};
+use std::sync::Arc;
+
+fn wallets() -> Wallets {
+    let clock = create_test_clock();
+    Wallets::new(WalletConfig::default(), clock, Arc::new(MemoryWalletStore::new()))
+}
+
+#[tokio::test]
+async fn earning_stops_at_the_cap_and_spending_at_zero() {
+    let wallets = wallets();
+    let entry = wallets.earn("hero", "honor", 70_000, "arena", None).await.unwrap();
+    assert_eq!((entry.delta, entry.balance, entry.kind), (70_000, 70_000, TransactionKind::Earn));
+    // Only what fits under the cap is credited
+    let entry = wallets.earn("hero", "honor", 10_000, "arena", None).await.unwrap();
+    assert_eq!((entry.delta, entry.balance), (5_000, 75_000));
+
+    assert!(matches!(wallets.spend("hero", "gold", 1, "repair", None).await, Err(ChaosError::Validation(_))));
+    let entry = wallets.spend("hero", "honor", 25_000, "vendor:arena", None).await.unwrap();
+    assert_eq!((entry.delta, entry.balance), (-25_000, 50_000));
+    assert!(wallets.earn("hero", "doubloons", 1, "pirates", None).await.is_err());
+    assert_eq!(wallets.balance("hero", "honor").await.unwrap(), 50_000);
+}
+
+#[tokio::test]
+async fn exchanges_follow_the_configured_rates() {
+    let wallets = wallets();
+    wallets.earn("hero", "honor", 1_000, "arena", None).await.unwrap();
+
+    assert!(wallets.exchange("hero", "honor", "gold", 15).await.is_err());
+    assert!(wallets.exchange("hero", "gold", "honor", 10).await.is_err());
+    assert!(wallets.exchange("hero", "honor", "gold", 2_000).await.is_err());
+    let entries = wallets.exchange("hero", "honor", "gold", 500).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].transaction_id, entries[1].transaction_id);
+
+    let wallet = wallets.wallet("hero").await.unwrap();
+    assert_eq!((wallet.balance("honor"), wallet.balance("gold")), (500, 50));
+    assert_eq!(wallet.version, 2);
+}
+
+#[tokio::test]
+async fn exchanges_refuse_to_overflow_the_cap() {
+    let mut config = WalletConfig::default();
+    config.exchanges.push(ExchangeRule {
+        from: "gold".to_string(),
+        to: "season_token".to_string(),
+        from_amount: 1,
+        to_amount: 1,
+    });
+    config.validate().unwrap();
+    let clock = create_test_clock();
+    let wallets = Wallets::new(config, clock, Arc::new(MemoryWalletStore::new()));
+    wallets.earn("hero", "gold", 6_000, "loot", None).await.unwrap();
+
+    assert!(wallets.exchange("hero", "gold", "season_token", 5_001).await.is_err());
+    // Nothing changed
+    assert_eq!(wallets.balance("hero", "gold").await.unwrap(), 6_000);
+    wallets.exchange("hero", "gold", "season_token", 5_000).await.unwrap();
+    assert_eq!(wallets.balance("hero", "season_token").await.unwrap(), 5_000);
+}
+
+#[tokio::test]
+async fn concurrent_operations_on_one_actor_all_apply() {
+    let wallets = Arc::new(wallets());
+    let tasks: Vec<_> = (0..20)
+        .map(|_| {
+            let wallets = wallets.clone();
+            tokio::spawn(async move { wallets.earn("hero", "gold", 5, "loot", None).await })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap().unwrap();
+    }
+    assert_eq!(wallets.balance("hero", "gold").await.unwrap(), 100);
+}
+
+#[tokio::test]
+async fn the_ledger_records_every_change() {
+    let wallets = Arc::new(wallets());
+    wallets.earn("hero", "gold", 100, "quest:1042", None).await.unwrap();
+    wallets.earn("sidekick", "gold", 100, "quest:1042", None).await.unwrap();
+    // The market spends through the same wallets
+    let market_wallet = CurrencyWallet::new(wallets.clone(), "gold");
+    market_wallet.withdraw("hero", 30, "market:listing-1:deposit").await.unwrap();
+    assert!(market_wallet.withdraw("hero", 300, "market:listing-2:deposit").await.is_err());
+
+    let query = ListQuery::new(PageRequest::first(10))
+        .with_filter(FilterSpec::new().eq("actor_id", "hero"))
+        .with_sort(SortSpec::new().desc("delta"));
+    let page = wallets.ledger(&query).await.unwrap();
+    assert_eq!(page.items.iter().map(|entry| entry.delta).collect::<Vec<_>>(), [100, -30]);
+    assert_eq!(page.items[1].reference.as_deref(), Some("market:listing-1:deposit"));
+    assert_eq!(page.items[1].balance, 70);
+
+    let query = ListQuery::new(PageRequest::first(10)).with_filter(FilterSpec::new().eq("balance", 70));
+    assert!(wallets.ledger(&query).await.is_err());
+}
+
+#[tokio::test]
+async fn committed_changes_are_published() {
+    let bus = Arc::new(InProcessEventBus::new());
+    let mut changes = bus.subscribe_topic(CURRENCY_CHANGED.name()).await.unwrap();
+    let wallets = wallets().with_bus(bus.clone(), "wallets");
+
+    wallets.earn("hero", "honor", 100, "arena:season-3", None).await.unwrap();
+    assert!(wallets.spend("hero", "honor", 500, "vendor", None).await.is_err());
+    wallets.exchange("hero", "honor", "gold", 100).await.unwrap();
+
+    let earned: CurrencyChanged = changes.next().await.unwrap().decode_message().unwrap();
+    assert_eq!((earned.delta, earned.kind, earned.source()), (100, TransactionKind::Earn, "arena"));
+    // Both sides of the exchange, and nothing for the refused spend
+    let sold: CurrencyChanged = changes.next().await.unwrap().decode_message().unwrap();
+    let bought: CurrencyChanged = changes.next().await.unwrap().decode_message().unwrap();
+    assert_eq!((sold.currency.as_str(), sold.delta), ("honor", -100));
+    assert_eq!((bought.currency.as_str(), bought.delta), ("gold", 10));
+    assert_eq!(sold.transaction_id, bought.transaction_id);
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    assert!(WalletConfig::default().validate().is_ok());
+    let mut config = WalletConfig::default();
+    config.currencies.push(config.currencies[0].clone());
+    assert!(config.validate().is_err());
+
+    let mut config = WalletConfig::default();
+    config.exchanges[0].to = "gems".to_string();
+    assert!(config.validate().is_err());
+}
@@ -0,0 +1,408 @@
+//! NPC behavior trees.
+//!
+//! Trees are defined in YAML ([`TreeDefinition`]) and compiled once into a
+//! [`BehaviorTree`] every NPC of a template shares. Each NPC gets a [`Brain`]: its
+//! [`Blackboard`] and where it is in the tree. An [`AiDirector`] owns the brains of one
+//! zone; the host ticks it after the zone itself and tells it as NPCs spawn and leave.
+//!
+//! Composites keep their place: a sequence whose second child is still running resumes
+//! there on the next tick rather than re-checking the first. Leaves reach the rest of the
+//! game through the host:
+//! - `action` leaves go to the host's [`NpcActions`], which moves NPCs or calls into combat
+//! - `sense` leaves query the zone through [`Perception`]
+//! - `condition` leaves check condition-core chains, e.g. a target's health, through the
+//!   resolver given to [`AiDirector::with_conditions`]; without one they fail
+
+pub mod tree;
+
+pub use tree::{BehaviorTree, NodeDefinition, TreeDefinition};
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Timelike;
+use condition_core::{ActorTarget, ConditionContext, ConditionResolverTrait, WeatherType, WorldState};
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+use tracing::warn;
+
+use crate::enums::WeatherKind;
+use crate::error::{WorldCoreError, WorldCoreResult};
+use crate::services::ZoneRuntime;
+use crate::types::{NearbyEntity, Vec3};
+use tree::Node;
+
+/// Result of ticking a node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeStatus {
+    Success,
+    Failure,
+    /// Not finished; ticked again next time
+    Running,
+}
+
+/// What an NPC remembers between ticks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Blackboard {
+    values: HashMap<String, serde_json::Value>,
+}
+
+impl Blackboard {
+    pub fn get(&self, key: &str) -> Option<&serde_json::Value> {
+        self.values.get(key)
+    }
+
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        self.values.get(key).and_then(|value| value.as_str())
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<serde_json::Value>) {
+        self.values.insert(key.into(), value.into());
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.values.remove(key)
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+}
+
+/// The NPC a leaf runs for
+#[derive(Debug, Clone, Copy)]
+pub struct NpcContext<'a> {
+    pub npc_id: &'a str,
+    pub zone_id: &'a str,
+    pub now: Timestamp,
+}
+
+/// What NPCs can see of their zone
+pub trait Perception: Send + Sync {
+    fn position(&self, entity_id: &str) -> Option<Vec3>;
+    /// Entities within `radius` of `center`, closest first
+    fn nearby(&self, center: Vec3, radius: f64, limit: usize) -> Vec<NearbyEntity>;
+    fn is_npc(&self, entity_id: &str) -> bool;
+    fn weather(&self) -> WeatherKind;
+    fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool;
+}
+
+impl Perception for ZoneRuntime {
+    fn position(&self, entity_id: &str) -> Option<Vec3> {
+        ZoneRuntime::position(self, entity_id)
+    }
+
+    fn nearby(&self, center: Vec3, radius: f64, limit: usize) -> Vec<NearbyEntity> {
+        ZoneRuntime::nearby(self, center, radius, limit)
+    }
+
+    fn is_npc(&self, entity_id: &str) -> bool {
+        self.is_spawned(entity_id)
+    }
+
+    fn weather(&self) -> WeatherKind {
+        ZoneRuntime::weather(self).current
+    }
+
+    fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.has_line_of_sight(from, to)
+    }
+}
+
+/// Carries out `action` leaves
+#[async_trait]
+pub trait NpcActions: Send + Sync {
+    /// Perform an action, e.g. `attack` with `{ skill: bite }`
+    async fn perform(
+        &self,
+        npc: NpcContext<'_>,
+        action: &str,
+        params: &serde_json::Value,
+        blackboard: &mut Blackboard,
+    ) -> NodeStatus;
+}
+
+/// Entities considered by `sense` leaves
+const SENSE_LIMIT: usize = 16;
+
+/// One NPC running a tree
+#[derive(Debug, Clone)]
+pub struct Brain {
+    tree: Arc<BehaviorTree>,
+    pub blackboard: Blackboard,
+    /// Child a running selector or sequence resumes at
+    resume_at: HashMap<usize, usize>,
+    /// Iterations a repeat node has completed
+    repeated: HashMap<usize, u32>,
+    /// When a cooling-down node may run again
+    ready_at: HashMap<usize, Timestamp>,
+}
+
+/// Everything a tick of one brain needs besides the brain itself
+struct TickContext<'a> {
+    npc: NpcContext<'a>,
+    perception: &'a dyn Perception,
+    actions: &'a dyn NpcActions,
+    conditions: Option<&'a (dyn ConditionResolverTrait + Send + Sync)>,
+}
+
+type NodeFuture<'a> = Pin<Box<dyn Future<Output = NodeStatus> + Send + 'a>>;
+
+impl Brain {
+    pub fn new(tree: Arc<BehaviorTree>) -> Self {
+        Self {
+            tree,
+            blackboard: Blackboard::default(),
+            resume_at: HashMap::new(),
+            repeated: HashMap::new(),
+            ready_at: HashMap::new(),
+        }
+    }
+
+    pub fn tree(&self) -> &BehaviorTree {
+        &self.tree
+    }
+
+    fn tick<'a>(&'a mut self, index: usize, cx: &'a TickContext<'a>) -> NodeFuture<'a> {
+        Box::pin(async move {
+            let tree = self.tree.clone();
+            match &tree.nodes[index] {
+                Node::Selector(children) => self.composite(index, children, NodeStatus::Failure, cx).await,
+                Node::Sequence(children) => self.composite(index, children, NodeStatus::Success, cx).await,
+                Node::Inverter(child) => match self.tick(*child, cx).await {
+                    NodeStatus::Success => NodeStatus::Failure,
+                    NodeStatus::Failure => NodeStatus::Success,
+                    NodeStatus::Running => NodeStatus::Running,
+                },
+                Node::Succeeder(child) => match self.tick(*child, cx).await {
+                    NodeStatus::Running => NodeStatus::Running,
+                    _ => NodeStatus::Success,
+                },
+                Node::Repeat { times, child } => match self.tick(*child, cx).await {
+                    NodeStatus::Success => {
+                        let repeated = self.repeated.entry(index).or_default();
+                        *repeated += 1;
+                        if times.is_some_and(|times| *repeated >= times) {
+                            self.repeated.remove(&index);
+                            NodeStatus::Success
+                        } else {
+                            NodeStatus::Running
+                        }
+                    }
+                    NodeStatus::Failure => {
+                        self.repeated.remove(&index);
+                        // Repeating until failure is how an open-ended repeat finishes
+                        match times {
+                            Some(_) => NodeStatus::Failure,
+                            None => NodeStatus::Success,
+                        }
+                    }
+                    NodeStatus::Running => NodeStatus::Running,
+                },
+                Node::Cooldown { secs, child } => {
+                    if self.ready_at.get(&index).is_some_and(|ready_at| cx.npc.now < *ready_at) {
+                        return NodeStatus::Failure;
+                    }
+                    let status = self.tick(*child, cx).await;
+                    if status == NodeStatus::Success {
+                        let cooldown = chrono::Duration::milliseconds((secs * 1000.0) as i64);
+                        self.ready_at.insert(index, cx.npc.now + cooldown);
+                    }
+                    status
+                }
+                Node::Action { name, params } => cx.actions.perform(cx.npc, name, params, &mut self.blackboard).await,
+                Node::Sense { radius, store_as, include_npcs, line_of_sight } => {
+                    let Some(position) = cx.perception.position(cx.npc.npc_id) else {
+                        return NodeStatus::Failure;
+                    };
+                    let sensed = cx
+                        .perception
+                        .nearby(position, *radius, SENSE_LIMIT)
+                        .into_iter()
+                        .find(|entity| {
+                            entity.entity_id != cx.npc.npc_id
+                                && (*include_npcs || !cx.perception.is_npc(&entity.entity_id))
+                                && (!*line_of_sight || cx.perception.line_of_sight(position, entity.position))
+                        });
+                    match sensed {
+                        Some(entity) => {
+                            self.blackboard.set(store_as.clone(), entity.entity_id);
+                            NodeStatus::Success
+                        }
+                        None => {
+                            self.blackboard.remove(store_as);
+                            NodeStatus::Failure
+                        }
+                    }
+                }
+                Node::Has(key) => status(self.blackboard.contains(key)),
+                Node::Condition { chain, target } => {
+                    let Some(resolver) = cx.conditions else {
+                        return NodeStatus::Failure;
+                    };
+                    let target_id = match target {
+                        Some(key) => match self.blackboard.get_str(key) {
+                            Some(target_id) => target_id.to_string(),
+                            None => return NodeStatus::Failure,
+                        },
+                        None => cx.npc.npc_id.to_string(),
+                    };
+                    let context = condition_context(&target_id, cx.npc, cx.perception.weather());
+                    match resolver.resolve_condition_chain(chain, &context).await {
+                        Ok(passed) => status(passed),
+                        Err(e) => {
+                            warn!(npc_id = cx.npc.npc_id, chain = %chain.chain_id, "Condition failed to resolve: {}", e);
+                            NodeStatus::Failure
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Run children in order from where the node left off, stopping at the first that does
+    /// not return `pass_on`
+    async fn composite(
+        &mut self,
+        index: usize,
+        children: &[usize],
+        pass_on: NodeStatus,
+        cx: &TickContext<'_>,
+    ) -> NodeStatus {
+        let start = self.resume_at.remove(&index).unwrap_or(0);
+        for (position, child) in children.iter().enumerate().skip(start) {
+            match self.tick(*child, cx).await {
+                status if status == pass_on => continue,
+                NodeStatus::Running => {
+                    self.resume_at.insert(index, position);
+                    return NodeStatus::Running;
+                }
+                status => return status,
+            }
+        }
+        pass_on
+    }
+}
+
+fn status(passed: bool) -> NodeStatus {
+    if passed {
+        NodeStatus::Success
+    } else {
+        NodeStatus::Failure
+    }
+}
+
+/// Condition-core context for checking `target_id` from an NPC's zone
+fn condition_context(target_id: &str, npc: NpcContext<'_>, weather: WeatherKind) -> ConditionContext {
+    ConditionContext {
+        target: ActorTarget { id: target_id.to_string() },
+        world_id: npc.zone_id.to_string(),
+        current_time: npc.now.into(),
+        current_weather: match weather {
+            WeatherKind::Clear | WeatherKind::Cloudy => WeatherType::Clear,
+            WeatherKind::Rain => WeatherType::Rain,
+            WeatherKind::Storm => WeatherType::Storm,
+            WeatherKind::Snow => WeatherType::Snow,
+            WeatherKind::Fog => WeatherType::Fog,
+        },
+        world_state: WorldState {
+            time_of_day: npc.now.hour() as f64 + npc.now.minute() as f64 / 60.0,
+            season: "default".to_string(),
+            temperature: 20.0,
+            humidity: 0.5,
+        },
+    }
+}
+
+/// Which tree each NPC template runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AiConfig {
+    pub trees: Vec<TreeDefinition>,
+    /// Tree id per NPC template; templates without one stand idle
+    pub templates: HashMap<String, String>,
+}
+
+/// The brains of one zone's NPCs
+pub struct AiDirector {
+    zone_id: String,
+    trees: HashMap<String, Arc<BehaviorTree>>,
+    templates: HashMap<String, String>,
+    brains: HashMap<String, Brain>,
+    conditions: Option<Arc<dyn ConditionResolverTrait + Send + Sync>>,
+}
+
+impl AiDirector {
+    /// Compile the configured trees for a zone; every template must name a defined tree
+    pub fn new(zone_id: impl Into<String>, config: &AiConfig) -> WorldCoreResult<Self> {
+        let mut trees = HashMap::new();
+        for definition in &config.trees {
+            let tree = BehaviorTree::compile(definition)?;
+            if trees.insert(tree.id().to_string(), Arc::new(tree)).is_some() {
+                return Err(WorldCoreError::invalid_tree(&definition.id, "the tree id is defined twice"));
+            }
+        }
+        for (template, tree_id) in &config.templates {
+            if !trees.contains_key(tree_id) {
+                return Err(WorldCoreError::invalid_tree(tree_id, format!("template {} runs an undefined tree", template)));
+            }
+        }
+        Ok(Self {
+            zone_id: zone_id.into(),
+            trees,
+            templates: config.templates.clone(),
+            brains: HashMap::new(),
+            conditions: None,
+        })
+    }
+
+    /// Resolve `condition` leaves with condition-core
+    pub fn with_conditions(mut self, resolver: Arc<dyn ConditionResolverTrait + Send + Sync>) -> Self {
+        self.conditions = Some(resolver);
+        self
+    }
+
+    /// Give a spawned NPC the brain of its template; returns whether it has one
+    pub fn spawned(&mut self, npc_id: &str, template: &str) -> bool {
+        let Some(tree) = self.templates.get(template).and_then(|tree_id| self.trees.get(tree_id)) else {
+            return false;
+        };
+        self.brains.insert(npc_id.to_string(), Brain::new(tree.clone()));
+        true
+    }
+
+    /// Forget an NPC that left the zone
+    pub fn despawned(&mut self, npc_id: &str) {
+        self.brains.remove(npc_id);
+    }
+
+    pub fn brain(&self, npc_id: &str) -> Option<&Brain> {
+        self.brains.get(npc_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.brains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.brains.is_empty()
+    }
+
+    /// Tick every brain once
+    pub async fn tick(&mut self, perception: &dyn Perception, actions: &dyn NpcActions, now: Timestamp) {
+        let zone_id = self.zone_id.as_str();
+        for (npc_id, brain) in self.brains.iter_mut() {
+            let cx = TickContext {
+                npc: NpcContext { npc_id, zone_id, now },
+                perception,
+                actions,
+                conditions: self.conditions.as_deref(),
+            };
+            brain.tick(0, &cx).await;
+        }
+    }
+}
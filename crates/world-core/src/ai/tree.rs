@@ -0,0 +1,181 @@
+//! Behavior tree definitions, as written in YAML, and their compiled form.
+
+use condition_core::ConditionChainConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{WorldCoreError, WorldCoreResult};
+
+/// A behavior tree as defined in YAML
+///
+/// ```yaml
+/// id: forest_wolf
+/// root:
+///   selector:
+///     - sequence:
+///         - sense: { radius: 15, store_as: target }
+///         - action: { name: attack, params: { skill: bite } }
+///     - cooldown:
+///         secs: 5
+///         child: { action: { name: wander, params: { radius: 8 } } }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeDefinition {
+    pub id: String,
+    /// Nodes are single-key maps such as `selector: [...]` rather than YAML tags
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub root: NodeDefinition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeDefinition {
+    /// Runs children in order until one does not fail
+    Selector(Vec<NodeDefinition>),
+    /// Runs children in order until one does not succeed
+    Sequence(Vec<NodeDefinition>),
+    /// Turns success into failure and back
+    Inverter(Box<NodeDefinition>),
+    /// Succeeds whether its child succeeds or fails
+    Succeeder(Box<NodeDefinition>),
+    /// Runs its child once per tick `times` times, or until it fails when unset
+    Repeat {
+        #[serde(default)]
+        times: Option<u32>,
+        child: Box<NodeDefinition>,
+    },
+    /// Fails for `secs` after its child succeeded
+    Cooldown { secs: f64, child: Box<NodeDefinition> },
+    /// Leaf handled by the host's [`NpcActions`](super::NpcActions), e.g. `attack` or `move_to`
+    Action {
+        name: String,
+        #[serde(default)]
+        params: serde_json::Value,
+    },
+    /// Stores the closest entity within `radius` under `store_as`, failing when there is none
+    Sense {
+        radius: f64,
+        store_as: String,
+        /// Whether other NPCs count; only players do by default
+        #[serde(default)]
+        include_npcs: bool,
+        /// Whether only entities in line of sight count
+        #[serde(default)]
+        line_of_sight: bool,
+    },
+    /// Succeeds when the blackboard holds the key
+    Has(String),
+    /// Checks a condition-core chain against the entity stored under `target`, or the NPC itself
+    Condition {
+        chain: ConditionChainConfig,
+        #[serde(default)]
+        target: Option<String>,
+    },
+}
+
+/// Node of a compiled tree; children are indices into [`BehaviorTree::nodes`]
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Selector(Vec<usize>),
+    Sequence(Vec<usize>),
+    Inverter(usize),
+    Succeeder(usize),
+    Repeat { times: Option<u32>, child: usize },
+    Cooldown { secs: f64, child: usize },
+    Action { name: String, params: serde_json::Value },
+    Sense { radius: f64, store_as: String, include_npcs: bool, line_of_sight: bool },
+    Has(String),
+    Condition { chain: ConditionChainConfig, target: Option<String> },
+}
+
+/// A validated tree, shared by every NPC running it
+#[derive(Debug, Clone)]
+pub struct BehaviorTree {
+    id: String,
+    pub(crate) nodes: Vec<Node>,
+}
+
+impl BehaviorTree {
+    /// Compile a tree; the root is node 0
+    pub fn compile(definition: &TreeDefinition) -> WorldCoreResult<Self> {
+        let mut tree = Self { id: definition.id.clone(), nodes: Vec::new() };
+        if tree.id.is_empty() {
+            return Err(WorldCoreError::invalid_tree(&tree.id, "the tree id is empty"));
+        }
+        tree.add(&definition.root)?;
+        Ok(tree)
+    }
+
+    pub fn from_yaml(yaml: &str) -> WorldCoreResult<Self> {
+        let definition: TreeDefinition =
+            serde_yaml::from_str(yaml).map_err(|e| WorldCoreError::invalid_tree("<yaml>", e.to_string()))?;
+        Self::compile(&definition)
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Add a node and its subtree, returning its index
+    fn add(&mut self, definition: &NodeDefinition) -> WorldCoreResult<usize> {
+        let index = self.nodes.len();
+        // Reserve the slot so parents come before their children
+        self.nodes.push(Node::Has(String::new()));
+        let node = match definition {
+            NodeDefinition::Selector(children) | NodeDefinition::Sequence(children) => {
+                if children.is_empty() {
+                    return Err(WorldCoreError::invalid_tree(&self.id, "selectors and sequences need children"));
+                }
+                let children = children.iter().map(|child| self.add(child)).collect::<WorldCoreResult<Vec<_>>>()?;
+                match definition {
+                    NodeDefinition::Selector(_) => Node::Selector(children),
+                    _ => Node::Sequence(children),
+                }
+            }
+            NodeDefinition::Inverter(child) => Node::Inverter(self.add(child)?),
+            NodeDefinition::Succeeder(child) => Node::Succeeder(self.add(child)?),
+            NodeDefinition::Repeat { times, child } => {
+                if *times == Some(0) {
+                    return Err(WorldCoreError::invalid_tree(&self.id, "repeat needs times of at least 1"));
+                }
+                Node::Repeat { times: *times, child: self.add(child)? }
+            }
+            NodeDefinition::Cooldown { secs, child } => {
+                if !(secs.is_finite() && *secs >= 0.0) {
+                    return Err(WorldCoreError::invalid_tree(&self.id, "cooldown secs must be non-negative"));
+                }
+                Node::Cooldown { secs: *secs, child: self.add(child)? }
+            }
+            NodeDefinition::Action { name, params } => {
+                if name.is_empty() {
+                    return Err(WorldCoreError::invalid_tree(&self.id, "actions need a name"));
+                }
+                Node::Action { name: name.clone(), params: params.clone() }
+            }
+            NodeDefinition::Sense { radius, store_as, include_npcs, line_of_sight } => {
+                if !(radius.is_finite() && *radius > 0.0) || store_as.is_empty() {
+                    return Err(WorldCoreError::invalid_tree(&self.id, "sense needs a positive radius and a key"));
+                }
+                Node::Sense {
+                    radius: *radius,
+                    store_as: store_as.clone(),
+                    include_npcs: *include_npcs,
+                    line_of_sight: *line_of_sight,
+                }
+            }
+            NodeDefinition::Has(key) => Node::Has(key.clone()),
+            NodeDefinition::Condition { chain, target } => {
+                Node::Condition { chain: chain.clone(), target: target.clone() }
+            }
+        };
+        self.nodes[index] = node;
+        Ok(index)
+    }
+}
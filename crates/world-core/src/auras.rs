@@ -0,0 +1,433 @@
+//! Auras: buffs and debuffs projected on the entities around a point or an actor.
+//!
+//! Area auras are part of a zone's definition, e.g. a shrine blessing everyone near it;
+//! actors carry auras attached at runtime, e.g. a paladin's devotion aura, which can be
+//! limited to the carrier's party. The zone runtime re-evaluates them once per tick from
+//! its spatial index, not on every position update, and only the auras whose area saw
+//! movement since the last tick; party auras are always re-evaluated as membership
+//! changes without anyone moving.
+//!
+//! What each entity is under is kept in an [`AuraBook`], shared between zones, and turned
+//! into stat contributions by the [`AuraSubsystem`]. Several sources of the same aura
+//! follow its [`AuraStacking`] rule.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use shared::party::{BuffMode, PartyManager};
+
+use actor_core::aggregation_context::AggregationContext;
+use actor_core::enums::Bucket;
+use actor_core::interfaces::Subsystem;
+use actor_core::types::{Actor, Contribution, SubsystemOutput};
+use actor_core::ActorCoreResult;
+
+use crate::services::ZoneEvent;
+use crate::types::Vec3;
+use crate::zones::SpatialIndex;
+
+/// System ID of the aura subsystem
+pub const AURAS_SYSTEM_ID: &str = "auras";
+
+/// Who an aura applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuraTargets {
+    /// Every entity in range
+    #[default]
+    Everyone,
+    /// The carrier and the members of its party in range
+    Party,
+}
+
+/// How several sources of the same aura on one entity combine
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum AuraStacking {
+    /// Only the strongest source counts
+    #[default]
+    Strongest,
+    /// The strongest `max_stacks` sources all count
+    Stack { max_stacks: u32 },
+}
+
+/// Change an aura makes to a stat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuraEffect {
+    pub stat: String,
+    pub value: f64,
+    pub mode: BuffMode,
+}
+
+/// A buff or debuff applied to the entities within `radius` of its source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuraDefinition {
+    pub id: String,
+    pub radius: f64,
+    pub effects: Vec<AuraEffect>,
+    #[serde(default)]
+    pub targets: AuraTargets,
+    #[serde(default)]
+    pub stacking: AuraStacking,
+}
+
+impl AuraDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.is_empty() {
+            return Err("an aura id is empty".to_string());
+        }
+        if !(self.radius.is_finite() && self.radius > 0.0) {
+            return Err(format!("aura {} needs a positive radius", self.id));
+        }
+        if self.effects.is_empty() || self.effects.iter().any(|effect| !effect.value.is_finite()) {
+            return Err(format!("aura {} needs finite effects", self.id));
+        }
+        if self.stacking == (AuraStacking::Stack { max_stacks: 0 }) {
+            return Err(format!("aura {} must allow at least one stack", self.id));
+        }
+        Ok(())
+    }
+
+    /// Magnitude of the aura, ranking sources under the stacking rules
+    fn strength(&self) -> f64 {
+        self.effects
+            .iter()
+            .map(|effect| match effect.mode {
+                BuffMode::Add => effect.value.abs(),
+                BuffMode::Multiply => (effect.value - 1.0).abs(),
+            })
+            .sum()
+    }
+}
+
+/// An aura around a fixed point of a zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AreaAura {
+    /// Source of the aura, unique in the zone, e.g. `moon_shrine`
+    pub id: String,
+    pub center: Vec3,
+    pub aura: AuraDefinition,
+}
+
+/// An aura an entity is under
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppliedAura {
+    /// Area aura or carrier projecting it
+    pub source_id: String,
+    pub aura: Arc<AuraDefinition>,
+}
+
+/// Auras every entity is under, across the zones sharing the book
+#[derive(Debug, Default)]
+pub struct AuraBook {
+    applied: Mutex<HashMap<String, Vec<AppliedAura>>>,
+}
+
+impl AuraBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Auras an entity is under, from every source
+    pub fn applied(&self, entity_id: &str) -> Vec<AppliedAura> {
+        self.lock().get(entity_id).cloned().unwrap_or_default()
+    }
+
+    /// Effects on an entity once the stacking rules are applied
+    pub fn effects(&self, entity_id: &str) -> Vec<AuraEffect> {
+        let mut by_aura: BTreeMap<&str, Vec<&AppliedAura>> = BTreeMap::new();
+        let applied = self.lock();
+        for aura in applied.get(entity_id).into_iter().flatten() {
+            by_aura.entry(aura.aura.id.as_str()).or_default().push(aura);
+        }
+        let mut effects = Vec::new();
+        for mut sources in by_aura.into_values() {
+            sources.sort_by(|a, b| {
+                b.aura.strength().total_cmp(&a.aura.strength()).then_with(|| a.source_id.cmp(&b.source_id))
+            });
+            let counted = match sources[0].aura.stacking {
+                AuraStacking::Strongest => 1,
+                AuraStacking::Stack { max_stacks } => max_stacks as usize,
+            };
+            effects.extend(sources.into_iter().take(counted).flat_map(|source| source.aura.effects.iter().cloned()));
+        }
+        effects
+    }
+
+    fn apply(&self, entity_id: &str, source_id: &str, aura: &Arc<AuraDefinition>) {
+        let mut applied = self.lock();
+        let auras = applied.entry(entity_id.to_string()).or_default();
+        auras.retain(|applied| !(applied.source_id == source_id && applied.aura.id == aura.id));
+        auras.push(AppliedAura { source_id: source_id.to_string(), aura: aura.clone() });
+    }
+
+    fn remove(&self, entity_id: &str, source_id: &str, aura_id: &str) {
+        let mut applied = self.lock();
+        if let Some(auras) = applied.get_mut(entity_id) {
+            auras.retain(|applied| !(applied.source_id == source_id && applied.aura.id == aura_id));
+            if auras.is_empty() {
+                applied.remove(entity_id);
+            }
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Vec<AppliedAura>>> {
+        self.applied.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Subsystem adding the effects of the auras an actor is under
+pub struct AuraSubsystem {
+    book: Arc<AuraBook>,
+    priority: i64,
+}
+
+impl AuraSubsystem {
+    pub fn new(book: Arc<AuraBook>) -> Self {
+        Self { book, priority: 140 }
+    }
+
+    /// Set the subsystem priority
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[async_trait]
+impl Subsystem for AuraSubsystem {
+    fn system_id(&self) -> &str {
+        AURAS_SYSTEM_ID
+    }
+
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    async fn contribute(&self, actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(AURAS_SYSTEM_ID.to_string());
+        for effect in self.book.effects(&actor.id) {
+            let bucket = match effect.mode {
+                BuffMode::Add => Bucket::Flat,
+                BuffMode::Multiply => Bucket::Mult,
+            };
+            output.add_contribution(Contribution::new(effect.stat, bucket, effect.value, AURAS_SYSTEM_ID.to_string()));
+        }
+        Ok(output)
+    }
+}
+
+#[derive(Debug)]
+enum Anchor {
+    Point(Vec3),
+    Carrier(String),
+}
+
+#[derive(Debug)]
+struct AuraSource {
+    anchor: Anchor,
+    aura: Arc<AuraDefinition>,
+    /// Entities under the aura
+    inside: HashSet<String>,
+    /// Attached or replaced since the last evaluation
+    fresh: bool,
+    detached: bool,
+}
+
+/// Sources of the auras in one zone and who they apply to
+#[derive(Debug)]
+pub(crate) struct AuraField {
+    /// Sources by source and aura id
+    sources: BTreeMap<(String, String), AuraSource>,
+    /// Entities moved since the last evaluation, with where they were before; `None` when
+    /// they entered the zone
+    moved: HashMap<String, Option<Vec3>>,
+    book: Arc<AuraBook>,
+}
+
+impl AuraField {
+    pub(crate) fn new(areas: &[AreaAura], book: Arc<AuraBook>) -> Self {
+        let sources = areas
+            .iter()
+            .map(|area| {
+                let source = AuraSource {
+                    anchor: Anchor::Point(area.center),
+                    aura: Arc::new(area.aura.clone()),
+                    inside: HashSet::new(),
+                    fresh: true,
+                    detached: false,
+                };
+                ((area.id.clone(), area.aura.id.clone()), source)
+            })
+            .collect();
+        Self { sources, moved: HashMap::new(), book }
+    }
+
+    pub(crate) fn book(&self) -> &Arc<AuraBook> {
+        &self.book
+    }
+
+    /// Attach an aura to a carrier, replacing the one with the same id
+    pub(crate) fn attach(&mut self, carrier_id: &str, aura: AuraDefinition) {
+        let key = (carrier_id.to_string(), aura.id.clone());
+        let aura = Arc::new(aura);
+        let source = self.sources.entry(key).or_insert_with(|| AuraSource {
+            anchor: Anchor::Carrier(carrier_id.to_string()),
+            aura: aura.clone(),
+            inside: HashSet::new(),
+            fresh: true,
+            detached: false,
+        });
+        for entity_id in &source.inside {
+            self.book.apply(entity_id, carrier_id, &aura);
+        }
+        source.aura = aura;
+        source.fresh = true;
+        source.detached = false;
+    }
+
+    /// Stop a carrier's aura on the next evaluation
+    pub(crate) fn detach(&mut self, carrier_id: &str, aura_id: &str) -> bool {
+        match self.sources.get_mut(&(carrier_id.to_string(), aura_id.to_string())) {
+            Some(source) if matches!(source.anchor, Anchor::Carrier(_)) && !source.detached => {
+                source.detached = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Auras a carrier carries, detached ones aside
+    pub(crate) fn carried(&self, carrier_id: &str) -> Vec<AuraDefinition> {
+        self.sources
+            .values()
+            .filter(|source| matches!(&source.anchor, Anchor::Carrier(id) if id == carrier_id) && !source.detached)
+            .map(|source| source.aura.as_ref().clone())
+            .collect()
+    }
+
+    /// Auras every carrier carries, detached ones aside
+    pub(crate) fn all_carried(&self) -> Vec<(String, AuraDefinition)> {
+        self.sources
+            .values()
+            .filter_map(|source| match &source.anchor {
+                Anchor::Carrier(carrier_id) if !source.detached => {
+                    Some((carrier_id.clone(), source.aura.as_ref().clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Make the carried auras those of another copy of the zone: attach the new or
+    /// changed ones and detach those it no longer has
+    pub(crate) fn sync_carried(&mut self, carried: Vec<(String, AuraDefinition)>) {
+        let kept: HashSet<(String, String)> =
+            carried.iter().map(|(carrier_id, aura)| (carrier_id.clone(), aura.id.clone())).collect();
+        let gone: Vec<(String, String)> = self
+            .sources
+            .iter()
+            .filter(|(key, source)| matches!(source.anchor, Anchor::Carrier(_)) && !kept.contains(*key))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for (carrier_id, aura_id) in gone {
+            self.detach(&carrier_id, &aura_id);
+        }
+        for (carrier_id, aura) in carried {
+            let current = self.sources.get(&(carrier_id.clone(), aura.id.clone()));
+            if !current.is_some_and(|source| !source.detached && *source.aura == aura) {
+                self.attach(&carrier_id, aura);
+            }
+        }
+    }
+
+    /// Note that an entity moved, entered or left the zone
+    pub(crate) fn moved(&mut self, entity_id: &str, previous: Option<Vec3>) {
+        self.moved.entry(entity_id.to_string()).or_insert(previous);
+    }
+
+    /// Re-evaluate the sources whose area saw movement, reporting who came under and
+    /// left each aura. Carried auras end when their carrier is no longer in the zone.
+    pub(crate) fn evaluate(
+        &mut self,
+        index: &SpatialIndex,
+        parties: Option<&PartyManager>,
+        events: &mut Vec<ZoneEvent>,
+    ) {
+        let moved = std::mem::take(&mut self.moved);
+        let mut ended = Vec::new();
+        for ((source_id, aura_id), source) in &mut self.sources {
+            let center = match &source.anchor {
+                Anchor::Point(center) => Some(*center),
+                Anchor::Carrier(carrier_id) => index.position(carrier_id),
+            };
+            let radius = source.aura.radius;
+            let in_range = |position: Option<Vec3>, center: Vec3| {
+                position.is_some_and(|position| position.distance(center) <= radius)
+            };
+            let now: HashSet<String> = match center.filter(|_| !source.detached) {
+                None => {
+                    ended.push((source_id.clone(), aura_id.clone()));
+                    HashSet::new()
+                }
+                Some(center) => {
+                    let touched = source.fresh
+                        || source.aura.targets == AuraTargets::Party
+                        || moved.iter().any(|(entity_id, before)| {
+                            let carried = matches!(&source.anchor, Anchor::Carrier(carrier) if carrier == entity_id);
+                            carried || in_range(*before, center) || in_range(index.position(entity_id), center)
+                        });
+                    if !touched {
+                        continue;
+                    }
+                    let allowed = allowed_targets(source, parties);
+                    index
+                        .within(center, radius, usize::MAX)
+                        .into_iter()
+                        .map(|entity| entity.entity_id)
+                        .filter(|entity_id| allowed.as_ref().is_none_or(|allowed| allowed.contains(entity_id)))
+                        .collect()
+                }
+            };
+            source.fresh = false;
+
+            let mut applied: Vec<String> = now.difference(&source.inside).cloned().collect();
+            let mut removed: Vec<String> = source.inside.difference(&now).cloned().collect();
+            for entity_id in &applied {
+                self.book.apply(entity_id, source_id, &source.aura);
+            }
+            for entity_id in &removed {
+                self.book.remove(entity_id, source_id, aura_id);
+            }
+            source.inside = now;
+            if !applied.is_empty() || !removed.is_empty() {
+                applied.sort();
+                removed.sort();
+                events.push(ZoneEvent::AuraChanged {
+                    source_id: source_id.clone(),
+                    aura_id: aura_id.clone(),
+                    applied,
+                    removed,
+                });
+            }
+        }
+        for key in ended {
+            self.sources.remove(&key);
+        }
+    }
+}
+
+/// Entities a party aura may apply to; `None` when it applies to everyone
+fn allowed_targets(source: &AuraSource, parties: Option<&PartyManager>) -> Option<HashSet<String>> {
+    let (AuraTargets::Party, Anchor::Carrier(carrier_id)) = (source.aura.targets, &source.anchor) else {
+        return None;
+    };
+    let mut allowed: HashSet<String> = parties
+        .and_then(|parties| parties.party_of(carrier_id))
+        .map(|party| party.member_ids().into_iter().collect())
+        .unwrap_or_default();
+    allowed.insert(carrier_id.clone());
+    Some(allowed)
+}
@@ -0,0 +1,28 @@
+//! Enumerations for world-core.
+
+use serde::{Deserialize, Serialize};
+
+/// Weather over a zone
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WeatherKind {
+    Clear,
+    Cloudy,
+    Rain,
+    Storm,
+    Snow,
+    Fog,
+}
+
+impl WeatherKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WeatherKind::Clear => "clear",
+            WeatherKind::Cloudy => "cloudy",
+            WeatherKind::Rain => "rain",
+            WeatherKind::Storm => "storm",
+            WeatherKind::Snow => "snow",
+            WeatherKind::Fog => "fog",
+        }
+    }
+}
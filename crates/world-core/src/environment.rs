@@ -0,0 +1,28 @@
+//! Environmental hazards: areas that hurt (or help) the entities inside them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::enums::WeatherKind;
+use crate::types::Vec3;
+
+/// An area pulsing on a resource of every entity inside it, e.g. a poison swamp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HazardArea {
+    pub id: String,
+    pub center: Vec3,
+    pub radius: f64,
+    /// Resource affected, e.g. `health`
+    pub resource: String,
+    /// Added on every pulse, negative for damage
+    pub per_pulse: f64,
+    pub interval_secs: u64,
+    /// Weather the hazard is active in, any weather when empty
+    #[serde(default)]
+    pub weather: Vec<WeatherKind>,
+}
+
+impl HazardArea {
+    pub fn is_active(&self, weather: WeatherKind) -> bool {
+        self.weather.is_empty() || self.weather.contains(&weather)
+    }
+}
@@ -0,0 +1,58 @@
+//! Error types for world-core.
+
+use thiserror::Error;
+
+use crate::types::Vec3;
+
+/// Errors raised by zone definitions and the zone runtime
+#[derive(Debug, Error)]
+pub enum WorldCoreError {
+    /// A zone definition that cannot be hosted
+    #[error("Invalid zone {zone}: {reason}")]
+    InvalidZone { zone: String, reason: String },
+
+    /// A zone that is not loaded
+    #[error("Zone not found: {0}")]
+    ZoneNotFound(String),
+
+    /// A zone kept as a standby copy, run by another instance
+    #[error("Zone {0} is run by another instance")]
+    Standby(String),
+
+    /// A position outside of a zone's bounds
+    #[error("Position {position} is outside zone {zone}")]
+    OutOfBounds { zone: String, position: Vec3 },
+
+    /// A position inside one of a zone's obstacles
+    #[error("Position {position} is blocked in zone {zone}")]
+    Blocked { zone: String, position: Vec3 },
+
+    /// A behavior tree that cannot be run
+    #[error("Invalid behavior tree {tree}: {reason}")]
+    InvalidTree { tree: String, reason: String },
+
+    /// An aura that cannot be attached
+    #[error("Invalid aura {aura}: {reason}")]
+    InvalidAura { aura: String, reason: String },
+
+    /// A mount that cannot be collected or ridden
+    #[error("Invalid mount {mount}: {reason}")]
+    InvalidMount { mount: String, reason: String },
+
+    /// Loading zone definitions failed
+    #[error("Zone storage error: {0}")]
+    Storage(String),
+}
+
+/// Result type for world-core operations
+pub type WorldCoreResult<T> = Result<T, WorldCoreError>;
+
+impl WorldCoreError {
+    pub(crate) fn invalid(zone: &str, reason: impl Into<String>) -> Self {
+        WorldCoreError::InvalidZone { zone: zone.to_string(), reason: reason.into() }
+    }
+
+    pub(crate) fn invalid_tree(tree: &str, reason: impl Into<String>) -> Self {
+        WorldCoreError::InvalidTree { tree: tree.to_string(), reason: reason.into() }
+    }
+}
@@ -0,0 +1,13 @@
+//! Interfaces world hosts implement.
+
+use async_trait::async_trait;
+
+use crate::error::WorldCoreResult;
+use crate::zones::ZoneDefinition;
+
+/// Where zone definitions are stored
+#[async_trait]
+pub trait ZoneSource: Send + Sync {
+    /// Definitions of the given zones; zones the source does not know are left out.
+    async fn load(&self, zone_ids: &[String]) -> WorldCoreResult<Vec<ZoneDefinition>>;
+}
@@ -10,6 +10,12 @@ pub mod services;
 pub mod zones;
 pub mod environment;
 pub mod weather;
+pub mod ai;
+pub mod movement;
+pub mod navigation;
+pub mod auras;
+pub mod mounts;
+pub mod replication;
 pub mod error;
 
 // Re-export commonly used types
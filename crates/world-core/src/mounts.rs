@@ -0,0 +1,403 @@
+//! Mounts: creatures and vehicles actors ride to travel faster.
+//!
+//! Accounts collect mounts; any of their characters may then ride one. While riding, the
+//! [`MountSubsystem`] multiplies the actor's speed stat by the mount's
+//! [`MountDefinition::speed_multiplier`], the same stat [`crate::movement`] validates moves
+//! against, and the mount grants its mounted-only abilities.
+//!
+//! When an actor may ride is configured as condition-core chains, checked through the
+//! resolver given to [`MountManager::with_conditions`]: every [`MountConfig::mount_conditions`]
+//! chain must pass to mount, and riders are dismounted by [`MountManager::enforce`] as soon
+//! as any [`MountConfig::dismount_conditions`] chain passes. The defaults keep riders out of
+//! combat, through the `is_actor_in_combat` function, and out of indoor areas, through the
+//! `is_actor_indoors` function that [`IsActorIndoorsFunction`] provides from the
+//! [`IndoorFlags`] the zone runtime keeps. Without a resolver the rules are not checked.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use async_trait::async_trait;
+use condition_core::{
+    ActorTarget, ChainLogic, ConditionChainConfig, ConditionConfig, ConditionContext, ConditionFunction,
+    ConditionOperator, ConditionParameter, ConditionResolverTrait, ConditionResult, ConditionValue, WeatherType,
+    WorldState,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use actor_core::aggregation_context::AggregationContext;
+use actor_core::enums::Bucket;
+use actor_core::interfaces::{Aggregator, Subsystem};
+use actor_core::types::{Actor, Contribution, SubsystemOutput};
+use actor_core::ActorCoreResult;
+
+use crate::error::{WorldCoreError, WorldCoreResult};
+
+/// System ID of the mount subsystem
+pub const MOUNTS_SYSTEM_ID: &str = "mounts";
+
+/// A mount actors can collect and ride
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MountDefinition {
+    pub id: String,
+    pub name: String,
+    /// Factor the rider's speed stat is multiplied by
+    pub speed_multiplier: f64,
+    /// Abilities only usable while riding this mount
+    #[serde(default)]
+    pub abilities: Vec<String>,
+}
+
+impl MountDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.speed_multiplier.is_finite() && self.speed_multiplier > 0.0) {
+            return Err("speed_multiplier must be a positive number".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MountConfig {
+    /// Snapshot stat the mount speed applies to
+    pub speed_stat: String,
+    pub mounts: Vec<MountDefinition>,
+    /// Chains that must all pass for an actor to mount
+    pub mount_conditions: Vec<ConditionChainConfig>,
+    /// Chains dismounting the rider as soon as any passes
+    pub dismount_conditions: Vec<ConditionChainConfig>,
+}
+
+impl Default for MountConfig {
+    fn default() -> Self {
+        Self {
+            speed_stat: "move_speed".to_string(),
+            mounts: Vec::new(),
+            mount_conditions: vec![
+                flag_chain("not_in_combat", "is_actor_in_combat", false),
+                flag_chain("outdoors", IS_ACTOR_INDOORS, false),
+            ],
+            dismount_conditions: vec![
+                flag_chain("in_combat", "is_actor_in_combat", true),
+                flag_chain("indoors", IS_ACTOR_INDOORS, true),
+            ],
+        }
+    }
+}
+
+/// Chain checking that a boolean condition function returns `expected`
+fn flag_chain(chain_id: &str, function_name: &str, expected: bool) -> ConditionChainConfig {
+    ConditionChainConfig {
+        chain_id: chain_id.to_string(),
+        logic: ChainLogic::And,
+        conditions: vec![ConditionConfig {
+            condition_id: chain_id.to_string(),
+            function_name: function_name.to_string(),
+            operator: ConditionOperator::Equal,
+            value: ConditionValue::Boolean(expected),
+            parameters: Vec::new(),
+        }],
+    }
+}
+
+/// Name of the condition function telling whether an actor is indoors
+pub const IS_ACTOR_INDOORS: &str = "is_actor_indoors";
+
+/// Actors currently in indoor areas, flagged by the zone runtime as they enter and leave them
+#[derive(Debug, Default)]
+pub struct IndoorFlags {
+    indoors: Mutex<HashSet<String>>,
+}
+
+impl IndoorFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, actor_id: &str, indoors: bool) {
+        let mut flags = self.indoors.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if indoors {
+            flags.insert(actor_id.to_string());
+        } else {
+            flags.remove(actor_id);
+        }
+    }
+
+    pub fn is_indoors(&self, actor_id: &str) -> bool {
+        self.indoors.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).contains(actor_id)
+    }
+}
+
+/// Condition function `is_actor_indoors`, registered with condition-core's function registry
+pub struct IsActorIndoorsFunction {
+    flags: Arc<IndoorFlags>,
+}
+
+impl IsActorIndoorsFunction {
+    pub fn new(flags: Arc<IndoorFlags>) -> Self {
+        Self { flags }
+    }
+}
+
+#[async_trait]
+impl ConditionFunction for IsActorIndoorsFunction {
+    fn name(&self) -> &str {
+        IS_ACTOR_INDOORS
+    }
+
+    async fn evaluate(
+        &self,
+        _parameters: &[ConditionParameter],
+        context: &ConditionContext,
+    ) -> ConditionResult<ConditionValue> {
+        Ok(ConditionValue::Boolean(self.flags.is_indoors(&context.target.id)))
+    }
+}
+
+/// Mounts an account has collected
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MountCollection {
+    pub account_id: String,
+    pub mounts: BTreeSet<String>,
+}
+
+#[derive(Default)]
+struct State {
+    collections: HashMap<String, MountCollection>,
+    /// Mount each rider is on
+    riding: HashMap<String, String>,
+}
+
+/// Mount collections of every account and who is riding what
+pub struct MountManager {
+    speed_stat: String,
+    definitions: HashMap<String, Arc<MountDefinition>>,
+    /// Abilities usable only while riding some mount
+    mounted_abilities: HashSet<String>,
+    mount_conditions: Vec<ConditionChainConfig>,
+    dismount_conditions: Vec<ConditionChainConfig>,
+    conditions: Option<Arc<dyn ConditionResolverTrait + Send + Sync>>,
+    aggregator: Option<Arc<dyn Aggregator>>,
+    state: Mutex<State>,
+}
+
+impl MountManager {
+    pub fn new(config: MountConfig) -> WorldCoreResult<Self> {
+        let mut definitions = HashMap::new();
+        for mount in config.mounts {
+            mount.validate().map_err(|reason| invalid(&mount.id, reason))?;
+            if definitions.contains_key(&mount.id) {
+                return Err(invalid(&mount.id, "Defined twice"));
+            }
+            definitions.insert(mount.id.clone(), Arc::new(mount));
+        }
+        let mounted_abilities = definitions.values().flat_map(|mount| mount.abilities.iter().cloned()).collect();
+        Ok(Self {
+            speed_stat: config.speed_stat,
+            definitions,
+            mounted_abilities,
+            mount_conditions: config.mount_conditions,
+            dismount_conditions: config.dismount_conditions,
+            conditions: None,
+            aggregator: None,
+            state: Mutex::new(State::default()),
+        })
+    }
+
+    /// Check the mount and dismount rules with condition-core
+    pub fn with_conditions(mut self, resolver: Arc<dyn ConditionResolverTrait + Send + Sync>) -> Self {
+        self.conditions = Some(resolver);
+        self
+    }
+
+    /// Mark riders' speed stat dirty in `aggregator` whenever they mount or dismount
+    pub fn with_aggregator(mut self, aggregator: Arc<dyn Aggregator>) -> Self {
+        self.aggregator = Some(aggregator);
+        self
+    }
+
+    pub fn definition(&self, mount_id: &str) -> Option<Arc<MountDefinition>> {
+        self.definitions.get(mount_id).cloned()
+    }
+
+    /// Add a mount to an account's collection, returning false if it was collected already
+    pub fn learn(&self, account_id: &str, mount_id: &str) -> WorldCoreResult<bool> {
+        if !self.definitions.contains_key(mount_id) {
+            return Err(invalid(mount_id, "Unknown mount"));
+        }
+        let mut state = self.state();
+        let collection = state
+            .collections
+            .entry(account_id.to_string())
+            .or_insert_with(|| MountCollection { account_id: account_id.to_string(), mounts: BTreeSet::new() });
+        Ok(collection.mounts.insert(mount_id.to_string()))
+    }
+
+    /// Get an account's collection, e.g. to save it
+    pub fn collection(&self, account_id: &str) -> MountCollection {
+        self.state()
+            .collections
+            .get(account_id)
+            .cloned()
+            .unwrap_or_else(|| MountCollection { account_id: account_id.to_string(), mounts: BTreeSet::new() })
+    }
+
+    /// Restore a saved collection, e.g. on login; mounts no longer defined are dropped
+    pub fn restore(&self, mut collection: MountCollection) {
+        collection.mounts.retain(|mount_id| self.definitions.contains_key(mount_id));
+        self.state().collections.insert(collection.account_id.clone(), collection);
+    }
+
+    /// Mount `actor_id`, a character of `account_id`, in `zone_id`; riders switch mounts
+    /// without dismounting first
+    pub async fn mount(&self, account_id: &str, actor_id: &str, mount_id: &str, zone_id: &str) -> WorldCoreResult<()> {
+        if !self.definitions.contains_key(mount_id) {
+            return Err(invalid(mount_id, "Unknown mount"));
+        }
+        let collected =
+            self.state().collections.get(account_id).is_some_and(|collection| collection.mounts.contains(mount_id));
+        if !collected {
+            return Err(invalid(mount_id, format!("Account {} has not collected it", account_id)));
+        }
+        if let Some(chain) = self.first_passing(&self.mount_conditions, actor_id, zone_id, false).await {
+            return Err(invalid(mount_id, format!("Condition {} does not hold for {}", chain, actor_id)));
+        }
+        self.state().riding.insert(actor_id.to_string(), mount_id.to_string());
+        self.speed_changed(actor_id);
+        Ok(())
+    }
+
+    /// Dismount an actor, returning the mount it rode
+    pub fn dismount(&self, actor_id: &str) -> Option<String> {
+        let mount_id = self.state().riding.remove(actor_id)?;
+        self.speed_changed(actor_id);
+        Some(mount_id)
+    }
+
+    /// Dismount the actor if any dismount condition holds, e.g. after it enters combat or
+    /// an indoor area; returns the mount it was taken off
+    pub async fn enforce(&self, actor_id: &str, zone_id: &str) -> Option<String> {
+        self.riding(actor_id)?;
+        let chain = self.first_passing(&self.dismount_conditions, actor_id, zone_id, true).await?;
+        let mount_id = self.dismount(actor_id)?;
+        debug!(actor_id, mount_id = %mount_id, "Dismounted by condition {}", chain);
+        Some(mount_id)
+    }
+
+    /// Get the mount an actor rides
+    pub fn riding(&self, actor_id: &str) -> Option<Arc<MountDefinition>> {
+        let mount_id = self.state().riding.get(actor_id).cloned()?;
+        self.definition(&mount_id)
+    }
+
+    /// Whether an actor may use an ability: mounted-only abilities need a mount granting them
+    pub fn can_use_ability(&self, actor_id: &str, ability_id: &str) -> bool {
+        if !self.mounted_abilities.contains(ability_id) {
+            return true;
+        }
+        self.riding(actor_id).is_some_and(|mount| mount.abilities.iter().any(|ability| ability == ability_id))
+    }
+
+    /// First chain whose result is `wanted`: a failing mount condition (`wanted` false) or a
+    /// passing dismount condition (`wanted` true). Chains that fail to resolve count as not
+    /// passing.
+    async fn first_passing(
+        &self,
+        chains: &[ConditionChainConfig],
+        actor_id: &str,
+        zone_id: &str,
+        wanted: bool,
+    ) -> Option<String> {
+        let resolver = self.conditions.as_ref()?;
+        let context = condition_context(actor_id, zone_id);
+        for chain in chains {
+            let passed = match resolver.resolve_condition_chain(chain, &context).await {
+                Ok(passed) => passed,
+                Err(e) => {
+                    warn!(actor_id, chain = %chain.chain_id, "Mount condition failed to resolve: {}", e);
+                    false
+                }
+            };
+            if passed == wanted {
+                return Some(chain.chain_id.clone());
+            }
+        }
+        None
+    }
+
+    fn speed_changed(&self, actor_id: &str) {
+        if let Some(aggregator) = &self.aggregator {
+            aggregator.mark_dirty(actor_id, std::slice::from_ref(&self.speed_stat));
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn invalid(mount_id: &str, reason: impl Into<String>) -> WorldCoreError {
+    WorldCoreError::InvalidMount { mount: mount_id.to_string(), reason: reason.into() }
+}
+
+/// Condition-core context for checking `actor_id` in its zone
+fn condition_context(actor_id: &str, zone_id: &str) -> ConditionContext {
+    ConditionContext {
+        target: ActorTarget { id: actor_id.to_string() },
+        world_id: zone_id.to_string(),
+        current_time: SystemTime::now(),
+        current_weather: WeatherType::Clear,
+        world_state: WorldState {
+            time_of_day: 12.0,
+            season: "default".to_string(),
+            temperature: 20.0,
+            humidity: 0.5,
+        },
+    }
+}
+
+/// Subsystem multiplying riders' speed stat by their mount's speed
+pub struct MountSubsystem {
+    mounts: Arc<MountManager>,
+    priority: i64,
+}
+
+impl MountSubsystem {
+    pub fn new(mounts: Arc<MountManager>) -> Self {
+        // Runs after the subsystems contributing base speed, whose flat contributions the
+        // multiplier must apply to
+        Self { mounts, priority: 10 }
+    }
+
+    /// Set the subsystem priority
+    pub fn with_priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+}
+
+#[async_trait]
+impl Subsystem for MountSubsystem {
+    fn system_id(&self) -> &str {
+        MOUNTS_SYSTEM_ID
+    }
+
+    fn priority(&self) -> i64 {
+        self.priority
+    }
+
+    async fn contribute(&self, actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new(MOUNTS_SYSTEM_ID.to_string());
+        if let Some(mount) = self.mounts.riding(&actor.id) {
+            output.add_contribution(Contribution::new(
+                self.mounts.speed_stat.clone(),
+                Bucket::Mult,
+                mount.speed_multiplier,
+                MOUNTS_SYSTEM_ID.to_string(),
+            ));
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,201 @@
+//! Server-authoritative movement.
+//!
+//! Game servers relay the positions clients claim; the [`MovementValidator`] checks each
+//! against the last position it accepted for the entity:
+//! - positions outside the zone bounds, or inside or through one of its obstacles, are
+//!   refused
+//! - a single update covering more than `teleport_distance` is a teleport the server did
+//!   not initiate; server-initiated teleports skip the checks
+//! - otherwise the move may cover `max_speed × tolerance` units per second since the last
+//!   accepted position
+//!
+//! A refused move leaves the entity where it was: the verdict carries that position for
+//! the client to snap back to (rubber-banding) and a [`MovementViolation`] the host
+//! reports to anti-cheat. Entering a zone is a first placement with nothing to travel
+//! from, so only the bounds and obstacles apply.
+
+use std::collections::HashMap;
+
+use actor_core::types::Snapshot;
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+pub use shared::events::topics::MovementViolationKind;
+
+use crate::error::{WorldCoreError, WorldCoreResult};
+use crate::types::Vec3;
+use crate::zones::ZoneDefinition;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MovementConfig {
+    /// Snapshot stat holding an actor's movement speed, in units per second
+    pub speed_stat: String,
+    /// Speed of actors whose snapshot lacks the stat
+    pub default_speed: f64,
+    /// Allowed speed as a multiple of the speed stat, absorbing latency
+    pub tolerance: f64,
+    /// Moves are measured over at least this long, so updates arriving in a burst do not
+    /// look like a burst of speed
+    pub min_interval_ms: u64,
+    /// Longest distance a single update may cover
+    pub teleport_distance: f64,
+}
+
+impl Default for MovementConfig {
+    fn default() -> Self {
+        Self {
+            speed_stat: "move_speed".to_string(),
+            default_speed: 5.0,
+            tolerance: 1.25,
+            min_interval_ms: 250,
+            teleport_distance: 50.0,
+        }
+    }
+}
+
+impl MovementConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.default_speed.is_finite() && self.default_speed >= 0.0) {
+            return Err("default_speed must be a non-negative number".to_string());
+        }
+        if !(self.tolerance.is_finite() && self.tolerance >= 1.0) {
+            return Err("tolerance must be at least 1".to_string());
+        }
+        if !(self.teleport_distance.is_finite() && self.teleport_distance > 0.0) {
+            return Err("teleport_distance must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Movement speed of an actor, from its derived stats, then its primary stats
+    pub fn max_speed(&self, snapshot: &Snapshot) -> f64 {
+        snapshot
+            .derived
+            .get(&self.speed_stat)
+            .or_else(|| snapshot.primary.get(&self.speed_stat))
+            .copied()
+            .filter(|speed| speed.is_finite() && *speed >= 0.0)
+            .unwrap_or(self.default_speed)
+    }
+}
+
+/// A refused move
+#[derive(Debug, Clone, PartialEq)]
+pub struct MovementViolation {
+    pub kind: MovementViolationKind,
+    /// Last accepted position
+    pub from: Vec3,
+    /// Position the client claimed
+    pub attempted: Vec3,
+    /// Measured speed or distance; equal to `limit` for collisions and bounds
+    pub observed: f64,
+    pub limit: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveVerdict {
+    /// The entity may be placed where it claimed
+    Accepted,
+    /// The entity stays at `position`, where the client must put it back
+    Corrected { position: Vec3, violation: MovementViolation },
+}
+
+/// Where an entity was last accepted
+#[derive(Debug, Clone)]
+struct Track {
+    zone_id: String,
+    position: Vec3,
+    at: Timestamp,
+}
+
+/// Checks reported positions against each entity's last accepted one
+#[derive(Debug, Default)]
+pub struct MovementValidator {
+    config: MovementConfig,
+    tracks: HashMap<String, Track>,
+}
+
+impl MovementValidator {
+    pub fn new(config: MovementConfig) -> Self {
+        Self { config, tracks: HashMap::new() }
+    }
+
+    pub fn config(&self) -> &MovementConfig {
+        &self.config
+    }
+
+    /// Check a move of `entity_id` to `position` in `zone` and remember it if accepted.
+    ///
+    /// First placements in a zone have no position to put the entity back to, so invalid
+    /// ones fail with [`WorldCoreError::OutOfBounds`] or [`WorldCoreError::Blocked`]
+    /// instead of a correction.
+    pub fn validate(
+        &mut self,
+        zone: &ZoneDefinition,
+        entity_id: &str,
+        position: Vec3,
+        max_speed: f64,
+        teleported: bool,
+        now: Timestamp,
+    ) -> WorldCoreResult<MoveVerdict> {
+        let previous = self.tracks.get(entity_id).filter(|track| track.zone_id == zone.id && !teleported);
+        let Some(previous) = previous else {
+            if !position.is_finite() || !zone.bounds.contains(position) {
+                return Err(WorldCoreError::OutOfBounds { zone: zone.id.clone(), position });
+            }
+            if zone.obstacles.iter().any(|obstacle| obstacle.contains(position)) {
+                return Err(WorldCoreError::Blocked { zone: zone.id.clone(), position });
+            }
+            self.accept(zone, entity_id, position, now);
+            return Ok(MoveVerdict::Accepted);
+        };
+
+        if let Some(violation) = self.violation(zone, previous, position, max_speed, now) {
+            return Ok(MoveVerdict::Corrected { position: previous.position, violation });
+        }
+        self.accept(zone, entity_id, position, now);
+        Ok(MoveVerdict::Accepted)
+    }
+
+    /// Drop the track of an entity that left
+    pub fn forget(&mut self, entity_id: &str) {
+        self.tracks.remove(entity_id);
+    }
+
+    fn violation(
+        &self,
+        zone: &ZoneDefinition,
+        previous: &Track,
+        position: Vec3,
+        max_speed: f64,
+        now: Timestamp,
+    ) -> Option<MovementViolation> {
+        let violation = |kind, observed, limit| {
+            Some(MovementViolation { kind, from: previous.position, attempted: position, observed, limit })
+        };
+        if !position.is_finite() || !zone.bounds.contains(position) {
+            return violation(MovementViolationKind::OutOfBounds, 1.0, 1.0);
+        }
+        if zone.obstacles.iter().any(|obstacle| obstacle.intersects_segment(previous.position, position)) {
+            return violation(MovementViolationKind::Collision, 1.0, 1.0);
+        }
+
+        let distance = previous.position.distance(position);
+        if distance > self.config.teleport_distance {
+            return violation(MovementViolationKind::Teleport, distance, self.config.teleport_distance);
+        }
+        let elapsed = (now - previous.at).num_milliseconds().max(self.config.min_interval_ms as i64);
+        let speed = distance / (elapsed as f64 / 1000.0);
+        let limit = max_speed * self.config.tolerance;
+        if speed > limit {
+            return violation(MovementViolationKind::Speed, speed, limit);
+        }
+        None
+    }
+
+    fn accept(&mut self, zone: &ZoneDefinition, entity_id: &str, position: Vec3, now: Timestamp) {
+        let track = Track { zone_id: zone.id.clone(), position, at: now };
+        self.tracks.insert(entity_id.to_string(), track);
+    }
+}
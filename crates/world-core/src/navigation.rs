@@ -0,0 +1,451 @@
+//! Zone navigation: line of sight and walkable paths.
+//!
+//! Each zone gets a [`NavGrid`] over the x/z plane of its bounds. Cells are blocked by the
+//! zone's obstacles and by walls imported from level tooling
+//! ([`NavigationDefinition::walls`]). Line of sight is checked exactly against the
+//! obstacles, heights included, and cell by cell against imported walls.
+//!
+//! Paths are searched with A* by a [`Navigator`], which expands at most
+//! `expansions_per_step` cells before yielding to other tasks and gives up after
+//! `max_expansions`, so a long search neither stalls a zone tick nor runs unbounded.
+//! Found paths are kept for a while: NPCs heading for the same place, such as a pack
+//! chasing one player, reuse the rest of a path another NPC found once they stand on it.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::error::{WorldCoreError, WorldCoreResult};
+use crate::types::{Bounds, Vec3};
+use crate::zones::ZoneDefinition;
+
+/// Largest grid a zone may have
+pub const MAX_NAV_CELLS: usize = 4_000_000;
+
+/// Cost of a diagonal step, in straight steps
+const DIAGONAL: f64 = std::f64::consts::SQRT_2;
+
+/// Navigation data of a zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NavigationDefinition {
+    /// Edge of a grid cell; smaller cells follow obstacles more closely
+    pub cell_size: f64,
+    /// Imported walls: one row per cell along z from the bounds' minimum, one character
+    /// per cell along x, `#` for a wall and anything else for open ground. Cells past the
+    /// listed rows and columns are open.
+    pub walls: Vec<String>,
+}
+
+impl Default for NavigationDefinition {
+    fn default() -> Self {
+        Self { cell_size: 2.0, walls: Vec::new() }
+    }
+}
+
+impl NavigationDefinition {
+    pub fn validate(&self, bounds: &Bounds) -> Result<(), String> {
+        if !(self.cell_size.is_finite() && self.cell_size > 0.0) {
+            return Err("navigation.cell_size must be positive".to_string());
+        }
+        let (cols, rows) = self.dimensions(bounds);
+        if cols.saturating_mul(rows) > MAX_NAV_CELLS {
+            return Err(format!("navigation grid of {}x{} cells is too large", cols, rows));
+        }
+        if self.walls.len() > rows || self.walls.iter().any(|row| row.chars().count() > cols) {
+            return Err(format!("navigation.walls must fit a grid of {}x{} cells", cols, rows));
+        }
+        Ok(())
+    }
+
+    /// Columns (along x) and rows (along z) of the grid over `bounds`
+    fn dimensions(&self, bounds: &Bounds) -> (usize, usize) {
+        let cells = |extent: f64| ((extent / self.cell_size).ceil() as usize).max(1);
+        (cells(bounds.max.x - bounds.min.x), cells(bounds.max.z - bounds.min.z))
+    }
+}
+
+/// How path searches are budgeted and cached
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathfindingConfig {
+    /// Cells expanded before a search yields to other tasks
+    pub expansions_per_step: usize,
+    /// Cells expanded before a search gives up
+    pub max_expansions: usize,
+    /// Paths kept for reuse
+    pub cache_capacity: usize,
+    /// How long a path is reused; targets move, so this stays short
+    pub cache_ttl_ms: u64,
+}
+
+impl Default for PathfindingConfig {
+    fn default() -> Self {
+        Self {
+            expansions_per_step: 256,
+            max_expansions: 20_000,
+            cache_capacity: 256,
+            cache_ttl_ms: 2_000,
+        }
+    }
+}
+
+/// Column (along x) and row (along z) of a grid cell
+type Cell = (usize, usize);
+
+/// Walkability of a zone's x/z plane
+#[derive(Debug)]
+pub struct NavGrid {
+    zone_id: String,
+    bounds: Bounds,
+    cell_size: f64,
+    cols: usize,
+    rows: usize,
+    /// Cells that cannot be walked through
+    blocked: Vec<bool>,
+    /// Cells imported as walls, which block sight as well
+    walls: Vec<bool>,
+    obstacles: Vec<Bounds>,
+}
+
+impl NavGrid {
+    pub fn build(definition: &ZoneDefinition) -> WorldCoreResult<Self> {
+        let navigation = &definition.navigation;
+        navigation.validate(&definition.bounds).map_err(|reason| WorldCoreError::invalid(&definition.id, reason))?;
+        let (cols, rows) = navigation.dimensions(&definition.bounds);
+        let mut grid = Self {
+            zone_id: definition.id.clone(),
+            bounds: definition.bounds,
+            cell_size: navigation.cell_size,
+            cols,
+            rows,
+            blocked: vec![false; cols * rows],
+            walls: vec![false; cols * rows],
+            obstacles: definition.obstacles.clone(),
+        };
+        for (row, line) in navigation.walls.iter().enumerate() {
+            for (col, _) in line.chars().enumerate().filter(|(_, c)| *c == '#') {
+                let index = grid.index((col, row));
+                grid.walls[index] = true;
+                grid.blocked[index] = true;
+            }
+        }
+        for obstacle in &definition.obstacles {
+            let (min, max) = (grid.clamped_cell(obstacle.min), grid.clamped_cell(obstacle.max));
+            for row in min.1..=max.1 {
+                for col in min.0..=max.0 {
+                    let index = grid.index((col, row));
+                    grid.blocked[index] = true;
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn zone_id(&self) -> &str {
+        &self.zone_id
+    }
+
+    /// Whether an entity can stand at `position`
+    pub fn is_walkable(&self, position: Vec3) -> bool {
+        self.cell(position).is_some_and(|cell| !self.blocked[self.index(cell)])
+    }
+
+    /// Whether nothing blocks the view from `from` to `to`
+    pub fn has_line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.cell(from).is_some()
+            && self.cell(to).is_some()
+            && !self.obstacles.iter().any(|obstacle| obstacle.intersects_segment(from, to))
+            && self.clear_line(from, to, &self.walls)
+    }
+
+    /// Whether an entity can walk straight from `from` to `to`
+    pub fn can_walk_straight(&self, from: Vec3, to: Vec3) -> bool {
+        self.clear_line(from, to, &self.blocked)
+    }
+
+    /// Cell holding `position`, if inside the zone
+    fn cell(&self, position: Vec3) -> Option<Cell> {
+        let inside = position.is_finite()
+            && (self.bounds.min.x..=self.bounds.max.x).contains(&position.x)
+            && (self.bounds.min.z..=self.bounds.max.z).contains(&position.z);
+        inside.then(|| self.clamped_cell(position))
+    }
+
+    fn clamped_cell(&self, position: Vec3) -> Cell {
+        let axis = |value: f64, min: f64, cells: usize| {
+            (((value - min) / self.cell_size).floor().max(0.0) as usize).min(cells - 1)
+        };
+        (axis(position.x, self.bounds.min.x, self.cols), axis(position.z, self.bounds.min.z, self.rows))
+    }
+
+    fn index(&self, (col, row): Cell) -> usize {
+        row * self.cols + col
+    }
+
+    fn center(&self, (col, row): Cell) -> (f64, f64) {
+        (
+            self.bounds.min.x + (col as f64 + 0.5) * self.cell_size,
+            self.bounds.min.z + (row as f64 + 0.5) * self.cell_size,
+        )
+    }
+
+    /// Whether no cell of `mask` lies on the segment, sampled every quarter cell
+    fn clear_line(&self, from: Vec3, to: Vec3, mask: &[bool]) -> bool {
+        let length = ((to.x - from.x).powi(2) + (to.z - from.z).powi(2)).sqrt();
+        let samples = (length / (self.cell_size / 4.0)).ceil() as usize;
+        (0..=samples).all(|sample| {
+            let t = if samples == 0 { 0.0 } else { sample as f64 / samples as f64 };
+            let point = Vec3::new(from.x + (to.x - from.x) * t, from.y, from.z + (to.z - from.z) * t);
+            !mask[self.index(self.clamped_cell(point))]
+        })
+    }
+
+    /// Walkable neighbours of a cell with the cost of stepping there; diagonal steps may
+    /// not cut the corners of blocked cells
+    fn neighbours(&self, (col, row): Cell) -> impl Iterator<Item = (Cell, f64)> + '_ {
+        let open = move |dc: isize, dr: isize| {
+            let (c, r) = (col as isize + dc, row as isize + dr);
+            (c >= 0 && r >= 0 && (c as usize) < self.cols && (r as usize) < self.rows)
+                .then_some((c as usize, r as usize))
+                .filter(|cell| !self.blocked[self.index(*cell)])
+        };
+        [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (-1, 1), (1, -1), (1, 1)].into_iter().filter_map(move |(dc, dr)| {
+            let cell = open(dc, dr)?;
+            if dc != 0 && dr != 0 {
+                open(dc, 0)?;
+                open(0, dr)?;
+                return Some((cell, DIAGONAL));
+            }
+            Some((cell, 1.0))
+        })
+    }
+}
+
+/// Octile distance between cells, the exact cost on an open grid
+fn estimate(a: Cell, b: Cell) -> f64 {
+    let (dx, dz) = (a.0.abs_diff(b.0) as f64, a.1.abs_diff(b.1) as f64);
+    dx.max(dz) + (DIAGONAL - 1.0) * dx.min(dz)
+}
+
+/// A cell waiting to be expanded, cheapest estimate first
+#[derive(Debug, PartialEq)]
+struct Open {
+    estimate: f64,
+    cell: Cell,
+}
+
+impl Eq for Open {}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate).then_with(|| other.cell.cmp(&self.cell))
+    }
+}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+enum Progress {
+    Found(Vec<Cell>),
+    Unreachable,
+    Searching,
+}
+
+/// An A* search that can be run a few cells at a time
+struct Search {
+    goal: Cell,
+    open: BinaryHeap<Open>,
+    closed: HashSet<Cell>,
+    costs: HashMap<Cell, f64>,
+    came_from: HashMap<Cell, Cell>,
+    expanded: usize,
+}
+
+impl Search {
+    fn new(start: Cell, goal: Cell) -> Self {
+        Self {
+            goal,
+            open: BinaryHeap::from([Open { estimate: estimate(start, goal), cell: start }]),
+            closed: HashSet::new(),
+            costs: HashMap::from([(start, 0.0)]),
+            came_from: HashMap::new(),
+            expanded: 0,
+        }
+    }
+
+    /// Expand up to `budget` cells
+    fn step(&mut self, grid: &NavGrid, budget: usize) -> Progress {
+        for _ in 0..budget {
+            let Some(Open { cell, .. }) = self.open.pop() else {
+                return Progress::Unreachable;
+            };
+            if cell == self.goal {
+                return Progress::Found(self.trace(cell));
+            }
+            if !self.closed.insert(cell) {
+                continue;
+            }
+            self.expanded += 1;
+            let cost = self.costs[&cell];
+            for (next, step) in grid.neighbours(cell) {
+                let cost = cost + step;
+                if matches!(self.costs.get(&next), Some(known) if *known <= cost) {
+                    continue;
+                }
+                self.costs.insert(next, cost);
+                self.came_from.insert(next, cell);
+                self.open.push(Open { estimate: cost + estimate(next, self.goal), cell: next });
+            }
+        }
+        Progress::Searching
+    }
+
+    /// Cells from the start to `cell`
+    fn trace(&self, mut cell: Cell) -> Vec<Cell> {
+        let mut cells = vec![cell];
+        while let Some(previous) = self.came_from.get(&cell) {
+            cell = *previous;
+            cells.push(cell);
+        }
+        cells.reverse();
+        cells
+    }
+}
+
+#[derive(Debug)]
+struct CachedPath {
+    goal: Cell,
+    /// Cells from where the search started to the goal
+    cells: Vec<Cell>,
+    at: Timestamp,
+}
+
+/// Recently found paths, oldest first
+#[derive(Debug, Default)]
+struct PathCache {
+    paths: VecDeque<CachedPath>,
+}
+
+impl PathCache {
+    /// Rest of a fresh path to `goal` that passes through `start`
+    fn lookup(&mut self, start: Cell, goal: Cell, now: Timestamp, ttl: Duration) -> Option<Vec<Cell>> {
+        while self.paths.front().is_some_and(|path| now - path.at > ttl) {
+            self.paths.pop_front();
+        }
+        self.paths.iter().rev().filter(|path| path.goal == goal).find_map(|path| {
+            let from = path.cells.iter().position(|cell| *cell == start)?;
+            Some(path.cells[from..].to_vec())
+        })
+    }
+
+    fn insert(&mut self, goal: Cell, cells: Vec<Cell>, now: Timestamp, capacity: usize) {
+        self.paths.push_back(CachedPath { goal, cells, at: now });
+        while self.paths.len() > capacity {
+            self.paths.pop_front();
+        }
+    }
+}
+
+/// Finds paths through one zone
+#[derive(Debug)]
+pub struct Navigator {
+    grid: Arc<NavGrid>,
+    config: PathfindingConfig,
+    cache: Mutex<PathCache>,
+}
+
+impl Navigator {
+    pub fn new(grid: Arc<NavGrid>, config: PathfindingConfig) -> Self {
+        Self { grid, config, cache: Mutex::new(PathCache::default()) }
+    }
+
+    pub fn grid(&self) -> &Arc<NavGrid> {
+        &self.grid
+    }
+
+    pub fn config(&self) -> &PathfindingConfig {
+        &self.config
+    }
+
+    pub fn has_line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.grid.has_line_of_sight(from, to)
+    }
+
+    /// Paths kept for reuse, including stale ones not yet dropped
+    pub fn cached_paths(&self) -> usize {
+        self.cache().paths.len()
+    }
+
+    /// Waypoints leading from `from` to `to`, ending at `to`; `None` when `to` cannot be
+    /// reached within the search budget
+    pub async fn find_path(&self, from: Vec3, to: Vec3, now: Timestamp) -> WorldCoreResult<Option<Vec<Vec3>>> {
+        let grid = &self.grid;
+        let out_of_bounds = |position| WorldCoreError::OutOfBounds { zone: grid.zone_id.clone(), position };
+        let start = grid.cell(from).ok_or_else(|| out_of_bounds(from))?;
+        let goal = grid.cell(to).ok_or_else(|| out_of_bounds(to))?;
+        if grid.blocked[grid.index(goal)] {
+            return Ok(None);
+        }
+        if start == goal || grid.can_walk_straight(from, to) {
+            return Ok(Some(vec![to]));
+        }
+
+        let ttl = Duration::milliseconds(self.config.cache_ttl_ms as i64);
+        let cached = self.cache().lookup(start, goal, now, ttl);
+        let cells = match cached {
+            Some(cells) => cells,
+            None => {
+                let mut search = Search::new(start, goal);
+                loop {
+                    match search.step(grid, self.config.expansions_per_step.max(1)) {
+                        Progress::Found(cells) => {
+                            self.cache().insert(goal, cells.clone(), now, self.config.cache_capacity);
+                            break cells;
+                        }
+                        Progress::Unreachable => return Ok(None),
+                        Progress::Searching if search.expanded >= self.config.max_expansions => return Ok(None),
+                        Progress::Searching => tokio::task::yield_now().await,
+                    }
+                }
+            }
+        };
+        Ok(Some(self.smooth(from, to, &cells)))
+    }
+
+    /// Drop the cell centres an entity can walk past in a straight line
+    fn smooth(&self, from: Vec3, to: Vec3, cells: &[Cell]) -> Vec<Vec3> {
+        let mut points: Vec<Vec3> = cells[1..cells.len().saturating_sub(1)]
+            .iter()
+            .map(|cell| {
+                let (x, z) = self.grid.center(*cell);
+                Vec3::new(x, from.y, z)
+            })
+            .collect();
+        points.push(to);
+
+        let mut waypoints = Vec::new();
+        let (mut anchor, mut next) = (from, 0);
+        while next < points.len() {
+            let mut furthest = points.len() - 1;
+            while furthest > next && !self.grid.can_walk_straight(anchor, points[furthest]) {
+                furthest -= 1;
+            }
+            anchor = points[furthest];
+            waypoints.push(anchor);
+            next = furthest + 1;
+        }
+        waypoints
+    }
+
+    fn cache(&self) -> MutexGuard<'_, PathCache> {
+        self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
@@ -0,0 +1,81 @@
+//! Zone replication: what a standby instance keeps of a zone to take it over.
+//!
+//! The instance running a zone turns its state into [`ZoneReplica`]s after its ticks:
+//! the entities placed or removed since the previous replica, and the rest of the state,
+//! small enough to send whole every time. A [`full`](ZoneReplica::full) replica carries
+//! every entity instead, for standbys that just started or missed a replica. Standbys
+//! apply them to a runtime they do not tick, ready to carry on from there.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::auras::AuraDefinition;
+use crate::types::Vec3;
+use crate::weather::WeatherState;
+
+/// An entity of a replicated zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityReplica {
+    pub entity_id: String,
+    pub position: Vec3,
+    /// Spawn that placed it, for NPCs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spawn_id: Option<String>,
+}
+
+/// Progress of a spawn of a replicated zone
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpawnReplica {
+    pub spawn_id: String,
+    /// When NPCs that left are replaced
+    pub respawns: Vec<Timestamp>,
+    /// NPCs placed so far, numbering the next one
+    pub spawned: u64,
+}
+
+/// An aura an entity of a replicated zone carries
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CarriedAura {
+    pub carrier_id: String,
+    pub aura: AuraDefinition,
+}
+
+/// State of a zone for its standbys
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneReplica {
+    /// Every entity of the zone is listed, replacing those the standby has
+    pub full: bool,
+    /// Entities placed since the previous replica, or all of them
+    pub entities: Vec<EntityReplica>,
+    /// Entities that left since the previous replica
+    #[serde(default)]
+    pub removed: Vec<String>,
+    pub spawns: Vec<SpawnReplica>,
+    /// Next pulse of each active hazard
+    #[serde(default)]
+    pub hazards: BTreeMap<String, Timestamp>,
+    pub weather: WeatherState,
+    #[serde(default)]
+    pub auras: Vec<CarriedAura>,
+}
+
+/// Entities placed and removed since the last replica
+#[derive(Debug, Default)]
+pub(crate) struct ReplicaChanges {
+    pub(crate) placed: HashSet<String>,
+    pub(crate) removed: HashSet<String>,
+}
+
+impl ReplicaChanges {
+    pub(crate) fn placed(&mut self, entity_id: &str) {
+        self.removed.remove(entity_id);
+        self.placed.insert(entity_id.to_string());
+    }
+
+    pub(crate) fn removed(&mut self, entity_id: &str) {
+        self.placed.remove(entity_id);
+        self.removed.insert(entity_id.to_string());
+    }
+}
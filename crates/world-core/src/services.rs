@@ -0,0 +1,412 @@
+//! The zone runtime: one hosted zone, ticked by its host.
+//!
+//! A [`ZoneRuntime`] owns the spatial index of a zone and advances its spawns, hazards,
+//! auras and weather on every [`tick`](ZoneRuntime::tick), returning what happened as
+//! [`ZoneEvent`]s for the host to publish. Entities are placed by their owners (game
+//! servers for players, the runtime itself for spawned NPCs) through
+//! [`update_position`](ZoneRuntime::update_position).
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use chrono::Duration;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use shared::party::PartyManager;
+use shared::Timestamp;
+
+use crate::auras::{AuraBook, AuraDefinition, AuraField};
+use crate::enums::WeatherKind;
+use crate::error::{WorldCoreError, WorldCoreResult};
+use crate::navigation::{NavGrid, Navigator, PathfindingConfig};
+use crate::replication::{CarriedAura, EntityReplica, ReplicaChanges, SpawnReplica, ZoneReplica};
+use crate::types::{NearbyEntity, Vec3};
+use crate::weather::WeatherState;
+use crate::zones::{SpatialIndex, SpawnDefinition, ZoneDefinition};
+
+/// Something that happened in a zone during a tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ZoneEvent {
+    /// A spawn placed a new NPC
+    Spawned { entity_id: String, spawn_id: String, template: String, position: Vec3 },
+    /// A hazard pulsed on the entities inside it
+    HazardPulsed { hazard_id: String, resource: String, amount: f64, entity_ids: Vec<String> },
+    /// The weather changed
+    WeatherChanged { previous: WeatherKind, current: WeatherKind, until: Timestamp },
+    /// Entities came under or left an aura
+    AuraChanged { source_id: String, aura_id: String, applied: Vec<String>, removed: Vec<String> },
+}
+
+/// NPCs of one spawn
+#[derive(Debug, Default)]
+struct SpawnState {
+    alive: HashSet<String>,
+    /// When NPCs that left are replaced
+    respawns: Vec<Timestamp>,
+    spawned: u64,
+}
+
+/// A hosted zone
+pub struct ZoneRuntime {
+    definition: ZoneDefinition,
+    index: SpatialIndex,
+    spawns: HashMap<String, SpawnState>,
+    /// Spawn owning each spawned NPC
+    spawned_by: HashMap<String, String>,
+    /// Next pulse of each active hazard
+    hazard_due: HashMap<String, Timestamp>,
+    weather: WeatherState,
+    navigator: Arc<Navigator>,
+    auras: AuraField,
+    /// Membership of party auras
+    parties: Option<Arc<PartyManager>>,
+    /// Changes not replicated yet, when replicating
+    changes: Option<ReplicaChanges>,
+    rng: StdRng,
+}
+
+impl ZoneRuntime {
+    /// Host a zone from `now`, filling its spawns on the first tick
+    pub fn new(definition: ZoneDefinition, now: Timestamp) -> WorldCoreResult<Self> {
+        Self::with_rng(definition, now, StdRng::from_entropy())
+    }
+
+    /// Host a zone with a seeded random source, for reproducible spawns and weather
+    pub fn with_seed(definition: ZoneDefinition, now: Timestamp, seed: u64) -> WorldCoreResult<Self> {
+        Self::with_rng(definition, now, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(definition: ZoneDefinition, now: Timestamp, mut rng: StdRng) -> WorldCoreResult<Self> {
+        definition.validate()?;
+        let weather = definition.weather.roll(&mut rng, now);
+        let grid = Arc::new(NavGrid::build(&definition)?);
+        let spawns = definition.spawns.iter().map(|spawn| (spawn.id.clone(), SpawnState::default())).collect();
+        let auras = AuraField::new(&definition.auras, Arc::new(AuraBook::new()));
+        Ok(Self {
+            index: SpatialIndex::new(definition.cell_size),
+            definition,
+            spawns,
+            spawned_by: HashMap::new(),
+            hazard_due: HashMap::new(),
+            weather,
+            navigator: Arc::new(Navigator::new(grid, PathfindingConfig::default())),
+            auras,
+            parties: None,
+            changes: None,
+            rng,
+        })
+    }
+
+    /// Budget and cache path searches as configured
+    pub fn with_pathfinding(mut self, config: PathfindingConfig) -> Self {
+        self.navigator = Arc::new(Navigator::new(self.navigator.grid().clone(), config));
+        self
+    }
+
+    /// Record auras in a book shared with other zones
+    pub fn with_aura_book(mut self, book: Arc<AuraBook>) -> Self {
+        self.auras = AuraField::new(&self.definition.auras, book);
+        self
+    }
+
+    /// Limit party auras to the carriers' parties
+    pub fn with_parties(mut self, parties: Arc<PartyManager>) -> Self {
+        self.parties = Some(parties);
+        self
+    }
+
+    /// Track changes for [`replica`](Self::replica)s
+    pub fn with_replication(mut self) -> Self {
+        self.changes = Some(ReplicaChanges::default());
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.definition.id
+    }
+
+    pub fn definition(&self) -> &ZoneDefinition {
+        &self.definition
+    }
+
+    pub fn weather(&self) -> WeatherState {
+        self.weather
+    }
+
+    /// Path finding for the zone, shared so searches can run without holding the zone
+    pub fn navigator(&self) -> Arc<Navigator> {
+        self.navigator.clone()
+    }
+
+    pub fn has_line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        self.navigator.has_line_of_sight(from, to)
+    }
+
+    /// Auras the entities of the zone are under
+    pub fn aura_book(&self) -> Arc<AuraBook> {
+        self.auras.book().clone()
+    }
+
+    /// Have an entity of the zone carry an aura from the next tick, replacing the one it
+    /// carries with the same id; the aura ends when the entity leaves the zone
+    pub fn attach_aura(&mut self, carrier_id: &str, aura: AuraDefinition) -> WorldCoreResult<()> {
+        let invalid = |reason: String| WorldCoreError::InvalidAura { aura: aura.id.clone(), reason };
+        aura.validate().map_err(invalid)?;
+        if self.index.position(carrier_id).is_none() {
+            return Err(invalid(format!("{} is not in zone {}", carrier_id, self.definition.id)));
+        }
+        self.auras.attach(carrier_id, aura);
+        Ok(())
+    }
+
+    /// Auras an entity of the zone carries, e.g. to hand them over with it
+    pub fn carried_auras(&self, carrier_id: &str) -> Vec<AuraDefinition> {
+        self.auras.carried(carrier_id)
+    }
+
+    /// Stop an aura an entity carries on the next tick
+    pub fn detach_aura(&mut self, carrier_id: &str, aura_id: &str) -> bool {
+        self.auras.detach(carrier_id, aura_id)
+    }
+
+    pub fn entity_count(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn position(&self, entity_id: &str) -> Option<Vec3> {
+        self.index.position(entity_id)
+    }
+
+    /// Whether an entity is an NPC this zone spawned
+    pub fn is_spawned(&self, entity_id: &str) -> bool {
+        self.spawned_by.contains_key(entity_id)
+    }
+
+    /// Place an entity, returning where it was before; `None` means it just entered the zone
+    pub fn update_position(&mut self, entity_id: &str, position: Vec3) -> WorldCoreResult<Option<Vec3>> {
+        if !position.is_finite() || !self.definition.bounds.contains(position) {
+            return Err(WorldCoreError::OutOfBounds { zone: self.definition.id.clone(), position });
+        }
+        let previous = self.index.upsert(entity_id, position);
+        self.auras.moved(entity_id, previous);
+        if let Some(changes) = &mut self.changes {
+            changes.placed(entity_id);
+        }
+        Ok(previous)
+    }
+
+    /// Take an entity out of the zone; a spawned NPC is replaced after its spawn's delay
+    pub fn remove(&mut self, entity_id: &str, now: Timestamp) -> Option<Vec3> {
+        let position = self.index.remove(entity_id)?;
+        self.auras.moved(entity_id, Some(position));
+        if let Some(changes) = &mut self.changes {
+            changes.removed(entity_id);
+        }
+        if let Some(spawn_id) = self.spawned_by.remove(entity_id) {
+            let respawn_secs = self.spawn_definition(&spawn_id).map_or(0, |spawn| spawn.respawn_secs);
+            if let Some(state) = self.spawns.get_mut(&spawn_id) {
+                state.alive.remove(entity_id);
+                state.respawns.push(now + Duration::seconds(respawn_secs as i64));
+            }
+        }
+        Some(position)
+    }
+
+    pub fn entity_ids(&self) -> Vec<String> {
+        self.index.entities().map(|(entity_id, _)| entity_id.clone()).collect()
+    }
+
+    /// NPCs this zone spawned, with their spawn's template
+    pub fn spawned_npcs(&self) -> Vec<(String, String)> {
+        self.spawned_by
+            .iter()
+            .filter_map(|(entity_id, spawn_id)| {
+                Some((entity_id.clone(), self.spawn_definition(spawn_id)?.template.clone()))
+            })
+            .collect()
+    }
+
+    /// State for standby instances: the entities placed and removed since the previous
+    /// replica, or all of them when `full`
+    pub fn replica(&mut self, full: bool) -> ZoneReplica {
+        let changes = self.changes.as_mut().map(std::mem::take).unwrap_or_default();
+        let entity = |entity_id: &String, position: Vec3| EntityReplica {
+            entity_id: entity_id.clone(),
+            position,
+            spawn_id: self.spawned_by.get(entity_id).cloned(),
+        };
+        let (mut entities, mut removed): (Vec<EntityReplica>, Vec<String>) = if full {
+            (self.index.entities().map(|(entity_id, position)| entity(entity_id, position)).collect(), Vec::new())
+        } else {
+            let placed = changes
+                .placed
+                .iter()
+                .filter_map(|entity_id| Some(entity(entity_id, self.index.position(entity_id)?)))
+                .collect();
+            (placed, changes.removed.into_iter().collect())
+        };
+        entities.sort_by(|a, b| a.entity_id.cmp(&b.entity_id));
+        removed.sort();
+        ZoneReplica {
+            full,
+            entities,
+            removed,
+            spawns: self
+                .definition
+                .spawns
+                .iter()
+                .filter_map(|spawn| {
+                    let state = self.spawns.get(&spawn.id)?;
+                    let (respawns, spawned) = (state.respawns.clone(), state.spawned);
+                    Some(SpawnReplica { spawn_id: spawn.id.clone(), respawns, spawned })
+                })
+                .collect(),
+            hazards: self.hazard_due.iter().map(|(hazard_id, due)| (hazard_id.clone(), *due)).collect(),
+            weather: self.weather,
+            auras: self
+                .auras
+                .all_carried()
+                .into_iter()
+                .map(|(carrier_id, aura)| CarriedAura { carrier_id, aura })
+                .collect(),
+        }
+    }
+
+    /// Bring a standby copy of the zone up to a replica of the running zone
+    pub fn apply_replica(&mut self, replica: ZoneReplica) {
+        let mut removed = replica.removed;
+        if replica.full {
+            let listed: HashSet<&str> = replica.entities.iter().map(|entity| entity.entity_id.as_str()).collect();
+            let unlisted = self.index.entities().map(|(entity_id, _)| entity_id.clone());
+            removed.extend(unlisted.filter(|entity_id| !listed.contains(entity_id.as_str())));
+        }
+        for entity_id in &removed {
+            if let Some(position) = self.index.remove(entity_id) {
+                self.auras.moved(entity_id, Some(position));
+            }
+            if let Some(spawn_id) = self.spawned_by.remove(entity_id) {
+                if let Some(state) = self.spawns.get_mut(&spawn_id) {
+                    state.alive.remove(entity_id);
+                }
+            }
+        }
+        for entity in replica.entities {
+            let previous = self.index.upsert(&entity.entity_id, entity.position);
+            self.auras.moved(&entity.entity_id, previous);
+            if let Some(spawn_id) = entity.spawn_id {
+                if let Some(state) = self.spawns.get_mut(&spawn_id) {
+                    state.alive.insert(entity.entity_id.clone());
+                }
+                self.spawned_by.insert(entity.entity_id, spawn_id);
+            }
+        }
+        for spawn in replica.spawns {
+            if let Some(state) = self.spawns.get_mut(&spawn.spawn_id) {
+                state.respawns = spawn.respawns;
+                state.spawned = spawn.spawned;
+            }
+        }
+        self.hazard_due = replica.hazards.into_iter().collect();
+        self.weather = replica.weather;
+        self.auras.sync_carried(replica.auras.into_iter().map(|carried| (carried.carrier_id, carried.aura)).collect());
+    }
+
+    /// Entities within `radius` of `center`, closest first, at most `limit`
+    pub fn nearby(&self, center: Vec3, radius: f64, limit: usize) -> Vec<NearbyEntity> {
+        self.index.within(center, radius, limit)
+    }
+
+    /// Advance weather, spawns and hazards to `now` and re-evaluate auras
+    pub fn tick(&mut self, now: Timestamp) -> Vec<ZoneEvent> {
+        let mut events = Vec::new();
+        self.advance_weather(now, &mut events);
+        self.advance_spawns(now, &mut events);
+        self.advance_hazards(now, &mut events);
+        self.auras.evaluate(&self.index, self.parties.as_deref(), &mut events);
+        events
+    }
+
+    fn advance_weather(&mut self, now: Timestamp, events: &mut Vec<ZoneEvent>) {
+        if now < self.weather.until {
+            return;
+        }
+        let previous = self.weather.current;
+        self.weather = self.definition.weather.roll(&mut self.rng, now);
+        if self.weather.current != previous {
+            events.push(ZoneEvent::WeatherChanged { previous, current: self.weather.current, until: self.weather.until });
+        }
+    }
+
+    fn advance_spawns(&mut self, now: Timestamp, events: &mut Vec<ZoneEvent>) {
+        for spawn in &self.definition.spawns {
+            let Some(state) = self.spawns.get_mut(&spawn.id) else {
+                continue;
+            };
+            state.respawns.retain(|due| *due > now);
+            let occupied = state.alive.len() + state.respawns.len();
+            for _ in occupied..spawn.max_alive as usize {
+                state.spawned += 1;
+                let entity_id = format!("{}:{}:{}", self.definition.id, spawn.id, state.spawned);
+                let position = self.definition.bounds.clamp(random_point(&mut self.rng, spawn));
+                self.index.upsert(&entity_id, position);
+                if let Some(changes) = &mut self.changes {
+                    changes.placed(&entity_id);
+                }
+                state.alive.insert(entity_id.clone());
+                self.spawned_by.insert(entity_id.clone(), spawn.id.clone());
+                events.push(ZoneEvent::Spawned {
+                    entity_id,
+                    spawn_id: spawn.id.clone(),
+                    template: spawn.template.clone(),
+                    position,
+                });
+            }
+        }
+    }
+
+    fn advance_hazards(&mut self, now: Timestamp, events: &mut Vec<ZoneEvent>) {
+        for hazard in &self.definition.hazards {
+            if !hazard.is_active(self.weather.current) {
+                // Restart the interval when it becomes active again
+                self.hazard_due.remove(&hazard.id);
+                continue;
+            }
+            let interval = Duration::seconds(hazard.interval_secs as i64);
+            let due = *self.hazard_due.entry(hazard.id.clone()).or_insert(now + interval);
+            if now < due {
+                continue;
+            }
+            self.hazard_due.insert(hazard.id.clone(), now + interval);
+            let entity_ids: Vec<String> = self
+                .index
+                .within(hazard.center, hazard.radius, usize::MAX)
+                .into_iter()
+                .map(|entity| entity.entity_id)
+                .collect();
+            if !entity_ids.is_empty() {
+                events.push(ZoneEvent::HazardPulsed {
+                    hazard_id: hazard.id.clone(),
+                    resource: hazard.resource.clone(),
+                    amount: hazard.per_pulse,
+                    entity_ids,
+                });
+            }
+        }
+    }
+
+    fn spawn_definition(&self, spawn_id: &str) -> Option<&SpawnDefinition> {
+        self.definition.spawns.iter().find(|spawn| spawn.id == spawn_id)
+    }
+}
+
+/// Uniform point on the horizontal disc of a spawn, at the height of its center
+fn random_point(rng: &mut impl Rng, spawn: &SpawnDefinition) -> Vec3 {
+    if spawn.radius <= 0.0 {
+        return spawn.center;
+    }
+    let angle = rng.gen_range(0.0..std::f64::consts::TAU);
+    let distance = spawn.radius * rng.gen::<f64>().sqrt();
+    Vec3::new(spawn.center.x + distance * angle.cos(), spawn.center.y, spawn.center.z + distance * angle.sin())
+}
@@ -0,0 +1,96 @@
+//! Core types for world-core.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A point in a zone, in world units
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vec3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vec3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn distance(self, other: Vec3) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)).sqrt()
+    }
+
+    pub fn is_finite(self) -> bool {
+        self.x.is_finite() && self.y.is_finite() && self.z.is_finite()
+    }
+}
+
+impl fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({:.1}, {:.1}, {:.1})", self.x, self.y, self.z)
+    }
+}
+
+/// Axis-aligned box a zone occupies
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn contains(&self, point: Vec3) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    /// Closest point inside the bounds
+    pub fn clamp(&self, point: Vec3) -> Vec3 {
+        Vec3 {
+            x: point.x.clamp(self.min.x, self.max.x),
+            y: point.y.clamp(self.min.y, self.max.y),
+            z: point.z.clamp(self.min.z, self.max.z),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.min.is_finite() && self.max.is_finite() && self.min.x <= self.max.x && self.min.y <= self.max.y && self.min.z <= self.max.z
+    }
+
+    /// Whether the segment from `from` to `to` touches the bounds
+    pub fn intersects_segment(&self, from: Vec3, to: Vec3) -> bool {
+        let (mut enter, mut exit) = (0.0_f64, 1.0_f64);
+        let axes = [
+            (from.x, to.x, self.min.x, self.max.x),
+            (from.y, to.y, self.min.y, self.max.y),
+            (from.z, to.z, self.min.z, self.max.z),
+        ];
+        for (start, end, min, max) in axes {
+            let delta = end - start;
+            if delta == 0.0 {
+                if start < min || start > max {
+                    return false;
+                }
+                continue;
+            }
+            let (a, b) = ((min - start) / delta, (max - start) / delta);
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+            if enter > exit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// An entity found by a proximity query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NearbyEntity {
+    pub entity_id: String,
+    pub position: Vec3,
+    /// Distance from the query center
+    pub distance: f64,
+}
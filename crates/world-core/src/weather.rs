@@ -0,0 +1,65 @@
+//! Weather cycles of a zone.
+
+use std::collections::BTreeMap;
+
+use chrono::Duration;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::enums::WeatherKind;
+
+/// How likely each weather is and how long it lasts once rolled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WeatherTable {
+    /// Relative chance of each weather when it changes
+    pub weights: BTreeMap<WeatherKind, u32>,
+    pub min_secs: u64,
+    pub max_secs: u64,
+}
+
+impl Default for WeatherTable {
+    fn default() -> Self {
+        Self {
+            weights: BTreeMap::from([(WeatherKind::Clear, 60), (WeatherKind::Cloudy, 25), (WeatherKind::Rain, 15)]),
+            min_secs: 300,
+            max_secs: 900,
+        }
+    }
+}
+
+/// Weather in effect and when it changes next
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeatherState {
+    pub current: WeatherKind,
+    pub until: Timestamp,
+}
+
+impl WeatherTable {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.weights.values().all(|weight| *weight == 0) {
+            return Err("the weather table needs a weather with a positive weight".to_string());
+        }
+        if self.min_secs == 0 || self.min_secs > self.max_secs {
+            return Err("weather must last at least 1s with min_secs <= max_secs".to_string());
+        }
+        Ok(())
+    }
+
+    /// Pick the weather starting at `now`
+    pub fn roll(&self, rng: &mut impl Rng, now: Timestamp) -> WeatherState {
+        let total: u32 = self.weights.values().sum();
+        let mut pick = rng.gen_range(0..total.max(1));
+        let mut current = WeatherKind::Clear;
+        for (kind, weight) in &self.weights {
+            if pick < *weight {
+                current = *kind;
+                break;
+            }
+            pick -= weight;
+        }
+        let secs = rng.gen_range(self.min_secs..=self.max_secs.max(self.min_secs));
+        WeatherState { current, until: now + Duration::seconds(secs as i64) }
+    }
+}
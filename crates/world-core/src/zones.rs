@@ -0,0 +1,216 @@
+//! Zone definitions and the spatial index of the entities in a zone.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::auras::{AreaAura, AuraTargets};
+use crate::environment::HazardArea;
+use crate::error::{WorldCoreError, WorldCoreResult};
+use crate::navigation::NavigationDefinition;
+use crate::types::{Bounds, NearbyEntity, Vec3};
+use crate::weather::WeatherTable;
+
+/// Everything needed to host a zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneDefinition {
+    pub id: String,
+    pub name: String,
+    pub bounds: Bounds,
+    /// Edge of the spatial index cells; about the usual query radius works best
+    #[serde(default = "default_cell_size")]
+    pub cell_size: f64,
+    #[serde(default)]
+    pub spawns: Vec<SpawnDefinition>,
+    #[serde(default)]
+    pub hazards: Vec<HazardArea>,
+    /// Buffs and debuffs around fixed points, e.g. a shrine's blessing
+    #[serde(default)]
+    pub auras: Vec<AreaAura>,
+    #[serde(default)]
+    pub weather: WeatherTable,
+    /// Solid boxes entities cannot stand in or move through, e.g. buildings and cliffs
+    #[serde(default)]
+    pub obstacles: Vec<Bounds>,
+    #[serde(default)]
+    pub navigation: NavigationDefinition,
+}
+
+fn default_cell_size() -> f64 {
+    32.0
+}
+
+/// Keeps up to `max_alive` NPCs of a template alive around a point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnDefinition {
+    pub id: String,
+    /// NPC template spawned, e.g. `forest_wolf`
+    pub template: String,
+    pub center: Vec3,
+    /// NPCs appear at random within this distance of the center
+    #[serde(default)]
+    pub radius: f64,
+    pub max_alive: u32,
+    /// Time before an NPC that left the zone is replaced
+    pub respawn_secs: u64,
+}
+
+impl ZoneDefinition {
+    /// Reject definitions the zone runtime could not host
+    pub fn validate(&self) -> WorldCoreResult<()> {
+        let invalid = |reason: String| Err(WorldCoreError::invalid(&self.id, reason));
+        if self.id.is_empty() {
+            return invalid("the zone id is empty".to_string());
+        }
+        if !self.bounds.is_valid() {
+            return invalid("bounds must be finite with min <= max".to_string());
+        }
+        if !(self.cell_size.is_finite() && self.cell_size > 0.0) {
+            return invalid("cell_size must be positive".to_string());
+        }
+
+        let mut ids = HashSet::new();
+        for spawn in &self.spawns {
+            if !ids.insert(spawn.id.as_str()) {
+                return invalid(format!("duplicate spawn id {}", spawn.id));
+            }
+            if !self.bounds.contains(spawn.center) || spawn.radius.is_nan() || spawn.radius < 0.0 {
+                return invalid(format!("spawn {} must be inside the zone with a non-negative radius", spawn.id));
+            }
+        }
+        let mut ids = HashSet::new();
+        for hazard in &self.hazards {
+            if !ids.insert(hazard.id.as_str()) {
+                return invalid(format!("duplicate hazard id {}", hazard.id));
+            }
+            if hazard.radius.is_nan() || hazard.radius <= 0.0 || hazard.interval_secs == 0 {
+                return invalid(format!("hazard {} needs a positive radius and interval", hazard.id));
+            }
+        }
+        let mut ids = HashSet::new();
+        for area in &self.auras {
+            if !ids.insert(area.id.as_str()) {
+                return invalid(format!("duplicate aura id {}", area.id));
+            }
+            area.aura.validate().map_err(|reason| WorldCoreError::invalid(&self.id, reason))?;
+            if !self.bounds.contains(area.center) {
+                return invalid(format!("aura {} must be inside the zone", area.id));
+            }
+            if area.aura.targets == AuraTargets::Party {
+                return invalid(format!("aura {} has no carrier to take the party of", area.id));
+            }
+        }
+        if self.obstacles.iter().any(|obstacle| !obstacle.is_valid()) {
+            return invalid("obstacles must be finite with min <= max".to_string());
+        }
+        self.navigation.validate(&self.bounds).map_err(|reason| WorldCoreError::invalid(&self.id, reason))?;
+        self.weather.validate().map_err(|reason| WorldCoreError::invalid(&self.id, reason))
+    }
+}
+
+type Cell = (i64, i64, i64);
+
+/// Uniform grid over entity positions, answering proximity queries without scanning
+/// every entity
+#[derive(Debug)]
+pub struct SpatialIndex {
+    cell_size: f64,
+    cells: HashMap<Cell, HashSet<String>>,
+    positions: HashMap<String, Vec3>,
+}
+
+impl SpatialIndex {
+    pub fn new(cell_size: f64) -> Self {
+        Self { cell_size, cells: HashMap::new(), positions: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn position(&self, entity_id: &str) -> Option<Vec3> {
+        self.positions.get(entity_id).copied()
+    }
+
+    /// Every entity with its position
+    pub fn entities(&self) -> impl Iterator<Item = (&String, Vec3)> + '_ {
+        self.positions.iter().map(|(entity_id, position)| (entity_id, *position))
+    }
+
+    /// Place an entity, returning where it was before
+    pub fn upsert(&mut self, entity_id: &str, position: Vec3) -> Option<Vec3> {
+        let previous = self.positions.insert(entity_id.to_string(), position);
+        let cell = self.cell(position);
+        match previous.map(|previous| self.cell(previous)) {
+            Some(old) if old == cell => {}
+            old => {
+                if let Some(old) = old {
+                    self.remove_from_cell(old, entity_id);
+                }
+                self.cells.entry(cell).or_default().insert(entity_id.to_string());
+            }
+        }
+        previous
+    }
+
+    /// Take an entity out, returning its last position
+    pub fn remove(&mut self, entity_id: &str) -> Option<Vec3> {
+        let position = self.positions.remove(entity_id)?;
+        self.remove_from_cell(self.cell(position), entity_id);
+        Some(position)
+    }
+
+    /// Entities within `radius` of `center`, closest first, at most `limit`
+    pub fn within(&self, center: Vec3, radius: f64, limit: usize) -> Vec<NearbyEntity> {
+        let low = self.cell(Vec3::new(center.x - radius, center.y - radius, center.z - radius));
+        let high = self.cell(Vec3::new(center.x + radius, center.y + radius, center.z + radius));
+        let span = |low: i64, high: i64| (high as i128 - low as i128 + 1) as u128;
+        let cells = span(low.0, high.0).saturating_mul(span(low.1, high.1)).saturating_mul(span(low.2, high.2));
+
+        let mut found: Vec<NearbyEntity> = Vec::new();
+        let mut consider = |entity_id: &String, position: Vec3| {
+            let distance = position.distance(center);
+            if distance <= radius {
+                found.push(NearbyEntity { entity_id: entity_id.clone(), position, distance });
+            }
+        };
+        // Scanning every entity is cheaper than visiting more cells than there are entities
+        if cells > self.positions.len() as u128 {
+            for (entity_id, position) in &self.positions {
+                consider(entity_id, *position);
+            }
+        } else {
+            for x in low.0..=high.0 {
+                for y in low.1..=high.1 {
+                    for z in low.2..=high.2 {
+                        for entity_id in self.cells.get(&(x, y, z)).into_iter().flatten() {
+                            consider(entity_id, self.positions[entity_id]);
+                        }
+                    }
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.distance.total_cmp(&b.distance).then_with(|| a.entity_id.cmp(&b.entity_id)));
+        found.truncate(limit);
+        found
+    }
+
+    fn cell(&self, position: Vec3) -> Cell {
+        let cell = |value: f64| (value / self.cell_size).floor() as i64;
+        (cell(position.x), cell(position.y), cell(position.z))
+    }
+
+    fn remove_from_cell(&mut self, cell: Cell, entity_id: &str) {
+        if let Some(entities) = self.cells.get_mut(&cell) {
+            entities.remove(entity_id);
+            if entities.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+}
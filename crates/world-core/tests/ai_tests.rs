@@ -0,0 +1,220 @@
+//! Behavior tree tests: YAML trees, composites, decorators and perception.
+
+mod common;
+
+use async_trait::async_trait;
+use common::at;
+use condition_core::{ConditionChainConfig, ConditionConfig, ConditionContext, ConditionResolverTrait, ConditionResult};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use world_core::ai::{AiConfig, AiDirector, BehaviorTree, Blackboard, NodeStatus, NpcActions, NpcContext, TreeDefinition};
+use world_core::weather::WeatherTable;
+use world_core::zones::{SpawnDefinition, ZoneDefinition};
+use world_core::{Bounds, Vec3, WeatherKind, WorldCoreError, ZoneEvent, ZoneRuntime};
+
+const WOLF: &str = r#"
+id: wolf
+root:
+  selector:
+    - sequence:
+        - sense: { radius: 20, store_as: target }
+        - action: { name: attack }
+    - cooldown:
+        secs: 5
+        child: { action: { name: wander } }
+"#;
+
+/// Records actions; `attack` takes two ticks, everything else one
+#[derive(Default)]
+struct Recorder {
+    performed: Mutex<Vec<(String, String)>>,
+    swings: Mutex<HashMap<String, u32>>,
+}
+
+impl Recorder {
+    fn performed(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.performed.lock().unwrap())
+    }
+}
+
+#[async_trait]
+impl NpcActions for Recorder {
+    async fn perform(
+        &self,
+        npc: NpcContext<'_>,
+        action: &str,
+        _params: &serde_json::Value,
+        blackboard: &mut Blackboard,
+    ) -> NodeStatus {
+        let target = blackboard.get_str("target").unwrap_or_default().to_string();
+        self.performed.lock().unwrap().push((action.to_string(), target));
+        if action != "attack" {
+            return NodeStatus::Success;
+        }
+        let mut swings = self.swings.lock().unwrap();
+        let swing = swings.entry(npc.npc_id.to_string()).or_default();
+        *swing += 1;
+        if *swing % 2 == 1 {
+            NodeStatus::Running
+        } else {
+            NodeStatus::Success
+        }
+    }
+}
+
+/// Passes chains whose target is `wounded`
+struct WoundedOnly;
+
+#[async_trait]
+impl ConditionResolverTrait for WoundedOnly {
+    async fn resolve_condition(&self, _: &ConditionConfig, context: &ConditionContext) -> ConditionResult<bool> {
+        Ok(context.target.id == "wounded")
+    }
+
+    async fn resolve_conditions(&self, configs: &[ConditionConfig], context: &ConditionContext) -> ConditionResult<Vec<bool>> {
+        Ok(configs.iter().map(|_| context.target.id == "wounded").collect())
+    }
+
+    async fn resolve_condition_chain(&self, _: &ConditionChainConfig, context: &ConditionContext) -> ConditionResult<bool> {
+        Ok(context.target.id == "wounded")
+    }
+}
+
+fn zone() -> ZoneRuntime {
+    let definition = ZoneDefinition {
+        id: "forest".to_string(),
+        name: "Whispering Forest".to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(1000.0, 100.0, 1000.0) },
+        cell_size: 16.0,
+        spawns: vec![SpawnDefinition {
+            id: "wolves".to_string(),
+            template: "forest_wolf".to_string(),
+            center: Vec3::new(100.0, 0.0, 100.0),
+            radius: 0.0,
+            max_alive: 2,
+            respawn_secs: 30,
+        }],
+        hazards: Vec::new(),
+        auras: Vec::new(),
+        weather: WeatherTable { weights: BTreeMap::from([(WeatherKind::Clear, 1)]), min_secs: 60, max_secs: 60 },
+        obstacles: Vec::new(),
+        navigation: Default::default(),
+    };
+    ZoneRuntime::with_seed(definition, at(0), 7).unwrap()
+}
+
+fn tree(yaml: &str) -> TreeDefinition {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+/// A zone with its wolves spawned and a director running `yaml` for them
+fn director(yaml: &str) -> (ZoneRuntime, AiDirector, Recorder, Vec<String>) {
+    let mut zone = zone();
+    let recorder = Recorder::default();
+    let config = AiConfig {
+        trees: vec![tree(yaml)],
+        templates: HashMap::from([("forest_wolf".to_string(), "wolf".to_string())]),
+    };
+    let mut director = AiDirector::new("forest", &config).unwrap();
+    let mut wolves = Vec::new();
+    for event in zone.tick(at(0)) {
+        if let ZoneEvent::Spawned { entity_id, template, .. } = event {
+            assert!(director.spawned(&entity_id, &template));
+            wolves.push(entity_id);
+        }
+    }
+    wolves.sort();
+    (zone, director, recorder, wolves)
+}
+
+#[test]
+fn trees_are_validated_when_compiled() {
+    let compiled = BehaviorTree::from_yaml(WOLF).unwrap();
+    assert_eq!((compiled.id(), compiled.len()), ("wolf", 6));
+
+    assert!(matches!(BehaviorTree::from_yaml("id: idle\nroot: { sequence: [] }"), Err(WorldCoreError::InvalidTree { .. })));
+    assert!(BehaviorTree::from_yaml("id: idle\nroot: { sense: { radius: 0, store_as: target } }").is_err());
+    assert!(BehaviorTree::from_yaml("id: idle\nroot: { dance: {} }").is_err());
+
+    // Templates must run a defined tree
+    let templates = HashMap::from([("forest_wolf".to_string(), "bear".to_string())]);
+    let config = AiConfig { trees: vec![tree(WOLF)], templates };
+    assert!(AiDirector::new("forest", &config).is_err());
+}
+
+#[tokio::test]
+async fn npcs_fall_back_to_lower_priorities_until_they_sense_a_player() {
+    let (mut zone, mut director, recorder, wolves) = director(WOLF);
+    assert_eq!(director.len(), 2);
+
+    // Nothing in range: both wolves wander, then wait out the cooldown
+    director.tick(&zone, &recorder, at(1)).await;
+    assert_eq!(recorder.performed().len(), 2);
+    director.tick(&zone, &recorder, at(2)).await;
+    assert!(recorder.performed().is_empty());
+
+    // Players are sensed, other wolves are not
+    zone.update_position("player", Vec3::new(105.0, 0.0, 100.0)).unwrap();
+    director.tick(&zone, &recorder, at(3)).await;
+    assert_eq!(recorder.performed(), vec![("attack".to_string(), "player".to_string()); 2]);
+    assert_eq!(director.brain(&wolves[0]).unwrap().blackboard.get_str("target"), Some("player"));
+
+    // The running attack resumes without sensing again, even once the player has left
+    zone.remove("player", at(4));
+    director.tick(&zone, &recorder, at(4)).await;
+    assert_eq!(recorder.performed(), vec![("attack".to_string(), "player".to_string()); 2]);
+    // Back to wandering once the cooldown is over
+    director.tick(&zone, &recorder, at(6)).await;
+    assert_eq!(recorder.performed().iter().filter(|(action, _)| action == "wander").count(), 2);
+
+    director.despawned(&wolves[0]);
+    assert!(director.brain(&wolves[0]).is_none());
+}
+
+#[tokio::test]
+async fn decorators_shape_their_child_results() {
+    let yaml = r#"
+id: wolf
+root:
+  sequence:
+    - repeat: { times: 2, child: { action: { name: howl } } }
+    - inverter: { has: target }
+    - succeeder: { action: { name: attack } }
+    - action: { name: rest }
+"#;
+    let (zone, mut director, recorder, _) = director(yaml);
+    let ticks = |recorder: &Recorder| recorder.performed().into_iter().map(|(action, _)| action).collect::<Vec<_>>();
+
+    director.tick(&zone, &recorder, at(1)).await;
+    assert_eq!(ticks(&recorder), ["howl", "howl"]);
+    // The second howl finishes the repeat; the attack runs on
+    director.tick(&zone, &recorder, at(2)).await;
+    assert_eq!(ticks(&recorder), ["howl", "attack", "howl", "attack"]);
+    director.tick(&zone, &recorder, at(3)).await;
+    assert_eq!(ticks(&recorder), ["attack", "rest", "attack", "rest"]);
+}
+
+#[tokio::test]
+async fn condition_leaves_check_the_blackboard_target() {
+    let yaml = r#"
+id: wolf
+root:
+  sequence:
+    - sense: { radius: 20, store_as: target }
+    - condition:
+        target: target
+        chain: { chain_id: prey_is_wounded, logic: And, conditions: [] }
+    - action: { name: attack }
+"#;
+    let (mut zone, director, recorder, _) = director(yaml);
+    let mut director = director.with_conditions(Arc::new(WoundedOnly));
+
+    zone.update_position("healthy", Vec3::new(101.0, 0.0, 100.0)).unwrap();
+    director.tick(&zone, &recorder, at(1)).await;
+    assert!(recorder.performed().is_empty());
+
+    zone.remove("healthy", at(2));
+    zone.update_position("wounded", Vec3::new(101.0, 0.0, 100.0)).unwrap();
+    director.tick(&zone, &recorder, at(2)).await;
+    assert_eq!(recorder.performed().len(), 2);
+}
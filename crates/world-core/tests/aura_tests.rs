@@ -0,0 +1,171 @@
+//! Aura tests: range-based application, batching per tick, party auras and stacking.
+
+mod common;
+
+use common::start_time;
+use shared::party::{BuffMode, PartyConfig, PartyManager};
+use shared::SimulatedClock;
+use std::sync::Arc;
+use world_core::auras::{AreaAura, AuraBook, AuraDefinition, AuraEffect, AuraStacking, AuraTargets};
+use world_core::zones::ZoneDefinition;
+use world_core::{Bounds, Vec3, WorldCoreError, ZoneEvent, ZoneRuntime};
+
+fn aura(id: &str, radius: f64, value: f64) -> AuraDefinition {
+    AuraDefinition {
+        id: id.to_string(),
+        radius,
+        effects: vec![AuraEffect { stat: "armor".to_string(), value, mode: BuffMode::Add }],
+        targets: AuraTargets::Everyone,
+        stacking: AuraStacking::Strongest,
+    }
+}
+
+fn zone(auras: Vec<AreaAura>) -> ZoneRuntime {
+    let definition = ZoneDefinition {
+        id: "temple".to_string(),
+        name: "Temple".to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(200.0, 10.0, 200.0) },
+        cell_size: 8.0,
+        spawns: Vec::new(),
+        hazards: Vec::new(),
+        auras,
+        weather: Default::default(),
+        obstacles: Vec::new(),
+        navigation: Default::default(),
+    };
+    ZoneRuntime::new(definition, start_time()).unwrap()
+}
+
+/// Applied and removed entities of the aura events of a tick
+fn changes(events: Vec<ZoneEvent>) -> Vec<(String, Vec<String>, Vec<String>)> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            ZoneEvent::AuraChanged { source_id, applied, removed, .. } => Some((source_id, applied, removed)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn ids(ids: &[&str]) -> Vec<String> {
+    ids.iter().map(|id| id.to_string()).collect()
+}
+
+#[test]
+fn area_auras_follow_entities_once_per_tick() {
+    let shrine = AreaAura {
+        id: "shrine".to_string(),
+        center: Vec3::new(50.0, 0.0, 50.0),
+        aura: aura("blessing", 10.0, 5.0),
+    };
+    let mut zone = zone(vec![shrine]);
+    let book = zone.aura_book();
+
+    zone.update_position("pilgrim", Vec3::new(55.0, 0.0, 50.0)).unwrap();
+    zone.update_position("guard", Vec3::new(150.0, 0.0, 150.0)).unwrap();
+    assert!(book.applied("pilgrim").is_empty());
+    assert_eq!(changes(zone.tick(start_time())), [("shrine".to_string(), ids(&["pilgrim"]), Vec::new())]);
+    assert_eq!(book.effects("pilgrim")[0].value, 5.0);
+
+    // Nothing moved near the shrine: nothing to re-evaluate
+    zone.update_position("guard", Vec3::new(151.0, 0.0, 150.0)).unwrap();
+    assert!(changes(zone.tick(start_time())).is_empty());
+
+    // Movement packets between ticks are batched; passing through leaves no trace
+    zone.update_position("guard", Vec3::new(52.0, 0.0, 50.0)).unwrap();
+    zone.update_position("guard", Vec3::new(100.0, 0.0, 50.0)).unwrap();
+    zone.update_position("pilgrim", Vec3::new(80.0, 0.0, 50.0)).unwrap();
+    assert_eq!(changes(zone.tick(start_time())), [("shrine".to_string(), Vec::new(), ids(&["pilgrim"]))]);
+    assert!(book.effects("pilgrim").is_empty());
+
+    // Leaving the zone inside the aura removes it too
+    zone.update_position("guard", Vec3::new(50.0, 0.0, 45.0)).unwrap();
+    zone.tick(start_time());
+    zone.remove("guard", start_time());
+    assert_eq!(changes(zone.tick(start_time())), [("shrine".to_string(), Vec::new(), ids(&["guard"]))]);
+}
+
+#[tokio::test]
+async fn party_auras_reach_members_in_range_of_their_carrier() {
+    let clock = Arc::new(SimulatedClock::new(start_time(), std::time::Duration::from_secs(1)));
+    let parties = Arc::new(PartyManager::new(clock, PartyConfig::default()));
+    let party = parties.create("paladin").await.unwrap();
+    parties.add_member(&party.id, "paladin", "rogue").await.unwrap();
+
+    let mut zone = zone(Vec::new()).with_parties(parties.clone());
+    zone.update_position("paladin", Vec3::new(20.0, 0.0, 20.0)).unwrap();
+    zone.update_position("rogue", Vec3::new(25.0, 0.0, 20.0)).unwrap();
+    zone.update_position("stranger", Vec3::new(20.0, 0.0, 25.0)).unwrap();
+    zone.update_position("mage", Vec3::new(15.0, 0.0, 20.0)).unwrap();
+    assert!(matches!(zone.attach_aura("ghost", aura("devotion", 12.0, 10.0)), Err(WorldCoreError::InvalidAura { .. })));
+    let devotion = AuraDefinition { targets: AuraTargets::Party, ..aura("devotion", 12.0, 10.0) };
+    zone.attach_aura("paladin", devotion.clone()).unwrap();
+    assert_eq!(zone.carried_auras("paladin"), [devotion]);
+    assert_eq!(changes(zone.tick(start_time())), [("paladin".to_string(), ids(&["paladin", "rogue"]), Vec::new())]);
+
+    // Joining the party is enough, nobody has to move
+    parties.add_member(&party.id, "paladin", "mage").await.unwrap();
+    assert_eq!(changes(zone.tick(start_time())), [("paladin".to_string(), ids(&["mage"]), Vec::new())]);
+
+    // The carrier walking away takes the aura along
+    zone.update_position("paladin", Vec3::new(100.0, 0.0, 100.0)).unwrap();
+    assert_eq!(changes(zone.tick(start_time())), [("paladin".to_string(), Vec::new(), ids(&["mage", "rogue"]))]);
+
+    // Detached auras end on the next tick
+    assert!(zone.detach_aura("paladin", "devotion"));
+    assert!(!zone.detach_aura("paladin", "devotion"));
+    assert!(zone.carried_auras("paladin").is_empty());
+    assert_eq!(changes(zone.tick(start_time())), [("paladin".to_string(), Vec::new(), ids(&["paladin"]))]);
+    assert!(zone.aura_book().applied("paladin").is_empty());
+}
+
+#[test]
+fn sources_of_one_aura_stack_by_its_rule() {
+    let book = Arc::new(AuraBook::new());
+    let mut zone = zone(Vec::new()).with_aura_book(book.clone());
+    for (carrier, x) in [("weak", 10.0), ("strong", 12.0), ("middle", 14.0)] {
+        zone.update_position(carrier, Vec3::new(x, 0.0, 10.0)).unwrap();
+    }
+    zone.update_position("target", Vec3::new(11.0, 0.0, 11.0)).unwrap();
+    zone.attach_aura("weak", aura("war_cry", 20.0, 2.0)).unwrap();
+    zone.attach_aura("strong", aura("war_cry", 20.0, 8.0)).unwrap();
+    zone.attach_aura("middle", aura("war_cry", 20.0, 5.0)).unwrap();
+    zone.attach_aura("weak", aura("haste", 20.0, 1.0)).unwrap();
+    zone.tick(start_time());
+
+    let values = |book: &AuraBook| book.effects("target").iter().map(|effect| effect.value).collect::<Vec<_>>();
+    assert_eq!(book.applied("target").len(), 4);
+    assert_eq!(values(&book), [1.0, 8.0]);
+
+    // Re-attaching replaces the carrier's aura, stacking rule included
+    let stacking = AuraStacking::Stack { max_stacks: 2 };
+    for (carrier, value) in [("weak", 2.0), ("strong", 8.0), ("middle", 5.0)] {
+        zone.attach_aura(carrier, AuraDefinition { stacking, ..aura("war_cry", 20.0, value) }).unwrap();
+    }
+    zone.tick(start_time());
+    assert_eq!(values(&book), [1.0, 8.0, 5.0]);
+}
+
+#[test]
+fn invalid_auras_are_rejected() {
+    let shrine = AreaAura {
+        id: "shrine".to_string(),
+        center: Vec3::new(50.0, 0.0, 50.0),
+        aura: aura("blessing", 10.0, 5.0),
+    };
+    let party_aura = AuraDefinition { targets: AuraTargets::Party, ..shrine.aura.clone() };
+    let party_shrine = AreaAura { aura: party_aura, ..shrine.clone() };
+    let mut definition = zone(Vec::new()).definition().clone();
+    definition.auras = vec![party_shrine];
+    assert!(matches!(ZoneRuntime::new(definition.clone(), start_time()), Err(WorldCoreError::InvalidZone { .. })));
+    definition.auras = vec![shrine.clone(), shrine.clone()];
+    assert!(ZoneRuntime::new(definition.clone(), start_time()).is_err());
+    definition.auras = vec![AreaAura { aura: aura("blessing", 0.0, 5.0), ..shrine }];
+    assert!(ZoneRuntime::new(definition, start_time()).is_err());
+
+    let mut zone = zone(Vec::new());
+    zone.update_position("bard", Vec3::new(10.0, 0.0, 10.0)).unwrap();
+    let stacking = AuraStacking::Stack { max_stacks: 0 };
+    assert!(zone.attach_aura("bard", AuraDefinition { stacking, ..aura("song", 10.0, 1.0) }).is_err());
+    assert!(zone.attach_aura("bard", AuraDefinition { effects: Vec::new(), ..aura("song", 10.0, 1.0) }).is_err());
+}
@@ -0,0 +1,21 @@
+//! Helpers shared by the integration tests
+
+#![allow(dead_code)]
+
+use chrono::{Duration, TimeZone, Utc};
+use shared::Timestamp;
+
+/// Fixed start of every test timeline
+pub fn start_time() -> Timestamp {
+    Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+}
+
+/// `secs` seconds after [`start_time`]
+pub fn at(secs: i64) -> Timestamp {
+    start_time() + Duration::seconds(secs)
+}
+
+/// `millis` milliseconds after [`start_time`]
+pub fn at_millis(millis: i64) -> Timestamp {
+    start_time() + Duration::milliseconds(millis)
+}
@@ -0,0 +1,192 @@
+//! Mount tests: collections, speed contributions, mount and dismount rules and mounted-only
+//! abilities.
+
+use actor_core::aggregator::AggregatorImpl;
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use condition_core::{
+    ConditionChainConfig, ConditionConfig, ConditionContext, ConditionFunction, ConditionResolverTrait, ConditionResult,
+    ConditionValue,
+};
+use std::collections::HashSet;
+use std::sync::Mutex;
+use world_core::mounts::{
+    IndoorFlags, IsActorIndoorsFunction, MountConfig, MountDefinition, MountManager, MountSubsystem,
+};
+use world_core::WorldCoreError;
+
+/// Answers the default rules: combat from a set of actors, indoors from the flags
+struct Rules {
+    in_combat: Mutex<HashSet<String>>,
+    indoors: IsActorIndoorsFunction,
+}
+
+impl Rules {
+    fn set_combat(&self, actor_id: &str, in_combat: bool) {
+        let mut fighting = self.in_combat.lock().unwrap();
+        if in_combat {
+            fighting.insert(actor_id.to_string());
+        } else {
+            fighting.remove(actor_id);
+        }
+    }
+}
+
+#[async_trait]
+impl ConditionResolverTrait for Rules {
+    async fn resolve_condition(&self, config: &ConditionConfig, context: &ConditionContext) -> ConditionResult<bool> {
+        let actual = match config.function_name.as_str() {
+            "is_actor_in_combat" => {
+                let in_combat = self.in_combat.lock().unwrap().contains(&context.target.id);
+                ConditionValue::Boolean(in_combat)
+            }
+            _ => self.indoors.evaluate(&config.parameters, context).await?,
+        };
+        Ok(actual == config.value)
+    }
+
+    async fn resolve_conditions(
+        &self,
+        configs: &[ConditionConfig],
+        context: &ConditionContext,
+    ) -> ConditionResult<Vec<bool>> {
+        let mut results = Vec::new();
+        for config in configs {
+            results.push(self.resolve_condition(config, context).await?);
+        }
+        Ok(results)
+    }
+
+    async fn resolve_condition_chain(
+        &self,
+        chain: &ConditionChainConfig,
+        context: &ConditionContext,
+    ) -> ConditionResult<bool> {
+        Ok(self.resolve_conditions(&chain.conditions, context).await?.into_iter().all(|passed| passed))
+    }
+}
+
+/// Walking speed of every actor
+struct Legs;
+
+#[async_trait]
+impl Subsystem for Legs {
+    fn system_id(&self) -> &str {
+        "legs"
+    }
+
+    fn priority(&self) -> i64 {
+        100
+    }
+
+    async fn contribute(&self, _actor: &Actor, _context: &AggregationContext) -> ActorCoreResult<SubsystemOutput> {
+        let mut output = SubsystemOutput::new("legs".to_string());
+        output.add_contribution(Contribution::new("move_speed".to_string(), Bucket::Flat, 5.0, "legs".to_string()));
+        Ok(output)
+    }
+}
+
+fn config() -> MountConfig {
+    let horse = MountDefinition {
+        id: "horse".to_string(),
+        name: "Horse".to_string(),
+        speed_multiplier: 1.6,
+        abilities: vec!["trample".to_string()],
+    };
+    let tiger = MountDefinition {
+        id: "tiger".to_string(),
+        name: "Tiger".to_string(),
+        speed_multiplier: 2.0,
+        abilities: vec!["pounce".to_string()],
+    };
+    MountConfig { mounts: vec![horse, tiger], ..MountConfig::default() }
+}
+
+fn create_test_rules(flags: &Arc<IndoorFlags>) -> Arc<Rules> {
+    Arc::new(Rules { in_combat: Mutex::default(), indoors: IsActorIndoorsFunction::new(flags.clone()) })
+}
+
+fn create_test_mounts(rules: &Arc<Rules>) -> Arc<MountManager> {
+    Arc::new(MountManager::new(config()).unwrap().with_conditions(rules.clone()))
+}
+
+#[test]
+fn accounts_collect_defined_mounts() {
+    let mounts = create_test_mounts(&create_test_rules(&Arc::new(IndoorFlags::new())));
+    assert!(mounts.learn("account", "horse").unwrap());
+    assert!(!mounts.learn("account", "horse").unwrap());
+    assert!(matches!(mounts.learn("account", "dragon"), Err(WorldCoreError::InvalidMount { .. })));
+
+    // Saved collections come back without mounts that were removed since
+    let mut saved = mounts.collection("account");
+    saved.mounts.insert("retired_camel".to_string());
+    saved.account_id = "other".to_string();
+    mounts.restore(saved);
+    assert_eq!(mounts.collection("other").mounts.into_iter().collect::<Vec<_>>(), ["horse"]);
+
+    let duplicated = MountConfig { mounts: [config().mounts, config().mounts].concat(), ..MountConfig::default() };
+    assert!(MountManager::new(duplicated).is_err());
+}
+
+#[tokio::test]
+async fn riding_multiplies_the_speed_stat() {
+    let plugins = RegistryFactory::create_plugin_registry();
+    plugins.register(Arc::new(Legs)).unwrap();
+    let combiner = RegistryFactory::create_combiner_registry();
+    let rule = MergeRule { use_pipeline: true, operator: Operator::Sum, clamp_default: None };
+    combiner.set_rule("move_speed", rule).unwrap();
+    let caps_provider = ServiceFactory::create_caps_provider(RegistryFactory::create_cap_layer_registry());
+    let cache = CacheFactory::create_in_memory_cache(1024, 60);
+    let aggregator = Arc::new(AggregatorImpl::new(plugins.clone(), combiner, caps_provider, cache));
+    let mounts = Arc::new(MountManager::new(config()).unwrap().with_aggregator(aggregator.clone()));
+    plugins.register(Arc::new(MountSubsystem::new(mounts.clone()))).unwrap();
+
+    let hero = Actor::simple("hero", "Human", 10);
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("move_speed"), Some(5.0));
+    mounts.learn("account", "horse").unwrap();
+    // Mounting marks the speed stat dirty
+    mounts.mount("account", "hero", "horse", "plains").await.unwrap();
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("move_speed"), Some(8.0));
+    assert_eq!(mounts.dismount("hero").as_deref(), Some("horse"));
+    assert_eq!(aggregator.resolve(&hero).await.unwrap().get_stat("move_speed"), Some(5.0));
+}
+
+#[tokio::test]
+async fn combat_and_indoor_areas_keep_riders_off_their_mounts() {
+    let flags = Arc::new(IndoorFlags::new());
+    let rules = create_test_rules(&flags);
+    let mounts = create_test_mounts(&rules);
+    mounts.learn("account", "horse").unwrap();
+    assert!(mounts.mount("account", "hero", "tiger", "plains").await.is_err());
+
+    rules.set_combat("hero", true);
+    assert!(mounts.mount("account", "hero", "horse", "plains").await.is_err());
+    rules.set_combat("hero", false);
+    flags.set("hero", true);
+    assert!(mounts.mount("account", "hero", "horse", "inn").await.is_err());
+    flags.set("hero", false);
+    mounts.mount("account", "hero", "horse", "plains").await.unwrap();
+
+    // Riders stay mounted until a dismount condition holds
+    assert_eq!(mounts.enforce("hero", "plains").await, None);
+    rules.set_combat("hero", true);
+    assert_eq!(mounts.enforce("hero", "plains").await.as_deref(), Some("horse"));
+    assert!(mounts.riding("hero").is_none());
+}
+
+#[tokio::test]
+async fn mounted_only_abilities_need_the_right_mount() {
+    let mounts = create_test_mounts(&create_test_rules(&Arc::new(IndoorFlags::new())));
+    mounts.learn("account", "horse").unwrap();
+    mounts.learn("account", "tiger").unwrap();
+    assert!(!mounts.can_use_ability("hero", "trample"));
+    assert!(mounts.can_use_ability("hero", "fireball"));
+
+    mounts.mount("account", "hero", "horse", "plains").await.unwrap();
+    assert!(mounts.can_use_ability("hero", "trample"));
+    assert!(!mounts.can_use_ability("hero", "pounce"));
+    // Switching mounts swaps the abilities
+    mounts.mount("account", "hero", "tiger", "plains").await.unwrap();
+    assert!(mounts.can_use_ability("hero", "pounce"));
+    assert!(!mounts.can_use_ability("hero", "trample"));
+}
@@ -0,0 +1,103 @@
+//! Movement validation tests: speed, teleports, obstacles and zone bounds.
+
+mod common;
+
+use actor_core::types::Snapshot;
+use common::at_millis;
+use world_core::movement::{MoveVerdict, MovementConfig, MovementValidator, MovementViolationKind};
+use world_core::zones::ZoneDefinition;
+use world_core::{Bounds, Vec3, WorldCoreError};
+
+fn zone(id: &str) -> ZoneDefinition {
+    ZoneDefinition {
+        id: id.to_string(),
+        name: id.to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(200.0, 50.0, 200.0) },
+        cell_size: 16.0,
+        spawns: Vec::new(),
+        hazards: Vec::new(),
+        auras: Vec::new(),
+        weather: Default::default(),
+        // A wall across x = 100..102, z = 0..150
+        obstacles: vec![Bounds { min: Vec3::new(100.0, 0.0, 0.0), max: Vec3::new(102.0, 50.0, 150.0) }],
+        navigation: Default::default(),
+    }
+}
+
+fn kind(verdict: MoveVerdict) -> Option<MovementViolationKind> {
+    match verdict {
+        MoveVerdict::Accepted => None,
+        MoveVerdict::Corrected { violation, .. } => Some(violation.kind),
+    }
+}
+
+#[test]
+fn moves_are_held_to_the_speed_stat() {
+    let forest = zone("forest");
+    let mut validator = MovementValidator::new(MovementConfig::default());
+    let mut check =
+        |position: Vec3, millis: i64| validator.validate(&forest, "player", position, 5.0, false, at_millis(millis));
+
+    check(Vec3::new(10.0, 0.0, 10.0), 0).unwrap();
+    // 6 units in 1s at speed 5 is within the 1.25 tolerance
+    assert_eq!(check(Vec3::new(16.0, 0.0, 10.0), 1000).unwrap(), MoveVerdict::Accepted);
+
+    // Updates arriving in a burst are measured over the minimum interval
+    assert_eq!(check(Vec3::new(17.5, 0.0, 10.0), 1010).unwrap(), MoveVerdict::Accepted);
+
+    // 20 units in 1s is too fast; the client is put back at the last accepted position
+    let MoveVerdict::Corrected { position, violation } = check(Vec3::new(37.5, 0.0, 10.0), 2010).unwrap() else {
+        panic!("speed hack accepted");
+    };
+    assert_eq!(position, Vec3::new(17.5, 0.0, 10.0));
+    assert_eq!(violation.kind, MovementViolationKind::Speed);
+    assert!((violation.observed - 20.0).abs() < 1e-9 && (violation.limit - 6.25).abs() < 1e-9);
+
+    // Long jumps are teleports however long it has been
+    assert_eq!(kind(check(Vec3::new(90.0, 0.0, 10.0), 60_000).unwrap()), Some(MovementViolationKind::Teleport));
+}
+
+#[test]
+fn obstacles_and_bounds_stop_movement() {
+    let forest = zone("forest");
+    let mut validator = MovementValidator::new(MovementConfig::default());
+    let mut check = |position: Vec3, teleported: bool, millis: i64| {
+        validator.validate(&forest, "player", position, 5.0, teleported, at_millis(millis))
+    };
+
+    check(Vec3::new(98.0, 0.0, 10.0), false, 0).unwrap();
+    // Through the wall, though in range
+    assert_eq!(kind(check(Vec3::new(104.0, 0.0, 10.0), false, 1000).unwrap()), Some(MovementViolationKind::Collision));
+    // Server teleports skip the checks, not the bounds
+    assert_eq!(check(Vec3::new(104.0, 0.0, 10.0), true, 1000).unwrap(), MoveVerdict::Accepted);
+    let outside = check(Vec3::new(204.0, 0.0, 10.0), false, 2000).unwrap();
+    assert_eq!(kind(outside), Some(MovementViolationKind::OutOfBounds));
+
+    // First placements have nothing to snap back to and fail instead
+    let mut validator = MovementValidator::new(MovementConfig::default());
+    let placed = validator.validate(&forest, "npc", Vec3::new(101.0, 0.0, 10.0), 5.0, false, at_millis(0));
+    assert!(matches!(placed, Err(WorldCoreError::Blocked { .. })));
+    let placed = validator.validate(&forest, "npc", Vec3::new(-1.0, 0.0, 10.0), 5.0, false, at_millis(0));
+    assert!(matches!(placed, Err(WorldCoreError::OutOfBounds { .. })));
+
+    // Entering another zone is a first placement as well
+    validator.validate(&forest, "npc", Vec3::new(10.0, 0.0, 10.0), 5.0, false, at_millis(0)).unwrap();
+    let city = zone("city");
+    let entered = validator.validate(&city, "npc", Vec3::new(190.0, 0.0, 190.0), 5.0, false, at_millis(10)).unwrap();
+    assert_eq!(entered, MoveVerdict::Accepted);
+}
+
+#[test]
+fn max_speed_comes_from_the_snapshot() {
+    let config = MovementConfig::default();
+    let mut snapshot = Snapshot::new("player".to_string());
+    assert_eq!(config.max_speed(&snapshot), 5.0);
+    snapshot.primary.insert("move_speed".to_string(), 6.0);
+    assert_eq!(config.max_speed(&snapshot), 6.0);
+    // Derived stats include buffs and take precedence
+    snapshot.derived.insert("move_speed".to_string(), 7.5);
+    assert_eq!(config.max_speed(&snapshot), 7.5);
+
+    assert!(config.validate().is_ok());
+    assert!(MovementConfig { tolerance: 0.9, ..MovementConfig::default() }.validate().is_err());
+}
@@ -0,0 +1,94 @@
+//! Navigation tests: line of sight, budgeted path searches and the path cache.
+
+mod common;
+
+use common::at_millis;
+use std::sync::Arc;
+use world_core::navigation::{NavGrid, NavigationDefinition, Navigator, PathfindingConfig};
+use world_core::zones::ZoneDefinition;
+use world_core::{Bounds, Vec3, WorldCoreError};
+
+/// 100x100 zone with a 20 high wall across x = 40..44 from z = 0 to 80
+fn zone(walls: Vec<String>) -> ZoneDefinition {
+    ZoneDefinition {
+        id: "forest".to_string(),
+        name: "Whispering Forest".to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(100.0, 50.0, 100.0) },
+        cell_size: 16.0,
+        spawns: Vec::new(),
+        hazards: Vec::new(),
+        auras: Vec::new(),
+        weather: Default::default(),
+        obstacles: vec![Bounds { min: Vec3::new(40.0, 0.0, 0.0), max: Vec3::new(44.0, 20.0, 80.0) }],
+        navigation: NavigationDefinition { cell_size: 2.0, walls },
+    }
+}
+
+fn forest_navigator(config: PathfindingConfig) -> Navigator {
+    Navigator::new(Arc::new(NavGrid::build(&zone(Vec::new())).unwrap()), config)
+}
+
+#[test]
+fn obstacles_and_imported_walls_block_the_view() {
+    let grid = NavGrid::build(&zone(Vec::new())).unwrap();
+    assert!(!grid.has_line_of_sight(Vec3::new(20.0, 1.0, 50.0), Vec3::new(60.0, 1.0, 50.0)));
+    assert!(grid.has_line_of_sight(Vec3::new(20.0, 1.0, 90.0), Vec3::new(60.0, 1.0, 90.0)));
+    // Obstacles have a height; the view over the wall is clear
+    assert!(grid.has_line_of_sight(Vec3::new(20.0, 30.0, 50.0), Vec3::new(60.0, 30.0, 50.0)));
+    assert!(!grid.is_walkable(Vec3::new(42.0, 0.0, 50.0)));
+
+    // A wall cell imported at x = 20..22, z = 20..22
+    let mut walls = vec![String::new(); 10];
+    walls.push(format!("{}#", ".".repeat(10)));
+    let grid = NavGrid::build(&zone(walls)).unwrap();
+    assert!(!grid.has_line_of_sight(Vec3::new(10.0, 0.0, 21.0), Vec3::new(30.0, 0.0, 21.0)));
+    assert!(grid.has_line_of_sight(Vec3::new(10.0, 0.0, 25.0), Vec3::new(30.0, 0.0, 25.0)));
+
+    // Imports must fit the zone
+    assert!(NavGrid::build(&zone(vec!["#".repeat(51)])).is_err());
+}
+
+#[tokio::test]
+async fn paths_lead_around_obstacles_within_their_budget() {
+    // One cell per step: the search yields between every expansion and still gets there
+    let config = PathfindingConfig { expansions_per_step: 1, ..PathfindingConfig::default() };
+    let navigator = forest_navigator(config);
+    let (from, to) = (Vec3::new(20.0, 0.0, 50.0), Vec3::new(60.0, 0.0, 50.0));
+
+    let path = navigator.find_path(from, to, at_millis(0)).await.unwrap().unwrap();
+    assert_eq!(path.last(), Some(&to));
+    assert!(path.iter().any(|waypoint| waypoint.z > 80.0));
+    let mut legs = std::iter::once(from).chain(path.iter().copied()).zip(path.iter().copied());
+    assert!(legs.all(|(a, b)| navigator.grid().can_walk_straight(a, b)));
+
+    // Open ground needs no search
+    let direct = Vec3::new(60.0, 0.0, 90.0);
+    assert_eq!(navigator.find_path(Vec3::new(20.0, 0.0, 90.0), direct, at_millis(0)).await.unwrap(), Some(vec![direct]));
+    // Nothing stands inside an obstacle
+    assert_eq!(navigator.find_path(from, Vec3::new(42.0, 0.0, 50.0), at_millis(0)).await.unwrap(), None);
+    let outside = navigator.find_path(from, Vec3::new(120.0, 0.0, 50.0), at_millis(0)).await;
+    assert!(matches!(outside, Err(WorldCoreError::OutOfBounds { .. })));
+
+    // Searches give up once over budget
+    let config = PathfindingConfig { expansions_per_step: 5, max_expansions: 10, ..PathfindingConfig::default() };
+    assert_eq!(forest_navigator(config).find_path(from, to, at_millis(0)).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn npcs_heading_for_the_same_place_share_paths() {
+    let navigator = forest_navigator(PathfindingConfig::default());
+    let to = Vec3::new(60.0, 0.0, 50.0);
+    let path = navigator.find_path(Vec3::new(20.0, 0.0, 50.0), to, at_millis(0)).await.unwrap().unwrap();
+    assert_eq!(navigator.cached_paths(), 1);
+
+    // Starting on the cached path reuses the rest of it
+    let rest = navigator.find_path(path[0], to, at_millis(100)).await.unwrap().unwrap();
+    assert_eq!(rest.last(), Some(&to));
+    assert_eq!(navigator.cached_paths(), 1);
+
+    // Elsewhere, or once the path is stale, a new search runs
+    navigator.find_path(Vec3::new(10.0, 0.0, 10.0), to, at_millis(200)).await.unwrap().unwrap();
+    assert_eq!(navigator.cached_paths(), 2);
+    navigator.find_path(path[0], to, at_millis(10_000)).await.unwrap().unwrap();
+    assert_eq!(navigator.cached_paths(), 1);
+}
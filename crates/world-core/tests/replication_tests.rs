@@ -0,0 +1,90 @@
+//! Zone replication tests: full and delta replicas keeping a standby copy in step.
+
+mod common;
+
+use common::at;
+use shared::party::BuffMode;
+use world_core::auras::{AuraDefinition, AuraEffect, AuraStacking, AuraTargets};
+use world_core::zones::{SpawnDefinition, ZoneDefinition};
+use world_core::{Bounds, Vec3, ZoneEvent, ZoneRuntime};
+
+fn zone() -> ZoneDefinition {
+    ZoneDefinition {
+        id: "marsh".to_string(),
+        name: "Sunken Marsh".to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(500.0, 50.0, 500.0) },
+        cell_size: 16.0,
+        spawns: vec![SpawnDefinition {
+            id: "toads".to_string(),
+            template: "marsh_toad".to_string(),
+            center: Vec3::new(100.0, 0.0, 100.0),
+            radius: 10.0,
+            max_alive: 2,
+            respawn_secs: 30,
+        }],
+        hazards: Vec::new(),
+        auras: Vec::new(),
+        weather: Default::default(),
+        obstacles: Vec::new(),
+        navigation: Default::default(),
+    }
+}
+
+fn ward() -> AuraDefinition {
+    AuraDefinition {
+        id: "ward".to_string(),
+        radius: 10.0,
+        effects: vec![AuraEffect { stat: "armor".to_string(), value: 3.0, mode: BuffMode::Add }],
+        targets: AuraTargets::Everyone,
+        stacking: AuraStacking::Strongest,
+    }
+}
+
+fn spawned(events: Vec<ZoneEvent>) -> Vec<String> {
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            ZoneEvent::Spawned { entity_id, .. } => Some(entity_id),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn standbys_follow_full_and_delta_replicas() {
+    let mut primary = ZoneRuntime::with_seed(zone(), at(0), 7).unwrap().with_replication();
+    let mut standby = ZoneRuntime::with_seed(zone(), at(0), 7).unwrap();
+    let toads = spawned(primary.tick(at(0)));
+    primary.update_position("warden", Vec3::new(20.0, 0.0, 20.0)).unwrap();
+    primary.attach_aura("warden", ward()).unwrap();
+
+    // A full replica brings everything over, dropping what the standby had of its own
+    standby.update_position("ghost", Vec3::new(1.0, 0.0, 1.0)).unwrap();
+    let full = primary.replica(true);
+    assert_eq!(full.entities.len(), 3);
+    standby.apply_replica(full);
+    assert_eq!(standby.entity_count(), 3);
+    assert!(standby.position("ghost").is_none());
+    assert!(toads.iter().all(|toad| standby.is_spawned(toad)));
+    assert_eq!(standby.carried_auras("warden"), [ward()]);
+    assert_eq!(standby.weather(), primary.weather());
+
+    // Deltas only list what changed since
+    primary.remove(&toads[0], at(1));
+    primary.update_position("warden", Vec3::new(25.0, 0.0, 20.0)).unwrap();
+    primary.update_position("scout", Vec3::new(40.0, 0.0, 40.0)).unwrap();
+    primary.remove("scout", at(1));
+    primary.detach_aura("warden", "ward");
+    let delta = primary.replica(false);
+    assert_eq!(delta.entities.iter().map(|entity| entity.entity_id.as_str()).collect::<Vec<_>>(), ["warden"]);
+    assert_eq!(delta.removed, [toads[0].clone(), "scout".to_string()]);
+    standby.apply_replica(delta);
+    assert_eq!(standby.position("warden"), Some(Vec3::new(25.0, 0.0, 20.0)));
+    assert!(standby.position(&toads[0]).is_none());
+    assert!(standby.carried_auras("warden").is_empty());
+    assert!(primary.replica(false).entities.is_empty());
+
+    // Taking over, the standby carries on the spawns where the primary left them
+    assert_eq!(spawned(standby.tick(at(31))), ["marsh:toads:3"]);
+    assert_eq!(standby.spawned_npcs().len(), 2);
+}
@@ -0,0 +1,147 @@
+//! Zone runtime tests: spatial queries, spawns, hazards and weather.
+
+mod common;
+
+use chrono::Duration;
+use common::at;
+use std::collections::BTreeMap;
+use world_core::environment::HazardArea;
+use world_core::weather::WeatherTable;
+use world_core::zones::{SpatialIndex, SpawnDefinition, ZoneDefinition};
+use world_core::{Bounds, Vec3, WeatherKind, WorldCoreError, ZoneEvent, ZoneRuntime};
+
+fn zone() -> ZoneDefinition {
+    ZoneDefinition {
+        id: "forest".to_string(),
+        name: "Whispering Forest".to_string(),
+        bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(1000.0, 100.0, 1000.0) },
+        cell_size: 16.0,
+        spawns: vec![SpawnDefinition {
+            id: "wolves".to_string(),
+            template: "forest_wolf".to_string(),
+            center: Vec3::new(100.0, 0.0, 100.0),
+            radius: 10.0,
+            max_alive: 2,
+            respawn_secs: 30,
+        }],
+        hazards: vec![HazardArea {
+            id: "swamp".to_string(),
+            center: Vec3::new(500.0, 0.0, 500.0),
+            radius: 20.0,
+            resource: "health".to_string(),
+            per_pulse: -5.0,
+            interval_secs: 2,
+            weather: Vec::new(),
+        }],
+        auras: Vec::new(),
+        weather: WeatherTable { weights: BTreeMap::from([(WeatherKind::Rain, 1)]), min_secs: 60, max_secs: 60 },
+        obstacles: Vec::new(),
+        navigation: Default::default(),
+    }
+}
+
+#[test]
+fn nearby_queries_return_the_closest_entities_first() {
+    let mut index = SpatialIndex::new(8.0);
+    for i in 0..20 {
+        index.upsert(&format!("filler_{}", i), Vec3::new(500.0 + i as f64, 0.0, 0.0));
+    }
+    index.upsert("far", Vec3::new(40.0, 0.0, 0.0));
+    index.upsert("near", Vec3::new(3.0, 0.0, 4.0));
+    index.upsert("middle", Vec3::new(0.0, 0.0, 10.0));
+
+    let ids = |found: Vec<world_core::NearbyEntity>| found.into_iter().map(|entity| entity.entity_id).collect::<Vec<_>>();
+    assert_eq!(ids(index.within(Vec3::default(), 12.0, 10)), vec!["near", "middle"]);
+    assert_eq!(ids(index.within(Vec3::default(), 12.0, 1)), vec!["near"]);
+    // Huge radii fall back to scanning every entity
+    assert_eq!(index.within(Vec3::default(), 1e12, 100).len(), 23);
+
+    // Small radii visit the grid cells around the center; moving across cells keeps them consistent
+    assert_eq!(index.upsert("far", Vec3::new(1.0, 0.0, 0.0)), Some(Vec3::new(40.0, 0.0, 0.0)));
+    assert_eq!(ids(index.within(Vec3::default(), 2.0, 10)), vec!["far"]);
+    assert_eq!(index.remove("far"), Some(Vec3::new(1.0, 0.0, 0.0)));
+    assert!(index.within(Vec3::default(), 2.0, 10).is_empty());
+}
+
+#[test]
+fn spawns_fill_up_and_replace_npcs_after_their_delay() {
+    let mut runtime = ZoneRuntime::with_seed(zone(), at(0), 7).unwrap();
+    let spawned: Vec<String> = runtime
+        .tick(at(0))
+        .into_iter()
+        .filter_map(|event| match event {
+            ZoneEvent::Spawned { entity_id, position, .. } => {
+                assert!(position.distance(Vec3::new(100.0, 0.0, 100.0)) <= 10.0);
+                Some(entity_id)
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(spawned.len(), 2);
+    assert_eq!(runtime.entity_count(), 2);
+
+    runtime.remove(&spawned[0], at(1));
+    let respawned = |events: Vec<ZoneEvent>| events.iter().filter(|event| matches!(event, ZoneEvent::Spawned { .. })).count();
+    assert_eq!(respawned(runtime.tick(at(10))), 0);
+    assert_eq!(respawned(runtime.tick(at(31))), 1);
+    assert_eq!(runtime.entity_count(), 2);
+}
+
+#[test]
+fn hazards_pulse_on_the_entities_inside_them() {
+    let mut definition = zone();
+    definition.spawns.clear();
+    let mut runtime = ZoneRuntime::with_seed(definition, at(0), 7).unwrap();
+    runtime.update_position("player", Vec3::new(505.0, 0.0, 500.0)).unwrap();
+    runtime.update_position("bystander", Vec3::new(600.0, 0.0, 500.0)).unwrap();
+
+    assert!(runtime.tick(at(0)).is_empty());
+    assert_eq!(
+        runtime.tick(at(2)),
+        vec![ZoneEvent::HazardPulsed {
+            hazard_id: "swamp".to_string(),
+            resource: "health".to_string(),
+            amount: -5.0,
+            entity_ids: vec!["player".to_string()],
+        }]
+    );
+    assert!(runtime.tick(at(3)).is_empty());
+}
+
+#[test]
+fn positions_outside_the_zone_are_rejected() {
+    let mut runtime = ZoneRuntime::with_seed(zone(), at(0), 7).unwrap();
+    let error = runtime.update_position("player", Vec3::new(-1.0, 0.0, 0.0)).unwrap_err();
+    assert!(matches!(error, WorldCoreError::OutOfBounds { .. }));
+    assert!(runtime.update_position("player", Vec3::new(f64::NAN, 0.0, 0.0)).is_err());
+
+    let mut invalid = zone();
+    invalid.hazards[0].interval_secs = 0;
+    assert!(ZoneRuntime::new(invalid, at(0)).is_err());
+}
+
+#[test]
+fn weather_changes_when_it_runs_out() {
+    let mut definition = zone();
+    definition.weather = WeatherTable {
+        weights: BTreeMap::from([(WeatherKind::Rain, 1), (WeatherKind::Snow, 1)]),
+        min_secs: 60,
+        max_secs: 60,
+    };
+    let mut runtime = ZoneRuntime::with_seed(definition, at(0), 7).unwrap();
+    let first = runtime.weather();
+    assert_eq!(first.until, at(60));
+
+    let mut changed = false;
+    for round in 1..=20 {
+        let now = at(60 * round);
+        for event in runtime.tick(now) {
+            if let ZoneEvent::WeatherChanged { previous, current, until } = event {
+                assert_ne!(previous, current);
+                assert_eq!(until, now + Duration::seconds(60));
+                changed = true;
+            }
+        }
+    }
+    assert!(changed);
+}
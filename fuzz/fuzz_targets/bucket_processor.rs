@@ -0,0 +1,98 @@
+//! Arbitrary contribution sets through `process_contributions_in_order`.
+//!
+//! Checks that aggregation never panics, that clamping keeps results inside valid caps,
+//! that NaN inputs are never silently dropped, that finite bounded inputs give a finite
+//! result, and that the outcome does not depend on the order contributions arrive in.
+
+#![no_main]
+
+use actor_core::bucket_processor::process_contributions_in_order;
+use actor_core::enums::{AcrossLayerPolicy, Bucket};
+use actor_core::types::{Caps, Contribution};
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+
+/// Largest magnitude for which a finite result is guaranteed
+const BOUNDED: f64 = 1e3;
+
+/// Most contributions for which a finite result is guaranteed
+const BOUNDED_COUNT: usize = 32;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    initial_value: f64,
+    contributions: Vec<FuzzContribution>,
+    caps: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Arbitrary)]
+struct FuzzContribution {
+    bucket: u8,
+    value: f64,
+    priority: Option<i8>,
+    system: u8,
+}
+
+impl FuzzContribution {
+    fn to_contribution(&self) -> Contribution {
+        let bucket = match self.bucket % 4 {
+            0 => Bucket::Flat,
+            1 => Bucket::Mult,
+            2 => Bucket::PostAdd,
+            _ => Bucket::Override,
+        };
+        let mut contribution =
+            Contribution::new("attack".to_string(), bucket, self.value, format!("system_{}", self.system % 4));
+        contribution.priority = self.priority.map(i64::from);
+        contribution
+    }
+}
+
+fn same(a: f64, b: f64) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+}
+
+fuzz_target!(|input: Input| {
+    let contributions: Vec<Contribution> = input.contributions.iter().map(FuzzContribution::to_contribution).collect();
+    let caps = input
+        .caps
+        .map(|(min, max)| Caps::with_values("attack".to_string(), min, max, AcrossLayerPolicy::Intersect));
+
+    let result = process_contributions_in_order(contributions.clone(), input.initial_value, caps.as_ref())
+        .expect("aggregation never fails");
+
+    // A valid range always holds a non-NaN result
+    if let Some((min, max)) = input.caps {
+        if min <= max && !result.is_nan() {
+            assert!(min <= result && result <= max, "{} escaped the caps [{}, {}]", result, min, max);
+        }
+    }
+
+    // Without an override to replace it, a NaN input poisons the result; caps do not hide it
+    let overridden = contributions.iter().any(|contribution| contribution.bucket == Bucket::Override);
+    let nan_input =
+        input.initial_value.is_nan() || contributions.iter().any(|contribution| contribution.value.is_nan());
+    if nan_input && !overridden {
+        assert!(result.is_nan(), "NaN input gave {}", result);
+    }
+
+    // NaN and infinities only come from non-finite inputs or overflow
+    let bounded = |value: f64| value.is_finite() && value.abs() <= BOUNDED;
+    if bounded(input.initial_value)
+        && contributions.len() <= BOUNDED_COUNT
+        && contributions.iter().all(|contribution| bounded(contribution.value))
+        && input.caps.map_or(true, |(min, max)| min.is_finite() && max.is_finite())
+    {
+        assert!(result.is_finite(), "bounded inputs gave {}", result);
+    }
+
+    // Contributions are sorted before they are applied, so arrival order does not matter;
+    // NaN values have no order and are left out of this check
+    if contributions.iter().all(|contribution| !contribution.value.is_nan()) {
+        let mut reversed = contributions;
+        reversed.reverse();
+        let again = process_contributions_in_order(reversed, input.initial_value, caps.as_ref())
+            .expect("aggregation never fails");
+        assert!(same(result, again), "order changed the result: {} vs {}", result, again);
+    }
+});
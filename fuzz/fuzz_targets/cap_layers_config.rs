@@ -0,0 +1,20 @@
+//! Arbitrary text into the cap layers loader.
+//!
+//! Loading must fail with an error rather than panic, and every cap of a configuration
+//! it accepts must be an ordered range.
+
+#![no_main]
+
+use actor_core::registry::loader::parse_cap_layers;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(registry) = parse_cap_layers(content) {
+        for (layer, id, caps) in registry.caps() {
+            assert!(caps.is_valid(), "cap {} of layer {} accepted as [{}, {}]", id, layer, caps.min, caps.max);
+        }
+    }
+});
@@ -0,0 +1,20 @@
+//! Arbitrary text into the combiner loader.
+//!
+//! Loading must fail with an error rather than panic, and the clamp of every rule it
+//! accepts must be an ordered range.
+
+#![no_main]
+
+use actor_core::registry::loader::parse_combiner;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(registry) = parse_combiner(content) {
+        for (id, clamp) in registry.clamps() {
+            assert!(clamp.is_valid(), "rule {} accepted with clamp [{}, {}]", id, clamp.min, clamp.max);
+        }
+    }
+});
@@ -0,0 +1,23 @@
+//! Arbitrary YAML into the element configuration loader.
+//!
+//! Loading must fail with an error rather than panic, and a configuration it accepts
+//! must load again once written back out.
+
+#![no_main]
+
+use element_core::config::YamlConfigLoader;
+use libfuzzer_sys::fuzz_target;
+use std::path::PathBuf;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(content) = std::str::from_utf8(data) else {
+        return;
+    };
+    let loader = YamlConfigLoader::new(PathBuf::from("."));
+    if let Ok(config) = loader.parse_element_config(content) {
+        let written = serde_yaml::to_string(&config).expect("accepted configurations serialize");
+        if let Err(e) = loader.parse_element_config(&written) {
+            panic!("accepted configuration does not load again: {}\n{}", e, written);
+        }
+    }
+});
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub economy: EconomySettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// How economy events are bucketed and queried
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EconomySettings {
+    /// Length of a bucket; changing it only affects buckets written afterwards
+    pub bucket_secs: u64,
+    /// How often aggregated events are written to MongoDB
+    pub flush_interval_secs: u64,
+    /// Range queried when a request gives none
+    pub default_range_hours: u64,
+    /// Most buckets a query may span
+    pub max_buckets: u64,
+    /// Gateway roles allowed to read the metrics
+    pub viewer_roles: Vec<String>,
+}
+
+impl Default for EconomySettings {
+    fn default() -> Self {
+        Self {
+            bucket_secs: 3600,
+            flush_interval_secs: 10,
+            default_range_hours: 7 * 24,
+            max_buckets: 2000,
+            viewer_roles: vec!["admin".to_string(), "analyst".to_string()],
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/analytics-service.yaml".to_string());
+
+        let config = if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            serde_yaml::from_str(&content)?
+        } else {
+            tracing::warn!("Config file not found at {}, using environment variables", config_path);
+            Self::from_env()
+        };
+        let economy = &config.economy;
+        if economy.bucket_secs == 0 || economy.flush_interval_secs == 0 || economy.max_buckets == 0 {
+            return Err(ConfigError::InvalidConfig(
+                "Economy bucket length, flush interval and max buckets must be at least 1".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    fn from_env() -> Self {
+        let server = ServerConfig {
+            port: env::var("ANALYTICS_SERVICE_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("ANALYTICS_SERVICE_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_analytics".to_string()),
+        };
+
+        Config { server, database, economy: EconomySettings::default() }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
@@ -0,0 +1,486 @@
+//! Economy metrics aggregated from bus events into time buckets.
+//!
+//! Every currency change, market sale and loot roll adds to the [`BucketTotals`] of the
+//! bucket its timestamp falls in:
+//! - `currency` buckets per currency and source (the reason's first token, e.g. `quest`):
+//!   credited amounts are faucets, debited amounts sinks
+//! - `market` buckets per item: sales, quantity sold, volume, fees and unit price range
+//! - `loot` buckets per loot source count its rolls, and per source and item the rolls
+//!   that dropped the item and the quantity dropped
+//!
+//! The [`EconomyAggregator`] sums events in memory until they are flushed to MongoDB,
+//! where totals are incremented, so every analytics-service instance adds to the same
+//! buckets. The query side turns stored buckets into the series the CMS dashboard charts:
+//! faucets and sinks, market prices, realized drop rates and inflation indicators.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use shared::events::topics::{CurrencyChanged, LootDropped, MarketTrade};
+use shared::Timestamp;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Currency,
+    Market,
+    Loot,
+}
+
+impl Metric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Metric::Currency => "currency",
+            Metric::Market => "market",
+            Metric::Loot => "loot",
+        }
+    }
+}
+
+/// Sums over one bucket; which fields apply depends on the metric
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BucketTotals {
+    /// Currency changes, sales, or loot rolls
+    pub count: i64,
+    /// Currency credited
+    pub credited: i64,
+    /// Currency debited, as a positive amount
+    pub debited: i64,
+    /// Items sold or dropped
+    pub quantity: i64,
+    /// Currency paid for sales
+    pub volume: i64,
+    /// Market cuts, removed from the economy
+    pub fees: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_unit_price: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_unit_price: Option<f64>,
+}
+
+impl BucketTotals {
+    /// Average price paid per item sold
+    pub fn average_unit_price(&self) -> Option<f64> {
+        (self.quantity > 0).then(|| self.volume as f64 / self.quantity as f64)
+    }
+
+    pub fn add(&mut self, other: &BucketTotals) {
+        self.count += other.count;
+        self.credited += other.credited;
+        self.debited += other.debited;
+        self.quantity += other.quantity;
+        self.volume += other.volume;
+        self.fees += other.fees;
+        self.min_unit_price = merge(self.min_unit_price, other.min_unit_price, f64::min);
+        self.max_unit_price = merge(self.max_unit_price, other.max_unit_price, f64::max);
+    }
+}
+
+fn merge(a: Option<f64>, b: Option<f64>, pick: fn(f64, f64) -> f64) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(pick(a, b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Totals of one metric, key and detail over one bucket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EconomyBucket {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub metric: Metric,
+    /// Currency, item, or loot source
+    pub key: String,
+    /// Source of currency buckets, dropped item of loot buckets; empty otherwise
+    pub detail: String,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub start: Timestamp,
+    pub totals: BucketTotals,
+}
+
+impl EconomyBucket {
+    fn new(metric: Metric, key: &str, detail: &str, start: Timestamp) -> Self {
+        Self {
+            id: format!("{}|{}|{}|{}", metric.as_str(), key, detail, start.timestamp()),
+            metric,
+            key: key.to_string(),
+            detail: detail.to_string(),
+            start,
+            totals: BucketTotals::default(),
+        }
+    }
+}
+
+/// Sums events into buckets until they are flushed
+#[derive(Debug)]
+pub struct EconomyAggregator {
+    bucket_secs: i64,
+    pending: HashMap<String, EconomyBucket>,
+}
+
+impl EconomyAggregator {
+    pub fn new(bucket_secs: u64) -> Self {
+        Self { bucket_secs: bucket_secs.max(1) as i64, pending: HashMap::new() }
+    }
+
+    /// Start of the bucket `at` falls in
+    pub fn bucket_start(&self, at: Timestamp) -> Timestamp {
+        bucket_start(at, self.bucket_secs)
+    }
+
+    pub fn record_currency(&mut self, changed: &CurrencyChanged) {
+        let totals = BucketTotals {
+            count: 1,
+            credited: changed.delta.max(0),
+            debited: changed.delta.min(0).saturating_neg(),
+            ..BucketTotals::default()
+        };
+        self.add(Metric::Currency, &changed.currency, changed.source(), changed.at, totals);
+    }
+
+    pub fn record_trade(&mut self, trade: &MarketTrade) {
+        let unit_price = trade.unit_price();
+        let totals = BucketTotals {
+            count: 1,
+            quantity: trade.quantity as i64,
+            volume: trade.price as i64,
+            fees: trade.fee as i64,
+            min_unit_price: Some(unit_price),
+            max_unit_price: Some(unit_price),
+            ..BucketTotals::default()
+        };
+        self.add(Metric::Market, &trade.item_id, "", trade.at, totals);
+    }
+
+    pub fn record_loot(&mut self, loot: &LootDropped) {
+        self.add(Metric::Loot, &loot.source, "", loot.at, BucketTotals { count: 1, ..BucketTotals::default() });
+        // Stacks of one item in the same roll are a single drop
+        let mut dropped: BTreeMap<&str, i64> = BTreeMap::new();
+        for item in &loot.items {
+            *dropped.entry(&item.item_id).or_default() += item.quantity as i64;
+        }
+        for (item_id, quantity) in dropped {
+            let totals = BucketTotals { count: 1, quantity, ..BucketTotals::default() };
+            self.add(Metric::Loot, &loot.source, item_id, loot.at, totals);
+        }
+    }
+
+    /// Take the buckets summed since the last drain
+    pub fn drain(&mut self) -> Vec<EconomyBucket> {
+        self.pending.drain().map(|(_, bucket)| bucket).collect()
+    }
+
+    /// Add back buckets that could not be stored
+    pub fn restore(&mut self, buckets: Vec<EconomyBucket>) {
+        for bucket in buckets {
+            match self.pending.get_mut(&bucket.id) {
+                Some(pending) => pending.totals.add(&bucket.totals),
+                None => {
+                    self.pending.insert(bucket.id.clone(), bucket);
+                }
+            }
+        }
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    fn add(&mut self, metric: Metric, key: &str, detail: &str, at: Timestamp, totals: BucketTotals) {
+        let bucket = EconomyBucket::new(metric, key, detail, self.bucket_start(at));
+        self.pending.entry(bucket.id.clone()).or_insert(bucket).totals.add(&totals);
+    }
+}
+
+pub fn bucket_start(at: Timestamp, bucket_secs: i64) -> Timestamp {
+    let secs = at.timestamp();
+    let start = secs - secs.rem_euclid(bucket_secs);
+    Utc.timestamp_opt(start, 0).single().unwrap_or(at)
+}
+
+/// Faucets and sinks of a currency over one bucket
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FlowPoint {
+    pub start: Timestamp,
+    /// Amount credited per source
+    pub faucets: BTreeMap<String, i64>,
+    /// Amount debited per source
+    pub sinks: BTreeMap<String, i64>,
+    /// Faucets less sinks
+    pub net: i64,
+}
+
+/// Flows of one currency from its buckets
+pub fn currency_flows(buckets: &[EconomyBucket]) -> Vec<FlowPoint> {
+    let mut points: BTreeMap<Timestamp, FlowPoint> = BTreeMap::new();
+    for bucket in buckets.iter().filter(|bucket| bucket.metric == Metric::Currency) {
+        let point = points.entry(bucket.start).or_insert_with(|| FlowPoint {
+            start: bucket.start,
+            faucets: BTreeMap::new(),
+            sinks: BTreeMap::new(),
+            net: 0,
+        });
+        let totals = &bucket.totals;
+        if totals.credited > 0 {
+            *point.faucets.entry(bucket.detail.clone()).or_default() += totals.credited;
+        }
+        if totals.debited > 0 {
+            *point.sinks.entry(bucket.detail.clone()).or_default() += totals.debited;
+        }
+        point.net += totals.credited - totals.debited;
+    }
+    points.into_values().collect()
+}
+
+/// Market activity over one bucket
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketPoint {
+    pub start: Timestamp,
+    pub sales: i64,
+    pub quantity: i64,
+    pub volume: i64,
+    pub fees: i64,
+    pub average_unit_price: Option<f64>,
+    pub min_unit_price: Option<f64>,
+    pub max_unit_price: Option<f64>,
+}
+
+/// Market activity per bucket, across the items of `buckets`
+pub fn market_series(buckets: &[EconomyBucket]) -> Vec<MarketPoint> {
+    let mut totals: BTreeMap<Timestamp, BucketTotals> = BTreeMap::new();
+    for bucket in buckets.iter().filter(|bucket| bucket.metric == Metric::Market) {
+        totals.entry(bucket.start).or_default().add(&bucket.totals);
+    }
+    totals
+        .into_iter()
+        .map(|(start, totals)| MarketPoint {
+            start,
+            sales: totals.count,
+            quantity: totals.quantity,
+            volume: totals.volume,
+            fees: totals.fees,
+            average_unit_price: totals.average_unit_price(),
+            min_unit_price: totals.min_unit_price,
+            max_unit_price: totals.max_unit_price,
+        })
+        .collect()
+}
+
+/// How often an item actually dropped from a loot source
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DropRate {
+    pub source: String,
+    pub item_id: String,
+    pub rolls: i64,
+    /// Rolls that dropped the item
+    pub drops: i64,
+    pub quantity: i64,
+    /// `drops / rolls`
+    pub rate: f64,
+}
+
+/// Realized drop rates over all of `buckets`
+pub fn drop_rates(buckets: &[EconomyBucket]) -> Vec<DropRate> {
+    let mut rolls: HashMap<&str, i64> = HashMap::new();
+    let mut items: BTreeMap<(&str, &str), BucketTotals> = BTreeMap::new();
+    for bucket in buckets.iter().filter(|bucket| bucket.metric == Metric::Loot) {
+        if bucket.detail.is_empty() {
+            *rolls.entry(bucket.key.as_str()).or_default() += bucket.totals.count;
+        } else {
+            items.entry((bucket.key.as_str(), bucket.detail.as_str())).or_default().add(&bucket.totals);
+        }
+    }
+    items
+        .into_iter()
+        .map(|((source, item_id), totals)| {
+            let rolls = rolls.get(source).copied().unwrap_or_default().max(totals.count);
+            DropRate {
+                source: source.to_string(),
+                item_id: item_id.to_string(),
+                rolls,
+                drops: totals.count,
+                quantity: totals.quantity,
+                rate: totals.count as f64 / rolls as f64,
+            }
+        })
+        .collect()
+}
+
+/// Inflation indicators over one bucket
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InflationPoint {
+    pub start: Timestamp,
+    /// Currency created less currency destroyed in the bucket
+    pub net_issuance: i64,
+    /// Net issuance since the start of the range
+    pub supply_change: i64,
+    /// Average unit price of the items sold, relative to their first bucket in the
+    /// range, 100 being unchanged; none without sales
+    pub price_index: Option<f64>,
+}
+
+/// Inflation of a currency from its buckets and the market's, which sells in it
+pub fn inflation(currency: &[EconomyBucket], market: &[EconomyBucket]) -> Vec<InflationPoint> {
+    let flows: BTreeMap<Timestamp, i64> =
+        currency_flows(currency).into_iter().map(|point| (point.start, point.net)).collect();
+
+    // Prices per bucket and item, against each item's first price in the range
+    let mut prices: BTreeMap<Timestamp, Vec<(&str, f64)>> = BTreeMap::new();
+    for bucket in market.iter().filter(|bucket| bucket.metric == Metric::Market) {
+        if let Some(price) = bucket.totals.average_unit_price() {
+            prices.entry(bucket.start).or_default().push((bucket.key.as_str(), price));
+        }
+    }
+    let mut base: HashMap<&str, f64> = HashMap::new();
+    let mut index: BTreeMap<Timestamp, f64> = BTreeMap::new();
+    for (start, sold) in &prices {
+        let ratios: Vec<f64> = sold
+            .iter()
+            .filter_map(|(item_id, price)| {
+                let base = *base.entry(*item_id).or_insert(*price);
+                (base > 0.0).then(|| price / base)
+            })
+            .collect();
+        if !ratios.is_empty() {
+            index.insert(*start, 100.0 * ratios.iter().sum::<f64>() / ratios.len() as f64);
+        }
+    }
+
+    let starts: BTreeSet<Timestamp> = flows.keys().chain(prices.keys()).copied().collect();
+    let mut supply_change = 0;
+    starts
+        .into_iter()
+        .map(|start| {
+            let net_issuance = flows.get(&start).copied().unwrap_or_default();
+            supply_change += net_issuance;
+            InflationPoint { start, net_issuance, supply_change, price_index: index.get(&start).copied() }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use shared::events::topics::DroppedItem;
+    use shared::wallet::TransactionKind;
+
+    fn at(minutes: i64) -> Timestamp {
+        Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn currency(delta: i64, reason: &str, minutes: i64) -> CurrencyChanged {
+        CurrencyChanged {
+            transaction_id: "t".to_string(),
+            actor_id: "hero".to_string(),
+            currency: "gold".to_string(),
+            delta,
+            balance: 0,
+            kind: if delta > 0 { TransactionKind::Earn } else { TransactionKind::Spend },
+            reason: reason.to_string(),
+            at: at(minutes),
+        }
+    }
+
+    fn trade(item_id: &str, quantity: u32, price: u64, minutes: i64) -> MarketTrade {
+        MarketTrade {
+            listing_id: "l".to_string(),
+            item_id: item_id.to_string(),
+            quantity,
+            seller_id: "seller".to_string(),
+            buyer_id: "buyer".to_string(),
+            price,
+            fee: price / 20,
+            buyout: false,
+            reference_unit_price: None,
+            at: at(minutes),
+        }
+    }
+
+    fn loot(items: &[(&str, u32)], minutes: i64) -> LootDropped {
+        LootDropped {
+            actor_id: "hero".to_string(),
+            source: "forest_wolf".to_string(),
+            items: items
+                .iter()
+                .map(|(item_id, quantity)| DroppedItem { item_id: item_id.to_string(), quantity: *quantity })
+                .collect(),
+            at: at(minutes),
+        }
+    }
+
+    #[test]
+    fn currency_changes_become_faucets_and_sinks_per_bucket() {
+        let mut aggregator = EconomyAggregator::new(3600);
+        aggregator.record_currency(&currency(100, "quest:1042", 5));
+        aggregator.record_currency(&currency(50, "quest:7", 30));
+        aggregator.record_currency(&currency(-30, "repair", 40));
+        aggregator.record_currency(&currency(-20, "market:listing-1:deposit", 70));
+        let buckets = aggregator.drain();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(aggregator.pending(), 0);
+
+        let flows = currency_flows(&buckets);
+        assert_eq!(flows.len(), 2);
+        assert_eq!((flows[0].start, flows[0].faucets["quest"], flows[0].sinks["repair"]), (at(0), 150, 30));
+        assert_eq!(flows[0].net, 120);
+        assert_eq!((flows[1].start, flows[1].net), (at(60), -20));
+    }
+
+    #[test]
+    fn failed_flushes_are_merged_back() {
+        let mut aggregator = EconomyAggregator::new(3600);
+        aggregator.record_trade(&trade("sword", 1, 200, 0));
+        let failed = aggregator.drain();
+        aggregator.record_trade(&trade("sword", 2, 100, 10));
+        aggregator.restore(failed);
+
+        let buckets = aggregator.drain();
+        assert_eq!(buckets.len(), 1);
+        let totals = &buckets[0].totals;
+        assert_eq!((totals.count, totals.quantity, totals.volume, totals.fees), (2, 3, 300, 15));
+        assert_eq!((totals.min_unit_price, totals.max_unit_price), (Some(50.0), Some(200.0)));
+        assert_eq!(market_series(&buckets)[0].average_unit_price, Some(100.0));
+    }
+
+    #[test]
+    fn drop_rates_count_rolls_that_dropped_nothing() {
+        let mut aggregator = EconomyAggregator::new(3600);
+        aggregator.record_loot(&loot(&[("pelt", 1), ("pelt", 2), ("fang", 1)], 0));
+        aggregator.record_loot(&loot(&[("pelt", 1)], 10));
+        aggregator.record_loot(&loot(&[], 70));
+        aggregator.record_loot(&loot(&[], 80));
+
+        let rates = drop_rates(&aggregator.drain());
+        assert_eq!(rates.len(), 2);
+        let (fang, pelt) = (&rates[0], &rates[1]);
+        assert_eq!((pelt.rolls, pelt.drops, pelt.quantity, pelt.rate), (4, 2, 4, 0.5));
+        assert_eq!((fang.item_id.as_str(), fang.rate), ("fang", 0.25));
+    }
+
+    #[test]
+    fn inflation_tracks_supply_and_prices() {
+        let mut aggregator = EconomyAggregator::new(3600);
+        aggregator.record_currency(&currency(1000, "quest", 0));
+        aggregator.record_currency(&currency(-200, "repair", 10));
+        aggregator.record_trade(&trade("sword", 1, 100, 0));
+        aggregator.record_trade(&trade("potion", 10, 50, 0));
+        aggregator.record_currency(&currency(500, "quest", 60));
+        aggregator.record_trade(&trade("sword", 1, 150, 60));
+        // Potions hold their price; a new item sets its own base
+        aggregator.record_trade(&trade("potion", 5, 25, 60));
+        aggregator.record_trade(&trade("shield", 1, 400, 60));
+        let buckets = aggregator.drain();
+        let (currency, market): (Vec<_>, Vec<_>) =
+            buckets.into_iter().partition(|bucket| bucket.metric == Metric::Currency);
+
+        let points = inflation(&currency, &market);
+        assert_eq!(points.len(), 2);
+        assert_eq!((points[0].net_issuance, points[0].supply_change, points[0].price_index), (800, 800, Some(100.0)));
+        assert_eq!((points[1].net_issuance, points[1].supply_change), (500, 1300));
+        // (150% + 100% + 100%) / 3
+        assert!((points[1].price_index.unwrap() - 116.666).abs() < 0.01);
+    }
+}
@@ -0,0 +1,177 @@
+//! Economy queries for the CMS dashboard, reached through the gateway.
+//!
+//! Every endpoint reads the buckets starting in `[from, to)`, the last
+//! `default_range_hours` unless given, and is restricted to the configured viewer roles.
+
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use shared::Timestamp;
+
+use crate::config::EconomySettings;
+use crate::economy::{self, DropRate, FlowPoint, InflationPoint, MarketPoint, Metric};
+use crate::store::{BucketQuery, EconomyStore};
+
+/// Trusted gateway header carrying the authenticated user's roles, comma-separated
+const USER_ROLES_HEADER: &str = "x-user-roles";
+
+#[derive(Clone)]
+pub struct AppState {
+    pub store: EconomyStore,
+    pub settings: Arc<EconomySettings>,
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/economy/currencies/:currency/flows", get(currency_flows))
+        .route("/economy/currencies/:currency/inflation", get(inflation))
+        .route("/economy/market", get(market))
+        .route("/economy/drops", get(drop_rates))
+        .with_state(state)
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum QueryError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+}
+
+impl IntoResponse for QueryError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            QueryError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            QueryError::Forbidden(_) => StatusCode::FORBIDDEN,
+            QueryError::Database(e) => {
+                tracing::error!("Database error: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(json!({ "success": false, "error": self.to_string() }))).into_response()
+    }
+}
+
+pub type QueryResult<T> = Result<T, QueryError>;
+
+/// Roles of the user calling through the gateway
+pub struct Viewer {
+    pub roles: Vec<String>,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Viewer {
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let roles: Vec<String> = parts
+            .headers
+            .get(USER_ROLES_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|role| !role.is_empty())
+            .map(str::to_string)
+            .collect();
+        if roles.is_empty() {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "success": false, "error": "User not authenticated" })),
+            ));
+        }
+        Ok(Self { roles })
+    }
+}
+
+/// Range and filters of a query
+#[derive(Debug, Default, Deserialize)]
+pub struct RangeQuery {
+    pub from: Option<Timestamp>,
+    pub to: Option<Timestamp>,
+    /// Market item
+    pub item_id: Option<String>,
+    /// Loot source
+    pub source: Option<String>,
+}
+
+impl AppState {
+    /// Check the viewer may read metrics and build the bucket query
+    fn bucket_query(
+        &self,
+        viewer: &Viewer,
+        metric: Metric,
+        key: Option<String>,
+        range: &RangeQuery,
+    ) -> QueryResult<BucketQuery> {
+        if !viewer.roles.iter().any(|role| self.settings.viewer_roles.contains(role)) {
+            return Err(QueryError::Forbidden("Analytics viewer role required".to_string()));
+        }
+        let to = range.to.unwrap_or_else(Utc::now);
+        let from = range.from.unwrap_or(to - Duration::hours(self.settings.default_range_hours as i64));
+        if from >= to {
+            return Err(QueryError::BadRequest("from must be before to".to_string()));
+        }
+        let buckets = (to - from).num_seconds() / self.settings.bucket_secs.max(1) as i64;
+        if buckets > self.settings.max_buckets as i64 {
+            let message = format!("The range spans more than {} buckets", self.settings.max_buckets);
+            return Err(QueryError::BadRequest(message));
+        }
+        Ok(BucketQuery { metric, key, from, to })
+    }
+}
+
+/// Faucets and sinks of a currency per bucket
+async fn currency_flows(
+    State(state): State<AppState>,
+    viewer: Viewer,
+    Path(currency): Path<String>,
+    Query(range): Query<RangeQuery>,
+) -> QueryResult<Json<Vec<FlowPoint>>> {
+    let query = state.bucket_query(&viewer, Metric::Currency, Some(currency), &range)?;
+    Ok(Json(economy::currency_flows(&state.store.find(&query).await?)))
+}
+
+/// Money supply change and market price index per bucket; market prices are paid in
+/// the queried currency
+async fn inflation(
+    State(state): State<AppState>,
+    viewer: Viewer,
+    Path(currency): Path<String>,
+    Query(range): Query<RangeQuery>,
+) -> QueryResult<Json<Vec<InflationPoint>>> {
+    let flows = state.bucket_query(&viewer, Metric::Currency, Some(currency), &range)?;
+    let sales = BucketQuery { metric: Metric::Market, key: None, ..flows.clone() };
+    let (flows, sales) = (state.store.find(&flows).await?, state.store.find(&sales).await?);
+    Ok(Json(economy::inflation(&flows, &sales)))
+}
+
+/// Market activity per bucket, of one item or all of them
+async fn market(
+    State(state): State<AppState>,
+    viewer: Viewer,
+    Query(range): Query<RangeQuery>,
+) -> QueryResult<Json<Vec<MarketPoint>>> {
+    let query = state.bucket_query(&viewer, Metric::Market, range.item_id.clone(), &range)?;
+    Ok(Json(economy::market_series(&state.store.find(&query).await?)))
+}
+
+/// Realized drop rates over the range, of one loot source or all of them
+async fn drop_rates(
+    State(state): State<AppState>,
+    viewer: Viewer,
+    Query(range): Query<RangeQuery>,
+) -> QueryResult<Json<Vec<DropRate>>> {
+    let query = state.bucket_query(&viewer, Metric::Loot, range.source.clone(), &range)?;
+    Ok(Json(economy::drop_rates(&state.store.find(&query).await?)))
+}
@@ -1,26 +1,99 @@
+mod config;
+mod economy;
+mod handlers;
+mod pipeline;
+mod store;
+
 use axum::{
     routing::get,
     Router,
 };
+use config::Config;
+use handlers::AppState;
+use pipeline::EconomyPipeline;
+use shared::events::topics::{CurrencyChanged, LootDropped, MarketTrade, CURRENCY_CHANGED, LOOT_DROPPED, MARKET_TRADE};
+use shared::events::{connect, BusConfig, Consumer, ConsumerOptions, EventBus, Message, Topic};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use std::time::Duration;
+use store::EconomyStore;
+
+/// Consumer name, a single topic token
+const CONSUMER: &str = "analytics_service";
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+    let store = EconomyStore::new(&database);
+    if let Err(e) = store.ensure_indexes().await {
+        tracing::warn!("Failed to create indexes: {}", e);
+    }
+
+    // Economy events are summed in memory and flushed into their buckets
+    let settings = Arc::new(config.economy.clone());
+    let pipeline = Arc::new(EconomyPipeline::new(settings.bucket_secs, store.clone()));
+    let flusher = pipeline.spawn_flusher(Duration::from_secs(settings.flush_interval_secs));
+    let bus = connect(&BusConfig::from_env()).await.unwrap();
+    aggregate(&bus, &pipeline, CURRENCY_CHANGED, |pipeline, changed: CurrencyChanged| {
+        pipeline.currency_changed(&changed)
+    })
+    .await;
+    aggregate(&bus, &pipeline, MARKET_TRADE, |pipeline, trade: MarketTrade| pipeline.market_trade(&trade)).await;
+    aggregate(&bus, &pipeline, LOOT_DROPPED, |pipeline, loot: LootDropped| pipeline.loot_dropped(&loot)).await;
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/", get(root))
+        .merge(handlers::routes(AppState { store, settings }));
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 analytics-service server starting on {}", addr);
-    
+
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    flusher.abort();
+    let pending = pipeline.flush().await;
+    if pending > 0 {
+        tracing::warn!("⚠️  {} economy bucket(s) could not be stored", pending);
+    }
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 analytics-service stopped");
+    telemetry.shutdown();
+}
+
+/// Feed an economy topic to the pipeline
+async fn aggregate<M: Message + Clone>(
+    bus: &Arc<dyn EventBus>,
+    pipeline: &Arc<EconomyPipeline>,
+    topic: Topic<M>,
+    record: fn(&EconomyPipeline, M),
+) {
+    let pipeline = pipeline.clone();
+    Consumer::new(bus.clone(), topic, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: M, _| {
+            record(&pipeline, event);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
 }
 
 async fn health_check() -> &'static str {
@@ -30,4 +103,3 @@ async fn health_check() -> &'static str {
 async fn root() -> &'static str {
     "Hello from analytics-service!"
 }
-
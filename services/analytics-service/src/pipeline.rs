@@ -0,0 +1,66 @@
+//! Feeds economy events to the aggregator and flushes it to MongoDB.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use shared::events::topics::{CurrencyChanged, LootDropped, MarketTrade};
+use tokio::task::JoinHandle;
+
+use crate::economy::EconomyAggregator;
+use crate::store::EconomyStore;
+
+pub struct EconomyPipeline {
+    aggregator: Mutex<EconomyAggregator>,
+    store: EconomyStore,
+}
+
+impl EconomyPipeline {
+    pub fn new(bucket_secs: u64, store: EconomyStore) -> Self {
+        Self { aggregator: Mutex::new(EconomyAggregator::new(bucket_secs)), store }
+    }
+
+    pub fn currency_changed(&self, changed: &CurrencyChanged) {
+        self.aggregator().record_currency(changed);
+    }
+
+    pub fn market_trade(&self, trade: &MarketTrade) {
+        self.aggregator().record_trade(trade);
+    }
+
+    pub fn loot_dropped(&self, loot: &LootDropped) {
+        self.aggregator().record_loot(loot);
+    }
+
+    /// Write the aggregated buckets, returning how many could not be written; those are
+    /// kept for the next flush
+    pub async fn flush(&self) -> usize {
+        let buckets = self.aggregator().drain();
+        let mut failed = Vec::new();
+        for bucket in buckets {
+            if let Err(e) = self.store.add(&bucket).await {
+                tracing::warn!("Failed to store economy bucket {}: {}", bucket.id, e);
+                failed.push(bucket);
+            }
+        }
+        let mut aggregator = self.aggregator();
+        aggregator.restore(failed);
+        aggregator.pending()
+    }
+
+    /// Flush every `interval`
+    pub fn spawn_flusher(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let pipeline = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                pipeline.flush().await;
+            }
+        })
+    }
+
+    fn aggregator(&self) -> MutexGuard<'_, EconomyAggregator> {
+        self.aggregator.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
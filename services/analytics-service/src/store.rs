@@ -0,0 +1,91 @@
+//! Economy buckets in MongoDB.
+//!
+//! A bucket is one document keyed by metric, key, detail and start. Flushes increment its
+//! totals and widen its price range in place, so buckets written by several instances, or
+//! by one instance over several flushes, add up.
+
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::{FindOptions, IndexOptions, UpdateOptions};
+use mongodb::{Collection, Database, IndexModel};
+use shared::Timestamp;
+
+use crate::economy::{EconomyBucket, Metric};
+
+pub const COLLECTION: &str = "economy_buckets";
+
+/// Most buckets a query reads
+const MAX_BUCKETS: i64 = 50_000;
+
+/// Buckets of one metric starting in `[from, to)`
+#[derive(Debug, Clone)]
+pub struct BucketQuery {
+    pub metric: Metric,
+    /// Currency, item or loot source; all of them when absent
+    pub key: Option<String>,
+    pub from: Timestamp,
+    pub to: Timestamp,
+}
+
+#[derive(Clone)]
+pub struct EconomyStore {
+    collection: Collection<EconomyBucket>,
+}
+
+impl EconomyStore {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let indexes = vec![IndexModel::builder()
+            .keys(doc! { "metric": 1, "key": 1, "start": 1 })
+            .options(IndexOptions::builder().name("metric_key_start".to_string()).build())
+            .build()];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// Add a bucket's totals to the stored ones
+    pub async fn add(&self, bucket: &EconomyBucket) -> mongodb::error::Result<()> {
+        let totals = &bucket.totals;
+        let mut update = doc! {
+            "$setOnInsert": {
+                "metric": bucket.metric.as_str(),
+                "key": &bucket.key,
+                "detail": &bucket.detail,
+                "start": DateTime::from_chrono(bucket.start),
+            },
+            "$inc": {
+                "totals.count": totals.count,
+                "totals.credited": totals.credited,
+                "totals.debited": totals.debited,
+                "totals.quantity": totals.quantity,
+                "totals.volume": totals.volume,
+                "totals.fees": totals.fees,
+            },
+        };
+        if let Some(min) = totals.min_unit_price {
+            update.insert("$min", doc! { "totals.min_unit_price": min });
+        }
+        if let Some(max) = totals.max_unit_price {
+            update.insert("$max", doc! { "totals.max_unit_price": max });
+        }
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.collection.update_one(doc! { "_id": &bucket.id }, update, options).await?;
+        Ok(())
+    }
+
+    /// Matching buckets, oldest first
+    pub async fn find(&self, query: &BucketQuery) -> mongodb::error::Result<Vec<EconomyBucket>> {
+        let mut filter = Document::new();
+        filter.insert("metric", query.metric.as_str());
+        if let Some(key) = &query.key {
+            filter.insert("key", key.as_str());
+        }
+        let (from, to) = (DateTime::from_chrono(query.from), DateTime::from_chrono(query.to));
+        filter.insert("start", doc! { "$gte": from, "$lt": to });
+        let options = FindOptions::builder().sort(doc! { "start": 1 }).limit(MAX_BUCKETS).build();
+        self.collection.find(filter, options).await?.try_collect().await
+    }
+}
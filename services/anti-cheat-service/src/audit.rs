@@ -0,0 +1,151 @@
+//! Append-only log of sanction workflow decisions.
+//!
+//! Every entry names who acted and links the sanction, review case and incidents
+//! involved, so any sanction can be traced back to its evidence.
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOptions, IndexOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+use uuid::Uuid;
+
+use crate::reviews::ReviewCase;
+use crate::sanctions::Sanction;
+
+pub const COLLECTION: &str = "sanction_audit";
+
+/// Entries listed at once by default, and at most
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// e.g. `sanction_issued`, `review_rejected`, `appeal_granted`
+    pub event: String,
+    /// GM or player user id, `auto` for automatic sanctions or `system`
+    pub by: String,
+    pub actor_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanction_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_id: Option<String>,
+    pub incident_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+    pub at: Timestamp,
+}
+
+impl AuditEntry {
+    /// An entry about a sanction, linking its review and evidence
+    pub fn sanction(event: &str, by: &str, sanction: &Sanction) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            event: event.to_string(),
+            by: by.to_string(),
+            actor_id: sanction.actor_id.clone(),
+            user_id: sanction.user_id.clone(),
+            sanction_id: Some(sanction.id.clone()),
+            review_id: sanction.review_id.clone(),
+            incident_ids: sanction.incident_ids.clone(),
+            details: None,
+            at: Utc::now(),
+        }
+    }
+
+    /// An entry about a review case and its evidence
+    pub fn review(event: &str, by: &str, case: &ReviewCase) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            event: event.to_string(),
+            by: by.to_string(),
+            actor_id: case.actor_id.clone(),
+            user_id: None,
+            sanction_id: case.sanction_id.clone(),
+            review_id: Some(case.id.clone()),
+            incident_ids: case.incident_ids.clone(),
+            details: None,
+            at: Utc::now(),
+        }
+    }
+
+    pub fn with_details(mut self, details: impl Into<String>) -> Self {
+        self.details = Some(details.into());
+        self
+    }
+}
+
+/// Filters for listing entries, newest first
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<String>,
+    pub user_id: Option<String>,
+    pub sanction_id: Option<String>,
+    pub review_id: Option<String>,
+    pub incident_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct AuditLog {
+    collection: Collection<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "actor_id": 1, "at": -1 })
+                .options(IndexOptions::builder().name("actor_at".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "sanction_id": 1 })
+                .options(IndexOptions::builder().name("sanction".to_string()).build())
+                .build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// Append an entry; failures are logged, decisions are never undone for lack of a trail
+    pub async fn record(&self, entry: AuditEntry) {
+        if let Err(e) = self.collection.insert_one(&entry, None).await {
+            tracing::error!("Failed to record audit entry {} for {}: {}", entry.event, entry.actor_id, e);
+        }
+    }
+
+    pub async fn list(&self, query: &AuditQuery) -> mongodb::error::Result<Vec<AuditEntry>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "at": -1 })
+            .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+            .build();
+        self.collection.find(filter(query), options).await?.try_collect().await
+    }
+}
+
+fn filter(query: &AuditQuery) -> Document {
+    let mut filter = Document::new();
+    let fields = [
+        ("actor_id", &query.actor_id),
+        ("user_id", &query.user_id),
+        ("sanction_id", &query.sanction_id),
+        ("review_id", &query.review_id),
+        ("incident_ids", &query.incident_id),
+    ];
+    for (field, value) in fields {
+        if let Some(value) = value {
+            filter.insert(field, value);
+        }
+    }
+    filter
+}
@@ -0,0 +1,295 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::rules::{RuleConfig, RuleKind, Severity};
+use crate::sanctions::{LadderStep, SanctionAction};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub detection: DetectionConfig,
+    #[serde(default)]
+    pub sanctions: SanctionConfig,
+    #[serde(default)]
+    pub user_management: UserManagementConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// Rules telemetry is checked against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionConfig {
+    /// How long an actor is not reported again for the same rule
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    pub rules: Vec<RuleConfig>,
+}
+
+fn default_cooldown_secs() -> u64 {
+    60
+}
+
+impl Default for DetectionConfig {
+    fn default() -> Self {
+        Self {
+            cooldown_secs: default_cooldown_secs(),
+            rules: vec![
+                RuleConfig {
+                    id: "stat_spike".to_string(),
+                    severity: Severity::High,
+                    kind: RuleKind::StatDelta { stats: Vec::new(), max_delta: 500.0 },
+                },
+                RuleConfig {
+                    id: "experience_rate".to_string(),
+                    severity: Severity::Medium,
+                    kind: RuleKind::ExperienceRate { max_per_second: 50.0, window_secs: 60 },
+                },
+                RuleConfig {
+                    id: "damage_rate".to_string(),
+                    severity: Severity::High,
+                    kind: RuleKind::DamageRate { max_per_second: 2000.0, window_secs: 10 },
+                },
+                RuleConfig {
+                    id: "speed_hack".to_string(),
+                    severity: Severity::Critical,
+                    kind: RuleKind::MovementSpeed { tolerance: 1.5 },
+                },
+                RuleConfig {
+                    id: "snapshot_tampering".to_string(),
+                    severity: Severity::Critical,
+                    kind: RuleKind::SnapshotMismatch { sources: vec!["client".to_string()] },
+                },
+                // Corrupted caches are not the player's doing; recorded for investigation only
+                RuleConfig {
+                    id: "snapshot_corruption".to_string(),
+                    severity: Severity::Low,
+                    kind: RuleKind::SnapshotMismatch { sources: vec!["cache".to_string()] },
+                },
+                RuleConfig {
+                    id: "chat_spam".to_string(),
+                    severity: Severity::Low,
+                    kind: RuleKind::ChatAbuse { kinds: Vec::new(), max_reports: 10, window_secs: 300 },
+                },
+                RuleConfig {
+                    id: "trade_price".to_string(),
+                    severity: Severity::Medium,
+                    kind: RuleKind::TradePrice { max_ratio: 5.0 },
+                },
+                RuleConfig {
+                    id: "rejected_movement".to_string(),
+                    severity: Severity::Medium,
+                    kind: RuleKind::RejectedMovement { kinds: Vec::new() },
+                },
+            ],
+        }
+    }
+}
+
+/// What happens to actors once incidents are raised against them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SanctionConfig {
+    /// Incidents scoring at least this are sanctioned without review
+    pub auto_min_score: f64,
+    /// Incidents scoring at least this, but below `auto_min_score`, are queued for review
+    pub review_min_score: f64,
+    /// Incidents within this long after a sanction join it as evidence instead of
+    /// escalating again
+    pub cooldown_secs: u64,
+    /// How far back sanctions count towards the ladder
+    pub offence_window_days: u64,
+    pub ladder: Vec<LadderStep>,
+    /// Gateway roles allowed to review incidents and decide appeals
+    pub moderator_roles: Vec<String>,
+    /// How often expiring sanctions and failed enforcements are checked
+    pub sweep_interval_secs: u64,
+}
+
+impl Default for SanctionConfig {
+    fn default() -> Self {
+        Self {
+            auto_min_score: 80.0,
+            review_min_score: 40.0,
+            cooldown_secs: 3600,
+            offence_window_days: 90,
+            ladder: vec![
+                LadderStep { action: SanctionAction::Warn, duration_secs: None },
+                LadderStep { action: SanctionAction::Mute, duration_secs: Some(86_400) },
+                LadderStep { action: SanctionAction::TempBan, duration_secs: Some(604_800) },
+                LadderStep { action: SanctionAction::PermaBan, duration_secs: None },
+            ],
+            moderator_roles: vec!["gm".to_string(), "admin".to_string()],
+            sweep_interval_secs: 60,
+        }
+    }
+}
+
+/// Internal API of user-management, which enforces sanctions on accounts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserManagementConfig {
+    pub url: String,
+    /// Sent as `X-Internal-Api-Key`; `USER_MANAGEMENT_API_KEY` takes precedence
+    pub api_key: Option<String>,
+    /// World whose characters incidents are about
+    pub world_id: String,
+}
+
+impl Default for UserManagementConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:8082".to_string(),
+            api_key: None,
+            world_id: "default".to_string(),
+        }
+    }
+}
+
+impl UserManagementConfig {
+    fn apply_env(&mut self) {
+        if let Ok(url) = env::var("USER_MANAGEMENT_URL") {
+            self.url = url;
+        }
+        if let Ok(api_key) = env::var("USER_MANAGEMENT_API_KEY") {
+            self.api_key = Some(api_key);
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/anti-cheat-service.yaml".to_string());
+
+        if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            let mut config: Config = serde_yaml::from_str(&content)?;
+            config.user_management.apply_env();
+            config.detection.validate()?;
+            config.sanctions.validate()?;
+            return Ok(config);
+        }
+
+        tracing::warn!("Config file not found at {}, using environment variables", config_path);
+        let server = ServerConfig {
+            port: env::var("ANTI_CHEAT_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("ANTI_CHEAT_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_anti_cheat".to_string()),
+        };
+
+        let mut user_management = UserManagementConfig::default();
+        user_management.apply_env();
+
+        Ok(Config {
+            server,
+            database,
+            detection: DetectionConfig::default(),
+            sanctions: SanctionConfig::default(),
+            user_management,
+        })
+    }
+}
+
+impl DetectionConfig {
+    /// Reject rules that could never be evaluated
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut ids = std::collections::HashSet::new();
+        for rule in &self.rules {
+            if !ids.insert(rule.id.as_str()) {
+                return Err(ConfigError::InvalidConfig(format!("Duplicate rule id: {}", rule.id)));
+            }
+            let window_secs = match &rule.kind {
+                RuleKind::ExperienceRate { window_secs, .. }
+                | RuleKind::DamageRate { window_secs, .. }
+                | RuleKind::ChatAbuse { window_secs, .. } => *window_secs,
+                _ => 1,
+            };
+            if window_secs == 0 {
+                return Err(ConfigError::InvalidConfig(format!("Rule {} needs a window of at least 1s", rule.id)));
+            }
+            // Every sale is at least 1x off its reference price
+            if matches!(rule.kind, RuleKind::TradePrice { max_ratio } if max_ratio < 1.0) {
+                return Err(ConfigError::InvalidConfig(format!("Rule {} needs a max_ratio of at least 1", rule.id)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SanctionConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.ladder.is_empty() {
+            return Err(ConfigError::InvalidConfig("The sanction ladder needs at least one step".to_string()));
+        }
+        if self.review_min_score > self.auto_min_score {
+            return Err(ConfigError::InvalidConfig(
+                "review_min_score must not be above auto_min_score".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_are_read_from_yaml() {
+        let yaml = r#"
+cooldown_secs: 30
+rules:
+  - id: strength_spike
+    kind: stat_delta
+    severity: critical
+    stats: [strength]
+    max_delta: 10
+  - id: speed_hack
+    kind: movement_speed
+    severity: high
+"#;
+        let detection: DetectionConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(detection.cooldown_secs, 30);
+        assert_eq!(detection.rules.len(), 2);
+        assert_eq!(detection.rules[0].severity, Severity::Critical);
+        assert!(matches!(&detection.rules[0].kind, RuleKind::StatDelta { stats, .. } if stats == &["strength"]));
+        assert!(matches!(detection.rules[1].kind, RuleKind::MovementSpeed { tolerance } if tolerance > 1.0));
+        detection.validate().unwrap();
+
+        let mut duplicated = detection.clone();
+        duplicated.rules.push(detection.rules[0].clone());
+        assert!(duplicated.validate().is_err());
+    }
+}
@@ -0,0 +1,100 @@
+//! Runs bus telemetry through the [`Detector`], stores the incidents it raises and
+//! hands them to the sanction workflow.
+//!
+//! Incidents MongoDB rejects are kept and retried before the next ones, so a database
+//! hiccup does not lose detections: the detector has already moved on and would not
+//! raise them again.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use shared::events::EventEnvelope;
+use tracing::{info, warn};
+
+use crate::incidents::{Incident, IncidentStore};
+use crate::rules::{Detector, Observation, RuleConfig};
+use crate::workflow::SanctionWorkflow;
+
+/// Incidents kept for retry at most; the oldest are dropped beyond this
+const MAX_PENDING: usize = 10_000;
+
+pub struct DetectionEngine {
+    detector: Mutex<Detector>,
+    store: IncidentStore,
+    workflow: Arc<SanctionWorkflow>,
+    /// Incidents not stored yet, oldest first
+    pending: tokio::sync::Mutex<VecDeque<Incident>>,
+}
+
+impl DetectionEngine {
+    pub fn new(detector: Detector, store: IncidentStore, workflow: Arc<SanctionWorkflow>) -> Self {
+        Self { detector: Mutex::new(detector), store, workflow, pending: Default::default() }
+    }
+
+    pub fn rules(&self) -> Vec<RuleConfig> {
+        self.detector.lock().unwrap().rules().to_vec()
+    }
+
+    /// Check telemetry from the bus and store the incidents it raises
+    pub async fn observe(&self, observation: Observation, envelope: &EventEnvelope) {
+        let detections = self.detector.lock().unwrap().observe(&observation, envelope.timestamp);
+        if detections.is_empty() {
+            return;
+        }
+
+        let mut pending = self.pending.lock().await;
+        for detection in detections {
+            info!(
+                actor_id = %detection.actor_id,
+                rule_id = %detection.rule_id,
+                score = detection.score,
+                "🚨 {}", detection.summary
+            );
+            pending.push_back(Incident::new(detection, envelope.id, envelope.timestamp));
+        }
+        while pending.len() > MAX_PENDING {
+            if let Some(dropped) = pending.pop_front() {
+                warn!(actor_id = %dropped.actor_id, rule_id = %dropped.rule_id, "Dropped an incident that could not be stored");
+            }
+        }
+        let stored = store_pending(&self.store, &mut pending).await;
+        drop(pending);
+        self.act_on(stored).await;
+    }
+
+    /// Forget an actor that left the world
+    pub fn forget(&self, actor_id: &str) {
+        self.detector.lock().unwrap().forget(actor_id);
+    }
+
+    /// Store and act on incidents left over from failed writes, returning how many are still pending
+    pub async fn flush(&self) -> usize {
+        let mut pending = self.pending.lock().await;
+        let stored = store_pending(&self.store, &mut pending).await;
+        let remaining = pending.len();
+        drop(pending);
+        self.act_on(stored).await;
+        remaining
+    }
+
+    async fn act_on(&self, incidents: Vec<Incident>) {
+        for incident in incidents {
+            if let Err(e) = self.workflow.incident(&incident).await {
+                warn!(incident_id = %incident.id, "⚠️  Failed to act on incident: {}", e);
+            }
+        }
+    }
+}
+
+/// Store pending incidents in order, stopping at the first failure, and return the stored ones
+async fn store_pending(store: &IncidentStore, pending: &mut VecDeque<Incident>) -> Vec<Incident> {
+    let mut stored = Vec::new();
+    while let Some(incident) = pending.front() {
+        if let Err(e) = store.insert(incident).await {
+            warn!(pending = pending.len(), "⚠️  Failed to store incident, will retry: {}", e);
+            break;
+        }
+        stored.extend(pending.pop_front());
+    }
+    stored
+}
@@ -0,0 +1,256 @@
+//! HTTP API for GMs and players, reached through the gateway.
+//!
+//! Callers are identified by the trusted headers the gateway sets after validating
+//! their token. Incidents, review cases, sanctions and the audit log are restricted to
+//! moderators; players can list their own sanctions and appeal them.
+
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, Path, Query, State};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::audit::{AuditEntry, AuditLog, AuditQuery};
+use crate::engine::DetectionEngine;
+use crate::incidents::{Incident, IncidentQuery, IncidentStore};
+use crate::reviews::{ReviewCase, ReviewQuery, ReviewStatus, ReviewStore};
+use crate::rules::RuleConfig;
+use crate::sanctions::{Sanction, SanctionQuery, SanctionStore};
+use crate::workflow::{SanctionWorkflow, WorkflowError, WorkflowResult};
+
+/// Trusted gateway header carrying the authenticated user ID
+const USER_ID_HEADER: &str = "x-user-id";
+
+/// Trusted gateway header carrying the authenticated user's roles, comma-separated
+const USER_ROLES_HEADER: &str = "x-user-roles";
+
+/// Longest appeal statement accepted
+const MAX_STATEMENT_LEN: usize = 2000;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub engine: Arc<DetectionEngine>,
+    pub workflow: Arc<SanctionWorkflow>,
+    pub incidents: IncidentStore,
+    pub reviews: ReviewStore,
+    pub sanctions: SanctionStore,
+    pub audit: AuditLog,
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/incidents", get(list_incidents))
+        .route("/incidents/:id", get(get_incident))
+        .route("/rules", get(list_rules))
+        .route("/reviews", get(list_reviews))
+        .route("/reviews/:id", get(get_review))
+        .route("/reviews/:id/approve", post(approve_review))
+        .route("/reviews/:id/reject", post(reject_review))
+        .route("/sanctions", get(list_sanctions))
+        .route("/sanctions/mine", get(my_sanctions))
+        .route("/sanctions/:id", get(get_sanction))
+        .route("/sanctions/:id/appeal", post(appeal_sanction))
+        .route("/sanctions/:id/appeal/decision", post(decide_appeal))
+        .route("/audit", get(list_audit))
+        .with_state(state)
+}
+
+/// User calling through the gateway
+pub struct Caller {
+    pub user_id: String,
+    pub roles: Vec<String>,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| parts.headers.get(name).and_then(|value| value.to_str().ok());
+        let Some(user_id) = header(USER_ID_HEADER).filter(|user_id| !user_id.is_empty()) else {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "success": false, "error": "User not authenticated" })),
+            ));
+        };
+        let roles = header(USER_ROLES_HEADER)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|role| !role.is_empty())
+            .map(str::to_string)
+            .collect();
+        Ok(Self { user_id: user_id.to_string(), roles })
+    }
+}
+
+impl AppState {
+    fn require_moderator(&self, caller: &Caller) -> WorkflowResult<()> {
+        if self.workflow.is_moderator(&caller.roles) {
+            Ok(())
+        } else {
+            Err(WorkflowError::Forbidden("Moderator role required".to_string()))
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DecisionRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealRequest {
+    pub statement: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AppealDecisionRequest {
+    /// Overturn and lift the sanction when set, uphold it otherwise
+    pub grant: bool,
+    pub resolution: Option<String>,
+}
+
+/// Incidents, newest first, filtered by actor, rule and minimum score
+async fn list_incidents(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(query): Query<IncidentQuery>,
+) -> WorkflowResult<Json<Vec<Incident>>> {
+    state.require_moderator(&caller)?;
+    Ok(Json(state.incidents.list(&query).await?))
+}
+
+async fn get_incident(State(state): State<AppState>, caller: Caller, Path(id): Path<String>) -> WorkflowResult<Json<Incident>> {
+    state.require_moderator(&caller)?;
+    match state.incidents.get(&id).await? {
+        Some(incident) => Ok(Json(incident)),
+        None => Err(WorkflowError::NotFound(format!("Incident not found: {}", id))),
+    }
+}
+
+/// Configured detection rules
+async fn list_rules(State(state): State<AppState>, caller: Caller) -> WorkflowResult<Json<Vec<RuleConfig>>> {
+    state.require_moderator(&caller)?;
+    Ok(Json(state.engine.rules()))
+}
+
+/// Review queue, highest score first; pending cases unless another status is asked for
+async fn list_reviews(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(mut query): Query<ReviewQuery>,
+) -> WorkflowResult<Json<Vec<ReviewCase>>> {
+    state.require_moderator(&caller)?;
+    query.status.get_or_insert(ReviewStatus::Pending);
+    Ok(Json(state.reviews.list(&query).await?))
+}
+
+/// A review case with its incidents
+async fn get_review(State(state): State<AppState>, caller: Caller, Path(id): Path<String>) -> WorkflowResult<Json<Value>> {
+    state.require_moderator(&caller)?;
+    let case = state
+        .reviews
+        .get(&id)
+        .await?
+        .ok_or_else(|| WorkflowError::NotFound(format!("Review not found: {}", id)))?;
+    let incidents = state.incidents.find_many(&case.incident_ids).await?;
+    Ok(Json(json!({ "review": case, "incidents": incidents })))
+}
+
+async fn approve_review(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    payload: Option<Json<DecisionRequest>>,
+) -> WorkflowResult<Json<Value>> {
+    state.require_moderator(&caller)?;
+    let note = payload.and_then(|Json(payload)| payload.note);
+    let (case, sanction) = state.workflow.approve(&id, &caller.user_id, note).await?;
+    Ok(Json(json!({ "review": case, "sanction": sanction })))
+}
+
+async fn reject_review(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    payload: Option<Json<DecisionRequest>>,
+) -> WorkflowResult<Json<ReviewCase>> {
+    state.require_moderator(&caller)?;
+    let note = payload.and_then(|Json(payload)| payload.note);
+    Ok(Json(state.workflow.reject(&id, &caller.user_id, note).await?))
+}
+
+/// Sanctions, newest first, filtered by actor, account and status
+async fn list_sanctions(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(query): Query<SanctionQuery>,
+) -> WorkflowResult<Json<Vec<Sanction>>> {
+    state.require_moderator(&caller)?;
+    Ok(Json(state.sanctions.list(&query).await?))
+}
+
+/// Sanctions of the calling account
+async fn my_sanctions(State(state): State<AppState>, caller: Caller) -> WorkflowResult<Json<Vec<Sanction>>> {
+    let query = SanctionQuery { user_id: Some(caller.user_id), ..Default::default() };
+    Ok(Json(state.sanctions.list(&query).await?))
+}
+
+/// A sanction with its evidence, for moderators and the sanctioned account
+async fn get_sanction(State(state): State<AppState>, caller: Caller, Path(id): Path<String>) -> WorkflowResult<Json<Value>> {
+    let sanction = state
+        .sanctions
+        .get(&id)
+        .await?
+        .ok_or_else(|| WorkflowError::NotFound(format!("Sanction not found: {}", id)))?;
+    let moderator = state.workflow.is_moderator(&caller.roles);
+    if !moderator && sanction.user_id.as_deref() != Some(caller.user_id.as_str()) {
+        return Err(WorkflowError::NotFound(format!("Sanction not found: {}", id)));
+    }
+    // Players see what they were sanctioned for, not how detection works
+    if !moderator {
+        return Ok(Json(json!({ "sanction": sanction })));
+    }
+    let incidents = state.incidents.find_many(&sanction.incident_ids).await?;
+    Ok(Json(json!({ "sanction": sanction, "incidents": incidents })))
+}
+
+async fn appeal_sanction(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Json(payload): Json<AppealRequest>,
+) -> WorkflowResult<Json<Sanction>> {
+    let statement = payload.statement.trim();
+    if statement.is_empty() || statement.chars().count() > MAX_STATEMENT_LEN {
+        let error = format!("The statement must be between 1 and {} characters", MAX_STATEMENT_LEN);
+        return Err(WorkflowError::BadRequest(error));
+    }
+    Ok(Json(state.workflow.appeal(&id, &caller.user_id, statement.to_string()).await?))
+}
+
+async fn decide_appeal(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Json(payload): Json<AppealDecisionRequest>,
+) -> WorkflowResult<Json<Sanction>> {
+    state.require_moderator(&caller)?;
+    let sanction = state.workflow.decide_appeal(&id, &caller.user_id, payload.grant, payload.resolution).await?;
+    Ok(Json(sanction))
+}
+
+/// Workflow decisions, newest first, filtered by actor, account, sanction, review or incident
+async fn list_audit(
+    State(state): State<AppState>,
+    caller: Caller,
+    Query(query): Query<AuditQuery>,
+) -> WorkflowResult<Json<Vec<AuditEntry>>> {
+    state.require_moderator(&caller)?;
+    Ok(Json(state.audit.list(&query).await?))
+}
@@ -0,0 +1,127 @@
+//! Incidents raised by the detection rules, stored in MongoDB.
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOptions, IndexOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+use uuid::Uuid;
+
+use crate::rules::{Detection, Severity};
+
+pub const COLLECTION: &str = "incidents";
+
+/// Incidents listed at once by default, and at most
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+/// A rule violation by an actor, with the evidence that triggered it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub actor_id: String,
+    pub rule_id: String,
+    pub severity: Severity,
+    /// 0 to 100, higher is more certain
+    pub score: f64,
+    pub summary: String,
+    pub evidence: serde_json::Value,
+    /// Bus message carrying the telemetry
+    pub event_id: String,
+    /// When the telemetry was published
+    pub observed_at: Timestamp,
+    pub detected_at: Timestamp,
+}
+
+impl Incident {
+    pub fn new(detection: Detection, event_id: Uuid, observed_at: Timestamp) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            actor_id: detection.actor_id,
+            rule_id: detection.rule_id,
+            severity: detection.severity,
+            score: detection.score,
+            summary: detection.summary,
+            evidence: detection.evidence,
+            event_id: event_id.to_string(),
+            observed_at,
+            detected_at: Utc::now(),
+        }
+    }
+}
+
+/// Filters for listing incidents, newest first
+#[derive(Debug, Default, Deserialize)]
+pub struct IncidentQuery {
+    pub actor_id: Option<String>,
+    pub rule_id: Option<String>,
+    pub min_score: Option<f64>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct IncidentStore {
+    collection: Collection<Incident>,
+}
+
+impl IncidentStore {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    /// Index incidents by actor and by score for the review queries
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "actor_id": 1, "detected_at": -1 })
+                .options(IndexOptions::builder().name("actor_detected".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "score": -1, "detected_at": -1 })
+                .options(IndexOptions::builder().name("score_detected".to_string()).build())
+                .build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    pub async fn insert(&self, incident: &Incident) -> mongodb::error::Result<()> {
+        self.collection.insert_one(incident, None).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> mongodb::error::Result<Option<Incident>> {
+        self.collection.find_one(doc! { "_id": id }, None).await
+    }
+
+    /// Incidents with the given ids, e.g. the evidence of a sanction
+    pub async fn find_many(&self, ids: &[String]) -> mongodb::error::Result<Vec<Incident>> {
+        let options = FindOptions::builder().sort(doc! { "detected_at": 1 }).build();
+        self.collection.find(doc! { "_id": { "$in": ids } }, options).await?.try_collect().await
+    }
+
+    pub async fn list(&self, query: &IncidentQuery) -> mongodb::error::Result<Vec<Incident>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "detected_at": -1 })
+            .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+            .build();
+        self.collection.find(filter(query), options).await?.try_collect().await
+    }
+}
+
+fn filter(query: &IncidentQuery) -> Document {
+    let mut filter = Document::new();
+    if let Some(actor_id) = &query.actor_id {
+        filter.insert("actor_id", actor_id);
+    }
+    if let Some(rule_id) = &query.rule_id {
+        filter.insert("rule_id", rule_id);
+    }
+    if let Some(min_score) = query.min_score {
+        filter.insert("score", doc! { "$gte": min_score });
+    }
+    filter
+}
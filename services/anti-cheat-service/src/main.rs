@@ -1,33 +1,199 @@
+mod audit;
+mod config;
+mod engine;
+mod handlers;
+mod incidents;
+mod reviews;
+mod rules;
+mod sanctions;
+mod user_management;
+mod workflow;
+
+use audit::AuditLog;
 use axum::{
+    extract::State,
+    http::StatusCode,
     routing::get,
-    Router,
+    Json, Router,
+};
+use config::Config;
+use engine::DetectionEngine;
+use handlers::AppState;
+use incidents::IncidentStore;
+use reviews::ReviewStore;
+use rules::{Detector, Observation};
+use sanctions::SanctionStore;
+use shared::events::topics::{
+    ActorDespawned, ACTOR_DESPAWNED, ACTOR_EXPERIENCE_GAINED, ACTOR_MOVED, ACTOR_SNAPSHOT_MISMATCH, ACTOR_STAT_CHANGED,
+    CHAT_ABUSE, DAMAGE_DEALT, MARKET_TRADE, MOVEMENT_REJECTED,
 };
+use shared::events::{connect, BusConfig, Consumer, ConsumerOptions, EventBus, Message, Topic};
+use shared::health::{check_fn, BusCheck, HealthRegistry, HealthReport, LIVE_PATH, READY_PATH};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
+use shared::ChaosError;
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use std::time::Duration;
+use user_management::UserManagementClient;
+use workflow::SanctionWorkflow;
+
+const SERVICE: &str = "anti-cheat-service";
+
+/// Consumer name, a single topic token
+const CONSUMER: &str = "anti_cheat_service";
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+    let incidents = IncidentStore::new(&database);
+    let reviews = ReviewStore::new(&database);
+    let sanctions = SanctionStore::new(&database);
+    let audit = AuditLog::new(&database);
+    let indexes = [
+        incidents.ensure_indexes().await,
+        reviews.ensure_indexes().await,
+        sanctions.ensure_indexes().await,
+        audit.ensure_indexes().await,
+    ];
+    for result in indexes {
+        if let Err(e) = result {
+            tracing::warn!("Failed to create indexes: {}", e);
+        }
+    }
+
+    // Incidents are sanctioned up the ladder, through GM review below the auto score
+    let workflow = Arc::new(SanctionWorkflow::new(
+        config.sanctions.clone(),
+        sanctions.clone(),
+        reviews.clone(),
+        audit.clone(),
+        UserManagementClient::new(config.user_management.clone()),
+    ));
+    let sweeper = workflow.spawn_sweeper();
+
+    let detector = Detector::new(config.detection.rules.clone(), Duration::from_secs(config.detection.cooldown_secs));
+    tracing::info!("🛡️  Loaded {} detection rule(s)", detector.rules().len());
+    let engine = Arc::new(DetectionEngine::new(detector, incidents.clone(), workflow.clone()));
+
+    // Check the telemetry game servers publish
+    let bus = connect(&BusConfig::from_env()).await.unwrap();
+    observe(&bus, &engine, ACTOR_STAT_CHANGED, Observation::StatChanged).await;
+    observe(&bus, &engine, ACTOR_EXPERIENCE_GAINED, Observation::ExperienceGained).await;
+    observe(&bus, &engine, ACTOR_MOVED, Observation::Moved).await;
+    observe(&bus, &engine, DAMAGE_DEALT, Observation::DamageDealt).await;
+    observe(&bus, &engine, ACTOR_SNAPSHOT_MISMATCH, Observation::SnapshotMismatch).await;
+    observe(&bus, &engine, CHAT_ABUSE, Observation::ChatAbuse).await;
+    observe(&bus, &engine, MARKET_TRADE, Observation::MarketTrade).await;
+    observe(&bus, &engine, MOVEMENT_REJECTED, Observation::MovementRejected).await;
+    let despawned = engine.clone();
+    Consumer::new(bus.clone(), ACTOR_DESPAWNED, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: ActorDespawned, _| {
+            despawned.forget(&event.actor_id);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+    // Ready while telemetry arrives and incidents can be stored
+    let ping = database.clone();
+    let health = Arc::new(
+        HealthRegistry::new(SERVICE)
+            .with_shutdown(&shutdown)
+            .register("event_bus", BusCheck::new(bus.clone()))
+            .register(
+                "mongodb",
+                check_fn(move || {
+                    let database = ping.clone();
+                    async move {
+                        database
+                            .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| ChaosError::Database(e.to_string()))
+                    }
+                }),
+            ),
+    );
+
     // Create router
+    let state = AppState { engine: engine.clone(), workflow, incidents, reviews, sanctions, audit };
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/", get(root))
+        .merge(handlers::routes(state))
+        .merge(
+            Router::new()
+                .route(LIVE_PATH, get(liveness))
+                .route(READY_PATH, get(readiness))
+                .with_state(health),
+        );
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 anti-cheat-service server starting on {}", addr);
-    
+
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    sweeper.abort();
+    let pending = engine.flush().await;
+    if pending > 0 {
+        tracing::warn!("⚠️  {} incident(s) could not be stored", pending);
+    }
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 anti-cheat-service stopped");
+    telemetry.shutdown();
+}
+
+/// Feed a telemetry topic to the detection engine
+async fn observe<M: Message + Clone>(
+    bus: &Arc<dyn EventBus>,
+    engine: &Arc<DetectionEngine>,
+    topic: Topic<M>,
+    observation: fn(M) -> Observation,
+) {
+    let engine = engine.clone();
+    Consumer::new(bus.clone(), topic, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: M, envelope| {
+            let engine = engine.clone();
+            async move {
+                engine.observe(observation(event), &envelope).await;
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
 
+async fn liveness(State(health): State<Arc<HealthRegistry>>) -> Json<HealthReport> {
+    Json(health.liveness())
+}
+
+/// 503 while the event bus or MongoDB is unavailable
+async fn readiness(State(health): State<Arc<HealthRegistry>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = StatusCode::from_u16(report.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report))
+}
+
 async fn root() -> &'static str {
     "Hello from anti-cheat-service!"
 }
-
@@ -0,0 +1,164 @@
+//! Queue of incidents for GMs to review.
+//!
+//! Incidents scoring too low to sanction automatically but too high to ignore open a
+//! review case for the actor; later incidents join the open case. Approving a case
+//! issues the actor's next sanction, rejecting it closes the case without one.
+
+use chrono::Utc;
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReplaceOptions, ReturnDocument};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+use uuid::Uuid;
+
+use crate::incidents::Incident;
+
+pub const COLLECTION: &str = "reviews";
+
+/// Cases listed at once by default, and at most
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewCase {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub actor_id: String,
+    pub incident_ids: Vec<String>,
+    /// Highest score among the incidents, the queue is worked from the top
+    pub max_score: f64,
+    pub status: ReviewStatus,
+    pub opened_at: Timestamp,
+    pub updated_at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Sanction issued on approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanction_id: Option<String>,
+}
+
+impl ReviewCase {
+    pub fn open(incident: &Incident) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Uuid::new_v4().to_string(),
+            actor_id: incident.actor_id.clone(),
+            incident_ids: vec![incident.id.clone()],
+            max_score: incident.score,
+            status: ReviewStatus::Pending,
+            opened_at: now,
+            updated_at: now,
+            decided_by: None,
+            decided_at: None,
+            note: None,
+            sanction_id: None,
+        }
+    }
+
+    /// Close the case with a GM's decision
+    pub fn decide(&mut self, status: ReviewStatus, decided_by: &str, note: Option<String>) {
+        let now = Utc::now();
+        self.status = status;
+        self.decided_by = Some(decided_by.to_string());
+        self.decided_at = Some(now);
+        self.updated_at = now;
+        self.note = note;
+    }
+}
+
+/// Filters for listing cases, highest score first
+#[derive(Debug, Default, Deserialize)]
+pub struct ReviewQuery {
+    pub status: Option<ReviewStatus>,
+    pub actor_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct ReviewStore {
+    collection: Collection<ReviewCase>,
+}
+
+impl ReviewStore {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "status": 1, "max_score": -1 })
+                .options(IndexOptions::builder().name("status_score".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "actor_id": 1, "status": 1 })
+                .options(IndexOptions::builder().name("actor_status".to_string()).build())
+                .build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    pub async fn insert(&self, case: &ReviewCase) -> mongodb::error::Result<()> {
+        self.collection.insert_one(case, None).await?;
+        Ok(())
+    }
+
+    pub async fn save(&self, case: &ReviewCase) -> mongodb::error::Result<()> {
+        let upsert = ReplaceOptions::builder().upsert(true).build();
+        self.collection.replace_one(doc! { "_id": &case.id }, case, upsert).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> mongodb::error::Result<Option<ReviewCase>> {
+        self.collection.find_one(doc! { "_id": id }, None).await
+    }
+
+    /// Add an incident to the actor's pending case, if there is one
+    pub async fn join_pending(&self, incident: &Incident) -> mongodb::error::Result<Option<ReviewCase>> {
+        let update = doc! {
+            "$push": { "incident_ids": &incident.id },
+            "$max": { "max_score": incident.score },
+            "$set": { "updated_at": Utc::now().to_rfc3339() },
+        };
+        let options = FindOneAndUpdateOptions::builder().return_document(ReturnDocument::After).build();
+        self.collection
+            .find_one_and_update(doc! { "actor_id": &incident.actor_id, "status": "pending" }, update, options)
+            .await
+    }
+
+    pub async fn list(&self, query: &ReviewQuery) -> mongodb::error::Result<Vec<ReviewCase>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "max_score": -1, "opened_at": 1 })
+            .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+            .build();
+        self.collection.find(filter(query), options).await?.try_collect().await
+    }
+}
+
+fn filter(query: &ReviewQuery) -> Document {
+    let mut filter = Document::new();
+    if let Some(status) = query.status {
+        if let Ok(status) = mongodb::bson::to_bson(&status) {
+            filter.insert("status", status);
+        }
+    }
+    if let Some(actor_id) = &query.actor_id {
+        filter.insert("actor_id", actor_id);
+    }
+    filter
+}
@@ -0,0 +1,672 @@
+//! Rule-based cheat detection.
+//!
+//! Rules are configured per deployment and checked by the [`Detector`] against the
+//! gameplay telemetry game servers publish on the bus:
+//! - `stat_delta`: a stat changing by more than `max_delta` at once, or exceeding its cap
+//! - `experience_rate` / `damage_rate`: more than `max_per_second` on average over a
+//!   sliding window of `window_secs`
+//! - `movement_speed`: covering ground faster than the actor's speed stat allows, times
+//!   `tolerance` to absorb latency
+//! - `snapshot_mismatch`: a cached or client-reported snapshot the game server could not
+//!   reproduce from the actor's contributions, from the listed `sources`
+//! - `chat_abuse`: more than `max_reports` refused chat messages of the listed `kinds`
+//!   (`flooding`, `filtered`) within `window_secs`; reported against the sending user
+//! - `trade_price`: a market sale whose unit price is more than `max_ratio` times above
+//!   or below the item's recent median; reported against the side the price favours,
+//!   as lopsided sales are how gold is moved between accounts
+//! - `rejected_movement`: a position world-service refused, of the listed `kinds`
+//!   (`speed`, `teleport`, `collision`, `out_of_bounds`)
+//!
+//! A violation scores `severity weight × observed / limit`, capped at 100, so barely
+//! crossing a limit scores the weight and blatant cheating saturates. An actor raises a
+//! rule at most once per cooldown so a single cheater does not flood the incidents.
+
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use shared::events::topics::{
+    ActorMoved, ActorSnapshotMismatch, ActorStatChanged, ChatAbuseKind, ChatAbuseReported, DamageDealt, ExperienceGained,
+    MarketTrade, MovementRejected, MovementViolationKind,
+};
+use shared::Timestamp;
+use std::collections::{HashMap, VecDeque};
+
+/// Highest incident score
+pub const MAX_SCORE: f64 = 100.0;
+
+/// Moves closer together than this are measured against the earlier position, so
+/// timestamp jitter between two updates does not look like a burst of speed
+const MIN_MOVE_INTERVAL_MS: i64 = 250;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    /// Score of a violation just over its limit
+    pub fn weight(self) -> f64 {
+        match self {
+            Severity::Low => 10.0,
+            Severity::Medium => 25.0,
+            Severity::High => 50.0,
+            Severity::Critical => 80.0,
+        }
+    }
+}
+
+/// A configured detection rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConfig {
+    /// Unique name, recorded on incidents
+    pub id: String,
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub kind: RuleKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleKind {
+    StatDelta {
+        /// Stats the rule applies to, every stat when empty
+        #[serde(default)]
+        stats: Vec<String>,
+        max_delta: f64,
+    },
+    ExperienceRate {
+        max_per_second: f64,
+        window_secs: u64,
+    },
+    DamageRate {
+        max_per_second: f64,
+        window_secs: u64,
+    },
+    MovementSpeed {
+        #[serde(default = "default_tolerance")]
+        tolerance: f64,
+    },
+    SnapshotMismatch {
+        /// Snapshot sources the rule applies to (`cache`, `client`), every source when empty
+        #[serde(default)]
+        sources: Vec<String>,
+    },
+    ChatAbuse {
+        /// Refusals the rule counts, every kind when empty
+        #[serde(default)]
+        kinds: Vec<ChatAbuseKind>,
+        max_reports: u32,
+        window_secs: u64,
+    },
+    TradePrice {
+        max_ratio: f64,
+    },
+    RejectedMovement {
+        /// Refusals the rule applies to, every kind when empty
+        #[serde(default)]
+        kinds: Vec<MovementViolationKind>,
+    },
+}
+
+fn default_tolerance() -> f64 {
+    1.5
+}
+
+/// Telemetry rules are checked against
+#[derive(Debug, Clone)]
+pub enum Observation {
+    StatChanged(ActorStatChanged),
+    ExperienceGained(ExperienceGained),
+    Moved(ActorMoved),
+    DamageDealt(DamageDealt),
+    SnapshotMismatch(ActorSnapshotMismatch),
+    ChatAbuse(ChatAbuseReported),
+    MarketTrade(MarketTrade),
+    MovementRejected(MovementRejected),
+}
+
+impl Observation {
+    /// Actor the observation is about; the attacker for damage, and for a sale the seller
+    /// when it sold above the reference price and the buyer when below
+    pub fn actor_id(&self) -> &str {
+        match self {
+            Observation::StatChanged(event) => &event.actor_id,
+            Observation::ExperienceGained(event) => &event.actor_id,
+            Observation::Moved(event) => &event.actor_id,
+            Observation::DamageDealt(event) => &event.attacker_id,
+            Observation::SnapshotMismatch(event) => &event.actor_id,
+            Observation::ChatAbuse(event) => &event.sender_id,
+            Observation::MarketTrade(event) => match event.reference_unit_price {
+                Some(reference) if event.unit_price() < reference => &event.buyer_id,
+                _ => &event.seller_id,
+            },
+            Observation::MovementRejected(event) => &event.actor_id,
+        }
+    }
+
+    fn telemetry(&self) -> serde_json::Value {
+        let value = match self {
+            Observation::StatChanged(event) => serde_json::to_value(event),
+            Observation::ExperienceGained(event) => serde_json::to_value(event),
+            Observation::Moved(event) => serde_json::to_value(event),
+            Observation::DamageDealt(event) => serde_json::to_value(event),
+            Observation::SnapshotMismatch(event) => serde_json::to_value(event),
+            Observation::ChatAbuse(event) => serde_json::to_value(event),
+            Observation::MarketTrade(event) => serde_json::to_value(event),
+            Observation::MovementRejected(event) => serde_json::to_value(event),
+        };
+        value.unwrap_or_default()
+    }
+}
+
+/// A rule an actor violated
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Detection {
+    pub rule_id: String,
+    pub actor_id: String,
+    pub severity: Severity,
+    pub score: f64,
+    pub summary: String,
+    /// Observed value and limit, with the telemetry that crossed it
+    pub evidence: serde_json::Value,
+}
+
+/// How far an observation went over a rule's limit
+struct Violation {
+    observed: f64,
+    limit: f64,
+    summary: String,
+    details: serde_json::Value,
+}
+
+/// Last position of an actor speed is measured from
+#[derive(Debug, Clone)]
+struct Position {
+    at: Timestamp,
+    zone: Option<String>,
+    coordinates: [f64; 3],
+}
+
+/// Checks telemetry against the configured rules, keeping the per-actor history the
+/// rules need
+pub struct Detector {
+    rules: Vec<RuleConfig>,
+    cooldown: Duration,
+    /// Recent amounts per rule index and actor, for rate rules
+    windows: HashMap<(usize, String), VecDeque<(Timestamp, f64)>>,
+    positions: HashMap<String, Position>,
+    /// When each rule last fired per actor
+    fired: HashMap<(usize, String), Timestamp>,
+}
+
+impl Detector {
+    pub fn new(rules: Vec<RuleConfig>, cooldown: std::time::Duration) -> Self {
+        Self {
+            rules,
+            cooldown: Duration::from_std(cooldown).unwrap_or_else(|_| Duration::days(365)),
+            windows: HashMap::new(),
+            positions: HashMap::new(),
+            fired: HashMap::new(),
+        }
+    }
+
+    pub fn rules(&self) -> &[RuleConfig] {
+        &self.rules
+    }
+
+    /// Check an observation made at `at` against every rule
+    pub fn observe(&mut self, observation: &Observation, at: Timestamp) -> Vec<Detection> {
+        let actor_id = observation.actor_id().to_string();
+        let mut detections = Vec::new();
+        for index in 0..self.rules.len() {
+            let Some(violation) = self.evaluate(index, observation, at) else {
+                continue;
+            };
+            let key = (index, actor_id.clone());
+            if self.fired.get(&key).is_some_and(|fired| at - *fired < self.cooldown) {
+                continue;
+            }
+            self.fired.insert(key, at);
+
+            let rule = &self.rules[index];
+            let mut evidence = serde_json::json!({
+                "observed": violation.observed,
+                "limit": violation.limit,
+                "telemetry": observation.telemetry(),
+            });
+            if let (Some(evidence), serde_json::Value::Object(details)) = (evidence.as_object_mut(), violation.details) {
+                evidence.extend(details);
+            }
+            detections.push(Detection {
+                rule_id: rule.id.clone(),
+                actor_id: actor_id.clone(),
+                severity: rule.severity,
+                score: score(rule.severity, violation.observed, violation.limit),
+                summary: violation.summary,
+                evidence,
+            });
+        }
+
+        // Speed is measured between accepted positions, after every rule saw the previous one
+        if let Observation::Moved(event) = observation {
+            self.moved(event, at);
+        }
+        detections
+    }
+
+    /// Drop the history of an actor that left the world
+    pub fn forget(&mut self, actor_id: &str) {
+        self.positions.remove(actor_id);
+        self.windows.retain(|(_, actor), _| actor != actor_id);
+        self.fired.retain(|(_, actor), _| actor != actor_id);
+    }
+
+    fn evaluate(&mut self, index: usize, observation: &Observation, at: Timestamp) -> Option<Violation> {
+        match (&self.rules[index].kind, observation) {
+            (RuleKind::StatDelta { stats, max_delta }, Observation::StatChanged(event)) => {
+                if !stats.is_empty() && !stats.contains(&event.stat) {
+                    return None;
+                }
+                stat_violation(event, *max_delta)
+            }
+            (RuleKind::ExperienceRate { max_per_second, window_secs }, Observation::ExperienceGained(event)) => {
+                let (max_per_second, window_secs) = (*max_per_second, *window_secs);
+                let rate = rate(&mut self.windows, (index, event.actor_id.clone()), event.amount, window_secs, at);
+                (rate > max_per_second).then(|| Violation {
+                    observed: rate,
+                    limit: max_per_second,
+                    summary: format!("Gained {:.1} experience/s over {}s, limit {:.1}", rate, window_secs, max_per_second),
+                    details: serde_json::json!({ "window_secs": window_secs }),
+                })
+            }
+            (RuleKind::DamageRate { max_per_second, window_secs }, Observation::DamageDealt(event)) => {
+                let (max_per_second, window_secs) = (*max_per_second, *window_secs);
+                let rate = rate(&mut self.windows, (index, event.attacker_id.clone()), event.amount, window_secs, at);
+                (rate > max_per_second).then(|| Violation {
+                    observed: rate,
+                    limit: max_per_second,
+                    summary: format!("Dealt {:.1} damage/s over {}s, limit {:.1}", rate, window_secs, max_per_second),
+                    details: serde_json::json!({ "window_secs": window_secs }),
+                })
+            }
+            (RuleKind::MovementSpeed { tolerance }, Observation::Moved(event)) => {
+                self.speed_violation(event, *tolerance, at)
+            }
+            (RuleKind::SnapshotMismatch { sources }, Observation::SnapshotMismatch(event)) => {
+                if !sources.is_empty() && !sources.contains(&event.source) {
+                    return None;
+                }
+                // Either the snapshot reproduces or it does not, so a mismatch scores the severity weight
+                Some(Violation {
+                    observed: 1.0,
+                    limit: 1.0,
+                    summary: format!(
+                        "{} snapshot {} does not match the server's {}",
+                        event.source, event.observed_hash, event.expected_hash
+                    ),
+                    details: serde_json::json!({ "source": event.source, "stats": event.stats }),
+                })
+            }
+            (RuleKind::ChatAbuse { kinds, max_reports, window_secs }, Observation::ChatAbuse(event)) => {
+                if !kinds.is_empty() && !kinds.contains(&event.kind) {
+                    return None;
+                }
+                let (limit, window_secs) = (*max_reports as f64, *window_secs);
+                let reports = (rate(&mut self.windows, (index, event.sender_id.clone()), 1.0, window_secs, at)
+                    * window_secs.max(1) as f64)
+                    .round();
+                (reports > limit).then(|| Violation {
+                    observed: reports,
+                    limit,
+                    summary: format!("{} refused chat messages in {}s, limit {}", reports, window_secs, limit),
+                    details: serde_json::json!({ "window_secs": window_secs, "channel": event.channel }),
+                })
+            }
+            (RuleKind::TradePrice { max_ratio }, Observation::MarketTrade(event)) => {
+                // The first sales of an item have nothing to be compared with
+                let reference = event.reference_unit_price.filter(|reference| *reference > 0.0)?;
+                let unit_price = event.unit_price();
+                if unit_price <= 0.0 {
+                    return None;
+                }
+                let ratio = (unit_price / reference).max(reference / unit_price);
+                (ratio > *max_ratio).then(|| Violation {
+                    observed: ratio,
+                    limit: *max_ratio,
+                    summary: format!(
+                        "Sold {} at {:.1} per unit, {:.1}x off the usual {:.1}",
+                        event.item_id, unit_price, ratio, reference
+                    ),
+                    details: serde_json::json!({ "unit_price": unit_price }),
+                })
+            }
+            (RuleKind::RejectedMovement { kinds }, Observation::MovementRejected(event)) => {
+                if !kinds.is_empty() && !kinds.contains(&event.kind) {
+                    return None;
+                }
+                // Collisions and bounds are refused outright, so they score the severity weight
+                Some(Violation {
+                    observed: event.observed,
+                    limit: event.limit,
+                    summary: format!(
+                        "Moved to {:?} in {}, refused for {}",
+                        event.attempted,
+                        event.zone,
+                        event.kind.as_str()
+                    ),
+                    details: serde_json::json!({ "from": event.from, "zone": event.zone }),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn speed_violation(&self, event: &ActorMoved, tolerance: f64, at: Timestamp) -> Option<Violation> {
+        let previous = self.positions.get(&event.actor_id)?;
+        if event.teleported || previous.zone != event.zone {
+            return None;
+        }
+        let elapsed = at - previous.at;
+        if elapsed < Duration::milliseconds(MIN_MOVE_INTERVAL_MS) {
+            return None;
+        }
+        let seconds = elapsed.num_milliseconds() as f64 / 1000.0;
+        let distance = distance(previous.coordinates, [event.x, event.y, event.z]);
+        let speed = distance / seconds;
+        let limit = event.max_speed * tolerance;
+        (speed > limit).then(|| Violation {
+            observed: speed,
+            limit,
+            summary: format!("Moved {:.1} units in {:.2}s ({:.1}/s), limit {:.1}/s", distance, seconds, speed, limit),
+            details: serde_json::json!({
+                "from": previous.coordinates,
+                "elapsed_secs": seconds,
+            }),
+        })
+    }
+
+    /// Remember where an actor moved to, unless it is too soon to measure from there
+    fn moved(&mut self, event: &ActorMoved, at: Timestamp) {
+        let too_soon = self.positions.get(&event.actor_id).is_some_and(|previous| {
+            !event.teleported && previous.zone == event.zone && at - previous.at < Duration::milliseconds(MIN_MOVE_INTERVAL_MS)
+        });
+        if too_soon {
+            return;
+        }
+        self.positions.insert(
+            event.actor_id.clone(),
+            Position { at, zone: event.zone.clone(), coordinates: [event.x, event.y, event.z] },
+        );
+    }
+}
+
+/// Average amount per second over the window, including `amount` observed at `at`
+fn rate(
+    windows: &mut HashMap<(usize, String), VecDeque<(Timestamp, f64)>>,
+    key: (usize, String),
+    amount: f64,
+    window_secs: u64,
+    at: Timestamp,
+) -> f64 {
+    let window = windows.entry(key).or_default();
+    window.push_back((at, amount));
+    let start = at - Duration::seconds(window_secs as i64);
+    while window.front().is_some_and(|(observed_at, _)| *observed_at <= start) {
+        window.pop_front();
+    }
+    let total: f64 = window.iter().map(|(_, amount)| amount).sum();
+    total / window_secs.max(1) as f64
+}
+
+fn stat_violation(event: &ActorStatChanged, max_delta: f64) -> Option<Violation> {
+    let delta = (event.current - event.previous).abs();
+    let over_delta = (delta > max_delta).then(|| Violation {
+        observed: delta,
+        limit: max_delta,
+        summary: format!("{} changed by {:.1} at once, limit {:.1}", event.stat, delta, max_delta),
+        details: serde_json::json!({ "stat": event.stat }),
+    });
+    let over_cap = event.cap.filter(|cap| event.current > *cap).map(|cap| Violation {
+        observed: event.current,
+        limit: cap,
+        summary: format!("{} is {:.1}, above its cap of {:.1}", event.stat, event.current, cap),
+        details: serde_json::json!({ "stat": event.stat }),
+    });
+    // Report whichever is further over its limit
+    match (over_delta, over_cap) {
+        (Some(delta), Some(cap)) if ratio(cap.observed, cap.limit) > ratio(delta.observed, delta.limit) => Some(cap),
+        (Some(delta), _) => Some(delta),
+        (None, cap) => cap,
+    }
+}
+
+fn ratio(observed: f64, limit: f64) -> f64 {
+    if limit > 0.0 {
+        observed / limit
+    } else {
+        f64::INFINITY
+    }
+}
+
+/// `severity weight × observed / limit`, capped at [`MAX_SCORE`]
+pub fn score(severity: Severity, observed: f64, limit: f64) -> f64 {
+    (severity.weight() * ratio(observed, limit)).min(MAX_SCORE)
+}
+
+fn distance(from: [f64; 3], to: [f64; 3]) -> f64 {
+    from.iter().zip(to).map(|(from, to)| (to - from).powi(2)).sum::<f64>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn at(ms: i64) -> Timestamp {
+        Utc.timestamp_millis_opt(1_700_000_000_000 + ms).unwrap()
+    }
+
+    fn detector(kind: RuleKind) -> Detector {
+        let rule = RuleConfig { id: "rule".to_string(), severity: Severity::High, kind };
+        Detector::new(vec![rule], std::time::Duration::from_secs(60))
+    }
+
+    fn moved(x: f64, teleported: bool) -> Observation {
+        Observation::Moved(ActorMoved {
+            actor_id: "player".to_string(),
+            zone: Some("forest".to_string()),
+            x,
+            y: 0.0,
+            z: 0.0,
+            max_speed: 10.0,
+            teleported,
+        })
+    }
+
+    fn damage(amount: f64) -> Observation {
+        Observation::DamageDealt(DamageDealt {
+            attacker_id: "player".to_string(),
+            target_id: "goblin".to_string(),
+            amount,
+            skill: None,
+        })
+    }
+
+    #[test]
+    fn snapshot_rules_only_fire_for_their_sources() {
+        let mut detector = detector(RuleKind::SnapshotMismatch { sources: vec!["client".to_string()] });
+        let mismatch = |source: &str| {
+            Observation::SnapshotMismatch(ActorSnapshotMismatch {
+                actor_id: "player".to_string(),
+                source: source.to_string(),
+                expected_hash: "00000000000000aa".to_string(),
+                observed_hash: "00000000000000bb".to_string(),
+                stats: Vec::new(),
+            })
+        };
+
+        assert!(detector.observe(&mismatch("cache"), at(0)).is_empty());
+        let detections = detector.observe(&mismatch("client"), at(0));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].score, Severity::High.weight());
+        assert_eq!(detections[0].evidence["source"], "client");
+    }
+
+    #[test]
+    fn chat_abuse_rules_count_reports_of_their_kinds() {
+        let kind = RuleKind::ChatAbuse { kinds: vec![ChatAbuseKind::Flooding], max_reports: 2, window_secs: 60 };
+        let mut detector = detector(kind);
+        let report = |kind: ChatAbuseKind| {
+            Observation::ChatAbuse(ChatAbuseReported {
+                sender_id: "user".to_string(),
+                channel: "zone:forest".to_string(),
+                kind,
+                reason: "More than 5 messages in 10s".to_string(),
+                excerpt: "buy gold".to_string(),
+            })
+        };
+
+        assert!(detector.observe(&report(ChatAbuseKind::Filtered), at(0)).is_empty());
+        assert!(detector.observe(&report(ChatAbuseKind::Flooding), at(1_000)).is_empty());
+        assert!(detector.observe(&report(ChatAbuseKind::Flooding), at(2_000)).is_empty());
+        let detections = detector.observe(&report(ChatAbuseKind::Flooding), at(3_000));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].actor_id, "user");
+        assert_eq!(detections[0].evidence["observed"], 3.0);
+        assert_eq!(detections[0].evidence["channel"], "zone:forest");
+    }
+
+    #[test]
+    fn trade_price_rules_flag_the_side_a_lopsided_sale_favours() {
+        let mut detector = detector(RuleKind::TradePrice { max_ratio: 5.0 });
+        let trade = |price: u64, reference: Option<f64>| {
+            Observation::MarketTrade(MarketTrade {
+                listing_id: "listing".to_string(),
+                item_id: "sword".to_string(),
+                quantity: 2,
+                seller_id: "seller".to_string(),
+                buyer_id: "buyer".to_string(),
+                price,
+                fee: 0,
+                buyout: true,
+                reference_unit_price: reference,
+                at: at(0),
+            })
+        };
+
+        assert!(detector.observe(&trade(10_000, None), at(0)).is_empty());
+        assert!(detector.observe(&trade(800, Some(100.0)), at(0)).is_empty());
+        let detections = detector.observe(&trade(2_000, Some(100.0)), at(0));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].actor_id, "seller");
+        assert_eq!(detections[0].evidence["observed"], 10.0);
+        let detections = detector.observe(&trade(20, Some(100.0)), at(0));
+        assert_eq!(detections[0].actor_id, "buyer");
+    }
+
+    #[test]
+    fn rejected_movement_rules_score_how_far_the_move_went() {
+        let mut detector = detector(RuleKind::RejectedMovement { kinds: vec![MovementViolationKind::Speed] });
+        let rejected = |kind: MovementViolationKind, observed: f64| {
+            Observation::MovementRejected(MovementRejected {
+                actor_id: "player".to_string(),
+                zone: "forest".to_string(),
+                kind,
+                from: [10.0, 0.0, 10.0],
+                attempted: [40.0, 0.0, 10.0],
+                observed,
+                limit: 6.25,
+            })
+        };
+
+        assert!(detector.observe(&rejected(MovementViolationKind::Collision, 6.25), at(0)).is_empty());
+        let detections = detector.observe(&rejected(MovementViolationKind::Speed, 9.375), at(0));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].score, Severity::High.weight() * 1.5);
+        assert_eq!(detections[0].evidence["zone"], "forest");
+    }
+
+    #[test]
+    fn scores_grow_with_the_violation_and_saturate() {
+        assert_eq!(score(Severity::Medium, 15.0, 10.0), 37.5);
+        assert_eq!(score(Severity::High, 20.0, 10.0), 100.0);
+        assert_eq!(score(Severity::Low, 1.0, 0.0), MAX_SCORE);
+    }
+
+    #[test]
+    fn stat_rules_flag_spikes_and_values_over_the_cap() {
+        let mut detector = detector(RuleKind::StatDelta { stats: vec!["strength".to_string()], max_delta: 10.0 });
+        let change = |stat: &str, previous: f64, current: f64, cap: Option<f64>| {
+            Observation::StatChanged(ActorStatChanged {
+                actor_id: "player".to_string(),
+                stat: stat.to_string(),
+                previous,
+                current,
+                cap,
+                source: None,
+            })
+        };
+
+        assert!(detector.observe(&change("strength", 10.0, 15.0, None), at(0)).is_empty());
+        assert!(detector.observe(&change("agility", 10.0, 500.0, None), at(0)).is_empty());
+
+        let detections = detector.observe(&change("strength", 15.0, 18.0, Some(16.0)), at(0));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].evidence["observed"], 18.0);
+        assert_eq!(detections[0].evidence["limit"], 16.0);
+        assert_eq!(detections[0].evidence["telemetry"]["stat"], "strength");
+    }
+
+    #[test]
+    fn rate_rules_use_a_sliding_window() {
+        let mut detector = detector(RuleKind::DamageRate { max_per_second: 100.0, window_secs: 10 });
+
+        // 900 damage in 10s is within 100/s
+        for second in 0..9 {
+            assert!(detector.observe(&damage(100.0), at(second * 1000)).is_empty());
+        }
+        // The first hit left the window, so this is 900 again
+        assert!(detector.observe(&damage(100.0), at(10_000)).is_empty());
+
+        let detections = detector.observe(&damage(500.0), at(10_500));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].rule_id, "rule");
+        assert_eq!(detections[0].actor_id, "player");
+        assert_eq!(detections[0].evidence["observed"], 140.0);
+        assert_eq!(detections[0].evidence["window_secs"], 10);
+        assert_eq!(detections[0].score, 70.0);
+    }
+
+    #[test]
+    fn actors_are_not_reported_again_during_the_cooldown() {
+        let mut detector = detector(RuleKind::DamageRate { max_per_second: 1.0, window_secs: 1 });
+        assert_eq!(detector.observe(&damage(10.0), at(0)).len(), 1);
+        assert!(detector.observe(&damage(10.0), at(30_000)).is_empty());
+        assert_eq!(detector.observe(&damage(10.0), at(60_000)).len(), 1);
+
+        detector.forget("player");
+        assert_eq!(detector.observe(&damage(10.0), at(61_000)).len(), 1);
+    }
+
+    #[test]
+    fn movement_is_checked_against_the_actor_speed() {
+        let mut detector = detector(RuleKind::MovementSpeed { tolerance: 1.5 });
+        assert!(detector.observe(&moved(0.0, false), at(0)).is_empty());
+        // 10 units in 1s is the actor's speed
+        assert!(detector.observe(&moved(10.0, false), at(1000)).is_empty());
+        // Teleports skip the check and move the reference
+        assert!(detector.observe(&moved(1000.0, true), at(1100)).is_empty());
+        // Updates in quick succession are measured from the last accepted position
+        assert!(detector.observe(&moved(1001.0, false), at(1200)).is_empty());
+
+        // 50 units in 1s from the teleport target
+        let detections = detector.observe(&moved(1050.0, false), at(2100));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].evidence["limit"], 15.0);
+        assert_eq!(detections[0].evidence["observed"], 50.0);
+        assert_eq!(detections[0].severity, Severity::High);
+    }
+}
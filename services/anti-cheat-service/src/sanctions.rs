@@ -0,0 +1,289 @@
+//! Sanctions against accounts, issued up the sanction ladder and open to appeal.
+//!
+//! Every offence moves an actor one step up the configured ladder (e.g. warn → mute →
+//! temp ban → permaban), counting the sanctions of the offence window that were not
+//! overturned. Sanctions are enforced by user-management on the account owning the
+//! actor and remember the incidents that led to them.
+
+use futures::TryStreamExt;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOptions, IndexOptions, ReplaceOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+pub const COLLECTION: &str = "sanctions";
+
+/// Sanctions listed at once by default, and at most
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanctionAction {
+    Warn,
+    Mute,
+    TempBan,
+    PermaBan,
+}
+
+impl SanctionAction {
+    /// Action applying the sanction through user-management
+    pub fn enforcing_action(self) -> &'static str {
+        match self {
+            SanctionAction::Warn => "warn",
+            SanctionAction::Mute => "mute",
+            SanctionAction::TempBan => "suspend",
+            SanctionAction::PermaBan => "ban",
+        }
+    }
+
+    /// Action undoing the sanction when it expires or is overturned; warnings stay on record
+    pub fn lifting_action(self) -> Option<&'static str> {
+        match self {
+            SanctionAction::Warn => None,
+            SanctionAction::Mute => Some("unmute"),
+            SanctionAction::TempBan | SanctionAction::PermaBan => Some("reinstate"),
+        }
+    }
+
+    pub fn is_ban(self) -> bool {
+        matches!(self, SanctionAction::TempBan | SanctionAction::PermaBan)
+    }
+}
+
+/// One step of the sanction ladder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LadderStep {
+    pub action: SanctionAction,
+    /// How long the sanction lasts, permanent when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<u64>,
+}
+
+/// Step for an actor with `prior` offences: one up per offence, staying on the last step
+pub fn next_step(ladder: &[LadderStep], prior: usize) -> Option<&LadderStep> {
+    ladder.get(prior.min(ladder.len().saturating_sub(1)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SanctionStatus {
+    Active,
+    Expired,
+    /// Lifted on appeal; does not count as an offence
+    Overturned,
+}
+
+/// Whether user-management applied the sanction to the account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Enforcement {
+    Pending,
+    Applied { at: Timestamp },
+    /// Retried until it succeeds or the sanction ends
+    Failed { error: String, attempts: u32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AppealStatus {
+    Pending,
+    /// The sanction stands
+    Upheld,
+    /// The sanction was overturned
+    Granted,
+}
+
+/// A player's appeal against a sanction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Appeal {
+    pub statement: String,
+    pub submitted_by: String,
+    pub submitted_at: Timestamp,
+    pub status: AppealStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decided_at: Option<Timestamp>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sanction {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub actor_id: String,
+    /// Account owning the actor, once resolved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    pub action: SanctionAction,
+    pub reason: String,
+    /// Incidents the sanction is based on
+    pub incident_ids: Vec<String>,
+    /// Review that confirmed the sanction, absent for automatic ones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review_id: Option<String>,
+    /// Reviewing GM, or `auto`
+    pub issued_by: String,
+    pub issued_at: Timestamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Timestamp>,
+    pub status: SanctionStatus,
+    pub enforcement: Enforcement,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub appeal: Option<Appeal>,
+}
+
+impl Sanction {
+    /// Whether the sanction counts towards the ladder at `since` or later
+    pub fn is_offence_since(&self, since: Timestamp) -> bool {
+        self.status != SanctionStatus::Overturned && self.issued_at >= since
+    }
+}
+
+/// Filters for listing sanctions, newest first
+#[derive(Debug, Default, Deserialize)]
+pub struct SanctionQuery {
+    pub actor_id: Option<String>,
+    pub user_id: Option<String>,
+    pub status: Option<SanctionStatus>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct SanctionStore {
+    collection: Collection<Sanction>,
+}
+
+impl SanctionStore {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "actor_id": 1, "issued_at": -1 })
+                .options(IndexOptions::builder().name("actor_issued".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "user_id": 1, "status": 1 })
+                .options(IndexOptions::builder().name("user_status".to_string()).build())
+                .build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    pub async fn insert(&self, sanction: &Sanction) -> mongodb::error::Result<()> {
+        self.collection.insert_one(sanction, None).await?;
+        Ok(())
+    }
+
+    /// Store the current state of a sanction
+    pub async fn save(&self, sanction: &Sanction) -> mongodb::error::Result<()> {
+        let upsert = ReplaceOptions::builder().upsert(true).build();
+        self.collection.replace_one(doc! { "_id": &sanction.id }, sanction, upsert).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> mongodb::error::Result<Option<Sanction>> {
+        self.collection.find_one(doc! { "_id": id }, None).await
+    }
+
+    pub async fn list(&self, query: &SanctionQuery) -> mongodb::error::Result<Vec<Sanction>> {
+        let options = FindOptions::builder()
+            .sort(doc! { "issued_at": -1 })
+            .limit(query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT))
+            .build();
+        self.collection.find(filter(query), options).await?.try_collect().await
+    }
+
+    /// Every sanction of an actor, newest first
+    pub async fn for_actor(&self, actor_id: &str) -> mongodb::error::Result<Vec<Sanction>> {
+        let options = FindOptions::builder().sort(doc! { "issued_at": -1 }).build();
+        self.collection.find(doc! { "actor_id": actor_id }, options).await?.try_collect().await
+    }
+
+    /// Active bans of an account
+    pub async fn active_bans(&self, user_id: &str) -> mongodb::error::Result<Vec<Sanction>> {
+        let filter = doc! {
+            "user_id": user_id,
+            "status": "active",
+            "action": { "$in": ["temp_ban", "perma_ban"] },
+        };
+        self.collection.find(filter, None).await?.try_collect().await
+    }
+
+    /// Active sanctions that may expire or still need enforcing
+    pub async fn needing_attention(&self) -> mongodb::error::Result<Vec<Sanction>> {
+        let filter = doc! {
+            "status": "active",
+            "$or": [
+                { "expires_at": { "$exists": true } },
+                { "enforcement.state": { "$in": ["pending", "failed"] } },
+            ],
+        };
+        self.collection.find(filter, None).await?.try_collect().await
+    }
+}
+
+fn filter(query: &SanctionQuery) -> Document {
+    let mut filter = Document::new();
+    if let Some(actor_id) = &query.actor_id {
+        filter.insert("actor_id", actor_id);
+    }
+    if let Some(user_id) = &query.user_id {
+        filter.insert("user_id", user_id);
+    }
+    if let Some(status) = query.status {
+        if let Ok(status) = mongodb::bson::to_bson(&status) {
+            filter.insert("status", status);
+        }
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ladder() -> Vec<LadderStep> {
+        vec![
+            LadderStep { action: SanctionAction::Warn, duration_secs: None },
+            LadderStep { action: SanctionAction::Mute, duration_secs: Some(3600) },
+            LadderStep { action: SanctionAction::TempBan, duration_secs: Some(86400) },
+            LadderStep { action: SanctionAction::PermaBan, duration_secs: None },
+        ]
+    }
+
+    #[test]
+    fn each_offence_moves_one_step_up_the_ladder() {
+        let ladder = ladder();
+        let actions: Vec<SanctionAction> =
+            (0..6).map(|prior| next_step(&ladder, prior).unwrap().action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                SanctionAction::Warn,
+                SanctionAction::Mute,
+                SanctionAction::TempBan,
+                SanctionAction::PermaBan,
+                SanctionAction::PermaBan,
+                SanctionAction::PermaBan,
+            ]
+        );
+        assert!(next_step(&[], 0).is_none());
+    }
+
+    #[test]
+    fn enforcement_state_is_tagged() {
+        let failed = Enforcement::Failed { error: "timeout".to_string(), attempts: 2 };
+        let json = serde_json::to_value(&failed).unwrap();
+        assert_eq!(json["state"], "failed");
+        assert_eq!(json["attempts"], 2);
+        assert_eq!(serde_json::to_value(Enforcement::Pending).unwrap()["state"], "pending");
+    }
+}
@@ -0,0 +1,86 @@
+//! Client for the user-management internal API, which owns accounts and enforces
+//! sanctions on them.
+
+use serde::Deserialize;
+use serde_json::json;
+use shared::{ChaosError, ChaosResult, Timestamp};
+use std::time::Duration;
+
+use crate::config::UserManagementConfig;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct OwnerResponse {
+    account: Account,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    user_id: String,
+}
+
+pub struct UserManagementClient {
+    client: reqwest::Client,
+    config: UserManagementConfig,
+}
+
+impl UserManagementClient {
+    pub fn new(config: UserManagementConfig) -> Self {
+        Self {
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            config,
+        }
+    }
+
+    /// Account owning an actor of the configured world
+    pub async fn resolve_owner(&self, actor_id: &str) -> ChaosResult<String> {
+        let path = format!("/internal/characters/{}/{}/owner", self.config.world_id, actor_id);
+        let response = self.send(self.client.get(self.url(&path))).await?;
+        let owner: OwnerResponse = response
+            .json()
+            .await
+            .map_err(|e| ChaosError::Serialization(format!("Unexpected owner response: {}", e)))?;
+        Ok(owner.account.user_id)
+    }
+
+    /// Apply a sanction action to an account; `reference` is recorded in its audit log
+    pub async fn apply(
+        &self,
+        user_id: &str,
+        action: &str,
+        reason: &str,
+        reference: &str,
+        expires_at: Option<Timestamp>,
+    ) -> ChaosResult<()> {
+        let path = format!("/internal/users/{}/sanctions", user_id);
+        let body = json!({
+            "action": action,
+            "reason": reason,
+            "reference": reference,
+            "expires_at": expires_at,
+        });
+        self.send(self.client.post(self.url(&path)).json(&body)).await?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.config.url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, mut request: reqwest::RequestBuilder) -> ChaosResult<reqwest::Response> {
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("X-Internal-Api-Key", api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ChaosError::Network(format!("user-management unreachable: {}", e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ChaosError::ExternalService(format!("user-management answered {}: {}", status, body)));
+        }
+        Ok(response)
+    }
+}
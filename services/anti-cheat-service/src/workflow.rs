@@ -0,0 +1,406 @@
+//! Turns incidents into sanctions: automatically above the auto score, through GM
+//! review below it, and back out again on expiry or a granted appeal.
+//!
+//! Decisions are serialized so concurrent incidents for one actor cannot skip ladder
+//! steps or open duplicate review cases. Enforcement through user-management may fail;
+//! the sanction is recorded anyway and enforcement is retried by the sweeper.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::Utc;
+use serde_json::json;
+use shared::ChaosResult;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::audit::{AuditEntry, AuditLog};
+use crate::config::SanctionConfig;
+use crate::incidents::Incident;
+use crate::reviews::{ReviewCase, ReviewStatus, ReviewStore};
+use crate::sanctions::{next_step, Appeal, AppealStatus, Enforcement, Sanction, SanctionStatus, SanctionStore};
+use crate::user_management::UserManagementClient;
+
+/// Issuer of sanctions nobody reviewed
+pub const AUTOMATED: &str = "auto";
+
+/// Author of expiries and enforcement retries
+pub const SYSTEM: &str = "system";
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorkflowError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+}
+
+impl IntoResponse for WorkflowError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            WorkflowError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            WorkflowError::NotFound(_) => StatusCode::NOT_FOUND,
+            WorkflowError::Conflict(_) => StatusCode::CONFLICT,
+            WorkflowError::Forbidden(_) => StatusCode::FORBIDDEN,
+            WorkflowError::Database(e) => {
+                tracing::error!("Database error: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, axum::Json(json!({ "success": false, "error": self.to_string() }))).into_response()
+    }
+}
+
+pub type WorkflowResult<T> = Result<T, WorkflowError>;
+
+pub struct SanctionWorkflow {
+    config: SanctionConfig,
+    sanctions: SanctionStore,
+    reviews: ReviewStore,
+    audit: AuditLog,
+    users: UserManagementClient,
+    decisions: Mutex<()>,
+}
+
+impl SanctionWorkflow {
+    pub fn new(
+        config: SanctionConfig,
+        sanctions: SanctionStore,
+        reviews: ReviewStore,
+        audit: AuditLog,
+        users: UserManagementClient,
+    ) -> Self {
+        Self { config, sanctions, reviews, audit, users, decisions: Mutex::new(()) }
+    }
+
+    /// Whether a caller with these gateway roles may review and decide appeals
+    pub fn is_moderator(&self, roles: &[String]) -> bool {
+        roles.iter().any(|role| self.config.moderator_roles.contains(role))
+    }
+
+    /// Act on a stored incident: sanction, queue for review, or only keep it
+    pub async fn incident(&self, incident: &Incident) -> WorkflowResult<()> {
+        if incident.score < self.config.review_min_score {
+            return Ok(());
+        }
+        let _decision = self.decisions.lock().await;
+
+        if incident.score >= self.config.auto_min_score {
+            // A burst of incidents is one offence, not a trip up the whole ladder
+            let cooldown = chrono::Duration::seconds(self.config.cooldown_secs as i64);
+            let sanctions = self.sanctions.for_actor(&incident.actor_id).await?;
+            let recent = sanctions
+                .into_iter()
+                .find(|sanction| sanction.is_offence_since(Utc::now() - cooldown));
+            if let Some(mut sanction) = recent {
+                sanction.incident_ids.push(incident.id.clone());
+                self.sanctions.save(&sanction).await?;
+                self.audit
+                    .record(
+                        AuditEntry::sanction("evidence_attached", AUTOMATED, &sanction)
+                            .with_details(format!("incident={}", incident.id)),
+                    )
+                    .await;
+                return Ok(());
+            }
+            let reason = format!("{} (incident {})", incident.summary, incident.id);
+            self.issue(&incident.actor_id, vec![incident.id.clone()], None, AUTOMATED, reason).await?;
+            return Ok(());
+        }
+
+        match self.reviews.join_pending(incident).await? {
+            Some(case) => {
+                self.audit
+                    .record(
+                        AuditEntry::review("evidence_attached", AUTOMATED, &case)
+                            .with_details(format!("incident={}", incident.id)),
+                    )
+                    .await;
+            }
+            None => {
+                let case = ReviewCase::open(incident);
+                self.reviews.insert(&case).await?;
+                info!(actor_id = %case.actor_id, review_id = %case.id, "📋 Incident queued for review");
+                self.audit.record(AuditEntry::review("review_opened", AUTOMATED, &case)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Approve a review case, issuing the actor's next sanction
+    pub async fn approve(&self, review_id: &str, by: &str, note: Option<String>) -> WorkflowResult<(ReviewCase, Sanction)> {
+        let _decision = self.decisions.lock().await;
+        let mut case = self.pending_case(review_id).await?;
+        let reason = note.clone().unwrap_or_else(|| format!("Confirmed on review {}", case.id));
+        let sanction = self.issue(&case.actor_id, case.incident_ids.clone(), Some(case.id.clone()), by, reason).await?;
+
+        case.decide(ReviewStatus::Approved, by, note);
+        case.sanction_id = Some(sanction.id.clone());
+        self.reviews.save(&case).await?;
+        self.audit.record(AuditEntry::review("review_approved", by, &case)).await;
+        Ok((case, sanction))
+    }
+
+    /// Reject a review case without sanctioning the actor
+    pub async fn reject(&self, review_id: &str, by: &str, note: Option<String>) -> WorkflowResult<ReviewCase> {
+        let _decision = self.decisions.lock().await;
+        let mut case = self.pending_case(review_id).await?;
+        case.decide(ReviewStatus::Rejected, by, note.clone());
+        self.reviews.save(&case).await?;
+        let mut entry = AuditEntry::review("review_rejected", by, &case);
+        if let Some(note) = note {
+            entry = entry.with_details(note);
+        }
+        self.audit.record(entry).await;
+        Ok(case)
+    }
+
+    /// Appeal a sanction on behalf of the sanctioned account
+    pub async fn appeal(&self, sanction_id: &str, user_id: &str, statement: String) -> WorkflowResult<Sanction> {
+        let _decision = self.decisions.lock().await;
+        let mut sanction = self.sanction(sanction_id).await?;
+        if sanction.user_id.as_deref() != Some(user_id) {
+            return Err(WorkflowError::Forbidden("Only the sanctioned account can appeal".to_string()));
+        }
+        if sanction.status == SanctionStatus::Overturned {
+            return Err(WorkflowError::Conflict("The sanction was already overturned".to_string()));
+        }
+        if sanction.appeal.is_some() {
+            return Err(WorkflowError::Conflict("The sanction was already appealed".to_string()));
+        }
+
+        sanction.appeal = Some(Appeal {
+            statement,
+            submitted_by: user_id.to_string(),
+            submitted_at: Utc::now(),
+            status: AppealStatus::Pending,
+            decided_by: None,
+            decided_at: None,
+            resolution: None,
+        });
+        self.sanctions.save(&sanction).await?;
+        self.audit.record(AuditEntry::sanction("appeal_submitted", user_id, &sanction)).await;
+        Ok(sanction)
+    }
+
+    /// Decide a pending appeal; granting it overturns and lifts the sanction
+    pub async fn decide_appeal(
+        &self,
+        sanction_id: &str,
+        by: &str,
+        grant: bool,
+        resolution: Option<String>,
+    ) -> WorkflowResult<Sanction> {
+        let _decision = self.decisions.lock().await;
+        let mut sanction = self.sanction(sanction_id).await?;
+        let Some(appeal) = sanction.appeal.as_mut().filter(|appeal| appeal.status == AppealStatus::Pending) else {
+            return Err(WorkflowError::Conflict("The sanction has no pending appeal".to_string()));
+        };
+        appeal.status = if grant { AppealStatus::Granted } else { AppealStatus::Upheld };
+        appeal.decided_by = Some(by.to_string());
+        appeal.decided_at = Some(Utc::now());
+        appeal.resolution = resolution.clone();
+
+        let was_active = sanction.status == SanctionStatus::Active;
+        if grant {
+            sanction.status = SanctionStatus::Overturned;
+        }
+        self.sanctions.save(&sanction).await?;
+        let event = if grant { "appeal_granted" } else { "appeal_upheld" };
+        let mut entry = AuditEntry::sanction(event, by, &sanction);
+        if let Some(resolution) = resolution {
+            entry = entry.with_details(resolution);
+        }
+        self.audit.record(entry).await;
+
+        if grant && was_active {
+            self.lift(&sanction, by, "Appeal granted").await?;
+        }
+        Ok(sanction)
+    }
+
+    /// Expire sanctions that ran out and retry failed enforcements
+    pub async fn sweep(&self) -> WorkflowResult<()> {
+        let _decision = self.decisions.lock().await;
+        let now = Utc::now();
+        for mut sanction in self.sanctions.needing_attention().await? {
+            if sanction.expires_at.is_some_and(|expires_at| expires_at <= now) {
+                sanction.status = SanctionStatus::Expired;
+                self.sanctions.save(&sanction).await?;
+                self.audit.record(AuditEntry::sanction("sanction_expired", SYSTEM, &sanction)).await;
+                self.lift(&sanction, SYSTEM, "Sanction expired").await?;
+            } else if !matches!(sanction.enforcement, Enforcement::Applied { .. }) {
+                self.enforce(&mut sanction, SYSTEM).await;
+                self.sanctions.save(&sanction).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sweep on the configured interval until the task is aborted
+    pub fn spawn_sweeper(self: &Arc<Self>) -> JoinHandle<()> {
+        let workflow = self.clone();
+        let period = Duration::from_secs(self.config.sweep_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = workflow.sweep().await {
+                    warn!("⚠️  Sanction sweep failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Record and enforce the actor's next sanction on the ladder
+    async fn issue(
+        &self,
+        actor_id: &str,
+        incident_ids: Vec<String>,
+        review_id: Option<String>,
+        issued_by: &str,
+        reason: String,
+    ) -> WorkflowResult<Sanction> {
+        let window = chrono::Duration::days(self.config.offence_window_days as i64);
+        let since = Utc::now() - window;
+        let prior = self
+            .sanctions
+            .for_actor(actor_id)
+            .await?
+            .iter()
+            .filter(|sanction| sanction.is_offence_since(since))
+            .count();
+        let step = next_step(&self.config.ladder, prior)
+            .ok_or_else(|| WorkflowError::Conflict("No sanction ladder is configured".to_string()))?;
+
+        let issued_at = Utc::now();
+        let mut sanction = Sanction {
+            id: Uuid::new_v4().to_string(),
+            actor_id: actor_id.to_string(),
+            user_id: None,
+            action: step.action,
+            reason,
+            incident_ids,
+            review_id,
+            issued_by: issued_by.to_string(),
+            issued_at,
+            expires_at: step.duration_secs.map(|secs| issued_at + chrono::Duration::seconds(secs as i64)),
+            status: SanctionStatus::Active,
+            enforcement: Enforcement::Pending,
+            appeal: None,
+        };
+        self.sanctions.insert(&sanction).await?;
+        info!(
+            actor_id = %sanction.actor_id,
+            sanction_id = %sanction.id,
+            action = ?sanction.action,
+            offences = prior + 1,
+            "⚖️  Sanction issued by {}", issued_by
+        );
+        self.audit
+            .record(AuditEntry::sanction("sanction_issued", issued_by, &sanction).with_details(format!("offences={}", prior + 1)))
+            .await;
+
+        self.enforce(&mut sanction, issued_by).await;
+        self.sanctions.save(&sanction).await?;
+        Ok(sanction)
+    }
+
+    /// Apply a sanction to the owning account, recording the outcome on the sanction
+    async fn enforce(&self, sanction: &mut Sanction, by: &str) {
+        let result: ChaosResult<()> = async {
+            let user_id = match &sanction.user_id {
+                Some(user_id) => user_id.clone(),
+                None => {
+                    let user_id = self.users.resolve_owner(&sanction.actor_id).await?;
+                    sanction.user_id = Some(user_id.clone());
+                    user_id
+                }
+            };
+            let action = sanction.action.enforcing_action();
+            self.users.apply(&user_id, action, &sanction.reason, &sanction.id, sanction.expires_at).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                sanction.enforcement = Enforcement::Applied { at: Utc::now() };
+                self.audit.record(AuditEntry::sanction("sanction_enforced", by, sanction)).await;
+            }
+            Err(e) => {
+                let attempts = match &sanction.enforcement {
+                    Enforcement::Failed { attempts, .. } => attempts + 1,
+                    _ => 1,
+                };
+                warn!(sanction_id = %sanction.id, attempts, "⚠️  Failed to enforce sanction: {}", e);
+                // Retries are not audited one by one, only the first failure
+                if attempts == 1 {
+                    self.audit
+                        .record(AuditEntry::sanction("sanction_enforcement_failed", by, sanction).with_details(e.to_string()))
+                        .await;
+                }
+                sanction.enforcement = Enforcement::Failed { error: e.to_string(), attempts };
+            }
+        }
+    }
+
+    /// Undo an enforced sanction, unless another ban still holds the account
+    async fn lift(&self, sanction: &Sanction, by: &str, reason: &str) -> WorkflowResult<()> {
+        let (Some(action), Some(user_id)) = (sanction.action.lifting_action(), &sanction.user_id) else {
+            return Ok(());
+        };
+        if !matches!(sanction.enforcement, Enforcement::Applied { .. }) {
+            return Ok(());
+        }
+        if sanction.action.is_ban() {
+            let other_bans = self.sanctions.active_bans(user_id).await?;
+            if other_bans.iter().any(|ban| ban.id != sanction.id) {
+                self.audit
+                    .record(AuditEntry::sanction("sanction_lift_skipped", by, sanction).with_details("Another ban is active"))
+                    .await;
+                return Ok(());
+            }
+        }
+
+        match self.users.apply(user_id, action, reason, &sanction.id, None).await {
+            Ok(()) => self.audit.record(AuditEntry::sanction("sanction_lifted", by, sanction)).await,
+            Err(e) => {
+                warn!(sanction_id = %sanction.id, "⚠️  Failed to lift sanction: {}", e);
+                self.audit
+                    .record(AuditEntry::sanction("sanction_lift_failed", by, sanction).with_details(e.to_string()))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn sanction(&self, id: &str) -> WorkflowResult<Sanction> {
+        self.sanctions
+            .get(id)
+            .await?
+            .ok_or_else(|| WorkflowError::NotFound(format!("Sanction not found: {}", id)))
+    }
+
+    async fn pending_case(&self, id: &str) -> WorkflowResult<ReviewCase> {
+        let case = self
+            .reviews
+            .get(id)
+            .await?
+            .ok_or_else(|| WorkflowError::NotFound(format!("Review not found: {}", id)))?;
+        if case.status != ReviewStatus::Pending {
+            return Err(WorkflowError::Conflict("The review was already decided".to_string()));
+        }
+        Ok(case)
+    }
+}
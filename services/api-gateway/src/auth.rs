@@ -1,313 +1,462 @@
-//! Authentication and authorization for API Gateway
-
-use crate::config::{Config, JwtConfig, OAuth2Config, ApiKeysConfig};
-use crate::errors::{ApiGatewayError, Result};
-use crate::types::{User, RequestContext};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+//! Bearer token validation at the gateway.
+//!
+//! Each route declares an [`AuthMode`]. Tokens are validated before the request is
+//! proxied, and the validated claims are forwarded to the upstream service as trusted
+//! headers ([`USER_ID_HEADER`], [`USER_ROLES_HEADER`]). Copies of those headers sent by
+//! clients are always stripped, so upstream services can rely on them. The same goes for
+//! the client address headers, which the gateway sets from the connection.
+
+use crate::config::{AuthConfig, AuthMode, RouteAuthConfig, RouteConfig};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
 use serde::{Deserialize, Serialize};
+use shared::error::{ApiError, ErrorCode};
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{info, debug, error, warn};
-
-/// Authentication service
-#[derive(Clone)]
-pub struct AuthService {
-    config: Config,
-    jwt_config: JwtConfig,
-    oauth2_config: OAuth2Config,
-    api_keys_config: ApiKeysConfig,
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
-    validation: Validation,
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Trusted header carrying the authenticated user ID
+pub const USER_ID_HEADER: &str = "x-user-id";
+
+/// Trusted header carrying the authenticated user's roles, comma-separated
+pub const USER_ROLES_HEADER: &str = "x-user-roles";
+
+/// Trusted header carrying the address the client connected from
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Trusted header carrying the same address, for services reading X-Real-IP
+pub const REAL_IP_HEADER: &str = "x-real-ip";
+
+/// Env var overriding `auth.jwt_secret`
+pub const JWT_SECRET_ENV: &str = "API_GATEWAY_JWT_SECRET";
+
+const DEFAULT_JWKS_REFRESH: Duration = Duration::from_secs(300);
+
+/// Shortest interval between JWKS fetches triggered by unknown key IDs
+const MIN_JWKS_REFETCH: Duration = Duration::from_secs(10);
+
+/// Claims the gateway reads from a validated token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GatewayClaims {
+    /// Subject (user ID)
+    pub sub: String,
+    /// Expiration time
+    pub exp: u64,
+    /// User roles
+    #[serde(default)]
+    pub roles: Vec<String>,
 }
 
-/// JWT claims
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: String,        // Subject (user ID)
-    pub iss: String,        // Issuer
-    pub aud: String,        // Audience
-    pub exp: u64,           // Expiration time
-    pub iat: u64,           // Issued at
-    pub roles: Vec<String>, // User roles
-    pub permissions: Vec<String>, // User permissions
+impl GatewayClaims {
+    /// Trusted headers forwarded upstream for these claims
+    pub fn forwarded_headers(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (USER_ID_HEADER, self.sub.clone()),
+            (USER_ROLES_HEADER, self.roles.join(",")),
+        ]
+    }
 }
 
-/// API key information
-#[derive(Debug, Clone)]
-pub struct ApiKeyInfo {
-    pub name: String,
-    pub permissions: Vec<String>,
-    pub expires_at: Option<SystemTime>,
+/// Trusted headers forwarded upstream for the client's connection
+///
+/// Replace whatever the client sent, so services can key rate limits and lockouts on them.
+pub fn client_address_headers(peer: &SocketAddr) -> Vec<(&'static str, String)> {
+    vec![
+        (FORWARDED_FOR_HEADER, peer.ip().to_string()),
+        (REAL_IP_HEADER, peer.ip().to_string()),
+    ]
 }
 
-/// Initialize auth service
-pub async fn init(config: &Config) -> Result<AuthService> {
-    AuthService::new(config.clone())
+/// Check whether a client header must not be forwarded upstream
+pub fn is_trusted_header(name: &str) -> bool {
+    [USER_ID_HEADER, USER_ROLES_HEADER, FORWARDED_FOR_HEADER, REAL_IP_HEADER]
+        .iter()
+        .any(|trusted| name.eq_ignore_ascii_case(trusted))
 }
 
-impl std::fmt::Debug for AuthService {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("AuthService")
-            .field("config", &self.config)
-            .field("jwt_config", &self.jwt_config)
-            .field("oauth2_config", &self.oauth2_config)
-            .field("api_keys_config", &self.api_keys_config)
-            .field("validation", &self.validation)
-            .finish()
-    }
+/// Why a request was rejected before proxying
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthRejection {
+    /// No bearer token on a route that requires one
+    Missing,
+    /// Token is malformed, badly signed, or has wrong claims
+    Invalid(String),
+    /// Token has expired
+    Expired,
+    /// Token is valid but lacks a required role
+    Forbidden,
+    /// Signing keys could not be loaded
+    Unavailable(String),
 }
 
-impl AuthService {
-    /// Create a new authentication service
-    pub fn new(config: Config) -> Result<Self> {
-        let jwt_config = config.auth.jwt.clone();
-        let oauth2_config = config.auth.oauth2.clone();
-        let api_keys_config = config.auth.api_keys.clone();
-
-        // Create JWT keys
-        let encoding_key = EncodingKey::from_secret(jwt_config.secret.as_ref());
-        let decoding_key = DecodingKey::from_secret(jwt_config.secret.as_ref());
-
-        // Create JWT validation
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.set_issuer(&[&jwt_config.issuer]);
-        validation.set_audience(&[&jwt_config.audience]);
-
-        Ok(Self {
-            config,
-            jwt_config,
-            oauth2_config,
-            api_keys_config,
-            encoding_key,
-            decoding_key,
-            validation,
-        })
+impl AuthRejection {
+    fn error(&self) -> ApiError {
+        match self {
+            AuthRejection::Missing => ApiError::new(ErrorCode::Unauthenticated, "Bearer token required"),
+            AuthRejection::Invalid(_) => ApiError::new(ErrorCode::Unauthenticated, "Invalid bearer token"),
+            AuthRejection::Expired => ApiError::new(ErrorCode::TokenExpired, "Bearer token expired"),
+            AuthRejection::Forbidden => ApiError::new(ErrorCode::PermissionDenied, "Missing required role"),
+            AuthRejection::Unavailable(_) => {
+                ApiError::new(ErrorCode::ServiceUnavailable, "Authentication is temporarily unavailable")
+            }
+        }
     }
+}
 
-    /// Authenticate a request using JWT token
-    pub async fn authenticate_jwt(&self, token: &str) -> Result<User> {
-        debug!("Authenticating JWT token");
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        let error = self.error();
+        let status = StatusCode::from_u16(error.code.http_status()).unwrap_or(StatusCode::UNAUTHORIZED);
+        let mut response = (status, Json(error)).into_response();
+        if status == StatusCode::UNAUTHORIZED {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
-            .map_err(|e| ApiGatewayError::Auth(format!("Invalid JWT token: {}", e)))?;
+/// Keys fetched from a JWKS endpoint
+struct JwksKeys {
+    keys: HashMap<String, DecodingKey>,
+    fetched_at: Instant,
+}
 
-        let claims = token_data.claims;
+/// JWKS endpoint with cached keys
+struct JwksSource {
+    url: String,
+    refresh: Duration,
+    client: reqwest::Client,
+    cache: RwLock<Option<JwksKeys>>,
+}
 
-        // Check if token is expired
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        if claims.exp < now {
-            return Err(ApiGatewayError::Auth("Token expired".to_string()));
+impl JwksSource {
+    async fn key(&self, kid: Option<&str>) -> Result<DecodingKey, AuthRejection> {
+        if let Some(key) = self.cached_key(kid, false).await {
+            return Ok(key);
         }
-
-        // Create user from claims
-        let user = User {
-            id: claims.sub,
-            username: "".to_string(), // TODO: Get from database
-            email: "".to_string(),    // TODO: Get from database
-            roles: claims.roles,
-            permissions: claims.permissions,
-            created_at: SystemTime::now(), // TODO: Get from database
-            last_login: Some(SystemTime::now()),
-            is_active: true,
-        };
-
-        debug!("JWT authentication successful for user: {}", user.id);
-        Ok(user)
+        self.refetch().await?;
+        self.cached_key(kid, true)
+            .await
+            .ok_or_else(|| AuthRejection::Invalid("Unknown signing key".to_string()))
     }
 
-    /// Authenticate a request using API key
-    pub async fn authenticate_api_key(&self, api_key: &str) -> Result<ApiKeyInfo> {
-        debug!("Authenticating API key");
+    async fn cached_key(&self, kid: Option<&str>, allow_stale: bool) -> Option<DecodingKey> {
+        let cache = self.cache.read().await;
+        let cache = cache.as_ref()?;
+        if !allow_stale && cache.fetched_at.elapsed() > self.refresh {
+            return None;
+        }
+        match kid {
+            Some(kid) => cache.keys.get(kid).cloned(),
+            // Tokens without a key ID are only accepted when there is a single key
+            None if cache.keys.len() == 1 => cache.keys.values().next().cloned(),
+            None => None,
+        }
+    }
 
-        if !self.api_keys_config.enabled {
-            return Err(ApiGatewayError::Auth("API key authentication disabled".to_string()));
+    async fn refetch(&self) -> Result<(), AuthRejection> {
+        let mut cache = self.cache.write().await;
+        if let Some(existing) = cache.as_ref() {
+            if existing.fetched_at.elapsed() < MIN_JWKS_REFETCH {
+                return Ok(());
+            }
         }
 
-        for key in &self.api_keys_config.keys {
-            if key.key == api_key {
-                // Check if key is expired
-                if let Ok(expires_at) = chrono::DateTime::parse_from_rfc3339(&key.expires_at) {
-                    if expires_at.timestamp() < SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64 {
-                        return Err(ApiGatewayError::Auth("API key expired".to_string()));
-                    }
-                }
-
-                let api_key_info = ApiKeyInfo {
-                    name: key.name.clone(),
-                    permissions: key.permissions.clone(),
-                    expires_at: None, // TODO: Parse expires_at
+        debug!("Fetching JWKS from {}", self.url);
+        let fetched = async {
+            let response = self.client.get(&self.url).send().await?.error_for_status()?;
+            response.json::<JwkSet>().await
+        }
+        .await;
+        let jwks = match fetched {
+            Ok(jwks) => jwks,
+            Err(e) => {
+                warn!("Failed to fetch JWKS from {}: {}", self.url, e);
+                // Keep serving the previous keys if there are any
+                return match cache.as_ref() {
+                    Some(_) => Ok(()),
+                    None => Err(AuthRejection::Unavailable(e.to_string())),
                 };
-
-                debug!("API key authentication successful: {}", api_key_info.name);
-                return Ok(api_key_info);
             }
-        }
+        };
 
-        Err(ApiGatewayError::Auth("Invalid API key".to_string()))
+        let keys = jwks
+            .keys
+            .iter()
+            .filter_map(|jwk| {
+                let kid = jwk.common.key_id.clone().unwrap_or_default();
+                DecodingKey::from_jwk(jwk).ok().map(|key| (kid, key))
+            })
+            .collect();
+        *cache = Some(JwksKeys {
+            keys,
+            fetched_at: Instant::now(),
+        });
+        Ok(())
     }
+}
 
-    /// Authenticate a request using OAuth2
-    pub async fn authenticate_oauth2(&self, provider: &str, code: &str) -> Result<User> {
-        debug!("Authenticating OAuth2 with provider: {}", provider);
+/// Validates bearer tokens against the configured keys
+pub struct JwtAuthenticator {
+    secret: Option<DecodingKey>,
+    jwks: Option<JwksSource>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    leeway: u64,
+}
 
-        if !self.oauth2_config.enabled {
-            return Err(ApiGatewayError::Auth("OAuth2 authentication disabled".to_string()));
-        }
+impl std::fmt::Debug for JwtAuthenticator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JwtAuthenticator")
+            .field("secret", &self.secret.is_some())
+            .field("jwks_url", &self.jwks.as_ref().map(|jwks| &jwks.url))
+            .field("issuer", &self.issuer)
+            .field("audience", &self.audience)
+            .finish()
+    }
+}
 
-        let oauth_provider = self.oauth2_config.providers.get(provider)
-            .ok_or_else(|| ApiGatewayError::Auth(format!("Unknown OAuth2 provider: {}", provider)))?;
-
-        // TODO: Implement OAuth2 flow
-        // This is a placeholder implementation
-        let user = User {
-            id: "oauth_user_123".to_string(),
-            username: "oauth_user".to_string(),
-            email: "oauth@example.com".to_string(),
-            roles: vec!["user".to_string()],
-            permissions: vec!["read".to_string(), "write".to_string()],
-            created_at: SystemTime::now(),
-            last_login: Some(SystemTime::now()),
-            is_active: true,
+impl JwtAuthenticator {
+    /// Build an authenticator from configuration
+    ///
+    /// Fails if a route requires tokens but no secret or JWKS endpoint is configured.
+    pub fn from_config(config: &AuthConfig, routes: &[RouteConfig]) -> Result<Self, String> {
+        let secret = std::env::var(JWT_SECRET_ENV)
+            .ok()
+            .or_else(|| config.jwt_secret.clone())
+            .filter(|secret| !secret.is_empty());
+        let jwks = config.jwks_url.as_ref().map(|url| JwksSource {
+            url: url.clone(),
+            refresh: config.jwks_refresh_secs.map(Duration::from_secs).unwrap_or(DEFAULT_JWKS_REFRESH),
+            client: reqwest::Client::new(),
+            cache: RwLock::new(None),
+        });
+
+        let authenticator = Self {
+            secret: secret.map(|secret| DecodingKey::from_secret(secret.as_bytes())),
+            jwks,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+            leeway: config.leeway_secs,
         };
+        for route in routes {
+            authenticator.ensure_keys(&format!("Route {}", route.path), &route.auth)?;
+        }
+        Ok(authenticator)
+    }
 
-        debug!("OAuth2 authentication successful for user: {}", user.id);
-        Ok(user)
+    /// Authenticate a request for a route
+    ///
+    /// Returns the validated claims, or `None` when the route does not need a token
+    /// and none was sent.
+    pub async fn authenticate(
+        &self,
+        route: &RouteConfig,
+        headers: &HeaderMap,
+    ) -> Result<Option<GatewayClaims>, AuthRejection> {
+        self.authorize(&route.path, &route.auth, headers).await
     }
 
-    /// Generate a JWT token for a user
-    pub async fn generate_token(&self, user: &User) -> Result<String> {
-        debug!("Generating JWT token for user: {}", user.id);
-
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let exp = now + self.jwt_config.expiration;
-
-        let claims = Claims {
-            sub: user.id.clone(),
-            iss: self.jwt_config.issuer.clone(),
-            aud: self.jwt_config.audience.clone(),
-            exp,
-            iat: now,
-            roles: user.roles.clone(),
-            permissions: user.permissions.clone(),
+    /// Authenticate a request against an explicit requirement
+    ///
+    /// `target` only names the request in logs.
+    pub async fn authorize(
+        &self,
+        target: &str,
+        auth: &RouteAuthConfig,
+        headers: &HeaderMap,
+    ) -> Result<Option<GatewayClaims>, AuthRejection> {
+        let mode = effective_mode(auth);
+        if mode == AuthMode::None {
+            return Ok(None);
+        }
+
+        let token = match bearer_token(headers) {
+            Some(token) => token,
+            None if mode == AuthMode::Optional => return Ok(None),
+            None => return Err(AuthRejection::Missing),
         };
+        let claims = self.validate(token).await?;
 
-        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
-            .map_err(|e| ApiGatewayError::Auth(format!("Failed to generate JWT token: {}", e)))?;
+        let roles = &auth.roles;
+        if !roles.is_empty() && !roles.iter().any(|role| claims.roles.contains(role)) {
+            debug!("User {} lacks roles {:?} for {}", claims.sub, roles, target);
+            return Err(AuthRejection::Forbidden);
+        }
+        Ok(Some(claims))
+    }
 
-        debug!("JWT token generated successfully");
-        Ok(token)
+    /// Check that a requirement can be enforced with the configured keys
+    pub fn ensure_keys(&self, target: &str, auth: &RouteAuthConfig) -> Result<(), String> {
+        if self.secret.is_none() && self.jwks.is_none() && effective_mode(auth) != AuthMode::None {
+            return Err(format!(
+                "{} needs token validation but neither auth.jwt_secret nor auth.jwks_url is set",
+                target
+            ));
+        }
+        Ok(())
     }
 
-    /// Generate a refresh token
-    pub async fn generate_refresh_token(&self, user: &User) -> Result<String> {
-        debug!("Generating refresh token for user: {}", user.id);
-
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        let exp = now + self.jwt_config.refresh_expiration;
-
-        let claims = Claims {
-            sub: user.id.clone(),
-            iss: self.jwt_config.issuer.clone(),
-            aud: self.jwt_config.audience.clone(),
-            exp,
-            iat: now,
-            roles: user.roles.clone(),
-            permissions: user.permissions.clone(),
+    /// Validate a token's signature and standard claims
+    pub async fn validate(&self, token: &str) -> Result<GatewayClaims, AuthRejection> {
+        let header = decode_header(token).map_err(|e| AuthRejection::Invalid(e.to_string()))?;
+        let key = match header.alg {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => self
+                .secret
+                .clone()
+                .ok_or_else(|| AuthRejection::Invalid("HMAC tokens are not accepted".to_string()))?,
+            _ => match &self.jwks {
+                Some(jwks) => jwks.key(header.kid.as_deref()).await?,
+                None => return Err(AuthRejection::Invalid("Asymmetric tokens are not accepted".to_string())),
+            },
         };
 
-        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
-            .map_err(|e| ApiGatewayError::Auth(format!("Failed to generate refresh token: {}", e)))?;
-
-        debug!("Refresh token generated successfully");
-        Ok(token)
-    }
-
-    /// Authorize a user for a specific action
-    pub async fn authorize(&self, user: &User, required_roles: &[String], required_permissions: &[String]) -> Result<bool> {
-        debug!("Authorizing user: {} for roles: {:?}, permissions: {:?}", 
-               user.id, required_roles, required_permissions);
-
-        // Check roles
-        if !required_roles.is_empty() {
-            let has_role = required_roles.iter().any(|role| user.roles.contains(role));
-            if !has_role {
-                warn!("User {} lacks required roles: {:?}", user.id, required_roles);
-                return Ok(false);
-            }
+        let mut validation = Validation::new(header.alg);
+        validation.leeway = self.leeway;
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
         }
-
-        // Check permissions
-        if !required_permissions.is_empty() {
-            let has_permission = required_permissions.iter().any(|permission| user.permissions.contains(permission));
-            if !has_permission {
-                warn!("User {} lacks required permissions: {:?}", user.id, required_permissions);
-                return Ok(false);
-            }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
         }
 
-        debug!("Authorization successful for user: {}", user.id);
-        Ok(true)
+        decode::<GatewayClaims>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                ErrorKind::ExpiredSignature => AuthRejection::Expired,
+                _ => AuthRejection::Invalid(e.to_string()),
+            })
     }
+}
 
-    /// Validate a JWT token
-    pub async fn validate_token(&self, token: &str) -> Result<Claims> {
-        debug!("Validating JWT token");
+/// Route mode, treating a role requirement as `required`
+fn effective_mode(auth: &RouteAuthConfig) -> AuthMode {
+    if auth.roles.is_empty() {
+        auth.mode
+    } else {
+        AuthMode::Required
+    }
+}
 
-        let token_data = decode::<Claims>(token, &self.decoding_key, &self.validation)
-            .map_err(|e| ApiGatewayError::Auth(format!("Invalid JWT token: {}", e)))?;
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+}
 
-        let claims = token_data.claims;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
 
-        // Check if token is expired
-        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
-        if claims.exp < now {
-            return Err(ApiGatewayError::Auth("Token expired".to_string()));
-        }
+    const SECRET: &str = "gateway-test-secret";
 
-        debug!("JWT token validation successful");
-        Ok(claims)
+    fn authenticator() -> JwtAuthenticator {
+        let config = AuthConfig {
+            jwt_secret: Some(SECRET.to_string()),
+            ..AuthConfig::default()
+        };
+        JwtAuthenticator::from_config(&config, &[]).unwrap()
     }
 
-    /// Extract user from request context
-    pub async fn extract_user_from_context(&self, context: &RequestContext) -> Result<Option<User>> {
-        // Try to extract from Authorization header
-        if let Some(auth_header) = context.headers.get("Authorization") {
-            if let Some(token) = auth_header.strip_prefix("Bearer ") {
-                return Ok(Some(self.authenticate_jwt(token).await?));
-            }
-        }
-
-        // Try to extract from API key header
-        if let Some(api_key) = context.headers.get(&self.api_keys_config.header_name) {
-            let _api_key_info = self.authenticate_api_key(api_key).await?;
-            // TODO: Convert API key info to User
-            return Ok(None);
+    fn route(mode: AuthMode, roles: &[&str]) -> RouteConfig {
+        RouteConfig {
+            path: "/api/*".to_string(),
+            service: "chaos-backend".to_string(),
+            methods: vec!["GET".to_string()],
+            strip_prefix: true,
+            add_headers: None,
+            rate_limit: None,
+            auth: RouteAuthConfig {
+                mode,
+                roles: roles.iter().map(|role| role.to_string()).collect(),
+            },
+            retry: None,
+            websocket: None,
+            cache: None,
         }
+    }
 
-        Ok(None)
+    fn headers(roles: &[&str], exp_offset: i64) -> HeaderMap {
+        let claims = GatewayClaims {
+            sub: "user-1".to_string(),
+            exp: (chrono::Utc::now().timestamp() + exp_offset) as u64,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        };
+        let token = encode(&Header::default(), &claims, &EncodingKey::from_secret(SECRET.as_bytes())).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", token).parse().unwrap());
+        headers
     }
 
-    /// Check if user has required role
-    pub fn has_role(&self, user: &User, role: &str) -> bool {
-        user.roles.contains(&role.to_string())
+    #[tokio::test]
+    async fn test_required_route_rejects_missing_and_expired_tokens() {
+        let auth = authenticator();
+        let route = route(AuthMode::Required, &[]);
+
+        let rejection = auth.authenticate(&route, &HeaderMap::new()).await.unwrap_err();
+        assert_eq!(rejection, AuthRejection::Missing);
+        assert_eq!(rejection.into_response().status(), StatusCode::UNAUTHORIZED);
+
+        let rejection = auth.authenticate(&route, &headers(&[], -3600)).await.unwrap_err();
+        assert_eq!(rejection, AuthRejection::Expired);
+
+        let mut forged = headers(&[], 3600);
+        let token = forged[header::AUTHORIZATION].to_str().unwrap().to_string();
+        forged.insert(header::AUTHORIZATION, format!("{}x", token).parse().unwrap());
+        assert!(matches!(
+            auth.authenticate(&route, &forged).await,
+            Err(AuthRejection::Invalid(_))
+        ));
     }
 
-    /// Check if user has required permission
-    pub fn has_permission(&self, user: &User, permission: &str) -> bool {
-        user.permissions.contains(&permission.to_string())
+    #[tokio::test]
+    async fn test_valid_token_yields_forwarded_claims() {
+        let auth = authenticator();
+        let claims = auth
+            .authenticate(&route(AuthMode::Required, &[]), &headers(&["player", "gm"], 3600))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            claims.forwarded_headers(),
+            vec![(USER_ID_HEADER, "user-1".to_string()), (USER_ROLES_HEADER, "player,gm".to_string())]
+        );
     }
 
-    /// Get user roles
-    pub fn get_user_roles<'a>(&self, user: &'a User) -> &'a [String] {
-        &user.roles
+    #[tokio::test]
+    async fn test_roles_and_optional_routes() {
+        let auth = authenticator();
+        let admin = route(AuthMode::None, &["admin"]);
+        let rejection = auth.authenticate(&admin, &headers(&["player"], 3600)).await.unwrap_err();
+        assert_eq!(rejection, AuthRejection::Forbidden);
+        assert_eq!(rejection.into_response().status(), StatusCode::FORBIDDEN);
+        assert_eq!(auth.authenticate(&admin, &HeaderMap::new()).await, Err(AuthRejection::Missing));
+
+        let optional = route(AuthMode::Optional, &[]);
+        assert_eq!(auth.authenticate(&optional, &HeaderMap::new()).await, Ok(None));
+        assert!(auth.authenticate(&optional, &headers(&[], 3600)).await.unwrap().is_some());
     }
 
-    /// Get user permissions
-    pub fn get_user_permissions<'a>(&self, user: &'a User) -> &'a [String] {
-        &user.permissions
+    #[test]
+    fn test_protected_routes_need_keys() {
+        let routes = [route(AuthMode::Required, &[])];
+        assert!(JwtAuthenticator::from_config(&AuthConfig::default(), &routes).is_err());
+        assert!(is_trusted_header("X-User-Id"));
+        assert!(is_trusted_header("X-Forwarded-For"));
+        assert!(!is_trusted_header("x-request-id"));
     }
 }
-
-
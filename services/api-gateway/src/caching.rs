@@ -1,355 +1,491 @@
-//! Caching service for API Gateway
-
-use crate::config::{Config, CachingConfig, TtlConfig};
-use crate::errors::{ApiGatewayError, Result};
-use crate::types::CacheEntry;
-use redis::Client as RedisClient;
-use redis::Commands;
+//! Response caching for idempotent routes.
+//!
+//! `GET` responses of routes with a `cache` section are kept in memory and, when
+//! `cache.redis_url` is set, in Redis so every gateway replica shares them. Entries are
+//! keyed on path, query and the caller's auth scope. Clients skip the cache with
+//! `Cache-Control: no-cache` / `no-store` or the `x-cache-bypass` header, and the CMS
+//! drops stale entries through `POST /cache/invalidate` after publishing. Draft previews
+//! (`x-chaos-preview`) neither read nor fill the cache.
+
+use crate::auth::GatewayClaims;
+use crate::config::{AuthMode, CacheConfig, CacheScope, RouteAuthConfig};
+use crate::state::GatewayState;
+use axum::body::{Body, Bytes};
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use moka::future::Cache;
+use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use shared::preview::PREVIEW_HEADER;
 use std::sync::Arc;
-use std::time::{SystemTime, Duration};
-use tracing::{info, debug, warn, error};
-
-/// Cache service
-#[derive(Debug, Clone)]
-pub struct CacheService {
-    config: CachingConfig,
-    redis_client: Arc<RedisClient>,
-    ttl_config: TtlConfig,
-    local_cache: Arc<tokio::sync::RwLock<HashMap<String, CacheEntry<String>>>>,
-}
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::OnceCell;
+use tracing::{debug, info, warn};
 
-/// Cache key types
-#[derive(Debug, Clone, PartialEq)]
-pub enum CacheKeyType {
-    Auth,
-    User,
-    Game,
-    Inventory,
-    Chat,
-    Guild,
-    World,
-    Matchmaking,
-    Events,
-    Content,
-    Notifications,
-    Payments,
-    AntiCheat,
-    Analytics,
-    Default,
-}
+/// Request header that skips the cache
+pub const CACHE_BYPASS_HEADER: &str = "x-cache-bypass";
 
-/// Initialize cache service
-pub async fn init(config: &Config) -> Result<CacheService> {
-    CacheService::new(config.clone())
-}
+/// Response header telling whether the cache was used (`HIT`, `MISS` or `BYPASS`)
+pub const CACHE_STATUS_HEADER: &str = "x-cache";
 
-impl CacheService {
-    /// Create a new cache service
-    pub fn new(config: Config) -> Result<Self> {
-        let caching_config = config.caching.clone();
-        let ttl_config = config.caching.ttl.clone();
-        
-        // Create Redis client
-        let redis_url = format!("redis://{}:{}", 
-            caching_config.redis.host, 
-            caching_config.redis.port
-        );
-        
-        let redis_client = RedisClient::open(redis_url)
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to connect to Redis: {}", e)))?;
+/// Keys scanned per Redis `SCAN` call during invalidation
+const SCAN_BATCH: usize = 500;
 
-        Ok(Self {
-            config: caching_config,
-            redis_client: Arc::new(redis_client),
-            ttl_config,
-            local_cache: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-        })
-    }
+/// Cached upstream response
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    /// Upstream status
+    pub status: u16,
+    /// Upstream headers
+    pub headers: Vec<(String, String)>,
+    /// Upstream body
+    pub body: Bytes,
+    /// When the entry stops being served
+    pub expires_at: SystemTime,
+}
 
-    /// Get a value from cache
-    pub async fn get<T>(&self, key: &str, key_type: CacheKeyType) -> Result<Option<T>>
-    where
-        T: for<'de> Deserialize<'de> + Clone,
-    {
-        debug!("Getting cache value for key: {} with type: {:?}", key, key_type);
-
-        // Try local cache first
-        if let Some(value) = self.get_from_local_cache(key).await? {
-            debug!("Cache hit in local cache for key: {}", key);
-            return Ok(Some(value));
-        }
+#[derive(Serialize, Deserialize)]
+struct StoredMeta {
+    status: u16,
+    headers: Vec<(String, String)>,
+    expires_at_ms: u64,
+}
 
-        // Try Redis cache
-        if self.config.enabled {
-            if let Some(value) = self.get_from_redis_cache(key).await? {
-                debug!("Cache hit in Redis for key: {}", key);
-                return Ok(Some(value));
-            }
+impl CachedResponse {
+    /// Cache a response for `ttl`
+    pub fn new(status: u16, headers: Vec<(String, String)>, body: Bytes, ttl: Duration) -> Self {
+        Self {
+            status,
+            headers,
+            body,
+            expires_at: SystemTime::now() + ttl,
         }
-
-        debug!("Cache miss for key: {}", key);
-        Ok(None)
     }
 
-    /// Set a value in cache
-    pub async fn set<T>(&self, key: &str, value: T, key_type: CacheKeyType) -> Result<()>
-    where
-        T: Serialize + Clone,
-    {
-        debug!("Setting cache value for key: {} with type: {:?}", key, key_type);
-
-        let ttl = self.get_ttl_for_key_type(&key_type);
-        
-        // Set in local cache
-        self.set_in_local_cache(key, value.clone(), ttl).await?;
-
-        // Set in Redis cache if enabled
-        if self.config.enabled && ttl > 0 {
-            self.set_in_redis_cache(key, value, ttl).await?;
-        }
-
-        debug!("Cache value set successfully for key: {}", key);
-        Ok(())
+    /// Whether the entry may still be served
+    pub fn is_fresh(&self) -> bool {
+        self.expires_at > SystemTime::now()
     }
 
-    /// Delete a value from cache
-    pub async fn delete(&self, key: &str) -> Result<()> {
-        debug!("Deleting cache value for key: {}", key);
-
-        // Delete from local cache
-        self.delete_from_local_cache(key).await?;
-
-        // Delete from Redis cache if enabled
-        if self.config.enabled {
-            self.delete_from_redis_cache(key).await?;
+    /// Build the response sent to the client
+    pub fn to_response(&self, cache_status: &'static str) -> Response {
+        let mut builder = Response::builder().status(StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK));
+        for (key, value) in &self.headers {
+            builder = builder.header(key.as_str(), value.as_str());
         }
+        builder
+            .header(CACHE_STATUS_HEADER, cache_status)
+            .body(Body::from(self.body.clone()))
+            .unwrap()
+    }
 
-        debug!("Cache value deleted successfully for key: {}", key);
-        Ok(())
+    /// Redis value: big-endian length of the JSON metadata, the metadata, then the body
+    fn encode(&self) -> Vec<u8> {
+        let meta = StoredMeta {
+            status: self.status,
+            headers: self.headers.clone(),
+            expires_at_ms: self
+                .expires_at
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_millis() as u64),
+        };
+        let meta = serde_json::to_vec(&meta).unwrap_or_default();
+        let mut encoded = Vec::with_capacity(4 + meta.len() + self.body.len());
+        encoded.extend_from_slice(&(meta.len() as u32).to_be_bytes());
+        encoded.extend_from_slice(&meta);
+        encoded.extend_from_slice(&self.body);
+        encoded
     }
 
-    /// Clear all cache
-    pub async fn clear(&self) -> Result<()> {
-        debug!("Clearing all cache");
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let len = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+        let meta: StoredMeta = serde_json::from_slice(bytes.get(4..4 + len)?).ok()?;
+        Some(Self {
+            status: meta.status,
+            headers: meta.headers,
+            body: Bytes::copy_from_slice(&bytes[4 + len..]),
+            expires_at: UNIX_EPOCH + Duration::from_millis(meta.expires_at_ms),
+        })
+    }
+}
 
-        // Clear local cache
-        self.clear_local_cache().await?;
+/// Body of `POST /cache/invalidate`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InvalidationRequest {
+    /// Paths whose entries are dropped, for every query and scope
+    #[serde(default)]
+    pub paths: Vec<String>,
+    /// Path prefixes whose entries are dropped
+    #[serde(default)]
+    pub prefixes: Vec<String>,
+}
 
-        // Clear Redis cache if enabled
-        if self.config.enabled {
-            self.clear_redis_cache().await?;
-        }
+/// Two-tier response cache
+pub struct ResponseCache {
+    memory: Cache<String, Arc<CachedResponse>>,
+    redis: Option<RedisTier>,
+    max_body_bytes: usize,
+}
 
-        info!("All cache cleared successfully");
-        Ok(())
+impl std::fmt::Debug for ResponseCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResponseCache")
+            .field("entries", &self.memory.entry_count())
+            .field("redis", &self.redis.as_ref().map(|redis| &redis.key_prefix))
+            .field("max_body_bytes", &self.max_body_bytes)
+            .finish()
     }
+}
 
-    /// Get cache statistics
-    pub async fn get_stats(&self) -> Result<CacheStats> {
-        let local_cache = self.local_cache.read().await;
-        let local_size = local_cache.len();
-
-        let mut redis_size = 0;
-        if self.config.enabled {
-            let mut conn = self.redis_client.get_connection()
-                .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis connection: {}", e)))?;
-            
-            redis_size = redis::cmd("DBSIZE").query_async::<_, u64>(&mut conn).await
-                .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis size: {}", e)))?;
-        }
-
-        Ok(CacheStats {
-            local_cache_size: local_size,
-            redis_cache_size: redis_size,
-            total_size: local_size + redis_size as usize,
+impl ResponseCache {
+    /// Create the cache from configuration
+    pub fn from_config(config: &CacheConfig) -> Result<Self, String> {
+        let redis = match &config.redis_url {
+            Some(url) => Some(RedisTier {
+                client: redis::Client::open(url.as_str()).map_err(|e| format!("Invalid cache.redis_url: {}", e))?,
+                connection: OnceCell::new(),
+                key_prefix: config.key_prefix.clone(),
+            }),
+            None => None,
+        };
+        Ok(Self {
+            memory: Cache::builder()
+                .max_capacity(config.max_entries)
+                .support_invalidation_closures()
+                .build(),
+            redis,
+            max_body_bytes: config.max_body_bytes,
         })
     }
 
-    /// Get value from local cache
-    async fn get_from_local_cache<T>(&self, key: &str) -> Result<Option<T>>
-    where
-        T: for<'de> Deserialize<'de>,
-    {
-        let local_cache = self.local_cache.read().await;
-        
-        if let Some(entry) = local_cache.get(key) {
-            // Check if entry is expired
-            if entry.expires_at > SystemTime::now() {
-                let value: T = serde_json::from_str(&entry.value)
-                    .map_err(|e| ApiGatewayError::Serialization(format!("Failed to deserialize cache value: {}", e)))?;
-                return Ok(Some(value));
-            } else {
-                // Entry is expired, remove it
-                drop(local_cache);
-                self.delete_from_local_cache(key).await?;
+    /// Cache key of a request
+    pub fn key(path: &str, query: Option<&str>, scope: CacheScope, claims: Option<&GatewayClaims>) -> String {
+        let scope = match (scope, claims) {
+            (CacheScope::Public, _) | (_, None) => "public".to_string(),
+            (CacheScope::User, Some(claims)) => format!("user:{}", claims.sub),
+            (CacheScope::Roles, Some(claims)) => {
+                let mut roles = claims.roles.clone();
+                roles.sort();
+                format!("roles:{}", roles.join(","))
             }
-        }
-
-        Ok(None)
+        };
+        format!("{}?{}#{}", path, query.unwrap_or_default(), scope)
     }
 
-    /// Set value in local cache
-    async fn set_in_local_cache<T>(&self, key: &str, value: T, ttl: u64) -> Result<()>
-    where
-        T: Serialize,
-    {
-        let serialized_value = serde_json::to_string(&value)
-            .map_err(|e| ApiGatewayError::Serialization(format!("Failed to serialize cache value: {}", e)))?;
-
-        let now = SystemTime::now();
-        let expires_at = now + Duration::from_secs(ttl);
-
-        let entry = CacheEntry {
-            key: key.to_string(),
-            value: serialized_value,
-            ttl,
-            created_at: now,
-            expires_at,
-        };
+    /// Whether the client asked to skip the cache
+    pub fn is_bypass(headers: &HeaderMap) -> bool {
+        if headers.contains_key(CACHE_BYPASS_HEADER) || Self::is_preview(headers) {
+            return true;
+        }
+        headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| value.contains("no-cache") || value.contains("no-store"))
+    }
 
-        let mut local_cache = self.local_cache.write().await;
-        local_cache.insert(key.to_string(), entry);
+    /// Whether the client asked for the response not to be stored
+    pub fn is_no_store(headers: &HeaderMap) -> bool {
+        headers
+            .get_all(header::CACHE_CONTROL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .any(|value| value.contains("no-store"))
+    }
 
-        Ok(())
+    /// Whether the request previews draft content, which must never be cached
+    pub fn is_preview(headers: &HeaderMap) -> bool {
+        headers.contains_key(PREVIEW_HEADER)
     }
 
-    /// Delete value from local cache
-    async fn delete_from_local_cache(&self, key: &str) -> Result<()> {
-        let mut local_cache = self.local_cache.write().await;
-        local_cache.remove(key);
-        Ok(())
+    /// Whether an upstream response may be stored
+    ///
+    /// Responses setting cookies are never stored, replaying them would hand one caller's
+    /// session to everyone else.
+    pub fn is_storable(&self, status: u16, headers: &[(String, String)], body_len: usize) -> bool {
+        let no_store = headers.iter().any(|(key, value)| {
+            key.eq_ignore_ascii_case(header::SET_COOKIE.as_str())
+                || (key.eq_ignore_ascii_case(header::CACHE_CONTROL.as_str())
+                    && (value.contains("no-store") || value.contains("private")))
+        });
+        status == 200 && !no_store && body_len <= self.max_body_bytes
     }
 
-    /// Clear local cache
-    async fn clear_local_cache(&self) -> Result<()> {
-        let mut local_cache = self.local_cache.write().await;
-        local_cache.clear();
-        Ok(())
+    /// Fresh entry of a key
+    pub async fn get(&self, key: &str) -> Option<Arc<CachedResponse>> {
+        if let Some(entry) = self.memory.get(key).await {
+            if entry.is_fresh() {
+                return Some(entry);
+            }
+            self.memory.invalidate(key).await;
+        }
+        let entry = Arc::new(self.redis.as_ref()?.get(key).await?);
+        if !entry.is_fresh() {
+            return None;
+        }
+        self.memory.insert(key.to_string(), entry.clone()).await;
+        Some(entry)
     }
 
-    /// Get value from Redis cache
-    async fn get_from_redis_cache<T>(&self, key: &str) -> Result<Option<T>>
-    where
-        T: for<'de> Deserialize<'de>,
-    {
-        let mut conn = self.redis_client.get_connection()
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis connection: {}", e)))?;
-
-        let value: Option<String> = conn.get(key)
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis value: {}", e)))?;
-
-        if let Some(value) = value {
-            let deserialized: T = serde_json::from_str(&value)
-                .map_err(|e| ApiGatewayError::Serialization(format!("Failed to deserialize Redis value: {}", e)))?;
-            Ok(Some(deserialized))
-        } else {
-            Ok(None)
+    /// Store an entry in every tier
+    pub async fn put(&self, key: String, response: CachedResponse) {
+        if let Some(redis) = &self.redis {
+            redis.put(&key, &response).await;
         }
+        self.memory.insert(key, Arc::new(response)).await;
     }
 
-    /// Set value in Redis cache
-    async fn set_in_redis_cache<T>(&self, key: &str, value: T, ttl: u64) -> Result<()>
-    where
-        T: Serialize,
-    {
-        let mut conn = self.redis_client.get_connection()
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis connection: {}", e)))?;
-
-        let serialized_value = serde_json::to_string(&value)
-            .map_err(|e| ApiGatewayError::Serialization(format!("Failed to serialize Redis value: {}", e)))?;
-
-        if ttl > 0 {
-            let _: () = conn.set_ex(key, serialized_value, ttl as u64)
-                .map_err(|e| ApiGatewayError::Database(format!("Failed to set Redis value with TTL: {}", e)))?;
-        } else {
-            let _: () = conn.set(key, serialized_value)
-                .map_err(|e| ApiGatewayError::Database(format!("Failed to set Redis value: {}", e)))?;
+    /// Drop entries of the given paths and prefixes
+    pub async fn invalidate(&self, request: &InvalidationRequest) {
+        let prefixes: Vec<String> = request
+            .paths
+            .iter()
+            .map(|path| format!("{}?", path))
+            .chain(request.prefixes.iter().cloned())
+            .collect();
+        if prefixes.is_empty() {
+            return;
         }
 
-        Ok(())
+        let memory_prefixes = prefixes.clone();
+        if let Err(e) = self
+            .memory
+            .invalidate_entries_if(move |key, _| memory_prefixes.iter().any(|prefix| key.starts_with(prefix)))
+        {
+            warn!("In-memory cache invalidation failed: {}", e);
+        }
+        if let Some(redis) = &self.redis {
+            for prefix in &prefixes {
+                redis.invalidate_prefix(prefix).await;
+            }
+        }
     }
+}
 
-    /// Delete value from Redis cache
-    async fn delete_from_redis_cache(&self, key: &str) -> Result<()> {
-        let mut conn = self.redis_client.get_connection()
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis connection: {}", e)))?;
+/// `POST /cache/invalidate`, for the CMS after publishing content
+///
+/// Needs a token with one of `cache.invalidation_roles`.
+pub async fn invalidate_handler(
+    State(state): State<GatewayState>,
+    headers: HeaderMap,
+    Json(request): Json<InvalidationRequest>,
+) -> Response {
+    let requirement = RouteAuthConfig {
+        mode: AuthMode::Required,
+        roles: state.config.cache.invalidation_roles.clone(),
+    };
+    let claims = match state.auth.authorize("/cache/invalidate", &requirement, &headers).await {
+        Ok(claims) => claims,
+        Err(rejection) => {
+            warn!("❌ Rejected cache invalidation: {:?}", rejection);
+            return rejection.into_response();
+        }
+    };
+
+    info!(
+        "🧹 Cache invalidation by {}: paths {:?}, prefixes {:?}",
+        claims.map_or_else(|| "(unknown)".to_string(), |claims| claims.sub),
+        request.paths,
+        request.prefixes
+    );
+    state.cache.invalidate(&request).await;
+    StatusCode::NO_CONTENT.into_response()
+}
 
-        let _: () = conn.del(key)
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to delete Redis value: {}", e)))?;
+/// Shared Redis tier; errors are logged and treated as misses
+struct RedisTier {
+    client: redis::Client,
+    connection: OnceCell<redis::aio::MultiplexedConnection>,
+    key_prefix: String,
+}
 
-        Ok(())
+impl RedisTier {
+    async fn connection(&self) -> Option<redis::aio::MultiplexedConnection> {
+        self.connection
+            .get_or_try_init(|| self.client.get_multiplexed_tokio_connection())
+            .await
+            .map_err(|e| warn!("Redis cache unavailable: {}", e))
+            .ok()
+            .cloned()
     }
 
-    /// Clear Redis cache
-    async fn clear_redis_cache(&self) -> Result<()> {
-        let mut conn = self.redis_client.get_connection()
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to get Redis connection: {}", e)))?;
-
-        let _: () = redis::cmd("FLUSHDB").query_async(&mut conn).await
-            .map_err(|e| ApiGatewayError::Database(format!("Failed to clear Redis cache: {}", e)))?;
+    async fn get(&self, key: &str) -> Option<CachedResponse> {
+        let mut connection = self.connection().await?;
+        let value: Option<Vec<u8>> = connection
+            .get(format!("{}{}", self.key_prefix, key))
+            .await
+            .map_err(|e| debug!("Redis cache read failed: {}", e))
+            .ok()?;
+        CachedResponse::decode(&value?)
+    }
 
-        Ok(())
+    async fn put(&self, key: &str, response: &CachedResponse) {
+        let ttl = response
+            .expires_at
+            .duration_since(SystemTime::now())
+            .map_or(0, |ttl| ttl.as_secs().max(1));
+        if ttl == 0 {
+            return;
+        }
+        let Some(mut connection) = self.connection().await else {
+            return;
+        };
+        let result: redis::RedisResult<()> = connection
+            .set_ex(format!("{}{}", self.key_prefix, key), response.encode(), ttl)
+            .await;
+        if let Err(e) = result {
+            debug!("Redis cache write failed: {}", e);
+        }
     }
 
-    /// Get TTL for key type
-    fn get_ttl_for_key_type(&self, key_type: &CacheKeyType) -> u64 {
-        match key_type {
-            CacheKeyType::Auth => self.ttl_config.auth,
-            CacheKeyType::User => self.ttl_config.user,
-            CacheKeyType::Game => self.ttl_config.game,
-            CacheKeyType::Inventory => self.ttl_config.inventory,
-            CacheKeyType::Chat => self.ttl_config.chat,
-            CacheKeyType::Guild => self.ttl_config.guild,
-            CacheKeyType::World => self.ttl_config.world,
-            CacheKeyType::Matchmaking => self.ttl_config.matchmaking,
-            CacheKeyType::Events => self.ttl_config.events,
-            CacheKeyType::Content => self.ttl_config.content,
-            CacheKeyType::Notifications => self.ttl_config.notifications,
-            CacheKeyType::Payments => self.ttl_config.payments,
-            CacheKeyType::AntiCheat => self.ttl_config.anti_cheat,
-            CacheKeyType::Analytics => self.ttl_config.analytics,
-            CacheKeyType::Default => self.ttl_config.default,
+    async fn invalidate_prefix(&self, prefix: &str) {
+        let Some(mut connection) = self.connection().await else {
+            return;
+        };
+        let pattern = format!("{}*", escape_glob(&format!("{}{}", self.key_prefix, prefix)));
+        let mut cursor: u64 = 0;
+        loop {
+            let scanned: redis::RedisResult<(u64, Vec<String>)> = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_BATCH)
+                .query_async(&mut connection)
+                .await;
+            let (next, keys) = match scanned {
+                Ok(scanned) => scanned,
+                Err(e) => {
+                    warn!("Redis cache invalidation of {} failed: {}", prefix, e);
+                    return;
+                }
+            };
+            if !keys.is_empty() {
+                let deleted: redis::RedisResult<()> = connection.del(keys).await;
+                if let Err(e) = deleted {
+                    warn!("Redis cache invalidation of {} failed: {}", prefix, e);
+                    return;
+                }
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
         }
     }
+}
 
-    /// Create cache key with prefix
-    pub fn create_cache_key(&self, prefix: &str, key: &str) -> String {
-        format!("{}:{}", prefix, key)
+/// Escape Redis glob metacharacters
+fn escape_glob(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
     }
+    escaped
+}
 
-    /// Check if cache is enabled
-    pub fn is_enabled(&self) -> bool {
-        self.config.enabled
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(sub: &str, roles: &[&str]) -> GatewayClaims {
+        GatewayClaims {
+            sub: sub.to_string(),
+            exp: 0,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        }
     }
 
-    /// Get cache configuration
-    pub fn get_config(&self) -> &CachingConfig {
-        &self.config
+    fn cache() -> ResponseCache {
+        ResponseCache::from_config(&CacheConfig::default()).unwrap()
     }
-}
 
-/// Cache statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CacheStats {
-    pub local_cache_size: usize,
-    pub redis_cache_size: u64,
-    pub total_size: usize,
-}
+    #[test]
+    fn test_keys_follow_scope() {
+        let alice = claims("alice", &["player", "gm"]);
+        let bob = claims("bob", &["gm", "player"]);
 
-impl CacheStats {
-    /// Get cache hit rate
-    pub fn get_hit_rate(&self) -> f64 {
-        // TODO: Implement actual hit rate calculation
-        0.0
+        assert_eq!(
+            ResponseCache::key("/api/news", Some("page=2"), CacheScope::User, Some(&alice)),
+            "/api/news?page=2#user:alice"
+        );
+        assert_eq!(
+            ResponseCache::key("/api/news", None, CacheScope::User, None),
+            "/api/news?#public"
+        );
+        assert_eq!(
+            ResponseCache::key("/api/news", None, CacheScope::Roles, Some(&alice)),
+            ResponseCache::key("/api/news", None, CacheScope::Roles, Some(&bob))
+        );
+        assert_eq!(
+            ResponseCache::key("/api/news", None, CacheScope::Public, Some(&alice)),
+            "/api/news?#public"
+        );
     }
 
-    /// Get memory usage
-    pub fn get_memory_usage(&self) -> u64 {
-        // TODO: Implement actual memory usage calculation
-        0
+    #[test]
+    fn test_bypass_and_storability() {
+        let mut headers = HeaderMap::new();
+        assert!(!ResponseCache::is_bypass(&headers));
+        headers.insert(header::CACHE_CONTROL, "no-cache".parse().unwrap());
+        assert!(ResponseCache::is_bypass(&headers));
+        assert!(!ResponseCache::is_no_store(&headers));
+        headers.insert(header::CACHE_CONTROL, "no-store".parse().unwrap());
+        assert!(ResponseCache::is_no_store(&headers));
+        let mut preview_headers = HeaderMap::new();
+        preview_headers.insert(PREVIEW_HEADER, "pv_editor".parse().unwrap());
+        assert!(ResponseCache::is_bypass(&preview_headers));
+        assert!(ResponseCache::is_preview(&preview_headers));
+        assert!(!ResponseCache::is_preview(&headers));
+
+        let cache = cache();
+        assert!(cache.is_storable(200, &[], 10));
+        assert!(!cache.is_storable(404, &[], 10));
+        assert!(!cache.is_storable(200, &[("Cache-Control".to_string(), "private".to_string())], 10));
+        assert!(!cache.is_storable(200, &[("Set-Cookie".to_string(), "session=abc".to_string())], 10));
+        assert!(!cache.is_storable(200, &[], 2 * 1024 * 1024));
     }
-}
 
+    #[test]
+    fn test_stored_entries_round_trip() {
+        let response = CachedResponse::new(
+            200,
+            vec![("content-type".to_string(), "application/json".to_string())],
+            Bytes::from_static(b"{\"news\":[]}"),
+            Duration::from_secs(60),
+        );
+        let decoded = CachedResponse::decode(&response.encode()).unwrap();
+        assert_eq!(decoded.body, response.body);
+        assert_eq!(decoded.headers, response.headers);
+        assert!(decoded.is_fresh());
+        assert!(CachedResponse::decode(&[0, 0]).is_none());
+        assert_eq!(escape_glob("/api/[x]*"), "/api/\\[x\\]\\*");
+    }
 
+    #[tokio::test]
+    async fn test_invalidation_by_path_and_prefix() {
+        let cache = cache();
+        let entry = || CachedResponse::new(200, Vec::new(), Bytes::from_static(b"ok"), Duration::from_secs(60));
+        cache.put("/api/news?#public".to_string(), entry()).await;
+        cache.put("/api/news?page=2#user:alice".to_string(), entry()).await;
+        cache.put("/api/newsletter?#public".to_string(), entry()).await;
+        cache.put("/api/content/items/1?#public".to_string(), entry()).await;
+
+        cache
+            .invalidate(&InvalidationRequest {
+                paths: vec!["/api/news".to_string()],
+                prefixes: vec!["/api/content/".to_string()],
+            })
+            .await;
+
+        assert!(cache.get("/api/news?#public").await.is_none());
+        assert!(cache.get("/api/news?page=2#user:alice").await.is_none());
+        assert!(cache.get("/api/newsletter?#public").await.is_some());
+        assert!(cache.get("/api/content/items/1?#public").await.is_none());
+    }
+}
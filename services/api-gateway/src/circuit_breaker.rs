@@ -0,0 +1,394 @@
+//! Per-service circuit breakers.
+//!
+//! A breaker counts upstream outcomes in fixed windows. Once enough requests have
+//! been seen and the failure rate reaches the threshold it opens and the gateway
+//! answers `503` without calling the service. After the open period a limited number
+//! of probes are let through; the breaker closes when they all succeed and opens
+//! again on the first failed probe.
+
+use crate::config::{CircuitBreakerConfig, RoutingConfig};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Breaker state as reported on `/services/health`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests flow normally
+    Closed,
+    /// Requests are rejected
+    Open,
+    /// A few probe requests are let through
+    HalfOpen,
+}
+
+impl std::fmt::Display for BreakerState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakerState::Closed => write!(f, "closed"),
+            BreakerState::Open => write!(f, "open"),
+            BreakerState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+/// Snapshot of one breaker
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BreakerStatus {
+    /// Current state
+    pub state: BreakerState,
+    /// Requests in the current window
+    pub requests: u32,
+    /// Failed requests in the current window
+    pub failures: u32,
+    /// Seconds until probes are let through, while open
+    pub retry_after_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+enum Phase {
+    Closed { window_start: Instant, requests: u32, failures: u32 },
+    Open { until: Instant },
+    HalfOpen { in_flight: u32, successes: u32 },
+}
+
+/// Circuit breaker of one service
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    phase: Mutex<Phase>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            phase: Mutex::new(Phase::Closed {
+                window_start: Instant::now(),
+                requests: 0,
+                failures: 0,
+            }),
+        }
+    }
+
+    /// Ask to send a request; `None` while the breaker rejects requests
+    pub fn try_acquire(self: &Arc<Self>) -> Option<BreakerPermit> {
+        self.try_acquire_at(Instant::now())
+    }
+
+    fn try_acquire_at(self: &Arc<Self>, now: Instant) -> Option<BreakerPermit> {
+        if !self.config.enabled {
+            return Some(BreakerPermit::new(self.clone(), false));
+        }
+        let mut phase = self.phase.lock().unwrap();
+        if let Phase::Open { until } = *phase {
+            if now < until {
+                return None;
+            }
+            *phase = Phase::HalfOpen { in_flight: 0, successes: 0 };
+        }
+        match &mut *phase {
+            Phase::HalfOpen { in_flight, successes } => {
+                if *in_flight + *successes >= self.config.half_open_probes.max(1) {
+                    return None;
+                }
+                *in_flight += 1;
+                Some(BreakerPermit::new(self.clone(), true))
+            }
+            _ => Some(BreakerPermit::new(self.clone(), false)),
+        }
+    }
+
+    /// Current state of the breaker
+    pub fn status(&self) -> BreakerStatus {
+        self.status_at(Instant::now())
+    }
+
+    fn status_at(&self, now: Instant) -> BreakerStatus {
+        match *self.phase.lock().unwrap() {
+            Phase::Closed { window_start, requests, failures } => {
+                let expired = now.duration_since(window_start) >= self.window();
+                BreakerStatus {
+                    state: BreakerState::Closed,
+                    requests: if expired { 0 } else { requests },
+                    failures: if expired { 0 } else { failures },
+                    retry_after_secs: None,
+                }
+            }
+            Phase::Open { until } if now < until => BreakerStatus {
+                state: BreakerState::Open,
+                requests: 0,
+                failures: 0,
+                retry_after_secs: Some(until.saturating_duration_since(now).as_secs().max(1)),
+            },
+            Phase::Open { .. } => BreakerStatus {
+                state: BreakerState::HalfOpen,
+                requests: 0,
+                failures: 0,
+                retry_after_secs: None,
+            },
+            Phase::HalfOpen { in_flight, successes } => BreakerStatus {
+                state: BreakerState::HalfOpen,
+                requests: in_flight + successes,
+                failures: 0,
+                retry_after_secs: None,
+            },
+        }
+    }
+
+    fn record(&self, success: bool, probe: bool, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut phase = self.phase.lock().unwrap();
+        match &mut *phase {
+            Phase::HalfOpen { in_flight, successes } if probe => {
+                *in_flight = in_flight.saturating_sub(1);
+                if !success {
+                    *phase = self.open(now);
+                } else {
+                    *successes += 1;
+                    if *successes >= self.config.half_open_probes.max(1) {
+                        *phase = Phase::Closed {
+                            window_start: now,
+                            requests: 0,
+                            failures: 0,
+                        };
+                    }
+                }
+            }
+            Phase::Closed { window_start, requests, failures } => {
+                if now.duration_since(*window_start) >= self.window() {
+                    *window_start = now;
+                    *requests = 0;
+                    *failures = 0;
+                }
+                *requests += 1;
+                if !success {
+                    *failures += 1;
+                }
+                let rate = f64::from(*failures) / f64::from(*requests);
+                if *requests >= self.config.minimum_requests.max(1) && rate >= self.config.failure_rate_threshold {
+                    tracing::warn!("⚡ Circuit opened after {}/{} failed requests", failures, requests);
+                    *phase = self.open(now);
+                }
+            }
+            // Outcomes of requests started in an earlier phase are ignored
+            _ => {}
+        }
+    }
+
+    fn release(&self, probe: bool) {
+        if let Phase::HalfOpen { in_flight, .. } = &mut *self.phase.lock().unwrap() {
+            if probe {
+                *in_flight = in_flight.saturating_sub(1);
+            }
+        }
+    }
+
+    fn open(&self, now: Instant) -> Phase {
+        Phase::Open {
+            until: now + Duration::from_secs(self.config.open_secs),
+        }
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs.max(1))
+    }
+}
+
+/// Permission to send one request
+///
+/// Report the outcome with [`BreakerPermit::success`] or [`BreakerPermit::failure`];
+/// a permit dropped without an outcome (e.g. the client went away) is not counted.
+#[derive(Debug)]
+pub struct BreakerPermit {
+    breaker: Arc<CircuitBreaker>,
+    probe: bool,
+    recorded: bool,
+}
+
+impl BreakerPermit {
+    fn new(breaker: Arc<CircuitBreaker>, probe: bool) -> Self {
+        Self {
+            breaker,
+            probe,
+            recorded: false,
+        }
+    }
+
+    /// The upstream answered normally
+    pub fn success(self) {
+        self.finish(true);
+    }
+
+    /// The upstream failed or returned a server error
+    pub fn failure(self) {
+        self.finish(false);
+    }
+
+    fn finish(self, success: bool) {
+        self.finish_at(success, Instant::now());
+    }
+
+    fn finish_at(mut self, success: bool, now: Instant) {
+        self.recorded = true;
+        self.breaker.record(success, self.probe, now);
+    }
+}
+
+impl Drop for BreakerPermit {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breaker.release(self.probe);
+        }
+    }
+}
+
+/// Circuit breakers of every service
+#[derive(Debug)]
+pub struct CircuitBreakers {
+    configs: HashMap<String, CircuitBreakerConfig>,
+    breakers: RwLock<HashMap<String, Arc<CircuitBreaker>>>,
+}
+
+impl CircuitBreakers {
+    /// Create breakers from the routing configuration
+    pub fn from_config(routing: &RoutingConfig) -> Self {
+        let breakers = routing
+            .routes
+            .iter()
+            .map(|route| {
+                let config = routing.circuit_breakers.get(&route.service).cloned().unwrap_or_default();
+                (route.service.clone(), Arc::new(CircuitBreaker::new(config)))
+            })
+            .collect();
+        Self {
+            configs: routing.circuit_breakers.clone(),
+            breakers: RwLock::new(breakers),
+        }
+    }
+
+    /// Breaker of a service
+    pub fn get(&self, service: &str) -> Arc<CircuitBreaker> {
+        if let Some(breaker) = self.breakers.read().unwrap().get(service) {
+            return breaker.clone();
+        }
+        self.breakers
+            .write()
+            .unwrap()
+            .entry(service.to_string())
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(self.configs.get(service).cloned().unwrap_or_default())))
+            .clone()
+    }
+
+    /// State of every breaker
+    pub fn statuses(&self) -> HashMap<String, BreakerStatus> {
+        self.breakers
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(service, breaker)| (service.clone(), breaker.status()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker() -> Arc<CircuitBreaker> {
+        Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            minimum_requests: 4,
+            open_secs: 10,
+            half_open_probes: 2,
+            ..CircuitBreakerConfig::default()
+        }))
+    }
+
+    fn outcome(breaker: &Arc<CircuitBreaker>, now: Instant, success: bool) {
+        breaker.try_acquire_at(now).expect("request allowed").finish_at(success, now);
+    }
+
+    #[test]
+    fn test_opens_at_failure_rate() {
+        let breaker = breaker();
+        let now = Instant::now();
+
+        outcome(&breaker, now, true);
+        outcome(&breaker, now, false);
+        outcome(&breaker, now, true);
+        assert_eq!(breaker.status_at(now).state, BreakerState::Closed);
+
+        // 2 of 4 failed, which meets the 50% threshold
+        outcome(&breaker, now, false);
+        let status = breaker.status_at(now);
+        assert_eq!(status.state, BreakerState::Open);
+        assert_eq!(status.retry_after_secs, Some(10));
+        assert!(breaker.try_acquire_at(now).is_none());
+    }
+
+    #[test]
+    fn test_failures_in_old_windows_are_forgotten() {
+        let breaker = breaker();
+        let start = Instant::now();
+
+        outcome(&breaker, start, false);
+        outcome(&breaker, start, false);
+        outcome(&breaker, start, false);
+
+        let later = start + Duration::from_secs(31);
+        outcome(&breaker, later, false);
+        let status = breaker.status_at(later);
+        assert_eq!(status.state, BreakerState::Closed);
+        assert_eq!(status.requests, 1);
+    }
+
+    #[test]
+    fn test_half_open_probes_close_or_reopen() {
+        let breaker = breaker();
+        let now = Instant::now();
+        for _ in 0..4 {
+            outcome(&breaker, now, false);
+        }
+
+        // After the open period only the configured number of probes get through
+        let later = now + Duration::from_secs(10);
+        let first = breaker.try_acquire_at(later).expect("first probe");
+        let second = breaker.try_acquire_at(later).expect("second probe");
+        assert!(breaker.try_acquire_at(later).is_none());
+        assert_eq!(breaker.status_at(later).state, BreakerState::HalfOpen);
+
+        // An abandoned probe frees its slot
+        drop(second);
+        let second = breaker.try_acquire_at(later).expect("replacement probe");
+
+        first.success();
+        second.success();
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+
+        // A failed probe opens the breaker again
+        let breaker_again = self::breaker();
+        for _ in 0..4 {
+            outcome(&breaker_again, now, false);
+        }
+        breaker_again.try_acquire_at(later).expect("probe").failure();
+        assert_eq!(breaker_again.status().state, BreakerState::Open);
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: false,
+            minimum_requests: 1,
+            ..CircuitBreakerConfig::default()
+        }));
+        for _ in 0..10 {
+            breaker.try_acquire().expect("allowed").failure();
+        }
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+    }
+}
@@ -6,6 +6,93 @@ use std::collections::HashMap;
 pub struct ApiGatewayConfig {
     pub server: ServerConfig,
     pub routing: RoutingConfig,
+    /// Token validation settings
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Response cache settings
+    #[serde(default)]
+    pub cache: CacheConfig,
+    /// Maintenance mode settings
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+}
+
+/// Maintenance mode configuration
+///
+/// Maintenance is toggled with `admin-cli world maintenance`, which publishes on the
+/// event bus; without `nats_url` the gateway never enters maintenance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// NATS server the maintenance state and service lifecycle announcements are received from
+    pub nats_url: Option<String>,
+    /// Subject prefix shared with the other services
+    pub subject_prefix: Option<String>,
+    /// Roles still allowed to open game connections during maintenance
+    pub admin_roles: Vec<String>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            nats_url: None,
+            subject_prefix: None,
+            admin_roles: vec!["admin".to_string(), "gm".to_string()],
+        }
+    }
+}
+
+/// Response cache configuration
+///
+/// Only routes with a `cache` section are cached.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Responses kept in memory
+    pub max_entries: u64,
+    /// Larger responses are not cached
+    pub max_body_bytes: usize,
+    /// Shared Redis tier behind the in-memory one (e.g., "redis://localhost:6379")
+    pub redis_url: Option<String>,
+    /// Prefix of Redis keys
+    pub key_prefix: String,
+    /// Roles allowed to call `POST /cache/invalidate`
+    pub invalidation_roles: Vec<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 10_000,
+            max_body_bytes: 1024 * 1024,
+            redis_url: None,
+            key_prefix: "gateway:cache:".to_string(),
+            invalidation_roles: vec!["cms".to_string(), "admin".to_string()],
+        }
+    }
+}
+
+/// Caching of a route's `GET` responses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteCacheConfig {
+    /// How long responses are served from cache, in seconds
+    pub ttl_secs: u64,
+    /// Who shares cached responses
+    #[serde(default)]
+    pub scope: CacheScope,
+}
+
+/// Who shares a cached response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheScope {
+    /// Each user has their own entries; anonymous callers share one
+    #[default]
+    User,
+    /// Callers with the same roles share entries
+    Roles,
+    /// Everyone shares entries, whatever their token
+    Public,
 }
 
 /// Server configuration
@@ -22,21 +109,242 @@ pub struct RoutingConfig {
     pub service_discovery: ServiceDiscoveryConfig,
     /// Route definitions
     pub routes: Vec<RouteConfig>,
+    /// Load balancing per service; services not listed use round-robin
+    #[serde(default)]
+    pub load_balancing: HashMap<String, LoadBalancingConfig>,
+    /// Circuit breakers per service; services not listed use the defaults
+    #[serde(default)]
+    pub circuit_breakers: HashMap<String, CircuitBreakerConfig>,
+    /// gRPC routes, matched on the fully qualified gRPC service name
+    #[serde(default)]
+    pub grpc_routes: Vec<GrpcRouteConfig>,
+    /// Weighted upstream groups per routed service, for canary releases
+    #[serde(default)]
+    pub traffic_splits: HashMap<String, TrafficSplitConfig>,
+    /// Roles allowed to change split weights at runtime
+    #[serde(default = "default_traffic_split_admin_roles")]
+    pub traffic_split_admin_roles: Vec<String>,
+}
+
+fn default_traffic_split_admin_roles() -> Vec<String> {
+    vec!["admin".to_string()]
+}
+
+/// Traffic split of one routed service
+///
+/// Requests to the service go to one of the groups in proportion to its weight
+/// (e.g., 90 to "stable", 10 to "canary"). Each group is a discovered service of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrafficSplitConfig {
+    /// Upstream groups
+    pub groups: Vec<UpstreamGroupConfig>,
+    /// Request header naming the group to use, for testing a group directly
+    #[serde(default = "default_split_header")]
+    pub sticky_header: String,
+    /// Cookie remembering the group a client was assigned; not set when absent
+    pub sticky_cookie: Option<String>,
+    /// Lifetime of the sticky cookie, in seconds
+    #[serde(default = "default_split_cookie_max_age")]
+    pub cookie_max_age_secs: u64,
+}
+
+fn default_split_header() -> String {
+    "x-upstream-group".to_string()
+}
+
+fn default_split_cookie_max_age() -> u64 {
+    24 * 60 * 60
+}
+
+/// Upstream group of a traffic split
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamGroupConfig {
+    /// Group name (e.g., "stable", "canary")
+    pub name: String,
+    /// Service whose instances serve the group
+    pub service: String,
+    /// Share of the traffic, relative to the other groups
+    pub weight: u32,
+}
+
+/// gRPC route configuration
+///
+/// Calls to `/{grpc_service}/{method}` are forwarded over HTTP/2 to an instance of
+/// `service`, streaming bodies and trailers both ways.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcRouteConfig {
+    /// Fully qualified gRPC service (e.g., "chaos.v1.ActorService")
+    pub grpc_service: String,
+    /// Target service name
+    pub service: String,
+    /// Connect to the upstream over TLS instead of cleartext HTTP/2
+    #[serde(default)]
+    pub tls: bool,
+    /// Deadline in seconds for calls that carry no `grpc-timeout`
+    pub timeout_secs: Option<u64>,
+    /// Authentication requirement
+    #[serde(default)]
+    pub auth: RouteAuthConfig,
+}
+
+/// Circuit breaker configuration of one service
+///
+/// The breaker opens when the failure rate within a window reaches the threshold,
+/// rejects requests while open, then lets a few probes through (half-open) and
+/// closes again once they all succeed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Whether the breaker is active
+    pub enabled: bool,
+    /// Failure rate (0.0 - 1.0) that opens the breaker
+    pub failure_rate_threshold: f64,
+    /// Requests needed in a window before the rate is evaluated
+    pub minimum_requests: u32,
+    /// Length of the measurement window, in seconds
+    pub window_secs: u64,
+    /// How long the breaker stays open, in seconds
+    pub open_secs: u64,
+    /// Probes let through while half-open
+    pub half_open_probes: u32,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            failure_rate_threshold: 0.5,
+            minimum_requests: 20,
+            window_secs: 30,
+            open_secs: 15,
+            half_open_probes: 3,
+        }
+    }
+}
+
+/// Retry policy of a route
+///
+/// Only idempotent methods (GET, HEAD, PUT, DELETE, OPTIONS) are retried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Retries after the first attempt
+    pub max_retries: u32,
+    /// Backoff before the first retry, in milliseconds; doubles per retry
+    pub base_delay_ms: u64,
+    /// Upper bound of the backoff, in milliseconds
+    pub max_delay_ms: u64,
+    /// Upstream statuses that are retried (connection errors always are)
+    pub retry_on_status: Vec<u16>,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            base_delay_ms: 50,
+            max_delay_ms: 1000,
+            retry_on_status: vec![502, 503, 504],
+        }
+    }
+}
+
+/// Load balancing configuration of one service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadBalancingConfig {
+    /// How instances are picked
+    #[serde(default)]
+    pub strategy: LoadBalancingStrategy,
+    /// Header holding the affinity key when the caller is not authenticated
+    /// (default "x-player-id"); only used by `consistent_hash`
+    pub hash_header: Option<String>,
+    /// Idle connections kept per upstream instance (default 32)
+    pub max_idle_connections: Option<usize>,
+    /// How long idle connections are kept, in seconds (default 90)
+    pub idle_timeout_secs: Option<u64>,
+}
+
+/// Instance selection strategy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoadBalancingStrategy {
+    /// Weighted round-robin
+    #[default]
+    RoundRobin,
+    /// Instance with the fewest in-flight requests
+    LeastConnections,
+    /// Hash ring on the player ID, so a player sticks to one instance
+    ConsistentHash,
 }
 
 /// Service discovery configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceDiscoveryConfig {
-    /// Static service discovery
+    /// Where upstream instances come from
+    #[serde(default)]
+    pub backend: DiscoveryBackendKind,
+    /// How often instance lists are refreshed, in seconds (default 15)
+    pub refresh_secs: Option<u64>,
+    /// Consul backend settings
+    pub consul: Option<ConsulConfig>,
+    /// DNS SRV backend settings
+    pub dns_srv: Option<DnsSrvConfig>,
+    /// Static service discovery, also used as fallback when the backend fails
     pub static_services: HashMap<String, ServiceConfig>,
 }
 
+/// Service discovery backend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscoveryBackendKind {
+    /// Only `static_services`
+    #[default]
+    Static,
+    /// Consul health API
+    Consul,
+    /// DNS SRV records
+    DnsSrv,
+}
+
+/// Consul backend configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsulConfig {
+    /// Consul HTTP address (e.g., "http://localhost:8500")
+    pub address: String,
+    /// Datacenter to query
+    pub datacenter: Option<String>,
+    /// ACL token
+    pub token: Option<String>,
+}
+
+/// DNS SRV backend configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsSrvConfig {
+    /// SRV name template; `{service}` is replaced by the service name
+    /// (e.g., "_{service}._tcp.chaos.internal")
+    pub name_template: String,
+}
+
 /// Service configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceConfig {
     pub host: String,
     pub port: u16,
     pub health_check: Option<String>,
+    /// Check health with `grpc.health.v1.Health/Check` instead of an HTTP path
+    #[serde(default)]
+    pub grpc_health_check: Option<GrpcHealthCheckConfig>,
+}
+
+/// gRPC health check of a service
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GrpcHealthCheckConfig {
+    /// Service name sent in the check; empty checks the whole server
+    #[serde(default)]
+    pub service: String,
+    /// Connect over TLS instead of cleartext HTTP/2
+    #[serde(default)]
+    pub tls: bool,
 }
 
 /// Route configuration
@@ -54,6 +362,79 @@ pub struct RouteConfig {
     pub add_headers: Option<HashMap<String, String>>,
     /// Rate limiting configuration
     pub rate_limit: Option<RateLimitConfig>,
+    /// Authentication requirement
+    #[serde(default)]
+    pub auth: RouteAuthConfig,
+    /// Retry policy; requests are not retried when unset
+    pub retry: Option<RetryConfig>,
+    /// WebSocket proxying; upgrade requests are refused when unset
+    pub websocket: Option<WebSocketConfig>,
+    /// Response caching; responses are not cached when unset
+    pub cache: Option<RouteCacheConfig>,
+}
+
+/// WebSocket proxying configuration of a route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebSocketConfig {
+    /// Open connections per user (token subject, or client address when anonymous)
+    pub max_connections_per_user: usize,
+    /// Connections without traffic in either direction are closed after this many seconds
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            max_connections_per_user: 4,
+            idle_timeout_secs: 300,
+        }
+    }
+}
+
+/// Token validation configuration
+///
+/// Tokens signed with HMAC are checked against the shared secret; other algorithms
+/// are checked against the keys published at `jwks_url`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Shared HS256 secret (overridden by the `API_GATEWAY_JWT_SECRET` env var)
+    pub jwt_secret: Option<String>,
+    /// JWKS endpoint of the identity provider
+    pub jwks_url: Option<String>,
+    /// How often to refetch the JWKS, in seconds (default 300)
+    pub jwks_refresh_secs: Option<u64>,
+    /// Expected `iss` claim
+    pub issuer: Option<String>,
+    /// Expected `aud` claim
+    pub audience: Option<String>,
+    /// Allowed clock skew, in seconds
+    #[serde(default)]
+    pub leeway_secs: u64,
+}
+
+/// Per-route authentication requirement
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteAuthConfig {
+    /// Whether a token is needed
+    #[serde(default)]
+    pub mode: AuthMode,
+    /// Roles of which the caller needs at least one (implies `required`)
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+/// How a route treats bearer tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthMode {
+    /// Tokens are ignored and no claims are forwarded
+    #[default]
+    None,
+    /// Tokens are validated and forwarded when present
+    Optional,
+    /// Requests without a valid token are rejected
+    Required,
 }
 
 /// Rate limiting configuration
@@ -71,7 +452,8 @@ impl Default for ApiGatewayConfig {
             ServiceConfig {
                 host: "localhost".to_string(),
                 port: 8082,
-                health_check: Some("/health".to_string()),
+                health_check: Some("/health/ready".to_string()),
+                grpc_health_check: None,
             },
         );
         static_services.insert(
@@ -79,7 +461,8 @@ impl Default for ApiGatewayConfig {
             ServiceConfig {
                 host: "localhost".to_string(),
                 port: 8081,
-                health_check: Some("/health".to_string()),
+                health_check: Some("/health/ready".to_string()),
+                grpc_health_check: None,
             },
         );
 
@@ -90,6 +473,10 @@ impl Default for ApiGatewayConfig {
             },
             routing: RoutingConfig {
                 service_discovery: ServiceDiscoveryConfig {
+                    backend: DiscoveryBackendKind::Static,
+                    refresh_secs: None,
+                    consul: None,
+                    dns_srv: None,
                     static_services,
                 },
                 routes: vec![
@@ -103,6 +490,13 @@ impl Default for ApiGatewayConfig {
                             requests_per_minute: 100,
                             burst_size: Some(10),
                         }),
+                        auth: RouteAuthConfig {
+                            mode: AuthMode::Optional,
+                            roles: Vec::new(),
+                        },
+                        retry: None,
+                        websocket: None,
+                        cache: None,
                     },
                     RouteConfig {
                         path: "/api/*".to_string(),
@@ -111,9 +505,24 @@ impl Default for ApiGatewayConfig {
                         strip_prefix: false,
                         add_headers: None,
                         rate_limit: None,
+                        auth: RouteAuthConfig {
+                            mode: AuthMode::Required,
+                            roles: Vec::new(),
+                        },
+                        retry: None,
+                        websocket: None,
+                        cache: None,
                     },
                 ],
+                load_balancing: HashMap::new(),
+                circuit_breakers: HashMap::new(),
+                grpc_routes: Vec::new(),
+                traffic_splits: HashMap::new(),
+                traffic_split_admin_roles: default_traffic_split_admin_roles(),
             },
+            auth: AuthConfig::default(),
+            cache: CacheConfig::default(),
+            maintenance: MaintenanceConfig::default(),
         }
     }
 }
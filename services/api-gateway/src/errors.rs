@@ -2,6 +2,7 @@
 
 use std::fmt;
 use std::error::Error as StdError;
+use shared::error::{ErrorCode, ToApiError};
 
 /// API Gateway result type
 pub type Result<T> = std::result::Result<T, ApiGatewayError>;
@@ -185,29 +186,7 @@ impl From<tonic::Status> for ApiGatewayError {
 /// HTTP status code for API Gateway errors
 impl ApiGatewayError {
     pub fn status_code(&self) -> u16 {
-        match self {
-            ApiGatewayError::Config(_) => 500,
-            ApiGatewayError::Server(_) => 500,
-            ApiGatewayError::Routing(_) => 404,
-            ApiGatewayError::Auth(_) => 401,
-            ApiGatewayError::Authorization(_) => 403,
-            ApiGatewayError::RateLimit(_) => 429,
-            ApiGatewayError::ServiceDiscovery(_) => 503,
-            ApiGatewayError::LoadBalancing(_) => 503,
-            ApiGatewayError::Caching(_) => 500,
-            ApiGatewayError::Monitoring(_) => 500,
-            ApiGatewayError::Security(_) => 403,
-            ApiGatewayError::Network(_) => 503,
-            ApiGatewayError::Timeout(_) => 504,
-            ApiGatewayError::CircuitBreaker(_) => 503,
-            ApiGatewayError::Validation(_) => 400,
-            ApiGatewayError::Serialization(_) => 400,
-            ApiGatewayError::Io(_) => 500,
-            ApiGatewayError::Http(_) => 502,
-            ApiGatewayError::Database(_) => 500,
-            ApiGatewayError::ExternalService(_) => 502,
-            ApiGatewayError::Internal(_) => 500,
-        }
+        self.error_code().http_status()
     }
 
     /// Get error message for client
@@ -265,3 +244,35 @@ impl ApiGatewayError {
         }
     }
 }
+
+impl ToApiError for ApiGatewayError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ApiGatewayError::Config(_) => ErrorCode::Configuration,
+            ApiGatewayError::Server(_) => ErrorCode::Internal,
+            ApiGatewayError::Routing(_) => ErrorCode::NotFound,
+            ApiGatewayError::Auth(_) => ErrorCode::Unauthenticated,
+            ApiGatewayError::Authorization(_) => ErrorCode::PermissionDenied,
+            ApiGatewayError::RateLimit(_) => ErrorCode::RateLimited,
+            ApiGatewayError::ServiceDiscovery(_) => ErrorCode::ServiceUnavailable,
+            ApiGatewayError::LoadBalancing(_) => ErrorCode::ServiceUnavailable,
+            ApiGatewayError::Caching(_) => ErrorCode::Internal,
+            ApiGatewayError::Monitoring(_) => ErrorCode::Internal,
+            ApiGatewayError::Security(_) => ErrorCode::PermissionDenied,
+            ApiGatewayError::Network(_) => ErrorCode::ServiceUnavailable,
+            ApiGatewayError::Timeout(_) => ErrorCode::Timeout,
+            ApiGatewayError::CircuitBreaker(_) => ErrorCode::ServiceUnavailable,
+            ApiGatewayError::Validation(_) => ErrorCode::ValidationFailed,
+            ApiGatewayError::Serialization(_) => ErrorCode::InvalidFormat,
+            ApiGatewayError::Io(_) => ErrorCode::Internal,
+            ApiGatewayError::Http(_) => ErrorCode::UpstreamError,
+            ApiGatewayError::Database(_) => ErrorCode::Database,
+            ApiGatewayError::ExternalService(_) => ErrorCode::UpstreamError,
+            ApiGatewayError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        ApiGatewayError::client_message(self)
+    }
+}
@@ -0,0 +1,356 @@
+//! gRPC proxying.
+//!
+//! Calls to `/{package.Service}/{Method}` are matched against `grpc_routes` and
+//! forwarded over HTTP/2 (cleartext or TLS) to an instance of the route's service.
+//! Request and response bodies are streamed frame by frame, so streaming RPCs and
+//! trailers (`grpc-status`, `grpc-message`, custom metadata) pass through unchanged.
+//! Errors raised by the gateway itself are returned as trailers-only gRPC responses.
+
+use crate::auth::{AuthRejection, USER_ID_HEADER, USER_ROLES_HEADER};
+use crate::config::{GrpcHealthCheckConfig, GrpcRouteConfig};
+use crate::monitoring;
+use crate::service_discovery::ServiceInstance;
+use crate::state::GatewayState;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, StatusCode, Uri, Version};
+use axum::response::{IntoResponse, Response};
+use http_body_util::BodyExt;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Content type of gRPC requests (`application/grpc+proto` etc. also match)
+pub const GRPC_CONTENT_TYPE: &str = "application/grpc";
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// gRPC status codes raised by the gateway
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrpcCode {
+    /// Upstream did not answer in time
+    DeadlineExceeded = 4,
+    /// Caller lacks a required role
+    PermissionDenied = 7,
+    /// No route for the gRPC service
+    Unimplemented = 12,
+    /// Upstream unreachable or circuit open
+    Unavailable = 14,
+    /// Missing or invalid token
+    Unauthenticated = 16,
+}
+
+/// Trailers-only gRPC response carrying an error status
+pub fn status_response(code: GrpcCode, message: &str) -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, GRPC_CONTENT_TYPE)
+        .header("grpc-status", (code as u8).to_string())
+        .header("grpc-message", encode_message(message))
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn rejection_response(rejection: &AuthRejection) -> Response {
+    match rejection {
+        AuthRejection::Missing => status_response(GrpcCode::Unauthenticated, "Bearer token required"),
+        AuthRejection::Invalid(_) => status_response(GrpcCode::Unauthenticated, "Invalid bearer token"),
+        AuthRejection::Expired => status_response(GrpcCode::Unauthenticated, "Bearer token expired"),
+        AuthRejection::Forbidden => status_response(GrpcCode::PermissionDenied, "Insufficient role"),
+        AuthRejection::Unavailable(_) => status_response(GrpcCode::Unavailable, "Token validation unavailable"),
+    }
+}
+
+/// Percent-encode a `grpc-message` value as required by the gRPC HTTP/2 spec
+fn encode_message(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..=0x7e).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// HTTP/2 client for gRPC upstreams
+#[derive(Clone)]
+pub struct GrpcClient {
+    inner: Client<HttpsConnector<HttpConnector>, Body>,
+}
+
+impl std::fmt::Debug for GrpcClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcClient").finish_non_exhaustive()
+    }
+}
+
+impl Default for GrpcClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GrpcClient {
+    /// Create a client speaking HTTP/2 only, over cleartext or TLS depending on the URI
+    pub fn new() -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http2()
+            .build();
+        Self {
+            inner: Client::builder(TokioExecutor::new()).http2_only(true).build(connector),
+        }
+    }
+
+    /// Check an instance with `grpc.health.v1.Health/Check`
+    pub async fn check_health(&self, instance: &ServiceInstance, config: &GrpcHealthCheckConfig) -> bool {
+        let uri = format!("{}/grpc.health.v1.Health/Check", base_uri(instance, config.tls));
+        let request = match axum::http::Request::post(&uri)
+            .header(header::CONTENT_TYPE, GRPC_CONTENT_TYPE)
+            .header(header::TE, "trailers")
+            .body(Body::from(health_check_request(&config.service)))
+        {
+            Ok(request) => request,
+            Err(e) => {
+                debug!("Invalid gRPC health check URI {}: {}", uri, e);
+                return false;
+            }
+        };
+
+        let check = async {
+            let response = self.inner.request(request).await.map_err(|e| e.to_string())?;
+            if response.status() != StatusCode::OK {
+                return Err(format!("HTTP status {}", response.status()));
+            }
+            let headers = response.headers().clone();
+            let collected = response.into_body().collect().await.map_err(|e| e.to_string())?;
+            let status = headers
+                .get("grpc-status")
+                .or_else(|| collected.trailers().and_then(|trailers| trailers.get("grpc-status")))
+                .and_then(|value| value.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+            if status != "0" {
+                return Err(format!("grpc-status {:?}", status));
+            }
+            Ok(is_serving(&collected.to_bytes()))
+        };
+        match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check).await {
+            Ok(Ok(serving)) => serving,
+            Ok(Err(e)) => {
+                debug!("gRPC health check {} failed: {}", uri, e);
+                false
+            }
+            Err(_) => {
+                debug!("gRPC health check {} timed out", uri);
+                false
+            }
+        }
+    }
+}
+
+fn base_uri(instance: &ServiceInstance, tls: bool) -> String {
+    let scheme = if tls { "https" } else { "http" };
+    format!("{}://{}:{}", scheme, instance.host, instance.port)
+}
+
+/// Framed `HealthCheckRequest { string service = 1; }`
+fn health_check_request(service: &str) -> Vec<u8> {
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        message.push(0x0a);
+        let mut len = service.len();
+        while len >= 0x80 {
+            message.push((len as u8 & 0x7f) | 0x80);
+            len >>= 7;
+        }
+        message.push(len as u8);
+        message.extend_from_slice(service.as_bytes());
+    }
+    let mut frame = Vec::with_capacity(5 + message.len());
+    frame.push(0);
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend(message);
+    frame
+}
+
+/// Whether a framed `HealthCheckResponse { ServingStatus status = 1; }` says SERVING (1)
+fn is_serving(frame: &[u8]) -> bool {
+    matches!(frame, [0, _, _, _, _, 0x08, 0x01, ..])
+}
+
+/// Forward a gRPC call to the service of its route
+pub async fn proxy_grpc(State(state): State<GatewayState>, request: Request) -> Response {
+    let path = request.uri().path().to_string();
+    let grpc_service = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+    let Some(route) = state.config.routing.grpc_routes.iter().find(|route| route.grpc_service == grpc_service) else {
+        return status_response(GrpcCode::Unimplemented, &format!("Unknown service {}", grpc_service));
+    };
+    monitoring::record_route(&route.grpc_service);
+
+    let is_grpc = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(GRPC_CONTENT_TYPE));
+    if !is_grpc {
+        return StatusCode::UNSUPPORTED_MEDIA_TYPE.into_response();
+    }
+
+    let claims = match state.auth.authorize(&path, &route.auth, request.headers()).await {
+        Ok(claims) => claims,
+        Err(rejection) => {
+            warn!("❌ Rejected gRPC call {}: {:?}", path, rejection);
+            return rejection_response(&rejection);
+        }
+    };
+    if let Some(claims) = &claims {
+        monitoring::record_user(&claims.sub);
+    }
+
+    let Some(permit) = state.circuit_breakers.get(&route.service).try_acquire() else {
+        warn!("⚡ Circuit open for service {}, rejecting gRPC call", route.service);
+        return status_response(GrpcCode::Unavailable, &format!("Service {} is unavailable", route.service));
+    };
+
+    let instances = state.discovery.instances(&route.service);
+    let affinity_key = claims.as_ref().map(|claims| claims.sub.as_str());
+    let Some(selection) = state.load_balancer.select(&route.service, &instances, affinity_key) else {
+        warn!("❌ No instances of service: {}", route.service);
+        return status_response(GrpcCode::Unavailable, &format!("No instances of {}", route.service));
+    };
+    monitoring::record_upstream(&route.service, &selection.instance.address());
+
+    let (mut parts, body) = request.into_parts();
+    let path_and_query = parts.uri.path_and_query().map_or(path.as_str(), |value| value.as_str());
+    parts.uri = match format!("{}{}", base_uri(&selection.instance, route.tls), path_and_query).parse::<Uri>() {
+        Ok(uri) => uri,
+        Err(e) => {
+            warn!("❌ Invalid upstream URI for {}: {}", route.service, e);
+            return status_response(GrpcCode::Unavailable, "Invalid upstream address");
+        }
+    };
+    parts.version = Version::HTTP_2;
+    prepare_headers(&mut parts.headers, route, claims.as_ref().map(|claims| claims.forwarded_headers()));
+
+    info!("🔀 GRPC PROXY: {} -> {}", path, parts.uri);
+    let send = state.grpc.inner.request(axum::http::Request::from_parts(parts, body));
+    let result = match route.timeout_secs {
+        Some(secs) => match tokio::time::timeout(Duration::from_secs(secs), send).await {
+            Ok(result) => result,
+            Err(_) => {
+                permit.failure();
+                warn!("⏱️ gRPC call {} exceeded {}s", path, secs);
+                return status_response(GrpcCode::DeadlineExceeded, "Upstream deadline exceeded");
+            }
+        },
+        None => send.await,
+    };
+
+    match result {
+        Ok(response) => {
+            // Status of trailers-only responses is known now; otherwise it arrives in trailers
+            let unavailable = response
+                .headers()
+                .get("grpc-status")
+                .is_some_and(|status| status == "14");
+            if response.status() != StatusCode::OK || unavailable {
+                permit.failure();
+            } else {
+                permit.success();
+            }
+
+            // The instance stays in use until the response stream ends
+            let (parts, body) = response.into_parts();
+            let body = body.map_frame(move |frame| {
+                let _in_use = &selection;
+                frame
+            });
+            Response::from_parts(parts, Body::new(body))
+        }
+        Err(e) => {
+            permit.failure();
+            warn!("❌ gRPC call {} to {} failed: {}", path, route.service, e);
+            status_response(GrpcCode::Unavailable, &format!("Service {} is unreachable", route.service))
+        }
+    }
+}
+
+/// Strip hop-by-hop and client-supplied identity headers, then add the gateway's own
+fn prepare_headers(
+    headers: &mut axum::http::HeaderMap,
+    route: &GrpcRouteConfig,
+    identity: Option<Vec<(&'static str, String)>>,
+) {
+    for name in [header::HOST.as_str(), header::CONNECTION.as_str(), USER_ID_HEADER, USER_ROLES_HEADER] {
+        headers.remove(name);
+    }
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+    if let Some(secs) = route.timeout_secs {
+        if !headers.contains_key("grpc-timeout") {
+            if let Ok(value) = HeaderValue::from_str(&format!("{}S", secs)) {
+                headers.insert("grpc-timeout", value);
+            }
+        }
+    }
+    for (key, value) in identity.into_iter().flatten() {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_check_framing() {
+        assert_eq!(health_check_request(""), vec![0, 0, 0, 0, 0]);
+        assert_eq!(
+            health_check_request("chaos"),
+            vec![0, 0, 0, 0, 7, 0x0a, 5, b'c', b'h', b'a', b'o', b's']
+        );
+        let long = "x".repeat(200);
+        assert_eq!(&health_check_request(&long)[5..8], &[0x0a, 0xc8, 0x01]);
+
+        assert!(is_serving(&[0, 0, 0, 0, 2, 0x08, 0x01]));
+        assert!(!is_serving(&[0, 0, 0, 0, 2, 0x08, 0x02]));
+        assert!(!is_serving(&[0, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_status_response_is_trailers_only() {
+        let response = status_response(GrpcCode::Unauthenticated, "token 100% invalid");
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["grpc-status"], "16");
+        assert_eq!(response.headers()["grpc-message"], "token 100%25 invalid");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], GRPC_CONTENT_TYPE);
+    }
+
+    #[test]
+    fn test_identity_headers_are_replaced() {
+        let route = GrpcRouteConfig {
+            grpc_service: "chaos.v1.ActorService".to_string(),
+            service: "chaos-backend".to_string(),
+            tls: false,
+            timeout_secs: Some(5),
+            auth: Default::default(),
+        };
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(USER_ID_HEADER, HeaderValue::from_static("forged"));
+        headers.insert(header::HOST, HeaderValue::from_static("gateway"));
+
+        prepare_headers(&mut headers, &route, Some(vec![(USER_ID_HEADER, "player-1".to_string())]));
+        assert_eq!(headers[USER_ID_HEADER], "player-1");
+        assert_eq!(headers["grpc-timeout"], "5S");
+        assert_eq!(headers[header::TE], "trailers");
+        assert!(!headers.contains_key(header::HOST));
+    }
+}
@@ -0,0 +1,240 @@
+//! Aggregated health of the upstream services, served on `/services/health`.
+//!
+//! Every configured service is probed in parallel on its health check. Services
+//! exposing the shared readiness probe (`/health/ready`) answer with a [`HealthReport`]
+//! that is passed through with its dependency checks; others are judged by status code
+//! alone. Results are cached briefly so dashboards polling the endpoint do not
+//! multiply upstream probes.
+
+use crate::circuit_breaker::BreakerStatus;
+use crate::config::ServiceConfig;
+use crate::grpc::GrpcClient;
+use crate::service_discovery::ServiceInstance;
+use crate::state::GatewayState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use futures::future::join_all;
+use serde::Serialize;
+use shared::health::{HealthReport, HealthStatus};
+use shared::Timestamp;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// How long aggregated results are reused
+const CACHE_TTL: Duration = Duration::from_secs(2);
+
+/// How long one service may take to answer its probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Health of every upstream service
+#[derive(Debug, Clone, Serialize)]
+pub struct ServicesHealth {
+    /// Unhealthy when every service is, degraded when some are
+    pub status: HealthStatus,
+    pub checked_at: Timestamp,
+    pub services: BTreeMap<String, ServiceHealth>,
+}
+
+/// Health of one upstream service
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceHealth {
+    pub status: HealthStatus,
+    /// Probed endpoint, absent when the service has no health check
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probe: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Readiness report with dependency checks, from services using the shared probes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<HealthReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<BreakerStatus>,
+}
+
+/// Probes upstream services and caches the results
+#[derive(Debug)]
+pub struct HealthAggregator {
+    client: reqwest::Client,
+    cached: parking_lot::Mutex<Option<(Instant, ServicesHealth)>>,
+}
+
+impl Default for HealthAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HealthAggregator {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder().timeout(PROBE_TIMEOUT).build().unwrap_or_default(),
+            cached: parking_lot::Mutex::new(None),
+        }
+    }
+
+    /// Probe every configured service, reusing results younger than the cache TTL
+    pub async fn check(&self, state: &GatewayState) -> ServicesHealth {
+        if let Some(health) = self.fresh() {
+            return health;
+        }
+
+        let services = &state.config.routing.service_discovery.static_services;
+        let probes = services.iter().map(|(name, service)| async move {
+            (name.clone(), self.probe(service, &state.grpc).await)
+        });
+        let mut results: BTreeMap<String, ServiceHealth> = join_all(probes).await.into_iter().collect();
+
+        let breakers = state.circuit_breakers.statuses();
+        for (name, health) in results.iter_mut() {
+            health.circuit = breakers.get(name).cloned();
+        }
+
+        let health = ServicesHealth {
+            status: overall(results.values().map(|health| health.status)),
+            checked_at: Utc::now(),
+            services: results,
+        };
+        *self.cached.lock() = Some((Instant::now(), health.clone()));
+        health
+    }
+
+    fn fresh(&self) -> Option<ServicesHealth> {
+        let cached = self.cached.lock();
+        cached.as_ref().filter(|(at, _)| at.elapsed() < CACHE_TTL).map(|(_, health)| health.clone())
+    }
+
+    async fn probe(&self, service: &ServiceConfig, grpc: &GrpcClient) -> ServiceHealth {
+        let started = Instant::now();
+        let instance = ServiceInstance::new(service.host.clone(), service.port);
+
+        if let Some(config) = &service.grpc_health_check {
+            let serving = grpc.check_health(&instance, config).await;
+            return ServiceHealth {
+                status: if serving { HealthStatus::Healthy } else { HealthStatus::Unhealthy },
+                probe: Some(format!("grpc://{}/grpc.health.v1.Health/Check", instance.address())),
+                error: (!serving).then(|| "Not serving".to_string()),
+                ..ServiceHealth::unprobed(started)
+            };
+        }
+
+        let Some(path) = &service.health_check else {
+            // Without a health check the service is assumed healthy
+            return ServiceHealth::unprobed(started);
+        };
+        let url = format!("{}{}", instance.base_url(), path);
+        let response = match self.client.get(&url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return ServiceHealth {
+                    status: HealthStatus::Unhealthy,
+                    probe: Some(url),
+                    error: Some(e.to_string()),
+                    ..ServiceHealth::unprobed(started)
+                };
+            }
+        };
+
+        let http_status = response.status();
+        // Plain `/health` endpoints answer with text; only shared probes carry a report
+        let report = response.json::<HealthReport>().await.ok();
+        let status = match &report {
+            Some(report) => report.status,
+            None if http_status.is_success() => HealthStatus::Healthy,
+            None => HealthStatus::Unhealthy,
+        };
+        ServiceHealth {
+            status,
+            probe: Some(url),
+            http_status: Some(http_status.as_u16()),
+            error: (!http_status.is_success()).then(|| format!("Probe answered {}", http_status)),
+            report,
+            ..ServiceHealth::unprobed(started)
+        }
+    }
+}
+
+impl ServiceHealth {
+    fn unprobed(started: Instant) -> Self {
+        Self {
+            status: HealthStatus::Healthy,
+            probe: None,
+            http_status: None,
+            latency_ms: started.elapsed().as_secs_f64() * 1000.0,
+            error: None,
+            report: None,
+            circuit: None,
+        }
+    }
+}
+
+/// Unhealthy only when nothing can be routed to, degraded when anything is not healthy
+fn overall(statuses: impl Iterator<Item = HealthStatus>) -> HealthStatus {
+    let statuses: Vec<HealthStatus> = statuses.collect();
+    if !statuses.is_empty() && statuses.iter().all(|status| *status == HealthStatus::Unhealthy) {
+        HealthStatus::Unhealthy
+    } else if statuses.iter().any(|status| *status != HealthStatus::Healthy) {
+        HealthStatus::Degraded
+    } else {
+        HealthStatus::Healthy
+    }
+}
+
+/// Log the health of every service, e.g. at startup
+pub fn log(health: &ServicesHealth) {
+    for (name, service) in &health.services {
+        match &service.error {
+            None => info!("🏥 Service {} health: {:?}", name, service.status),
+            Some(error) => warn!("🏥 Service {} health: {:?} ({})", name, service.status, error),
+        }
+    }
+}
+
+/// Aggregated upstream health; 503 when no service is usable
+pub async fn services_health_handler(State(state): State<GatewayState>) -> (StatusCode, Json<ServicesHealth>) {
+    let health = state.health.check(&state).await;
+    let status = StatusCode::from_u16(health.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(health))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overall_status_is_degraded_until_everything_fails() {
+        use HealthStatus::*;
+        assert_eq!(overall([Healthy, Healthy].into_iter()), Healthy);
+        assert_eq!(overall([Healthy, Degraded].into_iter()), Degraded);
+        assert_eq!(overall([Healthy, Unhealthy].into_iter()), Degraded);
+        assert_eq!(overall([Unhealthy, Unhealthy].into_iter()), Unhealthy);
+        assert_eq!(overall(std::iter::empty()), Healthy);
+    }
+
+    #[tokio::test]
+    async fn services_without_health_checks_are_assumed_healthy() {
+        let aggregator = HealthAggregator::new();
+        let service = ServiceConfig {
+            host: "localhost".to_string(),
+            port: 1,
+            health_check: None,
+            grpc_health_check: None,
+        };
+        let grpc = GrpcClient::new();
+        let health = aggregator.probe(&service, &grpc).await;
+        assert_eq!(health.status, HealthStatus::Healthy);
+        assert!(health.probe.is_none());
+
+        // Nothing listens on port 1
+        let service = ServiceConfig { health_check: Some("/health/ready".to_string()), ..service };
+        let health = aggregator.probe(&service, &grpc).await;
+        assert_eq!(health.status, HealthStatus::Unhealthy);
+        assert_eq!(health.probe.as_deref(), Some("http://localhost:1/health/ready"));
+        assert!(health.error.is_some());
+    }
+}
@@ -1,369 +1,346 @@
-//! Load balancing for API Gateway
+//! Per-service load balancing.
+//!
+//! The [`LoadBalancer`] picks one of a service's discovered instances using the
+//! service's [`LoadBalancingStrategy`] and hands out the service's pooled HTTP client.
+//! Every pick is counted per instance, and in-flight requests are tracked until the
+//! returned [`Selection`] is dropped.
 
-use crate::config::{Config, LoadBalancingConfig, ServiceConfig};
-use crate::errors::{ApiGatewayError, Result};
-use crate::types::{Service, ServiceStatus, LoadBalancerStats, ServiceStats};
+use crate::config::{LoadBalancingConfig, LoadBalancingStrategy, RoutingConfig};
+use crate::service_discovery::ServiceInstance;
+use serde::Serialize;
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::{SystemTime, Duration};
-use tracing::{info, debug, warn, error};
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
-/// Load balancer service
-#[derive(Debug, Clone)]
-pub struct LoadBalancer {
-    config: LoadBalancingConfig,
-    services: Arc<RwLock<HashMap<String, Vec<Service>>>>,
-    stats: Arc<RwLock<LoadBalancerStats>>,
-    current_index: Arc<RwLock<HashMap<String, usize>>>,
+/// Default header holding the affinity key for consistent hashing
+pub const DEFAULT_HASH_HEADER: &str = "x-player-id";
+
+const DEFAULT_MAX_IDLE_CONNECTIONS: usize = 32;
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Ring points per unit of instance weight
+const VIRTUAL_NODES: u32 = 64;
+
+/// Request counters of one instance
+#[derive(Debug, Default)]
+struct InstanceStats {
+    in_flight: AtomicUsize,
+    requests: AtomicU64,
 }
 
-/// Load balancing algorithm
-#[derive(Debug, Clone, PartialEq)]
-pub enum LoadBalancingAlgorithm {
-    RoundRobin,
-    LeastConnections,
-    WeightedRoundRobin,
-    IpHash,
+/// Distribution metrics of one instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InstanceDistribution {
+    /// Instance address (`host:port`)
+    pub instance: String,
+    /// Requests sent since startup
+    pub requests: u64,
+    /// Requests currently in flight
+    pub in_flight: usize,
 }
 
-/// Initialize load balancer
-pub async fn init(config: &Config) -> Result<LoadBalancer> {
-    LoadBalancer::new(config.clone())
+/// Picked instance of a service
+///
+/// Counts as in flight until dropped.
+#[derive(Debug)]
+pub struct Selection {
+    /// Instance to send the request to
+    pub instance: ServiceInstance,
+    /// Pooled client of the service
+    pub client: reqwest::Client,
+    stats: Arc<InstanceStats>,
 }
 
-impl LoadBalancer {
-    /// Create a new load balancer
-    pub fn new(config: Config) -> Result<Self> {
-        let load_balancing_config = config.load_balancing.clone();
-        
-        Ok(Self {
-            config: load_balancing_config,
-            services: Arc::new(RwLock::new(HashMap::new())),
-            stats: Arc::new(RwLock::new(LoadBalancerStats {
-                total_requests: 0,
-                successful_requests: 0,
-                failed_requests: 0,
-                average_response_time: 0.0,
-                active_connections: 0,
-                service_stats: HashMap::new(),
-            })),
-            current_index: Arc::new(RwLock::new(HashMap::new())),
-        })
+impl Drop for Selection {
+    fn drop(&mut self) {
+        self.stats.in_flight.fetch_sub(1, Ordering::Relaxed);
     }
+}
 
-    /// Add a service to the load balancer
-    pub async fn add_service(&self, service_name: String, service: Service) -> Result<()> {
-        debug!("Adding service: {} to load balancer", service_name);
-        
-        let mut services = self.services.write().await;
-        services.entry(service_name.clone()).or_insert_with(Vec::new).push(service);
-        
-        info!("Service added to load balancer: {}", service_name);
-        Ok(())
-    }
+/// Consistent hash ring over a set of instances
+struct HashRing {
+    instances: Vec<ServiceInstance>,
+    points: Vec<(u64, usize)>,
+}
 
-    /// Remove a service from the load balancer
-    pub async fn remove_service(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Removing service: {} with ID: {} from load balancer", service_name, service_id);
-        
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            service_list.retain(|s| s.name != service_id);
-            if service_list.is_empty() {
-                services.remove(service_name);
+impl HashRing {
+    fn new(instances: &[ServiceInstance]) -> Self {
+        let mut points = Vec::new();
+        for (index, instance) in instances.iter().enumerate() {
+            for node in 0..VIRTUAL_NODES * instance.weight.max(1) {
+                points.push((fnv1a(&format!("{}:{}#{}", instance.host, instance.port, node)), index));
             }
         }
-        
-        info!("Service removed from load balancer: {} - {}", service_name, service_id);
-        Ok(())
+        points.sort_unstable();
+        Self {
+            instances: instances.to_vec(),
+            points,
+        }
     }
 
-    /// Get the next available service for a request
-    pub async fn get_next_service(&self, service_name: &str) -> Result<Option<Service>> {
-        debug!("Getting next service for: {}", service_name);
-        
-        let services = self.services.read().await;
-        let service_list = services.get(service_name);
-        
-        if let Some(service_list) = service_list {
-            if service_list.is_empty() {
-                warn!("No services available for: {}", service_name);
-                return Ok(None);
-            }
-            
-            // Filter healthy services
-            let healthy_services: Vec<&Service> = service_list.iter()
-                .filter(|s| s.status == ServiceStatus::Healthy)
-                .collect();
-            
-            if healthy_services.is_empty() {
-                warn!("No healthy services available for: {}", service_name);
-                return Ok(None);
-            }
-            
-            // Select service based on algorithm
-            let selected_service = self.select_service(healthy_services, service_name).await?;
-            
-            debug!("Selected service: {} for request", selected_service.name);
-            Ok(Some(selected_service.clone()))
-        } else {
-            warn!("Service not found: {}", service_name);
-            Ok(None)
+    fn get(&self, key: &str) -> Option<usize> {
+        let hash = fnv1a(key);
+        let position = self.points.partition_point(|(point, _)| *point < hash);
+        self.points
+            .get(position)
+            .or_else(|| self.points.first())
+            .map(|(_, index)| *index)
+    }
+}
+
+/// Balancing state of one service
+struct ServiceBalancer {
+    config: LoadBalancingConfig,
+    client: reqwest::Client,
+    next: AtomicUsize,
+    stats: Mutex<HashMap<ServiceInstance, Arc<InstanceStats>>>,
+    ring: Mutex<Option<HashRing>>,
+}
+
+impl ServiceBalancer {
+    fn new(config: LoadBalancingConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .pool_max_idle_per_host(config.max_idle_connections.unwrap_or(DEFAULT_MAX_IDLE_CONNECTIONS))
+            .pool_idle_timeout(config.idle_timeout_secs.map(Duration::from_secs).unwrap_or(DEFAULT_IDLE_TIMEOUT))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            client,
+            next: AtomicUsize::new(0),
+            stats: Mutex::new(HashMap::new()),
+            ring: Mutex::new(None),
         }
     }
 
-    /// Select a service based on the configured algorithm
-    async fn select_service(&self, services: Vec<&Service>, service_name: &str) -> Result<Service> {
-        let algorithm = self.parse_algorithm(&self.config.algorithm)?;
-        
-        match algorithm {
-            LoadBalancingAlgorithm::RoundRobin => {
-                self.round_robin_selection(services, service_name).await
-            }
-            LoadBalancingAlgorithm::LeastConnections => {
-                self.least_connections_selection(services).await
+    fn pick(&self, instances: &[ServiceInstance], affinity_key: Option<&str>) -> usize {
+        match (self.config.strategy, affinity_key) {
+            (LoadBalancingStrategy::ConsistentHash, Some(key)) => {
+                let mut ring = self.ring.lock().unwrap();
+                if ring.as_ref().is_none_or(|ring| ring.instances != instances) {
+                    *ring = Some(HashRing::new(instances));
+                }
+                ring.as_ref().and_then(|ring| ring.get(key)).unwrap_or(0)
             }
-            LoadBalancingAlgorithm::WeightedRoundRobin => {
-                self.weighted_round_robin_selection(services, service_name).await
+            (LoadBalancingStrategy::LeastConnections, _) => {
+                let stats = self.stats.lock().unwrap();
+                let start = self.next.fetch_add(1, Ordering::Relaxed);
+                // Start the scan at a rotating offset so ties are spread out
+                (0..instances.len())
+                    .map(|offset| (start + offset) % instances.len())
+                    .min_by_key(|index| {
+                        stats
+                            .get(&instances[*index])
+                            .map_or(0, |stats| stats.in_flight.load(Ordering::Relaxed))
+                    })
+                    .unwrap_or(0)
             }
-            LoadBalancingAlgorithm::IpHash => {
-                self.ip_hash_selection(services).await
+            // Consistent hashing without a key degrades to round-robin
+            _ => {
+                let total: u64 = instances.iter().map(|instance| u64::from(instance.weight.max(1))).sum();
+                let mut slot = self.next.fetch_add(1, Ordering::Relaxed) as u64 % total;
+                for (index, instance) in instances.iter().enumerate() {
+                    let weight = u64::from(instance.weight.max(1));
+                    if slot < weight {
+                        return index;
+                    }
+                    slot -= weight;
+                }
+                0
             }
         }
     }
 
-    /// Round robin selection
-    async fn round_robin_selection(&self, services: Vec<&Service>, service_name: &str) -> Result<Service> {
-        let mut current_index = self.current_index.write().await;
-        let index = current_index.entry(service_name.to_string()).or_insert(0);
-        
-        let selected_service = services[*index % services.len()].clone();
-        *index = (*index + 1) % services.len();
-        
-        Ok(selected_service)
-    }
-
-    /// Least connections selection
-    async fn least_connections_selection(&self, services: Vec<&Service>) -> Result<Service> {
-        let mut least_connections = u32::MAX;
-        let mut selected_service = services[0].clone();
-        
-        for service in services {
-            // TODO: Get actual connection count from service
-            let connection_count = 0; // Placeholder
-            
-            if connection_count < least_connections {
-                least_connections = connection_count;
-                selected_service = service.clone();
-            }
-        }
-        
-        Ok(selected_service)
+    fn track(&self, instance: &ServiceInstance) -> Arc<InstanceStats> {
+        let stats = self.stats.lock().unwrap().entry(instance.clone()).or_default().clone();
+        stats.requests.fetch_add(1, Ordering::Relaxed);
+        stats.in_flight.fetch_add(1, Ordering::Relaxed);
+        stats
     }
+}
 
-    /// Weighted round robin selection
-    async fn weighted_round_robin_selection(&self, services: Vec<&Service>, service_name: &str) -> Result<Service> {
-        // TODO: Implement weighted round robin
-        // For now, fall back to round robin
-        self.round_robin_selection(services, service_name).await
-    }
+/// Load balancers of every service
+pub struct LoadBalancer {
+    configs: HashMap<String, LoadBalancingConfig>,
+    services: RwLock<HashMap<String, Arc<ServiceBalancer>>>,
+}
 
-    /// IP hash selection
-    async fn ip_hash_selection(&self, services: Vec<&Service>) -> Result<Service> {
-        // TODO: Implement IP hash selection
-        // For now, select the first service
-        Ok(services[0].clone())
+impl std::fmt::Debug for LoadBalancer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadBalancer").field("configs", &self.configs).finish()
     }
+}
 
-    /// Update service health status
-    pub async fn update_service_health(&self, service_name: &str, service_id: &str, status: ServiceStatus) -> Result<()> {
-        debug!("Updating service health: {} - {} to {:?}", service_name, service_id, status);
-        
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.status = status;
-                    service.last_health_check = Some(SystemTime::now());
-                    break;
-                }
-            }
+impl LoadBalancer {
+    /// Create load balancers from the routing configuration
+    pub fn from_config(routing: &RoutingConfig) -> Self {
+        Self {
+            configs: routing.load_balancing.clone(),
+            services: RwLock::new(HashMap::new()),
         }
-        
-        Ok(())
     }
 
-    /// Update service response time
-    pub async fn update_service_response_time(&self, service_name: &str, service_id: &str, response_time: u64) -> Result<()> {
-        debug!("Updating service response time: {} - {} to {}ms", service_name, service_id, response_time);
-        
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.response_time = Some(response_time);
-                    break;
-                }
-            }
-        }
-        
-        // Update statistics
-        self.update_stats(service_name, true, response_time).await?;
-        
-        Ok(())
+    /// Header holding a service's affinity key
+    pub fn hash_header(&self, service: &str) -> &str {
+        self.configs
+            .get(service)
+            .and_then(|config| config.hash_header.as_deref())
+            .unwrap_or(DEFAULT_HASH_HEADER)
     }
 
-    /// Record service error
-    pub async fn record_service_error(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Recording service error: {} - {}", service_name, service_id);
-        
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.error_count += 1;
-                    break;
-                }
-            }
+    /// Pick an instance of a service
+    ///
+    /// `affinity_key` (usually the player ID) is only used by consistent hashing.
+    /// Returns `None` if the service has no instances.
+    pub fn select(&self, service: &str, instances: &[ServiceInstance], affinity_key: Option<&str>) -> Option<Selection> {
+        if instances.is_empty() {
+            return None;
         }
-        
-        // Update statistics
-        self.update_stats(service_name, false, 0).await?;
-        
-        Ok(())
+        let balancer = self.balancer(service);
+        let instance = instances[balancer.pick(instances, affinity_key)].clone();
+        let stats = balancer.track(&instance);
+        Some(Selection {
+            instance,
+            client: balancer.client.clone(),
+            stats,
+        })
     }
 
-    /// Record service success
-    pub async fn record_service_success(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Recording service success: {} - {}", service_name, service_id);
-        
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.success_count += 1;
-                    break;
-                }
-            }
-        }
-        
-        Ok(())
+    /// Request distribution per service and instance
+    pub fn distribution(&self) -> HashMap<String, Vec<InstanceDistribution>> {
+        let services = self.services.read().unwrap();
+        services
+            .iter()
+            .map(|(service, balancer)| {
+                let mut instances: Vec<_> = balancer
+                    .stats
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|(instance, stats)| InstanceDistribution {
+                        instance: instance.address(),
+                        requests: stats.requests.load(Ordering::Relaxed),
+                        in_flight: stats.in_flight.load(Ordering::Relaxed),
+                    })
+                    .collect();
+                instances.sort_by(|a, b| a.instance.cmp(&b.instance));
+                (service.clone(), instances)
+            })
+            .collect()
     }
 
-    /// Update load balancer statistics
-    async fn update_stats(&self, service_name: &str, success: bool, response_time: u64) -> Result<()> {
-        let mut stats = self.stats.write().await;
-        
-        stats.total_requests += 1;
-        if success {
-            stats.successful_requests += 1;
-        } else {
-            stats.failed_requests += 1;
+    fn balancer(&self, service: &str) -> Arc<ServiceBalancer> {
+        if let Some(balancer) = self.services.read().unwrap().get(service) {
+            return balancer.clone();
         }
-        
-        // Update average response time
-        if response_time > 0 {
-            let total_time = stats.average_response_time * (stats.total_requests - 1) as f64 + response_time as f64;
-            stats.average_response_time = total_time / stats.total_requests as f64;
-        }
-        
-        // Update service-specific stats
-        let service_stats = stats.service_stats.entry(service_name.to_string()).or_insert(ServiceStats {
-            requests: 0,
-            successful_requests: 0,
-            failed_requests: 0,
-            average_response_time: 0.0,
-            error_rate: 0.0,
-            last_request: None,
-        });
-        
-        service_stats.requests += 1;
-        if success {
-            service_stats.successful_requests += 1;
-        } else {
-            service_stats.failed_requests += 1;
-        }
-        
-        if response_time > 0 {
-            let total_time = service_stats.average_response_time * (service_stats.requests - 1) as f64 + response_time as f64;
-            service_stats.average_response_time = total_time / service_stats.requests as f64;
-        }
-        
-        service_stats.error_rate = service_stats.failed_requests as f64 / service_stats.requests as f64;
-        service_stats.last_request = Some(SystemTime::now());
-        
-        Ok(())
+        let mut services = self.services.write().unwrap();
+        services
+            .entry(service.to_string())
+            .or_insert_with(|| Arc::new(ServiceBalancer::new(self.configs.get(service).cloned().unwrap_or_default())))
+            .clone()
     }
+}
 
-    /// Get load balancer statistics
-    pub async fn get_stats(&self) -> Result<LoadBalancerStats> {
-        let stats = self.stats.read().await;
-        Ok(stats.clone())
-    }
+/// 64-bit FNV-1a, stable across processes so every gateway replica agrees
+pub(crate) fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
 
-    /// Get service statistics
-    pub async fn get_service_stats(&self, service_name: &str) -> Result<Option<ServiceStats>> {
-        let stats = self.stats.read().await;
-        Ok(stats.service_stats.get(service_name).cloned())
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ServiceDiscoveryConfig;
 
-    /// Get all services
-    pub async fn get_services(&self) -> Result<HashMap<String, Vec<Service>>> {
-        let services = self.services.read().await;
-        Ok(services.clone())
+    fn balancer(strategy: LoadBalancingStrategy) -> LoadBalancer {
+        let routing = RoutingConfig {
+            service_discovery: ServiceDiscoveryConfig {
+                backend: Default::default(),
+                refresh_secs: None,
+                consul: None,
+                dns_srv: None,
+                static_services: HashMap::new(),
+            },
+            routes: Vec::new(),
+            load_balancing: HashMap::from([(
+                "world".to_string(),
+                LoadBalancingConfig {
+                    strategy,
+                    ..LoadBalancingConfig::default()
+                },
+            )]),
+            circuit_breakers: HashMap::new(),
+            grpc_routes: Vec::new(),
+            traffic_splits: HashMap::new(),
+            traffic_split_admin_roles: Vec::new(),
+        };
+        LoadBalancer::from_config(&routing)
     }
 
-    /// Get services for a specific service name
-    pub async fn get_services_for_name(&self, service_name: &str) -> Result<Vec<Service>> {
-        let services = self.services.read().await;
-        Ok(services.get(service_name).cloned().unwrap_or_default())
+    fn instances(count: u16) -> Vec<ServiceInstance> {
+        (0..count).map(|i| ServiceInstance::new("10.0.0.1", 9000 + i)).collect()
     }
 
-    /// Check if service is available
-    pub async fn is_service_available(&self, service_name: &str) -> Result<bool> {
-        let services = self.services.read().await;
-        if let Some(service_list) = services.get(service_name) {
-            Ok(service_list.iter().any(|s| s.status == ServiceStatus::Healthy))
-        } else {
-            Ok(false)
-        }
-    }
+    #[test]
+    fn test_round_robin_honors_weights() {
+        let lb = balancer(LoadBalancingStrategy::RoundRobin);
+        let mut instances = instances(2);
+        instances[1].weight = 3;
 
-    /// Parse load balancing algorithm from string
-    fn parse_algorithm(&self, algorithm: &str) -> Result<LoadBalancingAlgorithm> {
-        match algorithm.to_lowercase().as_str() {
-            "round_robin" => Ok(LoadBalancingAlgorithm::RoundRobin),
-            "least_connections" => Ok(LoadBalancingAlgorithm::LeastConnections),
-            "weighted_round_robin" => Ok(LoadBalancingAlgorithm::WeightedRoundRobin),
-            "ip_hash" => Ok(LoadBalancingAlgorithm::IpHash),
-            _ => Err(ApiGatewayError::LoadBalancing(format!("Unknown load balancing algorithm: {}", algorithm))),
-        }
-    }
+        let ports: Vec<_> = (0..8)
+            .map(|_| lb.select("world", &instances, None).unwrap().instance.port)
+            .collect();
+        assert_eq!(ports, vec![9000, 9001, 9001, 9001, 9000, 9001, 9001, 9001]);
 
-    /// Start health checking
-    pub async fn start_health_checking(&self) -> Result<()> {
-        if !self.config.health_check.enabled {
-            return Ok(());
-        }
-        
-        info!("Starting health checking for load balancer");
-        
-        // TODO: Implement health checking
-        // This is a placeholder implementation
-        
-        Ok(())
+        let distribution = &lb.distribution()["world"];
+        assert_eq!(distribution[0].requests, 2);
+        assert_eq!(distribution[1].requests, 6);
+        assert_eq!(distribution[1].in_flight, 0);
     }
 
-    /// Stop health checking
-    pub async fn stop_health_checking(&self) -> Result<()> {
-        info!("Stopping health checking for load balancer");
-        
-        // TODO: Implement health checking stop
-        // This is a placeholder implementation
-        
-        Ok(())
+    #[test]
+    fn test_least_connections_avoids_busy_instances() {
+        let lb = balancer(LoadBalancingStrategy::LeastConnections);
+        let instances = instances(3);
+
+        let first = lb.select("world", &instances, None).unwrap();
+        let second = lb.select("world", &instances, None).unwrap();
+        let third = lb.select("world", &instances, None).unwrap();
+        let mut ports = vec![first.instance.port, second.instance.port, third.instance.port];
+        ports.sort_unstable();
+        assert_eq!(ports, vec![9000, 9001, 9002]);
+
+        let freed = second.instance.port;
+        drop(second);
+        let next = lb.select("world", &instances, None).unwrap();
+        assert_eq!(next.instance.port, freed);
+        drop((first, third));
     }
-}
 
+    #[test]
+    fn test_consistent_hash_keeps_players_sticky() {
+        let lb = balancer(LoadBalancingStrategy::ConsistentHash);
+        let instances = instances(4);
+        let players: Vec<String> = (0..200).map(|i| format!("player-{}", i)).collect();
+        let assign = |instances: &[ServiceInstance]| -> Vec<u16> {
+            players
+                .iter()
+                .map(|player| lb.select("world", instances, Some(player)).unwrap().instance.port)
+                .collect()
+        };
+
+        let before = assign(&instances);
+        assert_eq!(before, assign(&instances));
 
+        // Removing one instance only moves the players that were on it
+        let after = assign(&instances[..3]);
+        for (old, new) in before.iter().zip(&after) {
+            if *old != 9003 {
+                assert_eq!(old, new);
+            }
+        }
+    }
+}
@@ -1,20 +1,37 @@
 use axum::{
     extract::{Path, State},
     http::Method,
-    response::Response,
     routing::{get, post, put, delete, options},
-    Router,
+    Json, Router,
     body::Bytes,
 };
 use tower_http::cors::CorsLayer;
+use std::collections::HashMap;
 use std::net::SocketAddr;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use shared::telemetry::{self, TelemetryOptions};
 
+mod auth;
+mod caching;
+mod circuit_breaker;
 mod config;
+mod grpc;
+mod health;
+mod load_balancing;
+mod maintenance;
+mod monitoring;
 mod proxy;
+mod request_context;
+mod retry;
+mod service_discovery;
+mod state;
+mod traffic_split;
+mod websocket;
 
 use config::ApiGatewayConfig;
-use proxy::{proxy_request, proxy_request_with_path, proxy_request_health, proxy_request_api_root, get_services_health};
+use load_balancing::InstanceDistribution;
+use traffic_split::GroupSnapshot;
+use state::GatewayState;
+use proxy::{proxy_request, proxy_request_with_path, proxy_request_health, proxy_request_api_root};
 
 #[tokio::main]
 async fn main() {
@@ -34,14 +51,12 @@ async fn main() {
             std::fs::File::create("api-gateway.log").unwrap()
         });
     
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "api_gateway=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_writer(file))
-        .with(tracing_subscriber::fmt::layer()) // Also output to console
-        .init();
+    // Logs go to the file and the console, spans and metrics to OTLP when configured
+    let telemetry = telemetry::init_with(
+        &TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .with_default_filter("api_gateway=debug,tower_http=debug"),
+        vec![Box::new(tracing_subscriber::fmt::layer().with_writer(file)) as telemetry::BoxedLayer],
+    );
 
     // Load configuration
     let config_path = std::env::var("CONFIG_PATH")
@@ -71,16 +86,53 @@ async fn main() {
     }
     tracing::info!("  Routes:");
     for route in &config.routing.routes {
-        tracing::info!("    {} -> {} (methods: {:?})", route.path, route.service, route.methods);
+        tracing::info!("    {} -> {} (methods: {:?}, auth: {:?})", route.path, route.service, route.methods, route.auth);
     }
+    for route in &config.routing.grpc_routes {
+        tracing::info!("    gRPC {} -> {} (tls: {}, auth: {:?})", route.grpc_service, route.service, route.tls, route.auth);
+    }
+
+    let state = match GatewayState::new(config.clone()) {
+        Ok(state) => state,
+        Err(e) => {
+            tracing::error!("❌ Invalid gateway configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("  Auth: {:?}", state.auth);
+    tracing::info!("  Discovery: {:?}", state.discovery);
+    tracing::info!("  Load balancing: {:?}", state.load_balancer);
+    tracing::info!("  Cache: {:?}", state.cache);
+    tracing::info!("  Traffic splits: {:?}", config.routing.traffic_splits);
+
+    // Resolve upstreams now, then keep them fresh in the background
+    state.discovery.refresh().await;
+    let _discovery_refresh = state.discovery.spawn_refresh();
+
+    // Follow maintenance mode toggled through admin-cli, and upstream instances shutting down
+    if let Some(bus) = maintenance::connect(&config.maintenance).await {
+        maintenance::follow(&bus, &state.maintenance).await;
+        match state.discovery.follow_lifecycle(&bus).await {
+            Ok(_) => tracing::info!("🛑 Following service lifecycle announcements"),
+            Err(e) => tracing::error!("❌ Cannot follow service lifecycle announcements: {}", e),
+        }
+    }
+    let shutdown = state.shutdown.clone();
+    shutdown.listen_for_signals();
 
     // Check services health
-    let _health_status = get_services_health(&config).await;
+    health::log(&state.health.check(&state).await);
 
     // Create router with routes from configuration
     let mut app = Router::new()
         .route("/", get(root))
-        .route("/services/health", get(services_health_handler));
+        .route("/services/health", get(health::services_health_handler))
+        .route("/metrics", get(monitoring::metrics_handler))
+        .route("/services/load-balancing", get(load_balancing_handler))
+        .route("/cache/invalidate", post(caching::invalidate_handler))
+        .route("/maintenance", get(maintenance::status_handler))
+        .route("/traffic-splits", get(traffic_splits_handler))
+        .route("/traffic-splits/:service", put(traffic_split::update_weights_handler));
     
     // Add routes from configuration
     for route in &config.routing.routes {
@@ -152,13 +204,27 @@ async fn main() {
         }
     }
     
+    // gRPC routes: /{package.Service}/{Method}
+    for route in &config.routing.grpc_routes {
+        let grpc_pattern = format!("/{}/:method", route.grpc_service);
+        tracing::info!("🔧 Registering gRPC route: {} -> {}", grpc_pattern, route.service);
+        app = app.route(&grpc_pattern, post(grpc::proxy_grpc));
+    }
+
+    let trace_headers = [
+        axum::http::HeaderName::from_static(shared::trace::REQUEST_ID_HEADER),
+        axum::http::HeaderName::from_static(shared::trace::TRACEPARENT_HEADER),
+    ];
     let app = app
-        .with_state(config.clone())
+        .with_state(state.clone())
+        .layer(axum::middleware::from_fn_with_state(state, monitoring::track))
+        .layer(axum::middleware::from_fn(request_context::propagate))
         .layer(
             CorsLayer::new()
                 .allow_origin("http://localhost:3200".parse::<axum::http::HeaderValue>().unwrap())
                 .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT, axum::http::Method::DELETE, axum::http::Method::OPTIONS])
-                .allow_headers([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION])
+                .allow_headers([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION, trace_headers[0].clone(), trace_headers[1].clone()])
+                .expose_headers(trace_headers)
         );
 
     // Start server
@@ -166,20 +232,31 @@ async fn main() {
     tracing::info!("🚀 API Gateway server starting on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service()).await.unwrap();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    // Upgraded WebSocket connections are not tracked by the server
+    shutdown.wait_idle().await;
+    tracing::info!("👋 API Gateway stopped");
+    telemetry.shutdown();
 }
 
 async fn root() -> &'static str {
     "Hello from API Gateway!"
 }
 
-async fn services_health_handler(State(config): State<ApiGatewayConfig>) -> String {
-    let health_status = get_services_health(&config).await;
-    
-    let mut response = String::from("Services Health Status:\n");
-    for (service_name, is_healthy) in health_status {
-        response.push_str(&format!("  {}: {}\n", service_name, if is_healthy { "✅ Healthy" } else { "❌ Unhealthy" }));
-    }
-    
-    response
-}
\ No newline at end of file
+async fn load_balancing_handler(
+    State(state): State<GatewayState>,
+) -> Json<HashMap<String, Vec<InstanceDistribution>>> {
+    Json(state.load_balancer.distribution())
+}
+
+/// Weights and per-group metrics of every traffic split
+async fn traffic_splits_handler(
+    State(state): State<GatewayState>,
+) -> Json<HashMap<String, Vec<GroupSnapshot>>> {
+    Json(state.traffic_splits.snapshot())
+}
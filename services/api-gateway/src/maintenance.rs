@@ -0,0 +1,99 @@
+//! Maintenance mode at the gateway.
+//!
+//! The gateway follows the maintenance state published by `admin-cli world maintenance`
+//! and, while it is enabled, refuses new game connections (WebSocket upgrades) from
+//! callers without one of `maintenance.admin_roles`. Logins are checked by
+//! user-management, which knows the user's roles before issuing a token.
+//! `GET /maintenance` tells clients whether to show the maintenance notice.
+
+use crate::auth::GatewayClaims;
+use crate::config::MaintenanceConfig;
+use crate::state::GatewayState;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use shared::error::{ApiError, ErrorCode};
+use shared::events::{EventBus, NatsEventBus};
+use shared::maintenance::{MaintenanceFlag, MaintenanceState};
+use tracing::{error, info};
+
+/// Connect to the event bus, when one is configured
+///
+/// The same connection carries service lifecycle announcements, see
+/// [`ServiceRegistry::follow_lifecycle`](crate::service_discovery::ServiceRegistry::follow_lifecycle).
+pub async fn connect(config: &MaintenanceConfig) -> Option<NatsEventBus> {
+    let Some(nats_url) = &config.nats_url else {
+        info!("🛠️ Maintenance: no event bus configured, maintenance mode is unavailable");
+        return None;
+    };
+    let bus = match NatsEventBus::connect(nats_url).await {
+        Ok(bus) => bus,
+        Err(e) => {
+            error!("❌ Maintenance: cannot connect to {}: {}", nats_url, e);
+            return None;
+        }
+    };
+    match &config.subject_prefix {
+        Some(prefix) => match bus.with_subject_prefix(prefix) {
+            Ok(bus) => Some(bus),
+            Err(e) => {
+                error!("❌ Maintenance: invalid subject prefix {}: {}", prefix, e);
+                None
+            }
+        },
+        None => Some(bus),
+    }
+}
+
+/// Follow the maintenance state on the event bus
+pub async fn follow(bus: &dyn EventBus, flag: &MaintenanceFlag) {
+    match flag.follow(bus).await {
+        Ok(_) => info!("🛠️ Maintenance: following state on the {} bus", bus.backend_name()),
+        Err(e) => error!("❌ Maintenance: cannot subscribe: {}", e),
+    }
+}
+
+/// Whether the caller may connect during maintenance
+pub fn is_admin(claims: Option<&GatewayClaims>, admin_roles: &[String]) -> bool {
+    claims.is_some_and(|claims| claims.roles.iter().any(|role| admin_roles.contains(role)))
+}
+
+/// Response for callers turned away during maintenance
+pub fn rejection(state: &MaintenanceState) -> Response {
+    let error = ApiError::new(ErrorCode::ServiceUnavailable, state.notice());
+    (StatusCode::SERVICE_UNAVAILABLE, Json(error)).into_response()
+}
+
+/// `GET /maintenance`
+pub async fn status_handler(State(state): State<GatewayState>) -> Json<MaintenanceState> {
+    Json(state.maintenance.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(roles: &[&str]) -> GatewayClaims {
+        GatewayClaims {
+            sub: "user-1".to_string(),
+            exp: 0,
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_admins_are_recognized_by_role() {
+        let admin_roles = MaintenanceConfig::default().admin_roles;
+        assert!(is_admin(Some(&claims(&["player", "gm"])), &admin_roles));
+        assert!(!is_admin(Some(&claims(&["player"])), &admin_roles));
+        assert!(!is_admin(None, &admin_roles));
+    }
+
+    #[test]
+    fn test_rejection_shows_the_notice() {
+        let state = MaintenanceState::on("ops", Some("Patch 1.2".to_string()), None);
+        let response = rejection(&state);
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
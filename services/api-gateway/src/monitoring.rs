@@ -1,330 +1,292 @@
-//! Monitoring service for API Gateway
-
-use crate::config::{Config, MonitoringConfig};
-use crate::errors::Result;
-use crate::types::{Metrics, PerformanceMetrics, HealthCheck, SecurityEvent};
+//! Access logging and Prometheus metrics.
+//!
+//! The [`track`] middleware writes one structured access log line per request and
+//! feeds the request counters, latency histograms and in-flight gauge. Handlers note
+//! what they learned about a request (matched route, user, upstream) with the
+//! `record_*` functions while it is being served. Upstream in-flight counts and
+//! circuit breaker states are read from the live state on every `/metrics` scrape.
+
+use crate::circuit_breaker::{BreakerState, BreakerStatus};
+use crate::load_balancing::InstanceDistribution;
+use crate::state::GatewayState;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use shared::trace::REQUEST_ID_HEADER;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::SystemTime;
-use tracing::{info, debug, warn, error};
-
-/// Monitoring service
-#[derive(Debug, Clone)]
-pub struct MonitoringService {
-    config: MonitoringConfig,
-    metrics: Arc<tokio::sync::RwLock<Metrics>>,
-    health_checks: Arc<tokio::sync::RwLock<HashMap<String, HealthCheck>>>,
-    security_events: Arc<tokio::sync::RwLock<Vec<SecurityEvent>>>,
-    performance_metrics: Arc<tokio::sync::RwLock<PerformanceMetrics>>,
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// Label value of requests that matched no route or reached no upstream
+const NONE_LABEL: &str = "none";
+
+/// Latency buckets in seconds, from cache hits to slow upstreams
+const LATENCY_BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// What the handlers learned about the request being served
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct AccessDetails {
+    route: Option<String>,
+    user_id: Option<String>,
+    upstream: Option<String>,
+    instance: Option<String>,
 }
 
-/// Initialize monitoring service
-pub async fn init(config: &Config) -> Result<MonitoringService> {
-    MonitoringService::new(&config.clone())
+tokio::task_local! {
+    static ACCESS: RefCell<AccessDetails>;
 }
 
-impl MonitoringService {
-    /// Create a new monitoring service
-    pub fn new(config: &Config) -> Result<Self> {
-        let monitoring_config = config.monitoring.clone();
-        
-        Ok(Self {
-            config: monitoring_config,
-            metrics: Arc::new(tokio::sync::RwLock::new(Metrics {
-                request_count: 0,
-                request_duration: 0.0,
-                error_count: 0,
-                active_connections: 0,
-                rate_limit_hits: 0,
-                circuit_breaker_state: HashMap::new(),
-                service_health: HashMap::new(),
-            })),
-            health_checks: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-            security_events: Arc::new(tokio::sync::RwLock::new(Vec::new())),
-            performance_metrics: Arc::new(tokio::sync::RwLock::new(PerformanceMetrics {
-                cpu_usage: 0.0,
-                memory_usage: 0,
-                disk_usage: 0,
-                network_io: crate::types::NetworkIO {
-                    bytes_sent: 0,
-                    bytes_received: 0,
-                    packets_sent: 0,
-                    packets_received: 0,
-                },
-                request_metrics: crate::types::RequestMetrics {
-                    total_requests: 0,
-                    requests_per_second: 0.0,
-                    average_response_time: 0.0,
-                    p50_response_time: 0.0,
-                    p95_response_time: 0.0,
-                    p99_response_time: 0.0,
-                    error_rate: 0.0,
-                },
-            })),
-        })
-    }
-
-    /// Start the monitoring service
-    pub async fn start(&self) -> Result<()> {
-        info!("Starting monitoring service");
-
-        // Start metrics collection
-        self.start_metrics_collection().await?;
-
-        // Start health checking
-        self.start_health_checking().await?;
-
-        // Start performance monitoring
-        self.start_performance_monitoring().await?;
-
-        info!("Monitoring service started successfully");
-        Ok(())
-    }
-
-    /// Stop the monitoring service
-    pub async fn stop(&self) -> Result<()> {
-        info!("Stopping monitoring service");
-
-        // Stop metrics collection
-        self.stop_metrics_collection().await?;
-
-        // Stop health checking
-        self.stop_health_checking().await?;
-
-        // Stop performance monitoring
-        self.stop_performance_monitoring().await?;
-
-        info!("Monitoring service stopped successfully");
-        Ok(())
-    }
-
-    /// Record a request
-    pub async fn record_request(&self, duration: f64, status_code: u16) -> Result<()> {
-        debug!("Recording request: {}ms, status: {}", duration, status_code);
-
-        let mut metrics = self.metrics.write().await;
-        metrics.request_count += 1;
-        metrics.request_duration = (metrics.request_duration + duration) / 2.0;
-
-        if status_code >= 400 {
-            metrics.error_count += 1;
-        }
+/// Note the configured route serving the current request
+pub fn record_route(route: &str) {
+    let _ = ACCESS.try_with(|access| access.borrow_mut().route = Some(route.to_string()));
+}
 
-        Ok(())
-    }
+/// Note the authenticated user of the current request
+pub fn record_user(user_id: &str) {
+    let _ = ACCESS.try_with(|access| access.borrow_mut().user_id = Some(user_id.to_string()));
+}
 
-    /// Record a rate limit hit
-    pub async fn record_rate_limit_hit(&self, rule_name: &str, ip: &str) -> Result<()> {
-        debug!("Recording rate limit hit: {} for IP: {}", rule_name, ip);
+/// Note the upstream service and instance the current request was sent to
+pub fn record_upstream(service: &str, instance: &str) {
+    let _ = ACCESS.try_with(|access| {
+        let mut access = access.borrow_mut();
+        access.upstream = Some(service.to_string());
+        access.instance = Some(instance.to_string());
+    });
+}
 
-        let mut metrics = self.metrics.write().await;
-        metrics.rate_limit_hits += 1;
+/// Prometheus metrics of the gateway
+#[derive(Clone)]
+pub struct GatewayMetrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    in_flight: IntGauge,
+    upstream_in_flight: IntGaugeVec,
+    breaker_state: IntGaugeVec,
+}
 
-        Ok(())
+impl std::fmt::Debug for GatewayMetrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GatewayMetrics")
+            .field("in_flight", &self.in_flight.get())
+            .finish()
     }
+}
 
-    /// Record circuit breaker state change
-    pub async fn record_circuit_breaker_state(&self, service_name: &str, state: crate::types::CircuitBreakerState) -> Result<()> {
-        debug!("Recording circuit breaker state change: {} -> {:?}", service_name, state);
-
-        let mut metrics = self.metrics.write().await;
-        metrics.circuit_breaker_state.insert(service_name.to_string(), state);
+impl GatewayMetrics {
+    /// Register the gateway metrics
+    pub fn new() -> Result<Self, prometheus::Error> {
+        let registry = Registry::new();
+        let requests = IntCounterVec::new(
+            Opts::new("gateway_requests_total", "Requests handled by the gateway"),
+            &["route", "upstream", "method", "status"],
+        )?;
+        let latency = HistogramVec::new(
+            HistogramOpts::new("gateway_request_duration_seconds", "Time to the response head, in seconds")
+                .buckets(LATENCY_BUCKETS.to_vec()),
+            &["route", "upstream"],
+        )?;
+        let in_flight = IntGauge::new("gateway_requests_in_flight", "Requests being handled")?;
+        let upstream_in_flight = IntGaugeVec::new(
+            Opts::new("gateway_upstream_requests_in_flight", "Requests waiting on an upstream service"),
+            &["upstream"],
+        )?;
+        let breaker_state = IntGaugeVec::new(
+            Opts::new(
+                "gateway_circuit_breaker_state",
+                "Circuit breaker state per service (0 closed, 1 half-open, 2 open)",
+            ),
+            &["service"],
+        )?;
+
+        registry.register(Box::new(requests.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        registry.register(Box::new(in_flight.clone()))?;
+        registry.register(Box::new(upstream_in_flight.clone()))?;
+        registry.register(Box::new(breaker_state.clone()))?;
 
-        Ok(())
+        Ok(Self {
+            registry,
+            requests,
+            latency,
+            in_flight,
+            upstream_in_flight,
+            breaker_state,
+        })
     }
 
-    /// Record service health
-    pub async fn record_service_health(&self, service_name: &str, health: crate::types::ServiceStatus) -> Result<()> {
-        debug!("Recording service health: {} -> {:?}", service_name, health);
-
-        let mut metrics = self.metrics.write().await;
-        metrics.service_health.insert(service_name.to_string(), health);
-
-        Ok(())
+    fn observe(&self, access: &AccessDetails, method: &str, status: u16, elapsed: Duration) {
+        let route = access.route.as_deref().unwrap_or(NONE_LABEL);
+        let upstream = access.upstream.as_deref().unwrap_or(NONE_LABEL);
+        self.requests
+            .with_label_values(&[route, upstream, method, &status.to_string()])
+            .inc();
+        self.latency
+            .with_label_values(&[route, upstream])
+            .observe(elapsed.as_secs_f64());
     }
 
-    /// Record security event
-    pub async fn record_security_event(&self, event: SecurityEvent) -> Result<()> {
-        debug!("Recording security event: {:?}", event.event_type);
-
-        let mut security_events = self.security_events.write().await;
-        security_events.push(event);
-
-        // Keep only the last 1000 events
-        if security_events.len() > 1000 {
-            let len = security_events.len();
-            if len > 1000 {
-                security_events.drain(0..len - 1000);
-            }
+    /// Metrics in the Prometheus text format, with live upstream and breaker gauges
+    pub fn render(
+        &self,
+        distribution: &HashMap<String, Vec<InstanceDistribution>>,
+        breakers: &HashMap<String, BreakerStatus>,
+    ) -> String {
+        for (service, instances) in distribution {
+            let in_flight: usize = instances.iter().map(|instance| instance.in_flight).sum();
+            self.upstream_in_flight
+                .with_label_values(&[service.as_str()])
+                .set(in_flight as i64);
+        }
+        for (service, status) in breakers {
+            let state = match status.state {
+                BreakerState::Closed => 0,
+                BreakerState::HalfOpen => 1,
+                BreakerState::Open => 2,
+            };
+            self.breaker_state.with_label_values(&[service.as_str()]).set(state);
         }
 
-        Ok(())
-    }
-
-    /// Get current metrics
-    pub async fn get_metrics(&self) -> Result<Metrics> {
-        let metrics = self.metrics.read().await;
-        Ok(metrics.clone())
-    }
-
-    /// Get health checks
-    pub async fn get_health_checks(&self) -> Result<HashMap<String, HealthCheck>> {
-        let health_checks = self.health_checks.read().await;
-        Ok(health_checks.clone())
-    }
-
-    /// Get security events
-    pub async fn get_security_events(&self) -> Result<Vec<SecurityEvent>> {
-        let security_events = self.security_events.read().await;
-        Ok(security_events.clone())
-    }
-
-    /// Get performance metrics
-    pub async fn get_performance_metrics(&self) -> Result<PerformanceMetrics> {
-        let performance_metrics = self.performance_metrics.read().await;
-        Ok(performance_metrics.clone())
-    }
-
-    /// Start metrics collection
-    async fn start_metrics_collection(&self) -> Result<()> {
-        debug!("Starting metrics collection");
-
-        // TODO: Implement metrics collection
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Stop metrics collection
-    async fn stop_metrics_collection(&self) -> Result<()> {
-        debug!("Stopping metrics collection");
-
-        // TODO: Implement metrics collection stop
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Start health checking
-    async fn start_health_checking(&self) -> Result<()> {
-        debug!("Starting health checking");
-
-        // TODO: Implement health checking
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Stop health checking
-    async fn stop_health_checking(&self) -> Result<()> {
-        debug!("Stopping health checking");
-
-        // TODO: Implement health checking stop
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Start performance monitoring
-    async fn start_performance_monitoring(&self) -> Result<()> {
-        debug!("Starting performance monitoring");
-
-        // TODO: Implement performance monitoring
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Stop performance monitoring
-    async fn stop_performance_monitoring(&self) -> Result<()> {
-        debug!("Stopping performance monitoring");
-
-        // TODO: Implement performance monitoring stop
-        // This is a placeholder implementation
-
-        Ok(())
-    }
-
-    /// Update health check
-    pub async fn update_health_check(&self, service_name: &str, health_check: HealthCheck) -> Result<()> {
-        debug!("Updating health check for service: {}", service_name);
-
-        let mut health_checks = self.health_checks.write().await;
-        health_checks.insert(service_name.to_string(), health_check);
-
-        Ok(())
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("❌ Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
     }
+}
 
-    /// Get health check for service
-    pub async fn get_health_check(&self, service_name: &str) -> Result<Option<HealthCheck>> {
-        let health_checks = self.health_checks.read().await;
-        Ok(health_checks.get(service_name).cloned())
-    }
+/// Middleware writing the access log and request metrics
+pub async fn track(State(state): State<GatewayState>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    let in_flight = InFlight::start(&state.metrics.in_flight);
+    let started = Instant::now();
+    let (response, access) = ACCESS
+        .scope(RefCell::new(AccessDetails::default()), async move {
+            let response = next.run(request).await;
+            (response, ACCESS.with(|access| access.borrow().clone()))
+        })
+        .await;
+    let elapsed = started.elapsed();
+    drop(in_flight);
+
+    let status = response.status().as_u16();
+    state.metrics.observe(&access, &method, status, elapsed);
+    info!(
+        request_id = %request_id,
+        method = %method,
+        path = %path,
+        route = access.route.as_deref().unwrap_or(NONE_LABEL),
+        upstream = access.upstream.as_deref().unwrap_or(NONE_LABEL),
+        instance = access.instance.as_deref().unwrap_or(NONE_LABEL),
+        user_id = access.user_id.as_deref().unwrap_or(NONE_LABEL),
+        status,
+        latency_ms = elapsed.as_millis() as u64,
+        "📒 ACCESS"
+    );
+    response
+}
 
-    /// Check if service is healthy
-    pub async fn is_service_healthy(&self, service_name: &str) -> Result<bool> {
-        let health_checks = self.health_checks.read().await;
-        if let Some(health_check) = health_checks.get(service_name) {
-            Ok(health_check.status == crate::types::ServiceStatus::Healthy)
-        } else {
-            Ok(false)
-        }
-    }
+/// Counts a request as in flight until dropped, also when the client goes away
+struct InFlight(IntGauge);
 
-    /// Get service health status
-    pub async fn get_service_health_status(&self, service_name: &str) -> Result<Option<crate::types::ServiceStatus>> {
-        let health_checks = self.health_checks.read().await;
-        Ok(health_checks.get(service_name).map(|h| h.status.clone()))
+impl InFlight {
+    fn start(gauge: &IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge.clone())
     }
+}
 
-    /// Get all service health statuses
-    pub async fn get_all_service_health_statuses(&self) -> Result<HashMap<String, crate::types::ServiceStatus>> {
-        let health_checks = self.health_checks.read().await;
-        Ok(health_checks.iter().map(|(k, v)| (k.clone(), v.status.clone())).collect())
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.dec();
     }
+}
 
-    /// Get metrics in Prometheus format
-    pub async fn get_prometheus_metrics(&self) -> Result<String> {
-        let metrics = self.metrics.read().await;
-        
-        let mut prometheus_metrics = String::new();
-        
-        // Request count
-        prometheus_metrics.push_str(&format!("# HELP api_gateway_requests_total Total number of requests\n"));
-        prometheus_metrics.push_str(&format!("# TYPE api_gateway_requests_total counter\n"));
-        prometheus_metrics.push_str(&format!("api_gateway_requests_total {}\n", metrics.request_count));
-        
-        // Request duration
-        prometheus_metrics.push_str(&format!("# HELP api_gateway_request_duration_seconds Average request duration in seconds\n"));
-        prometheus_metrics.push_str(&format!("# TYPE api_gateway_request_duration_seconds histogram\n"));
-        prometheus_metrics.push_str(&format!("api_gateway_request_duration_seconds {}\n", metrics.request_duration));
-        
-        // Error count
-        prometheus_metrics.push_str(&format!("# HELP api_gateway_errors_total Total number of errors\n"));
-        prometheus_metrics.push_str(&format!("# TYPE api_gateway_errors_total counter\n"));
-        prometheus_metrics.push_str(&format!("api_gateway_errors_total {}\n", metrics.error_count));
-        
-        // Active connections
-        prometheus_metrics.push_str(&format!("# HELP api_gateway_active_connections Current number of active connections\n"));
-        prometheus_metrics.push_str(&format!("# TYPE api_gateway_active_connections gauge\n"));
-        prometheus_metrics.push_str(&format!("api_gateway_active_connections {}\n", metrics.active_connections));
-        
-        // Rate limit hits
-        prometheus_metrics.push_str(&format!("# HELP api_gateway_rate_limit_hits_total Total number of rate limit hits\n"));
-        prometheus_metrics.push_str(&format!("# TYPE api_gateway_rate_limit_hits_total counter\n"));
-        prometheus_metrics.push_str(&format!("api_gateway_rate_limit_hits_total {}\n", metrics.rate_limit_hits));
-        
-        Ok(prometheus_metrics)
-    }
+/// `GET /metrics`
+pub async fn metrics_handler(State(state): State<GatewayState>) -> Response {
+    let body = state
+        .metrics
+        .render(&state.load_balancer.distribution(), &state.circuit_breakers.statuses());
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        body,
+    )
+        .into_response()
+}
 
-    /// Get monitoring configuration
-    pub fn get_config(&self) -> &MonitoringConfig {
-        &self.config
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_handlers_record_access_details() {
+        let access = ACCESS
+            .scope(RefCell::new(AccessDetails::default()), async {
+                record_route("/api/*");
+                record_user("player-1");
+                tokio::task::yield_now().await;
+                record_upstream("chaos-backend", "10.0.0.5:8081");
+                ACCESS.with(|access| access.borrow().clone())
+            })
+            .await;
+        assert_eq!(access.route.as_deref(), Some("/api/*"));
+        assert_eq!(access.user_id.as_deref(), Some("player-1"));
+        assert_eq!(access.upstream.as_deref(), Some("chaos-backend"));
+        assert_eq!(access.instance.as_deref(), Some("10.0.0.5:8081"));
+
+        // Outside a request there is nothing to record into
+        record_route("/api/*");
     }
 
-    /// Check if monitoring is enabled
-    pub fn is_enabled(&self) -> bool {
-        self.config.tracing.enabled || self.config.metrics.prometheus.enabled
+    #[test]
+    fn test_render_exports_requests_and_breakers() {
+        let metrics = GatewayMetrics::new().unwrap();
+        let access = AccessDetails {
+            route: Some("/api/*".to_string()),
+            upstream: Some("chaos-backend".to_string()),
+            ..AccessDetails::default()
+        };
+        metrics.observe(&access, "GET", 200, Duration::from_millis(20));
+        metrics.observe(&AccessDetails::default(), "GET", 404, Duration::from_millis(1));
+
+        let distribution = HashMap::from([(
+            "chaos-backend".to_string(),
+            vec![InstanceDistribution {
+                instance: "10.0.0.5:8081".to_string(),
+                requests: 1,
+                in_flight: 3,
+            }],
+        )]);
+        let breakers = HashMap::from([(
+            "chaos-backend".to_string(),
+            BreakerStatus {
+                state: BreakerState::Open,
+                requests: 0,
+                failures: 0,
+                retry_after_secs: Some(5),
+            },
+        )]);
+        let text = metrics.render(&distribution, &breakers);
+
+        assert!(text.contains(
+            r#"gateway_requests_total{method="GET",route="/api/*",status="200",upstream="chaos-backend"} 1"#
+        ));
+        assert!(text.contains(r#"gateway_requests_total{method="GET",route="none",status="404",upstream="none"} 1"#));
+        assert!(text.contains(r#"gateway_request_duration_seconds_count{route="/api/*",upstream="chaos-backend"} 1"#));
+        assert!(text.contains(r#"gateway_upstream_requests_in_flight{upstream="chaos-backend"} 3"#));
+        assert!(text.contains(r#"gateway_circuit_breaker_state{service="chaos-backend"} 2"#));
     }
 }
-
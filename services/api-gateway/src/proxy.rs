@@ -1,60 +1,98 @@
-use crate::config::{ApiGatewayConfig, ServiceConfig, RouteConfig};
+use crate::auth::{client_address_headers, is_trusted_header, GatewayClaims};
+use crate::caching::{CachedResponse, ResponseCache, CACHE_STATUS_HEADER};
+use crate::config::{ApiGatewayConfig, RouteConfig};
+use crate::maintenance;
+use crate::monitoring;
+use crate::retry;
+use crate::state::GatewayState;
+use crate::websocket::{self, ClientUpgrade};
 use axum::{
     body::Bytes,
-    extract::{Path, State},
-    http::{HeaderMap, Method, StatusCode},
-    response::Response,
+    extract::{ws::WebSocketUpgrade, ConnectInfo, Path, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
 };
-use reqwest::Client;
-use std::collections::HashMap;
-use tracing::{error, info, warn};
+use futures::StreamExt;
+use shared::telemetry;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tracing::{error, info, info_span, warn, Instrument};
+
+/// Request as received from the client
+struct ClientRequest {
+    peer: SocketAddr,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+}
 
 /// Proxy handler for routes with path parameters (e.g., /auth/:path)
+#[allow(clippy::too_many_arguments)]
 pub async fn proxy_request_with_path(
-    State(config): State<ApiGatewayConfig>,
+    State(state): State<GatewayState>,
     Path(path): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    upgrade: Option<WebSocketUpgrade>,
     method: Method,
+    uri: Uri,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, StatusCode> {
+    let query = uri.query().map(str::to_string);
+    let upgrade = upgrade.map(|upgrade| ClientUpgrade { upgrade, query, peer });
     // Determine which route this came from based on the request path
-    let route = determine_route_from_path(&config, &path);
-    proxy_request_internal_with_route(&config, Some(path), method, headers, body, route).await
+    let route = determine_route_from_path(&state.config, &path);
+    let request = ClientRequest { peer, method, uri, headers, body };
+    proxy_request_internal_with_route(&state, Some(path), request, route, upgrade).await
 }
 
 /// Proxy handler for health route
 pub async fn proxy_request_health(
-    State(config): State<ApiGatewayConfig>,
+    State(state): State<GatewayState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     method: Method,
+    uri: Uri,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, StatusCode> {
-    let route = config.routing.routes.iter().find(|r| r.path == "/health");
-    proxy_request_internal_with_route(&config, None, method, headers, body, route).await
+    let route = state.config.routing.routes.iter().find(|r| r.path == "/health");
+    let request = ClientRequest { peer, method, uri, headers, body };
+    proxy_request_internal_with_route(&state, None, request, route, None).await
 }
 
 /// Proxy handler for API root route
 pub async fn proxy_request_api_root(
-    State(config): State<ApiGatewayConfig>,
+    State(state): State<GatewayState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
     method: Method,
+    uri: Uri,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, StatusCode> {
-    let route = config.routing.routes.iter().find(|r| r.path == "/api");
+    let route = state.config.routing.routes.iter().find(|r| r.path == "/api");
+    let request = ClientRequest { peer, method, uri, headers, body };
     // For API root, we want to send "/" to the backend service
-    proxy_request_internal_with_route(&config, Some("/".to_string()), method, headers, body, route).await
+    proxy_request_internal_with_route(&state, Some("/".to_string()), request, route, None).await
 }
 
 /// Generic proxy handler that can route to any service
+#[allow(clippy::too_many_arguments)]
 pub async fn proxy_request(
-    State(config): State<ApiGatewayConfig>,
+    State(state): State<GatewayState>,
     Path(path): Path<String>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    upgrade: Option<WebSocketUpgrade>,
     method: Method,
+    uri: Uri,
     headers: HeaderMap,
     body: Bytes,
 ) -> Result<Response, StatusCode> {
-    let route = determine_route_from_path(&config, &path);
-    proxy_request_internal_with_route(&config, Some(path), method, headers, body, route).await
+    let query = uri.query().map(str::to_string);
+    let upgrade = upgrade.map(|upgrade| ClientUpgrade { upgrade, query, peer });
+    let route = determine_route_from_path(&state.config, &path);
+    let request = ClientRequest { peer, method, uri, headers, body };
+    proxy_request_internal_with_route(&state, Some(path), request, route, upgrade).await
 }
 
 /// Determine which route a path belongs to
@@ -81,13 +119,13 @@ fn determine_route_from_path<'a>(config: &'a ApiGatewayConfig, path: &str) -> Op
 
 /// Internal proxy logic with explicit route
 async fn proxy_request_internal_with_route(
-    config: &ApiGatewayConfig,
+    state: &GatewayState,
     path: Option<String>,
-    method: Method,
-    headers: HeaderMap,
-    body: Bytes,
+    request: ClientRequest,
     route: Option<&RouteConfig>,
+    upgrade: Option<ClientUpgrade>,
 ) -> Result<Response, StatusCode> {
+    let ClientRequest { peer, method, uri, headers, body } = request;
     let route = match route {
         Some(route) => route,
         None => {
@@ -96,6 +134,9 @@ async fn proxy_request_internal_with_route(
             return Err(StatusCode::NOT_FOUND);
         }
     };
+    monitoring::record_route(&route.path);
+
+    let config = &state.config;
 
     // Check if method is allowed
     if !config.is_method_allowed(route, method.as_str()) {
@@ -103,16 +144,19 @@ async fn proxy_request_internal_with_route(
         return Err(StatusCode::METHOD_NOT_ALLOWED);
     }
 
-    // Get service configuration
-    let service = match config.get_service(&route.service) {
-        Some(service) => service,
-        None => {
-            error!("❌ Service not found: {}", route.service);
-            return Err(StatusCode::BAD_GATEWAY);
+    // Validate the bearer token before anything is sent upstream
+    let claims = match state.auth.authenticate(route, &headers).await {
+        Ok(claims) => claims,
+        Err(rejection) => {
+            warn!("❌ Rejected request for route {}: {:?}", route.path, rejection);
+            return Ok(rejection.into_response());
         }
     };
+    if let Some(claims) = &claims {
+        monitoring::record_user(&claims.sub);
+    }
 
-    // Build target URL
+    // Build target path
     let target_path = if route.strip_prefix {
         // Remove the route prefix from the path
         let prefix = route.path.trim_end_matches("/*");
@@ -143,18 +187,6 @@ async fn proxy_request_internal_with_route(
         }
     };
 
-    let target_url = format!("http://{}:{}/{}", service.host, service.port, target_path);
-
-    info!("🔍 PROXY REQUEST:");
-    info!("  Method: {}", method);
-    info!("  Source Path: /{}", path.as_deref().unwrap_or("(direct)"));
-    info!("  Target URL: {}", target_url);
-    info!("  Service: {}", route.service);
-    info!("  Strip Prefix: {}", route.strip_prefix);
-
-    // Create HTTP client
-    let client = Client::new();
-
     // Convert Axum method to Reqwest method
     let reqwest_method = match method.as_str() {
         "GET" => reqwest::Method::GET,
@@ -170,96 +202,339 @@ async fn proxy_request_internal_with_route(
         }
     };
 
-    // Build request
-    let mut request = client.request(reqwest_method, &target_url);
+    // Players stick to one instance under consistent hashing, keyed by their
+    // token subject or the hash header
+    let affinity_key = claims
+        .as_ref()
+        .map(|claims| claims.sub.clone())
+        .or_else(|| {
+            headers
+                .get(state.load_balancer.hash_header(&route.service))
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        });
 
-    // Forward headers (excluding host)
-    for (key, value) in headers.iter() {
-        if key.as_str() != "host" {
-            if let Ok(value_str) = value.to_str() {
-                request = request.header(key.as_str(), value_str);
-            }
+    if let Some(client) = upgrade {
+        // New game connections wait for maintenance to end, except for admins
+        let maintenance = state.maintenance.get();
+        if maintenance.refuses(maintenance::is_admin(claims.as_ref(), &config.maintenance.admin_roles)) {
+            warn!("❌ Refused game connection on {} during maintenance", route.path);
+            return Ok(maintenance::rejection(&maintenance));
         }
+        return proxy_upgrade(state, route, &target_path, &headers, claims.as_ref(), affinity_key.as_deref(), client).await;
     }
 
-    // Add custom headers if configured
-    if let Some(add_headers) = &route.add_headers {
-        for (key, value) in add_headers {
-            request = request.header(key, value);
+    // Canary releases send part of the service's traffic to another upstream group
+    let user = claims.as_ref().map(|claims| claims.sub.as_str());
+    let assignment = state.traffic_splits.assign(&route.service, &headers, user);
+    let service = assignment.as_ref().map_or(route.service.as_str(), |assignment| assignment.service.as_str());
+
+    // Serve cacheable GET requests from the response cache unless the client opts out;
+    // groups of a split don't share entries
+    let cache_entry = match &route.cache {
+        Some(cache) if method == Method::GET => {
+            let mut key = ResponseCache::key(uri.path(), uri.query(), cache.scope, claims.as_ref());
+            if let Some(assignment) = &assignment {
+                key.push('@');
+                key.push_str(&assignment.group);
+            }
+            Some((cache, key))
+        }
+        _ => None,
+    };
+    let bypass = cache_entry.is_some() && ResponseCache::is_bypass(&headers);
+    if let Some((_, key)) = cache_entry.as_ref().filter(|_| !bypass) {
+        if let Some(cached) = state.cache.get(key).await {
+            info!("✅ CACHE HIT: {}", key);
+            return Ok(cached.to_response("HIT"));
         }
     }
 
-    // Add body if present
-    if !body.is_empty() {
-        request = request.body(body.to_vec());
-    }
+    // Non-idempotent requests are sent once, whatever the route's retry policy
+    let retry = route.retry.as_ref().filter(|_| retry::is_idempotent(&method));
+    let max_attempts = 1 + retry.map_or(0, |retry| retry.max_retries);
+    let breaker = state.circuit_breakers.get(service);
+    let mut attempt = 0;
 
-    info!("🚀 SENDING REQUEST to {}", target_url);
+    loop {
+        attempt += 1;
 
-    // Send request
-    match request.send().await {
-        Ok(response) => {
-            let status = response.status().as_u16();
-            let response_headers = response.headers().clone();
-            let response_body = response.bytes().await.unwrap_or_default();
+        let permit = match breaker.try_acquire() {
+            Some(permit) => permit,
+            None => {
+                warn!("⚡ Circuit open for service {}, rejecting request", service);
+                return Err(StatusCode::SERVICE_UNAVAILABLE);
+            }
+        };
 
-            info!("✅ RESPONSE RECEIVED:");
-            info!("  Status: {}", status);
-            info!("  Body Length: {}", response_body.len());
+        // Pick a discovered instance of the service
+        let instances = state.discovery.instances(service);
+        let selection = match state.load_balancer.select(service, &instances, affinity_key.as_deref()) {
+            Some(selection) => selection,
+            None => {
+                error!("❌ No instances of service: {}", service);
+                return Err(StatusCode::BAD_GATEWAY);
+            }
+        };
+        monitoring::record_upstream(service, &selection.instance.address());
 
-            // Build response
-            let mut response_builder = Response::builder()
-                .status(StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR));
+        let mut target_url = format!("{}/{}", selection.instance.base_url(), target_path);
+        if let Some(query) = uri.query() {
+            target_url.push('?');
+            target_url.push_str(query);
+        }
 
-            // Forward response headers
-            for (key, value) in response_headers.iter() {
+        info!("🔍 PROXY REQUEST:");
+        info!("  Method: {}", method);
+        info!("  Source Path: /{}", path.as_deref().unwrap_or("(direct)"));
+        info!("  Target URL: {}", target_url);
+        info!("  Service: {}", service);
+        if let Some(assignment) = &assignment {
+            info!("  Upstream Group: {}", assignment.group);
+        }
+        info!("  Strip Prefix: {}", route.strip_prefix);
+        info!("  Attempt: {}/{}", attempt, max_attempts);
+
+        // Build request on the pooled client of the service
+        let mut request = selection.client.request(reqwest_method.clone(), &target_url);
+
+        // Forward headers (excluding host and client-supplied identity headers)
+        for (key, value) in headers.iter() {
+            if key.as_str() != "host" && !is_trusted_header(key.as_str()) {
                 if let Ok(value_str) = value.to_str() {
-                    response_builder = response_builder.header(key.as_str(), value_str);
+                    request = request.header(key.as_str(), value_str);
                 }
             }
+        }
 
-            Ok(response_builder
-                .body(axum::body::Body::from(response_body))
-                .unwrap())
+        // Add custom headers if configured
+        if let Some(add_headers) = &route.add_headers {
+            for (key, value) in add_headers {
+                request = request.header(key, value);
+            }
         }
-        Err(e) => {
-            error!("❌ PROXY REQUEST FAILED:");
-            error!("  Error: {}", e);
-            error!("  Target URL: {}", target_url);
-            error!("  Service: {}", route.service);
-            Err(StatusCode::BAD_GATEWAY)
+
+        // Forward validated identity
+        if let Some(claims) = &claims {
+            for (key, value) in claims.forwarded_headers() {
+                request = request.header(key, value);
+            }
+        }
+
+        // Forward the address the client connected from
+        for (key, value) in client_address_headers(&peer) {
+            request = request.header(key, value);
+        }
+
+        // Add body if present
+        if !body.is_empty() {
+            request = request.body(body.clone());
         }
-    }
-}
 
-/// Health check for a specific service
-pub async fn check_service_health(service: &ServiceConfig) -> bool {
-    if let Some(health_path) = &service.health_check {
-        let health_url = format!("http://{}:{}{}", service.host, service.port, health_path);
-        
-        match Client::new().get(&health_url).send().await {
-            Ok(response) => response.status().is_success(),
+        info!("🚀 SENDING REQUEST to {}", target_url);
+
+        // Send request
+        let span = info_span!(
+            "gateway.upstream",
+            otel.kind = "client",
+            service = %service,
+            instance = %selection.instance.address(),
+            attempt,
+            http.status_code = tracing::field::Empty,
+        );
+        let started = Instant::now();
+        let result = request.send().instrument(span.clone()).await;
+        let status = result.as_ref().ok().map(|response| response.status().as_u16());
+        if let Some(status) = status {
+            span.record("http.status_code", status);
+        }
+        telemetry::record_duration(
+            "gateway.upstream.duration",
+            started.elapsed(),
+            &[
+                ("service", service.to_string()),
+                ("status", status.map_or_else(|| "error".to_string(), |status| status.to_string())),
+            ],
+        );
+        if let Some(assignment) = &assignment {
+            assignment.record(status, started.elapsed());
+        }
+        let failure = match result {
+            Ok(response) => {
+                let status = response.status().as_u16();
+                let retryable = retry.is_some_and(|retry| retry::is_retryable_status(retry, status));
+                if response.status().is_server_error() {
+                    permit.failure();
+                } else {
+                    permit.success();
+                }
+
+                if retryable && attempt < max_attempts {
+                    format!("status {}", status)
+                } else {
+                    let response_headers = response.headers().clone();
+
+                    // Build response
+                    let mut response_builder = Response::builder()
+                        .status(StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR));
+
+                    // Event streams are passed through as they arrive instead of being buffered
+                    let streaming = response_headers
+                        .get(header::CONTENT_TYPE.as_str())
+                        .and_then(|value| value.to_str().ok())
+                        .is_some_and(|value| value.starts_with("text/event-stream"));
+
+                    // Forward response headers
+                    for (key, value) in response_headers.iter() {
+                        if streaming && is_hop_by_hop(key.as_str()) {
+                            continue;
+                        }
+                        if let Ok(value_str) = value.to_str() {
+                            response_builder = response_builder.header(key.as_str(), value_str);
+                        }
+                    }
+
+                    if let Some(headers) = response_builder.headers_mut() {
+                        if let Some(assignment) = &assignment {
+                            assignment.apply(headers);
+                        }
+                    }
+
+                    if streaming {
+                        info!("✅ STREAMING RESPONSE: status {} from {}", status, target_url);
+                        // The instance stays in use until the stream ends
+                        let events = response.bytes_stream().map(move |chunk| {
+                            let _in_use = &selection;
+                            chunk
+                        });
+                        return Ok(response_builder
+                            .body(axum::body::Body::from_stream(events))
+                            .unwrap());
+                    }
+
+                    let response_body = response.bytes().await.unwrap_or_default();
+
+                    info!("✅ RESPONSE RECEIVED:");
+                    info!("  Status: {}", status);
+                    info!("  Body Length: {}", response_body.len());
+
+                    if let Some((cache, key)) = &cache_entry {
+                        let cached_headers: Vec<(String, String)> = response_headers
+                            .iter()
+                            .filter(|(key, _)| !is_hop_by_hop(key.as_str()))
+                            .filter_map(|(key, value)| Some((key.as_str().to_string(), value.to_str().ok()?.to_string())))
+                            .collect();
+                        let storable = !ResponseCache::is_preview(&headers)
+                            && !ResponseCache::is_no_store(&headers)
+                            && state.cache.is_storable(status, &cached_headers, response_body.len());
+                        if storable {
+                            let ttl = Duration::from_secs(cache.ttl_secs);
+                            let cached = CachedResponse::new(status, cached_headers, response_body.clone(), ttl);
+                            state.cache.put(key.clone(), cached).await;
+                        }
+                        response_builder = response_builder.header(CACHE_STATUS_HEADER, if bypass { "BYPASS" } else { "MISS" });
+                    }
+
+                    return Ok(response_builder
+                        .body(axum::body::Body::from(response_body))
+                        .unwrap());
+                }
+            }
             Err(e) => {
-                warn!("Health check failed for {}: {}", health_url, e);
-                false
+                permit.failure();
+                error!("❌ PROXY REQUEST FAILED:");
+                error!("  Error: {}", e);
+                error!("  Target URL: {}", target_url);
+                error!("  Service: {}", service);
+                if retry.is_none() || attempt >= max_attempts {
+                    return Err(StatusCode::BAD_GATEWAY);
+                }
+                e.to_string()
             }
+        };
+
+        // Release the instance before backing off
+        drop(selection);
+        if let Some(retry) = retry {
+            let delay = retry::backoff(retry, attempt);
+            warn!("🔁 Retrying {} after {} ({:?} backoff)", target_url, failure, delay);
+            tokio::time::sleep(delay).await;
         }
-    } else {
-        // If no health check path is configured, assume the service is healthy
-        true
     }
 }
 
-/// Get all services and their health status
-pub async fn get_services_health(config: &ApiGatewayConfig) -> HashMap<String, bool> {
-    let mut health_status = HashMap::new();
-    
-    for (service_name, service_config) in &config.routing.service_discovery.static_services {
-        let is_healthy = check_service_health(service_config).await;
-        health_status.insert(service_name.clone(), is_healthy);
-        
-        info!("🏥 Service {} health: {}", service_name, if is_healthy { "✅ Healthy" } else { "❌ Unhealthy" });
+/// Headers that describe one connection and are not replayed on another
+fn is_hop_by_hop(name: &str) -> bool {
+    name.eq_ignore_ascii_case(header::TRANSFER_ENCODING.as_str()) || name.eq_ignore_ascii_case(header::CONNECTION.as_str())
+}
+
+/// Proxy a WebSocket upgrade to an instance of the route's service
+async fn proxy_upgrade(
+    state: &GatewayState,
+    route: &RouteConfig,
+    target_path: &str,
+    headers: &HeaderMap,
+    claims: Option<&GatewayClaims>,
+    affinity_key: Option<&str>,
+    client: ClientUpgrade,
+) -> Result<Response, StatusCode> {
+    let ws_config = match &route.websocket {
+        Some(ws_config) => ws_config,
+        None => {
+            warn!("❌ WebSocket upgrade not enabled for route: {}", route.path);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let user = claims.map(|claims| claims.sub.as_str());
+    let assignment = state.traffic_splits.assign(&route.service, headers, user);
+    let service = assignment.as_ref().map_or(route.service.as_str(), |assignment| assignment.service.as_str());
+
+    let permit = match state.circuit_breakers.get(service).try_acquire() {
+        Some(permit) => permit,
+        None => {
+            warn!("⚡ Circuit open for service {}, rejecting upgrade", service);
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+    };
+
+    let instances = state.discovery.instances(service);
+    let selection = match state.load_balancer.select(service, &instances, affinity_key) {
+        Some(selection) => selection,
+        None => {
+            error!("❌ No instances of service: {}", service);
+            return Err(StatusCode::BAD_GATEWAY);
+        }
+    };
+    monitoring::record_upstream(service, &selection.instance.address());
+
+    // Same header rules as plain requests
+    let mut upstream_headers = HeaderMap::new();
+    for (key, value) in headers.iter() {
+        if !is_trusted_header(key.as_str()) {
+            upstream_headers.append(key.clone(), value.clone());
+        }
+    }
+    let added = route.add_headers.iter().flatten().map(|(key, value)| (key.as_str(), value.clone()));
+    let identity = claims.into_iter().flat_map(|claims| claims.forwarded_headers());
+    let address = client_address_headers(&client.peer);
+    for (key, value) in added.chain(identity).chain(address) {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(key), HeaderValue::from_str(&value)) {
+            upstream_headers.insert(name, value);
+        }
+    }
+
+    let started = Instant::now();
+    let mut response =
+        websocket::proxy_websocket(state, ws_config, user, selection, target_path, upstream_headers, client).await;
+    match response.status() {
+        StatusCode::SWITCHING_PROTOCOLS => permit.success(),
+        StatusCode::BAD_GATEWAY => permit.failure(),
+        _ => {}
+    }
+    if let Some(assignment) = &assignment {
+        assignment.record(Some(response.status().as_u16()), started.elapsed());
+        assignment.apply(response.headers_mut());
     }
-    
-    health_status
+    Ok(response)
 }
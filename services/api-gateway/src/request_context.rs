@@ -0,0 +1,103 @@
+//! Request IDs and trace context propagation.
+//!
+//! Every request gets an `X-Request-Id` (the client's when it is usable) and a gateway
+//! span in the client's W3C trace, or in a new trace. Both replace the incoming headers
+//! so the proxies pass them on upstream, are returned to the client, and tag every
+//! log line of the request, including its access log line.
+//!
+//! When spans are exported over OTLP, the exported request span takes the place of the
+//! generated one, so upstream services are parented on a span the collector knows.
+
+use axum::extract::Request;
+use axum::http::{HeaderMap, HeaderValue};
+use axum::middleware::Next;
+use axum::response::Response;
+use shared::telemetry;
+use shared::trace::{self, TraceContext, REQUEST_ID_HEADER, TRACEPARENT_HEADER, TRACESTATE_HEADER};
+use tracing::field::Empty;
+use tracing::{info_span, Instrument};
+
+/// Middleware assigning request IDs and trace context
+pub async fn propagate(mut request: Request, next: Next) -> Response {
+    let parent = header_str(request.headers(), TRACEPARENT_HEADER).and_then(TraceContext::parse);
+    let (request_id, mut trace) = prepare_request(request.headers_mut());
+    let span = info_span!("request", otel.kind = "server", request_id = %request_id, trace_id = Empty);
+    if let Some(exported) = telemetry::export_span(&span, parent.as_ref()) {
+        set_header(request.headers_mut(), TRACEPARENT_HEADER, &exported.to_traceparent());
+        trace = exported;
+    }
+    span.record("trace_id", trace.trace_id.as_str());
+    let mut response = next.run(request).instrument(span).await;
+
+    set_header(response.headers_mut(), REQUEST_ID_HEADER, &request_id);
+    set_header(response.headers_mut(), TRACEPARENT_HEADER, &trace.to_traceparent());
+    response
+}
+
+/// Resolve the request ID and the gateway's span, and rewrite the request headers
+/// that are forwarded upstream
+fn prepare_request(headers: &mut HeaderMap) -> (String, TraceContext) {
+    let request_id = trace::request_id_or_new(header_str(headers, REQUEST_ID_HEADER));
+    let parent = header_str(headers, TRACEPARENT_HEADER).and_then(TraceContext::parse);
+    let trace = match &parent {
+        Some(parent) => parent.child(),
+        None => {
+            // Vendor state belongs to the trace it came with
+            headers.remove(TRACESTATE_HEADER);
+            TraceContext::new_root()
+        }
+    };
+
+    set_header(headers, REQUEST_ID_HEADER, &request_id);
+    set_header(headers, TRACEPARENT_HEADER, &trace.to_traceparent());
+    (request_id, trace)
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|value| value.to_str().ok())
+}
+
+fn set_header(headers: &mut HeaderMap, name: &'static str, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incoming_ids_are_continued() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("req-1"));
+        headers.insert(
+            TRACEPARENT_HEADER,
+            HeaderValue::from_static("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+        );
+        headers.insert(TRACESTATE_HEADER, HeaderValue::from_static("chaos=1"));
+
+        let (request_id, trace) = prepare_request(&mut headers);
+        assert_eq!(request_id, "req-1");
+        assert_eq!(trace.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_ne!(trace.span_id, "00f067aa0ba902b7");
+
+        // Upstream sees the gateway's span as its parent
+        assert_eq!(headers[TRACEPARENT_HEADER], trace.to_traceparent().as_str());
+        assert_eq!(headers[TRACESTATE_HEADER], "chaos=1");
+    }
+
+    #[test]
+    fn test_missing_or_invalid_ids_are_generated() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("not valid"));
+        headers.insert(TRACEPARENT_HEADER, HeaderValue::from_static("garbage"));
+        headers.insert(TRACESTATE_HEADER, HeaderValue::from_static("chaos=1"));
+
+        let (request_id, trace) = prepare_request(&mut headers);
+        assert_ne!(request_id, "not valid");
+        assert_eq!(headers[REQUEST_ID_HEADER], request_id.as_str());
+        assert_eq!(TraceContext::parse(headers[TRACEPARENT_HEADER].to_str().unwrap()), Some(trace));
+        assert!(!headers.contains_key(TRACESTATE_HEADER));
+    }
+}
@@ -0,0 +1,61 @@
+//! Bounded retries of upstream requests.
+
+use crate::config::RetryConfig;
+use axum::http::Method;
+use rand::Rng;
+use std::time::Duration;
+
+/// Whether repeating a request has no additional effect
+pub fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+/// Whether an upstream status should be retried
+pub fn is_retryable_status(config: &RetryConfig, status: u16) -> bool {
+    config.retry_on_status.contains(&status)
+}
+
+/// Backoff before the given retry (1 for the first), with full jitter
+///
+/// The upper bound doubles per retry up to `max_delay_ms`; the actual delay is
+/// drawn uniformly below it so retrying gateways don't hit the service in lockstep.
+pub fn backoff(config: &RetryConfig, retry: u32) -> Duration {
+    let ceiling = config
+        .base_delay_ms
+        .saturating_mul(1u64 << retry.saturating_sub(1).min(20))
+        .min(config.max_delay_ms);
+    if ceiling == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::thread_rng().gen_range(0..=ceiling))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let config = RetryConfig {
+            base_delay_ms: 100,
+            max_delay_ms: 500,
+            ..RetryConfig::default()
+        };
+        for _ in 0..100 {
+            assert!(backoff(&config, 1) <= Duration::from_millis(100));
+            assert!(backoff(&config, 2) <= Duration::from_millis(200));
+            assert!(backoff(&config, 10) <= Duration::from_millis(500));
+        }
+    }
+
+    #[test]
+    fn test_only_idempotent_methods_are_retried() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(is_idempotent(&Method::PUT));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::PATCH));
+    }
+}
@@ -1,325 +1,627 @@
-//! Service discovery for API Gateway
-
-use crate::config::{Config, ServiceDiscoveryConfig, ServiceConfig};
-use crate::errors::ApiGatewayError;
-use crate::errors::Result;
-use crate::types::{Service, ServiceStatus};
-use std::collections::HashMap;
-use std::sync::Arc;
-use std::time::SystemTime;
-use tracing::{info, debug, warn, error};
-
-/// Service discovery service
-#[derive(Debug, Clone)]
-pub struct ServiceDiscoveryService {
-    config: ServiceDiscoveryConfig,
-    services: Arc<tokio::sync::RwLock<HashMap<String, Vec<Service>>>>,
-    service_registry: Arc<tokio::sync::RwLock<HashMap<String, ServiceConfig>>>,
+//! Upstream service discovery.
+//!
+//! A [`DiscoveryBackend`] resolves a service name to its instances. The
+//! [`ServiceRegistry`] refreshes every routed service in the background, drops
+//! instances that fail their health check, and falls back to `static_services` when
+//! the backend fails or returns nothing, so routing follows the backend without a
+//! restart.
+//!
+//! Instances announcing on the event bus that they are stopping are taken out of
+//! routing right away, without waiting for the next refresh to notice, and come back
+//! when they announce they started again.
+
+use crate::config::{
+    ApiGatewayConfig, ConsulConfig, DiscoveryBackendKind, DnsSrvConfig, GrpcHealthCheckConfig, ServiceConfig,
+    ServiceDiscoveryConfig,
+};
+use crate::grpc::GrpcClient;
+use async_trait::async_trait;
+use hickory_resolver::TokioAsyncResolver;
+use serde::{Deserialize, Serialize};
+use shared::events::topics::{LifecycleState, ServiceLifecycle, SERVICE_LIFECYCLE};
+use shared::events::EventBus;
+use shared::ChaosResult;
+use std::collections::{BTreeSet, HashMap};
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+const DEFAULT_REFRESH: Duration = Duration::from_secs(15);
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// One reachable instance of an upstream service
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ServiceInstance {
+    /// Host name or IP address
+    pub host: String,
+    /// Port
+    pub port: u16,
+    /// Relative weight for load balancing
+    pub weight: u32,
 }
 
-/// Initialize service discovery
-pub async fn init(config: &Config) -> Result<ServiceDiscoveryService> {
-    Ok(ServiceDiscoveryService::new(config.clone())?)
-}
+impl ServiceInstance {
+    /// Create an instance with weight 1
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            weight: 1,
+        }
+    }
 
-impl ServiceDiscoveryService {
-    /// Initialize service discovery
-    pub async fn init(config: &Config) -> Result<Self> {
-        Self::new(config.clone())
+    /// `host:port` of the instance
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
     }
 
-    /// Create a new service discovery service
-    pub fn new(config: Config) -> Result<Self> {
-        let service_discovery_config = config.service_discovery.clone();
-        
-        Ok(Self {
-            config: service_discovery_config,
-            services: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-            service_registry: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
-        })
+    /// Base URL of the instance, without trailing slash
+    pub fn base_url(&self) -> String {
+        format!("http://{}:{}", self.host, self.port)
     }
 
+    /// Whether an address a service announced itself on is this instance
+    ///
+    /// Services bound to all interfaces announce `0.0.0.0:<port>`, which matches any
+    /// host on that port.
+    fn is_announced_as(&self, address: &str) -> bool {
+        let Some((host, port)) = address.rsplit_once(':') else {
+            return false;
+        };
+        if port.parse::<u16>().ok() != Some(self.port) {
+            return false;
+        }
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        host == self.host || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+    }
+}
 
-    /// Initialize Consul service discovery
-    async fn init_consul(&self) -> Result<()> {
-        debug!("Initializing Consul service discovery");
+/// Resolves service names to instances
+#[async_trait]
+pub trait DiscoveryBackend: Send + Sync {
+    /// Backend name for logs
+    fn name(&self) -> &'static str;
 
-        // TODO: Implement Consul service discovery
-        // This is a placeholder implementation
+    /// Resolve a service's current instances
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>, String>;
 
-        Ok(())
+    /// Whether the backend only returns instances that pass health checks
+    fn reports_health(&self) -> bool {
+        false
     }
+}
 
-    /// Initialize Kubernetes service discovery
-    async fn init_kubernetes(&self) -> Result<()> {
-        debug!("Initializing Kubernetes service discovery");
+/// Instances listed in `static_services`
+pub struct StaticDiscovery {
+    services: HashMap<String, ServiceConfig>,
+}
+
+impl StaticDiscovery {
+    /// Create a backend from the static service map
+    pub fn new(services: HashMap<String, ServiceConfig>) -> Self {
+        Self { services }
+    }
 
-        // TODO: Implement Kubernetes service discovery
-        // This is a placeholder implementation
+    fn instances(&self, service: &str) -> Vec<ServiceInstance> {
+        self.services
+            .get(service)
+            .map(|config| vec![ServiceInstance::new(config.host.clone(), config.port)])
+            .unwrap_or_default()
+    }
+}
 
-        Ok(())
+#[async_trait]
+impl DiscoveryBackend for StaticDiscovery {
+    fn name(&self) -> &'static str {
+        "static"
     }
 
-    /// Initialize static service discovery
-    async fn init_static(&self) -> Result<()> {
-        debug!("Initializing static service discovery");
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>, String> {
+        Ok(self.instances(service))
+    }
+}
 
-        if let Some(static_config) = &self.config.static_config {
-            for service_config in &static_config.services {
-                let service = Service {
-                    name: service_config.name.clone(),
-                    host: service_config.host.clone(),
-                    port: service_config.port,
-                    health_check: service_config.health_check.clone(),
-                    status: ServiceStatus::Unknown,
-                    last_health_check: None,
-                    response_time: None,
-                    error_count: 0,
-                    success_count: 0,
-                };
+/// Instances passing their Consul health checks
+pub struct ConsulDiscovery {
+    config: ConsulConfig,
+    client: reqwest::Client,
+}
 
-                self.register_service(service_config.name.clone(), service).await?;
-            }
-        }
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulEntry {
+    node: ConsulNode,
+    service: ConsulService,
+}
 
-        Ok(())
-    }
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulNode {
+    address: String,
+}
 
-    /// Register a service
-    pub async fn register_service(&self, service_name: String, service: Service) -> Result<()> {
-        debug!("Registering service: {} at {}:{}", service_name, service.host, service.port);
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulService {
+    #[serde(default)]
+    address: String,
+    port: u16,
+    #[serde(default)]
+    weights: Option<ConsulWeights>,
+}
 
-        let mut services = self.services.write().await;
-        services.entry(service_name.clone()).or_insert_with(Vec::new).push(service);
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ConsulWeights {
+    passing: u32,
+}
 
-        info!("Service registered: {}", service_name);
-        Ok(())
+impl ConsulDiscovery {
+    /// Create a backend querying a Consul agent
+    pub fn new(config: ConsulConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
     }
+}
 
-    /// Deregister a service
-    pub async fn deregister_service(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Deregistering service: {} with ID: {}", service_name, service_id);
+#[async_trait]
+impl DiscoveryBackend for ConsulDiscovery {
+    fn name(&self) -> &'static str {
+        "consul"
+    }
 
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            service_list.retain(|s| s.name != service_id);
-            if service_list.is_empty() {
-                services.remove(service_name);
-            }
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>, String> {
+        let url = format!("{}/v1/health/service/{}", self.config.address.trim_end_matches('/'), service);
+        let mut request = self.client.get(&url).query(&[("passing", "true")]);
+        if let Some(datacenter) = &self.config.datacenter {
+            request = request.query(&[("dc", datacenter)]);
+        }
+        if let Some(token) = &self.config.token {
+            request = request.header("X-Consul-Token", token);
         }
 
-        info!("Service deregistered: {} - {}", service_name, service_id);
-        Ok(())
+        let entries: Vec<ConsulEntry> = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Consul query failed: {}", e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid Consul response: {}", e))?;
+
+        Ok(entries
+            .into_iter()
+            .map(|entry| {
+                // Services registered without an address use their node's address
+                let host = if entry.service.address.is_empty() {
+                    entry.node.address
+                } else {
+                    entry.service.address
+                };
+                ServiceInstance {
+                    host,
+                    port: entry.service.port,
+                    weight: entry.service.weights.map_or(1, |weights| weights.passing.max(1)),
+                }
+            })
+            .collect())
     }
 
-    /// Get all services
-    pub async fn get_services(&self) -> Result<HashMap<String, Vec<Service>>> {
-        let services = self.services.read().await;
-        Ok(services.clone())
+    fn reports_health(&self) -> bool {
+        true
     }
+}
 
-    /// Get services for a specific name
-    pub async fn get_services_for_name(&self, service_name: &str) -> Result<Vec<Service>> {
-        let services = self.services.read().await;
-        Ok(services.get(service_name).cloned().unwrap_or_default())
+/// Instances published as DNS SRV records
+pub struct DnsSrvDiscovery {
+    config: DnsSrvConfig,
+    resolver: TokioAsyncResolver,
+}
+
+impl DnsSrvDiscovery {
+    /// Create a backend using the system resolver configuration
+    pub fn new(config: DnsSrvConfig) -> Result<Self, String> {
+        let resolver = TokioAsyncResolver::tokio_from_system_conf()
+            .map_err(|e| format!("Failed to create DNS resolver: {}", e))?;
+        Ok(Self { config, resolver })
     }
+}
 
-    /// Get healthy services for a specific name
-    pub async fn get_healthy_services_for_name(&self, service_name: &str) -> Result<Vec<Service>> {
-        let services = self.services.read().await;
-        if let Some(service_list) = services.get(service_name) {
-            Ok(service_list.iter()
-                .filter(|s| s.status == ServiceStatus::Healthy)
-                .cloned()
-                .collect())
-        } else {
-            Ok(Vec::new())
-        }
+#[async_trait]
+impl DiscoveryBackend for DnsSrvDiscovery {
+    fn name(&self) -> &'static str {
+        "dns_srv"
     }
 
-    /// Check if service is available
-    pub async fn is_service_available(&self, service_name: &str) -> Result<bool> {
-        let services = self.services.read().await;
-        if let Some(service_list) = services.get(service_name) {
-            Ok(service_list.iter().any(|s| s.status == ServiceStatus::Healthy))
-        } else {
-            Ok(false)
-        }
+    async fn resolve(&self, service: &str) -> Result<Vec<ServiceInstance>, String> {
+        let name = self.config.name_template.replace("{service}", service);
+        let lookup = self
+            .resolver
+            .srv_lookup(name.as_str())
+            .await
+            .map_err(|e| format!("SRV lookup for {} failed: {}", name, e))?;
+
+        // Only the records with the best (lowest) priority are used
+        let best = lookup.iter().map(|record| record.priority()).min();
+        Ok(lookup
+            .iter()
+            .filter(|record| Some(record.priority()) == best)
+            .map(|record| ServiceInstance {
+                host: record.target().to_utf8().trim_end_matches('.').to_string(),
+                port: record.port(),
+                weight: u32::from(record.weight()).max(1),
+            })
+            .collect())
     }
+}
 
-    /// Update service health
-    pub async fn update_service_health(&self, service_name: &str, service_id: &str, status: ServiceStatus) -> Result<()> {
-        debug!("Updating service health: {} - {} to {:?}", service_name, service_id, status);
+/// Current instances of every routed service
+pub struct ServiceRegistry {
+    backend: Arc<dyn DiscoveryBackend>,
+    fallback: StaticDiscovery,
+    services: BTreeSet<String>,
+    health_checks: HashMap<String, HealthCheck>,
+    refresh: Duration,
+    instances: RwLock<HashMap<String, Vec<ServiceInstance>>>,
+    /// Addresses of instances that announced they are stopping, by service
+    draining: RwLock<HashMap<String, BTreeSet<String>>>,
+    client: reqwest::Client,
+    grpc: GrpcClient,
+}
 
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.status = status;
-                    service.last_health_check = Some(SystemTime::now());
-                    break;
-                }
-            }
-        }
+/// How instances of a service are probed
+#[derive(Debug, Clone)]
+enum HealthCheck {
+    /// `GET` on a path, healthy on 2xx
+    Http(String),
+    /// `grpc.health.v1.Health/Check`, healthy when SERVING
+    Grpc(GrpcHealthCheckConfig),
+}
 
-        Ok(())
+impl std::fmt::Debug for ServiceRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceRegistry")
+            .field("backend", &self.backend.name())
+            .field("services", &self.services)
+            .field("refresh", &self.refresh)
+            .finish()
     }
+}
 
-    /// Update service response time
-    pub async fn update_service_response_time(&self, service_name: &str, service_id: &str, response_time: u64) -> Result<()> {
-        debug!("Updating service response time: {} - {} to {}ms", service_name, service_id, response_time);
-
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.response_time = Some(response_time);
-                    break;
-                }
+impl ServiceRegistry {
+    /// Build a registry using the configured backend
+    pub fn from_config(config: &ApiGatewayConfig) -> Result<Self, String> {
+        let discovery = &config.routing.service_discovery;
+        let backend: Arc<dyn DiscoveryBackend> = match discovery.backend {
+            DiscoveryBackendKind::Static => Arc::new(StaticDiscovery::new(discovery.static_services.clone())),
+            DiscoveryBackendKind::Consul => {
+                let consul = discovery.consul.clone().ok_or("service_discovery.consul is not configured")?;
+                Arc::new(ConsulDiscovery::new(consul))
             }
-        }
+            DiscoveryBackendKind::DnsSrv => {
+                let dns_srv = discovery.dns_srv.clone().ok_or("service_discovery.dns_srv is not configured")?;
+                Arc::new(DnsSrvDiscovery::new(dns_srv)?)
+            }
+        };
+        let routed = config.routing.routes.iter().map(|route| route.service.clone());
+        let grpc_routed = config.routing.grpc_routes.iter().map(|route| route.service.clone());
+        let split_groups = config
+            .routing
+            .traffic_splits
+            .values()
+            .flat_map(|split| split.groups.iter().map(|group| group.service.clone()));
+        let services = routed.chain(grpc_routed).chain(split_groups).collect();
+        Ok(Self::with_backend(backend, discovery, services))
+    }
 
-        Ok(())
+    /// Build a registry with an explicit backend
+    pub fn with_backend(
+        backend: Arc<dyn DiscoveryBackend>,
+        discovery: &ServiceDiscoveryConfig,
+        services: BTreeSet<String>,
+    ) -> Self {
+        let fallback = StaticDiscovery::new(discovery.static_services.clone());
+        // Serve the static instances until the first refresh completes
+        let instances = services
+            .iter()
+            .map(|service| (service.clone(), fallback.instances(service)))
+            .collect();
+        let health_checks = discovery
+            .static_services
+            .iter()
+            .filter_map(|(name, config)| {
+                let check = match (&config.grpc_health_check, &config.health_check) {
+                    (Some(grpc), _) => HealthCheck::Grpc(grpc.clone()),
+                    (None, Some(path)) => HealthCheck::Http(path.clone()),
+                    (None, None) => return None,
+                };
+                Some((name.clone(), check))
+            })
+            .collect();
+
+        Self {
+            backend,
+            fallback,
+            services,
+            health_checks,
+            refresh: discovery.refresh_secs.map(Duration::from_secs).unwrap_or(DEFAULT_REFRESH),
+            instances: RwLock::new(instances),
+            draining: RwLock::new(HashMap::new()),
+            client: reqwest::Client::builder()
+                .timeout(HEALTH_CHECK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            grpc: GrpcClient::new(),
+        }
     }
 
-    /// Record service error
-    pub async fn record_service_error(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Recording service error: {} - {}", service_name, service_id);
+    /// Current instances of a service, without the ones shutting down
+    pub fn instances(&self, service: &str) -> Vec<ServiceInstance> {
+        let instances = self.instances.read().unwrap().get(service).cloned().unwrap_or_default();
+        let draining = self.draining.read().unwrap();
+        let Some(addresses) = draining.get(service) else {
+            return instances;
+        };
+        instances
+            .into_iter()
+            .filter(|instance| !addresses.iter().any(|address| instance.is_announced_as(address)))
+            .collect()
+    }
 
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.error_count += 1;
-                    break;
+    /// Stop routing to an instance announced as stopping, until it announces it started
+    pub fn apply_lifecycle(&self, lifecycle: &ServiceLifecycle) {
+        let Some(address) = &lifecycle.address else {
+            return;
+        };
+        let mut draining = self.draining.write().unwrap();
+        match lifecycle.state {
+            LifecycleState::Stopping | LifecycleState::Stopped => {
+                let addresses = draining.entry(lifecycle.service.clone()).or_default();
+                if addresses.insert(address.clone()) {
+                    info!("🛑 {} at {} is shutting down, no longer routing to it", lifecycle.service, address);
+                }
+            }
+            LifecycleState::Started => {
+                let Some(addresses) = draining.get_mut(&lifecycle.service) else {
+                    return;
+                };
+                if addresses.remove(address) {
+                    info!("▶️ {} at {} started, routing to it again", lifecycle.service, address);
+                }
+                if addresses.is_empty() {
+                    draining.remove(&lifecycle.service);
                 }
             }
         }
-
-        Ok(())
     }
 
-    /// Record service success
-    pub async fn record_service_success(&self, service_name: &str, service_id: &str) -> Result<()> {
-        debug!("Recording service success: {} - {}", service_name, service_id);
-
-        let mut services = self.services.write().await;
-        if let Some(service_list) = services.get_mut(service_name) {
-            for service in service_list.iter_mut() {
-                if service.name == service_id {
-                    service.success_count += 1;
+    /// Follow service lifecycle announcements until the bus closes or the registry is dropped
+    pub async fn follow_lifecycle(self: &Arc<Self>, bus: &dyn EventBus) -> ChaosResult<tokio::task::JoinHandle<()>> {
+        let mut subscription = bus.subscribe_topic(SERVICE_LIFECYCLE.name()).await?;
+        let registry = Arc::downgrade(self);
+        Ok(tokio::spawn(async move {
+            while let Some(envelope) = subscription.next().await {
+                let Some(registry) = registry.upgrade() else {
                     break;
+                };
+                match envelope.decode_message::<ServiceLifecycle>() {
+                    Ok(lifecycle) => registry.apply_lifecycle(&lifecycle),
+                    Err(e) => warn!("Ignoring malformed lifecycle announcement: {}", e),
                 }
             }
-        }
-
-        Ok(())
+        }))
     }
 
-    /// Start service discovery
-    pub async fn start(&self) -> Result<()> {
-        info!("Starting service discovery");
+    /// Snapshot of every service's instances
+    pub fn all_instances(&self) -> HashMap<String, Vec<ServiceInstance>> {
+        self.instances.read().unwrap().clone()
+    }
 
-        match self.config.discovery_type.as_str() {
-            "consul" => self.start_consul().await?,
-            "kubernetes" => self.start_kubernetes().await?,
-            "static" => self.start_static().await?,
-            _ => return Err(ApiGatewayError::ServiceDiscovery(format!("Unknown service discovery type: {}", self.config.discovery_type))),
+    /// Re-resolve every service
+    pub async fn refresh(&self) {
+        for service in &self.services {
+            let instances = self.resolve(service).await;
+            let mut current = self.instances.write().unwrap();
+            if current.get(service) != Some(&instances) {
+                info!("🔄 Service {} now has {} instance(s)", service, instances.len());
+                current.insert(service.clone(), instances);
+            }
         }
+    }
 
-        info!("Service discovery started successfully");
-        Ok(())
+    /// Refresh in the background until the registry is dropped
+    pub fn spawn_refresh(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let registry = Arc::downgrade(self);
+        let period = self.refresh;
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let Some(registry) = registry.upgrade() else {
+                    break;
+                };
+                registry.refresh().await;
+            }
+        })
     }
 
-    /// Stop service discovery
-    pub async fn stop(&self) -> Result<()> {
-        info!("Stopping service discovery");
+    async fn resolve(&self, service: &str) -> Vec<ServiceInstance> {
+        let resolved = match self.backend.resolve(service).await {
+            Ok(instances) if !instances.is_empty() => instances,
+            Ok(_) => {
+                debug!("{} returned no instances for {}, using static config", self.backend.name(), service);
+                self.fallback.instances(service)
+            }
+            Err(e) => {
+                warn!("Discovery via {} failed for {}: {}; using static config", self.backend.name(), service, e);
+                self.fallback.instances(service)
+            }
+        };
 
-        match self.config.discovery_type.as_str() {
-            "consul" => self.stop_consul().await?,
-            "kubernetes" => self.stop_kubernetes().await?,
-            "static" => self.stop_static().await?,
-            _ => return Err(ApiGatewayError::ServiceDiscovery(format!("Unknown service discovery type: {}", self.config.discovery_type))),
+        if self.backend.reports_health() {
+            return resolved;
         }
-
-        info!("Service discovery stopped successfully");
-        Ok(())
+        let Some(check) = self.health_checks.get(service) else {
+            return resolved;
+        };
+
+        let checks = resolved.iter().map(|instance| self.is_healthy(instance, check));
+        let healthy: Vec<_> = resolved
+            .iter()
+            .zip(futures::future::join_all(checks).await)
+            .filter(|(_, healthy)| *healthy)
+            .map(|(instance, _)| instance.clone())
+            .collect();
+        if healthy.is_empty() {
+            // Failing open keeps traffic flowing when only the health endpoint is broken
+            warn!("No healthy instances of {}, keeping all {}", service, resolved.len());
+            return resolved;
+        }
+        healthy
     }
 
-    /// Start Consul service discovery
-    async fn start_consul(&self) -> Result<()> {
-        debug!("Starting Consul service discovery");
+    async fn is_healthy(&self, instance: &ServiceInstance, check: &HealthCheck) -> bool {
+        let path = match check {
+            HealthCheck::Http(path) => path,
+            HealthCheck::Grpc(grpc) => return self.grpc.check_health(instance, grpc).await,
+        };
+        let url = format!("{}{}", instance.base_url(), path);
+        match self.client.get(&url).send().await {
+            Ok(response) => response.status().is_success(),
+            Err(e) => {
+                debug!("Health check {} failed: {}", url, e);
+                false
+            }
+        }
+    }
+}
 
-        // TODO: Implement Consul service discovery start
-        // This is a placeholder implementation
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
 
-        Ok(())
+    struct FakeBackend {
+        responses: Mutex<Vec<Result<Vec<ServiceInstance>, String>>>,
     }
 
-    /// Stop Consul service discovery
-    async fn stop_consul(&self) -> Result<()> {
-        debug!("Stopping Consul service discovery");
+    #[async_trait]
+    impl DiscoveryBackend for FakeBackend {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
 
-        // TODO: Implement Consul service discovery stop
-        // This is a placeholder implementation
+        async fn resolve(&self, _service: &str) -> Result<Vec<ServiceInstance>, String> {
+            self.responses.lock().unwrap().remove(0)
+        }
 
-        Ok(())
+        fn reports_health(&self) -> bool {
+            true
+        }
     }
 
-    /// Start Kubernetes service discovery
-    async fn start_kubernetes(&self) -> Result<()> {
-        debug!("Starting Kubernetes service discovery");
-
-        // TODO: Implement Kubernetes service discovery start
-        // This is a placeholder implementation
-
-        Ok(())
+    fn discovery_config() -> ServiceDiscoveryConfig {
+        let mut static_services = HashMap::new();
+        static_services.insert(
+            "chaos-backend".to_string(),
+            ServiceConfig {
+                host: "localhost".to_string(),
+                port: 8081,
+                health_check: Some("/health".to_string()),
+                grpc_health_check: None,
+            },
+        );
+        ServiceDiscoveryConfig {
+            backend: DiscoveryBackendKind::Static,
+            refresh_secs: Some(1),
+            consul: None,
+            dns_srv: None,
+            static_services,
+        }
     }
 
-    /// Stop Kubernetes service discovery
-    async fn stop_kubernetes(&self) -> Result<()> {
-        debug!("Stopping Kubernetes service discovery");
+    #[tokio::test]
+    async fn test_registry_follows_backend_and_falls_back_to_static() {
+        let discovered = vec![ServiceInstance::new("10.0.0.1", 9000), ServiceInstance::new("10.0.0.2", 9000)];
+        let backend = Arc::new(FakeBackend {
+            responses: Mutex::new(vec![Ok(discovered.clone()), Err("unreachable".to_string()), Ok(Vec::new())]),
+        });
+        let services = BTreeSet::from(["chaos-backend".to_string()]);
+        let registry = ServiceRegistry::with_backend(backend, &discovery_config(), services);
 
-        // TODO: Implement Kubernetes service discovery stop
-        // This is a placeholder implementation
+        let static_instance = vec![ServiceInstance::new("localhost", 8081)];
+        assert_eq!(registry.instances("chaos-backend"), static_instance);
 
-        Ok(())
-    }
+        registry.refresh().await;
+        assert_eq!(registry.instances("chaos-backend"), discovered);
 
-    /// Start static service discovery
-    async fn start_static(&self) -> Result<()> {
-        debug!("Starting static service discovery");
+        registry.refresh().await;
+        assert_eq!(registry.instances("chaos-backend"), static_instance);
 
-        // TODO: Implement static service discovery start
-        // This is a placeholder implementation
+        registry.refresh().await;
+        assert_eq!(registry.instances("chaos-backend"), static_instance);
+        assert!(registry.instances("unknown").is_empty());
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn test_unhealthy_instances_fail_open() {
+        let backend = Arc::new(StaticDiscovery::new(HashMap::from([(
+            "chaos-backend".to_string(),
+            ServiceConfig {
+                // Nothing listens on port 1, so the health check fails
+                host: "127.0.0.1".to_string(),
+                port: 1,
+                health_check: None,
+                grpc_health_check: None,
+            },
+        )])));
+        let services = BTreeSet::from(["chaos-backend".to_string()]);
+        let registry = ServiceRegistry::with_backend(backend, &discovery_config(), services);
+
+        registry.refresh().await;
+        assert_eq!(registry.instances("chaos-backend"), vec![ServiceInstance::new("127.0.0.1", 1)]);
     }
 
-    /// Stop static service discovery
-    async fn stop_static(&self) -> Result<()> {
-        debug!("Stopping static service discovery");
+    fn lifecycle(state: LifecycleState, address: &str) -> ServiceLifecycle {
+        ServiceLifecycle {
+            service: "chaos-backend".to_string(),
+            instance: "replica-1".to_string(),
+            state,
+            address: Some(address.to_string()),
+        }
+    }
 
-        // TODO: Implement static service discovery stop
-        // This is a placeholder implementation
+    #[tokio::test]
+    async fn test_stopping_instances_are_not_routed_to() {
+        let discovered = vec![ServiceInstance::new("10.0.0.1", 8081), ServiceInstance::new("10.0.0.2", 8081)];
+        let backend = Arc::new(FakeBackend { responses: Mutex::new(vec![Ok(discovered.clone())]) });
+        let services = BTreeSet::from(["chaos-backend".to_string()]);
+        let registry = ServiceRegistry::with_backend(backend, &discovery_config(), services);
+        registry.refresh().await;
 
-        Ok(())
-    }
+        registry.apply_lifecycle(&lifecycle(LifecycleState::Stopping, "10.0.0.1:8081"));
+        assert_eq!(registry.instances("chaos-backend"), vec![ServiceInstance::new("10.0.0.2", 8081)]);
 
-    /// Get service discovery configuration
-    pub fn get_config(&self) -> &ServiceDiscoveryConfig {
-        &self.config
-    }
+        registry.apply_lifecycle(&lifecycle(LifecycleState::Started, "10.0.0.1:8081"));
+        assert_eq!(registry.instances("chaos-backend"), discovered);
 
-    /// Get service discovery type
-    pub fn get_discovery_type(&self) -> &str {
-        &self.config.discovery_type
+        // Instances bound to all interfaces match on the port alone
+        registry.apply_lifecycle(&lifecycle(LifecycleState::Stopped, "0.0.0.0:8081"));
+        assert!(registry.instances("chaos-backend").is_empty());
     }
 
-    /// Check if service discovery is enabled
-    pub fn is_enabled(&self) -> bool {
-        !self.config.discovery_type.is_empty()
+    #[test]
+    fn test_consul_entries_use_node_address_fallback() {
+        let entries: Vec<ConsulEntry> = serde_json::from_str(
+            r#"[{"Node":{"Address":"10.0.0.9"},"Service":{"Address":"","Port":8081,"Weights":{"Passing":3,"Warning":1}}}]"#,
+        )
+        .unwrap();
+        assert_eq!(entries[0].node.address, "10.0.0.9");
+        assert_eq!(entries[0].service.weights.as_ref().unwrap().passing, 3);
     }
 }
-
-
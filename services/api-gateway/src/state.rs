@@ -0,0 +1,78 @@
+//! Shared state of the gateway handlers.
+
+use crate::auth::JwtAuthenticator;
+use crate::caching::ResponseCache;
+use crate::circuit_breaker::CircuitBreakers;
+use crate::config::ApiGatewayConfig;
+use crate::grpc::GrpcClient;
+use crate::health::HealthAggregator;
+use crate::load_balancing::LoadBalancer;
+use crate::monitoring::GatewayMetrics;
+use crate::service_discovery::ServiceRegistry;
+use crate::traffic_split::TrafficSplitter;
+use crate::websocket::ConnectionLimiter;
+use shared::maintenance::MaintenanceFlag;
+use shared::shutdown::Shutdown;
+use std::sync::Arc;
+
+/// State passed to every gateway handler
+#[derive(Debug, Clone)]
+pub struct GatewayState {
+    /// Gateway configuration
+    pub config: Arc<ApiGatewayConfig>,
+    /// Bearer token validation
+    pub auth: Arc<JwtAuthenticator>,
+    /// Upstream instances
+    pub discovery: Arc<ServiceRegistry>,
+    /// Instance selection and upstream connection pools
+    pub load_balancer: Arc<LoadBalancer>,
+    /// Upstream circuit breakers
+    pub circuit_breakers: Arc<CircuitBreakers>,
+    /// Open WebSocket connections per user
+    pub ws_connections: Arc<ConnectionLimiter>,
+    /// HTTP/2 client for gRPC upstreams
+    pub grpc: GrpcClient,
+    /// Cached responses of cacheable routes
+    pub cache: Arc<ResponseCache>,
+    /// Weighted upstream groups of canary releases
+    pub traffic_splits: Arc<TrafficSplitter>,
+    /// Prometheus metrics
+    pub metrics: GatewayMetrics,
+    /// Latest maintenance state from the event bus
+    pub maintenance: MaintenanceFlag,
+    /// Cached upstream health
+    pub health: Arc<HealthAggregator>,
+    /// Graceful shutdown of the gateway and its WebSocket sessions
+    pub shutdown: Shutdown,
+}
+
+impl GatewayState {
+    /// Build the state from configuration
+    pub fn new(config: ApiGatewayConfig) -> Result<Self, String> {
+        let auth = JwtAuthenticator::from_config(&config.auth, &config.routing.routes)?;
+        for route in &config.routing.grpc_routes {
+            auth.ensure_keys(&format!("gRPC service {}", route.grpc_service), &route.auth)?;
+        }
+        let discovery = ServiceRegistry::from_config(&config)?;
+        let load_balancer = LoadBalancer::from_config(&config.routing);
+        let circuit_breakers = CircuitBreakers::from_config(&config.routing);
+        let cache = ResponseCache::from_config(&config.cache)?;
+        let traffic_splits = TrafficSplitter::from_config(&config.routing)?;
+        let metrics = GatewayMetrics::new().map_err(|e| format!("Failed to register metrics: {}", e))?;
+        Ok(Self {
+            config: Arc::new(config),
+            auth: Arc::new(auth),
+            discovery: Arc::new(discovery),
+            load_balancer: Arc::new(load_balancer),
+            circuit_breakers: Arc::new(circuit_breakers),
+            ws_connections: Arc::new(ConnectionLimiter::new()),
+            grpc: GrpcClient::new(),
+            cache: Arc::new(cache),
+            traffic_splits: Arc::new(traffic_splits),
+            metrics,
+            maintenance: MaintenanceFlag::new(),
+            health: Arc::new(HealthAggregator::new()),
+            shutdown: Shutdown::from_env(),
+        })
+    }
+}
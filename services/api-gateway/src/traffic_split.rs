@@ -0,0 +1,446 @@
+//! Traffic splitting for canary releases.
+//!
+//! A split sends a routed service's requests to weighted upstream groups, each backed
+//! by a discovered service of its own. A client keeps its group: the sticky header
+//! pins a group explicitly, the sticky cookie remembers an earlier assignment, and
+//! authenticated players are hashed on their ID so every gateway replica agrees.
+//! Weights can be changed at runtime through `PUT /traffic-splits/:service`, and
+//! outcomes are counted per group so a canary can be compared with the stable group.
+
+use crate::config::{AuthMode, RouteAuthConfig, RoutingConfig, TrafficSplitConfig};
+use crate::load_balancing::fnv1a;
+use crate::state::GatewayState;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Response header naming the group that served a request
+pub const UPSTREAM_GROUP_HEADER: &str = "x-upstream-group";
+
+/// Outcome counters of one group
+#[derive(Debug, Default)]
+struct GroupStats {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_ms_total: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Group {
+    name: String,
+    service: String,
+    weight: AtomicU32,
+    stats: Arc<GroupStats>,
+}
+
+/// Split of one routed service
+#[derive(Debug)]
+struct ServiceSplit {
+    config: TrafficSplitConfig,
+    groups: Vec<Group>,
+}
+
+/// Group a request was assigned to
+#[derive(Debug)]
+pub struct Assignment {
+    /// Group name
+    pub group: String,
+    /// Service serving the group
+    pub service: String,
+    /// `Set-Cookie` value remembering a new assignment
+    pub set_cookie: Option<String>,
+    stats: Arc<GroupStats>,
+}
+
+impl Assignment {
+    /// Count one upstream attempt; `status` is `None` when no response arrived
+    pub fn record(&self, status: Option<u16>, elapsed: Duration) {
+        self.stats.requests.fetch_add(1, Ordering::Relaxed);
+        if status.is_none_or(|status| status >= 500) {
+            self.stats.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        self.stats
+            .latency_ms_total
+            .fetch_add(elapsed.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Tell the client which group served it and remember new assignments
+    pub fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(group) = HeaderValue::from_str(&self.group) {
+            headers.insert(UPSTREAM_GROUP_HEADER, group);
+        }
+        if let Some(cookie) = self.set_cookie.as_deref().and_then(|cookie| HeaderValue::from_str(cookie).ok()) {
+            headers.append(header::SET_COOKIE, cookie);
+        }
+    }
+}
+
+/// Weight and metrics of one group, as reported on `/traffic-splits`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupSnapshot {
+    /// Group name
+    pub group: String,
+    /// Service serving the group
+    pub service: String,
+    /// Current weight
+    pub weight: u32,
+    /// Upstream attempts since startup
+    pub requests: u64,
+    /// Attempts that failed or returned a server error
+    pub errors: u64,
+    /// Mean upstream latency in milliseconds
+    pub avg_latency_ms: f64,
+}
+
+/// Body of `PUT /traffic-splits/:service`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WeightsUpdate {
+    /// New weight per group; groups not listed keep theirs
+    pub weights: HashMap<String, u32>,
+}
+
+/// Traffic splits of every routed service
+#[derive(Debug, Default)]
+pub struct TrafficSplitter {
+    splits: HashMap<String, ServiceSplit>,
+}
+
+impl TrafficSplitter {
+    /// Create splits from the routing configuration
+    pub fn from_config(routing: &RoutingConfig) -> Result<Self, String> {
+        let mut splits = HashMap::new();
+        for (service, config) in &routing.traffic_splits {
+            if config.groups.is_empty() {
+                return Err(format!("Traffic split of {} has no groups", service));
+            }
+            if config.groups.iter().all(|group| group.weight == 0) {
+                return Err(format!("Traffic split of {} has no group with a weight", service));
+            }
+            let groups = config
+                .groups
+                .iter()
+                .map(|group| Group {
+                    name: group.name.clone(),
+                    service: group.service.clone(),
+                    weight: AtomicU32::new(group.weight),
+                    stats: Arc::default(),
+                })
+                .collect();
+            splits.insert(
+                service.clone(),
+                ServiceSplit {
+                    config: config.clone(),
+                    groups,
+                },
+            );
+        }
+        Ok(Self { splits })
+    }
+
+    /// Assign a request for a routed service to a group; `None` if it is not split
+    ///
+    /// `user` is the authenticated player, hashed so they see the same group
+    /// everywhere; anonymous callers without a cookie are assigned at random.
+    pub fn assign(&self, service: &str, headers: &HeaderMap, user: Option<&str>) -> Option<Assignment> {
+        let split = self.splits.get(service)?;
+
+        // An explicitly requested group is honored even at weight 0, so a canary
+        // can be tested before it takes traffic
+        let pinned = headers
+            .get(split.config.sticky_header.as_str())
+            .and_then(|value| value.to_str().ok())
+            .and_then(|name| split.group(name));
+        if let Some(group) = pinned {
+            return Some(split.assignment(group, false));
+        }
+
+        // Remembered groups are dropped once their weight goes to 0 (rollback)
+        let remembered = split
+            .config
+            .sticky_cookie
+            .as_deref()
+            .and_then(|cookie| cookie_value(headers, cookie))
+            .and_then(|name| split.group(name))
+            .filter(|group| group.weight.load(Ordering::Relaxed) > 0);
+        if let Some(group) = remembered {
+            return Some(split.assignment(group, false));
+        }
+
+        let weights: Vec<u32> = split.groups.iter().map(|group| group.weight.load(Ordering::Relaxed)).collect();
+        let total: u64 = weights.iter().map(|weight| u64::from(*weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let point = match user {
+            Some(user) => fnv1a(&format!("{}#{}", service, user)) % total,
+            None => rand::thread_rng().gen_range(0..total),
+        };
+        let index = pick(&weights, point);
+        Some(split.assignment(&split.groups[index], true))
+    }
+
+    /// Change group weights of a split
+    pub fn set_weights(&self, service: &str, weights: &HashMap<String, u32>) -> Result<(), String> {
+        let split = self
+            .splits
+            .get(service)
+            .ok_or_else(|| format!("Service {} has no traffic split", service))?;
+        if let Some(unknown) = weights.keys().find(|name| split.group(name).is_none()) {
+            return Err(format!("Unknown group {} of service {}", unknown, service));
+        }
+        let total: u64 = split
+            .groups
+            .iter()
+            .map(|group| u64::from(weights.get(&group.name).copied().unwrap_or_else(|| group.weight.load(Ordering::Relaxed))))
+            .sum();
+        if total == 0 {
+            return Err(format!("Traffic split of {} needs a group with a weight", service));
+        }
+        for group in &split.groups {
+            if let Some(weight) = weights.get(&group.name) {
+                group.weight.store(*weight, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    /// Weights and metrics of every split
+    pub fn snapshot(&self) -> HashMap<String, Vec<GroupSnapshot>> {
+        self.splits
+            .iter()
+            .map(|(service, split)| {
+                let groups = split
+                    .groups
+                    .iter()
+                    .map(|group| {
+                        let requests = group.stats.requests.load(Ordering::Relaxed);
+                        let latency = group.stats.latency_ms_total.load(Ordering::Relaxed);
+                        GroupSnapshot {
+                            group: group.name.clone(),
+                            service: group.service.clone(),
+                            weight: group.weight.load(Ordering::Relaxed),
+                            requests,
+                            errors: group.stats.errors.load(Ordering::Relaxed),
+                            avg_latency_ms: if requests == 0 { 0.0 } else { latency as f64 / requests as f64 },
+                        }
+                    })
+                    .collect();
+                (service.clone(), groups)
+            })
+            .collect()
+    }
+}
+
+impl ServiceSplit {
+    fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|group| group.name == name)
+    }
+
+    fn assignment(&self, group: &Group, remember: bool) -> Assignment {
+        let set_cookie = match &self.config.sticky_cookie {
+            Some(cookie) if remember => Some(format!(
+                "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax",
+                cookie, group.name, self.config.cookie_max_age_secs
+            )),
+            _ => None,
+        };
+        Assignment {
+            group: group.name.clone(),
+            service: group.service.clone(),
+            set_cookie,
+            stats: group.stats.clone(),
+        }
+    }
+}
+
+/// Index of the group a point in `0..sum(weights)` falls into
+fn pick(weights: &[u32], point: u64) -> usize {
+    let mut upper = 0;
+    for (index, weight) in weights.iter().enumerate() {
+        upper += u64::from(*weight);
+        if point < upper {
+            return index;
+        }
+    }
+    weights.len() - 1
+}
+
+/// Value of a request cookie
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// `PUT /traffic-splits/:service`, to shift traffic between groups
+///
+/// Needs a token with one of `routing.traffic_split_admin_roles`.
+pub async fn update_weights_handler(
+    State(state): State<GatewayState>,
+    Path(service): Path<String>,
+    headers: HeaderMap,
+    Json(update): Json<WeightsUpdate>,
+) -> Response {
+    let requirement = RouteAuthConfig {
+        mode: AuthMode::Required,
+        roles: state.config.routing.traffic_split_admin_roles.clone(),
+    };
+    if let Err(rejection) = state.auth.authorize("/traffic-splits", &requirement, &headers).await {
+        warn!("❌ Rejected traffic split update: {:?}", rejection);
+        return rejection.into_response();
+    }
+
+    match state.traffic_splits.set_weights(&service, &update.weights) {
+        Ok(()) => {
+            info!("⚖️ Traffic split of {} updated: {:?}", service, update.weights);
+            let groups = state.traffic_splits.snapshot().remove(&service).unwrap_or_default();
+            Json(groups).into_response()
+        }
+        Err(e) => {
+            warn!("❌ Invalid traffic split update: {}", e);
+            (StatusCode::BAD_REQUEST, e).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::UpstreamGroupConfig;
+
+    fn splitter(sticky_cookie: Option<&str>) -> TrafficSplitter {
+        let config = TrafficSplitConfig {
+            groups: vec![
+                UpstreamGroupConfig {
+                    name: "stable".to_string(),
+                    service: "chaos-backend".to_string(),
+                    weight: 90,
+                },
+                UpstreamGroupConfig {
+                    name: "canary".to_string(),
+                    service: "chaos-backend-v2".to_string(),
+                    weight: 10,
+                },
+            ],
+            sticky_header: "x-upstream-group".to_string(),
+            sticky_cookie: sticky_cookie.map(str::to_string),
+            cookie_max_age_secs: 60,
+        };
+        TrafficSplitter {
+            splits: HashMap::from([(
+                "chaos-backend".to_string(),
+                ServiceSplit {
+                    groups: config
+                        .groups
+                        .iter()
+                        .map(|group| Group {
+                            name: group.name.clone(),
+                            service: group.service.clone(),
+                            weight: AtomicU32::new(group.weight),
+                            stats: Arc::default(),
+                        })
+                        .collect(),
+                    config,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn test_weights_split_players() {
+        let splitter = splitter(None);
+        let headers = HeaderMap::new();
+        let canary = (0..1000)
+            .filter(|player| {
+                let user = format!("player-{}", player);
+                splitter.assign("chaos-backend", &headers, Some(&user)).unwrap().group == "canary"
+            })
+            .count();
+        assert!((50..150).contains(&canary), "canary got {} of 1000 players", canary);
+
+        // The same player always lands in the same group
+        let first = splitter.assign("chaos-backend", &headers, Some("player-7")).unwrap();
+        let second = splitter.assign("chaos-backend", &headers, Some("player-7")).unwrap();
+        assert_eq!(first.group, second.group);
+        assert!(splitter.assign("user-management", &headers, Some("player-7")).is_none());
+    }
+
+    #[test]
+    fn test_header_and_cookie_stickiness() {
+        let splitter = splitter(Some("canary_group"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-upstream-group", HeaderValue::from_static("canary"));
+        let pinned = splitter.assign("chaos-backend", &headers, None).unwrap();
+        assert_eq!(pinned.service, "chaos-backend-v2");
+        assert!(pinned.set_cookie.is_none());
+
+        let fresh = splitter.assign("chaos-backend", &HeaderMap::new(), None).unwrap();
+        let cookie = fresh.set_cookie.expect("new assignments are remembered");
+        assert!(cookie.starts_with(&format!("canary_group={};", fresh.group)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::COOKIE, HeaderValue::from_static("theme=dark; canary_group=canary"));
+        let remembered = splitter.assign("chaos-backend", &headers, None).unwrap();
+        assert_eq!(remembered.group, "canary");
+        assert!(remembered.set_cookie.is_none());
+
+        // Rolling the canary back moves remembered clients off it
+        splitter
+            .set_weights("chaos-backend", &HashMap::from([("canary".to_string(), 0)]))
+            .unwrap();
+        assert_eq!(splitter.assign("chaos-backend", &headers, None).unwrap().group, "stable");
+    }
+
+    #[test]
+    fn test_set_weights_is_validated() {
+        let splitter = splitter(None);
+        assert!(splitter
+            .set_weights("chaos-backend", &HashMap::from([("blue".to_string(), 50)]))
+            .is_err());
+        assert!(splitter
+            .set_weights(
+                "chaos-backend",
+                &HashMap::from([("stable".to_string(), 0), ("canary".to_string(), 0)])
+            )
+            .is_err());
+        assert!(splitter.set_weights("user-management", &HashMap::new()).is_err());
+
+        splitter
+            .set_weights("chaos-backend", &HashMap::from([("canary".to_string(), 50)]))
+            .unwrap();
+        let weights: Vec<u32> = splitter.snapshot()["chaos-backend"].iter().map(|group| group.weight).collect();
+        assert_eq!(weights, vec![90, 50]);
+    }
+
+    #[test]
+    fn test_metrics_are_split_by_group() {
+        let splitter = splitter(None);
+        let mut headers = HeaderMap::new();
+        headers.insert("x-upstream-group", HeaderValue::from_static("canary"));
+        let canary = splitter.assign("chaos-backend", &headers, None).unwrap();
+        canary.record(Some(200), Duration::from_millis(10));
+        canary.record(Some(503), Duration::from_millis(30));
+        canary.record(None, Duration::from_millis(20));
+
+        let snapshot = &splitter.snapshot()["chaos-backend"];
+        let canary = snapshot.iter().find(|group| group.group == "canary").unwrap();
+        assert_eq!((canary.requests, canary.errors), (3, 2));
+        assert_eq!(canary.avg_latency_ms, 20.0);
+        let stable = snapshot.iter().find(|group| group.group == "stable").unwrap();
+        assert_eq!(stable.requests, 0);
+    }
+}
@@ -0,0 +1,317 @@
+//! WebSocket proxying.
+//!
+//! Upgrade requests on routes with a `websocket` section are authenticated like any
+//! other request, then the gateway opens its own connection to the upstream instance
+//! and only completes the client handshake once that succeeded. Messages are relayed
+//! both ways until either side closes or the connection is idle for too long.
+//!
+//! When the gateway shuts down, both sides are sent a going-away close so clients
+//! reconnect through another gateway instance; shutdown waits for the closes to go out.
+
+use crate::config::WebSocketConfig;
+use crate::load_balancing::Selection;
+use crate::state::GatewayState;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use futures::{SinkExt, StreamExt};
+use shared::shutdown::Shutdown;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::{frame::coding::CloseCode, CloseFrame as UpstreamCloseFrame};
+use tokio_tungstenite::tungstenite::Message as UpstreamMessage;
+use tracing::{info, warn};
+
+/// Close code sent when a connection is closed for inactivity or shutdown (going away)
+const GOING_AWAY_CLOSE_CODE: u16 = 1001;
+
+/// Handshake headers negotiated separately on each leg (the requested subprotocols
+/// are passed through)
+const HANDSHAKE_HEADERS: [&str; 6] = [
+    "host",
+    "connection",
+    "upgrade",
+    "sec-websocket-key",
+    "sec-websocket-version",
+    "sec-websocket-extensions",
+];
+
+/// Upgrade request of a client
+pub struct ClientUpgrade {
+    /// Pending client handshake
+    pub upgrade: WebSocketUpgrade,
+    /// Raw query string, forwarded upstream
+    pub query: Option<String>,
+    /// Client address, used as the user key of anonymous connections
+    pub peer: SocketAddr,
+}
+
+/// Open WebSocket connections per user
+#[derive(Debug, Default)]
+pub struct ConnectionLimiter {
+    open: Mutex<HashMap<String, usize>>,
+}
+
+impl ConnectionLimiter {
+    /// Create an empty limiter
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserve a connection for a user; `None` if the user is at the limit
+    pub fn try_acquire(self: &Arc<Self>, user: &str, max: usize) -> Option<ConnectionGuard> {
+        let mut open = self.open.lock().unwrap();
+        let count = open.entry(user.to_string()).or_default();
+        if *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(ConnectionGuard {
+            limiter: self.clone(),
+            user: user.to_string(),
+        })
+    }
+
+    /// Open connections across all users
+    pub fn total(&self) -> usize {
+        self.open.lock().unwrap().values().sum()
+    }
+}
+
+/// Reserved connection slot, released on drop
+#[derive(Debug)]
+pub struct ConnectionGuard {
+    limiter: Arc<ConnectionLimiter>,
+    user: String,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut open = self.limiter.open.lock().unwrap();
+        if let Some(count) = open.get_mut(&self.user) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                open.remove(&self.user);
+            }
+        }
+    }
+}
+
+/// Connect to the upstream and upgrade the client connection
+///
+/// `headers` are the headers to send upstream (client headers plus the ones added by
+/// the gateway); handshake headers among them are replaced by the gateway's own. The
+/// selected instance counts as in use for as long as the connection stays open.
+pub async fn proxy_websocket(
+    state: &GatewayState,
+    config: &WebSocketConfig,
+    user: Option<&str>,
+    selection: Selection,
+    target_path: &str,
+    headers: HeaderMap,
+    client: ClientUpgrade,
+) -> Response {
+    let user = user.map(str::to_string).unwrap_or_else(|| client.peer.ip().to_string());
+    let guard = match state.ws_connections.try_acquire(&user, config.max_connections_per_user) {
+        Some(guard) => guard,
+        None => {
+            warn!("❌ WebSocket limit reached for {}", user);
+            return StatusCode::TOO_MANY_REQUESTS.into_response();
+        }
+    };
+
+    let mut url = format!("{}/{}", selection.instance.base_url(), target_path).replacen("http", "ws", 1);
+    if let Some(query) = &client.query {
+        url.push('?');
+        url.push_str(query);
+    }
+    let mut request = match url.as_str().into_client_request() {
+        Ok(request) => request,
+        Err(e) => {
+            warn!("❌ Invalid upstream WebSocket URL {}: {}", url, e);
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+    for (key, value) in headers.iter() {
+        let name = key.as_str();
+        if !HANDSHAKE_HEADERS.contains(&name) {
+            request.headers_mut().append(key.clone(), value.clone());
+        }
+    }
+
+    info!("🔌 WEBSOCKET PROXY: {} -> {}", user, url);
+    let (upstream, response) = match tokio_tungstenite::connect_async(request).await {
+        Ok(connection) => connection,
+        Err(e) => {
+            warn!("❌ Upstream WebSocket connection to {} failed: {}", url, e);
+            return StatusCode::BAD_GATEWAY.into_response();
+        }
+    };
+
+    // Offer the client the subprotocol the upstream picked
+    let mut upgrade = client.upgrade;
+    if let Some(protocol) = response
+        .headers()
+        .get("sec-websocket-protocol")
+        .and_then(|value| value.to_str().ok())
+    {
+        upgrade = upgrade.protocols([protocol.to_string()]);
+    }
+
+    let idle_timeout = Duration::from_secs(config.idle_timeout_secs.max(1));
+    let (in_flight, shutdown) = (state.shutdown.track(), state.shutdown.clone());
+    upgrade.on_upgrade(move |socket| async move {
+        relay(socket, upstream, idle_timeout, &shutdown).await;
+        drop((guard, selection, in_flight));
+        info!("🔌 WebSocket connection of {} closed", user);
+    })
+}
+
+type Upstream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Relay messages until either side closes, the connection goes idle or the gateway shuts down
+async fn relay(client: WebSocket, upstream: Upstream, idle_timeout: Duration, shutdown: &Shutdown) {
+    let (mut client_tx, mut client_rx) = client.split();
+    let (mut upstream_tx, mut upstream_rx) = upstream.split();
+    let stopping = shutdown.signal();
+    tokio::pin!(stopping);
+
+    loop {
+        let event = tokio::time::timeout(idle_timeout, async {
+            tokio::select! {
+                message = client_rx.next() => Leg::Client(message.and_then(Result::ok)),
+                message = upstream_rx.next() => Leg::Upstream(message.and_then(Result::ok)),
+                _ = &mut stopping => Leg::Shutdown,
+            }
+        })
+        .await;
+
+        match event {
+            Ok(Leg::Client(Some(message))) => {
+                let closing = matches!(message, Message::Close(_));
+                if let Some(message) = to_upstream(message) {
+                    if upstream_tx.send(message).await.is_err() || closing {
+                        break;
+                    }
+                }
+            }
+            Ok(Leg::Upstream(Some(message))) => {
+                let closing = matches!(message, UpstreamMessage::Close(_));
+                if let Some(message) = to_client(message) {
+                    if client_tx.send(message).await.is_err() || closing {
+                        break;
+                    }
+                }
+            }
+            // One side went away; close the other
+            Ok(Leg::Client(None)) => {
+                let _ = upstream_tx.close().await;
+                break;
+            }
+            Ok(Leg::Upstream(None)) => {
+                let _ = client_tx.close().await;
+                break;
+            }
+            Ok(Leg::Shutdown) => {
+                info!("🔌 Closing WebSocket connection for shutdown");
+                going_away(&mut client_tx, &mut upstream_tx, "server shutting down").await;
+                break;
+            }
+            Err(_) => {
+                warn!("⏱️ Closing idle WebSocket connection after {:?}", idle_timeout);
+                going_away(&mut client_tx, &mut upstream_tx, "idle timeout").await;
+                break;
+            }
+        }
+    }
+}
+
+/// Tell both sides the connection is going away
+async fn going_away<C, U>(client_tx: &mut C, upstream_tx: &mut U, reason: &'static str)
+where
+    C: SinkExt<Message> + Unpin,
+    U: SinkExt<UpstreamMessage> + Unpin,
+{
+    let _ = client_tx
+        .send(Message::Close(Some(CloseFrame {
+            code: GOING_AWAY_CLOSE_CODE,
+            reason: reason.into(),
+        })))
+        .await;
+    let _ = upstream_tx
+        .send(UpstreamMessage::Close(Some(UpstreamCloseFrame {
+            code: CloseCode::from(GOING_AWAY_CLOSE_CODE),
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+enum Leg<C, U> {
+    Client(Option<C>),
+    Upstream(Option<U>),
+    Shutdown,
+}
+
+/// Pings and pongs are answered on each leg and only count as activity
+fn to_upstream(message: Message) -> Option<UpstreamMessage> {
+    match message {
+        Message::Text(text) => Some(UpstreamMessage::Text(text)),
+        Message::Binary(data) => Some(UpstreamMessage::Binary(data)),
+        Message::Close(frame) => Some(UpstreamMessage::Close(frame.map(|frame| UpstreamCloseFrame {
+            code: CloseCode::from(frame.code),
+            reason: frame.reason,
+        }))),
+        Message::Ping(_) | Message::Pong(_) => None,
+    }
+}
+
+fn to_client(message: UpstreamMessage) -> Option<Message> {
+    match message {
+        UpstreamMessage::Text(text) => Some(Message::Text(text)),
+        UpstreamMessage::Binary(data) => Some(Message::Binary(data)),
+        UpstreamMessage::Close(frame) => Some(Message::Close(frame.map(|frame| CloseFrame {
+            code: frame.code.into(),
+            reason: frame.reason,
+        }))),
+        UpstreamMessage::Ping(_) | UpstreamMessage::Pong(_) | UpstreamMessage::Frame(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_limit_per_user() {
+        let limiter = Arc::new(ConnectionLimiter::new());
+
+        let first = limiter.try_acquire("player-1", 2).unwrap();
+        let _second = limiter.try_acquire("player-1", 2).unwrap();
+        assert!(limiter.try_acquire("player-1", 2).is_none());
+        assert!(limiter.try_acquire("player-2", 2).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire("player-1", 2).is_some());
+        assert_eq!(limiter.total(), 1);
+    }
+
+    #[test]
+    fn test_close_frames_keep_code_and_reason() {
+        let message = to_upstream(Message::Close(Some(CloseFrame {
+            code: 4000,
+            reason: "bye".into(),
+        })))
+        .unwrap();
+        match to_client(message).unwrap() {
+            Message::Close(Some(frame)) => {
+                assert_eq!(frame.code, 4000);
+                assert_eq!(frame.reason, "bye");
+            }
+            other => panic!("unexpected message: {:?}", other),
+        }
+        assert!(to_client(UpstreamMessage::Ping(vec![1])).is_none());
+    }
+}
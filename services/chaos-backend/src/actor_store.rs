@@ -0,0 +1,502 @@
+//! Actor definitions stored in MongoDB behind a write-behind cache.
+//!
+//! Definitions are loaded in bulk when a zone starts and served from memory from then
+//! on. Buff and subsystem changes apply to the cached definition, bump its version and
+//! mark it dirty; dirty definitions are written every `flush_secs` and once more on
+//! shutdown, so an actor changing many times between flushes costs a single write.
+//!
+//! A consistency check compares the stored definitions of the actors without pending
+//! changes with the cached ones: a missing document, a version that drifted or a
+//! definition resolving to a different snapshot is reported, and repaired by writing the
+//! cached definition again on the next flush.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::bson::{self, doc, Document};
+use mongodb::options::ReplaceOptions;
+use mongodb::{Collection, Database};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use shared::{ChaosError, ChaosResult};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+pub const COLLECTION: &str = "actors";
+
+/// Data entry holding an actor's buffs by buff id
+pub const BUFFS: &str = "buffs";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActorStoreConfig {
+    /// Time between writes of the changed definitions
+    pub flush_secs: u64,
+    /// Time between consistency checks
+    pub check_secs: u64,
+    /// Zones whose actors are loaded at start
+    pub zones: Vec<String>,
+}
+
+impl Default for ActorStoreConfig {
+    fn default() -> Self {
+        Self { flush_secs: 5, check_secs: 300, zones: Vec::new() }
+    }
+}
+
+impl ActorStoreConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.flush_secs == 0 || self.check_secs == 0 {
+            return Err("actors.flush_secs and actors.check_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// An actor definition as stored, filed under the zone loading it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredActor {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zone: Option<String>,
+    pub actor: Actor,
+}
+
+/// Storage of actor definitions
+#[async_trait]
+pub trait ActorRepository: Send + Sync {
+    /// Definitions of the actors filed under a zone
+    async fn load_zone(&self, zone: &str) -> ChaosResult<Vec<StoredActor>>;
+
+    /// Definitions by actor id, unknown ids left out
+    async fn load(&self, actor_ids: &[String]) -> ChaosResult<Vec<StoredActor>>;
+
+    /// Write definitions, replacing the stored ones
+    async fn save(&self, actors: &[StoredActor]) -> ChaosResult<()>;
+}
+
+/// Actor definitions in the [`COLLECTION`] collection, keyed by actor id
+pub struct MongoActorRepository {
+    collection: Collection<Document>,
+}
+
+impl MongoActorRepository {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    async fn find(&self, filter: Document) -> ChaosResult<Vec<StoredActor>> {
+        let cursor = self
+            .collection
+            .find(filter, None)
+            .await
+            .map_err(|e| ChaosError::Database(format!("Failed to load actors: {}", e)))?;
+        let documents: Vec<Document> =
+            cursor.try_collect().await.map_err(|e| ChaosError::Database(format!("Failed to load actors: {}", e)))?;
+        documents
+            .into_iter()
+            .map(|document| bson::from_document(document).map_err(|e| ChaosError::Serialization(e.to_string())))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ActorRepository for MongoActorRepository {
+    async fn load_zone(&self, zone: &str) -> ChaosResult<Vec<StoredActor>> {
+        self.find(doc! { "zone": zone }).await
+    }
+
+    async fn load(&self, actor_ids: &[String]) -> ChaosResult<Vec<StoredActor>> {
+        self.find(doc! { "_id": { "$in": actor_ids } }).await
+    }
+
+    async fn save(&self, actors: &[StoredActor]) -> ChaosResult<()> {
+        let upsert = ReplaceOptions::builder().upsert(true).build();
+        for stored in actors {
+            let id = &stored.actor.id;
+            let document = actor_document(stored)?;
+            self.collection
+                .replace_one(doc! { "_id": id }, document, upsert.clone())
+                .await
+                .map_err(|e| ChaosError::Database(format!("Failed to save actor {}: {}", id, e)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Document of a stored definition, keyed by actor id
+pub(crate) fn actor_document(stored: &StoredActor) -> ChaosResult<Document> {
+    let mut document = bson::to_document(stored).map_err(|e| ChaosError::Serialization(e.to_string()))?;
+    document.insert("_id", stored.actor.id.clone());
+    Ok(document)
+}
+
+/// A change to an actor's definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActorChange {
+    /// Apply or refresh a buff, kept under the actor's [`BUFFS`] data
+    BuffApplied {
+        buff_id: String,
+        #[serde(default)]
+        buff: serde_json::Value,
+    },
+    BuffRemoved { buff_id: String },
+    SubsystemEnabled { system_id: String },
+    SubsystemDisabled { system_id: String },
+}
+
+impl ActorChange {
+    /// Apply the change, returning whether the definition changed
+    fn apply(&self, actor: &mut Actor) -> bool {
+        match self {
+            ActorChange::BuffApplied { buff_id, buff } => {
+                let buffs = actor.data.entry(BUFFS.to_string()).or_insert_with(|| serde_json::json!({}));
+                if !buffs.is_object() {
+                    *buffs = serde_json::json!({});
+                }
+                let buffs = buffs.as_object_mut().expect("buffs were just made an object");
+                buffs.insert(buff_id.clone(), buff.clone()).as_ref() != Some(buff)
+            }
+            ActorChange::BuffRemoved { buff_id } => actor
+                .data
+                .get_mut(BUFFS)
+                .and_then(|buffs| buffs.as_object_mut())
+                .is_some_and(|buffs| buffs.remove(buff_id).is_some()),
+            ActorChange::SubsystemEnabled { system_id } => {
+                let before = (actor.subsystems.len(), actor.disabled_subsystems.len());
+                actor.enable_subsystem(system_id);
+                before != (actor.subsystems.len(), actor.disabled_subsystems.len())
+            }
+            ActorChange::SubsystemDisabled { system_id } => {
+                let before = (actor.subsystems.len(), actor.disabled_subsystems.len());
+                actor.disable_subsystem(system_id);
+                before != (actor.subsystems.len(), actor.disabled_subsystems.len())
+            }
+        }
+    }
+}
+
+/// How a stored definition differs from the cached one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Inconsistency {
+    /// Nothing is stored for the actor
+    Missing { actor_id: String },
+    /// The stored definition has another version
+    VersionDrift { actor_id: String, stored: i64, cached: i64 },
+    /// Both definitions have the same version but resolve to different snapshots
+    SnapshotDrift { actor_id: String, stored_hash: String, cached_hash: String },
+}
+
+/// Result of a consistency check
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConsistencyReport {
+    pub checked: usize,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+#[derive(Default)]
+struct Cache {
+    actors: HashMap<String, StoredActor>,
+    /// Actors changed since their last write
+    dirty: HashSet<String>,
+}
+
+/// Actor definitions served from memory and written behind
+pub struct ActorStore {
+    repository: Arc<dyn ActorRepository>,
+    aggregator: Arc<dyn Aggregator>,
+    cache: Mutex<Cache>,
+}
+
+impl ActorStore {
+    /// Store definitions in `repository`, resolving snapshots with `aggregator` for the
+    /// consistency checks
+    pub fn new(repository: Arc<dyn ActorRepository>, aggregator: Arc<dyn Aggregator>) -> Self {
+        Self { repository, aggregator, cache: Mutex::new(Cache::default()) }
+    }
+
+    /// Load the actors of a zone, keeping the cached definitions of those already loaded;
+    /// returns how many were loaded
+    pub async fn load_zone(&self, zone: &str) -> ChaosResult<usize> {
+        let loaded = self.repository.load_zone(zone).await?;
+        let mut cache = self.cache.lock();
+        let mut count = 0;
+        for stored in loaded {
+            if !cache.actors.contains_key(&stored.actor.id) {
+                cache.actors.insert(stored.actor.id.clone(), stored);
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Add a new definition, written on the next flush
+    pub fn insert(&self, zone: Option<String>, actor: Actor) {
+        let mut cache = self.cache.lock();
+        cache.dirty.insert(actor.id.clone());
+        cache.actors.insert(actor.id.clone(), StoredActor { zone, actor });
+    }
+
+    /// Cache a definition that is already stored
+    pub fn insert_stored(&self, stored: StoredActor) {
+        self.cache.lock().actors.insert(stored.actor.id.clone(), stored);
+    }
+
+    pub fn get(&self, actor_id: &str) -> Option<Actor> {
+        self.cache.lock().actors.get(actor_id).map(|stored| stored.actor.clone())
+    }
+
+    pub fn actors(&self) -> Vec<Actor> {
+        self.cache.lock().actors.values().map(|stored| stored.actor.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.lock().actors.is_empty()
+    }
+
+    /// Change an actor's definition, returning it as changed; `None` when it is unknown.
+    /// Changes bump the version and drop the cached snapshot.
+    pub fn apply(&self, actor_id: &str, change: &ActorChange) -> Option<Actor> {
+        let actor = {
+            let mut cache = self.cache.lock();
+            let actor = &mut cache.actors.get_mut(actor_id)?.actor;
+            if !change.apply(actor) {
+                return Some(actor.clone());
+            }
+            actor.version += 1;
+            actor.updated_at = chrono::Utc::now();
+            let actor = actor.clone();
+            cache.dirty.insert(actor_id.to_string());
+            actor
+        };
+        self.aggregator.invalidate_cache(&actor.id);
+        Some(actor)
+    }
+
+    /// Write the changed definitions, returning how many were written. Definitions that
+    /// could not be written stay dirty.
+    pub async fn flush(&self) -> ChaosResult<usize> {
+        let (ids, actors): (Vec<String>, Vec<StoredActor>) = {
+            let mut cache = self.cache.lock();
+            let dirty = std::mem::take(&mut cache.dirty);
+            let actors = dirty.iter().filter_map(|id| cache.actors.get(id).cloned()).collect();
+            (dirty.into_iter().collect(), actors)
+        };
+        if actors.is_empty() {
+            return Ok(0);
+        }
+        if let Err(e) = self.repository.save(&actors).await {
+            self.cache.lock().dirty.extend(ids);
+            return Err(e);
+        }
+        Ok(actors.len())
+    }
+
+    /// Compare the stored definitions of the actors without pending changes with the
+    /// cached ones, marking those that differ dirty so the next flush repairs them
+    pub async fn check(&self) -> ChaosResult<ConsistencyReport> {
+        let clean: Vec<Actor> = {
+            let cache = self.cache.lock();
+            let clean = cache.actors.values().filter(|stored| !cache.dirty.contains(&stored.actor.id));
+            clean.map(|stored| stored.actor.clone()).collect()
+        };
+        let ids: Vec<String> = clean.iter().map(|actor| actor.id.clone()).collect();
+        let loaded = self.repository.load(&ids).await?;
+        let mut stored: HashMap<String, Actor> =
+            loaded.into_iter().map(|stored| (stored.actor.id.clone(), stored.actor)).collect();
+
+        let mut report = ConsistencyReport { checked: clean.len(), inconsistencies: Vec::new() };
+        for cached in &clean {
+            let actor_id = cached.id.clone();
+            let inconsistency = match stored.remove(&cached.id) {
+                None => Inconsistency::Missing { actor_id },
+                Some(stored) if stored.version != cached.version => {
+                    Inconsistency::VersionDrift { actor_id, stored: stored.version, cached: cached.version }
+                }
+                Some(stored) => match (self.resolve_hash(&stored).await, self.resolve_hash(cached).await) {
+                    (Ok(stored_hash), Ok(cached_hash)) if stored_hash != cached_hash => {
+                        Inconsistency::SnapshotDrift { actor_id, stored_hash, cached_hash }
+                    }
+                    (Ok(_), Ok(_)) => continue,
+                    (Err(e), _) | (_, Err(e)) => {
+                        warn!("⚠️  Failed to resolve {} for the consistency check: {}", actor_id, e);
+                        continue;
+                    }
+                },
+            };
+            report.inconsistencies.push(inconsistency);
+        }
+
+        let mut cache = self.cache.lock();
+        for inconsistency in &report.inconsistencies {
+            let (Inconsistency::Missing { actor_id }
+            | Inconsistency::VersionDrift { actor_id, .. }
+            | Inconsistency::SnapshotDrift { actor_id, .. }) = inconsistency;
+            warn!("⚠️  Stored definition of {} is inconsistent: {:?}", actor_id, inconsistency);
+            if cache.actors.contains_key(actor_id) {
+                cache.dirty.insert(actor_id.clone());
+            }
+        }
+        Ok(report)
+    }
+
+    /// Flush every `every` until the task is aborted
+    pub fn spawn_flushes(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                match store.flush().await {
+                    Ok(count) => debug!("💾 Wrote {} actor definition(s)", count),
+                    Err(e) => warn!("⚠️  Actor flush failed, retrying in {:?}: {}", every, e),
+                }
+            }
+        })
+    }
+
+    /// Check consistency every `every` until the task is aborted
+    pub fn spawn_checks(self: &Arc<Self>, every: Duration) -> JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // The first tick is immediate; nothing was written yet
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                match store.check().await {
+                    Ok(report) => info!(
+                        "🔍 Checked {} stored actor definition(s), {} inconsistent",
+                        report.checked,
+                        report.inconsistencies.len()
+                    ),
+                    Err(e) => warn!("⚠️  Actor consistency check failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Hash of the snapshot a definition resolves to, bypassing the snapshot cache
+    async fn resolve_hash(&self, actor: &Actor) -> ActorCoreResult<String> {
+        self.aggregator.invalidate_cache(&actor.id);
+        let snapshot = self.aggregator.resolve(actor).await;
+        self.aggregator.invalidate_cache(&actor.id);
+        Ok(snapshot?.content_hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Definitions kept in memory, counting writes
+    #[derive(Default)]
+    struct MemoryRepository {
+        actors: Mutex<HashMap<String, StoredActor>>,
+        writes: Mutex<usize>,
+        failing: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl ActorRepository for MemoryRepository {
+        async fn load_zone(&self, zone: &str) -> ChaosResult<Vec<StoredActor>> {
+            let actors = self.actors.lock();
+            Ok(actors.values().filter(|stored| stored.zone.as_deref() == Some(zone)).cloned().collect())
+        }
+
+        async fn load(&self, actor_ids: &[String]) -> ChaosResult<Vec<StoredActor>> {
+            let actors = self.actors.lock();
+            Ok(actor_ids.iter().filter_map(|id| actors.get(id).cloned()).collect())
+        }
+
+        async fn save(&self, actors: &[StoredActor]) -> ChaosResult<()> {
+            if *self.failing.lock() {
+                return Err(ChaosError::Database("unreachable".to_string()));
+            }
+            *self.writes.lock() += actors.len();
+            let mut stored = self.actors.lock();
+            stored.extend(actors.iter().map(|actor| (actor.actor.id.clone(), actor.clone())));
+            Ok(())
+        }
+    }
+
+    fn repository(actors: &[(&str, &str)]) -> Arc<MemoryRepository> {
+        let repository = Arc::new(MemoryRepository::default());
+        for (id, zone) in actors {
+            let actor = Actor::new(id.to_string(), "Human".to_string());
+            repository.actors.lock().insert(id.to_string(), StoredActor { zone: Some(zone.to_string()), actor });
+        }
+        repository
+    }
+
+    async fn store(repository: Arc<MemoryRepository>) -> ActorStore {
+        let (aggregator, _cache) = quick_setup().await.unwrap();
+        ActorStore::new(repository, aggregator)
+    }
+
+    #[tokio::test]
+    async fn changes_are_written_behind_once_per_flush() {
+        let repository = repository(&[("knight", "keep"), ("squire", "keep"), ("crow", "moor")]);
+        let store = store(repository.clone()).await;
+        assert_eq!(store.load_zone("keep").await.unwrap(), 2);
+        assert!(store.get("crow").is_none());
+        assert_eq!(store.flush().await.unwrap(), 0);
+
+        let haste =
+            ActorChange::BuffApplied { buff_id: "haste".to_string(), buff: serde_json::json!({ "speed": 1.2 }) };
+        assert_eq!(store.apply("knight", &haste).unwrap().version, 2);
+        assert_eq!(store.apply("knight", &haste).unwrap().version, 2);
+        let enabled = ActorChange::SubsystemEnabled { system_id: "combat".to_string() };
+        let knight = store.apply("knight", &enabled).unwrap();
+        assert_eq!((knight.version, knight.subsystems.clone()), (3, vec!["combat".to_string()]));
+        assert!(store.apply("ghost", &enabled).is_none());
+
+        // Several changes, one write; failed writes are retried on the next flush
+        *repository.failing.lock() = true;
+        assert!(store.flush().await.is_err());
+        *repository.failing.lock() = false;
+        assert_eq!(store.flush().await.unwrap(), 1);
+        assert_eq!(*repository.writes.lock(), 1);
+        assert_eq!(repository.actors.lock()["knight"].actor.data[BUFFS]["haste"]["speed"], 1.2);
+
+        // Reloading the zone keeps the cached definitions
+        let removed = ActorChange::BuffRemoved { buff_id: "haste".to_string() };
+        store.apply("knight", &removed).unwrap();
+        assert_eq!(store.load_zone("keep").await.unwrap(), 0);
+        assert_eq!(store.get("knight").unwrap().version, 4);
+    }
+
+    #[tokio::test]
+    async fn inconsistent_definitions_are_reported_and_written_again() {
+        let repository = repository(&[("knight", "keep"), ("squire", "keep")]);
+        let store = store(repository.clone()).await;
+        store.load_zone("keep").await.unwrap();
+        store.insert(Some("keep".to_string()), Actor::new("page".to_string(), "Elf".to_string()));
+        assert_eq!(store.check().await.unwrap().checked, 2);
+
+        repository.actors.lock().remove("squire");
+        repository.actors.lock().get_mut("knight").unwrap().actor.version = 7;
+        let report = store.check().await.unwrap();
+        let mut found = report.inconsistencies.clone();
+        found.sort_by_key(|inconsistency| format!("{:?}", inconsistency));
+        assert_eq!(
+            found,
+            [
+                Inconsistency::Missing { actor_id: "squire".to_string() },
+                Inconsistency::VersionDrift { actor_id: "knight".to_string(), stored: 7, cached: 1 },
+            ]
+        );
+
+        // The cached definitions win
+        assert_eq!(store.flush().await.unwrap(), 3);
+        assert!(store.check().await.unwrap().inconsistencies.is_empty());
+        assert_eq!(repository.actors.lock()["knight"].actor.version, 1);
+    }
+}
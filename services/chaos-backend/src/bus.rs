@@ -0,0 +1,171 @@
+//! Event bus wiring for the game loop.
+//!
+//! Messages the simulation emits are queued on the [`World`] outbox during the tick and
+//! published from here, so a slow bus never stalls a tick. World events from
+//! event-service are applied to the world as they arrive.
+//!
+//! The service announces `Stopping` as soon as shutdown starts, so gateways stop
+//! routing to it while it drains, and `Stopped` once the outbox has been published.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use shared::events::topics::{LifecycleState, ServiceLifecycle, WorldEventChanged, WorldEventState, SERVICE_LIFECYCLE, WORLD_EVENT};
+use shared::events::{
+    connect, BusConfig, Checkpoints, Consumer, ConsumerOptions, EventBus, EventBusExt, EventEnvelope, FileCheckpoints,
+    MemoryCheckpoints, ReplayingBus,
+};
+use shared::shutdown::Shutdown;
+use shared::ChaosResult;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::game_loop::{SharedWorld, World};
+
+pub const SERVICE: &str = "chaos-backend";
+
+/// Consumer name, a single topic token
+const CONSUMER: &str = "chaos_backend";
+const CHECKPOINT_PATH: &str = "data/chaos-backend-checkpoints.json";
+const OUTBOX_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Bus connection and the tasks moving messages between it and the world
+pub struct GameBus {
+    bus: Arc<ReplayingBus>,
+    world: SharedWorld,
+    checkpoints: Arc<dyn Checkpoints>,
+    instance: String,
+    address: String,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl GameBus {
+    /// Connect, start publishing the world outbox and following world events
+    pub async fn start(world: SharedWorld, address: String, shutdown: &Shutdown) -> ChaosResult<Self> {
+        let inner = connect(&BusConfig::from_env()).await?;
+        info!("📡 Event bus: {}", inner.backend_name());
+        let bus = Arc::new(ReplayingBus::new(inner));
+        let mut tasks = vec![bus.clone().serve().await?];
+
+        let checkpoints: Arc<dyn Checkpoints> = match open_checkpoints() {
+            Ok(checkpoints) => Arc::new(checkpoints),
+            Err(e) => {
+                warn!("⚠️  Keeping bus checkpoints in memory, {} is unusable: {}", CHECKPOINT_PATH, e);
+                Arc::new(MemoryCheckpoints::default())
+            }
+        };
+        let events_world = world.clone();
+        tasks.push(
+            Consumer::new(bus.clone(), WORLD_EVENT, ConsumerOptions::new(CONSUMER))
+                .with_checkpoints(checkpoints.clone())
+                .spawn(move |event: WorldEventChanged, _| {
+                    apply_world_event(&mut events_world.lock(), event);
+                    async { Ok(()) }
+                })
+                .await?,
+        );
+
+        tasks.push(tokio::spawn(publish_outbox(bus.clone(), world.clone())));
+
+        let instance = Uuid::new_v4().to_string();
+        let stopping = {
+            let (bus, instance, address, signal) = (bus.clone(), instance.clone(), address.clone(), shutdown.signal());
+            async move {
+                signal.await;
+                announce(bus.as_ref(), &instance, &address, LifecycleState::Stopping).await;
+            }
+        };
+        tasks.push(tokio::spawn(stopping));
+
+        let game_bus = Self { bus, world, checkpoints, instance, address, tasks };
+        announce(game_bus.bus.as_ref(), &game_bus.instance, &game_bus.address, LifecycleState::Started).await;
+        Ok(game_bus)
+    }
+
+    /// The connection, for health checks
+    pub fn bus(&self) -> Arc<dyn EventBus> {
+        self.bus.clone()
+    }
+
+    /// Publish what is left in the outbox, save consumer progress and announce the service is gone
+    pub async fn stop(self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        let envelopes = self.world.lock().drain_outbox();
+        publish_all(self.bus.as_ref(), envelopes).await;
+        if let Err(e) = self.checkpoints.flush() {
+            warn!("⚠️  Failed to save bus checkpoints: {}", e);
+        }
+        announce(self.bus.as_ref(), &self.instance, &self.address, LifecycleState::Stopped).await;
+        if let Err(e) = self.bus.flush().await {
+            warn!("⚠️  Failed to flush the event bus: {}", e);
+        }
+    }
+}
+
+async fn announce(bus: &dyn EventBus, instance: &str, address: &str, state: LifecycleState) {
+    let lifecycle = ServiceLifecycle {
+        service: SERVICE.to_string(),
+        instance: instance.to_string(),
+        state,
+        address: Some(address.to_string()),
+    };
+    if let Err(e) = bus.publish_message(SERVICE, &SERVICE_LIFECYCLE, &lifecycle).await {
+        warn!("⚠️  Failed to announce {:?}: {}", state, e);
+    }
+}
+
+fn open_checkpoints() -> ChaosResult<FileCheckpoints> {
+    std::fs::create_dir_all("data")?;
+    FileCheckpoints::open(CHECKPOINT_PATH)
+}
+
+fn apply_world_event(world: &mut World, event: WorldEventChanged) {
+    match event.state {
+        WorldEventState::Started => {
+            info!("🎉 World event {} started", event.name);
+            world.world_events.insert(event.event_id.clone(), event);
+        }
+        WorldEventState::Ended => {
+            info!("🏁 World event {} ended", event.name);
+            world.world_events.remove(&event.event_id);
+        }
+    }
+}
+
+async fn publish_outbox(bus: Arc<ReplayingBus>, world: SharedWorld) {
+    let mut interval = tokio::time::interval(OUTBOX_INTERVAL);
+    loop {
+        interval.tick().await;
+        let envelopes = world.lock().drain_outbox();
+        publish_all(bus.as_ref(), envelopes).await;
+    }
+}
+
+async fn publish_all(bus: &dyn EventBus, envelopes: Vec<EventEnvelope>) {
+    for envelope in envelopes {
+        if let Err(e) = bus.publish_envelope(envelope.with_source(SERVICE)).await {
+            warn!("⚠️  Failed to publish world message: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(state: WorldEventState) -> WorldEventChanged {
+        WorldEventChanged { event_id: "invasion".to_string(), name: "Goblin Invasion".to_string(), zone: None, state, ends_at: None }
+    }
+
+    #[test]
+    fn world_events_are_tracked_until_they_end() {
+        let mut world = World::new();
+        apply_world_event(&mut world, event(WorldEventState::Started));
+        assert!(world.world_events.contains_key("invasion"));
+        apply_world_event(&mut world, event(WorldEventState::Ended));
+        assert!(world.world_events.is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+//! Character creation: the single entry point building a valid new character.
+//!
+//! A request names the character and picks a race and a job. The name is checked
+//! against the naming rules, the job against those open to the race. The new actor
+//! gets its race's [`ElementalParams`], its job's template, subsystems and starting
+//! items, and one leveling track per configured track at the starting level. It is
+//! resolved once before anything is written, so a character that cannot be resolved is
+//! never stored; the character and its actor definition are then written together.
+
+use std::sync::Arc;
+
+use actor_core::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use element_core::ElementalParams;
+use mongodb::bson::{self, doc, Document};
+use mongodb::options::IndexOptions;
+use mongodb::{Client, Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::{ChaosError, ChaosResult};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::actor_store::{self, ActorStore, StoredActor};
+
+pub const COLLECTION: &str = "characters";
+
+/// What characters can be created
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CharacterRules {
+    pub name_min: usize,
+    pub name_max: usize,
+    pub starting_level: i64,
+    /// Zone new characters are filed under in the actor store
+    pub zone: Option<String>,
+    /// Leveling tracks every character starts, e.g. `character` and `job`
+    pub tracks: Vec<String>,
+    pub races: Vec<RaceRules>,
+    pub jobs: Vec<JobRules>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceRules {
+    pub id: String,
+    /// Jobs the race can take, any job when empty
+    #[serde(default)]
+    pub jobs: Vec<String>,
+    pub elemental: ElementalParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRules {
+    pub id: String,
+    /// Actor template selecting the job's default subsystems
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Subsystems enabled on top of the template's
+    #[serde(default)]
+    pub subsystems: Vec<String>,
+    #[serde(default)]
+    pub starting_items: Vec<StartingItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartingItem {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+impl Default for CharacterRules {
+    fn default() -> Self {
+        let race = |id: &str, element: &str| RaceRules {
+            id: id.to_string(),
+            jobs: Vec::new(),
+            elemental: ElementalParams {
+                primary_element: element.to_string(),
+                initial_mastery_levels: [(element.to_string(), 1.0)].into(),
+                initial_experience: [(element.to_string(), 0.0)].into(),
+                initial_qi_amounts: [(element.to_string(), 100.0)].into(),
+                elemental_preferences: vec![element.to_string()],
+            },
+        };
+        let job = |id: &str, item_id: &str| JobRules {
+            id: id.to_string(),
+            template: None,
+            subsystems: Vec::new(),
+            starting_items: vec![StartingItem { item_id: item_id.to_string(), quantity: 1 }],
+        };
+        Self {
+            name_min: 3,
+            name_max: 16,
+            starting_level: 1,
+            zone: None,
+            tracks: vec!["character".to_string(), "job".to_string()],
+            races: vec![race("Human", "fire"), race("Elf", "wood"), race("Dwarf", "earth")],
+            jobs: vec![job("warrior", "training_sword"), job("mage", "apprentice_staff")],
+        }
+    }
+}
+
+impl CharacterRules {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name_min == 0 || self.name_max < self.name_min {
+            return Err("characters.name_min must be at least 1 and at most characters.name_max".to_string());
+        }
+        if self.starting_level < 1 || self.tracks.is_empty() {
+            return Err("characters.starting_level must be at least 1 with at least one track".to_string());
+        }
+        for race in &self.races {
+            if race.elemental.primary_element.is_empty() {
+                return Err(format!("race {} has no primary element", race.id));
+            }
+            if let Some(job) = race.jobs.iter().find(|job| self.job(job).is_none()) {
+                return Err(format!("race {} lists unknown job {}", race.id, job));
+            }
+        }
+        Ok(())
+    }
+
+    fn race(&self, id: &str) -> Option<&RaceRules> {
+        self.races.iter().find(|race| race.id == id)
+    }
+
+    fn job(&self, id: &str) -> Option<&JobRules> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateCharacter {
+    pub name: String,
+    pub race: String,
+    pub job: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelingTrack {
+    pub track: String,
+    pub level: i64,
+    pub experience: u64,
+}
+
+/// A created character, stored next to its actor definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Character {
+    pub actor_id: String,
+    pub name: String,
+    pub race: String,
+    pub job: String,
+    pub elemental: ElementalParams,
+    pub tracks: Vec<LevelingTrack>,
+    pub items: Vec<StartingItem>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedCharacter {
+    pub character: Character,
+    pub actor: Actor,
+    pub snapshot: Snapshot,
+}
+
+#[derive(Debug, Error)]
+pub enum CreationError {
+    #[error("Invalid name: {0}")]
+    InvalidName(String),
+    #[error("Unknown race {0}")]
+    UnknownRace(String),
+    #[error("Unknown job {0}")]
+    UnknownJob(String),
+    #[error("{race} cannot take the {job} job")]
+    JobUnavailable { race: String, job: String },
+    #[error("Name {0} is taken")]
+    NameTaken(String),
+    #[error("Failed to resolve the character: {0}")]
+    Resolve(#[from] ActorCoreError),
+    #[error(transparent)]
+    Storage(#[from] ChaosError),
+}
+
+/// Storage of created characters
+#[async_trait]
+pub trait CharacterRepository: Send + Sync {
+    /// Store a character and its actor definition, both or neither; `Ok(false)` when
+    /// another character has its name
+    async fn create(&self, character: &Character, actor: &StoredActor) -> ChaosResult<bool>;
+}
+
+/// Characters in the [`COLLECTION`] collection, written in one transaction with their
+/// actor definitions; names are unique regardless of case
+pub struct MongoCharacterRepository {
+    client: Client,
+    characters: Collection<Document>,
+    actors: Collection<Document>,
+}
+
+impl MongoCharacterRepository {
+    pub fn new(client: &Client, database: &Database) -> Self {
+        Self {
+            client: client.clone(),
+            characters: database.collection(COLLECTION),
+            actors: database.collection(actor_store::COLLECTION),
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> ChaosResult<()> {
+        let index = IndexModel::builder()
+            .keys(doc! { "name_key": 1 })
+            .options(IndexOptions::builder().name("name_key".to_string()).unique(true).build())
+            .build();
+        self.characters
+            .create_index(index, None)
+            .await
+            .map_err(|e| ChaosError::Database(format!("Failed to index characters: {}", e)))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CharacterRepository for MongoCharacterRepository {
+    async fn create(&self, character: &Character, actor: &StoredActor) -> ChaosResult<bool> {
+        let database_error = |e: mongodb::error::Error| {
+            ChaosError::Database(format!("Failed to create character {}: {}", character.actor_id, e))
+        };
+        let mut document = bson::to_document(character).map_err(|e| ChaosError::Serialization(e.to_string()))?;
+        document.insert("_id", character.actor_id.clone());
+        document.insert("name_key", character.name.to_lowercase());
+        let actor = actor_store::actor_document(actor)?;
+
+        let mut session = self.client.start_session(None).await.map_err(database_error)?;
+        session.start_transaction(None).await.map_err(database_error)?;
+        let inserted = match self.characters.insert_one_with_session(document, None, &mut session).await {
+            Ok(_) => self.actors.insert_one_with_session(actor, None, &mut session).await.map(|_| true),
+            Err(e) if is_duplicate_key_error(&e) => Ok(false),
+            Err(e) => Err(e),
+        };
+        match inserted {
+            Ok(true) => session.commit_transaction().await.map(|_| true).map_err(database_error),
+            Ok(false) => {
+                session.abort_transaction().await.ok();
+                Ok(false)
+            }
+            Err(e) => {
+                session.abort_transaction().await.ok();
+                Err(database_error(e))
+            }
+        }
+    }
+}
+
+fn is_duplicate_key_error(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(write_error))
+            if write_error.code == 11000
+    )
+}
+
+/// Builds, stores and resolves new characters
+pub struct CharacterCreationService {
+    rules: CharacterRules,
+    repository: Arc<dyn CharacterRepository>,
+    actors: Arc<ActorStore>,
+    aggregator: Arc<dyn Aggregator>,
+}
+
+impl CharacterCreationService {
+    pub fn new(
+        rules: CharacterRules,
+        repository: Arc<dyn CharacterRepository>,
+        actors: Arc<ActorStore>,
+        aggregator: Arc<dyn Aggregator>,
+    ) -> Self {
+        Self { rules, repository, actors, aggregator }
+    }
+
+    /// Create a character, returning it with its first snapshot
+    pub async fn create(&self, request: &CreateCharacter) -> Result<CreatedCharacter, CreationError> {
+        let name = self.check_name(&request.name)?;
+        let race = self.rules.race(&request.race).ok_or_else(|| CreationError::UnknownRace(request.race.clone()))?;
+        let job = self.rules.job(&request.job).ok_or_else(|| CreationError::UnknownJob(request.job.clone()))?;
+        if !race.jobs.is_empty() && !race.jobs.contains(&job.id) {
+            return Err(CreationError::JobUnavailable { race: race.id.clone(), job: job.id.clone() });
+        }
+
+        let mut actor = Actor::new(Uuid::new_v4().to_string(), race.id.clone());
+        actor.name = name.clone();
+        actor.level = self.rules.starting_level;
+        actor.template = job.template.clone();
+        for system_id in &job.subsystems {
+            actor.enable_subsystem(system_id);
+        }
+        let elemental = race.elemental.clone();
+        actor.data.insert("job".to_string(), serde_json::Value::String(job.id.clone()));
+        actor.data.insert("primary_element".to_string(), serde_json::Value::String(elemental.primary_element.clone()));
+
+        let character = Character {
+            actor_id: actor.id.clone(),
+            name,
+            race: race.id.clone(),
+            job: job.id.clone(),
+            elemental,
+            tracks: self
+                .rules
+                .tracks
+                .iter()
+                .map(|track| LevelingTrack { track: track.clone(), level: self.rules.starting_level, experience: 0 })
+                .collect(),
+            items: job.starting_items.clone(),
+            created_at: actor.created_at,
+        };
+
+        let snapshot = self.aggregator.resolve(&actor).await?;
+        let stored = StoredActor { zone: self.rules.zone.clone(), actor: actor.clone() };
+        match self.repository.create(&character, &stored).await {
+            Ok(true) => {}
+            Ok(false) => {
+                self.aggregator.invalidate_cache(&actor.id);
+                return Err(CreationError::NameTaken(character.name));
+            }
+            Err(e) => {
+                self.aggregator.invalidate_cache(&actor.id);
+                return Err(e.into());
+            }
+        }
+        self.actors.insert_stored(stored);
+        Ok(CreatedCharacter { character, actor, snapshot })
+    }
+
+    /// The name without surrounding whitespace, letters and digits only
+    fn check_name(&self, name: &str) -> Result<String, CreationError> {
+        let name = name.trim();
+        let length = name.chars().count();
+        if length < self.rules.name_min || length > self.rules.name_max {
+            return Err(CreationError::InvalidName(format!(
+                "names have {} to {} characters",
+                self.rules.name_min, self.rules.name_max
+            )));
+        }
+        if !name.chars().all(char::is_alphanumeric) {
+            return Err(CreationError::InvalidName("names have letters and digits only".to_string()));
+        }
+        Ok(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::actor_store::ActorRepository;
+
+    /// Characters and actor definitions kept in memory
+    #[derive(Default)]
+    struct MemoryRepository {
+        characters: Mutex<HashMap<String, Character>>,
+        actors: Mutex<Vec<StoredActor>>,
+    }
+
+    #[async_trait]
+    impl CharacterRepository for MemoryRepository {
+        async fn create(&self, character: &Character, actor: &StoredActor) -> ChaosResult<bool> {
+            let mut characters = self.characters.lock();
+            let key = character.name.to_lowercase();
+            if characters.contains_key(&key) {
+                return Ok(false);
+            }
+            characters.insert(key, character.clone());
+            self.actors.lock().push(actor.clone());
+            Ok(true)
+        }
+    }
+
+    #[async_trait]
+    impl ActorRepository for MemoryRepository {
+        async fn load_zone(&self, _zone: &str) -> ChaosResult<Vec<StoredActor>> {
+            Ok(Vec::new())
+        }
+
+        async fn load(&self, _actor_ids: &[String]) -> ChaosResult<Vec<StoredActor>> {
+            Ok(Vec::new())
+        }
+
+        async fn save(&self, actors: &[StoredActor]) -> ChaosResult<()> {
+            self.actors.lock().extend_from_slice(actors);
+            Ok(())
+        }
+    }
+
+    async fn service() -> (CharacterCreationService, Arc<MemoryRepository>, Arc<ActorStore>) {
+        let mut rules = CharacterRules { zone: Some("zone_01_forest".to_string()), ..Default::default() };
+        rules.races[1].jobs = vec!["mage".to_string()];
+        rules.jobs[0].subsystems = vec!["combat".to_string()];
+        rules.validate().unwrap();
+
+        let repository = Arc::new(MemoryRepository::default());
+        let (aggregator, _cache) = quick_setup().await.unwrap();
+        let actors = Arc::new(ActorStore::new(repository.clone(), aggregator.clone()));
+        let service = CharacterCreationService::new(rules, repository.clone(), actors.clone(), aggregator);
+        (service, repository, actors)
+    }
+
+    fn request(name: &str, race: &str, job: &str) -> CreateCharacter {
+        CreateCharacter { name: name.to_string(), race: race.to_string(), job: job.to_string() }
+    }
+
+    #[tokio::test]
+    async fn characters_are_built_from_their_race_and_job() {
+        let (service, repository, actors) = service().await;
+        let created = service.create(&request("  Linwen ", "Human", "warrior")).await.unwrap();
+
+        let actor = &created.actor;
+        assert_eq!((actor.name.as_str(), actor.race.as_str(), actor.level), ("Linwen", "Human", 1));
+        assert_eq!(actor.subsystems, ["combat"]);
+        assert_eq!(actor.data["primary_element"], "fire");
+        assert_eq!(created.snapshot.actor_id, actor.id);
+
+        let character = &created.character;
+        assert_eq!(character.elemental.initial_qi_amounts["fire"], 100.0);
+        assert_eq!(character.tracks.iter().map(|track| track.track.as_str()).collect::<Vec<_>>(), ["character", "job"]);
+        assert_eq!(character.items, [StartingItem { item_id: "training_sword".to_string(), quantity: 1 }]);
+
+        // Stored once, together, and cached without a pending write
+        assert_eq!(repository.characters.lock()["linwen"].actor_id, actor.id);
+        assert_eq!(repository.actors.lock()[0].zone.as_deref(), Some("zone_01_forest"));
+        assert_eq!(actors.get(&actor.id).unwrap().name, "Linwen");
+        assert_eq!(actors.flush().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn invalid_choices_create_nothing() {
+        let (service, repository, actors) = service().await;
+        service.create(&request("Thordor", "Dwarf", "warrior")).await.unwrap();
+
+        let failures = [
+            service.create(&request("Al", "Human", "warrior")).await,
+            service.create(&request("Ka-ra", "Human", "warrior")).await,
+            service.create(&request("Mirael", "Orc", "warrior")).await,
+            service.create(&request("Mirael", "Human", "bard")).await,
+            service.create(&request("Mirael", "Elf", "warrior")).await,
+            service.create(&request("THORDOR", "Human", "mage")).await,
+        ];
+        let failures: Vec<String> = failures.into_iter().map(|result| result.unwrap_err().to_string()).collect();
+        assert_eq!(
+            failures,
+            [
+                "Invalid name: names have 3 to 16 characters",
+                "Invalid name: names have letters and digits only",
+                "Unknown race Orc",
+                "Unknown job bard",
+                "Elf cannot take the warrior job",
+                "Name THORDOR is taken",
+            ]
+        );
+        assert_eq!(repository.actors.lock().len(), 1);
+        assert_eq!(actors.actors().len(), 1);
+    }
+}
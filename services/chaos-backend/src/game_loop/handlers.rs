@@ -0,0 +1,335 @@
+//! Built-in tick handlers.
+
+use std::time::Duration;
+
+use shared::events::topics::{ActorDespawned, ActorSpawned, DespawnReason, ACTOR_DESPAWNED, ACTOR_SPAWNED};
+use tracing::{debug, warn};
+
+use super::world::{ActorState, World};
+use super::{HandlerConfig, TickContext, TickHandler};
+
+/// Refills resources at their regeneration rate; dead actors do not regenerate
+pub struct Regeneration {
+    config: HandlerConfig,
+}
+
+/// Pulses status effects and drops them once they expire
+pub struct StatusTicks {
+    config: HandlerConfig,
+}
+
+/// Removes dead spawned actors and respawns them after their spawn point's delay
+pub struct Spawners {
+    config: HandlerConfig,
+}
+
+/// Runs jobs scheduled on the world once they are due
+pub struct Scheduler {
+    config: HandlerConfig,
+}
+
+impl Regeneration {
+    pub fn new(config: HandlerConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TickHandler for Regeneration {
+    fn name(&self) -> &str {
+        "regeneration"
+    }
+
+    fn budget(&self) -> Duration {
+        self.config.budget()
+    }
+
+    fn every_ticks(&self) -> u64 {
+        self.config.every_ticks
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext, world: &mut World) {
+        let secs = ctx.delta.as_secs_f64();
+        if secs == 0.0 {
+            return;
+        }
+        for actor in world.actors.values_mut().filter(|actor| actor.is_alive()) {
+            for resource in actor.resources.values_mut() {
+                if resource.regen_per_sec != 0.0 {
+                    resource.add(resource.regen_per_sec * secs);
+                }
+            }
+        }
+    }
+}
+
+impl StatusTicks {
+    pub fn new(config: HandlerConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TickHandler for StatusTicks {
+    fn name(&self) -> &str {
+        "status_ticks"
+    }
+
+    fn budget(&self) -> Duration {
+        self.config.budget()
+    }
+
+    fn every_ticks(&self) -> u64 {
+        self.config.every_ticks
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext, world: &mut World) {
+        for actor in world.actors.values_mut() {
+            if actor.statuses.is_empty() {
+                continue;
+            }
+            let mut statuses = std::mem::take(&mut actor.statuses);
+            for status in &mut statuses {
+                // Pulses only count up to the end of the effect
+                status.since_pulse += ctx.delta.min(status.remaining);
+                status.remaining = status.remaining.saturating_sub(ctx.delta);
+                if status.interval.is_zero() {
+                    continue;
+                }
+                while status.since_pulse >= status.interval {
+                    status.since_pulse -= status.interval;
+                    if let Some(resource) = actor.resources.get_mut(&status.resource) {
+                        resource.add(status.per_pulse);
+                    }
+                }
+            }
+            statuses.retain(|status| !status.remaining.is_zero());
+            actor.statuses = statuses;
+        }
+    }
+}
+
+impl Spawners {
+    pub fn new(config: HandlerConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TickHandler for Spawners {
+    fn name(&self) -> &str {
+        "spawners"
+    }
+
+    fn budget(&self) -> Duration {
+        self.config.budget()
+    }
+
+    fn every_ticks(&self) -> u64 {
+        self.config.every_ticks
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext, world: &mut World) {
+        let mut spawn_points = std::mem::take(&mut world.spawn_points);
+        for point in &mut spawn_points {
+            let dead: Vec<String> = world
+                .actors
+                .values()
+                .filter(|actor| actor.spawn_point.as_deref() == Some(point.id.as_str()) && !actor.is_alive())
+                .map(|actor| actor.id.clone())
+                .collect();
+            for id in dead {
+                world.actors.remove(&id);
+                point.respawns.push(point.respawn_time());
+                world.emit(
+                    &ACTOR_DESPAWNED,
+                    &ActorDespawned { actor_id: id, zone: Some(point.zone.clone()), reason: DespawnReason::Died },
+                );
+            }
+
+            for respawn in &mut point.respawns {
+                *respawn = respawn.saturating_sub(ctx.delta);
+            }
+            point.respawns.retain(|respawn| !respawn.is_zero());
+
+            // Pending respawns hold their slot until their timer runs out
+            let alive = world.actors.values().filter(|actor| actor.spawn_point.as_deref() == Some(point.id.as_str())).count();
+            let missing = (point.max_alive as usize).saturating_sub(alive + point.respawns.len());
+            for _ in 0..missing {
+                point.spawned += 1;
+                let mut actor = ActorState::new(format!("{}_{}", point.id, point.spawned)).in_zone(point.zone.clone());
+                actor.spawn_point = Some(point.id.clone());
+                debug!("Spawned {} in {}", actor.id, point.zone);
+                world.emit(
+                    &ACTOR_SPAWNED,
+                    &ActorSpawned { actor_id: actor.id.clone(), zone: actor.zone.clone(), spawn_point: Some(point.id.clone()) },
+                );
+                world.add_actor(actor);
+            }
+        }
+        world.spawn_points = spawn_points;
+    }
+}
+
+impl Scheduler {
+    pub fn new(config: HandlerConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl TickHandler for Scheduler {
+    fn name(&self) -> &str {
+        "scheduler"
+    }
+
+    fn budget(&self) -> Duration {
+        self.config.budget()
+    }
+
+    fn every_ticks(&self) -> u64 {
+        self.config.every_ticks
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext, world: &mut World) {
+        // Jobs left over once the budget is spent run on the next tick
+        while !ctx.over_budget() {
+            let Some(mut due) = world.next_due(ctx.now) else {
+                break;
+            };
+            (due.job)(world);
+            let Some(every) = due.every.filter(|every| !every.is_zero()) else {
+                continue;
+            };
+            let every = chrono::Duration::from_std(every).unwrap_or(chrono::Duration::MAX);
+            let mut next = due.due + every;
+            if next <= ctx.now {
+                // Fell behind, e.g. after a pause; skip the missed runs instead of bursting
+                warn!("Scheduled job {} is behind on tick {}, skipping missed runs", due.name, ctx.tick);
+                next = ctx.now + every;
+            }
+            world.reschedule(due, next);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_loop::world::{SpawnPoint, StatusEffect, HEALTH, MANA};
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Instant;
+
+    fn ctx(tick: u64, delta: Duration) -> TickContext {
+        TickContext { tick, now: Utc::now(), delta, deadline: Instant::now() + Duration::from_secs(1) }
+    }
+
+    fn config() -> HandlerConfig {
+        HandlerConfig { budget_ms: 5.0, every_ticks: 1 }
+    }
+
+    #[test]
+    fn regeneration_refills_living_actors_only() {
+        let mut world = World::new();
+        let mut alive = ActorState::new("alive");
+        alive.resources.get_mut(MANA).unwrap().current = 10.0;
+        let mut dead = ActorState::new("dead");
+        dead.resources.get_mut(HEALTH).unwrap().current = 0.0;
+        dead.resources.get_mut(MANA).unwrap().current = 10.0;
+        world.add_actor(alive);
+        world.add_actor(dead);
+
+        Regeneration::new(config()).on_tick(&ctx(1, Duration::from_secs(4)), &mut world);
+
+        assert_eq!(world.actors["alive"].resources[MANA].current, 12.0);
+        assert_eq!(world.actors["dead"].resources[MANA].current, 10.0);
+    }
+
+    #[test]
+    fn status_effects_pulse_and_expire() {
+        let mut world = World::new();
+        let mut actor = ActorState::new("target");
+        actor.statuses.push(StatusEffect {
+            id: "poison".to_string(),
+            resource: HEALTH.to_string(),
+            per_pulse: -5.0,
+            interval: Duration::from_secs(1),
+            remaining: Duration::from_millis(2500),
+            since_pulse: Duration::ZERO,
+        });
+        world.add_actor(actor);
+        let mut handler = StatusTicks::new(config());
+
+        handler.on_tick(&ctx(1, Duration::from_millis(1500)), &mut world);
+        assert_eq!(world.actors["target"].resources[HEALTH].current, 95.0);
+
+        handler.on_tick(&ctx(2, Duration::from_secs(5)), &mut world);
+        assert_eq!(world.actors["target"].resources[HEALTH].current, 90.0);
+        assert!(world.actors["target"].statuses.is_empty());
+    }
+
+    #[test]
+    fn spawners_fill_up_then_respawn_after_delay() {
+        let mut world = World::new();
+        world.spawn_points.push(SpawnPoint {
+            id: "wolves".to_string(),
+            zone: "forest".to_string(),
+            max_alive: 2,
+            respawn_secs: 10,
+            respawns: Vec::new(),
+            spawned: 0,
+        });
+        let mut handler = Spawners::new(config());
+
+        handler.on_tick(&ctx(1, Duration::ZERO), &mut world);
+        assert_eq!(world.actors.len(), 2);
+        let spawned = world.drain_outbox();
+        assert_eq!(spawned.len(), 2);
+        assert!(spawned.iter().all(|envelope| envelope.topic == ACTOR_SPAWNED.name()));
+
+        world.actors.get_mut("wolves_1").unwrap().resources.get_mut(HEALTH).unwrap().current = 0.0;
+        handler.on_tick(&ctx(2, Duration::from_secs(1)), &mut world);
+        assert_eq!(world.actors.len(), 1);
+        let despawned = world.drain_outbox();
+        assert_eq!(despawned.len(), 1);
+        let despawned: ActorDespawned = despawned[0].decode_message().unwrap();
+        assert_eq!(despawned.actor_id, "wolves_1");
+        assert_eq!(despawned.reason, DespawnReason::Died);
+
+        handler.on_tick(&ctx(3, Duration::from_secs(5)), &mut world);
+        assert_eq!(world.actors.len(), 1);
+        handler.on_tick(&ctx(4, Duration::from_secs(5)), &mut world);
+        assert_eq!(world.actors.len(), 2);
+        assert!(world.actors.contains_key("wolves_3"));
+    }
+
+    #[test]
+    fn scheduler_runs_due_jobs_and_repeats() {
+        let mut world = World::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        let now = Utc::now();
+        world.schedule_every(
+            "count",
+            now,
+            Duration::from_secs(10),
+            Box::new(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            }),
+        );
+        world.schedule_at("later", now + chrono::Duration::hours(1), Box::new(|_| panic!("not due yet")));
+        let mut handler = Scheduler::new(config());
+
+        let mut tick = ctx(1, Duration::ZERO);
+        tick.now = now;
+        handler.on_tick(&tick, &mut world);
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+        assert_eq!(world.scheduled_jobs(), 2);
+
+        tick.now = now + chrono::Duration::seconds(5);
+        handler.on_tick(&tick, &mut world);
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+
+        tick.now = now + chrono::Duration::seconds(10);
+        handler.on_tick(&tick, &mut world);
+        assert_eq!(runs.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,105 @@
+//! Tick timings and overrun counters.
+
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+/// Updated by the game loop, read by the HTTP server
+#[derive(Debug)]
+pub struct TickMetrics {
+    stats: Mutex<TickStats>,
+}
+
+/// Point-in-time view of the game loop
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TickStats {
+    pub tick_rate: u32,
+    pub ticks: u64,
+    /// Ticks that took longer than the tick period
+    pub overruns: u64,
+    /// Ticks dropped because the loop fell behind
+    pub skipped_ticks: u64,
+    pub last_tick_us: u64,
+    pub mean_tick_us: u64,
+    pub max_tick_us: u64,
+    pub handlers: Vec<HandlerStats>,
+    #[serde(skip)]
+    total_tick_us: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HandlerStats {
+    pub name: String,
+    pub budget_us: u64,
+    /// Runs every this many ticks
+    pub every_ticks: u64,
+    pub runs: u64,
+    /// Runs that took longer than the budget
+    pub overruns: u64,
+    pub panics: u64,
+    pub last_us: u64,
+    pub mean_us: u64,
+    pub max_us: u64,
+    #[serde(skip)]
+    total_us: u64,
+}
+
+impl TickMetrics {
+    pub fn new(tick_rate: u32, handlers: Vec<HandlerStats>) -> Self {
+        Self { stats: Mutex::new(TickStats { tick_rate, handlers, ..TickStats::default() }) }
+    }
+
+    pub fn snapshot(&self) -> TickStats {
+        self.stats.lock().clone()
+    }
+
+    pub fn set_tick_rate(&self, tick_rate: u32) {
+        self.stats.lock().tick_rate = tick_rate;
+    }
+
+    /// Record one tick, returning the overrun count so far when this one overran
+    pub fn record_tick(&self, elapsed: Duration, period: Duration, skipped: u64) -> Option<u64> {
+        let mut stats = self.stats.lock();
+        let us = elapsed.as_micros() as u64;
+        stats.ticks += 1;
+        stats.skipped_ticks += skipped;
+        stats.last_tick_us = us;
+        stats.total_tick_us += us;
+        stats.mean_tick_us = stats.total_tick_us / stats.ticks;
+        stats.max_tick_us = stats.max_tick_us.max(us);
+        if elapsed > period {
+            stats.overruns += 1;
+            Some(stats.overruns)
+        } else {
+            None
+        }
+    }
+
+    /// Record one handler run, returning the overrun count so far when this one overran
+    pub fn record_handler(&self, index: usize, elapsed: Duration, panicked: bool) -> Option<u64> {
+        let mut stats = self.stats.lock();
+        let handler = &mut stats.handlers[index];
+        let us = elapsed.as_micros() as u64;
+        handler.runs += 1;
+        handler.last_us = us;
+        handler.total_us += us;
+        handler.mean_us = handler.total_us / handler.runs;
+        handler.max_us = handler.max_us.max(us);
+        if panicked {
+            handler.panics += 1;
+        }
+        if us > handler.budget_us {
+            handler.overruns += 1;
+            Some(handler.overruns)
+        } else {
+            None
+        }
+    }
+}
+
+impl HandlerStats {
+    pub fn new(name: &str, budget: Duration, every_ticks: u64) -> Self {
+        Self { name: name.to_string(), budget_us: budget.as_micros() as u64, every_ticks, ..Self::default() }
+    }
+}
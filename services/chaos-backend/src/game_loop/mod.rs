@@ -0,0 +1,390 @@
+//! Game Loop
+//!
+//! Fixed-rate tick driver for the simulation. Every tick runs the registered
+//! [`TickHandler`]s in order against the [`World`]; handlers may run only every few
+//! ticks and get the game time elapsed since their last run, taken from the
+//! [`SharedClock`] so pausing or scaling game time applies to the simulation too.
+//!
+//! The rate follows the `tick_rate` runtime flag and is re-read after every tick.
+//! Handlers have a time budget: going over it is counted and logged, and handlers
+//! doing open-ended work (like the scheduler) stop early once it is spent. A tick
+//! longer than the tick period is an overrun; ticks missed while falling behind are
+//! skipped, not caught up in a burst.
+
+pub mod handlers;
+pub mod metrics;
+pub mod world;
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use shared::{SharedClock, Timestamp};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+
+use crate::RuntimeFlags;
+
+pub use handlers::{Regeneration, Scheduler, Spawners, StatusTicks};
+pub use metrics::{HandlerStats, TickMetrics, TickStats};
+pub use world::{ActorState, SharedWorld, SpawnPoint, World};
+
+/// Runtime flag holding the tick rate in Hz
+pub const TICK_RATE_FLAG: &str = "tick_rate";
+pub const DEFAULT_TICK_RATE: u32 = 60;
+pub const MAX_TICK_RATE: u32 = 1000;
+
+/// Work done on every tick, or every few ticks
+pub trait TickHandler: Send {
+    fn name(&self) -> &str;
+
+    /// Time a run may take before it counts as an overrun
+    fn budget(&self) -> Duration;
+
+    /// Run every this many ticks
+    fn every_ticks(&self) -> u64 {
+        1
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext, world: &mut World);
+}
+
+/// What a handler gets to know about the current tick
+#[derive(Debug, Clone)]
+pub struct TickContext {
+    pub tick: u64,
+    /// Game time of the tick
+    pub now: Timestamp,
+    /// Game time since the handler's previous run, zero on its first run
+    pub delta: Duration,
+    /// End of the handler's budget
+    pub deadline: Instant,
+}
+
+impl TickContext {
+    /// Whether the handler has used up its budget
+    pub fn over_budget(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HandlerConfig {
+    pub budget_ms: f64,
+    #[serde(default = "default_every_ticks")]
+    pub every_ticks: u64,
+}
+
+impl HandlerConfig {
+    pub fn budget(&self) -> Duration {
+        Duration::from_secs_f64(self.budget_ms.max(0.0) / 1000.0)
+    }
+}
+
+fn default_every_ticks() -> u64 {
+    1
+}
+
+/// `game_loop` section of the service config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameLoopConfig {
+    pub regeneration: HandlerConfig,
+    pub status_ticks: HandlerConfig,
+    pub spawners: HandlerConfig,
+    pub scheduler: HandlerConfig,
+    pub spawn_points: Vec<SpawnPoint>,
+}
+
+impl Default for GameLoopConfig {
+    fn default() -> Self {
+        Self {
+            regeneration: HandlerConfig { budget_ms: 2.0, every_ticks: 1 },
+            status_ticks: HandlerConfig { budget_ms: 2.0, every_ticks: 1 },
+            spawners: HandlerConfig { budget_ms: 1.0, every_ticks: 10 },
+            scheduler: HandlerConfig { budget_ms: 2.0, every_ticks: 1 },
+            spawn_points: Vec::new(),
+        }
+    }
+}
+
+struct Slot {
+    handler: Box<dyn TickHandler>,
+    last_run: Option<Timestamp>,
+}
+
+/// Drives the registered handlers at the configured tick rate
+pub struct TickDriver {
+    clock: SharedClock,
+    flags: RuntimeFlags,
+    world: SharedWorld,
+    handlers: Vec<Slot>,
+}
+
+/// Running game loop
+pub struct TickHandle {
+    metrics: Arc<TickMetrics>,
+    shutdown: watch::Sender<bool>,
+    task: JoinHandle<()>,
+}
+
+impl TickDriver {
+    pub fn new(clock: SharedClock, flags: RuntimeFlags, world: SharedWorld) -> Self {
+        Self { clock, flags, world, handlers: Vec::new() }
+    }
+
+    /// Driver with the built-in handlers, in the order they run
+    pub fn with_defaults(config: &GameLoopConfig, clock: SharedClock, flags: RuntimeFlags, world: SharedWorld) -> Self {
+        Self::new(clock, flags, world)
+            .with_handler(Spawners::new(config.spawners))
+            .with_handler(StatusTicks::new(config.status_ticks))
+            .with_handler(Regeneration::new(config.regeneration))
+            .with_handler(Scheduler::new(config.scheduler))
+    }
+
+    /// Register a handler; handlers run in registration order
+    pub fn with_handler(mut self, handler: impl TickHandler + 'static) -> Self {
+        self.handlers.push(Slot { handler: Box::new(handler), last_run: None });
+        self
+    }
+
+    /// Start the loop on the runtime
+    pub fn spawn(self) -> TickHandle {
+        let rate = tick_rate(&self.flags).unwrap_or(DEFAULT_TICK_RATE);
+        let handlers = self
+            .handlers
+            .iter()
+            .map(|slot| HandlerStats::new(slot.handler.name(), slot.handler.budget(), slot.handler.every_ticks().max(1)))
+            .collect();
+        let metrics = Arc::new(TickMetrics::new(rate, handlers));
+        let (shutdown, stopped) = watch::channel(false);
+        let task = tokio::spawn(self.run(rate, metrics.clone(), stopped));
+        TickHandle { metrics, shutdown, task }
+    }
+
+    async fn run(mut self, mut rate: u32, metrics: Arc<TickMetrics>, mut stopped: watch::Receiver<bool>) {
+        let mut interval = ticker(rate);
+        let mut tick = 0u64;
+        info!("Game loop started at {} Hz with {} handler(s)", rate, self.handlers.len());
+        self.check_budgets(rate);
+
+        loop {
+            let scheduled = tokio::select! {
+                scheduled = interval.tick() => scheduled,
+                _ = stopped.changed() => break,
+            };
+            let period = period(rate);
+            // Ticks that should have happened while this one was late
+            let skipped = (scheduled.elapsed().as_nanos() / period.as_nanos()) as u64;
+
+            let elapsed = self.run_tick(tick, &metrics);
+            if let Some(overruns) = metrics.record_tick(elapsed, period, skipped) {
+                // Every overrun would flood the log when the loop is overloaded
+                if overruns.is_power_of_two() {
+                    warn!("Tick {} took {:?}, over the {:?} tick period ({} overruns)", tick, elapsed, period, overruns);
+                }
+            }
+            tick += 1;
+
+            if let Some(current) = tick_rate(&self.flags) {
+                if current != rate {
+                    info!("Tick rate changed from {} to {} Hz", rate, current);
+                    rate = current;
+                    interval = ticker(rate);
+                    metrics.set_tick_rate(rate);
+                    self.check_budgets(rate);
+                }
+            }
+        }
+
+        info!("Game loop stopped after {} ticks", tick);
+    }
+
+    /// Run the handlers due on this tick, returning how long it took
+    fn run_tick(&mut self, tick: u64, metrics: &TickMetrics) -> Duration {
+        let started = Instant::now();
+        let now = self.clock.now();
+        let mut world = self.world.lock();
+        for (index, slot) in self.handlers.iter_mut().enumerate() {
+            if tick % slot.handler.every_ticks().max(1) != 0 {
+                continue;
+            }
+            let delta = slot.last_run.and_then(|last| (now - last).to_std().ok()).unwrap_or_default();
+            slot.last_run = Some(now);
+
+            let handler_started = Instant::now();
+            let ctx = TickContext { tick, now, delta, deadline: handler_started + slot.handler.budget() };
+            // A broken handler must not take the whole simulation down
+            let panicked = catch_unwind(AssertUnwindSafe(|| slot.handler.on_tick(&ctx, &mut world))).is_err();
+            let elapsed = handler_started.elapsed();
+            if panicked {
+                error!("Tick handler {} panicked on tick {}", slot.handler.name(), tick);
+            }
+            if let Some(overruns) = metrics.record_handler(index, elapsed, panicked) {
+                if overruns.is_power_of_two() {
+                    warn!(
+                        "Tick handler {} took {:?}, over its {:?} budget ({} overruns)",
+                        slot.handler.name(),
+                        elapsed,
+                        slot.handler.budget(),
+                        overruns
+                    );
+                }
+            }
+        }
+        started.elapsed()
+    }
+
+    fn check_budgets(&self, rate: u32) {
+        let total: Duration = self.handlers.iter().map(|slot| slot.handler.budget()).sum();
+        if total > period(rate) {
+            warn!("Tick handler budgets add up to {:?}, more than the {:?} tick period at {} Hz", total, period(rate), rate);
+        }
+    }
+}
+
+impl TickHandle {
+    pub fn metrics(&self) -> Arc<TickMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Stop after the current tick and wait for the loop to finish
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(true);
+        if let Err(e) = self.task.await {
+            error!("Game loop task failed: {}", e);
+        }
+    }
+}
+
+/// Tick rate set by the runtime flag; `None` when the flag is set to something invalid
+pub fn tick_rate(flags: &RuntimeFlags) -> Option<u32> {
+    let flags = flags.read();
+    let Some(value) = flags.get(TICK_RATE_FLAG) else {
+        return Some(DEFAULT_TICK_RATE);
+    };
+    match value.as_f64() {
+        Some(rate) if rate >= 1.0 => Some((rate.round() as u32).min(MAX_TICK_RATE)),
+        _ => None,
+    }
+}
+
+fn period(rate: u32) -> Duration {
+    Duration::from_secs_f64(1.0 / rate.max(1) as f64)
+}
+
+fn ticker(rate: u32) -> tokio::time::Interval {
+    let mut interval = tokio::time::interval(period(rate));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    interval
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn flags(values: &[(&str, serde_json::Value)]) -> RuntimeFlags {
+        Arc::new(RwLock::new(values.iter().map(|(key, value)| (key.to_string(), value.clone())).collect::<HashMap<_, _>>()))
+    }
+
+    struct Counting {
+        runs: Arc<AtomicU64>,
+        every_ticks: u64,
+        work: Duration,
+    }
+
+    impl TickHandler for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn budget(&self) -> Duration {
+            Duration::from_millis(1)
+        }
+
+        fn every_ticks(&self) -> u64 {
+            self.every_ticks
+        }
+
+        fn on_tick(&mut self, _ctx: &TickContext, _world: &mut World) {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(self.work);
+        }
+    }
+
+    struct Panicking;
+
+    impl TickHandler for Panicking {
+        fn name(&self) -> &str {
+            "panicking"
+        }
+
+        fn budget(&self) -> Duration {
+            Duration::from_millis(1)
+        }
+
+        fn on_tick(&mut self, _ctx: &TickContext, _world: &mut World) {
+            panic!("broken handler");
+        }
+    }
+
+    #[test]
+    fn tick_rate_follows_flag() {
+        assert_eq!(tick_rate(&flags(&[])), Some(DEFAULT_TICK_RATE));
+        assert_eq!(tick_rate(&flags(&[(TICK_RATE_FLAG, serde_json::json!(20))])), Some(20));
+        assert_eq!(tick_rate(&flags(&[(TICK_RATE_FLAG, serde_json::json!(1_000_000))])), Some(MAX_TICK_RATE));
+        assert_eq!(tick_rate(&flags(&[(TICK_RATE_FLAG, serde_json::json!(0))])), None);
+        assert_eq!(tick_rate(&flags(&[(TICK_RATE_FLAG, serde_json::json!("fast"))])), None);
+    }
+
+    #[test]
+    fn handlers_run_on_their_ticks_and_overruns_are_counted() {
+        let runs = Arc::new(AtomicU64::new(0));
+        let slow = Arc::new(AtomicU64::new(0));
+        let mut driver = TickDriver::new(shared::wall_clock(), flags(&[]), World::new().shared())
+            .with_handler(Counting { runs: runs.clone(), every_ticks: 1, work: Duration::ZERO })
+            .with_handler(Counting { runs: slow.clone(), every_ticks: 2, work: Duration::from_millis(3) })
+            .with_handler(Panicking);
+        let metrics = TickMetrics::new(
+            60,
+            driver
+                .handlers
+                .iter()
+                .map(|slot| HandlerStats::new(slot.handler.name(), slot.handler.budget(), slot.handler.every_ticks()))
+                .collect(),
+        );
+
+        for tick in 0..4 {
+            driver.run_tick(tick, &metrics);
+        }
+
+        let stats = metrics.snapshot();
+        assert_eq!(runs.load(Ordering::Relaxed), 4);
+        assert_eq!(slow.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.handlers[1].runs, 2);
+        assert_eq!(stats.handlers[1].overruns, 2);
+        assert_eq!(stats.handlers[2].panics, 4);
+    }
+
+    #[tokio::test]
+    async fn loop_ticks_until_stopped() {
+        let runs = Arc::new(AtomicU64::new(0));
+        let handle = TickDriver::new(shared::wall_clock(), flags(&[(TICK_RATE_FLAG, serde_json::json!(200))]), World::new().shared())
+            .with_handler(Counting { runs: runs.clone(), every_ticks: 1, work: Duration::ZERO })
+            .spawn();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let metrics = handle.metrics();
+        handle.stop().await;
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.tick_rate, 200);
+        assert!(stats.ticks > 0);
+        assert_eq!(stats.ticks, runs.load(Ordering::Relaxed));
+    }
+}
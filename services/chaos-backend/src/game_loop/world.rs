@@ -0,0 +1,226 @@
+//! Simulation state the tick handlers work on.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use shared::events::topics::WorldEventChanged;
+use shared::events::{EventEnvelope, Message, Topic};
+use shared::Timestamp;
+use tracing::warn;
+
+/// World handle shared between the game loop and the rest of the service
+pub type SharedWorld = Arc<Mutex<World>>;
+
+/// Work run by the scheduler
+pub type Job = Box<dyn FnMut(&mut World) + Send>;
+
+pub const HEALTH: &str = "health";
+pub const MANA: &str = "mana";
+pub const STAMINA: &str = "stamina";
+
+/// Everything simulated by the game loop
+#[derive(Default)]
+pub struct World {
+    pub actors: HashMap<String, ActorState>,
+    pub spawn_points: Vec<SpawnPoint>,
+    /// World events in progress by event id
+    pub world_events: HashMap<String, WorldEventChanged>,
+    /// Messages for other services, published after the tick
+    outbox: Vec<EventEnvelope>,
+    /// Ordered by due time
+    jobs: Vec<ScheduledJob>,
+    next_job_id: u64,
+}
+
+/// Simulated state of one actor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActorState {
+    pub id: String,
+    pub zone: Option<String>,
+    pub resources: BTreeMap<String, Resource>,
+    pub statuses: Vec<StatusEffect>,
+    /// Spawn point that owns the actor; it is removed and respawned when it dies
+    pub spawn_point: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Resource {
+    pub current: f64,
+    pub max: f64,
+    pub regen_per_sec: f64,
+}
+
+/// Effect pulsing on a resource until it expires, e.g. poison or a heal over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub id: String,
+    pub resource: String,
+    /// Added on every pulse, negative for damage
+    pub per_pulse: f64,
+    pub interval: Duration,
+    pub remaining: Duration,
+    /// Time since the last pulse
+    #[serde(default)]
+    pub since_pulse: Duration,
+}
+
+/// Keeps up to `max_alive` actors alive in a zone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnPoint {
+    pub id: String,
+    pub zone: String,
+    pub max_alive: u32,
+    pub respawn_secs: u64,
+    /// Time left on each pending respawn
+    #[serde(skip)]
+    pub respawns: Vec<Duration>,
+    #[serde(skip)]
+    pub spawned: u64,
+}
+
+struct ScheduledJob {
+    id: u64,
+    name: String,
+    due: Timestamp,
+    every: Option<Duration>,
+    job: Job,
+}
+
+/// A job taken off the schedule to run
+pub struct DueJob {
+    pub id: u64,
+    pub name: String,
+    pub due: Timestamp,
+    pub every: Option<Duration>,
+    pub job: Job,
+}
+
+impl Resource {
+    pub fn new(max: f64, regen_per_sec: f64) -> Self {
+        Self { current: max, max, regen_per_sec }
+    }
+
+    /// Add `amount`, clamped to `0..=max`
+    pub fn add(&mut self, amount: f64) {
+        self.current = (self.current + amount).clamp(0.0, self.max);
+    }
+}
+
+impl ActorState {
+    /// Actor with the default vital resources of `configs/minimal_test_config.yaml`
+    pub fn new(id: impl Into<String>) -> Self {
+        let resources = BTreeMap::from([
+            (HEALTH.to_string(), Resource::new(100.0, 1.0)),
+            (MANA.to_string(), Resource::new(50.0, 0.5)),
+            (STAMINA.to_string(), Resource::new(100.0, 2.0)),
+        ]);
+        Self { id: id.into(), zone: None, resources, statuses: Vec::new(), spawn_point: None }
+    }
+
+    pub fn in_zone(mut self, zone: impl Into<String>) -> Self {
+        self.zone = Some(zone.into());
+        self
+    }
+
+    /// Actors without health never die
+    pub fn is_alive(&self) -> bool {
+        self.resources.get(HEALTH).map_or(true, |health| health.current > 0.0)
+    }
+}
+
+impl SpawnPoint {
+    pub fn respawn_time(&self) -> Duration {
+        Duration::from_secs(self.respawn_secs)
+    }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn shared(self) -> SharedWorld {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn add_actor(&mut self, actor: ActorState) {
+        self.actors.insert(actor.id.clone(), actor);
+    }
+
+    /// Queue a message for the bus; the tick never waits on publishing
+    pub fn emit<M: Message>(&mut self, topic: &Topic<M>, message: &M) {
+        match EventEnvelope::message(topic, message) {
+            Ok(envelope) => self.outbox.push(envelope),
+            Err(e) => warn!("Dropping {} message: {}", M::TYPE, e),
+        }
+    }
+
+    /// Take the queued messages
+    pub fn drain_outbox(&mut self) -> Vec<EventEnvelope> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Run `job` once at `due`
+    #[allow(dead_code)]
+    pub fn schedule_at(&mut self, name: impl Into<String>, due: Timestamp, job: Job) -> u64 {
+        self.insert_job(name.into(), due, None, job)
+    }
+
+    /// Run `job` at `first`, then every `every`
+    pub fn schedule_every(&mut self, name: impl Into<String>, first: Timestamp, every: Duration, job: Job) -> u64 {
+        self.insert_job(name.into(), first, Some(every), job)
+    }
+
+    /// Remove a scheduled job, `false` when it does not exist
+    #[allow(dead_code)]
+    pub fn cancel(&mut self, id: u64) -> bool {
+        let before = self.jobs.len();
+        self.jobs.retain(|job| job.id != id);
+        self.jobs.len() != before
+    }
+
+    #[allow(dead_code)]
+    pub fn scheduled_jobs(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Take the earliest job if it is due at `now`
+    pub fn next_due(&mut self, now: Timestamp) -> Option<DueJob> {
+        if self.jobs.first()?.due > now {
+            return None;
+        }
+        let job = self.jobs.remove(0);
+        Some(DueJob { id: job.id, name: job.name, due: job.due, every: job.every, job: job.job })
+    }
+
+    /// Put a repeating job back on the schedule under its own id
+    pub fn reschedule(&mut self, job: DueJob, due: Timestamp) {
+        let index = self.jobs.partition_point(|scheduled| scheduled.due <= due);
+        self.jobs.insert(index, ScheduledJob { id: job.id, name: job.name, due, every: job.every, job: job.job });
+    }
+
+    fn insert_job(&mut self, name: String, due: Timestamp, every: Option<Duration>, job: Job) -> u64 {
+        self.next_job_id += 1;
+        let id = self.next_job_id;
+        let index = self.jobs.partition_point(|scheduled| scheduled.due <= due);
+        self.jobs.insert(index, ScheduledJob { id, name, due, every, job });
+        id
+    }
+}
+
+impl fmt::Debug for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("World")
+            .field("actors", &self.actors.len())
+            .field("spawn_points", &self.spawn_points.len())
+            .field("world_events", &self.world_events.len())
+            .field("outbox", &self.outbox.len())
+            .field("jobs", &self.jobs.len())
+            .finish()
+    }
+}
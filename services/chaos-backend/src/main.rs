@@ -1,15 +1,47 @@
 //! Chaos Backend Service - MongoDB Integration Test
 //!
-//! Simple version to test MongoDB runtime flags integration.
+//! Simple version to test MongoDB runtime flags integration, running the game loop
+//! next to the HTTP server.
+//!
+//! On Ctrl-C or SIGTERM the service stops accepting requests, drains the ones in
+//! flight, stops the game loop, saves the world and flushes the event bus.
+
+mod actor_store;
+mod bus;
+mod characters;
+mod game_loop;
+mod verification;
+mod world_store;
 
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{info, warn, error};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use parking_lot::RwLock;
 
 use actor_core::prelude::*;
 use actor_core::builder::ActorCoreBuilder;
 use mongodb::Collection;
+use shared::health::{check_fn, BusCheck, HealthRegistry, HealthReport};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
+use shared::ChaosError;
+
+use crate::actor_store::{ActorChange, ActorStore, ActorStoreConfig, MongoActorRepository};
+use crate::bus::GameBus;
+use crate::characters::{
+    CharacterCreationService, CharacterRules, CreateCharacter, CreatedCharacter, CreationError, MongoCharacterRepository,
+};
+use crate::game_loop::{ActorState, GameLoopConfig, TickDriver, TickMetrics, World};
+use crate::world_store::WorldStore;
+
+const MONGODB_URI: &str = "mongodb://localhost:27017";
+
+/// Runtime flags by name, shared with the systems that follow them
+pub type RuntimeFlags = Arc<RwLock<HashMap<String, serde_json::Value>>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ServerConfig {
@@ -20,6 +52,14 @@ struct ServerConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Config {
     server: ServerConfig,
+    #[serde(default)]
+    game_loop: GameLoopConfig,
+    #[serde(default)]
+    verification: VerificationConfig,
+    #[serde(default)]
+    actors: ActorStoreConfig,
+    #[serde(default)]
+    characters: CharacterRules,
 }
 
 impl Default for Config {
@@ -29,6 +69,10 @@ impl Default for Config {
                 port: 8081,
                 host: "0.0.0.0".to_string(),
             },
+            game_loop: GameLoopConfig::default(),
+            verification: VerificationConfig::default(),
+            actors: ActorStoreConfig::default(),
+            characters: CharacterRules::default(),
         }
     }
 }
@@ -39,6 +83,9 @@ impl Config {
         if std::path::Path::new(config_path).exists() {
             let content = fs::read_to_string(config_path)?;
             let config: Config = serde_yaml::from_str(&content)?;
+            config.verification.validate()?;
+            config.actors.validate()?;
+            config.characters.validate()?;
             Ok(config)
         } else {
             info!("Config file not found at {}, using default configuration", config_path);
@@ -64,16 +111,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             std::fs::File::create("chaos-backend.log").unwrap()
         });
     
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+    let telemetry = telemetry::init_with(
+        &TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .with_default_filter("chaos_backend=debug"),
+        vec![Box::new(tracing_subscriber::fmt::layer().with_writer(file).with_ansi(false)) as telemetry::BoxedLayer],
+    );
     
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "chaos_backend=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_writer(file).with_ansi(false))
-        .with(tracing_subscriber::fmt::layer()) // Also output to console
-        .init();
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
     
     // Load configuration
     let config = Config::load()?;
@@ -82,7 +127,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     
     // Use hardcoded runtime flags to avoid MongoDB port conflicts
     info!("🔧 Using hardcoded runtime flags (ignoring MongoDB)...");
-    let runtime_flags: HashMap<String, serde_json::Value> = HashMap::new();
+    let runtime_flags: RuntimeFlags = Arc::new(RwLock::new(HashMap::new()));
     
     // Create Actor Core with minimal configuration (no default config file)
     info!("📦 Creating minimal Actor Core without default config...");
@@ -115,17 +160,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         }
     };
     
-    // Test creating actors
-    info!("👤 Creating test actors...");
-    let actors = vec![
-        Actor::new("TestPlayer1".to_string(), "Human".to_string()),
-        Actor::new("TestPlayer2".to_string(), "Elf".to_string()),
-        Actor::new("TestPlayer3".to_string(), "Dwarf".to_string()),
-    ];
+    // Load the actors of the configured zones, creating test actors when there are none
+    let mongo_client = mongodb::Client::with_uri_str(MONGODB_URI).await?;
+    let database = mongo_client.database("chaos_game");
+    let (aggregator, _cache) = quick_setup().await?;
+    let actor_store = Arc::new(ActorStore::new(Arc::new(MongoActorRepository::new(&database)), aggregator.clone()));
+    for zone in &config.actors.zones {
+        match actor_store.load_zone(zone).await {
+            Ok(count) => info!("👤 Loaded {} actor(s) of {}", count, zone),
+            Err(e) => warn!("⚠️  Failed to load the actors of {}: {}", zone, e),
+        }
+    }
+    if actor_store.is_empty() {
+        info!("👤 Creating test actors...");
+        let zone = config.actors.zones.first().cloned();
+        for (id, race) in [("TestPlayer1", "Human"), ("TestPlayer2", "Elf"), ("TestPlayer3", "Dwarf")] {
+            actor_store.insert(zone.clone(), Actor::new(id.to_string(), race.to_string()));
+            info!("👤 Created actor: {}", id);
+        }
+    }
+    let actor_flushes = actor_store.spawn_flushes(Duration::from_secs(config.actors.flush_secs));
+    let actor_checks = actor_store.spawn_checks(Duration::from_secs(config.actors.check_secs));
     
-    for actor in &actors {
-        info!("👤 Created actor: {} ({})", actor.id, actor.name);
+    // Create characters into the actor store
+    let character_repository = MongoCharacterRepository::new(&mongo_client, &database);
+    if let Err(e) = character_repository.ensure_indexes().await {
+        warn!("⚠️  {}", e);
     }
+    let characters = Arc::new(CharacterCreationService::new(
+        config.characters.clone(),
+        Arc::new(character_repository),
+        actor_store.clone(),
+        aggregator.clone(),
+    ));
     
     // Test saving configuration to MongoDB
     info!("💾 Testing configuration save to MongoDB...");
@@ -173,17 +240,99 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     
     // Display runtime flags
     info!("📋 Current runtime flags:");
-    for (key, value) in &runtime_flags {
+    for (key, value) in runtime_flags.read().iter() {
         info!("   {}: {}", key, value);
     }
     
+    // Start the game loop with the test actors in the world
+    let mut world = World::new();
+    for actor in actor_store.actors() {
+        world.add_actor(ActorState::new(actor.id));
+    }
+    world.spawn_points = config.game_loop.spawn_points.clone();
+    world.schedule_every("world_stats", chrono::Utc::now(), std::time::Duration::from_secs(60), Box::new(|world: &mut World| {
+        info!("🌍 World: {} actors, {} spawn points", world.actors.len(), world.spawn_points.len());
+    }));
+    let world = world.shared();
+    let game_loop = TickDriver::with_defaults(&config.game_loop, shared::wall_clock(), runtime_flags.clone(), world.clone()).spawn();
+    info!("🎮 Game loop started");
+    
+    // Publish world changes and follow world events
+    let address = format!("{}:{}", config.server.host, config.server.port);
+    let game_bus = GameBus::start(world.clone(), address, &shutdown).await?;
+    
+    // Cross-check cached actor snapshots against fresh resolutions
+    let verifier = SnapshotVerifier::new(aggregator, config.verification.clone());
+    let snapshot_checks = verification::spawn(verifier, actor_store.clone(), world.clone());
+    info!("🔍 Verifying {}% of actor snapshots every {}s", config.verification.sample_rate * 100.0, config.verification.interval_secs);
+    
+    // Save actor state as it changes
+    let world_store = Arc::new(WorldStore::new(&database, world));
+    let world_saves = world_store.spawn_periodic();
+    
+    let health = Arc::new(health_checks(game_loop.metrics(), &game_bus, database).with_shutdown(&shutdown));
+    
     // Start HTTP server
     info!("🚀 Starting Chaos Backend HTTP server on port {}...", config.server.port);
-    start_http_server(config.server.port, config.server.host).await?;
+    let result = start_http_server(config.server.port, config.server.host, game_loop.metrics(), health, actor_store.clone(), characters, &shutdown).await;
+    
+    // Stop the simulation first so the state saved is the final one
+    game_loop.stop().await;
+    snapshot_checks.abort();
+    world_saves.abort();
+    actor_flushes.abort();
+    actor_checks.abort();
+    match world_store.save().await {
+        Ok(count) => info!("💾 Saved {} actor change(s)", count),
+        Err(e) => error!("❌ Failed to save the world: {}", e),
+    }
+    match actor_store.flush().await {
+        Ok(count) => info!("💾 Wrote {} actor definition(s)", count),
+        Err(e) => error!("❌ Failed to write actor definitions: {}", e),
+    }
+    game_bus.stop().await;
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    result?;
     
+    info!("👋 Chaos Backend stopped");
+    telemetry.shutdown();
     Ok(())
 }
 
+/// Ready while the game loop ticks and the bus is connected; MongoDB only holds configuration and saves
+fn health_checks(tick_metrics: Arc<TickMetrics>, game_bus: &GameBus, database: mongodb::Database) -> HealthRegistry {
+    let last_ticks = Arc::new(AtomicU64::new(0));
+    let game_loop = check_fn(move || {
+        let ticks = tick_metrics.snapshot().ticks;
+        let previous = last_ticks.swap(ticks, Ordering::Relaxed);
+        async move {
+            if ticks > previous {
+                Ok(())
+            } else {
+                Err(ChaosError::Internal(format!("Game loop stalled at tick {}", ticks)))
+            }
+        }
+    });
+    
+    let mongodb = check_fn(move || {
+        let database = database.clone();
+        async move {
+            database
+                .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+                .await
+                .map(|_| ())
+                .map_err(|e| ChaosError::Database(e.to_string()))
+        }
+    });
+    
+    HealthRegistry::new("chaos-backend")
+        .register("game_loop", game_loop)
+        .register("event_bus", BusCheck::new(game_bus.bus()))
+        .register_optional("mongodb", mongodb)
+}
+
 /// Create a minimal configuration file for testing
 async fn create_minimal_config_file() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use tokio::fs;
@@ -457,10 +606,10 @@ async fn initialize_default_flags(collection: &Collection<mongodb::bson::Documen
     Ok(())
 }
 
-/// Start the HTTP server
-async fn start_http_server(port: u16, _host: String) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Start the HTTP server, serving until shutdown has drained it
+async fn start_http_server(port: u16, _host: String, tick_metrics: Arc<TickMetrics>, health: Arc<HealthRegistry>, actor_store: Arc<ActorStore>, characters: Arc<CharacterCreationService>, shutdown: &Shutdown) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     use axum::{
-        routing::get,
+        routing::{get, post},
         Router,
     };
     use std::net::SocketAddr;
@@ -468,14 +617,36 @@ async fn start_http_server(port: u16, _host: String) -> Result<(), Box<dyn std::
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
+        .route("/tick", get(tick_stats))
+        .route("/", get(root))
+        .with_state(tick_metrics)
+        .merge(
+            Router::new()
+                .route(shared::health::LIVE_PATH, get(liveness))
+                .route(shared::health::READY_PATH, get(readiness))
+                .with_state(health),
+        )
+        .merge(
+            Router::new()
+                .route("/actors/:actor_id", get(get_actor))
+                .route("/actors/:actor_id/changes", post(change_actor))
+                .with_state(actor_store),
+        )
+        .merge(
+            Router::new()
+                .route("/characters", post(create_character))
+                .with_state(characters),
+        );
     
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     info!("🌐 Chaos Backend server starting on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result?;
+    }
     
     Ok(())
 }
@@ -484,6 +655,66 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Game loop timings and overruns
+async fn tick_stats(
+    axum::extract::State(tick_metrics): axum::extract::State<Arc<TickMetrics>>,
+) -> axum::Json<game_loop::TickStats> {
+    axum::Json(tick_metrics.snapshot())
+}
+
+async fn liveness(
+    axum::extract::State(health): axum::extract::State<Arc<HealthRegistry>>,
+) -> axum::Json<HealthReport> {
+    axum::Json(health.liveness())
+}
+
+/// 503 while the game loop is stalled or the bus is down
+async fn readiness(
+    axum::extract::State(health): axum::extract::State<Arc<HealthRegistry>>,
+) -> (axum::http::StatusCode, axum::Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = axum::http::StatusCode::from_u16(report.status.http_status())
+        .unwrap_or(axum::http::StatusCode::SERVICE_UNAVAILABLE);
+    (status, axum::Json(report))
+}
+
+/// An actor's definition as cached, 404 when it is not loaded
+async fn get_actor(
+    axum::extract::State(actor_store): axum::extract::State<Arc<ActorStore>>,
+    axum::extract::Path(actor_id): axum::extract::Path<String>,
+) -> Result<axum::Json<Actor>, axum::http::StatusCode> {
+    actor_store.get(&actor_id).map(axum::Json).ok_or(axum::http::StatusCode::NOT_FOUND)
+}
+
+/// Apply a buff or subsystem change to an actor, written on the next flush
+async fn change_actor(
+    axum::extract::State(actor_store): axum::extract::State<Arc<ActorStore>>,
+    axum::extract::Path(actor_id): axum::extract::Path<String>,
+    axum::Json(change): axum::Json<ActorChange>,
+) -> Result<axum::Json<Actor>, axum::http::StatusCode> {
+    actor_store.apply(&actor_id, &change).map(axum::Json).ok_or(axum::http::StatusCode::NOT_FOUND)
+}
+
+/// Create a character, answering with its first snapshot; 400 for invalid choices, 409 for a taken name
+async fn create_character(
+    axum::extract::State(characters): axum::extract::State<Arc<CharacterCreationService>>,
+    axum::Json(request): axum::Json<CreateCharacter>,
+) -> Result<axum::Json<CreatedCharacter>, (axum::http::StatusCode, axum::Json<serde_json::Value>)> {
+    use axum::http::StatusCode;
+    
+    characters.create(&request).await.map(axum::Json).map_err(|e| {
+        let status = match &e {
+            CreationError::NameTaken(_) => StatusCode::CONFLICT,
+            CreationError::Resolve(_) | CreationError::Storage(_) => {
+                error!("❌ Failed to create character {}: {}", request.name, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            _ => StatusCode::BAD_REQUEST,
+        };
+        (status, axum::Json(serde_json::json!({ "error": e.to_string() })))
+    })
+}
+
 async fn root() -> &'static str {
     "Hello from Chaos Backend!"
 }
\ No newline at end of file
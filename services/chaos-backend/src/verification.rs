@@ -0,0 +1,83 @@
+//! Periodic verification of actor snapshots.
+//!
+//! Every round a sample of actors is re-resolved from their contributions and compared
+//! with their cached snapshots. Snapshots that cannot be reproduced are reported on the
+//! bus, where anti-cheat-service raises incidents for them; the cache is repaired as a
+//! side effect of the check.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use actor_core::prelude::*;
+use shared::events::topics::{ActorSnapshotMismatch, ACTOR_SNAPSHOT_MISMATCH};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::actor_store::ActorStore;
+use crate::game_loop::{SharedWorld, World};
+
+/// Verify sampled actors of the store every `interval_secs` of the verifier's configuration
+pub fn spawn(verifier: SnapshotVerifier, actors: Arc<ActorStore>, world: SharedWorld) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(verifier.config().interval_secs));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        // Clients do not report snapshot hashes yet, so only the cache is checked
+        let client_hashes = HashMap::new();
+        loop {
+            interval.tick().await;
+            let report = verifier.run(&actors.actors(), &client_hashes).await;
+            debug!("🔍 Verified {} actor snapshot(s), {} failed", report.sampled, report.failed);
+            if !report.mismatches.is_empty() {
+                warn!("⚠️  {} snapshot mismatch(es) found", report.mismatches.len());
+                emit(&mut world.lock(), &report);
+            }
+        }
+    })
+}
+
+/// Queue the mismatches of a round for the bus
+fn emit(world: &mut World, report: &VerificationReport) {
+    for mismatch in &report.mismatches {
+        let source = match mismatch.source {
+            MismatchSource::Cache => "cache",
+            MismatchSource::Client => "client",
+        };
+        let message = ActorSnapshotMismatch {
+            actor_id: mismatch.actor_id.clone(),
+            source: source.to_string(),
+            expected_hash: mismatch.expected_hash.clone(),
+            observed_hash: mismatch.observed_hash.clone(),
+            stats: mismatch.stats.clone(),
+        };
+        world.emit(&ACTOR_SNAPSHOT_MISMATCH, &message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatches_are_queued_for_the_bus() {
+        let report = VerificationReport {
+            sampled: 2,
+            failed: 0,
+            mismatches: vec![SnapshotMismatch {
+                actor_id: "player1".to_string(),
+                source: MismatchSource::Cache,
+                expected_hash: "00000000000000aa".to_string(),
+                observed_hash: "00000000000000bb".to_string(),
+                stats: vec!["strength".to_string()],
+            }],
+        };
+        let mut world = World::new();
+        emit(&mut world, &report);
+
+        let outbox = world.drain_outbox();
+        assert_eq!(outbox.len(), 1);
+        let message: ActorSnapshotMismatch = outbox[0].decode().unwrap();
+        assert_eq!(message.source, "cache");
+        assert_eq!(message.stats, vec!["strength".to_string()]);
+    }
+}
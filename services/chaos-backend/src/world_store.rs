@@ -0,0 +1,132 @@
+//! Actor state saved to MongoDB.
+//!
+//! The world is saved periodically and once more after the game loop stops on
+//! shutdown. Only actors that changed since the last save are written and actors that
+//! left the world are deleted, so saving a quiet world costs next to nothing.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use mongodb::bson::{self, doc, Document};
+use mongodb::options::ReplaceOptions;
+use mongodb::{Collection, Database};
+use shared::{ChaosError, ChaosResult};
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::game_loop::{SharedWorld, World};
+
+pub const COLLECTION: &str = "actor_states";
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Saves the actors of the world
+pub struct WorldStore {
+    collection: Collection<Document>,
+    world: SharedWorld,
+    /// Actors as last saved, serialized, by id
+    saved: tokio::sync::Mutex<HashMap<String, String>>,
+}
+
+/// Actors to write and delete to bring the store up to date
+#[derive(Debug, Default)]
+struct Changes {
+    changed: Vec<(String, String, Document)>,
+    removed: Vec<String>,
+}
+
+impl WorldStore {
+    pub fn new(database: &Database, world: SharedWorld) -> Self {
+        Self { collection: database.collection(COLLECTION), world, saved: Default::default() }
+    }
+
+    /// Write the actors changed since the last save, returning how many were written or deleted
+    pub async fn save(&self) -> ChaosResult<usize> {
+        let mut saved = self.saved.lock().await;
+        let changes = changes(&self.world.lock(), &saved)?;
+        let count = changes.changed.len() + changes.removed.len();
+
+        let upsert = ReplaceOptions::builder().upsert(true).build();
+        for (id, serialized, document) in changes.changed {
+            self.collection
+                .replace_one(doc! { "_id": &id }, document, upsert.clone())
+                .await
+                .map_err(|e| ChaosError::Database(format!("Failed to save actor {}: {}", id, e)))?;
+            saved.insert(id, serialized);
+        }
+        for id in changes.removed {
+            self.collection
+                .delete_one(doc! { "_id": &id }, None)
+                .await
+                .map_err(|e| ChaosError::Database(format!("Failed to delete actor {}: {}", id, e)))?;
+            saved.remove(&id);
+        }
+        Ok(count)
+    }
+
+    /// Save every [`SAVE_INTERVAL`] until the task is aborted
+    pub fn spawn_periodic(self: &Arc<Self>) -> JoinHandle<()> {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SAVE_INTERVAL);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                match store.save().await {
+                    Ok(count) => debug!("💾 Saved {} actor change(s)", count),
+                    Err(e) => warn!("⚠️  World save failed, retrying in {:?}: {}", SAVE_INTERVAL, e),
+                }
+            }
+        })
+    }
+}
+
+fn changes(world: &World, saved: &HashMap<String, String>) -> ChaosResult<Changes> {
+    let mut changes = Changes::default();
+    for (id, actor) in &world.actors {
+        let serialized = serde_json::to_string(actor)?;
+        if saved.get(id) == Some(&serialized) {
+            continue;
+        }
+        let mut document = bson::to_document(actor).map_err(|e| ChaosError::Serialization(e.to_string()))?;
+        document.insert("_id", id.clone());
+        changes.changed.push((id.clone(), serialized, document));
+    }
+    changes.removed = saved.keys().filter(|id| !world.actors.contains_key(*id)).cloned().collect();
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_loop::world::HEALTH;
+    use crate::game_loop::ActorState;
+
+    fn saved(world: &World) -> HashMap<String, String> {
+        changes(world, &HashMap::new())
+            .unwrap()
+            .changed
+            .into_iter()
+            .map(|(id, serialized, _)| (id, serialized))
+            .collect()
+    }
+
+    #[test]
+    fn only_changed_and_removed_actors_are_written() {
+        let mut world = World::new();
+        world.add_actor(ActorState::new("goblin"));
+        world.add_actor(ActorState::new("wolf"));
+        world.add_actor(ActorState::new("bear"));
+        let saved = saved(&world);
+        assert_eq!(saved.len(), 3);
+        assert!(changes(&world, &saved).unwrap().changed.is_empty());
+
+        world.actors.get_mut("goblin").unwrap().resources.get_mut(HEALTH).unwrap().add(-10.0);
+        world.actors.remove("wolf");
+        let changes = changes(&world, &saved).unwrap();
+        assert_eq!(changes.changed.len(), 1);
+        assert_eq!(changes.changed[0].0, "goblin");
+        assert_eq!(changes.changed[0].2.get_str("_id").unwrap(), "goblin");
+        assert_eq!(changes.removed, vec!["wolf".to_string()]);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    /// Secret the WebSocket access tokens are signed with; `JWT_SECRET` takes precedence
+    #[serde(default)]
+    pub jwt_secret: Option<String>,
+    /// Where guild membership is checked; `GUILD_SERVICE_URL` takes precedence
+    #[serde(default = "default_guild_service_url")]
+    pub guild_service_url: String,
+    #[serde(default)]
+    pub chat: ChatSettings,
+}
+
+fn default_guild_service_url() -> String {
+    "http://localhost:8080".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// Limits and moderation applied to every message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatSettings {
+    /// Longest message, in characters
+    pub max_message_len: usize,
+    /// Messages a user may post per `rate_limit_window_secs`
+    pub rate_limit_messages: usize,
+    pub rate_limit_window_secs: u64,
+    /// Most messages a history request returns
+    pub max_history: usize,
+    /// Days messages are kept before MongoDB expires them
+    pub retention_days: u64,
+    /// Words replaced with `*`
+    pub masked_words: Vec<String>,
+    /// Words that get a message refused and reported to anti-cheat
+    pub banned_words: Vec<String>,
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        Self {
+            max_message_len: 500,
+            rate_limit_messages: 5,
+            rate_limit_window_secs: 10,
+            max_history: 50,
+            retention_days: 7,
+            masked_words: Vec::new(),
+            banned_words: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/chat-service.yaml".to_string());
+
+        let mut config = if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            serde_yaml::from_str(&content)?
+        } else {
+            tracing::warn!("Config file not found at {}, using environment variables", config_path);
+            Self::from_env()
+        };
+        if let Ok(secret) = env::var("JWT_SECRET") {
+            config.jwt_secret = Some(secret);
+        }
+        if let Ok(url) = env::var("GUILD_SERVICE_URL") {
+            config.guild_service_url = url;
+        }
+        if config.chat.rate_limit_window_secs == 0 || config.chat.retention_days == 0 {
+            return Err(ConfigError::InvalidConfig(
+                "Chat rate limit window and retention must be at least 1".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    fn from_env() -> Self {
+        let server = ServerConfig {
+            port: env::var("CHAT_SERVICE_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("CHAT_SERVICE_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_chat".to_string()),
+        };
+
+        Config {
+            server,
+            database,
+            jwt_secret: None,
+            guild_service_url: default_guild_service_url(),
+            chat: ChatSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
@@ -0,0 +1,83 @@
+//! Chat history in MongoDB.
+//!
+//! Every delivered message is one document keyed by its conversation. A TTL index on
+//! `stored_at` lets MongoDB drop messages past the retention period, so history needs
+//! no cleanup job.
+
+use std::time::Duration;
+
+use api::chat::{ChatHistory, ChatMessage};
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::options::{FindOptions, IndexOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use shared::{ChaosError, ChaosResult};
+
+pub const COLLECTION: &str = "chat_messages";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredMessage {
+    conversation: String,
+    /// BSON date the TTL index expires the message by
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    stored_at: chrono::DateTime<chrono::Utc>,
+    message: ChatMessage,
+}
+
+#[derive(Clone)]
+pub struct MongoChatHistory {
+    collection: Collection<StoredMessage>,
+}
+
+impl MongoChatHistory {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self, retention: Duration) -> mongodb::error::Result<()> {
+        let indexes = vec![
+            IndexModel::builder()
+                .keys(doc! { "conversation": 1, "stored_at": -1 })
+                .options(IndexOptions::builder().name("conversation".to_string()).build())
+                .build(),
+            IndexModel::builder()
+                .keys(doc! { "stored_at": 1 })
+                .options(IndexOptions::builder().name("retention".to_string()).expire_after(retention).build())
+                .build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ChatHistory for MongoChatHistory {
+    async fn append(&self, conversation: &str, message: &ChatMessage) -> ChaosResult<()> {
+        let stored = StoredMessage {
+            conversation: conversation.to_string(),
+            stored_at: message.sent_at,
+            message: message.clone(),
+        };
+        self.collection
+            .insert_one(stored, None)
+            .await
+            .map_err(|e| ChaosError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn recent(&self, conversation: &str, limit: usize) -> ChaosResult<Vec<ChatMessage>> {
+        let options = FindOptions::builder().sort(doc! { "stored_at": -1 }).limit(limit as i64).build();
+        let stored: Vec<StoredMessage> = self
+            .collection
+            .find(doc! { "conversation": conversation }, options)
+            .await
+            .map_err(|e| ChaosError::Database(e.to_string()))?
+            .try_collect()
+            .await
+            .map_err(|e| ChaosError::Database(e.to_string()))?;
+        // Newest were fetched first; history reads oldest first
+        Ok(stored.into_iter().rev().map(|stored| stored.message).collect())
+    }
+}
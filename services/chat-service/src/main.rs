@@ -1,26 +1,89 @@
+mod config;
+mod history;
+mod membership;
+
+use api::chat::{ChatConfig, ChatRateLimit, ChatService, WordFilter};
+use api::websocket::{ws_handler, SessionManager, TokenValidator, WebSocketConfig};
 use axum::{
     routing::get,
     Router,
 };
+use config::Config;
+use history::MongoChatHistory;
+use membership::MembershipAuthorizer;
+use shared::events::{connect, BusConfig};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SERVICE: &str = "chat-service";
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+    let settings = &config.chat;
+    let history = MongoChatHistory::new(&database);
+    let retention = Duration::from_secs(settings.retention_days * 24 * 60 * 60);
+    if let Err(e) = history.ensure_indexes(retention).await {
+        tracing::warn!("Failed to create indexes: {}", e);
+    }
+
+    // Refused messages are reported to anti-cheat
+    let bus = connect(&BusConfig::from_env()).await.unwrap();
+    let chat_config = ChatConfig {
+        max_message_len: settings.max_message_len,
+        rate_limit: ChatRateLimit {
+            max_messages: settings.rate_limit_messages,
+            window: Duration::from_secs(settings.rate_limit_window_secs),
+        },
+        max_history: settings.max_history,
+    };
+    let chat = ChatService::new(chat_config, shared::wall_clock(), Arc::new(history))
+        .with_filter(Arc::new(WordFilter::mask(&settings.masked_words)))
+        .with_filter(Arc::new(WordFilter::reject(&settings.banned_words)))
+        .with_bus(bus, SERVICE);
+
+    let secret = config.jwt_secret.clone().expect("A JWT secret is required (jwt_secret or JWT_SECRET)");
+    let manager = Arc::new(
+        SessionManager::with_authorizer(
+            WebSocketConfig::default(),
+            TokenValidator::hs256(secret.as_bytes()),
+            Arc::new(MembershipAuthorizer::new(&config.guild_service_url)),
+        )
+        .with_chat(Arc::new(chat)),
+    );
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/", get(root))
+        .merge(Router::new().route("/ws", get(ws_handler)).with_state(manager));
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 chat-service server starting on {}", addr);
-    
+
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 chat-service stopped");
+    telemetry.shutdown();
 }
 
 async fn health_check() -> &'static str {
@@ -30,4 +93,3 @@ async fn health_check() -> &'static str {
 async fn root() -> &'static str {
     "Hello from chat-service!"
 }
-
@@ -0,0 +1,62 @@
+//! Who may subscribe to which chat channel.
+//!
+//! Zones are public. Guild channels are open to the guild's members, as guild-service
+//! reports them. Party rosters live in the game servers' party managers, which this
+//! service cannot query, so party channels are open to anyone who knows the party ID.
+
+use api::websocket::{Channel, ChannelAuthorizer, WsClaims};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Header guild-service identifies the calling character by
+const ACTOR_ID_HEADER: &str = "x-actor-id";
+
+#[derive(Deserialize)]
+struct GuildResponse {
+    #[serde(rename = "_id")]
+    id: String,
+}
+
+pub struct MembershipAuthorizer {
+    client: reqwest::Client,
+    guild_service_url: String,
+}
+
+impl MembershipAuthorizer {
+    pub fn new(guild_service_url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build().unwrap_or_default(),
+            guild_service_url: guild_service_url.into(),
+        }
+    }
+
+    /// Guild the user's character belongs to; `None` when it has none or guild-service
+    /// cannot tell
+    async fn guild_of(&self, actor_id: &str) -> Option<String> {
+        let url = format!("{}/guilds/mine", self.guild_service_url.trim_end_matches('/'));
+        let response = match self.client.get(url).header(ACTOR_ID_HEADER, actor_id).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                tracing::warn!("guild-service unreachable: {}", e);
+                return None;
+            }
+        };
+        if !response.status().is_success() {
+            return None;
+        }
+        response.json::<GuildResponse>().await.ok().map(|guild| guild.id)
+    }
+}
+
+#[async_trait]
+impl ChannelAuthorizer for MembershipAuthorizer {
+    async fn can_subscribe(&self, claims: &WsClaims, channel: &Channel) -> bool {
+        match channel {
+            Channel::Zone(_) | Channel::Party(_) => true,
+            Channel::Guild(guild_id) => self.guild_of(&claims.sub).await.as_deref() == Some(guild_id.as_str()),
+        }
+    }
+}
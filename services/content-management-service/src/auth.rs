@@ -7,6 +7,7 @@ use axum::{
 };
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
+use shared::error::{ErrorCode, ToApiError};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
@@ -46,6 +47,22 @@ pub struct AuthError {
     pub message: String,
 }
 
+impl ToApiError for AuthError {
+    fn error_code(&self) -> ErrorCode {
+        match self.error.as_str() {
+            "INVALID_CREDENTIALS" => ErrorCode::Unauthenticated,
+            _ => ErrorCode::Internal,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self.error_code() {
+            ErrorCode::Unauthenticated => self.message.clone(),
+            code => code.default_message().to_string(),
+        }
+    }
+}
+
 pub struct AuthService {
     jwt_secret: String,
     jwt_expiry: i64,
@@ -1,12 +1,19 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
+use crate::publishing::Environment;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub auth: AuthConfig,
     pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub publishing: PublishingConfig,
+    #[serde(default)]
+    pub localization: LocalizationConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +37,40 @@ pub struct AuthConfig {
     pub admin_password: String,
 }
 
+/// Where published content goes, per environment
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PublishingConfig {
+    #[serde(default)]
+    pub environments: HashMap<Environment, PublishTargetConfig>,
+}
+
+/// Shared config database and event bus of one environment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishTargetConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+    /// Invalidation events are only logged when unset
+    pub nats_url: Option<String>,
+    pub subject_prefix: Option<String>,
+}
+
+/// Locales strings are translated into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalizationConfig {
+    /// Locale every key is written in first, used as export fallback
+    pub default_locale: String,
+    pub locales: Vec<String>,
+}
+
+impl Default for LocalizationConfig {
+    fn default() -> Self {
+        Self {
+            default_locale: "en".to_string(),
+            locales: vec!["en".to_string()],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
     pub metrics_enabled: bool,
@@ -97,11 +138,42 @@ impl Config {
                 .unwrap_or(true),
         };
 
+        // PUBLISH_DEV_MONGODB_URI, PUBLISH_STAGING_MONGODB_URI, ... enable an environment
+        let mut environments = HashMap::new();
+        for environment in Environment::ALL {
+            let prefix = format!("PUBLISH_{}", environment.as_str().to_uppercase());
+            if let Ok(mongodb_uri) = env::var(format!("{}_MONGODB_URI", prefix)) {
+                environments.insert(environment, PublishTargetConfig {
+                    mongodb_uri,
+                    mongodb_database: env::var(format!("{}_MONGODB_DATABASE", prefix))
+                        .unwrap_or_else(|_| "chaos_config".to_string()),
+                    nats_url: env::var(format!("{}_NATS_URL", prefix)).ok(),
+                    subject_prefix: env::var(format!("{}_SUBJECT_PREFIX", prefix)).ok(),
+                });
+            }
+        }
+
+        let default_locale = env::var("DEFAULT_LOCALE").unwrap_or_else(|_| "en".to_string());
+        let localization = LocalizationConfig {
+            locales: env::var("LOCALES")
+                .map(|locales| {
+                    locales
+                        .split(',')
+                        .map(|locale| locale.trim().to_string())
+                        .filter(|locale| !locale.is_empty())
+                        .collect()
+                })
+                .unwrap_or_else(|_| vec![default_locale.clone()]),
+            default_locale,
+        };
+
         Ok(Config {
             server,
             database,
             auth,
             monitoring,
+            publishing: PublishingConfig { environments },
+            localization,
         })
     }
 }
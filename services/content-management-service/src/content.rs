@@ -0,0 +1,454 @@
+use bson::doc;
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOneOptions, FindOptions, IndexOptions, UpdateOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::error::{ErrorCode, ToApiError};
+
+use crate::diff::changed_paths;
+use crate::validation::ValidationReport;
+
+/// Longest accepted content id
+const MAX_CONTENT_ID_LENGTH: usize = 128;
+
+/// Kinds of versioned game content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentType {
+    Quest,
+    ElementConfig,
+    LootTable,
+    Dialogue,
+}
+
+impl ContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentType::Quest => "quest",
+            ContentType::ElementConfig => "element_config",
+            ContentType::LootTable => "loot_table",
+            ContentType::Dialogue => "dialogue",
+        }
+    }
+}
+
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ContentType {
+    type Err = ContentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "quest" => Ok(ContentType::Quest),
+            "element_config" => Ok(ContentType::ElementConfig),
+            "loot_table" => Ok(ContentType::LootTable),
+            "dialogue" => Ok(ContentType::Dialogue),
+            _ => Err(ContentError::UnknownType(s.to_string())),
+        }
+    }
+}
+
+/// What produced a version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VersionAction {
+    Created,
+    Updated,
+    RolledBack,
+}
+
+/// Current version of a content document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentDocument {
+    pub content_type: ContentType,
+    pub content_id: String,
+    pub version: i64,
+    pub data: Value,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Immutable revision of a content document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentVersion {
+    pub content_type: ContentType,
+    pub content_id: String,
+    pub version: i64,
+    pub action: VersionAction,
+    /// Version restored by a rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rolled_back_to: Option<i64>,
+    /// Editor's description of the change
+    pub changes: String,
+    /// JSON pointers of the fields changed since the previous version
+    pub changed_paths: Vec<String>,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+    /// Left out of history queries
+    #[serde(default)]
+    pub data: Value,
+}
+
+/// Version history entry, without the document data
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionSummary {
+    pub version: i64,
+    pub action: VersionAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rolled_back_to: Option<i64>,
+    pub changes: String,
+    pub changed_paths: Vec<String>,
+    pub author: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ContentVersion> for VersionSummary {
+    fn from(version: ContentVersion) -> Self {
+        Self {
+            version: version.version,
+            action: version.action,
+            rolled_back_to: version.rolled_back_to,
+            changes: version.changes,
+            changed_paths: version.changed_paths,
+            author: version.author,
+            created_at: version.created_at,
+        }
+    }
+}
+
+/// A change to store as a new version
+#[derive(Debug, Clone)]
+pub struct ContentEdit {
+    pub data: Value,
+    /// Version the editor started from, the edit is refused when it is no longer current
+    pub expected_version: Option<i64>,
+    pub changes: String,
+    pub author: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ContentError {
+    #[error("Unknown content type: {0}")]
+    UnknownType(String),
+    #[error("Invalid content id: {0}")]
+    InvalidId(String),
+    #[error("Content not found: {0}/{1}")]
+    NotFound(ContentType, String),
+    #[error("Version {2} of {0}/{1} not found")]
+    VersionNotFound(ContentType, String, i64),
+    #[error("Version conflict: expected version {expected}, current version is {current}")]
+    VersionConflict { expected: i64, current: i64 },
+    #[error("Content failed validation with {} issue(s)", .0.issues.len())]
+    Invalid(ValidationReport),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] bson::ser::Error),
+}
+
+impl ToApiError for ContentError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            ContentError::UnknownType(_) | ContentError::NotFound(..) | ContentError::VersionNotFound(..) => {
+                ErrorCode::NotFound
+            }
+            ContentError::InvalidId(_) | ContentError::Invalid(_) => ErrorCode::ValidationFailed,
+            ContentError::VersionConflict { .. } => ErrorCode::Conflict,
+            ContentError::Database(_) => ErrorCode::Database,
+            ContentError::Serialization(_) => ErrorCode::Internal,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            ContentError::Database(_) | ContentError::Serialization(_) => {
+                self.error_code().default_message().to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Validate a content id, ids end up in URLs and game configs
+pub fn validate_content_id(content_id: &str) -> Result<(), ContentError> {
+    let valid = !content_id.is_empty()
+        && content_id.len() <= MAX_CONTENT_ID_LENGTH
+        && content_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ContentError::InvalidId(content_id.to_string()))
+    }
+}
+
+/// Versioned content storage
+///
+/// Every edit appends an immutable version to `versions`; `content` holds a copy of
+/// the latest version of each document for reads. Versions are never updated or
+/// deleted, a rollback appends a new version with the restored data.
+pub struct ContentStore {
+    documents: Collection<ContentDocument>,
+    versions: Collection<ContentVersion>,
+}
+
+impl ContentStore {
+    pub fn new(database: &Database) -> Self {
+        Self {
+            documents: database.collection("content"),
+            versions: database.collection("versions"),
+        }
+    }
+
+    /// Create the indexes the store relies on
+    ///
+    /// The unique version index makes concurrent edits of a document fail instead of
+    /// overwriting each other.
+    pub async fn ensure_indexes(&self) -> Result<(), ContentError> {
+        let unique = IndexOptions::builder().unique(true).build();
+        self.documents.create_index(
+            IndexModel::builder()
+                .keys(doc! { "content_type": 1, "content_id": 1 })
+                .options(unique.clone())
+                .build(),
+            None,
+        ).await?;
+        self.versions.create_index(
+            IndexModel::builder()
+                .keys(doc! { "content_type": 1, "content_id": 1, "version": -1 })
+                .options(unique)
+                .build(),
+            None,
+        ).await?;
+        Ok(())
+    }
+
+    /// Current version of every document of a type
+    pub async fn list(&self, content_type: ContentType) -> Result<Vec<ContentDocument>, ContentError> {
+        let options = FindOptions::builder().sort(doc! { "content_id": 1 }).build();
+        let mut cursor = self.documents
+            .find(doc! { "content_type": content_type.as_str() }, options)
+            .await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+        Ok(documents)
+    }
+
+    /// Current version of a document
+    pub async fn get(&self, content_type: ContentType, content_id: &str) -> Result<ContentDocument, ContentError> {
+        self.documents
+            .find_one(doc! { "content_type": content_type.as_str(), "content_id": content_id }, None)
+            .await?
+            .ok_or_else(|| ContentError::NotFound(content_type, content_id.to_string()))
+    }
+
+    /// Version history of a document, newest first
+    pub async fn history(&self, content_type: ContentType, content_id: &str) -> Result<Vec<VersionSummary>, ContentError> {
+        let options = FindOptions::builder()
+            .sort(doc! { "version": -1 })
+            .projection(doc! { "data": 0 })
+            .build();
+        let mut cursor = self.versions
+            .find(doc! { "content_type": content_type.as_str(), "content_id": content_id }, options)
+            .await?;
+
+        let mut versions = Vec::new();
+        while cursor.advance().await? {
+            versions.push(VersionSummary::from(cursor.deserialize_current()?));
+        }
+        if versions.is_empty() {
+            return Err(ContentError::NotFound(content_type, content_id.to_string()));
+        }
+        Ok(versions)
+    }
+
+    /// A single version of a document
+    pub async fn version(&self, content_type: ContentType, content_id: &str, version: i64) -> Result<ContentVersion, ContentError> {
+        self.versions
+            .find_one(doc! { "content_type": content_type.as_str(), "content_id": content_id, "version": version }, None)
+            .await?
+            .ok_or_else(|| ContentError::VersionNotFound(content_type, content_id.to_string(), version))
+    }
+
+    /// Latest version of a document, `None` when it was never saved
+    async fn latest_version(&self, content_type: ContentType, content_id: &str) -> Result<Option<ContentVersion>, ContentError> {
+        let options = FindOneOptions::builder().sort(doc! { "version": -1 }).build();
+        Ok(self.versions
+            .find_one(doc! { "content_type": content_type.as_str(), "content_id": content_id }, options)
+            .await?)
+    }
+
+    /// Store an edit as a new version
+    ///
+    /// An edit that does not change the data returns the current document without
+    /// adding a version.
+    pub async fn save(&self, content_type: ContentType, content_id: &str, edit: ContentEdit) -> Result<ContentDocument, ContentError> {
+        validate_content_id(content_id)?;
+        let latest = self.latest_version(content_type, content_id).await?;
+        let action = if latest.is_some() { VersionAction::Updated } else { VersionAction::Created };
+        self.append(content_type, content_id, latest, edit, action, None).await
+    }
+
+    /// Restore the data of an earlier version, as a new version
+    pub async fn rollback(
+        &self,
+        content_type: ContentType,
+        content_id: &str,
+        to_version: i64,
+        expected_version: Option<i64>,
+        author: String,
+    ) -> Result<ContentDocument, ContentError> {
+        let target = self.version(content_type, content_id, to_version).await?;
+        let latest = self.latest_version(content_type, content_id).await?;
+        let edit = ContentEdit {
+            data: target.data,
+            expected_version,
+            changes: format!("Rolled back to version {}", to_version),
+            author,
+        };
+        self.append(content_type, content_id, latest, edit, VersionAction::RolledBack, Some(to_version)).await
+    }
+
+    async fn append(
+        &self,
+        content_type: ContentType,
+        content_id: &str,
+        latest: Option<ContentVersion>,
+        edit: ContentEdit,
+        action: VersionAction,
+        rolled_back_to: Option<i64>,
+    ) -> Result<ContentDocument, ContentError> {
+        let current_version = latest.as_ref().map(|version| version.version).unwrap_or(0);
+        if let Some(expected) = edit.expected_version {
+            if expected != current_version {
+                return Err(ContentError::VersionConflict { expected, current: current_version });
+            }
+        }
+        if let Some(latest) = &latest {
+            if latest.data == edit.data {
+                return self.get(content_type, content_id).await;
+            }
+        }
+
+        let now = Utc::now();
+        let previous_data = latest.as_ref().map(|version| version.data.clone()).unwrap_or(Value::Null);
+        let version = ContentVersion {
+            content_type,
+            content_id: content_id.to_string(),
+            version: current_version + 1,
+            action,
+            rolled_back_to,
+            changes: edit.changes,
+            changed_paths: if latest.is_some() { changed_paths(&previous_data, &edit.data) } else { Vec::new() },
+            author: edit.author,
+            created_at: now,
+            data: edit.data,
+        };
+
+        // Another edit appended this version first
+        if let Err(e) = self.versions.insert_one(&version, None).await {
+            if is_duplicate_key_error(&e) {
+                return Err(ContentError::VersionConflict {
+                    expected: current_version,
+                    current: current_version + 1,
+                });
+            }
+            return Err(e.into());
+        }
+
+        let created = self.documents
+            .find_one(doc! { "content_type": content_type.as_str(), "content_id": content_id }, None)
+            .await?
+            .map(|document| (document.created_by, document.created_at));
+        let (created_by, created_at) = created.unwrap_or_else(|| (version.author.clone(), now));
+        let document = ContentDocument {
+            content_type,
+            content_id: content_id.to_string(),
+            version: version.version,
+            data: version.data.clone(),
+            created_by,
+            created_at,
+            updated_by: version.author.clone(),
+            updated_at: now,
+        };
+
+        // Never replace a newer copy written by a later edit
+        let update = doc! { "$set": bson::to_document(&document)? };
+        let options = UpdateOptions::builder().upsert(true).build();
+        let result = self.documents.update_one(
+            doc! {
+                "content_type": content_type.as_str(),
+                "content_id": content_id,
+                "version": { "$lt": document.version },
+            },
+            update,
+            options,
+        ).await;
+        match result {
+            Ok(_) => {}
+            Err(e) if is_duplicate_key_error(&e) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        tracing::info!(
+            "{} {}/{} version {} by {}",
+            match action {
+                VersionAction::Created => "Created",
+                VersionAction::Updated => "Updated",
+                VersionAction::RolledBack => "Rolled back",
+            },
+            content_type,
+            content_id,
+            document.version,
+            document.updated_by
+        );
+        Ok(document)
+    }
+}
+
+fn is_duplicate_key_error(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(write_error)) if write_error.code == 11000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_round_trip() {
+        for content_type in [ContentType::Quest, ContentType::ElementConfig, ContentType::LootTable] {
+            assert_eq!(content_type.as_str().parse::<ContentType>().unwrap(), content_type);
+            assert_eq!(serde_json::to_value(content_type).unwrap(), serde_json::json!(content_type.as_str()));
+        }
+        assert!("npc".parse::<ContentType>().is_err());
+    }
+
+    #[test]
+    fn test_validate_content_id() {
+        assert!(validate_content_id("quest_001").is_ok());
+        assert!(validate_content_id("fire-element.v2").is_ok());
+        assert!(validate_content_id("").is_err());
+        assert!(validate_content_id("../quests").is_err());
+        assert!(validate_content_id(&"a".repeat(MAX_CONTENT_ID_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let conflict = ContentError::VersionConflict { expected: 2, current: 3 };
+        assert_eq!(conflict.error_code(), ErrorCode::Conflict);
+        assert!(conflict.client_message().contains("current version is 3"));
+        assert_eq!(ContentError::NotFound(ContentType::Quest, "q".into()).error_code(), ErrorCode::NotFound);
+    }
+}
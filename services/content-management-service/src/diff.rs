@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// How a field changed between two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A single changed field, addressed by a JSON pointer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new: Option<Value>,
+}
+
+/// Structured diff between two content documents
+///
+/// Objects are compared key by key and arrays index by index, so a changed
+/// quest objective shows up as e.g. `/objectives/0/quantity`. Values of
+/// different JSON types are reported as one modification of the whole value.
+pub fn diff(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_at(String::new(), old, new, &mut changes);
+    changes
+}
+
+/// Paths of the fields changed between two documents
+pub fn changed_paths(old: &Value, new: &Value) -> Vec<String> {
+    diff(old, new).into_iter().map(|change| change.path).collect()
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(old_fields), Value::Object(new_fields)) => {
+            for (key, old_value) in old_fields {
+                let field_path = child_path(&path, key);
+                match new_fields.get(key) {
+                    Some(new_value) => diff_at(field_path, old_value, new_value, changes),
+                    None => changes.push(removed(field_path, old_value)),
+                }
+            }
+            for (key, new_value) in new_fields {
+                if !old_fields.contains_key(key) {
+                    changes.push(added(child_path(&path, key), new_value));
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for (index, old_item) in old_items.iter().enumerate() {
+                let item_path = child_path(&path, &index.to_string());
+                match new_items.get(index) {
+                    Some(new_item) => diff_at(item_path, old_item, new_item, changes),
+                    None => changes.push(removed(item_path, old_item)),
+                }
+            }
+            for (index, new_item) in new_items.iter().enumerate().skip(old_items.len()) {
+                changes.push(added(child_path(&path, &index.to_string()), new_item));
+            }
+        }
+        _ if old != new => changes.push(FieldChange {
+            path: if path.is_empty() { "/".to_string() } else { path },
+            change: ChangeKind::Modified,
+            old: Some(old.clone()),
+            new: Some(new.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn added(path: String, value: &Value) -> FieldChange {
+    FieldChange {
+        path,
+        change: ChangeKind::Added,
+        old: None,
+        new: Some(value.clone()),
+    }
+}
+
+fn removed(path: String, value: &Value) -> FieldChange {
+    FieldChange {
+        path,
+        change: ChangeKind::Removed,
+        old: Some(value.clone()),
+        new: None,
+    }
+}
+
+/// Append a JSON pointer segment, escaping `~` and `/` (RFC 6901)
+pub(crate) fn child_path(parent: &str, segment: &str) -> String {
+    format!("{}/{}", parent, segment.replace('~', "~0").replace('/', "~1"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_identical_documents_have_no_changes() {
+        let quest = json!({ "title": "The Lost Artifact", "objectives": [{ "quantity": 1 }] });
+        assert!(diff(&quest, &quest).is_empty());
+    }
+
+    #[test]
+    fn test_nested_changes() {
+        let old = json!({
+            "title": "The Lost Artifact",
+            "level_required": 10,
+            "objectives": [{ "id": "obj_001", "quantity": 1 }]
+        });
+        let new = json!({
+            "title": "The Ancient Artifact",
+            "objectives": [{ "id": "obj_001", "quantity": 2 }, { "id": "obj_002", "quantity": 1 }],
+            "time_limit": 3600
+        });
+
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 5);
+        assert!(changes.contains(&FieldChange {
+            path: "/title".to_string(),
+            change: ChangeKind::Modified,
+            old: Some(json!("The Lost Artifact")),
+            new: Some(json!("The Ancient Artifact")),
+        }));
+        assert!(changes.contains(&removed("/level_required".to_string(), &json!(10))));
+        assert!(changes.contains(&FieldChange {
+            path: "/objectives/0/quantity".to_string(),
+            change: ChangeKind::Modified,
+            old: Some(json!(1)),
+            new: Some(json!(2)),
+        }));
+        assert!(changes.contains(&added("/objectives/1".to_string(), &json!({ "id": "obj_002", "quantity": 1 }))));
+        assert!(changes.contains(&added("/time_limit".to_string(), &json!(3600))));
+    }
+
+    #[test]
+    fn test_type_change_and_root_change() {
+        let changes = diff(&json!({ "drops": [1, 2] }), &json!({ "drops": "none" }));
+        assert_eq!(changed_paths(&json!({ "drops": [1, 2] }), &json!({ "drops": "none" })), vec!["/drops"]);
+        assert_eq!(changes[0].change, ChangeKind::Modified);
+
+        assert_eq!(changed_paths(&json!(1), &json!(2)), vec!["/"]);
+    }
+
+    #[test]
+    fn test_pointer_escaping() {
+        assert_eq!(changed_paths(&json!({}), &json!({ "fire/water": 1, "a~b": 2 })).len(), 2);
+        assert!(changed_paths(&json!({}), &json!({ "fire/water": 1 })).contains(&"/fire~1water".to_string()));
+        assert!(changed_paths(&json!({}), &json!({ "a~b": 2 })).contains(&"/a~0b".to_string()));
+    }
+}
@@ -1,14 +1,29 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::Json,
-    routing::{get, post},
-    Router,
+    routing::{delete, get, post},
+    Extension, Router,
 };
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::error::{ApiError, ErrorCode, ToApiError};
+use shared::health::{HealthRegistry, HealthReport, LIVE_PATH, READY_PATH};
+use shared::preview::{PREVIEW_HEADER, PREVIEW_SERVICE_HEADER};
 use std::sync::Arc;
 
-use crate::auth::{AuthService, LoginRequest, LoginResponse, UserInfo};
+use crate::auth::{AuthService, Claims, LoginRequest, LoginResponse, UserInfo};
+use crate::content::{
+    ContentDocument, ContentEdit, ContentError, ContentStore, ContentType, ContentVersion, VersionSummary,
+};
+use crate::diff::{diff, FieldChange};
+use crate::localization::{
+    DanglingReference, ExportFormat, LocalizationStore, LocalizedString, MissingTranslationReport,
+};
+use crate::preview::{IssuedPreview, PreviewContent, PreviewItem, PreviewService, PreviewSessionInfo};
+use crate::publishing::{Environment, PublishError, PublishService, Release, ReleaseItem};
+use crate::sandbox::{dry_run_conditions, simulate_loot, ChainOutcome, LootSimulation, SandboxActor, SandboxWorld};
+use crate::validation::{ContentValidator, SubmittedContent, ValidationReport};
 use crate::monitoring::{MonitoringService, HealthStatus, MetricsInfo};
 
 #[derive(Debug, Serialize)]
@@ -16,6 +31,11 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    /// Structured error details, e.g. validation issues
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
     pub timestamp: String,
 }
 
@@ -39,6 +59,8 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            error_code: None,
+            details: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
@@ -48,9 +70,29 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(error),
+            error_code: None,
+            details: None,
             timestamp: chrono::Utc::now().to_rfc3339(),
         }
     }
+
+    pub fn from_api_error(error: ApiError) -> Self {
+        Self {
+            success: false,
+            data: None,
+            error: Some(error.message),
+            error_code: Some(error.code.to_string()),
+            details: None,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Convert an error into a status code and error response body
+pub fn error_response<E: ToApiError>(error: &E) -> (StatusCode, Json<ApiResponse<()>>) {
+    let api_error = error.to_api_error();
+    let status = StatusCode::from_u16(api_error.http_status()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    (status, Json(ApiResponse::from_api_error(api_error)))
 }
 
 // Auth handlers
@@ -60,10 +102,7 @@ pub async fn login_handler(
 ) -> Result<Json<ApiResponse<LoginResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
     match auth_service.login(request) {
         Ok(response) => Ok(Json(ApiResponse::success(response))),
-        Err(auth_error) => Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ApiResponse::error(auth_error.message)),
-        )),
+        Err(auth_error) => Err(error_response(&auth_error)),
     }
 }
 
@@ -94,6 +133,588 @@ pub fn create_protected_routes() -> Router<Arc<AuthService>> {
         .route("/admin", get(admin_handler))
 }
 
+// Content handlers
+type HandlerError = (StatusCode, Json<ApiResponse<()>>);
+type ContentState = (Arc<ContentStore>, Arc<ContentValidator>, Arc<LocalizationStore>);
+
+#[derive(Debug, Deserialize)]
+pub struct SaveContentRequest {
+    /// Content as JSON, or as YAML in `yaml`
+    pub data: Option<Value>,
+    pub yaml: Option<String>,
+    /// Version the edit is based on, omit to overwrite whatever is current
+    pub expected_version: Option<i64>,
+    #[serde(default)]
+    pub changes: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValidateContentRequest {
+    pub data: Option<Value>,
+    pub yaml: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RollbackRequest {
+    pub version: i64,
+    pub expected_version: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ContentDiffResponse {
+    pub content_type: ContentType,
+    pub content_id: String,
+    /// `None` when diffing against the empty document before the first version
+    pub from: Option<VersionSummary>,
+    pub to: VersionSummary,
+    pub changes: Vec<FieldChange>,
+}
+
+pub async fn list_content_handler(
+    State((store, _validator, _strings)): State<ContentState>,
+    Path(content_type): Path<String>,
+) -> Result<Json<ApiResponse<Vec<ContentDocument>>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let documents = store.list(content_type).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(documents)))
+}
+
+pub async fn get_content_handler(
+    State((store, _validator, _strings)): State<ContentState>,
+    Path((content_type, content_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<ContentDocument>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let document = store.get(content_type, &content_id).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(document)))
+}
+
+pub async fn save_content_handler(
+    State((store, validator, strings)): State<ContentState>,
+    Extension(claims): Extension<Claims>,
+    Path((content_type, content_id)): Path<(String, String)>,
+    Json(request): Json<SaveContentRequest>,
+) -> Result<Json<ApiResponse<ContentDocument>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let content = submitted_content(content_type, request.data, request.yaml)?;
+    check_valid(validate(&validator, &strings, content_type, &content).await?)?;
+
+    let edit = ContentEdit {
+        data: content.data,
+        expected_version: request.expected_version,
+        changes: request.changes,
+        author: claims.username,
+    };
+    let document = store.save(content_type, &content_id, edit).await.map_err(|e| content_error(&e))?;
+    Ok(Json(ApiResponse::success(document)))
+}
+
+/// Check content without saving it
+pub async fn validate_content_handler(
+    State((_store, validator, strings)): State<ContentState>,
+    Path(content_type): Path<String>,
+    Json(request): Json<ValidateContentRequest>,
+) -> Result<Json<ApiResponse<ValidationReport>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let content = submitted_content(content_type, request.data, request.yaml)?;
+    Ok(Json(ApiResponse::success(validate(&validator, &strings, content_type, &content).await?)))
+}
+
+pub async fn content_history_handler(
+    State((store, _validator, _strings)): State<ContentState>,
+    Path((content_type, content_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<Vec<VersionSummary>>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let versions = store.history(content_type, &content_id).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(versions)))
+}
+
+pub async fn content_version_handler(
+    State((store, _validator, _strings)): State<ContentState>,
+    Path((content_type, content_id, version)): Path<(String, String, i64)>,
+) -> Result<Json<ApiResponse<ContentVersion>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let version = store.version(content_type, &content_id, version).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(version)))
+}
+
+/// Diff two versions, by default the current version against the one before it
+pub async fn content_diff_handler(
+    State((store, _validator, _strings)): State<ContentState>,
+    Path((content_type, content_id)): Path<(String, String)>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Json<ApiResponse<ContentDiffResponse>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let to_version = match query.to {
+        Some(version) => version,
+        None => store.get(content_type, &content_id).await.map_err(|e| error_response(&e))?.version,
+    };
+    let from_version = query.from.unwrap_or(to_version - 1);
+
+    let to = store.version(content_type, &content_id, to_version).await.map_err(|e| error_response(&e))?;
+    let from = if from_version > 0 {
+        Some(store.version(content_type, &content_id, from_version).await.map_err(|e| error_response(&e))?)
+    } else {
+        None
+    };
+
+    let empty = Value::Object(Default::default());
+    let changes = diff(from.as_ref().map(|version| &version.data).unwrap_or(&empty), &to.data);
+    Ok(Json(ApiResponse::success(ContentDiffResponse {
+        content_type,
+        content_id,
+        from: from.map(VersionSummary::from),
+        to: to.into(),
+        changes,
+    })))
+}
+
+pub async fn rollback_content_handler(
+    State((store, validator, strings)): State<ContentState>,
+    Extension(claims): Extension<Claims>,
+    Path((content_type, content_id)): Path<(String, String)>,
+    Json(request): Json<RollbackRequest>,
+) -> Result<Json<ApiResponse<ContentDocument>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+
+    // Loaders may have become stricter and strings may have been deleted since the version was saved
+    let target = store.version(content_type, &content_id, request.version).await.map_err(|e| error_response(&e))?;
+    check_valid(validate(&validator, &strings, content_type, &SubmittedContent::from_json(target.data)).await?)?;
+
+    let document = store
+        .rollback(content_type, &content_id, request.version, request.expected_version, claims.username)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(document)))
+}
+
+fn parse_content_type(content_type: &str) -> Result<ContentType, HandlerError> {
+    content_type.parse().map_err(|e| error_response(&e))
+}
+
+/// Content from a request body, which carries either JSON `data` or `yaml`
+fn submitted_content(content_type: ContentType, data: Option<Value>, yaml: Option<String>) -> Result<SubmittedContent, HandlerError> {
+    match (data, yaml) {
+        (Some(data), None) => Ok(SubmittedContent::from_json(data)),
+        (None, Some(yaml)) => SubmittedContent::from_yaml(yaml).map_err(|issue| {
+            content_error(&ContentError::Invalid(ValidationReport::from_issue(content_type, issue)))
+        }),
+        _ => Err(error_response(&ApiError::new(
+            ErrorCode::ValidationFailed,
+            "Provide the content as either data or yaml".to_string(),
+        ))),
+    }
+}
+
+/// Run the loaders and check the localization keys the content references
+async fn validate(
+    validator: &ContentValidator,
+    strings: &LocalizationStore,
+    content_type: ContentType,
+    content: &SubmittedContent,
+) -> Result<ValidationReport, HandlerError> {
+    let issues = strings
+        .reference_issues(content_type, &content.data)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(validator.validate(content_type, content).with_issues(issues))
+}
+
+fn check_valid(report: ValidationReport) -> Result<(), HandlerError> {
+    for warning in &report.warnings {
+        tracing::debug!("Validation warning for {}: {}", report.content_type, warning);
+    }
+    if report.valid {
+        Ok(())
+    } else {
+        Err(content_error(&ContentError::Invalid(report)))
+    }
+}
+
+/// Error response that carries the validation report of invalid content
+fn content_error(error: &ContentError) -> HandlerError {
+    let (status, Json(mut response)) = error_response(error);
+    if let ContentError::Invalid(report) = error {
+        response.details = serde_json::to_value(report).ok();
+    }
+    (status, Json(response))
+}
+
+// Create content routes, every edit is kept as a version
+pub fn create_content_routes() -> Router<ContentState> {
+    Router::new()
+        .route("/content/:content_type", get(list_content_handler))
+        .route("/content/:content_type/validate", post(validate_content_handler))
+        .route("/content/:content_type/:content_id", get(get_content_handler).put(save_content_handler))
+        .route("/content/:content_type/:content_id/versions", get(content_history_handler))
+        .route("/content/:content_type/:content_id/versions/:version", get(content_version_handler))
+        .route("/content/:content_type/:content_id/diff", get(content_diff_handler))
+        .route("/content/:content_type/:content_id/rollback", post(rollback_content_handler))
+}
+
+// Publish handlers
+#[derive(Debug, Deserialize)]
+pub struct StageReleaseRequest {
+    pub environment: String,
+    pub items: Vec<ReleaseItem>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseQuery {
+    pub environment: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub async fn stage_release_handler(
+    State(publisher): State<Arc<PublishService>>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<StageReleaseRequest>,
+) -> Result<Json<ApiResponse<Release>>, HandlerError> {
+    let environment = parse_environment(&request.environment)?;
+    let release = publisher
+        .stage(environment, request.items, request.notes, claims.username)
+        .await
+        .map_err(|e| publish_error(&e))?;
+    Ok(Json(ApiResponse::success(release)))
+}
+
+pub async fn publish_release_handler(
+    State(publisher): State<Arc<PublishService>>,
+    Extension(claims): Extension<Claims>,
+    Path(release_id): Path<String>,
+) -> Result<Json<ApiResponse<Release>>, HandlerError> {
+    let release = publisher.publish(&release_id, claims.username).await.map_err(|e| publish_error(&e))?;
+    Ok(Json(ApiResponse::success(release)))
+}
+
+pub async fn list_releases_handler(
+    State(publisher): State<Arc<PublishService>>,
+    Query(query): Query<ReleaseQuery>,
+) -> Result<Json<ApiResponse<Vec<Release>>>, HandlerError> {
+    let environment = query.environment.as_deref().map(parse_environment).transpose()?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let releases = publisher.history(environment, limit).await.map_err(|e| publish_error(&e))?;
+    Ok(Json(ApiResponse::success(releases)))
+}
+
+pub async fn get_release_handler(
+    State(publisher): State<Arc<PublishService>>,
+    Path(release_id): Path<String>,
+) -> Result<Json<ApiResponse<Release>>, HandlerError> {
+    let release = publisher.release(&release_id).await.map_err(|e| publish_error(&e))?;
+    Ok(Json(ApiResponse::success(release)))
+}
+
+/// Restore what the environment served before the release, in one step
+pub async fn rollback_release_handler(
+    State(publisher): State<Arc<PublishService>>,
+    Extension(claims): Extension<Claims>,
+    Path(release_id): Path<String>,
+) -> Result<Json<ApiResponse<Release>>, HandlerError> {
+    let release = publisher.rollback(&release_id, claims.username).await.map_err(|e| publish_error(&e))?;
+    Ok(Json(ApiResponse::success(release)))
+}
+
+fn parse_environment(environment: &str) -> Result<Environment, HandlerError> {
+    environment.parse().map_err(|e| error_response(&e))
+}
+
+/// Error response that carries the validation report of content blocking a release
+fn publish_error(error: &PublishError) -> HandlerError {
+    match error {
+        PublishError::Content(e) => content_error(e),
+        _ => {
+            let (status, Json(mut response)) = error_response(error);
+            if let PublishError::InvalidContent(_, _, report) = error {
+                response.details = serde_json::to_value(report).ok();
+            }
+            (status, Json(response))
+        }
+    }
+}
+
+// Create publish routes, releases move content from the CMS to game services
+pub fn create_publish_routes() -> Router<Arc<PublishService>> {
+    Router::new()
+        .route("/publish/releases", get(list_releases_handler).post(stage_release_handler))
+        .route("/publish/releases/:release_id", get(get_release_handler))
+        .route("/publish/releases/:release_id/publish", post(publish_release_handler))
+        .route("/publish/releases/:release_id/rollback", post(rollback_release_handler))
+}
+
+// Preview handlers
+type PreviewState = (Arc<PreviewService>, Arc<ContentStore>);
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePreviewRequest {
+    pub services: Vec<String>,
+    /// Drafts to show, every document's latest revision when empty
+    #[serde(default)]
+    pub items: Vec<PreviewItem>,
+    pub ttl_minutes: Option<i64>,
+}
+
+/// Draft to run in the sandbox, a stored revision or unsaved `data` / `yaml`
+#[derive(Debug, Deserialize)]
+pub struct SandboxDraft {
+    pub content_id: Option<String>,
+    pub version: Option<i64>,
+    pub data: Option<Value>,
+    pub yaml: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SandboxConditionsRequest {
+    #[serde(flatten)]
+    pub draft: SandboxDraft,
+    #[serde(default)]
+    pub actor: SandboxActor,
+    #[serde(default)]
+    pub world: SandboxWorld,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SandboxLootRequest {
+    #[serde(flatten)]
+    pub draft: SandboxDraft,
+    pub runs: Option<u32>,
+    /// Seed of an earlier simulation to reproduce it
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SandboxConditionsResponse {
+    pub chains: Vec<ChainOutcome>,
+}
+
+pub async fn create_preview_handler(
+    State((previews, _store)): State<PreviewState>,
+    Extension(claims): Extension<Claims>,
+    Json(request): Json<CreatePreviewRequest>,
+) -> Result<Json<ApiResponse<IssuedPreview>>, HandlerError> {
+    let issued = previews
+        .create(request.services, request.items, request.ttl_minutes, claims.username)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(issued)))
+}
+
+pub async fn list_previews_handler(
+    State((previews, _store)): State<PreviewState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<PreviewSessionInfo>>>, HandlerError> {
+    let sessions = previews.list(&claims.username).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(sessions)))
+}
+
+pub async fn revoke_preview_handler(
+    State((previews, _store)): State<PreviewState>,
+    Extension(claims): Extension<Claims>,
+    Path(session_id): Path<String>,
+) -> Result<Json<ApiResponse<()>>, HandlerError> {
+    previews.revoke(&session_id, &claims.username).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// Draft a game service loads for a request carrying a preview token
+///
+/// Authenticated by the preview token, services call it from their config loaders.
+pub async fn preview_content_handler(
+    State((previews, _store)): State<PreviewState>,
+    headers: HeaderMap,
+    Path((content_type, content_id)): Path<(String, String)>,
+) -> Result<Json<ApiResponse<PreviewContent>>, HandlerError> {
+    let content_type = parse_content_type(&content_type)?;
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).unwrap_or_default();
+    let content = previews
+        .resolve(header(PREVIEW_HEADER), header(PREVIEW_SERVICE_HEADER), content_type, &content_id)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(content)))
+}
+
+/// Dry-run the condition chains of a quest draft against a fixture actor
+pub async fn sandbox_conditions_handler(
+    State((_previews, store)): State<PreviewState>,
+    Json(request): Json<SandboxConditionsRequest>,
+) -> Result<Json<ApiResponse<SandboxConditionsResponse>>, HandlerError> {
+    let data = sandbox_draft(&store, ContentType::Quest, request.draft).await?;
+    let chains = dry_run_conditions(&data, &request.actor, &request.world)
+        .await
+        .map_err(|message| error_response(&ApiError::new(ErrorCode::ValidationFailed, message)))?;
+    Ok(Json(ApiResponse::success(SandboxConditionsResponse { chains })))
+}
+
+/// Roll a loot table draft many times and report drop rates
+pub async fn sandbox_loot_handler(
+    State((_previews, store)): State<PreviewState>,
+    Json(request): Json<SandboxLootRequest>,
+) -> Result<Json<ApiResponse<LootSimulation>>, HandlerError> {
+    let data = sandbox_draft(&store, ContentType::LootTable, request.draft).await?;
+    let simulation = simulate_loot(&data, request.runs.unwrap_or(1000), request.seed.unwrap_or_else(rand::random))
+        .map_err(|message| error_response(&ApiError::new(ErrorCode::ValidationFailed, message)))?;
+    Ok(Json(ApiResponse::success(simulation)))
+}
+
+async fn sandbox_draft(store: &ContentStore, content_type: ContentType, draft: SandboxDraft) -> Result<Value, HandlerError> {
+    let content_id = match draft.content_id {
+        Some(content_id) if draft.data.is_none() && draft.yaml.is_none() => content_id,
+        Some(_) => {
+            return Err(error_response(&ApiError::new(
+                ErrorCode::ValidationFailed,
+                "Provide either content_id or the draft content, not both".to_string(),
+            )))
+        }
+        None => return Ok(submitted_content(content_type, draft.data, draft.yaml)?.data),
+    };
+    let data = match draft.version {
+        Some(version) => store.version(content_type, &content_id, version).await.map(|version| version.data),
+        None => store.get(content_type, &content_id).await.map(|document| document.data),
+    };
+    data.map_err(|e| content_error(&e))
+}
+
+// Create preview routes for editors
+pub fn create_preview_routes() -> Router<PreviewState> {
+    Router::new()
+        .route("/preview/sessions", get(list_previews_handler).post(create_preview_handler))
+        .route("/preview/sessions/:session_id", delete(revoke_preview_handler))
+        .route("/preview/sandbox/conditions", post(sandbox_conditions_handler))
+        .route("/preview/sandbox/loot", post(sandbox_loot_handler))
+}
+
+// Create the draft route game services call with a preview token
+pub fn create_preview_content_routes() -> Router<PreviewState> {
+    Router::new()
+        .route("/preview/content/:content_type/:content_id", get(preview_content_handler))
+}
+
+// Localization handlers
+type LocalizationState = (Arc<LocalizationStore>, Arc<ContentStore>);
+
+#[derive(Debug, Serialize)]
+pub struct LocalesResponse {
+    pub default_locale: String,
+    pub locales: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StringListQuery {
+    /// Only keys starting with this, e.g. `quest.lost_artifact.`
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PutStringRequest {
+    /// Translations by locale, an empty value removes a translation
+    pub values: std::collections::BTreeMap<String, String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>,
+    pub locale: Option<String>,
+    /// Fill missing translations from the default locale
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+pub async fn list_locales_handler(
+    State((strings, _store)): State<LocalizationState>,
+) -> Result<Json<ApiResponse<LocalesResponse>>, HandlerError> {
+    Ok(Json(ApiResponse::success(LocalesResponse {
+        default_locale: strings.default_locale().to_string(),
+        locales: strings.locales().to_vec(),
+    })))
+}
+
+pub async fn list_strings_handler(
+    State((strings, _store)): State<LocalizationState>,
+    Query(query): Query<StringListQuery>,
+) -> Result<Json<ApiResponse<Vec<LocalizedString>>>, HandlerError> {
+    let list = strings.list(query.prefix.as_deref()).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(list)))
+}
+
+pub async fn get_string_handler(
+    State((strings, _store)): State<LocalizationState>,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<LocalizedString>>, HandlerError> {
+    let string = strings.get(&key).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(string)))
+}
+
+pub async fn put_string_handler(
+    State((strings, _store)): State<LocalizationState>,
+    Extension(claims): Extension<Claims>,
+    Path(key): Path<String>,
+    Json(request): Json<PutStringRequest>,
+) -> Result<Json<ApiResponse<LocalizedString>>, HandlerError> {
+    let string = strings
+        .put(&key, request.values, request.description, claims.username)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(string)))
+}
+
+pub async fn delete_string_handler(
+    State((strings, _store)): State<LocalizationState>,
+    Path(key): Path<String>,
+) -> Result<Json<ApiResponse<()>>, HandlerError> {
+    strings.delete(&key).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(())))
+}
+
+pub async fn missing_translations_handler(
+    State((strings, _store)): State<LocalizationState>,
+) -> Result<Json<ApiResponse<MissingTranslationReport>>, HandlerError> {
+    let report = strings.missing_translations().await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// Content referencing keys that no longer exist
+pub async fn dangling_references_handler(
+    State((strings, store)): State<LocalizationState>,
+) -> Result<Json<ApiResponse<Vec<DanglingReference>>>, HandlerError> {
+    let references = strings.dangling_references(&store).await.map_err(|e| error_response(&e))?;
+    Ok(Json(ApiResponse::success(references)))
+}
+
+/// Strings in the format game services and clients load, served as a file
+pub async fn export_strings_handler(
+    State((strings, _store)): State<LocalizationState>,
+    Query(query): Query<ExportQuery>,
+) -> Result<([(header::HeaderName, &'static str); 1], String), HandlerError> {
+    let format: ExportFormat = query.format.as_deref().unwrap_or("json").parse().map_err(|e| error_response(&e))?;
+    let body = strings
+        .export(format, query.locale.as_deref(), query.fallback)
+        .await
+        .map_err(|e| error_response(&e))?;
+    Ok(([(header::CONTENT_TYPE, format.content_type())], body))
+}
+
+// Create localization routes
+pub fn create_localization_routes() -> Router<LocalizationState> {
+    Router::new()
+        .route("/localization/locales", get(list_locales_handler))
+        .route("/localization/strings", get(list_strings_handler))
+        .route(
+            "/localization/strings/:key",
+            get(get_string_handler).put(put_string_handler).delete(delete_string_handler),
+        )
+        .route("/localization/missing", get(missing_translations_handler))
+        .route("/localization/references", get(dangling_references_handler))
+        .route("/localization/export", get(export_strings_handler))
+}
+
 // Monitoring handlers
 pub async fn health_handler(
     State(monitoring): State<Arc<MonitoringService>>,
@@ -195,6 +816,24 @@ pub fn create_monitoring_routes() -> Router<Arc<MonitoringService>> {
         .route("/health/check", post(health_check_proxy_handler))
 }
 
+// Create liveness and readiness probe routes
+pub fn create_probe_routes() -> Router<Arc<HealthRegistry>> {
+    Router::new()
+        .route(LIVE_PATH, get(liveness_handler))
+        .route(READY_PATH, get(readiness_handler))
+}
+
+pub async fn liveness_handler(State(health): State<Arc<HealthRegistry>>) -> Json<HealthReport> {
+    Json(health.liveness())
+}
+
+/// 503 while a critical dependency is down
+pub async fn readiness_handler(State(health): State<Arc<HealthRegistry>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = StatusCode::from_u16(report.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report))
+}
+
 // Create basic routes
 pub fn create_basic_routes() -> Router<()> {
     Router::new()
@@ -0,0 +1,528 @@
+use bson::doc;
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOptions, IndexOptions, UpdateOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::error::{ErrorCode, ToApiError};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::config::LocalizationConfig;
+use crate::content::{ContentError, ContentStore, ContentType};
+use crate::diff::child_path;
+use crate::validation::ValidationIssue;
+
+/// Content strings starting with this prefix reference a localization key,
+/// e.g. `"title": "loc:quest.lost_artifact.title"`
+pub const KEY_REFERENCE_PREFIX: &str = "loc:";
+
+/// Longest accepted string key
+const MAX_KEY_LENGTH: usize = 128;
+
+/// A string key with its translations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalizedString {
+    pub key: String,
+    /// Translations by locale
+    #[serde(default)]
+    pub values: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub updated_by: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Translation coverage of one locale
+#[derive(Debug, Clone, Serialize)]
+pub struct LocaleCoverage {
+    pub translated: usize,
+    pub total: usize,
+    /// Share of keys translated, 0.0 to 1.0
+    pub coverage: f64,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingTranslationReport {
+    pub default_locale: String,
+    pub locales: BTreeMap<String, LocaleCoverage>,
+}
+
+/// A key referenced by content that has no string
+#[derive(Debug, Clone, Serialize)]
+pub struct DanglingReference {
+    pub content_type: ContentType,
+    pub content_id: String,
+    pub path: String,
+    pub key: String,
+}
+
+/// Formats strings are exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Flat `{ "key": "value" }` object of one locale, loaded by clients
+    Json,
+    /// Flat `key: value` mapping of one locale, loaded by game services
+    Yaml,
+    /// Every locale side by side, for translators
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "application/json",
+            ExportFormat::Yaml => "application/yaml",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = LocalizationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "yaml" => Ok(ExportFormat::Yaml),
+            "csv" => Ok(ExportFormat::Csv),
+            _ => Err(LocalizationError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocalizationError {
+    #[error("Invalid string key: {0}")]
+    InvalidKey(String),
+    #[error("Unsupported locale: {0}")]
+    UnsupportedLocale(String),
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+    #[error("String not found: {0}")]
+    NotFound(String),
+    #[error(transparent)]
+    Content(#[from] ContentError),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+impl ToApiError for LocalizationError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            LocalizationError::InvalidKey(_)
+            | LocalizationError::UnsupportedLocale(_)
+            | LocalizationError::UnknownFormat(_) => ErrorCode::ValidationFailed,
+            LocalizationError::NotFound(_) => ErrorCode::NotFound,
+            LocalizationError::Content(e) => e.error_code(),
+            LocalizationError::Database(_) => ErrorCode::Database,
+            LocalizationError::Serialization(_) => ErrorCode::Internal,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            LocalizationError::Content(e) => e.client_message(),
+            LocalizationError::Database(_) | LocalizationError::Serialization(_) => {
+                self.error_code().default_message().to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Validate a string key, dot-separated lowercase segments like `quest.lost_artifact.title`
+pub fn validate_key(key: &str) -> Result<(), LocalizationError> {
+    let valid = !key.is_empty()
+        && key.len() <= MAX_KEY_LENGTH
+        && key.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(LocalizationError::InvalidKey(key.to_string()))
+    }
+}
+
+/// Content types whose strings may reference localization keys
+const REFERENCING_TYPES: [ContentType; 2] = [ContentType::Quest, ContentType::Dialogue];
+
+pub fn references_strings(content_type: ContentType) -> bool {
+    REFERENCING_TYPES.contains(&content_type)
+}
+
+/// Keys a content document references, with the JSON pointer of each reference
+pub fn referenced_keys(data: &Value) -> Vec<(String, String)> {
+    let mut references = Vec::new();
+    collect_references(String::new(), data, &mut references);
+    references
+}
+
+fn collect_references(path: String, value: &Value, references: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(text) => {
+            if let Some(key) = text.strip_prefix(KEY_REFERENCE_PREFIX) {
+                references.push((if path.is_empty() { "/".to_string() } else { path }, key.to_string()));
+            }
+        }
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                collect_references(child_path(&path, name), field, references);
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect_references(child_path(&path, &index.to_string()), item, references);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// String keys and their translations
+pub struct LocalizationStore {
+    strings: Collection<LocalizedString>,
+    default_locale: String,
+    locales: Vec<String>,
+}
+
+impl LocalizationStore {
+    pub fn new(database: &Database, config: &LocalizationConfig) -> Self {
+        let mut locales = config.locales.clone();
+        if !locales.contains(&config.default_locale) {
+            locales.insert(0, config.default_locale.clone());
+        }
+        Self {
+            strings: database.collection("strings"),
+            default_locale: config.default_locale.clone(),
+            locales,
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), LocalizationError> {
+        self.strings.create_index(
+            IndexModel::builder()
+                .keys(doc! { "key": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None,
+        ).await?;
+        Ok(())
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// Strings ordered by key, optionally only keys under a prefix like `quest.`
+    pub async fn list(&self, prefix: Option<&str>) -> Result<Vec<LocalizedString>, LocalizationError> {
+        let options = FindOptions::builder().sort(doc! { "key": 1 }).build();
+        let mut cursor = self.strings.find(doc! {}, options).await?;
+
+        let mut strings = Vec::new();
+        while cursor.advance().await? {
+            let string: LocalizedString = cursor.deserialize_current()?;
+            if prefix.is_none_or(|prefix| string.key.starts_with(prefix)) {
+                strings.push(string);
+            }
+        }
+        Ok(strings)
+    }
+
+    pub async fn get(&self, key: &str) -> Result<LocalizedString, LocalizationError> {
+        self.strings
+            .find_one(doc! { "key": key }, None)
+            .await?
+            .ok_or_else(|| LocalizationError::NotFound(key.to_string()))
+    }
+
+    /// Create a key or update some of its translations
+    ///
+    /// Only the locales in `values` change, an empty value removes that translation.
+    pub async fn put(
+        &self,
+        key: &str,
+        values: BTreeMap<String, String>,
+        description: Option<String>,
+        author: String,
+    ) -> Result<LocalizedString, LocalizationError> {
+        validate_key(key)?;
+        if let Some(locale) = values.keys().find(|locale| !self.locales.contains(locale)) {
+            return Err(LocalizationError::UnsupportedLocale(locale.clone()));
+        }
+
+        let mut set = doc! {
+            "updated_by": author,
+            "updated_at": Utc::now().to_rfc3339(),
+        };
+        let mut unset = doc! {};
+        for (locale, value) in values {
+            if value.is_empty() {
+                unset.insert(format!("values.{}", locale), "");
+            } else {
+                set.insert(format!("values.{}", locale), value);
+            }
+        }
+        if let Some(description) = description {
+            set.insert("description", description);
+        }
+
+        let mut update = doc! { "$set": set };
+        if !unset.is_empty() {
+            update.insert("$unset", unset);
+        }
+        let options = UpdateOptions::builder().upsert(true).build();
+        self.strings.update_one(doc! { "key": key }, update, options).await?;
+        self.get(key).await
+    }
+
+    pub async fn delete(&self, key: &str) -> Result<(), LocalizationError> {
+        let result = self.strings.delete_one(doc! { "key": key }, None).await?;
+        if result.deleted_count == 0 {
+            return Err(LocalizationError::NotFound(key.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Keys without a translation, per supported locale
+    pub async fn missing_translations(&self) -> Result<MissingTranslationReport, LocalizationError> {
+        Ok(missing_report(&self.list(None).await?, &self.default_locale, &self.locales))
+    }
+
+    /// Strings of one locale, or of every locale for CSV
+    ///
+    /// With `fallback`, keys missing in the locale get the default locale's value.
+    pub async fn export(
+        &self,
+        format: ExportFormat,
+        locale: Option<&str>,
+        fallback: bool,
+    ) -> Result<String, LocalizationError> {
+        let locale = locale.unwrap_or(&self.default_locale);
+        if !self.locales.iter().any(|supported| supported == locale) {
+            return Err(LocalizationError::UnsupportedLocale(locale.to_string()));
+        }
+        let strings = self.list(None).await?;
+
+        match format {
+            ExportFormat::Json => {
+                let values = locale_values(&strings, locale, fallback.then_some(self.default_locale.as_str()));
+                serde_json::to_string_pretty(&values).map_err(|e| LocalizationError::Serialization(e.to_string()))
+            }
+            ExportFormat::Yaml => {
+                let values = locale_values(&strings, locale, fallback.then_some(self.default_locale.as_str()));
+                serde_yaml::to_string(&values).map_err(|e| LocalizationError::Serialization(e.to_string()))
+            }
+            ExportFormat::Csv => Ok(to_csv(&strings, &self.locales)),
+        }
+    }
+
+    /// Validation issues for references to keys that do not exist
+    pub async fn reference_issues(
+        &self,
+        content_type: ContentType,
+        data: &Value,
+    ) -> Result<Vec<ValidationIssue>, LocalizationError> {
+        if !references_strings(content_type) {
+            return Ok(Vec::new());
+        }
+        let references = referenced_keys(data);
+        let existing = self.existing_keys(references.iter().map(|(_, key)| key.as_str())).await?;
+
+        Ok(references
+            .into_iter()
+            .filter(|(_, key)| !existing.contains(key))
+            .map(|(path, key)| ValidationIssue {
+                source: "localization",
+                path: Some(path),
+                line: None,
+                column: None,
+                message: format!("Unknown localization key: {}", key),
+            })
+            .collect())
+    }
+
+    /// References to missing keys across the current version of all content
+    ///
+    /// Saves reject unknown keys, this finds references broken by deleting a key.
+    pub async fn dangling_references(&self, content: &ContentStore) -> Result<Vec<DanglingReference>, LocalizationError> {
+        let mut documents = Vec::new();
+        for content_type in REFERENCING_TYPES {
+            documents.extend(content.list(content_type).await?);
+        }
+        let references: Vec<(usize, String, String)> = documents
+            .iter()
+            .enumerate()
+            .flat_map(|(index, document)| {
+                referenced_keys(&document.data).into_iter().map(move |(path, key)| (index, path, key))
+            })
+            .collect();
+        let existing = self.existing_keys(references.iter().map(|(_, _, key)| key.as_str())).await?;
+
+        Ok(references
+            .into_iter()
+            .filter(|(_, _, key)| !existing.contains(key))
+            .map(|(index, path, key)| DanglingReference {
+                content_type: documents[index].content_type,
+                content_id: documents[index].content_id.clone(),
+                path,
+                key,
+            })
+            .collect())
+    }
+
+    /// Which of `keys` have a string
+    async fn existing_keys<'a>(&self, keys: impl Iterator<Item = &'a str>) -> Result<BTreeSet<String>, LocalizationError> {
+        let keys: BTreeSet<&str> = keys.collect();
+        if keys.is_empty() {
+            return Ok(BTreeSet::new());
+        }
+        let options = FindOptions::builder().projection(doc! { "key": 1, "_id": 0 }).build();
+        let mut cursor = self.strings
+            .clone_with_type::<bson::Document>()
+            .find(doc! { "key": { "$in": keys.into_iter().collect::<Vec<_>>() } }, options)
+            .await?;
+
+        let mut existing = BTreeSet::new();
+        while cursor.advance().await? {
+            if let Ok(key) = cursor.current().get_str("key") {
+                existing.insert(key.to_string());
+            }
+        }
+        Ok(existing)
+    }
+}
+
+fn missing_report(strings: &[LocalizedString], default_locale: &str, locales: &[String]) -> MissingTranslationReport {
+    let total = strings.len();
+    let locales = locales
+        .iter()
+        .map(|locale| {
+            let missing: Vec<String> = strings
+                .iter()
+                .filter(|string| string.values.get(locale).is_none_or(|value| value.is_empty()))
+                .map(|string| string.key.clone())
+                .collect();
+            let translated = total - missing.len();
+            let coverage = if total == 0 { 1.0 } else { translated as f64 / total as f64 };
+            (locale.clone(), LocaleCoverage { translated, total, coverage, missing })
+        })
+        .collect();
+    MissingTranslationReport {
+        default_locale: default_locale.to_string(),
+        locales,
+    }
+}
+
+fn locale_values(strings: &[LocalizedString], locale: &str, fallback: Option<&str>) -> BTreeMap<String, String> {
+    strings
+        .iter()
+        .filter_map(|string| {
+            let value = string.values.get(locale).or_else(|| fallback.and_then(|fallback| string.values.get(fallback)))?;
+            Some((string.key.clone(), value.clone()))
+        })
+        .collect()
+}
+
+fn to_csv(strings: &[LocalizedString], locales: &[String]) -> String {
+    let mut csv = String::new();
+    let header: Vec<&str> = ["key", "description"].into_iter().chain(locales.iter().map(String::as_str)).collect();
+    csv.push_str(&header.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(","));
+    csv.push('\n');
+
+    for string in strings {
+        let mut row = vec![csv_field(&string.key), csv_field(string.description.as_deref().unwrap_or_default())];
+        row.extend(locales.iter().map(|locale| csv_field(string.values.get(locale).map(String::as_str).unwrap_or_default())));
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Quote a CSV field when it needs it (RFC 4180)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn string(key: &str, values: &[(&str, &str)]) -> LocalizedString {
+        LocalizedString {
+            key: key.to_string(),
+            values: values.iter().map(|(locale, value)| (locale.to_string(), value.to_string())).collect(),
+            description: None,
+            updated_by: "editor".to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_key_validation() {
+        assert!(validate_key("quest.lost_artifact.title").is_ok());
+        assert!(validate_key("npc.old-sage.greeting_2").is_ok());
+        for key in ["", "Quest.title", "quest..title", ".quest", "quest.title.", "quest title", "quest/title"] {
+            assert!(validate_key(key).is_err(), "accepted {:?}", key);
+        }
+    }
+
+    #[test]
+    fn test_referenced_keys() {
+        let quest = json!({
+            "title": "loc:quest.lost_artifact.title",
+            "name": "The Lost Artifact",
+            "objectives": [{ "description": "loc:quest.lost_artifact.objective_1" }]
+        });
+        let references = referenced_keys(&quest);
+        assert_eq!(references.len(), 2);
+        assert!(references.contains(&("/title".to_string(), "quest.lost_artifact.title".to_string())));
+        assert!(references.contains(&("/objectives/0/description".to_string(), "quest.lost_artifact.objective_1".to_string())));
+
+        assert!(references_strings(ContentType::Dialogue));
+        assert!(!references_strings(ContentType::LootTable));
+    }
+
+    #[test]
+    fn test_missing_report() {
+        let strings = vec![
+            string("quest.a", &[("en", "A"), ("vi", "A vi")]),
+            string("quest.b", &[("en", "B")]),
+        ];
+        let report = missing_report(&strings, "en", &["en".to_string(), "vi".to_string()]);
+        assert_eq!(report.locales["en"].translated, 2);
+        assert_eq!(report.locales["vi"].missing, vec!["quest.b"]);
+        assert!((report.locales["vi"].coverage - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_exports() {
+        let strings = vec![
+            string("quest.a", &[("en", "Hello, \"hero\""), ("vi", "Xin chào")]),
+            string("quest.b", &[("en", "B")]),
+        ];
+        let vi = locale_values(&strings, "vi", None);
+        assert_eq!(vi.len(), 1);
+        let with_fallback = locale_values(&strings, "vi", Some("en"));
+        assert_eq!(with_fallback["quest.b"], "B");
+
+        let csv = to_csv(&strings, &["en".to_string(), "vi".to_string()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "key,description,en,vi");
+        assert_eq!(lines[1], "quest.a,,\"Hello, \"\"hero\"\"\",Xin chào");
+        assert_eq!(lines[2], "quest.b,,B,");
+    }
+}
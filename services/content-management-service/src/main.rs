@@ -2,6 +2,13 @@ mod config;
 mod auth;
 mod monitoring;
 mod handlers;
+mod content;
+mod diff;
+mod validation;
+mod publishing;
+mod preview;
+mod sandbox;
+mod localization;
 
 use axum::{
     middleware,
@@ -12,25 +19,34 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use shared::telemetry::{self, TelemetryOptions};
 use config::Config;
 use auth::{AuthService, auth_middleware};
 use monitoring::MonitoringService;
+use content::ContentStore;
+use validation::ContentValidator;
+use publishing::PublishService;
+use preview::PreviewService;
+use localization::LocalizationStore;
 use handlers::{
     create_auth_routes, create_monitoring_routes, create_basic_routes, create_protected_routes,
-    status_handler,
+    create_content_routes, create_publish_routes, create_preview_routes, create_preview_content_routes,
+    create_localization_routes, create_probe_routes, status_handler,
 };
+use shared::health::{check_fn, HealthRegistry};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::ChaosError;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "content_management_service=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let telemetry = telemetry::init(
+        &TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .with_default_filter("content_management_service=debug,tower_http=debug"),
+    );
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
 
     // Load configuration
     let config = Config::load()?;
@@ -46,13 +62,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     ));
 
     let monitoring_service = Arc::new(MonitoringService::new());
+
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await?;
+    let cms_database = mongo_client.database(&config.database.mongodb_database);
+    let content_store = Arc::new(ContentStore::new(&cms_database));
+    content_store.ensure_indexes().await?;
+    let content_validator = Arc::new(ContentValidator::new());
+    let localization_store = Arc::new(LocalizationStore::new(&cms_database, &config.localization));
+    localization_store.ensure_indexes().await?;
+    tracing::info!("🗄️ Content store ready (database: {})", config.database.mongodb_database);
+
+    let publish_service = Arc::new(PublishService::new(
+        &cms_database,
+        content_store.clone(),
+        content_validator.clone(),
+        &config.publishing,
+    ).await?);
+    publish_service.ensure_indexes().await?;
+
+    let preview_service = Arc::new(PreviewService::new(&cms_database, content_store.clone()));
+    preview_service.ensure_indexes().await?;
     tracing::info!("🔧 Services initialized successfully");
 
+    // Readiness follows the CMS database
+    let health_database = cms_database.clone();
+    let health = Arc::new(HealthRegistry::new("content-management-service").with_shutdown(&shutdown).register(
+        "mongodb",
+        check_fn(move || {
+            let database = health_database.clone();
+            async move {
+                database
+                    .run_command(bson::doc! { "ping": 1 }, None)
+                    .await
+                    .map(|_| ())
+                    .map_err(|e| ChaosError::Database(e.to_string()))
+            }
+        }),
+    ));
+
     // Create application router
     let app = Router::new()
         // Basic routes (no auth required)
         .merge(create_basic_routes())
         .route("/health", get(status_handler))
+        .merge(create_probe_routes().with_state(health))
         
         // Auth routes (no auth required)
         .nest("/api/v1/auth", create_auth_routes().with_state(auth_service.clone()))
@@ -68,6 +121,43 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                 auth_middleware,
             ))
         )
+
+        // Versioned content routes (auth required)
+        .nest("/api/v1", create_content_routes()
+            .with_state((content_store.clone(), content_validator.clone(), localization_store.clone()))
+            .route_layer(middleware::from_fn_with_state(
+                auth_service.clone(),
+                auth_middleware,
+            ))
+        )
+
+        // Localization routes (auth required)
+        .nest("/api/v1", create_localization_routes()
+            .with_state((localization_store.clone(), content_store.clone()))
+            .route_layer(middleware::from_fn_with_state(
+                auth_service.clone(),
+                auth_middleware,
+            ))
+        )
+
+        // Draft preview routes (auth required), drafts themselves are served by preview token
+        .nest("/api/v1", create_preview_routes()
+            .with_state((preview_service.clone(), content_store.clone()))
+            .route_layer(middleware::from_fn_with_state(
+                auth_service.clone(),
+                auth_middleware,
+            ))
+        )
+        .nest("/api/v1", create_preview_content_routes().with_state((preview_service.clone(), content_store.clone())))
+
+        // Publish pipeline routes (auth required)
+        .nest("/api/v1", create_publish_routes()
+            .with_state(publish_service.clone())
+            .route_layer(middleware::from_fn_with_state(
+                auth_service.clone(),
+                auth_middleware,
+            ))
+        )
         
         // Add middleware
         .layer(CorsLayer::permissive())
@@ -93,8 +183,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     tracing::info!("📝 Admin login: {} / {}", config.auth.admin_username, config.auth.admin_password);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result?;
+    }
 
+    // Let publishes and edits of drained requests reach MongoDB and the event bus before exiting
+    let flushed = async {
+        publish_service.flush().await;
+        mongo_client.shutdown().await;
+    };
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, flushed).await.is_err() {
+        tracing::warn!("⚠️ MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 CMS Service stopped");
+    telemetry.shutdown();
     Ok(())
 }
 
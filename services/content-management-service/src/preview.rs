@@ -0,0 +1,346 @@
+use bson::doc;
+use chrono::{DateTime, Duration, Utc};
+use mongodb::options::{FindOptions, IndexOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use shared::error::{ErrorCode, ToApiError};
+use shared::preview::{is_valid_preview_token, PREVIEW_TOKEN_PREFIX};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::content::{validate_content_id, ContentError, ContentStore, ContentType};
+
+/// Lifetime of a preview session when the editor does not ask for one
+pub const DEFAULT_PREVIEW_TTL_MINUTES: i64 = 60;
+
+/// Longest lifetime of a preview session
+pub const MAX_PREVIEW_TTL_MINUTES: i64 = 24 * 60;
+
+/// Draft revision a preview session shows
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviewItem {
+    pub content_type: ContentType,
+    pub content_id: String,
+    /// `None` follows the latest saved revision
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+/// Stored preview session, only a hash of its token is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewSession {
+    pub id: String,
+    pub token_hash: String,
+    /// Services that read drafts for the session
+    pub services: Vec<String>,
+    /// Drafts shown, every document's latest revision when empty
+    pub items: Vec<PreviewItem>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl PreviewSession {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at > now
+    }
+
+    /// Draft revision shown for a document, `None` when the session does not cover it
+    ///
+    /// The inner `None` means the latest saved revision.
+    fn covers(&self, content_type: ContentType, content_id: &str) -> Option<Option<i64>> {
+        if self.items.is_empty() {
+            return Some(None);
+        }
+        self.items
+            .iter()
+            .find(|item| item.content_type == content_type && item.content_id == content_id)
+            .map(|item| item.version)
+    }
+}
+
+/// Preview session as returned to editors
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewSessionInfo {
+    pub id: String,
+    pub services: Vec<String>,
+    pub items: Vec<PreviewItem>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub active: bool,
+}
+
+impl From<PreviewSession> for PreviewSessionInfo {
+    fn from(session: PreviewSession) -> Self {
+        Self {
+            active: session.is_active(Utc::now()),
+            id: session.id,
+            services: session.services,
+            items: session.items,
+            created_by: session.created_by,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+/// A new session with its token, the token is not shown again
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedPreview {
+    pub token: String,
+    pub session: PreviewSessionInfo,
+}
+
+/// Draft served to a game service
+#[derive(Debug, Clone, Serialize)]
+pub struct PreviewContent {
+    pub content_type: ContentType,
+    pub content_id: String,
+    pub version: i64,
+    pub data: Value,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PreviewError {
+    #[error("Preview token is invalid or expired")]
+    InvalidToken,
+    #[error("Service {0} is not part of this preview")]
+    ServiceNotSelected(String),
+    #[error("{0}/{1} is not part of this preview")]
+    NotCovered(ContentType, String),
+    #[error("Preview session not found: {0}")]
+    SessionNotFound(String),
+    #[error("{0}")]
+    InvalidRequest(String),
+    #[error(transparent)]
+    Content(#[from] ContentError),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+}
+
+impl ToApiError for PreviewError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            PreviewError::InvalidToken | PreviewError::ServiceNotSelected(_) => ErrorCode::PermissionDenied,
+            PreviewError::NotCovered(..) | PreviewError::SessionNotFound(_) => ErrorCode::NotFound,
+            PreviewError::InvalidRequest(_) => ErrorCode::ValidationFailed,
+            PreviewError::Content(e) => e.error_code(),
+            PreviewError::Database(_) => ErrorCode::Database,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            PreviewError::Content(e) => e.client_message(),
+            PreviewError::Database(_) => self.error_code().default_message().to_string(),
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Issues preview tokens and serves draft revisions to the services they select
+pub struct PreviewService {
+    sessions: Collection<PreviewSession>,
+    store: Arc<ContentStore>,
+}
+
+impl PreviewService {
+    pub fn new(database: &Database, store: Arc<ContentStore>) -> Self {
+        Self {
+            sessions: database.collection("preview_sessions"),
+            store,
+        }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), PreviewError> {
+        self.sessions.create_index(
+            IndexModel::builder()
+                .keys(doc! { "token_hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+            None,
+        ).await?;
+        self.sessions.create_index(
+            IndexModel::builder().keys(doc! { "created_by": 1, "created_at": -1 }).build(),
+            None,
+        ).await?;
+        Ok(())
+    }
+
+    /// Open a preview session for some services
+    pub async fn create(
+        &self,
+        services: Vec<String>,
+        items: Vec<PreviewItem>,
+        ttl_minutes: Option<i64>,
+        author: String,
+    ) -> Result<IssuedPreview, PreviewError> {
+        if services.is_empty() || services.iter().any(|service| service.trim().is_empty()) {
+            return Err(PreviewError::InvalidRequest("Select at least one service".to_string()));
+        }
+        let ttl_minutes = ttl_minutes.unwrap_or(DEFAULT_PREVIEW_TTL_MINUTES);
+        if !(1..=MAX_PREVIEW_TTL_MINUTES).contains(&ttl_minutes) {
+            return Err(PreviewError::InvalidRequest(format!(
+                "ttl_minutes must be between 1 and {}",
+                MAX_PREVIEW_TTL_MINUTES
+            )));
+        }
+        for item in &items {
+            validate_content_id(&item.content_id)?;
+            let exists = match item.version {
+                Some(version) => self.store.version(item.content_type, &item.content_id, version).await.map(|_| ()),
+                None => self.store.get(item.content_type, &item.content_id).await.map(|_| ()),
+            };
+            exists?;
+        }
+
+        let token = new_token();
+        let now = Utc::now();
+        let session = PreviewSession {
+            id: Uuid::new_v4().to_string(),
+            token_hash: hash_token(&token),
+            services,
+            items,
+            created_by: author,
+            created_at: now,
+            expires_at: now + Duration::minutes(ttl_minutes),
+            revoked_at: None,
+        };
+        self.sessions.insert_one(&session, None).await?;
+        tracing::info!("Preview session {} opened by {} for {:?}", session.id, session.created_by, session.services);
+
+        Ok(IssuedPreview {
+            token,
+            session: session.into(),
+        })
+    }
+
+    /// Sessions an editor opened, newest first
+    pub async fn list(&self, author: &str) -> Result<Vec<PreviewSessionInfo>, PreviewError> {
+        let options = FindOptions::builder().sort(doc! { "created_at": -1 }).limit(100).build();
+        let mut cursor = self.sessions.find(doc! { "created_by": author }, options).await?;
+
+        let mut sessions = Vec::new();
+        while cursor.advance().await? {
+            sessions.push(PreviewSessionInfo::from(cursor.deserialize_current()?));
+        }
+        Ok(sessions)
+    }
+
+    /// End a session before it expires
+    pub async fn revoke(&self, session_id: &str, author: &str) -> Result<(), PreviewError> {
+        let result = self.sessions.update_one(
+            doc! { "id": session_id, "created_by": author },
+            doc! { "$set": { "revoked_at": Utc::now().to_rfc3339() } },
+            None,
+        ).await?;
+        if result.matched_count == 0 {
+            return Err(PreviewError::SessionNotFound(session_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Draft revision a service should load for a preview token
+    pub async fn resolve(
+        &self,
+        token: &str,
+        service: &str,
+        content_type: ContentType,
+        content_id: &str,
+    ) -> Result<PreviewContent, PreviewError> {
+        if !is_valid_preview_token(token) {
+            return Err(PreviewError::InvalidToken);
+        }
+        let session = self.sessions
+            .find_one(doc! { "token_hash": hash_token(token) }, None)
+            .await?
+            .filter(|session| session.is_active(Utc::now()))
+            .ok_or(PreviewError::InvalidToken)?;
+        if !session.services.iter().any(|selected| selected == service) {
+            return Err(PreviewError::ServiceNotSelected(service.to_string()));
+        }
+
+        let (version, data) = match session.covers(content_type, content_id) {
+            None => return Err(PreviewError::NotCovered(content_type, content_id.to_string())),
+            Some(Some(version)) => {
+                let version = self.store.version(content_type, content_id, version).await?;
+                (version.version, version.data)
+            }
+            Some(None) => {
+                let document = self.store.get(content_type, content_id).await?;
+                (document.version, document.data)
+            }
+        };
+        Ok(PreviewContent {
+            content_type,
+            content_id: content_id.to_string(),
+            version,
+            data,
+        })
+    }
+}
+
+fn new_token() -> String {
+    format!("{}{}{}", PREVIEW_TOKEN_PREFIX, Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(items: Vec<PreviewItem>) -> PreviewSession {
+        let now = Utc::now();
+        PreviewSession {
+            id: "session".to_string(),
+            token_hash: hash_token("pv_test"),
+            services: vec!["world-service".to_string()],
+            items,
+            created_by: "editor".to_string(),
+            created_at: now,
+            expires_at: now + Duration::minutes(5),
+            revoked_at: None,
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        let token = new_token();
+        assert!(is_valid_preview_token(&token));
+        assert_ne!(token, new_token());
+        assert_eq!(hash_token(&token), hash_token(&token));
+        assert_ne!(hash_token(&token), token);
+    }
+
+    #[test]
+    fn test_session_coverage() {
+        let all = session(Vec::new());
+        assert_eq!(all.covers(ContentType::Quest, "quest_001"), Some(None));
+
+        let selected = session(vec![PreviewItem {
+            content_type: ContentType::Quest,
+            content_id: "quest_001".to_string(),
+            version: Some(3),
+        }]);
+        assert_eq!(selected.covers(ContentType::Quest, "quest_001"), Some(Some(3)));
+        assert_eq!(selected.covers(ContentType::LootTable, "quest_001"), None);
+        assert_eq!(selected.covers(ContentType::Quest, "quest_002"), None);
+    }
+
+    #[test]
+    fn test_session_expiry_and_revocation() {
+        let mut session = session(Vec::new());
+        assert!(session.is_active(Utc::now()));
+        assert!(!session.is_active(session.expires_at));
+        session.revoked_at = Some(Utc::now());
+        assert!(!session.is_active(Utc::now()));
+    }
+}
@@ -0,0 +1,556 @@
+use bson::doc;
+use chrono::{DateTime, Utc};
+use mongodb::options::{FindOneOptions, FindOptions, ReplaceOptions};
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use shared::error::{ChaosError, ErrorCode, ToApiError};
+use shared::events::{EventBus, EventBusExt, InProcessEventBus, NatsEventBus, Topic};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::{PublishTargetConfig, PublishingConfig};
+use crate::content::{ContentError, ContentStore, ContentType};
+use crate::validation::{ContentValidator, SubmittedContent, ValidationReport};
+
+/// Tells game services which published content to reload
+pub const CONTENT_PUBLISHED: Topic<ContentPublished> = Topic::new("cms.content.published");
+
+/// Deployment environment content is published to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    Dev,
+    Staging,
+    Prod,
+}
+
+impl Environment {
+    pub const ALL: [Environment; 3] = [Environment::Dev, Environment::Staging, Environment::Prod];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Dev => "dev",
+            Environment::Staging => "staging",
+            Environment::Prod => "prod",
+        }
+    }
+}
+
+impl std::fmt::Display for Environment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Environment {
+    type Err = PublishError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dev" => Ok(Environment::Dev),
+            "staging" => Ok(Environment::Staging),
+            "prod" => Ok(Environment::Prod),
+            _ => Err(PublishError::UnknownEnvironment(s.to_string())),
+        }
+    }
+}
+
+/// Lifecycle of a release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReleaseState {
+    Staged,
+    Publishing,
+    Published,
+    Failed,
+}
+
+impl ReleaseState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReleaseState::Staged => "staged",
+            ReleaseState::Publishing => "publishing",
+            ReleaseState::Published => "published",
+            ReleaseState::Failed => "failed",
+        }
+    }
+}
+
+/// A content version in a release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseItem {
+    pub content_type: ContentType,
+    pub content_id: String,
+    /// `None` removes the content from the environment
+    pub version: Option<i64>,
+}
+
+/// A set of content versions published to an environment together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub id: String,
+    pub environment: Environment,
+    pub state: ReleaseState,
+    pub items: Vec<ReleaseItem>,
+    /// What the environment served before the release, filled in when publishing
+    #[serde(default)]
+    pub previous: Vec<ReleaseItem>,
+    /// Release this one rolls back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<String>,
+    pub notes: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Problems that did not stop the release, e.g. a failed invalidation event
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// Content as game services read it from the shared config collections
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedContent {
+    #[serde(rename = "_id")]
+    pub content_id: String,
+    pub version: i64,
+    pub data: Value,
+    pub release_id: String,
+    pub published_by: String,
+    pub published_at: DateTime<Utc>,
+}
+
+/// Invalidation event emitted after a release is published
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentPublished {
+    pub release_id: String,
+    pub environment: Environment,
+    pub items: Vec<ReleaseItem>,
+    pub published_by: String,
+    pub published_at: DateTime<Utc>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PublishError {
+    #[error("Unknown environment: {0}")]
+    UnknownEnvironment(String),
+    #[error("Environment {0} has no publish target configured")]
+    NotConfigured(Environment),
+    #[error("Release not found: {0}")]
+    ReleaseNotFound(String),
+    #[error("Release is {0}, expected {1}")]
+    InvalidState(&'static str, &'static str),
+    #[error("Only the latest published release of an environment can be rolled back")]
+    NotLatest,
+    #[error("A release must contain at least one item")]
+    Empty,
+    #[error("{0}/{1} failed validation")]
+    InvalidContent(ContentType, String, ValidationReport),
+    #[error(transparent)]
+    Content(#[from] ContentError),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+    #[error("Event bus error: {0}")]
+    EventBus(#[from] ChaosError),
+}
+
+impl ToApiError for PublishError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            PublishError::UnknownEnvironment(_) | PublishError::ReleaseNotFound(_) => ErrorCode::NotFound,
+            PublishError::NotConfigured(_) => ErrorCode::Configuration,
+            PublishError::InvalidState(..) | PublishError::NotLatest => ErrorCode::Conflict,
+            PublishError::Empty | PublishError::InvalidContent(..) => ErrorCode::ValidationFailed,
+            PublishError::Content(e) => e.error_code(),
+            PublishError::Database(_) => ErrorCode::Database,
+            PublishError::EventBus(_) => ErrorCode::ServiceUnavailable,
+        }
+    }
+
+    fn client_message(&self) -> String {
+        match self {
+            PublishError::Content(e) => e.client_message(),
+            PublishError::Database(_) | PublishError::EventBus(_) => {
+                self.error_code().default_message().to_string()
+            }
+            _ => self.to_string(),
+        }
+    }
+}
+
+/// Shared config collection game services load a content type from
+pub fn config_collection(content_type: ContentType) -> &'static str {
+    match content_type {
+        ContentType::Quest => "quests",
+        ContentType::ElementConfig => "element_configs",
+        ContentType::LootTable => "loot_tables",
+        ContentType::Dialogue => "dialogues",
+    }
+}
+
+/// Config database and event bus of one environment
+struct PublishTarget {
+    client: mongodb::Client,
+    database: Database,
+    bus: Arc<dyn EventBus>,
+}
+
+impl PublishTarget {
+    async fn connect(environment: Environment, config: &PublishTargetConfig) -> Result<Self, PublishError> {
+        let client = mongodb::Client::with_uri_str(&config.mongodb_uri).await?;
+        let bus: Arc<dyn EventBus> = match &config.nats_url {
+            Some(nats_url) => {
+                let mut bus = NatsEventBus::connect(nats_url).await?;
+                if let Some(prefix) = &config.subject_prefix {
+                    bus = bus.with_subject_prefix(prefix.clone())?;
+                }
+                Arc::new(bus)
+            }
+            None => {
+                tracing::warn!("No event bus for {}, published content is not announced to game services", environment);
+                Arc::new(InProcessEventBus::new())
+            }
+        };
+        Ok(Self {
+            database: client.database(&config.mongodb_database),
+            client,
+            bus,
+        })
+    }
+
+    fn collection(&self, content_type: ContentType) -> Collection<PublishedContent> {
+        self.database.collection(config_collection(content_type))
+    }
+}
+
+/// Stages and publishes releases of versioned content
+pub struct PublishService {
+    releases: Collection<Release>,
+    store: Arc<ContentStore>,
+    validator: Arc<ContentValidator>,
+    targets: HashMap<Environment, PublishTarget>,
+}
+
+impl PublishService {
+    pub async fn new(
+        database: &Database,
+        store: Arc<ContentStore>,
+        validator: Arc<ContentValidator>,
+        config: &PublishingConfig,
+    ) -> Result<Self, PublishError> {
+        let mut targets = HashMap::new();
+        for (environment, target_config) in &config.environments {
+            targets.insert(*environment, PublishTarget::connect(*environment, target_config).await?);
+            tracing::info!("📦 Publish target {} -> {}", environment, target_config.mongodb_database);
+        }
+
+        Ok(Self {
+            releases: database.collection("releases"),
+            store,
+            validator,
+            targets,
+        })
+    }
+
+    /// Flush the environments' event buses and wait for their database writes, e.g. on shutdown
+    pub async fn flush(&self) {
+        for (environment, target) in &self.targets {
+            if let Err(e) = target.bus.flush().await {
+                tracing::warn!("Failed to flush the event bus of {}: {}", environment, e);
+            }
+            target.client.clone().shutdown().await;
+        }
+    }
+
+    /// Create the indexes the release history relies on
+    pub async fn ensure_indexes(&self) -> Result<(), PublishError> {
+        self.releases.create_index(
+            mongodb::IndexModel::builder()
+                .keys(doc! { "id": 1 })
+                .options(mongodb::options::IndexOptions::builder().unique(true).build())
+                .build(),
+            None,
+        ).await?;
+        self.releases.create_index(
+            mongodb::IndexModel::builder()
+                .keys(doc! { "environment": 1, "state": 1, "published_at": -1 })
+                .build(),
+            None,
+        ).await?;
+        Ok(())
+    }
+
+    /// Stage a release, items without a version take the current version
+    ///
+    /// Every item is validated now, so problems show up before anyone publishes.
+    pub async fn stage(
+        &self,
+        environment: Environment,
+        items: Vec<ReleaseItem>,
+        notes: String,
+        author: String,
+    ) -> Result<Release, PublishError> {
+        self.target(environment)?;
+        if items.is_empty() {
+            return Err(PublishError::Empty);
+        }
+
+        let mut staged = Vec::with_capacity(items.len());
+        for item in items {
+            let version = match item.version {
+                Some(version) => version,
+                None => self.store.get(item.content_type, &item.content_id).await?.version,
+            };
+            self.validate(item.content_type, &item.content_id, version).await?;
+            staged.push(ReleaseItem { version: Some(version), ..item });
+        }
+
+        let release = new_release(environment, staged, notes, author, None);
+        self.releases.insert_one(&release, None).await?;
+        tracing::info!("Staged release {} for {} with {} item(s)", release.id, environment, release.items.len());
+        Ok(release)
+    }
+
+    /// Write a staged release to its environment and announce it
+    pub async fn publish(&self, release_id: &str, author: String) -> Result<Release, PublishError> {
+        // Claim the release, a concurrent publish of the same release fails here
+        let claimed = self.releases.update_one(
+            doc! { "id": release_id, "state": ReleaseState::Staged.as_str() },
+            doc! { "$set": { "state": ReleaseState::Publishing.as_str() } },
+            None,
+        ).await?;
+        if claimed.matched_count == 0 {
+            let release = self.release(release_id).await?;
+            return Err(PublishError::InvalidState(release.state.as_str(), ReleaseState::Staged.as_str()));
+        }
+
+        let mut release = self.release(release_id).await?;
+        match self.write_release(&release, &author).await {
+            Ok(previous) => {
+                release.previous = previous;
+                release.state = ReleaseState::Published;
+            }
+            Err(e) => {
+                tracing::error!("Failed to publish release {}: {}", release.id, e);
+                release.state = ReleaseState::Failed;
+                release.error = Some(e.to_string());
+                self.save(&release).await?;
+                return Err(e);
+            }
+        }
+
+        let now = Utc::now();
+        release.published_by = Some(author.clone());
+        release.published_at = Some(now);
+
+        let event = ContentPublished {
+            release_id: release.id.clone(),
+            environment: release.environment,
+            items: release.items.clone(),
+            published_by: author,
+            published_at: now,
+        };
+        if let Err(e) = self.target(release.environment)?.bus.publish_from("content-management-service", &CONTENT_PUBLISHED, &event).await {
+            // The content is live, services pick it up on their next reload
+            tracing::error!("Failed to announce release {}: {}", release.id, e);
+            release.warnings.push(format!("Invalidation event not sent: {}", e));
+        }
+
+        self.save(&release).await?;
+        tracing::info!("Published release {} to {}", release.id, release.environment);
+        Ok(release)
+    }
+
+    /// Publish a release restoring what the environment served before `release_id`
+    pub async fn rollback(&self, release_id: &str, author: String) -> Result<Release, PublishError> {
+        let release = self.release(release_id).await?;
+        if release.state != ReleaseState::Published {
+            return Err(PublishError::InvalidState(release.state.as_str(), ReleaseState::Published.as_str()));
+        }
+        let latest = self.latest_published(release.environment).await?;
+        if latest.map(|latest| latest.id) != Some(release.id.clone()) {
+            return Err(PublishError::NotLatest);
+        }
+
+        let rollback = new_release(
+            release.environment,
+            release.previous.clone(),
+            format!("Rollback of release {}", release.id),
+            author.clone(),
+            Some(release.id.clone()),
+        );
+        self.releases.insert_one(&rollback, None).await?;
+        self.publish(&rollback.id, author).await
+    }
+
+    /// Release history of an environment, newest first
+    pub async fn history(&self, environment: Option<Environment>, limit: i64) -> Result<Vec<Release>, PublishError> {
+        let filter = match environment {
+            Some(environment) => doc! { "environment": environment.as_str() },
+            None => doc! {},
+        };
+        let options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .limit(limit)
+            .build();
+        let mut cursor = self.releases.find(filter, options).await?;
+
+        let mut releases = Vec::new();
+        while cursor.advance().await? {
+            releases.push(cursor.deserialize_current()?);
+        }
+        Ok(releases)
+    }
+
+    pub async fn release(&self, release_id: &str) -> Result<Release, PublishError> {
+        self.releases
+            .find_one(doc! { "id": release_id }, None)
+            .await?
+            .ok_or_else(|| PublishError::ReleaseNotFound(release_id.to_string()))
+    }
+
+    async fn latest_published(&self, environment: Environment) -> Result<Option<Release>, PublishError> {
+        let options = FindOneOptions::builder().sort(doc! { "published_at": -1 }).build();
+        Ok(self.releases
+            .find_one(doc! { "environment": environment.as_str(), "state": ReleaseState::Published.as_str() }, options)
+            .await?)
+    }
+
+    /// Write every item to the config collections, returning what they replaced
+    async fn write_release(&self, release: &Release, author: &str) -> Result<Vec<ReleaseItem>, PublishError> {
+        let target = self.target(release.environment)?;
+        let now = Utc::now();
+
+        // Check everything before the first write, a release goes out whole or not at all
+        let mut versions = Vec::with_capacity(release.items.len());
+        for item in &release.items {
+            let version = match item.version {
+                Some(version) => {
+                    let version = self.store.version(item.content_type, &item.content_id, version).await?;
+                    self.check(item.content_type, &item.content_id, &version.data)?;
+                    Some(version)
+                }
+                None => None,
+            };
+            versions.push(version);
+        }
+
+        let mut previous = Vec::with_capacity(release.items.len());
+        for (item, version) in release.items.iter().zip(versions) {
+            let collection = target.collection(item.content_type);
+            let current = collection.find_one(doc! { "_id": &item.content_id }, None).await?;
+            previous.push(ReleaseItem {
+                content_type: item.content_type,
+                content_id: item.content_id.clone(),
+                version: current.map(|current| current.version),
+            });
+
+            match version {
+                Some(version) => {
+                    let published = PublishedContent {
+                        content_id: item.content_id.clone(),
+                        version: version.version,
+                        data: version.data,
+                        release_id: release.id.clone(),
+                        published_by: author.to_string(),
+                        published_at: now,
+                    };
+                    let options = ReplaceOptions::builder().upsert(true).build();
+                    collection.replace_one(doc! { "_id": &item.content_id }, &published, options).await?;
+                }
+                None => {
+                    collection.delete_one(doc! { "_id": &item.content_id }, None).await?;
+                }
+            }
+        }
+        Ok(previous)
+    }
+
+    async fn validate(&self, content_type: ContentType, content_id: &str, version: i64) -> Result<(), PublishError> {
+        let version = self.store.version(content_type, content_id, version).await?;
+        self.check(content_type, content_id, &version.data)
+    }
+
+    fn check(&self, content_type: ContentType, content_id: &str, data: &Value) -> Result<(), PublishError> {
+        let report = self.validator.validate(content_type, &SubmittedContent::from_json(data.clone()));
+        if report.valid {
+            Ok(())
+        } else {
+            Err(PublishError::InvalidContent(content_type, content_id.to_string(), report))
+        }
+    }
+
+    async fn save(&self, release: &Release) -> Result<(), PublishError> {
+        self.releases.replace_one(doc! { "id": &release.id }, release, None).await?;
+        Ok(())
+    }
+
+    fn target(&self, environment: Environment) -> Result<&PublishTarget, PublishError> {
+        self.targets.get(&environment).ok_or(PublishError::NotConfigured(environment))
+    }
+}
+
+fn new_release(
+    environment: Environment,
+    items: Vec<ReleaseItem>,
+    notes: String,
+    author: String,
+    rollback_of: Option<String>,
+) -> Release {
+    Release {
+        id: Uuid::new_v4().to_string(),
+        environment,
+        state: ReleaseState::Staged,
+        items,
+        previous: Vec::new(),
+        rollback_of,
+        notes,
+        created_by: author,
+        created_at: Utc::now(),
+        published_by: None,
+        published_at: None,
+        error: None,
+        warnings: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_environment_round_trip() {
+        for environment in Environment::ALL {
+            assert_eq!(environment.as_str().parse::<Environment>().unwrap(), environment);
+            assert_eq!(serde_json::to_value(environment).unwrap(), serde_json::json!(environment.as_str()));
+        }
+        assert!("qa".parse::<Environment>().is_err());
+    }
+
+    #[test]
+    fn test_publishing_config_from_yaml() {
+        let config: PublishingConfig = serde_yaml::from_str(
+            "environments:\n  prod:\n    mongodb_uri: \"mongodb://db:27017\"\n    mongodb_database: \"chaos_config\"\n    nats_url: \"nats://nats:4222\"\n    subject_prefix: \"chaos.prod\"\n",
+        ).unwrap();
+        let prod = &config.environments[&Environment::Prod];
+        assert_eq!(prod.mongodb_database, "chaos_config");
+        assert_eq!(prod.subject_prefix.as_deref(), Some("chaos.prod"));
+        assert!(!config.environments.contains_key(&Environment::Dev));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(PublishError::NotConfigured(Environment::Prod).error_code(), ErrorCode::Configuration);
+        assert_eq!(PublishError::NotLatest.error_code(), ErrorCode::Conflict);
+        assert_eq!(
+            PublishError::Content(ContentError::NotFound(ContentType::Quest, "q".into())).error_code(),
+            ErrorCode::NotFound
+        );
+    }
+}
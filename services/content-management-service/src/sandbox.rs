@@ -0,0 +1,440 @@
+use condition_core::{
+    ActorDataProvider, ActorTarget, ConditionChainConfig, ConditionContext, ConditionError, ConditionResolver,
+    ConditionResolverTrait, ConditionResult, DataProviderRegistry, ResourceDataProvider, WeatherType, WorldState,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::SystemTime;
+
+/// Most loot table runs a single simulation may do
+pub const MAX_LOOT_RUNS: u32 = 100_000;
+
+/// Actor the conditions of a draft are evaluated against
+///
+/// Values the conditions ask for but the fixture leaves out make the condition fail
+/// with an error instead of silently evaluating to a default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SandboxActor {
+    pub id: Option<String>,
+    pub race: Option<String>,
+    pub in_combat: bool,
+    pub stats: HashMap<String, f64>,
+    pub derived_stats: HashMap<String, f64>,
+    pub resources: HashMap<String, SandboxResource>,
+    /// Active status effect counts by type, `type:category` for per-category counts
+    pub status_effects: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxResource {
+    pub current: f64,
+    pub max: f64,
+}
+
+/// World the conditions of a draft are evaluated in
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SandboxWorld {
+    pub world_id: String,
+    pub weather: WeatherType,
+    pub time_of_day: f64,
+    pub season: String,
+    pub temperature: f64,
+    pub humidity: f64,
+}
+
+impl Default for SandboxWorld {
+    fn default() -> Self {
+        Self {
+            world_id: "sandbox".to_string(),
+            weather: WeatherType::Clear,
+            time_of_day: 12.0,
+            season: "spring".to_string(),
+            temperature: 20.0,
+            humidity: 0.5,
+        }
+    }
+}
+
+/// Outcome of one condition in a dry-run
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionOutcome {
+    pub condition_id: String,
+    pub function_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Outcome of one condition chain in a dry-run
+#[derive(Debug, Clone, Serialize)]
+pub struct ChainOutcome {
+    pub chain_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub conditions: Vec<ConditionOutcome>,
+}
+
+/// Evaluate the condition chains of a quest draft with condition-core
+///
+/// Nothing is read from or written to game services, actor and world data come from
+/// the fixtures.
+pub async fn dry_run_conditions(
+    data: &Value,
+    actor: &SandboxActor,
+    world: &SandboxWorld,
+) -> Result<Vec<ChainOutcome>, String> {
+    let chains = match data.get("conditions") {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(chains)) => chains,
+        Some(_) => return Err("Conditions must be a list of condition chains".to_string()),
+    };
+
+    let mut providers = DataProviderRegistry::new();
+    providers.register_actor_provider(Box::new(actor.clone()));
+    providers.register_resource_provider(Box::new(actor.clone()));
+    let resolver = ConditionResolver::new(providers);
+    let context = ConditionContext {
+        target: ActorTarget { id: actor.id.clone().unwrap_or_else(|| "sandbox_actor".to_string()) },
+        world_id: world.world_id.clone(),
+        current_time: SystemTime::now(),
+        current_weather: world.weather.clone(),
+        world_state: WorldState {
+            time_of_day: world.time_of_day,
+            season: world.season.clone(),
+            temperature: world.temperature,
+            humidity: world.humidity,
+        },
+    };
+
+    let mut outcomes = Vec::with_capacity(chains.len());
+    for (index, chain) in chains.iter().enumerate() {
+        let chain: ConditionChainConfig = serde_json::from_value(chain.clone())
+            .map_err(|e| format!("/conditions/{}: {}", index, e))?;
+
+        let mut conditions = Vec::with_capacity(chain.conditions.len());
+        for condition in &chain.conditions {
+            let result = resolver.resolve_condition(condition, &context).await;
+            conditions.push(ConditionOutcome {
+                condition_id: condition.condition_id.clone(),
+                function_name: condition.function_name.clone(),
+                passed: result.as_ref().ok().copied(),
+                error: result.err().map(|e| e.to_string()),
+            });
+        }
+
+        let result = resolver.resolve_condition_chain(&chain, &context).await;
+        outcomes.push(ChainOutcome {
+            chain_id: chain.chain_id,
+            passed: result.as_ref().ok().copied(),
+            error: result.err().map(|e| e.to_string()),
+            conditions,
+        });
+    }
+    Ok(outcomes)
+}
+
+fn missing(what: &str, name: &str) -> ConditionError {
+    ConditionError::DataProviderError {
+        provider_name: "sandbox".to_string(),
+        message: format!("{} not set in the sandbox actor: {}", what, name),
+    }
+}
+
+impl SandboxActor {
+    fn resource(&self, resource_type: &str) -> ConditionResult<&SandboxResource> {
+        self.resources.get(resource_type).ok_or_else(|| missing("Resource", resource_type))
+    }
+
+    fn resource_percentage(&self, resource_type: &str) -> ConditionResult<f64> {
+        let resource = self.resource(resource_type)?;
+        if resource.max <= 0.0 {
+            return Ok(0.0);
+        }
+        Ok(resource.current / resource.max * 100.0)
+    }
+}
+
+#[async_trait::async_trait]
+impl ActorDataProvider for SandboxActor {
+    async fn get_actor_resource(&self, resource_type: &str, _actor_id: &str) -> ConditionResult<f64> {
+        Ok(self.resource(resource_type)?.current)
+    }
+
+    async fn get_actor_stat(&self, stat_name: &str, _actor_id: &str) -> ConditionResult<f64> {
+        self.stats.get(stat_name).copied().ok_or_else(|| missing("Stat", stat_name))
+    }
+
+    async fn get_actor_derived_stat(&self, stat_name: &str, _actor_id: &str) -> ConditionResult<f64> {
+        self.derived_stats.get(stat_name).copied().ok_or_else(|| missing("Derived stat", stat_name))
+    }
+
+    async fn get_actor_race(&self, _actor_id: &str) -> ConditionResult<String> {
+        self.race.clone().ok_or_else(|| missing("Race", "race"))
+    }
+
+    async fn is_actor_in_combat(&self, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.in_combat)
+    }
+
+    async fn has_actor_status_effects(&self, status_type: &str, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.status_effects.get(status_type).copied().unwrap_or(0) > 0)
+    }
+
+    async fn get_actor_status_effect_count(&self, status_type: &str, _actor_id: &str) -> ConditionResult<i64> {
+        Ok(self.status_effects.get(status_type).copied().unwrap_or(0))
+    }
+
+    async fn get_actor_status_effect_count_by_category(&self, status_type: &str, category: &str, _actor_id: &str) -> ConditionResult<i64> {
+        Ok(self.status_effects.get(&format!("{}:{}", status_type, category)).copied().unwrap_or(0))
+    }
+}
+
+#[async_trait::async_trait]
+impl ResourceDataProvider for SandboxActor {
+    async fn get_resource_value(&self, resource_type: &str, _actor_id: &str) -> ConditionResult<f64> {
+        Ok(self.resource(resource_type)?.current)
+    }
+
+    async fn get_resource_max(&self, resource_type: &str, _actor_id: &str) -> ConditionResult<f64> {
+        Ok(self.resource(resource_type)?.max)
+    }
+
+    async fn get_resource_percentage(&self, resource_type: &str, _actor_id: &str) -> ConditionResult<f64> {
+        self.resource_percentage(resource_type)
+    }
+
+    async fn is_resource_empty(&self, resource_type: &str, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.resource(resource_type)?.current <= 0.0)
+    }
+
+    async fn is_resource_below_threshold(&self, resource_type: &str, threshold: f64, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.resource(resource_type)?.current < threshold)
+    }
+
+    async fn is_resource_above_threshold(&self, resource_type: &str, threshold: f64, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.resource(resource_type)?.current > threshold)
+    }
+
+    async fn is_resource_below_percentage(&self, resource_type: &str, percentage: f64, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.resource_percentage(resource_type)? < percentage)
+    }
+
+    async fn is_resource_above_percentage(&self, resource_type: &str, percentage: f64, _actor_id: &str) -> ConditionResult<bool> {
+        Ok(self.resource_percentage(resource_type)? > percentage)
+    }
+
+    async fn list_resources(&self) -> ConditionResult<Vec<String>> {
+        Ok(self.resources.keys().cloned().collect())
+    }
+}
+
+/// Loot table layout the simulation understands
+///
+/// Each roll picks one entry of `drops` by weight, or nothing with `empty_weight`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootTable {
+    #[serde(default = "default_rolls")]
+    pub rolls: u32,
+    #[serde(default)]
+    pub empty_weight: f64,
+    pub drops: Vec<LootDrop>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LootDrop {
+    pub item_id: String,
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+    #[serde(default = "default_quantity")]
+    pub min_quantity: u32,
+    #[serde(default = "default_quantity")]
+    pub max_quantity: u32,
+}
+
+fn default_rolls() -> u32 {
+    1
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_quantity() -> u32 {
+    1
+}
+
+/// Drop statistics of one item over a simulation
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ItemDropStats {
+    /// Runs in which the item dropped at least once
+    pub runs_with_drop: u32,
+    pub drop_rate: f64,
+    pub total_quantity: u64,
+    pub average_quantity_per_run: f64,
+    /// Share of the rolls expected from the table weights
+    pub expected_roll_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LootSimulation {
+    pub runs: u32,
+    /// Seed of the run, send it back to reproduce the result
+    pub seed: u64,
+    pub empty_runs: u32,
+    pub items: BTreeMap<String, ItemDropStats>,
+}
+
+/// Roll a loot table draft `runs` times
+pub fn simulate_loot(data: &Value, runs: u32, seed: u64) -> Result<LootSimulation, String> {
+    if runs == 0 || runs > MAX_LOOT_RUNS {
+        return Err(format!("Runs must be between 1 and {}", MAX_LOOT_RUNS));
+    }
+    let table: LootTable = serde_json::from_value(data.clone()).map_err(|e| format!("Not a loot table: {}", e))?;
+    if table.empty_weight < 0.0 {
+        return Err("empty_weight must not be negative".to_string());
+    }
+    for (index, drop) in table.drops.iter().enumerate() {
+        if drop.weight < 0.0 {
+            return Err(format!("/drops/{}/weight must not be negative", index));
+        }
+        if drop.min_quantity > drop.max_quantity {
+            return Err(format!("/drops/{}: min_quantity is greater than max_quantity", index));
+        }
+    }
+    let total_weight = table.empty_weight + table.drops.iter().map(|drop| drop.weight).sum::<f64>();
+    if total_weight <= 0.0 {
+        return Err("The table has no weight to roll on".to_string());
+    }
+
+    let mut items: BTreeMap<String, ItemDropStats> = table
+        .drops
+        .iter()
+        .map(|drop| (drop.item_id.clone(), ItemDropStats::default()))
+        .collect();
+    for drop in &table.drops {
+        items.get_mut(&drop.item_id).unwrap().expected_roll_share += drop.weight / total_weight;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut empty_runs = 0;
+    for _ in 0..runs {
+        let mut dropped: Vec<&str> = Vec::new();
+        for _ in 0..table.rolls {
+            let mut pick = rng.gen_range(0.0..total_weight);
+            for drop in &table.drops {
+                if pick < drop.weight {
+                    let quantity = rng.gen_range(drop.min_quantity..=drop.max_quantity);
+                    items.get_mut(&drop.item_id).unwrap().total_quantity += u64::from(quantity);
+                    dropped.push(&drop.item_id);
+                    break;
+                }
+                pick -= drop.weight;
+            }
+        }
+        if dropped.is_empty() {
+            empty_runs += 1;
+        }
+        dropped.sort_unstable();
+        dropped.dedup();
+        for item_id in dropped {
+            items.get_mut(item_id).unwrap().runs_with_drop += 1;
+        }
+    }
+
+    for stats in items.values_mut() {
+        stats.drop_rate = f64::from(stats.runs_with_drop) / f64::from(runs);
+        stats.average_quantity_per_run = stats.total_quantity as f64 / f64::from(runs);
+    }
+    Ok(LootSimulation { runs, seed, empty_runs, items })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chain(function_name: &str, operator: &str, value: Value, parameters: Value) -> Value {
+        json!({
+            "chain_id": function_name,
+            "logic": "And",
+            "conditions": [{
+                "condition_id": function_name,
+                "function_name": function_name,
+                "operator": operator,
+                "value": value,
+                "parameters": parameters
+            }]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_against_fixture() {
+        let quest = json!({
+            "conditions": [
+                chain("is_actor_in_combat", "Equal", json!({ "Boolean": false }), json!([])),
+                chain("get_actor_stat", "GreaterThanOrEqual", json!({ "Float": 10.0 }), json!([{ "String": "strength" }])),
+                chain("get_actor_stat", "GreaterThanOrEqual", json!({ "Float": 10.0 }), json!([{ "String": "agility" }]))
+            ]
+        });
+        let actor = SandboxActor {
+            stats: HashMap::from([("strength".to_string(), 12.0)]),
+            ..Default::default()
+        };
+
+        let outcomes = dry_run_conditions(&quest, &actor, &SandboxWorld::default()).await.unwrap();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0].passed, Some(true));
+        assert_eq!(outcomes[1].passed, Some(true), "{:?}", outcomes[1].error);
+        // agility is not in the fixture
+        assert_eq!(outcomes[2].passed, None);
+        assert!(outcomes[2].conditions[0].error.as_deref().unwrap().contains("agility"));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_without_conditions() {
+        let (actor, world) = (SandboxActor::default(), SandboxWorld::default());
+        assert!(dry_run_conditions(&json!({ "title": "Draft" }), &actor, &world).await.unwrap().is_empty());
+        assert!(dry_run_conditions(&json!({ "conditions": 1 }), &actor, &world).await.is_err());
+    }
+
+    #[test]
+    fn test_loot_simulation() {
+        let table = json!({
+            "rolls": 2,
+            "empty_weight": 2,
+            "drops": [
+                { "item_id": "gold_coin", "weight": 6, "min_quantity": 1, "max_quantity": 5 },
+                { "item_id": "goblin_ear", "weight": 2 }
+            ]
+        });
+
+        let simulation = simulate_loot(&table, 10_000, 7).unwrap();
+        let gold = &simulation.items["gold_coin"];
+        assert!((gold.expected_roll_share - 0.6).abs() < 1e-9);
+        // 1 - 0.4^2 of the runs drop gold
+        assert!((gold.drop_rate - 0.84).abs() < 0.02, "{}", gold.drop_rate);
+        // Two rolls of 0.6 * 3 coins on average
+        assert!((gold.average_quantity_per_run - 3.6).abs() < 0.1, "{}", gold.average_quantity_per_run);
+        assert!((f64::from(simulation.empty_runs) / 10_000.0 - 0.04).abs() < 0.01);
+
+        // Same seed, same result
+        let again = simulate_loot(&table, 10_000, 7).unwrap();
+        assert_eq!(again.items["goblin_ear"].total_quantity, simulation.items["goblin_ear"].total_quantity);
+    }
+
+    #[test]
+    fn test_invalid_loot_tables() {
+        assert!(simulate_loot(&json!({ "drops": [] }), 10, 1).is_err());
+        assert!(simulate_loot(&json!({ "items": [] }), 10, 1).is_err());
+        assert!(simulate_loot(&json!({ "drops": [{ "item_id": "a", "min_quantity": 3, "max_quantity": 1 }] }), 10, 1).is_err());
+        assert!(simulate_loot(&json!({ "drops": [{ "item_id": "a" }] }), MAX_LOOT_RUNS + 1, 1).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use condition_core::{
+    create_function_registry_with_providers, validate_condition_chain_config, ConditionChainConfig,
+    DataProviderRegistry, FunctionRegistry,
+};
+use element_core::ElementConfigLoader;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::content::ContentType;
+
+/// A problem found in submitted content
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    /// Loader that reported the problem, e.g. `element-core`
+    pub source: &'static str,
+    /// JSON pointer of the offending part of the document, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Position in the YAML the loader parsed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+/// Outcome of validating a content document
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    pub content_type: ContentType,
+    pub valid: bool,
+    pub issues: Vec<ValidationIssue>,
+    /// Checks that could not run, the content is accepted without them
+    pub warnings: Vec<String>,
+}
+
+impl ValidationReport {
+    /// Report of content rejected before any loader ran, e.g. unparseable YAML
+    pub fn from_issue(content_type: ContentType, issue: ValidationIssue) -> Self {
+        Self::new(content_type, vec![issue], Vec::new())
+    }
+
+    /// Add issues found by checks outside the loaders, e.g. localization references
+    pub fn with_issues(mut self, issues: Vec<ValidationIssue>) -> Self {
+        self.issues.extend(issues);
+        self.valid = self.issues.is_empty();
+        self
+    }
+
+    fn new(content_type: ContentType, issues: Vec<ValidationIssue>, warnings: Vec<String>) -> Self {
+        Self {
+            content_type,
+            valid: issues.is_empty(),
+            issues,
+            warnings,
+        }
+    }
+}
+
+/// Content submitted by an editor, with the data it parses to
+#[derive(Debug, Clone)]
+pub struct SubmittedContent {
+    pub data: Value,
+    /// Text handed to YAML loaders, as submitted when the editor sent YAML
+    pub yaml: String,
+}
+
+impl SubmittedContent {
+    /// Content submitted as YAML, which game services load as is
+    pub fn from_yaml(yaml: String) -> Result<Self, ValidationIssue> {
+        match serde_yaml::from_str::<Value>(&yaml) {
+            Ok(data) => Ok(Self { data, yaml }),
+            Err(e) => Err(yaml_issue("yaml", &e)),
+        }
+    }
+
+    /// Content submitted as JSON, rendered to YAML for the loaders
+    pub fn from_json(data: Value) -> Self {
+        let yaml = serde_yaml::to_string(&data).unwrap_or_default();
+        Self { data, yaml }
+    }
+}
+
+/// Validates content with the same loaders the game services use
+pub struct ContentValidator {
+    condition_functions: FunctionRegistry,
+}
+
+impl ContentValidator {
+    pub fn new() -> Self {
+        Self {
+            condition_functions: create_function_registry_with_providers(&DataProviderRegistry::new()),
+        }
+    }
+
+    /// Validate a document, nothing is stored
+    pub fn validate(&self, content_type: ContentType, content: &SubmittedContent) -> ValidationReport {
+        let mut issues = Vec::new();
+        let mut warnings = Vec::new();
+        match content_type {
+            ContentType::ElementConfig => self.validate_element_config(content, &mut issues),
+            ContentType::Quest => self.validate_quest(&content.data, &mut issues),
+            ContentType::LootTable => {
+                require_object(&content.data, &mut issues);
+                warnings.push("item-core provides no loot table loader yet, only the document structure was checked".to_string());
+            }
+            ContentType::Dialogue => {
+                require_object(&content.data, &mut issues);
+            }
+        }
+        ValidationReport::new(content_type, issues, warnings)
+    }
+
+    /// Element configs are parsed and checked by element-core's config loader
+    fn validate_element_config(&self, content: &SubmittedContent, issues: &mut Vec<ValidationIssue>) {
+        match ElementConfigLoader::parse_element_config(&content.yaml) {
+            Ok(config) => {
+                if let Err(message) = ElementConfigLoader::default().validate_config(&config) {
+                    issues.push(issue("element-core", None, message));
+                }
+            }
+            Err(e) => issues.push(yaml_issue("element-core", &e)),
+        }
+    }
+
+    /// Quest conditions are condition-core chains, checked against the registered condition functions
+    fn validate_quest(&self, data: &Value, issues: &mut Vec<ValidationIssue>) {
+        if !require_object(data, issues) {
+            return;
+        }
+        let conditions = match data.get("conditions") {
+            None | Some(Value::Null) => return,
+            Some(Value::Array(conditions)) => conditions,
+            Some(_) => {
+                issues.push(issue("cms", Some("/conditions".to_string()), "Conditions must be a list of condition chains".to_string()));
+                return;
+            }
+        };
+
+        for (index, chain) in conditions.iter().enumerate() {
+            let path = format!("/conditions/{}", index);
+            // Read straight from the JSON tree: YAML would need tags for enum values like `{ "Boolean": false }`
+            let chain = match serde_json::from_value::<ConditionChainConfig>(chain.clone()) {
+                Ok(chain) => chain,
+                Err(e) => {
+                    issues.push(issue("condition-core", Some(path), e.to_string()));
+                    continue;
+                }
+            };
+            if let Err(e) = validate_condition_chain_config(&chain) {
+                issues.push(issue("condition-core", Some(path.clone()), e.to_string()));
+            }
+            for (condition_index, condition) in chain.conditions.iter().enumerate() {
+                if self.condition_functions.get(&condition.function_name).is_none() {
+                    issues.push(issue(
+                        "condition-core",
+                        Some(format!("{}/conditions/{}/function_name", path, condition_index)),
+                        format!("Function not found: {}", condition.function_name),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+impl Default for ContentValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn require_object(data: &Value, issues: &mut Vec<ValidationIssue>) -> bool {
+    if data.is_object() {
+        return true;
+    }
+    issues.push(issue("cms", Some("/".to_string()), "Content must be a mapping".to_string()));
+    false
+}
+
+fn issue(source: &'static str, path: Option<String>, message: String) -> ValidationIssue {
+    ValidationIssue {
+        source,
+        path,
+        line: None,
+        column: None,
+        message,
+    }
+}
+
+fn yaml_issue(source: &'static str, error: &serde_yaml::Error) -> ValidationIssue {
+    let location = error.location();
+    ValidationIssue {
+        source,
+        path: None,
+        line: location.as_ref().map(|location| location.line()),
+        column: location.as_ref().map(|location| location.column()),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn validate(content_type: ContentType, data: Value) -> ValidationReport {
+        ContentValidator::new().validate(content_type, &SubmittedContent::from_json(data))
+    }
+
+    #[test]
+    fn test_invalid_yaml_reports_position() {
+        let issue = SubmittedContent::from_yaml("title: [unclosed".to_string()).unwrap_err();
+        assert_eq!(issue.source, "yaml");
+        assert!(issue.line.is_some());
+    }
+
+    #[test]
+    fn test_element_config_uses_element_core_loader() {
+        let report = validate(ContentType::ElementConfig, json!({ "version": 1, "element": { "id": "fire" } }));
+        assert!(!report.valid);
+        assert_eq!(report.issues[0].source, "element-core");
+    }
+
+    #[test]
+    fn test_quest_conditions() {
+        let valid = validate(ContentType::Quest, json!({
+            "title": "The Lost Artifact",
+            "conditions": [{
+                "chain_id": "can_start",
+                "logic": "And",
+                "conditions": [{
+                    "condition_id": "in_combat",
+                    "function_name": "is_actor_in_combat",
+                    "operator": "Equal",
+                    "value": { "Boolean": false },
+                    "parameters": []
+                }]
+            }]
+        }));
+        assert!(valid.valid, "{:?}", valid.issues);
+
+        let unknown_function = validate(ContentType::Quest, json!({
+            "conditions": [{
+                "chain_id": "can_start",
+                "logic": "And",
+                "conditions": [{
+                    "condition_id": "level",
+                    "function_name": "get_player_level",
+                    "operator": "Equal",
+                    "value": { "Integer": 10 },
+                    "parameters": []
+                }]
+            }]
+        }));
+        assert_eq!(unknown_function.issues.len(), 1);
+        assert_eq!(unknown_function.issues[0].path.as_deref(), Some("/conditions/0/conditions/0/function_name"));
+
+        let empty_chain = validate(ContentType::Quest, json!({
+            "conditions": [{ "chain_id": "can_start", "logic": "And", "conditions": [] }]
+        }));
+        assert!(empty_chain.issues[0].message.contains("at least one condition"));
+    }
+
+    #[test]
+    fn test_loot_tables_are_checked_structurally() {
+        let report = validate(ContentType::LootTable, json!({ "drops": [] }));
+        assert!(report.valid);
+        assert_eq!(report.warnings.len(), 1);
+        assert!(!validate(ContentType::LootTable, json!([1, 2])).valid);
+    }
+}
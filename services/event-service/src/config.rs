@@ -0,0 +1,100 @@
+use event_core::TutorialDefinition;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    /// Starter zone tutorials, checked when the service starts
+    #[serde(default)]
+    pub tutorials: Vec<TutorialDefinition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// How scheduled world events are run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Time between checks of every schedule
+    pub tick_ms: u64,
+    /// How long a schedule stays with an instance that stopped renewing it
+    pub lease_secs: u64,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self { tick_ms: 1000, lease_secs: 15 }
+    }
+}
+
+impl SchedulerConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.tick_ms == 0 {
+            return Err(ConfigError::InvalidConfig("scheduler.tick_ms must be at least 1".to_string()));
+        }
+        // The lease has to outlive a few ticks, or it expires between renewals
+        if self.lease_secs * 1000 < self.tick_ms * 3 {
+            return Err(ConfigError::InvalidConfig(
+                "scheduler.lease_secs must cover at least three ticks".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/event-service.yaml".to_string());
+
+        if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            let config: Config = serde_yaml::from_str(&content)?;
+            config.scheduler.validate()?;
+            return Ok(config);
+        }
+
+        tracing::warn!("Config file not found at {}, using environment variables", config_path);
+        let server = ServerConfig {
+            port: env::var("EVENT_SERVICE_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("EVENT_SERVICE_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_events".to_string()),
+        };
+
+        Ok(Config { server, database, scheduler: SchedulerConfig::default(), tutorials: Vec::new() })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
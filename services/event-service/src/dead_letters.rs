@@ -0,0 +1,71 @@
+//! Recent dead letters, kept for inspection.
+
+use shared::events::DeadLetter;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Dead letters kept when no limit is given
+pub const DEFAULT_CAPACITY: usize = 500;
+
+/// Most recent dead letters, oldest dropped first
+#[derive(Debug)]
+pub struct DeadLetters {
+    capacity: usize,
+    letters: Mutex<VecDeque<DeadLetter>>,
+}
+
+impl Default for DeadLetters {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl DeadLetters {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), letters: Mutex::new(VecDeque::new()) }
+    }
+
+    pub fn push(&self, letter: DeadLetter) {
+        let mut letters = self.letters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if letters.len() == self.capacity {
+            letters.pop_front();
+        }
+        letters.push_back(letter);
+    }
+
+    /// Newest first
+    pub fn recent(&self) -> Vec<DeadLetter> {
+        let letters = self.letters.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        letters.iter().rev().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shared::events::{EventEnvelope, Topic};
+
+    fn letter(error: &str) -> DeadLetter {
+        let topic: Topic<u32> = Topic::new("world.actor.spawned");
+        DeadLetter {
+            consumer: "world_service".to_string(),
+            topic: topic.name().to_string(),
+            envelope: EventEnvelope::new(&topic, &1).unwrap(),
+            error: error.to_string(),
+            attempts: 3,
+            failed_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn keeps_the_most_recent_letters() {
+        let letters = DeadLetters::new(2);
+        letters.push(letter("first"));
+        letters.push(letter("second"));
+        letters.push(letter("third"));
+
+        let errors: Vec<String> = letters.recent().into_iter().map(|letter| letter.error).collect();
+        assert_eq!(errors, vec!["third", "second"]);
+    }
+}
@@ -0,0 +1,60 @@
+//! Leases in MongoDB, one document per key, taken over once they expire.
+
+use async_trait::async_trait;
+use event_core::{EventCoreError, EventCoreResult, LockManager};
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::{Collection, Database};
+use shared::Timestamp;
+use std::time::Duration;
+
+pub const COLLECTION: &str = "event_locks";
+
+/// MongoDB's duplicate key error
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Clone)]
+pub struct MongoLocks {
+    collection: Collection<Document>,
+}
+
+impl MongoLocks {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+}
+
+#[async_trait]
+impl LockManager for MongoLocks {
+    async fn acquire(&self, key: &str, owner: &str, now: Timestamp, ttl: Duration) -> EventCoreResult<bool> {
+        let now_millis = now.timestamp_millis();
+        let expires_at = DateTime::from_millis(now_millis.saturating_add(ttl.as_millis() as i64));
+        // Matches when the lease is ours or has expired; otherwise the upsert collides with
+        // the holder's document
+        let filter = doc! {
+            "_id": key,
+            "$or": [{ "owner": owner }, { "expires_at": { "$lte": DateTime::from_millis(now_millis) } }],
+        };
+        let update = doc! { "$set": { "owner": owner, "expires_at": expires_at } };
+        let options = mongodb::options::UpdateOptions::builder().upsert(true).build();
+        match self.collection.update_one(filter, update, options).await {
+            Ok(_) => Ok(true),
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(EventCoreError::Storage(e.to_string())),
+        }
+    }
+
+    async fn release(&self, key: &str, owner: &str) -> EventCoreResult<()> {
+        self.collection
+            .delete_one(doc! { "_id": key, "owner": owner }, None)
+            .await
+            .map_err(|e| EventCoreError::Storage(e.to_string()))?;
+        Ok(())
+    }
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(e)) => e.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
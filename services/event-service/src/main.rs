@@ -1,33 +1,340 @@
+mod config;
+mod dead_letters;
+mod locks;
+mod schedule_store;
+mod scheduler;
+mod tutorial_store;
+mod tutorials;
+mod world_events;
+
 use axum::{
-    routing::get,
-    Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
 };
+use config::Config;
+use dead_letters::DeadLetters;
+use event_core::{EventCoreError, TutorialProgress};
+use locks::MongoLocks;
+use schedule_store::MongoScheduleStore;
+use scheduler::{EventScheduler, ScheduleStatus};
+use shared::events::topics::{LifecycleState, ServiceLifecycle, WorldEventChanged, DEAD_LETTER, SERVICE_LIFECYCLE};
+use shared::events::{connect, BusConfig, Consumer, ConsumerOptions, DeadLetter, EventBus, EventBusExt, ReplayingBus};
+use shared::health::{check_fn, BusCheck, HealthRegistry, HealthReport, LIVE_PATH, READY_PATH};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
+use shared::ChaosError;
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use tutorial_store::MongoTutorialStore;
+use tutorials::{ReportAction, StartTutorial, TutorialHost};
+use world_events::{StartWorldEvent, WorldEvents};
+
+const SERVICE: &str = "event-service";
+
+/// Consumer name, a single topic token
+const CONSUMER: &str = "event_service";
+
+#[derive(Clone)]
+struct AppState {
+    world_events: Arc<WorldEvents>,
+    dead_letters: Arc<DeadLetters>,
+    scheduler: Arc<EventScheduler>,
+    tutorials: Arc<TutorialHost>,
+}
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+    let schedules = MongoScheduleStore::new(&database);
+    if let Err(e) = schedules.ensure_indexes().await {
+        tracing::warn!("Failed to create indexes: {}", e);
+    }
+    let tutorial_store = MongoTutorialStore::new(&database);
+    if let Err(e) = tutorial_store.ensure_indexes().await {
+        tracing::warn!("Failed to create indexes: {}", e);
+    }
+
+    // World events are replayed to game services that restart
+    let bus = Arc::new(ReplayingBus::new(connect(&BusConfig::from_env()).await.unwrap()));
+    bus.clone().serve().await.unwrap();
+    let bus: Arc<dyn EventBus> = bus;
+
+    // Keep what consumers everywhere gave up on
+    let dead_letters = Arc::new(DeadLetters::default());
+    let received = dead_letters.clone();
+    let options = ConsumerOptions { replay: false, ..ConsumerOptions::new(CONSUMER) };
+    Consumer::new(bus.clone(), DEAD_LETTER, options)
+        .spawn(move |letter: DeadLetter, _| {
+            received.push(letter);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+    // Run the stored schedules, each on a single instance at a time
+    let instance = uuid::Uuid::new_v4().to_string();
+    let scheduler = Arc::new(EventScheduler::new(
+        Arc::new(schedules),
+        Arc::new(MongoLocks::new(&database)),
+        bus.clone(),
+        shared::wall_clock(),
+        instance.clone(),
+        &config.scheduler,
+    ));
+    let ticks = scheduler.spawn();
+
+    // Advance the starter zone tutorials from what players do
+    let tutorials = Arc::new(
+        TutorialHost::new(config.tutorials.clone(), Arc::new(tutorial_store), bus.clone(), shared::wall_clock())
+            .await
+            .unwrap(),
+    );
+    tutorials.follow().await.unwrap();
+
+    let state = AppState {
+        world_events: Arc::new(WorldEvents::new(bus.clone())),
+        dead_letters,
+        scheduler: scheduler.clone(),
+        tutorials,
+    };
+
+    // Ready while the bus is connected and schedules can be run
+    let ping = database.clone();
+    let health = Arc::new(
+        HealthRegistry::new(SERVICE)
+            .with_shutdown(&shutdown)
+            .register("event_bus", BusCheck::new(bus.clone()))
+            .register(
+                "mongodb",
+                check_fn(move || {
+                    let database = ping.clone();
+                    async move {
+                        database
+                            .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| ChaosError::Database(e.to_string()))
+                    }
+                }),
+            ),
+    );
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/world-events", get(active_world_events).post(start_world_event))
+        .route("/world-events/:id/end", post(end_world_event))
+        .route("/dead-letters", get(recent_dead_letters))
+        .route("/schedules", get(list_schedules))
+        .route("/schedules/:id/pause", post(pause_schedule))
+        .route("/schedules/:id/resume", post(resume_schedule))
+        .route("/schedules/:id/trigger", post(trigger_schedule))
+        .route("/tutorials/actions", post(report_tutorial_action))
+        .route("/tutorials/:id/start", post(start_tutorial))
+        .route("/tutorials/:id/actors/:actor_id", get(tutorial_progress))
+        .route("/tutorials/:id/actors/:actor_id/skip", post(skip_tutorial))
+        .route("/tutorials/:id/actors/:actor_id/resume", post(resume_tutorial))
+        .route("/", get(root))
+        .with_state(state)
+        .merge(
+            Router::new()
+                .route(LIVE_PATH, get(liveness))
+                .route(READY_PATH, get(readiness))
+                .with_state(health),
+        );
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 event-service server starting on {}", addr);
-    
+
+    announce(bus.as_ref(), &instance, addr, LifecycleState::Started).await;
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+
+    // Gateways stop routing here once the instance announces it is stopping
+    let stopping = {
+        let (bus, instance, shutdown) = (bus.clone(), instance.clone(), shutdown.clone());
+        async move {
+            shutdown.signal().await;
+            announce(bus.as_ref(), &instance, addr, LifecycleState::Stopping).await;
+        }
+    };
+    let server = axum::serve(listener, app).with_graceful_shutdown(stopping);
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    // Hand the schedules over to the remaining instances
+    ticks.abort();
+    scheduler.release_all().await;
+
+    announce(bus.as_ref(), &instance, addr, LifecycleState::Stopped).await;
+    if let Err(e) = bus.flush().await {
+        tracing::warn!("Failed to flush the event bus: {}", e);
+    }
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 event-service stopped");
+    telemetry.shutdown();
+}
+
+async fn announce(bus: &dyn EventBus, instance: &str, addr: SocketAddr, state: LifecycleState) {
+    let lifecycle = ServiceLifecycle {
+        service: SERVICE.to_string(),
+        instance: instance.to_string(),
+        state,
+        address: Some(addr.to_string()),
+    };
+    if let Err(e) = bus.publish_message(SERVICE, &SERVICE_LIFECYCLE, &lifecycle).await {
+        tracing::warn!("Failed to announce {:?}: {}", state, e);
+    }
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
 
+async fn liveness(State(health): State<Arc<HealthRegistry>>) -> Json<HealthReport> {
+    Json(health.liveness())
+}
+
+/// 503 while the event bus or MongoDB is unavailable
+async fn readiness(State(health): State<Arc<HealthRegistry>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = StatusCode::from_u16(report.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report))
+}
+
+async fn active_world_events(State(state): State<AppState>) -> Json<Vec<WorldEventChanged>> {
+    Json(state.world_events.active())
+}
+
+async fn start_world_event(
+    State(state): State<AppState>,
+    Json(request): Json<StartWorldEvent>,
+) -> Result<(StatusCode, Json<WorldEventChanged>), (StatusCode, String)> {
+    match state.world_events.start(request).await {
+        Ok(event) => Ok((StatusCode::CREATED, Json(event))),
+        Err(e) => Err((StatusCode::BAD_GATEWAY, e.to_string())),
+    }
+}
+
+async fn end_world_event(
+    State(state): State<AppState>,
+    Path(event_id): Path<String>,
+) -> Result<Json<WorldEventChanged>, (StatusCode, String)> {
+    match state.world_events.end(&event_id).await {
+        Ok(Some(event)) => Ok(Json(event)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("World event {} is not running", event_id))),
+        Err(e) => Err((StatusCode::BAD_GATEWAY, e.to_string())),
+    }
+}
+
+/// Newest first
+async fn recent_dead_letters(State(state): State<AppState>) -> Json<Vec<DeadLetter>> {
+    Json(state.dead_letters.recent())
+}
+
+async fn list_schedules(State(state): State<AppState>) -> Result<Json<Vec<ScheduleStatus>>, (StatusCode, String)> {
+    state.scheduler.list().await.map(Json).map_err(event_error)
+}
+
+async fn pause_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.scheduler.set_paused(&id, true).await.map_err(event_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resume_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.scheduler.set_paused(&id, false).await.map_err(event_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Starts a run on the next tick of the instance running the schedule
+async fn trigger_schedule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    state.scheduler.trigger(&id).await.map_err(event_error)?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Shows the hint of the current step again when the tutorial was already started
+async fn start_tutorial(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<StartTutorial>,
+) -> Result<Json<TutorialProgress>, (StatusCode, String)> {
+    state.tutorials.start(&id, &request).await.map(Json).map_err(event_error)
+}
+
+async fn tutorial_progress(
+    State(state): State<AppState>,
+    Path((id, actor_id)): Path<(String, String)>,
+) -> Result<Json<TutorialProgress>, (StatusCode, String)> {
+    state
+        .tutorials
+        .progress(&actor_id, &id)
+        .map(Json)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("{} has not started tutorial {}", actor_id, id)))
+}
+
+async fn skip_tutorial(
+    State(state): State<AppState>,
+    Path((id, actor_id)): Path<(String, String)>,
+) -> Result<Json<TutorialProgress>, (StatusCode, String)> {
+    state.tutorials.skip(&id, &actor_id).await.map(Json).map_err(event_error)
+}
+
+async fn resume_tutorial(
+    State(state): State<AppState>,
+    Path((id, actor_id)): Path<(String, String)>,
+) -> Result<Json<TutorialProgress>, (StatusCode, String)> {
+    state.tutorials.resume(&id, &actor_id).await.map(Json).map_err(event_error)
+}
+
+/// Actions not reported on the bus, e.g. talking to an NPC or opening a window
+async fn report_tutorial_action(
+    State(state): State<AppState>,
+    Json(report): Json<ReportAction>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    match state.tutorials.record(&report.actor_id, report.action).await {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err((StatusCode::SERVICE_UNAVAILABLE, e.to_string())),
+    }
+}
+
+fn event_error(error: EventCoreError) -> (StatusCode, String) {
+    let status = match error {
+        EventCoreError::ScheduleNotFound(_)
+        | EventCoreError::QueueNotFound(_)
+        | EventCoreError::TutorialNotFound(_) => StatusCode::NOT_FOUND,
+        EventCoreError::InvalidSchedule { .. }
+        | EventCoreError::InvalidQueue { .. }
+        | EventCoreError::InvalidTicket { .. }
+        | EventCoreError::InvalidTutorial { .. } => StatusCode::BAD_REQUEST,
+        EventCoreError::Storage(_) | EventCoreError::Announce(_) | EventCoreError::Allocation(_) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+    };
+    (status, error.to_string())
+}
+
 async fn root() -> &'static str {
     "Hello from event-service!"
 }
-
@@ -0,0 +1,86 @@
+//! Event schedules and their checkpoints in MongoDB, keyed by schedule id.
+
+use async_trait::async_trait;
+use event_core::{EventCoreError, EventCoreResult, EventSchedule, ScheduleCheckpoint, ScheduleStore};
+use futures::TryStreamExt;
+use mongodb::bson::{self, doc};
+use mongodb::options::{IndexOptions, ReplaceOptions};
+use mongodb::{Collection, Database, IndexModel};
+use shared::Timestamp;
+
+pub const SCHEDULES: &str = "event_schedules";
+pub const CHECKPOINTS: &str = "event_checkpoints";
+
+#[derive(Clone)]
+pub struct MongoScheduleStore {
+    schedules: Collection<EventSchedule>,
+    checkpoints: Collection<ScheduleCheckpoint>,
+}
+
+impl MongoScheduleStore {
+    pub fn new(database: &Database) -> Self {
+        Self { schedules: database.collection(SCHEDULES), checkpoints: database.collection(CHECKPOINTS) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let schedule_id = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(IndexOptions::builder().name("id".to_string()).unique(true).build())
+            .build();
+        self.schedules.create_index(schedule_id, None).await?;
+        let checkpoint_id = IndexModel::builder()
+            .keys(doc! { "schedule_id": 1 })
+            .options(IndexOptions::builder().name("schedule_id".to_string()).unique(true).build())
+            .build();
+        self.checkpoints.create_index(checkpoint_id, None).await?;
+        Ok(())
+    }
+}
+
+fn storage(e: impl std::fmt::Display) -> EventCoreError {
+    EventCoreError::Storage(e.to_string())
+}
+
+#[async_trait]
+impl ScheduleStore for MongoScheduleStore {
+    async fn schedules(&self) -> EventCoreResult<Vec<EventSchedule>> {
+        self.schedules.find(doc! {}, None).await.map_err(storage)?.try_collect().await.map_err(storage)
+    }
+
+    async fn checkpoints(&self) -> EventCoreResult<Vec<ScheduleCheckpoint>> {
+        self.checkpoints.find(doc! {}, None).await.map_err(storage)?.try_collect().await.map_err(storage)
+    }
+
+    async fn checkpoint(&self, schedule_id: &str) -> EventCoreResult<Option<ScheduleCheckpoint>> {
+        self.checkpoints.find_one(doc! { "schedule_id": schedule_id }, None).await.map_err(storage)
+    }
+
+    async fn save_checkpoint(&self, checkpoint: &ScheduleCheckpoint) -> EventCoreResult<()> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.checkpoints
+            .replace_one(doc! { "schedule_id": &checkpoint.schedule_id }, checkpoint, options)
+            .await
+            .map_err(storage)?;
+        Ok(())
+    }
+
+    async fn set_paused(&self, schedule_id: &str, paused: bool) -> EventCoreResult<bool> {
+        let result = self
+            .schedules
+            .update_one(doc! { "id": schedule_id }, doc! { "$set": { "paused": paused } }, None)
+            .await
+            .map_err(storage)?;
+        Ok(result.matched_count > 0)
+    }
+
+    async fn request_trigger(&self, schedule_id: &str, at: Timestamp) -> EventCoreResult<bool> {
+        // Stored the way serde writes timestamps, so schedules read it back
+        let at = bson::to_bson(&at).map_err(storage)?;
+        let result = self
+            .schedules
+            .update_one(doc! { "id": schedule_id }, doc! { "$set": { "trigger_requested_at": at } }, None)
+            .await
+            .map_err(storage)?;
+        Ok(result.matched_count > 0)
+    }
+}
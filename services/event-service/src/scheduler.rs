@@ -0,0 +1,409 @@
+//! Runs the event schedules stored in MongoDB.
+//!
+//! Every instance looks at every schedule on each tick but only runs those it holds the
+//! lease of, so a run starts and ends once however many instances there are. Leases are
+//! renewed on every tick; when an instance stops, another one takes its schedules over
+//! once the lease expires and resumes from the last checkpoint. Runs are announced on
+//! `WORLD_EVENT` before the checkpoint is saved, so a crash in between announces a run
+//! again under the same event id.
+
+use event_core::{
+    advance, EventCoreError, EventCoreResult, EventRun, EventSchedule, LockManager, ScheduleCheckpoint, ScheduleStore,
+    Transition,
+};
+use serde::Serialize;
+use shared::events::topics::{WorldEventChanged, WorldEventState, WORLD_EVENT};
+use shared::events::{EventBus, EventBusExt};
+use shared::{SharedClock, Timestamp};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::config::SchedulerConfig;
+use crate::SERVICE;
+
+/// A schedule and how far it got
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleStatus {
+    #[serde(flatten)]
+    pub schedule: EventSchedule,
+    pub checkpoint: Option<ScheduleCheckpoint>,
+    /// Run by this instance
+    pub held: bool,
+}
+
+pub struct EventScheduler {
+    store: Arc<dyn ScheduleStore>,
+    locks: Arc<dyn LockManager>,
+    bus: Arc<dyn EventBus>,
+    clock: SharedClock,
+    /// Lease owner, unique per instance
+    owner: String,
+    lease: Duration,
+    tick: Duration,
+    /// Schedules this instance holds
+    held: Mutex<HashMap<String, Held>>,
+}
+
+struct Held {
+    checkpoint: ScheduleCheckpoint,
+    renewed_at: Timestamp,
+}
+
+impl EventScheduler {
+    pub fn new(
+        store: Arc<dyn ScheduleStore>,
+        locks: Arc<dyn LockManager>,
+        bus: Arc<dyn EventBus>,
+        clock: SharedClock,
+        owner: String,
+        config: &SchedulerConfig,
+    ) -> Self {
+        Self {
+            store,
+            locks,
+            bus,
+            clock,
+            owner,
+            lease: Duration::from_secs(config.lease_secs),
+            tick: Duration::from_millis(config.tick_ms),
+            held: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn spawn(self: &Arc<Self>) -> JoinHandle<()> {
+        let scheduler = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(scheduler.tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                if let Err(e) = scheduler.tick().await {
+                    warn!("⚠️  Failed to load event schedules: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Run every schedule this instance holds or can take over
+    pub async fn tick(&self) -> EventCoreResult<()> {
+        let now = self.clock.now();
+        let schedules = self.store.schedules().await?;
+        for schedule in &schedules {
+            if let Err(e) = schedule.validate() {
+                warn!("⚠️  Skipping schedule: {}", e);
+                continue;
+            }
+            if let Err(e) = self.run(schedule, now).await {
+                warn!("⚠️  Failed to run schedule {}: {}", schedule.id, e);
+            }
+        }
+
+        // Schedules that were deleted
+        let deleted: Vec<String> = self
+            .lock()
+            .keys()
+            .filter(|schedule_id| !schedules.iter().any(|schedule| &schedule.id == *schedule_id))
+            .cloned()
+            .collect();
+        for schedule_id in deleted {
+            self.lock().remove(&schedule_id);
+            self.locks.release(&lock_key(&schedule_id), &self.owner).await?;
+        }
+        Ok(())
+    }
+
+    async fn run(&self, schedule: &EventSchedule, now: Timestamp) -> EventCoreResult<()> {
+        if !self.locks.acquire(&lock_key(&schedule.id), &self.owner, now, self.lease).await? {
+            if self.lock().remove(&schedule.id).is_some() {
+                warn!("⚠️  Lost the lease of schedule {}", schedule.id);
+            }
+            return Ok(());
+        }
+
+        // Another instance may have run the schedule if our lease lapsed in between
+        let held = self
+            .lock()
+            .get(&schedule.id)
+            .filter(|held| now < held.renewed_at + self.lease_duration())
+            .map(|held| held.checkpoint.clone());
+        let (mut checkpoint, saved) = match held {
+            Some(checkpoint) => (checkpoint, true),
+            None => {
+                info!("📅 Running schedule {}", schedule.id);
+                match self.store.checkpoint(&schedule.id).await? {
+                    Some(checkpoint) => (checkpoint, true),
+                    None => (ScheduleCheckpoint::new(schedule, now), false),
+                }
+            }
+        };
+        let before = checkpoint.clone();
+        let transitions = advance(schedule, &mut checkpoint, now);
+        for transition in &transitions {
+            self.announce(transition).await?;
+        }
+        if !saved || changed(&before, &checkpoint) {
+            self.store.save_checkpoint(&checkpoint).await?;
+        }
+        self.lock().insert(schedule.id.clone(), Held { checkpoint, renewed_at: now });
+        Ok(())
+    }
+
+    async fn announce(&self, transition: &Transition) -> EventCoreResult<()> {
+        let (run, state): (&EventRun, _) = match transition {
+            Transition::Started(run) => (run, WorldEventState::Started),
+            Transition::Ended(run) => (run, WorldEventState::Ended),
+        };
+        info!("📣 {} {:?} ({})", run.name, state, run.run_id);
+        let event = WorldEventChanged {
+            event_id: run.run_id.clone(),
+            name: run.name.clone(),
+            zone: run.zone.clone(),
+            state,
+            ends_at: Some(run.ends_at),
+        };
+        self.bus
+            .publish_message(SERVICE, &WORLD_EVENT, &event)
+            .await
+            .map_err(|e| EventCoreError::Announce(e.to_string()))
+    }
+
+    /// Give up every lease so other instances take over right away
+    pub async fn release_all(&self) {
+        let held: Vec<String> = self.lock().drain().map(|(schedule_id, _)| schedule_id).collect();
+        for schedule_id in held {
+            if let Err(e) = self.locks.release(&lock_key(&schedule_id), &self.owner).await {
+                warn!("⚠️  Failed to release schedule {}: {}", schedule_id, e);
+            }
+        }
+    }
+
+    /// Every schedule with its last checkpoint
+    pub async fn list(&self) -> EventCoreResult<Vec<ScheduleStatus>> {
+        let mut checkpoints: HashMap<String, ScheduleCheckpoint> = self
+            .store
+            .checkpoints()
+            .await?
+            .into_iter()
+            .map(|checkpoint| (checkpoint.schedule_id.clone(), checkpoint))
+            .collect();
+        let schedules = self.store.schedules().await?;
+        let held = self.lock();
+        let mut statuses: Vec<ScheduleStatus> = schedules
+            .into_iter()
+            .map(|schedule| ScheduleStatus {
+                checkpoint: checkpoints.remove(&schedule.id),
+                held: held.contains_key(&schedule.id),
+                schedule,
+            })
+            .collect();
+        statuses.sort_by(|a, b| a.schedule.id.cmp(&b.schedule.id));
+        Ok(statuses)
+    }
+
+    /// Pause or resume a schedule, picked up by whichever instance runs it
+    pub async fn set_paused(&self, schedule_id: &str, paused: bool) -> EventCoreResult<()> {
+        if !self.store.set_paused(schedule_id, paused).await? {
+            return Err(EventCoreError::ScheduleNotFound(schedule_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Start a run of a schedule on the next tick, unless one is in progress
+    pub async fn trigger(&self, schedule_id: &str) -> EventCoreResult<()> {
+        if !self.store.request_trigger(schedule_id, self.clock.now()).await? {
+            return Err(EventCoreError::ScheduleNotFound(schedule_id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn lease_duration(&self) -> chrono::Duration {
+        chrono::Duration::from_std(self.lease).unwrap_or_else(|_| chrono::Duration::zero())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Held>> {
+        self.held.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn lock_key(schedule_id: &str) -> String {
+    format!("event_schedule:{}", schedule_id)
+}
+
+/// Whether anything but the time of the check moved
+fn changed(before: &ScheduleCheckpoint, after: &ScheduleCheckpoint) -> bool {
+    before.next_start != after.next_start
+        || before.active != after.active
+        || before.handled_trigger != after.handled_trigger
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use event_core::Recurrence;
+    use shared::events::InProcessEventBus;
+    use shared::SimulatedClock;
+
+    #[derive(Default)]
+    struct MemoryStore {
+        schedules: Mutex<Vec<EventSchedule>>,
+        checkpoints: Mutex<HashMap<String, ScheduleCheckpoint>>,
+    }
+
+    #[async_trait]
+    impl ScheduleStore for MemoryStore {
+        async fn schedules(&self) -> EventCoreResult<Vec<EventSchedule>> {
+            Ok(self.schedules.lock().unwrap().clone())
+        }
+
+        async fn checkpoints(&self) -> EventCoreResult<Vec<ScheduleCheckpoint>> {
+            Ok(self.checkpoints.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn checkpoint(&self, schedule_id: &str) -> EventCoreResult<Option<ScheduleCheckpoint>> {
+            Ok(self.checkpoints.lock().unwrap().get(schedule_id).cloned())
+        }
+
+        async fn save_checkpoint(&self, checkpoint: &ScheduleCheckpoint) -> EventCoreResult<()> {
+            self.checkpoints.lock().unwrap().insert(checkpoint.schedule_id.clone(), checkpoint.clone());
+            Ok(())
+        }
+
+        async fn set_paused(&self, schedule_id: &str, paused: bool) -> EventCoreResult<bool> {
+            let mut schedules = self.schedules.lock().unwrap();
+            let schedule = schedules.iter_mut().find(|schedule| schedule.id == schedule_id);
+            Ok(schedule.map(|schedule| schedule.paused = paused).is_some())
+        }
+
+        async fn request_trigger(&self, schedule_id: &str, at: Timestamp) -> EventCoreResult<bool> {
+            let mut schedules = self.schedules.lock().unwrap();
+            let schedule = schedules.iter_mut().find(|schedule| schedule.id == schedule_id);
+            Ok(schedule.map(|schedule| schedule.trigger_requested_at = Some(at)).is_some())
+        }
+    }
+
+    #[derive(Default)]
+    struct MemoryLocks {
+        leases: Mutex<HashMap<String, (String, Timestamp)>>,
+    }
+
+    #[async_trait]
+    impl LockManager for MemoryLocks {
+        async fn acquire(&self, key: &str, owner: &str, now: Timestamp, ttl: Duration) -> EventCoreResult<bool> {
+            let mut leases = self.leases.lock().unwrap();
+            if let Some((holder, expires_at)) = leases.get(key) {
+                if holder != owner && *expires_at > now {
+                    return Ok(false);
+                }
+            }
+            let expires_at = now + chrono::Duration::from_std(ttl).unwrap();
+            leases.insert(key.to_string(), (owner.to_string(), expires_at));
+            Ok(true)
+        }
+
+        async fn release(&self, key: &str, owner: &str) -> EventCoreResult<()> {
+            let mut leases = self.leases.lock().unwrap();
+            if leases.get(key).is_some_and(|(holder, _)| holder == owner) {
+                leases.remove(key);
+            }
+            Ok(())
+        }
+    }
+
+    fn start() -> Timestamp {
+        chrono::Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    }
+
+    fn store() -> Arc<MemoryStore> {
+        let store = MemoryStore::default();
+        store.schedules.lock().unwrap().push(EventSchedule {
+            id: "invasion".to_string(),
+            name: "Goblin Invasion".to_string(),
+            zone: None,
+            recurrence: Recurrence::Every { start: start() + chrono::Duration::seconds(10), interval_secs: 3600 },
+            duration_secs: 60,
+            paused: false,
+            trigger_requested_at: None,
+        });
+        Arc::new(store)
+    }
+
+    fn scheduler(
+        owner: &str,
+        store: &Arc<MemoryStore>,
+        locks: &Arc<MemoryLocks>,
+        bus: &Arc<dyn EventBus>,
+        clock: &Arc<SimulatedClock>,
+    ) -> EventScheduler {
+        EventScheduler::new(
+            store.clone(),
+            locks.clone(),
+            bus.clone(),
+            clock.clone(),
+            owner.to_string(),
+            &SchedulerConfig { tick_ms: 1000, lease_secs: 15 },
+        )
+    }
+
+    #[tokio::test]
+    async fn a_single_instance_runs_each_schedule() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut announced = bus.subscribe(&WORLD_EVENT).await.unwrap();
+        let (store, locks) = (store(), Arc::new(MemoryLocks::default()));
+        let clock = Arc::new(SimulatedClock::new(start(), Duration::from_secs(1)));
+        let first = scheduler("first", &store, &locks, &bus, &clock);
+        let second = scheduler("second", &store, &locks, &bus, &clock);
+
+        first.tick().await.unwrap();
+        second.tick().await.unwrap();
+        clock.advance(Duration::from_secs(10));
+        first.tick().await.unwrap();
+        second.tick().await.unwrap();
+
+        let started = announced.next().await.unwrap().unwrap();
+        assert_eq!(started.state, WorldEventState::Started);
+        assert!(first.list().await.unwrap()[0].held);
+        assert!(!second.list().await.unwrap()[0].held);
+        assert!(store.checkpoint("invasion").await.unwrap().unwrap().active.is_some());
+
+        // The first instance stops; the second resumes from its checkpoint once the lease expires
+        clock.advance(Duration::from_secs(10));
+        second.tick().await.unwrap();
+        assert!(!second.list().await.unwrap()[0].held);
+        clock.advance(Duration::from_secs(60));
+        second.tick().await.unwrap();
+        let ended = announced.next().await.unwrap().unwrap();
+        assert_eq!(ended.state, WorldEventState::Ended);
+        assert_eq!(ended.event_id, started.event_id);
+        assert!(second.list().await.unwrap()[0].held);
+    }
+
+    #[tokio::test]
+    async fn admins_pause_and_trigger_schedules() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut announced = bus.subscribe(&WORLD_EVENT).await.unwrap();
+        let (store, locks) = (store(), Arc::new(MemoryLocks::default()));
+        let clock = Arc::new(SimulatedClock::new(start(), Duration::from_secs(1)));
+        let scheduler = scheduler("only", &store, &locks, &bus, &clock);
+
+        scheduler.set_paused("invasion", true).await.unwrap();
+        scheduler.tick().await.unwrap();
+        clock.advance(Duration::from_secs(20));
+        scheduler.tick().await.unwrap();
+        assert!(store.checkpoint("invasion").await.unwrap().unwrap().active.is_none());
+
+        scheduler.trigger("invasion").await.unwrap();
+        scheduler.tick().await.unwrap();
+        let started = announced.next().await.unwrap().unwrap();
+        assert_eq!(started.state, WorldEventState::Started);
+        assert!(store.checkpoint("invasion").await.unwrap().unwrap().active.unwrap().triggered);
+
+        assert!(matches!(scheduler.trigger("unknown").await, Err(EventCoreError::ScheduleNotFound(_))));
+        scheduler.release_all().await;
+        assert!(locks.leases.lock().unwrap().is_empty());
+    }
+}
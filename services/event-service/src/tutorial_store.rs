@@ -0,0 +1,54 @@
+//! Tutorial progress in MongoDB, one document per actor and tutorial.
+
+use async_trait::async_trait;
+use event_core::{EventCoreError, EventCoreResult, TutorialProgress, TutorialStore};
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::options::{IndexOptions, ReplaceOptions};
+use mongodb::{Collection, Database, IndexModel};
+
+pub const PROGRESS: &str = "tutorial_progress";
+
+#[derive(Clone)]
+pub struct MongoTutorialStore {
+    progress: Collection<TutorialProgress>,
+}
+
+impl MongoTutorialStore {
+    pub fn new(database: &Database) -> Self {
+        Self { progress: database.collection(PROGRESS) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let actor_tutorial = IndexModel::builder()
+            .keys(doc! { "actor_id": 1, "tutorial_id": 1 })
+            .options(IndexOptions::builder().name("actor_tutorial".to_string()).unique(true).build())
+            .build();
+        self.progress.create_index(actor_tutorial, None).await?;
+        Ok(())
+    }
+}
+
+fn storage(e: impl std::fmt::Display) -> EventCoreError {
+    EventCoreError::Storage(e.to_string())
+}
+
+#[async_trait]
+impl TutorialStore for MongoTutorialStore {
+    async fn progress(&self) -> EventCoreResult<Vec<TutorialProgress>> {
+        self.progress.find(doc! {}, None).await.map_err(storage)?.try_collect().await.map_err(storage)
+    }
+
+    async fn save_progress(&self, progress: &TutorialProgress) -> EventCoreResult<()> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.progress
+            .replace_one(
+                doc! { "actor_id": &progress.actor_id, "tutorial_id": &progress.tutorial_id },
+                progress,
+                options,
+            )
+            .await
+            .map_err(storage)?;
+        Ok(())
+    }
+}
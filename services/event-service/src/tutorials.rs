@@ -0,0 +1,228 @@
+//! Starter zone tutorials, run with event-core's tutorial hooks.
+//!
+//! Kills reported on `ACTOR_EXPERIENCE_GAINED` and loot on `LOOT_DROPPED` advance the
+//! players' tutorials; game servers and clients report other actions, and start, skip or
+//! resume tutorials, over HTTP. Hints go out on `TUTORIAL_HINT` for the api crate to push
+//! to the players' sessions. Progress is saved as it changes and loaded on start.
+
+use event_core::{
+    ActionKind, EventCoreError, EventCoreResult, PlayerAction, TutorialChanges, TutorialDefinition, TutorialProgress,
+    TutorialStore, TutorialUpdate, Tutorials,
+};
+use serde::Deserialize;
+use shared::events::topics::{
+    ExperienceGained, LootDropped, TutorialHint, TutorialHintChanged, ACTOR_EXPERIENCE_GAINED, LOOT_DROPPED,
+    TUTORIAL_HINT,
+};
+use shared::events::{Consumer, ConsumerOptions, EventBus, EventBusExt};
+use shared::{ChaosError, ChaosResult, SharedClock};
+use std::sync::{Arc, Mutex, MutexGuard};
+use tracing::{info, warn};
+
+use crate::{CONSUMER, SERVICE};
+
+/// Request to start a tutorial
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartTutorial {
+    pub actor_id: String,
+    /// User whose sessions get the hints
+    pub user_id: String,
+}
+
+/// Action reported for an actor
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportAction {
+    pub actor_id: String,
+    #[serde(flatten)]
+    pub action: PlayerAction,
+}
+
+pub struct TutorialHost {
+    tutorials: Mutex<Tutorials>,
+    store: Arc<dyn TutorialStore>,
+    bus: Arc<dyn EventBus>,
+    clock: SharedClock,
+}
+
+impl TutorialHost {
+    /// Host the defined tutorials, picking up the stored progress
+    pub async fn new(
+        definitions: Vec<TutorialDefinition>,
+        store: Arc<dyn TutorialStore>,
+        bus: Arc<dyn EventBus>,
+        clock: SharedClock,
+    ) -> EventCoreResult<Self> {
+        let mut tutorials = Tutorials::new(definitions)?;
+        for progress in store.progress().await? {
+            tutorials.restore(progress);
+        }
+        Ok(Self { tutorials: Mutex::new(tutorials), store, bus, clock })
+    }
+
+    /// Advance the actors' tutorials from the kills and loot reported on the bus
+    pub async fn follow(self: &Arc<Self>) -> ChaosResult<()> {
+        let options = ConsumerOptions { replay: false, ..ConsumerOptions::new(CONSUMER) };
+        let host = self.clone();
+        Consumer::new(self.bus.clone(), ACTOR_EXPERIENCE_GAINED, options.clone())
+            .spawn(move |gained: ExperienceGained, _| {
+                let host = host.clone();
+                async move {
+                    if gained.source.as_deref() == Some("kill") {
+                        host.record(&gained.actor_id, PlayerAction::new(ActionKind::Kill, None)).await?;
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+        let host = self.clone();
+        Consumer::new(self.bus.clone(), LOOT_DROPPED, options)
+            .spawn(move |dropped: LootDropped, _| {
+                let host = host.clone();
+                async move {
+                    for item in &dropped.items {
+                        let action = PlayerAction::new(ActionKind::Loot, Some(&item.item_id));
+                        host.record(&dropped.actor_id, action).await?;
+                    }
+                    Ok(())
+                }
+            })
+            .await?;
+        Ok(())
+    }
+
+    pub fn progress(&self, actor_id: &str, tutorial_id: &str) -> Option<TutorialProgress> {
+        self.lock().progress(actor_id, tutorial_id).cloned()
+    }
+
+    pub async fn start(&self, tutorial_id: &str, request: &StartTutorial) -> EventCoreResult<TutorialProgress> {
+        let changes = self.lock().start(&request.actor_id, &request.user_id, tutorial_id, self.clock.now())?;
+        self.apply(changes).await?;
+        self.current(&request.actor_id, tutorial_id)
+    }
+
+    pub async fn skip(&self, tutorial_id: &str, actor_id: &str) -> EventCoreResult<TutorialProgress> {
+        let changes = self.lock().skip(actor_id, tutorial_id, self.clock.now())?;
+        self.apply(changes).await?;
+        self.current(actor_id, tutorial_id)
+    }
+
+    pub async fn resume(&self, tutorial_id: &str, actor_id: &str) -> EventCoreResult<TutorialProgress> {
+        let changes = self.lock().resume(actor_id, tutorial_id, self.clock.now())?;
+        self.apply(changes).await?;
+        self.current(actor_id, tutorial_id)
+    }
+
+    pub async fn record(&self, actor_id: &str, action: PlayerAction) -> ChaosResult<()> {
+        let changes = self.lock().record(actor_id, &action, self.clock.now());
+        self.apply(changes).await.map_err(|e| ChaosError::Database(e.to_string()))
+    }
+
+    /// Save the changed progress, then tell the players
+    async fn apply(&self, changes: TutorialChanges) -> EventCoreResult<()> {
+        for progress in &changes.progress {
+            self.store.save_progress(progress).await?;
+        }
+        let at = self.clock.now();
+        for update in changes.updates {
+            let (user_id, actor_id, tutorial_id, hint) = match update {
+                TutorialUpdate::ShowHint { user_id, actor_id, tutorial_id, step_id, hint } => {
+                    let hint = TutorialHint { step_id, text: hint.text, anchor: hint.anchor };
+                    (user_id, actor_id, tutorial_id, Some(hint))
+                }
+                TutorialUpdate::HideHint { user_id, actor_id, tutorial_id } => (user_id, actor_id, tutorial_id, None),
+                TutorialUpdate::QuestCompleted { actor_id, quest_id, .. } => {
+                    info!("📘 {} completed {}", actor_id, quest_id);
+                    continue;
+                }
+                TutorialUpdate::ChainCompleted { actor_id, chain_id, .. } => {
+                    info!("🎓 {} completed {}", actor_id, chain_id);
+                    continue;
+                }
+            };
+            let changed = TutorialHintChanged { user_id, actor_id, tutorial_id, hint, at };
+            // Hints are shown again when the tutorial is, so a lost one is not retried
+            if let Err(e) = self.bus.publish_message(SERVICE, &TUTORIAL_HINT, &changed).await {
+                warn!("Failed to push the {} hint to {}: {}", changed.tutorial_id, changed.user_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn current(&self, actor_id: &str, tutorial_id: &str) -> EventCoreResult<TutorialProgress> {
+        self.progress(actor_id, tutorial_id)
+            .ok_or_else(|| EventCoreError::TutorialNotFound(format!("{} for {}", tutorial_id, actor_id)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tutorials> {
+        self.tutorials.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use event_core::{StepTrigger, TutorialStatus, TutorialStep, UiHint};
+    use shared::events::InProcessEventBus;
+
+    /// Progress kept in memory
+    #[derive(Default)]
+    struct MemoryStore {
+        progress: Mutex<Vec<TutorialProgress>>,
+    }
+
+    #[async_trait]
+    impl TutorialStore for MemoryStore {
+        async fn progress(&self) -> EventCoreResult<Vec<TutorialProgress>> {
+            Ok(self.progress.lock().unwrap().clone())
+        }
+
+        async fn save_progress(&self, progress: &TutorialProgress) -> EventCoreResult<()> {
+            let mut stored = self.progress.lock().unwrap();
+            stored.retain(|stored| stored.actor_id != progress.actor_id || stored.tutorial_id != progress.tutorial_id);
+            stored.push(progress.clone());
+            Ok(())
+        }
+    }
+
+    fn first_steps() -> TutorialDefinition {
+        let step = |id: &str, action: ActionKind| TutorialStep {
+            id: id.to_string(),
+            trigger: StepTrigger { action, subject: None, count: 1 },
+            hint: UiHint { text: format!("Now {}", id), anchor: None },
+        };
+        TutorialDefinition {
+            id: "first_steps".to_string(),
+            steps: vec![step("hunt", ActionKind::Kill), step("loot", ActionKind::Loot)],
+            skippable: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn kills_and_loot_on_the_bus_advance_tutorials() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut hints = bus.subscribe(&TUTORIAL_HINT).await.unwrap();
+        let store = Arc::new(MemoryStore::default());
+        let host = TutorialHost::new(vec![first_steps()], store.clone(), bus.clone(), shared::wall_clock());
+        let host = Arc::new(host.await.unwrap());
+        host.follow().await.unwrap();
+
+        let request = StartTutorial { actor_id: "hero".to_string(), user_id: "user-1".to_string() };
+        host.start("first_steps", &request).await.unwrap();
+        assert_eq!(hints.next().await.unwrap().unwrap().hint.unwrap().step_id, "hunt");
+
+        let gained = ExperienceGained { actor_id: "hero".to_string(), amount: 40.0, source: Some("kill".to_string()) };
+        bus.publish_message("chaos-backend", &ACTOR_EXPERIENCE_GAINED, &gained).await.unwrap();
+        assert_eq!(hints.next().await.unwrap().unwrap().hint.unwrap().step_id, "loot");
+
+        host.record("hero", PlayerAction::new(ActionKind::Loot, Some("wolf_pelt"))).await.unwrap();
+        let hidden = hints.next().await.unwrap().unwrap();
+        assert_eq!((hidden.user_id.as_str(), hidden.hint), ("user-1", None));
+
+        // Progress is saved and picked up by the next instance
+        let restarted = TutorialHost::new(vec![first_steps()], store, bus, shared::wall_clock()).await.unwrap();
+        let progress = restarted.progress("hero", "first_steps").unwrap();
+        assert_eq!(progress.status, TutorialStatus::Completed);
+        assert_eq!(progress.completed_quests, ["tutorial.first_steps.hunt", "tutorial.first_steps.loot"]);
+    }
+}
@@ -0,0 +1,103 @@
+//! Scheduled world events, announced to the game services as they start and end.
+
+use chrono::Utc;
+use serde::Deserialize;
+use shared::events::topics::{WorldEventChanged, WorldEventState, WORLD_EVENT};
+use shared::events::{EventBus, EventBusExt};
+use shared::ChaosResult;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::SERVICE;
+
+/// Request to start a world event
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartWorldEvent {
+    pub name: String,
+    /// Whole world when absent
+    #[serde(default)]
+    pub zone: Option<String>,
+    pub duration_secs: u64,
+}
+
+/// World events in progress
+pub struct WorldEvents {
+    bus: Arc<dyn EventBus>,
+    active: Mutex<HashMap<String, WorldEventChanged>>,
+}
+
+impl WorldEvents {
+    pub fn new(bus: Arc<dyn EventBus>) -> Self {
+        Self { bus, active: Mutex::new(HashMap::new()) }
+    }
+
+    /// Start an event and end it once its duration is over
+    pub async fn start(self: &Arc<Self>, request: StartWorldEvent) -> ChaosResult<WorldEventChanged> {
+        let duration = Duration::from_secs(request.duration_secs);
+        let event = WorldEventChanged {
+            event_id: uuid::Uuid::new_v4().to_string(),
+            name: request.name,
+            zone: request.zone,
+            state: WorldEventState::Started,
+            ends_at: chrono::Duration::from_std(duration).ok().map(|duration| Utc::now() + duration),
+        };
+        self.bus.publish_message(SERVICE, &WORLD_EVENT, &event).await?;
+        self.lock().insert(event.event_id.clone(), event.clone());
+
+        let events = self.clone();
+        let event_id = event.event_id.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            if let Err(e) = events.end(&event_id).await {
+                tracing::warn!("Failed to end world event {}: {}", event_id, e);
+            }
+        });
+        Ok(event)
+    }
+
+    /// End an event early; `None` when it is not running
+    pub async fn end(&self, event_id: &str) -> ChaosResult<Option<WorldEventChanged>> {
+        let Some(mut event) = self.lock().remove(event_id) else {
+            return Ok(None);
+        };
+        event.state = WorldEventState::Ended;
+        event.ends_at = Some(Utc::now());
+        self.bus.publish_message(SERVICE, &WORLD_EVENT, &event).await?;
+        Ok(Some(event))
+    }
+
+    pub fn active(&self) -> Vec<WorldEventChanged> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, WorldEventChanged>> {
+        self.active.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::events::InProcessEventBus;
+
+    #[tokio::test]
+    async fn events_are_announced_when_they_start_and_end() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut announced = bus.subscribe(&WORLD_EVENT).await.unwrap();
+        let events = Arc::new(WorldEvents::new(bus));
+
+        let started = events
+            .start(StartWorldEvent { name: "Goblin Invasion".to_string(), zone: None, duration_secs: 3600 })
+            .await
+            .unwrap();
+        assert_eq!(events.active().len(), 1);
+        assert_eq!(announced.next().await.unwrap().unwrap().state, WorldEventState::Started);
+
+        let ended = events.end(&started.event_id).await.unwrap().unwrap();
+        assert_eq!(ended.state, WorldEventState::Ended);
+        assert!(events.active().is_empty());
+        assert_eq!(announced.next().await.unwrap().unwrap().state, WorldEventState::Ended);
+        assert!(events.end(&started.event_id).await.unwrap().is_none());
+    }
+}
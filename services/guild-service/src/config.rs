@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub guilds: GuildConfig,
+    /// Key game servers award guild experience with; `GUILD_INTERNAL_API_KEY` takes precedence
+    #[serde(default)]
+    pub internal_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// Rules every guild follows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildConfig {
+    pub max_name_len: usize,
+    /// Bank tabs of a new guild
+    pub bank_tabs: Vec<String>,
+    /// Item stacks a bank tab holds
+    pub bank_tab_slots: usize,
+    pub progression: ProgressionTrack,
+}
+
+impl Default for GuildConfig {
+    fn default() -> Self {
+        Self {
+            max_name_len: 24,
+            bank_tabs: vec!["General".to_string(), "Materials".to_string()],
+            bank_tab_slots: 98,
+            progression: ProgressionTrack::default(),
+        }
+    }
+}
+
+/// Guild levels and what they unlock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProgressionTrack {
+    /// Total experience needed for level 2, 3, ...; the track ends after the last entry
+    pub level_experience: Vec<u64>,
+    /// Members a level 1 guild has room for
+    pub base_members: usize,
+    /// Room for more members gained with each level
+    pub members_per_level: usize,
+}
+
+impl Default for ProgressionTrack {
+    fn default() -> Self {
+        Self {
+            level_experience: vec![10_000, 30_000, 70_000, 150_000, 300_000, 550_000, 900_000, 1_400_000, 2_000_000],
+            base_members: 30,
+            members_per_level: 10,
+        }
+    }
+}
+
+impl ProgressionTrack {
+    /// Level reached with `experience`
+    pub fn level_for(&self, experience: u64) -> u32 {
+        1 + self.level_experience.iter().take_while(|needed| experience >= **needed).count() as u32
+    }
+
+    /// Members a guild of `level` has room for
+    pub fn max_members(&self, level: u32) -> usize {
+        self.base_members + self.members_per_level * level.saturating_sub(1) as usize
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.level_experience.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(ConfigError::InvalidConfig(
+                "Guild level experience must increase with every level".to_string(),
+            ));
+        }
+        if self.base_members == 0 {
+            return Err(ConfigError::InvalidConfig("Guilds need room for at least one member".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/guild-service.yaml".to_string());
+
+        if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            let mut config: Config = serde_yaml::from_str(&content)?;
+            config.guilds.progression.validate()?;
+            if let Ok(key) = env::var("GUILD_INTERNAL_API_KEY") {
+                config.internal_api_key = Some(key);
+            }
+            return Ok(config);
+        }
+
+        tracing::warn!("Config file not found at {}, using environment variables", config_path);
+        let server = ServerConfig {
+            port: env::var("GUILD_SERVICE_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("GUILD_SERVICE_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_guilds".to_string()),
+        };
+
+        Ok(Config {
+            server,
+            database,
+            guilds: GuildConfig::default(),
+            internal_api_key: env::var("GUILD_INTERNAL_API_KEY").ok(),
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
@@ -0,0 +1,472 @@
+//! Guilds: their ranks, members, bank and progression.
+//!
+//! Every member holds one rank. Ranks are ordered by `level`, 0 being the guild master's,
+//! and grant [`Permissions`]; members only act on members of lower ranks. The guild bank
+//! keeps item stacks in tabs, and each rank may withdraw a limited number of items a day.
+//! Guild experience, earned by the members, raises the guild's level along the configured
+//! [`ProgressionTrack`], which in turn makes room for more members.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shared::Timestamp;
+
+use crate::config::{GuildConfig, ProgressionTrack};
+
+/// Permission bits granted by a rank
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Permissions(pub u32);
+
+impl Permissions {
+    pub const NONE: Self = Self(0);
+    pub const INVITE: Self = Self(1);
+    pub const KICK: Self = Self(1 << 1);
+    /// Change the rank of lower-ranked members
+    pub const PROMOTE: Self = Self(1 << 2);
+    pub const MANAGE_RANKS: Self = Self(1 << 3);
+    pub const DEPOSIT: Self = Self(1 << 4);
+    pub const WITHDRAW: Self = Self(1 << 5);
+    pub const DISBAND: Self = Self(1 << 6);
+    pub const ALL: Self = Self((1 << 7) - 1);
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for Permissions {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildRank {
+    pub id: String,
+    pub name: String,
+    /// 0 for the guild master, higher for lower ranks
+    pub level: u8,
+    pub permissions: Permissions,
+    /// Items a member of the rank may withdraw from the bank a day, unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily_withdrawal_limit: Option<u32>,
+}
+
+impl GuildRank {
+    fn new(id: &str, name: &str, level: u8, permissions: Permissions, daily_withdrawal_limit: Option<u32>) -> Self {
+        Self { id: id.to_string(), name: name.to_string(), level, permissions, daily_withdrawal_limit }
+    }
+
+    /// Ranks of a new guild
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("guild_master", "Guild Master", 0, Permissions::ALL, None),
+            Self::new(
+                "officer",
+                "Officer",
+                1,
+                Permissions::INVITE | Permissions::KICK | Permissions::PROMOTE | Permissions::DEPOSIT | Permissions::WITHDRAW,
+                Some(50),
+            ),
+            Self::new("member", "Member", 2, Permissions::DEPOSIT | Permissions::WITHDRAW, Some(5)),
+            Self::new("recruit", "Recruit", 3, Permissions::DEPOSIT, Some(0)),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildMember {
+    pub actor_id: String,
+    pub rank_id: String,
+    pub joined_at: Timestamp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemStack {
+    pub item_id: String,
+    pub quantity: u32,
+}
+
+/// Tab of the guild bank holding up to `slots` stacks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BankTab {
+    pub name: String,
+    pub slots: usize,
+    #[serde(default)]
+    pub items: Vec<ItemStack>,
+}
+
+/// Items a member withdrew on a day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyWithdrawals {
+    pub day: NaiveDate,
+    pub items: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GuildProgression {
+    pub level: u32,
+    /// Experience earned since the guild was founded
+    pub experience: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Guild {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub name: String,
+    pub tag: String,
+    pub ranks: Vec<GuildRank>,
+    /// Members in the order they joined
+    pub members: Vec<GuildMember>,
+    pub bank: Vec<BankTab>,
+    #[serde(default)]
+    pub withdrawals: HashMap<String, DailyWithdrawals>,
+    pub progression: GuildProgression,
+    pub created_at: Timestamp,
+    /// Bumped by every save, so concurrent updates do not overwrite each other
+    #[serde(default)]
+    pub revision: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GuildError {
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("Database error: {0}")]
+    Database(#[from] mongodb::error::Error),
+}
+
+pub type GuildResult<T> = Result<T, GuildError>;
+
+impl Guild {
+    /// Found a guild with `founder` as its guild master
+    pub fn new(id: String, name: &str, tag: &str, founder: &str, config: &GuildConfig, now: Timestamp) -> GuildResult<Self> {
+        let name = name.trim();
+        if name.chars().count() < 3 || name.chars().count() > config.max_name_len {
+            return Err(GuildError::BadRequest(format!(
+                "Guild names are 3 to {} characters long",
+                config.max_name_len
+            )));
+        }
+        if !(2..=5).contains(&tag.len()) || !tag.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(GuildError::BadRequest("Guild tags are 2 to 5 letters or digits".to_string()));
+        }
+        let ranks = GuildRank::defaults();
+        Ok(Self {
+            id,
+            name: name.to_string(),
+            tag: tag.to_ascii_uppercase(),
+            members: vec![GuildMember { actor_id: founder.to_string(), rank_id: ranks[0].id.clone(), joined_at: now }],
+            ranks,
+            bank: config
+                .bank_tabs
+                .iter()
+                .map(|name| BankTab { name: name.clone(), slots: config.bank_tab_slots, items: Vec::new() })
+                .collect(),
+            withdrawals: HashMap::new(),
+            progression: GuildProgression { level: 1, experience: 0 },
+            created_at: now,
+            revision: 0,
+        })
+    }
+
+    pub fn member(&self, actor_id: &str) -> Option<&GuildMember> {
+        self.members.iter().find(|member| member.actor_id == actor_id)
+    }
+
+    pub fn rank(&self, rank_id: &str) -> Option<&GuildRank> {
+        self.ranks.iter().find(|rank| rank.id == rank_id)
+    }
+
+    /// Rank of a member
+    pub fn rank_of(&self, actor_id: &str) -> Option<&GuildRank> {
+        self.member(actor_id).and_then(|member| self.rank(&member.rank_id))
+    }
+
+    pub fn leader_id(&self) -> Option<&str> {
+        self.members
+            .iter()
+            .find(|member| self.rank(&member.rank_id).is_some_and(|rank| rank.level == 0))
+            .map(|member| member.actor_id.as_str())
+    }
+
+    /// Members the guild has room for at its level
+    pub fn max_members(&self, track: &ProgressionTrack) -> usize {
+        track.max_members(self.progression.level)
+    }
+
+    /// Check that `actor_id` is a member whose rank grants `permission`
+    pub fn require(&self, actor_id: &str, permission: Permissions) -> GuildResult<&GuildRank> {
+        let rank = self
+            .rank_of(actor_id)
+            .ok_or_else(|| GuildError::Forbidden(format!("{} is not a member of {}", actor_id, self.name)))?;
+        if !rank.permissions.contains(permission) {
+            return Err(GuildError::Forbidden(format!("Rank {} may not do this", rank.name)));
+        }
+        Ok(rank)
+    }
+
+    /// Add a member at the lowest rank
+    pub fn add_member(&mut self, by: &str, actor_id: &str, track: &ProgressionTrack, now: Timestamp) -> GuildResult<()> {
+        self.require(by, Permissions::INVITE)?;
+        if self.member(actor_id).is_some() {
+            return Err(GuildError::Conflict(format!("{} is already a member", actor_id)));
+        }
+        if self.members.len() >= self.max_members(track) {
+            return Err(GuildError::Conflict(format!("{} has no room for more members", self.name)));
+        }
+        let rank_id = self.lowest_rank().id.clone();
+        self.members.push(GuildMember { actor_id: actor_id.to_string(), rank_id, joined_at: now });
+        Ok(())
+    }
+
+    /// Remove a member: themselves, or a lower-ranked one by a member allowed to kick.
+    /// The guild master hands leadership over before leaving.
+    pub fn remove_member(&mut self, by: &str, actor_id: &str) -> GuildResult<()> {
+        let target = self
+            .rank_of(actor_id)
+            .ok_or_else(|| GuildError::NotFound(format!("{} is not a member of {}", actor_id, self.name)))?
+            .level;
+        if by == actor_id {
+            if target == 0 {
+                return Err(GuildError::Conflict("The guild master hands leadership over before leaving".to_string()));
+            }
+        } else if self.require(by, Permissions::KICK)?.level >= target {
+            return Err(GuildError::Forbidden("Only lower-ranked members can be removed".to_string()));
+        }
+        self.members.retain(|member| member.actor_id != actor_id);
+        self.withdrawals.remove(actor_id);
+        Ok(())
+    }
+
+    /// Change a lower-ranked member's rank to one below the caller's own
+    pub fn set_rank(&mut self, by: &str, actor_id: &str, rank_id: &str) -> GuildResult<()> {
+        let own = self.require(by, Permissions::PROMOTE)?.level;
+        let current = self
+            .rank_of(actor_id)
+            .ok_or_else(|| GuildError::NotFound(format!("{} is not a member of {}", actor_id, self.name)))?
+            .level;
+        let new = self
+            .rank(rank_id)
+            .ok_or_else(|| GuildError::BadRequest(format!("Unknown rank: {}", rank_id)))?
+            .level;
+        if current <= own || new <= own {
+            return Err(GuildError::Forbidden("Ranks can only be changed below one's own".to_string()));
+        }
+        self.set_member_rank(actor_id, rank_id);
+        Ok(())
+    }
+
+    /// Hand leadership to another member; the guild master takes the next rank down
+    pub fn transfer_leadership(&mut self, by: &str, actor_id: &str) -> GuildResult<()> {
+        if self.rank_of(by).map(|rank| rank.level) != Some(0) {
+            return Err(GuildError::Forbidden("Only the guild master can hand leadership over".to_string()));
+        }
+        if self.member(actor_id).is_none() {
+            return Err(GuildError::NotFound(format!("{} is not a member of {}", actor_id, self.name)));
+        }
+        let leader_rank = self.rank_of(by).map(|rank| rank.id.clone()).unwrap_or_default();
+        let next_rank = self.ranks.iter().filter(|rank| rank.level > 0).min_by_key(|rank| rank.level);
+        let next_rank = next_rank.map(|rank| rank.id.clone()).unwrap_or_else(|| leader_rank.clone());
+        self.set_member_rank(by, &next_rank);
+        self.set_member_rank(actor_id, &leader_rank);
+        Ok(())
+    }
+
+    /// Replace the rank hierarchy; members of removed ranks drop to the lowest rank
+    pub fn set_ranks(&mut self, by: &str, ranks: Vec<GuildRank>) -> GuildResult<()> {
+        self.require(by, Permissions::MANAGE_RANKS)?;
+        validate_ranks(&ranks)?;
+        let leader_id = self.leader_id().map(str::to_string);
+        self.ranks = ranks;
+        let lowest = self.lowest_rank().id.clone();
+        let master = self.ranks.iter().find(|rank| rank.level == 0).map(|rank| rank.id.clone()).unwrap_or_default();
+        for member in &mut self.members {
+            if Some(&member.actor_id) == leader_id.as_ref() {
+                member.rank_id = master.clone();
+            } else if !self.ranks.iter().any(|rank| rank.id == member.rank_id && rank.level > 0) {
+                member.rank_id = lowest.clone();
+            }
+        }
+        Ok(())
+    }
+
+    /// Put items into a bank tab, stacking them with the same item
+    pub fn deposit(&mut self, by: &str, tab: usize, item: ItemStack) -> GuildResult<()> {
+        self.require(by, Permissions::DEPOSIT)?;
+        if item.quantity == 0 {
+            return Err(GuildError::BadRequest("Nothing to deposit".to_string()));
+        }
+        let tab = self.tab_mut(tab)?;
+        match tab.items.iter().position(|stack| stack.item_id == item.item_id) {
+            Some(index) => tab.items[index].quantity = tab.items[index].quantity.saturating_add(item.quantity),
+            None if tab.items.len() < tab.slots => tab.items.push(item),
+            None => return Err(GuildError::Conflict(format!("Bank tab {} is full", tab.name))),
+        }
+        Ok(())
+    }
+
+    /// Take items out of a bank tab, within the daily limit of the caller's rank
+    pub fn withdraw(&mut self, by: &str, tab: usize, item: ItemStack, today: NaiveDate) -> GuildResult<()> {
+        let limit = self.require(by, Permissions::WITHDRAW)?.daily_withdrawal_limit;
+        let withdrawn = match self.withdrawals.get(by) {
+            Some(withdrawals) if withdrawals.day == today => withdrawals.items,
+            _ => 0,
+        };
+        if let Some(limit) = limit {
+            if withdrawn.saturating_add(item.quantity) > limit {
+                return Err(GuildError::Forbidden(format!(
+                    "Withdrawal limit reached: {} of {} items left today",
+                    limit.saturating_sub(withdrawn),
+                    limit
+                )));
+            }
+        }
+
+        let tab = self.tab_mut(tab)?;
+        let position = tab
+            .items
+            .iter()
+            .position(|stack| stack.item_id == item.item_id && stack.quantity >= item.quantity)
+            .ok_or_else(|| GuildError::Conflict(format!("Not enough {} in bank tab {}", item.item_id, tab.name)))?;
+        tab.items[position].quantity -= item.quantity;
+        if tab.items[position].quantity == 0 {
+            tab.items.remove(position);
+        }
+        self.withdrawals
+            .insert(by.to_string(), DailyWithdrawals { day: today, items: withdrawn + item.quantity });
+        Ok(())
+    }
+
+    /// Add guild experience, returning the levels gained
+    pub fn add_experience(&mut self, amount: u64, track: &ProgressionTrack) -> u32 {
+        let before = self.progression.level;
+        self.progression.experience = self.progression.experience.saturating_add(amount);
+        self.progression.level = track.level_for(self.progression.experience);
+        self.progression.level - before
+    }
+
+    fn lowest_rank(&self) -> &GuildRank {
+        self.ranks.iter().max_by_key(|rank| rank.level).unwrap_or(&self.ranks[0])
+    }
+
+    fn set_member_rank(&mut self, actor_id: &str, rank_id: &str) {
+        if let Some(member) = self.members.iter_mut().find(|member| member.actor_id == actor_id) {
+            member.rank_id = rank_id.to_string();
+        }
+    }
+
+    fn tab_mut(&mut self, tab: usize) -> GuildResult<&mut BankTab> {
+        self.bank
+            .get_mut(tab)
+            .ok_or_else(|| GuildError::NotFound(format!("Bank tab not found: {}", tab)))
+    }
+}
+
+/// A hierarchy needs unique IDs and exactly one guild master rank holding every permission
+fn validate_ranks(ranks: &[GuildRank]) -> GuildResult<()> {
+    let mut ids = std::collections::HashSet::new();
+    if let Some(rank) = ranks.iter().find(|rank| rank.id.trim().is_empty() || !ids.insert(rank.id.as_str())) {
+        return Err(GuildError::BadRequest(format!("Rank IDs must be unique and not empty: '{}'", rank.id)));
+    }
+    let masters: Vec<&GuildRank> = ranks.iter().filter(|rank| rank.level == 0).collect();
+    match masters.as_slice() {
+        [master] if master.permissions == Permissions::ALL => Ok(()),
+        _ => Err(GuildError::BadRequest("Exactly one rank of level 0 with every permission is required".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn now() -> Timestamp {
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    }
+
+    fn guild() -> (Guild, GuildConfig) {
+        let config = GuildConfig::default();
+        let mut guild = Guild::new("g1".to_string(), "Azure Lotus", "lotus", "master", &config, now()).unwrap();
+        for actor_id in ["officer", "member", "recruit"] {
+            guild.add_member("master", actor_id, &config.progression, now()).unwrap();
+        }
+        guild.set_rank("master", "officer", "officer").unwrap();
+        guild.set_rank("officer", "member", "member").unwrap();
+        (guild, config)
+    }
+
+    #[test]
+    fn ranks_gate_what_members_may_do() {
+        let (mut guild, _) = guild();
+        assert_eq!(guild.tag, "LOTUS");
+        assert_eq!(guild.rank_of("recruit").unwrap().id, "recruit");
+
+        // Officers act below their own rank only
+        assert!(matches!(guild.set_rank("officer", "member", "officer"), Err(GuildError::Forbidden(_))));
+        assert!(matches!(guild.remove_member("member", "recruit"), Err(GuildError::Forbidden(_))));
+        assert!(matches!(guild.remove_member("officer", "master"), Err(GuildError::Forbidden(_))));
+        guild.remove_member("officer", "recruit").unwrap();
+        assert!(guild.member("recruit").is_none());
+
+        // The guild master hands over before leaving
+        assert!(matches!(guild.remove_member("master", "master"), Err(GuildError::Conflict(_))));
+        guild.transfer_leadership("master", "officer").unwrap();
+        assert_eq!(guild.leader_id(), Some("officer"));
+        assert_eq!(guild.rank_of("master").unwrap().id, "officer");
+        guild.remove_member("master", "master").unwrap();
+    }
+
+    #[test]
+    fn withdrawals_are_limited_per_rank_and_day() {
+        let (mut guild, _) = guild();
+        let herbs = |quantity| ItemStack { item_id: "spirit_herb".to_string(), quantity };
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        guild.deposit("recruit", 0, herbs(20)).unwrap();
+        guild.deposit("member", 0, herbs(5)).unwrap();
+        assert_eq!(guild.bank[0].items, vec![herbs(25)]);
+
+        assert!(matches!(guild.withdraw("recruit", 0, herbs(1), today), Err(GuildError::Forbidden(_))));
+        guild.withdraw("member", 0, herbs(3), today).unwrap();
+        assert!(matches!(guild.withdraw("member", 0, herbs(3), today), Err(GuildError::Forbidden(_))));
+        guild.withdraw("member", 0, herbs(3), today.succ_opt().unwrap()).unwrap();
+        guild.withdraw("master", 0, herbs(19), today).unwrap();
+        assert!(guild.bank[0].items.is_empty());
+        assert!(matches!(guild.withdraw("master", 0, herbs(1), today), Err(GuildError::Conflict(_))));
+    }
+
+    #[test]
+    fn rank_hierarchies_keep_a_single_master() {
+        let (mut guild, _) = guild();
+        let mut ranks = GuildRank::defaults();
+        ranks.retain(|rank| rank.id != "officer");
+        guild.set_ranks("master", ranks.clone()).unwrap();
+        assert_eq!(guild.rank_of("officer").unwrap().id, "recruit");
+
+        ranks[1].level = 0;
+        assert!(matches!(guild.set_ranks("master", ranks), Err(GuildError::BadRequest(_))));
+        assert!(matches!(guild.set_ranks("member", GuildRank::defaults()), Err(GuildError::Forbidden(_))));
+    }
+
+    #[test]
+    fn experience_raises_the_level_and_member_cap() {
+        let (mut guild, config) = guild();
+        let track = &config.progression;
+        let cap = guild.max_members(track);
+        assert_eq!(guild.add_experience(track.level_experience[0] - 1, track), 0);
+        assert_eq!(guild.add_experience(1, track), 1);
+        assert_eq!(guild.progression.level, 2);
+        assert!(guild.max_members(track) > cap);
+    }
+}
@@ -0,0 +1,272 @@
+//! HTTP API for players, reached through the gateway, and for game servers.
+//!
+//! Players act as the character in the trusted `x-actor-id` header the gateway sets
+//! after validating their token. Game servers award guild experience with the internal
+//! API key.
+
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, Path, State};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post, put};
+use axum::{Json, Router};
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::config::GuildConfig;
+use crate::guilds::{Guild, GuildError, GuildRank, GuildResult, ItemStack, Permissions};
+use crate::store::{is_duplicate_key, GuildStore};
+
+/// Trusted gateway header carrying the character the user plays
+const ACTOR_ID_HEADER: &str = "x-actor-id";
+
+/// Header game servers authenticate with
+const INTERNAL_API_KEY_HEADER: &str = "x-internal-api-key";
+
+/// Times an update is retried after losing a race with another one
+const MAX_SAVE_ATTEMPTS: usize = 5;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub guilds: GuildStore,
+    pub config: Arc<GuildConfig>,
+    /// Key game servers send; experience cannot be awarded without one
+    pub internal_api_key: Option<String>,
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/guilds", post(create_guild))
+        .route("/guilds/mine", get(my_guild))
+        .route("/guilds/:id", get(get_guild).delete(disband_guild))
+        .route("/guilds/:id/members", post(add_member))
+        .route("/guilds/:id/members/:actor_id", delete(remove_member))
+        .route("/guilds/:id/members/:actor_id/rank", put(set_rank))
+        .route("/guilds/:id/leader", post(transfer_leadership))
+        .route("/guilds/:id/ranks", put(set_ranks))
+        .route("/guilds/:id/bank/:tab/deposit", post(deposit))
+        .route("/guilds/:id/bank/:tab/withdraw", post(withdraw))
+        .route("/guilds/:id/experience", post(add_experience))
+        .with_state(state)
+}
+
+impl IntoResponse for GuildError {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            GuildError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            GuildError::NotFound(_) => StatusCode::NOT_FOUND,
+            GuildError::Conflict(_) => StatusCode::CONFLICT,
+            GuildError::Forbidden(_) => StatusCode::FORBIDDEN,
+            GuildError::Database(e) => {
+                tracing::error!("Database error: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        (status, Json(json!({ "success": false, "error": self.to_string() }))).into_response()
+    }
+}
+
+/// Character calling through the gateway
+pub struct Caller {
+    pub actor_id: String,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Caller {
+    type Rejection = (StatusCode, Json<Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        match parts.headers.get(ACTOR_ID_HEADER).and_then(|value| value.to_str().ok()) {
+            Some(actor_id) if !actor_id.is_empty() => Ok(Self { actor_id: actor_id.to_string() }),
+            _ => Err((
+                StatusCode::UNAUTHORIZED,
+                Json(json!({ "success": false, "error": "Character not authenticated" })),
+            )),
+        }
+    }
+}
+
+impl AppState {
+    async fn load(&self, id: &str) -> GuildResult<Guild> {
+        self.guilds
+            .get(id)
+            .await?
+            .ok_or_else(|| GuildError::NotFound(format!("Guild not found: {}", id)))
+    }
+
+    /// Apply a change to the latest state of a guild and save it, retrying when another
+    /// update got there first
+    async fn update<F>(&self, id: &str, mut change: F) -> GuildResult<Guild>
+    where
+        F: FnMut(&mut Guild) -> GuildResult<()>,
+    {
+        for _ in 0..MAX_SAVE_ATTEMPTS {
+            let mut guild = self.load(id).await?;
+            change(&mut guild)?;
+            match self.guilds.save(&mut guild).await {
+                Ok(true) => return Ok(guild),
+                Ok(false) => continue,
+                Err(e) if is_duplicate_key(&e) => {
+                    return Err(GuildError::Conflict("The character is already in a guild".to_string()))
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(GuildError::Conflict(format!("Guild {} is busy, try again", id)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateGuildRequest {
+    pub name: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemberRequest {
+    pub actor_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RankRequest {
+    pub rank_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExperienceRequest {
+    pub amount: u64,
+}
+
+/// Found a guild led by the caller
+async fn create_guild(
+    State(state): State<AppState>,
+    caller: Caller,
+    Json(request): Json<CreateGuildRequest>,
+) -> GuildResult<(StatusCode, Json<Guild>)> {
+    let guild = Guild::new(Uuid::new_v4().to_string(), &request.name, &request.tag, &caller.actor_id, &state.config, Utc::now())?;
+    match state.guilds.insert(&guild).await {
+        Ok(()) => Ok((StatusCode::CREATED, Json(guild))),
+        Err(e) if is_duplicate_key(&e) => Err(GuildError::Conflict(
+            "The name or tag is taken, or the character is already in a guild".to_string(),
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Guild of the calling character
+async fn my_guild(State(state): State<AppState>, caller: Caller) -> GuildResult<Json<Guild>> {
+    state
+        .guilds
+        .find_by_member(&caller.actor_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| GuildError::NotFound("The character is not in a guild".to_string()))
+}
+
+async fn get_guild(State(state): State<AppState>, Path(id): Path<String>) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.load(&id).await?))
+}
+
+async fn disband_guild(State(state): State<AppState>, caller: Caller, Path(id): Path<String>) -> GuildResult<StatusCode> {
+    state.load(&id).await?.require(&caller.actor_id, Permissions::DISBAND)?;
+    state.guilds.delete(&id).await?;
+    tracing::info!(guild_id = %id, by = %caller.actor_id, "Guild disbanded");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn add_member(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Json(request): Json<MemberRequest>,
+) -> GuildResult<Json<Guild>> {
+    let track = &state.config.progression;
+    let guild = state
+        .update(&id, |guild| guild.add_member(&caller.actor_id, &request.actor_id, track, Utc::now()))
+        .await?;
+    Ok(Json(guild))
+}
+
+/// Leave the guild, or remove a lower-ranked member from it
+async fn remove_member(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((id, actor_id)): Path<(String, String)>,
+) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.update(&id, |guild| guild.remove_member(&caller.actor_id, &actor_id)).await?))
+}
+
+async fn set_rank(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((id, actor_id)): Path<(String, String)>,
+    Json(request): Json<RankRequest>,
+) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.update(&id, |guild| guild.set_rank(&caller.actor_id, &actor_id, &request.rank_id)).await?))
+}
+
+async fn transfer_leadership(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Json(request): Json<MemberRequest>,
+) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.update(&id, |guild| guild.transfer_leadership(&caller.actor_id, &request.actor_id)).await?))
+}
+
+async fn set_ranks(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path(id): Path<String>,
+    Json(ranks): Json<Vec<GuildRank>>,
+) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.update(&id, |guild| guild.set_ranks(&caller.actor_id, ranks.clone())).await?))
+}
+
+async fn deposit(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((id, tab)): Path<(String, usize)>,
+    Json(item): Json<ItemStack>,
+) -> GuildResult<Json<Guild>> {
+    Ok(Json(state.update(&id, |guild| guild.deposit(&caller.actor_id, tab, item.clone())).await?))
+}
+
+async fn withdraw(
+    State(state): State<AppState>,
+    caller: Caller,
+    Path((id, tab)): Path<(String, usize)>,
+    Json(item): Json<ItemStack>,
+) -> GuildResult<Json<Guild>> {
+    let today = Utc::now().date_naive();
+    Ok(Json(state.update(&id, |guild| guild.withdraw(&caller.actor_id, tab, item.clone(), today)).await?))
+}
+
+/// Award guild experience; game servers only
+async fn add_experience(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+    Json(request): Json<ExperienceRequest>,
+) -> GuildResult<Json<Value>> {
+    let key = headers.get(INTERNAL_API_KEY_HEADER).and_then(|value| value.to_str().ok());
+    if state.internal_api_key.is_none() || key != state.internal_api_key.as_deref() {
+        return Err(GuildError::Forbidden("Guild experience is awarded by game servers".to_string()));
+    }
+    let track = &state.config.progression;
+    let mut levels_gained = 0;
+    let guild = state
+        .update(&id, |guild| {
+            levels_gained = guild.add_experience(request.amount, track);
+            Ok(())
+        })
+        .await?;
+    if levels_gained > 0 {
+        tracing::info!(guild_id = %id, level = guild.progression.level, "Guild levelled up");
+    }
+    Ok(Json(json!({ "progression": guild.progression, "levels_gained": levels_gained })))
+}
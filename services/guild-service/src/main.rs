@@ -1,26 +1,65 @@
+mod config;
+mod guilds;
+mod handlers;
+mod store;
+
 use axum::{
     routing::get,
     Router,
 };
+use config::Config;
+use handlers::AppState;
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
+use store::GuildStore;
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+    let guilds = GuildStore::new(&database);
+    if let Err(e) = guilds.ensure_indexes().await {
+        tracing::warn!("Failed to create indexes: {}", e);
+    }
+    if config.internal_api_key.is_none() {
+        tracing::warn!("No internal API key configured, guild experience cannot be awarded");
+    }
+
     // Create router
+    let state = AppState {
+        guilds,
+        config: Arc::new(config.guilds.clone()),
+        internal_api_key: config.internal_api_key.clone(),
+    };
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/", get(root))
+        .merge(handlers::routes(state));
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 guild-service server starting on {}", addr);
-    
+
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let server = axum::serve(listener, app).with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 guild-service stopped");
+    telemetry.shutdown();
 }
 
 async fn health_check() -> &'static str {
@@ -30,4 +69,3 @@ async fn health_check() -> &'static str {
 async fn root() -> &'static str {
     "Hello from guild-service!"
 }
-
@@ -0,0 +1,88 @@
+//! Guild persistence in MongoDB.
+//!
+//! A guild is one document, members and bank included. Saves are conditional on the
+//! revision the guild was read at, so two concurrent withdrawals cannot both spend the
+//! same stack; callers reload and retry when [`GuildStore::save`] reports a conflict.
+//! Unique indexes keep guild names and tags distinct and every actor in one guild.
+
+use mongodb::bson::doc;
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::IndexOptions;
+use mongodb::{Collection, Database, IndexModel};
+
+use crate::guilds::Guild;
+
+pub const COLLECTION: &str = "guilds";
+
+/// MongoDB's duplicate key error
+const DUPLICATE_KEY: i32 = 11000;
+
+#[derive(Clone)]
+pub struct GuildStore {
+    collection: Collection<Guild>,
+}
+
+impl GuildStore {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+
+    pub async fn ensure_indexes(&self) -> mongodb::error::Result<()> {
+        let unique = |name: &str| IndexOptions::builder().name(name.to_string()).unique(true).build();
+        let indexes = vec![
+            IndexModel::builder().keys(doc! { "name": 1 }).options(unique("name")).build(),
+            IndexModel::builder().keys(doc! { "tag": 1 }).options(unique("tag")).build(),
+            IndexModel::builder().keys(doc! { "members.actor_id": 1 }).options(unique("member")).build(),
+        ];
+        self.collection.create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    pub async fn insert(&self, guild: &Guild) -> mongodb::error::Result<()> {
+        self.collection.insert_one(guild, None).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> mongodb::error::Result<Option<Guild>> {
+        self.collection.find_one(doc! { "_id": id }, None).await
+    }
+
+    /// Guild an actor belongs to
+    pub async fn find_by_member(&self, actor_id: &str) -> mongodb::error::Result<Option<Guild>> {
+        self.collection.find_one(doc! { "members.actor_id": actor_id }, None).await
+    }
+
+    /// Store a guild read at `guild.revision`, bumping the revision; `false` when it was
+    /// changed since
+    pub async fn save(&self, guild: &mut Guild) -> mongodb::error::Result<bool> {
+        let read_at = guild.revision;
+        guild.revision += 1;
+        let result = self
+            .collection
+            .replace_one(doc! { "_id": &guild.id, "revision": read_at as i64 }, &*guild, None)
+            .await;
+        match result {
+            Ok(result) if result.matched_count == 1 => Ok(true),
+            Ok(_) => {
+                guild.revision = read_at;
+                Ok(false)
+            }
+            Err(e) => {
+                guild.revision = read_at;
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn delete(&self, id: &str) -> mongodb::error::Result<bool> {
+        Ok(self.collection.delete_one(doc! { "_id": id }, None).await?.deleted_count == 1)
+    }
+}
+
+/// Whether a write failed on one of the unique indexes
+pub fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
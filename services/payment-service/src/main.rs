@@ -3,12 +3,12 @@ use axum::{
     Router,
 };
 use std::net::SocketAddr;
-use tracing_subscriber;
+use shared::telemetry::{self, TelemetryOptions};
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
     
     // Create router
     let app = Router::new()
@@ -21,6 +21,7 @@ async fn main() {
     
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
+    telemetry.shutdown();
 }
 
 async fn health_check() -> &'static str {
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::fs;
+use std::net::IpAddr;
 
 /// User Management Service Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +13,20 @@ pub struct UserServiceConfig {
     pub password: PasswordConfig,
     pub rate_limiting: RateLimitingConfig,
     pub email: EmailConfig,
+    #[serde(default)]
+    pub password_reset: PasswordResetConfig,
+    #[serde(default)]
+    pub oauth: OAuthConfig,
+    #[serde(default)]
+    pub rbac: RbacConfig,
+    #[serde(default)]
+    pub internal_api: InternalApiConfig,
+    #[serde(default)]
+    pub login_security: LoginSecurityConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
 }
 
 /// Server configuration
@@ -83,6 +98,101 @@ pub struct EmailConfig {
     pub from_name: String,
 }
 
+/// Password reset configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordResetConfig {
+    pub token_expiry_seconds: u64,
+    /// Link sent by email, the token is appended as the `token` query parameter
+    pub reset_url: String,
+}
+
+/// OAuth2 / social login configuration, providers without a section are disabled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    /// Public base URL of this service, providers redirect to `<base>/auth/oauth/<provider>/callback`
+    pub callback_base_url: String,
+    pub state_expiry_seconds: u64,
+    pub google: Option<OAuthProviderConfig>,
+    pub discord: Option<OAuthProviderConfig>,
+    pub steam: Option<SteamConfig>,
+}
+
+/// OAuth2 client credentials of a provider
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthProviderConfig {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Steam OpenID configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SteamConfig {
+    /// Web API key used to fetch the player's persona name and avatar
+    pub api_key: Option<String>,
+}
+
+/// Role-based access control configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RbacConfig {
+    /// Usernames that always have the admin role, to bootstrap the first administrators
+    #[serde(default)]
+    pub bootstrap_admins: Vec<String>,
+}
+
+/// Account lockout and suspicious login detection configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginSecurityConfig {
+    pub lockout_enabled: bool,
+    /// Failed logins of an account within the window that lock it
+    pub account_failure_threshold: u32,
+    /// Failed logins from an IP within the window that lock it
+    pub ip_failure_threshold: u32,
+    pub failure_window_seconds: u64,
+    /// First lock duration, doubled by each further lock
+    pub base_lock_seconds: u64,
+    pub max_lock_seconds: u64,
+    /// Time without a lock after which lock durations start over
+    pub lock_decay_seconds: u64,
+    pub unlock_token_expiry_seconds: u64,
+    /// Link sent by email, the token is appended as the `token` query parameter
+    pub unlock_url: String,
+    /// Email users when they log in from a device not seen before
+    pub new_device_alerts: bool,
+    /// Base URL of the anti-cheat service receiving suspicious login reports, reports are off without it
+    pub anti_cheat_url: Option<String>,
+    pub anti_cheat_api_key: Option<String>,
+    /// Proxies (the API gateway) whose X-Forwarded-For is believed for IP lockouts, other
+    /// peers are locked by their socket address
+    #[serde(default)]
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// Security audit log configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Days audit events are kept before the TTL index removes them
+    pub retention_days: u64,
+}
+
+/// Maintenance mode, followed on the event bus; never enabled without `nats_url`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    /// NATS server `admin-cli world maintenance` publishes to
+    #[serde(default)]
+    pub nats_url: Option<String>,
+    /// Subject prefix shared with the other services
+    #[serde(default)]
+    pub subject_prefix: Option<String>,
+}
+
+/// Service-to-service API configuration, the internal API is disabled without keys
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InternalApiConfig {
+    /// Keys accepted in the `X-Internal-Api-Key` header, several keys allow rotation
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+}
+
 impl Default for UserServiceConfig {
     fn default() -> Self {
         Self {
@@ -93,6 +203,13 @@ impl Default for UserServiceConfig {
             password: PasswordConfig::default(),
             rate_limiting: RateLimitingConfig::default(),
             email: EmailConfig::default(),
+            password_reset: PasswordResetConfig::default(),
+            oauth: OAuthConfig::default(),
+            rbac: RbacConfig::default(),
+            internal_api: InternalApiConfig::default(),
+            login_security: LoginSecurityConfig::default(),
+            audit: AuditConfig::default(),
+            maintenance: MaintenanceConfig::default(),
         }
     }
 }
@@ -180,6 +297,55 @@ impl Default for EmailConfig {
     }
 }
 
+impl Default for PasswordResetConfig {
+    fn default() -> Self {
+        Self {
+            token_expiry_seconds: 3600, // 1 hour
+            reset_url: "http://localhost:3200/reset-password".to_string(),
+        }
+    }
+}
+
+impl Default for OAuthConfig {
+    fn default() -> Self {
+        Self {
+            callback_base_url: "http://localhost:8082".to_string(),
+            state_expiry_seconds: 600, // 10 minutes
+            google: None,
+            discord: None,
+            steam: None,
+        }
+    }
+}
+
+impl Default for LoginSecurityConfig {
+    fn default() -> Self {
+        Self {
+            lockout_enabled: true,
+            account_failure_threshold: 5,
+            ip_failure_threshold: 20,
+            failure_window_seconds: 900, // 15 minutes
+            base_lock_seconds: 60,
+            max_lock_seconds: 86400, // 1 day
+            lock_decay_seconds: 86400, // 1 day
+            unlock_token_expiry_seconds: 3600, // 1 hour
+            unlock_url: "http://localhost:3200/unlock-account".to_string(),
+            new_device_alerts: true,
+            anti_cheat_url: None,
+            anti_cheat_api_key: None,
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: 365,
+        }
+    }
+}
+
 impl UserServiceConfig {
     /// Load configuration from environment variables
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
@@ -285,11 +451,114 @@ impl UserServiceConfig {
                 from_name: env::var("EMAIL_FROM_NAME")
                     .unwrap_or_else(|_| "Chaos World".to_string()),
             },
+            password_reset: PasswordResetConfig {
+                token_expiry_seconds: env::var("PASSWORD_RESET_EXPIRY")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()?,
+                reset_url: env::var("PASSWORD_RESET_URL")
+                    .unwrap_or_else(|_| "http://localhost:3200/reset-password".to_string()),
+            },
+            oauth: OAuthConfig {
+                callback_base_url: env::var("OAUTH_CALLBACK_BASE_URL")
+                    .unwrap_or_else(|_| "http://localhost:8082".to_string()),
+                state_expiry_seconds: env::var("OAUTH_STATE_EXPIRY")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()?,
+                google: Self::oauth_provider_from_env("GOOGLE"),
+                discord: Self::oauth_provider_from_env("DISCORD"),
+                steam: match env::var("STEAM_OPENID_ENABLED").as_deref() {
+                    Ok("true") => Some(SteamConfig {
+                        api_key: env::var("STEAM_API_KEY").ok(),
+                    }),
+                    _ => None,
+                },
+            },
+            rbac: RbacConfig {
+                bootstrap_admins: env::var("RBAC_BOOTSTRAP_ADMINS")
+                    .map(|admins| {
+                        admins
+                            .split(',')
+                            .map(|admin| admin.trim().to_string())
+                            .filter(|admin| !admin.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            internal_api: InternalApiConfig {
+                api_keys: env::var("INTERNAL_API_KEYS")
+                    .map(|keys| {
+                        keys
+                            .split(',')
+                            .map(|key| key.trim().to_string())
+                            .filter(|key| !key.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            },
+            login_security: LoginSecurityConfig {
+                lockout_enabled: env::var("LOCKOUT_ENABLED")
+                    .map(|enabled| enabled != "false")
+                    .unwrap_or(true),
+                account_failure_threshold: env::var("LOCKOUT_ACCOUNT_THRESHOLD")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()?,
+                ip_failure_threshold: env::var("LOCKOUT_IP_THRESHOLD")
+                    .unwrap_or_else(|_| "20".to_string())
+                    .parse()?,
+                failure_window_seconds: env::var("LOCKOUT_WINDOW")
+                    .unwrap_or_else(|_| "900".to_string())
+                    .parse()?,
+                base_lock_seconds: env::var("LOCKOUT_BASE_SECONDS")
+                    .unwrap_or_else(|_| "60".to_string())
+                    .parse()?,
+                max_lock_seconds: env::var("LOCKOUT_MAX_SECONDS")
+                    .unwrap_or_else(|_| "86400".to_string())
+                    .parse()?,
+                lock_decay_seconds: env::var("LOCKOUT_DECAY_SECONDS")
+                    .unwrap_or_else(|_| "86400".to_string())
+                    .parse()?,
+                unlock_token_expiry_seconds: env::var("UNLOCK_TOKEN_EXPIRY")
+                    .unwrap_or_else(|_| "3600".to_string())
+                    .parse()?,
+                unlock_url: env::var("UNLOCK_URL")
+                    .unwrap_or_else(|_| "http://localhost:3200/unlock-account".to_string()),
+                new_device_alerts: env::var("NEW_DEVICE_ALERTS")
+                    .map(|enabled| enabled != "false")
+                    .unwrap_or(true),
+                anti_cheat_url: env::var("ANTI_CHEAT_URL").ok(),
+                anti_cheat_api_key: env::var("ANTI_CHEAT_API_KEY").ok(),
+                trusted_proxies: match env::var("TRUSTED_PROXIES") {
+                    Ok(proxies) => proxies
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|proxy| !proxy.is_empty())
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()?,
+                    Err(_) => Vec::new(),
+                },
+            },
+            audit: AuditConfig {
+                retention_days: env::var("AUDIT_RETENTION_DAYS")
+                    .unwrap_or_else(|_| "365".to_string())
+                    .parse()?,
+            },
+            maintenance: MaintenanceConfig {
+                nats_url: env::var("NATS_URL").ok(),
+                subject_prefix: env::var("NATS_SUBJECT_PREFIX").ok(),
+            },
         };
 
         Ok(config)
     }
 
+    /// OAuth2 client credentials from `<PREFIX>_CLIENT_ID` and `<PREFIX>_CLIENT_SECRET`
+    fn oauth_provider_from_env(prefix: &str) -> Option<OAuthProviderConfig> {
+        Some(OAuthProviderConfig {
+            client_id: env::var(format!("{}_CLIENT_ID", prefix)).ok()?,
+            client_secret: env::var(format!("{}_CLIENT_SECRET", prefix)).ok()?,
+        })
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> Result<(), Vec<String>> {
         let mut errors = Vec::new();
@@ -326,6 +595,51 @@ impl UserServiceConfig {
             errors.push("Password maximum length must be greater than minimum length".to_string());
         }
 
+        // Validate password reset config
+        if self.password_reset.token_expiry_seconds == 0 {
+            errors.push("Password reset token expiry must be greater than 0".to_string());
+        }
+        if self.rate_limiting.password_reset == 0 {
+            errors.push("Password reset rate limit must be greater than 0".to_string());
+        }
+
+        // Validate OAuth config
+        if self.oauth.state_expiry_seconds == 0 {
+            errors.push("OAuth state expiry must be greater than 0".to_string());
+        }
+        for (name, provider) in [("google", &self.oauth.google), ("discord", &self.oauth.discord)] {
+            if let Some(provider) = provider {
+                if provider.client_id.is_empty() || provider.client_secret.is_empty() {
+                    errors.push(format!("OAuth provider {} requires a client ID and secret", name));
+                }
+            }
+        }
+
+        // Validate login security config
+        let login_security = &self.login_security;
+        if login_security.account_failure_threshold == 0 || login_security.ip_failure_threshold == 0 {
+            errors.push("Lockout failure thresholds must be greater than 0".to_string());
+        }
+        if login_security.failure_window_seconds == 0 || login_security.base_lock_seconds == 0 {
+            errors.push("Lockout window and base lock duration must be greater than 0".to_string());
+        }
+        if login_security.max_lock_seconds < login_security.base_lock_seconds {
+            errors.push("Maximum lock duration must not be less than the base lock duration".to_string());
+        }
+        if login_security.unlock_token_expiry_seconds == 0 {
+            errors.push("Unlock token expiry must be greater than 0".to_string());
+        }
+
+        // Validate audit config
+        if self.audit.retention_days == 0 {
+            errors.push("Audit retention must be at least 1 day".to_string());
+        }
+
+        // Validate internal API config
+        if self.internal_api.api_keys.iter().any(|key| key.len() < 32) {
+            errors.push("Internal API keys must be at least 32 characters long".to_string());
+        }
+
         if errors.is_empty() {
             Ok(())
         } else {
@@ -1,12 +1,31 @@
 use mongodb::Database;
 use bson::doc;
 
+use crate::config::UserServiceConfig;
+
+/// Name of the TTL index implementing the audit retention policy
+const AUDIT_RETENTION_INDEX: &str = "audit_retention";
+
 /// Initialize MongoDB database with collections and indexes
-pub async fn initialize_database(database: &Database) -> Result<(), mongodb::error::Error> {
+pub async fn initialize_database(database: &Database, config: &UserServiceConfig) -> Result<(), mongodb::error::Error> {
     tracing::info!("Initializing MongoDB database...");
     
     // Create collections if they don't exist
-    let collections = ["users", "user_sessions", "user_preferences", "user_roles"];
+    let collections = [
+        "users",
+        "user_sessions",
+        "user_preferences",
+        "user_roles",
+        "user_permissions",
+        "password_reset_tokens",
+        "audit_events",
+        "oauth_states",
+        "oauth_accounts",
+        "character_links",
+        "login_lockouts",
+        "known_devices",
+        "account_unlock_tokens",
+    ];
     for collection_name in &collections {
         database.create_collection(collection_name, None).await?;
         tracing::info!("Created collection: {}", collection_name);
@@ -14,6 +33,7 @@ pub async fn initialize_database(database: &Database) -> Result<(), mongodb::err
     
     // Create indexes for better performance
     create_indexes(database).await?;
+    apply_audit_retention(database, config.audit.retention_days).await?;
     
     tracing::info!("MongoDB database initialization completed successfully");
     Ok(())
@@ -95,6 +115,23 @@ async fn create_indexes(database: &Database) -> Result<(), mongodb::error::Error
         None,
     ).await?;
     
+    // Session ID unique index (token session_id lookups)
+    sessions_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Active sessions of a user, most recently used first
+    sessions_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "is_active": 1, "last_accessed": -1 })
+            .build(),
+        None,
+    ).await?;
+    
     // Preferences collection indexes
     let prefs_collection = database.collection::<crate::models::UserPreferences>("user_preferences");
     
@@ -134,8 +171,218 @@ async fn create_indexes(database: &Database) -> Result<(), mongodb::error::Error
         None,
     ).await?;
     
+    // Permissions collection indexes
+    let permissions_collection = database.collection::<crate::models::UserPermission>("user_permissions");
+    
+    // User ID and active index
+    permissions_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "is_active": 1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // Password reset tokens collection indexes
+    let reset_tokens_collection = database.collection::<crate::models::PasswordResetToken>("password_reset_tokens");
+    
+    // Token hash unique index
+    reset_tokens_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // User ID and created at index (for per-account rate limiting)
+    reset_tokens_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "created_at": 1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // Expires at index (for TTL)
+    reset_tokens_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(mongodb::options::IndexOptions::builder().expire_after(Some(std::time::Duration::from_secs(0))).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Audit events collection indexes
+    let audit_collection = database.collection::<crate::models::AuditEvent>("audit_events");
+    
+    // User ID and created at index
+    audit_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "created_at": -1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // Event type and created at index
+    audit_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "event_type": 1, "created_at": -1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // OAuth states collection indexes
+    let oauth_states_collection = database.collection::<crate::models::OAuthState>("oauth_states");
+    
+    // State unique index
+    oauth_states_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "state": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Expires at index (for TTL)
+    oauth_states_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(mongodb::options::IndexOptions::builder().expire_after(Some(std::time::Duration::from_secs(0))).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // OAuth accounts collection indexes
+    let oauth_accounts_collection = database.collection::<crate::models::OAuthAccount>("oauth_accounts");
+    
+    // Provider account unique index
+    oauth_accounts_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "provider": 1, "provider_user_id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // User ID index
+    oauth_accounts_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // Character links collection indexes
+    let character_links_collection = database.collection::<crate::models::CharacterLink>("character_links");
+    
+    // World and actor unique index (a character has one owner)
+    character_links_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "world_id": 1, "actor_id": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // User ID and world index
+    character_links_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "world_id": 1 })
+            .build(),
+        None,
+    ).await?;
+    
+    // Login lockouts collection indexes
+    let lockouts_collection = database.collection::<crate::models::LoginLockout>("login_lockouts");
+    
+    // Key unique index
+    lockouts_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "key": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Expires at index (for TTL)
+    lockouts_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(mongodb::options::IndexOptions::builder().expire_after(Some(std::time::Duration::from_secs(0))).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Known devices collection indexes
+    let devices_collection = database.collection::<crate::models::KnownDevice>("known_devices");
+    
+    // User ID and device unique index
+    devices_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "user_id": 1, "device": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Account unlock tokens collection indexes
+    let unlock_tokens_collection = database.collection::<crate::models::AccountUnlockToken>("account_unlock_tokens");
+    
+    // Token hash unique index
+    unlock_tokens_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "token_hash": 1 })
+            .options(mongodb::options::IndexOptions::builder().unique(true).build())
+            .build(),
+        None,
+    ).await?;
+    
+    // Expires at index (for TTL)
+    unlock_tokens_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "expires_at": 1 })
+            .options(mongodb::options::IndexOptions::builder().expire_after(Some(std::time::Duration::from_secs(0))).build())
+            .build(),
+        None,
+    ).await?;
+    
     tracing::info!("Database indexes created successfully");
     Ok(())
 }
 
+/// Expire audit events after the retention period
+///
+/// The audit log is append-only, this TTL index is the only way events are removed.
+/// A changed retention updates the existing index in place.
+async fn apply_audit_retention(database: &Database, retention_days: u64) -> Result<(), mongodb::error::Error> {
+    let expire_after_seconds = retention_days * 24 * 60 * 60;
+    let audit_collection = database.collection::<crate::models::AuditEvent>("audit_events");
+    let result = audit_collection.create_index(
+        mongodb::IndexModel::builder()
+            .keys(doc! { "created_at": 1 })
+            .options(
+                mongodb::options::IndexOptions::builder()
+                    .name(AUDIT_RETENTION_INDEX.to_string())
+                    .expire_after(Some(std::time::Duration::from_secs(expire_after_seconds)))
+                    .build()
+            )
+            .build(),
+        None,
+    ).await;
+
+    match result {
+        Ok(_) => {}
+        // Index options conflict, the retention changed since the index was created
+        Err(e) if matches!(e.kind.as_ref(), mongodb::error::ErrorKind::Command(error) if error.code == 85) => {
+            database.run_command(doc! {
+                "collMod": "audit_events",
+                "index": { "name": AUDIT_RETENTION_INDEX, "expireAfterSeconds": expire_after_seconds as i64 }
+            }, None).await?;
+        }
+        Err(e) => return Err(e),
+    }
+
+    tracing::info!("Audit events are kept for {} days", retention_days);
+    Ok(())
+}
+
 // Unused utility functions removed for cleaner code
@@ -1,6 +1,13 @@
-use crate::models::{User, UserSession, UserPreferences};
+use crate::models::{
+    User, UserSession, UserPreferences, PasswordResetToken, AuditEvent, OAuthState, OAuthAccount,
+    UserRole, UserPermission, UserAccess, Role, CharacterLink, LoginLockout, KnownDevice, AccountUnlockToken,
+    AuditQueryParams,
+};
 use crate::config::UserServiceConfig;
 use mongodb::{Client, Database, Collection};
+use mongodb::options::FindOptions;
+use shared::query::mongo::to_mongo_query;
+use shared::query::ListQuery;
 use bson::doc;
 use uuid::Uuid;
 use chrono::Utc;
@@ -90,7 +97,8 @@ impl UserRepository {
                 "email_verified": user.email_verified,
                 "updated_at": user.updated_at.to_rfc3339(),
                 "last_login": user.last_login.map(|dt| dt.to_rfc3339()),
-                "login_count": user.login_count
+                "login_count": user.login_count,
+                "password_changed_at": user.password_changed_at.map(|dt| dt.to_rfc3339())
             }
         };
         
@@ -125,28 +133,29 @@ impl UserRepository {
         Ok(count > 0)
     }
 
-    /// Get all users with pagination
+    /// Get users matching a list query, with the total number of matches
     pub async fn get_users_paginated(
-        &self, 
-        page: u32, 
-        limit: u32
+        &self,
+        query: &ListQuery,
     ) -> Result<(Vec<User>, u64), mongodb::error::Error> {
-        let skip = (page - 1) * limit;
-        let total = self.collection.count_documents(None, None).await?;
-        
+        let mongo_query = to_mongo_query(query)
+            .map_err(|e| mongodb::error::Error::custom(e.to_string()))?;
+        let total = self.collection.count_documents(mongo_query.filter.clone(), None).await?;
+
+        let options = FindOptions::builder()
+            .sort(mongo_query.sort_option())
+            .skip(mongo_query.skip)
+            .limit(mongo_query.limit)
+            .build();
         let mut cursor = self.collection
-            .find(None, None)
+            .find(mongo_query.filter, options)
             .await?;
-        
+
         let mut users = Vec::new();
-        let mut count = 0;
         while cursor.advance().await? {
-            if count >= skip && users.len() < limit as usize {
-                users.push(cursor.deserialize_current()?);
-            }
-            count += 1;
+            users.push(cursor.deserialize_current()?);
         }
-        
+
         Ok((users, total))
     }
 }
@@ -176,7 +185,7 @@ impl SessionRepository {
 
     /// Find session by ID
     pub async fn find_by_id(&self, id: Uuid) -> Result<Option<UserSession>, mongodb::error::Error> {
-        let filter = doc! { "id": id.to_string() };
+        let filter = doc! { "id": uuid_as_binary(id) };
         let result = self.collection.find_one(filter, None).await?;
         Ok(result)
     }
@@ -193,7 +202,7 @@ impl SessionRepository {
 
     /// Update session
     pub async fn update_session(&self, session: &UserSession) -> Result<UserSession, mongodb::error::Error> {
-        let filter = doc! { "id": session.id.to_string() };
+        let filter = doc! { "id": uuid_as_binary(session.id) };
         let update = doc! { 
             "$set": {
                 "session_token": &session.session_token,
@@ -213,7 +222,7 @@ impl SessionRepository {
 
     /// Deactivate session
     pub async fn deactivate_session(&self, id: Uuid) -> Result<bool, mongodb::error::Error> {
-        let filter = doc! { "id": id.to_string() };
+        let filter = doc! { "id": uuid_as_binary(id) };
         let update = doc! { "$set": { "is_active": false } };
         let result = self.collection.update_one(filter, update, None).await?;
         Ok(result.modified_count > 0)
@@ -221,12 +230,63 @@ impl SessionRepository {
 
     /// Deactivate all sessions for user
     pub async fn deactivate_all_user_sessions(&self, user_id: Uuid) -> Result<u64, mongodb::error::Error> {
-        let filter = doc! { "user_id": user_id.to_string() };
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "is_active": true };
         let update = doc! { "$set": { "is_active": false } };
         let result = self.collection.update_many(filter, update, None).await?;
         Ok(result.modified_count)
     }
 
+    /// Find the active, unexpired sessions of a user, most recently used first
+    pub async fn find_active_user_sessions(&self, user_id: Uuid) -> Result<Vec<UserSession>, mongodb::error::Error> {
+        let filter = doc! {
+            "user_id": uuid_as_binary(user_id),
+            "is_active": true,
+            "expires_at": { "$gt": bson::DateTime::now() }
+        };
+        let options = FindOptions::builder()
+            .sort(doc! { "last_accessed": -1 })
+            .build();
+        let mut cursor = self.collection.find(filter, options).await?;
+
+        let mut sessions = Vec::new();
+        while cursor.advance().await? {
+            sessions.push(cursor.deserialize_current()?);
+        }
+        Ok(sessions)
+    }
+
+    /// Deactivate one session of a user
+    ///
+    /// Returns false when the session does not exist, belongs to another user or is already inactive.
+    pub async fn deactivate_user_session(&self, user_id: Uuid, id: Uuid) -> Result<bool, mongodb::error::Error> {
+        let filter = doc! {
+            "id": uuid_as_binary(id),
+            "user_id": uuid_as_binary(user_id),
+            "is_active": true
+        };
+        let update = doc! { "$set": { "is_active": false } };
+        let result = self.collection.update_one(filter, update, None).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Record that a session was used, with the client it was used from
+    pub async fn touch_session(
+        &self,
+        id: Uuid,
+        ip_address: Option<&str>,
+        user_agent: Option<&str>,
+    ) -> Result<(), mongodb::error::Error> {
+        let mut set = doc! { "last_accessed": bson::DateTime::now() };
+        if let Some(ip_address) = ip_address {
+            set.insert("ip_address", ip_address);
+        }
+        if let Some(user_agent) = user_agent {
+            set.insert("user_agent", user_agent);
+        }
+        self.collection.update_one(doc! { "id": uuid_as_binary(id) }, doc! { "$set": set }, None).await?;
+        Ok(())
+    }
+
     /// Clean up expired sessions
     pub async fn cleanup_expired_sessions(&self) -> Result<u64, mongodb::error::Error> {
         let filter = doc! { 
@@ -284,13 +344,505 @@ impl PreferencesRepository {
     }
 }
 
+/// Role repository for MongoDB operations on role and permission grants
+#[allow(dead_code)]
+pub struct RoleRepository {
+    roles: Collection<UserRole>,
+    permissions: Collection<UserPermission>,
+}
+
+#[allow(dead_code)]
+impl RoleRepository {
+    /// Create a new role repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            roles: database.collection::<UserRole>("user_roles"),
+            permissions: database.collection::<UserPermission>("user_permissions"),
+        }
+    }
+
+    /// Active, unexpired role grants of a user
+    pub async fn get_user_roles(&self, user_id: Uuid) -> Result<Vec<UserRole>, mongodb::error::Error> {
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "is_active": true };
+        let mut cursor = self.roles.find(filter, None).await?;
+
+        let now = Utc::now();
+        let mut roles = Vec::new();
+        while cursor.advance().await? {
+            let role: UserRole = cursor.deserialize_current()?;
+            if role.expires_at.map(|expires_at| expires_at > now).unwrap_or(true) {
+                roles.push(role);
+            }
+        }
+        Ok(roles)
+    }
+
+    /// Active, unexpired permission grants of a user
+    pub async fn get_user_permissions(&self, user_id: Uuid) -> Result<Vec<UserPermission>, mongodb::error::Error> {
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "is_active": true };
+        let mut cursor = self.permissions.find(filter, None).await?;
+
+        let now = Utc::now();
+        let mut permissions = Vec::new();
+        while cursor.advance().await? {
+            let permission: UserPermission = cursor.deserialize_current()?;
+            if permission.expires_at.map(|expires_at| expires_at > now).unwrap_or(true) {
+                permissions.push(permission);
+            }
+        }
+        Ok(permissions)
+    }
+
+    /// Roles and effective permissions of a user
+    pub async fn get_user_access(&self, user_id: Uuid) -> Result<UserAccess, mongodb::error::Error> {
+        let roles = self.get_user_roles(user_id).await?
+            .into_iter()
+            .filter_map(|grant| grant.role.parse::<Role>().ok());
+        let permissions = self.get_user_permissions(user_id).await?
+            .into_iter()
+            .map(|grant| grant.permission);
+        Ok(UserAccess::new(roles, permissions))
+    }
+
+    /// Grant a role, replacing an active grant of the same role
+    pub async fn grant_role(&self, grant: &UserRole) -> Result<(), mongodb::error::Error> {
+        self.revoke_role(grant.user_id, &grant.role).await?;
+        self.roles.insert_one(grant, None).await?;
+        Ok(())
+    }
+
+    /// Revoke a role, returning whether the user had it
+    pub async fn revoke_role(&self, user_id: Uuid, role: &str) -> Result<bool, mongodb::error::Error> {
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "role": role, "is_active": true };
+        let update = doc! { "$set": { "is_active": false } };
+        let result = self.roles.update_many(filter, update, None).await?;
+        Ok(result.modified_count > 0)
+    }
+
+    /// Grant a permission, replacing an active grant of the same permission
+    pub async fn grant_permission(&self, grant: &UserPermission) -> Result<(), mongodb::error::Error> {
+        self.revoke_permission(grant.user_id, &grant.permission).await?;
+        self.permissions.insert_one(grant, None).await?;
+        Ok(())
+    }
+
+    /// Revoke a permission, returning whether the user had it
+    pub async fn revoke_permission(&self, user_id: Uuid, permission: &str) -> Result<bool, mongodb::error::Error> {
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "permission": permission, "is_active": true };
+        let update = doc! { "$set": { "is_active": false } };
+        let result = self.permissions.update_many(filter, update, None).await?;
+        Ok(result.modified_count > 0)
+    }
+}
+
+/// Password reset token repository for MongoDB operations
+#[allow(dead_code)]
+pub struct PasswordResetRepository {
+    collection: Collection<PasswordResetToken>,
+}
+
+#[allow(dead_code)]
+impl PasswordResetRepository {
+    /// Create a new password reset token repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection::<PasswordResetToken>("password_reset_tokens"),
+        }
+    }
+
+    /// Store a new reset token
+    pub async fn create_token(&self, token: &PasswordResetToken) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(token, None).await?;
+        Ok(())
+    }
+
+    /// Count reset tokens issued to a user since the given time
+    pub async fn count_tokens_since(&self, user_id: Uuid, since: bson::DateTime) -> Result<u64, mongodb::error::Error> {
+        let filter = doc! {
+            "user_id": uuid_as_binary(user_id),
+            "created_at": { "$gte": since }
+        };
+        self.collection.count_documents(filter, None).await
+    }
+
+    /// Mark an unused, unexpired token as used and return it
+    ///
+    /// The lookup and update are a single atomic operation, so a token can only be
+    /// redeemed once even when requests race.
+    pub async fn consume_token(&self, token_hash: &str) -> Result<Option<PasswordResetToken>, mongodb::error::Error> {
+        let now = bson::DateTime::now();
+        let filter = doc! {
+            "token_hash": token_hash,
+            "used_at": null,
+            "expires_at": { "$gt": now }
+        };
+        let update = doc! { "$set": { "used_at": now } };
+        self.collection.find_one_and_update(filter, update, None).await
+    }
+
+    /// Mark all outstanding tokens of a user as used
+    pub async fn invalidate_user_tokens(&self, user_id: Uuid) -> Result<u64, mongodb::error::Error> {
+        let filter = doc! {
+            "user_id": uuid_as_binary(user_id),
+            "used_at": null
+        };
+        let update = doc! { "$set": { "used_at": bson::DateTime::now() } };
+        let result = self.collection.update_many(filter, update, None).await?;
+        Ok(result.modified_count)
+    }
+}
+
+/// OAuth repository for pending authorizations and linked provider accounts
+#[allow(dead_code)]
+pub struct OAuthRepository {
+    states: Collection<OAuthState>,
+    accounts: Collection<OAuthAccount>,
+}
+
+#[allow(dead_code)]
+impl OAuthRepository {
+    /// Create a new OAuth repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            states: database.collection::<OAuthState>("oauth_states"),
+            accounts: database.collection::<OAuthAccount>("oauth_accounts"),
+        }
+    }
+
+    /// Store a pending authorization
+    pub async fn create_state(&self, state: &OAuthState) -> Result<(), mongodb::error::Error> {
+        self.states.insert_one(state, None).await?;
+        Ok(())
+    }
+
+    /// Remove and return an unexpired pending authorization, so each state is used once
+    pub async fn consume_state(&self, provider: &str, state: &str) -> Result<Option<OAuthState>, mongodb::error::Error> {
+        let filter = doc! {
+            "state": state,
+            "provider": provider,
+            "expires_at": { "$gt": bson::DateTime::now() }
+        };
+        self.states.find_one_and_delete(filter, None).await
+    }
+
+    /// Find the link of a provider account
+    pub async fn find_account(&self, provider: &str, provider_user_id: &str) -> Result<Option<OAuthAccount>, mongodb::error::Error> {
+        let filter = doc! {
+            "provider": provider,
+            "provider_user_id": provider_user_id
+        };
+        self.accounts.find_one(filter, None).await
+    }
+
+    /// Link a provider account to a user
+    pub async fn create_account(&self, account: &OAuthAccount) -> Result<(), mongodb::error::Error> {
+        self.accounts.insert_one(account, None).await?;
+        Ok(())
+    }
+
+    /// Record a login through a linked provider account
+    pub async fn touch_account(&self, account: &OAuthAccount) -> Result<(), mongodb::error::Error> {
+        let filter = doc! {
+            "provider": &account.provider,
+            "provider_user_id": &account.provider_user_id
+        };
+        let update = doc! { "$set": { "last_login": Utc::now().to_rfc3339() } };
+        self.accounts.update_one(filter, update, None).await?;
+        Ok(())
+    }
+}
+
+/// Audit event repository for MongoDB operations
+#[allow(dead_code)]
+pub struct AuditRepository {
+    collection: Collection<AuditEvent>,
+}
+
+#[allow(dead_code)]
+impl AuditRepository {
+    /// Create a new audit event repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection::<AuditEvent>("audit_events"),
+        }
+    }
+
+    /// Record an audit event
+    ///
+    /// The audit log is append-only: there are no update or delete methods, events are
+    /// only removed by the retention TTL index.
+    pub async fn record(&self, event: &AuditEvent) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(event, None).await?;
+        Ok(())
+    }
+
+    /// Get audit events matching the query, newest first, with the total number of matches
+    pub async fn query(&self, params: &AuditQueryParams) -> Result<(Vec<AuditEvent>, u64), mongodb::error::Error> {
+        let mut filter = doc! {};
+        if let Some(user_id) = params.user_id {
+            filter.insert("user_id", user_id.to_string());
+        }
+        if let Some(event_type) = &params.event_type {
+            filter.insert("event_type", event_type);
+        }
+        if let Some(ip_address) = &params.ip_address {
+            filter.insert("ip_address", ip_address);
+        }
+        let mut created_at = doc! {};
+        if let Some(from) = params.from {
+            created_at.insert("$gte", bson::DateTime::from_chrono(from));
+        }
+        if let Some(to) = params.to {
+            created_at.insert("$lte", bson::DateTime::from_chrono(to));
+        }
+        if !created_at.is_empty() {
+            filter.insert("created_at", created_at);
+        }
+
+        let total = self.collection.count_documents(filter.clone(), None).await?;
+
+        let page = params.page_request();
+        let options = FindOptions::builder()
+            .sort(doc! { "created_at": -1 })
+            .skip(page.skip())
+            .limit(page.limit() as i64)
+            .build();
+        let mut cursor = self.collection.find(filter, options).await?;
+
+        let mut events = Vec::new();
+        while cursor.advance().await? {
+            events.push(cursor.deserialize_current()?);
+        }
+        Ok((events, total))
+    }
+}
+
+/// Character link repository for MongoDB operations
+#[allow(dead_code)]
+pub struct CharacterRepository {
+    collection: Collection<CharacterLink>,
+}
+
+#[allow(dead_code)]
+impl CharacterRepository {
+    /// Create a new character repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            collection: database.collection::<CharacterLink>("character_links"),
+        }
+    }
+
+    /// Link a character to a user, fails with a duplicate key error when the character is already linked
+    pub async fn link(&self, link: &CharacterLink) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(link, None).await?;
+        Ok(())
+    }
+
+    /// Remove a character link, only if it belongs to the given user when one is given
+    pub async fn unlink(&self, world_id: &str, actor_id: &str, user_id: Option<Uuid>) -> Result<bool, mongodb::error::Error> {
+        let mut filter = doc! { "world_id": world_id, "actor_id": actor_id };
+        if let Some(user_id) = user_id {
+            filter.insert("user_id", uuid_as_binary(user_id));
+        }
+        let result = self.collection.delete_one(filter, None).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Find the link of a character, which identifies the account owning it
+    pub async fn find_owner(&self, world_id: &str, actor_id: &str) -> Result<Option<CharacterLink>, mongodb::error::Error> {
+        let filter = doc! { "world_id": world_id, "actor_id": actor_id };
+        self.collection.find_one(filter, None).await
+    }
+
+    /// Find the characters of a user, in all worlds
+    pub async fn find_user_characters(&self, user_id: Uuid) -> Result<Vec<CharacterLink>, mongodb::error::Error> {
+        let options = FindOptions::builder()
+            .sort(doc! { "world_id": 1, "linked_at": 1 })
+            .build();
+        let mut cursor = self.collection.find(doc! { "user_id": uuid_as_binary(user_id) }, options).await?;
+
+        let mut characters = Vec::new();
+        while cursor.advance().await? {
+            characters.push(cursor.deserialize_current()?);
+        }
+        Ok(characters)
+    }
+
+    /// Count the characters of a user in a world
+    pub async fn count_user_characters(&self, user_id: Uuid, world_id: &str) -> Result<u64, mongodb::error::Error> {
+        let filter = doc! { "user_id": uuid_as_binary(user_id), "world_id": world_id };
+        self.collection.count_documents(filter, None).await
+    }
+}
+
+/// Lockout repository for failed login counters, known devices and unlock tokens
+#[allow(dead_code)]
+pub struct LockoutRepository {
+    lockouts: Collection<LoginLockout>,
+    devices: Collection<KnownDevice>,
+    unlock_tokens: Collection<AccountUnlockToken>,
+}
+
+#[allow(dead_code)]
+impl LockoutRepository {
+    /// Create a new lockout repository
+    pub fn new(database: &Database) -> Self {
+        Self {
+            lockouts: database.collection::<LoginLockout>("login_lockouts"),
+            devices: database.collection::<KnownDevice>("known_devices"),
+            unlock_tokens: database.collection::<AccountUnlockToken>("account_unlock_tokens"),
+        }
+    }
+
+    /// Find the lockout record of a key
+    pub async fn find(&self, key: &str) -> Result<Option<LoginLockout>, mongodb::error::Error> {
+        self.lockouts.find_one(doc! { "key": key }, None).await
+    }
+
+    /// Count a failed login and return the updated record
+    ///
+    /// Failures older than the window are dropped first, the increment itself is atomic.
+    pub async fn record_failure(
+        &self,
+        key: &str,
+        window_seconds: u64,
+        expires_at: bson::DateTime,
+    ) -> Result<Option<LoginLockout>, mongodb::error::Error> {
+        let now = bson::DateTime::now();
+        let window_start = bson::DateTime::from_millis(now.timestamp_millis() - (window_seconds as i64) * 1000);
+        self.lockouts.update_one(
+            doc! { "key": key, "window_started_at": { "$lt": window_start } },
+            doc! { "$set": { "failed_count": 0_i64, "window_started_at": now } },
+            None,
+        ).await?;
+
+        let update = doc! {
+            "$inc": { "failed_count": 1_i64 },
+            "$max": { "expires_at": expires_at },
+            "$setOnInsert": {
+                "window_started_at": now,
+                "lock_level": 0_i64,
+                "locked_until": null,
+                "last_locked_at": null
+            }
+        };
+        let options = mongodb::options::FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(mongodb::options::ReturnDocument::After)
+            .build();
+        self.lockouts.find_one_and_update(doc! { "key": key }, update, options).await
+    }
+
+    /// Lock a key until the given time and start a new failure window
+    pub async fn lock(
+        &self,
+        key: &str,
+        lock_level: i64,
+        locked_until: bson::DateTime,
+        expires_at: bson::DateTime,
+    ) -> Result<(), mongodb::error::Error> {
+        let now = bson::DateTime::now();
+        let update = doc! {
+            "$set": {
+                "failed_count": 0_i64,
+                "window_started_at": now,
+                "lock_level": lock_level,
+                "locked_until": locked_until,
+                "last_locked_at": now
+            },
+            "$max": { "expires_at": expires_at }
+        };
+        self.lockouts.update_one(doc! { "key": key }, update, None).await?;
+        Ok(())
+    }
+
+    /// Forget failed logins after a successful one, earlier locks still escalate later ones
+    pub async fn clear_failures(&self, key: &str) -> Result<(), mongodb::error::Error> {
+        let update = doc! { "$set": { "failed_count": 0_i64 } };
+        self.lockouts.update_one(doc! { "key": key }, update, None).await?;
+        Ok(())
+    }
+
+    /// Lift a lock and reset its escalation
+    pub async fn unlock(&self, key: &str) -> Result<bool, mongodb::error::Error> {
+        let result = self.lockouts.delete_one(doc! { "key": key }, None).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    /// Count the devices a user has logged in from
+    pub async fn count_devices(&self, user_id: Uuid) -> Result<u64, mongodb::error::Error> {
+        self.devices.count_documents(doc! { "user_id": uuid_as_binary(user_id) }, None).await
+    }
+
+    /// Record a login from a device, returns true when the device was not known yet
+    pub async fn remember_device(
+        &self,
+        user_id: Uuid,
+        device: &str,
+        ip_address: Option<&str>,
+    ) -> Result<bool, mongodb::error::Error> {
+        let now = bson::DateTime::now();
+        let update = doc! {
+            "$set": { "last_seen": now, "last_ip": ip_address },
+            "$setOnInsert": { "first_seen": now }
+        };
+        let options = mongodb::options::UpdateOptions::builder().upsert(true).build();
+        let result = self.devices
+            .update_one(doc! { "user_id": uuid_as_binary(user_id), "device": device }, update, options)
+            .await?;
+        Ok(result.upserted_id.is_some())
+    }
+
+    /// Store a new unlock token
+    pub async fn create_unlock_token(&self, token: &AccountUnlockToken) -> Result<(), mongodb::error::Error> {
+        self.unlock_tokens.insert_one(token, None).await?;
+        Ok(())
+    }
+
+    /// Mark an unused, unexpired unlock token as used and return it, atomically
+    pub async fn consume_unlock_token(&self, token_hash: &str) -> Result<Option<AccountUnlockToken>, mongodb::error::Error> {
+        let now = bson::DateTime::now();
+        let filter = doc! {
+            "token_hash": token_hash,
+            "used_at": null,
+            "expires_at": { "$gt": now }
+        };
+        let update = doc! { "$set": { "used_at": now } };
+        self.unlock_tokens.find_one_and_update(filter, update, None).await
+    }
+}
+
+/// Check whether an error is a unique index violation
+pub fn is_duplicate_key_error(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(write_error)) if write_error.code == 11000
+    )
+}
+
+/// UUID in the binary representation used for user IDs
+fn uuid_as_binary(id: Uuid) -> bson::Bson {
+    bson::Bson::Binary(bson::Binary {
+        subtype: bson::spec::BinarySubtype::UuidOld,
+        bytes: id.as_bytes().to_vec(),
+    })
+}
+
 /// Database connection manager for MongoDB
 #[allow(dead_code)]
 pub struct DatabaseManager {
     pub user_repo: UserRepository,
     pub session_repo: SessionRepository,
     pub preferences_repo: PreferencesRepository,
+    pub reset_token_repo: PasswordResetRepository,
+    pub audit_repo: AuditRepository,
+    pub oauth_repo: OAuthRepository,
+    pub role_repo: RoleRepository,
+    pub character_repo: CharacterRepository,
+    pub lockout_repo: LockoutRepository,
     pub database: Database,
+    /// Client the database was opened with, kept to shut it down
+    pub client: Client,
 }
 
 #[allow(dead_code)]
@@ -304,7 +856,14 @@ impl DatabaseManager {
             user_repo: UserRepository::new(&database),
             session_repo: SessionRepository::new(&database),
             preferences_repo: PreferencesRepository::new(&database),
+            reset_token_repo: PasswordResetRepository::new(&database),
+            audit_repo: AuditRepository::new(&database),
+            oauth_repo: OAuthRepository::new(&database),
+            role_repo: RoleRepository::new(&database),
+            character_repo: CharacterRepository::new(&database),
+            lockout_repo: LockoutRepository::new(&database),
             database,
+            client,
         })
     }
 
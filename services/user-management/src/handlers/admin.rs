@@ -0,0 +1,427 @@
+use axum::{
+    extract::{ConnectInfo, Json, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::UserServiceConfig;
+use crate::database::DatabaseManager;
+use crate::handlers::auth::{load_access, record_audit};
+use crate::metrics::METRICS;
+use crate::middleware::auth::CurrentUser;
+use crate::models::{
+    is_valid_permission, AdminAssignRoleRequest, AdminGrantPermissionRequest, AdminUpdateUserStatusRequest,
+    AuditEvent, AuditQueryParams, ErrorResponse, InternalSanctionRequest, PaginationInfo, Role, SuccessResponse, User, UserPermission,
+    UserRole, UserStatus,
+};
+use crate::utils::request::ClientInfo;
+
+type HandlerError = (StatusCode, ResponseJson<Value>);
+
+/// Get a user's role and permission grants
+pub async fn get_user_access(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path(user_id): Path<Uuid>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/admin/users/access", 200);
+
+    let user = find_user(&db_manager, user_id).await?;
+    let roles = db_manager.role_repo.get_user_roles(user_id).await.map_err(database_error)?;
+    let permissions = db_manager.role_repo.get_user_permissions(user_id).await.map_err(database_error)?;
+    let effective = load_access(&config, &db_manager, &user).await;
+
+    let role_grants: Vec<Value> = roles.iter().map(|grant| json!({
+        "role": grant.role,
+        "granted_by": grant.granted_by,
+        "granted_at": grant.granted_at,
+        "expires_at": grant.expires_at
+    })).collect();
+    let permission_grants: Vec<Value> = permissions.iter().map(|grant| json!({
+        "permission": grant.permission,
+        "granted_by": grant.granted_by,
+        "granted_at": grant.granted_at,
+        "expires_at": grant.expires_at
+    })).collect();
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "user_id": user_id,
+        "role_grants": role_grants,
+        "permission_grants": permission_grants,
+        "effective": effective
+    })))
+}
+
+/// Grant a role to a user
+pub async fn grant_role(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(actor): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<AdminAssignRoleRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/admin/users/roles", 200);
+
+    validate(&payload)?;
+    let role = parse_role(&payload.role)?;
+    if role == Role::Player {
+        return Err(bad_request("Every user has the player role"));
+    }
+    check_expiry(payload.expires_at)?;
+    find_user(&db_manager, user_id).await?;
+
+    let grant = UserRole {
+        id: Uuid::new_v4(),
+        user_id,
+        role: role.to_string(),
+        granted_by: Some(actor.user_id),
+        granted_at: Utc::now(),
+        expires_at: payload.expires_at,
+        is_active: true,
+    };
+    db_manager.role_repo.grant_role(&grant).await.map_err(database_error)?;
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "role_granted",
+        Some(user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("role={} by={}", role, actor.user_id))).await;
+    tracing::info!("Role {} granted to user {} by {}", role, user_id, actor.user_id);
+
+    let response = SuccessResponse::new("Role granted, it applies from the user's next login or token refresh");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Revoke a role from a user
+pub async fn revoke_role(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(actor): CurrentUser,
+    Path((user_id, role)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("DELETE", "/admin/users/roles", 200);
+
+    let role = parse_role(&role)?;
+    if role == Role::Player {
+        return Err(bad_request("The player role cannot be revoked"));
+    }
+    if role == Role::Admin && user_id == actor.user_id {
+        return Err(bad_request("Administrators cannot revoke their own admin role"));
+    }
+
+    let revoked = db_manager.role_repo
+        .revoke_role(user_id, role.as_str())
+        .await
+        .map_err(database_error)?;
+    if !revoked {
+        let error_response = ErrorResponse::new("User does not have this role");
+        return Err((StatusCode::NOT_FOUND, ResponseJson(json!(error_response))));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "role_revoked",
+        Some(user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("role={} by={}", role, actor.user_id))).await;
+    tracing::info!("Role {} revoked from user {} by {}", role, user_id, actor.user_id);
+
+    let response = SuccessResponse::new("Role revoked, it applies from the user's next login or token refresh");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Grant a permission to a user
+pub async fn grant_permission(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(actor): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<AdminGrantPermissionRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/admin/users/permissions", 200);
+
+    validate(&payload)?;
+    if !is_valid_permission(&payload.permission) {
+        return Err(bad_request("Permissions must look like resource:action"));
+    }
+    check_expiry(payload.expires_at)?;
+    find_user(&db_manager, user_id).await?;
+
+    let grant = UserPermission {
+        id: Uuid::new_v4(),
+        user_id,
+        permission: payload.permission.clone(),
+        granted_by: Some(actor.user_id),
+        granted_at: Utc::now(),
+        expires_at: payload.expires_at,
+        is_active: true,
+    };
+    db_manager.role_repo.grant_permission(&grant).await.map_err(database_error)?;
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "permission_granted",
+        Some(user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("permission={} by={}", payload.permission, actor.user_id))).await;
+    tracing::info!("Permission {} granted to user {} by {}", payload.permission, user_id, actor.user_id);
+
+    let response = SuccessResponse::new("Permission granted, it applies from the user's next login or token refresh");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Revoke a permission from a user
+pub async fn revoke_permission(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(actor): CurrentUser,
+    Path((user_id, permission)): Path<(Uuid, String)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("DELETE", "/admin/users/permissions", 200);
+
+    let revoked = db_manager.role_repo
+        .revoke_permission(user_id, &permission)
+        .await
+        .map_err(database_error)?;
+    if !revoked {
+        let error_response = ErrorResponse::new("User does not have this permission");
+        return Err((StatusCode::NOT_FOUND, ResponseJson(json!(error_response))));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "permission_revoked",
+        Some(user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("permission={} by={}", permission, actor.user_id))).await;
+    tracing::info!("Permission {} revoked from user {} by {}", permission, user_id, actor.user_id);
+
+    let response = SuccessResponse::new("Permission revoked, it applies from the user's next login or token refresh");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Change a user's status, e.g. to ban or suspend them
+///
+/// Banned, suspended and deactivated users are logged out everywhere.
+pub async fn update_user_status(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(actor): CurrentUser,
+    Path(user_id): Path<Uuid>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<AdminUpdateUserStatusRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("PUT", "/admin/users/status", 200);
+
+    validate(&payload)?;
+    let status = match payload.status.parse::<UserStatus>() {
+        Ok(status) if status != UserStatus::PendingVerification => status,
+        _ => {
+            let error_response = ErrorResponse::with_details("Invalid status", "Valid statuses: active, inactive, suspended, banned");
+            return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+        }
+    };
+    if user_id == actor.user_id {
+        return Err(bad_request("Administrators cannot change their own status"));
+    }
+
+    let mut user = find_user(&db_manager, user_id).await?;
+    let (previous_status, revoked_sessions) = change_status(&db_manager, &mut user, status.clone()).await?;
+
+    let event_type = match status {
+        UserStatus::Banned => "user_banned",
+        UserStatus::Suspended => "user_suspended",
+        _ => "user_status_changed",
+    };
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        event_type,
+        Some(user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!(
+        "status={}->{} by={} sessions_revoked={} reason={}",
+        previous_status, status, actor.user_id, revoked_sessions, payload.reason
+    ))).await;
+    tracing::info!("User {} status changed from {} to {} by {}", user_id, previous_status, status, actor.user_id);
+
+    let response = SuccessResponse::new("User status updated");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Internal: apply an anti-cheat sanction to an account
+///
+/// Warnings and mutes are only recorded, chat enforces mutes itself; suspensions and
+/// bans log the user out everywhere. Reinstating only reactivates suspended or banned
+/// accounts, so it cannot undo an unrelated deactivation.
+pub async fn internal_apply_sanction(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<InternalSanctionRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/internal/users/sanctions", 200);
+
+    validate(&payload)?;
+    let (event_type, status) = match payload.action.as_str() {
+        "warn" => ("user_warned", None),
+        "mute" => ("user_muted", None),
+        "unmute" => ("user_unmuted", None),
+        "suspend" => ("user_suspended", Some(UserStatus::Suspended)),
+        "ban" => ("user_banned", Some(UserStatus::Banned)),
+        "reinstate" => ("user_reinstated", Some(UserStatus::Active)),
+        _ => {
+            let error_response = ErrorResponse::with_details(
+                "Invalid sanction",
+                "Valid actions: warn, mute, unmute, suspend, ban, reinstate",
+            );
+            return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+        }
+    };
+
+    let mut user = find_user(&db_manager, user_id).await?;
+    let mut details = format!("action={} by=internal reason={}", payload.action, payload.reason);
+    let reinstating_inactive = status == Some(UserStatus::Active)
+        && !matches!(user.status, UserStatus::Suspended | UserStatus::Banned);
+    if let Some(status) = status.filter(|_| !reinstating_inactive) {
+        let (previous_status, revoked_sessions) = change_status(&db_manager, &mut user, status).await?;
+        details.push_str(&format!(
+            " status={}->{} sessions_revoked={}",
+            previous_status, user.status, revoked_sessions
+        ));
+    }
+    if let Some(reference) = &payload.reference {
+        details.push_str(&format!(" reference={}", reference));
+    }
+    if let Some(expires_at) = payload.expires_at {
+        details.push_str(&format!(" expires_at={}", expires_at.to_rfc3339()));
+    }
+
+    record_audit(&db_manager, AuditEvent::new(event_type, Some(user_id), None, None).with_details(&details)).await;
+    tracing::info!("Sanction {} applied to user {}", payload.action, user_id);
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "user_id": user_id,
+        "status": user.status
+    })))
+}
+
+/// Query the security audit log
+pub async fn list_audit_events(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Query(params): Query<AuditQueryParams>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/admin/audit-events", 200);
+
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return Err(bad_request("The start of the time range must be before its end"));
+        }
+    }
+
+    let (events, total) = db_manager.audit_repo.query(&params).await.map_err(database_error)?;
+    let page = params.page_request();
+    let page_number = page.skip().unwrap_or(0) / page.limit() as u64 + 1;
+
+    let data: Vec<Value> = events.iter().map(|event| json!({
+        "id": event.id,
+        "event_type": event.event_type,
+        "user_id": event.user_id,
+        "ip_address": event.ip_address,
+        "user_agent": event.user_agent,
+        "details": event.details,
+        "created_at": event.created_at.to_chrono()
+    })).collect();
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "data": data,
+        "pagination": PaginationInfo::new(page_number as u32, page.limit(), total)
+    })))
+}
+
+/// Set a user's status, logging them out everywhere unless they are reactivated
+///
+/// Returns the previous status and how many sessions were revoked.
+async fn change_status(
+    db_manager: &DatabaseManager,
+    user: &mut User,
+    status: UserStatus,
+) -> Result<(UserStatus, u64), HandlerError> {
+    let previous_status = std::mem::replace(&mut user.status, status);
+    user.updated_at = Utc::now();
+    db_manager.user_repo.update_user(user).await.map_err(database_error)?;
+
+    let revoked_sessions = if user.status == UserStatus::Active {
+        0
+    } else {
+        db_manager.session_repo
+            .deactivate_all_user_sessions(user.id)
+            .await
+            .map_err(database_error)?
+    };
+    Ok((previous_status, revoked_sessions))
+}
+
+async fn find_user(db_manager: &DatabaseManager, user_id: Uuid) -> Result<User, HandlerError> {
+    match db_manager.user_repo.find_by_id(user_id).await.map_err(database_error)? {
+        Some(user) => Ok(user),
+        None => {
+            let error_response = ErrorResponse::new("User not found");
+            Err((StatusCode::NOT_FOUND, ResponseJson(json!(error_response))))
+        }
+    }
+}
+
+fn parse_role(role: &str) -> Result<Role, HandlerError> {
+    role.parse::<Role>().map_err(|_| {
+        let roles: Vec<&str> = Role::ALL.iter().map(|role| role.as_str()).collect();
+        let error_response = ErrorResponse::with_details("Invalid role", &format!("Valid roles: {}", roles.join(", ")));
+        (StatusCode::BAD_REQUEST, ResponseJson(json!(error_response)))
+    })
+}
+
+fn check_expiry(expires_at: Option<chrono::DateTime<Utc>>) -> Result<(), HandlerError> {
+    match expires_at {
+        Some(expires_at) if expires_at <= Utc::now() => Err(bad_request("Expiry must be in the future")),
+        _ => Ok(()),
+    }
+}
+
+fn validate(payload: &impl Validate) -> Result<(), HandlerError> {
+    payload.validate().map_err(|validation_errors| {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details("Validation failed", &error_messages.join(", "));
+        (StatusCode::BAD_REQUEST, ResponseJson(json!(error_response)))
+    })
+}
+
+fn bad_request(error: &str) -> HandlerError {
+    let error_response = ErrorResponse::new(error);
+    (StatusCode::BAD_REQUEST, ResponseJson(json!(error_response)))
+}
+
+fn database_error(e: mongodb::error::Error) -> HandlerError {
+    tracing::error!("Database error: {}", e);
+    let error_response = ErrorResponse::new("Internal server error");
+    (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response)))
+}
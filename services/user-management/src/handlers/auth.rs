@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Json, State, Request, ConnectInfo},
+    extract::{Json, State, ConnectInfo},
     http::{StatusCode, HeaderMap},
     response::Json as ResponseJson,
 };
@@ -12,14 +12,16 @@ use validator::Validate;
 
 use crate::config::UserServiceConfig;
 use crate::models::{
-    RegisterRequest, LoginRequest, RefreshTokenRequest, 
+    RegisterRequest, LoginRequest, RefreshTokenRequest, ForgotPasswordRequest, ResetPasswordRequest, ChangePasswordRequest,
     AuthResponse, ErrorResponse, SuccessResponse, UserProfileResponse,
-    User, PublicUser, UserStatus, TokenClaims
+    User, PublicUser, UserStatus, PasswordResetToken, AuditEvent, UserAccess, Role
 };
-use crate::services::AuthService;
+use crate::services::{admitted_during_maintenance, AuthService, EmailService, LoginSecurityService, MAINTENANCE};
 use crate::database::DatabaseManager;
 use crate::metrics::METRICS;
 use crate::utils::request::ClientInfo;
+use crate::middleware::auth::CurrentUser;
+use crate::handlers::lockout::{check_login_device, check_login_lock, record_failed_login, record_successful_login};
 
 /// User registration handler
 pub async fn register(
@@ -36,6 +38,16 @@ pub async fn register(
     tracing::info!("Registration request from IP: {:?}, User-Agent: {:?}", 
                    client_info.ip_address, client_info.user_agent);
     
+    // New accounts wait for maintenance to end
+    let maintenance = MAINTENANCE.get();
+    if maintenance.enabled {
+        let error_response = ErrorResponse::new(maintenance.notice());
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
     // Validate request
     if let Err(validation_errors) = payload.validate() {
         let error_messages: Vec<String> = validation_errors
@@ -132,10 +144,11 @@ pub async fn register(
         updated_at: Utc::now(),
         last_login: None,
         login_count: 0,
+        password_changed_at: None,
     };
 
     // Create session
-    let session = match auth_service.create_session(user.id, client_info.ip_address, client_info.user_agent) {
+    let session = match auth_service.create_session(user.id, client_info.ip_address.clone(), client_info.user_agent.clone()) {
         Ok(session) => session,
         Err(e) => {
             tracing::error!("Failed to create session: {}", e);
@@ -148,7 +161,8 @@ pub async fn register(
     };
 
     // Generate tokens
-    let tokens = match auth_service.generate_tokens(&user, session.id) {
+    let access = load_access(&config, &db_manager, &user).await;
+    let tokens = match auth_service.generate_tokens(&user, session.id, &access) {
         Ok(tokens) => tokens,
         Err(e) => {
             tracing::error!("Failed to generate tokens: {}", e);
@@ -184,15 +198,26 @@ pub async fn register(
             tracing::info!("Session saved successfully to database: {:?}", saved_session);
         }
         Err(e) => {
+            // Tokens are only accepted for stored sessions, the user can log in once the database recovers
             tracing::error!("Failed to save session to database: {}", e);
             tracing::error!("Session data that failed to save: {:?}", session);
-            // Continue anyway, user is created
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
         }
     }
 
     // Record successful registration
     METRICS.record_registration();
     METRICS.record_auth_attempt("register", "success");
+    record_audit(&db_manager, AuditEvent::new(
+        "user_registered",
+        Some(saved_user.id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&saved_user.username)).await;
 
     let response = AuthResponse {
         success: true,
@@ -235,6 +260,11 @@ pub async fn login(
         ));
     }
 
+    // Refuse logins from an IP locked after too many failures
+    if let Some(ip_address) = client_info.lockout_ip(&config.login_security.trusted_proxies) {
+        check_login_lock(&config, &db_manager, &LoginSecurityService::ip_key(&ip_address)).await?;
+    }
+
     // Create auth service
     let auth_service = match AuthService::new(config.clone()) {
         Ok(service) => service,
@@ -257,6 +287,8 @@ pub async fn login(
         },
         Ok(None) => {
             tracing::warn!("User not found: {}", payload.username_or_email);
+            record_failed_login(&config, &db_manager, None, &client_info).await;
+            record_login_failure(&db_manager, None, &client_info, "unknown_user").await;
             let error_response = ErrorResponse::new("Invalid username or password");
             return Err((
                 StatusCode::UNAUTHORIZED,
@@ -273,8 +305,16 @@ pub async fn login(
         }
     };
 
+    // Refuse logins to an account locked after too many failures, before checking the password
+    if let Err(error) = check_login_lock(&config, &db_manager, &LoginSecurityService::account_key(user.id)).await {
+        record_login_failure(&db_manager, Some(user.id), &client_info, "locked").await;
+        return Err(error);
+    }
+
     // Verify password
     if !auth_service.verify_password(&payload.password, &user.password_hash).unwrap_or(false) {
+        record_failed_login(&config, &db_manager, Some(&user), &client_info).await;
+        record_login_failure(&db_manager, Some(user.id), &client_info, "bad_password").await;
         let error_response = ErrorResponse::new("Invalid username or password");
         return Err((
             StatusCode::UNAUTHORIZED,
@@ -284,6 +324,7 @@ pub async fn login(
 
     // Check if user is active
     if !auth_service.is_user_active(&user) {
+        record_login_failure(&db_manager, Some(user.id), &client_info, &user.status.to_string()).await;
         let error_response = ErrorResponse::new("Account is not active");
         return Err((
             StatusCode::UNAUTHORIZED,
@@ -291,6 +332,47 @@ pub async fn login(
         ));
     }
 
+    let failed_attempts = record_successful_login(&config, &db_manager, &user).await;
+    check_login_device(&config, &db_manager, &user, &client_info, failed_attempts).await;
+    record_audit(&db_manager, AuditEvent::new(
+        "login_succeeded",
+        Some(user.id),
+        client_info.ip_address.clone(),
+        client_info.user_agent.clone(),
+    ).with_details("method=password")).await;
+
+    let response = complete_login(&config, &auth_service, &db_manager, user, client_info).await?;
+
+    // Record successful login
+    METRICS.record_login();
+    METRICS.record_auth_attempt("login", "success");
+
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Start a session for an authenticated user and issue its tokens
+///
+/// Shared by every login method, so downstream services get the same tokens
+/// however the user authenticated.
+pub async fn complete_login(
+    config: &UserServiceConfig,
+    auth_service: &AuthService,
+    db_manager: &DatabaseManager,
+    user: User,
+    client_info: ClientInfo,
+) -> Result<AuthResponse, (StatusCode, ResponseJson<Value>)> {
+    // Only staff log in while maintenance is on
+    let access = load_access(config, db_manager, &user).await;
+    let maintenance = MAINTENANCE.get();
+    if maintenance.refuses(admitted_during_maintenance(&access)) {
+        tracing::info!("Login of {} refused during maintenance", user.username);
+        let error_response = ErrorResponse::new(maintenance.notice());
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
     // Create session
     let session = match auth_service.create_session(user.id, client_info.ip_address, client_info.user_agent) {
         Ok(session) => session,
@@ -305,7 +387,7 @@ pub async fn login(
     };
 
     // Generate tokens
-    let tokens = match auth_service.generate_tokens(&user, session.id) {
+    let tokens = match auth_service.generate_tokens(&user, session.id, &access) {
         Ok(tokens) => tokens,
         Err(e) => {
             tracing::error!("Failed to generate tokens: {}", e);
@@ -318,7 +400,7 @@ pub async fn login(
     };
 
     // Update user login info
-    let mut updated_user = user.clone();
+    let mut updated_user = user;
     updated_user.last_login = Some(Utc::now());
     updated_user.login_count += 1;
     updated_user.updated_at = Utc::now();
@@ -331,39 +413,47 @@ pub async fn login(
     // Save session to database
     tracing::info!("Saving session to database: {:?}", session);
     if let Err(e) = db_manager.session_repo.create_session(&session).await {
+        // Tokens are only accepted for stored sessions
         tracing::error!("Failed to save session to database: {}", e);
-        // Continue anyway
-    } else {
-        tracing::info!("Session saved successfully to database");
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ResponseJson(json!(error_response))
+        ));
     }
+    tracing::info!("Session saved successfully to database");
 
-    // Record successful login
-    METRICS.record_login();
-    METRICS.record_auth_attempt("login", "success");
-
-    let response = AuthResponse {
+    Ok(AuthResponse {
         success: true,
         user: PublicUser::from(updated_user),
         tokens,
-    };
+    })
+}
 
-    Ok(ResponseJson(json!(response)))
+/// Roles and permissions to embed in a user's tokens
+///
+/// Falls back to the player role when grants cannot be loaded.
+pub async fn load_access(config: &UserServiceConfig, db_manager: &DatabaseManager, user: &User) -> UserAccess {
+    let access = db_manager.role_repo
+        .get_user_access(user.id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to load roles of user {}: {}", user.id, e);
+            UserAccess::default()
+        });
+
+    if config.rbac.bootstrap_admins.contains(&user.username) {
+        access.with_role(Role::Admin)
+    } else {
+        access
+    }
 }
 
 /// Get current user profile handler
 pub async fn me(
     State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
-    request: Request,
-    // User claims will be extracted by the auth middleware
+    CurrentUser(claims): CurrentUser,
 ) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
-    // Extract user claims from request extensions (set by auth middleware)
-    let claims = request.extensions().get::<TokenClaims>()
-        .ok_or_else(|| {
-            tracing::error!("No token claims found in request extensions");
-            let error_response = ErrorResponse::new("User not authenticated");
-            (StatusCode::UNAUTHORIZED, ResponseJson(json!(error_response)))
-        })?;
-
     tracing::info!("Token claims found - Looking for user with ID: {}", claims.user_id);
 
     // Find user in database by ID from token claims
@@ -390,7 +480,9 @@ pub async fn me(
 
 /// Refresh token handler
 pub async fn refresh_token(
-    State((config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     Json(payload): Json<RefreshTokenRequest>,
 ) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
     // Validate request
@@ -435,27 +527,58 @@ pub async fn refresh_token(
         }
     };
 
-    // TODO: Find user by ID in database
-    // TODO: Check if session is still active
-    // For now, we'll create a mock user
-
-    let user = User {
-        id: claims.user_id,
-        username: claims.username.clone(),
-        email: claims.email.clone(),
-        password_hash: "hashed_password".to_string(),
-        display_name: Some("Test User".to_string()),
-        avatar_url: None,
-        status: UserStatus::Active,
-        email_verified: true,
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-        last_login: Some(Utc::now()),
-        login_count: 1,
+    // Find user by ID in database
+    let user = match db_manager.user_repo.find_by_id(claims.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            let error_response = ErrorResponse::new("Invalid refresh token");
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                ResponseJson(json!(error_response))
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
     };
 
+    // Reject tokens of inactive users and tokens issued before a password reset
+    if !auth_service.is_user_active(&user) || auth_service.is_refresh_token_revoked(&user, &claims) {
+        let error_response = ErrorResponse::new("Invalid refresh token");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Reject tokens of sessions that were logged out or revoked
+    match db_manager.session_repo.find_by_id(claims.session_id).await {
+        Ok(Some(session)) if session.is_active && session.user_id == user.id && session.expires_at > Utc::now() => {}
+        Ok(_) => {
+            let error_response = ErrorResponse::new("Invalid refresh token");
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                ResponseJson(json!(error_response))
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    }
+
     // Generate new tokens
-    let tokens = match auth_service.generate_tokens(&user, claims.session_id) {
+    let access = load_access(&config, &db_manager, &user).await;
+    let tokens = match auth_service.generate_tokens(&user, claims.session_id, &access) {
         Ok(tokens) => tokens,
         Err(e) => {
             tracing::error!("Failed to generate tokens: {}", e);
@@ -467,7 +590,14 @@ pub async fn refresh_token(
         }
     };
 
-    // TODO: Update session in database
+    // Record the refresh as session activity
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    if let Err(e) = db_manager.session_repo
+        .touch_session(claims.session_id, client_info.ip_address.as_deref(), client_info.user_agent.as_deref())
+        .await
+    {
+        tracing::error!("Failed to update session: {}", e);
+    }
 
     let response = json!({
         "success": true,
@@ -477,13 +607,19 @@ pub async fn refresh_token(
     Ok(ResponseJson(response))
 }
 
-/// User logout handler
+/// User logout handler, ends the session of the presented token
 pub async fn logout(
-    State((_config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
-    // TODO: Add authentication middleware to extract user from token
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
 ) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
-    // TODO: Extract user from JWT token
-    // TODO: Invalidate session in database
+    if let Err(e) = db_manager.session_repo.deactivate_user_session(claims.user_id, claims.session_id).await {
+        tracing::error!("Failed to deactivate session: {}", e);
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ResponseJson(json!(error_response))
+        ));
+    }
 
     let response = SuccessResponse::new("Logged out successfully");
     Ok(ResponseJson(json!(response)))
@@ -491,16 +627,496 @@ pub async fn logout(
 
 /// User logout from all devices handler
 pub async fn logout_all(
-    State((_config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
-    // TODO: Add authentication middleware to extract user from token
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
 ) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
-    // TODO: Extract user from JWT token
-    // TODO: Invalidate all sessions for user in database
+    let revoked_sessions = match db_manager.session_repo.deactivate_all_user_sessions(claims.user_id).await {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to deactivate sessions: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "logout_all",
+        Some(claims.user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("{} sessions revoked", revoked_sessions))).await;
 
     let response = SuccessResponse::new("Logged out from all devices");
     Ok(ResponseJson(json!(response)))
 }
 
+/// Forgot password handler
+///
+/// Always answers with the same message so the endpoint cannot be used to find out
+/// which emails have an account.
+pub async fn forgot_password(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
+    // Record HTTP request
+    METRICS.record_http_request("POST", "/auth/forgot-password", 200);
+    
+    // Extract client information
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    
+    // Validate request
+    if let Err(validation_errors) = payload.validate() {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details(
+            "Validation failed",
+            &error_messages.join(", ")
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    let response = SuccessResponse::new("If an account exists for this email, a password reset link has been sent");
+
+    // Find user by email, unknown and inactive accounts get the same answer
+    let user = match db_manager.user_repo.find_by_email(&payload.email).await {
+        Ok(Some(user)) if matches!(user.status, UserStatus::Active) => user,
+        Ok(_) => {
+            tracing::info!("Password reset requested for unknown or inactive account");
+            record_audit(&db_manager, AuditEvent::new(
+                "password_reset_requested",
+                None,
+                client_info.ip_address,
+                client_info.user_agent,
+            ).with_details("no active account")).await;
+            METRICS.record_password_operation("reset_request", "unknown_account");
+            return Ok(ResponseJson(json!(response)));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Limit reset emails per account, on top of the per-IP limit
+    if config.rate_limiting.enabled {
+        let window_start = bson::DateTime::from_millis(
+            bson::DateTime::now().timestamp_millis() - (config.rate_limiting.window_seconds as i64) * 1000
+        );
+        let recent = db_manager.reset_token_repo
+            .count_tokens_since(user.id, window_start)
+            .await
+            .unwrap_or(0);
+        if recent >= config.rate_limiting.password_reset as u64 {
+            tracing::warn!("Password reset limit reached for user: {}", user.id);
+            record_audit(&db_manager, AuditEvent::new(
+                "password_reset_throttled",
+                Some(user.id),
+                client_info.ip_address,
+                client_info.user_agent,
+            )).await;
+            METRICS.record_password_operation("reset_request", "throttled");
+            return Ok(ResponseJson(json!(response)));
+        }
+    }
+
+    // Create auth service
+    let auth_service = match AuthService::new(config.clone()) {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::error!("Failed to create auth service: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Only the newest reset link stays valid
+    if let Err(e) = db_manager.reset_token_repo.invalidate_user_tokens(user.id).await {
+        tracing::error!("Failed to invalidate previous reset tokens: {}", e);
+    }
+
+    let (token, token_hash) = auth_service.generate_reset_token();
+    let now = bson::DateTime::now();
+    let reset_token = PasswordResetToken {
+        id: Uuid::new_v4(),
+        user_id: user.id,
+        token_hash,
+        expires_at: bson::DateTime::from_millis(
+            now.timestamp_millis() + (config.password_reset.token_expiry_seconds as i64) * 1000
+        ),
+        created_at: now,
+        used_at: None,
+        ip_address: client_info.ip_address.clone(),
+    };
+
+    if let Err(e) = db_manager.reset_token_repo.create_token(&reset_token).await {
+        tracing::error!("Failed to save password reset token: {}", e);
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Send the reset link, failures are not revealed to the caller
+    let email_service = EmailService::new(config.clone());
+    let details = match email_service.send_password_reset(&user.email, &user.username, &token).await {
+        Ok(()) => {
+            METRICS.record_password_operation("reset_request", "success");
+            "email sent".to_string()
+        }
+        Err(e) => {
+            tracing::error!("Failed to send password reset email to user {}: {}", user.id, e);
+            METRICS.record_password_operation("reset_request", "email_failed");
+            format!("email delivery failed: {}", e)
+        }
+    };
+
+    record_audit(&db_manager, AuditEvent::new(
+        "password_reset_requested",
+        Some(user.id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&details)).await;
+
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Reset password handler
+pub async fn reset_password(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
+    // Record HTTP request
+    METRICS.record_http_request("POST", "/auth/reset-password", 200);
+    
+    // Extract client information
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    
+    // Validate request
+    if let Err(validation_errors) = payload.validate() {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details(
+            "Validation failed",
+            &error_messages.join(", ")
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Create auth service
+    let auth_service = match AuthService::new(config.clone()) {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::error!("Failed to create auth service: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Validate password strength before spending the token
+    if let Err(password_errors) = auth_service.validate_password_strength(&payload.new_password) {
+        let error_response = ErrorResponse::with_details(
+            "Password does not meet requirements",
+            &password_errors.join(", ")
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Redeem the token, it cannot be used again afterwards
+    let token_hash = AuthService::hash_reset_token(&payload.token);
+    let reset_token = match db_manager.reset_token_repo.consume_token(&token_hash).await {
+        Ok(Some(reset_token)) => reset_token,
+        Ok(None) => {
+            record_audit(&db_manager, AuditEvent::new(
+                "password_reset_failed",
+                None,
+                client_info.ip_address,
+                client_info.user_agent,
+            ).with_details("invalid or expired token")).await;
+            METRICS.record_password_operation("reset", "invalid_token");
+            let error_response = ErrorResponse::new("Invalid or expired reset token");
+            return Err((
+                StatusCode::BAD_REQUEST,
+                ResponseJson(json!(error_response))
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    let user = match db_manager.user_repo.find_by_id(reset_token.user_id).await {
+        Ok(Some(user)) if auth_service.is_user_active(&user) => user,
+        Ok(_) => {
+            let error_response = ErrorResponse::new("Invalid or expired reset token");
+            return Err((
+                StatusCode::BAD_REQUEST,
+                ResponseJson(json!(error_response))
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Hash password
+    let password_hash = match auth_service.hash_password(&payload.new_password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::error!("Failed to hash password: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Update password, refresh tokens issued before now stop working
+    let now = Utc::now();
+    let mut updated_user = user.clone();
+    updated_user.password_hash = password_hash;
+    updated_user.password_changed_at = Some(now);
+    updated_user.updated_at = now;
+
+    if let Err(e) = db_manager.user_repo.update_user(&updated_user).await {
+        tracing::error!("Failed to update password: {}", e);
+        METRICS.record_password_operation("reset", "error");
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Sign out everywhere and drop other outstanding reset links
+    let revoked_sessions = db_manager.session_repo
+        .deactivate_all_user_sessions(user.id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to deactivate sessions: {}", e);
+            0
+        });
+    if let Err(e) = db_manager.reset_token_repo.invalidate_user_tokens(user.id).await {
+        tracing::error!("Failed to invalidate reset tokens: {}", e);
+    }
+    // Whoever reset the password owns the account, so a lockout no longer protects it
+    if let Err(e) = db_manager.lockout_repo.unlock(&LoginSecurityService::account_key(user.id)).await {
+        tracing::error!("Failed to clear account lockout: {}", e);
+    }
+
+    record_audit(&db_manager, AuditEvent::new(
+        "password_reset_completed",
+        Some(user.id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("{} sessions revoked", revoked_sessions))).await;
+    METRICS.record_password_operation("reset", "success");
+
+    let response = SuccessResponse::new("Password has been reset, please log in again");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Change password handler, for a signed-in user who knows the current password
+pub async fn change_password(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<ResponseJson<Value>, (StatusCode, ResponseJson<Value>)> {
+    // Record HTTP request
+    METRICS.record_http_request("POST", "/auth/change-password", 200);
+
+    // Validate request
+    if let Err(validation_errors) = payload.validate() {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details(
+            "Validation failed",
+            &error_messages.join(", ")
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    let auth_service = match AuthService::new(config.clone()) {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::error!("Failed to create auth service: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    let user = match db_manager.user_repo.find_by_id(claims.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            let error_response = ErrorResponse::new("User not found");
+            return Err((
+                StatusCode::NOT_FOUND,
+                ResponseJson(json!(error_response))
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    if !auth_service.verify_password(&payload.current_password, &user.password_hash).unwrap_or(false) {
+        METRICS.record_password_operation("change", "invalid");
+        record_audit(&db_manager, AuditEvent::new(
+            "password_change_failed",
+            Some(user.id),
+            client_info.ip_address,
+            client_info.user_agent,
+        ).with_details("reason=bad_password")).await;
+        let error_response = ErrorResponse::new("Current password is incorrect");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Validate password strength
+    if let Err(password_errors) = auth_service.validate_password_strength(&payload.new_password) {
+        let error_response = ErrorResponse::with_details(
+            "Password does not meet requirements",
+            &password_errors.join(", ")
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Hash password
+    let password_hash = match auth_service.hash_password(&payload.new_password) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::error!("Failed to hash password: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ResponseJson(json!(error_response))
+            ));
+        }
+    };
+
+    // Update password, refresh tokens issued before now stop working
+    let now = Utc::now();
+    let mut updated_user = user;
+    updated_user.password_hash = password_hash;
+    updated_user.password_changed_at = Some(now);
+    updated_user.updated_at = now;
+
+    if let Err(e) = db_manager.user_repo.update_user(&updated_user).await {
+        tracing::error!("Failed to update password: {}", e);
+        METRICS.record_password_operation("change", "error");
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ResponseJson(json!(error_response))
+        ));
+    }
+
+    // Sign out everywhere, including this session
+    let revoked_sessions = db_manager.session_repo
+        .deactivate_all_user_sessions(updated_user.id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("Failed to deactivate sessions: {}", e);
+            0
+        });
+
+    record_audit(&db_manager, AuditEvent::new(
+        "password_changed",
+        Some(updated_user.id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("{} sessions revoked", revoked_sessions))).await;
+    METRICS.record_password_operation("change", "success");
+
+    let response = SuccessResponse::new("Password changed, please log in again");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Record a refused login, with the reason it was refused
+async fn record_login_failure(db_manager: &DatabaseManager, user_id: Option<Uuid>, client_info: &ClientInfo, reason: &str) {
+    record_audit(db_manager, AuditEvent::new(
+        "login_failed",
+        user_id,
+        client_info.ip_address.clone(),
+        client_info.user_agent.clone(),
+    ).with_details(&format!("reason={}", reason))).await;
+}
+
+/// Store an audit event, failures are logged but never fail the request
+pub async fn record_audit(db_manager: &DatabaseManager, event: AuditEvent) {
+    if let Err(e) = db_manager.audit_repo.record(&event).await {
+        tracing::error!("Failed to record audit event {}: {}", event.event_type, e);
+    }
+}
+
 // Debug functions removed for production security
 
 /// Health check handler
@@ -0,0 +1,264 @@
+use axum::{
+    extract::{ConnectInfo, Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::UserServiceConfig;
+use crate::database::{is_duplicate_key_error, DatabaseManager};
+use crate::handlers::auth::{load_access, record_audit};
+use crate::metrics::METRICS;
+use crate::middleware::auth::CurrentUser;
+use crate::models::{
+    AuditEvent, CharacterLink, ErrorResponse, InternalLinkCharacterRequest, LinkCharacterRequest,
+    SuccessResponse, User, UserStatus,
+};
+use crate::utils::request::ClientInfo;
+
+/// Characters a user can link in one world
+const MAX_CHARACTERS_PER_WORLD: u64 = 20;
+
+type HandlerError = (StatusCode, ResponseJson<Value>);
+
+/// List the characters linked to the current user
+pub async fn list_characters(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/users/me/characters", 200);
+
+    let characters = db_manager.character_repo
+        .find_user_characters(claims.user_id)
+        .await
+        .map_err(database_error)?;
+    let characters: Vec<Value> = characters.iter().map(character_json).collect();
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "characters": characters
+    })))
+}
+
+/// Link a character to the current user
+pub async fn link_character(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<LinkCharacterRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/users/me/characters", 200);
+
+    validate(&payload)?;
+    let link = create_link(&db_manager, claims.user_id, payload).await?;
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "character_linked",
+        Some(claims.user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("world={} actor={}", link.world_id, link.actor_id))).await;
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "character": character_json(&link)
+    })))
+}
+
+/// Unlink a character from the current user
+pub async fn unlink_character(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+    Path((world_id, actor_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("DELETE", "/users/me/characters", 200);
+
+    // Scoped to the current user, other users' characters look like missing ones
+    let unlinked = db_manager.character_repo
+        .unlink(&world_id, &actor_id, Some(claims.user_id))
+        .await
+        .map_err(database_error)?;
+    if !unlinked {
+        return Err(not_found("Character not found"));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "character_unlinked",
+        Some(claims.user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("world={} actor={}", world_id, actor_id))).await;
+
+    let response = SuccessResponse::new("Character unlinked");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Internal: resolve the account owning a character, for permission and anti-cheat checks
+pub async fn resolve_character_owner(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path((world_id, actor_id)): Path<(String, String)>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/internal/characters/owner", 200);
+
+    let link = match db_manager.character_repo.find_owner(&world_id, &actor_id).await.map_err(database_error)? {
+        Some(link) => link,
+        None => return Err(not_found("Character is not linked to an account")),
+    };
+    let user = match db_manager.user_repo.find_by_id(link.user_id).await.map_err(database_error)? {
+        Some(user) => user,
+        None => {
+            tracing::error!("Character {}/{} links to a missing user", world_id, actor_id);
+            return Err(not_found("Character is not linked to an account"));
+        }
+    };
+    let access = load_access(&config, &db_manager, &user).await;
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "character": character_json(&link),
+        "account": {
+            "user_id": user.id,
+            "username": user.username,
+            "status": user.status,
+            "active": user.status == UserStatus::Active,
+            "roles": access.roles,
+            "permissions": access.permissions
+        }
+    })))
+}
+
+/// Internal: link a character on behalf of a user, e.g. when a game service creates it
+pub async fn internal_link_character(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Json(payload): Json<InternalLinkCharacterRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/internal/characters", 200);
+
+    validate(&payload)?;
+    let user = find_user(&db_manager, payload.user_id).await?;
+    let request = LinkCharacterRequest {
+        world_id: payload.world_id,
+        actor_id: payload.actor_id,
+        name: payload.name,
+    };
+    let link = create_link(&db_manager, user.id, request).await?;
+
+    record_audit(&db_manager, AuditEvent::new("character_linked", Some(user.id), None, None)
+        .with_details(&format!("world={} actor={} by=internal", link.world_id, link.actor_id))).await;
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "character": character_json(&link)
+    })))
+}
+
+/// Internal: unlink a character whatever account owns it, e.g. when it is deleted
+pub async fn internal_unlink_character(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path((world_id, actor_id)): Path<(String, String)>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("DELETE", "/internal/characters", 200);
+
+    let owner = db_manager.character_repo.find_owner(&world_id, &actor_id).await.map_err(database_error)?;
+    let unlinked = db_manager.character_repo
+        .unlink(&world_id, &actor_id, None)
+        .await
+        .map_err(database_error)?;
+    if !unlinked {
+        return Err(not_found("Character not found"));
+    }
+
+    record_audit(&db_manager, AuditEvent::new("character_unlinked", owner.map(|link| link.user_id), None, None)
+        .with_details(&format!("world={} actor={} by=internal", world_id, actor_id))).await;
+
+    let response = SuccessResponse::new("Character unlinked");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Store a character link, each character can only belong to one account
+async fn create_link(
+    db_manager: &DatabaseManager,
+    user_id: Uuid,
+    request: LinkCharacterRequest,
+) -> Result<CharacterLink, HandlerError> {
+    let linked = db_manager.character_repo
+        .count_user_characters(user_id, &request.world_id)
+        .await
+        .map_err(database_error)?;
+    if linked >= MAX_CHARACTERS_PER_WORLD {
+        let error_response = ErrorResponse::with_details(
+            "Character limit reached",
+            &format!("At most {} characters can be linked per world", MAX_CHARACTERS_PER_WORLD),
+        );
+        return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+    }
+
+    let link = CharacterLink {
+        id: Uuid::new_v4(),
+        user_id,
+        world_id: request.world_id,
+        actor_id: request.actor_id,
+        name: request.name,
+        linked_at: Utc::now(),
+    };
+    match db_manager.character_repo.link(&link).await {
+        Ok(()) => {
+            tracing::info!("Character {}/{} linked to user {}", link.world_id, link.actor_id, user_id);
+            Ok(link)
+        }
+        Err(e) if is_duplicate_key_error(&e) => {
+            let error_response = ErrorResponse::new("Character is already linked to an account");
+            Err((StatusCode::CONFLICT, ResponseJson(json!(error_response))))
+        }
+        Err(e) => Err(database_error(e)),
+    }
+}
+
+fn character_json(link: &CharacterLink) -> Value {
+    json!({
+        "world_id": link.world_id,
+        "actor_id": link.actor_id,
+        "name": link.name,
+        "linked_at": link.linked_at
+    })
+}
+
+async fn find_user(db_manager: &DatabaseManager, user_id: Uuid) -> Result<User, HandlerError> {
+    match db_manager.user_repo.find_by_id(user_id).await.map_err(database_error)? {
+        Some(user) => Ok(user),
+        None => Err(not_found("User not found")),
+    }
+}
+
+fn validate(payload: &impl Validate) -> Result<(), HandlerError> {
+    payload.validate().map_err(|validation_errors| {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details("Validation failed", &error_messages.join(", "));
+        (StatusCode::BAD_REQUEST, ResponseJson(json!(error_response)))
+    })
+}
+
+fn not_found(error: &str) -> HandlerError {
+    let error_response = ErrorResponse::new(error);
+    (StatusCode::NOT_FOUND, ResponseJson(json!(error_response)))
+}
+
+fn database_error(e: mongodb::error::Error) -> HandlerError {
+    tracing::error!("Database error: {}", e);
+    let error_response = ErrorResponse::new("Internal server error");
+    (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response)))
+}
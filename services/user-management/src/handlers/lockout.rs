@@ -0,0 +1,321 @@
+use axum::{
+    extract::{ConnectInfo, Json, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::UserServiceConfig;
+use crate::database::DatabaseManager;
+use crate::handlers::auth::record_audit;
+use crate::metrics::METRICS;
+use crate::models::{AccountUnlockToken, AuditEvent, ErrorResponse, SuccessResponse, UnlockAccountRequest, User};
+use crate::services::{
+    AntiCheatReporter, AuthService, EmailService, LockDecision, LoginSecurityService, SuspiciousLoginReport,
+};
+use crate::utils::request::{describe_device, ClientInfo};
+
+type HandlerError = (StatusCode, ResponseJson<Value>);
+
+/// Reject the login while the key (account or IP) is locked
+pub async fn check_login_lock(
+    config: &UserServiceConfig,
+    db_manager: &DatabaseManager,
+    key: &str,
+) -> Result<(), HandlerError> {
+    if !config.login_security.lockout_enabled {
+        return Ok(());
+    }
+
+    let record = match db_manager.lockout_repo.find(key).await {
+        Ok(record) => record,
+        Err(e) => {
+            // Logins keep working when the lockout store is unavailable
+            tracing::error!("Failed to check login lock: {}", e);
+            return Ok(());
+        }
+    };
+    let remaining = record.and_then(|record| LoginSecurityService::remaining_lock_seconds(&record, bson::DateTime::now()));
+    match remaining {
+        Some(seconds) => {
+            METRICS.record_auth_attempt("login", "locked");
+            let error_response = ErrorResponse::with_details(
+                "Too many failed login attempts",
+                &format!("Try again in {} seconds", seconds),
+            );
+            Err((StatusCode::TOO_MANY_REQUESTS, ResponseJson(json!(error_response))))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Count a failed login against the IP and, when known, the account, locking them past the thresholds
+pub async fn record_failed_login(
+    config: &Arc<UserServiceConfig>,
+    db_manager: &DatabaseManager,
+    user: Option<&User>,
+    client_info: &ClientInfo,
+) {
+    if !config.login_security.lockout_enabled {
+        return;
+    }
+    let security = &config.login_security;
+    let service = LoginSecurityService::new(config.clone());
+
+    if let Some(ip_address) = client_info.lockout_ip(&security.trusted_proxies) {
+        let key = LoginSecurityService::ip_key(&ip_address);
+        if let Some((decision, failed_attempts)) = count_failure(&service, db_manager, &key, security.ip_failure_threshold, security.failure_window_seconds).await {
+            tracing::warn!("IP {} locked for {}s after {} failed logins", ip_address, decision.lock_seconds, failed_attempts);
+            record_audit(db_manager, AuditEvent::new(
+                "ip_locked",
+                None,
+                client_info.ip_address.clone(),
+                client_info.user_agent.clone(),
+            ).with_details(&format!("level={} seconds={}", decision.lock_level, decision.lock_seconds))).await;
+            report(config, "ip_locked", None, client_info, failed_attempts, decision.lock_level);
+        }
+    }
+
+    if let Some(user) = user {
+        let key = LoginSecurityService::account_key(user.id);
+        if let Some((decision, failed_attempts)) = count_failure(&service, db_manager, &key, security.account_failure_threshold, security.failure_window_seconds).await {
+            tracing::warn!("Account {} locked for {}s after {} failed logins", user.id, decision.lock_seconds, failed_attempts);
+            record_audit(db_manager, AuditEvent::new(
+                "account_locked",
+                Some(user.id),
+                client_info.ip_address.clone(),
+                client_info.user_agent.clone(),
+            ).with_details(&format!("level={} seconds={}", decision.lock_level, decision.lock_seconds))).await;
+            report(config, "account_locked", Some(user.id), client_info, failed_attempts, decision.lock_level);
+            send_unlock_email(config, db_manager, user, decision.lock_seconds).await;
+        }
+    }
+}
+
+/// Forget the account's failed logins after a successful login
+///
+/// Returns the number of failed attempts that preceded the login.
+pub async fn record_successful_login(config: &UserServiceConfig, db_manager: &DatabaseManager, user: &User) -> i64 {
+    if !config.login_security.lockout_enabled {
+        return 0;
+    }
+
+    let key = LoginSecurityService::account_key(user.id);
+    let failed_attempts = match db_manager.lockout_repo.find(&key).await {
+        Ok(record) => record.map(|record| record.failed_count).unwrap_or(0),
+        Err(e) => {
+            tracing::error!("Failed to read login failures: {}", e);
+            0
+        }
+    };
+    if failed_attempts > 0 {
+        if let Err(e) = db_manager.lockout_repo.clear_failures(&key).await {
+            tracing::error!("Failed to clear login failures: {}", e);
+        }
+    }
+    failed_attempts
+}
+
+/// Remember the device of a login, alerting the user when it is new
+///
+/// The first device of an account is not alerted. A new device right after failed
+/// logins is reported as suspicious.
+pub async fn check_login_device(
+    config: &Arc<UserServiceConfig>,
+    db_manager: &DatabaseManager,
+    user: &User,
+    client_info: &ClientInfo,
+    failed_attempts: i64,
+) {
+    let device = describe_device(client_info.user_agent.as_deref());
+    let known_devices = match db_manager.lockout_repo.count_devices(user.id).await {
+        Ok(count) => count,
+        Err(e) => {
+            tracing::error!("Failed to count known devices: {}", e);
+            return;
+        }
+    };
+    let is_new = match db_manager.lockout_repo
+        .remember_device(user.id, &device, client_info.ip_address.as_deref())
+        .await
+    {
+        Ok(is_new) => is_new,
+        Err(e) => {
+            tracing::error!("Failed to remember device: {}", e);
+            return;
+        }
+    };
+    if !is_new || known_devices == 0 {
+        return;
+    }
+
+    record_audit(db_manager, AuditEvent::new(
+        "new_device_login",
+        Some(user.id),
+        client_info.ip_address.clone(),
+        client_info.user_agent.clone(),
+    ).with_details(&device)).await;
+
+    if failed_attempts > 0 {
+        report(config, "new_device_after_failures", Some(user.id), client_info, failed_attempts, 0);
+    }
+
+    if config.login_security.new_device_alerts {
+        let email_service = EmailService::new(config.clone());
+        let (email, username, ip_address) = (user.email.clone(), user.username.clone(), client_info.ip_address.clone());
+        tokio::spawn(async move {
+            if let Err(e) = email_service.send_new_device_login(&email, &username, &device, ip_address.as_deref()).await {
+                tracing::error!("Failed to send new device email: {}", e);
+            }
+        });
+    }
+}
+
+/// Unlock account handler, redeems the token emailed when the account was locked
+pub async fn unlock_account(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    Json(payload): Json<UnlockAccountRequest>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("POST", "/auth/unlock", 200);
+
+    if let Err(validation_errors) = payload.validate() {
+        let error_messages: Vec<String> = validation_errors
+            .field_errors()
+            .values()
+            .flat_map(|errors| errors.iter().map(|e| e.message.clone().unwrap_or_else(|| "Invalid field".into()).to_string()))
+            .collect();
+        let error_response = ErrorResponse::with_details("Validation failed", &error_messages.join(", "));
+        return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    let token_hash = AuthService::hash_reset_token(&payload.token);
+    let unlock_token = match db_manager.lockout_repo.consume_unlock_token(&token_hash).await {
+        Ok(Some(unlock_token)) => unlock_token,
+        Ok(None) => {
+            let error_response = ErrorResponse::new("Invalid or expired unlock token");
+            return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            let error_response = ErrorResponse::new("Internal server error");
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response))));
+        }
+    };
+
+    if let Err(e) = db_manager.lockout_repo.unlock(&LoginSecurityService::account_key(unlock_token.user_id)).await {
+        tracing::error!("Failed to unlock account: {}", e);
+        let error_response = ErrorResponse::new("Internal server error");
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response))));
+    }
+
+    record_audit(&db_manager, AuditEvent::new(
+        "account_unlocked",
+        Some(unlock_token.user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    )).await;
+    tracing::info!("Account {} unlocked by email link", unlock_token.user_id);
+
+    let response = SuccessResponse::new("Account unlocked, you can log in again");
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Count a failure for a key and lock it when the threshold is reached
+///
+/// Returns the lock and the failed attempts that caused it.
+async fn count_failure(
+    service: &LoginSecurityService,
+    db_manager: &DatabaseManager,
+    key: &str,
+    threshold: u32,
+    window_seconds: u64,
+) -> Option<(LockDecision, i64)> {
+    let now = bson::DateTime::now();
+    let record = match db_manager.lockout_repo
+        .record_failure(key, window_seconds, service.record_expiry(now, None))
+        .await
+    {
+        Ok(Some(record)) => record,
+        Ok(None) => return None,
+        Err(e) => {
+            tracing::error!("Failed to record login failure: {}", e);
+            return None;
+        }
+    };
+
+    // Failures while already locked do not escalate further
+    if LoginSecurityService::remaining_lock_seconds(&record, now).is_some() {
+        return None;
+    }
+    let decision = service.lock_after_failure(&record, threshold, now)?;
+
+    let locked_until = bson::DateTime::from_millis(now.timestamp_millis() + (decision.lock_seconds as i64) * 1000);
+    let expires_at = service.record_expiry(now, Some(locked_until));
+    if let Err(e) = db_manager.lockout_repo.lock(key, decision.lock_level, locked_until, expires_at).await {
+        tracing::error!("Failed to lock {}: {}", key, e);
+        return None;
+    }
+    Some((decision, record.failed_count))
+}
+
+/// Email an unlock link to the owner of a locked account
+async fn send_unlock_email(config: &Arc<UserServiceConfig>, db_manager: &DatabaseManager, user: &User, lock_seconds: u64) {
+    let auth_service = match AuthService::new(config.clone()) {
+        Ok(service) => service,
+        Err(e) => {
+            tracing::error!("Failed to create auth service: {}", e);
+            return;
+        }
+    };
+    let (token, token_hash) = auth_service.generate_reset_token();
+    let now = bson::DateTime::now();
+    let unlock_token = AccountUnlockToken {
+        id: Uuid::new_v4(),
+        user_id: user.id,
+        token_hash,
+        expires_at: bson::DateTime::from_millis(
+            now.timestamp_millis() + (config.login_security.unlock_token_expiry_seconds as i64) * 1000
+        ),
+        created_at: now,
+        used_at: None,
+    };
+    if let Err(e) = db_manager.lockout_repo.create_unlock_token(&unlock_token).await {
+        tracing::error!("Failed to save unlock token: {}", e);
+        return;
+    }
+
+    let email_service = EmailService::new(config.clone());
+    let (email, username) = (user.email.clone(), user.username.clone());
+    tokio::spawn(async move {
+        if let Err(e) = email_service.send_account_locked(&email, &username, lock_seconds, &token).await {
+            tracing::error!("Failed to send account locked email: {}", e);
+        }
+    });
+}
+
+fn report(
+    config: &Arc<UserServiceConfig>,
+    kind: &'static str,
+    user_id: Option<Uuid>,
+    client_info: &ClientInfo,
+    failed_attempts: i64,
+    lock_level: i64,
+) {
+    AntiCheatReporter::new(config.clone()).report(SuspiciousLoginReport {
+        kind,
+        user_id,
+        ip_address: client_info.ip_address.clone(),
+        user_agent: client_info.user_agent.clone(),
+        failed_attempts,
+        lock_level,
+        occurred_at: Utc::now(),
+    });
+}
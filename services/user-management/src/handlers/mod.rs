@@ -1 +1,6 @@
+pub mod admin;
 pub mod auth;
+pub mod characters;
+pub mod lockout;
+pub mod oauth;
+pub mod sessions;
@@ -0,0 +1,280 @@
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{Json as ResponseJson, Redirect},
+};
+use chrono::Utc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::UserServiceConfig;
+use crate::database::DatabaseManager;
+use crate::handlers::auth::{complete_login, record_audit};
+use crate::handlers::lockout::check_login_device;
+use crate::metrics::METRICS;
+use crate::models::{AuditEvent, ErrorResponse, OAuthAccount, OAuthState, User, UserStatus};
+use crate::services::oauth::{suggested_username, OAuthProvider, OAuthService, ProviderIdentity};
+use crate::services::AuthService;
+use crate::utils::request::ClientInfo;
+
+/// Attempts at finding a free username for a new account
+const USERNAME_ATTEMPTS: usize = 5;
+
+type HandlerError = (StatusCode, ResponseJson<Value>);
+
+/// OAuth authorization handler, redirects to the provider's login page
+pub async fn oauth_authorize(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path(provider): Path<String>,
+) -> Result<Redirect, HandlerError> {
+    METRICS.record_http_request("GET", "/auth/oauth/authorize", 302);
+
+    let oauth_service = OAuthService::new(config.clone());
+    let provider = enabled_provider(&oauth_service, &provider)?;
+
+    let request = oauth_service.authorization_request(provider).map_err(|e| {
+        tracing::error!("Failed to build {} authorization URL: {}", provider, e);
+        internal_error()
+    })?;
+
+    let now = bson::DateTime::now();
+    let state = OAuthState {
+        state: request.state,
+        provider: provider.to_string(),
+        pkce_verifier: request.pkce_verifier,
+        expires_at: bson::DateTime::from_millis(
+            now.timestamp_millis() + (config.oauth.state_expiry_seconds as i64) * 1000
+        ),
+        created_at: now,
+    };
+    if let Err(e) = db_manager.oauth_repo.create_state(&state).await {
+        tracing::error!("Failed to save OAuth state: {}", e);
+        return Err(internal_error());
+    }
+
+    Ok(Redirect::to(&request.url))
+}
+
+/// OAuth callback handler, logs the user in with the same tokens as password login
+pub async fn oauth_callback(
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    Path(provider): Path<String>,
+    Query(params): Query<HashMap<String, String>>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/auth/oauth/callback", 200);
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    let oauth_service = OAuthService::new(config.clone());
+    let provider = enabled_provider(&oauth_service, &provider)?;
+    let attempt = format!("oauth_{}", provider);
+
+    if let Some(error) = params.get("error") {
+        METRICS.record_auth_attempt(&attempt, "denied");
+        let error_response = ErrorResponse::with_details("Login was cancelled or denied", error);
+        return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+    }
+
+    // The state proves the login was started here, and can only be used once
+    let state = params.get("state").map(String::as_str).unwrap_or_default();
+    let pending = match db_manager.oauth_repo.consume_state(provider.as_str(), state).await {
+        Ok(Some(pending)) => pending,
+        Ok(None) => {
+            METRICS.record_auth_attempt(&attempt, "invalid_state");
+            let error_response = ErrorResponse::new("Invalid or expired login state");
+            return Err((StatusCode::BAD_REQUEST, ResponseJson(json!(error_response))));
+        }
+        Err(e) => {
+            tracing::error!("Database error: {}", e);
+            return Err(internal_error());
+        }
+    };
+
+    let identity = match oauth_service
+        .authenticate(provider, &params, pending.pkce_verifier.as_deref())
+        .await
+    {
+        Ok(identity) => identity,
+        Err(e) => {
+            tracing::warn!("{} login failed: {}", provider, e);
+            METRICS.record_auth_attempt(&attempt, "failure");
+            let error_response = ErrorResponse::new("Login with provider failed");
+            return Err((StatusCode::UNAUTHORIZED, ResponseJson(json!(error_response))));
+        }
+    };
+
+    let auth_service = AuthService::new(config.clone()).map_err(|e| {
+        tracing::error!("Failed to create auth service: {}", e);
+        internal_error()
+    })?;
+
+    let (user, event) = resolve_user(&auth_service, &db_manager, provider, &identity).await?;
+
+    // Check if user is active
+    if !auth_service.is_user_active(&user) {
+        METRICS.record_auth_attempt(&attempt, "inactive");
+        let error_response = ErrorResponse::new("Account is not active");
+        return Err((StatusCode::UNAUTHORIZED, ResponseJson(json!(error_response))));
+    }
+
+    record_audit(&db_manager, AuditEvent::new(
+        event,
+        Some(user.id),
+        client_info.ip_address.clone(),
+        client_info.user_agent.clone(),
+    ).with_details(provider.as_str())).await;
+
+    check_login_device(&config, &db_manager, &user, &client_info, 0).await;
+
+    let response = complete_login(&config, &auth_service, &db_manager, user, client_info).await?;
+
+    METRICS.record_login();
+    METRICS.record_auth_attempt(&attempt, "success");
+
+    Ok(ResponseJson(json!(response)))
+}
+
+/// Find the user of a provider identity, linking or creating an account when needed
+///
+/// Returns the user and the audit event describing what happened.
+async fn resolve_user(
+    auth_service: &AuthService,
+    db_manager: &DatabaseManager,
+    provider: OAuthProvider,
+    identity: &ProviderIdentity,
+) -> Result<(User, &'static str), HandlerError> {
+    // Already linked provider account
+    let linked = db_manager.oauth_repo
+        .find_account(provider.as_str(), &identity.provider_user_id)
+        .await
+        .map_err(database_error)?;
+    if let Some(account) = linked {
+        if let Err(e) = db_manager.oauth_repo.touch_account(&account).await {
+            tracing::error!("Failed to update OAuth account: {}", e);
+        }
+        return match db_manager.user_repo.find_by_id(account.user_id).await.map_err(database_error)? {
+            Some(user) => Ok((user, "oauth_login")),
+            None => {
+                tracing::error!("OAuth account {} links to a missing user", account.id);
+                Err(internal_error())
+            }
+        };
+    }
+
+    // Existing user with the same verified email
+    let verified_email = identity.email.as_deref().filter(|_| identity.email_verified);
+    if let Some(email) = verified_email {
+        if let Some(user) = db_manager.user_repo.find_by_email(email).await.map_err(database_error)? {
+            link_account(db_manager, provider, identity, user.id).await?;
+            return Ok((user, "oauth_account_linked"));
+        }
+    }
+
+    // New user, only an unverified email can clash with an existing account
+    let email = match &identity.email {
+        Some(email) => {
+            if db_manager.user_repo.email_exists(email).await.map_err(database_error)? {
+                let error_response = ErrorResponse::new(
+                    "An account with this email already exists, log in with your password first"
+                );
+                return Err((StatusCode::CONFLICT, ResponseJson(json!(error_response))));
+            }
+            email.clone()
+        }
+        // Placeholder on the reserved .invalid domain for providers without emails
+        None => format!("{}-{}@{}.invalid", provider, identity.provider_user_id, provider),
+    };
+
+    let username = available_username(db_manager, provider, identity).await?;
+
+    // Random password the user never sees, a password can be set with a reset
+    let (random_password, _) = auth_service.generate_reset_token();
+    let password_hash = auth_service.hash_password(&random_password).map_err(|e| {
+        tracing::error!("Failed to hash password: {}", e);
+        internal_error()
+    })?;
+
+    let user = User {
+        id: Uuid::new_v4(),
+        username,
+        email,
+        password_hash,
+        display_name: identity.display_name.clone(),
+        avatar_url: identity.avatar_url.clone(),
+        status: UserStatus::Active,
+        email_verified: verified_email.is_some(),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+        last_login: None,
+        login_count: 0,
+        password_changed_at: None,
+    };
+    let user = db_manager.user_repo.create_user(&user).await.map_err(database_error)?;
+    link_account(db_manager, provider, identity, user.id).await?;
+
+    METRICS.record_registration();
+    Ok((user, "oauth_account_created"))
+}
+
+/// Link a provider account to a user
+async fn link_account(
+    db_manager: &DatabaseManager,
+    provider: OAuthProvider,
+    identity: &ProviderIdentity,
+    user_id: Uuid,
+) -> Result<(), HandlerError> {
+    let account = OAuthAccount {
+        id: Uuid::new_v4(),
+        user_id,
+        provider: provider.to_string(),
+        provider_user_id: identity.provider_user_id.clone(),
+        email: identity.email.clone(),
+        created_at: Utc::now(),
+        last_login: Some(Utc::now()),
+    };
+    db_manager.oauth_repo.create_account(&account).await.map_err(database_error)
+}
+
+/// First free username derived from the provider identity
+async fn available_username(
+    db_manager: &DatabaseManager,
+    provider: OAuthProvider,
+    identity: &ProviderIdentity,
+) -> Result<String, HandlerError> {
+    let base = suggested_username(provider, identity);
+    let mut candidate = base.clone();
+    for _ in 0..USERNAME_ATTEMPTS {
+        if !db_manager.user_repo.username_exists(&candidate).await.map_err(database_error)? {
+            return Ok(candidate);
+        }
+        candidate = format!("{}_{:04}", base, rand::random::<u16>() % 10000);
+    }
+
+    tracing::error!("No free username found for {} user {}", provider, identity.provider_user_id);
+    Err(internal_error())
+}
+
+/// Parse a provider path segment and check that it is configured
+fn enabled_provider(oauth_service: &OAuthService, provider: &str) -> Result<OAuthProvider, HandlerError> {
+    match provider.parse::<OAuthProvider>() {
+        Ok(provider) if oauth_service.is_enabled(provider) => Ok(provider),
+        _ => {
+            let error_response = ErrorResponse::new("Unknown login provider");
+            Err((StatusCode::NOT_FOUND, ResponseJson(json!(error_response))))
+        }
+    }
+}
+
+fn database_error(e: mongodb::error::Error) -> HandlerError {
+    tracing::error!("Database error: {}", e);
+    internal_error()
+}
+
+fn internal_error() -> HandlerError {
+    let error_response = ErrorResponse::new("Internal server error");
+    (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response)))
+}
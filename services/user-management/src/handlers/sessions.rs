@@ -0,0 +1,88 @@
+use axum::{
+    extract::{ConnectInfo, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::Json as ResponseJson,
+};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::config::UserServiceConfig;
+use crate::database::DatabaseManager;
+use crate::handlers::auth::record_audit;
+use crate::metrics::METRICS;
+use crate::middleware::auth::CurrentUser;
+use crate::models::{AuditEvent, ErrorResponse, SuccessResponse};
+use crate::utils::request::{describe_device, ClientInfo};
+
+type HandlerError = (StatusCode, ResponseJson<Value>);
+
+/// List the active sessions of the current user
+pub async fn list_sessions(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("GET", "/auth/sessions", 200);
+
+    let sessions = db_manager.session_repo
+        .find_active_user_sessions(claims.user_id)
+        .await
+        .map_err(database_error)?;
+
+    // Tokens stay private, only what helps the user recognise a device is returned
+    let sessions: Vec<Value> = sessions.iter().map(|session| json!({
+        "id": session.id,
+        "device": describe_device(session.user_agent.as_deref()),
+        "ip_address": session.ip_address,
+        "user_agent": session.user_agent,
+        "created_at": session.created_at,
+        "last_used": session.last_accessed,
+        "expires_at": session.expires_at,
+        "current": session.id == claims.session_id
+    })).collect();
+
+    Ok(ResponseJson(json!({
+        "success": true,
+        "sessions": sessions
+    })))
+}
+
+/// Revoke one session of the current user, logging that device out
+pub async fn revoke_session(
+    State((_config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    CurrentUser(claims): CurrentUser,
+    Path(session_id): Path<Uuid>,
+    headers: HeaderMap,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+) -> Result<ResponseJson<Value>, HandlerError> {
+    METRICS.record_http_request("DELETE", "/auth/sessions", 200);
+
+    // Scoped to the current user, other users' sessions look like missing ones
+    let revoked = db_manager.session_repo
+        .deactivate_user_session(claims.user_id, session_id)
+        .await
+        .map_err(database_error)?;
+    if !revoked {
+        let error_response = ErrorResponse::new("Session not found");
+        return Err((StatusCode::NOT_FOUND, ResponseJson(json!(error_response))));
+    }
+
+    let client_info = ClientInfo::from_request(&headers, connect_info);
+    record_audit(&db_manager, AuditEvent::new(
+        "session_revoked",
+        Some(claims.user_id),
+        client_info.ip_address,
+        client_info.user_agent,
+    ).with_details(&format!("session={}", session_id))).await;
+    tracing::info!("Session {} of user {} revoked", session_id, claims.user_id);
+
+    let response = SuccessResponse::new("Session revoked");
+    Ok(ResponseJson(json!(response)))
+}
+
+fn database_error(e: mongodb::error::Error) -> HandlerError {
+    tracing::error!("Database error: {}", e);
+    let error_response = ErrorResponse::new("Internal server error");
+    (StatusCode::INTERNAL_SERVER_ERROR, ResponseJson(json!(error_response)))
+}
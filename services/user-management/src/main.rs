@@ -1,11 +1,13 @@
 use axum::{
-    routing::{get, post},
-    Router,
+    extract::State,
+    http::StatusCode,
+    routing::{delete, get, post, put},
+    Json, Router,
 };
 use tower_http::cors::CorsLayer;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing_subscriber::{self, prelude::*};
+use shared::telemetry::{self, TelemetryOptions};
 mod config;
 mod handlers;
 mod models;
@@ -17,10 +19,25 @@ mod utils;
 
 use config::UserServiceConfig;
 use handlers::auth::*;
+use handlers::oauth::{oauth_authorize, oauth_callback};
+use handlers::sessions::{list_sessions, revoke_session};
+use handlers::lockout::unlock_account;
+use handlers::characters::{
+    list_characters, link_character, unlink_character,
+    resolve_character_owner, internal_link_character, internal_unlink_character,
+};
+use handlers::admin::{
+    get_user_access, grant_role, revoke_role, grant_permission, revoke_permission,
+    update_user_status, list_audit_events, internal_apply_sanction,
+};
 use database::{DatabaseManager, migrations::initialize_database};
-use middleware::auth::auth_middleware;
+use middleware::auth::{auth_middleware, internal_api_middleware, require_permission};
+use models::permissions;
 use metrics::METRICS;
-use middleware::rate_limit::{ip_rate_limit_middleware, user_rate_limit_middleware};
+use middleware::rate_limit::{ip_rate_limit_middleware, user_rate_limit_middleware, password_reset_rate_limit_middleware};
+use shared::health::{check_fn, HealthRegistry, HealthReport, RedisCheck};
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::ChaosError;
 
 #[tokio::main]
 async fn main() {
@@ -40,16 +57,15 @@ async fn main() {
             std::fs::File::create("user-management.log").unwrap()
         });
     
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "user_management=debug,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer().with_writer(file))
-        .with(tracing_subscriber::fmt::layer()) // Also output to console
-        .init();
+    let telemetry = telemetry::init_with(
+        &TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+            .with_default_filter("user_management=debug,tower_http=debug"),
+        vec![Box::new(tracing_subscriber::fmt::layer().with_writer(file)) as telemetry::BoxedLayer],
+    );
 
     tracing::info!("🚀 Starting User Management Service...");
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
     tracing::info!("📁 Current working directory: {:?}", std::env::current_dir());
     tracing::info!("🔍 CONFIG_PATH environment variable: {:?}", std::env::var("CONFIG_PATH"));
     
@@ -80,6 +96,8 @@ async fn main() {
     tracing::info!("  Server: {}:{}", config.server.host, config.server.port);
     tracing::info!("  Database: {}", config.database.url);
     tracing::info!("  Redis: {}", config.redis.url);
+    tracing::info!("  OAuth providers: google={} discord={} steam={}",
+                   config.oauth.google.is_some(), config.oauth.discord.is_some(), config.oauth.steam.is_some());
     
     // Initialize database
     tracing::info!("🗄️ Connecting to MongoDB...");
@@ -97,7 +115,7 @@ async fn main() {
             tracing::info!("✅ Connected to MongoDB database");
             
             // Initialize database with collections and indexes
-            if let Err(e) = initialize_database(&db.database).await {
+            if let Err(e) = initialize_database(&db.database, &config).await {
                 tracing::error!("Failed to initialize database: {}", e);
                 std::process::exit(1);
             }
@@ -115,6 +133,27 @@ async fn main() {
         }
     };
     
+    // Follow maintenance mode toggled through admin-cli
+    services::follow_maintenance(&config.maintenance).await;
+
+    // Readiness follows MongoDB; Redis is not on the request path yet, so it only degrades
+    let database = db_manager.database.clone();
+    let health = Arc::new(
+        HealthRegistry::new("user-management")
+            .with_shutdown(&shutdown)
+            .register("mongodb", check_fn(move || {
+                let database = database.clone();
+                async move {
+                    database
+                        .run_command(bson::doc! { "ping": 1 }, None)
+                        .await
+                        .map(|_| ())
+                        .map_err(|e| ChaosError::Database(e.to_string()))
+                }
+            }))
+            .register_optional("redis", RedisCheck::new(&config.redis.url)),
+    );
+
     // Create main production router
     let app = Router::new()
         .route("/health", get(health_check))
@@ -128,6 +167,26 @@ async fn main() {
             (config.clone(), db_manager.clone()),
             ip_rate_limit_middleware
         )))
+        .route("/auth/forgot-password", post(forgot_password).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            password_reset_rate_limit_middleware
+        )))
+        .route("/auth/reset-password", post(reset_password).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            password_reset_rate_limit_middleware
+        )))
+        .route("/auth/unlock", post(unlock_account).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            password_reset_rate_limit_middleware
+        )))
+        .route("/auth/oauth/:provider/authorize", get(oauth_authorize).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            ip_rate_limit_middleware
+        )))
+        .route("/auth/oauth/:provider/callback", get(oauth_callback).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            ip_rate_limit_middleware
+        )))
         .route("/auth/me", get(me).layer(axum::middleware::from_fn_with_state(
             (config.clone(), db_manager.clone()),
             auth_middleware
@@ -140,20 +199,53 @@ async fn main() {
             user_rate_limit_middleware
         )))
         .route("/auth/logout", post(logout).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        )).layer(axum::middleware::from_fn_with_state(
             (config.clone(), db_manager.clone()),
             user_rate_limit_middleware
         )))
         .route("/auth/logout-all", post(logout_all).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        )).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            user_rate_limit_middleware
+        )))
+        .route("/auth/change-password", post(change_password).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        )).layer(axum::middleware::from_fn_with_state(
             (config.clone(), db_manager.clone()),
             user_rate_limit_middleware
         )))
+        .route("/auth/sessions", get(list_sessions).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        )).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            user_rate_limit_middleware
+        )))
+        .route("/auth/sessions/:id", delete(revoke_session).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        )).layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            user_rate_limit_middleware
+        )))
+        .merge(admin_routes(config.clone(), db_manager.clone()))
+        .merge(user_admin_routes(config.clone(), db_manager.clone()))
+        .merge(audit_routes(config.clone(), db_manager.clone()))
+        .merge(character_routes(config.clone(), db_manager.clone()))
+        .merge(internal_routes(config.clone(), db_manager.clone()))
+        .merge(health_routes(health))
         .layer(
             CorsLayer::new()
                 .allow_origin("http://localhost:3200".parse::<axum::http::HeaderValue>().unwrap())
-                .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::OPTIONS])
+                .allow_methods([axum::http::Method::GET, axum::http::Method::POST, axum::http::Method::PUT, axum::http::Method::DELETE, axum::http::Method::OPTIONS])
                 .allow_headers([axum::http::header::CONTENT_TYPE, axum::http::header::AUTHORIZATION])
         )
-        .with_state((config.clone(), db_manager));
+        .with_state((config.clone(), db_manager.clone()));
 
     // Debug endpoints are disabled in production for security
     tracing::info!("🚀 Production mode - debug endpoints disabled");
@@ -166,13 +258,36 @@ async fn main() {
     tracing::info!("  Max connections: {}", config.server.max_connections);
     tracing::info!("📋 Available endpoints:");
     tracing::info!("  - GET  /health - Health check");
+    tracing::info!("  - GET  /health/live - Liveness probe");
+    tracing::info!("  - GET  /health/ready - Readiness probe with MongoDB and Redis checks");
     tracing::info!("  - GET  /metrics - Metrics");
     tracing::info!("  - POST /auth/register - User registration");
     tracing::info!("  - POST /auth/login - User login");
+    tracing::info!("  - POST /auth/forgot-password - Request password reset email");
+    tracing::info!("  - POST /auth/reset-password - Reset password with emailed token");
+    tracing::info!("  - POST /auth/unlock - Unlock a locked account with emailed token");
+    tracing::info!("  - GET  /auth/oauth/:provider/authorize - Start Google/Discord/Steam login");
+    tracing::info!("  - GET  /auth/oauth/:provider/callback - Complete provider login");
     tracing::info!("  - GET  /auth/me - Get current user");
     tracing::info!("  - POST /auth/refresh - Refresh token");
     tracing::info!("  - POST /auth/logout - Logout");
     tracing::info!("  - POST /auth/logout-all - Logout all sessions");
+    tracing::info!("  - POST /auth/change-password - Change password (signs out everywhere)");
+    tracing::info!("  - GET  /auth/sessions - List active sessions");
+    tracing::info!("  - DELETE /auth/sessions/:id - Revoke a session (remote logout)");
+    tracing::info!("  - GET  /admin/users/:id/access - User roles and permissions (roles:manage)");
+    tracing::info!("  - POST /admin/users/:id/roles - Grant role (roles:manage)");
+    tracing::info!("  - DELETE /admin/users/:id/roles/:role - Revoke role (roles:manage)");
+    tracing::info!("  - POST /admin/users/:id/permissions - Grant permission (roles:manage)");
+    tracing::info!("  - DELETE /admin/users/:id/permissions/:permission - Revoke permission (roles:manage)");
+    tracing::info!("  - PUT  /admin/users/:id/status - Ban, suspend or reactivate a user (users:manage)");
+    tracing::info!("  - GET  /admin/audit-events - Query the security audit log (audit:read)");
+    tracing::info!("  - GET  /users/me/characters - List linked characters");
+    tracing::info!("  - POST /users/me/characters - Link a character");
+    tracing::info!("  - DELETE /users/me/characters/:world_id/:actor_id - Unlink a character");
+    tracing::info!("  - GET  /internal/characters/:world_id/:actor_id/owner - Resolve character owner (internal)");
+    tracing::info!("  - POST /internal/characters - Link a character for a user (internal)");
+    tracing::info!("  - DELETE /internal/characters/:world_id/:actor_id - Unlink a character (internal)");
     tracing::info!("  - GET  /metrics - Prometheus metrics");
     
     // Debug endpoints are disabled for security
@@ -182,7 +297,125 @@ async fn main() {
     tracing::info!("🚀 User Management Service is now running!");
     tracing::info!("📝 Logs are being written to: C:\\ChaosWorld\\logs\\user-management.log");
     
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await.unwrap();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown.signal());
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+
+    // Let writes of drained requests reach MongoDB before exiting
+    let client = db_manager.client.clone();
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️ MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 User Management Service stopped");
+    telemetry.shutdown();
+}
+
+/// Liveness and readiness probes
+fn health_routes<S: Clone + Send + Sync + 'static>(health: Arc<HealthRegistry>) -> Router<S> {
+    Router::new()
+        .route(shared::health::LIVE_PATH, get(liveness))
+        .route(shared::health::READY_PATH, get(readiness))
+        .with_state(health)
+}
+
+/// Role management routes, authenticated and restricted to `roles:manage`
+fn admin_routes(
+    config: Arc<UserServiceConfig>,
+    db_manager: Arc<DatabaseManager>,
+) -> Router<(Arc<UserServiceConfig>, Arc<DatabaseManager>)> {
+    Router::new()
+        .route("/admin/users/:id/access", get(get_user_access))
+        .route("/admin/users/:id/roles", post(grant_role))
+        .route("/admin/users/:id/roles/:role", delete(revoke_role))
+        .route("/admin/users/:id/permissions", post(grant_permission))
+        .route("/admin/users/:id/permissions/:permission", delete(revoke_permission))
+        .route_layer(axum::middleware::from_fn(|request: axum::extract::Request, next: axum::middleware::Next| {
+            require_permission(permissions::ROLES_MANAGE, request, next)
+        }))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config, db_manager),
+            user_rate_limit_middleware
+        ))
+}
+
+/// User status routes, authenticated and restricted to `users:manage`
+fn user_admin_routes(
+    config: Arc<UserServiceConfig>,
+    db_manager: Arc<DatabaseManager>,
+) -> Router<(Arc<UserServiceConfig>, Arc<DatabaseManager>)> {
+    Router::new()
+        .route("/admin/users/:id/status", put(update_user_status))
+        .route_layer(axum::middleware::from_fn(|request: axum::extract::Request, next: axum::middleware::Next| {
+            require_permission(permissions::USERS_MANAGE, request, next)
+        }))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config, db_manager),
+            user_rate_limit_middleware
+        ))
+}
+
+/// Audit log routes, authenticated and restricted to `audit:read`
+fn audit_routes(
+    config: Arc<UserServiceConfig>,
+    db_manager: Arc<DatabaseManager>,
+) -> Router<(Arc<UserServiceConfig>, Arc<DatabaseManager>)> {
+    Router::new()
+        .route("/admin/audit-events", get(list_audit_events))
+        .route_layer(axum::middleware::from_fn(|request: axum::extract::Request, next: axum::middleware::Next| {
+            require_permission(permissions::AUDIT_READ, request, next)
+        }))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config, db_manager),
+            user_rate_limit_middleware
+        ))
+}
+
+/// Character link routes of the current user
+fn character_routes(
+    config: Arc<UserServiceConfig>,
+    db_manager: Arc<DatabaseManager>,
+) -> Router<(Arc<UserServiceConfig>, Arc<DatabaseManager>)> {
+    Router::new()
+        .route("/users/me/characters", get(list_characters).post(link_character))
+        .route("/users/me/characters/:world_id/:actor_id", delete(unlink_character))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config.clone(), db_manager.clone()),
+            auth_middleware
+        ))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config, db_manager),
+            user_rate_limit_middleware
+        ))
+}
+
+/// Service-to-service routes, not routed by the gateway and authenticated with internal API keys
+fn internal_routes(
+    config: Arc<UserServiceConfig>,
+    db_manager: Arc<DatabaseManager>,
+) -> Router<(Arc<UserServiceConfig>, Arc<DatabaseManager>)> {
+    Router::new()
+        .route("/internal/characters", post(internal_link_character))
+        .route("/internal/characters/:world_id/:actor_id", delete(internal_unlink_character))
+        .route("/internal/characters/:world_id/:actor_id/owner", get(resolve_character_owner))
+        .route("/internal/users/:id/sanctions", post(internal_apply_sanction))
+        .route_layer(axum::middleware::from_fn_with_state(
+            (config, db_manager),
+            internal_api_middleware
+        ))
 }
 
 /// Metrics handler for Prometheus
@@ -205,6 +438,17 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+async fn liveness(State(health): State<Arc<HealthRegistry>>) -> Json<HealthReport> {
+    Json(health.liveness())
+}
+
+/// 503 while a critical dependency is down
+async fn readiness(State(health): State<Arc<HealthRegistry>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = StatusCode::from_u16(report.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report))
+}
+
 async fn root() -> &'static str {
     "Hello from user-management! Use /auth endpoints for authentication."
 }
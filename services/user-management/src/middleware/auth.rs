@@ -1,6 +1,6 @@
 use axum::{
-    extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap, StatusCode},
     middleware::Next,
     response::Response,
 };
@@ -9,13 +9,13 @@ use serde_json::json;
 use std::sync::Arc;
 
 use crate::config::UserServiceConfig;
-// TokenClaims import removed as it's not used in this module
+use crate::models::TokenClaims;
 use crate::services::AuthService;
 use crate::database::DatabaseManager;
 
 /// Authentication middleware to verify JWT tokens
 pub async fn auth_middleware(
-    State((config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    State((config, db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
@@ -73,6 +73,30 @@ pub async fn auth_middleware(
             )
         })?;
 
+    // Tokens of logged out or revoked sessions stop working immediately
+    let session = db_manager.session_repo
+        .find_by_id(claims.session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Database error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                axum::Json(json!({
+                    "success": false,
+                    "error": "Internal server error"
+                })),
+            )
+        })?;
+    if !session.is_some_and(|session| session.is_active && session.user_id == claims.user_id) {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            axum::Json(json!({
+                "success": false,
+                "error": "Session has ended"
+            })),
+        ));
+    }
+
     tracing::info!("Token validated successfully for user: {}", claims.user_id);
     tracing::info!("Claims: user_id={}, username={}, email={}", claims.user_id, claims.username, claims.email);
 
@@ -82,4 +106,105 @@ pub async fn auth_middleware(
     Ok(next.run(request).await)
 }
 
-// Unused middleware functions removed for cleaner code
+/// Permission check middleware, layer it inside `auth_middleware`
+///
+/// ```ignore
+/// get(handler)
+///     .layer(axum::middleware::from_fn(|request, next| require_permission(permissions::ROLES_MANAGE, request, next)))
+///     .layer(axum::middleware::from_fn_with_state(state, auth_middleware))
+/// ```
+pub async fn require_permission(
+    permission: &'static str,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, axum::Json<serde_json::Value>)> {
+    let claims = request.extensions().get::<TokenClaims>().ok_or_else(unauthenticated)?;
+    if !claims.has_permission(permission) {
+        tracing::warn!("User {} lacks permission {}", claims.user_id, permission);
+        return Err(forbidden(permission));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Service-to-service authentication for `/internal` routes, using the `X-Internal-Api-Key` header
+pub async fn internal_api_middleware(
+    State((config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, axum::Json<serde_json::Value>)> {
+    if config.internal_api.api_keys.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            axum::Json(json!({
+                "success": false,
+                "error": "Internal API is not enabled"
+            })),
+        ));
+    }
+
+    let presented = headers
+        .get("X-Internal-Api-Key")
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or_default();
+    if !config.internal_api.api_keys.iter().any(|key| api_keys_match(key, presented)) {
+        tracing::warn!("Rejected internal API request with a missing or unknown key");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            axum::Json(json!({
+                "success": false,
+                "error": "Invalid internal API key"
+            })),
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Compare API keys in constant time, hashing first so the key length does not leak either
+fn api_keys_match(expected: &str, presented: &str) -> bool {
+    use sha2::{Digest, Sha256};
+
+    let expected = Sha256::digest(expected.as_bytes());
+    let presented = Sha256::digest(presented.as_bytes());
+    expected.iter().zip(presented.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Extractor for the claims of the authenticated user, set by `auth_middleware`
+pub struct CurrentUser(pub TokenClaims);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for CurrentUser {
+    type Rejection = (StatusCode, axum::Json<serde_json::Value>);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<TokenClaims>()
+            .cloned()
+            .map(CurrentUser)
+            .ok_or_else(unauthenticated)
+    }
+}
+
+fn unauthenticated() -> (StatusCode, axum::Json<serde_json::Value>) {
+    (
+        StatusCode::UNAUTHORIZED,
+        axum::Json(json!({
+            "success": false,
+            "error": "User not authenticated"
+        })),
+    )
+}
+
+fn forbidden(permission: &str) -> (StatusCode, axum::Json<serde_json::Value>) {
+    (
+        StatusCode::FORBIDDEN,
+        axum::Json(json!({
+            "success": false,
+            "error": "Insufficient permissions",
+            "details": { "required_permission": permission }
+        })),
+    )
+}
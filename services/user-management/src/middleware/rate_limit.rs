@@ -7,6 +7,7 @@ use axum::{
 use serde_json::json;
 use std::sync::Arc;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
@@ -124,6 +125,61 @@ pub async fn ip_rate_limit_middleware(
     Ok(response)
 }
 
+/// Shared limiter for the password reset endpoints, sized from the first request's config
+static PASSWORD_RESET_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// Password reset rate limiting middleware (`rate_limiting.password_reset` requests per endpoint, IP and window)
+pub async fn password_reset_rate_limit_middleware(
+    State((config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, axum::Json<serde_json::Value>)> {
+    let limits = &config.rate_limiting;
+    if !limits.enabled {
+        return Ok(next.run(request).await);
+    }
+
+    let rate_limiter = PASSWORD_RESET_LIMITER
+        .get_or_init(|| RateLimiter::new(limits.password_reset, limits.window_seconds));
+
+    // Get client IP from headers
+    let client_ip = headers
+        .get("x-forwarded-for")
+        .or_else(|| headers.get("x-real-ip"))
+        .or_else(|| headers.get("x-client-ip"))
+        .and_then(|header| header.to_str().ok())
+        .unwrap_or("unknown");
+
+    // Check rate limit
+    let rate_key = format!("{}:{}", request.uri().path(), client_ip);
+    let (allowed, current_count, remaining) = rate_limiter.is_allowed(&rate_key).await;
+
+    if !allowed {
+        let error_response = json!({
+            "success": false,
+            "error": "Rate limit exceeded",
+            "details": {
+                "limit": limits.password_reset,
+                "current": current_count,
+                "remaining": remaining,
+                "reset_in_seconds": limits.window_seconds
+            }
+        });
+
+        return Err((StatusCode::TOO_MANY_REQUESTS, axum::Json(error_response)));
+    }
+
+    // Add rate limit headers to response
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("x-ratelimit-limit", limits.password_reset.to_string().parse().unwrap());
+    headers.insert("x-ratelimit-remaining", remaining.to_string().parse().unwrap());
+    headers.insert("x-ratelimit-reset", limits.window_seconds.to_string().parse().unwrap());
+
+    Ok(response)
+}
+
 /// User-based rate limiting middleware (requires authentication)
 pub async fn user_rate_limit_middleware(
     State((_config, _db_manager)): State<(Arc<UserServiceConfig>, Arc<DatabaseManager>)>,
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use shared::query::{FilterOp, FilterSpec, ListQuery, PageRequest, SortDirection, SortSpec, MAX_PAGE_LIMIT};
+use shared::ChaosResult;
 use validator::Validate;
 
 /// User registration request
@@ -54,6 +56,32 @@ pub struct ChangePasswordRequest {
     pub new_password: String,
 }
 
+/// Forgot password request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email(message = "Invalid email format"))]
+    #[validate(length(max = 255, message = "Email must be less than 255 characters"))]
+    pub email: String,
+}
+
+/// Reset password request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    #[validate(length(min = 1, max = 128, message = "Reset token is required"))]
+    pub token: String,
+    
+    #[validate(length(min = 8, max = 128, message = "New password must be between 8 and 128 characters"))]
+    #[serde(alias = "newPassword")]
+    pub new_password: String,
+}
+
+/// Unlock account request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct UnlockAccountRequest {
+    #[validate(length(min = 1, max = 128, message = "Unlock token is required"))]
+    pub token: String,
+}
+
 /// Update profile request
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct UpdateProfileRequest {
@@ -114,6 +142,24 @@ impl Default for PaginationParams {
     }
 }
 
+impl PaginationParams {
+    /// Fields users can be sorted by
+    pub const SORT_FIELDS: &'static [&'static str] = &["created_at", "updated_at", "last_login", "username", "email"];
+
+    /// Build a list query from the pagination parameters
+    pub fn to_list_query(&self) -> ChaosResult<ListQuery> {
+        // Clamp first so the offset counts pages of the size actually returned
+        let page = PageRequest::page(self.page.unwrap_or(1), self.limit.unwrap_or(20).clamp(1, MAX_PAGE_LIMIT));
+        let direction = match self.sort_order.as_deref() {
+            Some(order) => order.parse()?,
+            None => SortDirection::Desc,
+        };
+        let sort = SortSpec::new().by(self.sort_by.as_deref().unwrap_or("created_at"), direction);
+        sort.validate_fields(Self::SORT_FIELDS)?;
+        Ok(ListQuery::new(page).with_sort(sort))
+    }
+}
+
 /// Paginated response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaginatedResponse<T> {
@@ -133,6 +179,68 @@ pub struct PaginationInfo {
     pub has_prev: bool,
 }
 
+impl PaginationInfo {
+    /// Pagination information of an offset page
+    pub fn new(page: u32, limit: u32, total: u64) -> Self {
+        let total_pages = total.div_ceil(limit.max(1) as u64) as u32;
+        Self {
+            page,
+            limit,
+            total,
+            total_pages,
+            has_next: page < total_pages,
+            has_prev: page > 1,
+        }
+    }
+}
+
+/// Audit event query parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditQueryParams {
+    pub page: Option<u32>,
+    pub limit: Option<u32>,
+    pub user_id: Option<uuid::Uuid>,
+    pub event_type: Option<String>,
+    pub ip_address: Option<String>,
+    pub from: Option<chrono::DateTime<chrono::Utc>>,
+    pub to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl AuditQueryParams {
+    /// Requested page, clamped to the maximum page size
+    pub fn page_request(&self) -> PageRequest {
+        // Clamp first so the offset counts pages of the size actually returned
+        PageRequest::page(self.page.unwrap_or(1).max(1), self.limit.unwrap_or(50).clamp(1, MAX_PAGE_LIMIT))
+    }
+}
+
+/// Admin update user status request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AdminUpdateUserStatusRequest {
+    #[validate(length(min = 1, message = "Status is required"))]
+    pub status: String,
+    
+    #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
+    pub reason: String,
+}
+
+/// Internal sanction request, sent by the anti-cheat service
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InternalSanctionRequest {
+    /// warn, mute, unmute, suspend, ban or reinstate
+    pub action: String,
+
+    #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
+    pub reason: String,
+
+    /// Sanction in the calling service, recorded in the audit log
+    #[validate(length(max = 64, message = "Reference must be at most 64 characters"))]
+    pub reference: Option<String>,
+
+    /// When a mute or suspension ends; lifting it is up to the caller
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// User filter parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserFilterParams {
@@ -143,6 +251,18 @@ pub struct UserFilterParams {
     pub search: Option<String>,
 }
 
+impl UserFilterParams {
+    /// Build a filter spec from the filter parameters (search matches usernames)
+    pub fn to_filter_spec(&self) -> FilterSpec {
+        FilterSpec::new()
+            .with_opt("status", FilterOp::Eq, self.status.clone())
+            .with_opt("email_verified", FilterOp::Eq, self.email_verified)
+            .with_opt("created_at", FilterOp::Gte, self.created_after.map(|date| date.to_rfc3339()))
+            .with_opt("created_at", FilterOp::Lte, self.created_before.map(|date| date.to_rfc3339()))
+            .with_opt("username", FilterOp::Contains, self.search.clone())
+    }
+}
+
 /// Admin user management request
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct AdminUpdateUserRequest {
@@ -161,6 +281,43 @@ pub struct AdminAssignRoleRequest {
     pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Admin grant permission request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct AdminGrantPermissionRequest {
+    #[validate(length(min = 1, max = 64, message = "Permission is required"))]
+    pub permission: String,
+    
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Link character request
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct LinkCharacterRequest {
+    #[validate(length(min = 1, max = 64, message = "World ID must be between 1 and 64 characters"))]
+    pub world_id: String,
+    
+    #[validate(length(min = 1, max = 64, message = "Actor ID must be between 1 and 64 characters"))]
+    pub actor_id: String,
+    
+    #[validate(length(max = 64, message = "Character name must be at most 64 characters"))]
+    pub name: Option<String>,
+}
+
+/// Internal link character request, sent by game services on behalf of a user
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InternalLinkCharacterRequest {
+    pub user_id: uuid::Uuid,
+    
+    #[validate(length(min = 1, max = 64, message = "World ID must be between 1 and 64 characters"))]
+    pub world_id: String,
+    
+    #[validate(length(min = 1, max = 64, message = "Actor ID must be between 1 and 64 characters"))]
+    pub actor_id: String,
+    
+    #[validate(length(max = 64, message = "Character name must be at most 64 characters"))]
+    pub name: Option<String>,
+}
+
 /// Rate limit information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RateLimitInfo {
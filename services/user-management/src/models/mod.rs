@@ -1,5 +1,7 @@
 pub mod user;
 pub mod dto;
+pub mod rbac;
 
 pub use user::*;
 pub use dto::*;
+pub use rbac::*;
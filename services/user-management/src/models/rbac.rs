@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Permission names, `<resource>:<action>`
+pub mod permissions {
+    pub const GAME_PLAY: &str = "game:play";
+    pub const SHOP_PURCHASE: &str = "shop:purchase";
+    pub const PROFILE_EDIT: &str = "profile:edit";
+    pub const GUILD_JOIN: &str = "guild:join";
+    pub const PLAYERS_READ: &str = "players:read";
+    pub const PLAYERS_MODERATE: &str = "players:moderate";
+    pub const EVENTS_MANAGE: &str = "events:manage";
+    pub const USERS_MANAGE: &str = "users:manage";
+    pub const ROLES_MANAGE: &str = "roles:manage";
+    pub const AUDIT_READ: &str = "audit:read";
+    /// Only carried by refresh tokens
+    pub const AUTH_REFRESH: &str = "auth:refresh";
+}
+
+/// User role, each role includes the permissions of the roles below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Player,
+    Gm,
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Player, Role::Gm, Role::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Player => "player",
+            Role::Gm => "gm",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Permissions granted by the role
+    pub fn permissions(&self) -> Vec<&'static str> {
+        use permissions::*;
+
+        let mut granted = vec![GAME_PLAY, SHOP_PURCHASE, PROFILE_EDIT, GUILD_JOIN];
+        if *self >= Role::Gm {
+            granted.extend([PLAYERS_READ, PLAYERS_MODERATE, EVENTS_MANAGE]);
+        }
+        if *self >= Role::Admin {
+            granted.extend([USERS_MANAGE, ROLES_MANAGE, AUDIT_READ]);
+        }
+        granted
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "player" => Ok(Role::Player),
+            "gm" => Ok(Role::Gm),
+            "admin" => Ok(Role::Admin),
+            _ => Err(format!("Invalid role: {}", s)),
+        }
+    }
+}
+
+/// Check that a permission name has the `<resource>:<action>` form
+pub fn is_valid_permission(permission: &str) -> bool {
+    let valid_part = |part: &str| {
+        !part.is_empty() && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    };
+    match permission.split_once(':') {
+        Some((resource, action)) => {
+            permission.len() <= 64 && valid_part(resource) && valid_part(action) && permission != permissions::AUTH_REFRESH
+        }
+        None => false,
+    }
+}
+
+/// Roles and effective permissions of a user, as embedded in access tokens
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserAccess {
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>,
+}
+
+impl UserAccess {
+    /// Combine granted roles and individually granted permissions
+    ///
+    /// Every user is a player, whatever was granted.
+    pub fn new(roles: impl IntoIterator<Item = Role>, extra_permissions: impl IntoIterator<Item = String>) -> Self {
+        let roles: BTreeSet<Role> = roles.into_iter().chain([Role::Player]).collect();
+        let permissions: BTreeSet<String> = roles
+            .iter()
+            .flat_map(|role| role.permissions())
+            .map(str::to_string)
+            .chain(extra_permissions)
+            .collect();
+
+        Self {
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            permissions: permissions.into_iter().collect(),
+        }
+    }
+
+    /// Add a role and its permissions
+    pub fn with_role(self, role: Role) -> Self {
+        let roles: Vec<Role> = self.roles.iter().filter_map(|role| role.parse().ok()).chain([role]).collect();
+        Self::new(roles, self.permissions)
+    }
+}
+
+impl Default for UserAccess {
+    fn default() -> Self {
+        Self::new([], [])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_include_lower_roles() {
+        let player = Role::Player.permissions();
+        let gm = Role::Gm.permissions();
+        let admin = Role::Admin.permissions();
+
+        assert!(player.iter().all(|permission| gm.contains(permission)));
+        assert!(gm.iter().all(|permission| admin.contains(permission)));
+        assert!(!gm.contains(&permissions::ROLES_MANAGE));
+        assert!(admin.contains(&permissions::ROLES_MANAGE));
+        assert!(!gm.contains(&permissions::AUDIT_READ));
+        assert!(admin.contains(&permissions::AUDIT_READ));
+    }
+
+    #[test]
+    fn test_user_access() {
+        let access = UserAccess::default();
+        assert_eq!(access.roles, vec!["player"]);
+        assert!(access.permissions.contains(&permissions::GAME_PLAY.to_string()));
+
+        let access = UserAccess::new([Role::Gm, Role::Gm], ["market:audit".to_string()]);
+        assert_eq!(access.roles, vec!["player", "gm"]);
+        assert!(access.permissions.contains(&permissions::PLAYERS_MODERATE.to_string()));
+        assert!(access.permissions.contains(&"market:audit".to_string()));
+        assert!(!access.permissions.contains(&permissions::ROLES_MANAGE.to_string()));
+
+        let access = access.with_role(Role::Admin);
+        assert_eq!(access.roles, vec!["player", "gm", "admin"]);
+        assert!(access.permissions.contains(&permissions::ROLES_MANAGE.to_string()));
+        assert!(access.permissions.contains(&"market:audit".to_string()));
+    }
+
+    #[test]
+    fn test_permission_names() {
+        assert!(is_valid_permission("events:manage"));
+        assert!(is_valid_permission("guild_bank:withdraw"));
+        assert!(!is_valid_permission("events"));
+        assert!(!is_valid_permission("Events:Manage"));
+        assert!(!is_valid_permission("events:"));
+        assert!(!is_valid_permission(permissions::AUTH_REFRESH));
+    }
+}
@@ -90,17 +90,26 @@ pub struct User {
     pub updated_at: DateTime<Utc>,
     pub last_login: Option<DateTime<Utc>>,
     pub login_count: i32,
+    /// Refresh tokens issued before this time are rejected
+    #[serde(default)]
+    pub password_changed_at: Option<DateTime<Utc>>,
 }
 
 /// User session entity
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSession {
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
     pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
     pub user_id: Uuid,
     pub session_token: String,
     pub refresh_token: String,
+    /// Stored as a BSON date so the TTL index removes expired sessions
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub expires_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub created_at: DateTime<Utc>,
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub last_accessed: DateTime<Utc>,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
@@ -126,6 +135,7 @@ pub struct UserPreferences {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserRole {
     pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
     pub user_id: Uuid,
     pub role: String,
     pub granted_by: Option<Uuid>,
@@ -134,6 +144,135 @@ pub struct UserRole {
     pub is_active: bool,
 }
 
+/// Permission granted to a user on top of their roles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserPermission {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub permission: String,
+    pub granted_by: Option<Uuid>,
+    pub granted_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub is_active: bool,
+}
+
+/// Password reset token entity (only the SHA-256 hash of the emailed token is stored)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordResetToken {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub used_at: Option<bson::DateTime>,
+    pub ip_address: Option<String>,
+}
+
+/// Account unlock token entity (only the SHA-256 hash of the emailed token is stored)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountUnlockToken {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+    pub used_at: Option<bson::DateTime>,
+}
+
+/// Failed login counter and lock state of an account (`account:<user id>`) or an IP (`ip:<address>`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginLockout {
+    pub key: String,
+    pub failed_count: i64,
+    pub window_started_at: bson::DateTime,
+    /// Number of locks so far, drives the lock duration
+    pub lock_level: i64,
+    pub locked_until: Option<bson::DateTime>,
+    pub last_locked_at: Option<bson::DateTime>,
+    /// Removal time for the TTL index, once the record no longer matters
+    pub expires_at: bson::DateTime,
+}
+
+/// Device a user has logged in from, to detect new-device logins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownDevice {
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub device: String,
+    pub last_ip: Option<String>,
+    pub first_seen: bson::DateTime,
+    pub last_seen: bson::DateTime,
+}
+
+/// Pending OAuth authorization, consumed by the provider callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthState {
+    pub state: String,
+    pub provider: String,
+    pub pkce_verifier: Option<String>,
+    pub expires_at: bson::DateTime,
+    pub created_at: bson::DateTime,
+}
+
+/// Link between a user and an external login provider account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthAccount {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub provider: String,
+    pub provider_user_id: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_login: Option<DateTime<Utc>>,
+}
+
+/// Link between a user and a game character (actor) in a world
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterLink {
+    pub id: Uuid,
+    #[serde(serialize_with = "serialize_uuid_as_binary", deserialize_with = "deserialize_uuid_from_binary")]
+    pub user_id: Uuid,
+    pub world_id: String,
+    pub actor_id: String,
+    pub name: Option<String>,
+    pub linked_at: DateTime<Utc>,
+}
+
+/// Security audit event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub event_type: String,
+    pub user_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub details: Option<String>,
+    pub created_at: bson::DateTime,
+}
+
+impl AuditEvent {
+    pub fn new(event_type: &str, user_id: Option<Uuid>, ip_address: Option<String>, user_agent: Option<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            event_type: event_type.to_string(),
+            user_id: user_id.map(|id| id.to_string()),
+            ip_address,
+            user_agent,
+            details: None,
+            created_at: bson::DateTime::now(),
+        }
+    }
+
+    pub fn with_details(mut self, details: &str) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+}
+
 /// Public user information (without sensitive data)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicUser {
@@ -183,6 +322,13 @@ pub struct TokenClaims {
     pub aud: String,
 }
 
+impl TokenClaims {
+    /// Check if the token grants a permission
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == permission)
+    }
+}
+
 /// Token response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenResponse {
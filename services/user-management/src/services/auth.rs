@@ -1,12 +1,16 @@
 use crate::config::UserServiceConfig;
-use crate::models::{User, UserSession, TokenClaims, TokenResponse, UserStatus};
+use crate::models::{User, UserSession, TokenClaims, TokenResponse, UserStatus, UserAccess, permissions};
 use argon2::{Argon2, PasswordHash, PasswordHasher, PasswordVerifier};
-use argon2::password_hash::{rand_core::OsRng, SaltString};
+use argon2::password_hash::{rand_core::{OsRng, RngCore}, SaltString};
 use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use uuid::Uuid;
 use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
+/// Random bytes in a password reset token
+const RESET_TOKEN_BYTES: usize = 32;
+
 /// Authentication service for handling user authentication and JWT tokens
 pub struct AuthService {
     config: Arc<UserServiceConfig>,
@@ -48,8 +52,8 @@ impl AuthService {
         Ok(result.is_ok())
     }
 
-    /// Generate access and refresh tokens for a user
-    pub fn generate_tokens(&self, user: &User, session_id: Uuid) -> Result<TokenResponse, Box<dyn std::error::Error>> {
+    /// Generate access and refresh tokens for a user, with their roles and permissions
+    pub fn generate_tokens(&self, user: &User, session_id: Uuid, access: &UserAccess) -> Result<TokenResponse, Box<dyn std::error::Error>> {
         let now = Utc::now();
         let access_exp = now + Duration::seconds(self.config.jwt.access_expiry_seconds as i64);
         let refresh_exp = now + Duration::seconds(self.config.jwt.refresh_expiry_seconds as i64);
@@ -59,13 +63,8 @@ impl AuthService {
             user_id: user.id,
             username: user.username.clone(),
             email: user.email.clone(),
-            roles: vec!["player".to_string()], // Default role
-            permissions: vec![
-                "game:play".to_string(),
-                "shop:purchase".to_string(),
-                "profile:edit".to_string(),
-                "guild:join".to_string(),
-            ],
+            roles: access.roles.clone(),
+            permissions: access.permissions.clone(),
             session_id,
             iat: now.timestamp(),
             exp: access_exp.timestamp(),
@@ -78,8 +77,8 @@ impl AuthService {
             user_id: user.id,
             username: user.username.clone(),
             email: user.email.clone(),
-            roles: vec!["player".to_string()],
-            permissions: vec!["auth:refresh".to_string()],
+            roles: access.roles.clone(),
+            permissions: vec![permissions::AUTH_REFRESH.to_string()],
             session_id,
             iat: now.timestamp(),
             exp: refresh_exp.timestamp(),
@@ -118,7 +117,7 @@ impl AuthService {
         let token_data = decode::<TokenClaims>(token, &self.decoding_key, &validation)?;
         
         // Check if token has refresh permission
-        if !token_data.claims.permissions.contains(&"auth:refresh".to_string()) {
+        if !token_data.claims.has_permission(permissions::AUTH_REFRESH) {
             return Err("Invalid refresh token".into());
         }
 
@@ -224,6 +223,27 @@ impl AuthService {
         matches!(user.status, UserStatus::Active)
     }
 
+    /// Generate a password reset token, returning the token to email and the hash to store
+    pub fn generate_reset_token(&self) -> (String, String) {
+        let mut bytes = [0u8; RESET_TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        let token = hex::encode(bytes);
+        let token_hash = Self::hash_reset_token(&token);
+        (token, token_hash)
+    }
+
+    /// Hash a password reset token for storage and lookup
+    pub fn hash_reset_token(token: &str) -> String {
+        hex::encode(Sha256::digest(token.as_bytes()))
+    }
+
+    /// Check if a refresh token was issued before the user's last password change
+    pub fn is_refresh_token_revoked(&self, user: &User, claims: &TokenClaims) -> bool {
+        user.password_changed_at
+            .map(|changed_at| claims.iat < changed_at.timestamp())
+            .unwrap_or(false)
+    }
+
     // Unused methods removed for cleaner code
 }
 
@@ -304,10 +324,11 @@ mod tests {
             updated_at: Utc::now(),
             last_login: None,
             login_count: 0,
+            password_changed_at: None,
         };
         
         let session_id = Uuid::new_v4();
-        let tokens = auth_service.generate_tokens(&user, session_id).unwrap();
+        let tokens = auth_service.generate_tokens(&user, session_id, &UserAccess::default()).unwrap();
         
         assert!(!tokens.access_token.is_empty());
         assert!(!tokens.refresh_token.is_empty());
@@ -332,14 +353,97 @@ mod tests {
             updated_at: Utc::now(),
             last_login: None,
             login_count: 0,
+            password_changed_at: None,
         };
         
         let session_id = Uuid::new_v4();
-        let tokens = auth_service.generate_tokens(&user, session_id).unwrap();
+        let tokens = auth_service.generate_tokens(&user, session_id, &UserAccess::default()).unwrap();
         
         let claims = auth_service.validate_token(&tokens.access_token).unwrap();
         assert_eq!(claims.user_id, user.id);
         assert_eq!(claims.username, user.username);
         assert_eq!(claims.email, user.email);
+        assert_eq!(claims.roles, vec!["player"]);
+        assert!(claims.has_permission(permissions::GAME_PLAY));
+        assert!(!claims.has_permission(permissions::ROLES_MANAGE));
+    }
+
+    #[test]
+    fn test_token_carries_granted_access() {
+        let config = Arc::new(create_test_config());
+        let auth_service = AuthService::new(config).unwrap();
+        
+        let user = User {
+            id: Uuid::new_v4(),
+            username: "gamemaster".to_string(),
+            email: "gm@example.com".to_string(),
+            password_hash: "hashed_password".to_string(),
+            display_name: None,
+            avatar_url: None,
+            status: UserStatus::Active,
+            email_verified: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login: None,
+            login_count: 0,
+            password_changed_at: None,
+        };
+        
+        let access = UserAccess::new([crate::models::Role::Gm], ["market:audit".to_string()]);
+        let tokens = auth_service.generate_tokens(&user, Uuid::new_v4(), &access).unwrap();
+        
+        let claims = auth_service.validate_token(&tokens.access_token).unwrap();
+        assert!(claims.roles.contains(&"gm".to_string()));
+        assert!(claims.has_permission(permissions::PLAYERS_MODERATE));
+        assert!(claims.has_permission("market:audit"));
+        
+        // Refresh tokens keep the roles but only allow refreshing
+        let refresh_claims = auth_service.validate_refresh_token(&tokens.refresh_token).unwrap();
+        assert!(refresh_claims.roles.contains(&"gm".to_string()));
+        assert_eq!(refresh_claims.permissions, vec![permissions::AUTH_REFRESH]);
+    }
+
+    #[test]
+    fn test_reset_token_generation() {
+        let config = Arc::new(create_test_config());
+        let auth_service = AuthService::new(config).unwrap();
+        
+        let (token, token_hash) = auth_service.generate_reset_token();
+        assert_eq!(token.len(), RESET_TOKEN_BYTES * 2);
+        assert_ne!(token, token_hash);
+        assert_eq!(AuthService::hash_reset_token(&token), token_hash);
+        
+        let (other_token, other_hash) = auth_service.generate_reset_token();
+        assert_ne!(token, other_token);
+        assert_ne!(token_hash, other_hash);
+    }
+
+    #[test]
+    fn test_refresh_token_revoked_by_password_change() {
+        let config = Arc::new(create_test_config());
+        let auth_service = AuthService::new(config).unwrap();
+        
+        let mut user = User {
+            id: Uuid::new_v4(),
+            username: "testuser".to_string(),
+            email: "test@example.com".to_string(),
+            password_hash: "hashed_password".to_string(),
+            display_name: None,
+            avatar_url: None,
+            status: UserStatus::Active,
+            email_verified: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            last_login: None,
+            login_count: 0,
+            password_changed_at: None,
+        };
+        
+        let tokens = auth_service.generate_tokens(&user, Uuid::new_v4(), &UserAccess::default()).unwrap();
+        let claims = auth_service.validate_refresh_token(&tokens.refresh_token).unwrap();
+        assert!(!auth_service.is_refresh_token_revoked(&user, &claims));
+        
+        user.password_changed_at = Some(Utc::now() + Duration::seconds(1));
+        assert!(auth_service.is_refresh_token_revoked(&user, &claims));
     }
 }
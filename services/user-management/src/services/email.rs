@@ -0,0 +1,115 @@
+use crate::config::UserServiceConfig;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Arc;
+
+/// Email service for sending account emails over SMTP
+pub struct EmailService {
+    config: Arc<UserServiceConfig>,
+}
+
+impl EmailService {
+    /// Create a new email service
+    pub fn new(config: Arc<UserServiceConfig>) -> Self {
+        Self { config }
+    }
+
+    /// Send a password reset link
+    pub async fn send_password_reset(&self, to: &str, username: &str, token: &str) -> Result<(), String> {
+        let expiry_minutes = self.config.password_reset.token_expiry_seconds / 60;
+        let body = format!(
+            "Hi {},\n\n\
+             We received a request to reset the password of your {} account.\n\
+             Open the link below to choose a new password. It expires in {} minutes and can only be used once.\n\n\
+             {}\n\n\
+             If you did not request a password reset, you can ignore this email.",
+            username,
+            self.config.email.from_name,
+            expiry_minutes.max(1),
+            token_link(&self.config.password_reset.reset_url, token),
+        );
+
+        self.send(to, "Reset your password", body).await
+    }
+
+    /// Tell a user their account was locked after failed logins, with an unlock link
+    pub async fn send_account_locked(&self, to: &str, username: &str, lock_seconds: u64, token: &str) -> Result<(), String> {
+        let expiry_minutes = self.config.login_security.unlock_token_expiry_seconds / 60;
+        let body = format!(
+            "Hi {},\n\n\
+             Your {} account was locked for {} minutes after several failed login attempts.\n\
+             If this was you, open the link below to unlock it now. It expires in {} minutes and can only be used once.\n\n\
+             {}\n\n\
+             If this was not you, someone may be trying to guess your password. Consider resetting it.",
+            username,
+            self.config.email.from_name,
+            (lock_seconds / 60).max(1),
+            expiry_minutes.max(1),
+            token_link(&self.config.login_security.unlock_url, token),
+        );
+
+        self.send(to, "Your account was locked", body).await
+    }
+
+    /// Tell a user about a login from a device not seen before
+    pub async fn send_new_device_login(
+        &self,
+        to: &str,
+        username: &str,
+        device: &str,
+        ip_address: Option<&str>,
+    ) -> Result<(), String> {
+        let body = format!(
+            "Hi {},\n\n\
+             Your {} account was just used to log in from a new device:\n\n\
+             Device: {}\n\
+             IP address: {}\n\
+             Time: {}\n\n\
+             If this was you, there is nothing to do. If not, reset your password and log out your other sessions.",
+            username,
+            self.config.email.from_name,
+            device,
+            ip_address.unwrap_or("unknown"),
+            chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+        );
+
+        self.send(to, "New login to your account", body).await
+    }
+
+    /// Send a plain text email
+    async fn send(&self, to: &str, subject: &str, body: String) -> Result<(), String> {
+        let email = &self.config.email;
+        let from = Mailbox::new(
+            Some(email.from_name.clone()),
+            email.from_email.parse().map_err(|e| format!("Invalid sender address: {}", e))?,
+        );
+        let to: Mailbox = to.parse().map_err(|e| format!("Invalid recipient address: {}", e))?;
+
+        let message = Message::builder()
+            .from(from)
+            .to(to)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| format!("Failed to build email: {}", e))?;
+
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&email.smtp_host)
+            .map_err(|e| format!("Failed to create SMTP transport: {}", e))?
+            .port(email.smtp_port)
+            .credentials(Credentials::new(email.smtp_username.clone(), email.smtp_password.clone()))
+            .build();
+
+        mailer
+            .send(message)
+            .await
+            .map_err(|e| format!("Failed to send email: {}", e))?;
+        Ok(())
+    }
+}
+
+/// Append a token to a link as the `token` query parameter
+fn token_link(url: &str, token: &str) -> String {
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!("{}{}token={}", url, separator, token)
+}
@@ -0,0 +1,205 @@
+use crate::config::UserServiceConfig;
+use crate::models::LoginLockout;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Timeout of suspicious login reports to the anti-cheat service
+const REPORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lock to apply after a failed login
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockDecision {
+    pub lock_level: i64,
+    pub lock_seconds: u64,
+}
+
+/// Progressive lockout policy for failed logins
+pub struct LoginSecurityService {
+    config: Arc<UserServiceConfig>,
+}
+
+impl LoginSecurityService {
+    /// Create a new login security service
+    pub fn new(config: Arc<UserServiceConfig>) -> Self {
+        Self { config }
+    }
+
+    /// Lockout key of an account
+    pub fn account_key(user_id: Uuid) -> String {
+        format!("account:{}", user_id)
+    }
+
+    /// Lockout key of an IP address
+    pub fn ip_key(ip_address: &str) -> String {
+        format!("ip:{}", ip_address)
+    }
+
+    /// Seconds until a lock ends, `None` when the key is not locked
+    pub fn remaining_lock_seconds(record: &LoginLockout, now: bson::DateTime) -> Option<i64> {
+        let locked_until = record.locked_until?;
+        let remaining_millis = locked_until.timestamp_millis() - now.timestamp_millis();
+        (remaining_millis > 0).then_some((remaining_millis + 999) / 1000)
+    }
+
+    /// Decide whether a failed login locks the key
+    ///
+    /// Each lock lasts twice as long as the previous one, up to the maximum. The escalation
+    /// starts over once no lock happened for the decay period.
+    pub fn lock_after_failure(&self, record: &LoginLockout, threshold: u32, now: bson::DateTime) -> Option<LockDecision> {
+        if record.failed_count < threshold as i64 {
+            return None;
+        }
+
+        let decay_millis = (self.config.login_security.lock_decay_seconds as i64) * 1000;
+        let previous_level = match record.last_locked_at {
+            Some(locked_at) if now.timestamp_millis() - locked_at.timestamp_millis() < decay_millis => record.lock_level,
+            _ => 0,
+        };
+        let lock_level = previous_level + 1;
+
+        Some(LockDecision {
+            lock_level,
+            lock_seconds: self.lock_duration_seconds(lock_level),
+        })
+    }
+
+    /// Duration of the n-th lock
+    pub fn lock_duration_seconds(&self, lock_level: i64) -> u64 {
+        let security = &self.config.login_security;
+        let doublings = (lock_level - 1).clamp(0, 32) as u32;
+        security.base_lock_seconds
+            .saturating_mul(2u64.saturating_pow(doublings))
+            .min(security.max_lock_seconds)
+    }
+
+    /// When a lockout record stops mattering: after its window or lock, plus the decay period
+    pub fn record_expiry(&self, now: bson::DateTime, locked_until: Option<bson::DateTime>) -> bson::DateTime {
+        let security = &self.config.login_security;
+        let window_end = now.timestamp_millis() + (security.failure_window_seconds as i64) * 1000;
+        let relevant_until = locked_until
+            .map(|locked_until| locked_until.timestamp_millis().max(window_end))
+            .unwrap_or(window_end);
+        bson::DateTime::from_millis(relevant_until + (security.lock_decay_seconds as i64) * 1000)
+    }
+}
+
+/// Suspicious login pattern reported to the anti-cheat service
+#[derive(Debug, Clone, Serialize)]
+pub struct SuspiciousLoginReport {
+    /// `account_locked`, `ip_locked` or `new_device_after_failures`
+    pub kind: &'static str,
+    pub user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub failed_attempts: i64,
+    pub lock_level: i64,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Reports suspicious login patterns to the anti-cheat service, when configured
+pub struct AntiCheatReporter {
+    config: Arc<UserServiceConfig>,
+}
+
+impl AntiCheatReporter {
+    /// Create a new reporter
+    pub fn new(config: Arc<UserServiceConfig>) -> Self {
+        Self { config }
+    }
+
+    /// Send a report in the background, logins never wait for or fail on the anti-cheat service
+    pub fn report(&self, report: SuspiciousLoginReport) {
+        let security = &self.config.login_security;
+        let Some(base_url) = security.anti_cheat_url.clone() else {
+            return;
+        };
+        let api_key = security.anti_cheat_api_key.clone();
+
+        tokio::spawn(async move {
+            let url = format!("{}/internal/reports/suspicious-login", base_url.trim_end_matches('/'));
+            let mut request = reqwest::Client::new().post(&url).timeout(REPORT_TIMEOUT).json(&report);
+            if let Some(api_key) = api_key {
+                request = request.header("X-Internal-Api-Key", api_key);
+            }
+
+            match request.send().await.and_then(|response| response.error_for_status()) {
+                Ok(_) => tracing::info!("Reported {} to anti-cheat service", report.kind),
+                Err(e) => tracing::warn!("Failed to report {} to anti-cheat service: {}", report.kind, e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service() -> LoginSecurityService {
+        LoginSecurityService::new(Arc::new(UserServiceConfig::default()))
+    }
+
+    fn record(failed_count: i64, lock_level: i64, last_locked_at: Option<bson::DateTime>) -> LoginLockout {
+        let now = bson::DateTime::now();
+        LoginLockout {
+            key: "account:test".to_string(),
+            failed_count,
+            window_started_at: now,
+            lock_level,
+            locked_until: None,
+            last_locked_at,
+            expires_at: now,
+        }
+    }
+
+    #[test]
+    fn test_lock_durations_escalate() {
+        let service = service();
+        assert_eq!(service.lock_duration_seconds(1), 60);
+        assert_eq!(service.lock_duration_seconds(2), 120);
+        assert_eq!(service.lock_duration_seconds(5), 960);
+        assert_eq!(service.lock_duration_seconds(30), 86400);
+        assert_eq!(service.lock_duration_seconds(i64::MAX), 86400);
+    }
+
+    #[test]
+    fn test_lock_after_failure() {
+        let service = service();
+        let now = bson::DateTime::now();
+
+        assert_eq!(service.lock_after_failure(&record(4, 0, None), 5, now), None);
+        assert_eq!(
+            service.lock_after_failure(&record(5, 0, None), 5, now),
+            Some(LockDecision { lock_level: 1, lock_seconds: 60 })
+        );
+
+        // Recent lock escalates
+        let recent = bson::DateTime::from_millis(now.timestamp_millis() - 3_600_000);
+        assert_eq!(
+            service.lock_after_failure(&record(5, 2, Some(recent)), 5, now),
+            Some(LockDecision { lock_level: 3, lock_seconds: 240 })
+        );
+
+        // Old lock starts over
+        let old = bson::DateTime::from_millis(now.timestamp_millis() - 2 * 86_400_000);
+        assert_eq!(
+            service.lock_after_failure(&record(5, 2, Some(old)), 5, now),
+            Some(LockDecision { lock_level: 1, lock_seconds: 60 })
+        );
+    }
+
+    #[test]
+    fn test_remaining_lock_seconds() {
+        let now = bson::DateTime::now();
+        let mut lockout = record(0, 1, Some(now));
+        assert_eq!(LoginSecurityService::remaining_lock_seconds(&lockout, now), None);
+
+        lockout.locked_until = Some(bson::DateTime::from_millis(now.timestamp_millis() + 59_500));
+        assert_eq!(LoginSecurityService::remaining_lock_seconds(&lockout, now), Some(60));
+
+        lockout.locked_until = Some(bson::DateTime::from_millis(now.timestamp_millis() - 1));
+        assert_eq!(LoginSecurityService::remaining_lock_seconds(&lockout, now), None);
+    }
+}
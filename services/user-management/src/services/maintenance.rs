@@ -0,0 +1,65 @@
+//! Maintenance mode: while it is on, only game masters and admins can log in.
+
+use shared::events::NatsEventBus;
+use shared::maintenance::MaintenanceFlag;
+
+use crate::config::MaintenanceConfig;
+use crate::models::{Role, UserAccess};
+
+lazy_static::lazy_static! {
+    /// Latest maintenance state published by `admin-cli world maintenance`
+    pub static ref MAINTENANCE: MaintenanceFlag = MaintenanceFlag::new();
+}
+
+/// Follow the maintenance state on the event bus, when one is configured
+pub async fn follow_maintenance(config: &MaintenanceConfig) {
+    let Some(nats_url) = &config.nats_url else {
+        tracing::info!("No event bus configured, maintenance mode is unavailable");
+        return;
+    };
+    let bus = match NatsEventBus::connect(nats_url).await {
+        Ok(bus) => bus,
+        Err(e) => {
+            tracing::error!("Failed to connect to {} for maintenance updates: {}", nats_url, e);
+            return;
+        }
+    };
+    let bus = match &config.subject_prefix {
+        Some(prefix) => match bus.with_subject_prefix(prefix) {
+            Ok(bus) => bus,
+            Err(e) => {
+                tracing::error!("Invalid subject prefix {}: {}", prefix, e);
+                return;
+            }
+        },
+        None => bus,
+    };
+    if let Err(e) = MAINTENANCE.follow(&bus).await {
+        tracing::error!("Failed to subscribe to maintenance updates: {}", e);
+    }
+}
+
+/// Whether a user may log in during maintenance
+pub fn admitted_during_maintenance(access: &UserAccess) -> bool {
+    access.roles.iter().any(|role| role.parse::<Role>().is_ok_and(|role| role >= Role::Gm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(roles: &[&str]) -> UserAccess {
+        UserAccess {
+            roles: roles.iter().map(|role| role.to_string()).collect(),
+            permissions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_staff_are_admitted() {
+        assert!(admitted_during_maintenance(&access(&["player", "gm"])));
+        assert!(admitted_during_maintenance(&access(&["admin"])));
+        assert!(!admitted_during_maintenance(&access(&["player"])));
+        assert!(!admitted_during_maintenance(&access(&["unknown"])));
+    }
+}
@@ -1,3 +1,11 @@
 pub mod auth;
+pub mod email;
+pub mod login_security;
+pub mod maintenance;
+pub mod oauth;
 
 pub use auth::*;
+pub use email::*;
+pub use login_security::*;
+pub use maintenance::*;
+pub use oauth::*;
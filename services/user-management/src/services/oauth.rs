@@ -0,0 +1,531 @@
+use crate::config::{OAuthProviderConfig, UserServiceConfig};
+use oauth2::basic::BasicClient;
+use oauth2::reqwest::async_http_client;
+use oauth2::{
+    AuthUrl, AuthorizationCode, ClientId, ClientSecret, CsrfToken, PkceCodeChallenge,
+    PkceCodeVerifier, RedirectUrl, Scope, TokenResponse, TokenUrl,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Steam OpenID 2.0 endpoint
+const STEAM_OPENID_URL: &str = "https://steamcommunity.com/openid/login";
+
+/// Prefix of the claimed ID Steam returns, followed by the SteamID64
+const STEAM_CLAIMED_ID_PREFIX: &str = "https://steamcommunity.com/openid/id/";
+
+const OPENID_NS: &str = "http://specs.openid.net/auth/2.0";
+const OPENID_IDENTIFIER_SELECT: &str = "http://specs.openid.net/auth/2.0/identifier_select";
+
+/// Timeout of requests to login providers
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Supported login providers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthProvider {
+    Google,
+    Discord,
+    Steam,
+}
+
+impl OAuthProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OAuthProvider::Google => "google",
+            OAuthProvider::Discord => "discord",
+            OAuthProvider::Steam => "steam",
+        }
+    }
+}
+
+impl std::fmt::Display for OAuthProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for OAuthProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "google" => Ok(OAuthProvider::Google),
+            "discord" => Ok(OAuthProvider::Discord),
+            "steam" => Ok(OAuthProvider::Steam),
+            _ => Err(format!("Unknown OAuth provider: {}", s)),
+        }
+    }
+}
+
+/// Endpoints and scopes of an OAuth2 provider
+struct ProviderEndpoints {
+    auth_url: &'static str,
+    token_url: &'static str,
+    scopes: &'static [&'static str],
+}
+
+const GOOGLE_ENDPOINTS: ProviderEndpoints = ProviderEndpoints {
+    auth_url: "https://accounts.google.com/o/oauth2/v2/auth",
+    token_url: "https://oauth2.googleapis.com/token",
+    scopes: &["openid", "email", "profile"],
+};
+
+const DISCORD_ENDPOINTS: ProviderEndpoints = ProviderEndpoints {
+    auth_url: "https://discord.com/oauth2/authorize",
+    token_url: "https://discord.com/api/oauth2/token",
+    scopes: &["identify", "email"],
+};
+
+/// Identity reported by a login provider
+#[derive(Debug, Clone)]
+pub struct ProviderIdentity {
+    pub provider_user_id: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+/// Provider authorization page to send the user to
+#[derive(Debug, Clone)]
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub state: String,
+    pub pkce_verifier: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+    picture: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DiscordUser {
+    id: String,
+    username: String,
+    global_name: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    verified: bool,
+    avatar: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SteamPlayerSummaries {
+    response: SteamPlayers,
+}
+
+#[derive(Deserialize)]
+struct SteamPlayers {
+    players: Vec<SteamPlayer>,
+}
+
+#[derive(Deserialize)]
+struct SteamPlayer {
+    personaname: Option<String>,
+    avatarfull: Option<String>,
+}
+
+/// OAuth2 / OpenID login service for Google, Discord and Steam
+pub struct OAuthService {
+    config: Arc<UserServiceConfig>,
+    http: reqwest::Client,
+}
+
+impl OAuthService {
+    /// Create a new OAuth service
+    pub fn new(config: Arc<UserServiceConfig>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(PROVIDER_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { config, http }
+    }
+
+    /// Check if a provider is configured
+    pub fn is_enabled(&self, provider: OAuthProvider) -> bool {
+        match provider {
+            OAuthProvider::Google => self.config.oauth.google.is_some(),
+            OAuthProvider::Discord => self.config.oauth.discord.is_some(),
+            OAuthProvider::Steam => self.config.oauth.steam.is_some(),
+        }
+    }
+
+    /// URL the provider redirects back to
+    pub fn callback_url(&self, provider: OAuthProvider) -> String {
+        format!(
+            "{}/auth/oauth/{}/callback",
+            self.config.oauth.callback_base_url.trim_end_matches('/'),
+            provider
+        )
+    }
+
+    /// Build the provider authorization page URL with a fresh state
+    pub fn authorization_request(&self, provider: OAuthProvider) -> Result<AuthorizationRequest, String> {
+        if provider == OAuthProvider::Steam {
+            let state = CsrfToken::new_random().secret().clone();
+            let url = steam_login_url(
+                &self.callback_url(provider),
+                &self.config.oauth.callback_base_url,
+                &state,
+            )?;
+            return Ok(AuthorizationRequest {
+                url,
+                state,
+                pkce_verifier: None,
+            });
+        }
+
+        let (client, endpoints) = self.oauth_client(provider)?;
+        let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+        let (url, state) = client
+            .authorize_url(CsrfToken::new_random)
+            .add_scopes(endpoints.scopes.iter().map(|scope| Scope::new(scope.to_string())))
+            .set_pkce_challenge(pkce_challenge)
+            .url();
+
+        Ok(AuthorizationRequest {
+            url: url.to_string(),
+            state: state.secret().clone(),
+            pkce_verifier: Some(pkce_verifier.secret().clone()),
+        })
+    }
+
+    /// Verify the provider callback and fetch the user's identity
+    pub async fn authenticate(
+        &self,
+        provider: OAuthProvider,
+        params: &HashMap<String, String>,
+        pkce_verifier: Option<&str>,
+    ) -> Result<ProviderIdentity, String> {
+        match provider {
+            OAuthProvider::Google => {
+                let access_token = self.exchange_code(provider, params, pkce_verifier).await?;
+                let info: GoogleUserInfo = self
+                    .get_json("https://openidconnect.googleapis.com/v1/userinfo", &access_token)
+                    .await?;
+                Ok(ProviderIdentity {
+                    provider_user_id: info.sub,
+                    email: info.email,
+                    email_verified: info.email_verified,
+                    display_name: info.name,
+                    avatar_url: info.picture,
+                })
+            }
+            OAuthProvider::Discord => {
+                let access_token = self.exchange_code(provider, params, pkce_verifier).await?;
+                let user: DiscordUser = self
+                    .get_json("https://discord.com/api/users/@me", &access_token)
+                    .await?;
+                let avatar_url = user
+                    .avatar
+                    .as_ref()
+                    .map(|avatar| format!("https://cdn.discordapp.com/avatars/{}/{}.png", user.id, avatar));
+                Ok(ProviderIdentity {
+                    email_verified: user.verified && user.email.is_some(),
+                    email: user.email,
+                    display_name: user.global_name.or(Some(user.username)),
+                    avatar_url,
+                    provider_user_id: user.id,
+                })
+            }
+            OAuthProvider::Steam => {
+                let steam_id = self.verify_steam_assertion(params).await?;
+                let (display_name, avatar_url) = self.fetch_steam_profile(&steam_id).await;
+                Ok(ProviderIdentity {
+                    provider_user_id: steam_id,
+                    // Steam does not share email addresses
+                    email: None,
+                    email_verified: false,
+                    display_name,
+                    avatar_url,
+                })
+            }
+        }
+    }
+
+    /// OAuth2 client of a provider
+    fn oauth_client(&self, provider: OAuthProvider) -> Result<(BasicClient, &'static ProviderEndpoints), String> {
+        let (credentials, endpoints): (&Option<OAuthProviderConfig>, &'static ProviderEndpoints) = match provider {
+            OAuthProvider::Google => (&self.config.oauth.google, &GOOGLE_ENDPOINTS),
+            OAuthProvider::Discord => (&self.config.oauth.discord, &DISCORD_ENDPOINTS),
+            OAuthProvider::Steam => return Err("Steam does not use OAuth2".to_string()),
+        };
+        let credentials = credentials
+            .as_ref()
+            .ok_or_else(|| format!("OAuth provider {} is not configured", provider))?;
+
+        let client = BasicClient::new(
+            ClientId::new(credentials.client_id.clone()),
+            Some(ClientSecret::new(credentials.client_secret.clone())),
+            AuthUrl::new(endpoints.auth_url.to_string()).map_err(|e| e.to_string())?,
+            Some(TokenUrl::new(endpoints.token_url.to_string()).map_err(|e| e.to_string())?),
+        )
+        .set_redirect_uri(RedirectUrl::new(self.callback_url(provider)).map_err(|e| e.to_string())?);
+
+        Ok((client, endpoints))
+    }
+
+    /// Exchange the authorization code for an access token
+    async fn exchange_code(
+        &self,
+        provider: OAuthProvider,
+        params: &HashMap<String, String>,
+        pkce_verifier: Option<&str>,
+    ) -> Result<String, String> {
+        let code = params
+            .get("code")
+            .ok_or_else(|| "Missing authorization code".to_string())?;
+        let pkce_verifier = pkce_verifier.ok_or_else(|| "Missing PKCE verifier".to_string())?;
+        let (client, _) = self.oauth_client(provider)?;
+
+        let token = client
+            .exchange_code(AuthorizationCode::new(code.clone()))
+            .set_pkce_verifier(PkceCodeVerifier::new(pkce_verifier.to_string()))
+            .request_async(async_http_client)
+            .await
+            .map_err(|e| format!("Failed to exchange authorization code: {}", e))?;
+
+        Ok(token.access_token().secret().clone())
+    }
+
+    /// GET a JSON resource with a bearer token
+    async fn get_json<T: for<'de> Deserialize<'de>>(&self, url: &str, access_token: &str) -> Result<T, String> {
+        self.http
+            .get(url)
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to fetch user info: {}", e))?
+            .json::<T>()
+            .await
+            .map_err(|e| format!("Invalid user info response: {}", e))
+    }
+
+    /// Check a Steam OpenID assertion with Steam and return the SteamID64
+    async fn verify_steam_assertion(&self, params: &HashMap<String, String>) -> Result<String, String> {
+        let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+
+        if param("openid.mode") != "id_res" {
+            return Err("Steam login was cancelled or failed".to_string());
+        }
+        if param("openid.op_endpoint") != STEAM_OPENID_URL {
+            return Err("Unexpected OpenID provider".to_string());
+        }
+        if !param("openid.return_to").starts_with(&self.callback_url(OAuthProvider::Steam)) {
+            return Err("OpenID return URL does not match".to_string());
+        }
+        let steam_id = steam_id_from_claimed_id(param("openid.claimed_id"))
+            .ok_or_else(|| "Invalid Steam claimed ID".to_string())?
+            .to_string();
+
+        // Ask Steam to confirm the signed assertion
+        let mut form: Vec<(&str, &str)> = params
+            .iter()
+            .filter(|(key, _)| key.starts_with("openid.") && key.as_str() != "openid.mode")
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        form.push(("openid.mode", "check_authentication"));
+
+        let body = self
+            .http
+            .post(STEAM_OPENID_URL)
+            .form(&form)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| format!("Failed to verify Steam login: {}", e))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to verify Steam login: {}", e))?;
+
+        if !body.lines().any(|line| line.trim() == "is_valid:true") {
+            return Err("Steam rejected the login assertion".to_string());
+        }
+        Ok(steam_id)
+    }
+
+    /// Persona name and avatar of a Steam player, when a Web API key is configured
+    async fn fetch_steam_profile(&self, steam_id: &str) -> (Option<String>, Option<String>) {
+        let Some(api_key) = self.config.oauth.steam.as_ref().and_then(|steam| steam.api_key.as_ref()) else {
+            return (None, None);
+        };
+
+        let result = self
+            .http
+            .get("https://api.steampowered.com/ISteamUser/GetPlayerSummaries/v2/")
+            .query(&[("key", api_key.as_str()), ("steamids", steam_id)])
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+        let summaries = match result {
+            Ok(response) => response.json::<SteamPlayerSummaries>().await.ok(),
+            Err(e) => {
+                tracing::warn!("Failed to fetch Steam profile: {}", e);
+                None
+            }
+        };
+
+        summaries
+            .and_then(|summaries| summaries.response.players.into_iter().next())
+            .map(|player| (player.personaname, player.avatarfull))
+            .unwrap_or((None, None))
+    }
+}
+
+/// Steam OpenID login URL that returns to the callback with the given state
+fn steam_login_url(callback_url: &str, realm: &str, state: &str) -> Result<String, String> {
+    let return_to = url::Url::parse_with_params(callback_url, &[("state", state)])
+        .map_err(|e| format!("Invalid callback URL: {}", e))?;
+    let url = url::Url::parse_with_params(
+        STEAM_OPENID_URL,
+        &[
+            ("openid.ns", OPENID_NS),
+            ("openid.mode", "checkid_setup"),
+            ("openid.return_to", return_to.as_str()),
+            ("openid.realm", realm),
+            ("openid.identity", OPENID_IDENTIFIER_SELECT),
+            ("openid.claimed_id", OPENID_IDENTIFIER_SELECT),
+        ],
+    )
+    .map_err(|e| format!("Invalid Steam login URL: {}", e))?;
+    Ok(url.to_string())
+}
+
+/// SteamID64 of a Steam OpenID claimed ID
+fn steam_id_from_claimed_id(claimed_id: &str) -> Option<&str> {
+    let steam_id = claimed_id.strip_prefix(STEAM_CLAIMED_ID_PREFIX)?;
+    if steam_id.len() == 17 && steam_id.chars().all(|c| c.is_ascii_digit()) {
+        Some(steam_id)
+    } else {
+        None
+    }
+}
+
+/// Username suggestion for an account created through a provider
+pub fn suggested_username(provider: OAuthProvider, identity: &ProviderIdentity) -> String {
+    let source = identity
+        .display_name
+        .clone()
+        .or_else(|| {
+            identity
+                .email
+                .as_ref()
+                .and_then(|email| email.split('@').next().map(str::to_string))
+        })
+        .unwrap_or_default();
+
+    let mut username: String = source
+        .chars()
+        .filter_map(|c| match c {
+            c if c.is_ascii_alphanumeric() || c == '_' => Some(c),
+            ' ' | '-' | '.' => Some('_'),
+            _ => None,
+        })
+        .take(40)
+        .collect();
+
+    if username.len() < 3 {
+        username = format!("{}_player", provider);
+    }
+    username
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> Arc<UserServiceConfig> {
+        Arc::new(UserServiceConfig {
+            oauth: crate::config::OAuthConfig {
+                callback_base_url: "https://api.chaosworld.com/".to_string(),
+                google: Some(OAuthProviderConfig {
+                    client_id: "google-client".to_string(),
+                    client_secret: "google-secret".to_string(),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+    }
+
+    fn identity(display_name: Option<&str>, email: Option<&str>) -> ProviderIdentity {
+        ProviderIdentity {
+            provider_user_id: "42".to_string(),
+            email: email.map(str::to_string),
+            email_verified: true,
+            display_name: display_name.map(str::to_string),
+            avatar_url: None,
+        }
+    }
+
+    #[test]
+    fn test_provider_parsing() {
+        assert_eq!("Google".parse::<OAuthProvider>(), Ok(OAuthProvider::Google));
+        assert_eq!("steam".parse::<OAuthProvider>(), Ok(OAuthProvider::Steam));
+        assert!("github".parse::<OAuthProvider>().is_err());
+    }
+
+    #[test]
+    fn test_authorization_request() {
+        let service = OAuthService::new(test_config());
+        assert!(service.is_enabled(OAuthProvider::Google));
+        assert!(!service.is_enabled(OAuthProvider::Discord));
+        assert_eq!(
+            service.callback_url(OAuthProvider::Google),
+            "https://api.chaosworld.com/auth/oauth/google/callback"
+        );
+
+        let request = service.authorization_request(OAuthProvider::Google).unwrap();
+        assert!(request.url.starts_with(GOOGLE_ENDPOINTS.auth_url));
+        assert!(request.url.contains(&format!("state={}", request.state)));
+        assert!(request.url.contains("code_challenge_method=S256"));
+        assert!(request.pkce_verifier.is_some());
+
+        assert!(service.authorization_request(OAuthProvider::Discord).is_err());
+    }
+
+    #[test]
+    fn test_steam_login_url_and_claimed_id() {
+        let url = steam_login_url(
+            "https://api.chaosworld.com/auth/oauth/steam/callback",
+            "https://api.chaosworld.com",
+            "abc",
+        )
+        .unwrap();
+        assert!(url.starts_with(STEAM_OPENID_URL));
+        assert!(url.contains("openid.mode=checkid_setup"));
+        assert!(url.contains("callback%3Fstate%3Dabc"));
+
+        assert_eq!(
+            steam_id_from_claimed_id("https://steamcommunity.com/openid/id/76561197960287930"),
+            Some("76561197960287930")
+        );
+        assert_eq!(steam_id_from_claimed_id("https://evil.example/openid/id/76561197960287930"), None);
+        assert_eq!(steam_id_from_claimed_id("https://steamcommunity.com/openid/id/123"), None);
+    }
+
+    #[test]
+    fn test_suggested_username() {
+        assert_eq!(
+            suggested_username(OAuthProvider::Google, &identity(Some("Jane Doe!"), None)),
+            "Jane_Doe"
+        );
+        assert_eq!(
+            suggested_username(OAuthProvider::Google, &identity(None, Some("jane.doe@example.com"))),
+            "jane_doe"
+        );
+        assert_eq!(
+            suggested_username(OAuthProvider::Steam, &identity(Some("龍"), None)),
+            "steam_player"
+        );
+    }
+}
@@ -2,7 +2,7 @@ use axum::{
     extract::ConnectInfo,
     http::HeaderMap,
 };
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 
 /// Extract IP address from request headers or connection info
 pub fn extract_ip_address(headers: &HeaderMap, connect_info: Option<ConnectInfo<SocketAddr>>) -> Option<String> {
@@ -45,13 +45,105 @@ pub fn extract_user_agent(headers: &HeaderMap) -> Option<String> {
 pub struct ClientInfo {
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
+    /// Address of the connection itself, which unlike the headers the client cannot choose
+    pub peer_ip: Option<IpAddr>,
 }
 
 impl ClientInfo {
     pub fn from_request(headers: &HeaderMap, connect_info: Option<ConnectInfo<SocketAddr>>) -> Self {
+        let peer_ip = connect_info.as_ref().map(|ConnectInfo(addr)| addr.ip());
         Self {
             ip_address: extract_ip_address(headers, connect_info),
             user_agent: extract_user_agent(headers),
+            peer_ip,
         }
     }
+
+    /// Address failed logins are counted against
+    ///
+    /// Forwarding headers are only believed from the trusted proxies, anyone else could
+    /// rotate them to dodge the lock or send a victim's address to lock them out.
+    pub fn lockout_ip(&self, trusted_proxies: &[IpAddr]) -> Option<String> {
+        let peer_ip = self.peer_ip?;
+        if trusted_proxies.contains(&peer_ip) {
+            self.ip_address.clone()
+        } else {
+            Some(peer_ip.to_string())
+        }
+    }
+}
+
+/// Short device description from a User-Agent, e.g. "Chrome on Windows"
+pub fn describe_device(user_agent: Option<&str>) -> String {
+    let user_agent = match user_agent {
+        Some(user_agent) if !user_agent.trim().is_empty() => user_agent,
+        _ => return "Unknown device".to_string(),
+    };
+
+    // Order matters, most user agents also mention the browsers they derive from
+    const BROWSERS: [(&str, &str); 7] = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+        ("UnityPlayer/", "Game client"),
+        ("curl/", "curl"),
+    ];
+    const SYSTEMS: [(&str, &str); 6] = [
+        ("Windows", "Windows"),
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ];
+
+    let browser = BROWSERS.iter().find(|(marker, _)| user_agent.contains(marker)).map(|(_, name)| *name);
+    let system = SYSTEMS.iter().find(|(marker, _)| user_agent.contains(marker)).map(|(_, name)| *name);
+
+    match (browser, system) {
+        (Some(browser), Some(system)) => format!("{} on {}", browser, system),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(system)) => system.to_string(),
+        (None, None) => "Unknown device".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_device() {
+        let chrome = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        assert_eq!(describe_device(Some(chrome)), "Chrome on Windows");
+
+        let edge = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0";
+        assert_eq!(describe_device(Some(edge)), "Edge on Windows");
+
+        let safari = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+        assert_eq!(describe_device(Some(safari)), "Safari on iOS");
+
+        assert_eq!(describe_device(Some("curl/8.4.0")), "curl");
+        assert_eq!(describe_device(Some("")), "Unknown device");
+        assert_eq!(describe_device(None), "Unknown device");
+    }
+
+    #[test]
+    fn test_lockout_ip_only_trusts_forwarding_from_proxies() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+        let from = |peer: &str| Some(ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000)));
+        let gateway: IpAddr = "10.0.0.2".parse().unwrap();
+
+        let via_gateway = ClientInfo::from_request(&headers, from("10.0.0.2"));
+        assert_eq!(via_gateway.lockout_ip(&[gateway]).as_deref(), Some("203.0.113.7"));
+
+        // A client spoofing the header is still locked by its own address
+        let direct = ClientInfo::from_request(&headers, from("198.51.100.4"));
+        assert_eq!(direct.ip_address.as_deref(), Some("203.0.113.7"));
+        assert_eq!(direct.lockout_ip(&[gateway]).as_deref(), Some("198.51.100.4"));
+        assert_eq!(ClientInfo::from_request(&headers, None).lockout_ip(&[gateway]), None);
+    }
 }
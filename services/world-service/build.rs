@@ -0,0 +1,5 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tonic_build::compile_protos("proto/zone_host.proto")?;
+    println!("cargo:rerun-if-changed=proto");
+    Ok(())
+}
@@ -0,0 +1,311 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use world_core::ai::AiConfig;
+use world_core::movement::MovementConfig;
+use world_core::navigation::PathfindingConfig;
+use world_core::zones::ZoneDefinition;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub zones: ZoneHostConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
+    #[serde(default)]
+    pub handoff: HandoffConfig,
+    #[serde(default)]
+    pub replication: ReplicationConfig,
+    /// Behavior trees of the spawned NPCs
+    #[serde(default)]
+    pub ai: AiConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerConfig {
+    pub port: u16,
+    pub host: String,
+    #[serde(default = "default_grpc_port")]
+    pub grpc_port: u16,
+}
+
+fn default_grpc_port() -> u16 {
+    50051
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub mongodb_uri: String,
+    pub mongodb_database: String,
+}
+
+/// Zones this instance hosts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ZoneHostConfig {
+    /// Zone ids assigned to this instance; `ASSIGNED_ZONES` (comma-separated) takes precedence
+    pub assigned: Vec<String>,
+    /// Time between ticks of each zone
+    pub tick_ms: u64,
+    /// Zones defined inline; assigned zones not defined here are loaded from MongoDB
+    pub definitions: Vec<ZoneDefinition>,
+    /// Checks of the positions reported for players
+    pub movement: MovementConfig,
+    /// Budget and cache of path searches, per zone
+    pub pathfinding: PathfindingConfig,
+}
+
+impl Default for ZoneHostConfig {
+    fn default() -> Self {
+        Self {
+            assigned: Vec::new(),
+            tick_ms: 100,
+            definitions: Vec::new(),
+            movement: MovementConfig::default(),
+            pathfinding: PathfindingConfig::default(),
+        }
+    }
+}
+
+impl ZoneHostConfig {
+    fn apply_env(&mut self) {
+        if let Ok(assigned) = env::var("ASSIGNED_ZONES") {
+            self.assigned = assigned
+                .split(',')
+                .map(str::trim)
+                .filter(|zone| !zone.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.tick_ms == 0 {
+            return Err(ConfigError::InvalidConfig("zones.tick_ms must be at least 1".to_string()));
+        }
+        for definition in &self.definitions {
+            definition.validate().map_err(|e| ConfigError::InvalidConfig(e.to_string()))?;
+        }
+        self.movement.validate().map_err(|reason| ConfigError::InvalidConfig(format!("zones.movement: {}", reason)))
+    }
+}
+
+/// Presence claims of the hosted entities
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresenceConfig {
+    /// Registry shared with the other instances; `PRESENCE_REDIS_URL` takes precedence.
+    /// Presence is only known to this instance without it.
+    pub redis_url: Option<String>,
+    /// Address other services route actions for hosted entities to, the gRPC address
+    /// when absent
+    pub address: Option<String>,
+    /// How long claims outlive the last heartbeat
+    pub ttl_secs: u64,
+    pub heartbeat_secs: u64,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self { redis_url: None, address: None, ttl_secs: 30, heartbeat_secs: 10 }
+    }
+}
+
+impl PresenceConfig {
+    fn apply_env(&mut self) {
+        if let Ok(url) = env::var("PRESENCE_REDIS_URL") {
+            self.redis_url = Some(url);
+        }
+        if let Ok(address) = env::var("PRESENCE_ADDRESS") {
+            self.address = Some(address);
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.heartbeat_secs == 0 || self.heartbeat_secs >= self.ttl_secs {
+            return Err(ConfigError::InvalidConfig(
+                "presence.heartbeat_secs must be at least 1 and below presence.ttl_secs".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Handoffs of entities crossing into zones other instances host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandoffConfig {
+    /// gRPC address of the instance hosting each zone this one hands entities off to
+    pub peers: HashMap<String, String>,
+    /// How long a call to a peer may take before the handoff is rolled back
+    pub timeout_ms: u64,
+    /// How long an accepted offer waits for its commit, and a committed state for the
+    /// game server to take it
+    pub hold_secs: u64,
+}
+
+impl Default for HandoffConfig {
+    fn default() -> Self {
+        Self { peers: HashMap::new(), timeout_ms: 3000, hold_secs: 30 }
+    }
+}
+
+impl HandoffConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.timeout_ms == 0 || self.hold_secs == 0 {
+            return Err(ConfigError::InvalidConfig(
+                "handoff.timeout_ms and handoff.hold_secs must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Hot standby of the hosted zones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplicationConfig {
+    /// Run each zone on the instance holding its lease, the others standing by;
+    /// `ZONE_REPLICATION` (`true`/`false`) takes precedence
+    pub enabled: bool,
+    /// How long a zone lease outlives its last renewal, bounding the time to take over
+    pub lease_ttl_ms: u64,
+    pub renew_ms: u64,
+    /// Ticks between full replicas, the ones in between only carry the changes
+    pub full_every: u64,
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self { enabled: false, lease_ttl_ms: 5000, renew_ms: 1000, full_every: 50 }
+    }
+}
+
+impl ReplicationConfig {
+    fn apply_env(&mut self) {
+        if let Some(enabled) = env::var("ZONE_REPLICATION").ok().and_then(|enabled| enabled.parse().ok()) {
+            self.enabled = enabled;
+        }
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.renew_ms == 0 || self.renew_ms >= self.lease_ttl_ms {
+            return Err(ConfigError::InvalidConfig(
+                "replication.renew_ms must be at least 1 and below replication.lease_ttl_ms".to_string(),
+            ));
+        }
+        if self.full_every == 0 {
+            return Err(ConfigError::InvalidConfig("replication.full_every must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let config_path = env::var("CONFIG_PATH")
+            .unwrap_or_else(|_| "configs/world-service.yaml".to_string());
+
+        if std::path::Path::new(&config_path).exists() {
+            tracing::info!("Loading configuration from file: {}", config_path);
+            let content = std::fs::read_to_string(&config_path)?;
+            let mut config: Config = serde_yaml::from_str(&content)?;
+            config.zones.apply_env();
+            config.zones.validate()?;
+            config.presence.apply_env();
+            config.presence.validate()?;
+            config.handoff.validate()?;
+            config.replication.apply_env();
+            config.replication.validate()?;
+            return Ok(config);
+        }
+
+        tracing::warn!("Config file not found at {}, using environment variables", config_path);
+        let server = ServerConfig {
+            port: env::var("WORLD_SERVICE_PORT")
+                .unwrap_or_else(|_| "8080".to_string())
+                .parse()
+                .unwrap_or(8080),
+            host: env::var("WORLD_SERVICE_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            grpc_port: env::var("WORLD_SERVICE_GRPC_PORT")
+                .ok()
+                .and_then(|port| port.parse().ok())
+                .unwrap_or_else(default_grpc_port),
+        };
+
+        let database = DatabaseConfig {
+            mongodb_uri: env::var("MONGODB_URI")
+                .unwrap_or_else(|_| "mongodb://localhost:27017".to_string()),
+            mongodb_database: env::var("MONGODB_DATABASE")
+                .unwrap_or_else(|_| "chaos_world".to_string()),
+        };
+
+        let mut zones = ZoneHostConfig::default();
+        zones.apply_env();
+        let mut presence = PresenceConfig::default();
+        presence.apply_env();
+        let mut replication = ReplicationConfig::default();
+        replication.apply_env();
+
+        Ok(Config {
+            server,
+            database,
+            zones,
+            presence,
+            handoff: HandoffConfig::default(),
+            replication,
+            ai: AiConfig::default(),
+        })
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("YAML parsing error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zones_are_read_from_yaml() {
+        let yaml = r#"
+assigned: [forest]
+definitions:
+  - id: forest
+    name: Whispering Forest
+    bounds: { min: { x: 0, y: 0, z: 0 }, max: { x: 1000, y: 100, z: 1000 } }
+    spawns:
+      - id: wolves
+        template: forest_wolf
+        center: { x: 100, y: 0, z: 100 }
+        radius: 10
+        max_alive: 5
+        respawn_secs: 30
+    weather:
+      weights: { clear: 3, storm: 1 }
+      min_secs: 60
+      max_secs: 120
+"#;
+        let zones: ZoneHostConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(zones.tick_ms, 100);
+        assert_eq!(zones.definitions[0].spawns[0].max_alive, 5);
+        assert_eq!(zones.definitions[0].cell_size, 32.0);
+        zones.validate().unwrap();
+
+        let mut broken = zones.clone();
+        broken.definitions[0].spawns[0].center.x = -50.0;
+        assert!(broken.validate().is_err());
+        let mut broken = zones.clone();
+        broken.movement.tolerance = 0.5;
+        assert!(broken.validate().is_err());
+    }
+}
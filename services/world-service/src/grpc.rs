@@ -0,0 +1,411 @@
+//! gRPC API of the zone host, for game servers reporting entity positions and asking
+//! what is around them, and for other instances handing entities off to this one.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use shared::events::topics::DespawnReason;
+use shared::party::BuffMode;
+use shared::Timestamp;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Response, Status};
+use world_core::auras::{AuraDefinition, AuraEffect, AuraStacking, AuraTargets};
+use world_core::movement::MoveVerdict;
+use world_core::{NearbyEntity, WorldCoreError};
+
+use crate::handoff::{self, ActorLiveState, HandoffError, HandoffPeer};
+use crate::host::ZoneHost;
+
+pub mod proto {
+    tonic::include_proto!("chaos.world.v1");
+}
+
+use proto::zone_host_client::ZoneHostClient;
+use proto::zone_host_server::{ZoneHost as ZoneHostApi, ZoneHostServer};
+use proto::{
+    ActorState, CarriedAura, EntityPosition, EntityRef, HandoffAck, HandoffRef, HandoffRequest, HandoffResponse,
+    LineOfSightResponse, NearbyRequest, NearbyResponse, PathResponse, PositionUpdate, RemoveEntityRequest,
+    RemoveEntityResponse, SegmentRequest, StatModifier,
+};
+
+/// Default number of results for nearby queries
+pub const DEFAULT_NEARBY_LIMIT: u32 = 50;
+
+/// Largest number of results for nearby queries
+pub const MAX_NEARBY_LIMIT: u32 = 500;
+
+pub struct ZoneHostService {
+    host: Arc<ZoneHost>,
+}
+
+impl ZoneHostService {
+    pub fn new(host: Arc<ZoneHost>) -> Self {
+        Self { host }
+    }
+
+    pub fn into_server(self) -> ZoneHostServer<Self> {
+        ZoneHostServer::new(self)
+    }
+}
+
+#[tonic::async_trait]
+impl ZoneHostApi for ZoneHostService {
+    async fn update_position(&self, request: Request<PositionUpdate>) -> Result<Response<EntityPosition>, Status> {
+        let update = request.into_inner();
+        require_ids(&update.zone_id, &update.entity_id)?;
+        let position = update.position.map(from_proto).ok_or_else(|| Status::invalid_argument("position is required"))?;
+        if !update.max_speed.is_finite() || update.max_speed < 0.0 {
+            return Err(Status::invalid_argument("max_speed must be a non-negative number"));
+        }
+        let verdict = self
+            .host
+            .update_position(&update.zone_id, &update.entity_id, position, update.max_speed, update.teleported)
+            .await
+            .map_err(to_status)?;
+        // Refused moves answer with the position the client must snap back to
+        let (position, violation) = match verdict {
+            MoveVerdict::Accepted => (position, String::new()),
+            MoveVerdict::Corrected { position, violation } => (position, violation.kind.as_str().to_string()),
+        };
+        Ok(Response::new(EntityPosition {
+            zone_id: update.zone_id,
+            entity_id: update.entity_id,
+            position: Some(to_proto(position)),
+            distance: 0.0,
+            corrected: !violation.is_empty(),
+            violation,
+        }))
+    }
+
+    async fn remove_entity(&self, request: Request<RemoveEntityRequest>) -> Result<Response<RemoveEntityResponse>, Status> {
+        let request = request.into_inner();
+        require_ids(&request.zone_id, &request.entity_id)?;
+        let reason = match request.reason.as_str() {
+            "died" => DespawnReason::Died,
+            "logged_out" => DespawnReason::LoggedOut,
+            "" | "removed" => DespawnReason::Removed,
+            other => return Err(Status::invalid_argument(format!("Unknown reason: {}", other))),
+        };
+        let removed = self.host.remove(&request.zone_id, &request.entity_id, reason).await.map_err(to_status)?;
+        Ok(Response::new(RemoveEntityResponse { removed }))
+    }
+
+    async fn get_position(&self, request: Request<EntityRef>) -> Result<Response<EntityPosition>, Status> {
+        let entity = request.into_inner();
+        require_ids(&entity.zone_id, &entity.entity_id)?;
+        let position = self
+            .host
+            .position(&entity.zone_id, &entity.entity_id)
+            .map_err(to_status)?
+            .ok_or_else(|| Status::not_found(format!("Entity '{}' is not in zone '{}'", entity.entity_id, entity.zone_id)))?;
+        Ok(Response::new(EntityPosition {
+            zone_id: entity.zone_id,
+            entity_id: entity.entity_id,
+            position: Some(to_proto(position)),
+            distance: 0.0,
+            ..Default::default()
+        }))
+    }
+
+    async fn query_nearby(&self, request: Request<NearbyRequest>) -> Result<Response<NearbyResponse>, Status> {
+        let query = request.into_inner();
+        let center = query.center.map(from_proto);
+        let Some(center) = center.filter(|_| !query.zone_id.is_empty()) else {
+            return Err(Status::invalid_argument("zone_id and center are required"));
+        };
+        if !query.radius.is_finite() || query.radius <= 0.0 {
+            return Err(Status::invalid_argument("radius must be positive"));
+        }
+        let limit = match query.limit {
+            0 => DEFAULT_NEARBY_LIMIT,
+            limit => limit.min(MAX_NEARBY_LIMIT),
+        };
+        let nearby = self.host.nearby(&query.zone_id, center, query.radius, limit as usize).map_err(to_status)?;
+        let entities = nearby.into_iter().map(|entity| to_entity_position(&query.zone_id, entity)).collect();
+        Ok(Response::new(NearbyResponse { entities }))
+    }
+
+    async fn has_line_of_sight(&self, request: Request<SegmentRequest>) -> Result<Response<LineOfSightResponse>, Status> {
+        let (zone_id, from, to) = segment(request.into_inner())?;
+        let visible = self.host.has_line_of_sight(&zone_id, from, to).map_err(to_status)?;
+        Ok(Response::new(LineOfSightResponse { visible }))
+    }
+
+    async fn find_path(&self, request: Request<SegmentRequest>) -> Result<Response<PathResponse>, Status> {
+        let (zone_id, from, to) = segment(request.into_inner())?;
+        let path = self.host.find_path(&zone_id, from, to).await.map_err(to_status)?;
+        Ok(Response::new(PathResponse {
+            found: path.is_some(),
+            waypoints: path.unwrap_or_default().into_iter().map(to_proto).collect(),
+        }))
+    }
+
+    async fn hand_off(&self, request: Request<HandoffRequest>) -> Result<Response<HandoffResponse>, Status> {
+        let request = request.into_inner();
+        require_ids(&request.zone_id, &request.entity_id)?;
+        let position = request.position.map(from_proto).ok_or_else(|| Status::invalid_argument("position is required"))?;
+        let state = state_from_proto(request.state.unwrap_or_default())?;
+        let handoff_id =
+            self.host.hand_off(&request.entity_id, &request.zone_id, position, state).await.map_err(handoff_status)?;
+        Ok(Response::new(HandoffResponse { handoff_id }))
+    }
+
+    async fn offer_handoff(&self, request: Request<proto::HandoffOffer>) -> Result<Response<HandoffAck>, Status> {
+        let offer = request.into_inner();
+        require_ids(&offer.zone_id, &offer.entity_id)?;
+        if offer.handoff_id.is_empty() {
+            return Err(Status::invalid_argument("handoff_id is required"));
+        }
+        let position = offer.position.map(from_proto).ok_or_else(|| Status::invalid_argument("position is required"))?;
+        let offer = handoff::HandoffOffer {
+            handoff_id: offer.handoff_id,
+            from_instance: offer.from_instance,
+            zone_id: offer.zone_id,
+            entity_id: offer.entity_id,
+            position,
+            state: state_from_proto(offer.state.unwrap_or_default())?,
+        };
+        acknowledge(self.host.accept_handoff(offer))
+    }
+
+    async fn commit_handoff(&self, request: Request<HandoffRef>) -> Result<Response<HandoffAck>, Status> {
+        let handoff_id = request.into_inner().handoff_id;
+        acknowledge(self.host.commit_handoff(&handoff_id).await)
+    }
+
+    async fn abort_handoff(&self, request: Request<HandoffRef>) -> Result<Response<HandoffAck>, Status> {
+        let accepted = self.host.abort_handoff(&request.into_inner().handoff_id);
+        Ok(Response::new(HandoffAck { accepted, reason: String::new() }))
+    }
+
+    async fn take_handoff_state(&self, request: Request<EntityRef>) -> Result<Response<ActorState>, Status> {
+        let entity = request.into_inner();
+        require_ids(&entity.zone_id, &entity.entity_id)?;
+        let state = self
+            .host
+            .take_handoff_state(&entity.entity_id)
+            .ok_or_else(|| Status::not_found(format!("No handoff state for '{}'", entity.entity_id)))?;
+        Ok(Response::new(state_to_proto(&state)))
+    }
+}
+
+/// Instance hosting a zone, reached over this API
+pub struct GrpcHandoffPeer {
+    client: ZoneHostClient<Channel>,
+}
+
+impl GrpcHandoffPeer {
+    /// Connect to the instance at `address` on first use; calls taking longer than
+    /// `timeout` fail
+    pub fn new(address: &str, timeout: Duration) -> Result<Self, tonic::transport::Error> {
+        let address = if address.contains("://") { address.to_string() } else { format!("http://{}", address) };
+        let channel = Endpoint::from_shared(address)?.timeout(timeout).connect_lazy();
+        Ok(Self { client: ZoneHostClient::new(channel) })
+    }
+}
+
+#[async_trait]
+impl HandoffPeer for GrpcHandoffPeer {
+    async fn offer(&self, offer: &handoff::HandoffOffer) -> Result<(), HandoffError> {
+        let offer = proto::HandoffOffer {
+            handoff_id: offer.handoff_id.clone(),
+            from_instance: offer.from_instance.clone(),
+            zone_id: offer.zone_id.clone(),
+            entity_id: offer.entity_id.clone(),
+            position: Some(to_proto(offer.position)),
+            state: Some(state_to_proto(&offer.state)),
+        };
+        acknowledged(self.client.clone().offer_handoff(offer).await)
+    }
+
+    async fn commit(&self, handoff_id: &str) -> Result<(), HandoffError> {
+        let handoff = HandoffRef { handoff_id: handoff_id.to_string() };
+        acknowledged(self.client.clone().commit_handoff(handoff).await)
+    }
+
+    async fn abort(&self, handoff_id: &str) -> Result<(), HandoffError> {
+        let handoff = HandoffRef { handoff_id: handoff_id.to_string() };
+        self.client.clone().abort_handoff(handoff).await.map_err(unreachable)?;
+        Ok(())
+    }
+}
+
+/// Answer refusals with an acknowledgement the source can roll back on
+fn acknowledge(result: Result<(), HandoffError>) -> Result<Response<HandoffAck>, Status> {
+    let reason = match result {
+        Ok(()) => return Ok(Response::new(HandoffAck { accepted: true, reason: String::new() })),
+        Err(HandoffError::Refused(reason)) => reason,
+        Err(HandoffError::Zone(e)) => e.to_string(),
+        Err(e) => return Err(handoff_status(e)),
+    };
+    Ok(Response::new(HandoffAck { accepted: false, reason }))
+}
+
+fn acknowledged(response: Result<Response<HandoffAck>, Status>) -> Result<(), HandoffError> {
+    let ack = response.map_err(unreachable)?.into_inner();
+    if !ack.accepted {
+        return Err(HandoffError::Refused(ack.reason));
+    }
+    Ok(())
+}
+
+fn unreachable(status: Status) -> HandoffError {
+    HandoffError::Unreachable(format!("{:?}: {}", status.code(), status.message()))
+}
+
+fn segment(request: SegmentRequest) -> Result<(String, world_core::Vec3, world_core::Vec3), Status> {
+    match (request.from, request.to) {
+        (Some(from), Some(to)) if !request.zone_id.is_empty() => Ok((request.zone_id, from_proto(from), from_proto(to))),
+        _ => Err(Status::invalid_argument("zone_id, from and to are required")),
+    }
+}
+
+fn require_ids(zone_id: &str, entity_id: &str) -> Result<(), Status> {
+    if zone_id.is_empty() || entity_id.is_empty() {
+        return Err(Status::invalid_argument("zone_id and entity_id are required"));
+    }
+    Ok(())
+}
+
+fn to_status(error: WorldCoreError) -> Status {
+    match error {
+        WorldCoreError::ZoneNotFound(_) => Status::not_found(error.to_string()),
+        WorldCoreError::OutOfBounds { .. }
+        | WorldCoreError::Blocked { .. }
+        | WorldCoreError::InvalidZone { .. }
+        | WorldCoreError::InvalidAura { .. }
+        | WorldCoreError::InvalidMount { .. } => Status::invalid_argument(error.to_string()),
+        WorldCoreError::InvalidTree { .. } => Status::internal(error.to_string()),
+        WorldCoreError::Storage(_) | WorldCoreError::Standby(_) => Status::unavailable(error.to_string()),
+    }
+}
+
+fn handoff_status(error: HandoffError) -> Status {
+    match error {
+        HandoffError::NoPeer(_) | HandoffError::Refused(_) => Status::failed_precondition(error.to_string()),
+        HandoffError::InProgress(_) => Status::aborted(error.to_string()),
+        HandoffError::Unreachable(_) => Status::unavailable(error.to_string()),
+        HandoffError::Zone(e) => to_status(e),
+    }
+}
+
+fn state_from_proto(state: ActorState) -> Result<ActorLiveState, Status> {
+    let effects = state
+        .effects
+        .into_iter()
+        .map(|effect| {
+            let modifier = effect.modifier.unwrap_or_default();
+            Ok(handoff::TimedEffect {
+                id: effect.id,
+                source_id: effect.source_id,
+                stat: modifier.stat,
+                value: modifier.value,
+                mode: mode_from_proto(modifier.multiply),
+                expires_at: timestamp(effect.expires_at_ms)?,
+            })
+        })
+        .collect::<Result<_, Status>>()?;
+    let cooldowns = state
+        .cooldowns
+        .into_iter()
+        .map(|cooldown| Ok(handoff::Cooldown { ability: cooldown.ability, ready_at: timestamp(cooldown.ready_at_ms)? }))
+        .collect::<Result<_, Status>>()?;
+    let auras = state
+        .auras
+        .into_iter()
+        .map(|aura| AuraDefinition {
+            id: aura.id,
+            radius: aura.radius,
+            effects: aura
+                .effects
+                .into_iter()
+                .map(|effect| AuraEffect {
+                    stat: effect.stat,
+                    value: effect.value,
+                    mode: mode_from_proto(effect.multiply),
+                })
+                .collect(),
+            targets: if aura.party { AuraTargets::Party } else { AuraTargets::Everyone },
+            stacking: match aura.max_stacks {
+                0 => AuraStacking::Strongest,
+                max_stacks => AuraStacking::Stack { max_stacks },
+            },
+        })
+        .collect();
+    Ok(ActorLiveState { auras, effects, cooldowns, actor_data: state.actor_data })
+}
+
+fn state_to_proto(state: &ActorLiveState) -> ActorState {
+    let modifier = |stat: &str, value: f64, mode: BuffMode| StatModifier {
+        stat: stat.to_string(),
+        value,
+        multiply: mode == BuffMode::Multiply,
+    };
+    ActorState {
+        auras: state
+            .auras
+            .iter()
+            .map(|aura| CarriedAura {
+                id: aura.id.clone(),
+                radius: aura.radius,
+                effects: aura.effects.iter().map(|effect| modifier(&effect.stat, effect.value, effect.mode)).collect(),
+                party: aura.targets == AuraTargets::Party,
+                max_stacks: match aura.stacking {
+                    AuraStacking::Strongest => 0,
+                    AuraStacking::Stack { max_stacks } => max_stacks,
+                },
+            })
+            .collect(),
+        effects: state
+            .effects
+            .iter()
+            .map(|effect| proto::TimedEffect {
+                id: effect.id.clone(),
+                source_id: effect.source_id.clone(),
+                modifier: Some(modifier(&effect.stat, effect.value, effect.mode)),
+                expires_at_ms: effect.expires_at.timestamp_millis(),
+            })
+            .collect(),
+        cooldowns: state
+            .cooldowns
+            .iter()
+            .map(|cooldown| proto::Cooldown {
+                ability: cooldown.ability.clone(),
+                ready_at_ms: cooldown.ready_at.timestamp_millis(),
+            })
+            .collect(),
+        actor_data: state.actor_data.clone(),
+    }
+}
+
+fn mode_from_proto(multiply: bool) -> BuffMode {
+    if multiply {
+        BuffMode::Multiply
+    } else {
+        BuffMode::Add
+    }
+}
+
+fn timestamp(millis: i64) -> Result<Timestamp, Status> {
+    DateTime::from_timestamp_millis(millis).ok_or_else(|| Status::invalid_argument(format!("Invalid time: {}", millis)))
+}
+
+fn from_proto(vec: proto::Vec3) -> world_core::Vec3 {
+    world_core::Vec3::new(vec.x, vec.y, vec.z)
+}
+
+fn to_proto(vec: world_core::Vec3) -> proto::Vec3 {
+    proto::Vec3 { x: vec.x, y: vec.y, z: vec.z }
+}
+
+fn to_entity_position(zone_id: &str, entity: NearbyEntity) -> EntityPosition {
+    EntityPosition {
+        zone_id: zone_id.to_string(),
+        entity_id: entity.entity_id,
+        position: Some(to_proto(entity.position)),
+        distance: entity.distance,
+        ..Default::default()
+    }
+}
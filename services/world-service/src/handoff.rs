@@ -0,0 +1,233 @@
+//! Handoffs of entities crossing into zones other instances host.
+//!
+//! The instance an entity leaves offers it to the one hosting the destination zone, with
+//! its live state: the auras it carries, the timed effects and cooldowns its game server
+//! tracks, and its hierarchical actor snapshot in the binary codec. The destination checks
+//! the offer and holds it; the source then commits, and lets the entity go only once the
+//! destination acknowledges. A refused offer or a failed commit rolls the handoff back:
+//! the offer is aborted and the entity stays where it was, with everything it carried.
+//!
+//! A commit whose acknowledgement is lost leaves the entity on both instances for a
+//! moment; the destination's presence claim hands it over, and the source lets go when it
+//! hears about it, as for any other handover.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::Duration;
+use shared::party::BuffMode;
+use shared::Timestamp;
+use world_core::auras::AuraDefinition;
+use world_core::{Vec3, WorldCoreError};
+
+use crate::config::HandoffConfig;
+
+/// Buff or debuff on an entity until `expires_at`, tracked by its game server
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedEffect {
+    pub id: String,
+    /// Entity that applied it
+    pub source_id: String,
+    pub stat: String,
+    pub value: f64,
+    pub mode: BuffMode,
+    pub expires_at: Timestamp,
+}
+
+/// Ability an entity cannot use again before `ready_at`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cooldown {
+    pub ability: String,
+    pub ready_at: Timestamp,
+}
+
+/// What an entity takes along to another instance
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActorLiveState {
+    /// Auras it carries, attached again on arrival
+    pub auras: Vec<AuraDefinition>,
+    pub effects: Vec<TimedEffect>,
+    pub cooldowns: Vec<Cooldown>,
+    /// Hierarchical actor snapshot in the binary codec; empty when there is none
+    pub actor_data: Vec<u8>,
+}
+
+impl ActorLiveState {
+    /// Drop the effects and cooldowns over by `now`
+    pub fn expire(&mut self, now: Timestamp) {
+        self.effects.retain(|effect| effect.expires_at > now);
+        self.cooldowns.retain(|cooldown| cooldown.ready_at > now);
+    }
+}
+
+/// An entity offered to the instance hosting `zone_id`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandoffOffer {
+    pub handoff_id: String,
+    pub from_instance: String,
+    pub zone_id: String,
+    pub entity_id: String,
+    /// Where it enters the zone
+    pub position: Vec3,
+    pub state: ActorLiveState,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HandoffError {
+    #[error("No instance is known to host zone {0}")]
+    NoPeer(String),
+    #[error("{0} is already being handed off")]
+    InProgress(String),
+    #[error("Handoff refused: {0}")]
+    Refused(String),
+    #[error("Handoff peer unreachable: {0}")]
+    Unreachable(String),
+    #[error(transparent)]
+    Zone(#[from] WorldCoreError),
+}
+
+/// The instance hosting a zone, as seen by the instances handing entities off to it
+#[async_trait]
+pub trait HandoffPeer: Send + Sync {
+    /// Offer an entity, `Refused` when the peer will not take it
+    async fn offer(&self, offer: &HandoffOffer) -> Result<(), HandoffError>;
+    /// Have the peer place an entity whose offer it accepted
+    async fn commit(&self, handoff_id: &str) -> Result<(), HandoffError>;
+    /// Have the peer drop an accepted offer
+    async fn abort(&self, handoff_id: &str) -> Result<(), HandoffError>;
+}
+
+/// State an entity handed over to this instance arrived with
+struct Arrival {
+    handoff_id: String,
+    state: ActorLiveState,
+    until: Timestamp,
+}
+
+/// Handoffs in flight, as the source and as the destination
+pub struct Handoffs {
+    peers: HashMap<String, Arc<dyn HandoffPeer>>,
+    hold: Duration,
+    /// Entities being handed off by this instance
+    leaving: Mutex<HashSet<String>>,
+    /// Accepted offers by handoff id, until they lapse
+    offered: Mutex<HashMap<String, (HandoffOffer, Timestamp)>>,
+    /// Committed states by entity, until the game server takes them or they lapse
+    arrived: Mutex<HashMap<String, Arrival>>,
+}
+
+impl Handoffs {
+    pub fn new(config: &HandoffConfig) -> Self {
+        Self {
+            peers: HashMap::new(),
+            hold: Duration::seconds(config.hold_secs as i64),
+            leaving: Mutex::new(HashSet::new()),
+            offered: Mutex::new(HashMap::new()),
+            arrived: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Hand entities crossing into `zone_id` off to `peer`
+    pub fn with_peer(mut self, zone_id: impl Into<String>, peer: Arc<dyn HandoffPeer>) -> Self {
+        self.peers.insert(zone_id.into(), peer);
+        self
+    }
+
+    pub fn peer(&self, zone_id: &str) -> Result<Arc<dyn HandoffPeer>, HandoffError> {
+        self.peers.get(zone_id).cloned().ok_or_else(|| HandoffError::NoPeer(zone_id.to_string()))
+    }
+
+    /// Note that an entity is leaving, unless it already is
+    pub fn start_leaving(&self, entity_id: &str) -> Result<(), HandoffError> {
+        if !lock(&self.leaving).insert(entity_id.to_string()) {
+            return Err(HandoffError::InProgress(entity_id.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn stop_leaving(&self, entity_id: &str) {
+        lock(&self.leaving).remove(entity_id);
+    }
+
+    /// Hold an accepted offer until committed, refusing a second one for its entity
+    pub fn hold(&self, offer: HandoffOffer, now: Timestamp) -> Result<(), HandoffError> {
+        let mut offered = lock(&self.offered);
+        offered.retain(|_, (_, until)| *until > now);
+        if offered.values().any(|(held, _)| held.entity_id == offer.entity_id) {
+            return Err(HandoffError::InProgress(offer.entity_id));
+        }
+        offered.insert(offer.handoff_id.clone(), (offer, now + self.hold));
+        Ok(())
+    }
+
+    /// Accepted offer to commit, unless it lapsed
+    pub fn take_offer(&self, handoff_id: &str, now: Timestamp) -> Option<HandoffOffer> {
+        lock(&self.offered).remove(handoff_id).filter(|(_, until)| *until > now).map(|(offer, _)| offer)
+    }
+
+    /// Drop an accepted offer, returning whether there was one
+    pub fn drop_offer(&self, handoff_id: &str) -> bool {
+        lock(&self.offered).remove(handoff_id).is_some()
+    }
+
+    pub fn is_committed(&self, handoff_id: &str) -> bool {
+        lock(&self.arrived).values().any(|arrival| arrival.handoff_id == handoff_id)
+    }
+
+    /// Keep the state a committed offer brought for its game server
+    pub fn arrive(&self, offer: HandoffOffer, now: Timestamp) {
+        let arrival = Arrival { handoff_id: offer.handoff_id, state: offer.state, until: now + self.hold };
+        let mut arrived = lock(&self.arrived);
+        arrived.retain(|_, arrival| arrival.until > now);
+        arrived.insert(offer.entity_id, arrival);
+    }
+
+    /// State an entity arrived with, once, without what ran out by `now`
+    pub fn take_arrived(&self, entity_id: &str, now: Timestamp) -> Option<ActorLiveState> {
+        let arrival = lock(&self.arrived).remove(entity_id).filter(|arrival| arrival.until > now)?;
+        let mut state = arrival.state;
+        state.expire(now);
+        Some(state)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn offer(handoff_id: &str) -> HandoffOffer {
+        HandoffOffer {
+            handoff_id: handoff_id.to_string(),
+            from_instance: "first".to_string(),
+            zone_id: "peaks".to_string(),
+            entity_id: "hero".to_string(),
+            position: Vec3::new(5.0, 0.0, 5.0),
+            state: ActorLiveState::default(),
+        }
+    }
+
+    #[test]
+    fn held_offers_and_arrivals_lapse() {
+        let handoffs = Handoffs::new(&HandoffConfig { hold_secs: 10, ..Default::default() });
+        let now = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+
+        // One offer per entity until it lapses
+        handoffs.hold(offer("a"), now).unwrap();
+        assert!(matches!(handoffs.hold(offer("b"), now), Err(HandoffError::InProgress(_))));
+        assert!(handoffs.take_offer("a", now + Duration::seconds(10)).is_none());
+        handoffs.hold(offer("b"), now + Duration::seconds(10)).unwrap();
+        assert!(handoffs.drop_offer("b"));
+        assert!(!handoffs.drop_offer("b"));
+
+        handoffs.arrive(offer("c"), now);
+        assert!(handoffs.is_committed("c"));
+        assert!(handoffs.take_arrived("hero", now + Duration::seconds(10)).is_none());
+        assert!(!handoffs.is_committed("c"));
+    }
+}
@@ -0,0 +1,1271 @@
+//! Hosts the zones assigned to this instance.
+//!
+//! Each zone ticks on its own task, so a busy zone does not hold up the others. What
+//! happens in a zone goes out on the bus: spawned NPCs as `ACTOR_SPAWNED`, weather,
+//! hazards and auras on the zone's topic. Game servers report entity positions through gRPC; they
+//! are indexed here and published as `ACTOR_MOVED` for anti-cheat-service.
+//!
+//! Hosted entities are claimed in the presence registry so other services can route
+//! actions to this instance. An entity reported here while another instance holds it is
+//! handed over, and the handover announced for the previous owner to let it go.
+//!
+//! Positions reported for players are checked by the movement validator first: refused
+//! moves leave the entity in place, are reported as `MOVEMENT_REJECTED` and answered
+//! with the position the client must snap back to.
+//!
+//! Entities crossing into a zone another instance hosts are handed off to it with their
+//! live state, see [`crate::handoff`].
+//!
+//! With replication, a zone runs on the instance holding its lease and the other
+//! instances assigned it keep a standby copy, see [`crate::replication`]. Standby zones
+//! do not tick and refuse the updates of game servers, which find the primary through
+//! the presence registry; the standby taking a zone over claims its entities.
+//!
+//! Spawned NPCs whose template has a behavior tree think after every tick of their zone.
+//! Their `move_toward` actions are carried out here; every other action, attacks
+//! included, is published as `NPC_ACTION` for the game servers running combat.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use actor_core_hierarchical::HierarchicalActor;
+use async_trait::async_trait;
+use serde::Serialize;
+use shared::events::topics::{
+    self, ActorDespawned, ActorHandedOver, ActorMoved, ActorSpawned, DespawnReason, MovementRejected, NpcActionRequested,
+    ZoneAuraChanged, ZoneFailoverRequested, ZoneHazardPulsed, ZoneReplicated, ZoneWeatherChanged, ACTOR_DESPAWNED,
+    ACTOR_HANDED_OVER, ACTOR_MOVED, ACTOR_SPAWNED, MOVEMENT_REJECTED, NPC_ACTION,
+};
+use shared::events::{EventBus, EventBusExt, Message, Topic};
+use shared::presence::{ClaimOutcome, PresenceOwner, PresenceRegistry};
+use shared::{SharedClock, Timestamp};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use world_core::ai::{AiConfig, AiDirector, Blackboard, NodeStatus, NpcActions, NpcContext, Perception};
+use world_core::movement::{MoveVerdict, MovementValidator};
+use world_core::replication::ZoneReplica;
+use world_core::{NearbyEntity, Vec3, WeatherKind, WorldCoreError, WorldCoreResult, ZoneEvent, ZoneRuntime, ZoneSource};
+
+use crate::config::{HandoffConfig, ZoneHostConfig};
+use crate::handoff::{ActorLiveState, HandoffError, HandoffOffer, HandoffPeer, Handoffs};
+use crate::replication::{self, Replication, RoleChange, ZoneLeases, ZoneRole};
+use crate::SERVICE;
+
+/// A hosted zone at a glance
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneSummary {
+    pub id: String,
+    pub name: String,
+    pub weather: String,
+    pub weather_until: Timestamp,
+    pub entities: usize,
+    /// Runs here rather than standing by
+    pub primary: bool,
+}
+
+/// Presence claims of the entities an instance hosts
+pub struct PresenceClaims {
+    pub registry: Arc<dyn PresenceRegistry>,
+    pub owner: PresenceOwner,
+    /// How long claims last without a heartbeat
+    pub ttl: Duration,
+    pub heartbeat: Duration,
+}
+
+pub struct ZoneHost {
+    zones: HashMap<String, Mutex<ZoneRuntime>>,
+    /// Hosted zone of each entity
+    entities: Mutex<HashMap<String, String>>,
+    bus: Arc<dyn EventBus>,
+    clock: SharedClock,
+    tick: Duration,
+    presence: Option<PresenceClaims>,
+    /// NPC brains of each zone with behavior trees
+    ai: HashMap<String, tokio::sync::Mutex<AiDirector>>,
+    movement: Mutex<MovementValidator>,
+    handoffs: Handoffs,
+    replication: Option<Replication>,
+}
+
+impl ZoneHost {
+    /// Host the assigned zones, or every inline definition when none are assigned.
+    /// Assigned zones without an inline definition are loaded from `source`.
+    pub async fn load(
+        config: &ZoneHostConfig,
+        source: Option<&dyn ZoneSource>,
+        bus: Arc<dyn EventBus>,
+        clock: SharedClock,
+    ) -> WorldCoreResult<Self> {
+        let mut definitions: Vec<_> = config
+            .definitions
+            .iter()
+            .filter(|definition| config.assigned.is_empty() || config.assigned.contains(&definition.id))
+            .cloned()
+            .collect();
+        let missing: Vec<String> = config
+            .assigned
+            .iter()
+            .filter(|zone_id| !definitions.iter().any(|definition| &definition.id == *zone_id))
+            .cloned()
+            .collect();
+        if let Some(source) = source.filter(|_| !missing.is_empty()) {
+            definitions.extend(source.load(&missing).await?);
+        }
+        for zone_id in &missing {
+            if !definitions.iter().any(|definition| &definition.id == zone_id) {
+                return Err(WorldCoreError::ZoneNotFound(zone_id.clone()));
+            }
+        }
+
+        let now = clock.now();
+        let mut zones = HashMap::new();
+        for definition in definitions {
+            info!("🗺️  Hosting zone {} ({})", definition.id, definition.name);
+            let zone = ZoneRuntime::new(definition, now)?.with_pathfinding(config.pathfinding.clone());
+            zones.insert(zone.id().to_string(), Mutex::new(zone));
+        }
+        Ok(Self {
+            zones,
+            entities: Mutex::new(HashMap::new()),
+            bus,
+            clock,
+            tick: Duration::from_millis(config.tick_ms),
+            presence: None,
+            ai: HashMap::new(),
+            movement: Mutex::new(MovementValidator::new(config.movement.clone())),
+            handoffs: Handoffs::new(&HandoffConfig::default()),
+            replication: None,
+        })
+    }
+
+    /// Run the behavior trees of spawned NPCs
+    pub fn with_ai(mut self, config: &AiConfig) -> WorldCoreResult<Self> {
+        for zone_id in self.zones.keys() {
+            self.ai.insert(zone_id.clone(), tokio::sync::Mutex::new(AiDirector::new(zone_id.clone(), config)?));
+        }
+        Ok(self)
+    }
+
+    /// Claim hosted entities in a presence registry
+    pub fn with_presence(mut self, presence: PresenceClaims) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+
+    /// Hand entities off to the instances hosting other zones
+    pub fn with_handoffs(mut self, handoffs: Handoffs) -> Self {
+        self.handoffs = handoffs;
+        self
+    }
+
+    /// Run each zone only while holding its lease, standing by otherwise
+    pub fn with_replication(mut self, leases: ZoneLeases) -> Self {
+        self.zones = self
+            .zones
+            .into_iter()
+            .map(|(zone_id, zone)| {
+                let zone = zone.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+                (zone_id, Mutex::new(zone.with_replication()))
+            })
+            .collect();
+        self.replication = Some(Replication::new(leases, self.zones.keys().cloned()));
+        self
+    }
+
+    /// Hosted zones by id
+    pub fn summaries(&self) -> Vec<ZoneSummary> {
+        let mut summaries: Vec<ZoneSummary> = self
+            .zones
+            .values()
+            .map(|zone| {
+                let zone = lock(zone);
+                let weather = zone.weather();
+                ZoneSummary {
+                    id: zone.id().to_string(),
+                    name: zone.definition().name.clone(),
+                    weather: weather.current.as_str().to_string(),
+                    weather_until: weather.until,
+                    entities: zone.entity_count(),
+                    primary: self.is_primary(zone.id()),
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.id.cmp(&b.id));
+        summaries
+    }
+
+    /// Start the tick loop of every zone
+    pub fn spawn_ticks(self: &Arc<Self>) -> Vec<JoinHandle<()>> {
+        self.zones
+            .keys()
+            .map(|zone_id| {
+                let (host, zone_id) = (self.clone(), zone_id.clone());
+                tokio::spawn(async move {
+                    let mut interval = tokio::time::interval(host.tick);
+                    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                    loop {
+                        interval.tick().await;
+                        host.tick(&zone_id).await;
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Advance a zone, publish what happened and let its NPCs think
+    pub async fn tick(&self, zone_id: &str) {
+        let Some(zone) = self.zones.get(zone_id).filter(|_| self.is_primary(zone_id)) else {
+            return;
+        };
+        let events = lock(zone).tick(self.clock.now());
+        for event in events {
+            match event {
+                ZoneEvent::Spawned { entity_id, spawn_id, template, .. } => {
+                    lock(&self.entities).insert(entity_id.clone(), zone_id.to_string());
+                    if let Some(director) = self.ai.get(zone_id) {
+                        director.lock().await.spawned(&entity_id, &template);
+                    }
+                    self.claim(zone_id, &entity_id).await;
+                    let spawned = ActorSpawned { actor_id: entity_id, zone: Some(zone_id.to_string()), spawn_point: Some(spawn_id) };
+                    self.publish(&ACTOR_SPAWNED, &spawned).await;
+                }
+                ZoneEvent::HazardPulsed { hazard_id, resource, amount, entity_ids } => {
+                    let pulsed = ZoneHazardPulsed { zone: zone_id.to_string(), hazard_id, resource, amount, actor_ids: entity_ids };
+                    self.publish(&Topic::dynamic(topics::zone(zone_id)), &pulsed).await;
+                }
+                ZoneEvent::WeatherChanged { previous, current, until } => {
+                    info!("🌦️  Weather in {} changed to {}", zone_id, current.as_str());
+                    let changed = ZoneWeatherChanged {
+                        zone: zone_id.to_string(),
+                        previous: previous.as_str().to_string(),
+                        current: current.as_str().to_string(),
+                        until,
+                    };
+                    self.publish(&Topic::dynamic(topics::zone(zone_id)), &changed).await;
+                }
+                ZoneEvent::AuraChanged { source_id, aura_id, applied, removed } => {
+                    let changed = ZoneAuraChanged { zone: zone_id.to_string(), source_id, aura_id, applied, removed };
+                    self.publish(&Topic::dynamic(topics::zone(zone_id)), &changed).await;
+                }
+            }
+        }
+
+        if let Some(director) = self.ai.get(zone_id) {
+            let actions = HostActions { host: self, zone_id };
+            director.lock().await.tick(&LockedZone(zone), &actions, self.clock.now()).await;
+        }
+        self.replicate(zone_id, false).await;
+    }
+
+    /// Move an entity to the position its client reported, unless the movement checks
+    /// refuse it; `teleported` marks server-initiated moves, which skip the checks
+    pub async fn update_position(
+        &self,
+        zone_id: &str,
+        entity_id: &str,
+        position: Vec3,
+        max_speed: f64,
+        teleported: bool,
+    ) -> WorldCoreResult<MoveVerdict> {
+        let zone = self.primary(zone_id)?;
+        let verdict = {
+            let zone = lock(zone);
+            let now = self.clock.now();
+            lock(&self.movement).validate(zone.definition(), entity_id, position, max_speed, teleported, now)?
+        };
+        if let MoveVerdict::Corrected { violation, .. } = &verdict {
+            warn!("🚫 Refused {} move of {} to {}", violation.kind.as_str(), entity_id, position);
+            let rejected = MovementRejected {
+                actor_id: entity_id.to_string(),
+                zone: zone_id.to_string(),
+                kind: violation.kind,
+                from: [violation.from.x, violation.from.y, violation.from.z],
+                attempted: [position.x, position.y, position.z],
+                observed: violation.observed,
+                limit: violation.limit,
+            };
+            self.publish(&MOVEMENT_REJECTED, &rejected).await;
+            return Ok(verdict);
+        }
+        self.place(zone_id, entity_id, position, max_speed, teleported).await?;
+        Ok(verdict)
+    }
+
+    /// Place an entity in a zone, taking it out of any other hosted zone first
+    async fn place(
+        &self,
+        zone_id: &str,
+        entity_id: &str,
+        position: Vec3,
+        max_speed: f64,
+        teleported: bool,
+    ) -> WorldCoreResult<()> {
+        let zone = self.primary(zone_id)?;
+        let previous_zone = lock(&self.entities).get(entity_id).cloned().filter(|previous| previous != zone_id);
+        let entered = lock(zone).update_position(entity_id, position)?.is_none();
+        lock(&self.entities).insert(entity_id.to_string(), zone_id.to_string());
+
+        if let Some(previous_zone) = previous_zone {
+            if let Some(previous) = self.zones.get(&previous_zone) {
+                lock(previous).remove(entity_id, self.clock.now());
+            }
+            let despawned = ActorDespawned {
+                actor_id: entity_id.to_string(),
+                zone: Some(previous_zone),
+                reason: DespawnReason::Removed,
+            };
+            self.publish(&ACTOR_DESPAWNED, &despawned).await;
+        }
+        if entered {
+            self.claim(zone_id, entity_id).await;
+            let spawned = ActorSpawned { actor_id: entity_id.to_string(), zone: Some(zone_id.to_string()), spawn_point: None };
+            self.publish(&ACTOR_SPAWNED, &spawned).await;
+        }
+        let moved = ActorMoved {
+            actor_id: entity_id.to_string(),
+            zone: Some(zone_id.to_string()),
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            max_speed,
+            teleported,
+        };
+        self.publish(&ACTOR_MOVED, &moved).await;
+        Ok(())
+    }
+
+    /// Take an entity out of a zone, returning whether it was there
+    pub async fn remove(&self, zone_id: &str, entity_id: &str, reason: DespawnReason) -> WorldCoreResult<bool> {
+        let zone = self.primary(zone_id)?;
+        if lock(zone).remove(entity_id, self.clock.now()).is_none() {
+            return Ok(false);
+        }
+        lock(&self.entities).remove(entity_id);
+        lock(&self.movement).forget(entity_id);
+        self.forget_brain(zone_id, entity_id).await;
+        if let Some(presence) = &self.presence {
+            if let Err(e) = presence.registry.release(&presence.owner.instance, entity_id).await {
+                warn!("⚠️  Failed to release {}: {}", entity_id, e);
+            }
+        }
+        let despawned = ActorDespawned { actor_id: entity_id.to_string(), zone: Some(zone_id.to_string()), reason };
+        self.publish(&ACTOR_DESPAWNED, &despawned).await;
+        Ok(true)
+    }
+
+    /// Let an entity another instance took over go, returning whether it was hosted here
+    pub async fn forget(&self, entity_id: &str) -> bool {
+        let Some(zone_id) = lock(&self.entities).remove(entity_id) else {
+            return false;
+        };
+        if let Some(zone) = self.zones.get(&zone_id) {
+            lock(zone).remove(entity_id, self.clock.now());
+        }
+        lock(&self.movement).forget(entity_id);
+        self.forget_brain(&zone_id, entity_id).await;
+        let despawned =
+            ActorDespawned { actor_id: entity_id.to_string(), zone: Some(zone_id), reason: DespawnReason::Removed };
+        self.publish(&ACTOR_DESPAWNED, &despawned).await;
+        true
+    }
+
+    /// Claim an entity that entered a zone, taking it over from its previous owner
+    async fn claim(&self, zone_id: &str, entity_id: &str) {
+        let Some(presence) = &self.presence else {
+            return;
+        };
+        let (registry, owner) = (&presence.registry, &presence.owner);
+        let claimed = match registry.claim(owner, entity_id, zone_id, presence.ttl).await {
+            Ok(ClaimOutcome::HeldBy(previous)) => registry
+                .handover(owner, entity_id, zone_id, &previous.instance, presence.ttl)
+                .await
+                .map(|outcome| (outcome, Some(previous.instance))),
+            Ok(outcome) => Ok((outcome, None)),
+            Err(e) => Err(e),
+        };
+        match claimed {
+            Ok((ClaimOutcome::Claimed(claimed), Some(from_instance))) => {
+                info!("🤝 Took {} over from {}", entity_id, from_instance);
+                let handed = ActorHandedOver {
+                    actor_id: entity_id.to_string(),
+                    from_instance,
+                    to_instance: owner.instance.clone(),
+                    zone: zone_id.to_string(),
+                    epoch: claimed.epoch,
+                };
+                self.publish(&ACTOR_HANDED_OVER, &handed).await;
+            }
+            Ok((ClaimOutcome::Claimed(_), None)) => {}
+            Ok((ClaimOutcome::HeldBy(holder), _)) => {
+                warn!("⚠️  {} is held by {}, which changed during the handover", entity_id, holder.instance);
+            }
+            Err(e) => warn!("⚠️  Failed to claim {}: {}", entity_id, e),
+        }
+    }
+
+    /// Renew the presence claims of every hosted entity until aborted
+    pub fn spawn_heartbeats(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let interval = self.presence.as_ref()?.heartbeat;
+        let host = self.clone();
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                host.heartbeat().await;
+            }
+        }))
+    }
+
+    /// Renew the claims; entities whose claim lapsed are claimed again
+    pub async fn heartbeat(&self) {
+        let Some(presence) = &self.presence else {
+            return;
+        };
+        let entities: Vec<(String, String)> =
+            lock(&self.entities).iter().map(|(entity_id, zone_id)| (entity_id.clone(), zone_id.clone())).collect();
+        let entity_ids: Vec<String> = entities.iter().map(|(entity_id, _)| entity_id.clone()).collect();
+        let lost = match presence.registry.heartbeat(&presence.owner, &entity_ids, presence.ttl).await {
+            Ok(lost) => lost,
+            Err(e) => {
+                warn!("⚠️  Failed to renew presence claims: {}", e);
+                return;
+            }
+        };
+        for entity_id in lost {
+            let Some((_, zone_id)) = entities.iter().find(|(id, _)| *id == entity_id) else {
+                continue;
+            };
+            match presence.registry.claim(&presence.owner, &entity_id, zone_id, presence.ttl).await {
+                Ok(ClaimOutcome::Claimed(_)) => {}
+                // Another instance reports it now; its handover arrives or already did
+                Ok(ClaimOutcome::HeldBy(holder)) => {
+                    warn!("⚠️  {} moved to {}", entity_id, holder.instance);
+                    self.forget(&entity_id).await;
+                }
+                Err(e) => warn!("⚠️  Failed to claim {} again: {}", entity_id, e),
+            }
+        }
+    }
+
+    /// Give up every claim so other instances can take the entities right away
+    pub async fn release_all(&self) {
+        let Some(presence) = &self.presence else {
+            return;
+        };
+        let entity_ids: Vec<String> = lock(&self.entities).keys().cloned().collect();
+        for entity_id in entity_ids {
+            if let Err(e) = presence.registry.release(&presence.owner.instance, &entity_id).await {
+                warn!("⚠️  Failed to release {}: {}", entity_id, e);
+            }
+        }
+    }
+
+    /// Keep the zone leases until aborted: renew those of the zones running here and
+    /// acquire those of the zones standing by once their primary lets them lapse
+    pub fn spawn_leases(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        let interval = self.replication.as_ref()?.leases.renew;
+        let host = self.clone();
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                interval.tick().await;
+                host.renew_leases().await;
+            }
+        }))
+    }
+
+    /// Acquire or renew the lease of every zone, taking zones over and stepping down
+    /// as the registry says
+    pub async fn renew_leases(&self) {
+        let Some(replication) = &self.replication else {
+            return;
+        };
+        let leases = &replication.leases;
+        for zone_id in self.zones.keys() {
+            let now = self.clock.now();
+            if !replication.may_acquire(zone_id, now) {
+                continue;
+            }
+            let name = replication::lease_name(zone_id);
+            let change = match leases.registry.acquire(&leases.owner, &name, leases.ttl, None).await {
+                Ok(outcome) => replication.renewed(zone_id, &outcome, now),
+                Err(e) => {
+                    warn!("⚠️  Failed to renew the lease of {}: {}", zone_id, e);
+                    replication.renewal_failed(zone_id, now)
+                }
+            };
+            match change {
+                Some(RoleChange::Promoted) => self.take_over(zone_id).await,
+                Some(RoleChange::Demoted) => self.stand_by(zone_id).await,
+                None => {}
+            }
+        }
+    }
+
+    /// Apply a replica the primary of a zone published
+    pub async fn replicated(&self, replicated: ZoneReplicated) {
+        let Some(replication) = &self.replication else {
+            return;
+        };
+        let Some(zone) = self.zones.get(&replicated.zone) else {
+            return;
+        };
+        let received = replication.receive(&replicated);
+        if received.demoted {
+            warn!("⚠️  {} took zone {} over", replicated.instance, replicated.zone);
+            self.stand_by(&replicated.zone).await;
+        }
+        if !received.apply {
+            return;
+        }
+        match serde_json::from_value::<ZoneReplica>(replicated.state) {
+            Ok(replica) => lock(zone).apply_replica(replica),
+            Err(e) => {
+                warn!("⚠️  Invalid replica of {}: {}", replicated.zone, e);
+                replication.resync(&replicated.zone);
+            }
+        }
+    }
+
+    /// Move a zone to a standby as an operator asked: the primary sends its whole state,
+    /// steps down and releases the lease for the standby to acquire
+    pub async fn failover_requested(&self, request: ZoneFailoverRequested) {
+        let Some(replication) = &self.replication else {
+            return;
+        };
+        let zone_id = request.zone.as_str();
+        let Some(role) = replication.role(zone_id) else {
+            return;
+        };
+        if role != ZoneRole::Standby && request.to_instance.as_deref() == Some(replication.instance()) {
+            // Already running here
+            return;
+        }
+        let target = request.to_instance.as_deref().unwrap_or("a standby");
+        info!("🔀 {} moves zone {} to {}", request.operator, zone_id, target);
+        self.replicate(zone_id, true).await;
+        let leases = &replication.leases;
+        let ttl = chrono::Duration::from_std(leases.ttl).unwrap_or_else(|_| chrono::Duration::zero());
+        let Some(token) = replication.fail_over(zone_id, request.to_instance.clone(), self.clock.now() + ttl) else {
+            return;
+        };
+        self.stand_by(zone_id).await;
+        let name = replication::lease_name(zone_id);
+        if let Err(e) = leases.registry.release(&leases.owner.instance, &name, token).await {
+            warn!("⚠️  Failed to release the lease of {}: {}", zone_id, e);
+        }
+    }
+
+    /// Run a zone this instance kept a standby copy of: host its entities, claim them
+    /// from the previous primary and give its NPCs their brains back
+    async fn take_over(&self, zone_id: &str) {
+        let Some(zone) = self.zones.get(zone_id) else {
+            return;
+        };
+        let (entity_ids, npcs) = {
+            let zone = lock(zone);
+            (zone.entity_ids(), zone.spawned_npcs())
+        };
+        info!("👑 Running zone {} with {} entities", zone_id, entity_ids.len());
+        lock(&self.entities).extend(entity_ids.iter().map(|entity_id| (entity_id.clone(), zone_id.to_string())));
+        if let Some(director) = self.ai.get(zone_id) {
+            let mut director = director.lock().await;
+            for (npc_id, template) in &npcs {
+                director.spawned(npc_id, template);
+            }
+        }
+        for entity_id in &entity_ids {
+            self.claim(zone_id, entity_id).await;
+        }
+    }
+
+    /// Keep a standby copy of a zone another instance runs now; its entities are left
+    /// to that instance
+    async fn stand_by(&self, zone_id: &str) {
+        info!("🪑 Zone {} is a standby here", zone_id);
+        let mut entity_ids = Vec::new();
+        lock(&self.entities).retain(|entity_id, hosted_in| {
+            if *hosted_in != zone_id {
+                return true;
+            }
+            entity_ids.push(entity_id.clone());
+            false
+        });
+        for entity_id in &entity_ids {
+            lock(&self.movement).forget(entity_id);
+            self.forget_brain(zone_id, entity_id).await;
+        }
+    }
+
+    /// Publish the changes of a zone running here since its previous replica, or its
+    /// whole state when `full`
+    async fn replicate(&self, zone_id: &str, full: bool) {
+        let Some(replication) = &self.replication else {
+            return;
+        };
+        let (Some(zone), Some((token, seq, full))) = (self.zones.get(zone_id), replication.next_replica(zone_id, full))
+        else {
+            return;
+        };
+        let replica = lock(zone).replica(full);
+        let state = match serde_json::to_value(&replica) {
+            Ok(state) => state,
+            Err(e) => {
+                warn!("⚠️  Failed to encode the replica of {}: {}", zone_id, e);
+                return;
+            }
+        };
+        let instance = replication.instance().to_string();
+        let replicated = ZoneReplicated { zone: zone_id.to_string(), instance, token, seq, full, state };
+        self.publish(&Topic::dynamic(topics::zone_replication(zone_id)), &replicated).await;
+    }
+
+    /// Hand an entity off to the instance hosting `zone_id`, entering it at `position`,
+    /// with the auras it carries and the rest of its live `state`. The entity is let go
+    /// once the destination commits; it stays here, with everything it carried, when the
+    /// destination refuses it or cannot be reached.
+    pub async fn hand_off(
+        &self,
+        entity_id: &str,
+        zone_id: &str,
+        position: Vec3,
+        mut state: ActorLiveState,
+    ) -> Result<String, HandoffError> {
+        if self.zones.contains_key(zone_id) {
+            return Err(HandoffError::Refused(format!("Zone {} is hosted by this instance", zone_id)));
+        }
+        let peer = self.handoffs.peer(zone_id)?;
+        let from_zone = lock(&self.entities)
+            .get(entity_id)
+            .cloned()
+            .ok_or_else(|| HandoffError::Refused(format!("{} is not hosted by this instance", entity_id)))?;
+        state.auras = lock(self.zone(&from_zone)?).carried_auras(entity_id);
+        self.handoffs.start_leaving(entity_id)?;
+        let offer = HandoffOffer {
+            handoff_id: uuid::Uuid::new_v4().to_string(),
+            from_instance: self.instance().to_string(),
+            zone_id: zone_id.to_string(),
+            entity_id: entity_id.to_string(),
+            position,
+            state,
+        };
+        let transferred = self.transfer(peer.as_ref(), &offer).await;
+        self.handoffs.stop_leaving(entity_id);
+        match transferred {
+            Ok(()) => {
+                info!("🛫 Handed {} off to {}", entity_id, zone_id);
+                self.forget(entity_id).await;
+                Ok(offer.handoff_id)
+            }
+            Err(e) => {
+                warn!("↩️  Handoff of {} to {} rolled back: {}", entity_id, zone_id, e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Offer and commit, aborting the offer when the commit fails
+    async fn transfer(&self, peer: &dyn HandoffPeer, offer: &HandoffOffer) -> Result<(), HandoffError> {
+        peer.offer(offer).await?;
+        if let Err(e) = peer.commit(&offer.handoff_id).await {
+            if let Err(abort) = peer.abort(&offer.handoff_id).await {
+                warn!("⚠️  Failed to abort handoff {}: {}", offer.handoff_id, abort);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Check an entity another instance offers and hold it until committed
+    pub fn accept_handoff(&self, offer: HandoffOffer) -> Result<(), HandoffError> {
+        let refused = |reason: String| Err(HandoffError::Refused(reason));
+        if !lock(self.primary(&offer.zone_id)?).definition().bounds.contains(offer.position) {
+            return refused(format!("{} is outside zone {}", offer.position, offer.zone_id));
+        }
+        if lock(&self.entities).contains_key(&offer.entity_id) {
+            return refused(format!("{} is already hosted here", offer.entity_id));
+        }
+        for aura in &offer.state.auras {
+            if let Err(reason) = aura.validate() {
+                return refused(format!("Invalid aura {}: {}", aura.id, reason));
+            }
+        }
+        if !offer.state.actor_data.is_empty() {
+            match HierarchicalActor::from_binary(&offer.state.actor_data) {
+                Ok(actor) if actor.id == offer.entity_id => {}
+                Ok(actor) => return refused(format!("Actor data of {} sent for {}", actor.id, offer.entity_id)),
+                Err(e) => return refused(format!("Invalid actor data: {}", e)),
+            }
+        }
+        self.handoffs.hold(offer, self.clock.now())
+    }
+
+    /// Place an entity whose offer was accepted, with the auras it carries; committing
+    /// again is harmless
+    pub async fn commit_handoff(&self, handoff_id: &str) -> Result<(), HandoffError> {
+        if self.handoffs.is_committed(handoff_id) {
+            return Ok(());
+        }
+        let offer = self
+            .handoffs
+            .take_offer(handoff_id, self.clock.now())
+            .ok_or_else(|| HandoffError::Refused(format!("Handoff {} is unknown or lapsed", handoff_id)))?;
+        let (zone_id, entity_id) = (offer.zone_id.as_str(), offer.entity_id.as_str());
+        let verdict = self.update_position(zone_id, entity_id, offer.position, 0.0, true).await?;
+        if let MoveVerdict::Corrected { violation, .. } = verdict {
+            return Err(HandoffError::Refused(format!("Cannot place {}: {}", entity_id, violation.kind.as_str())));
+        }
+        {
+            let mut zone = lock(self.zone(zone_id)?);
+            for aura in &offer.state.auras {
+                if let Err(e) = zone.attach_aura(entity_id, aura.clone()) {
+                    warn!("⚠️  {} arrived without aura {}: {}", entity_id, aura.id, e);
+                }
+            }
+        }
+        info!("🛬 {} arrived in {} from {}", entity_id, zone_id, offer.from_instance);
+        self.handoffs.arrive(offer, self.clock.now());
+        Ok(())
+    }
+
+    /// Drop an accepted offer, returning whether there was one
+    pub fn abort_handoff(&self, handoff_id: &str) -> bool {
+        self.handoffs.drop_offer(handoff_id)
+    }
+
+    /// Live state an entity arrived with, for its game server to take once; effects and
+    /// cooldowns that ran out meanwhile are left out
+    pub fn take_handoff_state(&self, entity_id: &str) -> Option<ActorLiveState> {
+        self.handoffs.take_arrived(entity_id, self.clock.now())
+    }
+
+    pub fn position(&self, zone_id: &str, entity_id: &str) -> WorldCoreResult<Option<Vec3>> {
+        Ok(lock(self.zone(zone_id)?).position(entity_id))
+    }
+
+    /// Entities within `radius` of `center`, closest first
+    pub fn nearby(&self, zone_id: &str, center: Vec3, radius: f64, limit: usize) -> WorldCoreResult<Vec<NearbyEntity>> {
+        Ok(lock(self.zone(zone_id)?).nearby(center, radius, limit))
+    }
+
+    /// Whether nothing blocks the view between two points of a zone
+    pub fn has_line_of_sight(&self, zone_id: &str, from: Vec3, to: Vec3) -> WorldCoreResult<bool> {
+        Ok(lock(self.zone(zone_id)?).has_line_of_sight(from, to))
+    }
+
+    /// Waypoints of a walkable path between two points of a zone, `None` when there is none
+    pub async fn find_path(&self, zone_id: &str, from: Vec3, to: Vec3) -> WorldCoreResult<Option<Vec<Vec3>>> {
+        let navigator = lock(self.zone(zone_id)?).navigator();
+        navigator.find_path(from, to, self.clock.now()).await
+    }
+
+    /// Instance this host claims entities for
+    fn instance(&self) -> &str {
+        self.presence.as_ref().map_or(SERVICE, |presence| presence.owner.instance.as_str())
+    }
+
+    /// Whether a zone runs here; every hosted zone does without replication
+    fn is_primary(&self, zone_id: &str) -> bool {
+        self.replication.as_ref().is_none_or(|replication| {
+            matches!(replication.role(zone_id), Some(ZoneRole::Primary { .. }))
+        })
+    }
+
+    /// A hosted zone that runs here, to update
+    fn primary(&self, zone_id: &str) -> WorldCoreResult<&Mutex<ZoneRuntime>> {
+        let zone = self.zone(zone_id)?;
+        if !self.is_primary(zone_id) {
+            return Err(WorldCoreError::Standby(zone_id.to_string()));
+        }
+        Ok(zone)
+    }
+
+    async fn forget_brain(&self, zone_id: &str, entity_id: &str) {
+        if let Some(director) = self.ai.get(zone_id) {
+            director.lock().await.despawned(entity_id);
+        }
+    }
+
+    fn zone(&self, zone_id: &str) -> WorldCoreResult<&Mutex<ZoneRuntime>> {
+        self.zones.get(zone_id).ok_or_else(|| WorldCoreError::ZoneNotFound(zone_id.to_string()))
+    }
+
+    async fn publish<M: Message>(&self, topic: &Topic<M>, message: &M) {
+        if let Err(e) = self.bus.publish_message(SERVICE, topic, message).await {
+            warn!("⚠️  Failed to publish {}: {}", M::TYPE, e);
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A zone as its NPCs see it; locked per query so actions can move entities meanwhile
+struct LockedZone<'a>(&'a Mutex<ZoneRuntime>);
+
+impl Perception for LockedZone<'_> {
+    fn position(&self, entity_id: &str) -> Option<Vec3> {
+        lock(self.0).position(entity_id)
+    }
+
+    fn nearby(&self, center: Vec3, radius: f64, limit: usize) -> Vec<NearbyEntity> {
+        lock(self.0).nearby(center, radius, limit)
+    }
+
+    fn is_npc(&self, entity_id: &str) -> bool {
+        lock(self.0).is_spawned(entity_id)
+    }
+
+    fn weather(&self) -> WeatherKind {
+        lock(self.0).weather().current
+    }
+
+    fn line_of_sight(&self, from: Vec3, to: Vec3) -> bool {
+        lock(self.0).has_line_of_sight(from, to)
+    }
+}
+
+/// Carries out the actions of one zone's NPCs
+struct HostActions<'a> {
+    host: &'a ZoneHost,
+    zone_id: &'a str,
+}
+
+impl HostActions<'_> {
+    /// Walk toward the entity under `target` (the blackboard key, `target` by default) at
+    /// `speed` units per second, around obstacles, succeeding once within `range`
+    async fn move_toward(&self, npc: NpcContext<'_>, params: &serde_json::Value, blackboard: &Blackboard) -> NodeStatus {
+        let key = params.get("target").and_then(|key| key.as_str()).unwrap_or("target");
+        let speed = params.get("speed").and_then(|speed| speed.as_f64()).unwrap_or(5.0);
+        let range = params.get("range").and_then(|range| range.as_f64()).unwrap_or(2.0);
+        let positions = blackboard.get_str(key).and_then(|target_id| {
+            let zone = lock(self.host.zones.get(self.zone_id)?);
+            Some((zone.position(npc.npc_id)?, zone.position(target_id)?))
+        });
+        let Some((from, to)) = positions else {
+            return NodeStatus::Failure;
+        };
+        let distance = from.distance(to);
+        if distance <= range {
+            return NodeStatus::Success;
+        }
+        let waypoint = match self.host.find_path(self.zone_id, from, to).await {
+            Ok(Some(waypoints)) => waypoints[0],
+            Ok(None) => return NodeStatus::Failure,
+            Err(e) => {
+                warn!("⚠️  {} cannot find a path: {}", npc.npc_id, e);
+                return NodeStatus::Failure;
+            }
+        };
+
+        // The last leg stops within range of the target
+        let leg = from.distance(waypoint);
+        let last = waypoint == to;
+        let step = (speed * self.host.tick.as_secs_f64()).min(if last { distance - range } else { leg });
+        let t = step / leg;
+        let next = Vec3::new(
+            from.x + (waypoint.x - from.x) * t,
+            from.y + (waypoint.y - from.y) * t,
+            from.z + (waypoint.z - from.z) * t,
+        );
+        match self.host.place(self.zone_id, npc.npc_id, next, speed, false).await {
+            Ok(()) if last && distance - step <= range => NodeStatus::Success,
+            Ok(()) => NodeStatus::Running,
+            Err(e) => {
+                warn!("⚠️  {} cannot move: {}", npc.npc_id, e);
+                NodeStatus::Failure
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl NpcActions for HostActions<'_> {
+    async fn perform(
+        &self,
+        npc: NpcContext<'_>,
+        action: &str,
+        params: &serde_json::Value,
+        blackboard: &mut Blackboard,
+    ) -> NodeStatus {
+        if action == "move_toward" {
+            return self.move_toward(npc, params, blackboard).await;
+        }
+        let requested = NpcActionRequested {
+            npc_id: npc.npc_id.to_string(),
+            zone: self.zone_id.to_string(),
+            action: action.to_string(),
+            target_id: blackboard.get_str("target").map(str::to_string),
+            params: params.clone(),
+        };
+        match self.host.bus.publish_message(SERVICE, &NPC_ACTION, &requested).await {
+            Ok(()) => NodeStatus::Success,
+            Err(e) => {
+                warn!("⚠️  Failed to publish {} of {}: {}", action, npc.npc_id, e);
+                NodeStatus::Failure
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::events::InProcessEventBus;
+    use crate::handoff::{Cooldown, TimedEffect};
+    use shared::party::BuffMode;
+    use shared::presence::{InMemoryLeases, InMemoryPresence, LeaseRegistry};
+    use world_core::auras::{AuraDefinition, AuraEffect};
+    use world_core::movement::MovementViolationKind;
+    use world_core::zones::{SpawnDefinition, ZoneDefinition};
+    use world_core::Bounds;
+
+    fn definition(id: &str) -> ZoneDefinition {
+        ZoneDefinition {
+            id: id.to_string(),
+            name: id.to_string(),
+            bounds: Bounds { min: Vec3::new(0.0, 0.0, 0.0), max: Vec3::new(100.0, 10.0, 100.0) },
+            cell_size: 16.0,
+            spawns: Vec::new(),
+            hazards: Vec::new(),
+            auras: Vec::new(),
+            weather: Default::default(),
+            obstacles: Vec::new(),
+            navigation: Default::default(),
+        }
+    }
+
+    async fn host(bus: Arc<dyn EventBus>, definitions: Vec<ZoneDefinition>) -> ZoneHost {
+        let config = ZoneHostConfig { definitions, ..Default::default() };
+        ZoneHost::load(&config, None, bus, shared::wall_clock()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn entities_move_between_hosted_zones() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut despawned = bus.subscribe(&ACTOR_DESPAWNED).await.unwrap();
+        let mut moved = bus.subscribe(&ACTOR_MOVED).await.unwrap();
+        let host = host(bus, vec![definition("forest"), definition("city")]).await;
+
+        host.update_position("forest", "player", Vec3::new(10.0, 0.0, 10.0), 5.0, false).await.unwrap();
+        assert_eq!(moved.next().await.unwrap().unwrap().zone.as_deref(), Some("forest"));
+        host.update_position("city", "player", Vec3::new(50.0, 0.0, 50.0), 5.0, true).await.unwrap();
+
+        assert_eq!(host.position("forest", "player").unwrap(), None);
+        assert_eq!(host.position("city", "player").unwrap(), Some(Vec3::new(50.0, 0.0, 50.0)));
+        assert_eq!(despawned.next().await.unwrap().unwrap().zone.as_deref(), Some("forest"));
+        assert!(moved.next().await.unwrap().unwrap().teleported);
+
+        let nearby = host.nearby("city", Vec3::new(52.0, 0.0, 50.0), 5.0, 10).unwrap();
+        assert_eq!(nearby.len(), 1);
+        assert!(host.remove("city", "player", DespawnReason::LoggedOut).await.unwrap());
+        assert!(!host.remove("city", "player", DespawnReason::LoggedOut).await.unwrap());
+        assert!(matches!(host.position("desert", "player"), Err(WorldCoreError::ZoneNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn refused_moves_put_entities_back() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut rejected = bus.subscribe(&MOVEMENT_REJECTED).await.unwrap();
+        let host = host(bus, vec![definition("forest")]).await;
+
+        host.update_position("forest", "player", Vec3::new(10.0, 0.0, 10.0), 5.0, false).await.unwrap();
+        let verdict = host.update_position("forest", "player", Vec3::new(40.0, 0.0, 10.0), 5.0, false).await.unwrap();
+        assert!(matches!(verdict, MoveVerdict::Corrected { position, .. } if position == Vec3::new(10.0, 0.0, 10.0)));
+        assert_eq!(host.position("forest", "player").unwrap(), Some(Vec3::new(10.0, 0.0, 10.0)));
+
+        let event = rejected.next().await.unwrap().unwrap();
+        assert_eq!((event.actor_id.as_str(), event.kind), ("player", MovementViolationKind::Speed));
+        assert_eq!(event.attempted, [40.0, 0.0, 10.0]);
+
+        // First placements outside the zone have nothing to snap back to
+        let placed = host.update_position("forest", "ghost", Vec3::new(-5.0, 0.0, 10.0), 5.0, false).await;
+        assert!(matches!(placed, Err(WorldCoreError::OutOfBounds { .. })));
+    }
+
+    #[tokio::test]
+    async fn obstacles_block_sight_and_paths() {
+        let mut forest = definition("forest");
+        forest.obstacles.push(Bounds { min: Vec3::new(40.0, 0.0, 0.0), max: Vec3::new(44.0, 10.0, 80.0) });
+        let host = host(Arc::new(InProcessEventBus::new()), vec![forest]).await;
+        let (from, to) = (Vec3::new(20.0, 0.0, 50.0), Vec3::new(60.0, 0.0, 50.0));
+
+        assert!(!host.has_line_of_sight("forest", from, to).unwrap());
+        assert!(host.has_line_of_sight("forest", from, Vec3::new(20.0, 0.0, 10.0)).unwrap());
+        let path = host.find_path("forest", from, to).await.unwrap().unwrap();
+        assert!(path.len() > 1 && path.last() == Some(&to));
+    }
+
+    #[tokio::test]
+    async fn spawned_npcs_are_announced() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut spawned = bus.subscribe(&ACTOR_SPAWNED).await.unwrap();
+        let mut forest = definition("forest");
+        forest.spawns.push(SpawnDefinition {
+            id: "wolves".to_string(),
+            template: "forest_wolf".to_string(),
+            center: Vec3::new(50.0, 0.0, 50.0),
+            radius: 5.0,
+            max_alive: 1,
+            respawn_secs: 30,
+        });
+        let host = host(bus, vec![forest]).await;
+
+        host.tick("forest").await;
+        let event = spawned.next().await.unwrap().unwrap();
+        assert_eq!(event.zone.as_deref(), Some("forest"));
+        assert_eq!(event.spawn_point.as_deref(), Some("wolves"));
+        // Spawned NPCs can be removed like any other entity
+        assert!(host.remove("forest", &event.actor_id, DespawnReason::Died).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn npcs_chase_and_attack_sensed_players() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut actions = bus.subscribe(&NPC_ACTION).await.unwrap();
+        let mut forest = definition("forest");
+        forest.spawns.push(SpawnDefinition {
+            id: "wolves".to_string(),
+            template: "forest_wolf".to_string(),
+            center: Vec3::new(50.0, 0.0, 50.0),
+            radius: 0.0,
+            max_alive: 1,
+            respawn_secs: 30,
+        });
+        let ai: AiConfig = serde_yaml::from_str(
+            r#"
+templates: { forest_wolf: wolf }
+trees:
+  - id: wolf
+    root:
+      sequence:
+        - sense: { radius: 10, store_as: target }
+        - action: { name: move_toward, params: { speed: 6, range: 2 } }
+        - action: { name: attack, params: { skill: bite } }
+"#,
+        )
+        .unwrap();
+        let host = host(bus, vec![forest]).await.with_ai(&ai).unwrap();
+        host.update_position("forest", "player", Vec3::new(53.0, 0.0, 50.0), 5.0, false).await.unwrap();
+
+        // 0.6 units per 100ms tick until within 2 units of the player
+        host.tick("forest").await;
+        let wolf = host.nearby("forest", Vec3::new(50.6, 0.0, 50.0), 0.01, 1).unwrap().remove(0).entity_id;
+        host.tick("forest").await;
+        let position = host.position("forest", &wolf).unwrap().unwrap();
+        assert!((position.distance(Vec3::new(53.0, 0.0, 50.0)) - 2.0).abs() < 1e-9);
+
+        let attack = actions.next().await.unwrap().unwrap();
+        assert_eq!((attack.npc_id.as_str(), attack.action.as_str()), (wolf.as_str(), "attack"));
+        assert_eq!(attack.target_id.as_deref(), Some("player"));
+        assert_eq!(attack.params["skill"], "bite");
+        assert!(host.remove("forest", &wolf, DespawnReason::Died).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn entities_are_handed_over_between_instances() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let mut handed = bus.subscribe(&ACTOR_HANDED_OVER).await.unwrap();
+        let registry: Arc<dyn PresenceRegistry> = Arc::new(InMemoryPresence::new(shared::wall_clock()));
+        let claims = |instance: &str| PresenceClaims {
+            registry: registry.clone(),
+            owner: PresenceOwner::new(instance, None),
+            ttl: Duration::from_secs(30),
+            heartbeat: Duration::from_secs(10),
+        };
+        let first = host(bus.clone(), vec![definition("forest")]).await.with_presence(claims("first"));
+        let second = host(bus, vec![definition("city")]).await.with_presence(claims("second"));
+
+        first.update_position("forest", "player", Vec3::new(10.0, 0.0, 10.0), 5.0, false).await.unwrap();
+        assert_eq!(registry.lookup("player").await.unwrap().unwrap().instance, "first");
+
+        second.update_position("city", "player", Vec3::new(20.0, 0.0, 20.0), 5.0, false).await.unwrap();
+        let event = handed.next().await.unwrap().unwrap();
+        assert_eq!((event.from_instance.as_str(), event.to_instance.as_str()), ("first", "second"));
+        assert_eq!(registry.lookup("player").await.unwrap().unwrap().zone, "city");
+
+        // The previous owner lets go once it hears about the handover, or on its next heartbeat
+        first.heartbeat().await;
+        assert_eq!(first.position("forest", "player").unwrap(), None);
+        assert!(!first.forget("player").await);
+
+        assert!(second.remove("city", "player", DespawnReason::LoggedOut).await.unwrap());
+        assert!(registry.lookup("player").await.unwrap().is_none());
+    }
+
+    /// Destination instance reached in-process; its commits get lost when `lose_commits`
+    struct LocalPeer {
+        host: Arc<ZoneHost>,
+        lose_commits: bool,
+    }
+
+    #[async_trait]
+    impl HandoffPeer for LocalPeer {
+        async fn offer(&self, offer: &HandoffOffer) -> Result<(), HandoffError> {
+            self.host.accept_handoff(offer.clone())
+        }
+
+        async fn commit(&self, handoff_id: &str) -> Result<(), HandoffError> {
+            if self.lose_commits {
+                return Err(HandoffError::Unreachable("connection reset".to_string()));
+            }
+            self.host.commit_handoff(handoff_id).await
+        }
+
+        async fn abort(&self, handoff_id: &str) -> Result<(), HandoffError> {
+            self.host.abort_handoff(handoff_id);
+            Ok(())
+        }
+    }
+
+    /// A host of `forest` handing entities off to `destination`, which hosts `peaks`
+    async fn crossing(destination: &Arc<ZoneHost>, lose_commits: bool) -> ZoneHost {
+        let peer = Arc::new(LocalPeer { host: destination.clone(), lose_commits });
+        let handoffs = Handoffs::new(&HandoffConfig::default()).with_peer("peaks", peer);
+        host(Arc::new(InProcessEventBus::new()), vec![definition("forest")]).await.with_handoffs(handoffs)
+    }
+
+    fn devotion() -> AuraDefinition {
+        AuraDefinition {
+            id: "devotion".to_string(),
+            radius: 10.0,
+            effects: vec![AuraEffect { stat: "armor".to_string(), value: 5.0, mode: BuffMode::Add }],
+            targets: Default::default(),
+            stacking: Default::default(),
+        }
+    }
+
+    /// A running haste, a faded shield, a blink cooldown and the snapshot of `actor_id`
+    fn live_state(actor_id: &str) -> ActorLiveState {
+        let now = shared::wall_clock().now();
+        let effect = |id: &str, expires_at| TimedEffect {
+            id: id.to_string(),
+            source_id: "shaman".to_string(),
+            stat: "speed".to_string(),
+            value: 1.3,
+            mode: BuffMode::Multiply,
+            expires_at,
+        };
+        ActorLiveState {
+            auras: Vec::new(),
+            effects: vec![effect("haste", now + chrono::Duration::minutes(5)), effect("shield", now)],
+            cooldowns: vec![Cooldown { ability: "blink".to_string(), ready_at: now + chrono::Duration::seconds(30) }],
+            actor_data: HierarchicalActor::with_id_and_name(actor_id.to_string(), "Hero".to_string()).to_binary(),
+        }
+    }
+
+    #[tokio::test]
+    async fn entities_are_handed_off_with_their_live_state() {
+        let destination = Arc::new(host(Arc::new(InProcessEventBus::new()), vec![definition("peaks")]).await);
+        let source = crossing(&destination, false).await;
+        source.update_position("forest", "hero", Vec3::new(10.0, 0.0, 10.0), 5.0, false).await.unwrap();
+        lock(source.zone("forest").unwrap()).attach_aura("hero", devotion()).unwrap();
+
+        let entry = Vec3::new(5.0, 0.0, 5.0);
+        let handoff_id = source.hand_off("hero", "peaks", entry, live_state("hero")).await.unwrap();
+        assert_eq!(source.position("forest", "hero").unwrap(), None);
+        assert_eq!(destination.position("peaks", "hero").unwrap(), Some(entry));
+        assert_eq!(lock(destination.zone("peaks").unwrap()).carried_auras("hero"), [devotion()]);
+        destination.commit_handoff(&handoff_id).await.unwrap();
+
+        // The new game server takes the rest once, without what ran out
+        let state = destination.take_handoff_state("hero").unwrap();
+        assert_eq!(state.effects.iter().map(|effect| effect.id.as_str()).collect::<Vec<_>>(), ["haste"]);
+        assert_eq!(state.cooldowns[0].ability, "blink");
+        assert_eq!(HierarchicalActor::from_binary(&state.actor_data).unwrap().id, "hero");
+        assert!(destination.take_handoff_state("hero").is_none());
+
+        let again = source.hand_off("hero", "peaks", entry, ActorLiveState::default()).await;
+        assert!(matches!(again, Err(HandoffError::Refused(_))));
+        let unknown = source.hand_off("hero", "desert", entry, ActorLiveState::default()).await;
+        assert!(matches!(unknown, Err(HandoffError::NoPeer(_))));
+    }
+
+    #[tokio::test]
+    async fn refused_handoffs_are_rolled_back() {
+        let destination = Arc::new(host(Arc::new(InProcessEventBus::new()), vec![definition("peaks")]).await);
+        let source = crossing(&destination, false).await;
+        let start = Vec3::new(10.0, 0.0, 10.0);
+        source.update_position("forest", "hero", start, 5.0, false).await.unwrap();
+        lock(source.zone("forest").unwrap()).attach_aura("hero", devotion()).unwrap();
+
+        let outside = source.hand_off("hero", "peaks", Vec3::new(500.0, 0.0, 5.0), live_state("hero")).await;
+        assert!(matches!(outside, Err(HandoffError::Refused(_))));
+        let impostor = source.hand_off("hero", "peaks", Vec3::new(5.0, 0.0, 5.0), live_state("villain")).await;
+        assert!(matches!(impostor, Err(HandoffError::Refused(_))));
+        assert_eq!(source.position("forest", "hero").unwrap(), Some(start));
+        assert_eq!(lock(source.zone("forest").unwrap()).carried_auras("hero"), [devotion()]);
+
+        // A commit that does not come through aborts the offer, which no longer holds the entity
+        let source = crossing(&destination, true).await;
+        source.update_position("forest", "hero", start, 5.0, false).await.unwrap();
+        let lost = source.hand_off("hero", "peaks", Vec3::new(5.0, 0.0, 5.0), live_state("hero")).await;
+        assert!(matches!(lost, Err(HandoffError::Unreachable(_))));
+        assert_eq!(source.position("forest", "hero").unwrap(), Some(start));
+        assert_eq!(destination.position("peaks", "hero").unwrap(), None);
+
+        let source = crossing(&destination, false).await;
+        source.update_position("forest", "hero", start, 5.0, false).await.unwrap();
+        source.hand_off("hero", "peaks", Vec3::new(5.0, 0.0, 5.0), live_state("hero")).await.unwrap();
+    }
+
+    fn leases(registry: Arc<dyn LeaseRegistry>, instance: &str) -> ZoneLeases {
+        ZoneLeases {
+            registry,
+            owner: PresenceOwner::new(instance, None),
+            ttl: Duration::from_secs(5),
+            renew: Duration::from_secs(1),
+            full_every: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn standbys_follow_their_primary_and_take_over_on_failover() {
+        let bus: Arc<dyn EventBus> = Arc::new(InProcessEventBus::new());
+        let registry: Arc<dyn LeaseRegistry> = Arc::new(InMemoryLeases::new(shared::wall_clock()));
+        let topic: Topic<ZoneReplicated> = Topic::dynamic(topics::zone_replication("forest"));
+        let mut replicas = bus.subscribe(&topic).await.unwrap();
+        let primary = host(bus.clone(), vec![definition("forest")]).await;
+        let primary = primary.with_replication(leases(registry.clone(), "first"));
+        let standby = host(bus, vec![definition("forest")]).await.with_replication(leases(registry, "second"));
+        primary.renew_leases().await;
+        standby.renew_leases().await;
+
+        // Only the primary runs the zone; the standby follows its replicas
+        let at = |x: f64| Vec3::new(x, 0.0, 10.0);
+        let refused = standby.update_position("forest", "player", at(10.0), 5.0, true).await;
+        assert!(matches!(refused, Err(WorldCoreError::Standby(_))));
+        for x in [10.0, 12.0] {
+            primary.update_position("forest", "player", at(x), 5.0, true).await.unwrap();
+            primary.tick("forest").await;
+            standby.tick("forest").await;
+        }
+        for _ in 0..2 {
+            standby.replicated(replicas.next().await.unwrap().unwrap()).await;
+        }
+        assert_eq!(standby.position("forest", "player").unwrap(), Some(at(12.0)));
+
+        // The primary sends its whole state, steps down and leaves the lease to the standby
+        let request = ZoneFailoverRequested {
+            zone: "forest".to_string(),
+            to_instance: Some("second".to_string()),
+            operator: "ops".to_string(),
+            requested_at: chrono::Utc::now(),
+        };
+        primary.failover_requested(request.clone()).await;
+        standby.failover_requested(request).await;
+        let last = replicas.next().await.unwrap().unwrap();
+        assert!(last.full);
+        standby.replicated(last).await;
+        primary.renew_leases().await;
+        standby.renew_leases().await;
+        assert!(standby.summaries()[0].primary && !primary.summaries()[0].primary);
+
+        standby.update_position("forest", "player", at(13.0), 5.0, true).await.unwrap();
+        assert!(primary.update_position("forest", "player", at(14.0), 5.0, true).await.is_err());
+    }
+}
@@ -1,33 +1,317 @@
+mod config;
+mod grpc;
+mod handoff;
+mod host;
+mod population;
+mod replication;
+mod zone_store;
+
 use axum::{
+    extract::{Path, State},
+    http::StatusCode,
     routing::get,
-    Router,
+    Json, Router,
+};
+use config::Config;
+use grpc::{GrpcHandoffPeer, ZoneHostService};
+use handoff::Handoffs;
+use host::{PresenceClaims, ZoneHost, ZoneSummary};
+use population::{ZonePopulation, ZonePopulations};
+use replication::ZoneLeases;
+use shared::events::topics::{
+    self, ActorDespawned, ActorHandedOver, ActorSpawned, LifecycleState, ServiceLifecycle, ZoneFailoverRequested,
+    ZoneReplicated, ACTOR_DESPAWNED, ACTOR_HANDED_OVER, ACTOR_SPAWNED, SERVICE_LIFECYCLE, ZONE_FAILOVER,
+};
+use shared::events::{connect, BusConfig, Consumer, ConsumerOptions, EventBus, EventBusExt, Topic};
+use shared::health::{check_fn, BusCheck, HealthRegistry, HealthReport, RedisCheck, LIVE_PATH, READY_PATH};
+use shared::presence::{
+    InMemoryLeases, InMemoryPresence, LeaseRegistry, Presence, PresenceOwner, PresenceRegistry, RedisLeases,
+    RedisPresence,
 };
+use shared::shutdown::{Shutdown, MONGODB_SHUTDOWN_TIMEOUT};
+use shared::telemetry::{self, TelemetryOptions};
+use shared::ChaosError;
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
+use std::time::Duration;
+use zone_store::MongoZoneSource;
+
+const SERVICE: &str = "world-service";
+
+/// Consumer name, a single topic token
+const CONSUMER: &str = "world_service";
 
 #[tokio::main]
 async fn main() {
     // Initialize tracing
-    tracing_subscriber::fmt::init();
-    
+    let telemetry = telemetry::init(&TelemetryOptions::from_env(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")));
+
+    let shutdown = Shutdown::from_env();
+    shutdown.listen_for_signals();
+
+    let config = Config::load().unwrap();
+    let mongo_client = mongodb::Client::with_uri_str(&config.database.mongodb_uri).await.unwrap();
+    let database = mongo_client.database(&config.database.mongodb_database);
+
+    // Follow the actors the game loop spawns
+    let bus = connect(&BusConfig::from_env()).await.unwrap();
+    let populations = Arc::new(ZonePopulations::new());
+    let spawned = populations.clone();
+    Consumer::new(bus.clone(), ACTOR_SPAWNED, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: ActorSpawned, _| {
+            spawned.spawned(&event);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+    let despawned = populations.clone();
+    Consumer::new(bus.clone(), ACTOR_DESPAWNED, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: ActorDespawned, _| {
+            despawned.despawned(&event);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+    // Hosted entities are claimed for this instance, shared through Redis when configured
+    let instance = uuid::Uuid::new_v4().to_string();
+    let grpc_addr: SocketAddr = format!("{}:{}", config.server.host, config.server.grpc_port).parse().unwrap();
+    let presence: Arc<dyn PresenceRegistry> = match &config.presence.redis_url {
+        Some(url) => Arc::new(RedisPresence::new(url).unwrap()),
+        None => {
+            tracing::warn!("presence.redis_url is not set, presence is only known to this instance");
+            Arc::new(InMemoryPresence::new(shared::wall_clock()))
+        }
+    };
+    let address = config.presence.address.clone().unwrap_or_else(|| grpc_addr.to_string());
+    let owner = PresenceOwner::new(instance.clone(), Some(address));
+    let claims = PresenceClaims {
+        registry: presence.clone(),
+        owner: owner.clone(),
+        ttl: Duration::from_secs(config.presence.ttl_secs),
+        heartbeat: Duration::from_secs(config.presence.heartbeat_secs),
+    };
+
+    // Entities crossing into zones other instances host are handed off to them
+    let timeout = Duration::from_millis(config.handoff.timeout_ms);
+    let handoffs = config.handoff.peers.iter().fold(Handoffs::new(&config.handoff), |handoffs, (zone_id, address)| {
+        handoffs.with_peer(zone_id.clone(), Arc::new(GrpcHandoffPeer::new(address, timeout).unwrap()))
+    });
+
+    // Host the assigned zones; those not defined in the config come from MongoDB
+    let source = MongoZoneSource::new(&database);
+    let mut host = ZoneHost::load(&config.zones, Some(&source), bus.clone(), shared::wall_clock())
+        .await
+        .unwrap()
+        .with_presence(claims)
+        .with_handoffs(handoffs)
+        .with_ai(&config.ai)
+        .unwrap();
+
+    // With replication, zones run on the instance holding their lease; the other instances
+    // assigned them stand by
+    if config.replication.enabled {
+        let registry: Arc<dyn LeaseRegistry> = match &config.presence.redis_url {
+            Some(url) => Arc::new(RedisLeases::new(url).unwrap()),
+            None => {
+                tracing::warn!("presence.redis_url is not set, zone leases are only known to this instance");
+                Arc::new(InMemoryLeases::new(shared::wall_clock()))
+            }
+        };
+        host = host.with_replication(ZoneLeases {
+            registry,
+            owner,
+            ttl: Duration::from_millis(config.replication.lease_ttl_ms),
+            renew: Duration::from_millis(config.replication.renew_ms),
+            full_every: config.replication.full_every,
+        });
+    }
+    let zones = Arc::new(host);
+    if config.replication.enabled {
+        // Replicas are only of use live: a standby that missed some waits for a full one
+        let options = ConsumerOptions { replay: false, ..ConsumerOptions::new(CONSUMER) };
+        for zone in zones.summaries() {
+            let replicated = zones.clone();
+            let topic: Topic<ZoneReplicated> = Topic::dynamic(topics::zone_replication(&zone.id));
+            Consumer::new(bus.clone(), topic, options.clone())
+                .spawn(move |event: ZoneReplicated, _| {
+                    let zones = replicated.clone();
+                    async move {
+                        zones.replicated(event).await;
+                        Ok(())
+                    }
+                })
+                .await
+                .unwrap();
+        }
+        let failover = zones.clone();
+        Consumer::new(bus.clone(), ZONE_FAILOVER, ConsumerOptions::new(CONSUMER))
+            .spawn(move |event: ZoneFailoverRequested, _| {
+                let zones = failover.clone();
+                async move {
+                    zones.failover_requested(event).await;
+                    Ok(())
+                }
+            })
+            .await
+            .unwrap();
+    }
+    let ticks = zones.spawn_ticks();
+    let heartbeats = zones.spawn_heartbeats();
+    let leases = zones.spawn_leases();
+
+    // Let go of the entities other instances took over
+    let handed_over = zones.clone();
+    let this_instance = instance.clone();
+    Consumer::new(bus.clone(), ACTOR_HANDED_OVER, ConsumerOptions::new(CONSUMER))
+        .spawn(move |event: ActorHandedOver, _| {
+            let (zones, ours) = (handed_over.clone(), event.from_instance == this_instance);
+            async move {
+                if ours {
+                    zones.forget(&event.actor_id).await;
+                }
+                Ok(())
+            }
+        })
+        .await
+        .unwrap();
+
+    // Ready while the bus and the presence registry are reachable; MongoDB is only read
+    // while loading zones
+    let ping = database.clone();
+    let mut health = HealthRegistry::new(SERVICE)
+        .with_shutdown(&shutdown)
+        .register("event_bus", BusCheck::new(bus.clone()));
+    if let Some(url) = &config.presence.redis_url {
+        health = health.register("presence", RedisCheck::new(url));
+    }
+    let health = Arc::new(
+        health
+            .register_optional(
+                "mongodb",
+                check_fn(move || {
+                    let database = ping.clone();
+                    async move {
+                        database
+                            .run_command(mongodb::bson::doc! { "ping": 1 }, None)
+                            .await
+                            .map(|_| ())
+                            .map_err(|e| ChaosError::Database(e.to_string()))
+                    }
+                }),
+            ),
+    );
+
     // Create router
     let app = Router::new()
         .route("/health", get(health_check))
-        .route("/", get(root));
-    
+        .route("/zones/population", get(zone_populations))
+        .route("/", get(root))
+        .with_state(populations)
+        .merge(Router::new().route("/zones", get(hosted_zones)).with_state(zones.clone()))
+        .merge(Router::new().route("/presence/:actor_id", get(actor_presence)).with_state(presence))
+        .merge(
+            Router::new()
+                .route(LIVE_PATH, get(liveness))
+                .route(READY_PATH, get(readiness))
+                .with_state(health),
+        );
+
+    // Game servers report positions and query proximity over gRPC
+    tracing::info!("🛰️  world-service gRPC starting on {}", grpc_addr);
+    let grpc = tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(ZoneHostService::new(zones.clone()).into_server())
+            .serve_with_shutdown(grpc_addr, shutdown.signal()),
+    );
+
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse().unwrap();
     tracing::info!("🚀 world-service server starting on {}", addr);
-    
+
+    announce(bus.as_ref(), &instance, addr, LifecycleState::Started).await;
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+
+    // Gateways stop routing here once the instance announces it is stopping
+    let stopping = {
+        let (bus, instance, shutdown) = (bus.clone(), instance.clone(), shutdown.clone());
+        async move {
+            shutdown.signal().await;
+            announce(bus.as_ref(), &instance, addr, LifecycleState::Stopping).await;
+        }
+    };
+    let server = axum::serve(listener, app).with_graceful_shutdown(stopping);
+    if let Some(result) = shutdown.drain(server).await {
+        result.unwrap();
+    }
+    if let Some(Ok(Err(e))) = shutdown.drain(grpc).await {
+        tracing::warn!("⚠️  gRPC server failed: {}", e);
+    }
+    for tick in ticks.into_iter().chain(heartbeats).chain(leases) {
+        tick.abort();
+    }
+    zones.release_all().await;
+
+    announce(bus.as_ref(), &instance, addr, LifecycleState::Stopped).await;
+    if let Err(e) = bus.flush().await {
+        tracing::warn!("Failed to flush the event bus: {}", e);
+    }
+    if tokio::time::timeout(MONGODB_SHUTDOWN_TIMEOUT, mongo_client.shutdown()).await.is_err() {
+        tracing::warn!("⚠️  MongoDB operations still pending after {:?}", MONGODB_SHUTDOWN_TIMEOUT);
+    }
+    tracing::info!("👋 world-service stopped");
+    telemetry.shutdown();
+}
+
+async fn announce(bus: &dyn EventBus, instance: &str, addr: SocketAddr, state: LifecycleState) {
+    let lifecycle = ServiceLifecycle {
+        service: SERVICE.to_string(),
+        instance: instance.to_string(),
+        state,
+        address: Some(addr.to_string()),
+    };
+    if let Err(e) = bus.publish_message(SERVICE, &SERVICE_LIFECYCLE, &lifecycle).await {
+        tracing::warn!("Failed to announce {:?}: {}", state, e);
+    }
 }
 
 async fn health_check() -> &'static str {
     "OK"
 }
 
+async fn liveness(State(health): State<Arc<HealthRegistry>>) -> Json<HealthReport> {
+    Json(health.liveness())
+}
+
+/// 503 while the event bus is disconnected
+async fn readiness(State(health): State<Arc<HealthRegistry>>) -> (StatusCode, Json<HealthReport>) {
+    let report = health.readiness().await;
+    let status = StatusCode::from_u16(report.status.http_status()).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(report))
+}
+
+/// Actors per zone
+async fn zone_populations(State(populations): State<Arc<ZonePopulations>>) -> Json<Vec<ZonePopulation>> {
+    Json(populations.snapshot())
+}
+
+/// Zones hosted by this instance
+async fn hosted_zones(State(zones): State<Arc<ZoneHost>>) -> Json<Vec<ZoneSummary>> {
+    Json(zones.summaries())
+}
+
+/// Instance owning an actor, for services routing actions to it
+async fn actor_presence(
+    State(presence): State<Arc<dyn PresenceRegistry>>,
+    Path(actor_id): Path<String>,
+) -> Result<Json<Presence>, (StatusCode, String)> {
+    match presence.lookup(&actor_id).await {
+        Ok(Some(presence)) => Ok(Json(presence)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("Actor {} is not present", actor_id))),
+        Err(e) => Err((StatusCode::SERVICE_UNAVAILABLE, e.to_string())),
+    }
+}
+
 async fn root() -> &'static str {
     "Hello from world-service!"
 }
-
@@ -0,0 +1,94 @@
+//! Zone populations followed from the actors chaos-backend spawns and despawns.
+
+use serde::Serialize;
+use shared::events::topics::{ActorDespawned, ActorSpawned};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::RwLock;
+
+/// Actors present in each zone
+#[derive(Debug, Default)]
+pub struct ZonePopulations {
+    zones: RwLock<HashMap<String, HashSet<String>>>,
+}
+
+/// Actor count of one zone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ZonePopulation {
+    pub zone: String,
+    pub actors: usize,
+}
+
+impl ZonePopulations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Track actor ids rather than counts so replayed messages are harmless
+    pub fn spawned(&self, event: &ActorSpawned) {
+        let Some(zone) = &event.zone else {
+            return;
+        };
+        let mut zones = self.zones.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        zones.entry(zone.clone()).or_default().insert(event.actor_id.clone());
+    }
+
+    pub fn despawned(&self, event: &ActorDespawned) {
+        let mut zones = self.zones.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match &event.zone {
+            Some(zone) => {
+                if let Some(actors) = zones.get_mut(zone) {
+                    actors.remove(&event.actor_id);
+                }
+            }
+            None => {
+                for actors in zones.values_mut() {
+                    actors.remove(&event.actor_id);
+                }
+            }
+        }
+        zones.retain(|_, actors| !actors.is_empty());
+    }
+
+    /// Populated zones by name
+    pub fn snapshot(&self) -> Vec<ZonePopulation> {
+        let zones = self.zones.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        zones
+            .iter()
+            .map(|(zone, actors)| (zone.clone(), actors.len()))
+            .collect::<BTreeMap<_, _>>()
+            .into_iter()
+            .map(|(zone, actors)| ZonePopulation { zone, actors })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::events::topics::DespawnReason;
+
+    fn spawned(actor_id: &str, zone: &str) -> ActorSpawned {
+        ActorSpawned { actor_id: actor_id.to_string(), zone: Some(zone.to_string()), spawn_point: None }
+    }
+
+    #[test]
+    fn populations_follow_spawns_and_despawns() {
+        let populations = ZonePopulations::new();
+        populations.spawned(&spawned("wolf_1", "forest"));
+        populations.spawned(&spawned("wolf_2", "forest"));
+        // Replayed duplicate
+        populations.spawned(&spawned("wolf_2", "forest"));
+        populations.spawned(&spawned("guard_1", "city"));
+
+        assert_eq!(
+            populations.snapshot(),
+            vec![
+                ZonePopulation { zone: "city".to_string(), actors: 1 },
+                ZonePopulation { zone: "forest".to_string(), actors: 2 },
+            ]
+        );
+
+        populations.despawned(&ActorDespawned { actor_id: "guard_1".to_string(), zone: None, reason: DespawnReason::Died });
+        assert_eq!(populations.snapshot(), vec![ZonePopulation { zone: "forest".to_string(), actors: 2 }]);
+    }
+}
@@ -0,0 +1,364 @@
+//! Hot standby of the hosted zones.
+//!
+//! Instances assigned the same zone run it on one of them, the primary, which holds the
+//! zone's lease; the others keep a standby copy. After every tick the primary publishes
+//! what changed on the zone's replication topic, and its whole state every few ticks.
+//! Standbys apply the replicas to their copy without ticking it. A standby that missed a
+//! replica waits for the next full one.
+//!
+//! A primary that stops renewing its lease, because it crashed or lost the registry,
+//! loses it after the lease's ttl; the first standby to acquire it takes the zone over
+//! with a higher fencing token. Replicas carry the token of their primary, so standbys
+//! ignore whatever a fenced-off primary still sends, and a primary hearing of a higher
+//! token steps down. A primary that cannot renew for the whole ttl steps down on its own,
+//! before a standby can take over.
+//!
+//! Operators move a zone on purpose with `admin-cli world failover`: the primary sends a
+//! last full replica, steps down and releases the lease, and only the requested standby
+//! may acquire it until the lease's ttl passed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use shared::events::topics::ZoneReplicated;
+use shared::presence::{LeaseOutcome, LeaseRegistry, PresenceOwner};
+use shared::Timestamp;
+
+/// Zone leases of an instance
+pub struct ZoneLeases {
+    pub registry: Arc<dyn LeaseRegistry>,
+    pub owner: PresenceOwner,
+    /// How long a lease lasts without renewal
+    pub ttl: Duration,
+    pub renew: Duration,
+    /// Replicas between full ones
+    pub full_every: u64,
+}
+
+/// Name of a zone's lease
+pub fn lease_name(zone_id: &str) -> String {
+    format!("zone:{}", zone_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneRole {
+    /// Runs the zone under the lease with this fencing token
+    Primary { token: u64 },
+    Standby,
+}
+
+/// Role change a lease renewal or a replica calls for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoleChange {
+    Promoted,
+    Demoted,
+}
+
+/// What to do with a replica from another instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Received {
+    /// A newer primary fenced this instance off
+    pub demoted: bool,
+    /// The replica follows what the copy has
+    pub apply: bool,
+}
+
+/// Failover an operator requested
+#[derive(Debug, Clone)]
+struct Failover {
+    /// Standby that may acquire the lease, any other instance when absent
+    to_instance: Option<String>,
+    until: Timestamp,
+    /// This instance was the primary
+    stepped_down: bool,
+}
+
+/// Replication of one zone
+#[derive(Debug)]
+struct ZoneReplication {
+    role: ZoneRole,
+    /// Highest fencing token seen
+    token: u64,
+    /// Replicas published as the primary, or applied from it
+    seq: u64,
+    /// A replica was missed, only a full one can be applied
+    resync: bool,
+    /// Last renewal as the primary
+    renewed_at: Option<Timestamp>,
+    failover: Option<Failover>,
+}
+
+/// Roles of the hosted zones
+pub struct Replication {
+    pub leases: ZoneLeases,
+    zones: Mutex<HashMap<String, ZoneReplication>>,
+}
+
+impl Replication {
+    /// Every zone starts as a standby, until its lease is acquired
+    pub fn new(leases: ZoneLeases, zone_ids: impl IntoIterator<Item = String>) -> Self {
+        let zones = zone_ids
+            .into_iter()
+            .map(|zone_id| {
+                let replication = ZoneReplication {
+                    role: ZoneRole::Standby,
+                    token: 0,
+                    seq: 0,
+                    resync: true,
+                    renewed_at: None,
+                    failover: None,
+                };
+                (zone_id, replication)
+            })
+            .collect();
+        Self { leases, zones: Mutex::new(zones) }
+    }
+
+    pub fn instance(&self) -> &str {
+        &self.leases.owner.instance
+    }
+
+    /// Role of a zone, `None` when it is not hosted
+    pub fn role(&self, zone_id: &str) -> Option<ZoneRole> {
+        lock(&self.zones).get(zone_id).map(|zone| zone.role)
+    }
+
+    /// Whether this instance may acquire or renew the lease of a zone: not while a
+    /// failover to another instance is under way
+    pub fn may_acquire(&self, zone_id: &str, now: Timestamp) -> bool {
+        let mut zones = lock(&self.zones);
+        let Some(zone) = zones.get_mut(zone_id) else {
+            return false;
+        };
+        zone.failover = zone.failover.take().filter(|failover| failover.until > now);
+        match &zone.failover {
+            Some(Failover { to_instance: Some(to_instance), .. }) => to_instance == self.instance(),
+            Some(Failover { to_instance: None, stepped_down, .. }) => !stepped_down,
+            None => true,
+        }
+    }
+
+    /// Take the outcome of acquiring a zone's lease into account
+    pub fn renewed(&self, zone_id: &str, outcome: &LeaseOutcome, now: Timestamp) -> Option<RoleChange> {
+        let mut zones = lock(&self.zones);
+        let zone = zones.get_mut(zone_id)?;
+        let lease = outcome.lease();
+        zone.token = zone.token.max(lease.token);
+        match (outcome.is_acquired(), zone.role) {
+            (true, ZoneRole::Primary { token }) if token == lease.token => {
+                zone.renewed_at = Some(now);
+                None
+            }
+            (true, _) => {
+                // A lease that lapsed and was acquired again has a new token, and its
+                // standbys have to start over from a full replica
+                zone.role = ZoneRole::Primary { token: lease.token };
+                zone.seq = 0;
+                zone.renewed_at = Some(now);
+                zone.failover = None;
+                Some(RoleChange::Promoted)
+            }
+            (false, ZoneRole::Primary { .. }) => {
+                zone.stand_by();
+                Some(RoleChange::Demoted)
+            }
+            (false, ZoneRole::Standby) => None,
+        }
+    }
+
+    /// Renewing a zone's lease failed; a primary that could not renew it for as long as
+    /// it lasts steps down
+    pub fn renewal_failed(&self, zone_id: &str, now: Timestamp) -> Option<RoleChange> {
+        let mut zones = lock(&self.zones);
+        let zone = zones.get_mut(zone_id)?;
+        let ttl = chrono::Duration::from_std(self.leases.ttl).unwrap_or_else(|_| chrono::Duration::zero());
+        match (zone.role, zone.renewed_at) {
+            (ZoneRole::Primary { .. }, Some(renewed_at)) if now - renewed_at >= ttl => {
+                zone.stand_by();
+                Some(RoleChange::Demoted)
+            }
+            _ => None,
+        }
+    }
+
+    /// Token, number and fullness of the next replica of a zone this instance runs
+    pub fn next_replica(&self, zone_id: &str, full: bool) -> Option<(u64, u64, bool)> {
+        let mut zones = lock(&self.zones);
+        let zone = zones.get_mut(zone_id)?;
+        let ZoneRole::Primary { token } = zone.role else {
+            return None;
+        };
+        zone.seq += 1;
+        let full = full || zone.seq == 1 || zone.seq % self.leases.full_every == 0;
+        Some((token, zone.seq, full))
+    }
+
+    /// Check a replica another instance published
+    pub fn receive(&self, replicated: &ZoneReplicated) -> Received {
+        let ignore = Received { demoted: false, apply: false };
+        let mut zones = lock(&self.zones);
+        let Some(zone) = zones.get_mut(&replicated.zone) else {
+            return ignore;
+        };
+        if replicated.instance == self.leases.owner.instance || replicated.token < zone.token {
+            return ignore;
+        }
+        let mut demoted = false;
+        if replicated.token > zone.token {
+            zone.token = replicated.token;
+            zone.resync = true;
+            zone.failover = None;
+            if let ZoneRole::Primary { .. } = zone.role {
+                zone.stand_by();
+                demoted = true;
+            }
+        } else if let ZoneRole::Primary { .. } = zone.role {
+            return ignore;
+        }
+        let apply = replicated.full || (!zone.resync && replicated.seq == zone.seq + 1);
+        if apply {
+            zone.seq = replicated.seq;
+            zone.resync = false;
+        } else {
+            zone.resync = true;
+        }
+        Received { demoted, apply }
+    }
+
+    /// A replica could not be applied; wait for the next full one
+    pub fn resync(&self, zone_id: &str) {
+        if let Some(zone) = lock(&self.zones).get_mut(zone_id) {
+            zone.resync = true;
+        }
+    }
+
+    /// Record a failover to `to_instance`, or any other instance, until `until`. Returns
+    /// the fencing token of the lease to release when this instance ran the zone and
+    /// stepped down.
+    pub fn fail_over(&self, zone_id: &str, to_instance: Option<String>, until: Timestamp) -> Option<u64> {
+        let mut zones = lock(&self.zones);
+        let zone = zones.get_mut(zone_id)?;
+        let token = match zone.role {
+            ZoneRole::Primary { token } => {
+                zone.stand_by();
+                Some(token)
+            }
+            ZoneRole::Standby => None,
+        };
+        zone.failover = Some(Failover { to_instance, until, stepped_down: token.is_some() });
+        token
+    }
+}
+
+impl ZoneReplication {
+    /// Keep the copy up to date from the replicas of the new primary
+    fn stand_by(&mut self) {
+        self.role = ZoneRole::Standby;
+        self.renewed_at = None;
+        self.resync = true;
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use shared::presence::{InMemoryLeases, Lease};
+
+    fn replication(instance: &str) -> Replication {
+        let leases = ZoneLeases {
+            registry: Arc::new(InMemoryLeases::new(shared::wall_clock())),
+            owner: PresenceOwner::new(instance, None),
+            ttl: Duration::from_secs(5),
+            renew: Duration::from_secs(1),
+            full_every: 3,
+        };
+        Replication::new(leases, ["bog".to_string()])
+    }
+
+    fn now() -> Timestamp {
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap()
+    }
+
+    fn lease(instance: &str, token: u64) -> Lease {
+        Lease {
+            name: lease_name("bog"),
+            instance: instance.to_string(),
+            address: None,
+            token,
+            expires_at: now() + chrono::Duration::seconds(5),
+        }
+    }
+
+    fn replica(instance: &str, token: u64, seq: u64, full: bool) -> ZoneReplicated {
+        let (zone, instance) = ("bog".to_string(), instance.to_string());
+        ZoneReplicated { zone, instance, token, seq, full, state: serde_json::Value::Null }
+    }
+
+    #[test]
+    fn standbys_apply_replicas_in_order_and_ignore_fenced_primaries() {
+        let standby = replication("b");
+        standby.renewed("bog", &LeaseOutcome::HeldBy(lease("a", 1)), now());
+        assert_eq!(standby.role("bog"), Some(ZoneRole::Standby));
+
+        // Deltas wait for a full replica, and for the ones missed since
+        assert!(!standby.receive(&replica("a", 1, 2, false)).apply);
+        assert!(standby.receive(&replica("a", 1, 3, true)).apply);
+        assert!(standby.receive(&replica("a", 1, 4, false)).apply);
+        assert!(!standby.receive(&replica("a", 1, 6, false)).apply);
+        assert!(!standby.receive(&replica("a", 1, 7, false)).apply);
+        assert!(standby.receive(&replica("a", 1, 9, true)).apply);
+
+        // Once "c" took over, the previous primary is not heard anymore
+        assert!(standby.receive(&replica("c", 2, 1, true)).apply);
+        assert!(!standby.receive(&replica("a", 1, 10, false)).apply);
+        assert!(!standby.receive(&replica("a", 1, 12, true)).apply);
+    }
+
+    #[test]
+    fn primaries_step_down_when_fenced_off() {
+        let primary = replication("a");
+        assert_eq!(primary.next_replica("bog", false), None);
+        let acquired = LeaseOutcome::Acquired(lease("a", 4));
+        assert_eq!(primary.renewed("bog", &acquired, now()), Some(RoleChange::Promoted));
+        assert_eq!(primary.renewed("bog", &acquired, now()), None);
+        let fulls: Vec<bool> = (0..4).map(|_| primary.next_replica("bog", false).unwrap().2).collect();
+        assert_eq!(fulls, [true, false, true, false]);
+
+        // Renewals failing for a whole ttl, or a replica with a higher token
+        assert_eq!(primary.renewal_failed("bog", now() + chrono::Duration::seconds(4)), None);
+        let later = now() + chrono::Duration::seconds(5);
+        assert_eq!(primary.renewal_failed("bog", later), Some(RoleChange::Demoted));
+        primary.renewed("bog", &LeaseOutcome::Acquired(lease("a", 5)), now());
+        assert_eq!(primary.receive(&replica("c", 6, 1, true)), Received { demoted: true, apply: true });
+        assert_eq!(primary.role("bog"), Some(ZoneRole::Standby));
+    }
+
+    #[test]
+    fn failovers_leave_the_lease_to_the_requested_standby() {
+        let (primary, target, other) = (replication("a"), replication("b"), replication("c"));
+        primary.renewed("bog", &LeaseOutcome::Acquired(lease("a", 1)), now());
+        let until = now() + chrono::Duration::seconds(5);
+        assert_eq!(primary.fail_over("bog", Some("b".to_string()), until), Some(1));
+        assert_eq!(primary.role("bog"), Some(ZoneRole::Standby));
+        for standby in [&target, &other] {
+            assert_eq!(standby.fail_over("bog", Some("b".to_string()), until), None);
+        }
+        assert!(!primary.may_acquire("bog", now()));
+        assert!(target.may_acquire("bog", now()));
+        assert!(!other.may_acquire("bog", now()));
+        assert!(other.may_acquire("bog", until));
+
+        // Without a target, any instance but the one stepping down
+        primary.renewed("bog", &LeaseOutcome::Acquired(lease("a", 2)), now());
+        primary.fail_over("bog", None, until);
+        other.fail_over("bog", None, until);
+        assert!(!primary.may_acquire("bog", now()));
+        assert!(other.may_acquire("bog", now()));
+    }
+}
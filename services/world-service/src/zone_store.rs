@@ -0,0 +1,34 @@
+//! Zone definitions stored in MongoDB, one document per zone keyed by its id.
+
+use futures::TryStreamExt;
+use mongodb::bson::doc;
+use mongodb::{Collection, Database};
+use world_core::zones::ZoneDefinition;
+use world_core::{WorldCoreError, WorldCoreResult, ZoneSource};
+
+pub const COLLECTION: &str = "zones";
+
+#[derive(Clone)]
+pub struct MongoZoneSource {
+    collection: Collection<ZoneDefinition>,
+}
+
+impl MongoZoneSource {
+    pub fn new(database: &Database) -> Self {
+        Self { collection: database.collection(COLLECTION) }
+    }
+}
+
+#[async_trait::async_trait]
+impl ZoneSource for MongoZoneSource {
+    async fn load(&self, zone_ids: &[String]) -> WorldCoreResult<Vec<ZoneDefinition>> {
+        let storage = |e: mongodb::error::Error| WorldCoreError::Storage(e.to_string());
+        self.collection
+            .find(doc! { "id": { "$in": zone_ids } }, None)
+            .await
+            .map_err(storage)?
+            .try_collect()
+            .await
+            .map_err(storage)
+    }
+}
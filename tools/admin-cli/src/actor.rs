@@ -0,0 +1,286 @@
+//! `admin-cli actor inspect`: stat snapshots and contributions from the actor service.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use serde::Deserialize;
+
+use crate::client::ServiceClient;
+
+/// Arguments of `actor inspect`.
+#[derive(Args, Debug)]
+pub struct InspectArgs {
+    /// Actor to inspect
+    pub actor_id: String,
+
+    /// Also fetch subsystem contributions and caps per dimension (the resolve trace)
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Only show this dimension
+    #[arg(long)]
+    pub dimension: Option<String>,
+
+    /// Resolve now instead of reading the cached snapshot
+    #[arg(long)]
+    pub fresh: bool,
+
+    /// Poll and highlight changing stats until interrupted
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Poll interval in seconds for --watch
+    #[arg(long, default_value_t = 2)]
+    pub interval: u64,
+}
+
+/// Stat snapshot as returned by `GET /actors/:id/snapshot` (API v2).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Snapshot {
+    pub actor_id: String,
+    #[serde(default)]
+    pub primary: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub derived: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub caps_used: BTreeMap<String, CapRange>,
+    pub version: i64,
+    pub processing: Option<ProcessingInfo>,
+    pub created_at: String,
+}
+
+/// Effective caps of a stat, unbounded sides are `null`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CapRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProcessingInfo {
+    pub processing_time_us: Option<u64>,
+    #[serde(default)]
+    pub subsystems_processed: Vec<String>,
+    #[serde(default)]
+    pub cache_hit: bool,
+}
+
+/// Response of `GET /actors/:id/contributions`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Contributions {
+    pub subsystems: Vec<SubsystemContributions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubsystemContributions {
+    pub system_id: String,
+    pub priority: i64,
+    #[serde(default)]
+    pub primary: Vec<Contribution>,
+    #[serde(default)]
+    pub derived: Vec<Contribution>,
+    #[serde(default)]
+    pub caps: Vec<CapContribution>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Contribution {
+    pub dimension: String,
+    pub bucket: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CapContribution {
+    pub dimension: String,
+    pub mode: String,
+    pub layer: String,
+    pub min_value: Option<f64>,
+    pub max_value: Option<f64>,
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Run `actor inspect`.
+pub async fn inspect(client: &ServiceClient, args: InspectArgs) -> Result<()> {
+    if !args.watch {
+        let snapshot = fetch_snapshot(client, &args).await?;
+        let contributions = fetch_contributions(client, &args).await?;
+        print!("{}", render(&snapshot, contributions.as_ref(), None, args.dimension.as_deref()));
+        return Ok(());
+    }
+
+    if args.interval == 0 {
+        bail!("--interval must be at least 1 second");
+    }
+    let mut ticker = tokio::time::interval(Duration::from_secs(args.interval));
+    let mut previous: Option<Snapshot> = None;
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        let snapshot = match fetch_snapshot(client, &args).await {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                // Keep polling through restarts of the actor service
+                eprintln!("{}fetch failed: {:#}{}", RED, e, RESET);
+                continue;
+            }
+        };
+        let contributions = fetch_contributions(client, &args).await.unwrap_or_else(|e| {
+            eprintln!("{}contributions unavailable: {:#}{}", RED, e, RESET);
+            None
+        });
+        println!(
+            "{}{}{}every {}s, Ctrl-C to stop{}",
+            CLEAR_SCREEN,
+            render(&snapshot, contributions.as_ref(), previous.as_ref(), args.dimension.as_deref()),
+            DIM,
+            args.interval,
+            RESET
+        );
+        previous = Some(snapshot);
+    }
+}
+
+async fn fetch_snapshot(client: &ServiceClient, args: &InspectArgs) -> Result<Snapshot> {
+    let snapshot = if args.fresh {
+        let path = format!("/actors/{}/resolve", args.actor_id);
+        client.post_json(&path, &serde_json::json!({ "force": true })).await
+    } else {
+        client.get_json(&format!("/actors/{}/snapshot", args.actor_id)).await
+    };
+    snapshot.with_context(|| format!("fetching snapshot of actor {}", args.actor_id))
+}
+
+async fn fetch_contributions(client: &ServiceClient, args: &InspectArgs) -> Result<Option<Contributions>> {
+    if !args.trace {
+        return Ok(None);
+    }
+    let mut path = format!("/actors/{}/contributions", args.actor_id);
+    if let Some(dimension) = &args.dimension {
+        path.push_str(&format!("?dimension={}", dimension));
+    }
+    let contributions = client
+        .get_json(&path)
+        .await
+        .with_context(|| format!("fetching contributions of actor {}", args.actor_id))?;
+    Ok(Some(contributions))
+}
+
+/// Stats whose value differs between two snapshots, with the old value (`None` for new stats).
+pub fn changed_stats(previous: &Snapshot, current: &Snapshot) -> BTreeMap<String, Option<f64>> {
+    let mut changed = BTreeMap::new();
+    for (stats, old_stats) in [(&current.primary, &previous.primary), (&current.derived, &previous.derived)] {
+        for (name, value) in stats {
+            match old_stats.get(name) {
+                Some(old) if (old - value).abs() < f64::EPSILON => {}
+                old => {
+                    changed.insert(name.clone(), old.copied());
+                }
+            }
+        }
+    }
+    changed
+}
+
+/// Render a snapshot, its contributions and, when watching, what changed since the last poll.
+pub fn render(
+    snapshot: &Snapshot,
+    contributions: Option<&Contributions>,
+    previous: Option<&Snapshot>,
+    dimension: Option<&str>,
+) -> String {
+    let changed = previous.map(|previous| changed_stats(previous, snapshot)).unwrap_or_default();
+    let mut out = String::new();
+
+    out.push_str(&format!("{}Actor {}{}  snapshot v{}  {}\n", BOLD, snapshot.actor_id, RESET, snapshot.version, snapshot.created_at));
+    if let Some(processing) = &snapshot.processing {
+        let timing = processing.processing_time_us.map(|us| format!("{}µs", us)).unwrap_or_else(|| "-".to_string());
+        out.push_str(&format!(
+            "{}resolved in {}, {}, subsystems: {}{}\n",
+            DIM,
+            timing,
+            if processing.cache_hit { "cached" } else { "fresh" },
+            processing.subsystems_processed.join(", "),
+            RESET
+        ));
+    }
+
+    for (title, stats) in [("Primary", &snapshot.primary), ("Derived", &snapshot.derived)] {
+        let stats: Vec<_> = stats
+            .iter()
+            .filter(|(name, _)| dimension.map_or(true, |dimension| dimension == name.as_str()))
+            .collect();
+        if stats.is_empty() {
+            continue;
+        }
+        out.push_str(&format!("\n{}{}{}\n", BOLD, title, RESET));
+        for (name, value) in stats {
+            out.push_str(&stat_line(name, *value, snapshot.caps_used.get(name), changed.get(name)));
+            if let Some(contributions) = contributions {
+                out.push_str(&trace_lines(name, contributions));
+            }
+        }
+    }
+    out
+}
+
+fn stat_line(name: &str, value: f64, caps: Option<&CapRange>, change: Option<&Option<f64>>) -> String {
+    let mut line = format!("  {:<28} {:>12.2}", name, value);
+    if let Some(caps) = caps {
+        line.push_str(&format!("  [{} .. {}]", bound(caps.min), bound(caps.max)));
+        let at_cap = [caps.min, caps.max].into_iter().flatten().any(|cap| (cap - value).abs() < f64::EPSILON);
+        if at_cap {
+            line.push_str(&format!(" {}capped{}", YELLOW, RESET));
+        }
+    }
+    match change {
+        Some(Some(old)) => {
+            let color = if value > *old { GREEN } else { RED };
+            line.push_str(&format!("  {}{:+.2} (was {:.2}){}", color, value - old, old, RESET));
+        }
+        Some(None) => line.push_str(&format!("  {}new{}", GREEN, RESET)),
+        None => {}
+    }
+    line.push('\n');
+    line
+}
+
+fn trace_lines(dimension: &str, contributions: &Contributions) -> String {
+    let mut lines = String::new();
+    for subsystem in &contributions.subsystems {
+        for contribution in subsystem.primary.iter().chain(&subsystem.derived).filter(|c| c.dimension == dimension) {
+            lines.push_str(&format!(
+                "    {}{:<24} {:<9} {:>+12.2}  priority {}{}\n",
+                DIM, subsystem.system_id, contribution.bucket, contribution.value, subsystem.priority, RESET
+            ));
+        }
+        for cap in subsystem.caps.iter().filter(|cap| cap.dimension == dimension) {
+            lines.push_str(&format!(
+                "    {}{:<24} cap {:<8} [{} .. {}]  layer {}{}\n",
+                DIM,
+                subsystem.system_id,
+                cap.mode,
+                bound(cap.min_value),
+                bound(cap.max_value),
+                cap.layer,
+                RESET
+            ));
+        }
+    }
+    lines
+}
+
+fn bound(value: Option<f64>) -> String {
+    value.map(|value| format!("{:.2}", value)).unwrap_or_else(|| "∞".to_string())
+}
@@ -0,0 +1,263 @@
+//! `admin-cli config`: runtime configuration stored in MongoDB.
+//!
+//! Reads and writes go through actor-core's [`MongoDBConfigurationProvider`], the same
+//! provider `ActorCoreBuilder` loads when MongoDB configuration is enabled. Services
+//! only see a change after reloading, see `config reload`.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use actor_core::config::mongodb::MongoDBConfigurationProvider;
+use actor_core::config::{ConfigurationProvider, ConfigurationValue, ConfigurationValueType};
+use anyhow::{bail, Context, Result};
+use clap::{Args, Subcommand};
+use serde_json::Value;
+
+use crate::client::ServiceClient;
+
+/// Provider id and priority `ActorCoreBuilder` registers the MongoDB provider with
+const PROVIDER_ID: &str = "mongodb_provider";
+const PROVIDER_PRIORITY: i64 = 50;
+
+/// `source_provider` recorded on entries created by this tool
+const SOURCE: &str = "admin-cli";
+
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    /// MongoDB configuration file used by actor-core
+    #[arg(long, env = "MONGODB_CONFIG", default_value = "configs/mongodb_config.yaml", global = true)]
+    pub mongodb_config: PathBuf,
+
+    #[command(subcommand)]
+    pub action: ConfigCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Show stored values, all categories when none is given
+    Get {
+        category: Option<String>,
+        key: Option<String>,
+    },
+    /// Change values, showing the diff first
+    Set {
+        /// Category of a single value
+        #[arg(required_unless_present = "file", requires = "key")]
+        category: Option<String>,
+        /// Key of a single value
+        #[arg(requires = "value")]
+        key: Option<String>,
+        /// JSON value, bare words are taken as strings
+        value: Option<String>,
+        /// YAML or JSON file of `category: { key: value }` overrides
+        #[arg(long, conflicts_with = "category")]
+        file: Option<PathBuf>,
+        /// Show the diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Apply without asking for confirmation
+        #[arg(long, short)]
+        yes: bool,
+        #[command(flatten)]
+        reload: ReloadTargets,
+    },
+    /// Compare a YAML or JSON file of overrides with the stored values
+    Diff { file: PathBuf },
+    /// Make services re-read their configuration
+    Reload {
+        #[command(flatten)]
+        targets: ReloadTargets,
+        /// List the services without calling them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ReloadTargets {
+    /// Base URLs of services to reload after applying
+    #[arg(long = "reload", env = "CONFIG_RELOAD_TARGETS", value_delimiter = ',')]
+    pub services: Vec<String>,
+}
+
+/// Change to one stored value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub category: String,
+    pub key: String,
+    pub old: Option<Value>,
+    pub new: Value,
+}
+
+/// Run a `config` subcommand.
+pub async fn run(args: ConfigArgs, token: Option<String>) -> Result<()> {
+    match args.action {
+        ConfigCommands::Get { category, key } => {
+            let provider = connect(&args.mongodb_config).await?;
+            let values = stored_values(&provider, category.as_deref()).await?;
+            let values: BTreeMap<_, _> = values
+                .into_iter()
+                .filter(|((_, stored_key), _)| key.as_ref().map_or(true, |key| key == stored_key))
+                .collect();
+            if values.is_empty() {
+                bail!("No configuration found");
+            }
+            for ((category, key), value) in values {
+                println!("{}.{} = {}", category, key, value.value);
+            }
+            Ok(())
+        }
+        ConfigCommands::Diff { file } => {
+            let provider = connect(&args.mongodb_config).await?;
+            let changes = changes(&provider, read_overrides(&file)?).await?;
+            print_changes(&changes);
+            Ok(())
+        }
+        ConfigCommands::Set { category, key, value, file, dry_run, yes, reload } => {
+            let overrides = match (file, category, key, value) {
+                (Some(file), ..) => read_overrides(&file)?,
+                (None, Some(category), Some(key), Some(value)) => {
+                    BTreeMap::from([((category, key), parse_value(&value))])
+                }
+                _ => bail!("Give a category, key and value, or --file"),
+            };
+            let provider = connect(&args.mongodb_config).await?;
+            let changes = changes(&provider, overrides).await?;
+            print_changes(&changes);
+            if changes.is_empty() || dry_run {
+                return Ok(());
+            }
+            if !yes && !dialoguer::Confirm::new().with_prompt("Apply these changes?").interact()? {
+                bail!("Aborted");
+            }
+            apply(&provider, &changes).await?;
+            println!("Applied {} change(s)", changes.len());
+            reload_services(&reload.services, token, false).await
+        }
+        ConfigCommands::Reload { targets, dry_run } => {
+            if targets.services.is_empty() {
+                bail!("No services to reload, pass --reload or set CONFIG_RELOAD_TARGETS");
+            }
+            reload_services(&targets.services, token, dry_run).await
+        }
+    }
+}
+
+async fn connect(mongodb_config: &std::path::Path) -> Result<MongoDBConfigurationProvider> {
+    let path = mongodb_config.to_str().context("MongoDB config path is not UTF-8")?;
+    let config = MongoDBConfigurationProvider::load_mongodb_config(path)?;
+    let provider = MongoDBConfigurationProvider::new(PROVIDER_ID.to_string(), PROVIDER_PRIORITY, config)
+        .await
+        .context("connecting to the configuration database")?;
+    Ok(provider)
+}
+
+async fn stored_values(
+    provider: &MongoDBConfigurationProvider,
+    category: Option<&str>,
+) -> Result<BTreeMap<(String, String), ConfigurationValue>> {
+    let categories = match category {
+        Some(category) => vec![category.to_string()],
+        None => provider.get_supported_categories(),
+    };
+    let mut values = BTreeMap::new();
+    for category in categories {
+        for (key, value) in provider.get_category_config(&category).await? {
+            values.insert((category.clone(), key), value);
+        }
+    }
+    Ok(values)
+}
+
+/// Read `category: { key: value }` overrides from a YAML or JSON file.
+fn read_overrides(path: &std::path::Path) -> Result<BTreeMap<(String, String), Value>> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    // JSON is valid YAML, one parser covers both
+    let file: BTreeMap<String, BTreeMap<String, Value>> =
+        serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?;
+    Ok(file
+        .into_iter()
+        .flat_map(|(category, values)| values.into_iter().map(move |(key, value)| ((category.clone(), key), value)))
+        .collect())
+}
+
+/// Parse a command line value as JSON, falling back to a plain string.
+pub fn parse_value(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+async fn changes(
+    provider: &MongoDBConfigurationProvider,
+    overrides: BTreeMap<(String, String), Value>,
+) -> Result<Vec<Change>> {
+    let mut changes = Vec::new();
+    for ((category, key), new) in overrides {
+        let old = provider.get_config_value(&category, &key).await?.map(|value| value.value);
+        if old.as_ref() != Some(&new) {
+            changes.push(Change { category, key, old, new });
+        }
+    }
+    Ok(changes)
+}
+
+fn print_changes(changes: &[Change]) {
+    if changes.is_empty() {
+        println!("No changes");
+        return;
+    }
+    for change in changes {
+        match &change.old {
+            Some(old) => println!("~ {}.{}: {} -> {}", change.category, change.key, old, change.new),
+            None => println!("+ {}.{} = {}", change.category, change.key, change.new),
+        }
+    }
+}
+
+async fn apply(provider: &MongoDBConfigurationProvider, changes: &[Change]) -> Result<()> {
+    for change in changes {
+        let value = match provider.get_config_value(&change.category, &change.key).await? {
+            Some(mut stored) => {
+                stored.value = change.new.clone();
+                stored.timestamp = chrono::Utc::now();
+                stored
+            }
+            None => ConfigurationValue::new(change.new.clone(), value_type(&change.new), SOURCE.to_string(), PROVIDER_PRIORITY),
+        };
+        provider.save_to_database(&change.category, &change.key, &value).await?;
+    }
+    Ok(())
+}
+
+/// Value type recorded for a new entry.
+pub fn value_type(value: &Value) -> ConfigurationValueType {
+    match value {
+        Value::Bool(_) => ConfigurationValueType::Boolean,
+        Value::Number(number) if number.is_f64() => ConfigurationValueType::Float,
+        Value::Number(_) => ConfigurationValueType::Integer,
+        Value::Array(_) => ConfigurationValueType::Array,
+        Value::Object(_) => ConfigurationValueType::Object,
+        Value::String(_) | Value::Null => ConfigurationValueType::String,
+    }
+}
+
+async fn reload_services(services: &[String], token: Option<String>, dry_run: bool) -> Result<()> {
+    let mut failed = 0;
+    for service in services {
+        if dry_run {
+            println!("would reload {}", service);
+            continue;
+        }
+        let client = ServiceClient::new(service, token.clone())?;
+        match client.post_json::<Value, _>("/config/reload", &serde_json::json!({})).await {
+            Ok(response) => println!("reloaded {} ({} providers)", service, response["providers"]),
+            Err(e) => {
+                eprintln!("reload of {} failed: {:#}", service, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{} of {} services failed to reload", failed, services.len());
+    }
+    Ok(())
+}
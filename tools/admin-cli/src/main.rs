@@ -2,10 +2,17 @@
 //!
 //! Command-line tool for administering the Chaos World MMORPG backend.
 
+mod actor;
+mod client;
+mod config;
+mod world;
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing::{info, error};
 
+use crate::client::ServiceClient;
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -15,6 +22,13 @@ struct Args {
     /// Log level
     #[arg(short, long, default_value = "info")]
     log_level: String,
+
+    /// Admin token sent as a bearer token to services
+    #[arg(long, env = "ADMIN_TOKEN", global = true, hide_env_values = true)]
+    token: Option<String>,
+
+    #[command(flatten)]
+    bus: world::BusArgs,
 }
 
 #[derive(Subcommand, Debug)]
@@ -24,11 +38,20 @@ enum Commands {
         #[command(subcommand)]
         action: PlayerCommands,
     },
+    /// Actor debugging
+    Actor {
+        #[command(subcommand)]
+        action: ActorCommands,
+    },
+    /// Runtime configuration
+    Config(config::ConfigArgs),
     /// World management
     World {
         #[command(subcommand)]
         action: WorldCommands,
     },
+    /// Message every connected player
+    Broadcast(world::BroadcastArgs),
     /// System status
     Status,
     /// Database operations
@@ -50,6 +73,19 @@ enum PlayerCommands {
     Unban { player_id: String },
 }
 
+#[derive(Subcommand, Debug)]
+enum ActorCommands {
+    /// Show an actor's resolved stats, caps and contributions
+    Inspect {
+        /// Actor service base URL
+        #[arg(long, env = "ACTOR_SERVICE_URL", default_value = "http://localhost:8080")]
+        url: String,
+
+        #[command(flatten)]
+        args: actor::InspectArgs,
+    },
+}
+
 #[derive(Subcommand, Debug)]
 enum WorldCommands {
     /// List zones
@@ -58,6 +94,13 @@ enum WorldCommands {
     Zone { zone_id: String },
     /// Restart world
     Restart,
+    /// Maintenance mode
+    Maintenance {
+        #[command(subcommand)]
+        action: world::MaintenanceCommands,
+    },
+    /// Move a zone to a standby world-service instance
+    Failover(world::FailoverArgs),
 }
 
 #[derive(Subcommand, Debug)]
@@ -102,6 +145,17 @@ async fn main() -> Result<()> {
                 }
             }
         }
+        Commands::Actor { action } => {
+            match action {
+                ActorCommands::Inspect { url, args: inspect } => {
+                    let client = ServiceClient::new(&url, args.token)?;
+                    actor::inspect(&client, inspect).await?;
+                }
+            }
+        }
+        Commands::Config(config) => {
+            config::run(config, args.token).await?;
+        }
         Commands::World { action } => {
             match action {
                 WorldCommands::Zones => {
@@ -116,8 +170,17 @@ async fn main() -> Result<()> {
                     info!("Restarting world...");
                     // TODO: Implement world restart
                 }
+                WorldCommands::Maintenance { action } => {
+                    world::maintenance(&args.bus, action).await?;
+                }
+                WorldCommands::Failover(failover) => {
+                    world::failover(&args.bus, failover).await?;
+                }
             }
         }
+        Commands::Broadcast(broadcast) => {
+            world::broadcast(&args.bus, broadcast).await?;
+        }
         Commands::Status => {
             info!("Checking system status...");
             // TODO: Implement status checking
@@ -0,0 +1,198 @@
+//! `admin-cli world maintenance`, `admin-cli world failover` and `admin-cli broadcast`,
+//! published on the event bus.
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use clap::{Args, Subcommand, ValueEnum};
+use shared::events::topics::{ZoneFailoverRequested, ZONE_FAILOVER};
+use shared::events::{EventBusExt, NatsEventBus};
+use shared::maintenance::{
+    self, Broadcast, BroadcastSeverity, MaintenanceState, BROADCAST_TOPIC, MAINTENANCE_TOPIC,
+};
+
+/// Event source recorded on published events
+const SOURCE: &str = "admin-cli";
+
+/// Event bus the services listen on
+#[derive(Args, Debug)]
+pub struct BusArgs {
+    /// NATS server URL
+    #[arg(long, env = "NATS_URL", default_value = "nats://localhost:4222", global = true)]
+    pub nats_url: String,
+
+    /// Subject prefix shared with the services
+    #[arg(long, env = "NATS_SUBJECT_PREFIX", global = true)]
+    pub subject_prefix: Option<String>,
+
+    /// Name recorded as the author of the change
+    #[arg(long = "as", env = "USER", default_value = SOURCE, global = true)]
+    pub operator: String,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MaintenanceCommands {
+    /// Refuse new logins and game connections from non-admins
+    On {
+        /// Notice shown to players who are turned away
+        #[arg(long)]
+        message: Option<String>,
+        /// Announce a shutdown this many minutes from now and count down to it
+        #[arg(long)]
+        shutdown_in: Option<u64>,
+    },
+    /// Let everyone log in again
+    Off,
+}
+
+#[derive(Args, Debug)]
+pub struct BroadcastArgs {
+    /// Message shown to every connected player
+    pub text: String,
+
+    #[arg(long, value_enum, default_value_t = Severity::Info)]
+    pub severity: Severity,
+}
+
+#[derive(Args, Debug)]
+pub struct FailoverArgs {
+    /// Zone to move off the instance running it
+    pub zone: String,
+
+    /// Standby instance to take the zone over, the first standby to notice by default
+    #[arg(long)]
+    pub to: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl From<Severity> for BroadcastSeverity {
+    fn from(severity: Severity) -> Self {
+        match severity {
+            Severity::Info => BroadcastSeverity::Info,
+            Severity::Warning => BroadcastSeverity::Warning,
+            Severity::Critical => BroadcastSeverity::Critical,
+        }
+    }
+}
+
+async fn connect(bus: &BusArgs) -> Result<NatsEventBus> {
+    let mut nats = NatsEventBus::connect(&bus.nats_url)
+        .await
+        .with_context(|| format!("connecting to {}", bus.nats_url))?;
+    if let Some(prefix) = &bus.subject_prefix {
+        nats = nats.with_subject_prefix(prefix)?;
+    }
+    Ok(nats)
+}
+
+/// Run `world maintenance on|off`.
+pub async fn maintenance(bus: &BusArgs, action: MaintenanceCommands) -> Result<()> {
+    let nats = connect(bus).await?;
+    match action {
+        MaintenanceCommands::On { message, shutdown_in } => {
+            if shutdown_in == Some(0) {
+                bail!("--shutdown-in must be at least 1 minute");
+            }
+            let shutdown_in = shutdown_in.map(|minutes| Duration::from_secs(minutes * 60));
+            let shutdown_at = shutdown_in.map(|remaining| Utc::now() + chrono::Duration::seconds(remaining.as_secs() as i64));
+            let state = MaintenanceState::on(&bus.operator, message, shutdown_at);
+            nats.publish_from(SOURCE, &MAINTENANCE_TOPIC, &state).await?;
+            nats.flush().await?;
+            println!("Maintenance on: {}", state.notice());
+
+            if let Some(remaining) = shutdown_in {
+                countdown(&nats, &bus.operator, remaining).await?;
+            }
+        }
+        MaintenanceCommands::Off => {
+            nats.publish_from(SOURCE, &MAINTENANCE_TOPIC, &MaintenanceState::off(&bus.operator)).await?;
+            nats.flush().await?;
+            println!("Maintenance off");
+        }
+    }
+    Ok(())
+}
+
+/// Broadcast shutdown warnings until the shutdown, Ctrl-C stops the countdown
+async fn countdown(nats: &NatsEventBus, operator: &str, remaining: Duration) -> Result<()> {
+    let shutdown = tokio::time::Instant::now() + remaining;
+    println!("Counting down to the shutdown, Ctrl-C stops the announcements");
+
+    let mut warnings = maintenance::shutdown_warnings(remaining);
+    if warnings.first() != Some(&remaining) {
+        // Announce right away, then at every warning mark
+        warnings.insert(0, remaining);
+    }
+    for warning in warnings {
+        tokio::select! {
+            _ = tokio::time::sleep_until(shutdown - warning) => {}
+            _ = tokio::signal::ctrl_c() => {
+                println!("Countdown stopped, maintenance stays on");
+                return Ok(());
+            }
+        }
+        let severity = if warning <= Duration::from_secs(60) {
+            BroadcastSeverity::Critical
+        } else {
+            BroadcastSeverity::Warning
+        };
+        let text = maintenance::shutdown_notice(warning);
+        nats.publish_from(SOURCE, &BROADCAST_TOPIC, &Broadcast::new(&text, severity, operator)).await?;
+        nats.flush().await?;
+        println!("{}", text);
+    }
+
+    tokio::select! {
+        _ = tokio::time::sleep_until(shutdown) => {}
+        _ = tokio::signal::ctrl_c() => {
+            println!("Countdown stopped, maintenance stays on");
+            return Ok(());
+        }
+    }
+    let text = "The server is shutting down for maintenance";
+    nats.publish_from(SOURCE, &BROADCAST_TOPIC, &Broadcast::new(text, BroadcastSeverity::Critical, operator)).await?;
+    nats.flush().await?;
+    println!("{}", text);
+    Ok(())
+}
+
+/// Run `world failover <zone>`.
+pub async fn failover(bus: &BusArgs, args: FailoverArgs) -> Result<()> {
+    if args.zone.trim().is_empty() {
+        bail!("Zone id is empty");
+    }
+    let nats = connect(bus).await?;
+    let request = ZoneFailoverRequested {
+        zone: args.zone,
+        to_instance: args.to,
+        operator: bus.operator.clone(),
+        requested_at: Utc::now(),
+    };
+    nats.publish_message(SOURCE, &ZONE_FAILOVER, &request).await?;
+    nats.flush().await?;
+    match &request.to_instance {
+        Some(instance) => println!("Failover of {} to {} requested", request.zone, instance),
+        None => println!("Failover of {} requested", request.zone),
+    }
+    Ok(())
+}
+
+/// Run `broadcast <text>`.
+pub async fn broadcast(bus: &BusArgs, args: BroadcastArgs) -> Result<()> {
+    if args.text.trim().is_empty() {
+        bail!("Broadcast text is empty");
+    }
+    let nats = connect(bus).await?;
+    let message = Broadcast::new(args.text, args.severity.into(), &bus.operator);
+    nats.publish_from(SOURCE, &BROADCAST_TOPIC, &message).await?;
+    nats.flush().await?;
+    println!("Broadcast sent");
+    Ok(())
+}
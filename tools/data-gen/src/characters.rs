@@ -0,0 +1,372 @@
+//! `data-gen characters`: realistic test characters.
+//!
+//! Each character is an actor-core [`Actor`] with a race, a job stored in its data,
+//! a level drawn from the profile's level distribution and element-core
+//! [`ElementalParams`] whose mastery follows the level: a primary element close to
+//! the character's progress, a few weaker secondary elements.
+//!
+//! Results are written as one JSON file per character, or into MongoDB with actors
+//! and elemental params in separate collections. A `--seed` makes runs reproducible.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use actor_core::types::Actor;
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use element_core::{ElementMasteryLevel, ElementalParams};
+use mongodb::bson::{self, doc};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::Normal;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, OutputArgs};
+
+#[derive(Args, Debug)]
+pub struct CharacterArgs {
+    /// YAML profile with races, jobs, level and mastery distributions
+    #[arg(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(long, default_value = "actors")]
+    pub actors_collection: String,
+
+    #[arg(long, default_value = "actor_elements")]
+    pub elements_collection: String,
+}
+
+/// What generated characters look like
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CharacterProfile {
+    pub races: Vec<WeightedChoice>,
+    pub jobs: Vec<JobChoice>,
+    pub levels: LevelDistribution,
+    /// Elements a character can master
+    pub elements: Vec<String>,
+    pub mastery: MasteryProfile,
+}
+
+impl Default for CharacterProfile {
+    fn default() -> Self {
+        let weighted = |id: &str, weight: f64| WeightedChoice { id: id.to_string(), weight };
+        let job = |id: &str, weight: f64, races: &[&str]| JobChoice {
+            id: id.to_string(),
+            weight,
+            races: races.iter().map(|race| race.to_string()).collect(),
+        };
+        Self {
+            races: vec![
+                weighted("human", 5.0),
+                weighted("elf", 2.0),
+                weighted("dwarf", 1.5),
+                weighted("demon", 1.0),
+                weighted("spirit", 0.5),
+            ],
+            jobs: vec![
+                job("warrior", 3.0, &[]),
+                job("mage", 3.0, &[]),
+                job("archer", 2.0, &["human", "elf"]),
+                job("smith", 1.0, &["human", "dwarf"]),
+                job("cultivator", 2.0, &["human", "spirit"]),
+                job("warlock", 1.5, &["demon"]),
+            ],
+            levels: LevelDistribution::Normal { mean: 25.0, std_dev: 15.0, min: 1, max: 100 },
+            elements: ["fire", "water", "earth", "wood", "metal", "ice", "lightning"]
+                .iter()
+                .map(|element| element.to_string())
+                .collect(),
+            mastery: MasteryProfile::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedChoice {
+    pub id: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobChoice {
+    pub id: String,
+    pub weight: f64,
+    /// Races that can take the job, any race when empty
+    #[serde(default)]
+    pub races: Vec<String>,
+}
+
+/// How character levels are spread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LevelDistribution {
+    Uniform { min: i64, max: i64 },
+    /// Bell curve clamped to `min..=max`
+    Normal { mean: f64, std_dev: f64, min: i64, max: i64 },
+    /// Level brackets picked by weight, uniform within a bracket
+    Brackets { brackets: Vec<LevelBracket> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LevelBracket {
+    pub min: i64,
+    pub max: i64,
+    pub weight: f64,
+}
+
+impl LevelDistribution {
+    fn max_level(&self) -> i64 {
+        match self {
+            LevelDistribution::Uniform { max, .. } | LevelDistribution::Normal { max, .. } => *max,
+            LevelDistribution::Brackets { brackets } => brackets.iter().map(|bracket| bracket.max).max().unwrap_or(1),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self {
+            LevelDistribution::Uniform { min, max } => check_range(*min, *max),
+            LevelDistribution::Normal { std_dev, min, max, .. } => {
+                if !std_dev.is_finite() || *std_dev <= 0.0 {
+                    bail!("levels.std_dev must be positive");
+                }
+                check_range(*min, *max)
+            }
+            LevelDistribution::Brackets { brackets } => {
+                if brackets.is_empty() {
+                    bail!("levels.brackets is empty");
+                }
+                brackets.iter().try_for_each(|bracket| check_range(bracket.min, bracket.max))
+            }
+        }
+    }
+}
+
+fn check_range(min: i64, max: i64) -> Result<()> {
+    if min < 1 || max < min {
+        bail!("invalid level range {}..={}", min, max);
+    }
+    Ok(())
+}
+
+/// How far characters have mastered their elements
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MasteryProfile {
+    /// Primary element experience at the lowest level, as a power of ten
+    pub min_log_experience: f64,
+    /// Primary element experience at the highest level, as a power of ten
+    pub max_log_experience: f64,
+    /// Spread around the level's expected experience, in powers of ten
+    pub spread: f64,
+    /// Most secondary elements a character trains
+    pub max_secondary_elements: usize,
+    /// Secondary experience relative to the primary element
+    pub secondary_ratio: (f64, f64),
+    /// Qi per mastery tier
+    pub qi_per_tier: f64,
+}
+
+impl Default for MasteryProfile {
+    fn default() -> Self {
+        Self {
+            min_log_experience: 2.0,
+            max_log_experience: 7.0,
+            spread: 0.4,
+            max_secondary_elements: 2,
+            secondary_ratio: (0.05, 0.4),
+            qi_per_tier: 100.0,
+        }
+    }
+}
+
+impl CharacterProfile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let profile: Self = serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?;
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.races.is_empty() || self.jobs.is_empty() || self.elements.is_empty() {
+            bail!("profile needs at least one race, job and element");
+        }
+        for race in &self.races {
+            if !self.jobs.iter().any(|job| job.races.is_empty() || job.races.contains(&race.id)) {
+                bail!("no job is open to race {}", race.id);
+            }
+        }
+        let (low, high) = self.mastery.secondary_ratio;
+        if !(0.0..=1.0).contains(&low) || !(low..=1.0).contains(&high) {
+            bail!("mastery.secondary_ratio must be within 0..=1 and ordered");
+        }
+        self.levels.validate()
+    }
+}
+
+/// Generated character
+#[derive(Debug, Clone, Serialize)]
+pub struct Character {
+    pub actor: Actor,
+    pub job: String,
+    pub elemental: ElementalParams,
+}
+
+/// Draws characters from a profile
+pub struct CharacterGenerator {
+    profile: CharacterProfile,
+    rng: ChaCha8Rng,
+    races: WeightedIndex<f64>,
+    levels: Option<Normal<f64>>,
+    brackets: Option<WeightedIndex<f64>>,
+}
+
+impl CharacterGenerator {
+    pub fn new(profile: CharacterProfile, seed: Option<u64>) -> Result<Self> {
+        profile.validate()?;
+        let races = WeightedIndex::new(profile.races.iter().map(|race| race.weight)).context("race weights")?;
+        let levels = match &profile.levels {
+            LevelDistribution::Normal { mean, std_dev, .. } => Some(Normal::new(*mean, *std_dev)?),
+            _ => None,
+        };
+        let brackets = match &profile.levels {
+            LevelDistribution::Brackets { brackets } => {
+                Some(WeightedIndex::new(brackets.iter().map(|bracket| bracket.weight)).context("bracket weights")?)
+            }
+            _ => None,
+        };
+        let rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        Ok(Self { profile, rng, races, levels, brackets })
+    }
+
+    pub fn generate(&mut self) -> Result<Character> {
+        let race = self.profile.races[self.races.sample(&mut self.rng)].id.clone();
+        let job = self.job_for(&race)?;
+        let level = self.level();
+
+        let id = format!("{:032x}", self.rng.gen::<u128>());
+        let mut actor = Actor::new(id, race);
+        actor.name = self.name();
+        actor.level = level;
+        actor.data.insert("job".to_string(), serde_json::Value::String(job.clone()));
+
+        let elemental = self.elemental(level);
+        actor.data.insert("primary_element".to_string(), serde_json::Value::String(elemental.primary_element.clone()));
+        Ok(Character { actor, job, elemental })
+    }
+
+    fn job_for(&mut self, race: &str) -> Result<String> {
+        let open: Vec<&JobChoice> =
+            self.profile.jobs.iter().filter(|job| job.races.is_empty() || job.races.iter().any(|r| r == race)).collect();
+        let index = WeightedIndex::new(open.iter().map(|job| job.weight)).context("job weights")?;
+        Ok(open[index.sample(&mut self.rng)].id.clone())
+    }
+
+    fn level(&mut self) -> i64 {
+        match &self.profile.levels {
+            LevelDistribution::Uniform { min, max } => self.rng.gen_range(*min..=*max),
+            LevelDistribution::Normal { min, max, .. } => {
+                let normal = self.levels.expect("normal distribution is built with the generator");
+                (normal.sample(&mut self.rng).round() as i64).clamp(*min, *max)
+            }
+            LevelDistribution::Brackets { brackets } => {
+                let index = self.brackets.as_ref().expect("bracket weights are built with the generator");
+                let bracket = &brackets[index.sample(&mut self.rng)];
+                self.rng.gen_range(bracket.min..=bracket.max)
+            }
+        }
+    }
+
+    /// Mastery grows with level: the primary element's experience is log-linear in
+    /// the character's progress, secondary elements get a fraction of it.
+    fn elemental(&mut self, level: i64) -> ElementalParams {
+        let mastery = self.profile.mastery.clone();
+        let progress = level as f64 / self.profile.levels.max_level().max(1) as f64;
+        let expected = mastery.min_log_experience + (mastery.max_log_experience - mastery.min_log_experience) * progress;
+        let log_experience = expected + self.rng.gen_range(-mastery.spread..=mastery.spread);
+        let primary_experience = 10f64.powf(log_experience.max(0.0)).round();
+
+        let mut elements = self.profile.elements.clone();
+        elements.shuffle(&mut self.rng);
+        let secondary_count = self.rng.gen_range(0..=mastery.max_secondary_elements.min(elements.len() - 1));
+        let primary = elements[0].clone();
+
+        let mut experience = HashMap::from([(primary.clone(), primary_experience)]);
+        for element in &elements[1..=secondary_count] {
+            let ratio = self.rng.gen_range(mastery.secondary_ratio.0..=mastery.secondary_ratio.1);
+            experience.insert(element.clone(), (primary_experience * ratio).round());
+        }
+
+        let mut mastery_levels = HashMap::new();
+        let mut qi_amounts = HashMap::new();
+        for (element, exp) in &experience {
+            let tier = ElementMasteryLevel::from_experience(*exp as i64) as usize as f64 + 1.0;
+            mastery_levels.insert(element.clone(), tier);
+            qi_amounts.insert(element.clone(), (tier * mastery.qi_per_tier * self.rng.gen_range(0.5..=1.0)).round());
+        }
+
+        ElementalParams {
+            primary_element: primary,
+            initial_mastery_levels: mastery_levels,
+            initial_experience: experience,
+            initial_qi_amounts: qi_amounts,
+            elemental_preferences: elements[..=secondary_count].to_vec(),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        const START: [&str; 12] = ["Lin", "Mei", "Tian", "Xu", "Ka", "Ro", "Ael", "Thor", "Vy", "Sa", "Dra", "Hu"];
+        const MIDDLE: [&str; 8] = ["", "a", "en", "ri", "o", "ya", "an", "el"];
+        const END: [&str; 10] = ["n", "ra", "th", "wen", "long", "mir", "ko", "dor", "xi", "s"];
+        let mut name = String::new();
+        name.push_str(START.choose(&mut self.rng).copied().unwrap_or_default());
+        name.push_str(MIDDLE.choose(&mut self.rng).copied().unwrap_or_default());
+        name.push_str(END.choose(&mut self.rng).copied().unwrap_or_default());
+        name
+    }
+}
+
+/// Run `characters <count>`.
+pub async fn run(count: usize, args: CharacterArgs) -> Result<()> {
+    let profile = match &args.profile {
+        Some(path) => CharacterProfile::load(path)?,
+        None => CharacterProfile::default(),
+    };
+    let mut generator = CharacterGenerator::new(profile, args.seed)?;
+    let characters = (0..count).map(|_| generator.generate()).collect::<Result<Vec<_>>>()?;
+
+    match args.output.database().await? {
+        Some(database) => {
+            let mut actors = Vec::with_capacity(characters.len());
+            let mut elements = Vec::with_capacity(characters.len());
+            for character in &characters {
+                let mut actor = bson::to_document(&character.actor)?;
+                actor.insert("_id", &character.actor.id);
+                actors.push(actor);
+
+                let mut elemental = bson::to_document(&character.elemental)?;
+                elemental.extend(doc! { "_id": &character.actor.id, "actor_id": &character.actor.id });
+                elements.push(elemental);
+            }
+            output::insert_all(&database, &args.actors_collection, actors, args.output.reset).await?;
+            output::insert_all(&database, &args.elements_collection, elements, args.output.reset).await?;
+        }
+        None => {
+            let files = characters.iter().map(|character| (character.actor.id.as_str(), character));
+            output::write_json(&args.output.out_dir("characters"), files, args.output.reset)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,418 @@
+//! `data-gen items`: item seed data with rolled affixes, sockets and set pieces.
+//!
+//! item-core does not ship its affix generator or rarity tables yet, so the tables
+//! live in an item profile (`configs/items.yaml` is the built-in default) and are
+//! rolled here the way loot drops roll them: rarity by weight, base by weight, then
+//! affixes by weight without repeats, capped per prefix/suffix, scaled by item level.
+//!
+//! Every run prints a distribution report (rarity histogram against the table
+//! weights, affix frequency, sockets, set pieces) to sanity-check the economy.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use clap::Args;
+use mongodb::bson;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, OutputArgs};
+
+/// Built-in item tables
+const DEFAULT_PROFILE: &str = include_str!("../configs/items.yaml");
+
+#[derive(Args, Debug)]
+pub struct ItemArgs {
+    /// YAML item tables, `configs/items.yaml` when omitted
+    #[arg(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Only print the distribution report
+    #[arg(long)]
+    pub report_only: bool,
+
+    /// Also write the distribution report as JSON
+    #[arg(long)]
+    pub report: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(long, default_value = "items")]
+    pub collection: String,
+}
+
+/// Item tables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemProfile {
+    pub item_levels: (u32, u32),
+    pub max_prefixes: usize,
+    pub max_suffixes: usize,
+    pub rarities: Vec<Rarity>,
+    pub bases: Vec<ItemBase>,
+    pub affixes: Vec<Affix>,
+    #[serde(default)]
+    pub sets: Vec<ItemSet>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rarity {
+    pub id: String,
+    pub weight: f64,
+    /// Affix count range
+    pub affixes: (usize, usize),
+    /// Socket count range, capped by the base
+    pub sockets: (u8, u8),
+    /// Chance that a base belonging to a set drops as the set piece
+    #[serde(default)]
+    pub set_chance: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemBase {
+    pub id: String,
+    pub name: String,
+    pub slot: String,
+    pub weight: f64,
+    #[serde(default)]
+    pub max_sockets: u8,
+    #[serde(default)]
+    pub stats: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Affix {
+    pub id: String,
+    pub name: String,
+    pub kind: AffixKind,
+    pub stat: String,
+    pub min: f64,
+    pub max: f64,
+    pub weight: f64,
+    /// Slots the affix rolls on, every slot when empty
+    #[serde(default)]
+    pub slots: Vec<String>,
+    #[serde(default)]
+    pub min_item_level: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSet {
+    pub id: String,
+    pub name: String,
+    /// Base ids making up the set
+    pub pieces: Vec<String>,
+}
+
+impl ItemProfile {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let profile: Self = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+                serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?
+            }
+            None => serde_yaml::from_str(DEFAULT_PROFILE).context("parsing built-in item profile")?,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let (min_level, max_level) = self.item_levels;
+        if min_level == 0 || max_level < min_level {
+            bail!("invalid item level range {}..={}", min_level, max_level);
+        }
+        if self.rarities.is_empty() || self.bases.is_empty() {
+            bail!("item profile needs at least one rarity and base");
+        }
+        for rarity in &self.rarities {
+            if rarity.affixes.1 < rarity.affixes.0 || rarity.sockets.1 < rarity.sockets.0 {
+                bail!("rarity {} has an inverted range", rarity.id);
+            }
+            if !(0.0..=1.0).contains(&rarity.set_chance) {
+                bail!("rarity {} set_chance must be within 0..=1", rarity.id);
+            }
+        }
+        for affix in &self.affixes {
+            if affix.max < affix.min {
+                bail!("affix {} has max below min", affix.id);
+            }
+        }
+        let bases: HashSet<&str> = self.bases.iter().map(|base| base.id.as_str()).collect();
+        for set in &self.sets {
+            if let Some(piece) = set.pieces.iter().find(|piece| !bases.contains(piece.as_str())) {
+                bail!("set {} uses unknown base {}", set.id, piece);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Generated item document
+#[derive(Debug, Clone, Serialize)]
+pub struct Item {
+    pub id: String,
+    pub base: String,
+    pub name: String,
+    pub slot: String,
+    pub rarity: String,
+    pub item_level: u32,
+    pub base_stats: HashMap<String, f64>,
+    pub affixes: Vec<RolledAffix>,
+    pub sockets: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RolledAffix {
+    pub id: String,
+    pub kind: AffixKind,
+    pub stat: String,
+    pub value: f64,
+}
+
+/// Rolls items from a profile
+pub struct ItemGenerator {
+    profile: ItemProfile,
+    rng: ChaCha8Rng,
+    rarities: WeightedIndex<f64>,
+    bases: WeightedIndex<f64>,
+}
+
+impl ItemGenerator {
+    pub fn new(profile: ItemProfile, seed: Option<u64>) -> Result<Self> {
+        let rarities = WeightedIndex::new(profile.rarities.iter().map(|rarity| rarity.weight)).context("rarity weights")?;
+        let bases = WeightedIndex::new(profile.bases.iter().map(|base| base.weight)).context("base weights")?;
+        let rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        Ok(Self { profile, rng, rarities, bases })
+    }
+
+    pub fn generate(&mut self) -> Item {
+        let (min_level, max_level) = self.profile.item_levels;
+        let item_level = self.rng.gen_range(min_level..=max_level);
+        let rarity = self.profile.rarities[self.rarities.sample(&mut self.rng)].clone();
+        let base = self.profile.bases[self.bases.sample(&mut self.rng)].clone();
+
+        let affixes = self.roll_affixes(&rarity, &base, item_level);
+        let sockets = self.rng.gen_range(rarity.sockets.0..=rarity.sockets.1).min(base.max_sockets);
+        let set = self
+            .profile
+            .sets
+            .iter()
+            .find(|set| set.pieces.contains(&base.id))
+            .filter(|_| rarity.set_chance > 0.0 && self.rng.gen_bool(rarity.set_chance))
+            .cloned();
+
+        let name = match &set {
+            Some(set) => format!("{} {}", set.name, base.name),
+            None => self.affixed_name(&base, &affixes),
+        };
+        Item {
+            id: format!("{:032x}", self.rng.gen::<u128>()),
+            base: base.id,
+            name,
+            slot: base.slot,
+            rarity: rarity.id,
+            item_level,
+            base_stats: base.stats,
+            affixes,
+            sockets,
+            set: set.map(|set| set.id),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn roll_affixes(&mut self, rarity: &Rarity, base: &ItemBase, item_level: u32) -> Vec<RolledAffix> {
+        let wanted = self.rng.gen_range(rarity.affixes.0..=rarity.affixes.1);
+        let mut eligible: Vec<&Affix> = self
+            .profile
+            .affixes
+            .iter()
+            .filter(|affix| affix.min_item_level <= item_level)
+            .filter(|affix| affix.slots.is_empty() || affix.slots.contains(&base.slot))
+            .collect();
+
+        let level_factor = 0.5 + 0.5 * item_level as f64 / self.profile.item_levels.1 as f64;
+        let mut rolled = Vec::with_capacity(wanted);
+        let (mut prefixes, mut suffixes) = (0, 0);
+        while rolled.len() < wanted && !eligible.is_empty() {
+            let Ok(index) = WeightedIndex::new(eligible.iter().map(|affix| affix.weight)) else {
+                break;
+            };
+            let affix = eligible.swap_remove(index.sample(&mut self.rng));
+            let count = match affix.kind {
+                AffixKind::Prefix => &mut prefixes,
+                AffixKind::Suffix => &mut suffixes,
+            };
+            let limit = match affix.kind {
+                AffixKind::Prefix => self.profile.max_prefixes,
+                AffixKind::Suffix => self.profile.max_suffixes,
+            };
+            if *count >= limit {
+                continue;
+            }
+            *count += 1;
+            let value = (affix.min + (affix.max - affix.min) * self.rng.gen::<f64>()) * level_factor;
+            rolled.push(RolledAffix {
+                id: affix.id.clone(),
+                kind: affix.kind,
+                stat: affix.stat.clone(),
+                value: (value * 1000.0).round() / 1000.0,
+            });
+        }
+        rolled
+    }
+
+    /// "Sharp Iron Sword of Precision", from the first prefix and suffix
+    fn affixed_name(&self, base: &ItemBase, affixes: &[RolledAffix]) -> String {
+        let name_of = |kind: AffixKind| {
+            affixes
+                .iter()
+                .find(|rolled| rolled.kind == kind)
+                .and_then(|rolled| self.profile.affixes.iter().find(|affix| affix.id == rolled.id))
+                .map(|affix| affix.name.as_str())
+        };
+        let mut name = base.name.clone();
+        if let Some(prefix) = name_of(AffixKind::Prefix) {
+            name = format!("{} {}", prefix, name);
+        }
+        if let Some(suffix) = name_of(AffixKind::Suffix) {
+            name = format!("{} {}", name, suffix);
+        }
+        name
+    }
+}
+
+/// Distribution of a batch of items
+#[derive(Debug, Default, Serialize)]
+pub struct DistributionReport {
+    pub total: usize,
+    pub rarity: BTreeMap<String, usize>,
+    /// Share of each rarity expected from the table weights
+    pub rarity_expected: BTreeMap<String, f64>,
+    pub affixes: BTreeMap<String, usize>,
+    pub affixes_per_item: BTreeMap<usize, usize>,
+    pub sockets: BTreeMap<u8, usize>,
+    pub set_pieces: BTreeMap<String, usize>,
+}
+
+impl DistributionReport {
+    pub fn new(profile: &ItemProfile, items: &[Item]) -> Self {
+        let total_weight: f64 = profile.rarities.iter().map(|rarity| rarity.weight).sum();
+        let mut report = Self {
+            total: items.len(),
+            rarity_expected: profile
+                .rarities
+                .iter()
+                .map(|rarity| (rarity.id.clone(), rarity.weight / total_weight))
+                .collect(),
+            ..Self::default()
+        };
+        for item in items {
+            *report.rarity.entry(item.rarity.clone()).or_default() += 1;
+            *report.affixes_per_item.entry(item.affixes.len()).or_default() += 1;
+            *report.sockets.entry(item.sockets).or_default() += 1;
+            for affix in &item.affixes {
+                *report.affixes.entry(affix.id.clone()).or_default() += 1;
+            }
+            if let Some(set) = &item.set {
+                *report.set_pieces.entry(set.clone()).or_default() += 1;
+            }
+        }
+        report
+    }
+
+    pub fn print(&self) {
+        let share = |count: usize| if self.total == 0 { 0.0 } else { count as f64 / self.total as f64 };
+        println!("{} items", self.total);
+
+        println!("\nRarity              count   share  expected");
+        for (rarity, expected) in &self.rarity_expected {
+            let count = self.rarity.get(rarity).copied().unwrap_or(0);
+            println!("  {:<16} {:>7} {:>6.2}% {:>8.2}%  {}", rarity, count, share(count) * 100.0, expected * 100.0, bar(share(count)));
+        }
+
+        println!("\nAffix               count  % items");
+        let mut affixes: Vec<_> = self.affixes.iter().collect();
+        affixes.sort_by(|a, b| b.1.cmp(a.1));
+        for (affix, count) in affixes {
+            println!("  {:<16} {:>7} {:>7.2}%  {}", affix, count, share(*count) * 100.0, bar(share(*count)));
+        }
+
+        println!("\nAffixes per item");
+        for (affixes, count) in &self.affixes_per_item {
+            println!("  {:<16} {:>7} {:>7.2}%", affixes, count, share(*count) * 100.0);
+        }
+
+        println!("\nSockets");
+        for (sockets, count) in &self.sockets {
+            println!("  {:<16} {:>7} {:>7.2}%", sockets, count, share(*count) * 100.0);
+        }
+
+        if !self.set_pieces.is_empty() {
+            println!("\nSet pieces");
+            for (set, count) in &self.set_pieces {
+                println!("  {:<16} {:>7} {:>7.2}%", set, count, share(*count) * 100.0);
+            }
+        }
+    }
+}
+
+fn bar(share: f64) -> String {
+    "#".repeat((share * 40.0).round() as usize)
+}
+
+/// Run `items <count>`.
+pub async fn run(count: usize, args: ItemArgs) -> Result<()> {
+    let profile = ItemProfile::load(args.profile.as_deref())?;
+    let mut generator = ItemGenerator::new(profile.clone(), args.seed)?;
+    let items: Vec<Item> = (0..count).map(|_| generator.generate()).collect();
+
+    let report = DistributionReport::new(&profile, &items);
+    report.print();
+    if let Some(path) = &args.report {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?).with_context(|| format!("writing {}", path.display()))?;
+    }
+    if args.report_only {
+        return Ok(());
+    }
+
+    match args.output.database().await? {
+        Some(database) => {
+            let documents = items
+                .iter()
+                .map(|item| {
+                    let mut document = bson::to_document(item)?;
+                    document.insert("_id", &item.id);
+                    Ok(document)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            output::insert_all(&database, &args.collection, documents, args.output.reset).await?;
+        }
+        None => {
+            let files = items.iter().map(|item| (item.id.as_str(), item));
+            output::write_json(&args.output.out_dir("items"), files, args.output.reset)?;
+        }
+    }
+    Ok(())
+}
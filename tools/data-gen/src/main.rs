@@ -2,6 +2,13 @@
 //!
 //! Tool for generating test data for the Chaos World MMORPG backend.
 
+mod characters;
+mod items;
+mod output;
+mod quests;
+mod validate;
+mod world;
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing::{info, error};
@@ -23,19 +30,22 @@ enum Commands {
     Characters {
         /// Number of characters to generate
         count: usize,
+
+        #[command(flatten)]
+        args: characters::CharacterArgs,
     },
     /// Generate items
     Items {
         /// Number of items to generate
         count: usize,
+
+        #[command(flatten)]
+        args: items::ItemArgs,
     },
-    /// Generate world data
-    World,
-    /// Generate quests
-    Quests {
-        /// Number of quests to generate
-        count: usize,
-    },
+    /// Generate zones and dungeons
+    World(world::WorldArgs),
+    /// Generate quest chains for the generated zones
+    Quests(quests::QuestArgs),
 }
 
 #[tokio::main]
@@ -50,21 +60,21 @@ async fn main() -> Result<()> {
     info!("Chaos World Data Generation Tool");
     
     match args.command {
-        Commands::Characters { count } => {
+        Commands::Characters { count, args } => {
             info!("Generating {} characters...", count);
-            // TODO: Implement character generation
+            characters::run(count, args).await?;
         }
-        Commands::Items { count } => {
+        Commands::Items { count, args } => {
             info!("Generating {} items...", count);
-            // TODO: Implement item generation
+            items::run(count, args).await?;
         }
-        Commands::World => {
+        Commands::World(args) => {
             info!("Generating world data...");
-            // TODO: Implement world generation
+            world::run(args).await?;
         }
-        Commands::Quests { count } => {
-            info!("Generating {} quests...", count);
-            // TODO: Implement quest generation
+        Commands::Quests(args) => {
+            info!("Generating quests...");
+            quests::run(args).await?;
         }
     }
     
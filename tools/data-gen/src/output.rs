@@ -0,0 +1,120 @@
+//! Where generated data goes: JSON files or MongoDB collections.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::Args;
+use mongodb::bson::{doc, Document};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tracing::info;
+
+/// Documents inserted per MongoDB round trip
+const INSERT_BATCH: usize = 1000;
+
+/// `generated_by` recorded on inserted documents, `--reset` removes only these
+const GENERATED_BY: &str = "data-gen";
+
+#[derive(Args, Debug)]
+pub struct OutputArgs {
+    /// Directory for JSON output, one file per document
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Write into MongoDB instead of JSON files
+    #[arg(long, env = "MONGODB_URI")]
+    pub mongodb_uri: Option<String>,
+
+    #[arg(long, env = "MONGODB_DATABASE", default_value = "chaos_world")]
+    pub database: String,
+
+    /// Remove previously generated data first, so reruns replace instead of adding
+    #[arg(long)]
+    pub reset: bool,
+}
+
+impl OutputArgs {
+    /// JSON output directory, `generated/<kind>` unless given
+    pub fn out_dir(&self, kind: &str) -> PathBuf {
+        self.out_dir.clone().unwrap_or_else(|| Path::new("generated").join(kind))
+    }
+
+    pub async fn database(&self) -> Result<Option<mongodb::Database>> {
+        let Some(uri) = &self.mongodb_uri else {
+            return Ok(None);
+        };
+        let client = mongodb::Client::with_uri_str(uri).await.context("connecting to MongoDB")?;
+        Ok(Some(client.database(&self.database)))
+    }
+}
+
+/// Write each value to `<out_dir>/<id>.json`, after removing the JSON files already there on reset.
+pub fn write_json<'a, T: Serialize + 'a>(
+    out_dir: &Path,
+    values: impl IntoIterator<Item = (&'a str, &'a T)>,
+    reset: bool,
+) -> Result<usize> {
+    if reset && out_dir.exists() {
+        for entry in std::fs::read_dir(out_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "json") {
+                std::fs::remove_file(&path).with_context(|| format!("removing {}", path.display()))?;
+            }
+        }
+    }
+    std::fs::create_dir_all(out_dir).with_context(|| format!("creating {}", out_dir.display()))?;
+    let mut written = 0;
+    for (id, value) in values {
+        let path = out_dir.join(format!("{}.json", id));
+        let json = serde_json::to_string_pretty(value)?;
+        std::fs::write(&path, json).with_context(|| format!("writing {}", path.display()))?;
+        written += 1;
+    }
+    info!("Wrote {} files to {}", written, out_dir.display());
+    Ok(written)
+}
+
+/// Read every `<dir>/*.json` written by [`write_json`].
+pub fn read_json<T: DeserializeOwned>(dir: &Path) -> Result<Vec<T>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    paths.retain(|path| path.extension().is_some_and(|extension| extension == "json"));
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+            serde_json::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+        })
+        .collect()
+}
+
+/// Insert documents in batches, after removing previously generated ones on reset.
+pub async fn insert_all(database: &mongodb::Database, collection: &str, documents: Vec<Document>, reset: bool) -> Result<()> {
+    let count = documents.len();
+    let target = database.collection::<Document>(collection);
+    if reset {
+        let removed = target
+            .delete_many(doc! { "generated_by": GENERATED_BY }, None)
+            .await
+            .with_context(|| format!("resetting {}", collection))?;
+        info!("Removed {} generated documents from {}.{}", removed.deleted_count, database.name(), collection);
+    }
+    let mut documents = documents
+        .into_iter()
+        .map(|mut document| {
+            document.insert("generated_by", GENERATED_BY);
+            document
+        })
+        .peekable();
+    while documents.peek().is_some() {
+        let batch: Vec<Document> = documents.by_ref().take(INSERT_BATCH).collect();
+        target.insert_many(batch, None).await.with_context(|| format!("inserting into {}", collection))?;
+    }
+    info!("Inserted {} documents into {}.{}", count, database.name(), collection);
+    Ok(())
+}
@@ -0,0 +1,339 @@
+//! `data-gen quests`: quest chains for a generated world.
+//!
+//! event-core does not model quests yet, so chains are built here from a quest profile
+//! (`configs/quests.yaml` is the built-in default) on top of the zones written by
+//! `data-gen world`. Quests are documents in the shape the content service validates:
+//! a title plus condition-core `conditions` chains, with `prerequisites` naming quests
+//! that must be finished first. Chains stay in their zone, may branch, and may require
+//! a chain from a lower-level neighbouring zone. The prerequisite graph, quest levels
+//! and condition chains are validated before anything is written.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use condition_core::{ChainLogic, ConditionBuilder, ConditionChainBuilder, ConditionChainConfig, ConditionOperator, ConditionValue};
+use futures::TryStreamExt;
+use mongodb::bson::{self, Document};
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, OutputArgs};
+use crate::validate::{self, ConditionChecker};
+use crate::world::{self, Zone};
+
+/// Built-in quest profile
+const DEFAULT_PROFILE: &str = include_str!("../configs/quests.yaml");
+
+#[derive(Args, Debug)]
+pub struct QuestArgs {
+    /// YAML quest profile, `configs/quests.yaml` when omitted
+    #[arg(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Zones written by `data-gen world`, used when not writing to MongoDB
+    #[arg(long, default_value = "generated/world/zones")]
+    pub zones_dir: PathBuf,
+
+    #[arg(long, default_value = "zones")]
+    pub zones_collection: String,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(long, default_value = "quests")]
+    pub collection: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestProfile {
+    pub chains_per_zone: (usize, usize),
+    pub chain_length: (usize, usize),
+    #[serde(default)]
+    pub branch_chance: f64,
+    #[serde(default)]
+    pub cross_zone_chance: f64,
+    #[serde(default)]
+    pub element_quest_chance: f64,
+    pub objectives: Vec<ObjectiveTemplate>,
+    pub rewards: RewardProfile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectiveTemplate {
+    pub kind: ObjectiveKind,
+    pub weight: f64,
+    pub count: (u32, u32),
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveKind {
+    Kill,
+    Collect,
+    Explore,
+    Deliver,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RewardProfile {
+    pub experience_per_level: u64,
+    pub gold_per_level: u64,
+}
+
+impl QuestProfile {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let profile: Self = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+                serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?
+            }
+            None => serde_yaml::from_str(DEFAULT_PROFILE).context("parsing built-in quest profile")?,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.chains_per_zone.1 < self.chains_per_zone.0 || self.chain_length.1 < self.chain_length.0 {
+            bail!("chains_per_zone and chain_length must be ordered ranges");
+        }
+        if self.chain_length.0 == 0 {
+            bail!("chains need at least one quest");
+        }
+        for chance in [self.branch_chance, self.cross_zone_chance, self.element_quest_chance] {
+            if !(0.0..=1.0).contains(&chance) {
+                bail!("chances must be within 0..=1");
+            }
+        }
+        if self.objectives.is_empty() || self.objectives.iter().any(|objective| objective.targets.is_empty()) {
+            bail!("every objective needs at least one target");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Quest {
+    pub id: String,
+    pub chain_id: String,
+    pub title: String,
+    pub zone_id: String,
+    pub min_level: u32,
+    /// Quests that must be finished first
+    pub prerequisites: Vec<String>,
+    pub objectives: Vec<Objective>,
+    pub rewards: Rewards,
+    /// Who may start the quest, condition-core chains
+    pub conditions: Vec<ConditionChainConfig>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Objective {
+    pub kind: ObjectiveKind,
+    pub target: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Rewards {
+    pub experience: u64,
+    pub gold: u64,
+}
+
+pub fn generate(profile: &QuestProfile, zones: &[Zone], seed: Option<u64>) -> Result<Vec<Quest>> {
+    let mut rng = match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let objectives = WeightedIndex::new(profile.objectives.iter().map(|objective| objective.weight))
+        .context("objective weights")?;
+
+    let mut zones: Vec<&Zone> = zones.iter().collect();
+    zones.sort_by_key(|zone| (zone.min_level, zone.id.clone()));
+    // Last quest of every chain, per zone, for cross-zone prerequisites
+    let mut chain_ends: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut quests = Vec::new();
+
+    for zone in &zones {
+        let chains = rng.gen_range(profile.chains_per_zone.0..=profile.chains_per_zone.1);
+        for chain_number in 1..=chains {
+            let chain_id = format!("{}_chain_{}", zone.id, chain_number);
+            let length = rng.gen_range(profile.chain_length.0..=profile.chain_length.1);
+            let mut chain: Vec<String> = Vec::with_capacity(length);
+
+            for step in 0..length {
+                let mut prerequisites = Vec::new();
+                if let Some(previous) = chain.last() {
+                    prerequisites.push(previous.clone());
+                    if chain.len() >= 2 && rng.gen_bool(profile.branch_chance) {
+                        let earlier = chain[rng.gen_range(0..chain.len() - 1)].clone();
+                        prerequisites.push(earlier);
+                    }
+                } else if rng.gen_bool(profile.cross_zone_chance) {
+                    let lower: Vec<&String> = zone
+                        .neighbours
+                        .iter()
+                        .filter_map(|neighbour| chain_ends.get(neighbour.as_str()))
+                        .flatten()
+                        .collect();
+                    if let Some(end) = lower.choose(&mut rng) {
+                        prerequisites.push((*end).clone());
+                    }
+                }
+
+                let span = zone.max_level - zone.min_level;
+                let min_level = zone.min_level + (span as usize * step / length) as u32;
+                let template = &profile.objectives[objectives.sample(&mut rng)];
+                let objective = Objective {
+                    kind: template.kind,
+                    target: template.targets.choose(&mut rng).cloned().unwrap_or_default(),
+                    count: rng.gen_range(template.count.0..=template.count.1),
+                };
+                let element = zone
+                    .elements
+                    .choose(&mut rng)
+                    .filter(|_| rng.gen_bool(profile.element_quest_chance))
+                    .cloned();
+
+                let id = format!("{}_{:02}", chain_id, step + 1);
+                quests.push(Quest {
+                    conditions: vec![can_start(&id, min_level, element.as_deref())?],
+                    id: id.clone(),
+                    chain_id: chain_id.clone(),
+                    title: format!("{}: {}", zone.name, title(&objective)),
+                    zone_id: zone.id.clone(),
+                    min_level,
+                    prerequisites,
+                    objectives: vec![objective],
+                    rewards: Rewards {
+                        experience: profile.rewards.experience_per_level * min_level as u64,
+                        gold: profile.rewards.gold_per_level * min_level as u64,
+                    },
+                });
+                chain.push(id);
+            }
+            if let Some(end) = chain.pop() {
+                chain_ends.entry(zone.id.as_str()).or_default().push(end);
+            }
+        }
+    }
+    Ok(quests)
+}
+
+fn title(objective: &Objective) -> String {
+    match objective.kind {
+        ObjectiveKind::Kill => format!("Cull the {}", objective.target),
+        ObjectiveKind::Collect => format!("Gather {}", objective.target),
+        ObjectiveKind::Explore => format!("Scout the {}", objective.target),
+        ObjectiveKind::Deliver => format!("Deliver the {}", objective.target),
+    }
+}
+
+/// Level requirement, plus element mastery for element quests
+fn can_start(quest_id: &str, min_level: u32, element: Option<&str>) -> Result<ConditionChainConfig> {
+    let mut chain = ConditionChainBuilder::new().id(format!("{}_can_start", quest_id)).logic(ChainLogic::And).condition(
+        ConditionBuilder::new()
+            .id("min_level")
+            .function("get_actor_stat")
+            .parameter("level")
+            .operator(ConditionOperator::GreaterThanOrEqual)
+            .value(ConditionValue::Float(min_level as f64))
+            .build()?,
+    );
+    if let Some(element) = element {
+        chain = chain.condition(
+            ConditionBuilder::new()
+                .id("element_mastery")
+                .function("get_element_mastery")
+                .parameter(element)
+                .operator(ConditionOperator::GreaterThanOrEqual)
+                .value(ConditionValue::Float((min_level / 10).max(1) as f64))
+                .build()?,
+        );
+    }
+    Ok(chain.build()?)
+}
+
+/// Check prerequisites form a graph without cycles, levels never go down along it,
+/// every quest sits in a known zone and its conditions are valid.
+pub fn validate(quests: &[Quest], zones: &[Zone]) -> Result<()> {
+    let zones: HashMap<&str, &Zone> = zones.iter().map(|zone| (zone.id.as_str(), zone)).collect();
+    let by_id: HashMap<&str, &Quest> = quests.iter().map(|quest| (quest.id.as_str(), quest)).collect();
+    if by_id.len() != quests.len() {
+        bail!("quest ids are not unique");
+    }
+    let prerequisites: HashMap<&str, Vec<&str>> = quests
+        .iter()
+        .map(|quest| (quest.id.as_str(), quest.prerequisites.iter().map(String::as_str).collect()))
+        .collect();
+    validate::check_prerequisites(&prerequisites)?;
+
+    let checker = ConditionChecker::new();
+    for quest in quests {
+        let zone = zones
+            .get(quest.zone_id.as_str())
+            .with_context(|| format!("quest {} is in unknown zone {}", quest.id, quest.zone_id))?;
+        if !(zone.min_level..=zone.max_level).contains(&quest.min_level) {
+            bail!("quest {} level {} is outside zone {}", quest.id, quest.min_level, zone.id);
+        }
+        for prerequisite in &quest.prerequisites {
+            if by_id[prerequisite.as_str()].min_level > quest.min_level {
+                bail!("quest {} requires higher-level quest {}", quest.id, prerequisite);
+            }
+        }
+        for chain in &quest.conditions {
+            checker.check(&quest.id, chain)?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_zones(args: &QuestArgs, database: Option<&mongodb::Database>) -> Result<Vec<Zone>> {
+    let zones: Vec<Zone> = match database {
+        Some(database) => {
+            let documents: Vec<Document> = database
+                .collection::<Document>(&args.zones_collection)
+                .find(None, None)
+                .await?
+                .try_collect()
+                .await
+                .context("reading zones")?;
+            documents.into_iter().map(bson::from_document).collect::<Result<_, _>>()?
+        }
+        None => output::read_json(&args.zones_dir)?,
+    };
+    if zones.is_empty() {
+        bail!("No zones found, run `data-gen world` first");
+    }
+    Ok(zones)
+}
+
+/// Run `quests`.
+pub async fn run(args: QuestArgs) -> Result<()> {
+    let profile = QuestProfile::load(args.profile.as_deref())?;
+    let database = args.output.database().await?;
+    let zones = load_zones(&args, database.as_ref()).await?;
+    let quests = generate(&profile, &zones, args.seed)?;
+    validate(&quests, &zones).context("generated quests are invalid")?;
+
+    match database {
+        Some(database) => {
+            let documents = world::documents(&quests, |quest| &quest.id)?;
+            output::insert_all(&database, &args.collection, documents, args.output.reset).await?;
+        }
+        None => {
+            let files = quests.iter().map(|quest| (quest.id.as_str(), quest));
+            output::write_json(&args.output.out_dir("quests"), files, args.output.reset)?;
+        }
+    }
+    Ok(())
+}
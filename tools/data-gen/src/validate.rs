@@ -0,0 +1,100 @@
+//! Checks run on generated world and quest data before anything is written.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::{bail, Result};
+use condition_core::{
+    create_function_registry_with_providers, validate_condition_chain_config, ConditionChainConfig, DataProviderRegistry,
+    FunctionRegistry,
+};
+
+/// Validates condition chains the way the content service does: condition-core's
+/// config validation plus a lookup of every function in the registered functions
+pub struct ConditionChecker {
+    functions: FunctionRegistry,
+}
+
+impl ConditionChecker {
+    pub fn new() -> Self {
+        Self {
+            functions: create_function_registry_with_providers(&DataProviderRegistry::new()),
+        }
+    }
+
+    pub fn check(&self, owner: &str, chain: &ConditionChainConfig) -> Result<()> {
+        // Round trip through YAML so the chain is read by the same parser as content files
+        let yaml = serde_yaml::to_string(chain)?;
+        let chain = condition_core::parse_condition_chain_config(&yaml)?;
+        if let Err(e) = validate_condition_chain_config(&chain) {
+            bail!("{}: condition chain {}: {}", owner, chain.chain_id, e);
+        }
+        for condition in &chain.conditions {
+            if self.functions.get(&condition.function_name).is_none() {
+                bail!("{}: condition {} uses unknown function {}", owner, condition.condition_id, condition.function_name);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ConditionChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Every node can reach every other one over undirected `edges`
+pub fn check_connected(nodes: &[&str], edges: &HashMap<&str, Vec<&str>>) -> Result<()> {
+    let Some(start) = nodes.first() else {
+        return Ok(());
+    };
+    let mut seen = HashSet::from([*start]);
+    let mut queue = VecDeque::from([*start]);
+    while let Some(node) = queue.pop_front() {
+        for next in edges.get(node).into_iter().flatten() {
+            if seen.insert(*next) {
+                queue.push_back(*next);
+            }
+        }
+    }
+    if let Some(unreachable) = nodes.iter().find(|node| !seen.contains(*node)) {
+        bail!("{} cannot be reached from {}", unreachable, start);
+    }
+    Ok(())
+}
+
+/// Prerequisites exist and contain no cycle; returns the nodes in an order where
+/// every node comes after its prerequisites
+pub fn check_prerequisites<'a>(prerequisites: &HashMap<&'a str, Vec<&'a str>>) -> Result<Vec<&'a str>> {
+    let mut remaining: HashMap<&'a str, usize> = HashMap::new();
+    let mut unlocks: HashMap<&'a str, Vec<&'a str>> = HashMap::new();
+    for (&node, required) in prerequisites {
+        remaining.insert(node, required.len());
+        for &requirement in required {
+            if !prerequisites.contains_key(requirement) {
+                bail!("{} requires unknown {}", node, requirement);
+            }
+            unlocks.entry(requirement).or_default().push(node);
+        }
+    }
+
+    let mut ready: Vec<&'a str> = remaining.iter().filter(|(_, count)| **count == 0).map(|(node, _)| *node).collect();
+    ready.sort_unstable();
+    let mut order = Vec::with_capacity(prerequisites.len());
+    while let Some(node) = ready.pop() {
+        order.push(node);
+        for &unlocked in unlocks.get(node).into_iter().flatten() {
+            let count = remaining.get_mut(unlocked).expect("every node has a count");
+            *count -= 1;
+            if *count == 0 {
+                ready.push(unlocked);
+            }
+        }
+    }
+    if order.len() != prerequisites.len() {
+        let mut cyclic: Vec<&str> = remaining.iter().filter(|(_, count)| **count > 0).map(|(node, _)| *node).collect();
+        cyclic.sort_unstable();
+        bail!("prerequisite cycle among {}", cyclic.join(", "));
+    }
+    Ok(order)
+}
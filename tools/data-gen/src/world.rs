@@ -0,0 +1,319 @@
+//! `data-gen world`: zones and dungeons.
+//!
+//! generator-core and world-core do not provide zone generation yet, so zones are laid
+//! out here from a world profile (`configs/world.yaml` is the built-in default): the
+//! level range is split across zones, each zone connects to the next one up plus a few
+//! zones of similar level, and dungeons get condition-core entry chains. Everything is
+//! validated (connected zone graph, entry chains against the registered condition
+//! functions) before it is written. Zone and dungeon ids are stable across runs, so
+//! `--reset` reruns replace the previous world.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use condition_core::{ChainLogic, ConditionBuilder, ConditionChainBuilder, ConditionChainConfig, ConditionOperator, ConditionValue};
+use mongodb::bson;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::output::{self, OutputArgs};
+use crate::validate::{self, ConditionChecker};
+
+/// Built-in world profile
+const DEFAULT_PROFILE: &str = include_str!("../configs/world.yaml");
+
+#[derive(Args, Debug)]
+pub struct WorldArgs {
+    /// YAML world profile, `configs/world.yaml` when omitted
+    #[arg(long)]
+    pub profile: Option<PathBuf>,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    #[command(flatten)]
+    pub output: OutputArgs,
+
+    #[arg(long, default_value = "zones")]
+    pub zones_collection: String,
+
+    #[arg(long, default_value = "dungeons")]
+    pub dungeons_collection: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorldProfile {
+    pub zones: usize,
+    pub level_range: (u32, u32),
+    #[serde(default)]
+    pub extra_connection_chance: f64,
+    pub biomes: Vec<Biome>,
+    pub dungeons_per_zone: (usize, usize),
+    pub dungeon_kinds: Vec<DungeonKind>,
+    pub bosses: Vec<String>,
+    pub element_gate_level: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Biome {
+    pub id: String,
+    pub weight: f64,
+    #[serde(default)]
+    pub elements: Vec<String>,
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DungeonKind {
+    pub id: String,
+    pub name: String,
+    pub party_size: (u32, u32),
+}
+
+impl WorldProfile {
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let profile: Self = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+                serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?
+            }
+            None => serde_yaml::from_str(DEFAULT_PROFILE).context("parsing built-in world profile")?,
+        };
+        profile.validate()?;
+        Ok(profile)
+    }
+
+    fn validate(&self) -> Result<()> {
+        let (min_level, max_level) = self.level_range;
+        if min_level == 0 || max_level < min_level {
+            bail!("invalid level range {}..={}", min_level, max_level);
+        }
+        if self.zones == 0 || self.zones as u32 > max_level - min_level + 1 {
+            bail!("cannot fit {} zones into levels {}..={}", self.zones, min_level, max_level);
+        }
+        if self.biomes.is_empty() || self.biomes.iter().any(|biome| biome.names.is_empty()) {
+            bail!("every biome needs at least one zone name");
+        }
+        if self.dungeons_per_zone.1 < self.dungeons_per_zone.0 {
+            bail!("dungeons_per_zone is inverted");
+        }
+        if self.dungeons_per_zone.1 > 0 && (self.dungeon_kinds.is_empty() || self.bosses.is_empty()) {
+            bail!("dungeons need at least one kind and boss");
+        }
+        if !(0.0..=1.0).contains(&self.extra_connection_chance) {
+            bail!("extra_connection_chance must be within 0..=1");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+    pub biome: String,
+    pub min_level: u32,
+    pub max_level: u32,
+    /// Elements strong in the zone
+    pub elements: Vec<String>,
+    /// Zones reachable from this one
+    pub neighbours: Vec<String>,
+    pub dungeons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Dungeon {
+    pub id: String,
+    pub zone_id: String,
+    pub name: String,
+    pub kind: String,
+    pub min_level: u32,
+    pub max_level: u32,
+    pub party_size: (u32, u32),
+    pub boss: String,
+    /// Who may enter, a condition-core chain
+    pub entry: ConditionChainConfig,
+}
+
+/// Generated world
+#[derive(Debug, Default)]
+pub struct World {
+    pub zones: Vec<Zone>,
+    pub dungeons: Vec<Dungeon>,
+}
+
+pub fn generate(profile: &WorldProfile, seed: Option<u64>) -> Result<World> {
+    let mut rng = match seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => ChaCha8Rng::from_entropy(),
+    };
+    let biomes = WeightedIndex::new(profile.biomes.iter().map(|biome| biome.weight)).context("biome weights")?;
+
+    let (min_level, max_level) = profile.level_range;
+    let span = (max_level - min_level + 1) / profile.zones as u32;
+    let mut used_names: HashMap<String, usize> = HashMap::new();
+    let mut world = World::default();
+
+    for index in 0..profile.zones {
+        let biome = &profile.biomes[biomes.sample(&mut rng)];
+        let zone_min = min_level + index as u32 * span;
+        let zone_max = if index + 1 == profile.zones { max_level } else { zone_min + span - 1 };
+
+        let base_name = biome.names.choose(&mut rng).cloned().unwrap_or_else(|| biome.id.clone());
+        let uses = used_names.entry(base_name.clone()).or_default();
+        *uses += 1;
+        let name = if *uses == 1 { base_name } else { format!("{} {}", base_name, uses) };
+
+        world.zones.push(Zone {
+            id: format!("zone_{:02}_{}", index + 1, biome.id),
+            name,
+            biome: biome.id.clone(),
+            min_level: zone_min,
+            max_level: zone_max,
+            elements: biome.elements.clone(),
+            neighbours: Vec::new(),
+            dungeons: Vec::new(),
+        });
+    }
+
+    connect_zones(&mut world.zones, profile.extra_connection_chance, &mut rng);
+    for zone in &mut world.zones {
+        let count = rng.gen_range(profile.dungeons_per_zone.0..=profile.dungeons_per_zone.1);
+        for number in 1..=count {
+            let dungeon = roll_dungeon(profile, zone, number, &mut rng)?;
+            zone.dungeons.push(dungeon.id.clone());
+            world.dungeons.push(dungeon);
+        }
+    }
+    Ok(world)
+}
+
+/// Each zone leads to the next one up; zones up to two steps apart may also connect
+fn connect_zones(zones: &mut [Zone], extra_chance: f64, rng: &mut ChaCha8Rng) {
+    let mut edges = Vec::new();
+    for index in 1..zones.len() {
+        edges.push((index - 1, index));
+        if index >= 2 && rng.gen_bool(extra_chance) {
+            edges.push((index - 2, index));
+        }
+    }
+    for (from, to) in edges {
+        let (from_id, to_id) = (zones[from].id.clone(), zones[to].id.clone());
+        zones[from].neighbours.push(to_id);
+        zones[to].neighbours.push(from_id);
+    }
+}
+
+fn roll_dungeon(profile: &WorldProfile, zone: &Zone, number: usize, rng: &mut ChaCha8Rng) -> Result<Dungeon> {
+    let kind = profile.dungeon_kinds.choose(rng).context("no dungeon kinds")?;
+    let boss = profile.bosses.choose(rng).context("no bosses")?.clone();
+    // Dungeons sit in the upper half of their zone's levels
+    let min_level = rng.gen_range((zone.min_level + zone.max_level) / 2..=zone.max_level);
+    let id = format!("{}_{}_{}", zone.id, kind.id, number);
+
+    let mut entry = ConditionChainBuilder::new().id(format!("{}_entry", id)).logic(ChainLogic::And).condition(
+        ConditionBuilder::new()
+            .id("min_level")
+            .function("get_actor_stat")
+            .parameter("level")
+            .operator(ConditionOperator::GreaterThanOrEqual)
+            .value(ConditionValue::Float(min_level as f64))
+            .build()?,
+    );
+    if min_level >= profile.element_gate_level {
+        if let Some(element) = zone.elements.first() {
+            entry = entry.condition(
+                ConditionBuilder::new()
+                    .id("element_mastery")
+                    .function("get_element_mastery")
+                    .parameter(element.as_str())
+                    .operator(ConditionOperator::GreaterThanOrEqual)
+                    .value(ConditionValue::Float((min_level / 10) as f64))
+                    .build()?,
+            );
+        }
+    }
+
+    Ok(Dungeon {
+        id,
+        zone_id: zone.id.clone(),
+        name: format!("{} {}", zone.name, kind.name),
+        kind: kind.id.clone(),
+        min_level,
+        max_level: zone.max_level,
+        party_size: kind.party_size,
+        boss,
+        entry: entry.build()?,
+    })
+}
+
+/// Check the zone graph and every dungeon entry chain.
+pub fn validate(world: &World) -> Result<()> {
+    let ids: Vec<&str> = world.zones.iter().map(|zone| zone.id.as_str()).collect();
+    let edges: HashMap<&str, Vec<&str>> = world
+        .zones
+        .iter()
+        .map(|zone| (zone.id.as_str(), zone.neighbours.iter().map(String::as_str).collect()))
+        .collect();
+    for (zone, neighbours) in &edges {
+        if let Some(unknown) = neighbours.iter().find(|neighbour| !edges.contains_key(*neighbour)) {
+            bail!("zone {} connects to unknown zone {}", zone, unknown);
+        }
+    }
+    validate::check_connected(&ids, &edges)?;
+
+    let checker = ConditionChecker::new();
+    for dungeon in &world.dungeons {
+        if !edges.contains_key(dungeon.zone_id.as_str()) {
+            bail!("dungeon {} is in unknown zone {}", dungeon.id, dungeon.zone_id);
+        }
+        checker.check(&dungeon.id, &dungeon.entry)?;
+    }
+    Ok(())
+}
+
+/// Run `world`.
+pub async fn run(args: WorldArgs) -> Result<()> {
+    let profile = WorldProfile::load(args.profile.as_deref())?;
+    let world = generate(&profile, args.seed)?;
+    validate(&world).context("generated world is invalid")?;
+
+    match args.output.database().await? {
+        Some(database) => {
+            output::insert_all(&database, &args.zones_collection, documents(&world.zones, |zone| &zone.id)?, args.output.reset)
+                .await?;
+            output::insert_all(
+                &database,
+                &args.dungeons_collection,
+                documents(&world.dungeons, |dungeon| &dungeon.id)?,
+                args.output.reset,
+            )
+            .await?;
+        }
+        None => {
+            let out_dir = args.output.out_dir("world");
+            let zones = world.zones.iter().map(|zone| (zone.id.as_str(), zone));
+            output::write_json(&out_dir.join("zones"), zones, args.output.reset)?;
+            let dungeons = world.dungeons.iter().map(|dungeon| (dungeon.id.as_str(), dungeon));
+            output::write_json(&out_dir.join("dungeons"), dungeons, args.output.reset)?;
+        }
+    }
+    Ok(())
+}
+
+/// Serialize values to documents keyed by their id.
+pub fn documents<T: Serialize>(values: &[T], id: impl Fn(&T) -> &String) -> Result<Vec<bson::Document>> {
+    values
+        .iter()
+        .map(|value| {
+            let mut document = bson::to_document(value)?;
+            document.insert("_id", id(value));
+            Ok(document)
+        })
+        .collect()
+}
@@ -0,0 +1,12 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Clients for the api crate's streaming services, compiled from its protos
+    tonic_build::configure()
+        .build_server(false)
+        .compile(
+            &["../../crates/api/proto/chaos/v1/combat.proto", "../../crates/api/proto/chaos/v1/world.proto"],
+            &["../../crates/api/proto"],
+        )?;
+
+    println!("cargo:rerun-if-changed=../../crates/api/proto");
+    Ok(())
+}
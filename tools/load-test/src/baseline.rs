@@ -0,0 +1,130 @@
+//! Baseline comparison for CI performance gates.
+//!
+//! A run is compared step by step against the JSON report of an earlier run. Latency
+//! regressions are relative, error rate increases absolute; steps with too few
+//! requests on either side are skipped since their percentiles are noise.
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::{LatencySummary, RunReport};
+
+/// Latency changes below this are ignored whatever the relative change
+const MIN_LATENCY_DELTA_MS: f64 = 1.0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CompareOptions {
+    /// Allowed relative latency increase, 0.10 for 10%
+    pub max_latency_regression: f64,
+    /// Allowed absolute error rate increase, 0.005 for half a percentage point
+    pub max_error_rate_increase: f64,
+    /// Steps with fewer requests in either run are not compared
+    pub min_requests: u64,
+}
+
+/// A metric that got worse than allowed since the baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Regression {
+    pub scenario: String,
+    /// `None` for the scenario as a whole
+    pub step: Option<String>,
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// Relative change for latencies, absolute for error rates
+    pub change: f64,
+}
+
+/// Find what regressed in `current` compared to `baseline`
+pub fn compare(baseline: &RunReport, current: &RunReport, options: &CompareOptions) -> Vec<Regression> {
+    let mut regressions = Vec::new();
+    for scenario in &current.scenarios {
+        let Some(previous) = baseline.scenario(&scenario.name) else {
+            continue;
+        };
+        let mut compare_one = |step: Option<&str>, before: (u64, f64, &LatencySummary), after: (u64, f64, &LatencySummary)| {
+            if before.0 < options.min_requests || after.0 < options.min_requests {
+                return;
+            }
+            let latencies = [
+                ("p50_ms", before.2.p50_ms, after.2.p50_ms),
+                ("p95_ms", before.2.p95_ms, after.2.p95_ms),
+                ("p99_ms", before.2.p99_ms, after.2.p99_ms),
+            ];
+            for (metric, was, now) in latencies {
+                if now - was < MIN_LATENCY_DELTA_MS || was <= 0.0 {
+                    continue;
+                }
+                let change = (now - was) / was;
+                if change > options.max_latency_regression {
+                    regressions.push(Regression {
+                        scenario: scenario.name.clone(),
+                        step: step.map(str::to_string),
+                        metric: metric.to_string(),
+                        baseline: was,
+                        current: now,
+                        change,
+                    });
+                }
+            }
+            let change = after.1 - before.1;
+            if change > options.max_error_rate_increase {
+                regressions.push(Regression {
+                    scenario: scenario.name.clone(),
+                    step: step.map(str::to_string),
+                    metric: "error_rate".to_string(),
+                    baseline: before.1,
+                    current: after.1,
+                    change,
+                });
+            }
+        };
+
+        compare_one(
+            None,
+            (previous.requests, previous.error_rate, &previous.latency),
+            (scenario.requests, scenario.error_rate, &scenario.latency),
+        );
+        for step in &scenario.steps {
+            let Some(before) = previous.steps.iter().find(|before| before.name == step.name) else {
+                continue;
+            };
+            compare_one(
+                Some(&step.name),
+                (before.requests, before.error_rate, &before.latency),
+                (step.requests, step.error_rate, &step.latency),
+            );
+        }
+    }
+    regressions
+}
+
+/// Side by side view of the scenarios both runs have
+pub fn print_diff(baseline: &RunReport, current: &RunReport) {
+    println!();
+    println!("compared to baseline {} ({})", baseline.run_id, baseline.target);
+    println!(
+        "{:<32} {:>10} {:>10} {:>8} {:>10} {:>10} {:>8}",
+        "scenario", "p95 was", "p95 now", "change", "err was", "err now", "change"
+    );
+    for scenario in &current.scenarios {
+        let Some(previous) = baseline.scenario(&scenario.name) else {
+            println!("{:<32} (not in baseline)", scenario.name);
+            continue;
+        };
+        let change = if previous.latency.p95_ms > 0.0 {
+            format!("{:+.1}%", (scenario.latency.p95_ms - previous.latency.p95_ms) / previous.latency.p95_ms * 100.0)
+        } else {
+            "-".to_string()
+        };
+        println!(
+            "{:<32} {:>10.1} {:>10.1} {:>8} {:>9.2}% {:>9.2}% {:>+7.2}pp",
+            scenario.name,
+            previous.latency.p95_ms,
+            scenario.latency.p95_ms,
+            change,
+            previous.error_rate * 100.0,
+            scenario.error_rate * 100.0,
+            (scenario.error_rate - previous.error_rate) * 100.0,
+        );
+    }
+}
@@ -0,0 +1,368 @@
+//! gRPC load against the api crate's streaming services.
+//!
+//! - Zone event streams: `World.SubscribeZoneEvents`, one zone per worker
+//! - Combat log streams: `Combat.SubscribeCombatLog`
+//! - Combat callers: `Combat.SubmitAction` in a loop, over one shared connection
+//!
+//! Stream workers open their own connection. With churn enabled they drop it after a
+//! random lifetime and reconnect with the last resume token, exercising the resume
+//! path; streams flagged with `gap` are counted as failures.
+//!
+//! Recorded under `grpc`: `login`, `connect`, `subscribe` (until the stream is
+//! accepted), `stream` (ends and errors) and `submit_action`. Probe delivery latency
+//! goes under `grpc delivery`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Request, Response, Status, Streaming};
+use tracing::debug;
+
+use crate::metrics::Metrics;
+use crate::probe;
+use crate::runner::{backoff, run_step, Worker, Workload};
+use crate::scenario::{render, CombatPlan, GrpcPlan, GRPC, GRPC_DELIVERY};
+
+/// Generated clients
+pub mod proto {
+    tonic::include_proto!("chaos.v1");
+}
+
+use proto::combat_client::CombatClient;
+use proto::world_client::WorldClient;
+use proto::{CombatAction, CombatLogEntry, CombatLogRequest, ZoneEvent, ZoneEventsRequest};
+
+/// Events of a resumable stream
+trait StreamEvent {
+    fn resume_token(&self) -> &str;
+    fn gap(&self) -> bool;
+    fn payload(&self) -> Option<&prost_types::Struct>;
+}
+
+impl StreamEvent for ZoneEvent {
+    fn resume_token(&self) -> &str {
+        &self.resume_token
+    }
+
+    fn gap(&self) -> bool {
+        self.gap
+    }
+
+    fn payload(&self) -> Option<&prost_types::Struct> {
+        self.payload.as_ref()
+    }
+}
+
+impl StreamEvent for CombatLogEntry {
+    fn resume_token(&self) -> &str {
+        &self.resume_token
+    }
+
+    fn gap(&self) -> bool {
+        self.gap
+    }
+
+    fn payload(&self) -> Option<&prost_types::Struct> {
+        self.payload.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum StreamKind {
+    ZoneEvents,
+    CombatLog,
+}
+
+/// Long-lived server streams
+pub struct GrpcStreams {
+    plan: GrpcPlan,
+    kind: StreamKind,
+}
+
+impl GrpcStreams {
+    pub fn new(plan: GrpcPlan, kind: StreamKind) -> Self {
+        Self { plan, kind }
+    }
+}
+
+impl Workload for GrpcStreams {
+    fn name(&self) -> &str {
+        match self.kind {
+            StreamKind::ZoneEvents => "grpc zone events",
+            StreamKind::CombatLog => "grpc combat log",
+        }
+    }
+
+    fn peak(&self) -> usize {
+        match self.kind {
+            StreamKind::ZoneEvents => self.plan.zone_streams,
+            StreamKind::CombatLog => self.plan.combat_log_streams,
+        }
+    }
+
+    fn run(self: Arc<Self>, mut worker: Worker) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let mut iteration = 0u64;
+            let mut failures = 0u32;
+            // Kept across reconnects so streams resume where they left off
+            let mut resume_token = String::new();
+            while worker.ramp.active().await {
+                let opened = self.session(&mut worker, iteration, &mut resume_token).await;
+                iteration += 1;
+                failures = if opened { 0 } else { failures + 1 };
+                if failures > 0 {
+                    backoff(&mut worker, failures).await;
+                }
+            }
+        })
+    }
+}
+
+impl GrpcStreams {
+    /// Run one stream, `false` when it could not be opened
+    async fn session(&self, worker: &mut Worker, iteration: u64, resume_token: &mut String) -> bool {
+        let ctx = worker.ctx.clone();
+        let metrics = &ctx.metrics;
+        // A fixed zone per worker, resume tokens belong to one topic
+        let zone = if self.plan.zones.is_empty() {
+            String::new()
+        } else {
+            self.plan.zones[worker.index % self.plan.zones.len()].clone()
+        };
+        let Some(mut vars) = login(&self.plan, worker, iteration).await else {
+            return false;
+        };
+        vars.insert("zone".to_string(), zone.clone());
+
+        let Some(channel) = connect(&self.plan, worker).await else {
+            return false;
+        };
+
+        let started = Instant::now();
+        match self.kind {
+            StreamKind::ZoneEvents => {
+                let request = ZoneEventsRequest {
+                    zone_id: zone,
+                    event_types: self.plan.event_types.clone(),
+                    resume_token: resume_token.clone(),
+                };
+                let request = with_metadata(request, &self.plan.metadata, &vars, &mut worker.rng);
+                let response = WorldClient::new(channel).subscribe_zone_events(request).await;
+                let Some(stream) = opened(metrics, started, response) else {
+                    return false;
+                };
+                self.consume(worker, stream, "zone event", resume_token).await;
+            }
+            StreamKind::CombatLog => {
+                let request = CombatLogRequest { actor_ids: Vec::new(), resume_token: resume_token.clone() };
+                let request = with_metadata(request, &self.plan.metadata, &vars, &mut worker.rng);
+                let response = CombatClient::new(channel).subscribe_combat_log(request).await;
+                let Some(stream) = opened(metrics, started, response) else {
+                    return false;
+                };
+                self.consume(worker, stream, "combat log entry", resume_token).await;
+            }
+        }
+        true
+    }
+
+    /// Read a stream until it ends, its lifetime is over or the ramp releases the worker
+    async fn consume<E: StreamEvent>(&self, worker: &mut Worker, mut stream: Streaming<E>, step: &str, resume_token: &mut String) {
+        let ctx = worker.ctx.clone();
+        let metrics = &ctx.metrics;
+        let lifetime = self.plan.churn.as_ref().map(|churn| churn.sample(&mut worker.rng));
+        let expires = async move {
+            match lifetime {
+                Some(lifetime) => tokio::time::sleep(lifetime).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(expires);
+
+        loop {
+            tokio::select! {
+                message = stream.message() => match message {
+                    Ok(Some(event)) => {
+                        if !event.resume_token().is_empty() {
+                            *resume_token = event.resume_token().to_string();
+                        }
+                        if event.gap() {
+                            metrics.record_failure(GRPC, "stream", "gap".to_string());
+                        }
+                        if let Some(latency) = event.payload().and_then(|payload| probe::latency_struct(payload, &ctx.run_id)) {
+                            metrics.record_request(GRPC_DELIVERY, step, latency, None);
+                        }
+                    }
+                    Ok(None) => {
+                        metrics.record_failure(GRPC, "stream", "ended".to_string());
+                        return;
+                    }
+                    Err(status) => {
+                        debug!("{} stream: {}", step, status);
+                        metrics.record_failure(GRPC, "stream", format!("{:?}", status.code()));
+                        return;
+                    }
+                },
+                _ = &mut expires => return,
+                _ = worker.ramp.released() => return,
+            }
+        }
+    }
+}
+
+/// `SubmitAction` callers sharing one connection
+pub struct CombatCallers {
+    plan: GrpcPlan,
+    combat: CombatPlan,
+    channel: Channel,
+}
+
+impl CombatCallers {
+    pub fn new(plan: GrpcPlan, combat: CombatPlan) -> Result<Self> {
+        let channel = Endpoint::from_shared(plan.endpoint.clone())
+            .with_context(|| format!("invalid gRPC endpoint {}", plan.endpoint))?
+            .connect_lazy();
+        Ok(Self { plan, combat, channel })
+    }
+}
+
+impl Workload for CombatCallers {
+    fn name(&self) -> &str {
+        "grpc combat actions"
+    }
+
+    fn peak(&self) -> usize {
+        self.combat.callers
+    }
+
+    fn run(self: Arc<Self>, mut worker: Worker) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let mut client = CombatClient::new(self.channel.clone());
+            let mut session: Option<BTreeMap<String, String>> = None;
+            let mut failures = 0u32;
+            let mut sequence = 0u64;
+            while worker.ramp.active().await {
+                let vars = match &session {
+                    Some(vars) => vars.clone(),
+                    None => match login(&self.plan, &mut worker, 0).await {
+                        Some(vars) => {
+                            failures = 0;
+                            session.insert(vars).clone()
+                        }
+                        None => {
+                            failures += 1;
+                            backoff(&mut worker, failures).await;
+                            continue;
+                        }
+                    },
+                };
+
+                let action = self.roll_action(&mut worker, sequence);
+                sequence += 1;
+                let request = with_metadata(action, &self.plan.metadata, &vars, &mut worker.rng);
+                let started = Instant::now();
+                let error = client.submit_action(request).await.err().map(|status| format!("{:?}", status.code()));
+                worker.ctx.metrics.record_request(GRPC, "submit_action", started.elapsed(), error);
+
+                let think_time = self.combat.think_time.sample(&mut worker.rng);
+                if !think_time.is_zero() {
+                    tokio::select! {
+                        _ = tokio::time::sleep(think_time) => {}
+                        _ = worker.ramp.released() => {}
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl CombatCallers {
+    fn roll_action(&self, worker: &mut Worker, sequence: u64) -> CombatAction {
+        let rng = &mut worker.rng;
+        // Validated non-empty on load
+        let pick = |values: &[String], rng: &mut rand::rngs::StdRng| values.choose(rng).cloned().unwrap_or_default();
+        let targets = rng.gen_range(1..=self.combat.targets.len().min(3));
+        CombatAction {
+            action_id: format!("{}-{}-{}", worker.ctx.run_id, worker.index, sequence),
+            attacker_id: pick(&self.combat.attackers, rng),
+            target_ids: self.combat.targets.choose_multiple(rng, targets).cloned().collect(),
+            skill_id: pick(&self.combat.skills, rng),
+            tick: sequence,
+        }
+    }
+}
+
+/// Run the plan's login step, returning the worker's variables
+async fn login(plan: &GrpcPlan, worker: &mut Worker, iteration: u64) -> Option<BTreeMap<String, String>> {
+    let ctx = worker.ctx.clone();
+    let mut vars = ctx.vars(worker.index, iteration);
+    if let Some(step) = &plan.login {
+        if !run_step(&ctx, GRPC, step, &mut vars, &mut worker.rng).await {
+            return None;
+        }
+    }
+    Some(vars)
+}
+
+async fn connect(plan: &GrpcPlan, worker: &Worker) -> Option<Channel> {
+    let metrics = &worker.ctx.metrics;
+    let endpoint = match Endpoint::from_shared(plan.endpoint.clone()) {
+        Ok(endpoint) => endpoint,
+        Err(_) => {
+            metrics.record_failure(GRPC, "connect", "invalid endpoint".to_string());
+            return None;
+        }
+    };
+    let started = Instant::now();
+    match endpoint.connect().await {
+        Ok(channel) => {
+            metrics.record_request(GRPC, "connect", started.elapsed(), None);
+            Some(channel)
+        }
+        Err(e) => {
+            debug!("{}: {}", plan.endpoint, e);
+            metrics.record_request(GRPC, "connect", started.elapsed(), Some("connect".to_string()));
+            None
+        }
+    }
+}
+
+/// Record how opening a stream went
+fn opened<E>(metrics: &Metrics, started: Instant, response: Result<Response<Streaming<E>>, Status>) -> Option<Streaming<E>> {
+    match response {
+        Ok(response) => {
+            metrics.record_request(GRPC, "subscribe", started.elapsed(), None);
+            Some(response.into_inner())
+        }
+        Err(status) => {
+            debug!("subscribe: {}", status);
+            metrics.record_request(GRPC, "subscribe", started.elapsed(), Some(format!("{:?}", status.code())));
+            None
+        }
+    }
+}
+
+/// Wrap a message with the plan's templated metadata; invalid entries are skipped
+fn with_metadata<T>(
+    message: T,
+    metadata: &BTreeMap<String, String>,
+    vars: &BTreeMap<String, String>,
+    rng: &mut impl Rng,
+) -> Request<T> {
+    let mut request = Request::new(message);
+    for (name, value) in metadata {
+        let key = MetadataKey::<Ascii>::from_bytes(name.to_ascii_lowercase().as_bytes());
+        let value = render(value, vars, rng).parse::<MetadataValue<Ascii>>();
+        if let (Ok(key), Ok(value)) = (key, value) {
+            request.metadata_mut().insert(key, value);
+        }
+    }
+    request
+}
@@ -0,0 +1,270 @@
+//! Self-contained HTML report: no scripts, no external assets, one file to attach to a
+//! CI job.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::report::{RunReport, ScenarioReport};
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Helvetica, Arial, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; } h2 { font-size: 1.2em; margin-top: 2em; } h3 { font-size: 1em; }
+table { border-collapse: collapse; margin: 0.5em 0 1em; font-size: 0.9em; }
+th, td { border: 1px solid #ddd; padding: 0.3em 0.7em; text-align: right; }
+th:first-child, td:first-child { text-align: left; }
+th { background: #f4f4f4; }
+.bad { color: #b00020; font-weight: bold; }
+.ok { color: #1b7f3b; font-weight: bold; }
+.meta { color: #666; }
+svg { background: #fafafa; border: 1px solid #eee; }
+"#;
+
+/// Chart size in pixels
+const WIDTH: f64 = 900.0;
+const HEIGHT: f64 = 200.0;
+
+pub fn write(report: &RunReport, path: &Path) -> Result<()> {
+    std::fs::write(path, render(report)).with_context(|| format!("writing {}", path.display()))
+}
+
+pub fn render(report: &RunReport) -> String {
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Load test {}</title><style>{}</style></head><body>\n",
+        escape(&report.run_id),
+        STYLE
+    );
+    let _ = writeln!(html, "<h1>Load test {}</h1>", escape(&report.run_id));
+    let _ = writeln!(
+        html,
+        "<p class=\"meta\">Target {} &middot; {:.0} s &middot; started at {} ms since the epoch</p>",
+        escape(&report.target),
+        report.duration_secs,
+        report.started_at_ms
+    );
+
+    let failed = report.breaches.len() + report.regressions.len();
+    if failed == 0 {
+        html.push_str("<p class=\"ok\">All thresholds met</p>\n");
+    } else {
+        let _ = writeln!(html, "<p class=\"bad\">{} threshold(s) breached or regressed</p>", failed);
+    }
+
+    summary(&mut html, report);
+    checks(&mut html, report);
+
+    html.push_str("<h2>Throughput</h2>\n");
+    timeline(&mut html, report);
+
+    for scenario in &report.scenarios {
+        details(&mut html, report, scenario);
+    }
+    html.push_str("</body></html>\n");
+    html
+}
+
+fn summary(html: &mut String, report: &RunReport) {
+    html.push_str("<h2>Summary</h2>\n<table><tr><th>scenario</th><th>requests</th><th>req/s</th><th>errors</th><th>mean ms</th><th>p50 ms</th><th>p95 ms</th><th>p99 ms</th><th>max ms</th><th>iterations</th></tr>\n");
+    for scenario in &report.scenarios {
+        let _ = writeln!(
+            html,
+            "<tr><td><a href=\"#{}\">{}</a></td><td>{}</td><td>{:.1}</td><td>{:.2}%</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{} ({} failed)</td></tr>",
+            anchor(&scenario.name),
+            escape(&scenario.name),
+            scenario.requests,
+            scenario.throughput,
+            scenario.error_rate * 100.0,
+            scenario.latency.mean_ms,
+            scenario.latency.p50_ms,
+            scenario.latency.p95_ms,
+            scenario.latency.p99_ms,
+            scenario.latency.max_ms,
+            scenario.iterations,
+            scenario.failed_iterations,
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn checks(html: &mut String, report: &RunReport) {
+    if !report.breaches.is_empty() {
+        html.push_str("<h2>Threshold breaches</h2>\n<table><tr><th>scenario</th><th>threshold</th><th>limit</th><th>actual</th></tr>\n");
+        for breach in &report.breaches {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{:.3}</td><td class=\"bad\">{:.3}</td></tr>",
+                escape(&breach.scenario),
+                escape(&breach.threshold),
+                breach.limit,
+                breach.actual
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    if !report.regressions.is_empty() {
+        html.push_str("<h2>Regressions against the baseline</h2>\n<table><tr><th>scenario</th><th>step</th><th>metric</th><th>baseline</th><th>current</th><th>change</th></tr>\n");
+        for regression in &report.regressions {
+            let change = if regression.metric == "error_rate" {
+                format!("{:+.2}pp", regression.change * 100.0)
+            } else {
+                format!("{:+.1}%", regression.change * 100.0)
+            };
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.3}</td><td>{:.3}</td><td class=\"bad\">{}</td></tr>",
+                escape(&regression.scenario),
+                escape(regression.step.as_deref().unwrap_or("-")),
+                escape(&regression.metric),
+                regression.baseline,
+                regression.current,
+                change
+            );
+        }
+        html.push_str("</table>\n");
+    }
+}
+
+/// Requests per second as a bar chart, errors stacked in red
+fn timeline(html: &mut String, report: &RunReport) {
+    if report.timeline.is_empty() {
+        html.push_str("<p class=\"meta\">No requests recorded</p>\n");
+        return;
+    }
+    let peak = report.timeline.iter().map(|point| point.requests).max().unwrap_or(0).max(1) as f64;
+    let bar = WIDTH / report.timeline.len() as f64;
+    let _ = writeln!(html, "<svg width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", WIDTH, HEIGHT + 20.0, WIDTH, HEIGHT + 20.0);
+    for point in &report.timeline {
+        let x = point.second as f64 * bar;
+        let height = point.requests as f64 / peak * HEIGHT;
+        let errors = point.errors as f64 / peak * HEIGHT;
+        let _ = writeln!(
+            html,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"#4c78a8\"><title>{}s: {} requests, {} errors</title></rect>",
+            x,
+            HEIGHT - height,
+            bar.max(1.0),
+            height,
+            point.second,
+            point.requests,
+            point.errors
+        );
+        if point.errors > 0 {
+            let _ = writeln!(
+                html,
+                "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"#e45756\"/>",
+                x,
+                HEIGHT - errors,
+                bar.max(1.0),
+                errors
+            );
+        }
+    }
+    let _ = writeln!(
+        html,
+        "<text x=\"4\" y=\"{}\" font-size=\"11\">0s</text><text x=\"{}\" y=\"{}\" font-size=\"11\" text-anchor=\"end\">{}s &middot; peak {} req/s</text>",
+        HEIGHT + 15.0,
+        WIDTH - 4.0,
+        HEIGHT + 15.0,
+        report.timeline.len(),
+        peak
+    );
+    html.push_str("</svg>\n");
+}
+
+fn details(html: &mut String, report: &RunReport, scenario: &ScenarioReport) {
+    let _ = writeln!(html, "<h2 id=\"{}\">{}</h2>", anchor(&scenario.name), escape(&scenario.name));
+    html.push_str("<h3>Latency distribution</h3>\n");
+    histogram(html, &report.histogram_bounds_ms, &scenario.histogram);
+
+    html.push_str("<h3>Steps</h3>\n<table><tr><th>step</th><th>requests</th><th>req/s</th><th>errors</th><th>mean ms</th><th>p50 ms</th><th>p95 ms</th><th>p99 ms</th><th>max ms</th></tr>\n");
+    for step in &scenario.steps {
+        let class = if step.errors > 0 { " class=\"bad\"" } else { "" };
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{:.1}</td><td{}>{:.2}%</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td><td>{:.1}</td></tr>",
+            escape(&step.name),
+            step.requests,
+            step.throughput,
+            class,
+            step.error_rate * 100.0,
+            step.latency.mean_ms,
+            step.latency.p50_ms,
+            step.latency.p95_ms,
+            step.latency.p99_ms,
+            step.latency.max_ms,
+        );
+    }
+    html.push_str("</table>\n");
+
+    if scenario.steps.iter().any(|step| !step.error_kinds.is_empty()) {
+        html.push_str("<h3>Errors</h3>\n<table><tr><th>step</th><th>kind</th><th>count</th></tr>\n");
+        for step in &scenario.steps {
+            for (kind, count) in &step.error_kinds {
+                let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td></tr>", escape(&step.name), escape(kind), count);
+            }
+        }
+        html.push_str("</table>\n");
+    }
+}
+
+/// Horizontal bars, one per bucket
+fn histogram(html: &mut String, bounds_ms: &[f64], counts: &[u64]) {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        html.push_str("<p class=\"meta\">No timed requests</p>\n");
+        return;
+    }
+    let peak = counts.iter().copied().max().unwrap_or(1).max(1) as f64;
+    let row = 18.0;
+    let label = 110.0;
+    let height = row * counts.len() as f64;
+    let _ = writeln!(html, "<svg width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", WIDTH, height, WIDTH, height);
+    for (bucket, &count) in counts.iter().enumerate() {
+        let name = match bounds_ms.get(bucket) {
+            Some(bound) => format!("&le; {} ms", bound),
+            None => format!("&gt; {} ms", bounds_ms.last().copied().unwrap_or(0.0)),
+        };
+        let y = bucket as f64 * row;
+        let width = count as f64 / peak * (WIDTH - label - 120.0);
+        let _ = writeln!(
+            html,
+            "<text x=\"4\" y=\"{:.1}\" font-size=\"11\">{}</text><rect x=\"{}\" y=\"{:.1}\" width=\"{:.2}\" height=\"{}\" fill=\"#4c78a8\"/><text x=\"{:.1}\" y=\"{:.1}\" font-size=\"11\">{} ({:.1}%)</text>",
+            y + 13.0,
+            name,
+            label,
+            y + 3.0,
+            width,
+            row - 5.0,
+            label + width + 6.0,
+            y + 13.0,
+            count,
+            count as f64 / total as f64 * 100.0
+        );
+    }
+    html.push_str("</svg>\n");
+}
+
+/// Escape text for element content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Element id for a scenario name
+fn anchor(name: &str) -> String {
+    let id: String = name.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' }).collect();
+    format!("scenario-{}", id)
+}
@@ -1,10 +1,36 @@
 //! Load Testing Tool
 //!
 //! Tool for load testing the Chaos World MMORPG backend services.
+//!
+//! Virtual users run the scenarios of a YAML test plan (see `configs/scenarios.yaml`)
+//! following its ramp, next to the plan's WebSocket sessions and gRPC streams. The
+//! run fails when a scenario breaches its thresholds or, with `--baseline`, regresses
+//! against an earlier run's JSON report.
+
+mod baseline;
+mod grpc;
+mod html;
+mod metrics;
+mod probe;
+mod report;
+mod runner;
+mod scenario;
+mod websocket;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
-use clap::Parser;
-use tracing::info;
+use anyhow::{bail, Result};
+use clap::{Parser, ValueEnum};
+use tracing::{error, info, warn};
+
+use crate::baseline::CompareOptions;
+use crate::grpc::{CombatCallers, GrpcStreams, StreamKind};
+use crate::report::RunReport;
+use crate::runner::{HttpUsers, RunOptions, Workload};
+use crate::scenario::TestPlan;
+use crate::websocket::WebSocketSessions;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -12,40 +38,209 @@ struct Args {
     /// Target URL
     #[arg(short, long, default_value = "http://localhost:8080")]
     target: String,
-    
-    /// Number of concurrent users
-    #[arg(short, long, default_value = "100")]
-    users: usize,
-    
-    /// Test duration in seconds
+
+    /// YAML test plan, `configs/scenarios.yaml` when omitted
+    #[arg(short, long)]
+    scenarios: Option<PathBuf>,
+
+    /// Number of concurrent users, replaces the plan's ramp with a flat profile
+    #[arg(short, long)]
+    users: Option<usize>,
+
+    /// Test duration in seconds for the flat profile
     #[arg(short, long, default_value = "60")]
     duration: u64,
-    
+
+    /// Seconds to reach `--users` in the flat profile
+    #[arg(long, default_value = "10")]
+    ramp_up: u64,
+
+    /// Request timeout in seconds
+    #[arg(long, default_value = "30")]
+    timeout: u64,
+
+    /// Seconds users get to finish their iteration after the run
+    #[arg(long, default_value = "30")]
+    grace_period: u64,
+
+    /// Seed for reproducible scenario mixes and think times
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Only run these parts of the plan
+    #[arg(long, value_enum)]
+    only: Vec<Protocol>,
+
+    /// NATS server probes are published on; probes are skipped without it
+    #[arg(long, env = "NATS_URL")]
+    nats_url: Option<String>,
+
+    /// Subject prefix shared with the services
+    #[arg(long, env = "NATS_SUBJECT_PREFIX")]
+    subject_prefix: Option<String>,
+
+    /// Write the results as JSON, usable as a later run's `--baseline`
+    #[arg(long)]
+    report_json: Option<PathBuf>,
+
+    /// Write a self-contained HTML report
+    #[arg(long)]
+    report_html: Option<PathBuf>,
+
+    /// JSON report of an earlier run to compare against
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+
+    /// Allowed relative latency increase over the baseline
+    #[arg(long, default_value = "0.10")]
+    max_regression: f64,
+
+    /// Allowed absolute error rate increase over the baseline
+    #[arg(long, default_value = "0.005")]
+    max_error_rate_increase: f64,
+
+    /// Steps with fewer requests in either run are not compared
+    #[arg(long, default_value = "100")]
+    baseline_min_requests: u64,
+
     /// Log level
     #[arg(short, long, default_value = "info")]
     log_level: String,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Protocol {
+    Http,
+    Websocket,
+    Grpc,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_env_filter(args.log_level)
         .init();
-    
+
+    let mut plan = TestPlan::load(args.scenarios.as_deref())?;
+    // Read up front so a bad path fails before the run, not after it
+    let baseline = args.baseline.as_deref().map(RunReport::read_json).transpose()?;
+    if let Some(users) = args.users {
+        plan = plan.with_flat_ramp(users, args.ramp_up, args.duration);
+        plan.validate()?;
+    }
+
+    let enabled = |protocol: Protocol| args.only.is_empty() || args.only.contains(&protocol);
+    let mut workloads: Vec<Arc<dyn Workload>> = Vec::new();
+    if enabled(Protocol::Http) && !plan.scenarios.is_empty() {
+        workloads.push(Arc::new(HttpUsers::new(&plan)?));
+    }
+    if let Some(websocket) = plan.websocket.clone().filter(|_| enabled(Protocol::Websocket)) {
+        workloads.push(Arc::new(WebSocketSessions::new(websocket)));
+    }
+    if let Some(grpc) = plan.grpc.clone().filter(|_| enabled(Protocol::Grpc)) {
+        workloads.push(Arc::new(GrpcStreams::new(grpc.clone(), StreamKind::ZoneEvents)));
+        workloads.push(Arc::new(GrpcStreams::new(grpc.clone(), StreamKind::CombatLog)));
+        if let Some(combat) = grpc.combat.clone() {
+            workloads.push(Arc::new(CombatCallers::new(grpc, combat)?));
+        }
+    }
+    workloads.retain(|workload| workload.peak() > 0);
+    if workloads.is_empty() {
+        bail!("Nothing to run, the plan has no workload for the selected protocols");
+    }
+
     info!("Starting load test against {}", args.target);
-    info!("Concurrent users: {}", args.users);
-    info!("Duration: {} seconds", args.duration);
-    
-    // TODO: Implement load testing logic
-    // TODO: Generate test data
-    // TODO: Simulate user behavior
-    // TODO: Collect metrics
-    // TODO: Generate report
-    
+    info!("Duration: {} seconds", plan.duration().as_secs());
+    for workload in &workloads {
+        info!("{}: up to {} worker(s)", workload.name(), workload.peak());
+    }
+    for scenario in plan.scenarios.iter().filter(|scenario| scenario.weight > 0.0 && enabled(Protocol::Http)) {
+        info!("Scenario {} (weight {}, {} steps)", scenario.name, scenario.weight, scenario.steps.len());
+    }
+
+    let started = Instant::now();
+    let started_at_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let options = RunOptions {
+        target: args.target.clone(),
+        request_timeout: Duration::from_secs(args.timeout),
+        grace_period: Duration::from_secs(args.grace_period),
+        seed: args.seed,
+    };
+    let peak = workloads.iter().map(|workload| workload.peak()).max().unwrap_or(0);
+    let ctx = runner::context(&options, peak)?;
+    let run_id = ctx.run_id.clone();
+
+    let probes = match (plan.probes.clone(), args.nats_url) {
+        (Some(probes), Some(nats_url)) => {
+            let bus = probe::BusOptions { nats_url, subject_prefix: args.subject_prefix };
+            Some(tokio::spawn(probe::publish(probes, plan.probe_zones(), bus, ctx.clone())))
+        }
+        (Some(_), None) => {
+            warn!("The plan has probes but no --nats-url is set, delivery latency is not measured");
+            None
+        }
+        _ => None,
+    };
+
+    let metrics = runner::run(&plan, workloads, &options, ctx).await?;
+    if let Some(probes) = probes {
+        match probes.await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => error!("Probe publisher failed: {:#}", e),
+            Err(e) => error!("Probe publisher panicked: {}", e),
+        }
+    }
+    let mut report = RunReport::new(&plan, &metrics, &run_id, &args.target, started_at_ms, started.elapsed());
+    if let Some(baseline) = &baseline {
+        let options = CompareOptions {
+            max_latency_regression: args.max_regression,
+            max_error_rate_increase: args.max_error_rate_increase,
+            min_requests: args.baseline_min_requests,
+        };
+        report.regressions = baseline::compare(baseline, &report, &options);
+    }
+
+    report.print();
+    if let Some(baseline) = &baseline {
+        baseline::print_diff(baseline, &report);
+    }
+    if let Some(path) = &args.report_json {
+        report.write_json(path)?;
+        info!("JSON report written to {}", path.display());
+    }
+    if let Some(path) = &args.report_html {
+        html::write(&report, path)?;
+        info!("HTML report written to {}", path.display());
+    }
+
+    for breach in &report.breaches {
+        error!(
+            "SLA breach in {}: {} is {:.3}, limit {:.3}",
+            breach.scenario, breach.threshold, breach.actual, breach.limit
+        );
+    }
+    for regression in &report.regressions {
+        error!(
+            "Regression in {}{}: {} went from {:.3} to {:.3}",
+            regression.scenario,
+            regression.step.as_deref().map(|step| format!(" / {}", step)).unwrap_or_default(),
+            regression.metric,
+            regression.baseline,
+            regression.current
+        );
+    }
+    if !report.breaches.is_empty() || !report.regressions.is_empty() {
+        bail!(
+            "{} threshold(s) breached, {} regression(s) against the baseline",
+            report.breaches.len(),
+            report.regressions.len()
+        );
+    }
+
     info!("Load test completed");
-    
+
     Ok(())
 }
@@ -0,0 +1,160 @@
+//! Request and iteration results collected while the test runs.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Shared between all virtual users
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    scenarios: Mutex<BTreeMap<String, ScenarioMetrics>>,
+    /// Requests and failures per second since the run started
+    timeline: Mutex<Vec<Second>>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Second {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ScenarioMetrics {
+    pub iterations: u64,
+    pub failed_iterations: u64,
+    /// Per step, in the order steps first ran
+    pub steps: Vec<(String, StepMetrics)>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct StepMetrics {
+    /// Requests and events, failed ones included
+    pub count: u64,
+    /// Latency of every timed request, including failed ones, in microseconds
+    pub latencies_us: Vec<u64>,
+    pub errors: u64,
+    /// Failures by status code or error kind
+    pub error_kinds: BTreeMap<String, u64>,
+}
+
+impl StepMetrics {
+    pub fn requests(&self) -> u64 {
+        self.count
+    }
+}
+
+impl ScenarioMetrics {
+    fn step(&mut self, step: &str) -> &mut StepMetrics {
+        let index = match self.steps.iter().position(|(name, _)| name == step) {
+            Some(index) => index,
+            None => {
+                self.steps.push((step.to_string(), StepMetrics::default()));
+                self.steps.len() - 1
+            }
+        };
+        &mut self.steps[index].1
+    }
+
+    pub fn requests(&self) -> u64 {
+        self.steps.iter().map(|(_, step)| step.requests()).sum()
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.steps.iter().map(|(_, step)| step.errors).sum()
+    }
+
+    /// Every request latency of the scenario, sorted
+    pub fn sorted_latencies(&self) -> Vec<u64> {
+        let mut latencies: Vec<u64> = self.steps.iter().flat_map(|(_, step)| step.latencies_us.iter().copied()).collect();
+        latencies.sort_unstable();
+        latencies
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            scenarios: Mutex::default(),
+            timeline: Mutex::default(),
+        }
+    }
+}
+
+impl Metrics {
+    /// Record one request; `error` is set when it failed
+    pub fn record_request(&self, scenario: &str, step: &str, latency: Duration, error: Option<String>) {
+        self.tick(error.is_some());
+        let mut scenarios = self.scenarios.lock().expect("metrics lock poisoned");
+        let metrics = scenarios.entry(scenario.to_string()).or_default().step(step);
+        metrics.count += 1;
+        metrics.latencies_us.push(latency.as_micros() as u64);
+        if let Some(kind) = error {
+            metrics.errors += 1;
+            *metrics.error_kinds.entry(kind).or_default() += 1;
+        }
+    }
+
+    /// Record a failure without a latency, e.g. a dropped connection
+    pub fn record_failure(&self, scenario: &str, step: &str, kind: String) {
+        self.tick(true);
+        let mut scenarios = self.scenarios.lock().expect("metrics lock poisoned");
+        let metrics = scenarios.entry(scenario.to_string()).or_default().step(step);
+        metrics.count += 1;
+        metrics.errors += 1;
+        *metrics.error_kinds.entry(kind).or_default() += 1;
+    }
+
+    pub fn record_iteration(&self, scenario: &str, failed: bool) {
+        let mut scenarios = self.scenarios.lock().expect("metrics lock poisoned");
+        let metrics = scenarios.entry(scenario.to_string()).or_default();
+        metrics.iterations += 1;
+        if failed {
+            metrics.failed_iterations += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<String, ScenarioMetrics> {
+        self.scenarios.lock().expect("metrics lock poisoned").clone()
+    }
+
+    pub fn timeline(&self) -> Vec<Second> {
+        self.timeline.lock().expect("metrics lock poisoned").clone()
+    }
+
+    fn tick(&self, failed: bool) {
+        let second = self.started.elapsed().as_secs() as usize;
+        let mut timeline = self.timeline.lock().expect("metrics lock poisoned");
+        if timeline.len() <= second {
+            timeline.resize(second + 1, Second::default());
+        }
+        timeline[second].requests += 1;
+        if failed {
+            timeline[second].errors += 1;
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values, in milliseconds
+pub fn percentile_ms(sorted_us: &[u64], percentile: f64) -> f64 {
+    if sorted_us.is_empty() {
+        return 0.0;
+    }
+    let rank = ((percentile / 100.0) * sorted_us.len() as f64).ceil() as usize;
+    sorted_us[rank.clamp(1, sorted_us.len()) - 1] as f64 / 1000.0
+}
+
+/// Upper bounds of the latency histogram buckets, in milliseconds
+pub const HISTOGRAM_BOUNDS_MS: [f64; 13] = [1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0];
+
+/// Count of latencies per [`HISTOGRAM_BOUNDS_MS`] bucket, plus one for slower ones
+pub fn histogram(latencies_us: &[u64]) -> Vec<u64> {
+    let mut counts = vec![0; HISTOGRAM_BOUNDS_MS.len() + 1];
+    for &latency in latencies_us {
+        let ms = latency as f64 / 1000.0;
+        let bucket = HISTOGRAM_BOUNDS_MS.iter().position(|&bound| ms <= bound).unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+        counts[bucket] += 1;
+    }
+    counts
+}
@@ -0,0 +1,116 @@
+//! Probe events for measuring delivery latency.
+//!
+//! While WebSocket sessions and gRPC streams are open, probes are published on the
+//! event bus with the time they were sent. Clients recognise them in event payloads
+//! and record the time to delivery, so the clocks of this machine are the only ones
+//! involved.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use shared::events::{EventBusExt, NatsEventBus, Topic};
+use tracing::{info, warn};
+
+use crate::runner::RunContext;
+use crate::scenario::ProbePlan;
+
+/// Payload field carrying the probe
+pub const PROBE_FIELD: &str = "load_test_probe";
+
+/// Metrics name of the publisher
+pub const PROBES: &str = "probes";
+
+/// Event source recorded on published probes
+const SOURCE: &str = "load-test";
+
+/// Combat log topic of the api crate's streams
+const COMBAT_LOG_TOPIC: &str = "combat.log";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Probe {
+    load_test_probe: ProbeMark,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProbeMark {
+    run: String,
+    sent_at_us: u64,
+}
+
+/// Event bus probes are published on
+pub struct BusOptions {
+    pub nats_url: String,
+    pub subject_prefix: Option<String>,
+}
+
+pub fn now_us() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_micros() as u64
+}
+
+/// Delivery latency of a probe of this run found in a JSON payload.
+///
+/// Bridges may forward the payload itself or the whole bus envelope, both are
+/// recognised.
+pub fn latency_json(payload: &serde_json::Value, run_id: &str) -> Option<Duration> {
+    let mark = payload.get(PROBE_FIELD).or_else(|| payload.get("payload")?.get(PROBE_FIELD))?;
+    if mark.get("run")?.as_str()? != run_id {
+        return None;
+    }
+    let sent_at_us = mark.get("sent_at_us")?.as_u64()?;
+    Some(Duration::from_micros(now_us().saturating_sub(sent_at_us)))
+}
+
+/// Delivery latency of a probe of this run found in a protobuf `Struct` payload
+pub fn latency_struct(payload: &prost_types::Struct, run_id: &str) -> Option<Duration> {
+    use prost_types::value::Kind;
+
+    let Some(Kind::StructValue(mark)) = payload.fields.get(PROBE_FIELD)?.kind.as_ref() else {
+        return None;
+    };
+    match mark.fields.get("run")?.kind.as_ref()? {
+        Kind::StringValue(run) if run == run_id => {}
+        _ => return None,
+    }
+    let Kind::NumberValue(sent_at_us) = mark.fields.get("sent_at_us")?.kind.as_ref()? else {
+        return None;
+    };
+    Some(Duration::from_micros(now_us().saturating_sub(*sent_at_us as u64)))
+}
+
+/// Publish probes on every zone, and the combat log if enabled, until the run stops
+pub async fn publish(plan: ProbePlan, zones: Vec<String>, bus: BusOptions, ctx: Arc<RunContext>) -> Result<()> {
+    let mut nats = NatsEventBus::connect(&bus.nats_url)
+        .await
+        .with_context(|| format!("connecting to {}", bus.nats_url))?;
+    if let Some(prefix) = &bus.subject_prefix {
+        nats = nats.with_subject_prefix(prefix)?;
+    }
+
+    // Same naming as the api crate's zone streams
+    let mut topics: Vec<Topic<Probe>> = zones.iter().map(|zone| Topic::dynamic(format!("world.zone.{}", zone))).collect();
+    if plan.combat_log {
+        topics.push(Topic::new(COMBAT_LOG_TOPIC));
+    }
+    if topics.is_empty() {
+        warn!("Probes are enabled but there are no zones to publish to");
+        return Ok(());
+    }
+    info!("Publishing {} probe(s)/s on {} topic(s)", plan.rate_per_sec, topics.len());
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / plan.rate_per_sec));
+    while !ctx.stopping() {
+        ticker.tick().await;
+        for topic in &topics {
+            let probe = Probe {
+                load_test_probe: ProbeMark { run: ctx.run_id.clone(), sent_at_us: now_us() },
+            };
+            let started = Instant::now();
+            let error = nats.publish_from(SOURCE, topic, &probe).await.err().map(|_| "publish".to_string());
+            ctx.metrics.record_request(PROBES, topic.name(), started.elapsed(), error);
+        }
+    }
+    nats.flush().await?;
+    Ok(())
+}
@@ -0,0 +1,267 @@
+//! End-of-run report: summary table, SLA checks and JSON export.
+//!
+//! A [`RunReport`] is what gets printed, written as JSON or HTML, and read back as
+//! the baseline of a later run.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::baseline::Regression;
+use crate::metrics::{histogram, percentile_ms, Metrics, StepMetrics, HISTOGRAM_BOUNDS_MS};
+use crate::scenario::TestPlan;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    pub run_id: String,
+    pub target: String,
+    /// Unix time the run started, in milliseconds
+    pub started_at_ms: u64,
+    pub duration_secs: f64,
+    /// Upper bounds of the histogram buckets; histograms have one more bucket for slower requests
+    pub histogram_bounds_ms: Vec<f64>,
+    pub scenarios: Vec<ScenarioReport>,
+    /// Requests and errors per second of the run
+    pub timeline: Vec<TimelinePoint>,
+    pub breaches: Vec<Breach>,
+    /// Filled when the run is compared against a baseline
+    #[serde(default)]
+    pub regressions: Vec<Regression>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    /// Requests per second over the run
+    pub throughput: f64,
+    pub iterations: u64,
+    pub failed_iterations: u64,
+    pub latency: LatencySummary,
+    pub histogram: Vec<u64>,
+    /// One entry per endpoint or step
+    pub steps: Vec<StepReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepReport {
+    pub name: String,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub throughput: f64,
+    pub latency: LatencySummary,
+    pub histogram: Vec<u64>,
+    /// Failures by status code or error kind
+    pub error_kinds: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelinePoint {
+    pub second: u64,
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/// A threshold a scenario did not meet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breach {
+    pub scenario: String,
+    pub threshold: String,
+    pub limit: f64,
+    pub actual: f64,
+}
+
+impl LatencySummary {
+    fn from_sorted(sorted_us: &[u64]) -> Self {
+        if sorted_us.is_empty() {
+            return Self::default();
+        }
+        Self {
+            mean_ms: sorted_us.iter().sum::<u64>() as f64 / sorted_us.len() as f64 / 1000.0,
+            p50_ms: percentile_ms(sorted_us, 50.0),
+            p95_ms: percentile_ms(sorted_us, 95.0),
+            p99_ms: percentile_ms(sorted_us, 99.0),
+            max_ms: percentile_ms(sorted_us, 100.0),
+        }
+    }
+}
+
+impl StepReport {
+    fn new(name: &str, step: &StepMetrics, seconds: f64) -> Self {
+        let mut latencies = step.latencies_us.clone();
+        latencies.sort_unstable();
+        Self {
+            name: name.to_string(),
+            requests: step.requests(),
+            errors: step.errors,
+            error_rate: rate(step.errors, step.requests()),
+            throughput: step.requests() as f64 / seconds,
+            latency: LatencySummary::from_sorted(&latencies),
+            histogram: histogram(&latencies),
+            error_kinds: step.error_kinds.clone(),
+        }
+    }
+}
+
+impl RunReport {
+    /// Summarize what was measured and check the plan's thresholds
+    pub fn new(plan: &TestPlan, metrics: &Metrics, run_id: &str, target: &str, started_at_ms: u64, elapsed: Duration) -> Self {
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let scenarios = metrics
+            .snapshot()
+            .into_iter()
+            .map(|(name, scenario)| {
+                let latencies = scenario.sorted_latencies();
+                ScenarioReport {
+                    requests: scenario.requests(),
+                    errors: scenario.errors(),
+                    error_rate: rate(scenario.errors(), scenario.requests()),
+                    throughput: scenario.requests() as f64 / seconds,
+                    iterations: scenario.iterations,
+                    failed_iterations: scenario.failed_iterations,
+                    latency: LatencySummary::from_sorted(&latencies),
+                    histogram: histogram(&latencies),
+                    steps: scenario.steps.iter().map(|(step_name, step)| StepReport::new(step_name, step, seconds)).collect(),
+                    name,
+                }
+            })
+            .collect();
+        let timeline = metrics
+            .timeline()
+            .into_iter()
+            .enumerate()
+            .map(|(second, point)| TimelinePoint { second: second as u64, requests: point.requests, errors: point.errors })
+            .collect();
+
+        let mut report = Self {
+            run_id: run_id.to_string(),
+            target: target.to_string(),
+            started_at_ms,
+            duration_secs: elapsed.as_secs_f64(),
+            histogram_bounds_ms: HISTOGRAM_BOUNDS_MS.to_vec(),
+            scenarios,
+            timeline,
+            breaches: Vec::new(),
+            regressions: Vec::new(),
+        };
+        report.breaches = report.check(plan);
+        report
+    }
+
+    pub fn scenario(&self, name: &str) -> Option<&ScenarioReport> {
+        self.scenarios.iter().find(|scenario| scenario.name == name)
+    }
+
+    /// Compare every scenario and workload against its thresholds
+    fn check(&self, plan: &TestPlan) -> Vec<Breach> {
+        let mut breaches = Vec::new();
+        for (name, thresholds) in plan.thresholds() {
+            // Scenarios that never ran have nothing to measure
+            let Some(result) = self.scenario(name) else {
+                continue;
+            };
+            let checks = [
+                ("p50_ms", thresholds.p50_ms, result.latency.p50_ms),
+                ("p95_ms", thresholds.p95_ms, result.latency.p95_ms),
+                ("p99_ms", thresholds.p99_ms, result.latency.p99_ms),
+                ("max_error_rate", thresholds.max_error_rate, result.error_rate),
+                (
+                    "max_failed_iterations",
+                    thresholds.max_failed_iterations,
+                    rate(result.failed_iterations, result.iterations),
+                ),
+            ];
+            for (threshold, limit, actual) in checks {
+                if let Some(limit) = limit {
+                    if actual > limit {
+                        breaches.push(Breach { scenario: name.to_string(), threshold: threshold.to_string(), limit, actual });
+                    }
+                }
+            }
+        }
+        breaches
+    }
+
+    pub fn print(&self) {
+        println!();
+        println!(
+            "{:<32} {:>9} {:>9} {:>8} {:>9} {:>9} {:>9} {:>9}",
+            "scenario / step", "requests", "req/s", "errors", "p50 ms", "p95 ms", "p99 ms", "max ms"
+        );
+        for scenario in &self.scenarios {
+            println!(
+                "{:<32} {:>9} {:>9.1} {:>7.2}% {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+                scenario.name,
+                scenario.requests,
+                scenario.throughput,
+                scenario.error_rate * 100.0,
+                scenario.latency.p50_ms,
+                scenario.latency.p95_ms,
+                scenario.latency.p99_ms,
+                scenario.latency.max_ms,
+            );
+            for step in &scenario.steps {
+                println!(
+                    "  {:<30} {:>9} {:>9.1} {:>7.2}% {:>9.1} {:>9.1} {:>9.1} {:>9.1}",
+                    step.name,
+                    step.requests,
+                    step.throughput,
+                    step.error_rate * 100.0,
+                    step.latency.p50_ms,
+                    step.latency.p95_ms,
+                    step.latency.p99_ms,
+                    step.latency.max_ms,
+                );
+                for (kind, count) in &step.error_kinds {
+                    println!("    {:<28} {:>9}", kind, count);
+                }
+            }
+            if scenario.iterations > 0 {
+                println!("  iterations: {} ({} failed)", scenario.iterations, scenario.failed_iterations);
+            }
+        }
+
+        if let Some(peak) = self.timeline.iter().max_by_key(|point| point.requests) {
+            println!();
+            println!(
+                "throughput: peak {} req/s at {}s, {} second(s) with errors",
+                peak.requests,
+                peak.second,
+                self.timeline.iter().filter(|point| point.errors > 0).count()
+            );
+        }
+    }
+
+    pub fn write_json(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).with_context(|| format!("writing {}", path.display()))
+    }
+
+    pub fn read_json(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        serde_json::from_str(&content).with_context(|| format!("parsing {}", path.display()))
+    }
+}
+
+pub fn rate(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
@@ -0,0 +1,358 @@
+//! Ramp controller and the HTTP virtual users.
+//!
+//! A controller follows the plan's ramp and scales every [`Workload`] to it, spawning
+//! workers as the wanted count grows. Workers above the current count finish what
+//! they are doing and idle until the ramp needs them again. When the ramp ends, or on
+//! Ctrl-C, workers get a grace period to finish before they are cancelled.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use rand::distributions::WeightedIndex;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use reqwest::{Client, Method};
+use tokio::sync::watch;
+use tracing::{debug, info, warn};
+
+use crate::metrics::Metrics;
+use crate::scenario::{render, render_json, Scenario, Step, TestPlan};
+
+/// How often the controller adjusts the number of workers
+const TICK: Duration = Duration::from_millis(200);
+
+pub struct RunOptions {
+    /// Base URL requests are sent to
+    pub target: String,
+    pub request_timeout: Duration,
+    /// How long workers may take to finish once the run is over
+    pub grace_period: Duration,
+    /// Seed for reproducible scenario picks and think times
+    pub seed: Option<u64>,
+}
+
+/// State shared by every worker of a run
+pub struct RunContext {
+    pub client: Client,
+    /// Target URL without a trailing slash
+    pub target: String,
+    pub run_id: String,
+    pub metrics: Arc<Metrics>,
+    stopping: AtomicBool,
+}
+
+impl RunContext {
+    pub fn stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Variables every worker starts an iteration with
+    pub fn vars(&self, index: usize, iteration: u64) -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("user".to_string(), index.to_string()),
+            ("iteration".to_string(), iteration.to_string()),
+            ("run".to_string(), self.run_id.clone()),
+        ])
+    }
+}
+
+/// A kind of load, scaled to the ramp
+pub trait Workload: Send + Sync + 'static {
+    fn name(&self) -> &str;
+
+    /// Workers at the ramp's peak
+    fn peak(&self) -> usize;
+
+    /// Run one worker until the run is over
+    fn run(self: Arc<Self>, worker: Worker) -> BoxFuture<'static, ()>;
+}
+
+/// One worker of a workload
+pub struct Worker {
+    pub index: usize,
+    pub rng: StdRng,
+    pub ramp: RampSignal,
+    pub ctx: Arc<RunContext>,
+}
+
+/// Tells a worker whether the ramp currently wants it
+pub struct RampSignal {
+    index: usize,
+    wanted: watch::Receiver<usize>,
+    ctx: Arc<RunContext>,
+}
+
+impl RampSignal {
+    /// Wait until the ramp wants this worker, `false` once the run is over
+    pub async fn active(&mut self) -> bool {
+        loop {
+            if self.ctx.stopping() {
+                return false;
+            }
+            if self.index < *self.wanted.borrow_and_update() {
+                return true;
+            }
+            if self.wanted.changed().await.is_err() {
+                return false;
+            }
+        }
+    }
+
+    /// Resolve once the ramp no longer wants this worker or the run is over
+    pub async fn released(&mut self) {
+        loop {
+            if self.ctx.stopping() || self.index >= *self.wanted.borrow_and_update() {
+                return;
+            }
+            if self.wanted.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+struct Pool {
+    workload: Arc<dyn Workload>,
+    wanted: watch::Sender<usize>,
+    workers: Vec<tokio::task::JoinHandle<()>>,
+}
+
+/// Run the workloads along the plan's ramp, returning what was measured
+pub async fn run(plan: &TestPlan, workloads: Vec<Arc<dyn Workload>>, options: &RunOptions, ctx: Arc<RunContext>) -> Result<Arc<Metrics>> {
+    let mut pools: Vec<Pool> = workloads
+        .into_iter()
+        .map(|workload| Pool { workload, wanted: watch::channel(0).0, workers: Vec::new() })
+        .collect();
+    let started = Instant::now();
+    let mut ticker = tokio::time::interval(TICK);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = &mut ctrl_c => {
+                warn!("Interrupted, stopping early");
+                break;
+            }
+        }
+        let Some(load) = plan.load_at(started.elapsed()) else {
+            break;
+        };
+        for (position, pool) in pools.iter_mut().enumerate() {
+            let wanted = (pool.workload.peak() as f64 * load).round() as usize;
+            if wanted != *pool.wanted.borrow() {
+                debug!("{}: {} worker(s)", pool.workload.name(), wanted);
+                pool.wanted.send_replace(wanted);
+            }
+            while pool.workers.len() < wanted {
+                let index = pool.workers.len();
+                let rng = match options.seed {
+                    Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(((position as u64) << 32) | index as u64)),
+                    None => StdRng::from_entropy(),
+                };
+                let worker = Worker {
+                    index,
+                    rng,
+                    ramp: RampSignal { index, wanted: pool.wanted.subscribe(), ctx: ctx.clone() },
+                    ctx: ctx.clone(),
+                };
+                pool.workers.push(tokio::spawn(pool.workload.clone().run(worker)));
+            }
+        }
+    }
+
+    let spawned: usize = pools.iter().map(|pool| pool.workers.len()).sum();
+    info!("Run over after {:.1}s, waiting for {} worker(s) to finish", started.elapsed().as_secs_f64(), spawned);
+    ctx.stopping.store(true, Ordering::SeqCst);
+    for pool in &pools {
+        pool.wanted.send_replace(0);
+    }
+    let deadline = tokio::time::Instant::now() + options.grace_period;
+    for worker in pools.into_iter().flat_map(|pool| pool.workers) {
+        let abort = worker.abort_handle();
+        if tokio::time::timeout_at(deadline, worker).await.is_err() {
+            abort.abort();
+        }
+    }
+    Ok(ctx.metrics.clone())
+}
+
+/// Context shared by the workers of a run and anything running beside them
+pub fn context(options: &RunOptions, max_connections: usize) -> Result<Arc<RunContext>> {
+    let client = Client::builder()
+        .timeout(options.request_timeout)
+        .pool_max_idle_per_host(max_connections)
+        .build()
+        .context("building HTTP client")?;
+    Ok(Arc::new(RunContext {
+        client,
+        target: options.target.trim_end_matches('/').to_string(),
+        run_id: run_id(),
+        metrics: Arc::new(Metrics::default()),
+        stopping: AtomicBool::new(false),
+    }))
+}
+
+fn run_id() -> String {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis().to_string()
+}
+
+/// Virtual users running the weighted HTTP scenarios
+pub struct HttpUsers {
+    scenarios: Vec<Scenario>,
+    mix: WeightedIndex<f64>,
+    peak: usize,
+}
+
+impl HttpUsers {
+    pub fn new(plan: &TestPlan) -> Result<Self> {
+        let mix = WeightedIndex::new(plan.scenarios.iter().map(|scenario| scenario.weight)).context("scenario weights")?;
+        Ok(Self { scenarios: plan.scenarios.clone(), mix, peak: plan.max_users() })
+    }
+}
+
+impl Workload for HttpUsers {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn peak(&self) -> usize {
+        self.peak
+    }
+
+    fn run(self: Arc<Self>, mut worker: Worker) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let mut iteration = 0u64;
+            while worker.ramp.active().await {
+                let scenario = &self.scenarios[self.mix.sample(&mut worker.rng)];
+                let mut vars = worker.ctx.vars(worker.index, iteration);
+                iteration += 1;
+                let completed = run_scenario(&worker.ctx, scenario, &mut vars, &mut worker.rng).await;
+                worker.ctx.metrics.record_iteration(&scenario.name, !completed);
+            }
+        })
+    }
+}
+
+/// Run one iteration, `false` when a required step failed
+async fn run_scenario(ctx: &RunContext, scenario: &Scenario, vars: &mut BTreeMap<String, String>, rng: &mut StdRng) -> bool {
+    for step in &scenario.steps {
+        let times = step.repeat.map(|(min, max)| rng.gen_range(min..=max)).unwrap_or(1);
+        for _ in 0..times {
+            let completed = run_step(ctx, &scenario.name, step, vars, rng).await;
+            if !completed && step.required {
+                return false;
+            }
+            let think_time = step.think_time.as_ref().unwrap_or(&scenario.think_time).sample(rng);
+            if !think_time.is_zero() {
+                tokio::time::sleep(think_time).await;
+            }
+        }
+    }
+    true
+}
+
+/// Run and record one HTTP step under `name`, `false` when it failed
+pub async fn run_step(ctx: &RunContext, name: &str, step: &Step, vars: &mut BTreeMap<String, String>, rng: &mut StdRng) -> bool {
+    match execute(ctx, step, vars, rng).await {
+        Ok(latency) => {
+            ctx.metrics.record_request(name, &step.name, latency, None);
+            true
+        }
+        Err(error) => {
+            debug!("{} / {}: {}", name, step.name, error.message);
+            ctx.metrics.record_request(name, &step.name, error.latency, Some(error.kind));
+            false
+        }
+    }
+}
+
+struct StepError {
+    /// Grouping key in the report, e.g. `status 503` or `timeout`
+    kind: String,
+    message: String,
+    latency: Duration,
+}
+
+/// Send one request and capture its variables, returning its latency
+async fn execute(ctx: &RunContext, step: &Step, vars: &mut BTreeMap<String, String>, rng: &mut StdRng) -> Result<Duration, StepError> {
+    // Validated on load
+    let method = Method::from_bytes(step.method.as_bytes()).unwrap_or(Method::GET);
+    let url = format!("{}{}", ctx.target, render(&step.path, vars, rng));
+    let mut request = ctx.client.request(method, &url);
+    for (name, value) in &step.headers {
+        request = request.header(name, render(value, vars, rng));
+    }
+    if let Some(body) = &step.body {
+        request = request.json(&render_json(body, vars, rng));
+    }
+
+    let started = Instant::now();
+    let fail = |kind: String, message: String| StepError { kind, message, latency: started.elapsed() };
+    let response = request.send().await.map_err(|e| fail(transport_kind(&e).to_string(), e.to_string()))?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(|e| fail(transport_kind(&e).to_string(), e.to_string()))?;
+    let latency = started.elapsed();
+
+    let expected = if step.expect_status.is_empty() {
+        status.is_success()
+    } else {
+        step.expect_status.contains(&status.as_u16())
+    };
+    if !expected {
+        return Err(StepError {
+            kind: format!("status {}", status.as_u16()),
+            message: format!("{} returned {}", url, status),
+            latency,
+        });
+    }
+
+    if !step.extract.is_empty() {
+        let json: serde_json::Value = serde_json::from_slice(&body).map_err(|e| StepError {
+            kind: "invalid json".to_string(),
+            message: format!("{}: {}", url, e),
+            latency,
+        })?;
+        for (name, pointer) in &step.extract {
+            let value = json.pointer(pointer).ok_or_else(|| StepError {
+                kind: format!("missing {}", pointer),
+                message: format!("{}: response has no {}", url, pointer),
+                latency,
+            })?;
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            vars.insert(name.clone(), value);
+        }
+    }
+
+    Ok(latency)
+}
+
+fn transport_kind(error: &reqwest::Error) -> &'static str {
+    if error.is_timeout() {
+        "timeout"
+    } else if error.is_connect() {
+        "connect"
+    } else if error.is_body() || error.is_decode() {
+        "body"
+    } else {
+        "request"
+    }
+}
+
+/// Back off after a failed connection so a down server is not hammered
+pub async fn backoff(worker: &mut Worker, failures: u32) {
+    let base = Duration::from_millis(250 * 2u64.pow(failures.min(5)));
+    let jitter = Duration::from_millis(worker.rng.gen_range(0..250));
+    tokio::select! {
+        _ = tokio::time::sleep(base + jitter) => {}
+        _ = worker.ramp.released() => {}
+    }
+}
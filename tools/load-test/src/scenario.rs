@@ -0,0 +1,485 @@
+//! Scenario definitions loaded from YAML.
+//!
+//! A test plan names the scenarios virtual users pick from, weighted, and how the
+//! number of users changes over time. A scenario is a list of HTTP steps run in
+//! order; steps can capture values from JSON responses into variables that later
+//! steps use as `{{name}}` in their path, headers and body. Every user also has:
+//!
+//! - `{{user}}`: the virtual user number
+//! - `{{iteration}}`: how many scenarios this user has started
+//! - `{{run}}`: an identifier shared by the whole run
+//! - `{{random}}`: a fresh random number on every use
+//!
+//! Variables live for one scenario iteration, so each iteration logs in again.
+//!
+//! Besides HTTP scenarios a plan can hold WebSocket sessions and gRPC streams. Their
+//! counts follow the shape of the same ramp, scaled to their own peak.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use rand::Rng;
+use rand_distr::{Distribution, Exp, Normal};
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PLAN: &str = include_str!("../configs/scenarios.yaml");
+
+/// Everything a run needs besides the target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestPlan {
+    /// How many users are active over the run
+    pub ramp: Vec<RampStage>,
+    #[serde(default)]
+    pub scenarios: Vec<Scenario>,
+    #[serde(default)]
+    pub websocket: Option<WebSocketPlan>,
+    #[serde(default)]
+    pub grpc: Option<GrpcPlan>,
+    /// Events published on the bus to measure delivery latency
+    #[serde(default)]
+    pub probes: Option<ProbePlan>,
+}
+
+/// Metrics names of the non-HTTP workloads
+pub const WEBSOCKET: &str = "websocket";
+pub const WEBSOCKET_DELIVERY: &str = "websocket delivery";
+pub const GRPC: &str = "grpc";
+pub const GRPC_DELIVERY: &str = "grpc delivery";
+
+/// Move linearly from the previous stage's user count to `users` over `duration_secs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RampStage {
+    pub duration_secs: u64,
+    pub users: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Share of iterations running this scenario, relative to the other weights
+    pub weight: f64,
+    /// Pause after each step unless the step sets its own
+    #[serde(default)]
+    pub think_time: ThinkTime,
+    pub steps: Vec<Step>,
+    #[serde(default)]
+    pub thresholds: Thresholds,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Step {
+    pub name: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Path appended to the target URL
+    pub path: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// JSON body, strings in it are templated
+    #[serde(default)]
+    pub body: Option<serde_json::Value>,
+    /// Accepted statuses, any 2xx when empty
+    #[serde(default)]
+    pub expect_status: Vec<u16>,
+    /// Variables to capture, as JSON pointers into the response body
+    #[serde(default)]
+    pub extract: BTreeMap<String, String>,
+    /// Run the step a random number of times in this inclusive range
+    #[serde(default)]
+    pub repeat: Option<(u32, u32)>,
+    #[serde(default)]
+    pub think_time: Option<ThinkTime>,
+    /// Abort the iteration when the step fails
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// Pause between steps, in milliseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ThinkTime {
+    #[default]
+    None,
+    Constant { ms: u64 },
+    Uniform { min_ms: u64, max_ms: u64 },
+    Normal { mean_ms: f64, std_dev_ms: f64 },
+    Exponential { mean_ms: f64 },
+}
+
+impl ThinkTime {
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        let ms = match *self {
+            ThinkTime::None => 0.0,
+            ThinkTime::Constant { ms } => ms as f64,
+            ThinkTime::Uniform { min_ms, max_ms } => rng.gen_range(min_ms..=max_ms) as f64,
+            // Validated on load
+            ThinkTime::Normal { mean_ms, std_dev_ms } => Normal::new(mean_ms, std_dev_ms).map(|normal| normal.sample(rng)).unwrap_or(mean_ms),
+            ThinkTime::Exponential { mean_ms } => Exp::new(1.0 / mean_ms).map(|exp| exp.sample(rng)).unwrap_or(mean_ms),
+        };
+        Duration::from_secs_f64(ms.max(0.0) / 1000.0)
+    }
+
+    fn validate(&self) -> Result<()> {
+        match *self {
+            ThinkTime::Uniform { min_ms, max_ms } if min_ms > max_ms => bail!("uniform think time has min_ms above max_ms"),
+            ThinkTime::Normal { std_dev_ms, .. } if !std_dev_ms.is_finite() || std_dev_ms <= 0.0 => {
+                bail!("normal think time needs a positive std_dev_ms")
+            }
+            ThinkTime::Exponential { mean_ms } if !mean_ms.is_finite() || mean_ms <= 0.0 => {
+                bail!("exponential think time needs a positive mean_ms")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Long-lived WebSocket sessions subscribed to channels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketPlan {
+    /// Upgrade endpoint on the target
+    pub path: String,
+    /// Sessions at the ramp's peak
+    pub connections: usize,
+    /// Run before each connection, e.g. to log in and extract `token`
+    #[serde(default)]
+    pub login: Option<Step>,
+    /// Handshake headers, templated
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// `json` or `protobuf` stat updates
+    #[serde(default = "default_encoding")]
+    pub encoding: String,
+    /// Channels to subscribe to, templated; `{{zone}}` is a random entry of `zones`
+    pub channels: Vec<String>,
+    #[serde(default)]
+    pub zones: Vec<String>,
+    /// Application-level ping, measured as a round trip; 0 disables it
+    #[serde(default)]
+    pub ping_interval_secs: u64,
+    /// Reconnect after a random lifetime instead of keeping sessions open
+    #[serde(default)]
+    pub churn: Option<Churn>,
+    /// Over connect, subscribe and ping
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    /// Over the delivery latency of probe events
+    #[serde(default)]
+    pub delivery_thresholds: Thresholds,
+}
+
+fn default_encoding() -> String {
+    "json".to_string()
+}
+
+/// gRPC streams and combat actions against the api crate's services
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcPlan {
+    /// gRPC server, e.g. `http://localhost:50051`
+    pub endpoint: String,
+    /// HTTP step run against the target before connecting, e.g. to log in
+    #[serde(default)]
+    pub login: Option<Step>,
+    /// Request metadata, templated
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// `SubscribeZoneEvents` streams at the ramp's peak
+    #[serde(default)]
+    pub zone_streams: usize,
+    /// `SubscribeCombatLog` streams at the ramp's peak
+    #[serde(default)]
+    pub combat_log_streams: usize,
+    /// Zones streams pick from
+    #[serde(default)]
+    pub zones: Vec<String>,
+    #[serde(default)]
+    pub event_types: Vec<String>,
+    /// Reconnect streams after a random lifetime, resuming from the last token
+    #[serde(default)]
+    pub churn: Option<Churn>,
+    #[serde(default)]
+    pub combat: Option<CombatPlan>,
+    /// Over connect, subscribe and unary calls
+    #[serde(default)]
+    pub thresholds: Thresholds,
+    /// Over the delivery latency of probe events
+    #[serde(default)]
+    pub delivery_thresholds: Thresholds,
+}
+
+/// `SubmitAction` callers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CombatPlan {
+    /// Callers at the ramp's peak
+    pub callers: usize,
+    #[serde(default)]
+    pub think_time: ThinkTime,
+    pub attackers: Vec<String>,
+    pub targets: Vec<String>,
+    pub skills: Vec<String>,
+}
+
+/// Session lifetime range for connection churn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Churn {
+    pub min_secs: u64,
+    pub max_secs: u64,
+}
+
+impl Churn {
+    pub fn sample(&self, rng: &mut impl Rng) -> Duration {
+        Duration::from_secs(rng.gen_range(self.min_secs..=self.max_secs))
+    }
+}
+
+/// Probe events published on zone topics and the combat log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbePlan {
+    /// Probes per second on each topic
+    pub rate_per_sec: f64,
+    /// Zones to publish to, all zones of the WebSocket and gRPC sections when empty
+    #[serde(default)]
+    pub zones: Vec<String>,
+    /// Also publish on the combat log
+    #[serde(default)]
+    pub combat_log: bool,
+}
+
+/// SLA of a scenario, over every request it made. Unset limits are not checked.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    /// Highest share of failed requests, 0.01 is 1%
+    pub max_error_rate: Option<f64>,
+    /// Highest share of iterations aborted by a failed required step
+    pub max_failed_iterations: Option<f64>,
+}
+
+impl TestPlan {
+    /// Load a plan, the built-in `configs/scenarios.yaml` when no path is given
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let plan: Self = match path {
+            Some(path) => {
+                let content = std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+                serde_yaml::from_str(&content).with_context(|| format!("parsing {}", path.display()))?
+            }
+            None => serde_yaml::from_str(DEFAULT_PLAN).context("parsing built-in scenarios")?,
+        };
+        plan.validate()?;
+        Ok(plan)
+    }
+
+    /// Replace the ramp with `ramp_up_secs` to `users`, then `duration_secs` holding them
+    pub fn with_flat_ramp(mut self, users: usize, ramp_up_secs: u64, duration_secs: u64) -> Self {
+        self.ramp = vec![
+            RampStage { duration_secs: ramp_up_secs, users },
+            RampStage { duration_secs, users },
+        ];
+        self
+    }
+
+    /// Users that should be active `elapsed` into the run, `None` once the ramp is over
+    pub fn users_at(&self, elapsed: Duration) -> Option<usize> {
+        let mut from = 0usize;
+        let mut start = Duration::ZERO;
+        for stage in &self.ramp {
+            let length = Duration::from_secs(stage.duration_secs);
+            if elapsed < start + length {
+                let progress = (elapsed - start).as_secs_f64() / length.as_secs_f64();
+                let users = from as f64 + (stage.users as f64 - from as f64) * progress;
+                return Some(users.round() as usize);
+            }
+            from = stage.users;
+            start += length;
+        }
+        None
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.ramp.iter().map(|stage| stage.duration_secs).sum())
+    }
+
+    pub fn max_users(&self) -> usize {
+        self.ramp.iter().map(|stage| stage.users).max().unwrap_or(0)
+    }
+
+    /// Share of the peak active `elapsed` into the run, `None` once the ramp is over
+    pub fn load_at(&self, elapsed: Duration) -> Option<f64> {
+        let peak = self.max_users().max(1);
+        self.users_at(elapsed).map(|users| users as f64 / peak as f64)
+    }
+
+    /// Thresholds by metrics name, for every workload in the plan
+    pub fn thresholds(&self) -> Vec<(&str, &Thresholds)> {
+        let mut thresholds: Vec<(&str, &Thresholds)> =
+            self.scenarios.iter().map(|scenario| (scenario.name.as_str(), &scenario.thresholds)).collect();
+        if let Some(websocket) = &self.websocket {
+            thresholds.push((WEBSOCKET, &websocket.thresholds));
+            thresholds.push((WEBSOCKET_DELIVERY, &websocket.delivery_thresholds));
+        }
+        if let Some(grpc) = &self.grpc {
+            thresholds.push((GRPC, &grpc.thresholds));
+            thresholds.push((GRPC_DELIVERY, &grpc.delivery_thresholds));
+        }
+        thresholds
+    }
+
+    /// Zones probes are published to
+    pub fn probe_zones(&self) -> Vec<String> {
+        let mut zones: Vec<String> = match &self.probes {
+            Some(probes) if !probes.zones.is_empty() => probes.zones.clone(),
+            _ => self
+                .websocket
+                .iter()
+                .flat_map(|websocket| websocket.zones.iter())
+                .chain(self.grpc.iter().flat_map(|grpc| grpc.zones.iter()))
+                .cloned()
+                .collect(),
+        };
+        zones.sort();
+        zones.dedup();
+        zones
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.ramp.is_empty() || self.duration().is_zero() || self.max_users() == 0 {
+            bail!("The ramp needs at least one stage with a duration and users");
+        }
+        if !self.scenarios.is_empty() && !self.scenarios.iter().any(|scenario| scenario.weight > 0.0) {
+            bail!("At least one scenario needs a positive weight");
+        }
+        if self.scenarios.is_empty() && self.websocket.is_none() && self.grpc.is_none() {
+            bail!("The plan has no scenarios, websocket or grpc section");
+        }
+        for scenario in &self.scenarios {
+            let context = || format!("scenario {}", scenario.name);
+            if !scenario.weight.is_finite() || scenario.weight < 0.0 {
+                bail!("{}: weight must be zero or positive", context());
+            }
+            if scenario.steps.is_empty() {
+                bail!("{}: has no steps", context());
+            }
+            scenario.think_time.validate().with_context(context)?;
+            for step in &scenario.steps {
+                step.validate().with_context(|| format!("scenario {}, step {}", scenario.name, step.name))?;
+            }
+        }
+
+        if let Some(websocket) = &self.websocket {
+            if websocket.channels.is_empty() {
+                bail!("websocket: no channels to subscribe to");
+            }
+            if websocket.channels.iter().any(|channel| channel.contains("{{zone}}")) && websocket.zones.is_empty() {
+                bail!("websocket: channels use {{{{zone}}}} but no zones are listed");
+            }
+            if !["json", "protobuf"].contains(&websocket.encoding.as_str()) {
+                bail!("websocket: encoding must be json or protobuf");
+            }
+            if let Some(login) = &websocket.login {
+                login.validate().context("websocket login")?;
+            }
+            if let Some(churn) = &websocket.churn {
+                churn.validate().context("websocket")?;
+            }
+        }
+
+        if let Some(grpc) = &self.grpc {
+            if grpc.zone_streams > 0 && grpc.zones.is_empty() {
+                bail!("grpc: zone streams need zones");
+            }
+            if let Some(login) = &grpc.login {
+                login.validate().context("grpc login")?;
+            }
+            if let Some(churn) = &grpc.churn {
+                churn.validate().context("grpc")?;
+            }
+            if let Some(combat) = &grpc.combat {
+                if combat.attackers.is_empty() || combat.targets.is_empty() || combat.skills.is_empty() {
+                    bail!("grpc combat: needs attackers, targets and skills");
+                }
+                combat.think_time.validate().context("grpc combat")?;
+            }
+        }
+
+        if let Some(probes) = &self.probes {
+            if !probes.rate_per_sec.is_finite() || probes.rate_per_sec <= 0.0 {
+                bail!("probes: rate_per_sec must be positive");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Step {
+    fn validate(&self) -> Result<()> {
+        reqwest::Method::from_bytes(self.method.as_bytes()).with_context(|| format!("unknown method {}", self.method))?;
+        if let Some((min, max)) = self.repeat {
+            if min > max {
+                bail!("repeat range is empty");
+            }
+        }
+        if let Some(think_time) = &self.think_time {
+            think_time.validate()?;
+        }
+        if let Some(pointer) = self.extract.values().find(|pointer| !pointer.is_empty() && !pointer.starts_with('/')) {
+            bail!("extract pointer {:?} must start with /", pointer);
+        }
+        Ok(())
+    }
+}
+
+impl Churn {
+    fn validate(&self) -> Result<()> {
+        if self.min_secs == 0 || self.min_secs > self.max_secs {
+            bail!("churn needs 0 < min_secs <= max_secs");
+        }
+        Ok(())
+    }
+}
+
+/// Substitute `{{name}}` placeholders; unknown names are left as they are
+pub fn render(template: &str, vars: &BTreeMap<String, String>, rng: &mut impl Rng) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            out.push_str(&rest[start..]);
+            return out;
+        };
+        let name = after[..end].trim();
+        match (name, vars.get(name)) {
+            ("random", _) => out.push_str(&rng.gen::<u32>().to_string()),
+            (_, Some(value)) => out.push_str(value),
+            (_, None) => out.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Template every string inside a JSON body
+pub fn render_json(value: &serde_json::Value, vars: &BTreeMap<String, String>, rng: &mut impl Rng) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(render(s, vars, rng)),
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.iter().map(|item| render_json(item, vars, rng)).collect()),
+        serde_json::Value::Object(map) => {
+            serde_json::Value::Object(map.iter().map(|(key, item)| (key.clone(), render_json(item, vars, rng))).collect())
+        }
+        other => other.clone(),
+    }
+}
@@ -0,0 +1,228 @@
+//! WebSocket sessions against the gateway's upgrade path.
+//!
+//! Each worker holds one session: it optionally logs in over HTTP, upgrades, subscribes
+//! to its channels and stays connected, pinging if configured, until the ramp releases
+//! it. With churn enabled sessions close after a random lifetime and reconnect, which
+//! keeps the gateway busy with fresh upgrades.
+//!
+//! Recorded under `websocket`: `login`, `connect` (the upgrade handshake), `subscribe`
+//! (until `subscribed`), `ping` (round trip) and `session` (unexpected closes and
+//! server errors). Probe delivery latency goes under `websocket delivery`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use futures::{SinkExt, StreamExt};
+use rand::seq::SliceRandom;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tracing::debug;
+
+use crate::probe;
+use crate::runner::{backoff, run_step, Worker, Workload};
+use crate::scenario::{render, WebSocketPlan, WEBSOCKET, WEBSOCKET_DELIVERY};
+
+pub struct WebSocketSessions {
+    plan: WebSocketPlan,
+}
+
+impl WebSocketSessions {
+    pub fn new(plan: WebSocketPlan) -> Self {
+        Self { plan }
+    }
+}
+
+impl Workload for WebSocketSessions {
+    fn name(&self) -> &str {
+        WEBSOCKET
+    }
+
+    fn peak(&self) -> usize {
+        self.plan.connections
+    }
+
+    fn run(self: Arc<Self>, mut worker: Worker) -> BoxFuture<'static, ()> {
+        Box::pin(async move {
+            let mut iteration = 0u64;
+            let mut failures = 0u32;
+            while worker.ramp.active().await {
+                let connected = self.session(&mut worker, iteration).await;
+                iteration += 1;
+                failures = if connected { 0 } else { failures + 1 };
+                if failures > 0 {
+                    backoff(&mut worker, failures).await;
+                }
+            }
+        })
+    }
+}
+
+impl WebSocketSessions {
+    /// Run one session, `false` when it could not be established
+    async fn session(&self, worker: &mut Worker, iteration: u64) -> bool {
+        let ctx = worker.ctx.clone();
+        let metrics = &ctx.metrics;
+        let mut vars = ctx.vars(worker.index, iteration);
+        if let Some(zone) = self.plan.zones.choose(&mut worker.rng) {
+            vars.insert("zone".to_string(), zone.clone());
+        }
+        if let Some(login) = &self.plan.login {
+            if !run_step(&ctx, WEBSOCKET, login, &mut vars, &mut worker.rng).await {
+                return false;
+            }
+        }
+
+        let path = render(&self.plan.path, &vars, &mut worker.rng);
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let url = format!("{}{}{}encoding={}", websocket_url(&ctx.target), path, separator, self.plan.encoding);
+        let mut request = match url.as_str().into_client_request() {
+            Ok(request) => request,
+            Err(e) => {
+                metrics.record_failure(WEBSOCKET, "connect", "invalid url".to_string());
+                debug!("{}: {}", url, e);
+                return false;
+            }
+        };
+        for (name, value) in &self.plan.headers {
+            let value = render(value, &vars, &mut worker.rng);
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&value)) {
+                request.headers_mut().insert(name, value);
+            }
+        }
+
+        let started = Instant::now();
+        let socket = match tokio_tungstenite::connect_async(request).await {
+            Ok((socket, _)) => {
+                metrics.record_request(WEBSOCKET, "connect", started.elapsed(), None);
+                socket
+            }
+            Err(e) => {
+                let kind = match &e {
+                    WsError::Http(response) => format!("status {}", response.status().as_u16()),
+                    _ => "connect".to_string(),
+                };
+                debug!("{}: {}", url, e);
+                metrics.record_request(WEBSOCKET, "connect", started.elapsed(), Some(kind));
+                return false;
+            }
+        };
+        let (mut sink, mut stream) = socket.split();
+
+        let mut pending: HashMap<String, Instant> = HashMap::new();
+        for channel in &self.plan.channels {
+            let channel = render(channel, &vars, &mut worker.rng);
+            let subscribe = serde_json::json!({ "type": "subscribe", "channel": channel });
+            pending.insert(channel, Instant::now());
+            if sink.send(Message::Text(subscribe.to_string())).await.is_err() {
+                metrics.record_failure(WEBSOCKET, "session", "send failed".to_string());
+                return true;
+            }
+        }
+
+        let lifetime = self.plan.churn.as_ref().map(|churn| churn.sample(&mut worker.rng));
+        let expires = async move {
+            match lifetime {
+                Some(lifetime) => tokio::time::sleep(lifetime).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(expires);
+        let ping_every = Duration::from_secs(self.plan.ping_interval_secs.max(1));
+        let mut ping_timer = tokio::time::interval_at(tokio::time::Instant::now() + ping_every, ping_every);
+        let mut ping_sent: Option<Instant> = None;
+
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        // Protobuf stat updates; probes only travel as JSON events
+                        Some(Ok(Message::Binary(_) | Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => continue,
+                        Some(Ok(Message::Close(frame))) => {
+                            let kind = frame.map(|frame| format!("closed {}", u16::from(frame.code))).unwrap_or_else(|| "closed".to_string());
+                            metrics.record_failure(WEBSOCKET, "session", kind);
+                            return true;
+                        }
+                        Some(Err(e)) => {
+                            debug!("{}: {}", url, e);
+                            metrics.record_failure(WEBSOCKET, "session", "read failed".to_string());
+                            return true;
+                        }
+                        None => {
+                            metrics.record_failure(WEBSOCKET, "session", "closed".to_string());
+                            return true;
+                        }
+                    };
+                    let Ok(message) = serde_json::from_str::<serde_json::Value>(&text) else {
+                        continue;
+                    };
+                    match message.get("type").and_then(|kind| kind.as_str()) {
+                        Some("subscribed") => {
+                            let channel = message.get("channel").and_then(|channel| channel.as_str()).unwrap_or_default();
+                            if let Some(sent) = pending.remove(channel) {
+                                metrics.record_request(WEBSOCKET, "subscribe", sent.elapsed(), None);
+                            }
+                        }
+                        Some("pong") => {
+                            if let Some(sent) = ping_sent.take() {
+                                metrics.record_request(WEBSOCKET, "ping", sent.elapsed(), None);
+                            }
+                        }
+                        Some("event") => {
+                            let payload = message.get("payload").unwrap_or(&serde_json::Value::Null);
+                            if let Some(latency) = probe::latency_json(payload, &ctx.run_id) {
+                                metrics.record_request(WEBSOCKET_DELIVERY, "event", latency, None);
+                            }
+                        }
+                        Some("error") => {
+                            let code = message.get("code").and_then(|code| code.as_str()).unwrap_or("UNKNOWN");
+                            // Errors answer the oldest outstanding subscription, if any
+                            let oldest = pending.iter().min_by_key(|(_, sent)| **sent).map(|(channel, _)| channel.clone());
+                            let step = match oldest {
+                                Some(channel) => {
+                                    pending.remove(&channel);
+                                    "subscribe"
+                                }
+                                None => "session",
+                            };
+                            metrics.record_failure(WEBSOCKET, step, code.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+                _ = ping_timer.tick(), if self.plan.ping_interval_secs > 0 => {
+                    if ping_sent.is_some() {
+                        metrics.record_failure(WEBSOCKET, "ping", "no pong".to_string());
+                    }
+                    ping_sent = Some(Instant::now());
+                    if sink.send(Message::Text(r#"{"type":"ping"}"#.to_string())).await.is_err() {
+                        metrics.record_failure(WEBSOCKET, "session", "send failed".to_string());
+                        return true;
+                    }
+                }
+                _ = &mut expires => {
+                    let _ = sink.close().await;
+                    return true;
+                }
+                _ = worker.ramp.released() => {
+                    let _ = sink.close().await;
+                    return true;
+                }
+            }
+        }
+    }
+}
+
+/// `ws://` or `wss://` form of the target
+fn websocket_url(target: &str) -> String {
+    if let Some(rest) = target.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = target.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        target.to_string()
+    }
+}
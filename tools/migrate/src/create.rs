@@ -0,0 +1,97 @@
+//! `migrate create <name>`: scaffold a migration module and register it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+const MODULES_MARKER: &str = "// migrate:modules";
+const REGISTRY_MARKER: &str = "// migrate:registry";
+
+/// Write `m<version>_<name>.rs` into `dir` and register it in `dir/mod.rs`
+pub fn create(dir: &Path, name: &str) -> Result<PathBuf> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        bail!("Migration names are snake_case, got {:?}", name);
+    }
+    let registry_path = dir.join("mod.rs");
+    let registry = std::fs::read_to_string(&registry_path).with_context(|| format!("reading {}", registry_path.display()))?;
+    if !registry.contains(MODULES_MARKER) || !registry.contains(REGISTRY_MARKER) {
+        bail!("{} is missing the `{}` or `{}` marker", registry_path.display(), MODULES_MARKER, REGISTRY_MARKER);
+    }
+
+    let version = next_version(dir)?;
+    let module = format!("m{:04}_{}", version, name);
+    let type_name: String = name
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect();
+
+    let path = dir.join(format!("{}.rs", module));
+    std::fs::write(&path, template(version, name, &module, &type_name)).with_context(|| format!("writing {}", path.display()))?;
+
+    let registry = registry
+        .replacen(MODULES_MARKER, &format!("mod {};\n{}", module, MODULES_MARKER), 1)
+        .replacen(REGISTRY_MARKER, &format!("Box::new({}::{}),\n        {}", module, type_name, REGISTRY_MARKER), 1);
+    std::fs::write(&registry_path, registry).with_context(|| format!("writing {}", registry_path.display()))?;
+    Ok(path)
+}
+
+/// One past the highest `m<version>_` module in `dir`
+fn next_version(dir: &Path) -> Result<u64> {
+    let mut highest = 0;
+    for entry in std::fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let file_name = entry?.file_name();
+        let Some(version) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix('m'))
+            .and_then(|rest| rest.split('_').next())
+            .and_then(|digits| digits.parse::<u64>().ok())
+        else {
+            continue;
+        };
+        highest = highest.max(version);
+    }
+    Ok(highest + 1)
+}
+
+fn template(version: u64, name: &str, module: &str, type_name: &str) -> String {
+    format!(
+        r#"//! TODO: describe the change.
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+use crate::migration::{{Migration, MigrationContext}};
+
+pub struct {type_name};
+
+#[async_trait]
+impl Migration for {type_name} {{
+    fn version(&self) -> u64 {{
+        {version}
+    }}
+
+    fn name(&self) -> &'static str {{
+        "{name}"
+    }}
+
+    fn source(&self) -> &'static str {{
+        include_str!("{module}.rs")
+    }}
+
+    async fn up(&self, ctx: &mut MigrationContext<'_>) -> Result<()> {{
+        let _ = ctx;
+        Ok(())
+    }}
+
+    async fn down(&self, ctx: &mut MigrationContext<'_>) -> Result<()> {{
+        let _ = ctx;
+        Ok(())
+    }}
+}}
+"#
+    )
+}
@@ -0,0 +1,95 @@
+//! Lock document keeping two migration runs from overlapping.
+//!
+//! The lock is a single document in `schema_lock`. It expires so a crashed run does
+//! not block migrations forever; `migrate unlock` removes it by hand.
+
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::{Collection, Database};
+use serde::{Deserialize, Serialize};
+
+pub const LOCK_COLLECTION: &str = "schema_lock";
+const LOCK_ID: &str = "migrate";
+
+/// How long a run may hold the lock before others may take it over
+const LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockInfo {
+    pub owner: String,
+    pub acquired_at: DateTime,
+    pub expires_at: DateTime,
+}
+
+/// Held migration lock, release it with [`MigrationLock::release`]
+pub struct MigrationLock {
+    collection: Collection<Document>,
+    owner: String,
+}
+
+impl MigrationLock {
+    /// Take the lock, failing if another run holds an unexpired one
+    pub async fn acquire(db: &Database) -> Result<Self> {
+        let collection = db.collection::<Document>(LOCK_COLLECTION);
+        let owner = format!(
+            "{}:{}",
+            std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown-host".to_string()),
+            std::process::id()
+        );
+        let now = DateTime::now();
+        let expires_at = DateTime::from_millis(now.timestamp_millis() + LOCK_TTL.as_millis() as i64);
+
+        // Upsert over a missing or expired lock; a live lock fails the filter and the
+        // upsert then collides with its _id
+        let filter = doc! { "_id": LOCK_ID, "expires_at": { "$lt": now } };
+        let update = doc! { "$set": {
+            "owner": &owner,
+            "acquired_at": now,
+            "expires_at": expires_at,
+        } };
+        let options = FindOneAndUpdateOptions::builder().upsert(true).return_document(ReturnDocument::After).build();
+        match collection.find_one_and_update(filter, update, options).await {
+            Ok(_) => Ok(Self { collection, owner }),
+            Err(e) if is_duplicate_key(&e) => match status(db).await? {
+                Some(lock) => bail!(
+                    "Another migration run holds the lock ({} since {}, expires {}); use `migrate unlock` if it crashed",
+                    lock.owner,
+                    lock.acquired_at,
+                    lock.expires_at
+                ),
+                None => bail!("Lost a race for the migration lock, try again"),
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Release the lock if this run still holds it
+    pub async fn release(self) -> Result<()> {
+        self.collection.delete_one(doc! { "_id": LOCK_ID, "owner": &self.owner }, None).await?;
+        Ok(())
+    }
+}
+
+/// Current lock holder, if any
+pub async fn status(db: &Database) -> Result<Option<LockInfo>> {
+    let lock = db.collection::<LockInfo>(LOCK_COLLECTION).find_one(doc! { "_id": LOCK_ID }, None).await?;
+    Ok(lock)
+}
+
+/// Remove the lock whoever holds it
+pub async fn force_unlock(db: &Database) -> Result<bool> {
+    let result = db.collection::<Document>(LOCK_COLLECTION).delete_one(doc! { "_id": LOCK_ID }, None).await?;
+    Ok(result.deleted_count > 0)
+}
+
+fn is_duplicate_key(error: &mongodb::error::Error) -> bool {
+    const DUPLICATE_KEY: i32 = 11000;
+    match error.kind.as_ref() {
+        mongodb::error::ErrorKind::Command(command) => command.code == DUPLICATE_KEY,
+        mongodb::error::ErrorKind::Write(mongodb::error::WriteFailure::WriteError(write)) => write.code == DUPLICATE_KEY,
+        _ => false,
+    }
+}
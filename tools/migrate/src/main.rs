@@ -2,20 +2,46 @@
 //!
 //! Tool for managing database migrations for the Chaos World MMORPG backend.
 
-use anyhow::Result;
+mod create;
+mod lock;
+mod migration;
+mod migrations;
+mod runner;
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use tracing::info;
 
+use crate::runner::{Migrator, RunOptions};
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
     #[command(subcommand)]
     command: Commands,
-    
-    /// Database URL
-    #[arg(short, long, env = "DATABASE_URL")]
-    database_url: String,
-    
+
+    /// MongoDB connection string
+    #[arg(short, long, env = "DATABASE_URL", global = true, hide_env_values = true)]
+    database_url: Option<String>,
+
+    /// Database to migrate
+    #[arg(long, env = "MONGODB_DATABASE", default_value = "chaos_world", global = true)]
+    database: String,
+
+    /// Apply migrations without transactions even when the server supports them
+    #[arg(long, global = true)]
+    no_transactions: bool,
+
+    /// Report affected collections and document counts without writing
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Skip copying touched collections before each migration
+    #[arg(long, global = true)]
+    no_backup: bool,
+
     /// Log level
     #[arg(short, long, default_value = "info")]
     log_level: String,
@@ -24,47 +50,87 @@ struct Args {
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Run pending migrations
-    Up,
+    Up {
+        /// Stop after this version
+        #[arg(long)]
+        to: Option<u64>,
+    },
     /// Rollback the last migration
-    Down,
+    Down {
+        /// Roll back every migration newer than this version instead
+        #[arg(long)]
+        to: Option<u64>,
+
+        /// Confirm rolling back more than one migration
+        #[arg(long, short)]
+        yes: bool,
+    },
     /// Show migration status
     Status,
     /// Create a new migration
     Create {
         /// Migration name
         name: String,
+
+        /// Migration modules directory
+        #[arg(long, env = "MIGRATIONS_DIR", default_value = "tools/migrate/src/migrations")]
+        dir: PathBuf,
     },
+    /// Remove a lock left behind by a crashed run
+    Unlock,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    
+
     // Initialize tracing
     tracing_subscriber::fmt()
-        .with_env_filter(args.log_level)
+        .with_env_filter(args.log_level.clone())
         .init();
-    
+
     info!("Chaos World Database Migration Tool");
-    
+
+    if let Commands::Create { name, dir } = &args.command {
+        info!("Creating migration: {}", name);
+        let path = create::create(dir, name)?;
+        println!("Created {}", path.display());
+        return Ok(());
+    }
+
+    let database_url = args.database_url.as_deref().context("Set --database-url or DATABASE_URL")?;
+    let client = mongodb::Client::with_uri_str(database_url).await.context("connecting to MongoDB")?;
+    let options = RunOptions {
+        transactions: !args.no_transactions,
+        dry_run: args.dry_run,
+        backup: !args.no_backup,
+    };
+    let migrator = Migrator::new(client, &args.database, migrations::all(), options).await?;
+
     match args.command {
-        Commands::Up => {
+        Commands::Up { to } => {
             info!("Running pending migrations...");
-            // TODO: Implement migration up
+            let applied = migrator.up(to).await?;
+            info!("Applied {} migration(s)", applied);
         }
-        Commands::Down => {
-            info!("Rolling back last migration...");
-            // TODO: Implement migration down
+        Commands::Down { to, yes } => {
+            info!("Rolling back migrations...");
+            let rolled_back = migrator.down(to, yes).await?;
+            info!("Rolled back {} migration(s)", rolled_back);
         }
         Commands::Status => {
             info!("Checking migration status...");
-            // TODO: Implement migration status
+            migrator.status().await?;
         }
-        Commands::Create { name } => {
-            info!("Creating migration: {}", name);
-            // TODO: Implement migration creation
+        Commands::Unlock => {
+            if lock::force_unlock(migrator.database()).await? {
+                info!("Removed the migration lock");
+            } else {
+                info!("No migration lock was held");
+            }
         }
+        Commands::Create { .. } => unreachable!("handled before connecting"),
     }
-    
+
     Ok(())
 }
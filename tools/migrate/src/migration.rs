@@ -0,0 +1,202 @@
+//! The migration trait and the context migrations run in.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use mongodb::bson::Document;
+use mongodb::{ClientSession, Database, IndexModel};
+
+/// A versioned schema change.
+///
+/// Migrations live in `src/migrations/`, one module each, and are registered in
+/// [`crate::migrations::all`]. Versions must be unique and only ever grow; a migration
+/// that has been applied anywhere must not be edited, add a new one instead.
+#[async_trait]
+pub trait Migration: Send + Sync {
+    /// Version recorded in `schema_version`
+    fn version(&self) -> u64;
+
+    /// Short description shown by `status`
+    fn name(&self) -> &'static str;
+
+    /// Source of the migration module, `include_str!` of its own file.
+    ///
+    /// Its checksum is recorded when the migration is applied; `up` and `down` refuse to
+    /// run while an applied migration's source no longer matches.
+    fn source(&self) -> &'static str;
+
+    /// Collections whose documents the migration changes, backed up before it runs
+    fn collections(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Whether the migration runs inside a transaction when the server supports them.
+    ///
+    /// Index and collection changes cannot be part of a transaction on most deployments,
+    /// migrations doing those return `false`.
+    fn transactional(&self) -> bool {
+        true
+    }
+
+    async fn up(&self, ctx: &mut MigrationContext<'_>) -> Result<()>;
+
+    async fn down(&self, ctx: &mut MigrationContext<'_>) -> Result<()>;
+}
+
+/// Database access for a running migration, inside its transaction when there is one.
+///
+/// Migrations write through these helpers so a dry run can report what they would
+/// change instead of changing it.
+pub struct MigrationContext<'a> {
+    pub db: &'a Database,
+    session: Option<&'a mut ClientSession>,
+    dry_run: Option<DryRunReport>,
+}
+
+// Not every helper is used by the migrations registered so far
+#[allow(dead_code)]
+impl<'a> MigrationContext<'a> {
+    pub fn new(db: &'a Database, session: Option<&'a mut ClientSession>) -> Self {
+        Self { db, session, dry_run: None }
+    }
+
+    /// Context that records writes without making them
+    pub fn dry_run(db: &'a Database) -> Self {
+        Self { db, session: None, dry_run: Some(DryRunReport::default()) }
+    }
+
+    /// Whether writes are part of a transaction
+    pub fn in_transaction(&self) -> bool {
+        self.session.is_some()
+    }
+
+    /// Whether writes are only recorded
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run.is_some()
+    }
+
+    /// Writes recorded by a dry run
+    pub fn into_report(self) -> Option<DryRunReport> {
+        self.dry_run
+    }
+
+    pub async fn insert_many(&mut self, collection: &str, documents: Vec<Document>) -> Result<()> {
+        if let Some(report) = &mut self.dry_run {
+            report.record(collection, "insert", Some(documents.len() as u64));
+            return Ok(());
+        }
+        let collection = self.db.collection::<Document>(collection);
+        match self.session.as_deref_mut() {
+            Some(session) => collection.insert_many_with_session(documents, None, session).await?,
+            None => collection.insert_many(documents, None).await?,
+        };
+        Ok(())
+    }
+
+    /// Update matching documents, returning how many changed
+    pub async fn update_many(&mut self, collection: &str, filter: Document, update: Document) -> Result<u64> {
+        if self.dry_run.is_some() {
+            return self.record_matching(collection, "update", filter).await;
+        }
+        let collection = self.db.collection::<Document>(collection);
+        let result = match self.session.as_deref_mut() {
+            Some(session) => collection.update_many_with_session(filter, update, None, session).await?,
+            None => collection.update_many(filter, update, None).await?,
+        };
+        Ok(result.modified_count)
+    }
+
+    /// Delete matching documents, returning how many were removed
+    pub async fn delete_many(&mut self, collection: &str, filter: Document) -> Result<u64> {
+        if self.dry_run.is_some() {
+            return self.record_matching(collection, "delete", filter).await;
+        }
+        let collection = self.db.collection::<Document>(collection);
+        let result = match self.session.as_deref_mut() {
+            Some(session) => collection.delete_many_with_session(filter, None, session).await?,
+            None => collection.delete_many(filter, None).await?,
+        };
+        Ok(result.deleted_count)
+    }
+
+    /// Create indexes, never transactional
+    pub async fn create_indexes(&mut self, collection: &str, indexes: Vec<IndexModel>) -> Result<()> {
+        if let Some(report) = &mut self.dry_run {
+            for index in &indexes {
+                let name = index.options.as_ref().and_then(|options| options.name.clone()).unwrap_or_default();
+                report.record(collection, &format!("create index {}", name), None);
+            }
+            return Ok(());
+        }
+        self.db.collection::<Document>(collection).create_indexes(indexes, None).await?;
+        Ok(())
+    }
+
+    /// Drop an index by name, ignoring indexes that are already gone
+    pub async fn drop_index(&mut self, collection: &str, name: &str) -> Result<()> {
+        if let Some(report) = &mut self.dry_run {
+            report.record(collection, &format!("drop index {}", name), None);
+            return Ok(());
+        }
+        match self.db.collection::<Document>(collection).drop_index(name, None).await {
+            Ok(()) => Ok(()),
+            Err(e) if is_missing(&e) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Count what a write would touch, for a dry run
+    async fn record_matching(&mut self, collection: &str, operation: &str, filter: Document) -> Result<u64> {
+        let count = self.db.collection::<Document>(collection).count_documents(filter, None).await?;
+        if let Some(report) = &mut self.dry_run {
+            report.record(collection, operation, Some(count));
+        }
+        Ok(count)
+    }
+}
+
+/// Writes a dry run would have made
+#[derive(Debug, Default)]
+pub struct DryRunReport {
+    pub writes: Vec<PlannedWrite>,
+}
+
+#[derive(Debug)]
+pub struct PlannedWrite {
+    pub collection: String,
+    pub operation: String,
+    /// Documents affected, for document writes
+    pub documents: Option<u64>,
+}
+
+impl DryRunReport {
+    fn record(&mut self, collection: &str, operation: &str, documents: Option<u64>) {
+        self.writes.push(PlannedWrite {
+            collection: collection.to_string(),
+            operation: operation.to_string(),
+            documents,
+        });
+    }
+
+    pub fn print(&self) {
+        if self.writes.is_empty() {
+            println!("  no writes");
+        }
+        for write in &self.writes {
+            match write.documents {
+                Some(documents) => println!("  {:<24} {:<28} {} document(s)", write.collection, write.operation, documents),
+                None => println!("  {:<24} {}", write.collection, write.operation),
+            }
+        }
+    }
+}
+
+/// Server error codes for dropping an index that does not exist
+const INDEX_NOT_FOUND: i32 = 27;
+const NAMESPACE_NOT_FOUND: i32 = 26;
+
+fn is_missing(error: &mongodb::error::Error) -> bool {
+    matches!(
+        error.kind.as_ref(),
+        mongodb::error::ErrorKind::Command(command) if [INDEX_NOT_FOUND, NAMESPACE_NOT_FOUND].contains(&command.code)
+    )
+}
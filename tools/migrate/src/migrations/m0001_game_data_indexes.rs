@@ -0,0 +1,62 @@
+//! Indexes for the game data collections seeded by `data-gen`.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use mongodb::bson::doc;
+use mongodb::options::IndexOptions;
+use mongodb::IndexModel;
+
+use crate::migration::{Migration, MigrationContext};
+
+pub struct GameDataIndexes;
+
+/// (collection, index name, keys)
+const INDEXES: [(&str, &str, &[&str]); 6] = [
+    ("actors", "race_level", &["race", "level"]),
+    ("items", "rarity_base", &["rarity", "base"]),
+    ("zones", "min_level", &["min_level"]),
+    ("dungeons", "zone_id", &["zone_id"]),
+    ("quests", "zone_id_min_level", &["zone_id", "min_level"]),
+    ("quests", "chain_id", &["chain_id"]),
+];
+
+#[async_trait]
+impl Migration for GameDataIndexes {
+    fn version(&self) -> u64 {
+        1
+    }
+
+    fn name(&self) -> &'static str {
+        "game_data_indexes"
+    }
+
+    fn source(&self) -> &'static str {
+        include_str!("m0001_game_data_indexes.rs")
+    }
+
+    fn transactional(&self) -> bool {
+        false
+    }
+
+    async fn up(&self, ctx: &mut MigrationContext<'_>) -> Result<()> {
+        for (collection, name, keys) in INDEXES {
+            let mut spec = doc! {};
+            for key in keys {
+                spec.insert(*key, 1);
+            }
+            let index = IndexModel::builder()
+                .keys(spec)
+                .options(IndexOptions::builder().name(name.to_string()).build())
+                .build();
+            ctx.create_indexes(collection, vec![index]).await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, ctx: &mut MigrationContext<'_>) -> Result<()> {
+        for (collection, name, _) in INDEXES {
+            ctx.drop_index(collection, name).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,16 @@
+//! Registered migrations, oldest first.
+//!
+//! `migrate create <name>` adds a module here and registers it; keep the markers below.
+
+use crate::migration::Migration;
+
+mod m0001_game_data_indexes;
+// migrate:modules
+
+/// Every migration this build knows about, in version order
+pub fn all() -> Vec<Box<dyn Migration>> {
+    vec![
+        Box::new(m0001_game_data_indexes::GameDataIndexes),
+        // migrate:registry
+    ]
+}
@@ -0,0 +1,370 @@
+//! Applies and rolls back migrations, recording them in `schema_version`.
+
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+use anyhow::{bail, Context, Result};
+use futures::TryStreamExt;
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::{Client, Database};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::lock::{self, MigrationLock};
+use crate::migration::{Migration, MigrationContext};
+
+pub const VERSION_COLLECTION: &str = "schema_version";
+
+/// How migrations are run
+#[derive(Debug, Clone, Copy)]
+pub struct RunOptions {
+    /// Use transactions when the server supports them
+    pub transactions: bool,
+    /// Report what would change without writing
+    pub dry_run: bool,
+    /// Copy touched collections before changing them
+    pub backup: bool,
+}
+
+/// A migration recorded as applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    #[serde(rename = "_id")]
+    pub version: i64,
+    pub name: String,
+    pub applied_at: DateTime,
+    pub duration_ms: i64,
+    pub transactional: bool,
+    /// Checksum of the migration source when it was applied
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Collection copies taken before it ran
+    #[serde(default)]
+    pub backups: Vec<String>,
+}
+
+pub struct Migrator {
+    client: Client,
+    db: Database,
+    migrations: Vec<Box<dyn Migration>>,
+    transactions: bool,
+    options: RunOptions,
+}
+
+impl Migrator {
+    /// Check the registered migrations and whether the server supports transactions
+    pub async fn new(client: Client, database: &str, migrations: Vec<Box<dyn Migration>>, options: RunOptions) -> Result<Self> {
+        for pair in migrations.windows(2) {
+            if pair[1].version() <= pair[0].version() {
+                bail!(
+                    "Migrations must be registered in increasing version order: {} ({}) comes after {} ({})",
+                    pair[1].version(),
+                    pair[1].name(),
+                    pair[0].version(),
+                    pair[0].name()
+                );
+            }
+        }
+        let db = client.database(database);
+        let transactions = options.transactions && supports_transactions(&client).await?;
+        if !transactions && !options.dry_run {
+            warn!("Transactions are unavailable, migrations are applied without them");
+        }
+        Ok(Self { client, db, migrations, transactions, options })
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    pub async fn applied(&self) -> Result<BTreeMap<u64, AppliedMigration>> {
+        let applied: Vec<AppliedMigration> = self
+            .db
+            .collection::<AppliedMigration>(VERSION_COLLECTION)
+            .find(None, None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(applied.into_iter().map(|migration| (migration.version as u64, migration)).collect())
+    }
+
+    /// Print applied and pending migrations
+    pub async fn status(&self) -> Result<()> {
+        let applied = self.applied().await?;
+        println!("{:<8} {:<40} {}", "VERSION", "NAME", "APPLIED");
+        for migration in &self.migrations {
+            let state = match applied.get(&migration.version()) {
+                Some(record) if changed(migration.as_ref(), record) => {
+                    format!("{} ({} ms), CHANGED since it was applied", record.applied_at, record.duration_ms)
+                }
+                Some(record) => format!("{} ({} ms)", record.applied_at, record.duration_ms),
+                None => "pending".to_string(),
+            };
+            println!("{:<8} {:<40} {}", migration.version(), migration.name(), state);
+        }
+        for (version, record) in &applied {
+            if !self.migrations.iter().any(|migration| migration.version() == *version) {
+                println!("{:<8} {:<40} {} (unknown to this build)", version, record.name, record.applied_at);
+            }
+        }
+        match lock::status(&self.db).await? {
+            Some(lock) => println!("\nLocked by {} since {}, expires {}", lock.owner, lock.acquired_at, lock.expires_at),
+            None => println!("\nNot locked"),
+        }
+        Ok(())
+    }
+
+    /// Apply pending migrations up to and including `target`, or all of them
+    pub async fn up(&self, target: Option<u64>) -> Result<usize> {
+        if self.options.dry_run {
+            return self.up_locked(target).await;
+        }
+        let lock = MigrationLock::acquire(&self.db).await?;
+        let result = self.up_locked(target).await;
+        lock.release().await?;
+        result
+    }
+
+    async fn up_locked(&self, target: Option<u64>) -> Result<usize> {
+        let applied = self.applied().await?;
+        self.verify(&applied, applied.keys().copied())?;
+        let pending: Vec<&dyn Migration> = self
+            .migrations
+            .iter()
+            .map(|migration| migration.as_ref())
+            .filter(|migration| !applied.contains_key(&migration.version()))
+            .filter(|migration| target.map_or(true, |target| migration.version() <= target))
+            .collect();
+        if pending.is_empty() {
+            info!("Database is up to date");
+            return Ok(0);
+        }
+        for migration in &pending {
+            self.apply(*migration, Direction::Up).await?;
+        }
+        Ok(pending.len())
+    }
+
+    /// Roll back applied migrations newer than `target`, or the latest one
+    ///
+    /// Rolling back more than one migration needs `confirmed`; every step is checked
+    /// before the first one runs, and the run stops at the first failure.
+    pub async fn down(&self, target: Option<u64>, confirmed: bool) -> Result<usize> {
+        if self.options.dry_run {
+            return self.down_locked(target, true).await;
+        }
+        let lock = MigrationLock::acquire(&self.db).await?;
+        let result = self.down_locked(target, confirmed).await;
+        lock.release().await?;
+        result
+    }
+
+    async fn down_locked(&self, target: Option<u64>, confirmed: bool) -> Result<usize> {
+        let applied = self.applied().await?;
+        if let Some(target) = target {
+            if target > 0 && !applied.contains_key(&target) {
+                bail!("Version {} is not applied, roll back to an applied version or 0", target);
+            }
+        }
+        let mut versions: Vec<u64> = applied.keys().copied().collect();
+        versions.reverse();
+        let versions: Vec<u64> = match target {
+            Some(target) => versions.into_iter().filter(|version| *version > target).collect(),
+            None => versions.into_iter().take(1).collect(),
+        };
+        if versions.is_empty() {
+            info!("Nothing to roll back");
+            return Ok(0);
+        }
+
+        let steps = versions
+            .iter()
+            .map(|version| {
+                self.migrations
+                    .iter()
+                    .find(|migration| migration.version() == *version)
+                    .map(|migration| migration.as_ref())
+                    .with_context(|| format!("Migration {} is applied but unknown to this build, cannot roll it back", version))
+            })
+            .collect::<Result<Vec<&dyn Migration>>>()?;
+        self.verify(&applied, versions.iter().copied())?;
+
+        println!("Rolling back:");
+        for migration in &steps {
+            println!("  {} {}", migration.version(), migration.name());
+        }
+        if steps.len() > 1 && !confirmed {
+            bail!("Rolling back {} migrations, pass --yes to confirm", steps.len());
+        }
+        for migration in &steps {
+            self.apply(*migration, Direction::Down).await?;
+        }
+        Ok(steps.len())
+    }
+
+    /// Fail when an applied migration's source changed since it was applied
+    fn verify(&self, applied: &BTreeMap<u64, AppliedMigration>, versions: impl Iterator<Item = u64>) -> Result<()> {
+        let changed: Vec<String> = versions
+            .filter_map(|version| {
+                let record = applied.get(&version)?;
+                let migration = self.migrations.iter().find(|migration| migration.version() == version)?;
+                changed(migration.as_ref(), record).then(|| format!("{} {}", version, migration.name()))
+            })
+            .collect();
+        if !changed.is_empty() {
+            bail!(
+                "Applied migrations were edited after they ran: {}. Restore them and add a new migration instead",
+                changed.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Run one migration and record the result, in one transaction when possible
+    async fn apply(&self, migration: &dyn Migration, direction: Direction) -> Result<()> {
+        let label = format!("{} {}", migration.version(), migration.name());
+        if self.options.dry_run {
+            println!("{} {} (dry run)", direction.verb(), label);
+            let mut ctx = MigrationContext::dry_run(&self.db);
+            direction.run(migration, &mut ctx).await.with_context(|| format!("dry run of {}", label))?;
+            if let Some(report) = ctx.into_report() {
+                report.print();
+            }
+            return Ok(());
+        }
+
+        info!("{} {}", direction.verb(), label);
+        let backups = if self.options.backup { self.backup(migration).await? } else { Vec::new() };
+        let started = Instant::now();
+        let versions = self.db.collection::<Document>(VERSION_COLLECTION);
+
+        if self.transactions && migration.transactional() {
+            let mut session = self.client.start_session(None).await?;
+            session.start_transaction(None).await?;
+            let result = async {
+                let mut ctx = MigrationContext::new(&self.db, Some(&mut session));
+                direction.run(migration, &mut ctx).await?;
+                drop(ctx);
+                match direction {
+                    Direction::Up => {
+                        let record = record(migration, started, true, &backups)?;
+                        versions.insert_one_with_session(record, None, &mut session).await?;
+                    }
+                    Direction::Down => {
+                        versions
+                            .delete_one_with_session(doc! { "_id": migration.version() as i64 }, None, &mut session)
+                            .await?;
+                    }
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            match result {
+                Ok(()) => session.commit_transaction().await.with_context(|| format!("committing {}", label))?,
+                Err(e) => {
+                    session.abort_transaction().await.ok();
+                    return Err(e.context(format!("{} failed, its transaction was rolled back", label)));
+                }
+            }
+        } else {
+            let mut ctx = MigrationContext::new(&self.db, None);
+            direction
+                .run(migration, &mut ctx)
+                .await
+                .with_context(|| format!("{} failed and was not transactional, check the database by hand", label))?;
+            match direction {
+                Direction::Up => {
+                    versions.insert_one(record(migration, started, false, &backups)?, None).await?;
+                }
+                Direction::Down => {
+                    versions.delete_one(doc! { "_id": migration.version() as i64 }, None).await?;
+                }
+            }
+        }
+        info!("{} {} in {} ms", direction.past(), label, started.elapsed().as_millis());
+        Ok(())
+    }
+
+    /// Copy every non-empty collection the migration touches to `<name>__backup_v<version>_<time>`
+    async fn backup(&self, migration: &dyn Migration) -> Result<Vec<String>> {
+        let suffix = format!("v{}_{}", migration.version(), chrono::Utc::now().format("%Y%m%d%H%M%S"));
+        let mut backups = Vec::new();
+        for name in migration.collections() {
+            let collection = self.db.collection::<Document>(name);
+            if collection.estimated_document_count(None).await? == 0 {
+                continue;
+            }
+            let backup = format!("{}__backup_{}", name, suffix);
+            let pipeline = vec![doc! { "$match": {} }, doc! { "$out": &backup }];
+            collection
+                .aggregate(pipeline, None)
+                .await
+                .with_context(|| format!("backing up {} to {}", name, backup))?
+                .try_collect::<Vec<Document>>()
+                .await?;
+            info!("Backed up {} to {}", name, backup);
+            backups.push(backup);
+        }
+        Ok(backups)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+}
+
+impl Direction {
+    async fn run(self, migration: &dyn Migration, ctx: &mut MigrationContext<'_>) -> Result<()> {
+        match self {
+            Direction::Up => migration.up(ctx).await,
+            Direction::Down => migration.down(ctx).await,
+        }
+    }
+
+    fn verb(self) -> &'static str {
+        match self {
+            Direction::Up => "Applying",
+            Direction::Down => "Rolling back",
+        }
+    }
+
+    fn past(self) -> &'static str {
+        match self {
+            Direction::Up => "Applied",
+            Direction::Down => "Rolled back",
+        }
+    }
+}
+
+fn record(migration: &dyn Migration, started: Instant, transactional: bool, backups: &[String]) -> Result<Document> {
+    let record = AppliedMigration {
+        version: migration.version() as i64,
+        name: migration.name().to_string(),
+        applied_at: DateTime::now(),
+        duration_ms: started.elapsed().as_millis() as i64,
+        transactional,
+        checksum: Some(checksum(migration.source())),
+        backups: backups.to_vec(),
+    };
+    Ok(mongodb::bson::to_document(&record)?)
+}
+
+/// SHA-256 of the source with line endings normalized
+pub fn checksum(source: &str) -> String {
+    hex::encode(Sha256::digest(source.replace("\r\n", "\n").as_bytes()))
+}
+
+/// Whether the migration differs from what was applied; records from before checksums
+/// were kept are trusted
+fn changed(migration: &dyn Migration, record: &AppliedMigration) -> bool {
+    record.checksum.as_ref().is_some_and(|recorded| *recorded != checksum(migration.source()))
+}
+
+/// Transactions need a replica set or a sharded cluster
+async fn supports_transactions(client: &Client) -> Result<bool> {
+    let hello = client.database("admin").run_command(doc! { "hello": 1 }, None).await?;
+    Ok(hello.contains_key("setName") || hello.get_str("msg").map_or(false, |msg| msg == "isdbgrid"))
+}